@@ -0,0 +1,307 @@
+//! Agent loop hot-path benchmarks.
+//!
+//! Run with `cargo bench --bench agent_loop [-- <name filter>]`. Uses a small
+//! std-only runner (`harness = false`) so the suite builds without extra
+//! dependencies; each case reports the median and minimum of repeated samples.
+//! The recorded baseline lives in `docs/operations/AGENT_LOOP_BENCHMARKS.md`.
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use localagent::compaction::{
+    maybe_compact_in_place_with_origins, CompactionMode, CompactionReport, CompactionSettings,
+    HeuristicTokenCounter, ToolResultPersist,
+};
+use localagent::repo_map::{resolve_repo_map, RepoMapLimits};
+use localagent::store::sha256_hex;
+use localagent::tools::{envelope_to_message, to_tool_result_envelope, ToolResultMeta};
+use localagent::trust::approvals::canonical_json;
+use localagent::types::{GenerateRequest, Message, Role, SideEffects, ToolCall};
+use serde_json::{json, Value};
+
+const TRANSCRIPT_MESSAGES: usize = 500;
+const REPO_MAP_FILES: usize = 2_000;
+const SAMPLE_BUDGET: Duration = Duration::from_millis(800);
+const MIN_SAMPLES: usize = 10;
+const MAX_SAMPLES: usize = 500;
+
+struct Runner {
+    filter: Option<String>,
+}
+
+impl Runner {
+    fn from_args() -> Self {
+        let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+        Self { filter }
+    }
+
+    fn bench<T>(&self, name: &str, mut f: impl FnMut() -> T) {
+        if self
+            .filter
+            .as_deref()
+            .is_some_and(|filter| !name.contains(filter))
+        {
+            return;
+        }
+        for _ in 0..3 {
+            black_box(f());
+        }
+        let mut samples = Vec::new();
+        let started = Instant::now();
+        while samples.len() < MIN_SAMPLES
+            || (samples.len() < MAX_SAMPLES && started.elapsed() < SAMPLE_BUDGET)
+        {
+            let t = Instant::now();
+            black_box(f());
+            samples.push(t.elapsed());
+        }
+        samples.sort();
+        println!(
+            "{name:<44} median {:>12}  min {:>12}  ({} samples)",
+            format_duration(samples[samples.len() / 2]),
+            format_duration(samples[0]),
+            samples.len()
+        );
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let micros = d.as_secs_f64() * 1e6;
+    if micros >= 1_000.0 {
+        format!("{:.3} ms", micros / 1_000.0)
+    } else {
+        format!("{micros:.1} us")
+    }
+}
+
+fn message(role: Role, content: String) -> Message {
+    Message {
+        role,
+        content: Some(content),
+        tool_call_id: None,
+        tool_name: None,
+        tool_calls: None,
+    }
+}
+
+/// A long coding session: a system prompt followed by user turns, assistant
+/// tool calls, and ~2 KiB tool results, in the shape the agent builds.
+fn synthetic_transcript(len: usize) -> Vec<Message> {
+    let mut messages = vec![message(Role::System, "You are a coding agent. ".repeat(80))];
+    let mut i = 0usize;
+    while messages.len() < len {
+        match i % 4 {
+            0 => messages.push(message(
+                Role::User,
+                format!("Step {i}: fix the failing test in src/module_{i}.rs"),
+            )),
+            1 => messages.push(Message {
+                role: Role::Assistant,
+                content: Some(String::new()),
+                tool_call_id: None,
+                tool_name: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: format!("call_{i}"),
+                    name: "read_file".to_string(),
+                    arguments: json!({"path": format!("src/module_{i}.rs")}),
+                }]),
+            }),
+            2 => messages.push(Message {
+                role: Role::Tool,
+                content: Some(
+                    json!({
+                        "schema_version": "openagent.tool_result.v1",
+                        "tool_name": "read_file",
+                        "tool_call_id": format!("call_{}", i - 1),
+                        "ok": true,
+                        "content": format!("fn f_{i}() {{ let x = {i}; }}\n").repeat(64),
+                    })
+                    .to_string(),
+                ),
+                tool_call_id: Some(format!("call_{}", i - 1)),
+                tool_name: Some("read_file".to_string()),
+                tool_calls: None,
+            }),
+            _ => messages.push(message(
+                Role::Assistant,
+                format!("The bug in module {i} is an off-by-one; patching it now."),
+            )),
+        }
+        i += 1;
+    }
+    messages
+}
+
+fn build_request(messages: &[Message]) -> GenerateRequest {
+    GenerateRequest {
+        model: "bench-model".to_string(),
+        messages: messages.to_vec(),
+        tools: None,
+        temperature: Some(0.2),
+        top_p: None,
+        max_tokens: None,
+        seed: None,
+    }
+}
+
+fn tool_meta() -> ToolResultMeta {
+    ToolResultMeta {
+        side_effects: SideEffects::FilesystemRead,
+        bytes: Some(256 * 1024),
+        exit_code: None,
+        stderr_truncated: None,
+        stdout_truncated: None,
+        source: "builtin".to_string(),
+        execution_target: "host".to_string(),
+        warnings: None,
+        warnings_max: None,
+        warnings_truncated: None,
+        docker: None,
+        write_protection: None,
+        cwd: None,
+        timed_out: None,
+        dry_run: None,
+    }
+}
+
+fn large_arguments() -> Value {
+    let items = (0..2_000)
+        .map(|i| {
+            json!({
+                "zeta": i,
+                "path": format!("src/dir_{}/file_{i}.rs", i % 40),
+                "alpha": {"nested": [i, i + 1, i + 2], "label": "x".repeat(32)},
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({"patch": "-a\n+b\n".repeat(4_000), "items": items, "path": "src/main.rs"})
+}
+
+fn write_synthetic_repo(root: &Path, files: usize) {
+    for i in 0..files {
+        let dir = root.join(format!("src/pkg_{}", i % 50));
+        std::fs::create_dir_all(&dir).expect("create bench dir");
+        std::fs::write(
+            dir.join(format!("file_{i}.rs")),
+            format!("pub struct S{i};\n\npub fn f{i}() -> usize {{\n    {i}\n}}\n"),
+        )
+        .expect("write bench file");
+    }
+}
+
+/// The agent loop's compaction call, without tool origins or an artifact store.
+fn compact(messages: &mut Vec<Message>, settings: &CompactionSettings) -> Option<CompactionReport> {
+    maybe_compact_in_place_with_origins(
+        messages,
+        settings,
+        &BTreeMap::new(),
+        &HeuristicTokenCounter::default(),
+        None,
+    )
+    .expect("compact")
+}
+
+fn main() {
+    let runner = Runner::from_args();
+    let transcript = synthetic_transcript(TRANSCRIPT_MESSAGES);
+    let transcript_chars = localagent::compaction::context_size_chars(&transcript);
+
+    runner.bench("request/build_500_messages", || build_request(&transcript));
+    runner.bench("request/build_and_serialize_500_messages", || {
+        serde_json::to_vec(&build_request(&transcript)).expect("serialize request")
+    });
+
+    // Per-step compaction check when the transcript fits the budget, which is
+    // the common case in long runs.
+    let under_budget = CompactionSettings {
+        max_context_chars: transcript_chars * 2,
+        max_context_tokens: None,
+        mode: CompactionMode::Summary,
+        keep_last: 20,
+        tool_result_persist: ToolResultPersist::Digest,
+    };
+    let mut step_messages = transcript.clone();
+    runner.bench("step/compaction_check_500", || {
+        compact(&mut step_messages, &under_budget)
+    });
+    // Whole per-step preparation: compaction check, then the request copy.
+    runner.bench("step/prepare_request_500", || {
+        compact(&mut step_messages, &under_budget);
+        build_request(&step_messages)
+    });
+
+    for (label, mode, persist) in [
+        ("off", CompactionMode::Off, ToolResultPersist::All),
+        (
+            "summary_all",
+            CompactionMode::Summary,
+            ToolResultPersist::All,
+        ),
+        (
+            "summary_digest",
+            CompactionMode::Summary,
+            ToolResultPersist::Digest,
+        ),
+        (
+            "summary_none",
+            CompactionMode::Summary,
+            ToolResultPersist::None,
+        ),
+    ] {
+        let settings = CompactionSettings {
+            max_context_chars: transcript_chars / 4,
+            max_context_tokens: None,
+            mode,
+            keep_last: 20,
+            tool_result_persist: persist,
+        };
+        runner.bench(&format!("compaction/{label}_500"), || {
+            let mut messages = transcript.clone();
+            compact(&mut messages, &settings);
+            messages
+        });
+    }
+
+    let tc = ToolCall {
+        id: "call_big".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path": "src/big.rs"}),
+    };
+    let big_content = "let value = compute(input);\n".repeat(256 * 1024 / 28);
+    runner.bench("envelope/serialize_256k", || {
+        envelope_to_message(to_tool_result_envelope(
+            &tc,
+            "builtin",
+            true,
+            big_content.clone(),
+            false,
+            tool_meta(),
+        ))
+    });
+    let serialized = envelope_to_message(to_tool_result_envelope(
+        &tc,
+        "builtin",
+        true,
+        big_content.clone(),
+        false,
+        tool_meta(),
+    ))
+    .content
+    .expect("envelope content");
+    runner.bench("envelope/deserialize_256k", || {
+        serde_json::from_str::<Value>(&serialized).expect("parse envelope")
+    });
+
+    let args = large_arguments();
+    runner.bench("canonical_json/hash_large_arguments", || {
+        sha256_hex(canonical_json(&args).expect("canonical").as_bytes())
+    });
+
+    let repo = tempfile::tempdir().expect("bench tempdir");
+    write_synthetic_repo(repo.path(), REPO_MAP_FILES);
+    runner.bench("repo_map/walk_2000_files", || {
+        resolve_repo_map(repo.path(), RepoMapLimits::default(), None).expect("repo map")
+    });
+}
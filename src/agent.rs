@@ -4,13 +4,21 @@ use crate::agent_output_sanitize::sanitize_user_visible_output as sanitize_user_
 #[cfg(test)]
 use crate::agent_tool_exec::{classify_tool_failure, tool_result_has_error};
 use crate::agent_utils::provider_name;
-use crate::compaction::{context_size_chars, maybe_compact, CompactionReport, CompactionSettings};
-use crate::events::{EventKind, EventSink};
+use crate::compaction::{
+    context_size_chars, CompactionReport, CompactionSettings, DigestRefetchTracker, TokenCounter,
+};
+use crate::events::{
+    CompactionPerformedPayload, CompletionBlockedPayload, ContextOverflowRecoveredPayload,
+    ErrorPayload, EventSink, HookEndPayload, HookErrorPayload, HookStartPayload,
+    ModelResponseEndPayload, PhaseEnteredPayload, PhaseExitedPayload, RunResumedPayload,
+    StepBlockedPayload, StepReplannedPayload, StepVerifiedPayload, ToolCallNearMissPayload,
+};
 use crate::gate::{GateContext, GateDecision, ToolGate};
 use crate::hooks::protocol::{HookInvocationReport, PreModelCompactionPayload, PreModelPayload};
 use crate::hooks::runner::{make_pre_model_input, HookManager};
 use crate::mcp::registry::McpRegistry;
 use crate::operator_queue::{PendingMessageQueue, QueueLimits, QueueSubmitRequest};
+use crate::providers::http::is_context_overflow;
 use crate::providers::ModelProvider;
 use crate::taint::{TaintMode, TaintState, TaintToggle};
 use crate::tools::ToolRuntime;
@@ -21,41 +29,64 @@ use uuid::Uuid;
 
 mod agent_types;
 mod budget_guard;
+#[allow(dead_code)]
+mod builder;
+mod compaction_summary;
 pub(crate) mod completion_policy;
+mod deadline;
+pub(crate) mod denials;
 mod gate_paths;
+mod gate_snapshot;
 pub(crate) mod interrupts;
 mod mcp_drift;
+mod mcp_roots;
 mod model_io;
 mod operator_queue;
+mod parallel_tools;
 mod phase_transitions;
 mod planner_phase;
+mod post_run_hooks;
+mod pre_tool_hooks;
 mod response_guards;
 mod response_normalization;
+mod resume;
 mod run_control;
 mod run_events;
 mod run_finalize;
 mod run_setup;
 mod runtime_completion;
 mod runtime_effects;
+pub mod step_stats;
+mod subtask;
 pub mod task_contract;
+pub mod timeline;
 mod timeouts;
 pub mod tool_facts;
 mod tool_helpers;
+mod write_attribution;
 pub use agent_types::{
     AgentExitReason, AgentOutcome, AgentTaintRecord, McpPinEnforcementMode, McpRuntimeTraceEntry,
     PlanStepConstraint, PlanToolEnforcementMode, PolicyLoadedInfo, ToolCallBudget,
     ToolDecisionRecord,
 };
+#[allow(unused_imports)]
+pub use builder::AgentBuilder;
 pub(crate) use completion_policy::{
     approval_boundary_transition_decision, exact_final_answer_boundary_transition_decision,
     operator_boundary_transition_decision, required_validation_boundary_transition_decision,
 };
+pub use denials::DenialClass;
+pub use gate_snapshot::GateContextSnapshot;
+pub use parallel_tools::ParallelToolBatch;
+pub use step_stats::StepStats;
 #[allow(unused_imports)]
 pub use task_contract::{
     AllowedToolsSemantics, CompletionPolicyV1, ContractValueSource, FinalAnswerMode, RetryPolicyV1,
     TaskContractProvenanceV1, TaskContractV1, ValidationRequirement, WriteRequirement,
 };
 #[allow(unused_imports)]
+pub use timeline::{TimelineEntry, TimelineEntryKind};
+#[allow(unused_imports)]
 pub(crate) use tool_facts::{
     implementation_integrity_violation_from_facts,
     pending_post_write_verification_paths_from_facts, read_before_edit_violation_from_facts,
@@ -73,6 +104,7 @@ use phase_transitions::{
     refresh_phase_state_from_tool_facts,
 };
 use planner_phase::{evaluate_planner_response, PlannerResponseDecision};
+use pre_tool_hooks::{PreToolHookDecision, PreToolHookRunState};
 use response_guards::{
     decide_post_response_phase_guard, decide_required_validation_phase_response,
 };
@@ -96,6 +128,11 @@ fn contains_tool_wrapper_markers(text: &str) -> bool {
     crate::agent_tool_exec::contains_tool_wrapper_markers(text)
 }
 const MAX_SCHEMA_REPAIR_ATTEMPTS: u32 = 2;
+/// Repair turns granted to a final output that fails `final_output_schema`.
+const MAX_OUTPUT_SCHEMA_REPAIRS: u32 = 2;
+/// Emergency compactions per model call before a context overflow ends the
+/// run.
+const MAX_CONTEXT_OVERFLOW_RECOVERIES: u32 = 2;
 const MAX_FAILED_REPEAT_PER_KEY: u32 = 3;
 const MAX_FAILED_REPEAT_PER_TOOL_NAME: u32 = 5;
 const DEFAULT_POST_WRITE_VERIFY_TIMEOUT_MS: u64 = 5_000;
@@ -139,6 +176,61 @@ pub struct Agent<P: ModelProvider> {
     #[allow(dead_code)]
     pub operator_queue_limits: QueueLimits,
     pub operator_queue_rx: Option<std::sync::mpsc::Receiver<QueueSubmitRequest>>,
+    /// A delivered `Cancel` queue message; the run ends before its next step.
+    pub operator_cancel: Option<crate::operator_queue::QueuedOperatorMessage>,
+    pub attribution: Option<crate::attribution::AttributionConfig>,
+    /// Consecutive blank responses without tool calls before the run fails
+    /// with `MODEL_EMPTY_RESPONSE`.
+    pub max_consecutive_empty_responses: u32,
+    /// Tool call origins and compaction digests for the current run; see
+    /// [`DigestRefetchTracker`].
+    pub digest_refetch_tracker: DigestRefetchTracker,
+    /// Fail the run with `MODEL_MISMATCH` when the server reports serving a
+    /// different model than `model`.
+    pub require_exact_model: bool,
+    /// Model name the server reported for the current run, if any.
+    pub served_model: Option<String>,
+    /// Backoff spent on provider retries in the current run.
+    pub provider_retry_wait_ms: u64,
+    /// Declared MCP server roots; path-typed MCP arguments under them are
+    /// checked against the read allowlist and policy before the call runs.
+    pub mcp_root_map: crate::mcp::roots::McpRootMap,
+    /// Derived from emitted events; becomes `AgentOutcome::timeline`.
+    pub timeline_recorder: timeline::TimelineRecorder,
+    /// Gate state captured before the tool call being decided; copied onto
+    /// its decision records.
+    pub gate_context_snapshot: Option<GateContextSnapshot>,
+    /// Leave the snapshot off allow decisions that had no decision source.
+    pub skip_unevaluated_gate_snapshots: bool,
+    /// When the current run must wrap up; set at run start from
+    /// `tool_call_budget.deadline_ms`.
+    pub run_deadline: Option<std::time::Instant>,
+    /// Run a step's tool calls concurrently when every call only reads.
+    pub parallel_readonly_tools: bool,
+    /// Diff preview lines shown for pending `write_file`/`apply_patch`
+    /// approvals (`0` = no preview).
+    pub approval_diff_max_lines: usize,
+    /// Gate decisions and results of the current step's parallel read-only
+    /// batch, if it has one.
+    pub parallel_tool_batch: Option<ParallelToolBatch>,
+    /// Estimates transcript tokens for `compaction_settings.max_context_tokens`;
+    /// calibrated from each response's reported prompt tokens.
+    pub token_counter: Box<dyn TokenCounter>,
+    /// JSON schema the final output must validate against; see
+    /// [`crate::tools::validate_json_output`].
+    pub final_output_schema: Option<serde_json::Value>,
+    /// Nesting level of this agent's run: `0` for a top-level run, one more
+    /// for each `spawn_subtask` above it.
+    pub subtask_depth: u32,
+    /// Run ids of the subtasks spawned during the current run, nested ones
+    /// included.
+    pub subtask_run_ids: Vec<String>,
+    /// When the current run started.
+    pub run_started: Option<std::time::Instant>,
+    /// Fan-out siblings from a v2 step result still waiting to run, in order.
+    pub plan_step_queue: Vec<String>,
+    /// Plan steps the worker has reported done during the current run.
+    pub completed_plan_step_ids: std::collections::BTreeSet<String>,
 }
 
 enum PhaseLoopControl {
@@ -269,6 +361,9 @@ impl<P: ModelProvider> Agent<P> {
             crate::target::ExecTargetKind::Docker => {
                 crate::agent_runtime::state::ExecutionTier::DockerIsolated
             }
+            crate::target::ExecTargetKind::Ssh => {
+                crate::agent_runtime::state::ExecutionTier::RemoteSsh
+            }
             crate::target::ExecTargetKind::Host => {
                 if self.tool_rt.allow_shell {
                     crate::agent_runtime::state::ExecutionTier::ScopedHostShell
@@ -405,19 +500,18 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::PhaseExited,
-            serde_json::json!({
-                "phase": crate::agent::interrupts::run_phase_name(&transition.from_phase),
-                "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-            }),
+            PhaseExitedPayload {
+                phase: crate::agent::interrupts::run_phase_name(&transition.from_phase).to_string(),
+                next_phase: crate::agent::interrupts::run_phase_name(&transition.to_phase)
+                    .to_string(),
+            },
         );
         self.emit_event(
             run_id,
             step,
-            EventKind::PhaseEntered,
-            serde_json::json!({
-                "phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-            }),
+            PhaseEnteredPayload {
+                phase: crate::agent::interrupts::run_phase_name(&transition.to_phase).to_string(),
+            },
         );
     }
 
@@ -468,11 +562,11 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": "required_validation_phase_shell_shape_repaired",
-                        "tool_name": "shell"
-                    }),
+                    StepBlockedPayload {
+                        reason: Some("required_validation_phase_shell_shape_repaired".to_string()),
+                        tool_name: Some("shell".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
                 );
             }
         }
@@ -494,11 +588,11 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": step_block.reason,
-                        "blocked_count": step_block.blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some(step_block.reason.to_string()),
+                        blocked_count: Some(step_block.blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 Ok(PhaseLoopControl::ContinueStep)
             }
@@ -506,11 +600,11 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": step_block.reason,
-                        "blocked_count": step_block.blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some(step_block.reason.to_string()),
+                        blocked_count: Some(step_block.blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 Ok(PhaseLoopControl::ContinueAgentStep)
             }
@@ -518,21 +612,21 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": error.step_block.reason,
-                        "blocked_count": error.step_block.blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some(error.step_block.reason.to_string()),
+                        blocked_count: Some(error.step_block.blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::Error,
-                    serde_json::json!({
-                        "error": error.reason,
-                        "source": error.error_source,
-                        "failure_class": error.failure_class
-                    }),
+                    ErrorPayload {
+                        error: error.reason.clone(),
+                        source: Some(error.error_source.to_string()),
+                        failure_class: Some(error.failure_class.to_string()),
+                        ..ErrorPayload::default()
+                    },
                 );
                 Err(self.finalize_planner_error_with_output_with_end(
                     step,
@@ -588,6 +682,8 @@ impl<P: ModelProvider> Agent<P> {
             tool_calls,
             self.post_validation_final_answer_only_message(user_prompt),
             self.tool_only_reminder_message(),
+            self.max_consecutive_empty_responses,
+            self.empty_response_reminder_message(),
         );
         match apply_post_response_guard_decision(decision, assistant, messages) {
             GuardEffect::Proceed => Ok(PhaseLoopControl::Proceed),
@@ -599,11 +695,11 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": step_block.reason,
-                        "blocked_count": step_block.blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some(step_block.reason.to_string()),
+                        blocked_count: Some(step_block.blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 Ok(PhaseLoopControl::ContinueStep)
             }
@@ -611,11 +707,11 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": step_block.reason,
-                        "blocked_count": step_block.blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some(step_block.reason.to_string()),
+                        blocked_count: Some(step_block.blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 Ok(PhaseLoopControl::ContinueAgentStep)
             }
@@ -623,21 +719,21 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": error.step_block.reason,
-                        "blocked_count": error.step_block.blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some(error.step_block.reason.to_string()),
+                        blocked_count: Some(error.step_block.blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::Error,
-                    serde_json::json!({
-                        "error": error.reason,
-                        "source": error.error_source,
-                        "failure_class": error.failure_class
-                    }),
+                    ErrorPayload {
+                        error: error.reason.clone(),
+                        source: Some(error.error_source.to_string()),
+                        failure_class: Some(error.failure_class.to_string()),
+                        ..ErrorPayload::default()
+                    },
                 );
                 Err(self.finalize_planner_error_with_output_with_end(
                     step,
@@ -698,6 +794,8 @@ impl<P: ModelProvider> Agent<P> {
             active_plan_step_idx: *active_plan_step_idx,
             plan_step_constraints: &self.plan_step_constraints,
             step_retry_counts,
+            queued_step_ids: &self.plan_step_queue,
+            completed_step_ids: &self.completed_plan_step_ids,
         }) {
             PlannerResponseDecision::Proceed => {}
             PlannerResponseDecision::RemindControlEnvelope { blocked_count } => {
@@ -707,12 +805,14 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": "invalid_control_envelope",
-                        "required_schema_version": crate::planner::STEP_RESULT_SCHEMA_VERSION,
-                        "blocked_count": blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some("invalid_control_envelope".to_string()),
+                        required_schema_version: Some(
+                            crate::planner::STEP_RESULT_SCHEMA_VERSION.to_string(),
+                        ),
+                        blocked_count: Some(blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 messages.push(Message {
                     role: Role::Developer,
@@ -730,12 +830,14 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": "invalid_control_envelope",
-                        "required_schema_version": crate::planner::STEP_RESULT_SCHEMA_VERSION,
-                        "blocked_count": blocked_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some("invalid_control_envelope".to_string()),
+                        required_schema_version: Some(
+                            crate::planner::STEP_RESULT_SCHEMA_VERSION.to_string(),
+                        ),
+                        blocked_count: Some(blocked_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 return Err(self.finalize_planner_error_with_end(
                     step,
@@ -760,6 +862,7 @@ impl<P: ModelProvider> Agent<P> {
                 completed_step_id,
                 next_step_id,
                 next_active_plan_step_idx,
+                queued_step_ids,
                 user_output,
             } => {
                 runtime_checkpoint
@@ -774,14 +877,16 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepVerified,
-                    serde_json::json!({
-                        "step_id": completed_step_id,
-                        "next_step_id": next_step_id,
-                        "status": "done"
-                    }),
+                    StepVerifiedPayload {
+                        step_id: completed_step_id.clone(),
+                        next_step_id: next_step_id.clone(),
+                        status: "done".to_string(),
+                        queued_step_ids: queued_step_ids.clone(),
+                    },
                 );
                 step_retry_counts.remove(&completed_step_id);
+                self.completed_plan_step_ids.insert(completed_step_id);
+                self.plan_step_queue = queued_step_ids;
                 *active_plan_step_idx = next_active_plan_step_idx;
             }
             PlannerResponseDecision::InvalidDoneTransition {
@@ -794,12 +899,12 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "step_id": step_id,
-                        "expected_step_id": expected_step_id,
-                        "reason": "invalid_done_transition"
-                    }),
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        expected_step_id: Some(expected_step_id.clone()),
+                        reason: Some("invalid_done_transition".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 return Err(self.finalize_planner_error_with_end(
                     step,
@@ -832,12 +937,12 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "step_id": step_id,
-                        "next_step_id": next_step_id,
-                        "reason": "invalid_next_step_id"
-                    }),
+                    StepBlockedPayload {
+                        step_id: Some(step_id),
+                        next_step_id: Some(next_step_id.clone()),
+                        reason: Some("invalid_next_step_id".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 return Err(self.finalize_planner_error_with_end(
                     step,
@@ -857,6 +962,78 @@ impl<P: ModelProvider> Agent<P> {
                     taint_state,
                 ));
             }
+            PlannerResponseDecision::InvalidFanOut { step_id, reason } => {
+                runtime_checkpoint
+                    .tool_protocol_state
+                    .blocked_control_envelope_count = 0;
+                self.emit_event(
+                    run_id,
+                    step,
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        reason: Some("invalid_next_step_ids".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
+                );
+                return Err(self.finalize_planner_error_with_end(
+                    step,
+                    run_id.to_string(),
+                    started_at.to_string(),
+                    format!(
+                        "invalid next_step_ids in worker status for {}: {}",
+                        step_id, reason
+                    ),
+                    messages.clone(),
+                    observed_tool_calls.to_vec(),
+                    observed_tool_decisions.to_vec(),
+                    request_context_chars,
+                    last_compaction_report.clone(),
+                    hook_invocations.to_vec(),
+                    provider_retry_count,
+                    provider_error_count,
+                    saw_token_usage,
+                    total_token_usage,
+                    taint_state,
+                ));
+            }
+            PlannerResponseDecision::FinalWithPendingFanOut {
+                step_id,
+                pending_step_ids,
+            } => {
+                runtime_checkpoint
+                    .tool_protocol_state
+                    .blocked_control_envelope_count = 0;
+                self.emit_event(
+                    run_id,
+                    step,
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        reason: Some("pending_fan_out_steps".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
+                );
+                return Err(self.finalize_planner_error_with_end(
+                    step,
+                    run_id.to_string(),
+                    started_at.to_string(),
+                    format!(
+                        "invalid step completion transition: got final after {}, fan-out steps still pending ({})",
+                        step_id,
+                        pending_step_ids.join(", ")
+                    ),
+                    messages.clone(),
+                    observed_tool_calls.to_vec(),
+                    observed_tool_decisions.to_vec(),
+                    request_context_chars,
+                    last_compaction_report.clone(),
+                    hook_invocations.to_vec(),
+                    provider_retry_count,
+                    provider_error_count,
+                    saw_token_usage,
+                    total_token_usage,
+                    taint_state,
+                ));
+            }
             PlannerResponseDecision::StepRetry {
                 step_id,
                 retry_count,
@@ -880,12 +1057,12 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "step_id": step_id,
-                        "reason": "retry_limit_exceeded",
-                        "retry_count": retry_count
-                    }),
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        reason: Some("retry_limit_exceeded".to_string()),
+                        retry_count: Some(retry_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 return Err(self.finalize_planner_error_with_end(
                     step,
@@ -915,12 +1092,12 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "step_id": step_id,
-                        "expected_step_id": expected_step_id,
-                        "reason": "invalid_retry_transition"
-                    }),
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        expected_step_id: Some(expected_step_id.clone()),
+                        reason: Some("invalid_retry_transition".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 return Err(self.finalize_planner_error_with_end(
                     step,
@@ -943,6 +1120,78 @@ impl<P: ModelProvider> Agent<P> {
                     taint_state,
                 ));
             }
+            PlannerResponseDecision::RetryOfDoneStep { step_id } => {
+                runtime_checkpoint
+                    .tool_protocol_state
+                    .blocked_control_envelope_count = 0;
+                self.emit_event(
+                    run_id,
+                    step,
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        reason: Some("retry_of_done_step".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
+                );
+                return Err(self.finalize_planner_error_with_end(
+                    step,
+                    run_id.to_string(),
+                    started_at.to_string(),
+                    format!("invalid retry transition: step {} is already done", step_id),
+                    messages.clone(),
+                    observed_tool_calls.to_vec(),
+                    observed_tool_decisions.to_vec(),
+                    request_context_chars,
+                    last_compaction_report.clone(),
+                    hook_invocations.to_vec(),
+                    provider_retry_count,
+                    provider_error_count,
+                    saw_token_usage,
+                    total_token_usage,
+                    taint_state,
+                ));
+            }
+            PlannerResponseDecision::RetryCounterMismatch {
+                step_id,
+                retry_of,
+                expected,
+            } => {
+                runtime_checkpoint
+                    .tool_protocol_state
+                    .blocked_control_envelope_count = 0;
+                self.emit_event(
+                    run_id,
+                    step,
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        retry_count: Some(expected),
+                        reason: Some("retry_counter_mismatch".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
+                );
+                return Err(self.finalize_planner_error_with_end(
+                    step,
+                    run_id.to_string(),
+                    started_at.to_string(),
+                    format!(
+                        "invalid retry transition: retry_of {} for step {}, expected {}",
+                        retry_of.map_or_else(|| "missing".to_string(), |n| n.to_string()),
+                        step_id,
+                        expected
+                    ),
+                    messages.clone(),
+                    observed_tool_calls.to_vec(),
+                    observed_tool_decisions.to_vec(),
+                    request_context_chars,
+                    last_compaction_report.clone(),
+                    hook_invocations.to_vec(),
+                    provider_retry_count,
+                    provider_error_count,
+                    saw_token_usage,
+                    total_token_usage,
+                    taint_state,
+                ));
+            }
             PlannerResponseDecision::ReplanRequested { step_id, status } => {
                 runtime_checkpoint
                     .tool_protocol_state
@@ -950,11 +1199,10 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepReplanned,
-                    serde_json::json!({
-                        "step_id": step_id,
-                        "status": status
-                    }),
+                    StepReplannedPayload {
+                        step_id: step_id.clone(),
+                        status: status.clone(),
+                    },
                 );
                 return Err(self.finalize_planner_error_with_end(
                     step,
@@ -984,11 +1232,11 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "step_id": step_id,
-                        "reason": "worker_fail_transition"
-                    }),
+                    StepBlockedPayload {
+                        step_id: Some(step_id.clone()),
+                        reason: Some("worker_fail_transition".to_string()),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 return Err(self.finalize_planner_error_with_end(
                     step,
@@ -1044,7 +1292,48 @@ impl<P: ModelProvider> Agent<P> {
         taint_state: &mut TaintState,
         successful_write_tool_ok_this_step: &mut bool,
     ) -> Result<ToolLoopControl, AgentOutcome> {
-        for tc in tool_calls {
+        self.parallel_tool_batch = None;
+        if self.parallel_readonly_batch_eligible(tool_calls)
+            && self
+                .parallel_readonly_batch_runnable(
+                    tool_calls,
+                    active_plan_step_idx,
+                    failed_repeat_counts,
+                    expected_mcp_catalog_hash_hex,
+                    expected_mcp_docs_hash_hex,
+                )
+                .await
+            && !self.deadline_passed()
+        {
+            self.parallel_tool_batch =
+                Some(self.prepare_parallel_readonly_batch(step, tool_calls).await);
+        }
+        for (idx, tc) in tool_calls.iter().enumerate() {
+            if self.deadline_passed() {
+                return Err(self
+                    .finalize_deadline_reached(
+                        run_id,
+                        step,
+                        started_at,
+                        &tool_calls[idx..],
+                        messages.clone(),
+                        observed_tool_calls.clone(),
+                        observed_tool_decisions.clone(),
+                        last_compaction_report.clone(),
+                        hook_invocations.clone(),
+                        provider_retry_count,
+                        provider_error_count,
+                        saw_token_usage,
+                        total_token_usage.clone(),
+                        taint_state,
+                    )
+                    .await);
+            }
+            self.gate_context_snapshot = Some(self.capture_gate_context_snapshot(
+                active_plan_step_idx,
+                tool_budget_usage,
+                taint_state,
+            ));
             self.record_detected_tool_call(run_id, step, tc, observed_tool_calls);
             match self
                 .check_mcp_drift_for_tool_call(
@@ -1071,6 +1360,36 @@ impl<P: ModelProvider> Agent<P> {
                 McpDriftDecision::Continue => {}
                 McpDriftDecision::Finalize(outcome) => return Err(*outcome),
             }
+            let rewritten_tc;
+            let tc = match self
+                .run_pre_tool_hooks_for_call(
+                    run_id,
+                    step,
+                    tc,
+                    hook_invocations,
+                    &PreToolHookRunState {
+                        started_at,
+                        messages,
+                        observed_tool_calls,
+                        observed_tool_decisions,
+                        request_context_chars,
+                        last_compaction_report,
+                        provider_retry_count,
+                        provider_error_count,
+                        saw_token_usage,
+                        total_token_usage,
+                        taint_state,
+                    },
+                )
+                .await
+            {
+                PreToolHookDecision::Continue(None) => tc,
+                PreToolHookDecision::Continue(Some(call)) => {
+                    rewritten_tc = call;
+                    &rewritten_tc
+                }
+                PreToolHookDecision::Finalize(outcome) => return Err(*outcome),
+            };
             let planning_ctx = self.build_tool_call_planning_context(
                 active_plan_step_idx,
                 tc,
@@ -1140,7 +1459,27 @@ impl<P: ModelProvider> Agent<P> {
                 planner_hash_hex,
                 decision_exec_target,
             ) = self.gate_decision_metadata_for_tool(tc, taint_state);
-            if !planning_ctx.plan_tool_allowed {
+            let plan_arg_violation = match planning_ctx.plan_arg_violation {
+                Some(violation)
+                    if planning_ctx.plan_tool_allowed
+                        && self.plan_tool_enforcement == PlanToolEnforcementMode::Soft =>
+                {
+                    self.warn_plan_arg_violation(
+                        run_id,
+                        step,
+                        tc,
+                        planning_ctx.plan_step_id.clone(),
+                        active_plan_step_idx,
+                        violation,
+                        planner_hash_hex.clone(),
+                        taint_state,
+                        observed_tool_decisions,
+                    );
+                    None
+                }
+                violation => violation,
+            };
+            if !planning_ctx.plan_tool_allowed || plan_arg_violation.is_some() {
                 match self.handle_plan_constraint_deny(
                     run_id.to_string(),
                     step,
@@ -1148,6 +1487,7 @@ impl<P: ModelProvider> Agent<P> {
                     planning_ctx.plan_step_id,
                     active_plan_step_idx,
                     planning_ctx.plan_allowed_tools,
+                    plan_arg_violation.filter(|_| planning_ctx.plan_tool_allowed),
                     approval_mode_meta.clone(),
                     auto_scope_meta.clone(),
                     approval_key_version_meta.clone(),
@@ -1167,6 +1507,7 @@ impl<P: ModelProvider> Agent<P> {
                     saw_token_usage,
                     total_token_usage,
                     taint_state,
+                    failed_repeat_counts,
                 ) {
                     PlanConstraintDecision::Continue => {}
                     PlanConstraintDecision::ContinueToolLoop => continue,
@@ -1176,7 +1517,7 @@ impl<P: ModelProvider> Agent<P> {
                     PlanConstraintDecision::Finalize(outcome) => return Err(*outcome),
                 }
             }
-            match self.gate.decide(&self.gate_ctx, tc) {
+            match self.decide_tool_call(tc) {
                 GateDecision::Allow {
                     approval_id,
                     approval_key,
@@ -2210,7 +2551,7 @@ impl<P: ModelProvider> Agent<P> {
         run_id: &str,
         step: u32,
         started_at: &str,
-        messages: &[Message],
+        messages: &mut Vec<Message>,
         observed_tool_calls: &[ToolCall],
         observed_tool_decisions: &[ToolDecisionRecord],
         last_compaction_report: &Option<CompactionReport>,
@@ -2224,9 +2565,48 @@ impl<P: ModelProvider> Agent<P> {
         allowed_tool_names: &std::collections::BTreeSet<String>,
         tools_sorted: Vec<ToolDef>,
     ) -> Result<GeneratedTurnResponse, AgentOutcome> {
-        let req = self.build_generate_request(messages, tools_sorted);
-        let request_context_chars = context_size_chars(&req.messages);
-        let resp_result = self.execute_model_request(run_id, step, req).await;
+        let before_recovery = (context_size_chars(messages), messages.len());
+        let mut overflow_recoveries = 0u32;
+        let mut recovered_keep_last = None;
+        let (resp_result, request_context_chars) = loop {
+            let req = self.build_generate_request(messages, tools_sorted.clone());
+            let request_context_chars = context_size_chars(&req.messages);
+            let resp_result = self.execute_model_request(run_id, step, req).await;
+            let absorbed_retries = self.provider.take_retries();
+            self.record_provider_retries(run_id, step, &absorbed_retries, provider_retry_count);
+            let Err(e) = &resp_result else {
+                break (resp_result, request_context_chars);
+            };
+            if !is_context_overflow(e) || overflow_recoveries >= MAX_CONTEXT_OVERFLOW_RECOVERIES {
+                break (resp_result, request_context_chars);
+            }
+            let Some(keep_last) = self.emergency_compact(messages, overflow_recoveries + 1) else {
+                break (resp_result, request_context_chars);
+            };
+            self.record_provider_error_events(
+                run_id,
+                step,
+                e,
+                provider_retry_count,
+                provider_error_count,
+            );
+            overflow_recoveries += 1;
+            recovered_keep_last = Some(keep_last);
+        };
+        if let (Ok(_), Some(keep_last)) = (&resp_result, recovered_keep_last) {
+            self.emit_event(
+                run_id,
+                step,
+                ContextOverflowRecoveredPayload {
+                    attempts: overflow_recoveries,
+                    before_chars: before_recovery.0,
+                    after_chars: context_size_chars(messages),
+                    before_messages: before_recovery.1,
+                    after_messages: messages.len(),
+                    keep_last,
+                },
+            );
+        }
 
         let mut resp = match resp_result {
             Ok(r) => r,
@@ -2241,9 +2621,32 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::Error,
-                    serde_json::json!({"error": e.to_string()}),
+                    ErrorPayload {
+                        error: e.to_string(),
+                        ..ErrorPayload::default()
+                    },
                 );
+                if is_context_overflow(&e) {
+                    return Err(self.finalize_context_overflow_with_end(
+                        step,
+                        run_id.to_string(),
+                        started_at.to_string(),
+                        format!(
+                            "context overflow after {overflow_recoveries} emergency compaction(s): {e}"
+                        ),
+                        messages.to_vec(),
+                        observed_tool_calls.to_vec(),
+                        observed_tool_decisions.to_vec(),
+                        request_context_chars,
+                        last_compaction_report.clone(),
+                        hook_invocations.to_vec(),
+                        *provider_retry_count,
+                        *provider_error_count,
+                        *saw_token_usage,
+                        total_token_usage,
+                        taint_state,
+                    ));
+                }
                 return Err(self.finalize_provider_error_with_end(
                     step,
                     run_id.to_string(),
@@ -2263,6 +2666,35 @@ impl<P: ModelProvider> Agent<P> {
                 ));
             }
         };
+        if let Some(reason) = self.verify_served_model(run_id, step, resp.served_model.as_deref()) {
+            self.emit_event(
+                run_id,
+                step,
+                ErrorPayload {
+                    error: reason.clone(),
+                    source: Some("model_verification".to_string()),
+                    failure_class: Some("E_MODEL_MISMATCH".to_string()),
+                    ..ErrorPayload::default()
+                },
+            );
+            return Err(self.finalize_provider_error_with_end(
+                step,
+                run_id.to_string(),
+                started_at.to_string(),
+                reason,
+                messages.to_vec(),
+                observed_tool_calls.to_vec(),
+                observed_tool_decisions.to_vec(),
+                request_context_chars,
+                last_compaction_report.clone(),
+                hook_invocations.to_vec(),
+                *provider_retry_count,
+                *provider_error_count,
+                *saw_token_usage,
+                total_token_usage,
+                taint_state,
+            ));
+        }
         match normalize_assistant_response(&mut resp, step, allowed_tool_names) {
             AssistantResponseNormalization::Ready => {}
             AssistantResponseNormalization::MalformedWrapper => {
@@ -2274,13 +2706,13 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         run_id,
                         step,
-                        EventKind::Error,
-                        serde_json::json!({
-                            "error": reason,
-                            "source": "tool_protocol_guard",
-                            "failure_class": "E_PROTOCOL_TOOL_WRAPPER",
-                            "attempt": malformed_tool_call_attempts
-                        }),
+                        ErrorPayload {
+                            error: reason.clone(),
+                            source: Some("tool_protocol_guard".to_string()),
+                            failure_class: Some("E_PROTOCOL_TOOL_WRAPPER".to_string()),
+                            attempt: Some(*malformed_tool_call_attempts),
+                            ..ErrorPayload::default()
+                        },
                     );
                     return Err(self.finalize_planner_error_with_output_with_end(
                         step,
@@ -2301,6 +2733,20 @@ impl<P: ModelProvider> Agent<P> {
                     ));
                 }
             }
+            AssistantResponseNormalization::NearMissWrapper { near_misses } => {
+                for near_miss in near_misses {
+                    self.emit_event(
+                        run_id,
+                        step,
+                        ToolCallNearMissPayload {
+                            reason: near_miss.reason.to_string(),
+                            tool_name: near_miss.tool_name,
+                        },
+                    );
+                }
+            }
+            AssistantResponseNormalization::MultipleToolCalls { .. }
+                if self.parallel_readonly_batch_eligible(&resp.tool_calls) => {}
             AssistantResponseNormalization::MultipleToolCalls { count } => {
                 let reason = format!(
                     "MODEL_TOOL_PROTOCOL_VIOLATION: multiple tool calls in a single assistant step (max 1, got {})",
@@ -2309,13 +2755,13 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::Error,
-                    serde_json::json!({
-                        "error": reason,
-                        "source": "tool_protocol_guard",
-                        "failure_class": "E_PROTOCOL_MULTI_TOOL",
-                        "tool_calls": count
-                    }),
+                    ErrorPayload {
+                        error: reason.clone(),
+                        source: Some("tool_protocol_guard".to_string()),
+                        failure_class: Some("E_PROTOCOL_MULTI_TOOL".to_string()),
+                        tool_calls: Some(count),
+                        ..ErrorPayload::default()
+                    },
                 );
                 return Err(self.finalize_planner_error_with_output_with_end(
                     step,
@@ -2369,12 +2815,19 @@ impl<P: ModelProvider> Agent<P> {
     ) -> Result<Result<NormalizedTurnState, PhaseStepDispatch>, AgentOutcome> {
         if let Some(usage) = &resp.usage {
             apply_usage_totals(usage, saw_token_usage, total_token_usage);
+            if let Some(prompt_tokens) = usage.prompt_tokens {
+                self.token_counter
+                    .calibrate(request_context_chars, prompt_tokens as usize);
+            }
         }
         self.emit_event(
             run_id,
             step,
-            EventKind::ModelResponseEnd,
-            serde_json::json!({"tool_calls": resp.tool_calls.len()}),
+            ModelResponseEndPayload {
+                tool_calls: resp.tool_calls.len(),
+                usage: resp.usage.clone(),
+                ..ModelResponseEndPayload::default()
+            },
         );
         match self.handle_required_validation_phase_response(
             user_prompt,
@@ -2704,6 +3157,7 @@ impl<P: ModelProvider> Agent<P> {
             plan_tool_enforcement: self.plan_tool_enforcement,
             active_plan_step_idx: *active_plan_step_idx,
             plan_step_constraints_len: self.plan_step_constraints.len(),
+            queued_plan_step_count: self.plan_step_queue.len(),
             tool_only_phase_active: runtime_checkpoint
                 .tool_protocol_state
                 .tool_only_phase_active,
@@ -2747,6 +3201,7 @@ impl<P: ModelProvider> Agent<P> {
                 runtime_checkpoint
                     .retry_state
                     .required_validation_retry_count,
+                runtime_checkpoint.retry_state.output_schema_repair_count,
             )
             .await;
         match apply_runtime_completion_action_to_checkpoint(
@@ -2843,11 +3298,13 @@ impl<P: ModelProvider> Agent<P> {
             self.emit_event(
                 run_id,
                 step,
-                EventKind::CompletionBlocked,
-                serde_json::json!({
-                    "reason": effect.reason,
-                    "next_phase": crate::agent::interrupts::run_phase_name(&effect.transition.to_phase)
-                }),
+                CompletionBlockedPayload {
+                    reason: effect.reason.clone(),
+                    next_phase: crate::agent::interrupts::run_phase_name(
+                        &effect.transition.to_phase,
+                    )
+                    .to_string(),
+                },
             );
         }
 
@@ -2943,6 +3400,36 @@ impl<P: ModelProvider> Agent<P> {
     ) -> Result<PhaseStepDispatch, AgentOutcome> {
         runtime_checkpoint.step_index = step;
         self.drain_external_operator_queue(run_id, step);
+        if self.operator_cancel.is_none() && self.operator_queue.has_pending_cancel() {
+            // Cancel queued since the last boundary (or before the first
+            // provider call): this step has not started, so the run is idle.
+            self.deliver_operator_queue_at_boundary(
+                run_id,
+                step,
+                crate::operator_queue::DeliveryBoundary::TurnIdle,
+                messages,
+            );
+        }
+        if let Some(cancel) = self.operator_cancel.take() {
+            let final_prompt_size_chars = context_size_chars(messages);
+            return Err(self.finalize_operator_cancelled_with_end(
+                step,
+                run_id.to_string(),
+                started_at.to_string(),
+                &cancel,
+                messages.clone(),
+                observed_tool_calls.clone(),
+                observed_tool_decisions.clone(),
+                final_prompt_size_chars,
+                last_compaction_report.clone(),
+                hook_invocations.clone(),
+                *provider_retry_count,
+                *provider_error_count,
+                *saw_token_usage,
+                total_token_usage,
+                taint_state,
+            ));
+        }
         if let Some(reason) = self.check_wall_time_budget_exceeded(run_id, step, run_started) {
             let final_prompt_size_chars = context_size_chars(messages);
             return Err(self.finalize_budget_exceeded(
@@ -2962,12 +3449,33 @@ impl<P: ModelProvider> Agent<P> {
                 taint_state,
             ));
         }
+        if self.deadline_passed() {
+            return Err(self
+                .finalize_deadline_reached(
+                    run_id,
+                    step,
+                    started_at,
+                    &[],
+                    messages.clone(),
+                    observed_tool_calls.clone(),
+                    observed_tool_decisions.clone(),
+                    last_compaction_report.clone(),
+                    hook_invocations.clone(),
+                    *provider_retry_count,
+                    *provider_error_count,
+                    *saw_token_usage,
+                    total_token_usage.clone(),
+                    taint_state,
+                )
+                .await);
+        }
         self.emit_plan_step_started_if_needed(
             run_id,
             step,
             *active_plan_step_idx,
             announced_plan_step_id,
         );
+        let before_compaction = self.transcript_before_summarizing_compaction(messages);
         let compacted = self.compact_messages_for_step(
             run_id,
             step,
@@ -2975,8 +3483,8 @@ impl<P: ModelProvider> Agent<P> {
             provider_retry_count,
             provider_error_count,
         );
-        let compacted = match compacted {
-            Ok(c) => c,
+        let compaction_report = match compacted {
+            Ok(report) => report,
             Err(err_text) => {
                 return Err(self.finalize_provider_error_with_end(
                     step,
@@ -2997,23 +3505,36 @@ impl<P: ModelProvider> Agent<P> {
                 ));
             }
         };
-        if let Some(report) = compacted.report.clone() {
+        if let Some(mut report) = compaction_report {
+            if let Some(before) = &before_compaction {
+                report = self
+                    .summarize_evicted_messages(
+                        before,
+                        messages,
+                        report,
+                        tool_budget_usage,
+                        saw_token_usage,
+                        total_token_usage,
+                    )
+                    .await;
+            }
             self.emit_event(
                 run_id,
                 step,
-                EventKind::CompactionPerformed,
-                serde_json::json!({
-                    "before_chars": report.before_chars,
-                    "after_chars": report.after_chars,
-                    "before_messages": report.before_messages,
-                    "after_messages": report.after_messages,
-                    "compacted_messages": report.compacted_messages,
-                    "summary_digest_sha256": report.summary_digest_sha256
-                }),
+                CompactionPerformedPayload {
+                    before_chars: report.before_chars,
+                    after_chars: report.after_chars,
+                    before_messages: report.before_messages,
+                    after_messages: report.after_messages,
+                    compacted_messages: report.compacted_messages,
+                    summary_digest_sha256: report.summary_digest_sha256.clone(),
+                    phase: None,
+                    digested_tool_results: report.digested_tool_results.len(),
+                    summary_generated: report.summary_generated,
+                },
             );
             *last_compaction_report = Some(report);
         }
-        *messages = compacted.messages;
         let mut tools_sorted = self.tools.clone();
         tools_sorted.sort_by(|a, b| a.name.cmp(&b.name));
         if self.hooks.enabled() {
@@ -3054,27 +3575,32 @@ impl<P: ModelProvider> Agent<P> {
             );
             match self.hooks.run_pre_model_hooks(hook_input).await {
                 Ok(result) => {
-                    for inv in &result.invocations {
+                    self.emit_hook_budget_exhausted(run_id, step, &result.budget_exhausted);
+                    for inv in result
+                        .invocations
+                        .iter()
+                        .filter(|inv| !inv.skipped_due_to_budget)
+                    {
                         self.emit_event(
                             run_id,
                             step,
-                            EventKind::HookStart,
-                            serde_json::json!({
-                                "hook_name": inv.hook_name,
-                                "stage": inv.stage
-                            }),
+                            HookStartPayload {
+                                hook_name: inv.hook_name.clone(),
+                                stage: inv.stage.clone(),
+                            },
                         );
                         self.emit_event(
                             run_id,
                             step,
-                            EventKind::HookEnd,
-                            serde_json::json!({
-                                "hook_name": inv.hook_name,
-                                "stage": inv.stage,
-                                "action": inv.action,
-                                "modified": inv.modified,
-                                "duration_ms": inv.duration_ms
-                            }),
+                            HookEndPayload {
+                                hook_name: inv.hook_name.clone(),
+                                stage: inv.stage.clone(),
+                                action: inv.action.clone(),
+                                modified: inv.modified,
+                                duration_ms: inv.duration_ms as u64,
+                                input_digest: None,
+                                output_digest: None,
+                            },
                         );
                     }
                     hook_invocations.extend(result.invocations);
@@ -3101,33 +3627,37 @@ impl<P: ModelProvider> Agent<P> {
                     }
                     if !result.append_messages.is_empty() {
                         messages.extend(result.append_messages);
-                        if self.compaction_settings.max_context_chars > 0 {
-                            let compacted_again =
-                                maybe_compact(messages, &self.compaction_settings)
-                                    .map_err(|e| format!("compaction failed after hooks: {e}"));
+                        if self.compaction_settings.has_budget() {
+                            let compacted_again = self
+                                .compact_tracking_digests(messages)
+                                .map_err(|e| format!("compaction failed after hooks: {e}"));
                             match compacted_again {
-                                Ok(out) => {
-                                    if let Some(report) = out.report.clone() {
+                                Ok(report) => {
+                                    if let Some(report) = report {
                                         self.emit_event(
                                             run_id,
                                             step,
-                                            EventKind::CompactionPerformed,
-                                            serde_json::json!({
-                                                "before_chars": report.before_chars,
-                                                "after_chars": report.after_chars,
-                                                "before_messages": report.before_messages,
-                                                "after_messages": report.after_messages,
-                                                "compacted_messages": report.compacted_messages,
-                                                "summary_digest_sha256": report.summary_digest_sha256,
-                                                "phase": "post_pre_model_hooks"
-                                            }),
+                                            CompactionPerformedPayload {
+                                                before_chars: report.before_chars,
+                                                after_chars: report.after_chars,
+                                                before_messages: report.before_messages,
+                                                after_messages: report.after_messages,
+                                                compacted_messages: report.compacted_messages,
+                                                summary_digest_sha256: report
+                                                    .summary_digest_sha256
+                                                    .clone(),
+                                                phase: Some("post_pre_model_hooks".to_string()),
+                                                digested_tool_results: report
+                                                    .digested_tool_results
+                                                    .len(),
+                                                summary_generated: report.summary_generated,
+                                            },
                                         );
                                         *last_compaction_report = Some(report);
                                     }
-                                    *messages = out.messages;
-                                    if self.compaction_settings.max_context_chars > 0
-                                        && context_size_chars(messages)
-                                            > self.compaction_settings.max_context_chars
+                                    if self
+                                        .compaction_settings
+                                        .exceeds_budget(messages, self.token_counter.as_ref())
                                     {
                                         let prompt_chars = context_size_chars(messages);
                                         return Err(self.finalize_provider_error_with_end(
@@ -3177,8 +3707,10 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         run_id,
                         step,
-                        EventKind::HookError,
-                        serde_json::json!({"stage":"pre_model","error": e.message}),
+                        HookErrorPayload {
+                            stage: "pre_model".to_string(),
+                            error: e.message.clone(),
+                        },
                     );
                     let prompt_chars = context_size_chars(messages);
                     return Err(self.finalize_hook_aborted_with_end(
@@ -3253,6 +3785,44 @@ impl<P: ModelProvider> Agent<P> {
         session_messages: Vec<Message>,
         injected_messages: Vec<Message>,
         initial_runtime_checkpoint: Option<crate::agent_runtime::state::RunCheckpointV1>,
+    ) -> AgentOutcome {
+        self.run_from_messages(
+            user_prompt,
+            session_messages,
+            injected_messages,
+            initial_runtime_checkpoint,
+            None,
+        )
+        .await
+    }
+
+    async fn run_from_messages(
+        &mut self,
+        user_prompt: &str,
+        session_messages: Vec<Message>,
+        injected_messages: Vec<Message>,
+        initial_runtime_checkpoint: Option<crate::agent_runtime::state::RunCheckpointV1>,
+        resumed: Option<RunResumedPayload>,
+    ) -> AgentOutcome {
+        let outcome = self
+            .run_steps_from_messages(
+                user_prompt,
+                session_messages,
+                injected_messages,
+                initial_runtime_checkpoint,
+                resumed,
+            )
+            .await;
+        self.run_post_run_hooks(outcome).await
+    }
+
+    async fn run_steps_from_messages(
+        &mut self,
+        user_prompt: &str,
+        session_messages: Vec<Message>,
+        injected_messages: Vec<Message>,
+        initial_runtime_checkpoint: Option<crate::agent_runtime::state::RunCheckpointV1>,
+        resumed: Option<RunResumedPayload>,
     ) -> AgentOutcome {
         let enforce_implementation_integrity_guard =
             injected_messages_enforce_implementation_integrity_guard(&injected_messages);
@@ -3261,8 +3831,15 @@ impl<P: ModelProvider> Agent<P> {
             .clone()
             .unwrap_or_else(|| Uuid::new_v4().to_string());
         self.gate_ctx.run_id = Some(run_id.clone());
+        self.digest_refetch_tracker = DigestRefetchTracker::default();
+        self.served_model = None;
+        self.provider_retry_wait_ms = 0;
+        self.timeline_recorder = timeline::TimelineRecorder::default();
         let started_at = crate::trust::now_rfc3339();
         self.emit_run_start_events(&run_id);
+        if let Some(resumed) = resumed {
+            self.emit_event(&run_id, 0, resumed);
+        }
         let mut messages =
             self.build_initial_messages(user_prompt, session_messages, injected_messages);
         let mut observed_tool_calls = Vec::new();
@@ -3290,6 +3867,14 @@ impl<P: ModelProvider> Agent<P> {
             std::collections::BTreeMap::new();
         let mut tool_budget_usage = ToolCallBudgetUsage::default();
         let run_started = std::time::Instant::now();
+        self.run_started = Some(run_started);
+        self.subtask_run_ids.clear();
+        self.operator_cancel = None;
+        self.plan_step_queue.clear();
+        self.completed_plan_step_ids.clear();
+        self.run_deadline = (self.tool_call_budget.deadline_ms > 0).then(|| {
+            run_started + std::time::Duration::from_millis(self.tool_call_budget.deadline_ms)
+        });
         let mut announced_plan_step_id: Option<String> = None;
         let (expected_mcp_catalog_hash_hex, expected_mcp_docs_hash_hex, allowed_tool_names) =
             self.compute_run_preflight_caches();
@@ -3337,6 +3922,25 @@ impl<P: ModelProvider> Agent<P> {
         }
 
         let final_prompt_size_chars = context_size_chars(&messages);
+        if let Some(cancel) = self.operator_cancel.take() {
+            return self.finalize_operator_cancelled_with_end(
+                self.max_steps as u32,
+                run_id,
+                started_at,
+                &cancel,
+                messages,
+                observed_tool_calls,
+                observed_tool_decisions,
+                final_prompt_size_chars,
+                last_compaction_report,
+                hook_invocations,
+                provider_retry_count,
+                provider_error_count,
+                saw_token_usage,
+                &total_token_usage,
+                &taint_state,
+            );
+        }
         self.finalize_max_steps_with_end(
             self.max_steps as u32,
             run_id,
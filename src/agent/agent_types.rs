@@ -1,5 +1,7 @@
-use crate::compaction::{CompactionReport, CompactionSettings};
-use crate::hooks::protocol::HookInvocationReport;
+use std::collections::BTreeMap;
+
+use crate::compaction::{CompactionReport, CompactionSettings, DigestRefetchStatsV1};
+use crate::hooks::protocol::{HookInvocationReport, HookRunStats};
 use crate::taint::TaintSpan;
 use crate::trust::policy::McpAllowSummary;
 use crate::types::{Message, TokenUsage, ToolCall};
@@ -14,7 +16,15 @@ pub enum AgentExitReason {
     HookAborted,
     MaxSteps,
     BudgetExceeded,
+    /// The run deadline passed; the final output is the model's summary.
+    DeadlineExceeded,
     Cancelled,
+    /// The provider rejected the prompt as longer than the model's context
+    /// window and emergency compaction could not bring it under.
+    ContextOverflow,
+    /// The final output still failed `final_output_schema` after the repair
+    /// turns; `error` lists the validation errors.
+    OutputSchemaViolation,
 }
 
 impl AgentExitReason {
@@ -28,14 +38,21 @@ impl AgentExitReason {
             AgentExitReason::HookAborted => "hook_aborted",
             AgentExitReason::MaxSteps => "max_steps",
             AgentExitReason::BudgetExceeded => "budget_exceeded",
+            AgentExitReason::DeadlineExceeded => "deadline_exceeded",
             AgentExitReason::Cancelled => "cancelled",
+            AgentExitReason::ContextOverflow => "context_overflow",
+            AgentExitReason::OutputSchemaViolation => "output_schema_violation",
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct ToolCallBudget {
     pub max_wall_time_ms: u64,
+    /// Soft wall-clock limit for the run. Once passed, remaining tool calls
+    /// are skipped and the model gets one tool-less turn to summarize before
+    /// the run ends with `DeadlineExceeded`. `0` disables it.
+    pub deadline_ms: u64,
     pub max_total_tool_calls: usize,
     pub max_mcp_calls: usize,
     pub max_filesystem_read_calls: usize,
@@ -43,6 +60,13 @@ pub struct ToolCallBudget {
     pub max_shell_calls: usize,
     pub max_network_calls: usize,
     pub max_browser_calls: usize,
+    /// Call limits for individual tools by name, checked after the total
+    /// and side-effect limits. `0` is unlimited, like the fields above.
+    pub per_tool: BTreeMap<String, usize>,
+    /// Model calls `CompactionMode::Summarize` may spend on summaries of
+    /// evicted messages. Once spent, compaction keeps the deterministic
+    /// summary. `0` is unlimited.
+    pub max_compaction_summaries: usize,
     pub tool_exec_timeout_ms: u64,
     pub post_write_verify_timeout_ms: u64,
 }
@@ -64,8 +88,35 @@ pub struct AgentOutcome {
     pub hook_invocations: Vec<HookInvocationReport>,
     pub provider_retry_count: u32,
     pub provider_error_count: u32,
+    /// Backoff slept before provider retries, as recorded on each
+    /// `provider_retry` event.
+    pub provider_retry_wait_ms: u64,
     pub token_usage: Option<TokenUsage>,
     pub taint: Option<AgentTaintRecord>,
+    pub digest_refetch: Option<DigestRefetchStatsV1>,
+    /// Tool results compaction moved to artifact files during the run.
+    pub tool_result_artifacts: Vec<crate::store::ToolResultArtifactV1>,
+    /// Model name the server last reported serving; `None` when the provider
+    /// does not report one.
+    pub model_served: Option<String>,
+    /// Run ids of `spawn_subtask` child runs, nested ones included, in
+    /// spawn order.
+    pub subtask_run_ids: Vec<String>,
+    /// First post_run hook failure under `--hooks-strict`. Reported next to
+    /// the exit reason; it never changes it.
+    pub post_run_hook_error: Option<String>,
+    /// Ordered provider calls, tool executions, gate decisions and other spans
+    /// derived from the run's events; never sent to the provider.
+    pub timeline: Vec<super::TimelineEntry>,
+    /// Per-step provider latency, tool durations and token usage, derived
+    /// from `timeline`.
+    pub steps: Vec<super::StepStats>,
+}
+
+impl AgentOutcome {
+    pub fn hook_stats(&self) -> HookRunStats {
+        HookRunStats::from_reports(&self.hook_invocations)
+    }
 }
 
 pub(super) struct AgentOutcomeBuilderInput {
@@ -112,6 +163,15 @@ pub struct ToolDecisionRecord {
     pub escalated: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub escalation_reason: Option<String>,
+    /// Taint sources seen so far in the run; set when taint was enforced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub taint_sources: Vec<String>,
+    /// Set on denials: whether retrying could succeed later in the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denial_class: Option<super::DenialClass>,
+    /// What the gate saw at decision time; see [`super::GateContextSnapshot`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gate_context: Option<super::GateContextSnapshot>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -128,6 +188,13 @@ pub struct McpRuntimeTraceEntry {
     pub progress_ticks: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub elapsed_ms: Option<u64>,
+    /// Set on server-level entries (`server_degraded`, `server_reconnected`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Live input schema hash of the called tool on `drift` entries caused
+    /// by schema drift.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash_hex: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -137,6 +204,8 @@ pub struct PolicyLoadedInfo {
     pub includes_count: usize,
     pub includes_resolved: Vec<String>,
     pub mcp_allowlist: Option<McpAllowSummary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_hash_hex: Option<String>,
 }
 
 #[derive(
@@ -163,6 +232,9 @@ pub enum McpPinEnforcementMode {
 pub struct PlanStepConstraint {
     pub step_id: String,
     pub intended_tools: Vec<String>,
+    /// Argument limits on this step's calls; see [`crate::planner::ArgConstraint`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arg_constraints: Vec<crate::planner::ArgConstraint>,
 }
 
 #[derive(Debug, Clone)]
@@ -170,5 +242,11 @@ pub(crate) struct WorkerStepStatus {
     pub(crate) step_id: String,
     pub(crate) status: String,
     pub(crate) next_step_id: Option<String>,
+    /// v2 fan-out: steps to run next, in order.
+    pub(crate) next_step_ids: Vec<String>,
+    /// v2 retries: the attempt number the worker is asking for.
+    pub(crate) retry_of: Option<u32>,
+    /// Whether the result used `openagent.step_result.v2`.
+    pub(crate) v2: bool,
     pub(crate) user_output: Option<String>,
 }
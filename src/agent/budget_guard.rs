@@ -1,9 +1,11 @@
+use crate::events::{ErrorPayload, ToolBudgetPayload, ToolDecisionPayload};
 use crate::gate::GateEvent;
 use crate::providers::ModelProvider;
 use crate::taint::TaintState;
 use crate::types::{Message, TokenUsage, ToolCall};
 
 use super::agent_types::ToolDecisionRecord;
+use super::denials::DenialClass;
 use super::Agent;
 
 impl<P: ModelProvider> Agent<P> {
@@ -31,19 +33,21 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "deny",
-                "reason": reason.clone(),
-                "source": "runtime_budget",
-                "side_effects": side_effects,
-                "budget": {
-                    "max_total_tool_calls": self.tool_call_budget.max_total_tool_calls,
-                    "max_mcp_calls": self.tool_call_budget.max_mcp_calls
-                }
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "deny".to_string(),
+                reason: Some(reason.clone()),
+                source: Some(Some("runtime_budget".to_string())),
+                side_effects: Some(side_effects),
+                budget: Some(ToolBudgetPayload {
+                    max_total_tool_calls: Some(self.tool_call_budget.max_total_tool_calls),
+                    max_mcp_calls: Some(self.tool_call_budget.max_mcp_calls),
+                    ..ToolBudgetPayload::default()
+                }),
+                denial_class: Some(DenialClass::Permanent),
+                ..ToolDecisionPayload::default()
+            },
         );
         self.finalize_budget_exceeded_with_end(
             step,
@@ -86,14 +90,15 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::Error,
-            serde_json::json!({
-                "error": reason.clone(),
-                "source": "runtime_budget",
-                "budget": {
-                    "max_mcp_calls": self.tool_call_budget.max_mcp_calls
-                }
-            }),
+            ErrorPayload {
+                error: reason.clone(),
+                source: Some("runtime_budget".to_string()),
+                budget: Some(ToolBudgetPayload {
+                    max_mcp_calls: Some(self.tool_call_budget.max_mcp_calls),
+                    ..ToolBudgetPayload::default()
+                }),
+                ..ErrorPayload::default()
+            },
         );
         self.finalize_budget_exceeded_with_end(
             step,
@@ -182,12 +187,17 @@ impl<P: ModelProvider> Agent<P> {
             taint_enforced: false,
             escalated: false,
             escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: Some(DenialClass::Permanent),
+            gate_context: self.gate_context_snapshot.clone(),
         });
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::Error,
-            serde_json::json!({"error": reason.clone()}),
+            ErrorPayload {
+                error: reason.clone(),
+                ..ErrorPayload::default()
+            },
         );
         self.finalize_budget_exceeded_with_end(
             step,
@@ -0,0 +1,604 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::attribution::AttributionConfig;
+use crate::compaction::{
+    CompactionMode, CompactionSettings, DigestRefetchTracker, HeuristicTokenCounter, TokenCounter,
+    ToolResultPersist,
+};
+use crate::events::EventSink;
+use crate::gate::{
+    ApprovalKeyVersion, ApprovalMode, AutoApproveScope, GateContext, NoGate, ProviderKind,
+    ToolGate, DEFAULT_APPROVAL_DIFF_MAX_LINES,
+};
+use crate::hooks::config::HooksMode;
+use crate::hooks::runner::{HookManager, HookRuntimeConfig};
+use crate::mcp::registry::McpRegistry;
+use crate::mcp::roots::McpRootMap;
+use crate::operator_queue::{PendingMessageQueue, QueueLimits, QueueSubmitRequest};
+use crate::providers::ModelProvider;
+use crate::taint::{TaintLevel, TaintMode, TaintToggle};
+use crate::target::{ExecTarget, ExecTargetKind, HostTarget};
+use crate::tools::{ToolArgsStrict, ToolRuntime};
+use crate::trust::policy::Policy;
+use crate::types::ToolDef;
+
+use super::task_contract::{FinalAnswerMode, ValidationRequirement};
+use super::{
+    Agent, McpPinEnforcementMode, PlanStepConstraint, PlanToolEnforcementMode, PolicyLoadedInfo,
+    ToolCallBudget,
+};
+
+const DEFAULT_MAX_STEPS: usize = 20;
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 200_000;
+
+/// Builds an [`Agent`] from defaults that do nothing surprising: no gate,
+/// hooks and compaction off, host execution, read-only tools, empty operator
+/// queue. Setters that touch a setting mirrored in both [`ToolRuntime`] and
+/// [`GateContext`] (workdir, write/shell access, exec target) update both.
+pub struct AgentBuilder<P: ModelProvider> {
+    provider: P,
+    model: String,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_tokens: Option<u32>,
+    seed: Option<u64>,
+    tools: Vec<ToolDef>,
+    max_steps: usize,
+    tool_rt: ToolRuntime,
+    gate: Box<dyn ToolGate>,
+    gate_ctx: GateContext,
+    validation_requirement: Option<ValidationRequirement>,
+    final_answer_mode: Option<FinalAnswerMode>,
+    mcp_registry: Option<Arc<McpRegistry>>,
+    stream: bool,
+    event_sink: Option<Box<dyn EventSink>>,
+    compaction_settings: CompactionSettings,
+    hooks: Option<HookManager>,
+    policy_loaded: Option<PolicyLoadedInfo>,
+    policy_for_taint: Option<Policy>,
+    taint_toggle: TaintToggle,
+    taint_mode: TaintMode,
+    taint_digest_bytes: usize,
+    run_id_override: Option<String>,
+    omit_tools_field_when_empty: bool,
+    plan_tool_enforcement: PlanToolEnforcementMode,
+    mcp_pin_enforcement: McpPinEnforcementMode,
+    plan_step_constraints: Vec<PlanStepConstraint>,
+    tool_call_budget: ToolCallBudget,
+    operator_queue: PendingMessageQueue,
+    operator_queue_limits: QueueLimits,
+    operator_queue_rx: Option<std::sync::mpsc::Receiver<QueueSubmitRequest>>,
+    attribution: Option<AttributionConfig>,
+    max_consecutive_empty_responses: u32,
+    require_exact_model: bool,
+    mcp_root_map: McpRootMap,
+    skip_unevaluated_gate_snapshots: bool,
+    parallel_readonly_tools: bool,
+    approval_diff_max_lines: usize,
+    token_counter: Box<dyn TokenCounter>,
+    final_output_schema: Option<serde_json::Value>,
+}
+
+impl<P: ModelProvider> Agent<P> {
+    pub fn builder(provider: P) -> AgentBuilder<P> {
+        AgentBuilder::new(provider)
+    }
+}
+
+impl<P: ModelProvider> AgentBuilder<P> {
+    /// Starts from the process working directory; see [`Self::workdir`].
+    pub fn new(provider: P) -> Self {
+        let workdir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        Self {
+            provider,
+            model: String::new(),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            seed: None,
+            tools: Vec::new(),
+            max_steps: DEFAULT_MAX_STEPS,
+            tool_rt: ToolRuntime {
+                workdir: workdir.clone(),
+                allow_shell: false,
+                allow_shell_in_workdir_only: false,
+                shell_allowlist: None,
+                allow_write: false,
+                max_tool_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+                max_read_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+                unsafe_bypass_allow_flags: false,
+                restrict_to_workdir: true,
+                tool_timeout_ms: None,
+                stream_tool_output: false,
+                dry_run_writes: false,
+                write_checkpoint: None,
+                tool_args_strict: ToolArgsStrict::On,
+                exec_target_kind: ExecTargetKind::Host,
+                exec_target: Arc::new(HostTarget),
+                read_allowlist: None,
+                run_artifacts: None,
+                secret_redactor: None,
+            },
+            gate: Box::new(NoGate::new()),
+            gate_ctx: GateContext {
+                workdir,
+                allow_shell: false,
+                shell_allowlist: None,
+                allow_write: false,
+                approval_mode: ApprovalMode::Interrupt,
+                auto_approve_scope: AutoApproveScope::Run,
+                unsafe_mode: false,
+                unsafe_bypass_allow_flags: false,
+                run_id: None,
+                enable_write_tools: false,
+                max_tool_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+                max_read_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+                provider: ProviderKind::Mock,
+                model: String::new(),
+                exec_target: ExecTargetKind::Host,
+                approval_key_version: ApprovalKeyVersion::V1,
+                tool_schema_hashes: std::collections::BTreeMap::new(),
+                hooks_config_hash_hex: None,
+                planner_hash_hex: None,
+                taint_enabled: false,
+                taint_mode: TaintMode::Propagate,
+                taint_overall: TaintLevel::Clean,
+                taint_sources: Vec::new(),
+                taint_injection_digest: None,
+            },
+            validation_requirement: None,
+            final_answer_mode: None,
+            mcp_registry: None,
+            stream: false,
+            event_sink: None,
+            compaction_settings: CompactionSettings {
+                max_context_chars: 0,
+                max_context_tokens: None,
+                mode: CompactionMode::Off,
+                keep_last: 20,
+                tool_result_persist: ToolResultPersist::Digest,
+            },
+            hooks: None,
+            policy_loaded: None,
+            policy_for_taint: None,
+            taint_toggle: TaintToggle::Off,
+            taint_mode: TaintMode::Propagate,
+            taint_digest_bytes: 4096,
+            run_id_override: None,
+            omit_tools_field_when_empty: false,
+            plan_tool_enforcement: PlanToolEnforcementMode::Off,
+            mcp_pin_enforcement: McpPinEnforcementMode::Hard,
+            plan_step_constraints: Vec::new(),
+            tool_call_budget: ToolCallBudget::default(),
+            operator_queue: PendingMessageQueue::default(),
+            operator_queue_limits: QueueLimits::default(),
+            operator_queue_rx: None,
+            attribution: None,
+            max_consecutive_empty_responses: 2,
+            require_exact_model: false,
+            mcp_root_map: McpRootMap::default(),
+            skip_unevaluated_gate_snapshots: false,
+            parallel_readonly_tools: false,
+            approval_diff_max_lines: DEFAULT_APPROVAL_DIFF_MAX_LINES,
+            token_counter: Box::new(HeuristicTokenCounter::default()),
+            final_output_schema: None,
+        }
+    }
+
+    pub fn provider(mut self, provider: P) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Also the model recorded in the gate context.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self.gate_ctx.model = self.model.clone();
+        self
+    }
+
+    /// Provider recorded in the gate context (approval keys, audit).
+    pub fn provider_kind(mut self, kind: ProviderKind) -> Self {
+        self.gate_ctx.provider = kind;
+        self
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<ToolDef>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn workdir(mut self, workdir: impl Into<PathBuf>) -> Self {
+        let workdir = workdir.into();
+        self.tool_rt.workdir = workdir.clone();
+        self.gate_ctx.workdir = workdir;
+        self
+    }
+
+    pub fn allow_write(mut self, allow: bool) -> Self {
+        self.tool_rt.allow_write = allow;
+        self.gate_ctx.allow_write = allow;
+        self
+    }
+
+    /// Whether write tools are exposed, as recorded for the gate.
+    pub fn enable_write_tools(mut self, enable: bool) -> Self {
+        self.gate_ctx.enable_write_tools = enable;
+        self
+    }
+
+    pub fn allow_shell(mut self, allow: bool) -> Self {
+        self.tool_rt.allow_shell = allow;
+        self.gate_ctx.allow_shell = allow;
+        self
+    }
+
+    pub fn dry_run_writes(mut self, dry_run: bool) -> Self {
+        self.tool_rt.dry_run_writes = dry_run;
+        self
+    }
+
+    pub fn secret_redactor(mut self, redactor: Arc<crate::tools::SecretRedactor>) -> Self {
+        self.tool_rt.secret_redactor = Some(redactor);
+        self
+    }
+
+    pub fn exec_target(mut self, target: Arc<dyn ExecTarget>) -> Self {
+        self.tool_rt.exec_target = target;
+        self
+    }
+
+    pub fn exec_target_kind(mut self, kind: ExecTargetKind) -> Self {
+        self.tool_rt.exec_target_kind = kind;
+        self.gate_ctx.exec_target = kind;
+        self
+    }
+
+    /// Replaces the whole tool runtime, including its workdir.
+    pub fn tool_runtime(mut self, tool_rt: ToolRuntime) -> Self {
+        self.tool_rt = tool_rt;
+        self
+    }
+
+    pub fn gate(mut self, gate: Box<dyn ToolGate>) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    /// Replaces the whole gate context, including its workdir and model.
+    pub fn gate_ctx(mut self, gate_ctx: GateContext) -> Self {
+        self.gate_ctx = gate_ctx;
+        self
+    }
+
+    pub fn planner_hash_hex(mut self, hash: Option<String>) -> Self {
+        self.gate_ctx.planner_hash_hex = hash;
+        self
+    }
+
+    pub fn validation_requirement(mut self, requirement: ValidationRequirement) -> Self {
+        self.validation_requirement = Some(requirement);
+        self
+    }
+
+    pub fn final_answer_mode(mut self, mode: FinalAnswerMode) -> Self {
+        self.final_answer_mode = Some(mode);
+        self
+    }
+
+    /// Requires the final answer to be JSON matching `schema`.
+    pub fn final_output_schema(mut self, schema: serde_json::Value) -> Self {
+        self.final_output_schema = Some(schema);
+        self
+    }
+
+    pub fn mcp_registry(mut self, registry: Arc<McpRegistry>) -> Self {
+        self.mcp_registry = Some(registry);
+        self
+    }
+
+    pub fn mcp_root_map(mut self, roots: McpRootMap) -> Self {
+        self.mcp_root_map = roots;
+        self
+    }
+
+    pub fn mcp_pin_enforcement(mut self, mode: McpPinEnforcementMode) -> Self {
+        self.mcp_pin_enforcement = mode;
+        self
+    }
+
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    pub fn event_sink(mut self, sink: Box<dyn EventSink>) -> Self {
+        self.event_sink = Some(sink);
+        self
+    }
+
+    pub fn compaction(mut self, settings: CompactionSettings) -> Self {
+        self.compaction_settings = settings;
+        self
+    }
+
+    pub fn token_counter(mut self, counter: Box<dyn TokenCounter>) -> Self {
+        self.token_counter = counter;
+        self
+    }
+
+    /// Without this, hooks are off.
+    pub fn hooks(mut self, hooks: HookManager) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    pub fn policy_loaded(mut self, info: PolicyLoadedInfo) -> Self {
+        self.policy_loaded = Some(info);
+        self
+    }
+
+    /// Also mirrored into the gate context. `PropagateAndEnforce` needs a
+    /// [`Self::taint_policy`].
+    pub fn taint(mut self, toggle: TaintToggle, mode: TaintMode) -> Self {
+        self.taint_toggle = toggle;
+        self.taint_mode = mode;
+        self.gate_ctx.taint_enabled = matches!(toggle, TaintToggle::On);
+        self.gate_ctx.taint_mode = mode;
+        self
+    }
+
+    pub fn taint_policy(mut self, policy: Policy) -> Self {
+        self.policy_for_taint = Some(policy);
+        self
+    }
+
+    pub fn taint_digest_bytes(mut self, bytes: usize) -> Self {
+        self.taint_digest_bytes = bytes;
+        self
+    }
+
+    pub fn run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id_override = Some(run_id.into());
+        self
+    }
+
+    pub fn omit_tools_field_when_empty(mut self, omit: bool) -> Self {
+        self.omit_tools_field_when_empty = omit;
+        self
+    }
+
+    /// `Hard` needs non-empty [`Self::plan_step_constraints`].
+    pub fn plan_tool_enforcement(mut self, mode: PlanToolEnforcementMode) -> Self {
+        self.plan_tool_enforcement = mode;
+        self
+    }
+
+    pub fn plan_step_constraints(mut self, constraints: Vec<PlanStepConstraint>) -> Self {
+        self.plan_step_constraints = constraints;
+        self
+    }
+
+    pub fn tool_call_budget(mut self, budget: ToolCallBudget) -> Self {
+        self.tool_call_budget = budget;
+        self
+    }
+
+    pub fn operator_queue(mut self, queue: PendingMessageQueue) -> Self {
+        self.operator_queue = queue;
+        self
+    }
+
+    pub fn operator_queue_limits(mut self, limits: QueueLimits) -> Self {
+        self.operator_queue_limits = limits;
+        self
+    }
+
+    pub fn operator_queue_rx(mut self, rx: std::sync::mpsc::Receiver<QueueSubmitRequest>) -> Self {
+        self.operator_queue_rx = Some(rx);
+        self
+    }
+
+    pub fn attribution(mut self, attribution: AttributionConfig) -> Self {
+        self.attribution = Some(attribution);
+        self
+    }
+
+    pub fn max_consecutive_empty_responses(mut self, max: u32) -> Self {
+        self.max_consecutive_empty_responses = max;
+        self
+    }
+
+    pub fn require_exact_model(mut self, require: bool) -> Self {
+        self.require_exact_model = require;
+        self
+    }
+
+    pub fn skip_unevaluated_gate_snapshots(mut self, skip: bool) -> Self {
+        self.skip_unevaluated_gate_snapshots = skip;
+        self
+    }
+
+    pub fn parallel_readonly_tools(mut self, parallel: bool) -> Self {
+        self.parallel_readonly_tools = parallel;
+        self
+    }
+
+    pub fn approval_diff_max_lines(mut self, max_lines: usize) -> Self {
+        self.approval_diff_max_lines = max_lines;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<Agent<P>> {
+        if self.plan_tool_enforcement == PlanToolEnforcementMode::Hard
+            && self.plan_step_constraints.is_empty()
+        {
+            anyhow::bail!("hard plan tool enforcement requires plan step constraints");
+        }
+        if matches!(self.taint_toggle, TaintToggle::On)
+            && self.taint_mode == TaintMode::PropagateAndEnforce
+            && self.policy_for_taint.is_none()
+        {
+            anyhow::bail!("taint enforcement requires a policy");
+        }
+        let hooks = match self.hooks {
+            Some(hooks) => hooks,
+            None => HookManager::build(HookRuntimeConfig {
+                mode: HooksMode::Off,
+                config_path: PathBuf::new(),
+                strict: false,
+                timeout_ms: 0,
+                max_stdout_bytes: 0,
+                max_invocations_per_run: 0,
+                max_cumulative_ms: 0,
+                budget_strict: false,
+            })?,
+        };
+        Ok(Agent {
+            provider: self.provider,
+            model: self.model,
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+            seed: self.seed,
+            tools: self.tools,
+            max_steps: self.max_steps,
+            tool_rt: self.tool_rt,
+            gate: self.gate,
+            gate_ctx: self.gate_ctx,
+            validation_requirement: self.validation_requirement,
+            final_answer_mode: self.final_answer_mode,
+            mcp_registry: self.mcp_registry,
+            stream: self.stream,
+            event_sink: self.event_sink,
+            compaction_settings: self.compaction_settings,
+            hooks,
+            policy_loaded: self.policy_loaded,
+            policy_for_taint: self.policy_for_taint,
+            taint_toggle: self.taint_toggle,
+            taint_mode: self.taint_mode,
+            taint_digest_bytes: self.taint_digest_bytes,
+            run_id_override: self.run_id_override,
+            omit_tools_field_when_empty: self.omit_tools_field_when_empty,
+            plan_tool_enforcement: self.plan_tool_enforcement,
+            mcp_pin_enforcement: self.mcp_pin_enforcement,
+            plan_step_constraints: self.plan_step_constraints,
+            current_plan: Vec::new(),
+            tool_call_budget: self.tool_call_budget,
+            mcp_runtime_trace: Vec::new(),
+            operator_queue: self.operator_queue,
+            operator_queue_limits: self.operator_queue_limits,
+            operator_queue_rx: self.operator_queue_rx,
+            attribution: self.attribution,
+            max_consecutive_empty_responses: self.max_consecutive_empty_responses,
+            digest_refetch_tracker: DigestRefetchTracker::default(),
+            require_exact_model: self.require_exact_model,
+            served_model: None,
+            provider_retry_wait_ms: 0,
+            mcp_root_map: self.mcp_root_map,
+            timeline_recorder: Default::default(),
+            gate_context_snapshot: None,
+            skip_unevaluated_gate_snapshots: self.skip_unevaluated_gate_snapshots,
+            run_deadline: None,
+            parallel_readonly_tools: self.parallel_readonly_tools,
+            approval_diff_max_lines: self.approval_diff_max_lines,
+            parallel_tool_batch: None,
+            token_counter: self.token_counter,
+            final_output_schema: self.final_output_schema,
+            subtask_depth: 0,
+            subtask_run_ids: Vec::new(),
+            run_started: None,
+            operator_cancel: None,
+            plan_step_queue: Vec::new(),
+            completed_plan_step_ids: std::collections::BTreeSet::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{Agent, PlanStepConstraint, PlanToolEnforcementMode};
+    use crate::providers::mock::MockProvider;
+    use crate::taint::{TaintMode, TaintToggle};
+
+    #[test]
+    fn mirrored_settings_reach_tool_runtime_and_gate_context() {
+        let tmp = tempfile::tempdir().expect("tmp");
+        let agent = Agent::builder(MockProvider::new())
+            .model("m")
+            .workdir(tmp.path())
+            .allow_write(true)
+            .allow_shell(true)
+            .build()
+            .expect("agent");
+        assert_eq!(agent.tool_rt.workdir, tmp.path());
+        assert_eq!(agent.gate_ctx.workdir, tmp.path());
+        assert_eq!(agent.gate_ctx.model, "m");
+        assert!(agent.tool_rt.allow_write && agent.gate_ctx.allow_write);
+        assert!(agent.tool_rt.allow_shell && agent.gate_ctx.allow_shell);
+        assert!(!agent.gate_ctx.enable_write_tools);
+        assert_eq!(agent.plan_tool_enforcement, PlanToolEnforcementMode::Off);
+    }
+
+    #[test]
+    fn build_rejects_hard_plan_enforcement_without_constraints() {
+        let err = Agent::builder(MockProvider::new())
+            .plan_tool_enforcement(PlanToolEnforcementMode::Hard)
+            .build()
+            .err()
+            .expect("rejected");
+        assert!(err.to_string().contains("plan step constraints"), "{err}");
+
+        assert!(Agent::builder(MockProvider::new())
+            .plan_tool_enforcement(PlanToolEnforcementMode::Hard)
+            .plan_step_constraints(vec![PlanStepConstraint {
+                step_id: "S1".to_string(),
+                intended_tools: vec!["read_file".to_string()],
+                arg_constraints: Vec::new(),
+            }])
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn build_rejects_taint_enforcement_without_policy() {
+        let err = Agent::builder(MockProvider::new())
+            .taint(TaintToggle::On, TaintMode::PropagateAndEnforce)
+            .build()
+            .err()
+            .expect("rejected");
+        assert!(err.to_string().contains("requires a policy"), "{err}");
+
+        let agent = Agent::builder(MockProvider::new())
+            .taint(TaintToggle::On, TaintMode::Propagate)
+            .build()
+            .expect("propagate needs no policy");
+        assert!(agent.gate_ctx.taint_enabled);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::agent_budget::ToolCallBudgetUsage;
+use crate::compaction::{
+    context_size_chars, context_size_tokens, generated_summary_message, summary_request_messages,
+    CompactionMode, CompactionReport,
+};
+use crate::providers::ModelProvider;
+use crate::store::sha256_hex;
+use crate::types::{GenerateRequest, Message, TokenUsage};
+
+use super::run_events::apply_usage_totals;
+use super::Agent;
+
+const SUMMARY_MAX_TOKENS: u32 = 1024;
+const SUMMARY_REQUEST_TIMEOUT_MS: u64 = 120_000;
+
+impl<P: ModelProvider> Agent<P> {
+    /// The transcript as it was before compaction, kept only when
+    /// `Summarize` mode is about to evict messages.
+    pub(super) fn transcript_before_summarizing_compaction(
+        &self,
+        messages: &[Message],
+    ) -> Option<Vec<Message>> {
+        (matches!(self.compaction_settings.mode, CompactionMode::Summarize)
+            && self.compaction_settings.enabled()
+            && self
+                .compaction_settings
+                .exceeds_budget(messages, self.token_counter.as_ref()))
+        .then(|| messages.to_vec())
+    }
+
+    /// Asks the model to summarize the messages compaction evicted and puts
+    /// the fenced summary at the head of `messages`. The call is charged to
+    /// `max_compaction_summaries`; when the budget is spent or the call
+    /// fails, the deterministic summary from compaction stays in place.
+    pub(super) async fn summarize_evicted_messages(
+        &mut self,
+        before: &[Message],
+        messages: &mut [Message],
+        mut report: CompactionReport,
+        tool_budget_usage: &mut ToolCallBudgetUsage,
+        saw_token_usage: &mut bool,
+        total_token_usage: &mut TokenUsage,
+    ) -> CompactionReport {
+        let limit = self.tool_call_budget.max_compaction_summaries;
+        if limit > 0 && tool_budget_usage.compaction_summaries >= limit {
+            return report;
+        }
+        let Some(evicted) = before.get(..report.compacted_messages) else {
+            return report;
+        };
+        if evicted.is_empty() || messages.is_empty() {
+            return report;
+        }
+        tool_budget_usage.compaction_summaries =
+            tool_budget_usage.compaction_summaries.saturating_add(1);
+        let req = GenerateRequest {
+            model: self.model.clone(),
+            messages: summary_request_messages(evicted),
+            tools: None,
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: Some(
+                self.max_tokens
+                    .map_or(SUMMARY_MAX_TOKENS, |t| t.min(SUMMARY_MAX_TOKENS)),
+            ),
+            seed: self.seed,
+        };
+        let timeout = std::time::Duration::from_millis(SUMMARY_REQUEST_TIMEOUT_MS);
+        let resp = match tokio::time::timeout(timeout, self.provider.generate(req)).await {
+            Ok(Ok(resp)) => resp,
+            Ok(Err(_)) | Err(_) => return report,
+        };
+        if let Some(usage) = &resp.usage {
+            apply_usage_totals(usage, saw_token_usage, total_token_usage);
+        }
+        let summary = resp.assistant.content.unwrap_or_default();
+        if summary.trim().is_empty() {
+            return report;
+        }
+        let head = generated_summary_message(&summary);
+        let text = head.content.clone().unwrap_or_default();
+        messages[0] = head;
+        report.summary_digest_sha256 = sha256_hex(text.as_bytes());
+        report.summary_text = text;
+        report.summary_generated = true;
+        report.after_chars = context_size_chars(messages);
+        report.after_tokens = context_size_tokens(messages, self.token_counter.as_ref());
+        report
+    }
+}
@@ -0,0 +1,122 @@
+use crate::events::DeadlineReachedPayload;
+use crate::hooks::protocol::HookInvocationReport;
+use crate::providers::ModelProvider;
+use crate::taint::TaintState;
+use crate::types::{Message, Role, TokenUsage, ToolCall};
+
+use super::agent_types::AgentOutcomeBuilderInput;
+use super::run_events::apply_usage_totals;
+use super::{
+    context_size_chars, sanitize_user_visible_output, Agent, AgentExitReason, AgentOutcome,
+    ToolDecisionRecord,
+};
+
+const DEADLINE_SUMMARY_INSTRUCTION: &str = "The run deadline has been reached. No more tools can be called. Reply with a short summary of what was done, what is left, and anything the user should check.";
+
+impl<P: ModelProvider> Agent<P> {
+    pub(super) fn deadline_passed(&self) -> bool {
+        self.run_deadline
+            .is_some_and(|deadline| std::time::Instant::now() >= deadline)
+    }
+
+    /// Ends a run that hit `--deadline-ms`: every call in `skipped` gets a
+    /// failed result, then the model gets one turn without tools to write
+    /// the final output.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn finalize_deadline_reached(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        started_at: &str,
+        skipped: &[ToolCall],
+        mut messages: Vec<Message>,
+        tool_calls: Vec<ToolCall>,
+        tool_decisions: Vec<ToolDecisionRecord>,
+        compaction_report: Option<crate::compaction::CompactionReport>,
+        hook_invocations: Vec<HookInvocationReport>,
+        provider_retry_count: u32,
+        provider_error_count: u32,
+        mut saw_token_usage: bool,
+        mut total_token_usage: TokenUsage,
+        taint_state: &TaintState,
+    ) -> AgentOutcome {
+        let deadline_ms = self.tool_call_budget.deadline_ms;
+        let overrun_ms = self
+            .run_deadline
+            .map(|d| {
+                std::time::Instant::now()
+                    .saturating_duration_since(d)
+                    .as_millis() as u64
+            })
+            .unwrap_or(0);
+        self.emit_event(
+            run_id,
+            step,
+            DeadlineReachedPayload {
+                elapsed_ms: deadline_ms.saturating_add(overrun_ms),
+                deadline_ms,
+                skipped_tool_calls: skipped.len(),
+            },
+        );
+        for tc in skipped {
+            messages.push(self.runtime_tool_failure_message(
+                tc,
+                format!("tool call skipped: run deadline of {deadline_ms}ms reached"),
+            ));
+        }
+        messages.push(Message {
+            role: Role::Developer,
+            content: Some(DEADLINE_SUMMARY_INSTRUCTION.to_string()),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        });
+        let reason = format!("run deadline of {deadline_ms}ms reached");
+        let req = self.build_generate_request(&messages, Vec::new());
+        let summary = match self.execute_model_request(run_id, step, req).await {
+            Ok(resp) => {
+                if let Some(usage) = &resp.usage {
+                    apply_usage_totals(usage, &mut saw_token_usage, &mut total_token_usage);
+                }
+                let mut assistant = resp.assistant;
+                assistant.tool_calls = None;
+                let text = assistant
+                    .content
+                    .as_deref()
+                    .map(sanitize_user_visible_output)
+                    .unwrap_or_default();
+                assistant.content = Some(text.clone());
+                messages.push(assistant);
+                text
+            }
+            Err(_) => String::new(),
+        };
+        let final_output = if summary.trim().is_empty() {
+            reason.clone()
+        } else {
+            summary
+        };
+        let final_prompt_size_chars = context_size_chars(&messages);
+        self.finalize_run_outcome_with_end(
+            step,
+            AgentOutcomeBuilderInput {
+                run_id: run_id.to_string(),
+                started_at: started_at.to_string(),
+                exit_reason: AgentExitReason::DeadlineExceeded,
+                final_output,
+                error: Some(reason),
+                messages,
+                tool_calls,
+                tool_decisions,
+                final_prompt_size_chars,
+                compaction_report,
+                hook_invocations,
+                provider_retry_count,
+                provider_error_count,
+            },
+            saw_token_usage,
+            &total_token_usage,
+            taint_state,
+        )
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether retrying a denied call could succeed later in the same run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenialClass {
+    /// Policy deny, missing capability flag, plan-step constraint, exhausted
+    /// budget or an operator's explicit denial: nothing changes mid-run.
+    Permanent,
+    /// Approval-store failures and taint escalations an operator may still
+    /// approve.
+    Transient,
+}
+
+impl DenialClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Permanent => "permanent",
+            Self::Transient => "transient",
+        }
+    }
+
+    /// Model-facing guidance appended to the denial reason.
+    pub fn guidance(self) -> &'static str {
+        match self {
+            Self::Permanent => {
+                "This denial is permanent for this run: the call will not succeed however it is retried or rephrased. Do not retry it; use a different approach or explain what is blocked."
+            }
+            Self::Transient => {
+                "This denial may be temporary: you may ask the operator to approve it, or continue with other work and try again later."
+            }
+        }
+    }
+}
+
+/// Classifies a denial from its reason and escalation; the deciding source
+/// (gate, policy file, plan, budget) only matters through those.
+pub(crate) fn classify_denial(reason: &str, escalation_reason: Option<&str>) -> DenialClass {
+    if escalation_reason == Some("taint_escalation") {
+        return DenialClass::Transient;
+    }
+    const TRANSIENT_REASON_PREFIXES: [&str; 3] = [
+        "failed to read approvals store",
+        "failed to create approval request",
+        "failed to auto-approve",
+    ];
+    if TRANSIENT_REASON_PREFIXES
+        .iter()
+        .any(|prefix| reason.starts_with(prefix))
+    {
+        return DenialClass::Transient;
+    }
+    DenialClass::Permanent
+}
+
+pub(crate) fn denial_feedback(reason: &str, class: DenialClass) -> String {
+    format!(
+        "{reason}\n[denial: {}] {}",
+        class.as_str(),
+        class.guidance()
+    )
+}
+
+/// Permanently denied attempts at a tool before the model gets an explicit
+/// warning, and before the run fails with `MODEL_IGNORED_DENIAL`.
+pub(crate) const DENIAL_WARN_AFTER: u32 = 2;
+pub(crate) const DENIAL_FAIL_AFTER: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DenialRepeatAction {
+    Feedback,
+    Warn,
+    FailFast,
+}
+
+/// Counts a denied attempt under `denied::<tool>` in the run's repeat map.
+/// Keyed by tool name so cosmetic argument changes still count as repeats;
+/// transient denials are never escalated.
+pub(crate) fn record_denied_attempt(
+    repeat_counts: &mut std::collections::BTreeMap<String, u32>,
+    tool: &str,
+    class: DenialClass,
+) -> DenialRepeatAction {
+    if class == DenialClass::Transient {
+        return DenialRepeatAction::Feedback;
+    }
+    let n = repeat_counts.entry(format!("denied::{tool}")).or_insert(0);
+    *n = n.saturating_add(1);
+    if *n >= DENIAL_FAIL_AFTER {
+        DenialRepeatAction::FailFast
+    } else if *n >= DENIAL_WARN_AFTER {
+        DenialRepeatAction::Warn
+    } else {
+        DenialRepeatAction::Feedback
+    }
+}
+
+pub(crate) fn ignored_denial_warning(tool: &str) -> String {
+    format!(
+        "You have called '{tool}' again after it was permanently denied. It will be denied every time in this run. One more attempt ends the run with MODEL_IGNORED_DENIAL; choose a different tool or finish with what you have."
+    )
+}
+
+pub(crate) fn ignored_denial_error(tool: &str, attempts: u32) -> String {
+    format!("MODEL_IGNORED_DENIAL: model retried permanently denied tool '{tool}' {attempts} times")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{classify_denial, record_denied_attempt, DenialClass, DenialRepeatAction};
+
+    #[test]
+    fn denial_sources_classify_by_retry_prospects() {
+        // (reason as produced by each source, escalation_reason)
+        let permanent = [
+            ("shell requires --allow-shell", None),
+            ("writes require --allow-write", None),
+            ("mcp tool 'mcp.fs.delete' is not in the allowlist", None),
+            ("policy denied tool 'shell'", None),
+            (
+                "tool 'shell' is not allowed for plan step S1 (allowed: read_file)",
+                None,
+            ),
+            (
+                "runtime budget exceeded: shell tool calls 3 > limit 2",
+                None,
+            ),
+            ("approval denied: abc123", None),
+        ];
+        for (reason, escalation) in permanent {
+            assert_eq!(
+                classify_denial(reason, escalation),
+                DenialClass::Permanent,
+                "{reason}"
+            );
+        }
+        let transient = [
+            ("failed to read approvals store: locked", None),
+            ("failed to create approval request: io", None),
+            ("failed to auto-approve: io", None),
+            ("approval required", Some("taint_escalation")),
+        ];
+        for (reason, escalation) in transient {
+            assert_eq!(
+                classify_denial(reason, escalation),
+                DenialClass::Transient,
+                "{reason}"
+            );
+        }
+    }
+
+    #[test]
+    fn permanent_denials_escalate_and_transient_do_not() {
+        let mut counts = BTreeMap::new();
+        let seq = (0..3)
+            .map(|_| record_denied_attempt(&mut counts, "shell", DenialClass::Permanent))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            seq,
+            vec![
+                DenialRepeatAction::Feedback,
+                DenialRepeatAction::Warn,
+                DenialRepeatAction::FailFast
+            ]
+        );
+        for _ in 0..5 {
+            assert_eq!(
+                record_denied_attempt(&mut counts, "write_file", DenialClass::Transient),
+                DenialRepeatAction::Feedback
+            );
+        }
+        assert!(!counts.contains_key("denied::write_file"));
+    }
+}
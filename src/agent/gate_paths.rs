@@ -1,3 +1,7 @@
+use crate::events::{
+    ErrorPayload, StepBlockedPayload, ToolBudgetPayload, ToolDecisionGateContext,
+    ToolDecisionPayload, ToolExecEndPayload,
+};
 use crate::gate::GateEvent;
 use crate::providers::ModelProvider;
 use crate::taint::TaintState;
@@ -5,6 +9,11 @@ use crate::tools::tool_side_effects;
 use crate::types::{Message, Role, TokenUsage, ToolCall};
 
 use super::agent_types::ToolDecisionRecord;
+use super::denials::{
+    classify_denial, denial_feedback, ignored_denial_error, ignored_denial_warning,
+    record_denied_attempt, DenialClass, DenialRepeatAction,
+};
+use super::run_setup::PlanArgViolation;
 use super::tool_helpers::{
     normalized_tool_path_from_args, AllowedToolResultDecision, ToolRetryLoopOutcome,
 };
@@ -100,13 +109,13 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::Error,
-            serde_json::json!({
-                "error": reason,
-                "source": "implementation_integrity_guard",
-                "failure_class": "E_RUNTIME_WRITEFILE_EXISTING_BLOCKED",
-                "path": blocked_path
-            }),
+            ErrorPayload {
+                error: reason.clone(),
+                source: Some("implementation_integrity_guard".to_string()),
+                failure_class: Some("E_RUNTIME_WRITEFILE_EXISTING_BLOCKED".to_string()),
+                path: Some(blocked_path),
+                ..ErrorPayload::default()
+            },
         );
         self.finalize_planner_error_with_end(
             step,
@@ -195,6 +204,7 @@ impl<P: ModelProvider> Agent<P> {
             result_input_len: input_len,
             result_output_len: output_len,
         });
+        let gate_context = self.gate_context_for_allow(source.as_deref());
         observed_tool_decisions.push(ToolDecisionRecord {
             step,
             tool_call_id: tc.id.clone(),
@@ -207,6 +217,13 @@ impl<P: ModelProvider> Agent<P> {
             taint_enforced,
             escalated,
             escalation_reason,
+            taint_sources: if taint_enforced {
+                taint_state.sources()
+            } else {
+                Vec::new()
+            },
+            denial_class: None,
+            gate_context,
         });
         if final_ok {
             failed_repeat_counts.remove(repeat_key);
@@ -238,16 +255,18 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            crate::events::EventKind::ToolExecEnd,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "ok": final_ok,
-                "truncated": final_truncated,
-                "retry_count": tool_retry_count,
-                "failure_class": final_failure_class.map(|c| c.as_str()),
-                "error_code": final_error_code.map(|c| c.as_str())
-            }),
+            ToolExecEndPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                ok: final_ok,
+                truncated: final_truncated,
+                retry_count: Some(tool_retry_count),
+                failure_class: Some(final_failure_class.map(|c| c.as_str()).map(str::to_string)),
+                error_code: Some(final_error_code.map(|c| c.as_str()).map(str::to_string)),
+                batch_id: self.tool_batch_id(tc),
+                output_bytes: Some(content.len() as u64),
+                ..ToolExecEndPayload::default()
+            },
         );
         messages.push(tool_msg);
         // When a tool fails, inject a recovery hint to steer the model toward
@@ -290,6 +309,58 @@ impl<P: ModelProvider> Agent<P> {
         }
     }
 
+    /// Soft plan enforcement lets a call that breaks an argument constraint
+    /// run; the violation is still recorded against the step.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn warn_plan_arg_violation(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+        plan_step_id: String,
+        active_plan_step_idx: usize,
+        violation: PlanArgViolation,
+        planner_hash_hex: Option<String>,
+        taint_state: &TaintState,
+        observed_tool_decisions: &mut Vec<ToolDecisionRecord>,
+    ) {
+        let reason = format!("{} for plan step {}", violation.reason, plan_step_id);
+        observed_tool_decisions.push(ToolDecisionRecord {
+            step,
+            tool_call_id: tc.id.clone(),
+            tool: tc.name.clone(),
+            decision: "allow".to_string(),
+            reason: Some(reason.clone()),
+            source: Some("plan_step_constraint".to_string()),
+            approval_id: None,
+            taint_overall: Some(taint_state.overall_str().to_string()),
+            taint_enforced: false,
+            escalated: false,
+            escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: None,
+            gate_context: self.gate_context_snapshot.clone(),
+        });
+        self.emit_event(
+            run_id,
+            step,
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "allow".to_string(),
+                reason: Some(reason),
+                source: Some(Some("plan_step_constraint".to_string())),
+                planner_hash_hex: Some(planner_hash_hex),
+                plan_step_id: Some(plan_step_id),
+                plan_step_index: Some(active_plan_step_idx),
+                plan_constraint_id: Some(violation.constraint_id),
+                enforcement_mode: Some(format!("{:?}", self.plan_tool_enforcement).to_lowercase()),
+                side_effects: Some(tool_side_effects(&tc.name)),
+                ..ToolDecisionPayload::default()
+            },
+        );
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(super) fn handle_plan_constraint_deny(
         &mut self,
@@ -299,6 +370,7 @@ impl<P: ModelProvider> Agent<P> {
         plan_step_id: String,
         active_plan_step_idx: usize,
         plan_allowed_tools: Vec<String>,
+        arg_violation: Option<PlanArgViolation>,
         approval_mode_meta: Option<String>,
         auto_scope_meta: Option<String>,
         approval_key_version_meta: Option<String>,
@@ -318,44 +390,58 @@ impl<P: ModelProvider> Agent<P> {
         saw_token_usage: bool,
         total_token_usage: &TokenUsage,
         taint_state: &TaintState,
+        failed_repeat_counts: &mut std::collections::BTreeMap<String, u32>,
     ) -> PlanConstraintDecision {
-        let reason = format!(
-            "tool '{}' is not allowed for plan step {} (allowed: {})",
-            tc.name,
-            plan_step_id,
-            if plan_allowed_tools.is_empty() {
-                "none".to_string()
-            } else {
-                plan_allowed_tools.join(", ")
-            }
-        );
+        let (reason, block_reason) = match &arg_violation {
+            Some(violation) => (
+                format!("{} for plan step {}", violation.reason, plan_step_id),
+                "tool_args_not_allowed_by_plan",
+            ),
+            None => (
+                format!(
+                    "tool '{}' is not allowed for plan step {} (allowed: {})",
+                    tc.name,
+                    plan_step_id,
+                    if plan_allowed_tools.is_empty() {
+                        "none".to_string()
+                    } else {
+                        plan_allowed_tools.join(", ")
+                    }
+                ),
+                "tool_not_allowed_by_plan",
+            ),
+        };
+        let plan_constraint_id = arg_violation.map(|v| v.constraint_id);
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::StepBlocked,
-            serde_json::json!({
-                "step_id": plan_step_id.clone(),
-                "tool": tc.name,
-                "reason": "tool_not_allowed_by_plan",
-                "allowed_tools": plan_allowed_tools.clone()
-            }),
+            StepBlockedPayload {
+                step_id: Some(plan_step_id.clone()),
+                tool: Some(tc.name.clone()),
+                reason: Some(block_reason.to_string()),
+                allowed_tools: Some(plan_allowed_tools.clone()),
+                plan_constraint_id: plan_constraint_id.clone(),
+                ..StepBlockedPayload::default()
+            },
         );
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "deny",
-                "reason": reason,
-                "source": "plan_step_constraint",
-                "planner_hash_hex": planner_hash_hex.clone(),
-                "plan_step_id": plan_step_id,
-                "plan_step_index": active_plan_step_idx,
-                "plan_allowed_tools": plan_allowed_tools,
-                "enforcement_mode": format!("{:?}", self.plan_tool_enforcement).to_lowercase()
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "deny".to_string(),
+                reason: Some(reason.clone()),
+                source: Some(Some("plan_step_constraint".to_string())),
+                planner_hash_hex: Some(planner_hash_hex.clone()),
+                plan_step_id: Some(plan_step_id),
+                plan_step_index: Some(active_plan_step_idx),
+                plan_allowed_tools: Some(plan_allowed_tools.clone()),
+                plan_constraint_id,
+                enforcement_mode: Some(format!("{:?}", self.plan_tool_enforcement).to_lowercase()),
+                denial_class: Some(DenialClass::Permanent),
+                ..ToolDecisionPayload::default()
+            },
         );
         self.gate.record(GateEvent {
             run_id: run_id.clone(),
@@ -398,29 +484,73 @@ impl<P: ModelProvider> Agent<P> {
             taint_enforced: false,
             escalated: false,
             escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: Some(DenialClass::Permanent),
+            gate_context: self.gate_context_snapshot.clone(),
         });
 
         match self.plan_tool_enforcement {
             super::PlanToolEnforcementMode::Off => PlanConstraintDecision::Continue,
             super::PlanToolEnforcementMode::Soft => {
+                let repeat_action =
+                    record_denied_attempt(failed_repeat_counts, &tc.name, DenialClass::Permanent);
+                if repeat_action == DenialRepeatAction::FailFast {
+                    let error = ignored_denial_error(
+                        &tc.name,
+                        failed_repeat_counts
+                            .get(&format!("denied::{}", tc.name))
+                            .copied()
+                            .unwrap_or_default(),
+                    );
+                    self.emit_event(
+                        &run_id,
+                        step,
+                        ErrorPayload {
+                            error: error.clone(),
+                            source: Some("denial_repeat_guard".to_string()),
+                            tool_call_id: Some(tc.id.clone()),
+                            name: Some(tc.name.clone()),
+                            ..ErrorPayload::default()
+                        },
+                    );
+                    return PlanConstraintDecision::Finalize(Box::new(
+                        self.finalize_planner_error_with_output_with_end(
+                            step,
+                            run_id,
+                            started_at,
+                            error,
+                            messages.clone(),
+                            observed_tool_calls,
+                            observed_tool_decisions.clone(),
+                            request_context_chars,
+                            last_compaction_report,
+                            hook_invocations,
+                            provider_retry_count,
+                            provider_error_count,
+                            saw_token_usage,
+                            total_token_usage,
+                            taint_state,
+                        ),
+                    ));
+                }
                 self.emit_event(
                     &run_id,
                     step,
-                    crate::events::EventKind::ToolExecEnd,
-                    serde_json::json!({
-                        "tool_call_id": tc.id,
-                        "name": tc.name,
-                        "ok": false,
-                        "truncated": false,
-                        "source": "plan_step_constraint"
-                    }),
+                    ToolExecEndPayload {
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        ok: false,
+                        truncated: false,
+                        source: Some("plan_step_constraint".to_string()),
+                        ..ToolExecEndPayload::default()
+                    },
                 );
                 messages.push(crate::tools::envelope_to_message(
                     crate::tools::to_tool_result_envelope(
                         tc,
                         "runtime",
                         false,
-                        reason,
+                        denial_feedback(&reason, DenialClass::Permanent),
                         false,
                         crate::tools::ToolResultMeta {
                             side_effects: tool_side_effects(&tc.name),
@@ -434,9 +564,22 @@ impl<P: ModelProvider> Agent<P> {
                             warnings_max: None,
                             warnings_truncated: None,
                             docker: None,
+                            write_protection: None,
+                            cwd: None,
+                            timed_out: None,
+                            dry_run: None,
                         },
                     ),
                 ));
+                if repeat_action == DenialRepeatAction::Warn {
+                    messages.push(Message {
+                        role: Role::Developer,
+                        content: Some(ignored_denial_warning(&tc.name)),
+                        tool_call_id: None,
+                        tool_name: None,
+                        tool_calls: None,
+                    });
+                }
                 if self.inject_post_tool_operator_messages(&run_id, step, messages) {
                     return PlanConstraintDecision::RestartAgentStep;
                 }
@@ -511,29 +654,37 @@ impl<P: ModelProvider> Agent<P> {
         if let Some(reason) = crate::agent_budget::check_and_consume_tool_budget(
             &self.tool_call_budget,
             tool_budget_usage,
+            &tc.name,
             side_effects,
         ) {
             self.emit_event(
                 &run_id,
                 step,
-                crate::events::EventKind::ToolDecision,
-                serde_json::json!({
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "decision": "deny",
-                    "reason": reason.clone(),
-                    "source": "runtime_budget",
-                    "side_effects": side_effects,
-                    "budget": {
-                        "max_total_tool_calls": self.tool_call_budget.max_total_tool_calls,
-                        "max_mcp_calls": self.tool_call_budget.max_mcp_calls,
-                        "max_filesystem_read_calls": self.tool_call_budget.max_filesystem_read_calls,
-                        "max_filesystem_write_calls": self.tool_call_budget.max_filesystem_write_calls,
-                        "max_shell_calls": self.tool_call_budget.max_shell_calls,
-                        "max_network_calls": self.tool_call_budget.max_network_calls,
-                        "max_browser_calls": self.tool_call_budget.max_browser_calls
-                    }
-                }),
+                ToolDecisionPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    decision: "deny".to_string(),
+                    reason: Some(reason.clone()),
+                    source: Some(Some("runtime_budget".to_string())),
+                    side_effects: Some(side_effects),
+                    budget: Some(ToolBudgetPayload {
+                        max_total_tool_calls: Some(self.tool_call_budget.max_total_tool_calls),
+                        max_mcp_calls: Some(self.tool_call_budget.max_mcp_calls),
+                        max_filesystem_read_calls: Some(
+                            self.tool_call_budget.max_filesystem_read_calls,
+                        ),
+                        max_filesystem_write_calls: Some(
+                            self.tool_call_budget.max_filesystem_write_calls,
+                        ),
+                        max_shell_calls: Some(self.tool_call_budget.max_shell_calls),
+                        max_network_calls: Some(self.tool_call_budget.max_network_calls),
+                        max_browser_calls: Some(self.tool_call_budget.max_browser_calls),
+                        max_calls_per_tool: (!self.tool_call_budget.per_tool.is_empty())
+                            .then(|| self.tool_call_budget.per_tool.clone()),
+                    }),
+                    denial_class: Some(DenialClass::Permanent),
+                    ..ToolDecisionPayload::default()
+                },
             );
             return AllowToolCallDecision::Finalize(Box::new(
                 self.finalize_runtime_budget_deny_with_end(
@@ -593,35 +744,45 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "allow",
-                "approval_id": approval_id.clone(),
-                "approval_key": approval_key.clone(),
-                "reason": reason.clone(),
-                "source": source.clone(),
-                "approval_key_version": approval_key_version_meta.clone(),
-                "tool_schema_hash_hex": tool_schema_hash_hex.clone(),
-                "hooks_config_hash_hex": hooks_config_hash_hex.clone(),
-                "planner_hash_hex": planner_hash_hex.clone(),
-                "exec_target": decision_exec_target.clone(),
-                "taint_overall": taint_state.overall_str(),
-                "taint_enforced": taint_enforced,
-                "escalated": escalated,
-                "escalation_reason": escalation_reason.clone(),
-                "side_effects": tool_side_effects(&tc.name),
-                "tool_args_strict": if self.tool_rt.tool_args_strict.is_enabled() { "on" } else { "off" }
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "allow".to_string(),
+                approval_id: Some(approval_id.clone()),
+                approval_key: Some(approval_key.clone()),
+                reason: reason.clone(),
+                source: Some(source.clone()),
+                planner_hash_hex: Some(planner_hash_hex.clone()),
+                side_effects: Some(tool_side_effects(&tc.name)),
+                gate: Some(Box::new(ToolDecisionGateContext {
+                    approval_key_version: approval_key_version_meta.clone(),
+                    tool_schema_hash_hex: tool_schema_hash_hex.clone(),
+                    hooks_config_hash_hex: hooks_config_hash_hex.clone(),
+                    exec_target: decision_exec_target.clone(),
+                    taint_overall: taint_state.overall_str().to_string(),
+                    taint_enforced,
+                    escalated,
+                    escalation_reason: escalation_reason.clone(),
+                    tool_args_strict: if self.tool_rt.tool_args_strict.is_enabled() {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                    .to_string(),
+                })),
+                ..ToolDecisionPayload::default()
+            },
         );
         self.emit_tool_exec_start_events(&run_id, step, tc);
         let mut tool_msg = if let Some(err) = &invalid_args_error {
             crate::agent_tool_exec::make_invalid_args_tool_message(
                 tc,
                 err,
-                self.tool_rt.exec_target_kind,
+                self.tool_rt
+                    .exec_target_kind_for(crate::tools::tool_side_effects(&tc.name)),
             )
+        } else if tc.name == super::subtask::SPAWN_SUBTASK_TOOL {
+            self.run_subtask(&run_id, step, tc, tool_budget_usage).await
         } else {
             self.run_tool_with_timeout_and_emit_mcp_events(&run_id, step, tc, "await_result")
                 .await
@@ -899,30 +1060,43 @@ impl<P: ModelProvider> Agent<P> {
         total_token_usage: &TokenUsage,
         taint_state: &TaintState,
     ) -> super::agent_types::AgentOutcome {
+        let diff_preview = crate::gate::approval_diff_preview(
+            &self.gate_ctx.workdir,
+            tc,
+            self.approval_diff_max_lines,
+        );
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "require_approval",
-                "reason": reason.clone(),
-                "approval_id": approval_id.clone(),
-                "approval_key": approval_key.clone(),
-                "source": source.clone(),
-                "approval_key_version": approval_key_version_meta.clone(),
-                "tool_schema_hash_hex": tool_schema_hash_hex.clone(),
-                "hooks_config_hash_hex": hooks_config_hash_hex.clone(),
-                "planner_hash_hex": planner_hash_hex.clone(),
-                "exec_target": decision_exec_target.clone(),
-                "taint_overall": taint_state.overall_str(),
-                "taint_enforced": taint_enforced,
-                "escalated": escalated,
-                "escalation_reason": escalation_reason.clone(),
-                "side_effects": tool_side_effects(&tc.name),
-                "tool_args_strict": if self.tool_rt.tool_args_strict.is_enabled() { "on" } else { "off" }
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "require_approval".to_string(),
+                reason: Some(reason.clone()),
+                approval_id: Some(Some(approval_id.clone())),
+                approval_key: Some(approval_key.clone()),
+                source: Some(source.clone()),
+                planner_hash_hex: Some(planner_hash_hex.clone()),
+                side_effects: Some(tool_side_effects(&tc.name)),
+                gate: Some(Box::new(ToolDecisionGateContext {
+                    approval_key_version: approval_key_version_meta.clone(),
+                    tool_schema_hash_hex: tool_schema_hash_hex.clone(),
+                    hooks_config_hash_hex: hooks_config_hash_hex.clone(),
+                    exec_target: decision_exec_target.clone(),
+                    taint_overall: taint_state.overall_str().to_string(),
+                    taint_enforced,
+                    escalated,
+                    escalation_reason: escalation_reason.clone(),
+                    tool_args_strict: if self.tool_rt.tool_args_strict.is_enabled() {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                    .to_string(),
+                })),
+                diff_preview: diff_preview.clone().map(Box::new),
+                ..ToolDecisionPayload::default()
+            },
         );
         self.gate.record(GateEvent {
             run_id: run_id.clone(),
@@ -965,18 +1139,30 @@ impl<P: ModelProvider> Agent<P> {
             taint_enforced,
             escalated,
             escalation_reason,
+            taint_sources: if taint_enforced {
+                taint_state.sources()
+            } else {
+                Vec::new()
+            },
+            denial_class: None,
+            gate_context: self.gate_context_snapshot.clone(),
         });
+        let mut final_output = self.approval_required_output_message(
+            &approval_id,
+            &reason,
+            source.as_deref(),
+            escalated,
+            taint_state,
+        );
+        if let Some(preview) = &diff_preview {
+            final_output.push('\n');
+            final_output.push_str(&preview.render());
+        }
         self.finalize_approval_required_with_end(
             step,
             run_id,
             started_at,
-            self.approval_required_output_message(
-                &approval_id,
-                &reason,
-                source.as_deref(),
-                escalated,
-                taint_state,
-            ),
+            final_output,
             messages,
             observed_tool_calls,
             observed_tool_decisions,
@@ -1017,30 +1203,38 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            crate::events::EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "allow",
-                "reason": invalid_bypass_reason,
-                "approval_key_version": approval_key_version_meta.clone(),
-                "tool_schema_hash_hex": tool_schema_hash_hex.clone(),
-                "hooks_config_hash_hex": hooks_config_hash_hex.clone(),
-                "planner_hash_hex": planner_hash_hex.clone(),
-                "exec_target": decision_exec_target.clone(),
-                "taint_overall": taint_state.overall_str(),
-                "taint_enforced": taint_enforced,
-                "escalated": escalated,
-                "escalation_reason": escalation_reason.clone(),
-                "side_effects": tool_side_effects(&tc.name),
-                "tool_args_strict": if self.tool_rt.tool_args_strict.is_enabled() { "on" } else { "off" }
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "allow".to_string(),
+                reason: Some(invalid_bypass_reason.clone()),
+                planner_hash_hex: Some(planner_hash_hex.clone()),
+                side_effects: Some(tool_side_effects(&tc.name)),
+                gate: Some(Box::new(ToolDecisionGateContext {
+                    approval_key_version: approval_key_version_meta.clone(),
+                    tool_schema_hash_hex: tool_schema_hash_hex.clone(),
+                    hooks_config_hash_hex: hooks_config_hash_hex.clone(),
+                    exec_target: decision_exec_target.clone(),
+                    taint_overall: taint_state.overall_str().to_string(),
+                    taint_enforced,
+                    escalated,
+                    escalation_reason: escalation_reason.clone(),
+                    tool_args_strict: if self.tool_rt.tool_args_strict.is_enabled() {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                    .to_string(),
+                })),
+                ..ToolDecisionPayload::default()
+            },
         );
         self.emit_tool_exec_start_events(run_id, step, tc);
         let tool_msg = crate::agent_tool_exec::make_invalid_args_tool_message(
             tc,
             err,
-            self.tool_rt.exec_target_kind,
+            self.tool_rt
+                .exec_target_kind_for(crate::tools::tool_side_effects(&tc.name)),
         );
         let content = tool_msg.content.clone().unwrap_or_default();
         self.gate.record(GateEvent {
@@ -1084,17 +1278,24 @@ impl<P: ModelProvider> Agent<P> {
             taint_enforced,
             escalated,
             escalation_reason,
+            taint_sources: if taint_enforced {
+                taint_state.sources()
+            } else {
+                Vec::new()
+            },
+            denial_class: None,
+            gate_context: self.gate_context_snapshot.clone(),
         });
         self.emit_event(
             run_id,
             step,
-            crate::events::EventKind::ToolExecEnd,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "ok": false,
-                "truncated": false
-            }),
+            ToolExecEndPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                ok: false,
+                truncated: false,
+                ..ToolExecEndPayload::default()
+            },
         );
         messages.push(tool_msg);
         self.inject_post_tool_operator_messages(run_id, step, messages)
@@ -1132,29 +1333,38 @@ impl<P: ModelProvider> Agent<P> {
         total_token_usage: &TokenUsage,
         taint_state: &TaintState,
     ) -> super::agent_types::AgentOutcome {
+        let denial_class = classify_denial(&reason, escalation_reason.as_deref());
         self.emit_event(
             &run_id,
             step,
-            crate::events::EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "deny",
-                "reason": reason.clone(),
-                "approval_key": approval_key.clone(),
-                "source": source.clone(),
-                "approval_key_version": approval_key_version_meta.clone(),
-                "tool_schema_hash_hex": tool_schema_hash_hex.clone(),
-                "hooks_config_hash_hex": hooks_config_hash_hex.clone(),
-                "planner_hash_hex": planner_hash_hex.clone(),
-                "exec_target": decision_exec_target.clone(),
-                "taint_overall": taint_state.overall_str(),
-                "taint_enforced": taint_enforced,
-                "escalated": escalated,
-                "escalation_reason": escalation_reason.clone(),
-                "side_effects": tool_side_effects(&tc.name),
-                "tool_args_strict": if self.tool_rt.tool_args_strict.is_enabled() { "on" } else { "off" }
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "deny".to_string(),
+                reason: Some(reason.clone()),
+                approval_key: Some(approval_key.clone()),
+                source: Some(source.clone()),
+                planner_hash_hex: Some(planner_hash_hex.clone()),
+                side_effects: Some(tool_side_effects(&tc.name)),
+                gate: Some(Box::new(ToolDecisionGateContext {
+                    approval_key_version: approval_key_version_meta.clone(),
+                    tool_schema_hash_hex: tool_schema_hash_hex.clone(),
+                    hooks_config_hash_hex: hooks_config_hash_hex.clone(),
+                    exec_target: decision_exec_target.clone(),
+                    taint_overall: taint_state.overall_str().to_string(),
+                    taint_enforced,
+                    escalated,
+                    escalation_reason: escalation_reason.clone(),
+                    tool_args_strict: if self.tool_rt.tool_args_strict.is_enabled() {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                    .to_string(),
+                })),
+                denial_class: Some(denial_class),
+                ..ToolDecisionPayload::default()
+            },
         );
         self.gate.record(GateEvent {
             run_id: run_id.clone(),
@@ -1197,19 +1407,29 @@ impl<P: ModelProvider> Agent<P> {
             taint_enforced,
             escalated,
             escalation_reason,
+            taint_sources: if taint_enforced {
+                taint_state.sources()
+            } else {
+                Vec::new()
+            },
+            denial_class: Some(denial_class),
+            gate_context: self.gate_context_snapshot.clone(),
         });
         self.finalize_denied_with_end(
             step,
             run_id,
             started_at,
-            format!(
-                "Tool call '{}' denied: {}",
-                tc.name,
-                if let Some(src) = &source {
-                    format!("{} (source: {})", reason, src)
-                } else {
-                    reason.clone()
-                }
+            denial_feedback(
+                &format!(
+                    "Tool call '{}' denied: {}",
+                    tc.name,
+                    if let Some(src) = &source {
+                        format!("{} (source: {})", reason, src)
+                    } else {
+                        reason.clone()
+                    }
+                ),
+                denial_class,
             ),
             None,
             messages,
@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent_budget::ToolCallBudgetUsage;
+use crate::gate::{ApprovalMode, AutoApproveScope};
+use crate::hooks::protocol::HookArgsRewrite;
+use crate::providers::ModelProvider;
+use crate::taint::TaintState;
+
+use super::Agent;
+
+/// State the gate saw when it decided one tool call, kept on the decision
+/// record so the decision can be audited and re-evaluated offline. Budgets
+/// are the remaining calls before the call was charged; limits of `0`
+/// (unlimited) are left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateContextSnapshot {
+    pub taint_overall: String,
+    /// Tool calls whose output contributed taint so far.
+    pub taint_source_count: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_total_tool_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub remaining_calls_by_category: BTreeMap<String, usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_step_id: Option<String>,
+    pub approval_mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_approve_scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_hash_hex: Option<String>,
+    /// Set when pre_tool hooks rewrote the call's arguments before gating.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_rewrite: Option<HookArgsRewrite>,
+}
+
+impl GateContextSnapshot {
+    /// One-line form used by `replay`.
+    pub fn render_compact(&self) -> String {
+        let mut out = format!(
+            "taint={} taint_sources={} remaining_total={}",
+            self.taint_overall,
+            self.taint_source_count,
+            self.remaining_total_tool_calls
+                .map(|n| n.to_string())
+                .unwrap_or_else(|| "unlimited".to_string())
+        );
+        for (category, remaining) in &self.remaining_calls_by_category {
+            out.push_str(&format!(" remaining_{category}={remaining}"));
+        }
+        out.push_str(&format!(
+            " plan_step={} approval_mode={}",
+            self.plan_step_id.as_deref().unwrap_or("-"),
+            self.approval_mode
+        ));
+        if let Some(scope) = &self.auto_approve_scope {
+            out.push_str(&format!(" auto_approve_scope={scope}"));
+        }
+        out.push_str(&format!(
+            " policy={}",
+            self.policy_hash_hex.as_deref().unwrap_or("-")
+        ));
+        out
+    }
+}
+
+fn remaining(limit: usize, used: usize) -> Option<usize> {
+    (limit > 0).then(|| limit.saturating_sub(used))
+}
+
+impl<P: ModelProvider> Agent<P> {
+    pub(super) fn capture_gate_context_snapshot(
+        &self,
+        active_plan_step_idx: usize,
+        usage: &ToolCallBudgetUsage,
+        taint_state: &TaintState,
+    ) -> GateContextSnapshot {
+        let budget = &self.tool_call_budget;
+        let remaining_calls_by_category = [
+            ("mcp", remaining(budget.max_mcp_calls, usage.mcp_calls)),
+            (
+                "filesystem_read",
+                remaining(
+                    budget.max_filesystem_read_calls,
+                    usage.filesystem_read_calls,
+                ),
+            ),
+            (
+                "filesystem_write",
+                remaining(
+                    budget.max_filesystem_write_calls,
+                    usage.filesystem_write_calls,
+                ),
+            ),
+            (
+                "shell",
+                remaining(budget.max_shell_calls, usage.shell_calls),
+            ),
+            (
+                "network",
+                remaining(budget.max_network_calls, usage.network_calls),
+            ),
+            (
+                "browser",
+                remaining(budget.max_browser_calls, usage.browser_calls),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(category, left)| left.map(|left| (category.to_string(), left)))
+        .collect();
+        GateContextSnapshot {
+            taint_overall: taint_state.overall_str().to_string(),
+            taint_source_count: taint_state.spans_by_tool_call_id.len(),
+            remaining_total_tool_calls: remaining(
+                budget.max_total_tool_calls,
+                usage.total_tool_calls,
+            ),
+            remaining_calls_by_category,
+            plan_step_id: if self.plan_enforcement_active() {
+                self.current_plan_constraint(active_plan_step_idx)
+                    .map(|c| c.step_id)
+            } else {
+                None
+            },
+            approval_mode: match self.gate_ctx.approval_mode {
+                ApprovalMode::Interrupt => "interrupt",
+                ApprovalMode::Auto => "auto",
+                ApprovalMode::Fail => "fail",
+            }
+            .to_string(),
+            auto_approve_scope: matches!(self.gate_ctx.approval_mode, ApprovalMode::Auto).then(
+                || {
+                    match self.gate_ctx.auto_approve_scope {
+                        AutoApproveScope::Run => "run",
+                        AutoApproveScope::Session => "session",
+                        AutoApproveScope::Workspace => "workspace",
+                    }
+                    .to_string()
+                },
+            ),
+            policy_hash_hex: self
+                .policy_loaded
+                .as_ref()
+                .and_then(|p| p.policy_hash_hex.clone()),
+            hook_rewrite: None,
+        }
+    }
+
+    /// Snapshot for an allow decision. An allow with no decision source was
+    /// never evaluated (no gate, no rule), so it may skip the snapshot unless
+    /// it carries a hook rewrite.
+    pub(super) fn gate_context_for_allow(
+        &self,
+        source: Option<&str>,
+    ) -> Option<GateContextSnapshot> {
+        let rewritten = self
+            .gate_context_snapshot
+            .as_ref()
+            .is_some_and(|s| s.hook_rewrite.is_some());
+        if source.is_none() && self.skip_unevaluated_gate_snapshots && !rewritten {
+            return None;
+        }
+        self.gate_context_snapshot.clone()
+    }
+}
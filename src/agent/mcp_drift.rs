@@ -1,10 +1,12 @@
-use crate::events::EventKind;
+use crate::events::{McpDriftPayload, StepBlockedPayload, ToolDecisionPayload};
+use crate::mcp::registry::McpRegistry;
 use crate::providers::ModelProvider;
 use crate::taint::TaintState;
 use crate::tools::tool_side_effects;
 use crate::types::{Message, TokenUsage, ToolCall};
 
 use super::agent_types::ToolDecisionRecord;
+use super::denials::DenialClass;
 use super::Agent;
 
 pub(super) enum McpDriftDecision {
@@ -13,12 +15,14 @@ pub(super) enum McpDriftDecision {
 }
 
 impl<P: ModelProvider> Agent<P> {
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn record_mcp_drift_warn_decision(
         &mut self,
         run_id: &str,
         step: u32,
         tc: &ToolCall,
         reason: String,
+        source: &str,
         taint_state: &TaintState,
         observed_tool_decisions: &mut Vec<ToolDecisionRecord>,
     ) {
@@ -28,25 +32,28 @@ impl<P: ModelProvider> Agent<P> {
             tool: tc.name.clone(),
             decision: "allow".to_string(),
             reason: Some(reason.clone()),
-            source: Some("mcp_drift_warn".to_string()),
+            source: Some(source.to_string()),
             approval_id: None,
             taint_overall: Some(taint_state.overall_str().to_string()),
             taint_enforced: false,
             escalated: false,
             escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: None,
+            gate_context: self.gate_context_snapshot.clone(),
         });
         self.emit_event(
             run_id,
             step,
-            EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "allow",
-                "reason": reason,
-                "source": "mcp_drift_warn",
-                "side_effects": tool_side_effects(&tc.name)
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "allow".to_string(),
+                reason: Some(reason.clone()),
+                source: Some(Some(source.to_string())),
+                side_effects: Some(tool_side_effects(&tc.name)),
+                ..ToolDecisionPayload::default()
+            },
         );
     }
 
@@ -57,6 +64,7 @@ impl<P: ModelProvider> Agent<P> {
         step: u32,
         tc: &ToolCall,
         reason: String,
+        source: &str,
         step_block_reason: &str,
         started_at: String,
         messages: Vec<Message>,
@@ -74,12 +82,12 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            EventKind::StepBlocked,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "reason": step_block_reason
-            }),
+            StepBlockedPayload {
+                tool_call_id: Some(tc.id.clone()),
+                name: Some(tc.name.clone()),
+                reason: Some(step_block_reason.to_string()),
+                ..StepBlockedPayload::default()
+            },
         );
         observed_tool_decisions.push(ToolDecisionRecord {
             step,
@@ -87,25 +95,29 @@ impl<P: ModelProvider> Agent<P> {
             tool: tc.name.clone(),
             decision: "deny".to_string(),
             reason: Some(reason.clone()),
-            source: Some("mcp_drift".to_string()),
+            source: Some(source.to_string()),
             approval_id: None,
             taint_overall: Some(taint_state.overall_str().to_string()),
             taint_enforced: false,
             escalated: false,
             escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: Some(DenialClass::Permanent),
+            gate_context: self.gate_context_snapshot.clone(),
         });
         self.emit_event(
             &run_id,
             step,
-            EventKind::ToolDecision,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "decision": "deny",
-                "reason": reason,
-                "source": "mcp_drift",
-                "side_effects": tool_side_effects(&tc.name)
-            }),
+            ToolDecisionPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                decision: "deny".to_string(),
+                reason: Some(reason.clone()),
+                source: Some(Some(source.to_string())),
+                side_effects: Some(tool_side_effects(&tc.name)),
+                denial_class: Some(DenialClass::Permanent),
+                ..ToolDecisionPayload::default()
+            },
         );
         self.finalize_denied_with_end(
             step,
@@ -127,6 +139,46 @@ impl<P: ModelProvider> Agent<P> {
         )
     }
 
+    /// Whether `check_mcp_drift_for_tool_call` would report anything for
+    /// `tc`, without emitting or recording it. A failed probe counts as
+    /// drift.
+    pub(super) async fn mcp_drift_pending(
+        &mut self,
+        tc: &ToolCall,
+        expected_mcp_catalog_hash_hex: Option<&String>,
+        expected_mcp_docs_hash_hex: Option<&String>,
+    ) -> bool {
+        if !tc.name.starts_with("mcp.")
+            || matches!(self.mcp_pin_enforcement, super::McpPinEnforcementMode::Off)
+        {
+            return false;
+        }
+        if mcp_schema_drift(
+            self.mcp_registry.as_deref(),
+            self.gate_ctx.tool_schema_hashes.get(&tc.name),
+            tc,
+        )
+        .await
+        .is_some()
+        {
+            return true;
+        }
+        let (Some(registry), Some(expected_hash)) =
+            (self.mcp_registry.as_ref(), expected_mcp_catalog_hash_hex)
+        else {
+            return false;
+        };
+        if registry.live_tool_catalog_hash_hex().await.ok().as_ref() != Some(expected_hash) {
+            return true;
+        }
+        match expected_mcp_docs_hash_hex {
+            Some(expected_docs) => {
+                registry.live_tool_docs_hash_hex().await.ok().as_ref() != Some(expected_docs)
+            }
+            None => false,
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(super) async fn check_mcp_drift_for_tool_call(
         &mut self,
@@ -153,6 +205,76 @@ impl<P: ModelProvider> Agent<P> {
         {
             return McpDriftDecision::Continue;
         }
+        let schema_drift = mcp_schema_drift(
+            self.mcp_registry.as_deref(),
+            self.gate_ctx.tool_schema_hashes.get(&tc.name),
+            tc,
+        )
+        .await;
+        if let Some((pinned, live)) = schema_drift {
+            let reason = format!(
+                "MCP_SCHEMA_DRIFT detected: input schema of {} changed during run (pinned {}, got {})",
+                tc.name, pinned, live
+            );
+            self.emit_event(
+                &run_id,
+                step,
+                McpDriftPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    expected_hash_hex: pinned.clone(),
+                    actual_hash_hex: Some(live.clone()),
+                    catalog_hash_expected: expected_mcp_catalog_hash_hex
+                        .cloned()
+                        .unwrap_or_default(),
+                    schema_hash_pinned: Some(pinned),
+                    schema_hash_live: Some(live.clone()),
+                    schema_drift: Some(true),
+                    enforcement: format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
+                    codes: vec!["MCP_SCHEMA_DRIFT".to_string()],
+                    primary_code: "MCP_SCHEMA_DRIFT".to_string(),
+                    ..McpDriftPayload::default()
+                },
+            );
+            if matches!(self.mcp_pin_enforcement, super::McpPinEnforcementMode::Hard) {
+                return McpDriftDecision::Finalize(Box::new(
+                    self.finalize_mcp_drift_hard_deny_with_end(
+                        run_id,
+                        step,
+                        tc,
+                        reason,
+                        "mcp_pin",
+                        "mcp_schema_drift",
+                        started_at,
+                        messages,
+                        observed_tool_calls,
+                        observed_tool_decisions.clone(),
+                        request_context_chars,
+                        last_compaction_report,
+                        hook_invocations,
+                        provider_retry_count,
+                        provider_error_count,
+                        saw_token_usage,
+                        total_token_usage,
+                        taint_state,
+                    ),
+                ));
+            }
+            self.record_mcp_drift_warn_decision(
+                &run_id,
+                step,
+                tc,
+                reason,
+                "mcp_pin_warn",
+                taint_state,
+                observed_tool_decisions,
+            );
+            // Re-pin so the change is reported once and later approvals are
+            // keyed on the schema the tool has now.
+            self.gate_ctx
+                .tool_schema_hashes
+                .insert(tc.name.clone(), live);
+        }
         let (Some(registry), Some(expected_hash)) =
             (self.mcp_registry.as_ref(), expected_mcp_catalog_hash_hex)
         else {
@@ -202,22 +324,22 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     &run_id,
                     step,
-                    EventKind::McpDrift,
-                    serde_json::json!({
-                        "tool_call_id": tc.id,
-                        "name": tc.name,
-                        "expected_hash_hex": expected_hash,
-                        "actual_hash_hex": actual_hash,
-                        "catalog_hash_expected": expected_hash,
-                        "catalog_hash_live": actual_hash,
-                        "catalog_drift": catalog_drift,
-                        "docs_hash_expected": expected_docs_hash,
-                        "docs_hash_live": actual_docs_hash,
-                        "docs_drift": docs_drift,
-                        "enforcement": format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
-                        "codes": codes,
-                        "primary_code": primary_code
-                    }),
+                    McpDriftPayload {
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        expected_hash_hex: expected_hash.clone(),
+                        actual_hash_hex: Some(actual_hash.clone()),
+                        catalog_hash_expected: expected_hash.clone(),
+                        catalog_hash_live: Some(actual_hash),
+                        catalog_drift: Some(catalog_drift),
+                        docs_hash_expected: Some(Some(expected_docs_hash.to_string())),
+                        docs_hash_live: Some(actual_docs_hash),
+                        docs_drift: Some(docs_drift),
+                        enforcement: format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
+                        codes: codes.iter().map(|s| s.to_string()).collect(),
+                        primary_code: primary_code.to_string(),
+                        ..McpDriftPayload::default()
+                    },
                 );
                 if matches!(self.mcp_pin_enforcement, super::McpPinEnforcementMode::Hard) {
                     return McpDriftDecision::Finalize(Box::new(
@@ -227,6 +349,7 @@ impl<P: ModelProvider> Agent<P> {
                             tc,
                             reason,
                             "mcp_drift",
+                            "mcp_drift",
                             started_at,
                             messages,
                             observed_tool_calls,
@@ -247,6 +370,7 @@ impl<P: ModelProvider> Agent<P> {
                     step,
                     tc,
                     reason,
+                    "mcp_drift_warn",
                     taint_state,
                     observed_tool_decisions,
                 );
@@ -257,22 +381,22 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     &run_id,
                     step,
-                    EventKind::McpDrift,
-                    serde_json::json!({
-                        "tool_call_id": tc.id,
-                        "name": tc.name,
-                        "expected_hash_hex": expected_hash,
-                        "actual_hash_hex": actual_hash,
-                        "catalog_hash_expected": expected_hash,
-                        "catalog_hash_live": actual_hash,
-                        "catalog_drift": false,
-                        "docs_hash_expected": expected_mcp_docs_hash_hex,
-                        "docs_probe_error": e.to_string(),
-                        "docs_drift": false,
-                        "enforcement": format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
-                        "codes": ["MCP_DOCS_DRIFT_PROBE_FAILED"],
-                        "primary_code": "MCP_DOCS_DRIFT_PROBE_FAILED"
-                    }),
+                    McpDriftPayload {
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        expected_hash_hex: expected_hash.clone(),
+                        actual_hash_hex: Some(actual_hash.clone()),
+                        catalog_hash_expected: expected_hash.clone(),
+                        catalog_hash_live: Some(actual_hash),
+                        catalog_drift: Some(false),
+                        docs_hash_expected: Some(expected_mcp_docs_hash_hex.cloned()),
+                        docs_probe_error: Some(e.to_string()),
+                        docs_drift: Some(false),
+                        enforcement: format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
+                        codes: vec!["MCP_DOCS_DRIFT_PROBE_FAILED".to_string()],
+                        primary_code: "MCP_DOCS_DRIFT_PROBE_FAILED".to_string(),
+                        ..McpDriftPayload::default()
+                    },
                 );
                 if matches!(self.mcp_pin_enforcement, super::McpPinEnforcementMode::Hard) {
                     return McpDriftDecision::Finalize(Box::new(
@@ -282,6 +406,7 @@ impl<P: ModelProvider> Agent<P> {
                             tc,
                             reason,
                             "mcp_drift",
+                            "mcp_drift",
                             started_at,
                             messages,
                             observed_tool_calls,
@@ -302,6 +427,7 @@ impl<P: ModelProvider> Agent<P> {
                     step,
                     tc,
                     reason,
+                    "mcp_drift_warn",
                     taint_state,
                     observed_tool_decisions,
                 );
@@ -315,20 +441,20 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         &run_id,
                         step,
-                        EventKind::McpDrift,
-                        serde_json::json!({
-                            "tool_call_id": tc.id,
-                            "name": tc.name,
-                            "expected_hash_hex": expected_hash,
-                            "actual_hash_hex": actual_hash,
-                            "catalog_hash_expected": expected_hash,
-                            "catalog_hash_live": actual_hash,
-                            "catalog_drift": true,
-                            "docs_drift": false,
-                            "enforcement": format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
-                            "codes": ["MCP_CATALOG_DRIFT"],
-                            "primary_code": "MCP_CATALOG_DRIFT"
-                        }),
+                        McpDriftPayload {
+                            tool_call_id: tc.id.clone(),
+                            name: tc.name.clone(),
+                            expected_hash_hex: expected_hash.clone(),
+                            actual_hash_hex: Some(actual_hash.clone()),
+                            catalog_hash_expected: expected_hash.clone(),
+                            catalog_hash_live: Some(actual_hash),
+                            catalog_drift: Some(true),
+                            docs_drift: Some(false),
+                            enforcement: format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
+                            codes: vec!["MCP_CATALOG_DRIFT".to_string()],
+                            primary_code: "MCP_CATALOG_DRIFT".to_string(),
+                            ..McpDriftPayload::default()
+                        },
                     );
                     if matches!(self.mcp_pin_enforcement, super::McpPinEnforcementMode::Hard) {
                         return McpDriftDecision::Finalize(Box::new(
@@ -338,6 +464,7 @@ impl<P: ModelProvider> Agent<P> {
                                 tc,
                                 reason,
                                 "mcp_drift",
+                                "mcp_drift",
                                 started_at,
                                 messages,
                                 observed_tool_calls,
@@ -358,6 +485,7 @@ impl<P: ModelProvider> Agent<P> {
                         step,
                         tc,
                         reason,
+                        "mcp_drift_warn",
                         taint_state,
                         observed_tool_decisions,
                     );
@@ -370,18 +498,18 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     &run_id,
                     step,
-                    EventKind::McpDrift,
-                    serde_json::json!({
-                        "tool_call_id": tc.id,
-                        "name": tc.name,
-                        "expected_hash_hex": expected_hash,
-                        "catalog_hash_expected": expected_hash,
-                        "catalog_probe_error": e.to_string(),
-                        "enforcement": format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
-                        "codes": ["MCP_CATALOG_DRIFT_PROBE_FAILED"],
-                        "primary_code": "MCP_CATALOG_DRIFT_PROBE_FAILED",
-                        "error": e.to_string()
-                    }),
+                    McpDriftPayload {
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        expected_hash_hex: expected_hash.clone(),
+                        catalog_hash_expected: expected_hash.clone(),
+                        catalog_probe_error: Some(e.to_string()),
+                        enforcement: format!("{:?}", self.mcp_pin_enforcement).to_lowercase(),
+                        codes: vec!["MCP_CATALOG_DRIFT_PROBE_FAILED".to_string()],
+                        primary_code: "MCP_CATALOG_DRIFT_PROBE_FAILED".to_string(),
+                        error: Some(e.to_string()),
+                        ..McpDriftPayload::default()
+                    },
                 );
                 if matches!(self.mcp_pin_enforcement, super::McpPinEnforcementMode::Hard) {
                     return McpDriftDecision::Finalize(Box::new(
@@ -390,6 +518,7 @@ impl<P: ModelProvider> Agent<P> {
                             step,
                             tc,
                             reason,
+                            "mcp_drift",
                             "mcp_drift_probe_failed",
                             started_at,
                             messages,
@@ -411,6 +540,7 @@ impl<P: ModelProvider> Agent<P> {
                     step,
                     tc,
                     reason,
+                    "mcp_drift_warn",
                     taint_state,
                     observed_tool_decisions,
                 );
@@ -419,3 +549,19 @@ impl<P: ModelProvider> Agent<P> {
         McpDriftDecision::Continue
     }
 }
+
+/// Pinned and live input schema hash of `tc` when they differ. A failed
+/// probe is left to the catalog check, which reports it.
+async fn mcp_schema_drift(
+    registry: Option<&McpRegistry>,
+    pinned: Option<&String>,
+    tc: &ToolCall,
+) -> Option<(String, String)> {
+    let pinned = pinned?;
+    let live = registry?
+        .live_tool_schema_hash_hex(&tc.name)
+        .await
+        .ok()
+        .flatten()?;
+    (live != *pinned).then(|| (pinned.clone(), live))
+}
@@ -0,0 +1,88 @@
+use crate::mcp::roots::{check_mapped_paths, McpMappedPath};
+use crate::providers::ModelProvider;
+use crate::tools::{
+    envelope_to_message, to_tool_result_envelope_with_error, tool_side_effects, ToolErrorDetail,
+    ToolResultMeta,
+};
+use crate::types::{Message, ToolCall};
+
+use super::Agent;
+
+impl<P: ModelProvider> Agent<P> {
+    /// Server name and mapped path-typed arguments of an MCP call whose
+    /// server has declared roots.
+    fn mcp_mapped_argument_paths(&self, tc: &ToolCall) -> Option<(String, Vec<McpMappedPath>)> {
+        if self.mcp_root_map.is_empty() || !tc.name.starts_with("mcp.") {
+            return None;
+        }
+        let (server, schema) = self
+            .mcp_registry
+            .as_ref()?
+            .tool_server_and_schema(&tc.name)?;
+        if !self.mcp_root_map.is_mapped(server) {
+            return None;
+        }
+        let paths = self
+            .mcp_root_map
+            .mapped_argument_paths(server, &tc.arguments, schema);
+        Some((server.to_string(), paths))
+    }
+
+    /// Failed tool message when a mapped MCP path falls outside the declared
+    /// roots, the read allowlist, or policy read rules.
+    pub(super) fn mcp_root_denial_message(&self, tc: &ToolCall) -> Option<Message> {
+        let (server, paths) = self.mcp_mapped_argument_paths(tc)?;
+        let denial = check_mapped_paths(
+            &server,
+            &paths,
+            self.tool_rt.read_allowlist.as_ref(),
+            self.policy_for_taint.as_ref(),
+        )?;
+        Some(envelope_to_message(to_tool_result_envelope_with_error(
+            tc,
+            "mcp",
+            false,
+            denial.message.clone(),
+            false,
+            Some(ToolErrorDetail {
+                code: denial.code,
+                message: denial.message,
+                expected_schema: None,
+                received_args: Some(tc.arguments.clone()),
+                minimal_example: None,
+                available_tools: None,
+            }),
+            ToolResultMeta {
+                side_effects: tool_side_effects(&tc.name),
+                bytes: None,
+                exit_code: None,
+                stderr_truncated: None,
+                stdout_truncated: None,
+                source: "mcp".to_string(),
+                execution_target: "host".to_string(),
+                warnings: None,
+                warnings_max: None,
+                warnings_truncated: None,
+                docker: None,
+                write_protection: None,
+                cwd: None,
+                timed_out: None,
+                dry_run: None,
+            },
+        )))
+    }
+
+    /// Mapped host paths from an MCP call's arguments and result, for taint.
+    pub(super) fn mcp_mapped_paths_for_taint(
+        &self,
+        tc: &ToolCall,
+        content: &str,
+    ) -> Vec<McpMappedPath> {
+        let Some((server, mut paths)) = self.mcp_mapped_argument_paths(tc) else {
+            return Vec::new();
+        };
+        let result = crate::agent_taint_helpers::extract_tool_envelope_content(content);
+        paths.extend(self.mcp_root_map.mapped_result_paths(&server, &result));
+        paths
+    }
+}
@@ -1,5 +1,8 @@
-use crate::events::EventKind;
-use crate::providers::{ModelProvider, StreamDelta};
+use crate::events::{
+    ModelDeltaPayload, ModelMismatchPayload, ModelRequestStartPayload, ToolCallFragmentPayload,
+    ToolCallStreamedPayload,
+};
+use crate::providers::{ModelProvider, StreamDelta, ToolCallAssembler};
 use crate::types::GenerateRequest;
 
 use super::Agent;
@@ -18,11 +21,11 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::ModelRequestStart,
-            serde_json::json!({
-                "message_count": req.messages.len(),
-                "tool_count": req.tools.as_ref().map(|t| t.len()).unwrap_or(0)
-            }),
+            ModelRequestStartPayload {
+                message_count: Some(req.messages.len()),
+                tool_count: req.tools.as_ref().map(|t| t.len()).unwrap_or(0),
+                ..ModelRequestStartPayload::default()
+            },
         );
 
         let hard_timeout = std::time::Duration::from_millis(MODEL_REQUEST_HARD_TIMEOUT_MS);
@@ -32,38 +35,62 @@ impl<P: ModelProvider> Agent<P> {
                 if self.provider.supports_streaming() {
                     let mut collected = Vec::<StreamDelta>::new();
                     let mut callback = |delta| collected.push(delta);
-                    let out = self
+                    let mut out = self
                         .provider
                         .generate_streaming(req.clone(), &mut callback)
                         .await;
+                    let mut assembler = ToolCallAssembler::default();
                     for delta in collected {
                         match delta {
                             StreamDelta::Content(text) => {
                                 self.emit_event(
                                     run_id,
                                     step,
-                                    EventKind::ModelDelta,
-                                    serde_json::json!({"delta": text}),
+                                    ModelDeltaPayload {
+                                        delta: Some(text),
+                                        ..ModelDeltaPayload::default()
+                                    },
                                 );
                             }
                             StreamDelta::ToolCallFragment(fragment) => {
+                                assembler.push(&fragment);
                                 self.emit_event(
                                     run_id,
                                     step,
-                                    EventKind::ModelDelta,
-                                    serde_json::json!({
-                                        "tool_call_fragment": {
-                                            "index": fragment.index,
-                                            "id": fragment.id,
-                                            "name": fragment.name,
-                                            "arguments_fragment": fragment.arguments_fragment,
-                                            "complete": fragment.complete
-                                        }
-                                    }),
+                                    ModelDeltaPayload {
+                                        tool_call_fragment: Some(ToolCallFragmentPayload {
+                                            index: fragment.index,
+                                            id: fragment.id,
+                                            name: fragment.name,
+                                            arguments_fragment: fragment.arguments_fragment,
+                                            complete: fragment.complete,
+                                        }),
+                                        ..ModelDeltaPayload::default()
+                                    },
                                 );
                             }
                         }
                     }
+                    let streamed = assembler.finish();
+                    for (index, call) in streamed.iter().enumerate() {
+                        self.emit_event(
+                            run_id,
+                            step,
+                            ToolCallStreamedPayload {
+                                index,
+                                tool_call_id: call.id.clone(),
+                                name: call.name.clone(),
+                                arguments: call.arguments.clone(),
+                            },
+                        );
+                    }
+                    // Providers that only stream fragments still hand back
+                    // the calls the stream described.
+                    if let Ok(resp) = out.as_mut() {
+                        if resp.tool_calls.is_empty() {
+                            resp.tool_calls = streamed;
+                        }
+                    }
                     out
                 } else {
                     eprintln!(
@@ -85,4 +112,83 @@ impl<P: ModelProvider> Agent<P> {
             )),
         }
     }
+
+    /// Compares the model name the server reported against the requested
+    /// model. Returns the `MODEL_MISMATCH` failure reason when the mismatch
+    /// must end the run.
+    pub(super) fn verify_served_model(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        served: Option<&str>,
+    ) -> Option<String> {
+        let served = served?;
+        let newly_reported = self.served_model.as_deref() != Some(served);
+        self.served_model = Some(served.to_string());
+        if served_model_matches(&self.model, served) {
+            return None;
+        }
+        if newly_reported || self.require_exact_model {
+            self.emit_event(
+                run_id,
+                step,
+                ModelMismatchPayload {
+                    requested_model: self.model.clone(),
+                    served_model: served.to_string(),
+                    enforced: self.require_exact_model,
+                },
+            );
+        }
+        self.require_exact_model.then(|| {
+            format!(
+                "MODEL_MISMATCH: requested model '{}' but the server reported serving '{}'",
+                self.model, served
+            )
+        })
+    }
+}
+
+/// Whether `served` names the same model as `requested`. Tolerates Ollama's
+/// implicit `:latest` tag and dated snapshot suffixes (`gpt-4o` served as
+/// `gpt-4o-2024-08-06`).
+pub(crate) fn served_model_matches(requested: &str, served: &str) -> bool {
+    fn strip_latest(name: &str) -> &str {
+        name.trim().strip_suffix(":latest").unwrap_or(name.trim())
+    }
+    let requested = strip_latest(requested);
+    let served = strip_latest(served);
+    if requested.eq_ignore_ascii_case(served) {
+        return true;
+    }
+    served.len() > requested.len()
+        && served.is_char_boundary(requested.len())
+        && served[..requested.len()].eq_ignore_ascii_case(requested)
+        && served[requested.len()..]
+            .strip_prefix('-')
+            .is_some_and(|rest| {
+                rest.chars().any(|c| c.is_ascii_digit())
+                    && rest.chars().all(|c| c.is_ascii_digit() || c == '-')
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::served_model_matches;
+
+    #[test]
+    fn served_model_matching_tolerates_latest_tag_and_dated_snapshots() {
+        assert!(served_model_matches(
+            "qwen2.5-coder:14b",
+            "qwen2.5-coder:14b"
+        ));
+        assert!(served_model_matches("llama3.2", "llama3.2:latest"));
+        assert!(served_model_matches("llama3.2:latest", "llama3.2"));
+        assert!(served_model_matches("gpt-4o", "gpt-4o-2024-08-06"));
+        assert!(!served_model_matches(
+            "qwen2.5-coder:14b",
+            "qwen2.5-coder:7b"
+        ));
+        assert!(!served_model_matches("gpt-4o", "gpt-4o-mini"));
+        assert!(!served_model_matches("my-model", "default"));
+    }
 }
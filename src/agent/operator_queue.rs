@@ -1,5 +1,11 @@
-use crate::events::EventKind;
-use crate::operator_queue::{DeliveryBoundary, QueueMessageKind, QueuedOperatorMessage};
+use crate::events::{
+    CompletionBlockedPayload, InterruptRaisedPayload, PhaseEnteredPayload, PhaseExitedPayload,
+    QueueDeliveredPayload, QueueInterruptPayload, QueueOverflowPayload, QueueSubmittedPayload,
+};
+use crate::operator_queue::{
+    DeliveryBoundary, QueueMessageKind, QueueSubmitRequest, QueueSubmitResult,
+    QueuedOperatorMessage,
+};
 use crate::providers::ModelProvider;
 use crate::types::{Message, Role};
 
@@ -12,6 +18,7 @@ impl<P: ModelProvider> Agent<P> {
         step: u32,
         messages: &mut Vec<Message>,
     ) -> (bool, bool) {
+        self.drain_external_operator_queue(run_id, step);
         self.deliver_operator_queue_at_boundary(run_id, step, DeliveryBoundary::TurnIdle, messages)
     }
 
@@ -37,30 +44,57 @@ impl<P: ModelProvider> Agent<P> {
         kind: QueueMessageKind,
         content: &str,
     ) -> QueuedOperatorMessage {
-        let submitted = self
+        let result = self
             .operator_queue
-            .submit(kind, content, &self.operator_queue_limits)
-            .queued;
+            .submit(kind, content, &self.operator_queue_limits);
         if let Some(run_id) = self.gate_ctx.run_id.clone() {
+            self.emit_queue_submit_result(&run_id, 0, &result);
+        }
+        self.operator_queue
+            .record_submitted(&result.queued, 0, &crate::trust::now_rfc3339());
+        result.queued
+    }
+
+    fn emit_queue_submit_result(&mut self, run_id: &str, step: u32, result: &QueueSubmitResult) {
+        self.emit_queue_submitted(run_id, step, &result.queued);
+        if let Some(overflow) = &result.overflow {
             self.emit_event(
-                &run_id,
-                0,
-                EventKind::QueueSubmitted,
-                serde_json::json!({
-                    "queue_id": submitted.queue_id,
-                    "sequence_no": submitted.sequence_no,
-                    "kind": submitted.kind,
-                    "truncated": submitted.truncated,
-                    "bytes_kept": submitted.bytes_kept,
-                    "bytes_loaded": submitted.bytes_loaded,
-                    "next_delivery": match submitted.kind {
-                        QueueMessageKind::Steer => DeliveryBoundary::PostTool.user_phrase(),
-                        QueueMessageKind::FollowUp => DeliveryBoundary::TurnIdle.user_phrase(),
-                    }
-                }),
+                run_id,
+                step,
+                QueueOverflowPayload {
+                    queue_id: result.queued.queue_id.clone(),
+                    policy: overflow.policy,
+                    max_pending: overflow.max_pending,
+                    dropped_queue_ids: overflow
+                        .dropped
+                        .iter()
+                        .map(|m| m.queue_id.clone())
+                        .collect(),
+                },
             );
         }
-        submitted
+    }
+
+    fn emit_queue_submitted(&mut self, run_id: &str, step: u32, submitted: &QueuedOperatorMessage) {
+        self.emit_event(
+            run_id,
+            step,
+            QueueSubmittedPayload {
+                queue_id: submitted.queue_id.clone(),
+                sequence_no: submitted.sequence_no,
+                kind: submitted.kind,
+                truncated: submitted.truncated,
+                bytes_kept: submitted.bytes_kept,
+                bytes_loaded: submitted.bytes_loaded,
+                next_delivery: match submitted.kind {
+                    QueueMessageKind::Steer | QueueMessageKind::Cancel => {
+                        DeliveryBoundary::PostTool.user_phrase()
+                    }
+                    QueueMessageKind::FollowUp => DeliveryBoundary::TurnIdle.user_phrase(),
+                }
+                .to_string(),
+            },
+        );
     }
 
     #[allow(dead_code)]
@@ -80,79 +114,116 @@ impl<P: ModelProvider> Agent<P> {
         boundary: DeliveryBoundary,
         messages: &mut Vec<Message>,
     ) -> (bool, bool) {
-        let Some(delivery) = self.operator_queue.deliver_at_boundary(boundary) else {
+        let boundary_seq = self.operator_queue.reach_boundary(boundary);
+        let now = crate::trust::now_rfc3339();
+        let replayed = self.operator_queue.submit_replay_due(
+            boundary,
+            boundary_seq,
+            step,
+            &now,
+            &self.operator_queue_limits,
+        );
+        for result in &replayed {
+            self.emit_queue_submit_result(run_id, step, result);
+        }
+        let Some(delivery) = self
+            .operator_queue
+            .deliver_at_boundary(boundary, &self.operator_queue_limits)
+        else {
             return (false, false);
         };
+        for queue_id in
+            std::iter::once(&delivery.message.queue_id).chain(&delivery.coalesced_queue_ids)
+        {
+            self.operator_queue
+                .record_delivered(queue_id, boundary, boundary_seq, step, &now);
+        }
         self.emit_event(
             run_id,
             step,
-            EventKind::QueueDelivered,
-            serde_json::json!({
-                "queue_id": delivery.message.queue_id,
-                "sequence_no": delivery.message.sequence_no,
-                "kind": delivery.message.kind,
-                "truncated": delivery.message.truncated,
-                "bytes_kept": delivery.message.bytes_kept,
-                "bytes_loaded": delivery.message.bytes_loaded,
-                "delivery_boundary": delivery.delivery_boundary,
-            }),
+            QueueDeliveredPayload {
+                queue_id: delivery.message.queue_id.clone(),
+                sequence_no: delivery.message.sequence_no,
+                kind: delivery.message.kind,
+                truncated: delivery.message.truncated,
+                bytes_kept: delivery.message.bytes_kept,
+                bytes_loaded: delivery.message.bytes_loaded,
+                delivery_boundary: delivery.delivery_boundary,
+                coalesced_count: (!delivery.coalesced_queue_ids.is_empty())
+                    .then(|| delivery.coalesced_queue_ids.len() + 1),
+                coalesced_queue_ids: delivery.coalesced_queue_ids.clone(),
+            },
         );
-        messages.push(Message {
-            role: Role::User,
-            content: Some(delivery.message.content.clone()),
-            tool_call_id: None,
-            tool_name: None,
-            tool_calls: None,
-        });
+        if delivery.message.kind == QueueMessageKind::Cancel {
+            self.operator_cancel = Some(delivery.message.clone());
+        } else {
+            messages.push(Message {
+                role: Role::User,
+                content: Some(delivery.message.content.clone()),
+                tool_call_id: None,
+                tool_name: None,
+                tool_calls: None,
+            });
+        }
         if delivery.cancelled_remaining_work {
             let transition = crate::agent::operator_boundary_transition_decision();
             self.emit_event(
                 run_id,
                 step,
-                EventKind::QueueInterrupt,
-                serde_json::json!({
-                    "queue_id": delivery.message.queue_id,
-                    "sequence_no": delivery.message.sequence_no,
-                    "kind": delivery.message.kind,
-                    "delivery_boundary": delivery.delivery_boundary,
-                    "cancelled_remaining_work": true,
-                    "cancelled_reason": delivery.cancelled_reason.unwrap_or("operator_steer"),
-                }),
+                QueueInterruptPayload {
+                    queue_id: delivery.message.queue_id.clone(),
+                    sequence_no: delivery.message.sequence_no,
+                    kind: delivery.message.kind,
+                    delivery_boundary: delivery.delivery_boundary,
+                    cancelled_remaining_work: true,
+                    cancelled_reason: delivery
+                        .cancelled_reason
+                        .unwrap_or("operator_steer")
+                        .to_string(),
+                },
             );
             self.emit_event(
                 run_id,
                 step,
-                EventKind::InterruptRaised,
-                serde_json::json!({
-                    "kind": crate::agent::interrupts::interrupt_kind_name(&transition.interrupt_kind),
-                    "reason": delivery.cancelled_reason.unwrap_or("operator_steer"),
-                }),
+                InterruptRaisedPayload {
+                    kind: crate::agent::interrupts::interrupt_kind_name(&transition.interrupt_kind)
+                        .to_string(),
+                    reason: Some(
+                        delivery
+                            .cancelled_reason
+                            .unwrap_or("operator_steer")
+                            .to_string(),
+                    ),
+                    approval_id: None,
+                    tool_call_id: None,
+                },
             );
             self.emit_event(
                 run_id,
                 step,
-                EventKind::PhaseExited,
-                serde_json::json!({
-                    "phase": crate::agent::interrupts::run_phase_name(&transition.from_phase),
-                    "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                }),
+                PhaseExitedPayload {
+                    phase: crate::agent::interrupts::run_phase_name(&transition.from_phase)
+                        .to_string(),
+                    next_phase: crate::agent::interrupts::run_phase_name(&transition.to_phase)
+                        .to_string(),
+                },
             );
             self.emit_event(
                 run_id,
                 step,
-                EventKind::PhaseEntered,
-                serde_json::json!({
-                    "phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                }),
+                PhaseEnteredPayload {
+                    phase: crate::agent::interrupts::run_phase_name(&transition.to_phase)
+                        .to_string(),
+                },
             );
             self.emit_event(
                 run_id,
                 step,
-                EventKind::CompletionBlocked,
-                serde_json::json!({
-                    "reason": transition.completion_reason,
-                    "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                }),
+                CompletionBlockedPayload {
+                    reason: transition.completion_reason.to_string(),
+                    next_phase: crate::agent::interrupts::run_phase_name(&transition.to_phase)
+                        .to_string(),
+                },
             );
             return (true, true);
         }
@@ -167,27 +238,21 @@ impl<P: ModelProvider> Agent<P> {
             }
         }
         for req in drained {
-            let submitted = self
-                .operator_queue
-                .submit(req.kind, &req.content, &self.operator_queue_limits)
-                .queued;
-            self.emit_event(
-                run_id,
-                step,
-                EventKind::QueueSubmitted,
-                serde_json::json!({
-                    "queue_id": submitted.queue_id,
-                    "sequence_no": submitted.sequence_no,
-                    "kind": submitted.kind,
-                    "truncated": submitted.truncated,
-                    "bytes_kept": submitted.bytes_kept,
-                    "bytes_loaded": submitted.bytes_loaded,
-                    "next_delivery": match submitted.kind {
-                        QueueMessageKind::Steer => DeliveryBoundary::PostTool.user_phrase(),
-                        QueueMessageKind::FollowUp => DeliveryBoundary::TurnIdle.user_phrase(),
-                    }
-                }),
-            );
+            self.submit_operator_request(run_id, step, req);
         }
     }
+
+    pub(crate) fn submit_operator_request(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        req: QueueSubmitRequest,
+    ) {
+        let result =
+            self.operator_queue
+                .submit(req.kind, &req.content, &self.operator_queue_limits);
+        self.operator_queue
+            .record_submitted(&result.queued, step, &crate::trust::now_rfc3339());
+        self.emit_queue_submit_result(run_id, step, &result);
+    }
 }
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::agent_tool_exec::{run_tool_once, ToolRunOutcome};
+use crate::gate::GateDecision;
+use crate::hooks::config::HookStage;
+use crate::mcp::registry::McpRegistry;
+use crate::providers::ModelProvider;
+use crate::taint::TaintToggle;
+use crate::tools::tool_side_effects;
+use crate::types::{SideEffects, ToolCall};
+
+use super::Agent;
+
+/// Read-only calls of one step that were gated up front and executed
+/// together. The tool loop then handles each call in its original order,
+/// taking the gate decision and run result from here instead of deciding
+/// and executing the call again, so results land in the transcript in call
+/// order. Every call of a batch has finished before the batch's first
+/// `tool_exec_start`; start/end events then follow in call order and carry
+/// the batch's `batch_id`.
+#[derive(Debug, Default)]
+pub struct ParallelToolBatch {
+    batch_id: String,
+    tool_call_ids: Vec<String>,
+    arguments: BTreeMap<String, Value>,
+    decisions: BTreeMap<String, GateDecision>,
+    runs: BTreeMap<String, Result<ToolRunOutcome, ()>>,
+}
+
+/// Built-in filesystem reads, and MCP tools their server marks read-only.
+fn is_read_only(tc: &ToolCall, mcp_registry: Option<&McpRegistry>) -> bool {
+    if tc.name.starts_with("mcp.") {
+        return mcp_registry.is_some_and(|reg| reg.tool_is_read_only(&tc.name));
+    }
+    matches!(tool_side_effects(&tc.name), SideEffects::FilesystemRead)
+}
+
+impl<P: ModelProvider> Agent<P> {
+    /// A step may carry several calls when parallel read-only tools are on
+    /// and every call only reads. Taint tracking rules batches out because
+    /// each result can change the gate's decision for the next call. MCP
+    /// tools count as reads only when their server lists them with
+    /// `readOnlyHint`.
+    pub(super) fn parallel_readonly_batch_eligible(&self, tool_calls: &[ToolCall]) -> bool {
+        let mcp_registry = self.mcp_registry.as_deref();
+        self.parallel_readonly_tools
+            && tool_calls.len() > 1
+            && matches!(self.taint_toggle, TaintToggle::Off)
+            && tool_calls.iter().all(|tc| is_read_only(tc, mcp_registry))
+    }
+
+    /// Whether an eligible step's calls may run together before the tool
+    /// loop reaches them. The loop's per-call stages must leave every call
+    /// unchanged and let it through: no pre_tool hook may rewrite or abort
+    /// it, its arguments must be valid, the failed-repeat guard and the plan
+    /// must allow it, and no MCP drift may be pending. Otherwise the calls
+    /// run one at a time in the loop.
+    pub(super) async fn parallel_readonly_batch_runnable(
+        &mut self,
+        tool_calls: &[ToolCall],
+        active_plan_step_idx: usize,
+        failed_repeat_counts: &BTreeMap<String, u32>,
+        expected_mcp_catalog_hash_hex: Option<&String>,
+        expected_mcp_docs_hash_hex: Option<&String>,
+    ) -> bool {
+        for tc in tool_calls {
+            if self
+                .mcp_drift_pending(
+                    tc,
+                    expected_mcp_catalog_hash_hex,
+                    expected_mcp_docs_hash_hex,
+                )
+                .await
+            {
+                return false;
+            }
+        }
+        tool_calls.iter().all(|tc| {
+            if self.hooks.enabled() && self.hooks.has_hooks_for(HookStage::PreTool, &tc.name) {
+                return false;
+            }
+            if self.tool_call_args_error(tc).is_some() {
+                return false;
+            }
+            let planning_ctx = self.build_tool_call_planning_context(
+                active_plan_step_idx,
+                tc,
+                failed_repeat_counts,
+            );
+            planning_ctx.failed_repeat_count < super::MAX_FAILED_REPEAT_PER_KEY
+                && planning_ctx.failed_repeat_name_count < super::MAX_FAILED_REPEAT_PER_TOOL_NAME
+                && planning_ctx.plan_tool_allowed
+                && planning_ctx.plan_arg_violation.is_none()
+        })
+    }
+
+    /// Gates every call of the step, then runs them concurrently when all of
+    /// them were allowed. Decisions are kept either way so no call is
+    /// decided twice.
+    pub(super) async fn prepare_parallel_readonly_batch(
+        &mut self,
+        step: u32,
+        tool_calls: &[ToolCall],
+    ) -> ParallelToolBatch {
+        let mut batch = ParallelToolBatch {
+            batch_id: format!("step-{step}"),
+            tool_call_ids: tool_calls.iter().map(|tc| tc.id.clone()).collect(),
+            arguments: tool_calls
+                .iter()
+                .map(|tc| (tc.id.clone(), tc.arguments.clone()))
+                .collect(),
+            ..ParallelToolBatch::default()
+        };
+        for tc in tool_calls {
+            let decision = self.gate.decide(&self.gate_ctx, tc);
+            batch.decisions.insert(tc.id.clone(), decision);
+        }
+        if batch
+            .decisions
+            .values()
+            .all(|d| matches!(d, GateDecision::Allow { .. }))
+        {
+            let tool_rt = &self.tool_rt;
+            let mcp_registry = self.mcp_registry.as_ref();
+            let runs = tool_calls.iter().map(|tc| {
+                let dur = self.tool_run_timeout(tc);
+                async move {
+                    tokio::time::timeout(dur, run_tool_once(tool_rt, tc, mcp_registry, None))
+                        .await
+                        .map_err(|_| ())
+                }
+            });
+            for (tc, run) in tool_calls
+                .iter()
+                .zip(futures_util::future::join_all(runs).await)
+            {
+                batch.runs.insert(tc.id.clone(), run);
+            }
+        }
+        batch
+    }
+
+    pub(super) fn decide_tool_call(&mut self, tc: &ToolCall) -> GateDecision {
+        let batched = self
+            .batched_call(tc)
+            .and_then(|b| b.decisions.remove(&tc.id));
+        batched.unwrap_or_else(|| self.gate.decide(&self.gate_ctx, tc))
+    }
+
+    /// The batch's result for `tc`; taken once, so retries execute again.
+    pub(super) fn take_batched_tool_run(
+        &mut self,
+        tc: &ToolCall,
+    ) -> Option<Result<ToolRunOutcome, ()>> {
+        self.batched_call(tc).and_then(|b| b.runs.remove(&tc.id))
+    }
+
+    /// The batch holding `tc`, as long as the call still has the arguments
+    /// it was batched with.
+    fn batched_call(&mut self, tc: &ToolCall) -> Option<&mut ParallelToolBatch> {
+        self.parallel_tool_batch
+            .as_mut()
+            .filter(|b| b.arguments.get(&tc.id) == Some(&tc.arguments))
+    }
+
+    pub(super) fn tool_batch_id(&self, tc: &ToolCall) -> Option<String> {
+        self.parallel_tool_batch
+            .as_ref()
+            .filter(|b| b.tool_call_ids.contains(&tc.id))
+            .map(|b| b.batch_id.clone())
+    }
+}
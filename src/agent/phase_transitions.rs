@@ -92,6 +92,19 @@ pub(crate) fn apply_runtime_completion_action_to_checkpoint(
                 .blocked_post_validation_final_answer_count = 0;
             Ok(PhaseLoopControl::ContinueAgentStep)
         }
+        RuntimeCompletionAction::ContinueOutputSchemaRepair {
+            blocked_runtime_completion_count: next_count,
+            operator_delivery_count: next_op_count,
+        } => {
+            runtime_checkpoint
+                .retry_state
+                .blocked_runtime_completion_count = next_count;
+            runtime_checkpoint
+                .tool_protocol_state
+                .operator_delivery_count = next_op_count;
+            runtime_checkpoint.retry_state.output_schema_repair_count += 1;
+            Ok(PhaseLoopControl::ContinueAgentStep)
+        }
         RuntimeCompletionAction::ProceedToTools {
             blocked_runtime_completion_count: next_count,
         } => {
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use super::{PlanStepConstraint, WorkerStepStatus};
 
@@ -15,6 +15,8 @@ pub(super) enum PlannerResponseDecision {
         completed_step_id: String,
         next_step_id: Option<String>,
         next_active_plan_step_idx: usize,
+        /// Fan-out siblings still to run after the next step, in order.
+        queued_step_ids: Vec<String>,
         user_output: Option<String>,
     },
     InvalidDoneTransition {
@@ -25,6 +27,14 @@ pub(super) enum PlannerResponseDecision {
         step_id: String,
         next_step_id: String,
     },
+    InvalidFanOut {
+        step_id: String,
+        reason: String,
+    },
+    FinalWithPendingFanOut {
+        step_id: String,
+        pending_step_ids: Vec<String>,
+    },
     StepRetry {
         step_id: String,
         retry_count: u32,
@@ -38,6 +48,14 @@ pub(super) enum PlannerResponseDecision {
         step_id: String,
         expected_step_id: String,
     },
+    RetryOfDoneStep {
+        step_id: String,
+    },
+    RetryCounterMismatch {
+        step_id: String,
+        retry_of: Option<u32>,
+        expected: u32,
+    },
     ReplanRequested {
         step_id: String,
         status: String,
@@ -57,6 +75,8 @@ pub(super) struct PlannerResponseContext<'a> {
     pub(super) active_plan_step_idx: usize,
     pub(super) plan_step_constraints: &'a [PlanStepConstraint],
     pub(super) step_retry_counts: &'a BTreeMap<String, u32>,
+    pub(super) queued_step_ids: &'a [String],
+    pub(super) completed_step_ids: &'a BTreeSet<String>,
 }
 
 pub(super) fn evaluate_planner_response(
@@ -99,14 +119,45 @@ pub(super) fn evaluate_planner_response(
                     expected_step_id: current_step_id,
                 };
             }
+            let step_idx = |id: &str| {
+                context
+                    .plan_step_constraints
+                    .iter()
+                    .position(|constraint| constraint.step_id == id)
+            };
+            let mut queued_step_ids = context.queued_step_ids.to_vec();
+            if !step_status.next_step_ids.is_empty() {
+                if step_status.next_step_id.is_some() {
+                    return PlannerResponseDecision::InvalidFanOut {
+                        step_id: step_status.step_id.clone(),
+                        reason: "next_step_id and next_step_ids are mutually exclusive".to_string(),
+                    };
+                }
+                if let Some(unknown) = step_status
+                    .next_step_ids
+                    .iter()
+                    .find(|id| step_idx(id).is_none())
+                {
+                    return PlannerResponseDecision::InvalidFanOut {
+                        step_id: step_status.step_id.clone(),
+                        reason: format!("unknown step {unknown}"),
+                    };
+                }
+                // New siblings run before ones queued by an earlier fan-out.
+                let mut fan_out = step_status.next_step_ids.clone();
+                fan_out.append(&mut queued_step_ids);
+                queued_step_ids = fan_out;
+            }
             let next_active_plan_step_idx = match step_status.next_step_id.as_deref() {
+                Some("final") if !queued_step_ids.is_empty() => {
+                    return PlannerResponseDecision::FinalWithPendingFanOut {
+                        step_id: step_status.step_id.clone(),
+                        pending_step_ids: queued_step_ids,
+                    };
+                }
                 Some("final") => context.plan_step_constraints.len(),
                 Some(next_step_id) => {
-                    let Some(next_idx) = context
-                        .plan_step_constraints
-                        .iter()
-                        .position(|constraint| constraint.step_id == next_step_id)
-                    else {
+                    let Some(next_idx) = step_idx(next_step_id) else {
                         return PlannerResponseDecision::InvalidNextStepId {
                             step_id: step_status.step_id.clone(),
                             next_step_id: next_step_id.to_string(),
@@ -114,8 +165,19 @@ pub(super) fn evaluate_planner_response(
                     };
                     next_idx
                 }
+                None if !queued_step_ids.is_empty() => {
+                    let next_step_id = queued_step_ids.remove(0);
+                    step_idx(&next_step_id).unwrap_or(context.plan_step_constraints.len())
+                }
+                // Linear order, skipping steps a fan-out already completed.
                 None if context.active_plan_step_idx < context.plan_step_constraints.len() => {
-                    context.active_plan_step_idx.saturating_add(1)
+                    (context.active_plan_step_idx + 1..context.plan_step_constraints.len())
+                        .find(|idx| {
+                            !context
+                                .completed_step_ids
+                                .contains(&context.plan_step_constraints[*idx].step_id)
+                        })
+                        .unwrap_or(context.plan_step_constraints.len())
                 }
                 None => context.active_plan_step_idx,
             };
@@ -124,10 +186,16 @@ pub(super) fn evaluate_planner_response(
                 completed_step_id: step_status.step_id.clone(),
                 next_step_id: step_status.next_step_id.clone(),
                 next_active_plan_step_idx,
+                queued_step_ids,
                 user_output,
             }
         }
         "retry" => {
+            if context.completed_step_ids.contains(&step_status.step_id) {
+                return PlannerResponseDecision::RetryOfDoneStep {
+                    step_id: step_status.step_id.clone(),
+                };
+            }
             if step_status.step_id != current_step_id {
                 return PlannerResponseDecision::InvalidRetryTransition {
                     step_id: step_status.step_id.clone(),
@@ -140,12 +208,19 @@ pub(super) fn evaluate_planner_response(
                 .copied()
                 .unwrap_or(0)
                 .saturating_add(1);
-            if retry_count > 2 {
+            if retry_count > crate::planner::MAX_STEP_RETRIES {
                 return PlannerResponseDecision::RetryLimitExceeded {
                     step_id: step_status.step_id.clone(),
                     retry_count,
                 };
             }
+            if step_status.v2 && step_status.retry_of != Some(retry_count) {
+                return PlannerResponseDecision::RetryCounterMismatch {
+                    step_id: step_status.step_id.clone(),
+                    retry_of: step_status.retry_of,
+                    expected: retry_count,
+                };
+            }
             PlannerResponseDecision::StepRetry {
                 step_id: step_status.step_id.clone(),
                 retry_count,
@@ -168,17 +243,19 @@ pub(super) fn evaluate_planner_response(
 mod tests {
     use super::{evaluate_planner_response, PlannerResponseContext, PlannerResponseDecision};
     use crate::agent::{PlanStepConstraint, WorkerStepStatus};
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
 
     fn constraints() -> Vec<PlanStepConstraint> {
         vec![
             PlanStepConstraint {
                 step_id: "S1".to_string(),
                 intended_tools: vec!["read_file".to_string()],
+                arg_constraints: Vec::new(),
             },
             PlanStepConstraint {
                 step_id: "S2".to_string(),
                 intended_tools: vec!["shell".to_string()],
+                arg_constraints: Vec::new(),
             },
         ]
     }
@@ -194,6 +271,8 @@ mod tests {
             active_plan_step_idx: 0,
             plan_step_constraints: &constraints(),
             step_retry_counts: &BTreeMap::new(),
+            queued_step_ids: &[],
+            completed_step_ids: &BTreeSet::new(),
         });
         assert!(matches!(
             decision,
@@ -211,12 +290,17 @@ mod tests {
                 step_id: "S1".to_string(),
                 status: "done".to_string(),
                 next_step_id: Some("S2".to_string()),
+                next_step_ids: Vec::new(),
+                retry_of: None,
+                v2: false,
                 user_output: Some("  ready  ".to_string()),
             }),
             blocked_control_envelope_count: 1,
             active_plan_step_idx: 0,
             plan_step_constraints: &constraints(),
             step_retry_counts: &BTreeMap::new(),
+            queued_step_ids: &[],
+            completed_step_ids: &BTreeSet::new(),
         });
         assert!(matches!(
             decision,
@@ -239,16 +323,126 @@ mod tests {
                 step_id: "S1".to_string(),
                 status: "retry".to_string(),
                 next_step_id: None,
+                next_step_ids: Vec::new(),
+                retry_of: None,
+                v2: false,
                 user_output: None,
             }),
             blocked_control_envelope_count: 0,
             active_plan_step_idx: 0,
             plan_step_constraints: &constraints(),
             step_retry_counts: &retry_counts,
+            queued_step_ids: &[],
+            completed_step_ids: &BTreeSet::new(),
         });
         assert!(matches!(
             decision,
             PlannerResponseDecision::RetryLimitExceeded { retry_count: 3, .. }
         ));
     }
+
+    fn v2_status(step_id: &str, status: &str) -> WorkerStepStatus {
+        WorkerStepStatus {
+            step_id: step_id.to_string(),
+            status: status.to_string(),
+            next_step_id: None,
+            next_step_ids: Vec::new(),
+            retry_of: None,
+            v2: true,
+            user_output: None,
+        }
+    }
+
+    #[test]
+    fn planner_response_fan_out_runs_siblings_in_declared_order() {
+        let mut plan = constraints();
+        plan.push(PlanStepConstraint {
+            step_id: "S3".to_string(),
+            intended_tools: Vec::new(),
+            arg_constraints: Vec::new(),
+        });
+        let mut status = v2_status("S1", "done");
+        status.next_step_ids = vec!["S3".to_string(), "S2".to_string()];
+        let decision = evaluate_planner_response(PlannerResponseContext {
+            plan_enforcement_active: true,
+            has_actionable_tool_calls: false,
+            model_signaled_finalize: true,
+            worker_step_status: Some(&status),
+            blocked_control_envelope_count: 0,
+            active_plan_step_idx: 0,
+            plan_step_constraints: &plan,
+            step_retry_counts: &BTreeMap::new(),
+            queued_step_ids: &[],
+            completed_step_ids: &BTreeSet::new(),
+        });
+        let PlannerResponseDecision::StepDone {
+            next_active_plan_step_idx,
+            queued_step_ids,
+            ..
+        } = decision
+        else {
+            panic!("expected StepDone, got {decision:?}");
+        };
+        assert_eq!(next_active_plan_step_idx, 2);
+        assert_eq!(queued_step_ids, vec!["S2".to_string()]);
+
+        let mut status = v2_status("S3", "done");
+        status.next_step_id = Some("final".to_string());
+        let decision = evaluate_planner_response(PlannerResponseContext {
+            plan_enforcement_active: true,
+            has_actionable_tool_calls: false,
+            model_signaled_finalize: true,
+            worker_step_status: Some(&status),
+            blocked_control_envelope_count: 0,
+            active_plan_step_idx: 2,
+            plan_step_constraints: &plan,
+            step_retry_counts: &BTreeMap::new(),
+            queued_step_ids: &queued_step_ids,
+            completed_step_ids: &BTreeSet::from(["S1".to_string()]),
+        });
+        assert_eq!(
+            decision,
+            PlannerResponseDecision::FinalWithPendingFanOut {
+                step_id: "S3".to_string(),
+                pending_step_ids: vec!["S2".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn planner_response_rejects_retry_of_done_step_and_wrong_counter() {
+        let plan = constraints();
+        let retry_counts = BTreeMap::new();
+        let evaluate = |retry_of: u32, completed: &[&str]| {
+            let mut status = v2_status("S1", "retry");
+            status.retry_of = Some(retry_of);
+            let completed_step_ids = completed.iter().map(|id| id.to_string()).collect();
+            evaluate_planner_response(PlannerResponseContext {
+                plan_enforcement_active: true,
+                has_actionable_tool_calls: false,
+                model_signaled_finalize: true,
+                worker_step_status: Some(&status),
+                blocked_control_envelope_count: 0,
+                active_plan_step_idx: 0,
+                plan_step_constraints: &plan,
+                step_retry_counts: &retry_counts,
+                queued_step_ids: &[],
+                completed_step_ids: &completed_step_ids,
+            })
+        };
+        assert_eq!(
+            evaluate(1, &["S1"]),
+            PlannerResponseDecision::RetryOfDoneStep {
+                step_id: "S1".to_string()
+            }
+        );
+        assert_eq!(
+            evaluate(2, &[]),
+            PlannerResponseDecision::RetryCounterMismatch {
+                step_id: "S1".to_string(),
+                retry_of: Some(2),
+                expected: 1,
+            }
+        );
+    }
 }
@@ -0,0 +1,66 @@
+use crate::agent_utils::provider_name;
+use crate::hooks::config::HookStage;
+use crate::hooks::protocol::{PostRunBudgetPayload, PostRunPayload, PostRunTaintPayload};
+use crate::hooks::runner::make_post_run_input;
+use crate::providers::ModelProvider;
+
+use super::agent_types::AgentOutcome;
+use super::Agent;
+
+impl<P: ModelProvider> Agent<P> {
+    /// Runs post_run hooks after the run has ended, whatever its exit reason.
+    /// They only add their reports and, under `--hooks-strict`, the first
+    /// failure to the outcome; `run_end` has already been emitted, so they
+    /// emit no events.
+    pub(super) async fn run_post_run_hooks(&mut self, mut outcome: AgentOutcome) -> AgentOutcome {
+        if !self.hooks.enabled()
+            || !self
+                .hooks
+                .list()
+                .iter()
+                .any(|h| h.has_stage(HookStage::PostRun))
+        {
+            return outcome;
+        }
+        let (final_output, final_output_truncated) = crate::target::truncate_utf8_to_bytes(
+            &outcome.final_output,
+            self.hooks.max_stdout_bytes,
+        );
+        let payload = PostRunPayload {
+            run_id: outcome.run_id.clone(),
+            exit_reason: outcome.exit_reason.as_str().to_string(),
+            error: outcome.error.clone(),
+            final_output,
+            final_output_truncated,
+            tool_call_count: outcome.tool_calls.len(),
+            budget: PostRunBudgetPayload {
+                steps: outcome.steps.len(),
+                max_steps: self.max_steps,
+                tool_calls: outcome.tool_calls.len(),
+                max_total_tool_calls: self.tool_call_budget.max_total_tool_calls,
+                wall_time_ms: self
+                    .run_started
+                    .map(|started| started.elapsed().as_millis() as u64)
+                    .unwrap_or(0),
+                max_wall_time_ms: self.tool_call_budget.max_wall_time_ms,
+                token_usage: outcome.token_usage.clone(),
+            },
+            taint: outcome.taint.as_ref().map(|t| PostRunTaintPayload {
+                overall: t.overall.clone(),
+                source_count: t.spans_by_tool_call_id.len(),
+            }),
+        };
+        let hook_input = make_post_run_input(
+            &outcome.run_id,
+            outcome.steps.last().map(|s| s.step).unwrap_or(0),
+            provider_name(self.gate_ctx.provider),
+            &self.model,
+            &self.gate_ctx.workdir,
+            serde_json::to_value(payload).unwrap_or_default(),
+        );
+        let result = self.hooks.run_post_run_hooks(hook_input).await;
+        outcome.hook_invocations.extend(result.invocations);
+        outcome.post_run_hook_error = result.error;
+        outcome
+    }
+}
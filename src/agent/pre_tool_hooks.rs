@@ -0,0 +1,288 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::agent_utils::provider_name;
+use crate::events::{HookEndPayload, HookErrorPayload, HookRewroteArgsPayload, HookStartPayload};
+use crate::hooks::config::HookStage;
+use crate::hooks::protocol::{HookInvocationReport, PreToolPayload};
+use crate::hooks::runner::{args_digest, make_pre_tool_input};
+use crate::mcp::registry::McpRegistry;
+use crate::providers::ModelProvider;
+use crate::taint::TaintState;
+use crate::tools::ToolArgsStrict;
+use crate::types::{Message, TokenUsage, ToolCall};
+
+use super::agent_types::{AgentOutcome, ToolDecisionRecord};
+use super::Agent;
+
+/// Argument names holding a path the tool acts on. A rewrite that changes
+/// one of them must keep it inside the workdir.
+const PATH_ARG_KEYS: &[&str] = &["path", "cwd", "dir", "file", "workdir"];
+
+/// Run state a pre_tool abort needs to finalize the run.
+pub(super) struct PreToolHookRunState<'a> {
+    pub(super) started_at: &'a str,
+    pub(super) messages: &'a [Message],
+    pub(super) observed_tool_calls: &'a [ToolCall],
+    pub(super) observed_tool_decisions: &'a [ToolDecisionRecord],
+    pub(super) request_context_chars: usize,
+    pub(super) last_compaction_report: &'a Option<crate::compaction::CompactionReport>,
+    pub(super) provider_retry_count: u32,
+    pub(super) provider_error_count: u32,
+    pub(super) saw_token_usage: bool,
+    pub(super) total_token_usage: &'a TokenUsage,
+    pub(super) taint_state: &'a TaintState,
+}
+
+pub(super) enum PreToolHookDecision {
+    /// Carries the rewritten call when a hook changed its arguments.
+    Continue(Option<ToolCall>),
+    Finalize(Box<AgentOutcome>),
+}
+
+/// Whether `rewrite` may replace `tc.arguments`. A rewrite may narrow or
+/// normalize a call but never broaden it: it must pass the tool's schema,
+/// may not add arguments the model did not send, and may not move a path
+/// argument outside the workdir. The tool itself cannot change, since only
+/// arguments are rewritten.
+fn check_hook_rewrite(
+    tc: &ToolCall,
+    rewrite: &serde_json::Value,
+    workdir: &Path,
+    strict: ToolArgsStrict,
+    mcp_registry: Option<&McpRegistry>,
+) -> Result<(), String> {
+    let (Some(original), Some(rewritten)) = (tc.arguments.as_object(), rewrite.as_object()) else {
+        return Err("arguments must be a JSON object".to_string());
+    };
+    if let Some(added) = rewritten.keys().find(|k| !original.contains_key(*k)) {
+        return Err(format!("may not add argument '{added}'"));
+    }
+    for key in PATH_ARG_KEYS {
+        let Some(value) = rewritten.get(*key) else {
+            continue;
+        };
+        if original.get(*key) == Some(value) {
+            continue;
+        }
+        let Some(path) = value.as_str() else {
+            return Err(format!("argument '{key}' must stay a string"));
+        };
+        if !stays_in_workdir(workdir, path) {
+            return Err(format!("argument '{key}' may not leave the workdir"));
+        }
+    }
+    let call = ToolCall {
+        arguments: rewrite.clone(),
+        ..tc.clone()
+    };
+    if call.name.starts_with("mcp.") {
+        match mcp_registry {
+            Some(reg) => reg.validate_namespaced_tool_args(&call, strict),
+            None => Ok(()),
+        }
+    } else {
+        let normalized = crate::tools::normalize_builtin_tool_args(&call.name, &call.arguments);
+        crate::tools::validate_builtin_tool_args(&call.name, &normalized, strict)
+    }
+    .map_err(|e| format!("fails the tool schema: {e}"))
+}
+
+/// Lexical check, so paths that do not exist yet are judged the same way.
+fn stays_in_workdir(workdir: &Path, path: &str) -> bool {
+    let Some(root) = normalize_lexically(workdir) else {
+        return false;
+    };
+    normalize_lexically(&crate::target::resolve_path(workdir, path))
+        .is_some_and(|resolved| resolved.starts_with(root))
+}
+
+fn normalize_lexically(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return None;
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    Some(out)
+}
+
+impl<P: ModelProvider> Agent<P> {
+    /// Runs pre_tool hooks for one call before it is planned, gated, and
+    /// executed. An accepted rewrite is recorded on the gate context snapshot
+    /// so every decision record for the call carries it.
+    pub(super) async fn run_pre_tool_hooks_for_call(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+        hook_invocations: &mut Vec<HookInvocationReport>,
+        run: &PreToolHookRunState<'_>,
+    ) -> PreToolHookDecision {
+        if !self.hooks.enabled() || !self.hooks.has_hooks_for(HookStage::PreTool, &tc.name) {
+            return PreToolHookDecision::Continue(None);
+        }
+        let payload = PreToolPayload {
+            tool_call_id: tc.id.clone(),
+            tool_name: tc.name.clone(),
+            arguments: tc.arguments.clone(),
+        };
+        let hook_input = make_pre_tool_input(
+            run_id,
+            step,
+            provider_name(self.gate_ctx.provider),
+            &self.model,
+            &self.gate_ctx.workdir,
+            serde_json::to_value(payload).unwrap_or_default(),
+        );
+        let workdir = self.gate_ctx.workdir.clone();
+        let strict = self.tool_rt.tool_args_strict;
+        let registry = self.mcp_registry.clone();
+        let result = self
+            .hooks
+            .run_pre_tool_hooks(hook_input, &tc.name, &tc.arguments, |rewrite| {
+                check_hook_rewrite(tc, rewrite, &workdir, strict, registry.as_deref())
+            })
+            .await;
+        let (error, abort_reason) = match result {
+            Ok(out) => {
+                self.emit_hook_budget_exhausted(run_id, step, &out.budget_exhausted);
+                for inv in out
+                    .invocations
+                    .iter()
+                    .filter(|inv| !inv.skipped_due_to_budget)
+                {
+                    self.emit_event(
+                        run_id,
+                        step,
+                        HookStartPayload {
+                            hook_name: inv.hook_name.clone(),
+                            stage: inv.stage.clone(),
+                        },
+                    );
+                    self.emit_event(
+                        run_id,
+                        step,
+                        HookEndPayload {
+                            hook_name: inv.hook_name.clone(),
+                            stage: inv.stage.clone(),
+                            action: inv.action.clone(),
+                            modified: inv.modified,
+                            duration_ms: inv.duration_ms as u64,
+                            input_digest: Some(inv.input_digest.clone()),
+                            output_digest: Some(inv.output_digest.clone()),
+                        },
+                    );
+                }
+                hook_invocations.extend(out.invocations);
+                match out.abort_reason {
+                    Some(reason) => (reason.clone(), reason),
+                    None => {
+                        let Some(rewrite) = out.rewrite else {
+                            return PreToolHookDecision::Continue(None);
+                        };
+                        self.emit_event(
+                            run_id,
+                            step,
+                            HookRewroteArgsPayload {
+                                tool_call_id: tc.id.clone(),
+                                tool: tc.name.clone(),
+                                hooks: rewrite.hooks.clone(),
+                                original_args_sha256: rewrite.original_args_sha256.clone(),
+                                rewritten_args_sha256: args_digest(&rewrite.arguments),
+                                arguments: rewrite.arguments.clone(),
+                            },
+                        );
+                        if let Some(snapshot) = self.gate_context_snapshot.as_mut() {
+                            snapshot.hook_rewrite = Some(rewrite);
+                        }
+                        return PreToolHookDecision::Continue(Some(ToolCall {
+                            arguments: out.arguments,
+                            ..tc.clone()
+                        }));
+                    }
+                }
+            }
+            Err(e) => {
+                self.emit_event(
+                    run_id,
+                    step,
+                    HookErrorPayload {
+                        stage: "pre_tool".to_string(),
+                        error: e.message.clone(),
+                    },
+                );
+                (e.message, String::new())
+            }
+        };
+        PreToolHookDecision::Finalize(Box::new(self.finalize_hook_aborted_with_end(
+            step,
+            run_id.to_string(),
+            run.started_at.to_string(),
+            abort_reason,
+            error,
+            run.messages.to_vec(),
+            run.observed_tool_calls.to_vec(),
+            run.observed_tool_decisions.to_vec(),
+            run.request_context_chars,
+            run.last_compaction_report.clone(),
+            hook_invocations.clone(),
+            run.provider_retry_count,
+            run.provider_error_count,
+            run.saw_token_usage,
+            run.total_token_usage,
+            run.taint_state,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn read_call(path: &str) -> ToolCall {
+        ToolCall {
+            id: "tc1".to_string(),
+            name: "read_file".to_string(),
+            arguments: json!({ "path": path }),
+        }
+    }
+
+    #[test]
+    fn rewrite_may_normalize_a_path_inside_the_workdir() {
+        let workdir = Path::new("/work/repo");
+        let tc = read_call("./src/../src/main.rs");
+        let rewrite = json!({ "path": "src/main.rs" });
+        assert!(check_hook_rewrite(&tc, &rewrite, workdir, ToolArgsStrict::On, None).is_ok());
+        let absolute = json!({ "path": "/work/repo/src/main.rs" });
+        assert!(check_hook_rewrite(&tc, &absolute, workdir, ToolArgsStrict::On, None).is_ok());
+    }
+
+    #[test]
+    fn rewrite_may_not_broaden_the_call() {
+        let workdir = Path::new("/work/repo");
+        let tc = read_call("src/main.rs");
+        let outside = json!({ "path": "src/../../other/secret" });
+        let err = check_hook_rewrite(&tc, &outside, workdir, ToolArgsStrict::On, None)
+            .expect_err("escapes workdir");
+        assert!(err.contains("may not leave the workdir"), "{err}");
+        let added = json!({ "path": "src/main.rs", "max_line_chars": 10 });
+        let err = check_hook_rewrite(&tc, &added, workdir, ToolArgsStrict::On, None)
+            .expect_err("adds an argument");
+        assert!(
+            err.contains("may not add argument 'max_line_chars'"),
+            "{err}"
+        );
+        let invalid = json!({ "path": "" });
+        let err = check_hook_rewrite(&tc, &invalid, workdir, ToolArgsStrict::On, None)
+            .expect_err("fails schema");
+        assert!(err.starts_with("fails the tool schema"), "{err}");
+    }
+}
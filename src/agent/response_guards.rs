@@ -88,6 +88,7 @@ pub(crate) fn decide_required_validation_phase_response(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn decide_post_response_phase_guard(
     runtime_checkpoint: &mut RunCheckpointV1,
     assistant: &Message,
@@ -96,7 +97,44 @@ pub(crate) fn decide_post_response_phase_guard(
     tool_calls: &[ToolCall],
     post_validation_final_answer_only_message: String,
     tool_only_reminder_message: String,
+    max_empty_responses: u32,
+    empty_response_message: String,
 ) -> PostResponseGuardDecision {
+    // A blank reply without tool calls is never a final answer. This runs
+    // before the planner envelope is parsed, so plan-enforced runs get the
+    // same nudge instead of an envelope error.
+    if !has_actionable_tool_calls
+        && assistant
+            .content
+            .as_deref()
+            .unwrap_or_default()
+            .trim()
+            .is_empty()
+    {
+        runtime_checkpoint.retry_state.empty_response_count = runtime_checkpoint
+            .retry_state
+            .empty_response_count
+            .saturating_add(1);
+        let blocked_count = runtime_checkpoint.retry_state.empty_response_count;
+        if blocked_count >= max_empty_responses.max(1) {
+            return PostResponseGuardDecision::PlannerError {
+                reason: format!(
+                    "MODEL_EMPTY_RESPONSE: model returned {blocked_count} consecutive empty responses without a tool call"
+                ),
+                blocked_count,
+                step_block_reason: "empty_response",
+                failure_class: "E_MODEL_EMPTY_RESPONSE",
+                error_source: "empty_response_guard",
+            };
+        }
+        return PostResponseGuardDecision::ContinueAgentStep {
+            developer_message: empty_response_message,
+            blocked_count,
+            step_block_reason: "empty_response",
+        };
+    }
+    runtime_checkpoint.retry_state.empty_response_count = 0;
+
     if runtime_checkpoint.validation_state.repair_mode
         && has_actionable_tool_calls
         && tool_calls.iter().all(|tc| tc.name == "shell")
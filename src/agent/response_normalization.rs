@@ -1,12 +1,22 @@
 use std::collections::BTreeSet;
 
-use crate::agent_tool_exec::{contains_tool_wrapper_markers, extract_content_tool_calls};
+use crate::agent_tool_exec::{
+    contains_tool_wrapper_markers, extract_content_tool_calls, wrapped_tool_call_near_misses,
+    WrappedToolCallNearMiss,
+};
 use crate::types::GenerateResponse;
 
 pub(super) enum AssistantResponseNormalization {
     Ready,
     MalformedWrapper,
-    MultipleToolCalls { count: usize },
+    /// Complete wrapper blocks that failed to parse or named a tool outside
+    /// the allowed set; reported as diagnostics, not protocol violations.
+    NearMissWrapper {
+        near_misses: Vec<WrappedToolCallNearMiss>,
+    },
+    MultipleToolCalls {
+        count: usize,
+    },
 }
 
 pub(super) fn normalize_assistant_response(
@@ -22,6 +32,10 @@ pub(super) fn normalize_assistant_response(
             resp.tool_calls = normalized_calls;
             resp.assistant.content = None;
         } else if contains_tool_wrapper_markers(&assistant_content) {
+            let near_misses = wrapped_tool_call_near_misses(&assistant_content, allowed_tool_names);
+            if !near_misses.is_empty() {
+                return AssistantResponseNormalization::NearMissWrapper { near_misses };
+            }
             return AssistantResponseNormalization::MalformedWrapper;
         }
     }
@@ -52,6 +66,7 @@ mod tests {
             },
             tool_calls: Vec::new(),
             usage: None,
+            served_model: None,
         }
     }
 
@@ -80,6 +95,42 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn reports_near_miss_wrapped_tool_call_instead_of_malformed() {
+        let mut response = empty_response(
+            "[TOOL_CALL]{\"name\":\"unknown_tool\",\"arguments\":{}}[END_TOOL_CALL]",
+        );
+        let mut allowed = BTreeSet::new();
+        allowed.insert("shell".to_string());
+
+        let result = normalize_assistant_response(&mut response, 1, &allowed);
+
+        match result {
+            AssistantResponseNormalization::NearMissWrapper { near_misses } => {
+                assert_eq!(near_misses.len(), 1);
+                assert_eq!(near_misses[0].reason, "tool_not_allowed");
+                assert_eq!(near_misses[0].tool_name.as_deref(), Some("unknown_tool"));
+            }
+            _ => panic!("expected near-miss wrapper"),
+        }
+        assert!(response.tool_calls.is_empty());
+    }
+
+    #[test]
+    fn fenced_wrapped_tool_call_content_is_plain_text() {
+        let content =
+            "Usage:\n```\n[TOOL_CALL]{\"name\":\"shell\",\"arguments\":{}}[END_TOOL_CALL]\n```";
+        let mut response = empty_response(content);
+        let mut allowed = BTreeSet::new();
+        allowed.insert("shell".to_string());
+
+        let result = normalize_assistant_response(&mut response, 1, &allowed);
+
+        assert!(matches!(result, AssistantResponseNormalization::Ready));
+        assert!(response.tool_calls.is_empty());
+        assert_eq!(response.assistant.content.as_deref(), Some(content));
+    }
+
     #[test]
     fn normalizes_malformed_wrapped_named_arguments_single_tool_call() {
         let mut response = empty_response(
@@ -0,0 +1,77 @@
+use std::collections::BTreeSet;
+
+use crate::agent_utils::sha256_hex;
+use crate::events::RunResumedPayload;
+use crate::providers::ModelProvider;
+use crate::types::{Message, Role};
+
+use super::{Agent, AgentOutcome};
+
+#[allow(dead_code)]
+impl<P: ModelProvider> Agent<P> {
+    /// Continues `prior` with a new user message under the prior run id.
+    ///
+    /// The prior transcript, tool result envelopes included, is replayed
+    /// after a fresh system prompt and compacted with this agent's settings
+    /// on the first step. Tool calls left without a result (a deny or an
+    /// approval stop) get a failed one so the transcript stays well formed.
+    /// Nothing is inherited from the prior run's decisions: every new call
+    /// goes through the gate again, and only run-scoped auto-approval
+    /// carries over because the run id does.
+    pub async fn run_resumed(&mut self, prior: &AgentOutcome, user_prompt: &str) -> AgentOutcome {
+        let mut session_messages = prior
+            .messages
+            .iter()
+            .skip_while(|m| matches!(m.role, Role::System))
+            .cloned()
+            .collect::<Vec<_>>();
+        let closed_tool_calls = self.close_dangling_tool_calls(
+            &mut session_messages,
+            &format!(
+                "tool call not run: the prior run ended with {}",
+                prior.exit_reason.as_str()
+            ),
+        );
+        let resumed = RunResumedPayload {
+            prior_exit_reason: prior.exit_reason.as_str().to_string(),
+            prior_messages: prior.messages.len(),
+            prior_transcript_sha256: sha256_hex(
+                &serde_json::to_vec(&prior.messages).unwrap_or_default(),
+            ),
+            closed_tool_calls,
+        };
+        let run_id_override = self.run_id_override.replace(prior.run_id.clone());
+        let outcome = self
+            .run_from_messages(
+                user_prompt,
+                session_messages,
+                Vec::new(),
+                None,
+                Some(resumed),
+            )
+            .await;
+        self.run_id_override = run_id_override;
+        outcome
+    }
+
+    /// Appends a failed result for each assistant tool call that has none
+    /// and returns how many were closed.
+    fn close_dangling_tool_calls(&self, messages: &mut Vec<Message>, reason: &str) -> usize {
+        let answered = messages
+            .iter()
+            .filter(|m| matches!(m.role, Role::Tool))
+            .filter_map(|m| m.tool_call_id.clone())
+            .collect::<BTreeSet<_>>();
+        let dangling = messages
+            .iter()
+            .filter(|m| matches!(m.role, Role::Assistant))
+            .flat_map(|m| m.tool_calls.iter().flatten())
+            .filter(|tc| !answered.contains(&tc.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        for tc in &dangling {
+            messages.push(self.runtime_tool_failure_message(tc, reason.to_string()));
+        }
+        dangling.len()
+    }
+}
@@ -1,5 +1,8 @@
-use crate::compaction::{maybe_compact, CompactionOutcome};
-use crate::events::EventKind;
+use crate::compaction::{
+    context_size_chars, maybe_compact_in_place_with_origins, CompactionMode, CompactionReport,
+    CompactionSettings, ToolResultPersist,
+};
+use crate::events::{ErrorPayload, ProviderErrorPayload, RunEndPayload};
 use crate::providers::http::{message_short, ProviderError};
 use crate::providers::ModelProvider;
 use crate::taint::TaintState;
@@ -58,77 +61,125 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::Error,
-            serde_json::json!({
-                "error": reason,
-                "source": "runtime_budget",
-                "elapsed_ms": elapsed_ms,
-                "max_wall_time_ms": self.tool_call_budget.max_wall_time_ms
-            }),
+            ErrorPayload {
+                error: reason.clone(),
+                source: Some("runtime_budget".to_string()),
+                elapsed_ms: Some(elapsed_ms),
+                max_wall_time_ms: Some(self.tool_call_budget.max_wall_time_ms),
+                ..ErrorPayload::default()
+            },
         );
         self.emit_event(
             run_id,
             step,
-            EventKind::RunEnd,
-            serde_json::json!({"exit_reason":"budget_exceeded"}),
+            RunEndPayload {
+                exit_reason: "budget_exceeded".to_string(),
+            },
         );
         Some(reason)
     }
 
+    /// Compacts with the run's tool call origins and remembers which results
+    /// were digested, so later calls can be matched as refetches.
+    pub(super) fn compact_tracking_digests(
+        &mut self,
+        messages: &mut Vec<Message>,
+    ) -> anyhow::Result<Option<CompactionReport>> {
+        let report = maybe_compact_in_place_with_origins(
+            messages,
+            &self.compaction_settings,
+            self.digest_refetch_tracker.origins(),
+            self.token_counter.as_ref(),
+            self.tool_rt.run_artifacts.as_deref(),
+        )?;
+        if let Some(report) = &report {
+            self.digest_refetch_tracker
+                .record_digests(&report.digested_tool_results);
+        }
+        Ok(report)
+    }
+
+    /// Compaction for a prompt the provider rejected as too long: forced
+    /// whatever the configured budget or mode, keeping half as many recent
+    /// messages per attempt and digesting the tool results it keeps. Returns
+    /// the `keep_last` used, or `None` when the transcript cannot shrink any
+    /// further.
+    pub(super) fn emergency_compact(
+        &mut self,
+        messages: &mut Vec<Message>,
+        attempt: u32,
+    ) -> Option<usize> {
+        let size = context_size_chars(messages);
+        let keep_last = (self.compaction_settings.keep_last.max(2) >> attempt).max(1);
+        let settings = CompactionSettings {
+            max_context_chars: size.saturating_sub(1).max(1),
+            max_context_tokens: None,
+            mode: CompactionMode::Summary,
+            keep_last,
+            tool_result_persist: match self.compaction_settings.tool_result_persist {
+                ToolResultPersist::ArtifactFile => ToolResultPersist::ArtifactFile,
+                _ => ToolResultPersist::Digest,
+            },
+        };
+        let mut compacted = messages.clone();
+        let report = maybe_compact_in_place_with_origins(
+            &mut compacted,
+            &settings,
+            self.digest_refetch_tracker.origins(),
+            self.token_counter.as_ref(),
+            self.tool_rt.run_artifacts.as_deref(),
+        )
+        .ok()
+        .flatten()
+        .filter(|report| report.after_chars < report.before_chars)?;
+        self.digest_refetch_tracker
+            .record_digests(&report.digested_tool_results);
+        *messages = compacted;
+        Some(keep_last)
+    }
+
     pub(super) fn compact_messages_for_step(
         &mut self,
         run_id: &str,
         step: u32,
-        messages: &[Message],
+        messages: &mut Vec<Message>,
         provider_retry_count: &mut u32,
         provider_error_count: &mut u32,
-    ) -> Result<CompactionOutcome, String> {
-        match maybe_compact(messages, &self.compaction_settings) {
+    ) -> Result<Option<CompactionReport>, String> {
+        match self.compact_tracking_digests(messages) {
             Ok(c) => Ok(c),
             Err(e) => {
                 if let Some(pe) = e.downcast_ref::<ProviderError>() {
-                    for r in &pe.retries {
-                        *provider_retry_count = provider_retry_count.saturating_add(1);
-                        self.emit_event(
-                            run_id,
-                            step,
-                            EventKind::ProviderRetry,
-                            serde_json::json!({
-                                "attempt": r.attempt,
-                                "max_attempts": r.max_attempts,
-                                "kind": r.kind,
-                                "status": r.status,
-                                "backoff_ms": r.backoff_ms
-                            }),
-                        );
-                    }
+                    self.record_provider_retries(run_id, step, &pe.retries, provider_retry_count);
                     *provider_error_count = provider_error_count.saturating_add(1);
                     self.emit_event(
                         run_id,
                         step,
-                        EventKind::ProviderError,
-                        serde_json::json!({
-                            "kind": pe.kind,
-                            "status": pe.http_status,
-                            "retryable": pe.retryable,
-                            "attempt": pe.attempt,
-                            "max_attempts": pe.max_attempts,
-                            "message_short": message_short(&pe.message)
-                        }),
+                        ProviderErrorPayload {
+                            kind: pe.kind,
+                            status: pe.http_status,
+                            retryable: pe.retryable,
+                            attempt: pe.attempt,
+                            max_attempts: pe.max_attempts,
+                            message_short: message_short(&pe.message),
+                        },
                     );
                 }
                 let err_text = format!("compaction failed: {e}");
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::Error,
-                    serde_json::json!({"error": err_text}),
+                    ErrorPayload {
+                        error: err_text.clone(),
+                        ..ErrorPayload::default()
+                    },
                 );
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::RunEnd,
-                    serde_json::json!({"exit_reason":"provider_error"}),
+                    RunEndPayload {
+                        exit_reason: "provider_error".to_string(),
+                    },
                 );
                 Err(err_text)
             }
@@ -1,8 +1,11 @@
 use anyhow::Error;
 
 use crate::agent_utils::add_opt_u32;
-use crate::events::EventKind;
-use crate::providers::http::{message_short, ProviderError};
+use crate::events::{
+    ErrorPayload, ProviderErrorPayload, ProviderRetryPayload, ToolExecStartPayload,
+    ToolExecTargetPayload, ToolRetryPayload,
+};
+use crate::providers::http::{message_short, ProviderError, RetryRecord};
 use crate::providers::ModelProvider;
 use crate::tools::tool_side_effects;
 use crate::types::TokenUsage;
@@ -35,16 +38,16 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::ToolRetry,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "attempt": event.attempt,
-                "max_retries": event.max_retries,
-                "failure_class": event.failure_class,
-                "action": event.action,
-                "error_code": event.error_code
-            }),
+            ToolRetryPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                attempt: event.attempt,
+                max_retries: event.max_retries,
+                failure_class: event.failure_class.to_string(),
+                action: event.action.to_string(),
+                error_code: event.error_code.map(str::to_string),
+                ..ToolRetryPayload::default()
+            },
         );
     }
 
@@ -58,47 +61,81 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::Error,
-            serde_json::json!({
-                "error": "schema repair attempts exhausted",
-                "source": "schema_repair",
-                "code": "TOOL_SCHEMA_REPAIR_EXHAUSTED",
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "attempt": attempt,
-                "max_attempts": super::MAX_SCHEMA_REPAIR_ATTEMPTS
-            }),
+            ErrorPayload {
+                error: "schema repair attempts exhausted".to_string(),
+                source: Some("schema_repair".to_string()),
+                code: Some("TOOL_SCHEMA_REPAIR_EXHAUSTED".to_string()),
+                tool_call_id: Some(tc.id.clone()),
+                name: Some(tc.name.clone()),
+                attempt: Some(attempt),
+                max_attempts: Some(super::MAX_SCHEMA_REPAIR_ATTEMPTS),
+                ..ErrorPayload::default()
+            },
         );
     }
 
     pub(super) fn emit_tool_exec_start_events(&mut self, run_id: &str, step: u32, tc: &ToolCall) {
+        let kind = if tc.name.starts_with("mcp.") {
+            crate::target::ExecTargetKind::Host
+        } else {
+            self.tool_rt
+                .exec_target_kind_for(tool_side_effects(&tc.name))
+        };
+        let docker = match kind {
+            crate::target::ExecTargetKind::Docker => self.tool_rt.exec_target.describe().docker,
+            crate::target::ExecTargetKind::Host | crate::target::ExecTargetKind::Ssh => None,
+        };
         self.emit_event(
             run_id,
             step,
-            EventKind::ToolExecTarget,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "exec_target": if tc.name.starts_with("mcp.") { "host" } else {
-                    match self.tool_rt.exec_target_kind {
-                        crate::target::ExecTargetKind::Host => "host",
-                        crate::target::ExecTargetKind::Docker => "docker",
-                    }
+            ToolExecTargetPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                exec_target: match kind {
+                    crate::target::ExecTargetKind::Host => "host",
+                    crate::target::ExecTargetKind::Docker => "docker",
+                    crate::target::ExecTargetKind::Ssh => "ssh",
                 }
-            }),
+                .to_string(),
+                docker,
+            },
         );
         self.emit_event(
             run_id,
             step,
-            EventKind::ToolExecStart,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "side_effects": tool_side_effects(&tc.name)
-            }),
+            ToolExecStartPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                side_effects: tool_side_effects(&tc.name),
+                batch_id: self.tool_batch_id(tc),
+            },
         );
     }
 
+    pub(super) fn record_provider_retries(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        retries: &[RetryRecord],
+        provider_retry_count: &mut u32,
+    ) {
+        for r in retries {
+            *provider_retry_count = provider_retry_count.saturating_add(1);
+            self.provider_retry_wait_ms = self.provider_retry_wait_ms.saturating_add(r.backoff_ms);
+            self.emit_event(
+                run_id,
+                step,
+                ProviderRetryPayload {
+                    attempt: r.attempt,
+                    max_attempts: r.max_attempts,
+                    kind: r.kind,
+                    status: r.status,
+                    backoff_ms: r.backoff_ms,
+                },
+            );
+        }
+    }
+
     pub(super) fn record_provider_error_events(
         &mut self,
         run_id: &str,
@@ -108,34 +145,19 @@ impl<P: ModelProvider> Agent<P> {
         provider_error_count: &mut u32,
     ) {
         if let Some(pe) = err.downcast_ref::<ProviderError>() {
-            for r in &pe.retries {
-                *provider_retry_count = provider_retry_count.saturating_add(1);
-                self.emit_event(
-                    run_id,
-                    step,
-                    EventKind::ProviderRetry,
-                    serde_json::json!({
-                        "attempt": r.attempt,
-                        "max_attempts": r.max_attempts,
-                        "kind": r.kind,
-                        "status": r.status,
-                        "backoff_ms": r.backoff_ms
-                    }),
-                );
-            }
+            self.record_provider_retries(run_id, step, &pe.retries, provider_retry_count);
             *provider_error_count = provider_error_count.saturating_add(1);
             self.emit_event(
                 run_id,
                 step,
-                EventKind::ProviderError,
-                serde_json::json!({
-                    "kind": pe.kind,
-                    "status": pe.http_status,
-                    "retryable": pe.retryable,
-                    "attempt": pe.attempt,
-                    "max_attempts": pe.max_attempts,
-                    "message_short": message_short(&pe.message)
-                }),
+                ProviderErrorPayload {
+                    kind: pe.kind,
+                    status: pe.http_status,
+                    retryable: pe.retryable,
+                    attempt: pe.attempt,
+                    max_attempts: pe.max_attempts,
+                    message_short: message_short(&pe.message),
+                },
             );
         }
     }
@@ -1,4 +1,7 @@
-use crate::events::EventKind;
+use crate::events::{
+    CompletionBlockedPayload, ErrorPayload, InterruptRaisedPayload, PhaseEnteredPayload,
+    PhaseExitedPayload, RunEndPayload, StepBlockedPayload,
+};
 use crate::providers::ModelProvider;
 use crate::taint::TaintState;
 use crate::types::TokenUsage;
@@ -85,12 +88,12 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     &run_id,
                     step,
-                    EventKind::Error,
-                    serde_json::json!({
-                        "error": reason,
-                        "source": "implementation_integrity_guard",
-                        "reason_code": "post_write_guard_retry"
-                    }),
+                    ErrorPayload {
+                        error: reason.clone(),
+                        source: Some("implementation_integrity_guard".to_string()),
+                        reason_code: Some("post_write_guard_retry".to_string()),
+                        ..ErrorPayload::default()
+                    },
                 );
                 let corrective = if reason.contains("requires prior read_file") {
                     "You must read_file on a path before editing it. Use read_file to inspect the file contents first, then apply your changes, then read_file again to verify."
@@ -102,11 +105,11 @@ impl<P: ModelProvider> Agent<P> {
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::Error,
-                serde_json::json!({
-                    "error": reason,
-                    "source": "implementation_integrity_guard"
-                }),
+                ErrorPayload {
+                    error: reason.clone(),
+                    source: Some("implementation_integrity_guard".to_string()),
+                    ..ErrorPayload::default()
+                },
             );
             return VerifiedWriteResult::Done(Box::new(self.finalize_planner_error_with_end(
                 step,
@@ -147,31 +150,29 @@ impl<P: ModelProvider> Agent<P> {
             post_write_follow_on_turn_count,
         ) {
             crate::agent::completion_policy::VerifiedWriteCompletionDecision::StartFinalAnswerPhase(message) => {
-                self.emit_event(
-                    &run_id,
-                    step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": "post_write_final_answer_only_phase",
-                        "source": "runtime_post_write_follow_on"
-                    }),
-                );
+                self.emit_event(&run_id, step, StepBlockedPayload { reason: Some("post_write_final_answer_only_phase".to_string()), source: Some("runtime_post_write_follow_on".to_string()), ..StepBlockedPayload::default() });
                 return VerifiedWriteResult::StartFinalAnswerPhase(message);
             }
             crate::agent::completion_policy::VerifiedWriteCompletionDecision::StartRequiredValidationPhase(message) => {
-                self.emit_event(
-                    &run_id,
-                    step,
-                    EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": "post_write_validation_only_phase",
-                        "source": "runtime_post_write_follow_on"
-                    }),
-                );
+                self.emit_event(&run_id, step, StepBlockedPayload { reason: Some("post_write_validation_only_phase".to_string()), source: Some("runtime_post_write_follow_on".to_string()), ..StepBlockedPayload::default() });
                 return VerifiedWriteResult::StartRequiredValidationPhase(message);
             }
             crate::agent::completion_policy::VerifiedWriteCompletionDecision::FinalizeNow => {}
         }
+        if let Some(errors) = self.final_output_schema_errors(&final_output) {
+            self.emit_event(
+                &run_id,
+                step,
+                StepBlockedPayload {
+                    reason: Some("output_schema_violation".to_string()),
+                    source: Some("runtime_output_schema_guard".to_string()),
+                    ..StepBlockedPayload::default()
+                },
+            );
+            return VerifiedWriteResult::StartFinalAnswerPhase(
+                self.output_schema_repair_instruction(&errors),
+            );
+        }
         VerifiedWriteResult::Done(Box::new(self.finalize_ok_with_end(
             step,
             run_id,
@@ -258,39 +259,48 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            EventKind::InterruptRaised,
-            serde_json::json!({
-                "kind": crate::agent::interrupts::interrupt_kind_name(&transition.interrupt_kind),
-                "approval_id": approval.and_then(|decision| decision.approval_id.clone()),
-                "tool_call_id": approval.map(|decision| decision.tool_call_id.clone()),
-                "reason": approval.and_then(|decision| decision.reason.clone())
-            }),
+            InterruptRaisedPayload {
+                kind: crate::agent::interrupts::interrupt_kind_name(&transition.interrupt_kind)
+                    .to_string(),
+                approval_id: Some(
+                    approval
+                        .and_then(|decision| decision.approval_id.clone())
+                        .clone(),
+                ),
+                tool_call_id: Some(
+                    approval
+                        .map(|decision| decision.tool_call_id.clone())
+                        .clone(),
+                ),
+                reason: approval
+                    .and_then(|decision| decision.reason.clone())
+                    .clone(),
+            },
         );
         self.emit_event(
             &run_id,
             step,
-            EventKind::PhaseExited,
-            serde_json::json!({
-                "phase": crate::agent::interrupts::run_phase_name(&transition.from_phase),
-                "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-            }),
+            PhaseExitedPayload {
+                phase: crate::agent::interrupts::run_phase_name(&transition.from_phase).to_string(),
+                next_phase: crate::agent::interrupts::run_phase_name(&transition.to_phase)
+                    .to_string(),
+            },
         );
         self.emit_event(
             &run_id,
             step,
-            EventKind::PhaseEntered,
-            serde_json::json!({
-                "phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-            }),
+            PhaseEnteredPayload {
+                phase: crate::agent::interrupts::run_phase_name(&transition.to_phase).to_string(),
+            },
         );
         self.emit_event(
             &run_id,
             step,
-            EventKind::CompletionBlocked,
-            serde_json::json!({
-                "reason": transition.completion_reason,
-                "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-            }),
+            CompletionBlockedPayload {
+                reason: transition.completion_reason.to_string(),
+                next_phase: crate::agent::interrupts::run_phase_name(&transition.to_phase)
+                    .to_string(),
+            },
         );
         self.finalize_run_outcome_with_end(
             step,
@@ -334,6 +344,10 @@ impl<P: ModelProvider> Agent<P> {
         total_token_usage: &TokenUsage,
         taint_state: &TaintState,
     ) -> AgentOutcome {
+        debug_assert!(
+            !final_output.trim().is_empty(),
+            "ok exit must carry a non-empty final_output"
+        );
         self.finalize_run_outcome_with_end(
             step,
             AgentOutcomeBuilderInput {
@@ -399,6 +413,56 @@ impl<P: ModelProvider> Agent<P> {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn finalize_operator_cancelled_with_end(
+        &mut self,
+        step: u32,
+        run_id: String,
+        started_at: String,
+        cancel: &crate::operator_queue::QueuedOperatorMessage,
+        messages: Vec<crate::types::Message>,
+        tool_calls: Vec<crate::types::ToolCall>,
+        tool_decisions: Vec<super::ToolDecisionRecord>,
+        final_prompt_size_chars: usize,
+        compaction_report: Option<crate::compaction::CompactionReport>,
+        hook_invocations: Vec<crate::hooks::protocol::HookInvocationReport>,
+        provider_retry_count: u32,
+        provider_error_count: u32,
+        saw_token_usage: bool,
+        total_token_usage: &TokenUsage,
+        taint_state: &TaintState,
+    ) -> AgentOutcome {
+        let mut final_output = format!(
+            "Run cancelled by operator (queue message {}). No further tool calls or model turns were run.",
+            cancel.queue_id
+        );
+        let note = cancel.content.trim();
+        if !note.is_empty() {
+            final_output.push_str(&format!(" Operator message: {note}"));
+        }
+        self.finalize_run_outcome_with_end(
+            step,
+            AgentOutcomeBuilderInput {
+                run_id,
+                started_at,
+                exit_reason: super::AgentExitReason::Cancelled,
+                final_output,
+                error: Some("operator_cancel".to_string()),
+                messages,
+                tool_calls,
+                tool_decisions,
+                final_prompt_size_chars,
+                compaction_report,
+                hook_invocations,
+                provider_retry_count,
+                provider_error_count,
+            },
+            saw_token_usage,
+            total_token_usage,
+            taint_state,
+        )
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(super) fn finalize_hook_aborted_with_end(
         &mut self,
@@ -484,6 +548,91 @@ impl<P: ModelProvider> Agent<P> {
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn finalize_context_overflow_with_end(
+        &mut self,
+        step: u32,
+        run_id: String,
+        started_at: String,
+        error: String,
+        messages: Vec<crate::types::Message>,
+        tool_calls: Vec<crate::types::ToolCall>,
+        tool_decisions: Vec<super::ToolDecisionRecord>,
+        final_prompt_size_chars: usize,
+        compaction_report: Option<crate::compaction::CompactionReport>,
+        hook_invocations: Vec<crate::hooks::protocol::HookInvocationReport>,
+        provider_retry_count: u32,
+        provider_error_count: u32,
+        saw_token_usage: bool,
+        total_token_usage: &TokenUsage,
+        taint_state: &TaintState,
+    ) -> AgentOutcome {
+        self.finalize_run_outcome_with_end(
+            step,
+            AgentOutcomeBuilderInput {
+                run_id,
+                started_at,
+                exit_reason: super::AgentExitReason::ContextOverflow,
+                final_output: String::new(),
+                error: Some(error),
+                messages,
+                tool_calls,
+                tool_decisions,
+                final_prompt_size_chars,
+                compaction_report,
+                hook_invocations,
+                provider_retry_count,
+                provider_error_count,
+            },
+            saw_token_usage,
+            total_token_usage,
+            taint_state,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn finalize_output_schema_violation_with_end(
+        &mut self,
+        step: u32,
+        run_id: String,
+        started_at: String,
+        final_output: String,
+        error: String,
+        messages: Vec<crate::types::Message>,
+        tool_calls: Vec<crate::types::ToolCall>,
+        tool_decisions: Vec<super::ToolDecisionRecord>,
+        final_prompt_size_chars: usize,
+        compaction_report: Option<crate::compaction::CompactionReport>,
+        hook_invocations: Vec<crate::hooks::protocol::HookInvocationReport>,
+        provider_retry_count: u32,
+        provider_error_count: u32,
+        saw_token_usage: bool,
+        total_token_usage: &TokenUsage,
+        taint_state: &TaintState,
+    ) -> AgentOutcome {
+        self.finalize_run_outcome_with_end(
+            step,
+            AgentOutcomeBuilderInput {
+                run_id,
+                started_at,
+                exit_reason: super::AgentExitReason::OutputSchemaViolation,
+                final_output,
+                error: Some(error),
+                messages,
+                tool_calls,
+                tool_decisions,
+                final_prompt_size_chars,
+                compaction_report,
+                hook_invocations,
+                provider_retry_count,
+                provider_error_count,
+            },
+            saw_token_usage,
+            total_token_usage,
+            taint_state,
+        )
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(super) fn finalize_budget_exceeded_with_end(
         &mut self,
@@ -666,6 +815,8 @@ impl<P: ModelProvider> Agent<P> {
         total_token_usage: &TokenUsage,
         taint_state: &TaintState,
     ) -> AgentOutcome {
+        let timeline = self.timeline_recorder.snapshot();
+        let steps = self.timeline_recorder.step_stats(&timeline);
         AgentOutcome {
             run_id: input.run_id,
             started_at: input.started_at,
@@ -682,6 +833,7 @@ impl<P: ModelProvider> Agent<P> {
             hook_invocations: input.hook_invocations,
             provider_retry_count: input.provider_retry_count,
             provider_error_count: input.provider_error_count,
+            provider_retry_wait_ms: self.provider_retry_wait_ms,
             token_usage: if saw_token_usage {
                 Some(total_token_usage.clone())
             } else {
@@ -693,6 +845,18 @@ impl<P: ModelProvider> Agent<P> {
                 self.taint_digest_bytes,
                 taint_state,
             ),
+            digest_refetch: self.digest_refetch_tracker.stats(),
+            tool_result_artifacts: self
+                .tool_rt
+                .run_artifacts
+                .as_ref()
+                .map(|store| store.tool_result_files())
+                .unwrap_or_default(),
+            model_served: self.served_model.clone(),
+            subtask_run_ids: self.subtask_run_ids.clone(),
+            post_run_hook_error: None,
+            timeline,
+            steps,
         }
     }
 
@@ -706,12 +870,7 @@ impl<P: ModelProvider> Agent<P> {
     ) -> AgentOutcome {
         let run_id = input.run_id.clone();
         let exit_reason = input.exit_reason.as_str().to_string();
-        self.emit_event(
-            &run_id,
-            step,
-            EventKind::RunEnd,
-            serde_json::json!({"exit_reason": exit_reason}),
-        );
+        self.emit_event(&run_id, step, RunEndPayload { exit_reason });
         self.finalize_run_outcome(input, saw_token_usage, total_token_usage, taint_state)
     }
 }
@@ -1,7 +1,10 @@
 use std::collections::BTreeSet;
 
 use crate::agent_worker_protocol::parse_worker_step_status;
-use crate::events::EventKind;
+use crate::events::{
+    ErrorPayload, PolicyLoadedPayload, RunStartPayload, StepStartedPayload,
+    ToolCallDetectedPayload, ToolResultRefetchedPayload,
+};
 use crate::providers::ModelProvider;
 use crate::taint::{TaintState, TaintToggle};
 use crate::types::{GenerateRequest, Message, Role, ToolCall, ToolDef};
@@ -12,11 +15,19 @@ pub(super) struct ToolCallPlanningContext {
     pub(super) plan_allowed_tools: Vec<String>,
     pub(super) plan_tool_allowed: bool,
     pub(super) plan_step_id: String,
+    pub(super) plan_arg_violation: Option<PlanArgViolation>,
     pub(super) repeat_key: String,
     pub(super) failed_repeat_count: u32,
     pub(super) failed_repeat_name_count: u32,
 }
 
+/// First argument constraint of the active plan step that a call breaks.
+#[derive(Debug, Clone)]
+pub(super) struct PlanArgViolation {
+    pub(super) constraint_id: String,
+    pub(super) reason: String,
+}
+
 impl<P: ModelProvider> Agent<P> {
     #[allow(clippy::type_complexity)]
     pub(super) fn gate_decision_metadata_for_tool(
@@ -44,6 +55,7 @@ impl<P: ModelProvider> Agent<P> {
                     match self.gate_ctx.auto_approve_scope {
                         crate::gate::AutoApproveScope::Run => "run",
                         crate::gate::AutoApproveScope::Session => "session",
+                        crate::gate::AutoApproveScope::Workspace => "workspace",
                     }
                     .to_string(),
                 )
@@ -58,11 +70,17 @@ impl<P: ModelProvider> Agent<P> {
         self.gate_ctx.taint_enabled = matches!(self.taint_toggle, TaintToggle::On);
         self.gate_ctx.taint_mode = self.taint_mode;
         self.gate_ctx.taint_overall = taint_state.overall;
-        self.gate_ctx.taint_sources = taint_state.last_sources.clone();
+        self.gate_ctx.taint_sources = taint_state.sources();
+        self.gate_ctx.taint_injection_digest = taint_state.injection_digest.clone();
+        // Routed calls are gated, keyed and recorded against their own target.
+        self.gate_ctx.exec_target = self
+            .tool_rt
+            .exec_target_kind_for(crate::tools::tool_side_effects(&tc.name));
         let decision_exec_target = Some(
             match self.gate_ctx.exec_target {
                 crate::target::ExecTargetKind::Host => "host",
                 crate::target::ExecTargetKind::Docker => "docker",
+                crate::target::ExecTargetKind::Ssh => "ssh",
             }
             .to_string(),
         );
@@ -126,6 +144,27 @@ impl<P: ModelProvider> Agent<P> {
             || constraint.intended_tools.iter().any(|t| t == tool_name)
     }
 
+    pub(super) fn plan_arg_violation(
+        &self,
+        active_plan_step_idx: usize,
+        tc: &ToolCall,
+    ) -> Option<PlanArgViolation> {
+        if !self.plan_enforcement_active() {
+            return None;
+        }
+        let constraint = self.current_plan_constraint(active_plan_step_idx)?;
+        constraint
+            .arg_constraints
+            .iter()
+            .filter(|c| c.tool == tc.name)
+            .find_map(|c| {
+                c.violation(&tc.arguments).map(|reason| PlanArgViolation {
+                    constraint_id: c.id.clone(),
+                    reason,
+                })
+            })
+    }
+
     pub(super) fn plan_allowed_tools_and_decision(
         &self,
         active_plan_step_idx: usize,
@@ -159,6 +198,7 @@ impl<P: ModelProvider> Agent<P> {
             plan_allowed_tools,
             plan_tool_allowed,
             plan_step_id,
+            plan_arg_violation: self.plan_arg_violation(active_plan_step_idx, tc),
             repeat_key,
             failed_repeat_count,
             failed_repeat_name_count,
@@ -168,28 +208,46 @@ impl<P: ModelProvider> Agent<P> {
     pub(super) fn pending_plan_step_text(&self, active_plan_step_idx: usize) -> Option<String> {
         let step_constraint = self.current_plan_constraint(active_plan_step_idx)?;
         Some(format!(
-            "premature finalization blocked: plan step {} still pending (allowed tools: {})",
+            "premature finalization blocked: plan step {} still pending (allowed tools: {}){}",
             step_constraint.step_id,
             if step_constraint.intended_tools.is_empty() {
                 "none".to_string()
             } else {
                 step_constraint.intended_tools.join(", ")
-            }
+            },
+            self.queued_plan_steps_suffix()
         ))
     }
 
+    /// Fan-out siblings from a v2 step_result that have not started yet.
+    fn queued_plan_steps_suffix(&self) -> String {
+        if self.plan_step_queue.is_empty() {
+            String::new()
+        } else {
+            format!("; queued steps: {}", self.plan_step_queue.join(", "))
+        }
+    }
+
     pub(super) fn pending_plan_step_corrective_message(
         &self,
         active_plan_step_idx: usize,
     ) -> Option<String> {
         let step_constraint = self.current_plan_constraint(active_plan_step_idx)?;
         Some(format!(
-            "Continue execution. Do not finalize yet. Complete pending step {} using only intended tools ({}), then return the next tool call.",
+            "Continue execution. Do not finalize yet. Complete pending step {} using only intended tools ({}){}, then return the next tool call.",
             step_constraint.step_id,
             if step_constraint.intended_tools.is_empty() {
                 "none".to_string()
             } else {
                 step_constraint.intended_tools.join(", ")
+            },
+            if self.plan_step_queue.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    ", then the queued steps in order ({})",
+                    self.plan_step_queue.join(", ")
+                )
             }
         ))
     }
@@ -206,6 +264,25 @@ impl<P: ModelProvider> Agent<P> {
         }
     }
 
+    /// Validation errors of `final_output` against `final_output_schema`;
+    /// `None` when it conforms or no schema is set.
+    pub(super) fn final_output_schema_errors(&self, final_output: &str) -> Option<Vec<String>> {
+        let schema = self.final_output_schema.as_ref()?;
+        crate::tools::validate_json_output(final_output, schema).err()
+    }
+
+    pub(super) fn output_schema_repair_instruction(&self, errors: &[String]) -> String {
+        let schema = self
+            .final_output_schema
+            .as_ref()
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        format!(
+            "Your final answer does not satisfy the required output schema:\n- {}\nReply now with only a JSON value matching this schema, with no prose and no tool calls:\n{schema}",
+            errors.join("\n- ")
+        )
+    }
+
     pub(super) fn assistant_content_has_protocol_artifacts(
         &self,
         assistant_content: Option<&str>,
@@ -224,6 +301,15 @@ impl<P: ModelProvider> Agent<P> {
         )
     }
 
+    pub(super) fn empty_response_reminder_message(&self) -> String {
+        if self.plan_enforcement_active() {
+            self.control_envelope_reminder_message()
+                + " Your last response was empty; return the next tool call or the control JSON."
+        } else {
+            "Your last response was empty. Return the next tool call, or a non-empty final answer if the task is complete.".to_string()
+        }
+    }
+
     pub(super) fn tool_only_reminder_message(&self) -> String {
         "Tool-only phase active. Return exactly one valid tool call and no prose.".to_string()
     }
@@ -249,20 +335,38 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             0,
-            EventKind::RunStart,
-            serde_json::json!({"model": self.model}),
+            RunStartPayload {
+                model: self.model.clone(),
+            },
         );
         if let Some(policy) = &self.policy_loaded {
             self.emit_event(
                 run_id,
                 0,
-                EventKind::PolicyLoaded,
-                serde_json::json!({
-                    "version": policy.version,
-                    "rules_count": policy.rules_count,
-                    "includes_count": policy.includes_count,
-                    "mcp_allowlist": policy.mcp_allowlist
-                }),
+                PolicyLoadedPayload {
+                    version: policy.version,
+                    rules_count: policy.rules_count,
+                    includes_count: policy.includes_count,
+                    mcp_allowlist: policy.mcp_allowlist.clone(),
+                },
+            );
+        }
+        if let Some(reason) = self
+            .tool_rt
+            .exec_target
+            .describe()
+            .docker
+            .and_then(|d| d.persistent_fallback)
+        {
+            self.emit_event(
+                run_id,
+                0,
+                ErrorPayload {
+                    error: reason,
+                    source: Some("docker".to_string()),
+                    code: Some("DOCKER_PERSISTENT_FALLBACK".to_string()),
+                    ..ErrorPayload::default()
+                },
             );
         }
     }
@@ -388,13 +492,12 @@ Fallback when native tool calls are unavailable:\n\
         self.emit_event(
             run_id,
             step,
-            EventKind::StepStarted,
-            serde_json::json!({
-                "step_id": step_constraint.step_id,
-                "step_index": active_plan_step_idx,
-                "allowed_tools": step_constraint.intended_tools,
-                "enforcement_mode": format!("{:?}", self.plan_tool_enforcement).to_lowercase()
-            }),
+            StepStartedPayload {
+                step_id: step_constraint.step_id.clone(),
+                step_index: active_plan_step_idx,
+                allowed_tools: step_constraint.intended_tools.clone(),
+                enforcement_mode: format!("{:?}", self.plan_tool_enforcement).to_lowercase(),
+            },
         );
         *announced_plan_step_id = Some(step_constraint.step_id.clone());
     }
@@ -407,17 +510,35 @@ Fallback when native tool calls are unavailable:\n\
         observed_tool_calls: &mut Vec<ToolCall>,
     ) {
         observed_tool_calls.push(tc.clone());
+        let refetch = self.digest_refetch_tracker.record_call(tc, step);
         self.emit_event(
             run_id,
             step,
-            EventKind::ToolCallDetected,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "arguments": tc.arguments,
-                "side_effects": crate::tools::tool_side_effects(&tc.name),
-                "tool_args_strict": if self.tool_rt.tool_args_strict.is_enabled() { "on" } else { "off" }
-            }),
+            ToolCallDetectedPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                arguments: tc.arguments.clone(),
+                side_effects: crate::tools::tool_side_effects(&tc.name),
+                tool_args_strict: if self.tool_rt.tool_args_strict.is_enabled() {
+                    "on"
+                } else {
+                    "off"
+                }
+                .to_string(),
+            },
         );
+        if let Some(refetch) = refetch {
+            self.emit_event(
+                run_id,
+                step,
+                ToolResultRefetchedPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: refetch.tool,
+                    args_sha256: refetch.args_sha256,
+                    digested_tool_call_id: refetch.digested_tool_call_id,
+                    digested_step: refetch.digested_step,
+                },
+            );
+        }
     }
 }
@@ -1,5 +1,9 @@
 use super::PlanToolEnforcementMode;
 use crate::agent_impl_guard::ToolExecutionRecord;
+use crate::events::{
+    CompletionBlockedPayload, ErrorPayload, PhaseEnteredPayload, PhaseExitedPayload,
+    StepBlockedPayload,
+};
 use crate::providers::ModelProvider;
 use crate::taint::TaintState;
 use crate::types::{Message, TokenUsage, ToolCall};
@@ -50,6 +54,10 @@ pub(super) enum RuntimeCompletionAction {
         blocked_runtime_completion_count: u32,
         operator_delivery_count: u32,
     },
+    ContinueOutputSchemaRepair {
+        blocked_runtime_completion_count: u32,
+        operator_delivery_count: u32,
+    },
     ProceedToTools {
         blocked_runtime_completion_count: u32,
     },
@@ -61,6 +69,7 @@ pub(super) struct RuntimeCompletionInputs {
     pub(super) plan_tool_enforcement: PlanToolEnforcementMode,
     pub(super) active_plan_step_idx: usize,
     pub(super) plan_step_constraints_len: usize,
+    pub(super) queued_plan_step_count: usize,
     pub(super) tool_only_phase_active: bool,
     pub(super) exact_final_answer_only_phase_active: bool,
     pub(super) enforce_implementation_integrity_guard: bool,
@@ -83,7 +92,8 @@ pub(super) fn runtime_completion_decision(
     }
     if !matches!(inputs.plan_tool_enforcement, PlanToolEnforcementMode::Off)
         && inputs.plan_step_constraints_len > 0
-        && inputs.active_plan_step_idx < inputs.plan_step_constraints_len
+        && (inputs.active_plan_step_idx < inputs.plan_step_constraints_len
+            || inputs.queued_plan_step_count > 0)
     {
         if inputs.blocked_attempt_count_next >= 2 {
             return RuntimeCompletionDecision::FinalizeError {
@@ -158,6 +168,7 @@ impl<P: ModelProvider> Agent<P> {
         taint_state: &TaintState,
         exact_final_answer_retry_count: u32,
         required_validation_retry_count: u32,
+        output_schema_repair_count: u32,
     ) -> RuntimeCompletionAction {
         match decision {
             RuntimeCompletionDecision::Continue {
@@ -175,22 +186,22 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     &run_id,
                     step,
-                    crate::events::EventKind::Error,
-                    serde_json::json!({
-                        "error": error_text,
-                        "source": source,
-                        "reason_code": reason_code,
-                        "blocked_count": blocked_runtime_completion_count
-                    }),
+                    ErrorPayload {
+                        error: error_text,
+                        source: Some(source.to_string()),
+                        reason_code: Some(reason_code.to_string()),
+                        blocked_count: Some(blocked_runtime_completion_count),
+                        ..ErrorPayload::default()
+                    },
                 );
                 self.emit_event(
                     &run_id,
                     step,
-                    crate::events::EventKind::StepBlocked,
-                    serde_json::json!({
-                        "reason": reason_code,
-                        "blocked_count": blocked_runtime_completion_count
-                    }),
+                    StepBlockedPayload {
+                        reason: Some(reason_code.to_string()),
+                        blocked_count: Some(blocked_runtime_completion_count),
+                        ..StepBlockedPayload::default()
+                    },
                 );
                 let corrective_message =
                     if reason_code == "pending_plan_step" && self.plan_enforcement_active() {
@@ -218,12 +229,12 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     &run_id,
                     step,
-                    crate::events::EventKind::Error,
-                    serde_json::json!({
-                        "error": reason,
-                        "source": source,
-                        "failure_class": failure_class
-                    }),
+                    ErrorPayload {
+                        error: reason.to_string(),
+                        source: Some(source.to_string()),
+                        failure_class: Some(failure_class.to_string()),
+                        ..ErrorPayload::default()
+                    },
                 );
                 RuntimeCompletionAction::Finalize(Box::new(self.finalize_planner_error_with_end(
                     step,
@@ -290,22 +301,22 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         &run_id,
                         step,
-                        crate::events::EventKind::Error,
-                        serde_json::json!({
-                            "error": corrective_instruction,
-                            "source": "tool_protocol_guard",
-                            "reason_code": reason_code,
-                            "blocked_count": blocked_runtime_completion_count
-                        }),
+                        ErrorPayload {
+                            error: corrective_instruction.clone(),
+                            source: Some("tool_protocol_guard".to_string()),
+                            reason_code: Some(reason_code.to_string()),
+                            blocked_count: Some(blocked_runtime_completion_count),
+                            ..ErrorPayload::default()
+                        },
                     );
                     self.emit_event(
                         &run_id,
                         step,
-                        crate::events::EventKind::StepBlocked,
-                        serde_json::json!({
-                            "reason": reason_code,
-                            "blocked_count": blocked_runtime_completion_count
-                        }),
+                        StepBlockedPayload {
+                            reason: Some(reason_code.to_string()),
+                            blocked_count: Some(blocked_runtime_completion_count),
+                            ..StepBlockedPayload::default()
+                        },
                     );
                     messages.push(Message {
                         role: crate::types::Role::Developer,
@@ -348,11 +359,11 @@ impl<P: ModelProvider> Agent<P> {
                                 self.emit_event(
                                     &run_id,
                                     step,
-                                    crate::events::EventKind::Error,
-                                    serde_json::json!({
-                                        "error": reason,
-                                        "source": "implementation_integrity_guard"
-                                    }),
+                                    ErrorPayload {
+                                        error: reason.clone(),
+                                        source: Some("implementation_integrity_guard".to_string()),
+                                        ..ErrorPayload::default()
+                                    },
                                 );
                                 return RuntimeCompletionAction::Finalize(Box::new(
                                     self.finalize_planner_error_with_end(
@@ -407,22 +418,24 @@ impl<P: ModelProvider> Agent<P> {
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::Error,
-                            serde_json::json!({
-                                "error": corrective_instruction,
-                                "source": "implementation_integrity_guard",
-                                "reason_code": "implementation_requires_effective_write",
-                                "blocked_count": blocked_runtime_completion_count
-                            }),
+                            ErrorPayload {
+                                error: corrective_instruction.to_string(),
+                                source: Some("implementation_integrity_guard".to_string()),
+                                reason_code: Some(
+                                    "implementation_requires_effective_write".to_string(),
+                                ),
+                                blocked_count: Some(blocked_runtime_completion_count),
+                                ..ErrorPayload::default()
+                            },
                         );
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::StepBlocked,
-                            serde_json::json!({
-                                "reason": "implementation_requires_effective_write",
-                                "blocked_count": blocked_runtime_completion_count
-                            }),
+                            StepBlockedPayload {
+                                reason: Some("implementation_requires_effective_write".to_string()),
+                                blocked_count: Some(blocked_runtime_completion_count),
+                                ..StepBlockedPayload::default()
+                            },
                         );
                         messages.push(Message {
                             role: crate::types::Role::Developer,
@@ -439,11 +452,11 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         &run_id,
                         step,
-                        crate::events::EventKind::Error,
-                        serde_json::json!({
-                            "error": reason,
-                            "source": "implementation_integrity_guard"
-                        }),
+                        ErrorPayload {
+                            error: reason.clone(),
+                            source: Some("implementation_integrity_guard".to_string()),
+                            ..ErrorPayload::default()
+                        },
                     );
                     return RuntimeCompletionAction::Finalize(Box::new(
                         self.finalize_planner_error_with_end(
@@ -484,52 +497,11 @@ impl<P: ModelProvider> Agent<P> {
                             blocked_runtime_completion_count.saturating_add(1);
                         let transition =
                             crate::agent::required_validation_boundary_transition_decision();
-                        self.emit_event(
-                            &run_id,
-                            step,
-                            crate::events::EventKind::Error,
-                            serde_json::json!({
-                                "error": corrective_instruction,
-                                "source": "runtime_required_validation_guard",
-                                "reason_code": "required_validation_before_final",
-                                "blocked_count": blocked_runtime_completion_count
-                            }),
-                        );
-                        self.emit_event(
-                            &run_id,
-                            step,
-                            crate::events::EventKind::StepBlocked,
-                            serde_json::json!({
-                                "reason": "required_validation_before_final",
-                                "blocked_count": blocked_runtime_completion_count
-                            }),
-                        );
-                        self.emit_event(
-                            &run_id,
-                            step,
-                            crate::events::EventKind::PhaseExited,
-                            serde_json::json!({
-                                "phase": crate::agent::interrupts::run_phase_name(&transition.from_phase),
-                                "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                            }),
-                        );
-                        self.emit_event(
-                            &run_id,
-                            step,
-                            crate::events::EventKind::PhaseEntered,
-                            serde_json::json!({
-                                "phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                            }),
-                        );
-                        self.emit_event(
-                            &run_id,
-                            step,
-                            crate::events::EventKind::CompletionBlocked,
-                            serde_json::json!({
-                                "reason": transition.completion_reason,
-                                "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                            }),
-                        );
+                        self.emit_event(&run_id, step, ErrorPayload { error: corrective_instruction.clone(), source: Some("runtime_required_validation_guard".to_string()), reason_code: Some("required_validation_before_final".to_string()), blocked_count: Some(blocked_runtime_completion_count), ..ErrorPayload::default() });
+                        self.emit_event(&run_id, step, StepBlockedPayload { reason: Some("required_validation_before_final".to_string()), blocked_count: Some(blocked_runtime_completion_count), ..StepBlockedPayload::default() });
+                        self.emit_event(&run_id, step, PhaseExitedPayload { phase: crate::agent::interrupts::run_phase_name(&transition.from_phase).to_string(), next_phase: crate::agent::interrupts::run_phase_name(&transition.to_phase).to_string() });
+                        self.emit_event(&run_id, step, PhaseEnteredPayload { phase: crate::agent::interrupts::run_phase_name(&transition.to_phase).to_string() });
+                        self.emit_event(&run_id, step, CompletionBlockedPayload { reason: transition.completion_reason.to_string(), next_phase: crate::agent::interrupts::run_phase_name(&transition.to_phase).to_string() });
                         messages.push(Message {
                             role: crate::types::Role::Developer,
                             content: Some(corrective_instruction),
@@ -543,16 +515,7 @@ impl<P: ModelProvider> Agent<P> {
                         };
                     }
                     crate::agent::completion_policy::RequiredValidationCompletionDecision::FinalizeError(reason) => {
-                        self.emit_event(
-                            &run_id,
-                            step,
-                            crate::events::EventKind::Error,
-                            serde_json::json!({
-                                "error": reason,
-                                "source": "runtime_required_validation_guard",
-                                "failure_class": "E_RUNTIME_COMPLETION_REQUIRED_VALIDATION"
-                            }),
-                        );
+                        self.emit_event(&run_id, step, ErrorPayload { error: reason.to_string(), source: Some("runtime_required_validation_guard".to_string()), failure_class: Some("E_RUNTIME_COMPLETION_REQUIRED_VALIDATION".to_string()), ..ErrorPayload::default() });
                         return RuntimeCompletionAction::Finalize(Box::new(
                             self.finalize_planner_error_with_end(
                                 step,
@@ -590,48 +553,57 @@ impl<P: ModelProvider> Agent<P> {
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::Error,
-                            serde_json::json!({
-                                "error": corrective_instruction,
-                                "source": "runtime_exact_final_answer_guard",
-                                "reason_code": "exact_final_answer_required",
-                                "blocked_count": blocked_runtime_completion_count
-                            }),
+                            ErrorPayload {
+                                error: corrective_instruction.to_string(),
+                                source: Some("runtime_exact_final_answer_guard".to_string()),
+                                reason_code: Some("exact_final_answer_required".to_string()),
+                                blocked_count: Some(blocked_runtime_completion_count),
+                                ..ErrorPayload::default()
+                            },
                         );
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::StepBlocked,
-                            serde_json::json!({
-                                "reason": "exact_final_answer_required",
-                                "blocked_count": blocked_runtime_completion_count
-                            }),
+                            StepBlockedPayload {
+                                reason: Some("exact_final_answer_required".to_string()),
+                                blocked_count: Some(blocked_runtime_completion_count),
+                                ..StepBlockedPayload::default()
+                            },
                         );
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::PhaseExited,
-                            serde_json::json!({
-                                "phase": crate::agent::interrupts::run_phase_name(&transition.from_phase),
-                                "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                            }),
+                            PhaseExitedPayload {
+                                phase: crate::agent::interrupts::run_phase_name(
+                                    &transition.from_phase,
+                                )
+                                .to_string(),
+                                next_phase: crate::agent::interrupts::run_phase_name(
+                                    &transition.to_phase,
+                                )
+                                .to_string(),
+                            },
                         );
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::PhaseEntered,
-                            serde_json::json!({
-                                "phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                            }),
+                            PhaseEnteredPayload {
+                                phase: crate::agent::interrupts::run_phase_name(
+                                    &transition.to_phase,
+                                )
+                                .to_string(),
+                            },
                         );
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::CompletionBlocked,
-                            serde_json::json!({
-                                "reason": transition.completion_reason,
-                                "next_phase": crate::agent::interrupts::run_phase_name(&transition.to_phase)
-                            }),
+                            CompletionBlockedPayload {
+                                reason: transition.completion_reason.to_string(),
+                                next_phase: crate::agent::interrupts::run_phase_name(
+                                    &transition.to_phase,
+                                )
+                                .to_string(),
+                            },
                         );
                         messages.push(Message {
                             role: crate::types::Role::Developer,
@@ -650,12 +622,14 @@ impl<P: ModelProvider> Agent<P> {
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::Error,
-                            serde_json::json!({
-                                "error": reason,
-                                "source": "runtime_exact_final_answer_guard",
-                                "failure_class": "E_RUNTIME_COMPLETION_EXACT_FINAL_OUTPUT"
-                            }),
+                            ErrorPayload {
+                                error: reason.to_string(),
+                                source: Some("runtime_exact_final_answer_guard".to_string()),
+                                failure_class: Some(
+                                    "E_RUNTIME_COMPLETION_EXACT_FINAL_OUTPUT".to_string(),
+                                ),
+                                ..ErrorPayload::default()
+                            },
                         );
                         return RuntimeCompletionAction::Finalize(Box::new(
                             self.finalize_planner_error_with_end(
@@ -678,6 +652,77 @@ impl<P: ModelProvider> Agent<P> {
                         ));
                     }
                 }
+                if let Some(errors) = self.final_output_schema_errors(&final_output) {
+                    if output_schema_repair_count < super::MAX_OUTPUT_SCHEMA_REPAIRS {
+                        let blocked_runtime_completion_count =
+                            blocked_runtime_completion_count.saturating_add(1);
+                        self.emit_event(
+                            &run_id,
+                            step,
+                            ErrorPayload {
+                                error: errors.join("; "),
+                                source: Some("runtime_output_schema_guard".to_string()),
+                                reason_code: Some("output_schema_violation".to_string()),
+                                blocked_count: Some(blocked_runtime_completion_count),
+                                ..ErrorPayload::default()
+                            },
+                        );
+                        self.emit_event(
+                            &run_id,
+                            step,
+                            StepBlockedPayload {
+                                reason: Some("output_schema_violation".to_string()),
+                                blocked_count: Some(blocked_runtime_completion_count),
+                                ..StepBlockedPayload::default()
+                            },
+                        );
+                        messages.push(Message {
+                            role: crate::types::Role::Developer,
+                            content: Some(self.output_schema_repair_instruction(&errors)),
+                            tool_call_id: None,
+                            tool_name: None,
+                            tool_calls: None,
+                        });
+                        return RuntimeCompletionAction::ContinueOutputSchemaRepair {
+                            blocked_runtime_completion_count,
+                            operator_delivery_count,
+                        };
+                    }
+                    let reason = format!(
+                        "final output failed schema validation after {output_schema_repair_count} repair turn(s): {}",
+                        errors.join("; ")
+                    );
+                    self.emit_event(
+                        &run_id,
+                        step,
+                        ErrorPayload {
+                            error: reason.clone(),
+                            source: Some("runtime_output_schema_guard".to_string()),
+                            failure_class: Some("E_RUNTIME_OUTPUT_SCHEMA".to_string()),
+                            ..ErrorPayload::default()
+                        },
+                    );
+                    return RuntimeCompletionAction::Finalize(Box::new(
+                        self.finalize_output_schema_violation_with_end(
+                            step,
+                            run_id,
+                            started_at,
+                            final_output,
+                            reason,
+                            messages.clone(),
+                            observed_tool_calls,
+                            observed_tool_decisions,
+                            request_context_chars,
+                            last_compaction_report,
+                            hook_invocations,
+                            provider_retry_count,
+                            provider_error_count,
+                            saw_token_usage,
+                            total_token_usage,
+                            taint_state,
+                        ),
+                    ));
+                }
                 RuntimeCompletionAction::Finalize(Box::new(self.finalize_ok_with_end(
                     step,
                     run_id,
@@ -753,12 +798,12 @@ impl<P: ModelProvider> Agent<P> {
                         self.emit_event(
                             &run_id,
                             step,
-                            crate::events::EventKind::Error,
-                            serde_json::json!({
-                                "error": reason,
-                                "source": "implementation_integrity_guard",
-                                "reason_code": "post_write_guard_retry"
-                            }),
+                            ErrorPayload {
+                                error: reason,
+                                source: Some("implementation_integrity_guard".to_string()),
+                                reason_code: Some("post_write_guard_retry".to_string()),
+                                ..ErrorPayload::default()
+                            },
                         );
                         return VerifiedWriteResult::GuardRetry(
                             "You must read_file on a path before editing it. Use read_file to inspect the file contents first, then apply your changes, then read_file again to verify.".to_string(),
@@ -767,11 +812,11 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         &run_id,
                         step,
-                        crate::events::EventKind::Error,
-                        serde_json::json!({
-                            "error": reason,
-                            "source": "implementation_integrity_guard"
-                        }),
+                        ErrorPayload {
+                            error: reason.clone(),
+                            source: Some("implementation_integrity_guard".to_string()),
+                            ..ErrorPayload::default()
+                        },
                     );
                     return VerifiedWriteResult::Done(Box::new(
                         self.finalize_planner_error_with_end(
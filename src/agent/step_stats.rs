@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use super::timeline::{StepObservations, TimelineEntry, TimelineEntryKind};
+
+/// Timing and token accounting for one agent step, derived from the run
+/// timeline. Offsets are milliseconds from run start.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepStats {
+    pub step: u32,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub duration_ms: u64,
+    pub provider_calls: u32,
+    /// Summed duration of the step's provider calls, retries included.
+    pub provider_latency_ms: u64,
+    #[serde(default)]
+    pub tools: Vec<StepToolStats>,
+    /// Prompt tokens, when the provider reported usage for this step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_in: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_out: Option<u32>,
+    #[serde(default)]
+    pub compaction: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepToolStats {
+    pub tool_call_id: String,
+    pub name: String,
+    pub duration_ms: u64,
+    /// Size of the tool result handed back to the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    pub status: String,
+}
+
+impl StepStats {
+    pub fn tool_ms(&self) -> u64 {
+        self.tools.iter().map(|t| t.duration_ms).sum()
+    }
+}
+
+/// One entry per step span of `timeline`, in step order.
+pub(crate) fn step_stats_from_timeline(
+    timeline: &[TimelineEntry],
+    observed: &StepObservations,
+) -> Vec<StepStats> {
+    let mut steps = timeline
+        .iter()
+        .filter(|e| e.kind == TimelineEntryKind::Step)
+        .map(|e| {
+            let usage = observed.usage.get(&e.step);
+            StepStats {
+                step: e.step,
+                start_ms: e.start_ms,
+                end_ms: e.end_ms,
+                duration_ms: e.duration_ms,
+                tokens_in: usage.and_then(|u| u.prompt_tokens),
+                tokens_out: usage.and_then(|u| u.completion_tokens),
+                ..StepStats::default()
+            }
+        })
+        .collect::<Vec<_>>();
+    for entry in timeline {
+        let Some(stats) = steps.iter_mut().find(|s| s.step == entry.step) else {
+            continue;
+        };
+        match entry.kind {
+            TimelineEntryKind::ProviderCall => {
+                stats.provider_calls += 1;
+                stats.provider_latency_ms += entry.duration_ms;
+            }
+            TimelineEntryKind::ToolExec => {
+                let tool_call_id = entry.tool_call_id.clone().unwrap_or_default();
+                stats.tools.push(StepToolStats {
+                    bytes: observed.tool_bytes.get(&tool_call_id).copied(),
+                    tool_call_id,
+                    name: entry.name.clone().unwrap_or_default(),
+                    duration_ms: entry.duration_ms,
+                    status: entry.status.clone(),
+                });
+            }
+            TimelineEntryKind::Compaction => stats.compaction = true,
+            _ => {}
+        }
+    }
+    steps
+}
@@ -0,0 +1,275 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::agent_budget::ToolCallBudgetUsage;
+use crate::gate::NoGate;
+use crate::operator_queue::{QueueMessageKind, QueueSubmitRequest};
+use crate::providers::{http::RetryRecord, ModelProvider, StreamDelta};
+use crate::types::{GenerateRequest, GenerateResponse, Message, ToolCall};
+
+use super::{Agent, AgentOutcome};
+
+pub(super) const SPAWN_SUBTASK_TOOL: &str = "spawn_subtask";
+/// Deepest nesting of subtasks: the top-level run is depth 0, so a
+/// subtask's own subtask may not spawn another.
+pub(super) const MAX_SUBTASK_DEPTH: u32 = 2;
+
+/// The parent's provider, borrowed for the length of a child run. Every
+/// nesting level uses this same type, so spawning does not grow the type.
+struct SubtaskProvider<'a>(&'a dyn ModelProvider);
+
+#[async_trait]
+impl ModelProvider for SubtaskProvider<'_> {
+    async fn generate(&self, req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+        self.0.generate(req).await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.0.supports_streaming()
+    }
+
+    async fn generate_streaming(
+        &self,
+        req: GenerateRequest,
+        on_delta: &mut (dyn FnMut(StreamDelta) + Send),
+    ) -> anyhow::Result<GenerateResponse> {
+        self.0.generate_streaming(req, on_delta).await
+    }
+
+    fn take_retries(&self) -> Vec<RetryRecord> {
+        self.0.take_retries()
+    }
+}
+
+struct SubtaskArgs {
+    prompt: String,
+    allowed_tools: Option<Vec<String>>,
+    max_steps: Option<usize>,
+    max_tool_calls: Option<usize>,
+    max_wall_time_ms: Option<u64>,
+}
+
+impl SubtaskArgs {
+    /// Arguments were already checked by `validate_builtin_tool_args`.
+    fn parse(args: &serde_json::Value) -> Self {
+        let cap = |key: &str| args.get(key).and_then(serde_json::Value::as_u64);
+        Self {
+            prompt: args
+                .get("prompt")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            allowed_tools: args
+                .get("allowed_tools")
+                .and_then(serde_json::Value::as_array)
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|n| n.as_str().map(str::to_string))
+                        .collect()
+                }),
+            max_steps: cap("max_steps").map(|n| n as usize),
+            max_tool_calls: cap("max_tool_calls").map(|n| n as usize),
+            max_wall_time_ms: cap("max_wall_time_ms"),
+        }
+    }
+}
+
+/// Boxes the child run. A plain function rather than an inline `Box::pin`
+/// keeps the recursion (child runs may spawn subtasks too) out of the
+/// `async fn` bodies, so the compiler can still prove the future `Send`.
+fn run_child<'a>(
+    child: &'a mut Agent<SubtaskProvider<'_>>,
+    prompt: &'a str,
+) -> Pin<Box<dyn Future<Output = AgentOutcome> + Send + 'a>> {
+    Box::pin(child.run(prompt, Vec::new(), Vec::new()))
+}
+
+/// Half of what is left of a parent limit, further lowered by the
+/// requested cap. `0` means unlimited on both sides, as in
+/// [`super::ToolCallBudget`].
+fn child_limit(parent_limit: u64, parent_used: u64, requested: Option<u64>) -> u64 {
+    let share = if parent_limit == 0 {
+        0
+    } else {
+        (parent_limit.saturating_sub(parent_used) / 2).max(1)
+    };
+    match (share, requested) {
+        (0, Some(req)) => req,
+        (share, Some(req)) => share.min(req),
+        (share, None) => share,
+    }
+}
+
+impl<P: ModelProvider> Agent<P> {
+    /// Runs a `spawn_subtask` call as a child agent sharing this agent's
+    /// provider, tool runtime, gate, hooks, event sink and operator queue
+    /// receiver. Refusals and child failures come back as failed tool
+    /// results; they never end the parent run. An operator cancel the child
+    /// took is queued again here, so the parent stops at its next boundary.
+    pub(super) async fn run_subtask(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+        tool_budget_usage: &mut ToolCallBudgetUsage,
+    ) -> Message {
+        if self.subtask_depth >= MAX_SUBTASK_DEPTH {
+            return self.runtime_tool_failure_message(
+                tc,
+                format!("subtask depth limit reached ({MAX_SUBTASK_DEPTH}); do the work directly"),
+            );
+        }
+        let args = SubtaskArgs::parse(&tc.arguments);
+        let tools = match &args.allowed_tools {
+            Some(names) => {
+                if let Some(missing) = names
+                    .iter()
+                    .find(|name| !self.tools.iter().any(|t| &t.name == *name))
+                {
+                    return self.runtime_tool_failure_message(
+                        tc,
+                        format!(
+                            "cannot grant tool '{missing}' to a subtask: this run does not have it"
+                        ),
+                    );
+                }
+                self.tools
+                    .iter()
+                    .filter(|t| names.contains(&t.name))
+                    .cloned()
+                    .collect()
+            }
+            None => self
+                .tools
+                .iter()
+                .filter(|t| t.name != SPAWN_SUBTASK_TOOL)
+                .cloned()
+                .collect(),
+        };
+        let elapsed_ms = self
+            .run_started
+            .map(|started| started.elapsed().as_millis() as u64)
+            .unwrap_or(0);
+        let mut budget = self.tool_call_budget.clone();
+        budget.max_total_tool_calls = child_limit(
+            self.tool_call_budget.max_total_tool_calls as u64,
+            tool_budget_usage.total_tool_calls as u64,
+            args.max_tool_calls.map(|n| n as u64),
+        ) as usize;
+        budget.max_wall_time_ms = child_limit(
+            self.tool_call_budget.max_wall_time_ms,
+            elapsed_ms,
+            args.max_wall_time_ms,
+        );
+        budget.deadline_ms = child_limit(self.tool_call_budget.deadline_ms, elapsed_ms, None);
+        let child_run_id = format!("{run_id}.sub{}", self.subtask_run_ids.len() + 1);
+
+        let mut builder = Agent::builder(SubtaskProvider(&self.provider))
+            .model(self.model.clone())
+            .tools(tools)
+            .max_steps(
+                args.max_steps
+                    .map_or(self.max_steps, |n| n.min(self.max_steps)),
+            )
+            .tool_runtime(self.tool_rt.clone())
+            .gate_ctx(self.gate_ctx.clone())
+            .compaction(self.compaction_settings.clone())
+            .taint(self.taint_toggle, self.taint_mode)
+            .taint_digest_bytes(self.taint_digest_bytes)
+            .mcp_root_map(self.mcp_root_map.clone())
+            .mcp_pin_enforcement(self.mcp_pin_enforcement)
+            .tool_call_budget(budget)
+            .run_id(child_run_id.clone())
+            .max_consecutive_empty_responses(self.max_consecutive_empty_responses)
+            .parallel_readonly_tools(self.parallel_readonly_tools)
+            .approval_diff_max_lines(self.approval_diff_max_lines)
+            .hooks(self.hooks.clone())
+            .operator_queue_limits(self.operator_queue_limits.clone());
+        if let Some(policy) = &self.policy_for_taint {
+            builder = builder.taint_policy(policy.clone());
+        }
+        if let Some(registry) = &self.mcp_registry {
+            builder = builder.mcp_registry(registry.clone());
+        }
+        if let Some(temperature) = self.temperature {
+            builder = builder.temperature(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            builder = builder.top_p(top_p);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        let mut child = match builder.build() {
+            Ok(child) => child,
+            Err(e) => {
+                return self
+                    .runtime_tool_failure_message(tc, format!("failed to start subtask: {e}"));
+            }
+        };
+        // Lent for the child run and taken back below, like the gate.
+        child.gate = std::mem::replace(&mut self.gate, Box::new(NoGate::new()));
+        child.event_sink = self.event_sink.take();
+        child.operator_queue_rx = self.operator_queue_rx.take();
+        child.subtask_depth = self.subtask_depth + 1;
+        let outcome = run_child(&mut child, &args.prompt).await;
+        self.gate = std::mem::replace(&mut child.gate, Box::new(NoGate::new()));
+        self.event_sink = child.event_sink.take();
+        self.operator_queue_rx = child.operator_queue_rx.take();
+        let child_subtasks = std::mem::take(&mut child.subtask_run_ids);
+        let mut undelivered = child
+            .operator_queue
+            .pending()
+            .iter()
+            .map(|msg| QueueSubmitRequest {
+                kind: msg.kind,
+                content: msg.content.clone(),
+            })
+            .collect::<Vec<_>>();
+        drop(child);
+        if outcome.error.as_deref() == Some("operator_cancel") {
+            undelivered.push(QueueSubmitRequest {
+                kind: QueueMessageKind::Cancel,
+                content: format!("subtask {} was cancelled by the operator", outcome.run_id),
+            });
+        }
+        for req in undelivered {
+            self.submit_operator_request(run_id, step, req);
+        }
+
+        tool_budget_usage.total_tool_calls = tool_budget_usage
+            .total_tool_calls
+            .saturating_add(outcome.tool_calls.len());
+        self.subtask_run_ids.push(outcome.run_id.clone());
+        self.subtask_run_ids.extend(child_subtasks);
+        let ok = matches!(outcome.exit_reason, super::AgentExitReason::Ok);
+        let content = json!({
+            "run_id": outcome.run_id,
+            "exit_reason": outcome.exit_reason.as_str(),
+            "final_output": outcome.final_output,
+            "error": outcome.error,
+        });
+        self.runtime_tool_result_message(tc, ok, content.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::child_limit;
+
+    #[test]
+    fn child_gets_half_the_remaining_budget_capped_by_the_request() {
+        assert_eq!(child_limit(20, 6, None), 7);
+        assert_eq!(child_limit(20, 6, Some(3)), 3);
+        assert_eq!(child_limit(20, 20, None), 1);
+        assert_eq!(child_limit(0, 6, None), 0);
+        assert_eq!(child_limit(0, 6, Some(4)), 4);
+    }
+}
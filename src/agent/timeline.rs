@@ -0,0 +1,453 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::agent_utils::add_opt_u32;
+use crate::events::EventKind;
+use crate::types::TokenUsage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEntryKind {
+    Step,
+    ProviderCall,
+    ToolExec,
+    GateDecision,
+    Compaction,
+    QueueDelivery,
+    Hook,
+}
+
+/// One span of a run, with offsets in milliseconds from run start. Instant
+/// entries (gate decisions, compactions, queue deliveries) have zero length.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    pub seq: u32,
+    pub kind: TimelineEntryKind,
+    pub step: u32,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_seq: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub status: String,
+}
+
+/// Per-step data the timeline spans do not carry, kept for
+/// [`super::StepStats`].
+#[derive(Debug, Default)]
+pub(crate) struct StepObservations {
+    pub(crate) usage: BTreeMap<u32, TokenUsage>,
+    pub(crate) tool_bytes: BTreeMap<String, u64>,
+}
+
+const STATUS_OPEN: &str = "open";
+const STATUS_INCOMPLETE: &str = "incomplete";
+
+/// Builds the run timeline from the agent's own event stream, so it never
+/// touches the provider-visible transcript.
+#[derive(Debug)]
+pub struct TimelineRecorder {
+    started: Instant,
+    entries: Vec<TimelineEntry>,
+    open_step: Option<usize>,
+    open_provider_call: Option<usize>,
+    open_tools: BTreeMap<String, usize>,
+    exec_seq: u64,
+    observed: StepObservations,
+}
+
+impl Default for TimelineRecorder {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            entries: Vec::new(),
+            open_step: None,
+            open_provider_call: None,
+            open_tools: BTreeMap::new(),
+            exec_seq: 0,
+            observed: StepObservations::default(),
+        }
+    }
+}
+
+fn str_field(data: &Value, key: &str) -> Option<String> {
+    data.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+impl TimelineRecorder {
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    pub(crate) fn record(&mut self, step: u32, kind: &EventKind, data: &Value) {
+        self.record_at(self.now_ms(), step, kind, data);
+    }
+
+    fn record_at(&mut self, now: u64, step: u32, kind: &EventKind, data: &Value) {
+        let step = self.enter_step(now, step);
+        match kind {
+            EventKind::ModelRequestStart => {
+                if let Some(idx) = self.open_provider_call.take() {
+                    self.close(idx, now, STATUS_INCOMPLETE);
+                }
+                let idx = self.open(now, TimelineEntryKind::ProviderCall, step);
+                self.open_provider_call = Some(idx);
+            }
+            EventKind::ModelResponseEnd => {
+                if let Some(idx) = self.open_provider_call.take() {
+                    self.close(idx, now, "ok");
+                }
+                let usage = data
+                    .get("usage")
+                    .and_then(|u| serde_json::from_value::<TokenUsage>(u.clone()).ok());
+                if let Some(usage) = usage {
+                    let total = self.observed.usage.entry(step).or_default();
+                    total.prompt_tokens = add_opt_u32(total.prompt_tokens, usage.prompt_tokens);
+                    total.completion_tokens =
+                        add_opt_u32(total.completion_tokens, usage.completion_tokens);
+                    total.total_tokens = add_opt_u32(total.total_tokens, usage.total_tokens);
+                }
+            }
+            EventKind::ProviderError => {
+                if let Some(idx) = self.open_provider_call.take() {
+                    self.close(idx, now, "error");
+                }
+            }
+            EventKind::ToolExecStart => {
+                self.exec_seq += 1;
+                let id = str_field(data, "tool_call_id").unwrap_or_default();
+                let idx = self.open(now, TimelineEntryKind::ToolExec, step);
+                let entry = &mut self.entries[idx];
+                entry.exec_seq = Some(self.exec_seq);
+                entry.tool_call_id = Some(id.clone());
+                entry.name = str_field(data, "name");
+                if let Some(prev) = self.open_tools.insert(id, idx) {
+                    self.close(prev, now, STATUS_INCOMPLETE);
+                }
+            }
+            EventKind::ToolExecEnd => {
+                let id = str_field(data, "tool_call_id").unwrap_or_default();
+                if let Some(bytes) = data.get("output_bytes").and_then(Value::as_u64) {
+                    self.observed.tool_bytes.insert(id.clone(), bytes);
+                }
+                if let Some(idx) = self.open_tools.remove(&id) {
+                    let ok = data.get("ok").and_then(Value::as_bool).unwrap_or(false);
+                    self.close(idx, now, if ok { "ok" } else { "error" });
+                }
+            }
+            EventKind::ToolDecision => {
+                let decision = str_field(data, "decision").unwrap_or_default();
+                let idx = self.instant(now, TimelineEntryKind::GateDecision, step, &decision);
+                let entry = &mut self.entries[idx];
+                entry.tool_call_id = str_field(data, "tool_call_id");
+                entry.name = str_field(data, "name");
+            }
+            EventKind::CompactionPerformed => {
+                let idx = self.instant(now, TimelineEntryKind::Compaction, step, "ok");
+                self.entries[idx].name = str_field(data, "phase");
+            }
+            EventKind::QueueDelivered => {
+                let idx = self.instant(now, TimelineEntryKind::QueueDelivery, step, "delivered");
+                self.entries[idx].name = str_field(data, "kind");
+            }
+            EventKind::HookEnd => {
+                // Hook events are emitted after the hook ran; back-date the
+                // start by its measured duration, without leaving the step.
+                let duration = data.get("duration_ms").and_then(Value::as_u64).unwrap_or(0);
+                let floor = self
+                    .open_step
+                    .map(|idx| self.entries[idx].start_ms)
+                    .unwrap_or(0);
+                let start = now.saturating_sub(duration).max(floor);
+                let action = str_field(data, "action").unwrap_or_default();
+                let idx = self.open(start, TimelineEntryKind::Hook, step);
+                self.entries[idx].name = str_field(data, "hook_name");
+                self.close(idx, now, &action);
+            }
+            EventKind::HookError => {
+                let idx = self.instant(now, TimelineEntryKind::Hook, step, "error");
+                self.entries[idx].name = str_field(data, "stage");
+            }
+            _ => {}
+        }
+    }
+
+    /// Opens a step span when the run moves to a later step. Events tagged
+    /// with an earlier step stay attributed to the current one.
+    fn enter_step(&mut self, now: u64, step: u32) -> u32 {
+        if let Some(idx) = self.open_step {
+            let current = self.entries[idx].step;
+            if step <= current {
+                return current;
+            }
+            self.close_step_children(now);
+            self.close(idx, now, "ok");
+        }
+        let idx = self.open(now, TimelineEntryKind::Step, step);
+        self.open_step = Some(idx);
+        step
+    }
+
+    fn close_step_children(&mut self, now: u64) {
+        if let Some(idx) = self.open_provider_call.take() {
+            self.close(idx, now, STATUS_INCOMPLETE);
+        }
+        for idx in std::mem::take(&mut self.open_tools).into_values() {
+            self.close(idx, now, STATUS_INCOMPLETE);
+        }
+    }
+
+    fn open(&mut self, now: u64, kind: TimelineEntryKind, step: u32) -> usize {
+        self.entries.push(TimelineEntry {
+            seq: 0,
+            kind,
+            step,
+            start_ms: now,
+            end_ms: now,
+            duration_ms: 0,
+            exec_seq: None,
+            tool_call_id: None,
+            name: None,
+            status: STATUS_OPEN.to_string(),
+        });
+        self.entries.len() - 1
+    }
+
+    fn instant(&mut self, now: u64, kind: TimelineEntryKind, step: u32, status: &str) -> usize {
+        let idx = self.open(now, kind, step);
+        self.close(idx, now, status);
+        idx
+    }
+
+    fn close(&mut self, idx: usize, now: u64, status: &str) {
+        let entry = &mut self.entries[idx];
+        entry.end_ms = now.max(entry.start_ms);
+        entry.duration_ms = entry.end_ms - entry.start_ms;
+        entry.status = status.to_string();
+    }
+
+    /// Ordered copy of the timeline with anything still open closed at the
+    /// current offset; the last step closes `ok`, other spans `incomplete`.
+    pub(crate) fn snapshot(&self) -> Vec<TimelineEntry> {
+        self.snapshot_at(self.now_ms())
+    }
+
+    /// Per-step accounting for a [`Self::snapshot`] of this recorder.
+    pub(crate) fn step_stats(&self, timeline: &[TimelineEntry]) -> Vec<super::StepStats> {
+        super::step_stats::step_stats_from_timeline(timeline, &self.observed)
+    }
+
+    fn snapshot_at(&self, now: u64) -> Vec<TimelineEntry> {
+        let mut entries = self.entries.clone();
+        for entry in &mut entries {
+            if entry.status == STATUS_OPEN {
+                entry.end_ms = now.max(entry.start_ms);
+                entry.duration_ms = entry.end_ms - entry.start_ms;
+                entry.status = if entry.kind == TimelineEntryKind::Step {
+                    "ok".to_string()
+                } else {
+                    STATUS_INCOMPLETE.to_string()
+                };
+            }
+        }
+        entries.sort_by_key(|e| e.start_ms);
+        for (seq, entry) in entries.iter_mut().enumerate() {
+            entry.seq = seq as u32;
+        }
+        entries
+    }
+}
+
+/// Checks the invariants consumers rely on: ordered entries with consistent
+/// durations inside `wall_ms`, non-overlapping steps and provider calls, and
+/// every tool execution nested within its step.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn validate_timeline(entries: &[TimelineEntry], wall_ms: u64) -> Result<(), String> {
+    let mut last_start = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.seq as usize != i {
+            return Err(format!("entry {i} has seq {}", entry.seq));
+        }
+        if entry.start_ms < last_start {
+            return Err(format!("entry {i} starts before entry {}", i - 1));
+        }
+        last_start = entry.start_ms;
+        if entry.end_ms < entry.start_ms
+            || entry.duration_ms != entry.end_ms - entry.start_ms
+            || entry.end_ms > wall_ms
+        {
+            return Err(format!(
+                "entry {i} has inconsistent bounds {}..{} ({} ms, wall {wall_ms} ms)",
+                entry.start_ms, entry.end_ms, entry.duration_ms
+            ));
+        }
+    }
+    for kind in [TimelineEntryKind::Step, TimelineEntryKind::ProviderCall] {
+        let mut prev_end = 0;
+        for entry in entries.iter().filter(|e| e.kind == kind) {
+            if entry.start_ms < prev_end {
+                return Err(format!(
+                    "{kind:?} entry {} overlaps its predecessor",
+                    entry.seq
+                ));
+            }
+            prev_end = entry.end_ms;
+        }
+    }
+    for tool in entries
+        .iter()
+        .filter(|e| e.kind == TimelineEntryKind::ToolExec)
+    {
+        let nested = entries.iter().any(|s| {
+            s.kind == TimelineEntryKind::Step
+                && s.step == tool.step
+                && s.start_ms <= tool.start_ms
+                && tool.end_ms <= s.end_ms
+        });
+        if !nested {
+            return Err(format!(
+                "tool_exec entry {} is not nested within step {}",
+                tool.seq, tool.step
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn record(rec: &mut TimelineRecorder, now: u64, step: u32, kind: EventKind, data: Value) {
+        rec.record_at(now, step, &kind, &data);
+    }
+
+    fn two_step_recorder() -> TimelineRecorder {
+        let mut rec = TimelineRecorder::default();
+        let none = json!({});
+        record(&mut rec, 0, 0, EventKind::RunStart, none.clone());
+        record(&mut rec, 1, 0, EventKind::ModelRequestStart, none.clone());
+        record(&mut rec, 40, 0, EventKind::ModelResponseEnd, none.clone());
+        record(
+            &mut rec,
+            41,
+            0,
+            EventKind::ToolDecision,
+            json!({"tool_call_id": "tc1", "name": "shell", "decision": "allow"}),
+        );
+        record(
+            &mut rec,
+            42,
+            0,
+            EventKind::ToolExecStart,
+            json!({"tool_call_id": "tc1", "name": "shell"}),
+        );
+        record(
+            &mut rec,
+            70,
+            0,
+            EventKind::ToolExecEnd,
+            json!({"tool_call_id": "tc1", "name": "shell", "ok": false}),
+        );
+        record(
+            &mut rec,
+            75,
+            0,
+            EventKind::HookEnd,
+            json!({"hook_name": "redact", "action": "pass", "duration_ms": 4}),
+        );
+        record(&mut rec, 80, 1, EventKind::ModelRequestStart, none.clone());
+        record(&mut rec, 90, 1, EventKind::ProviderError, none.clone());
+        record(&mut rec, 95, 1, EventKind::ModelRequestStart, none.clone());
+        record(
+            &mut rec,
+            96,
+            1,
+            EventKind::CompactionPerformed,
+            json!({"phase": "pre_request"}),
+        );
+        record(&mut rec, 120, 1, EventKind::ModelResponseEnd, none.clone());
+        record(&mut rec, 121, 1, EventKind::RunEnd, none);
+        rec
+    }
+
+    #[test]
+    fn records_steps_calls_and_instants_in_order() {
+        let entries = two_step_recorder().snapshot_at(125);
+        validate_timeline(&entries, 125).expect("valid timeline");
+        let kinds = entries.iter().map(|e| e.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                TimelineEntryKind::Step,
+                TimelineEntryKind::ProviderCall,
+                TimelineEntryKind::GateDecision,
+                TimelineEntryKind::ToolExec,
+                TimelineEntryKind::Hook,
+                TimelineEntryKind::Step,
+                TimelineEntryKind::ProviderCall,
+                TimelineEntryKind::ProviderCall,
+                TimelineEntryKind::Compaction,
+            ]
+        );
+        let tool = &entries[3];
+        assert_eq!(tool.exec_seq, Some(1));
+        assert_eq!(tool.tool_call_id.as_deref(), Some("tc1"));
+        assert_eq!(
+            (tool.start_ms, tool.end_ms, tool.status.as_str()),
+            (42, 70, "error")
+        );
+        assert_eq!((entries[4].start_ms, entries[4].duration_ms), (71, 4));
+        assert_eq!(entries[6].status, "error");
+        assert_eq!(entries[7].status, "ok");
+        assert_eq!((entries[0].end_ms, entries[5].end_ms), (80, 125));
+    }
+
+    #[test]
+    fn open_spans_close_incomplete_at_step_change_and_snapshot() {
+        let mut rec = TimelineRecorder::default();
+        record(
+            &mut rec,
+            0,
+            0,
+            EventKind::ToolExecStart,
+            json!({"tool_call_id": "tc1", "name": "shell"}),
+        );
+        record(&mut rec, 10, 1, EventKind::ModelRequestStart, json!({}));
+        let entries = rec.snapshot_at(30);
+        validate_timeline(&entries, 30).expect("valid timeline");
+        assert_eq!(entries[1].status, "incomplete");
+        assert_eq!(entries[1].end_ms, 10);
+        assert_eq!(entries[3].status, "incomplete");
+        assert_eq!(entries[3].end_ms, 30);
+    }
+
+    #[test]
+    fn validation_rejects_overlap_and_unnested_tools() {
+        let mut entries = two_step_recorder().snapshot_at(125);
+        assert!(validate_timeline(&entries, 100)
+            .unwrap_err()
+            .contains("inconsistent bounds"));
+
+        let mut overlapping = entries.clone();
+        overlapping[6].end_ms = 100;
+        overlapping[6].duration_ms = 100 - overlapping[6].start_ms;
+        assert!(validate_timeline(&overlapping, 125)
+            .unwrap_err()
+            .contains("overlaps"));
+
+        entries[3].step = 1;
+        assert!(validate_timeline(&entries, 125)
+            .unwrap_err()
+            .contains("not nested"));
+    }
+}
@@ -24,6 +24,29 @@ impl<P: ModelProvider> Agent<P> {
     }
 
     pub(super) fn tool_timeout_message(&self, tc: &ToolCall, timeout_ms: u64) -> Message {
+        self.runtime_tool_failure_message(
+            tc,
+            format!(
+                "tool execution timed out after {}ms (runtime timeout)",
+                timeout_ms
+            ),
+        )
+    }
+
+    /// Failed result for a call the runtime stopped or never ran, so the
+    /// transcript keeps one result per tool call.
+    pub(super) fn runtime_tool_failure_message(&self, tc: &ToolCall, content: String) -> Message {
+        self.runtime_tool_result_message(tc, false, content)
+    }
+
+    /// Result envelope for a call the runtime handles itself instead of
+    /// dispatching it to a tool implementation.
+    pub(super) fn runtime_tool_result_message(
+        &self,
+        tc: &ToolCall,
+        ok: bool,
+        content: String,
+    ) -> Message {
         let source = if tc.name.starts_with("mcp.") {
             "mcp"
         } else {
@@ -32,19 +55,20 @@ impl<P: ModelProvider> Agent<P> {
         let execution_target = if source == "mcp" {
             "host".to_string()
         } else {
-            match self.tool_rt.exec_target_kind {
+            match self
+                .tool_rt
+                .exec_target_kind_for(crate::tools::tool_side_effects(&tc.name))
+            {
                 crate::target::ExecTargetKind::Host => "host".to_string(),
                 crate::target::ExecTargetKind::Docker => "docker".to_string(),
+                crate::target::ExecTargetKind::Ssh => "ssh".to_string(),
             }
         };
         envelope_to_message(to_tool_result_envelope(
             tc,
             source,
-            false,
-            format!(
-                "tool execution timed out after {}ms (runtime timeout)",
-                timeout_ms
-            ),
+            ok,
+            content,
             false,
             ToolResultMeta {
                 side_effects: tool_side_effects(&tc.name),
@@ -58,6 +82,10 @@ impl<P: ModelProvider> Agent<P> {
                 warnings_max: None,
                 warnings_truncated: None,
                 docker: None,
+                write_protection: None,
+                cwd: None,
+                timed_out: None,
+                dry_run: None,
             },
         ))
     }
@@ -411,10 +411,8 @@ pub(crate) fn read_before_edit_violation_from_facts(
     ordered.sort_by_key(|fact| fact.sequence());
     for fact in ordered {
         match fact {
-            ToolFactV1::Read { path, ok, .. } => {
-                if *ok {
-                    successful_read_paths.insert(path.clone());
-                }
+            ToolFactV1::Read { path, ok, .. } if *ok => {
+                successful_read_paths.insert(path.clone());
             }
             ToolFactV1::Write { tool, path, ok, .. } => {
                 if !ok {
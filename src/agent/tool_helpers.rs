@@ -3,14 +3,22 @@ use crate::agent_taint_helpers::compute_taint_spans_for_tool;
 use crate::agent_tool_exec::{run_tool_once, tool_result_has_error};
 use crate::agent_utils::provider_name;
 use crate::agent_utils::sha256_hex;
-use crate::events::EventKind;
+use crate::events::{
+    ErrorPayload, HookBudgetExhaustedPayload, HookEndPayload, HookErrorPayload, HookStartPayload,
+    McpCancelledPayload, McpProgressPayload, McpServerDegradedPayload, McpServerReconnectedPayload,
+    PlanItemPayload, PlanUpdatedPayload, PostWriteVerifyEndPayload, PostWriteVerifyStartPayload,
+    SecretRedactedPayload, ShellOutputChunkPayload, StepBlockedPayload, TaintUpdatedPayload,
+    ToolDecisionPayload, ToolExecEndPayload, ToolExecProgressPayload, ToolOutputTruncatedPayload,
+    ToolRetryPayload, WriteSkippedDryRunPayload,
+};
 use crate::hooks::protocol::{HookInvocationReport, ToolResultPayload};
-use crate::hooks::runner::make_tool_result_input;
+use crate::hooks::runner::{make_tool_result_input, HookBudgetExhaustion};
 use crate::providers::ModelProvider;
 use crate::tools::ToolErrorCode;
 use crate::types::{Message, Role, ToolCall};
 
 use super::run_events::ToolRetryEvent;
+use super::write_attribution::attach_attribution_records;
 use super::Agent;
 use super::INTERNAL_ENFORCE_IMPLEMENTATION_GUARD_FLAG;
 
@@ -37,6 +45,66 @@ pub(super) fn normalized_tool_path_from_args(tc: &ToolCall) -> Option<String> {
         .map(normalize_tool_path)
 }
 
+/// `WriteSkippedDryRun` payload for a successful `--dry-run-writes` result.
+fn dry_run_write_skipped(tc: &ToolCall, content: &str) -> Option<WriteSkippedDryRunPayload> {
+    let envelope = serde_json::from_str::<serde_json::Value>(content).ok()?;
+    let meta = envelope.get("meta")?;
+    if meta.get("dry_run").and_then(|v| v.as_bool()) != Some(true) {
+        return None;
+    }
+    let inner = envelope
+        .get("content")
+        .and_then(|c| c.as_str())
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(c).ok())
+        .unwrap_or_default();
+    let paths = match inner.get("files").and_then(|f| f.as_array()) {
+        Some(files) => files
+            .iter()
+            .filter_map(|f| f.get("path").and_then(|p| p.as_str()))
+            .map(str::to_string)
+            .collect(),
+        None => inner
+            .get("path")
+            .and_then(|p| p.as_str())
+            .or_else(|| tc.arguments.get("path").and_then(|p| p.as_str()))
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default(),
+    };
+    Some(WriteSkippedDryRunPayload {
+        tool_call_id: tc.id.clone(),
+        name: tc.name.clone(),
+        paths,
+        bytes: meta.get("bytes").and_then(|b| b.as_u64()),
+    })
+}
+
+/// One payload per distinct digest in the envelope's `redaction_digests`,
+/// in order of first appearance.
+fn secret_redactions(tc: &ToolCall, content: &str) -> Vec<SecretRedactedPayload> {
+    let Ok(envelope) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let digests = envelope
+        .get("redaction_digests")
+        .and_then(|d| d.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|d| d.as_str());
+    let mut out: Vec<SecretRedactedPayload> = Vec::new();
+    for digest in digests {
+        match out.iter_mut().find(|p| p.digest == digest) {
+            Some(existing) => existing.occurrences += 1,
+            None => out.push(SecretRedactedPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                digest: digest.to_string(),
+                occurrences: 1,
+            }),
+        }
+    }
+    out
+}
+
 pub(super) fn injected_messages_enforce_implementation_integrity_guard(
     messages: &[Message],
 ) -> bool {
@@ -107,6 +175,16 @@ pub(super) enum FailedRepeatGuardDecision {
 const SHELL_STREAM_MAX_BYTES: usize = 64 * 1024;
 const SHELL_STREAM_CHANNEL_CAPACITY: usize = 128;
 
+/// Extra time past the runtime deadline for builtin calls that enforce
+/// `tool_timeout_ms` themselves; longer than the shell grace in `tools`.
+const TOOL_TIMEOUT_BACKSTOP_GRACE_MS: u64 = 20_000;
+
+/// Tail bytes of each stream carried in a `ToolExecProgress` preview.
+const TOOL_EXEC_PROGRESS_PREVIEW_BYTES: usize = 512;
+/// Minimum gap between `ToolExecProgress` events for one call; the last
+/// update is always flushed when the command finishes.
+const TOOL_EXEC_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
 #[derive(Default)]
 struct ShellUtf8Decoder {
     pending: Vec<u8>,
@@ -279,16 +357,126 @@ fn truncate_str_to_utf8_budget(input: &str, max_bytes: usize) -> (String, bool)
     (input[..end].to_string(), true)
 }
 
+#[derive(Default)]
+struct StreamProgress {
+    bytes_so_far: u64,
+    tail: std::collections::VecDeque<u8>,
+    dirty: bool,
+}
+
+impl StreamProgress {
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes_so_far += bytes.len() as u64;
+        let keep = bytes.len().min(TOOL_EXEC_PROGRESS_PREVIEW_BYTES);
+        self.tail.extend(&bytes[bytes.len() - keep..]);
+        let excess = self
+            .tail
+            .len()
+            .saturating_sub(TOOL_EXEC_PROGRESS_PREVIEW_BYTES);
+        self.tail.drain(..excess);
+        self.dirty = true;
+    }
+
+    fn preview(&self) -> String {
+        // Skip continuation bytes so the preview never starts mid-codepoint.
+        let start = self
+            .tail
+            .iter()
+            .take(3)
+            .take_while(|b| (**b & 0b1100_0000) == 0b1000_0000)
+            .count();
+        let bytes = self.tail.iter().skip(start).copied().collect::<Vec<_>>();
+        String::from_utf8_lossy(&bytes).to_string()
+    }
+}
+
+/// Tracks per-stream byte counts and a bounded tail for `ToolExecProgress`
+/// events. Unlike [`ShellStreamCoalescer`] it never stops counting, so the
+/// totals stay accurate after the live output budget is spent.
+#[derive(Default)]
+struct ToolExecProgressTracker {
+    stdout: StreamProgress,
+    stderr: StreamProgress,
+    last_emit: Option<std::time::Instant>,
+}
+
+impl ToolExecProgressTracker {
+    fn ingest(&mut self, batch: &[crate::target::ShellOutputChunk]) {
+        for chunk in batch {
+            match chunk.stream {
+                crate::target::ShellStreamKind::Stdout => self.stdout.push(&chunk.bytes),
+                crate::target::ShellStreamKind::Stderr => self.stderr.push(&chunk.bytes),
+            }
+        }
+    }
+
+    /// Updates to emit now: streams that changed since the last emission,
+    /// once the interval has passed (or unconditionally when `force`).
+    fn take_due(
+        &mut self,
+        now: std::time::Instant,
+        force: bool,
+    ) -> Vec<(&'static str, u64, String)> {
+        if !force
+            && self
+                .last_emit
+                .is_some_and(|at| now.duration_since(at) < TOOL_EXEC_PROGRESS_INTERVAL)
+        {
+            return Vec::new();
+        }
+        let mut due = Vec::new();
+        for (name, stream) in [("stdout", &mut self.stdout), ("stderr", &mut self.stderr)] {
+            if stream.dirty {
+                stream.dirty = false;
+                due.push((name, stream.bytes_so_far, stream.preview()));
+            }
+        }
+        if !due.is_empty() {
+            self.last_emit = Some(now);
+        }
+        due
+    }
+}
+
 impl<P: ModelProvider> Agent<P> {
+    /// Live chunks and progress previews bypass the result redactor, and a
+    /// secret can straddle two chunks, so nothing streams while redaction is on.
     fn should_stream_shell_output(&self, tc: &ToolCall) -> bool {
         self.event_sink.is_some()
+            && self.tool_rt.secret_redactor.is_none()
             && tc.name == "shell"
             && matches!(
-                self.tool_rt.exec_target_kind,
+                self.tool_rt
+                    .exec_target_kind_for(crate::types::SideEffects::ShellExec),
                 crate::target::ExecTargetKind::Host
             )
     }
 
+    fn emit_tool_exec_progress(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tool_call_id: &str,
+        progress: &mut Option<ToolExecProgressTracker>,
+        force: bool,
+    ) {
+        let Some(tracker) = progress.as_mut() else {
+            return;
+        };
+        for (stream, bytes_so_far, preview) in tracker.take_due(std::time::Instant::now(), force) {
+            self.emit_event(
+                run_id,
+                step,
+                ToolExecProgressPayload {
+                    tool_call_id: tool_call_id.to_string(),
+                    stream: stream.to_string(),
+                    bytes_so_far,
+                    preview,
+                },
+            );
+        }
+    }
+
     fn emit_shell_stream_action(
         &mut self,
         run_id: &str,
@@ -301,24 +489,22 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::ShellOutputChunk,
-                    serde_json::json!({
-                        "tool_call_id": coalescer.tool_call_id,
-                        "stream": stream,
-                        "chunk": text,
-                    }),
+                    ShellOutputChunkPayload {
+                        tool_call_id: coalescer.tool_call_id.clone(),
+                        stream: stream.to_string(),
+                        chunk: text,
+                    },
                 );
             }
             ShellStreamAction::BudgetReached => {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::ShellOutputChunk,
-                    serde_json::json!({
-                        "tool_call_id": coalescer.tool_call_id,
-                        "stream": "meta",
-                        "chunk": "[live output truncated; see final result]",
-                    }),
+                    ShellOutputChunkPayload {
+                        tool_call_id: coalescer.tool_call_id.clone(),
+                        stream: "meta".to_string(),
+                        chunk: "[live output truncated; see final result]".to_string(),
+                    },
                 );
             }
         }
@@ -349,6 +535,10 @@ impl<P: ModelProvider> Agent<P> {
         let deadline = tokio::time::sleep(dur);
         tokio::pin!(deadline);
         let mut coalescer = ShellStreamCoalescer::new(tc.id.clone());
+        let mut progress = self
+            .tool_rt
+            .stream_tool_output
+            .then(ToolExecProgressTracker::default);
 
         loop {
             tokio::select! {
@@ -359,12 +549,16 @@ impl<P: ModelProvider> Agent<P> {
                     while let Ok(c) = chunk_rx.try_recv() {
                         batch.push(c);
                     }
+                    if let Some(tracker) = progress.as_mut() {
+                        tracker.ingest(&batch);
+                    }
                     for action in coalescer.ingest(&batch) {
                         self.emit_shell_stream_action(run_id, step, &coalescer, action);
                     }
                     for action in coalescer.flush_pending() {
                         self.emit_shell_stream_action(run_id, step, &coalescer, action);
                     }
+                    self.emit_tool_exec_progress(run_id, step, &tc.id, &mut progress, true);
                     return Ok(res);
                 }
                 maybe = chunk_rx.recv() => {
@@ -375,9 +569,13 @@ impl<P: ModelProvider> Agent<P> {
                         while let Ok(c) = chunk_rx.try_recv() {
                             batch.push(c);
                         }
+                        if let Some(tracker) = progress.as_mut() {
+                            tracker.ingest(&batch);
+                        }
                         for action in coalescer.ingest(&batch) {
                             self.emit_shell_stream_action(run_id, step, &coalescer, action);
                         }
+                        self.emit_tool_exec_progress(run_id, step, &tc.id, &mut progress, false);
                     }
                 }
                 _ = &mut deadline => {
@@ -387,6 +585,22 @@ impl<P: ModelProvider> Agent<P> {
         }
     }
 
+    /// Deadline for one run of `tc`. Builtin calls bounded by
+    /// `tool_timeout_ms` stop their own processes and report `timed_out`;
+    /// this deadline only backstops them.
+    pub(super) fn tool_run_timeout(&self, tc: &ToolCall) -> std::time::Duration {
+        let backstop_grace_ms =
+            if self.tool_rt.tool_timeout_ms.is_some() && !tc.name.starts_with("mcp.") {
+                TOOL_TIMEOUT_BACKSTOP_GRACE_MS
+            } else {
+                0
+            };
+        std::time::Duration::from_millis(
+            self.effective_tool_exec_timeout_ms()
+                .saturating_add(backstop_grace_ms),
+        )
+    }
+
     pub(super) async fn run_tool_with_timeout_and_emit_mcp_events(
         &mut self,
         run_id: &str,
@@ -394,15 +608,22 @@ impl<P: ModelProvider> Agent<P> {
         tc: &ToolCall,
         phase: &str,
     ) -> Message {
+        if let Some(denied) = self.mcp_root_denial_message(tc) {
+            return denied;
+        }
         let tool_exec_timeout_ms = self.effective_tool_exec_timeout_ms();
-        let dur = std::time::Duration::from_millis(tool_exec_timeout_ms);
-        let run_result = if self.should_stream_shell_output(tc) {
+        let dur = self.tool_run_timeout(tc);
+        let attributed_write = self.attribute_write_call(run_id, step, tc);
+        let exec_tc = attributed_write.as_ref().map_or(tc, |(call, _)| call);
+        let run_result = if let Some(batched) = self.take_batched_tool_run(tc) {
+            batched
+        } else if self.should_stream_shell_output(tc) {
             self.run_tool_once_with_live_stream(run_id, step, tc, dur)
                 .await
         } else {
             tokio::time::timeout(
                 dur,
-                run_tool_once(&self.tool_rt, tc, self.mcp_registry.as_ref(), None),
+                run_tool_once(&self.tool_rt, exec_tc, self.mcp_registry.as_ref(), None),
             )
             .await
             .map_err(|_| ())
@@ -417,14 +638,14 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::Error,
-                    serde_json::json!({
-                        "error": reason,
-                        "source": "runtime_tool_timeout",
-                        "tool_call_id": tc.id,
-                        "name": tc.name,
-                        "timeout_ms": tool_exec_timeout_ms
-                    }),
+                    ErrorPayload {
+                        error: reason,
+                        source: Some("runtime_tool_timeout".to_string()),
+                        tool_call_id: Some(tc.id.clone()),
+                        name: Some(tc.name.clone()),
+                        timeout_ms: Some(tool_exec_timeout_ms),
+                        ..ErrorPayload::default()
+                    },
                 );
                 return self.tool_timeout_message(tc, tool_exec_timeout_ms);
             }
@@ -434,31 +655,87 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::McpProgress,
-                    serde_json::json!({
-                        "tool_call_id": tc.id,
-                        "name": tc.name,
-                        "progress_ticks": meta.progress_ticks,
-                        "elapsed_ms": meta.elapsed_ms,
-                        "phase": phase
-                    }),
+                    McpProgressPayload {
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        progress_ticks: meta.progress_ticks,
+                        elapsed_ms: meta.elapsed_ms,
+                        phase: phase.to_string(),
+                    },
                 );
             }
             if meta.cancelled {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::McpCancelled,
-                    serde_json::json!({
-                        "tool_call_id": tc.id,
-                        "name": tc.name,
-                        "reason": "timeout",
-                        "elapsed_ms": meta.elapsed_ms
-                    }),
+                    McpCancelledPayload {
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        reason: "timeout".to_string(),
+                        elapsed_ms: meta.elapsed_ms,
+                    },
                 );
             }
+            if let Some(reconnected) = meta.reconnected {
+                self.emit_event(
+                    run_id,
+                    step,
+                    McpServerReconnectedPayload {
+                        server: reconnected.server,
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        degraded_ms: reconnected.degraded_ms,
+                        reconnect_attempts: reconnected.reconnect_attempts,
+                    },
+                );
+            }
+            if let Some(degraded) = meta.degraded {
+                self.emit_event(
+                    run_id,
+                    step,
+                    McpServerDegradedPayload {
+                        server: degraded.server,
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        reason: degraded.reason,
+                        reconnect_attempts: degraded.reconnect_attempts,
+                        max_reconnect_attempts: degraded.max_reconnect_attempts,
+                        retry_after_ms: degraded.retry_after_ms,
+                    },
+                );
+            }
+            if let Some(limit) = meta.output_limit {
+                self.emit_event(
+                    run_id,
+                    step,
+                    ToolOutputTruncatedPayload {
+                        tool_call_id: tc.id.clone(),
+                        name: tc.name.clone(),
+                        server: limit.server,
+                        tool: limit.tool,
+                        original_bytes: limit.original_bytes,
+                        kept_bytes: limit.kept_bytes,
+                        limit_bytes: limit.limit_bytes,
+                        rejected: limit.rejected,
+                    },
+                );
+            }
+        }
+        let mut message = outcome.message;
+        if tool_result_has_error(message.content.as_deref().unwrap_or_default()) {
+            return message;
+        }
+        let attributions = match attributed_write {
+            Some((_, record)) => vec![record],
+            None => self.attribute_patched_files(run_id, step, tc).await,
+        };
+        for record in &attributions {
+            self.emit_attribution_injected(run_id, step, tc, record);
+        }
+        if !attributions.is_empty() {
+            message = attach_attribution_records(message, &attributions);
         }
-        outcome.message
+        message
     }
 
     pub(super) async fn apply_tool_result_hooks(
@@ -467,6 +744,7 @@ impl<P: ModelProvider> Agent<P> {
         step: u32,
         tc: &ToolCall,
         tool_msg: Message,
+        exec_seq: u64,
         hook_invocations: &mut Vec<HookInvocationReport>,
     ) -> Result<ToolResultHookState, String> {
         let original_content = tool_msg.content.clone().unwrap_or_default();
@@ -490,7 +768,7 @@ impl<P: ModelProvider> Agent<P> {
             content: original_content.clone(),
             truncated: state.final_truncated,
         };
-        let hook_input = make_tool_result_input(
+        let mut hook_input = make_tool_result_input(
             run_id,
             step,
             provider_name(self.gate_ctx.provider),
@@ -502,13 +780,18 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         run_id,
                         step,
-                        EventKind::HookError,
-                        serde_json::json!({"stage":"tool_result","error": e.to_string()}),
+                        HookErrorPayload {
+                            stage: "tool_result".to_string(),
+                            error: e.to_string(),
+                        },
                     );
                     return Err(format!("failed to encode tool_result hook payload: {e}"));
                 }
             },
         );
+        hook_input.exec_seq = Some(exec_seq);
+        // Tool result hooks only see calls the gate allowed.
+        hook_input.decision = Some("allow".to_string());
         match self
             .hooks
             .run_tool_result_hooks(
@@ -520,29 +803,32 @@ impl<P: ModelProvider> Agent<P> {
             .await
         {
             Ok(hook_out) => {
-                for inv in &hook_out.invocations {
+                self.emit_hook_budget_exhausted(run_id, step, &hook_out.budget_exhausted);
+                for inv in hook_out
+                    .invocations
+                    .iter()
+                    .filter(|inv| !inv.skipped_due_to_budget)
+                {
                     self.emit_event(
                         run_id,
                         step,
-                        EventKind::HookStart,
-                        serde_json::json!({
-                            "hook_name": inv.hook_name,
-                            "stage": inv.stage
-                        }),
+                        HookStartPayload {
+                            hook_name: inv.hook_name.clone(),
+                            stage: inv.stage.clone(),
+                        },
                     );
                     self.emit_event(
                         run_id,
                         step,
-                        EventKind::HookEnd,
-                        serde_json::json!({
-                            "hook_name": inv.hook_name,
-                            "stage": inv.stage,
-                            "action": inv.action,
-                            "modified": inv.modified,
-                            "duration_ms": inv.duration_ms,
-                            "input_digest": inv.input_digest,
-                            "output_digest": inv.output_digest
-                        }),
+                        HookEndPayload {
+                            hook_name: inv.hook_name.clone(),
+                            stage: inv.stage.clone(),
+                            action: inv.action.clone(),
+                            modified: inv.modified,
+                            duration_ms: inv.duration_ms as u64,
+                            input_digest: Some(inv.input_digest.clone()),
+                            output_digest: Some(inv.output_digest.clone()),
+                        },
                     );
                 }
                 hook_invocations.extend(hook_out.invocations);
@@ -561,14 +847,57 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::HookError,
-                    serde_json::json!({"stage":"tool_result","error": e.message}),
+                    HookErrorPayload {
+                        stage: "tool_result".to_string(),
+                        error: e.message.clone(),
+                    },
                 );
                 Err(e.message)
             }
         }
     }
 
+    pub(super) fn emit_hook_budget_exhausted(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        exhausted: &[HookBudgetExhaustion],
+    ) {
+        for e in exhausted {
+            self.emit_event(
+                run_id,
+                step,
+                HookBudgetExhaustedPayload {
+                    hook_name: e.hook_name.clone(),
+                    stage: e.stage.clone(),
+                    limit: e.limit.clone(),
+                    limit_value: e.limit_value,
+                    invocations: e.invocations,
+                    cumulative_ms: e.cumulative_ms,
+                },
+            );
+        }
+    }
+
+    /// Why `tc`'s arguments fail its tool's schema, if they do.
+    pub(super) fn tool_call_args_error(&self, tc: &ToolCall) -> Option<String> {
+        if tc.name.starts_with("mcp.") {
+            self.mcp_registry.as_ref().and_then(|reg| {
+                reg.validate_namespaced_tool_args(tc, self.tool_rt.tool_args_strict)
+                    .err()
+            })
+        } else {
+            let normalized_args =
+                crate::tools::normalize_builtin_tool_args(&tc.name, &tc.arguments);
+            crate::tools::validate_builtin_tool_args(
+                &tc.name,
+                &normalized_args,
+                self.tool_rt.tool_args_strict,
+            )
+            .err()
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(super) fn handle_malformed_tool_call(
         &mut self,
@@ -591,21 +920,7 @@ impl<P: ModelProvider> Agent<P> {
         total_token_usage: &crate::types::TokenUsage,
         taint_state: &crate::taint::TaintState,
     ) -> MalformedToolCallDecision {
-        let invalid_args_error = if tc.name.starts_with("mcp.") {
-            self.mcp_registry.as_ref().and_then(|reg| {
-                reg.validate_namespaced_tool_args(tc, self.tool_rt.tool_args_strict)
-                    .err()
-            })
-        } else {
-            let normalized_args =
-                crate::tools::normalize_builtin_tool_args(&tc.name, &tc.arguments);
-            crate::tools::validate_builtin_tool_args(
-                &tc.name,
-                &normalized_args,
-                self.tool_rt.tool_args_strict,
-            )
-            .err()
-        };
+        let invalid_args_error = self.tool_call_args_error(tc);
 
         let Some(err) = invalid_args_error.as_ref() else {
             return MalformedToolCallDecision::ContinueToolLoop {
@@ -622,15 +937,15 @@ impl<P: ModelProvider> Agent<P> {
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::Error,
-                serde_json::json!({
-                    "error": reason,
-                    "source": "tool_protocol_guard",
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "failure_class": "E_SCHEMA",
-                    "attempt": *malformed_tool_call_attempts
-                }),
+                ErrorPayload {
+                    error: reason.clone(),
+                    source: Some("tool_protocol_guard".to_string()),
+                    tool_call_id: Some(tc.id.clone()),
+                    name: Some(tc.name.clone()),
+                    failure_class: Some("E_SCHEMA".to_string()),
+                    attempt: Some(*malformed_tool_call_attempts),
+                    ..ErrorPayload::default()
+                },
             );
             return MalformedToolCallDecision::Finalize(Box::new(
                 self.finalize_planner_error_with_output_with_end(
@@ -662,39 +977,47 @@ impl<P: ModelProvider> Agent<P> {
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::ToolRetry,
-                serde_json::json!({
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "attempt": *attempts,
-                    "max_retries": super::MAX_SCHEMA_REPAIR_ATTEMPTS,
-                    "max_attempts": super::MAX_SCHEMA_REPAIR_ATTEMPTS,
-                    "failure_class": "E_SCHEMA",
-                    "action": "repair",
-                    "error_code": crate::tools::ToolErrorCode::ToolArgsInvalid.as_str()
-                }),
+                ToolRetryPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    attempt: *attempts,
+                    max_retries: super::MAX_SCHEMA_REPAIR_ATTEMPTS,
+                    max_attempts: Some(super::MAX_SCHEMA_REPAIR_ATTEMPTS),
+                    failure_class: "E_SCHEMA".to_string(),
+                    action: "repair".to_string(),
+                    error_code: Some(
+                        crate::tools::ToolErrorCode::ToolArgsInvalid
+                            .as_str()
+                            .to_string(),
+                    ),
+                },
             );
             let tool_msg = crate::agent_tool_exec::make_invalid_args_tool_message(
                 tc,
                 err,
-                self.tool_rt.exec_target_kind,
+                self.tool_rt
+                    .exec_target_kind_for(crate::tools::tool_side_effects(&tc.name)),
             );
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::ToolExecEnd,
-                serde_json::json!({
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "ok": false,
-                    "truncated": false,
-                    "retry_count": 0,
-                    "failure_class": "E_SCHEMA",
-                    "source": "schema_repair",
-                    "repair_attempted": true,
-                    "repair_succeeded": false,
-                    "error_code": crate::tools::ToolErrorCode::ToolArgsInvalid.as_str()
-                }),
+                ToolExecEndPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    ok: false,
+                    truncated: false,
+                    retry_count: Some(0),
+                    failure_class: Some(Some("E_SCHEMA".to_string())),
+                    source: Some("schema_repair".to_string()),
+                    repair_attempted: Some(true),
+                    repair_succeeded: Some(false),
+                    error_code: Some(Some(
+                        crate::tools::ToolErrorCode::ToolArgsInvalid
+                            .as_str()
+                            .to_string(),
+                    )),
+                    ..ToolExecEndPayload::default()
+                },
             );
             messages.push(tool_msg);
             if self.inject_post_tool_operator_messages(&run_id, step, messages) {
@@ -709,32 +1032,35 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            EventKind::ToolRetry,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "attempt": *attempts,
-                "max_retries": super::MAX_SCHEMA_REPAIR_ATTEMPTS,
-                "max_attempts": super::MAX_SCHEMA_REPAIR_ATTEMPTS,
-                "failure_class": "E_SCHEMA",
-                "action": "stop",
-                "error_code": crate::tools::ToolErrorCode::ToolArgsInvalid.as_str()
-            }),
+            ToolRetryPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                attempt: *attempts,
+                max_retries: super::MAX_SCHEMA_REPAIR_ATTEMPTS,
+                max_attempts: Some(super::MAX_SCHEMA_REPAIR_ATTEMPTS),
+                failure_class: "E_SCHEMA".to_string(),
+                action: "stop".to_string(),
+                error_code: Some(
+                    crate::tools::ToolErrorCode::ToolArgsInvalid
+                        .as_str()
+                        .to_string(),
+                ),
+            },
         );
         if *attempts > super::MAX_SCHEMA_REPAIR_ATTEMPTS {
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::Error,
-                serde_json::json!({
-                    "error": "schema repair attempts exhausted",
-                    "source": "schema_repair",
-                    "code": "TOOL_SCHEMA_REPAIR_EXHAUSTED",
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "attempt": *attempts,
-                    "max_attempts": super::MAX_SCHEMA_REPAIR_ATTEMPTS
-                }),
+                ErrorPayload {
+                    error: "schema repair attempts exhausted".to_string(),
+                    source: Some("schema_repair".to_string()),
+                    code: Some("TOOL_SCHEMA_REPAIR_EXHAUSTED".to_string()),
+                    tool_call_id: Some(tc.id.clone()),
+                    name: Some(tc.name.clone()),
+                    attempt: Some(*attempts),
+                    max_attempts: Some(super::MAX_SCHEMA_REPAIR_ATTEMPTS),
+                    ..ErrorPayload::default()
+                },
             );
         }
         MalformedToolCallDecision::ContinueToolLoop { invalid_args_error }
@@ -778,14 +1104,14 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         &run_id,
                         step,
-                        EventKind::StepBlocked,
-                        serde_json::json!({
-                            "source": "tool_repeat_guard",
-                            "reason": "str_replace_repeat_requires_pivot",
-                            "tool_call_id": tc.id,
-                            "name": tc.name,
-                            "path": path
-                        }),
+                        StepBlockedPayload {
+                            source: Some("tool_repeat_guard".to_string()),
+                            reason: Some("str_replace_repeat_requires_pivot".to_string()),
+                            tool_call_id: Some(tc.id.clone()),
+                            name: Some(tc.name.clone()),
+                            path: Some(path.clone()),
+                            ..StepBlockedPayload::default()
+                        },
                     );
                     messages.push(Message {
                         role: Role::Developer,
@@ -810,14 +1136,14 @@ impl<P: ModelProvider> Agent<P> {
                     self.emit_event(
                         &run_id,
                         step,
-                        EventKind::StepBlocked,
-                        serde_json::json!({
-                            "source": "tool_repeat_guard",
-                            "reason": "apply_patch_repeat_requires_smaller_fix",
-                            "tool_call_id": tc.id,
-                            "name": tc.name,
-                            "path": path
-                        }),
+                        StepBlockedPayload {
+                            source: Some("tool_repeat_guard".to_string()),
+                            reason: Some("apply_patch_repeat_requires_smaller_fix".to_string()),
+                            tool_call_id: Some(tc.id.clone()),
+                            name: Some(tc.name.clone()),
+                            path: Some(path.clone()),
+                            ..StepBlockedPayload::default()
+                        },
                     );
                     messages.push(Message {
                         role: Role::Developer,
@@ -839,27 +1165,27 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             &run_id,
             step,
-            EventKind::StepBlocked,
-            serde_json::json!({
-                "source": "tool_repeat_guard",
-                "code": "TOOL_REPEAT_BLOCKED",
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "repeat_count": failed_repeat_count,
-                "repeat_limit": super::MAX_FAILED_REPEAT_PER_KEY,
-                "repeat_key_sha256": repeat_key
-            }),
+            StepBlockedPayload {
+                source: Some("tool_repeat_guard".to_string()),
+                code: Some("TOOL_REPEAT_BLOCKED".to_string()),
+                tool_call_id: Some(tc.id.clone()),
+                name: Some(tc.name.clone()),
+                repeat_count: Some(failed_repeat_count),
+                repeat_limit: Some(super::MAX_FAILED_REPEAT_PER_KEY),
+                repeat_key_sha256: Some(repeat_key.to_string()),
+                ..StepBlockedPayload::default()
+            },
         );
         self.emit_event(
             &run_id,
             step,
-            EventKind::Error,
-            serde_json::json!({
-                "error": reason.clone(),
-                "source": "tool_repeat_guard",
-                "tool_call_id": tc.id,
-                "name": tc.name
-            }),
+            ErrorPayload {
+                error: reason.clone(),
+                source: Some("tool_repeat_guard".to_string()),
+                tool_call_id: Some(tc.id.clone()),
+                name: Some(tc.name.clone()),
+                ..ErrorPayload::default()
+            },
         );
         FailedRepeatGuardDecision::Finalize(Box::new(
             self.finalize_planner_error_with_output_with_end(
@@ -894,11 +1220,13 @@ impl<P: ModelProvider> Agent<P> {
         if !matches!(self.taint_toggle, crate::taint::TaintToggle::On) {
             return;
         }
+        let mcp_paths = self.mcp_mapped_paths_for_taint(tc, content);
         let spans = compute_taint_spans_for_tool(
             tc,
             content,
             self.policy_for_taint.as_ref(),
             self.taint_digest_bytes,
+            &mcp_paths,
         );
         if spans.is_empty() {
             return;
@@ -907,12 +1235,11 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::TaintUpdated,
-            serde_json::json!({
-                "overall": taint_state.overall_str(),
-                "new_spans": spans.len(),
-                "sources": taint_state.sources_count_for_last_update()
-            }),
+            TaintUpdatedPayload {
+                overall: taint_state.overall_str().to_string(),
+                new_spans: spans.len(),
+                sources: taint_state.sources_count_for_last_update(),
+            },
         );
     }
 
@@ -926,13 +1253,12 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::PostWriteVerifyStart,
-            serde_json::json!({
-                "name": "read_file",
-                "path": path,
-                "source": "runtime_post_write_verify",
-                "timeout_ms": post_write_verify_timeout_ms
-            }),
+            PostWriteVerifyStartPayload {
+                name: "read_file".to_string(),
+                path: path.to_string(),
+                source: "runtime_post_write_verify".to_string(),
+                timeout_ms: post_write_verify_timeout_ms,
+            },
         );
         let verify_started = std::time::Instant::now();
         let verify = match tokio::time::timeout(
@@ -941,6 +1267,9 @@ impl<P: ModelProvider> Agent<P> {
                 workdir: self.tool_rt.workdir.clone(),
                 path: path.to_string(),
                 max_read_bytes: self.tool_rt.max_read_bytes,
+                max_line_chars: None,
+                range: None,
+                redaction: None,
             }),
         )
         .await
@@ -950,17 +1279,16 @@ impl<P: ModelProvider> Agent<P> {
                 self.emit_event(
                     run_id,
                     step,
-                    EventKind::PostWriteVerifyEnd,
-                    serde_json::json!({
-                        "name": "read_file",
-                        "path": path,
-                        "ok": false,
-                        "status": "timeout",
-                        "source": "runtime_post_write_verify",
-                        "failure_class": "E_RUNTIME_POST_WRITE_VERIFY_TIMEOUT",
-                        "elapsed_ms": verify_started.elapsed().as_millis() as u64,
-                        "timeout_ms": post_write_verify_timeout_ms
-                    }),
+                    PostWriteVerifyEndPayload {
+                        name: "read_file".to_string(),
+                        path: path.to_string(),
+                        ok: false,
+                        status: "timeout".to_string(),
+                        source: "runtime_post_write_verify".to_string(),
+                        failure_class: Some("E_RUNTIME_POST_WRITE_VERIFY_TIMEOUT".to_string()),
+                        elapsed_ms: verify_started.elapsed().as_millis() as u64,
+                        timeout_ms: Some(post_write_verify_timeout_ms),
+                    },
                 );
                 return Err(format!(
                     "implementation guard: runtime post-write verification timed out on read_file for '{path}' after {}ms",
@@ -971,16 +1299,17 @@ impl<P: ModelProvider> Agent<P> {
         self.emit_event(
             run_id,
             step,
-            EventKind::PostWriteVerifyEnd,
-            serde_json::json!({
-                "name": "read_file",
-                "path": path,
-                "ok": verify.ok,
-                "status": if verify.ok { "ok" } else { "failed" },
-                "source": "runtime_post_write_verify",
-                "failure_class": if verify.ok { serde_json::Value::Null } else { serde_json::Value::String("E_RUNTIME_POST_WRITE_VERIFY_FAILED".to_string()) },
-                "elapsed_ms": verify_started.elapsed().as_millis() as u64
-            }),
+            PostWriteVerifyEndPayload {
+                name: "read_file".to_string(),
+                path: path.to_string(),
+                ok: verify.ok,
+                status: if verify.ok { "ok" } else { "failed" }.to_string(),
+                source: "runtime_post_write_verify".to_string(),
+                failure_class: (!verify.ok)
+                    .then(|| "E_RUNTIME_POST_WRITE_VERIFY_FAILED".to_string()),
+                elapsed_ms: verify_started.elapsed().as_millis() as u64,
+                timeout_ms: None,
+            },
         );
         if !verify.ok {
             return Err(format!(
@@ -1024,27 +1353,24 @@ impl<P: ModelProvider> Agent<P> {
         let items = self
             .current_plan
             .iter()
-            .map(|item| {
-                serde_json::json!({
-                    "step": item.step,
-                    "status": item.status.as_str()
-                })
+            .map(|item| PlanItemPayload {
+                step: item.step.clone(),
+                status: item.status.as_str().to_string(),
             })
             .collect::<Vec<_>>();
         self.emit_event(
             run_id,
             step,
-            EventKind::PlanUpdated,
-            serde_json::json!({
-                "tool_call_id": tc.id,
-                "name": tc.name,
-                "explanation": update.explanation,
-                "items": items,
-                "item_count": self.current_plan.len(),
-                "pending": pending,
-                "completed": completed,
-                "in_progress": in_progress
-            }),
+            PlanUpdatedPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                explanation: update.explanation.clone(),
+                items,
+                item_count: self.current_plan.len(),
+                pending,
+                completed,
+                in_progress: in_progress.clone(),
+            },
         );
     }
 
@@ -1084,16 +1410,16 @@ impl<P: ModelProvider> Agent<P> {
             self.emit_event(
                 run_id,
                 step,
-                EventKind::ToolExecEnd,
-                serde_json::json!({
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "ok": false,
-                    "truncated": crate::agent_tool_exec::infer_truncated_flag(current_content),
-                    "retry_count": tool_retry_count,
-                    "failure_class": "E_SCHEMA",
-                    "error_code": error_code.as_str()
-                }),
+                ToolExecEndPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    ok: false,
+                    truncated: crate::agent_tool_exec::infer_truncated_flag(current_content),
+                    retry_count: Some(tool_retry_count),
+                    failure_class: Some(Some("E_SCHEMA".to_string())),
+                    error_code: Some(Some(error_code.as_str().to_string())),
+                    ..ToolExecEndPayload::default()
+                },
             );
             messages.push(tool_msg.clone());
             if self.inject_post_tool_operator_messages(run_id, step, messages) {
@@ -1168,17 +1494,17 @@ impl<P: ModelProvider> Agent<P> {
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::ToolExecEnd,
-                serde_json::json!({
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "ok": false,
-                    "truncated": crate::agent_tool_exec::infer_truncated_flag(current_content),
-                    "retry_count": tool_retry_count,
-                    "failure_class": "E_SCHEMA",
-                    "error_code": "tool_args_invalid",
-                    "attempt": invalid_patch_attempt
-                }),
+                ToolExecEndPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    ok: false,
+                    truncated: crate::agent_tool_exec::infer_truncated_flag(current_content),
+                    retry_count: Some(tool_retry_count),
+                    failure_class: Some(Some("E_SCHEMA".to_string())),
+                    error_code: Some(Some("tool_args_invalid".to_string())),
+                    attempt: Some(invalid_patch_attempt),
+                    ..ToolExecEndPayload::default()
+                },
             );
             messages.push(tool_msg);
             if self.inject_post_tool_operator_messages(&run_id, step, messages) {
@@ -1205,29 +1531,29 @@ impl<P: ModelProvider> Agent<P> {
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::ToolExecEnd,
-                serde_json::json!({
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "ok": false,
-                    "truncated": crate::agent_tool_exec::infer_truncated_flag(current_content),
-                    "retry_count": tool_retry_count,
-                    "failure_class": "E_PROTOCOL_PATCH_FORMAT",
-                    "attempt": invalid_patch_attempt
-                }),
+                ToolExecEndPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    ok: false,
+                    truncated: crate::agent_tool_exec::infer_truncated_flag(current_content),
+                    retry_count: Some(tool_retry_count),
+                    failure_class: Some(Some("E_PROTOCOL_PATCH_FORMAT".to_string())),
+                    attempt: Some(invalid_patch_attempt),
+                    ..ToolExecEndPayload::default()
+                },
             );
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::Error,
-                serde_json::json!({
-                    "error": reason,
-                    "source": "tool_protocol_guard",
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "failure_class": "E_PROTOCOL_PATCH_FORMAT",
-                    "attempt": invalid_patch_attempt
-                }),
+                ErrorPayload {
+                    error: reason.clone(),
+                    source: Some("tool_protocol_guard".to_string()),
+                    tool_call_id: Some(tc.id.clone()),
+                    name: Some(tc.name.clone()),
+                    failure_class: Some("E_PROTOCOL_PATCH_FORMAT".to_string()),
+                    attempt: Some(invalid_patch_attempt),
+                    ..ErrorPayload::default()
+                },
             );
             return InvalidPatchFormatDecision::Finalize(Box::new(
                 self.finalize_planner_error_with_output_with_end(
@@ -1284,9 +1610,13 @@ impl<P: ModelProvider> Agent<P> {
         taint_state: &crate::taint::TaintState,
     ) -> RetryLoopDecision {
         let class = crate::agent_tool_exec::classify_tool_failure(tc, current_content, false);
-        let retry_error_code =
-            crate::agent_tool_exec::tool_result_error_code(current_content).map(|c| c.as_str());
-        let max_retries = class.retry_limit_for(side_effects);
+        let error_code = crate::agent_tool_exec::tool_result_error_code(current_content);
+        let retry_error_code = error_code.map(|c| c.as_str());
+        let max_retries = if error_code.is_some_and(ToolErrorCode::is_fs_entity) {
+            0
+        } else {
+            class.retry_limit_for(side_effects)
+        };
         if tool_retry_count >= max_retries {
             self.emit_tool_retry_event(
                 &run_id,
@@ -1318,20 +1648,21 @@ impl<P: ModelProvider> Agent<P> {
         if let Some(reason) = crate::agent_budget::check_and_consume_tool_budget(
             &self.tool_call_budget,
             tool_budget_usage,
+            &tc.name,
             side_effects,
         ) {
             self.emit_event(
                 &run_id,
                 step,
-                EventKind::ToolDecision,
-                serde_json::json!({
-                    "tool_call_id": tc.id,
-                    "name": tc.name,
-                    "decision": "deny",
-                    "reason": reason.clone(),
-                    "source": "runtime_budget",
-                    "side_effects": side_effects
-                }),
+                ToolDecisionPayload {
+                    tool_call_id: tc.id.clone(),
+                    name: tc.name.clone(),
+                    decision: "deny".to_string(),
+                    reason: Some(reason.clone()),
+                    source: Some(Some("runtime_budget".to_string())),
+                    side_effects: Some(side_effects),
+                    ..ToolDecisionPayload::default()
+                },
             );
             return RetryLoopDecision::Finalize(Box::new(
                 self.finalize_runtime_budget_deny_with_end(
@@ -1584,8 +1915,18 @@ impl<P: ModelProvider> Agent<P> {
         observed_tool_executions: &mut Vec<crate::agent_impl_guard::ToolExecutionRecord>,
         observed_tool_calls: Vec<ToolCall>,
     ) -> AllowedToolResultDecision {
+        for redacted in secret_redactions(tc, tool_msg.content.as_deref().unwrap_or_default()) {
+            self.emit_event(&run_id, step, redacted);
+        }
         let hook_state = match self
-            .apply_tool_result_hooks(&run_id, step, tc, tool_msg, hook_invocations)
+            .apply_tool_result_hooks(
+                &run_id,
+                step,
+                tc,
+                tool_msg,
+                observed_tool_executions.len() as u64 + 1,
+                hook_invocations,
+            )
             .await
         {
             Ok(state) => state,
@@ -1636,6 +1977,19 @@ impl<P: ModelProvider> Agent<P> {
             ok: final_ok,
             changed: changed_flag,
         });
+        if let Some(skipped) = dry_run_write_skipped(tc, &content) {
+            // Nothing reached disk, so a post-write read-back would verify
+            // nothing; the diff in the envelope stands in for it.
+            for path in &skipped.paths {
+                observed_tool_executions.push(crate::agent_impl_guard::ToolExecutionRecord {
+                    name: "read_file".to_string(),
+                    path: Some(normalize_tool_path(path)),
+                    ok: true,
+                    changed: None,
+                });
+            }
+            self.emit_event(&run_id, step, skipped);
+        }
         let final_failure_class = if tool_result_has_error(&content) {
             Some(crate::agent_tool_exec::classify_tool_failure(
                 tc,
@@ -1719,7 +2073,7 @@ impl<P: ModelProvider> Agent<P> {
 
 #[cfg(test)]
 mod shell_stream_tests {
-    use super::{ShellStreamAction, ShellStreamCoalescer};
+    use super::{ShellStreamAction, ShellStreamCoalescer, ToolExecProgressTracker};
     use crate::target::{ShellOutputChunk, ShellStreamKind};
 
     #[test]
@@ -1788,4 +2142,96 @@ mod shell_stream_tests {
         assert!(text.is_char_boundary(text.len()));
         assert_eq!(actions.last(), Some(&ShellStreamAction::BudgetReached));
     }
+
+    #[test]
+    fn progress_tracker_counts_all_bytes_and_keeps_a_bounded_tail() {
+        let mut tracker = ToolExecProgressTracker::default();
+        let now = std::time::Instant::now();
+        tracker.ingest(&[
+            ShellOutputChunk {
+                stream: ShellStreamKind::Stdout,
+                bytes: vec![b'a'; 100_000],
+            },
+            ShellOutputChunk {
+                stream: ShellStreamKind::Stdout,
+                bytes: "€ done".as_bytes().to_vec(),
+            },
+        ]);
+        let due = tracker.take_due(now, false);
+        assert_eq!(due.len(), 1);
+        let (stream, bytes_so_far, preview) = &due[0];
+        assert_eq!(*stream, "stdout");
+        assert_eq!(*bytes_so_far, 100_000 + "€ done".len() as u64);
+        assert!(preview.len() <= super::TOOL_EXEC_PROGRESS_PREVIEW_BYTES);
+        assert!(preview.ends_with("€ done"));
+
+        // Within the interval nothing is emitted unless forced, and only
+        // streams that changed are reported.
+        tracker.ingest(&[ShellOutputChunk {
+            stream: ShellStreamKind::Stderr,
+            bytes: b"warn".to_vec(),
+        }]);
+        assert!(tracker.take_due(now, false).is_empty());
+        assert_eq!(
+            tracker.take_due(now, true),
+            vec![("stderr", 4, "warn".to_string())]
+        );
+        assert!(tracker.take_due(now, true).is_empty());
+    }
+
+    #[test]
+    fn progress_preview_never_starts_mid_codepoint() {
+        let mut tracker = ToolExecProgressTracker::default();
+        let text = "€".repeat(super::TOOL_EXEC_PROGRESS_PREVIEW_BYTES);
+        tracker.ingest(&[ShellOutputChunk {
+            stream: ShellStreamKind::Stdout,
+            bytes: text.into_bytes(),
+        }]);
+        let due = tracker.take_due(std::time::Instant::now(), true);
+        assert!(due[0].2.chars().all(|c| c == '€'), "{}", due[0].2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn progress_follows_a_command_that_prints_in_stages() {
+        use crate::target::{ExecTarget, HostTarget, ShellReq};
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let run = tokio::spawn(async move {
+            HostTarget
+                .exec_shell(ShellReq {
+                    workdir: tmp.path().to_path_buf(),
+                    cmd: "sh".to_string(),
+                    args: vec![
+                        "-c".to_string(),
+                        "printf one; sleep 0.3; printf two >&2; sleep 0.3; printf three"
+                            .to_string(),
+                    ],
+                    cwd: None,
+                    max_tool_output_bytes: 200_000,
+                    timeout_ms: 10_000,
+                    stream: Some(tx),
+                    create_cwd: false,
+                    redaction: None,
+                })
+                .await
+        });
+        let mut tracker = ToolExecProgressTracker::default();
+        let mut updates = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            tracker.ingest(&[chunk]);
+            updates.extend(tracker.take_due(std::time::Instant::now(), true));
+        }
+        let out = run.await.expect("join");
+        assert!(out.ok, "{}", out.content);
+        assert_eq!(
+            updates,
+            vec![
+                ("stdout", 3, "one".to_string()),
+                ("stderr", 3, "two".to_string()),
+                ("stdout", 8, "onethree".to_string()),
+            ]
+        );
+    }
 }
@@ -0,0 +1,255 @@
+use crate::attribution::{
+    apply_attribution, AttributionOutcome, AttributionRecord, AttributionVars, AttributionWriteKind,
+};
+use crate::events::{AttributionInjectedPayload, AttributionSkippedPayload};
+use crate::providers::ModelProvider;
+use crate::target::{split_multi_file_patch, ReadReq, WriteReq};
+use crate::types::{Message, ToolCall};
+
+use super::Agent;
+
+const ATTRIBUTION_MAX_READ_BYTES: usize = 10 * 1024 * 1024;
+
+impl<P: ModelProvider> Agent<P> {
+    /// Rewrites `write_file` content to carry the policy attribution line.
+    /// Returns the call to execute in place of `tc` along with the record to
+    /// attach to its result envelope once the write succeeds.
+    pub(super) fn attribute_write_call(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+    ) -> Option<(ToolCall, AttributionRecord)> {
+        if AttributionWriteKind::for_tool(&tc.name)? != AttributionWriteKind::NewContent {
+            return None;
+        }
+        let config = self.attribution.as_ref()?;
+        let args = crate::tools::normalize_builtin_tool_args(&tc.name, &tc.arguments);
+        let path = args.get("path")?.as_str()?;
+        let content = args.get("content")?.as_str()?;
+        let date = attribution_date();
+        let outcome = apply_attribution(
+            config,
+            path,
+            content,
+            AttributionWriteKind::NewContent,
+            AttributionVars {
+                run_id,
+                model: &self.model,
+                date: &date,
+            },
+        );
+        let (content, record) = self.take_injected_attribution(run_id, step, tc, outcome)?;
+        let mut rewritten = tc.clone();
+        let mut rewritten_args = args.clone();
+        rewritten_args["content"] = serde_json::Value::String(content);
+        rewritten.arguments = rewritten_args;
+        Some((rewritten, record))
+    }
+
+    /// Adds the attribution line to each file a successful patch-style write
+    /// touched: its `path`, or every file of a multi-file `apply_patch`. Only
+    /// runs when the policy opts in via `include_patches`.
+    pub(super) async fn attribute_patched_files(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+    ) -> Vec<AttributionRecord> {
+        if AttributionWriteKind::for_tool(&tc.name) != Some(AttributionWriteKind::Patch)
+            || self.tool_rt.dry_run_writes
+            || self.attribution.is_none()
+        {
+            return Vec::new();
+        }
+        let path = tc
+            .arguments
+            .get("path")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.is_empty());
+        let paths = match path {
+            Some(path) => vec![path.to_string()],
+            None => match tc
+                .arguments
+                .get("patch")
+                .and_then(|v| v.as_str())
+                .map(split_multi_file_patch)
+            {
+                Some(Ok(files)) => files.into_iter().map(|f| f.path).collect(),
+                _ => {
+                    self.emit_attribution_skipped(run_id, step, tc, "", "unknown_paths");
+                    return Vec::new();
+                }
+            },
+        };
+        let mut records = Vec::new();
+        for path in paths {
+            if let Some(record) = self.attribute_patched_file(run_id, step, tc, path).await {
+                records.push(record);
+            }
+        }
+        records
+    }
+
+    async fn attribute_patched_file(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+        path: String,
+    ) -> Option<AttributionRecord> {
+        let config = self.attribution.as_ref()?;
+        if !config.applies_to(&path, AttributionWriteKind::Patch) {
+            return None;
+        }
+        let read = self
+            .tool_rt
+            .exec_target
+            .read_file(ReadReq {
+                workdir: self.tool_rt.workdir.clone(),
+                path: path.clone(),
+                max_read_bytes: ATTRIBUTION_MAX_READ_BYTES,
+                max_line_chars: None,
+                range: None,
+                redaction: None,
+            })
+            .await;
+        let parsed = serde_json::from_str::<serde_json::Value>(&read.content).ok();
+        let current = parsed
+            .as_ref()
+            .filter(|v| {
+                read.ok
+                    && !v
+                        .get("truncated")
+                        .and_then(|t| t.as_bool())
+                        .unwrap_or(false)
+            })
+            .and_then(|v| v.get("content"))
+            .and_then(|c| c.as_str())?
+            .to_string();
+        let date = attribution_date();
+        let outcome = apply_attribution(
+            config,
+            &path,
+            &current,
+            AttributionWriteKind::Patch,
+            AttributionVars {
+                run_id,
+                model: &self.model,
+                date: &date,
+            },
+        );
+        let (content, record) = self.take_injected_attribution(run_id, step, tc, outcome)?;
+        let write = self
+            .tool_rt
+            .exec_target
+            .write_file(WriteReq {
+                workdir: self.tool_rt.workdir.clone(),
+                path: path.clone(),
+                content,
+                create_parents: false,
+                dry_run: false,
+            })
+            .await;
+        if !write.ok {
+            self.emit_attribution_skipped(run_id, step, tc, &path, "write_failed");
+            return None;
+        }
+        Some(record)
+    }
+
+    pub(super) fn emit_attribution_injected(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+        record: &AttributionRecord,
+    ) {
+        self.emit_event(
+            run_id,
+            step,
+            AttributionInjectedPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                path: record.path.clone(),
+                placement: record.placement,
+                pre_injection_sha256: record.pre_injection_sha256.clone(),
+                post_injection_sha256: record.post_injection_sha256.clone(),
+            },
+        );
+    }
+
+    fn take_injected_attribution(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+        outcome: AttributionOutcome,
+    ) -> Option<(String, AttributionRecord)> {
+        match outcome {
+            AttributionOutcome::Injected { content, record } => Some((content, record)),
+            AttributionOutcome::UnknownExtension { path } => {
+                self.emit_attribution_skipped(run_id, step, tc, &path, "unknown_extension");
+                None
+            }
+            AttributionOutcome::AlreadyPresent { path } => {
+                self.emit_attribution_skipped(run_id, step, tc, &path, "already_present");
+                None
+            }
+            AttributionOutcome::NotApplicable => None,
+        }
+    }
+
+    fn emit_attribution_skipped(
+        &mut self,
+        run_id: &str,
+        step: u32,
+        tc: &ToolCall,
+        path: &str,
+        reason: &str,
+    ) {
+        self.emit_event(
+            run_id,
+            step,
+            AttributionSkippedPayload {
+                tool_call_id: tc.id.clone(),
+                name: tc.name.clone(),
+                path: path.to_string(),
+                reason: reason.to_string(),
+            },
+        );
+    }
+}
+
+/// Records the attribution on the tool result envelope under `meta.attribution`
+/// so artifacts carry the hash of what the model actually produced. A
+/// multi-file patch records an array with one entry per attributed file.
+pub(super) fn attach_attribution_records(
+    mut message: Message,
+    records: &[AttributionRecord],
+) -> Message {
+    let Some(mut envelope) = message
+        .content
+        .as_deref()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(c).ok())
+    else {
+        return message;
+    };
+    let Some(meta) = envelope.get_mut("meta").and_then(|m| m.as_object_mut()) else {
+        return message;
+    };
+    let value = match records {
+        [record] => serde_json::to_value(record),
+        records => serde_json::to_value(records),
+    };
+    meta.insert(
+        "attribution".to_string(),
+        value.unwrap_or(serde_json::Value::Null),
+    );
+    message.content = Some(envelope.to_string());
+    message
+}
+
+fn attribution_date() -> String {
+    time::OffsetDateTime::now_utc().date().to_string()
+}
@@ -1,7 +1,11 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+
 use crate::agent::ToolCallBudget;
 use crate::types::SideEffects;
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct ToolCallBudgetUsage {
     pub(crate) total_tool_calls: usize,
     pub(crate) mcp_calls: usize,
@@ -10,11 +14,39 @@ pub(crate) struct ToolCallBudgetUsage {
     pub(crate) shell_calls: usize,
     pub(crate) network_calls: usize,
     pub(crate) browser_calls: usize,
+    /// Calls charged per tool name; only tools with a per-tool limit are
+    /// tracked.
+    pub(crate) calls_by_tool: BTreeMap<String, usize>,
+    /// Model calls spent summarizing evicted messages during compaction.
+    pub(crate) compaction_summaries: usize,
+}
+
+/// Parses `--max-tool-calls TOOL=N` values into per-tool limits. A later
+/// value for the same tool replaces an earlier one.
+pub(crate) fn parse_tool_call_limits(specs: &[String]) -> anyhow::Result<BTreeMap<String, usize>> {
+    let mut limits = BTreeMap::new();
+    for spec in specs {
+        let (tool, count) = spec
+            .split_once('=')
+            .ok_or_else(|| anyhow!("--max-tool-calls expects TOOL=N, got '{spec}'"))?;
+        let tool = tool.trim();
+        if tool.is_empty() {
+            return Err(anyhow!("--max-tool-calls expects TOOL=N, got '{spec}'"));
+        }
+        let count = count.trim().parse::<usize>().map_err(|_| {
+            anyhow!(
+                "--max-tool-calls limit for '{tool}' must be a non-negative integer, got '{count}'"
+            )
+        })?;
+        limits.insert(tool.to_string(), count);
+    }
+    Ok(limits)
 }
 
 pub(crate) fn check_and_consume_tool_budget(
     budget: &ToolCallBudget,
     usage: &mut ToolCallBudgetUsage,
+    tool_name: &str,
     side_effects: SideEffects,
 ) -> Option<String> {
     let next_total = usage.total_tool_calls.saturating_add(1);
@@ -39,7 +71,28 @@ pub(crate) fn check_and_consume_tool_budget(
         }
     }
 
+    let tool_limit = budget.per_tool.get(tool_name).copied();
+    if let Some(limit) = tool_limit.filter(|limit| *limit > 0) {
+        let next_tool_count = usage
+            .calls_by_tool
+            .get(tool_name)
+            .copied()
+            .unwrap_or(0)
+            .saturating_add(1);
+        if next_tool_count > limit {
+            return Some(format!(
+                "runtime budget exceeded: {tool_name} tool calls {next_tool_count} > limit {limit}"
+            ));
+        }
+    }
+
     increment_budget_usage(usage, side_effects);
+    if tool_limit.is_some() {
+        *usage
+            .calls_by_tool
+            .entry(tool_name.to_string())
+            .or_insert(0) += 1;
+    }
     None
 }
 
@@ -1,18 +1,26 @@
 use crate::agent::{Agent, McpRuntimeTraceEntry};
-use crate::events::{Event, EventKind};
+use crate::events::{Event, EventKind, EventPayload, StateLockReclaimedPayload};
 use crate::providers::ModelProvider;
 
 impl<P: ModelProvider> Agent<P> {
-    pub(crate) fn emit_event(
-        &mut self,
-        run_id: &str,
-        step: u32,
-        kind: EventKind,
-        data: serde_json::Value,
-    ) {
-        self.capture_mcp_runtime_trace(step, &kind, &data);
+    pub(crate) fn emit_event(&mut self, run_id: &str, step: u32, payload: impl Into<EventPayload>) {
+        // Locks reclaimed by synchronous store calls since the last event are
+        // reported ahead of it; agents without a sink leave them queued.
+        if self.event_sink.is_some() {
+            for reclaim in crate::store::take_state_lock_reclaims() {
+                self.emit_event_now(run_id, step, StateLockReclaimedPayload::from(reclaim));
+            }
+        }
+        self.emit_event_now(run_id, step, payload);
+    }
+
+    fn emit_event_now(&mut self, run_id: &str, step: u32, payload: impl Into<EventPayload>) {
+        let event = Event::new(run_id.to_string(), step, payload);
+        self.capture_mcp_runtime_trace(step, &event.kind, &event.data);
+        self.timeline_recorder
+            .record(step, &event.kind, &event.data);
         if let Some(sink) = &mut self.event_sink {
-            if let Err(e) = sink.emit(Event::new(run_id.to_string(), step, kind, data)) {
+            if let Err(e) = sink.emit(event) {
                 eprintln!("WARN: failed to emit event: {e}");
             }
         }
@@ -44,61 +52,57 @@ impl<P: ModelProvider> Agent<P> {
                     .get("progress_ticks")
                     .and_then(|v| v.as_u64())
                     .map(|v| v as u32),
-                elapsed_ms: data.get("elapsed_ms").and_then(|v| v.as_u64()),
+                elapsed_ms: data
+                    .get("elapsed_ms")
+                    .or_else(|| data.get("degraded_ms"))
+                    .and_then(|v| v.as_u64()),
+                server: data
+                    .get("server")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+                schema_hash_hex: data
+                    .get("schema_hash_live")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
             });
         };
+        let is_mcp_tool = data
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .starts_with("mcp.");
         match kind {
-            EventKind::ToolExecStart => {
-                if data
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or_default()
-                    .starts_with("mcp.")
-                {
-                    push("running");
+            EventKind::ToolExecStart if is_mcp_tool => push("running"),
+            EventKind::ToolExecEnd if is_mcp_tool => {
+                let ok = data.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
+                if ok {
+                    push("done");
+                } else {
+                    push("fail");
                 }
             }
-            EventKind::ToolExecEnd => {
-                if data
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or_default()
-                    .starts_with("mcp.")
-                {
-                    let ok = data.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
-                    if ok {
-                        push("done");
-                    } else {
-                        push("fail");
-                    }
-                }
-            }
-            EventKind::ToolRetry => {
-                if data
-                    .get("name")
+            EventKind::ToolRetry if is_mcp_tool => {
+                let action = data
+                    .get("action")
                     .and_then(|v| v.as_str())
-                    .unwrap_or_default()
-                    .starts_with("mcp.")
-                {
-                    let action = data
-                        .get("action")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("stop");
-                    if action == "retry" {
-                        push("wait_retry");
-                    } else {
-                        push("fail");
-                    }
+                    .unwrap_or("stop");
+                if action == "retry" {
+                    push("wait_retry");
+                } else {
+                    push("fail");
                 }
             }
             EventKind::McpProgress => push("wait_task"),
             EventKind::McpCancelled => push("cancelled"),
+            EventKind::McpServerDegraded => push("server_degraded"),
+            EventKind::McpServerReconnected => push("server_reconnected"),
             EventKind::McpPinned => push("pinned"),
             EventKind::McpDrift => push("drift"),
             EventKind::PackActivated => push("pack"),
             EventKind::QueueSubmitted => push("queue_submitted"),
             EventKind::QueueDelivered => push("queue_delivered"),
             EventKind::QueueInterrupt => push("queue_interrupt"),
+            EventKind::QueueOverflow => push("queue_overflow"),
             _ => {}
         }
     }
@@ -1,14 +1,19 @@
 use std::path::PathBuf;
 use std::sync::mpsc::Sender;
+
+use anyhow::Context;
 use tokio::sync::watch;
 
 use crate::agent::{self, Agent, AgentExitReason, ToolCallBudget};
 use crate::compaction::CompactionSettings;
-use crate::events::{Event, EventKind};
+use crate::events::{Event, PhaseEnteredPayload, RunEndPayload};
 use crate::gate::ProviderKind;
 use crate::mcp::registry::McpRegistry;
 use crate::packs;
 use crate::planner;
+use crate::providers::pacing::{PacedProvider, ProviderPacing, ProviderPacingConfig};
+use crate::providers::recording::{provider_trace_path, RecordingProvider};
+use crate::providers::retry::{RetryPolicy, RetryingProvider};
 use crate::providers::ModelProvider;
 use crate::runtime_events;
 use crate::runtime_paths;
@@ -86,6 +91,110 @@ fn select_runtime_context_messages(
     )
 }
 
+/// Optional channels and shared state a caller attaches to a run. Kept apart
+/// from the borrowed run inputs so it can default to a plain CLI run.
+#[derive(Default)]
+pub(crate) struct AgentUiRunAttachments {
+    pub(crate) ui_tx: Option<Sender<Event>>,
+    pub(crate) operator_queue_rx:
+        Option<std::sync::mpsc::Receiver<crate::operator_queue::QueueSubmitRequest>>,
+    pub(crate) cancel_pair: Option<(watch::Sender<bool>, watch::Receiver<bool>)>,
+    pub(crate) shared_mcp_registry: Option<std::sync::Arc<McpRegistry>>,
+    /// Hooks config pinned across a chat session's runs.
+    pub(crate) session_hooks: Option<crate::hooks::session::SessionHooks>,
+    pub(crate) resume_checkpoint: Option<store::RuntimeRunCheckpointRecordV1>,
+    pub(crate) suppress_stdout_stream: bool,
+    /// Caller sinks that see every event after the built-in ones.
+    pub(crate) event_sinks: Vec<Box<dyn crate::events::EventSink>>,
+}
+
+/// Inputs for [`run_agent_with_ui`] other than the provider itself.
+pub(crate) struct AgentUiRunRequest<'a> {
+    pub(crate) provider_kind: ProviderKind,
+    pub(crate) base_url: &'a str,
+    pub(crate) default_model: &'a str,
+    pub(crate) prompt: &'a str,
+    pub(crate) args: &'a RunArgs,
+    pub(crate) paths: &'a store::StatePaths,
+    pub(crate) attachments: AgentUiRunAttachments,
+}
+
+impl<'a> AgentUiRunRequest<'a> {
+    pub(crate) fn new(
+        provider_kind: ProviderKind,
+        base_url: &'a str,
+        default_model: &'a str,
+        prompt: &'a str,
+        args: &'a RunArgs,
+        paths: &'a store::StatePaths,
+    ) -> Self {
+        Self {
+            provider_kind,
+            base_url,
+            default_model,
+            prompt,
+            args,
+            paths,
+            attachments: AgentUiRunAttachments::default(),
+        }
+    }
+
+    pub(crate) fn with_ui_tx(mut self, ui_tx: Sender<Event>) -> Self {
+        self.attachments.ui_tx = Some(ui_tx);
+        self
+    }
+
+    pub(crate) fn with_operator_queue(
+        mut self,
+        operator_queue_rx: std::sync::mpsc::Receiver<crate::operator_queue::QueueSubmitRequest>,
+    ) -> Self {
+        self.attachments.operator_queue_rx = Some(operator_queue_rx);
+        self
+    }
+
+    pub(crate) fn with_cancel_pair(
+        mut self,
+        cancel_pair: (watch::Sender<bool>, watch::Receiver<bool>),
+    ) -> Self {
+        self.attachments.cancel_pair = Some(cancel_pair);
+        self
+    }
+
+    pub(crate) fn with_shared_mcp_registry(
+        mut self,
+        shared_mcp_registry: Option<std::sync::Arc<McpRegistry>>,
+    ) -> Self {
+        self.attachments.shared_mcp_registry = shared_mcp_registry;
+        self
+    }
+
+    pub(crate) fn with_session_hooks(
+        mut self,
+        session_hooks: crate::hooks::session::SessionHooks,
+    ) -> Self {
+        self.attachments.session_hooks = Some(session_hooks);
+        self
+    }
+
+    pub(crate) fn with_resume_checkpoint(
+        mut self,
+        resume_checkpoint: store::RuntimeRunCheckpointRecordV1,
+    ) -> Self {
+        self.attachments.resume_checkpoint = Some(resume_checkpoint);
+        self
+    }
+
+    pub(crate) fn with_event_sink(mut self, sink: Box<dyn crate::events::EventSink>) -> Self {
+        self.attachments.event_sinks.push(sink);
+        self
+    }
+
+    pub(crate) fn suppress_stdout_stream(mut self, suppress: bool) -> Self {
+        self.attachments.suppress_stdout_stream = suppress;
+        self
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_agent<P: ModelProvider>(
     provider: P,
@@ -98,18 +207,7 @@ pub(crate) async fn run_agent<P: ModelProvider>(
 ) -> anyhow::Result<RunExecutionResult> {
     run_agent_with_ui(
         provider,
-        provider_kind,
-        base_url,
-        default_model,
-        prompt,
-        args,
-        paths,
-        None,
-        None,
-        None,
-        None,
-        None,
-        false,
+        AgentUiRunRequest::new(provider_kind, base_url, default_model, prompt, args, paths),
     )
     .await
 }
@@ -127,40 +225,43 @@ pub(crate) async fn run_agent_from_checkpoint<P: ModelProvider>(
 ) -> anyhow::Result<RunExecutionResult> {
     run_agent_with_ui(
         provider,
-        provider_kind,
-        base_url,
-        default_model,
-        prompt,
-        args,
-        paths,
-        None,
-        None,
-        None,
-        None,
-        Some(resume_checkpoint),
-        false,
+        AgentUiRunRequest::new(provider_kind, base_url, default_model, prompt, args, paths)
+            .with_resume_checkpoint(resume_checkpoint),
     )
     .await
 }
 
-#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
     provider: P,
-    provider_kind: ProviderKind,
-    base_url: &str,
-    default_model: &str,
-    prompt: &str,
-    args: &RunArgs,
-    paths: &store::StatePaths,
-    external_ui_tx: Option<Sender<Event>>,
-    external_operator_queue_rx: Option<
-        std::sync::mpsc::Receiver<crate::operator_queue::QueueSubmitRequest>,
-    >,
-    external_cancel_pair: Option<(watch::Sender<bool>, watch::Receiver<bool>)>,
-    shared_mcp_registry: Option<std::sync::Arc<McpRegistry>>,
-    resume_checkpoint: Option<store::RuntimeRunCheckpointRecordV1>,
-    suppress_stdout_stream: bool,
+    request: AgentUiRunRequest<'_>,
 ) -> anyhow::Result<RunExecutionResult> {
+    let AgentUiRunRequest {
+        provider_kind,
+        base_url,
+        default_model,
+        prompt,
+        args,
+        paths,
+        attachments:
+            AgentUiRunAttachments {
+                ui_tx: external_ui_tx,
+                operator_queue_rx: external_operator_queue_rx,
+                cancel_pair: external_cancel_pair,
+                shared_mcp_registry,
+                session_hooks,
+                resume_checkpoint,
+                suppress_stdout_stream,
+                event_sinks: external_event_sinks,
+            },
+    } = request;
+    let provider_pacing = ProviderPacing::for_base_url(
+        ProviderPacingConfig {
+            min_interval_ms: args.provider_min_interval_ms,
+            concurrency: args.provider_concurrency,
+        },
+        base_url,
+    );
+    let provider = PacedProvider::new(provider, provider_pacing.clone());
     let mut launch = prepare_runtime_launch(
         &provider,
         provider_kind,
@@ -170,13 +271,34 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         args,
         paths,
         external_ui_tx,
+        external_event_sinks,
         external_cancel_pair,
         shared_mcp_registry,
+        session_hooks.as_ref(),
         suppress_stdout_stream,
     )
     .await?;
     let mcp_pin_snapshot = build_mcp_pin_snapshot(&launch);
     let run_id = uuid::Uuid::new_v4().to_string();
+    // Wrapped after launch so qualification probes stay out of the trace and
+    // their retries out of the run's retry count.
+    let provider = RetryingProvider::new(
+        provider,
+        RetryPolicy {
+            max_attempts: args.provider_retry_max_attempts,
+            base_delay_ms: args.provider_retry_base_delay_ms,
+            max_delay_ms: args.provider_retry_max_delay_ms,
+            retry_on: args.provider_retry_on.clone(),
+        },
+    );
+    let provider = RecordingProvider::new(
+        provider,
+        args.record_provider
+            .then(|| provider_trace_path(&paths.state_dir, &run_id))
+            .as_deref(),
+        provider_kind,
+        base_url,
+    )?;
     emit_startup_runtime_events(&mut launch, &run_id);
     let launch::RuntimeLaunch {
         args,
@@ -193,9 +315,16 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         effective_plan_tool_enforcement,
         session_store,
         mut session_data,
+        session_recovery: _,
         resolved_settings,
         session_messages,
         task_memory,
+        environment_probe,
+        queue_replay,
+        attribution,
+        read_allowlist,
+        secret_redactor,
+        mcp_root_map,
         instruction_resolution,
         task_contract,
         task_contract_provenance,
@@ -204,6 +333,9 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         repo_map_resolution,
         lsp_context_resolution,
         activated_packs,
+        context_pack,
+        learned_guidance,
+        prompt_template,
         mcp_config_path,
         mcp_registry,
         mcp_tool_snapshot,
@@ -219,12 +351,17 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         tool_schema_hash_hex_map,
         hooks_config_hash_hex,
         hook_manager,
+        hooks_reload: _,
         tool_catalog,
         mut event_sink,
+        otlp_stats,
         _cancel_tx: _cancel_tx_guard,
         mut cancel_rx,
         mut ui_join,
     } = launch;
+    let prompt = prompt_template
+        .as_ref()
+        .map_or(prompt, |rendered| rendered.prompt.as_str());
     let policy_hash_hex = gate_build.policy_hash_hex.clone();
     let policy_source = gate_build.policy_source.to_string();
     let policy_version = gate_build.policy_version;
@@ -277,6 +414,9 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
             repo_map_resolution: repo_map_resolution.as_ref(),
             lsp_context_resolution: lsp_context_resolution.as_ref(),
             activated_packs: &activated_packs,
+            context_pack: context_pack.as_ref(),
+            learned_guidance: learned_guidance.as_ref(),
+            prompt_template: prompt_template.as_ref().map(|t| &t.record),
         })
         .await?
         {
@@ -284,6 +424,26 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         }
     }
 
+    let write_checkpoint = (args.checkpoint_writes && !args.dry_run_writes).then(|| {
+        std::sync::Arc::new(crate::store::WriteCheckpointStore::new(
+            &paths.state_dir,
+            &run_id,
+            &workdir,
+        ))
+    });
+    let final_output_schema = match &args.final_output_schema {
+        Some(path) => {
+            let raw = std::fs::read_to_string(path).with_context(|| {
+                format!("failed to read final output schema {}", path.display())
+            })?;
+            Some(
+                serde_json::from_str::<serde_json::Value>(&raw).with_context(|| {
+                    format!("final output schema {} is not valid JSON", path.display())
+                })?,
+            )
+        }
+        None => None,
+    };
     let mut agent = Agent {
         provider,
         model: worker_model.clone(),
@@ -297,6 +457,8 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
             workdir,
             allow_shell: args.allow_shell,
             allow_shell_in_workdir_only: args.allow_shell_in_workdir,
+            shell_allowlist: (!args.allow_shell_cmd.is_empty())
+                .then(|| args.allow_shell_cmd.clone()),
             allow_write: args.allow_write,
             max_tool_output_bytes: if args.no_limits {
                 0
@@ -309,9 +471,22 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
                 args.max_read_bytes
             },
             unsafe_bypass_allow_flags: args.unsafe_bypass_allow_flags,
+            restrict_to_workdir: !args.unsafe_bypass_allow_flags,
+            tool_timeout_ms: (!args.no_limits && args.tool_exec_timeout_ms > 0)
+                .then_some(args.tool_exec_timeout_ms),
+            stream_tool_output: args.stream_tool_output,
+            dry_run_writes: args.dry_run_writes,
+            write_checkpoint,
             tool_args_strict: resolved_settings.tool_args_strict,
             exec_target_kind: resolved_target_kind,
             exec_target,
+            read_allowlist,
+            run_artifacts: Some(std::sync::Arc::new(crate::store::RunArtifactStore::new(
+                &paths.state_dir,
+                &run_id,
+                args.max_run_artifact_bytes,
+            ))),
+            secret_redactor,
         },
         gate,
         gate_ctx,
@@ -322,6 +497,7 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         event_sink,
         compaction_settings: CompactionSettings {
             max_context_chars: resolved_settings.max_context_chars,
+            max_context_tokens: args.max_context_tokens,
             mode: resolved_settings.compaction_mode,
             keep_last: resolved_settings.compaction_keep_last,
             tool_result_persist: resolved_settings.tool_result_persist,
@@ -344,6 +520,7 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
             } else {
                 args.max_wall_time_ms
             },
+            deadline_ms: if args.no_limits { 0 } else { args.deadline_ms },
             max_total_tool_calls: args.max_total_tool_calls,
             max_mcp_calls: args.max_mcp_calls,
             max_filesystem_read_calls: args.max_filesystem_read_calls,
@@ -351,6 +528,8 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
             max_shell_calls: args.max_shell_calls,
             max_network_calls: args.max_network_calls,
             max_browser_calls: args.max_browser_calls,
+            per_tool: crate::agent_budget::parse_tool_call_limits(&args.max_tool_calls)?,
+            max_compaction_summaries: args.max_compaction_summaries,
             tool_exec_timeout_ms: if args.no_limits {
                 0
             } else {
@@ -363,9 +542,33 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
             },
         },
         mcp_runtime_trace: Vec::new(),
-        operator_queue: crate::operator_queue::PendingMessageQueue::default(),
+        operator_queue: queue_replay
+            .map(crate::operator_queue::PendingMessageQueue::with_replay)
+            .unwrap_or_default(),
         operator_queue_limits: crate::operator_queue::QueueLimits::default(),
         operator_queue_rx: external_operator_queue_rx,
+        attribution,
+        max_consecutive_empty_responses: args.max_empty_responses,
+        digest_refetch_tracker: crate::compaction::DigestRefetchTracker::default(),
+        require_exact_model: args.require_exact_model,
+        served_model: None,
+        provider_retry_wait_ms: 0,
+        mcp_root_map,
+        timeline_recorder: Default::default(),
+        gate_context_snapshot: None,
+        skip_unevaluated_gate_snapshots: args.skip_unevaluated_gate_snapshots,
+        run_deadline: None,
+        parallel_readonly_tools: args.parallel_readonly_tools,
+        approval_diff_max_lines: args.approval_diff_max_lines,
+        parallel_tool_batch: None,
+        token_counter: Box::new(crate::compaction::HeuristicTokenCounter::default()),
+        final_output_schema,
+        subtask_depth: 0,
+        subtask_run_ids: Vec::new(),
+        run_started: None,
+        operator_cancel: None,
+        plan_step_queue: Vec::new(),
+        completed_plan_step_ids: std::collections::BTreeSet::new(),
     };
 
     let mut base_instruction_messages = instruction_resolution.messages.clone();
@@ -374,6 +577,18 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         &args,
         instruction_resolution.selected_task_kind.as_deref(),
     );
+    if let Some(message) = environment_probe
+        .as_ref()
+        .and_then(|record| crate::env_probe::environment_facts_message(&record.facts))
+    {
+        base_instruction_messages.push(message);
+    }
+    if let Some(resolution) = context_pack.as_ref() {
+        base_instruction_messages.extend(crate::context_packs::context_pack_messages(resolution));
+    }
+    if let Some(message) = learned_guidance.as_ref().and_then(|g| g.message.clone()) {
+        base_instruction_messages.push(message);
+    }
     let (project_guidance_message, repo_map_message, lsp_context_message) =
         select_runtime_context_messages(
             prompt,
@@ -447,10 +662,10 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         &mut agent.event_sink,
         &run_id,
         0,
-        EventKind::PhaseEntered,
-        serde_json::json!({
-            "phase": crate::agent::interrupts::run_phase_name(&initial_runtime_checkpoint.phase)
-        }),
+        PhaseEnteredPayload {
+            phase: crate::agent::interrupts::run_phase_name(&initial_runtime_checkpoint.phase)
+                .to_string(),
+        },
     );
 
     let mut outcome = tokio::select! {
@@ -494,13 +709,17 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
     )
     .await?;
 
-    if matches!(outcome.exit_reason, AgentExitReason::Cancelled) {
+    // An operator-queue cancel already emitted its own run end from the agent.
+    if matches!(outcome.exit_reason, AgentExitReason::Cancelled)
+        && outcome.error.as_deref() != Some("operator_cancel")
+    {
         if let Some(sink) = &mut agent.event_sink {
             if let Err(e) = sink.emit(Event::new(
                 outcome.run_id.clone(),
                 0,
-                EventKind::RunEnd,
-                serde_json::json!({"exit_reason":"cancelled"}),
+                RunEndPayload {
+                    exit_reason: "cancelled".to_string(),
+                },
             )) {
                 eprintln!("WARN: failed to emit cancellation event: {e}");
             }
@@ -523,6 +742,16 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
         &outcome,
     );
 
+    if let Some(report) = agent.operator_queue.replay_report() {
+        if !report.divergences.is_empty() {
+            eprintln!(
+                "WARN: queue replay from {} diverged: {} of {} scheduled message(s) did not land at the recorded boundary",
+                report.source_run_id,
+                report.divergences.len(),
+                report.scheduled
+            );
+        }
+    }
     let (run_artifact_path, runtime_checkpoint_path) =
         finalize_run_artifacts(FinalizeRunArtifactsInput {
             event_sink: &mut agent.event_sink,
@@ -556,11 +785,18 @@ pub(crate) async fn run_agent_with_ui<P: ModelProvider>(
             repo_map_resolution: repo_map_resolution.as_ref(),
             lsp_context_resolution: lsp_context_resolution.as_ref(),
             activated_packs: &activated_packs,
+            context_pack: context_pack.as_ref(),
+            learned_guidance: learned_guidance.as_ref(),
+            prompt_template: prompt_template.as_ref().map(|t| &t.record),
             outcome: &outcome,
             planner_record,
             worker_record,
             mcp_runtime_trace: agent.mcp_runtime_trace.clone(),
             mcp_pin_snapshot,
+            environment_probe,
+            operator_queue: agent.operator_queue.run_record(),
+            provider_pacing: provider_pacing.as_ref().map(|p| p.run_record()),
+            otlp_export: otlp_stats.as_ref().map(|s| s.run_record()),
         })?;
 
     if !suppress_stdout_stream {
@@ -870,6 +1106,133 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn attached_channel_sink_sees_the_same_events_as_the_events_file() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = crate::store::resolve_state_paths(tmp.path(), None, None, None, None);
+        let events_path = tmp.path().join("events.jsonl");
+        let mut args = crate::RunArgs::parse_from(["localagent", "--disable-implementation-guard"]);
+        args.workdir = tmp.path().to_path_buf();
+        args.events = Some(events_path.clone());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let out = super::run_agent_with_ui(
+            MockProvider::new(),
+            super::AgentUiRunRequest::new(
+                ProviderKind::Mock,
+                "mock://local",
+                "mock-model",
+                "say hi",
+                &args,
+                &paths,
+            )
+            .with_event_sink(Box::new(crate::events::ChannelSink::new(tx))),
+        )
+        .await
+        .expect("run");
+        assert!(matches!(
+            out.outcome.exit_reason,
+            crate::AgentExitReason::Ok
+        ));
+        let captured = std::iter::from_fn(|| rx.try_recv().ok()).collect::<Vec<_>>();
+        let logged = crate::events::read_events(&events_path).expect("events file");
+        assert!(!captured.is_empty());
+        assert_eq!(
+            captured
+                .iter()
+                .map(|e| serde_json::to_string(&e.kind).expect("kind"))
+                .collect::<Vec<_>>(),
+            logged
+                .iter()
+                .map(|e| serde_json::to_string(&e.kind).expect("kind"))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn recorded_provider_trace_replays_the_same_final_output() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = crate::store::resolve_state_paths(tmp.path(), None, None, None, None);
+        let mut args = crate::RunArgs::parse_from([
+            "localagent",
+            "--disable-implementation-guard",
+            "--no-session",
+        ]);
+        args.workdir = tmp.path().to_path_buf();
+        args.record_provider = true;
+        let recorded = super::run_agent(
+            MockProvider::new(),
+            ProviderKind::Mock,
+            "mock://local",
+            "mock-model",
+            "say hi",
+            &args,
+            &paths,
+        )
+        .await
+        .expect("recorded run");
+        let trace = crate::providers::recording::provider_trace_path(
+            &paths.state_dir,
+            &recorded.outcome.run_id,
+        );
+        let replay = crate::providers::recording::ReplayProvider::load(
+            &trace,
+            crate::providers::recording::ReplayMatching::Strict,
+        )
+        .expect("trace");
+
+        args.record_provider = false;
+        args.replay_provider = Some(recorded.outcome.run_id.clone());
+        let replayed = super::run_agent(
+            replay,
+            ProviderKind::Mock,
+            "mock://local",
+            "mock-model",
+            "say hi",
+            &args,
+            &paths,
+        )
+        .await
+        .expect("replayed run");
+        assert!(
+            matches!(replayed.outcome.exit_reason, crate::AgentExitReason::Ok),
+            "{:?}",
+            replayed.outcome.error
+        );
+        assert_eq!(replayed.outcome.final_output, recorded.outcome.final_output);
+    }
+
+    #[test]
+    fn gate_context_from_default_run_args_matches_cli_defaults() {
+        let tmp = tempdir().expect("tempdir");
+        let args = crate::RunArgs::parse_from(["localagent"]);
+        let ctx = super::setup::build_gate_context(
+            &args,
+            tmp.path(),
+            ProviderKind::Mock,
+            "mock-model",
+            crate::target::ExecTargetKind::Host,
+        )
+        .expect("default args are consistent");
+        assert!(!ctx.allow_shell && !ctx.allow_write && !ctx.enable_write_tools);
+        assert!(ctx.shell_allowlist.is_none());
+        assert_eq!(ctx.approval_mode, args.approval_mode);
+        assert_eq!(ctx.auto_approve_scope, args.auto_approve_scope);
+        assert_eq!(ctx.max_tool_output_bytes, args.max_tool_output_bytes);
+        assert_eq!(ctx.max_read_bytes, args.max_read_bytes);
+        assert_eq!(ctx.model, "mock-model");
+        assert!(!ctx.taint_enabled);
+
+        let write_tools_only = crate::RunArgs::parse_from(["localagent", "--enable-write-tools"]);
+        assert!(super::setup::build_gate_context(
+            &write_tools_only,
+            tmp.path(),
+            ProviderKind::Mock,
+            "mock-model",
+            crate::target::ExecTargetKind::Host,
+        )
+        .is_err());
+    }
+
     #[test]
     fn compact_manual_repair_context_detects_prepared_control_tasks() {
         let workdir = PathBuf::from(
@@ -232,7 +232,10 @@ pub(super) fn validate_final_run_artifact_consistency(
         | AgentExitReason::Denied
         | AgentExitReason::HookAborted
         | AgentExitReason::MaxSteps
-        | AgentExitReason::BudgetExceeded => {
+        | AgentExitReason::BudgetExceeded
+        | AgentExitReason::DeadlineExceeded
+        | AgentExitReason::ContextOverflow
+        | AgentExitReason::OutputSchemaViolation => {
             ensure!(
                 run_checkpoint.is_none(),
                 "terminal run artifact cannot keep a resumable run checkpoint"
@@ -555,16 +558,24 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
     push_option_display(&mut out, "--max-tokens", args.max_tokens);
     push_option_display(&mut out, "--seed", args.seed);
     push_arg(&mut out, "--max-steps", &args.max_steps.to_string());
+    push_flag(&mut out, "--require-exact-model", args.require_exact_model);
+    push_path_opt(
+        &mut out,
+        "--final-output-schema",
+        args.final_output_schema.as_ref(),
+    );
     push_arg(
         &mut out,
         "--max-wall-time-ms",
         &args.max_wall_time_ms.to_string(),
     );
+    push_arg(&mut out, "--deadline-ms", &args.deadline_ms.to_string());
     push_arg(
         &mut out,
         "--max-total-tool-calls",
         &args.max_total_tool_calls.to_string(),
     );
+    push_vec(&mut out, "--max-tool-calls", &args.max_tool_calls);
     push_arg(&mut out, "--max-mcp-calls", &args.max_mcp_calls.to_string());
     push_arg(
         &mut out,
@@ -591,11 +602,30 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
         "--max-browser-calls",
         &args.max_browser_calls.to_string(),
     );
+    push_arg(
+        &mut out,
+        "--max-compaction-summaries",
+        &args.max_compaction_summaries.to_string(),
+    );
     push_arg(
         &mut out,
         "--tool-exec-timeout-ms",
         &args.tool_exec_timeout_ms.to_string(),
     );
+    push_flag(&mut out, "--stream-tool-output", args.stream_tool_output);
+    push_flag(
+        &mut out,
+        "--parallel-readonly-tools",
+        args.parallel_readonly_tools,
+    );
+    push_flag(&mut out, "--enable-subtasks", args.enable_subtasks);
+    push_arg(
+        &mut out,
+        "--approval-diff-max-lines",
+        &args.approval_diff_max_lines.to_string(),
+    );
+    push_flag(&mut out, "--dry-run-writes", args.dry_run_writes);
+    push_flag(&mut out, "--checkpoint-writes", args.checkpoint_writes);
     push_arg(
         &mut out,
         "--post-write-verify-timeout-ms",
@@ -605,8 +635,19 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
     push_path_opt(&mut out, "--state-dir", args.state_dir.as_ref());
     push_vec(&mut out, "--mcp", &args.mcp);
     push_vec(&mut out, "--pack", &args.packs);
+    push_option(&mut out, "--context-pack", args.context_pack.as_ref());
+    push_flag(&mut out, "--use-learnings", args.use_learnings);
+    push_vec(&mut out, "--learning-tag", &args.learning_tags);
     push_path_opt(&mut out, "--mcp-config", args.mcp_config.as_ref());
+    push_vec(&mut out, "--mcp-root", &args.mcp_root);
+    push_flag(&mut out, "--mcp-strict-names", args.mcp_strict_names);
+    push_arg(
+        &mut out,
+        "--mcp-reconnect-attempts",
+        &args.mcp_reconnect_attempts.to_string(),
+    );
     push_flag(&mut out, "--allow-shell", args.allow_shell);
+    push_vec(&mut out, "--allow-shell-cmd", &args.allow_shell_cmd);
     push_flag(
         &mut out,
         "--allow-shell-in-workdir",
@@ -620,6 +661,24 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
     push_arg(&mut out, "--docker-workdir", &args.docker_workdir);
     push_value_enum(&mut out, "--docker-network", args.docker_network);
     push_option(&mut out, "--docker-user", args.docker_user.as_ref());
+    push_flag(&mut out, "--docker-persistent", args.docker_persistent);
+    push_option(&mut out, "--docker-memory", args.docker_memory.as_ref());
+    push_option(&mut out, "--docker-cpus", args.docker_cpus.as_ref());
+    push_option_display(&mut out, "--docker-pids-limit", args.docker_pids_limit);
+    push_flag(
+        &mut out,
+        "--docker-readonly-workdir",
+        args.docker_readonly_workdir,
+    );
+    push_option(&mut out, "--ssh-host", args.ssh_host.as_ref());
+    push_option(&mut out, "--ssh-user", args.ssh_user.as_ref());
+    push_option_display(&mut out, "--ssh-port", args.ssh_port);
+    push_path_opt(
+        &mut out,
+        "--ssh-identity-file",
+        args.ssh_identity_file.as_ref(),
+    );
+    push_option(&mut out, "--ssh-workdir", args.ssh_workdir.as_ref());
     push_arg(
         &mut out,
         "--max-tool-output-bytes",
@@ -633,7 +692,17 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
     push_value_enum(&mut out, "--trust", args.trust);
     push_value_enum(&mut out, "--approval-mode", args.approval_mode);
     push_value_enum(&mut out, "--auto-approve-scope", args.auto_approve_scope);
+    push_arg(
+        &mut out,
+        "--workspace-approval-ttl-hours",
+        &args.workspace_approval_ttl_hours.to_string(),
+    );
     push_value_enum(&mut out, "--approval-key", args.approval_key);
+    push_flag(
+        &mut out,
+        "--skip-unevaluated-gate-snapshots",
+        args.skip_unevaluated_gate_snapshots,
+    );
     push_flag(&mut out, "--unsafe", args.unsafe_mode);
     push_flag(&mut out, "--no-limits", args.no_limits);
     push_flag(
@@ -662,12 +731,15 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
         "--max-context-chars",
         &args.max_context_chars.to_string(),
     );
+    push_option_display(&mut out, "--max-context-tokens", args.max_context_tokens);
     push_flag(&mut out, "--use-repomap", args.use_repomap);
     push_arg(
         &mut out,
         "--repomap-max-bytes",
         &args.repomap_max_bytes.to_string(),
     );
+    push_flag(&mut out, "--repomap-refresh", args.repomap_refresh);
+    push_flag(&mut out, "--repomap-rank", args.repomap_rank);
     push_value_enum_opt(&mut out, "--lsp-provider", args.lsp_provider);
     push_path_opt(&mut out, "--lsp-command", args.lsp_command.as_ref());
     push_option(
@@ -695,6 +767,17 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
         "--hooks-max-stdout-bytes",
         &args.hooks_max_stdout_bytes.to_string(),
     );
+    push_arg(
+        &mut out,
+        "--hooks-max-invocations",
+        &args.hooks_max_invocations.to_string(),
+    );
+    push_arg(
+        &mut out,
+        "--hooks-max-cumulative-ms",
+        &args.hooks_max_cumulative_ms.to_string(),
+    );
+    push_flag(&mut out, "--hooks-budget-strict", args.hooks_budget_strict);
     push_value_enum(&mut out, "--tool-args-strict", args.tool_args_strict);
     push_path_opt(
         &mut out,
@@ -772,6 +855,11 @@ fn build_resume_argv(args: &RunArgs, prompt: &str) -> Vec<String> {
         "--tui-max-log-lines",
         &args.tui_max_log_lines.to_string(),
     );
+    push_arg(
+        &mut out,
+        "--tui-max-line-chars",
+        &args.tui_max_line_chars.to_string(),
+    );
     push_value_enum(&mut out, "--mode", args.mode);
     push_option(&mut out, "--planner-model", args.planner_model.as_ref());
     push_option(&mut out, "--worker-model", args.worker_model.as_ref());
@@ -913,9 +1001,13 @@ mod tests {
                 taint_enforced: false,
                 escalated: false,
                 escalation_reason: None,
+                taint_sources: Vec::new(),
+                denial_class: None,
+                gate_context: None,
             }],
             compaction_settings: CompactionSettings {
                 max_context_chars: 0,
+                max_context_tokens: None,
                 mode: CompactionMode::Off,
                 keep_last: 20,
                 tool_result_persist: ToolResultPersist::Digest,
@@ -925,8 +1017,16 @@ mod tests {
             hook_invocations: Vec::new(),
             provider_retry_count: 0,
             provider_error_count: 0,
+            provider_retry_wait_ms: 0,
             token_usage: None,
             taint: None,
+            digest_refetch: None,
+            tool_result_artifacts: Vec::new(),
+            model_served: None,
+            subtask_run_ids: Vec::new(),
+            post_run_hook_error: None,
+            timeline: Vec::new(),
+            steps: Vec::new(),
         }
     }
 
@@ -1,5 +1,5 @@
 use crate::agent::{self, AgentExitReason};
-use crate::events::EventKind;
+use crate::events::ReproSnapshotPayload;
 use crate::gate::ProviderKind;
 use crate::planner;
 use crate::repro;
@@ -53,6 +53,10 @@ pub(super) struct RunArtifactWriteInput {
     pub(super) repro_record: Option<crate::repro::RunReproRecord>,
     pub(super) mcp_runtime_trace: Vec<crate::agent::McpRuntimeTraceEntry>,
     pub(super) mcp_pin_snapshot: Option<store::McpPinSnapshotRecord>,
+    pub(super) environment_probe: Option<crate::env_probe::EnvironmentProbeRecord>,
+    pub(super) operator_queue: Option<crate::operator_queue::OperatorQueueRunRecordV1>,
+    pub(super) provider_pacing: Option<crate::providers::pacing::ProviderPacingRecordV1>,
+    pub(super) otlp_export: Option<crate::otlp::OtlpExportRecordV1>,
 }
 
 pub(super) struct RunCliFingerprintBuildInput<'a> {
@@ -83,6 +87,9 @@ pub(super) struct RunCliFingerprintBuildInput<'a> {
     pub(super) repo_map_resolution: Option<&'a crate::repo_map::ResolvedRepoMap>,
     pub(super) lsp_context_resolution: Option<&'a crate::lsp_context::ResolvedLspContext>,
     pub(super) activated_packs: &'a [crate::packs::ActivatedPack],
+    pub(super) context_pack: Option<&'a crate::context_packs::ContextPackResolution>,
+    pub(super) learned_guidance: Option<&'a crate::learning::LearnedGuidance>,
+    pub(super) prompt_template: Option<&'a crate::store::PromptTemplateRecord>,
 }
 
 pub(super) struct FinalizeRunArtifactsInput<'a> {
@@ -118,11 +125,18 @@ pub(super) struct FinalizeRunArtifactsInput<'a> {
     pub(super) repo_map_resolution: Option<&'a crate::repo_map::ResolvedRepoMap>,
     pub(super) lsp_context_resolution: Option<&'a crate::lsp_context::ResolvedLspContext>,
     pub(super) activated_packs: &'a [crate::packs::ActivatedPack],
+    pub(super) context_pack: Option<&'a crate::context_packs::ContextPackResolution>,
+    pub(super) learned_guidance: Option<&'a crate::learning::LearnedGuidance>,
+    pub(super) prompt_template: Option<&'a crate::store::PromptTemplateRecord>,
     pub(super) outcome: &'a agent::AgentOutcome,
     pub(super) planner_record: Option<PlannerRunRecord>,
     pub(super) worker_record: Option<WorkerRunRecord>,
     pub(super) mcp_runtime_trace: Vec<crate::agent::McpRuntimeTraceEntry>,
     pub(super) mcp_pin_snapshot: Option<store::McpPinSnapshotRecord>,
+    pub(super) environment_probe: Option<crate::env_probe::EnvironmentProbeRecord>,
+    pub(super) operator_queue: Option<crate::operator_queue::OperatorQueueRunRecordV1>,
+    pub(super) provider_pacing: Option<crate::providers::pacing::ProviderPacingRecordV1>,
+    pub(super) otlp_export: Option<crate::otlp::OtlpExportRecordV1>,
 }
 
 pub(super) fn write_run_artifact_with_warning(
@@ -153,6 +167,10 @@ pub(super) fn write_run_artifact_with_warning(
         input.repro_record,
         input.mcp_runtime_trace,
         input.mcp_pin_snapshot,
+        input.environment_probe,
+        input.operator_queue,
+        input.provider_pacing,
+        input.otlp_export,
     ) {
         Ok(p) => Some(p),
         Err(e) => {
@@ -191,6 +209,9 @@ pub(super) fn finalize_ui_and_session_state(
             eprintln!("WARN: tui thread ended unexpectedly");
         }
     }
+    if let Some(e) = &outcome.post_run_hook_error {
+        eprintln!("ERROR: post_run hook failed: {e}");
+    }
     if !args.no_session {
         session_data.messages = extract_session_messages(&outcome.messages);
         session_data.settings = settings_from_run(resolved_settings);
@@ -283,12 +304,11 @@ pub(super) fn build_and_emit_repro_snapshot(
             event_sink,
             input.run_id,
             0,
-            EventKind::ReproSnapshot,
-            serde_json::json!({
-                "enabled": true,
-                "env_mode": r.env_mode,
-                "repro_hash_hex": r.repro_hash_hex
-            }),
+            ReproSnapshotPayload {
+                enabled: true,
+                env_mode: r.env_mode.clone(),
+                repro_hash_hex: r.repro_hash_hex.clone(),
+            },
         );
         if matches!(input.args.repro_env, ReproEnvMode::All) {
             eprintln!(
@@ -333,6 +353,9 @@ pub(super) fn build_run_cli_config_fingerprint_bundle(
         repo_map: input.repo_map_resolution,
         lsp_context: input.lsp_context_resolution,
         activated_packs: input.activated_packs,
+        context_pack: input.context_pack,
+        learned_guidance: input.learned_guidance,
+        prompt_template: input.prompt_template,
     });
     let config_fingerprint = runtime_paths::build_config_fingerprint(
         &cli_config,
@@ -389,6 +412,9 @@ pub(super) fn finalize_run_artifacts(
             repo_map_resolution: input.repo_map_resolution,
             lsp_context_resolution: input.lsp_context_resolution,
             activated_packs: input.activated_packs,
+            context_pack: input.context_pack,
+            learned_guidance: input.learned_guidance,
+            prompt_template: input.prompt_template,
         })?;
     let repro_record = build_and_emit_repro_snapshot(
         input.event_sink,
@@ -490,6 +516,10 @@ pub(super) fn finalize_run_artifacts(
         repro_record,
         mcp_runtime_trace: input.mcp_runtime_trace,
         mcp_pin_snapshot: input.mcp_pin_snapshot,
+        environment_probe: input.environment_probe,
+        operator_queue: input.operator_queue,
+        provider_pacing: input.provider_pacing,
+        otlp_export: input.otlp_export,
     });
     let runtime_checkpoint_path = if let Some(record) =
         super::checkpoint::runtime_checkpoint_record_for_outcome(
@@ -5,9 +5,16 @@ use anyhow::Context;
 use tokio::sync::watch;
 
 use crate::agent::{PlanToolEnforcementMode, PolicyLoadedInfo};
-use crate::events::{Event, EventKind};
+use crate::events::{
+    ErrorPayload, Event, ExecutionTierSelectedPayload, HooksConfigReloadFailedPayload,
+    HooksConfigReloadedPayload, McpMetadataSanitizedPayload, McpPinnedPayload,
+    McpToolRejectedPayload, PackActivatedPayload, SessionRecoveredPayload,
+    StateLockReclaimedPayload, TaskContractResolvedPayload,
+};
 use crate::gate::{GateContext, ProviderKind};
+use crate::hooks::runner::HooksReload;
 use crate::mcp::registry::McpRegistry;
+use crate::mcp::sanitize::{McpMetadataSanitizedRecord, McpToolRejectedRecord};
 use crate::packs;
 use crate::providers::ModelProvider;
 use crate::run_prep;
@@ -43,9 +50,16 @@ pub(super) struct RuntimeLaunch {
     pub(super) effective_plan_tool_enforcement: PlanToolEnforcementMode,
     pub(super) session_store: SessionStore,
     pub(super) session_data: session::SessionData,
+    pub(super) session_recovery: Option<session::SessionRecovery>,
     pub(super) resolved_settings: session::RunSettingResolution,
     pub(super) session_messages: Vec<Message>,
     pub(super) task_memory: Option<Message>,
+    pub(super) environment_probe: Option<crate::env_probe::EnvironmentProbeRecord>,
+    pub(super) queue_replay: Option<crate::operator_queue::QueueReplayScript>,
+    pub(super) attribution: Option<crate::attribution::AttributionConfig>,
+    pub(super) read_allowlist: Option<crate::tools::ReadAllowlist>,
+    pub(super) secret_redactor: Option<Arc<crate::tools::SecretRedactor>>,
+    pub(super) mcp_root_map: crate::mcp::roots::McpRootMap,
     pub(super) instruction_resolution: crate::instructions::InstructionResolution,
     pub(super) task_contract: crate::agent::task_contract::TaskContractV1,
     pub(super) task_contract_provenance: crate::agent::task_contract::TaskContractProvenanceV1,
@@ -55,6 +69,9 @@ pub(super) struct RuntimeLaunch {
     pub(super) repo_map_resolution: Option<crate::repo_map::ResolvedRepoMap>,
     pub(super) lsp_context_resolution: Option<crate::lsp_context::ResolvedLspContext>,
     pub(super) activated_packs: Vec<packs::ActivatedPack>,
+    pub(super) context_pack: Option<crate::context_packs::ContextPackResolution>,
+    pub(super) learned_guidance: Option<crate::learning::LearnedGuidance>,
+    pub(super) prompt_template: Option<crate::prompt_template::RenderedPromptTemplate>,
     pub(super) mcp_config_path: PathBuf,
     pub(super) mcp_registry: Option<Arc<McpRegistry>>,
     pub(super) mcp_tool_snapshot: Vec<store::McpToolSnapshotEntry>,
@@ -70,8 +87,10 @@ pub(super) struct RuntimeLaunch {
     pub(super) tool_schema_hash_hex_map: std::collections::BTreeMap<String, String>,
     pub(super) hooks_config_hash_hex: Option<String>,
     pub(super) hook_manager: crate::hooks::runner::HookManager,
+    pub(super) hooks_reload: crate::hooks::session::SessionHooksReload,
     pub(super) tool_catalog: Vec<store::ToolCatalogEntry>,
     pub(super) event_sink: Option<Box<dyn crate::events::EventSink>>,
+    pub(super) otlp_stats: Option<Arc<crate::otlp::OtlpExportStats>>,
     pub(super) _cancel_tx: tokio::sync::watch::Sender<bool>,
     pub(super) cancel_rx: tokio::sync::watch::Receiver<bool>,
     pub(super) ui_join: Option<std::thread::JoinHandle<anyhow::Result<()>>>,
@@ -87,8 +106,10 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
     args: &RunArgs,
     paths: &store::StatePaths,
     external_ui_tx: Option<Sender<Event>>,
+    external_event_sinks: Vec<Box<dyn crate::events::EventSink>>,
     external_cancel_pair: Option<(watch::Sender<bool>, watch::Receiver<bool>)>,
     shared_mcp_registry: Option<Arc<McpRegistry>>,
+    session_hooks: Option<&crate::hooks::session::SessionHooks>,
     suppress_stdout_stream: bool,
 ) -> anyhow::Result<RuntimeLaunch> {
     let mut effective_args = args.clone();
@@ -99,7 +120,9 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
     let mut args = effective_args;
     let workdir = std::fs::canonicalize(&args.workdir)
         .with_context(|| format!("failed to resolve workdir: {}", args.workdir.display()))?;
-    let exec_target = build_exec_target(&args)?;
+    let gate_build = runtime_wiring::build_gate(&args, paths)?;
+    let exec_target =
+        build_exec_target(&args, &workdir, gate_build.policy_for_exposure.as_ref()).await?;
     let resolved_target_kind = exec_target.kind();
     let _target_desc = exec_target.describe();
     let mut gate_ctx = build_gate_context(
@@ -108,8 +131,38 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
         provider_kind,
         default_model,
         resolved_target_kind,
-    );
-    let gate_build = runtime_wiring::build_gate(&args, paths)?;
+    )?;
+    // Policy globs join the CLI ones so the run record shows the full allowlist.
+    if let Some(policy) = gate_build.policy_for_exposure.as_ref() {
+        for glob in policy.read_allowlist() {
+            if !args.allow_read_path.contains(glob) {
+                args.allow_read_path.push(glob.clone());
+            }
+        }
+    }
+    let read_allowlist = crate::tools::ReadAllowlist::from_globs(&args.allow_read_path)
+        .context("invalid --allow-read-path")?;
+    let secret_redactor = gate_build
+        .policy_for_exposure
+        .as_ref()
+        .and_then(|policy| policy.tool_result_redactor())
+        .cloned()
+        .map(Arc::new);
+    let mcp_root_map =
+        crate::mcp::roots::McpRootMap::from_specs(&args.mcp_root).context("invalid --mcp-root")?;
+    if let Some(server) = mcp_root_map
+        .servers()
+        .into_iter()
+        .find(|server| !args.mcp.iter().any(|m| m == server))
+    {
+        return Err(anyhow::anyhow!(
+            "--mcp-root names MCP server '{server}', which is not enabled with --mcp"
+        ));
+    }
+    let attribution = crate::attribution::resolve_run_attribution(
+        gate_build.policy_for_exposure.as_ref(),
+        args.no_attribution,
+    )?;
     let policy_loaded_info = gate_build.policy_version.map(|version| PolicyLoadedInfo {
         version,
         rules_count: gate_build
@@ -120,6 +173,7 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
         includes_count: gate_build.includes_resolved.len(),
         includes_resolved: gate_build.includes_resolved.clone(),
         mcp_allowlist: gate_build.mcp_allowlist.clone(),
+        policy_hash_hex: gate_build.policy_hash_hex.clone(),
     });
 
     let planner_strict_effective = if args.no_planner_strict {
@@ -145,18 +199,110 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
 
     let SessionBootstrap {
         session_store,
-        session_data,
+        mut session_data,
+        session_recovery,
         resolved_settings,
         session_messages,
         task_memory,
     } = build_session_bootstrap(&args, paths)?;
+    let environment_probe = if args.probe_environment {
+        let config = crate::env_probe::EnvironmentProbeConfig::from_policy(
+            gate_build.policy_for_exposure.as_ref(),
+        );
+        let record = crate::env_probe::resolve_environment_facts(
+            exec_target.as_ref(),
+            &workdir,
+            &config,
+            session_data.environment_facts.as_ref(),
+            time::OffsetDateTime::now_utc(),
+        )
+        .await;
+        session_data.environment_facts = Some(record.facts.clone());
+        Some(record)
+    } else {
+        None
+    };
+    let queue_replay = match args.replay_queue_from.as_deref() {
+        Some(source_run_id) => {
+            let source =
+                store::load_run_record(&paths.state_dir, source_run_id).with_context(|| {
+                    format!("failed to load run {source_run_id} for --replay-queue-from")
+                })?;
+            let entries = source
+                .operator_queue
+                .map(|queue| queue.entries)
+                .unwrap_or_default();
+            Some(crate::operator_queue::QueueReplayScript::from_log(
+                source_run_id,
+                &entries,
+            ))
+        }
+        None => None,
+    };
+    let max_read_bytes = if args.no_limits {
+        0
+    } else {
+        args.max_read_bytes
+    };
+    let prompt_template = match args.template.as_deref() {
+        Some(template_path) => Some(
+            crate::prompt_template::render_prompt_template(
+                exec_target.as_ref(),
+                &workdir,
+                template_path,
+                &crate::prompt_template::parse_template_vars(&args.template_vars)?,
+                args.allow_env_in_template,
+                &crate::context_packs::ContextPackReadPolicy {
+                    max_read_bytes,
+                    read_allowlist: read_allowlist.as_ref(),
+                    policy: gate_build.policy_for_exposure.as_ref(),
+                },
+            )
+            .await?,
+        ),
+        None => None,
+    };
+    let prompt = prompt_template
+        .as_ref()
+        .map_or(prompt, |rendered| rendered.prompt.as_str());
     let ContextAugmentations {
         instruction_resolution,
         project_guidance_resolution,
         repo_map_resolution,
         lsp_context_resolution,
         activated_packs,
-    } = build_context_augmentations(prompt, &args, paths, &worker_model)?;
+    } = build_context_augmentations(prompt, &args, paths, &worker_model, read_allowlist.as_ref())?;
+    let context_pack = match args.context_pack.as_deref() {
+        Some(name) => {
+            let pack = crate::context_packs::load_context_pack(&workdir, name)?;
+            Some(
+                crate::context_packs::read_context_pack(
+                    exec_target.as_ref(),
+                    &workdir,
+                    &pack,
+                    &crate::context_packs::ContextPackReadPolicy {
+                        max_read_bytes,
+                        read_allowlist: read_allowlist.as_ref(),
+                        policy: gate_build.policy_for_exposure.as_ref(),
+                    },
+                )
+                .await?,
+            )
+        }
+        None => None,
+    };
+    let learned_guidance = if args.use_learnings {
+        Some(crate::learning::select_learned_guidance(
+            &paths.state_dir,
+            &crate::learning::LearnedGuidanceSelector {
+                profile: args.reliability_profile.as_deref(),
+                tags: &args.learning_tags,
+                max_chars: crate::learning::DEFAULT_LEARNED_GUIDANCE_MAX_CHARS,
+            },
+        )?)
+    } else {
+        None
+    };
     validate_runtime_owned_http_timeouts(
         &args,
         planner_strict_effective,
@@ -196,8 +342,15 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
         tool_schema_hash_hex_map,
         hooks_config_hash_hex,
         hook_manager,
+        hooks_reload,
         tool_catalog,
-    } = build_hook_and_tool_setup(&args, paths, &resolved_settings, &prep.all_tools)?;
+    } = build_hook_and_tool_setup(
+        &args,
+        paths,
+        &resolved_settings,
+        &prep.all_tools,
+        session_hooks,
+    )?;
     let task_contract_resolution = crate::agent::task_contract::resolve_task_contract(
         &args,
         prompt,
@@ -216,6 +369,7 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
 
     let UiRuntimeSetup {
         event_sink,
+        otlp_stats,
         _cancel_tx,
         cancel_rx,
         ui_join,
@@ -229,6 +383,7 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
         policy_hash_hex: &gate_build.policy_hash_hex,
         mcp_tool_catalog_hash_hex: &prep.mcp_tool_catalog_hash_hex,
         external_ui_tx,
+        external_event_sinks,
         external_cancel_pair,
         suppress_stdout_stream,
     })?;
@@ -248,9 +403,16 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
         effective_plan_tool_enforcement,
         session_store,
         session_data,
+        session_recovery,
         resolved_settings,
         session_messages,
         task_memory,
+        environment_probe,
+        queue_replay,
+        attribution,
+        read_allowlist,
+        secret_redactor,
+        mcp_root_map,
         instruction_resolution,
         task_contract: task_contract_resolution.contract,
         task_contract_provenance: task_contract_resolution.provenance,
@@ -259,6 +421,9 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
         repo_map_resolution,
         lsp_context_resolution,
         activated_packs,
+        context_pack,
+        learned_guidance,
+        prompt_template,
         mcp_config_path,
         mcp_registry,
         mcp_tool_snapshot: prep.mcp_tool_snapshot,
@@ -274,8 +439,10 @@ pub(super) async fn prepare_runtime_launch<P: ModelProvider>(
         tool_schema_hash_hex_map,
         hooks_config_hash_hex,
         hook_manager,
+        hooks_reload,
         tool_catalog,
         event_sink,
+        otlp_stats,
         _cancel_tx,
         cancel_rx,
         ui_join,
@@ -291,6 +458,9 @@ fn resolve_execution_tier(
     if matches!(resolved_target_kind, ExecTargetKind::Docker) {
         return crate::agent_runtime::state::ExecutionTier::DockerIsolated;
     }
+    if matches!(resolved_target_kind, ExecTargetKind::Ssh) {
+        return crate::agent_runtime::state::ExecutionTier::RemoteSsh;
+    }
     if allow_shell {
         return crate::agent_runtime::state::ExecutionTier::ScopedHostShell;
     }
@@ -329,6 +499,8 @@ pub(super) fn build_mcp_pin_snapshot(
         || launch.mcp_startup_live_catalog_hash_hex.is_some()
         || launch.mcp_tool_docs_hash_hex.is_some()
         || launch.mcp_startup_live_docs_hash_hex.is_some()
+        || !mcp_metadata_sanitized(launch).is_empty()
+        || !mcp_tool_rejected(launch).is_empty()
     {
         Some(store::McpPinSnapshotRecord {
             enforcement: launch.mcp_pin_enforcement.clone(),
@@ -341,62 +513,166 @@ pub(super) fn build_mcp_pin_snapshot(
             startup_live_docs_hash_hex: launch.mcp_startup_live_docs_hash_hex.clone(),
             mcp_config_hash_hex: launch.mcp_config_hash_hex.clone(),
             pinned: launch.mcp_snapshot_pinned,
+            metadata_sanitized: mcp_metadata_sanitized(launch).to_vec(),
+            tool_rejected: mcp_tool_rejected(launch).to_vec(),
         })
     } else {
         None
     }
 }
 
+fn mcp_metadata_sanitized(launch: &RuntimeLaunch) -> &[McpMetadataSanitizedRecord] {
+    launch
+        .mcp_registry
+        .as_deref()
+        .map(|reg| reg.metadata_sanitized())
+        .unwrap_or_default()
+}
+
+fn mcp_tool_rejected(launch: &RuntimeLaunch) -> &[McpToolRejectedRecord] {
+    launch
+        .mcp_registry
+        .as_deref()
+        .map(|reg| reg.tool_rejected())
+        .unwrap_or_default()
+}
+
+fn mcp_tool_rejected_payload(record: &McpToolRejectedRecord) -> McpToolRejectedPayload {
+    McpToolRejectedPayload {
+        schema: "openagent.mcp_tool_rejected.v1".to_string(),
+        server: record.server.clone(),
+        tool: record.tool.clone(),
+        reason: record.reason,
+        conflicts_with: record.conflicts_with.clone(),
+    }
+}
+
+fn mcp_metadata_sanitized_payload(
+    record: &McpMetadataSanitizedRecord,
+) -> McpMetadataSanitizedPayload {
+    McpMetadataSanitizedPayload {
+        schema: "openagent.mcp_metadata_sanitized.v1".to_string(),
+        server: record.server.clone(),
+        tool: record.tool.clone(),
+        action: record.action,
+        matched_patterns: record.matched_patterns.clone(),
+    }
+}
+
 pub(super) fn emit_startup_runtime_events(launch: &mut RuntimeLaunch, run_id: &str) {
+    match &launch.hooks_reload.reload {
+        HooksReload::Unchanged => {}
+        HooksReload::Reloaded {
+            old_hash_hex,
+            new_hash_hex,
+        } => runtime_events::emit_event(
+            &mut launch.event_sink,
+            run_id,
+            0,
+            HooksConfigReloadedPayload {
+                old_hash_hex: old_hash_hex.clone(),
+                new_hash_hex: new_hash_hex.clone(),
+                approvals_invalidated: launch.hooks_reload.approvals_invalidated,
+            },
+        ),
+        HooksReload::Failed {
+            active_hash_hex,
+            error,
+        } => runtime_events::emit_event(
+            &mut launch.event_sink,
+            run_id,
+            0,
+            HooksConfigReloadFailedPayload {
+                active_hash_hex: active_hash_hex.clone(),
+                error: error.clone(),
+            },
+        ),
+    }
     runtime_events::emit_event(
         &mut launch.event_sink,
         run_id,
         0,
-        EventKind::ExecutionTierSelected,
-        serde_json::json!({
-            "execution_tier": launch.execution_tier
-        }),
+        ExecutionTierSelectedPayload {
+            execution_tier: launch.execution_tier.clone(),
+        },
     );
     runtime_events::emit_event(
         &mut launch.event_sink,
         run_id,
         0,
-        EventKind::TaskContractResolved,
-        serde_json::json!({
-            "task_kind": launch.task_contract.task_kind,
-            "validation_requirement": launch.task_contract.validation_requirement,
-            "allowed_tools_semantics": launch.task_contract.allowed_tools_semantics,
-            "task_kind_source": launch.task_contract_provenance.task_kind
-        }),
+        TaskContractResolvedPayload {
+            task_kind: launch.task_contract.task_kind.clone(),
+            validation_requirement: launch.task_contract.validation_requirement.clone(),
+            allowed_tools_semantics: launch.task_contract.allowed_tools_semantics.clone(),
+            task_kind_source: launch.task_contract_provenance.task_kind.clone(),
+        },
     );
     runtime_events::emit_event(
         &mut launch.event_sink,
         run_id,
         0,
-        EventKind::McpPinned,
-        serde_json::json!({
-            "enforcement": launch.mcp_pin_enforcement,
-            "configured_hash_hex": launch.mcp_tool_catalog_hash_hex,
-            "startup_live_hash_hex": launch.mcp_startup_live_catalog_hash_hex,
-            "configured_docs_hash_hex": launch.mcp_tool_docs_hash_hex,
-            "startup_live_docs_hash_hex": launch.mcp_startup_live_docs_hash_hex,
-            "mcp_config_hash_hex": launch.mcp_config_hash_hex,
-            "pinned": launch.mcp_snapshot_pinned
-        }),
+        McpPinnedPayload {
+            enforcement: launch.mcp_pin_enforcement.clone(),
+            configured_hash_hex: launch.mcp_tool_catalog_hash_hex.clone(),
+            startup_live_hash_hex: launch.mcp_startup_live_catalog_hash_hex.clone(),
+            configured_docs_hash_hex: launch.mcp_tool_docs_hash_hex.clone(),
+            startup_live_docs_hash_hex: launch.mcp_startup_live_docs_hash_hex.clone(),
+            mcp_config_hash_hex: launch.mcp_config_hash_hex.clone(),
+            pinned: launch.mcp_snapshot_pinned,
+        },
     );
+    let sanitized = mcp_metadata_sanitized(launch).to_vec();
+    for record in sanitized {
+        runtime_events::emit_event(
+            &mut launch.event_sink,
+            run_id,
+            0,
+            mcp_metadata_sanitized_payload(&record),
+        );
+    }
+    let rejected = mcp_tool_rejected(launch).to_vec();
+    for record in rejected {
+        runtime_events::emit_event(
+            &mut launch.event_sink,
+            run_id,
+            0,
+            mcp_tool_rejected_payload(&record),
+        );
+    }
     for pack in &launch.activated_packs {
         runtime_events::emit_event(
             &mut launch.event_sink,
             run_id,
             0,
-            EventKind::PackActivated,
-            serde_json::json!({
-                "schema": "openagent.pack_activated.v1",
-                "pack_id": pack.pack_id,
-                "pack_hash_hex": pack.pack_hash_hex,
-                "truncated": pack.truncated,
-                "bytes_kept": pack.bytes_kept
-            }),
+            PackActivatedPayload {
+                schema: "openagent.pack_activated.v1".to_string(),
+                pack_id: pack.pack_id.clone(),
+                pack_hash_hex: pack.pack_hash_hex.clone(),
+                truncated: pack.truncated,
+                bytes_kept: pack.bytes_kept,
+            },
+        );
+    }
+    if let Some(recovery) = &launch.session_recovery {
+        runtime_events::emit_event(
+            &mut launch.event_sink,
+            run_id,
+            0,
+            SessionRecoveredPayload {
+                schema: "openagent.session_recovered.v1".to_string(),
+                session_path: recovery.session_path.display().to_string(),
+                backup_path: recovery.backup_path.display().to_string(),
+                reason: recovery.reason.clone(),
+                recovered_messages: recovery.recovered_messages,
+            },
+        );
+    }
+    for reclaim in crate::store::take_state_lock_reclaims() {
+        runtime_events::emit_event(
+            &mut launch.event_sink,
+            run_id,
+            0,
+            StateLockReclaimedPayload::from(reclaim),
         );
     }
     if let Some(note) = &launch.qualification_fallback_note {
@@ -404,11 +680,11 @@ pub(super) fn emit_startup_runtime_events(launch: &mut RuntimeLaunch, run_id: &s
             &mut launch.event_sink,
             run_id,
             0,
-            EventKind::Error,
-            serde_json::json!({
-                "error": note,
-                "source": "orchestrator_qualification_fallback"
-            }),
+            ErrorPayload {
+                error: note.clone(),
+                source: Some("orchestrator_qualification_fallback".to_string()),
+                ..ErrorPayload::default()
+            },
         );
     }
 }
@@ -440,6 +716,8 @@ mod tests {
             &args,
             &paths,
             None,
+            Vec::new(),
+            None,
             None,
             None,
             true,
@@ -447,6 +725,63 @@ mod tests {
         .await
     }
 
+    #[tokio::test]
+    async fn docker_route_without_usable_docker_fails_at_startup() {
+        let args = crate::RunArgs::parse_from(["localagent", "--docker-image="]);
+        let routed = crate::trust::policy::Policy::from_yaml(
+            "version: 2\ndefault: allow\nexecution:\n  route:\n    shell_exec: docker\n",
+        )
+        .expect("policy");
+        let err = super::build_exec_target(&args, &args.workdir, Some(&routed))
+            .await
+            .err()
+            .expect("docker route must be validated at startup");
+        assert!(
+            format!("{err:#}").contains("policy execution.route sends tool calls to docker"),
+            "{err:#}"
+        );
+
+        let host_only = crate::trust::policy::Policy::from_yaml(
+            "version: 2\ndefault: allow\nexecution:\n  route:\n    filesystem_write: host\n",
+        )
+        .expect("policy");
+        let target = super::build_exec_target(&args, &args.workdir, Some(&host_only))
+            .await
+            .expect("host target");
+        assert_eq!(target.kind(), crate::target::ExecTargetKind::Host);
+        assert_eq!(
+            target.routed_kind(crate::types::SideEffects::ShellExec),
+            crate::target::ExecTargetKind::Host
+        );
+    }
+
+    #[tokio::test]
+    async fn ssh_target_requires_host_and_workdir_and_rejects_routes() {
+        let args = crate::RunArgs::parse_from(["localagent", "--exec-target", "ssh"]);
+        let err = super::build_exec_target(&args, &args.workdir, None)
+            .await
+            .err()
+            .expect("ssh without host must fail");
+        assert!(
+            format!("{err:#}").contains("requires --ssh-host and --ssh-workdir"),
+            "{err:#}"
+        );
+
+        let args = crate::RunArgs::parse_from(["localagent"]);
+        let routed = crate::trust::policy::Policy::from_yaml(
+            "version: 2\ndefault: allow\nexecution:\n  route:\n    shell_exec: ssh\n",
+        )
+        .expect("policy");
+        let err = super::build_exec_target(&args, &args.workdir, Some(&routed))
+            .await
+            .err()
+            .expect("routes to ssh must fail");
+        assert!(
+            format!("{err:#}").contains("cannot be combined with the ssh execution target"),
+            "{err:#}"
+        );
+    }
+
     #[tokio::test]
     async fn launch_resolves_explicit_task_kind_contract() {
         let launch = launch_for_args(
@@ -529,6 +864,84 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn launch_renders_template_before_contract_inference() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = crate::store::resolve_state_paths(tmp.path(), None, None, None, None);
+        let template = tmp.path().join("task.tmpl");
+        std::fs::write(
+            &template,
+            "Fix {{area}}. Before finishing, run {{check}} successfully.",
+        )
+        .expect("template");
+        let template_arg = template.display().to_string();
+        let mut args = crate::RunArgs::parse_from([
+            "localagent",
+            "--agent-mode",
+            "plan",
+            "--template",
+            &template_arg,
+            "--var",
+            "area=the parser",
+            "--var",
+            "check=cargo test",
+        ]);
+        args.workdir = tmp.path().to_path_buf();
+        let launch = prepare_runtime_launch(
+            &MockProvider::new(),
+            ProviderKind::Mock,
+            "mock://local",
+            "mock-model",
+            "",
+            &args,
+            &paths,
+            None,
+            Vec::new(),
+            None,
+            None,
+            None,
+            true,
+        )
+        .await
+        .expect("launch");
+        let rendered = launch.prompt_template.as_ref().expect("rendered template");
+        assert_eq!(
+            rendered.prompt,
+            "Fix the parser. Before finishing, run cargo test successfully."
+        );
+        assert_eq!(
+            launch.task_contract.validation_requirement,
+            ValidationRequirement::Command {
+                command: "cargo test".to_string(),
+            }
+        );
+
+        args.template_vars.pop();
+        let err = prepare_runtime_launch(
+            &MockProvider::new(),
+            ProviderKind::Mock,
+            "mock://local",
+            "mock-model",
+            "",
+            &args,
+            &paths,
+            None,
+            Vec::new(),
+            None,
+            None,
+            None,
+            true,
+        )
+        .await
+        .err()
+        .expect("missing var fails at startup");
+        assert!(
+            err.to_string()
+                .contains("undefined template variables: check"),
+            "{err}"
+        );
+    }
+
     #[tokio::test]
     async fn launch_prefers_explicit_validation_override() {
         let tmp = tempdir().expect("tempdir");
@@ -545,6 +958,8 @@ mod tests {
             &args,
             &paths,
             None,
+            Vec::new(),
+            None,
             None,
             None,
             true,
@@ -599,6 +1014,8 @@ mod tests {
             &args,
             &paths,
             None,
+            Vec::new(),
+            None,
             None,
             None,
             true,
@@ -630,4 +1047,45 @@ mod tests {
             crate::agent_runtime::state::ExecutionTier::ReadOnlyHost
         );
     }
+
+    #[test]
+    fn mcp_metadata_sanitized_event_names_pattern_not_text() {
+        let record = crate::mcp::sanitize::McpMetadataSanitizedRecord {
+            server: "stub".to_string(),
+            tool: "fetch".to_string(),
+            action: crate::mcp::sanitize::McpMetadataAction::Neutralized,
+            matched_patterns: vec!["phrase:ignore previous instructions".to_string()],
+        };
+        assert_eq!(
+            serde_json::to_value(super::mcp_metadata_sanitized_payload(&record))
+                .expect("payload json"),
+            serde_json::json!({
+                "schema": "openagent.mcp_metadata_sanitized.v1",
+                "server": "stub",
+                "tool": "fetch",
+                "action": "neutralized",
+                "matched_patterns": ["phrase:ignore previous instructions"]
+            })
+        );
+    }
+
+    #[test]
+    fn mcp_tool_rejected_event_names_reason_and_conflict() {
+        let record = crate::mcp::sanitize::McpToolRejectedRecord {
+            server: "evil".to_string(),
+            tool: "shell".to_string(),
+            reason: crate::mcp::sanitize::McpToolRejectReason::ReservedName,
+            conflicts_with: "shell".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(super::mcp_tool_rejected_payload(&record)).expect("payload json"),
+            serde_json::json!({
+                "schema": "openagent.mcp_tool_rejected.v1",
+                "server": "evil",
+                "tool": "shell",
+                "reason": "reserved_name",
+                "conflicts_with": "shell"
+            })
+        );
+    }
 }
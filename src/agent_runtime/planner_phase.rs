@@ -1,6 +1,6 @@
 use crate::agent::{self, Agent, AgentExitReason, PlanToolEnforcementMode};
 use crate::compaction::CompactionSettings;
-use crate::events::EventKind;
+use crate::events::{PlannerEndPayload, PlannerStartPayload, WorkerStartPayload};
 use crate::gate::{GateContext, ProviderKind};
 use crate::planner;
 use crate::planner_runtime;
@@ -114,6 +114,9 @@ pub(super) struct PlannerBootstrapInput<'a, P: ModelProvider> {
     pub(super) repo_map_resolution: Option<&'a crate::repo_map::ResolvedRepoMap>,
     pub(super) lsp_context_resolution: Option<&'a crate::lsp_context::ResolvedLspContext>,
     pub(super) activated_packs: &'a [crate::packs::ActivatedPack],
+    pub(super) context_pack: Option<&'a crate::context_packs::ContextPackResolution>,
+    pub(super) learned_guidance: Option<&'a crate::learning::LearnedGuidance>,
+    pub(super) prompt_template: Option<&'a crate::store::PromptTemplateRecord>,
 }
 
 pub(super) struct ReplanOrchestrationInput<'a, P: ModelProvider> {
@@ -146,11 +149,13 @@ pub(super) async fn run_planner_phase_with_start_event<P: ModelProvider>(
         event_sink,
         launch.run_id,
         0,
-        EventKind::PlannerStart,
-        serde_json::json!({
-            "planner_model": launch.planner_model,
-            "enforce_plan_tools_effective": format!("{:?}", launch.effective_plan_tool_enforcement).to_lowercase(),
-        }),
+        PlannerStartPayload {
+            planner_model: Some(launch.planner_model.to_string()),
+            enforce_plan_tools_effective: Some(
+                format!("{:?}", launch.effective_plan_tool_enforcement).to_lowercase(),
+            ),
+            ..PlannerStartPayload::default()
+        },
     );
     planner_runtime::run_planner_phase(
         provider,
@@ -174,11 +179,11 @@ pub(super) async fn run_replanner_phase_with_start_event<P: ModelProvider>(
         event_sink,
         launch.run_id,
         0,
-        EventKind::PlannerStart,
-        serde_json::json!({
-            "phase": "replan",
-            "reason": launch.replanner_reason
-        }),
+        PlannerStartPayload {
+            phase: Some("replan".to_string()),
+            reason: Some(launch.replanner_reason.to_string()),
+            ..PlannerStartPayload::default()
+        },
     );
     planner_runtime::run_planner_phase(
         provider,
@@ -280,6 +285,9 @@ pub(super) async fn bootstrap_planner_phase<P: ModelProvider>(
                         repo_map_resolution: input.repo_map_resolution,
                         lsp_context_resolution: input.lsp_context_resolution,
                         activated_packs: input.activated_packs,
+                        context_pack: input.context_pack,
+                        learned_guidance: input.learned_guidance,
+                        prompt_template: input.prompt_template,
                     })?;
                 let final_checkpoint = super::checkpoint::runtime_state_checkpoint_for_outcome(
                     &outcome,
@@ -339,6 +347,10 @@ pub(super) async fn bootstrap_planner_phase<P: ModelProvider>(
                     repro_record: None,
                     mcp_runtime_trace: Vec::new(),
                     mcp_pin_snapshot: input.mcp_pin_snapshot,
+                    environment_probe: None,
+                    operator_queue: None,
+                    provider_pacing: None,
+                    otlp_export: None,
                 });
                 return finalize_early_run_result(
                     input.ui_join.take(),
@@ -373,6 +385,7 @@ pub(super) async fn bootstrap_planner_phase<P: ModelProvider>(
                             .map(|s| agent::PlanStepConstraint {
                                 step_id: s.step_id,
                                 intended_tools: s.intended_tools,
+                                arg_constraints: s.arg_constraints,
                             })
                             .collect();
                     }
@@ -468,6 +481,9 @@ pub(super) async fn bootstrap_planner_phase<P: ModelProvider>(
                     repo_map_resolution: input.repo_map_resolution,
                     lsp_context_resolution: input.lsp_context_resolution,
                     activated_packs: input.activated_packs,
+                    context_pack: input.context_pack,
+                    learned_guidance: input.learned_guidance,
+                    prompt_template: input.prompt_template,
                 })?;
             let final_checkpoint = super::checkpoint::runtime_state_checkpoint_for_outcome(
                 &outcome,
@@ -525,6 +541,10 @@ pub(super) async fn bootstrap_planner_phase<P: ModelProvider>(
                 repro_record: None,
                 mcp_runtime_trace: Vec::new(),
                 mcp_pin_snapshot: input.mcp_pin_snapshot,
+                environment_probe: None,
+                operator_queue: None,
+                provider_pacing: None,
+                otlp_export: None,
             });
             finalize_early_run_result(input.ui_join.take(), outcome, run_artifact_path, None)
                 .map(Some)
@@ -658,6 +678,7 @@ pub(super) fn prepare_replan_success_resume<P: ModelProvider>(
                 .map(|s| agent::PlanStepConstraint {
                     step_id: s.step_id,
                     intended_tools: s.intended_tools,
+                    arg_constraints: s.arg_constraints,
                 })
                 .collect();
         }
@@ -731,41 +752,24 @@ pub(super) fn emit_planner_end_event(
     phase: Option<&str>,
     lineage_parent_plan_hash_hex: Option<&str>,
 ) {
-    let mut payload = serde_json::Map::new();
-    if let Some(phase) = phase {
-        payload.insert(
-            "phase".to_string(),
-            serde_json::Value::String(phase.to_string()),
-        );
-    }
-    payload.insert("ok".to_string(), serde_json::Value::Bool(ok));
-    payload.insert(
-        "planner_hash_hex".to_string(),
-        serde_json::Value::String(planner_hash_hex.to_string()),
-    );
-    if !error_short.is_empty() {
-        payload.insert(
-            "error_short".to_string(),
-            serde_json::Value::String(error_short.to_string()),
-        );
+    let error_short = if !error_short.is_empty() {
+        Some(error_short.to_string())
     } else if phase.is_none() {
-        payload.insert(
-            "error_short".to_string(),
-            serde_json::Value::String(String::new()),
-        );
-    }
-    if let Some(parent) = lineage_parent_plan_hash_hex {
-        payload.insert(
-            "lineage_parent_plan_hash_hex".to_string(),
-            serde_json::Value::String(parent.to_string()),
-        );
-    }
+        Some(String::new())
+    } else {
+        None
+    };
     runtime_events::emit_event(
         event_sink,
         run_id,
         0,
-        EventKind::PlannerEnd,
-        serde_json::Value::Object(payload),
+        PlannerEndPayload {
+            phase: phase.map(str::to_string),
+            ok,
+            planner_hash_hex: planner_hash_hex.to_string(),
+            error_short,
+            lineage_parent_plan_hash_hex: lineage_parent_plan_hash_hex.map(str::to_string),
+        },
     );
 }
 
@@ -777,31 +781,17 @@ pub(super) fn emit_worker_start_event(
     effective_plan_tool_enforcement: PlanToolEnforcementMode,
     phase: Option<&str>,
 ) {
-    let mut payload = serde_json::Map::new();
-    if let Some(phase) = phase {
-        payload.insert(
-            "phase".to_string(),
-            serde_json::Value::String(phase.to_string()),
-        );
-    }
-    payload.insert(
-        "worker_model".to_string(),
-        serde_json::Value::String(worker_model.to_string()),
-    );
-    payload.insert(
-        "planner_hash_hex".to_string(),
-        serde_json::Value::String(planner_hash_hex.to_string()),
-    );
-    payload.insert(
-        "enforce_plan_tools_effective".to_string(),
-        serde_json::Value::String(format!("{:?}", effective_plan_tool_enforcement).to_lowercase()),
-    );
     runtime_events::emit_event(
         event_sink,
         run_id,
         0,
-        EventKind::WorkerStart,
-        serde_json::Value::Object(payload),
+        WorkerStartPayload {
+            phase: phase.map(str::to_string),
+            worker_model: worker_model.to_string(),
+            planner_hash_hex: planner_hash_hex.to_string(),
+            enforce_plan_tools_effective: format!("{:?}", effective_plan_tool_enforcement)
+                .to_lowercase(),
+        },
     );
 }
 
@@ -820,6 +810,7 @@ pub(super) fn cancelled_outcome(
         tool_decisions: Vec::new(),
         compaction_settings: CompactionSettings {
             max_context_chars: resolved_settings.max_context_chars,
+            max_context_tokens: None,
             mode: resolved_settings.compaction_mode,
             keep_last: resolved_settings.compaction_keep_last,
             tool_result_persist: resolved_settings.tool_result_persist,
@@ -829,8 +820,16 @@ pub(super) fn cancelled_outcome(
         hook_invocations: Vec::new(),
         provider_retry_count: 0,
         provider_error_count: 0,
+        provider_retry_wait_ms: 0,
         token_usage: None,
         taint: None,
+        digest_refetch: None,
+        tool_result_artifacts: Vec::new(),
+        model_served: None,
+        subtask_run_ids: Vec::new(),
+        post_run_hook_error: None,
+        timeline: Vec::new(),
+        steps: Vec::new(),
     }
 }
 
@@ -858,6 +857,7 @@ pub(super) fn planner_strict_failure_outcome(
         tool_decisions: Vec::new(),
         compaction_settings: CompactionSettings {
             max_context_chars: resolved_settings.max_context_chars,
+            max_context_tokens: None,
             mode: resolved_settings.compaction_mode,
             keep_last: resolved_settings.compaction_keep_last,
             tool_result_persist: resolved_settings.tool_result_persist,
@@ -867,8 +867,16 @@ pub(super) fn planner_strict_failure_outcome(
         hook_invocations: Vec::new(),
         provider_retry_count: 0,
         provider_error_count: 0,
+        provider_retry_wait_ms: 0,
         token_usage: None,
         taint: None,
+        digest_refetch: None,
+        tool_result_artifacts: Vec::new(),
+        model_served: None,
+        subtask_run_ids: Vec::new(),
+        post_run_hook_error: None,
+        timeline: Vec::new(),
+        steps: Vec::new(),
     }
 }
 
@@ -896,6 +904,7 @@ pub(super) fn planner_runtime_error_outcome(
         tool_decisions: Vec::new(),
         compaction_settings: CompactionSettings {
             max_context_chars: resolved_settings.max_context_chars,
+            max_context_tokens: None,
             mode: resolved_settings.compaction_mode,
             keep_last: resolved_settings.compaction_keep_last,
             tool_result_persist: resolved_settings.tool_result_persist,
@@ -905,7 +914,15 @@ pub(super) fn planner_runtime_error_outcome(
         hook_invocations: Vec::new(),
         provider_retry_count: 0,
         provider_error_count: 0,
+        provider_retry_wait_ms: 0,
         token_usage: None,
         taint: None,
+        digest_refetch: None,
+        tool_result_artifacts: Vec::new(),
+        model_served: None,
+        subtask_run_ids: Vec::new(),
+        post_run_hook_error: None,
+        timeline: Vec::new(),
+        steps: Vec::new(),
     }
 }
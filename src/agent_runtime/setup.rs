@@ -1,3 +1,4 @@
+use std::io::IsTerminal;
 use std::sync::mpsc::Sender;
 use std::time::Duration;
 
@@ -5,12 +6,12 @@ use anyhow::Context;
 use tokio::sync::watch;
 
 use crate::events::Event;
-use crate::gate::{ApprovalMode, GateContext, ProviderKind};
-use crate::hooks::runner::{HookManager, HookRuntimeConfig};
+use crate::gate::{ApprovalMode, GateContext, ProviderKind, RunConfig};
+use crate::hooks::runner::{HookManager, HookRuntimeConfig, HooksReload};
+use crate::hooks::session::{SessionHooks, SessionHooksReload};
 use crate::lsp_context;
 use crate::lsp_context_provider;
 use crate::mcp::registry::McpRegistry;
-use crate::ops_helpers;
 use crate::packs;
 use crate::project_guidance;
 use crate::repo_map;
@@ -19,15 +20,18 @@ use crate::runtime_paths;
 use crate::runtime_wiring;
 use crate::session::{self, task_memory_message, RunSettingInputs, SessionStore};
 use crate::store::{self, provider_to_string};
-use crate::taint;
 use crate::taint::TaintToggle;
-use crate::target::{DockerTarget, ExecTarget, ExecTargetKind, HostTarget};
+use crate::target::{
+    DockerLimits, DockerTarget, ExecTarget, ExecTargetKind, HostTarget, RoutedTarget, SshConfig,
+    SshTarget,
+};
 use crate::types::Message;
-use crate::{instruction_runtime, tui, DockerNetwork, RunArgs};
+use crate::{instruction_runtime, tui, DockerNetwork, RunArgs, RunOutputMode};
 
 pub(super) struct SessionBootstrap {
     pub(super) session_store: SessionStore,
     pub(super) session_data: session::SessionData,
+    pub(super) session_recovery: Option<session::SessionRecovery>,
     pub(super) resolved_settings: session::RunSettingResolution,
     pub(super) session_messages: Vec<Message>,
     pub(super) task_memory: Option<Message>,
@@ -43,6 +47,7 @@ pub(super) struct ContextAugmentations {
 
 pub(super) struct UiRuntimeSetup {
     pub(super) event_sink: Option<Box<dyn crate::events::EventSink>>,
+    pub(super) otlp_stats: Option<std::sync::Arc<crate::otlp::OtlpExportStats>>,
     pub(super) _cancel_tx: watch::Sender<bool>,
     pub(super) cancel_rx: watch::Receiver<bool>,
     pub(super) ui_join: Option<std::thread::JoinHandle<anyhow::Result<()>>>,
@@ -58,6 +63,7 @@ pub(super) struct UiRuntimeSetupInput<'a> {
     pub(super) policy_hash_hex: &'a Option<String>,
     pub(super) mcp_tool_catalog_hash_hex: &'a Option<String>,
     pub(super) external_ui_tx: Option<Sender<Event>>,
+    pub(super) external_event_sinks: Vec<Box<dyn crate::events::EventSink>>,
     pub(super) external_cancel_pair: Option<(watch::Sender<bool>, watch::Receiver<bool>)>,
     pub(super) suppress_stdout_stream: bool,
 }
@@ -67,50 +73,130 @@ pub(super) struct HookToolSetup {
     pub(super) tool_schema_hash_hex_map: std::collections::BTreeMap<String, String>,
     pub(super) hooks_config_hash_hex: Option<String>,
     pub(super) hook_manager: HookManager,
+    pub(super) hooks_reload: SessionHooksReload,
     pub(super) tool_catalog: Vec<store::ToolCatalogEntry>,
 }
 
-pub(super) fn build_exec_target(args: &RunArgs) -> anyhow::Result<std::sync::Arc<dyn ExecTarget>> {
+/// Builds the run's target. A policy `execution.route` that sends any side
+/// effect to a different target wraps host and docker in a `RoutedTarget`;
+/// docker is validated up front whenever anything routes to it. With
+/// `--docker-persistent` the docker side starts its container for `workdir`
+/// here. The ssh target runs everything remotely and cannot be combined with
+/// routes.
+pub(super) async fn build_exec_target(
+    args: &RunArgs,
+    workdir: &std::path::Path,
+    policy: Option<&crate::trust::policy::Policy>,
+) -> anyhow::Result<std::sync::Arc<dyn ExecTarget>> {
+    let routes = policy.map(|p| p.execution_routes()).unwrap_or_default();
+    if args.exec_target == ExecTargetKind::Ssh || routes.uses(ExecTargetKind::Ssh) {
+        if routes.diverges_from(ExecTargetKind::Ssh) || args.exec_target != ExecTargetKind::Ssh {
+            return Err(anyhow::anyhow!(
+                "policy execution.route cannot be combined with the ssh execution target"
+            ));
+        }
+        return build_ssh_target(args);
+    }
+    let docker_config = policy.and_then(|p| p.execution_docker());
+    let image = docker_config
+        .and_then(|d| d.image.clone())
+        .unwrap_or_else(|| args.docker_image.clone());
+    let docker_routed =
+        args.exec_target == ExecTargetKind::Host && routes.uses(ExecTargetKind::Docker);
+    if args.exec_target == ExecTargetKind::Docker {
+        DockerTarget::validate_available().with_context(|| {
+            "docker execution target requested. Install/start Docker or re-run with --exec-target host"
+        })?;
+        DockerTarget::validate_image_present_local(&image).with_context(|| {
+            "docker execution target requested. Ensure the configured image is present locally or re-run with --exec-target host"
+        })?;
+    } else if docker_routed {
+        DockerTarget::validate_available().with_context(|| {
+            "policy execution.route sends tool calls to docker. Install/start Docker or remove the docker routes"
+        })?;
+        DockerTarget::validate_image_present_local(&image).with_context(|| {
+            "policy execution.route sends tool calls to docker. Ensure the image is present locally or remove the docker routes"
+        })?;
+    }
+    let docker = || async {
+        let target = DockerTarget::new(
+            image.clone(),
+            args.docker_workdir.clone(),
+            docker_config
+                .and_then(|d| d.network.clone())
+                .unwrap_or_else(|| {
+                    match args.docker_network {
+                        DockerNetwork::None => "none",
+                        DockerNetwork::Bridge => "bridge",
+                    }
+                    .to_string()
+                }),
+            docker_config
+                .and_then(|d| d.user.clone())
+                .or_else(|| args.docker_user.clone()),
+            DockerLimits {
+                memory: args.docker_memory.clone(),
+                cpus: args.docker_cpus.clone(),
+                pids_limit: args.docker_pids_limit,
+                readonly_workdir: args.docker_readonly_workdir,
+            },
+        );
+        std::sync::Arc::new(if args.docker_persistent {
+            target.with_persistent_container(workdir).await
+        } else {
+            target
+        })
+    };
+    if routes.diverges_from(args.exec_target) {
+        return Ok(std::sync::Arc::new(RoutedTarget::new(
+            args.exec_target,
+            std::sync::Arc::new(HostTarget),
+            docker().await,
+            routes,
+        )));
+    }
     match args.exec_target {
         ExecTargetKind::Host => Ok(std::sync::Arc::new(HostTarget)),
-        ExecTargetKind::Docker => {
-            DockerTarget::validate_available().with_context(|| {
-                "docker execution target requested. Install/start Docker or re-run with --exec-target host"
-            })?;
-            DockerTarget::validate_image_present_local(&args.docker_image).with_context(|| {
-                "docker execution target requested. Ensure the configured image is present locally or re-run with --exec-target host"
-            })?;
-            Ok(std::sync::Arc::new(DockerTarget::new(
-                args.docker_image.clone(),
-                args.docker_workdir.clone(),
-                match args.docker_network {
-                    DockerNetwork::None => "none",
-                    DockerNetwork::Bridge => "bridge",
-                }
-                .to_string(),
-                args.docker_user.clone(),
-            )))
-        }
+        ExecTargetKind::Docker => Ok(docker().await),
+        ExecTargetKind::Ssh => build_ssh_target(args),
     }
 }
 
+fn build_ssh_target(args: &RunArgs) -> anyhow::Result<std::sync::Arc<dyn ExecTarget>> {
+    let (Some(host), Some(remote_workdir)) = (args.ssh_host.clone(), args.ssh_workdir.clone())
+    else {
+        return Err(anyhow::anyhow!(
+            "--exec-target ssh requires --ssh-host and --ssh-workdir"
+        ));
+    };
+    SshTarget::validate_available().with_context(|| {
+        "ssh execution target requested. Install an OpenSSH client or re-run with --exec-target host"
+    })?;
+    let target = SshTarget::new(SshConfig {
+        host,
+        user: args.ssh_user.clone(),
+        port: args.ssh_port,
+        identity_file: args.ssh_identity_file.clone(),
+        remote_workdir,
+    })?;
+    Ok(std::sync::Arc::new(target))
+}
+
 pub(super) fn build_gate_context(
     args: &RunArgs,
     workdir: &std::path::Path,
     provider_kind: ProviderKind,
     default_model: &str,
     resolved_target_kind: ExecTargetKind,
-) -> GateContext {
-    GateContext {
+) -> anyhow::Result<GateContext> {
+    GateContext::from_run_config(&RunConfig {
         workdir: workdir.to_path_buf(),
         allow_shell: args.allow_shell || args.allow_shell_in_workdir,
+        shell_allowlist: (!args.allow_shell_cmd.is_empty()).then(|| args.allow_shell_cmd.clone()),
         allow_write: args.allow_write,
-        approval_mode: args.approval_mode,
-        auto_approve_scope: args.auto_approve_scope,
+        enable_write_tools: args.enable_write_tools,
         unsafe_mode: args.unsafe_mode,
         unsafe_bypass_allow_flags: args.unsafe_bypass_allow_flags,
-        run_id: None,
-        enable_write_tools: args.enable_write_tools,
         max_tool_output_bytes: if args.no_limits {
             0
         } else {
@@ -124,15 +210,12 @@ pub(super) fn build_gate_context(
         provider: provider_kind,
         model: default_model.to_string(),
         exec_target: resolved_target_kind,
+        approval_mode: args.approval_mode,
+        auto_approve_scope: args.auto_approve_scope,
         approval_key_version: args.approval_key,
-        tool_schema_hashes: std::collections::BTreeMap::new(),
-        hooks_config_hash_hex: None,
-        planner_hash_hex: None,
         taint_enabled: matches!(args.taint, TaintToggle::On),
         taint_mode: args.taint_mode,
-        taint_overall: taint::TaintLevel::Clean,
-        taint_sources: Vec::new(),
-    }
+    })
 }
 
 pub(super) async fn resolve_mcp_runtime_registry(
@@ -147,8 +230,14 @@ pub(super) async fn resolve_mcp_runtime_registry(
         None
     } else {
         Some(std::sync::Arc::new(
-            McpRegistry::from_config_path(&mcp_config_path, &args.mcp, Duration::from_secs(30))
-                .await?,
+            McpRegistry::from_config_path_with_metadata_policy(
+                &mcp_config_path,
+                &args.mcp,
+                Duration::from_secs(30),
+                runtime_paths::mcp_metadata_policy(args),
+            )
+            .await?
+            .with_reconnect_policy(runtime_paths::mcp_reconnect_policy(args)),
         ))
     };
     Ok((mcp_config_path, mcp_registry))
@@ -163,11 +252,14 @@ pub(super) fn build_session_bootstrap(
     if !args.no_session && args.reset_session {
         session_store.reset()?;
     }
-    let session_data = if args.no_session {
-        session::SessionData::empty(&args.session)
+    let (session_data, session_recovery) = if args.no_session {
+        (session::SessionData::empty(&args.session), None)
     } else {
-        session_store.load()?
+        session_store.load_with_recovery()?
     };
+    if let Some(recovery) = &session_recovery {
+        eprintln!("{}", recovery.warning());
+    }
     let explicit_flags = runtime_flags::parse_explicit_flags();
     let resolved_settings = session::resolve_run_settings(
         args.use_session_settings,
@@ -197,6 +289,7 @@ pub(super) fn build_session_bootstrap(
     Ok(SessionBootstrap {
         session_store,
         session_data,
+        session_recovery,
         resolved_settings,
         session_messages,
         task_memory,
@@ -208,6 +301,7 @@ pub(super) fn build_context_augmentations(
     args: &RunArgs,
     paths: &store::StatePaths,
     worker_model: &str,
+    read_allowlist: Option<&crate::tools::ReadAllowlist>,
 ) -> anyhow::Result<ContextAugmentations> {
     let instruction_resolution =
         instruction_runtime::resolve_instruction_messages(args, &paths.state_dir, worker_model)?;
@@ -232,8 +326,15 @@ pub(super) fn build_context_augmentations(
             &args.workdir,
             repo_map::RepoMapLimits {
                 max_out_bytes: args.repomap_max_bytes,
+                read_allowlist: read_allowlist.cloned(),
+                relevance_prompt: args.repomap_rank.then(|| prompt.to_string()),
                 ..repo_map::RepoMapLimits::default()
             },
+            Some(&repo_map::RepoMapCacheOptions {
+                index_path: repo_map::repo_map_index_path(&paths.state_dir),
+                refresh: args.repomap_refresh,
+                write: true,
+            }),
         )
         .ok()
         .filter(|m| !m.content.is_empty())
@@ -290,6 +391,7 @@ pub(super) fn build_ui_runtime_setup(
         let cfg = tui::TuiConfig {
             refresh_ms: input.args.tui_refresh_ms,
             max_log_lines: input.args.tui_max_log_lines,
+            max_line_chars: input.args.tui_max_line_chars,
             provider: provider_to_string(input.provider_kind),
             model: input.worker_model.to_string(),
             mode_label: format!(
@@ -320,6 +422,37 @@ pub(super) fn build_ui_runtime_setup(
     } else {
         None
     };
+    let progress = crate::progress_line::progress_line_enabled(
+        input.args.no_progress,
+        matches!(input.args.output, RunOutputMode::Json),
+        input.args.tui || ui_tx.is_some(),
+        input.suppress_stdout_stream,
+    )
+    .then(|| crate::progress_line::ProgressLineConfig {
+        max_steps: input.args.max_steps,
+        max_tool_calls: input.args.max_total_tool_calls,
+        echo_stream: input.args.stream && std::io::stdout().is_terminal(),
+    });
+    let span_export = crate::otlp::SpanExportConfig {
+        endpoint: input.args.otlp_endpoint.clone(),
+        headers: input.args.otlp_headers.clone(),
+        spans_path: input.args.spans_file.clone(),
+        flush_interval: input
+            .args
+            .otlp_flush_interval_ms
+            .map(std::time::Duration::from_millis),
+    };
+    let (otlp_sink, otlp_stats) = if span_export.is_enabled() {
+        let stats = std::sync::Arc::new(crate::otlp::OtlpExportStats::default());
+        let sink = crate::otlp::OtlpSpanSink::from_config(
+            &provider_to_string(input.provider_kind),
+            &span_export,
+            stats.clone(),
+        )?;
+        (Some(sink), Some(stats))
+    } else {
+        (None, None)
+    };
     let event_sink = runtime_wiring::build_event_sink(
         input.args.stream,
         input.args.output,
@@ -327,34 +460,54 @@ pub(super) fn build_ui_runtime_setup(
         input.args.tui,
         ui_tx,
         input.suppress_stdout_stream,
+        progress,
+        otlp_sink,
+        input.external_event_sinks,
     )?;
     Ok(UiRuntimeSetup {
         event_sink,
+        otlp_stats,
         _cancel_tx: cancel_tx,
         cancel_rx,
         ui_join,
     })
 }
 
+/// With `session_hooks`, the hooks config is the session's pinned one,
+/// reloaded if hooks.yaml changed since the previous run.
 pub(super) fn build_hook_and_tool_setup(
     args: &RunArgs,
     paths: &store::StatePaths,
     resolved_settings: &session::RunSettingResolution,
     all_tools: &[crate::types::ToolDef],
+    session_hooks: Option<&SessionHooks>,
 ) -> anyhow::Result<HookToolSetup> {
     let hooks_config_path = runtime_paths::resolved_hooks_config_path(args, &paths.state_dir);
     let tool_schema_hash_hex_map = store::tool_schema_hash_hex_map(all_tools);
-    let hooks_config_hash_hex = ops_helpers::compute_hooks_config_hash_hex(
-        resolved_settings.hooks_mode,
-        &hooks_config_path,
-    );
-    let hook_manager = HookManager::build(HookRuntimeConfig {
+    let hook_runtime = HookRuntimeConfig {
         mode: resolved_settings.hooks_mode,
         config_path: hooks_config_path.clone(),
         strict: args.hooks_strict,
         timeout_ms: args.hooks_timeout_ms,
         max_stdout_bytes: args.hooks_max_stdout_bytes,
-    })?;
+        max_invocations_per_run: args.hooks_max_invocations,
+        max_cumulative_ms: args.hooks_max_cumulative_ms,
+        budget_strict: args.hooks_budget_strict,
+    };
+    let (hook_manager, hooks_reload) = match session_hooks {
+        Some(session_hooks) => session_hooks.manager_for_run(
+            hook_runtime,
+            &crate::trust::approvals::ApprovalsStore::new(paths.approvals_path.clone()),
+        )?,
+        None => (
+            HookManager::build(hook_runtime)?,
+            SessionHooksReload {
+                reload: HooksReload::Unchanged,
+                approvals_invalidated: 0,
+            },
+        ),
+    };
+    let hooks_config_hash_hex = hook_manager.config_hash_hex.clone();
     let tool_catalog = all_tools
         .iter()
         .map(|t| store::ToolCatalogEntry {
@@ -367,6 +520,7 @@ pub(super) fn build_hook_and_tool_setup(
         tool_schema_hash_hex_map,
         hooks_config_hash_hex,
         hook_manager,
+        hooks_reload,
         tool_catalog,
     })
 }
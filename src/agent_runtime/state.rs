@@ -29,6 +29,7 @@ pub enum ExecutionTier {
     ScopedHostWrite,
     ScopedHostShell,
     DockerIsolated,
+    RemoteSsh,
     McpOnly,
 }
 
@@ -50,6 +51,11 @@ pub struct RetryState {
     pub blocked_validation_failure_repair_count: u32,
     #[serde(default)]
     pub blocked_post_validation_final_answer_count: u32,
+    #[serde(default)]
+    pub output_schema_repair_count: u32,
+    /// Consecutive responses with blank content and no tool calls.
+    #[serde(default)]
+    pub empty_response_count: u32,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -1,9 +1,16 @@
 use crate::agent::AgentTaintRecord;
-use crate::taint::{digest_prefix_hex, TaintMode, TaintSpan, TaintState, TaintToggle};
+use crate::mcp::roots::McpMappedPath;
+use crate::mcp::sanitize::DEFAULT_INJECTION_PHRASES;
+use crate::taint::{
+    digest_prefix_hex, TaintMode, TaintSpan, TaintState, TaintToggle, INJECTION_SUSPECTED_SOURCE,
+};
 use crate::tools::tool_side_effects;
 use crate::trust::policy::Policy;
 use crate::types::ToolCall;
 
+/// Injection scanning looks at no more than this much of a tool result.
+const INJECTION_SCAN_MAX_BYTES: usize = 256 * 1024;
+
 pub(crate) fn taint_record_from_state(
     toggle: TaintToggle,
     mode: TaintMode,
@@ -30,22 +37,26 @@ pub(crate) fn compute_taint_spans_for_tool(
     tool_message_content: &str,
     policy: Option<&Policy>,
     digest_bytes: usize,
+    mcp_paths: &[McpMappedPath],
 ) -> Vec<TaintSpan> {
     let mut spans = Vec::new();
     let side_effects = tool_side_effects(&tc.name);
     let content_for_digest = extract_tool_envelope_content(tool_message_content);
     let digest = digest_prefix_hex(&content_for_digest, digest_bytes);
+    let first_host_path = mcp_paths.iter().find_map(|p| p.host_path.clone());
 
     match side_effects {
         crate::types::SideEffects::Browser => spans.push(TaintSpan {
             source: "browser".to_string(),
             detail: tc.name.clone(),
-            digest,
+            digest: digest.clone(),
+            host_path: first_host_path,
         }),
         crate::types::SideEffects::Network => spans.push(TaintSpan {
             source: "network".to_string(),
             detail: tc.name.clone(),
-            digest,
+            digest: digest.clone(),
+            host_path: first_host_path,
         }),
         _ => {
             if tc.name == "read_file" {
@@ -54,16 +65,73 @@ pub(crate) fn compute_taint_spans_for_tool(
                         spans.push(TaintSpan {
                             source: "file".to_string(),
                             detail: format!("matched taint glob: {p}"),
-                            digest,
+                            digest: digest.clone(),
+                            host_path: None,
                         });
                     }
                 }
             }
         }
     }
+    let injection_patterns = injection_patterns_in(&content_for_digest);
+    if !injection_patterns.is_empty() {
+        spans.push(TaintSpan {
+            source: INJECTION_SUSPECTED_SOURCE.to_string(),
+            detail: injection_patterns.join(","),
+            digest: digest.clone(),
+            host_path: None,
+        });
+    }
+    let mut seen = std::collections::BTreeSet::new();
+    for host_path in mcp_paths.iter().filter_map(|p| p.host_path.as_deref()) {
+        if !seen.insert(host_path) {
+            continue;
+        }
+        if let Some(p) = policy.and_then(|p| p.taint_file_match(host_path)) {
+            spans.push(TaintSpan {
+                source: "file".to_string(),
+                detail: format!("matched taint glob: {p} (via {})", tc.name),
+                digest: digest.clone(),
+                host_path: Some(host_path.to_string()),
+            });
+        }
+    }
     spans
 }
 
+/// Patterns in tool output that try to steer the model: known injection
+/// phrases, tool-call wrappers, and fenced blocks shaped like a worker
+/// step_result envelope. Plain substring checks over a bounded prefix, so
+/// the cost is linear in the scanned length.
+pub(crate) fn injection_patterns_in(content: &str) -> Vec<String> {
+    let mut end = content.len().min(INJECTION_SCAN_MAX_BYTES);
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    let scanned = &content[..end];
+    let normalized = scanned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_ascii_lowercase();
+    let mut patterns = DEFAULT_INJECTION_PHRASES
+        .iter()
+        .filter(|phrase| normalized.contains(*phrase))
+        .map(|phrase| format!("phrase:{phrase}"))
+        .collect::<Vec<_>>();
+    if normalized.contains("[tool_call]") {
+        patterns.push("tool_call_wrapper".to_string());
+    }
+    let mut in_fence = scanned.split("```").skip(1).step_by(2);
+    if in_fence.any(|block| {
+        block.contains(crate::planner::STEP_RESULT_SCHEMA_VERSION)
+            || block.contains(crate::planner::STEP_RESULT_SCHEMA_VERSION_V2)
+    }) {
+        patterns.push("step_result_envelope".to_string());
+    }
+    patterns
+}
+
 pub(crate) fn extract_tool_envelope_content(raw: &str) -> String {
     match serde_json::from_str::<serde_json::Value>(raw) {
         Ok(v) => v
@@ -13,6 +13,7 @@ pub(crate) enum ToolFailureClass {
     SelectorAmbiguous,
     NetworkTransient,
     NonIdempotent,
+    Oversize,
     Other,
 }
 
@@ -25,6 +26,7 @@ impl ToolFailureClass {
             Self::SelectorAmbiguous => "E_SELECTOR_AMBIGUOUS",
             Self::NetworkTransient => "E_NETWORK_TRANSIENT",
             Self::NonIdempotent => "E_NON_IDEMPOTENT",
+            Self::Oversize => "E_OVERSIZE",
             Self::Other => "E_OTHER",
         }
     }
@@ -44,7 +46,7 @@ impl ToolFailureClass {
             Self::TimeoutTransient => 1,
             Self::SelectorAmbiguous => 1,
             Self::NetworkTransient => 1,
-            Self::Policy | Self::NonIdempotent | Self::Other => 0,
+            Self::Policy | Self::NonIdempotent | Self::Oversize | Self::Other => 0,
         }
     }
 }
@@ -57,7 +59,15 @@ pub(crate) async fn run_tool_once(
 ) -> ToolRunOutcome {
     if tc.name.starts_with("mcp.") {
         match mcp_registry {
-            Some(reg) => match reg.call_namespaced_tool(tc, tool_rt.tool_args_strict).await {
+            Some(reg) => match reg
+                .call_namespaced_tool_with_limits(
+                    tc,
+                    tool_rt.tool_args_strict,
+                    tool_rt.run_artifacts.as_deref(),
+                    tool_rt.max_tool_output_bytes,
+                )
+                .await
+            {
                 Ok(outcome) => ToolRunOutcome {
                     message: outcome.message,
                     mcp_meta: Some(outcome.meta),
@@ -81,6 +91,10 @@ pub(crate) async fn run_tool_once(
                             warnings_max: None,
                             warnings_truncated: None,
                             docker: None,
+                            write_protection: None,
+                            cwd: None,
+                            timed_out: None,
+                            dry_run: None,
                         },
                     )),
                     mcp_meta: None,
@@ -105,6 +119,10 @@ pub(crate) async fn run_tool_once(
                         warnings_max: None,
                         warnings_truncated: None,
                         docker: None,
+                        write_protection: None,
+                        cwd: None,
+                        timed_out: None,
+                        dry_run: None,
                     },
                 )),
                 mcp_meta: None,
@@ -124,6 +142,7 @@ pub(crate) async fn run_tool_once(
     }
 }
 
+#[derive(Debug)]
 pub(crate) struct ToolRunOutcome {
     pub(crate) message: Message,
     pub(crate) mcp_meta: Option<crate::mcp::registry::McpCallMeta>,
@@ -134,6 +153,15 @@ pub(crate) fn classify_tool_failure(
     raw_content: &str,
     invalid_args_error: bool,
 ) -> ToolFailureClass {
+    match tool_result_error_code(raw_content) {
+        Some(ToolErrorCode::ToolOutputOversize) => return ToolFailureClass::Oversize,
+        Some(ToolErrorCode::McpServerDegraded | ToolErrorCode::SshConnectionFailed) => {
+            return ToolFailureClass::NetworkTransient
+        }
+        Some(ToolErrorCode::McpSchemaDrift) => return ToolFailureClass::Schema,
+        Some(code) if code.is_fs_entity() => return ToolFailureClass::Schema,
+        _ => {}
+    }
     let text = tool_result_text(raw_content).to_ascii_lowercase();
     if invalid_args_error
         || text.contains("invalid tool arguments")
@@ -228,6 +256,15 @@ pub(crate) fn tool_result_error_code(content: &str) -> Option<ToolErrorCode> {
         "shell_exec_not_found" => Some(ToolErrorCode::ShellExecNotFound),
         "shell_exec_os_error" => Some(ToolErrorCode::ShellExecOsError),
         "shell_exec_non_zero_exit" => Some(ToolErrorCode::ShellExecNonZeroExit),
+        "is_directory" => Some(ToolErrorCode::IsDirectory),
+        "not_a_directory" => Some(ToolErrorCode::NotADirectory),
+        "not_found" => Some(ToolErrorCode::NotFound),
+        "special_file" => Some(ToolErrorCode::SpecialFile),
+        "path_escape" => Some(ToolErrorCode::PathEscape),
+        "tool_output_oversize" => Some(ToolErrorCode::ToolOutputOversize),
+        "mcp_server_degraded" => Some(ToolErrorCode::McpServerDegraded),
+        "mcp_schema_drift" => Some(ToolErrorCode::McpSchemaDrift),
+        "ssh_connection_failed" => Some(ToolErrorCode::SshConnectionFailed),
         _ => None,
     }
 }
@@ -266,6 +303,7 @@ pub(crate) fn make_invalid_args_tool_message(
         match exec_target_kind {
             crate::target::ExecTargetKind::Host => "host".to_string(),
             crate::target::ExecTargetKind::Docker => "docker".to_string(),
+            crate::target::ExecTargetKind::Ssh => "ssh".to_string(),
         }
     };
     invalid_args_tool_message(tc, source, err, execution_target)
@@ -365,14 +403,39 @@ fn repair_json_unescaped_newlines(raw: &str) -> String {
     out
 }
 
+/// Whether `s` carries tool-call wrapper markers outside fenced code blocks
+/// and inline code spans; markers quoted as code are documentation, not calls.
 pub(crate) fn contains_tool_wrapper_markers(s: &str) -> bool {
-    let u = s.to_ascii_uppercase();
+    let u = mask_code_regions(s).to_ascii_uppercase();
     u.contains("[TOOL_CALL]")
         || u.contains("[END_TOOL_CALL]")
         || u.contains("<|BEGIN_OF_BOX|>")
         || u.contains("<|END_OF_BOX|>")
 }
 
+/// Returns the JSON bodies of complete `[TOOL_CALL]...[END_TOOL_CALL]` blocks
+/// that sit outside code regions, in order.
+fn wrapped_tool_call_bodies(raw: &str) -> Vec<&str> {
+    let upper = mask_code_regions(raw).to_ascii_uppercase();
+    let start_tag = "[TOOL_CALL]";
+    let end_tag = "[END_TOOL_CALL]";
+    let mut out = Vec::new();
+    let mut offset = 0usize;
+    while let Some(rel_start) = upper[offset..].find(start_tag) {
+        let start = offset + rel_start + start_tag.len();
+        let Some(rel_end) = upper[start..].find(end_tag) else {
+            break;
+        };
+        let end = start + rel_end;
+        let body = raw[start..end].trim();
+        if !body.is_empty() {
+            out.push(body);
+        }
+        offset = end + end_tag.len();
+    }
+    out
+}
+
 pub(crate) fn extract_content_tool_calls(
     raw: &str,
     step: u32,
@@ -385,6 +448,14 @@ pub(crate) fn extract_content_tool_calls(
     if let Some(tc) = extract_recoverable_single_wrapped_tool_call(raw, step, allowed_tool_names) {
         return vec![tc];
     }
+    // Wrapper markers that only appear inside code are quoted examples; the
+    // inline fallback would otherwise lift the example's JSON out of the fence.
+    let upper = raw.to_ascii_uppercase();
+    if (upper.contains("[TOOL_CALL]") || upper.contains("[END_TOOL_CALL]"))
+        && !contains_tool_wrapper_markers(raw)
+    {
+        return Vec::new();
+    }
     if let Some(tc) = extract_inline_tool_call(raw, step, allowed_tool_names) {
         return vec![tc];
     }
@@ -405,34 +476,136 @@ pub(crate) fn extract_wrapped_tool_calls(
     step: u32,
     allowed_tool_names: &std::collections::BTreeSet<String>,
 ) -> Vec<ToolCall> {
-    let upper = raw.to_ascii_uppercase();
-    let start_tag = "[TOOL_CALL]";
-    let end_tag = "[END_TOOL_CALL]";
     let mut out = Vec::new();
-    let mut offset = 0usize;
-    while let Some(rel_start) = upper[offset..].find(start_tag) {
-        let start = offset + rel_start + start_tag.len();
-        let Some(rel_end) = upper[start..].find(end_tag) else {
-            break;
-        };
-        let end = start + rel_end;
-        let body = raw[start..end].trim();
-        if !body.is_empty() {
-            if let Some(v) = parse_jsonish(body) {
-                if let Some(tc) = tool_call_from_json_value_with_id(
-                    v,
-                    format!("wrapped_tc_{step}_{}", out.len()),
-                    allowed_tool_names,
-                ) {
-                    out.push(tc);
-                }
+    for body in wrapped_tool_call_bodies(raw) {
+        if let Some(v) = parse_jsonish(body) {
+            if let Some(tc) = tool_call_from_json_value_with_id(
+                v,
+                format!("wrapped_tc_{step}_{}", out.len()),
+                allowed_tool_names,
+            ) {
+                out.push(tc);
             }
         }
-        offset = end + end_tag.len();
     }
     out
 }
 
+/// A complete wrapper block outside code regions that did not yield a tool
+/// call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WrappedToolCallNearMiss {
+    /// `unparseable_body`, `missing_tool_name`, or `tool_not_allowed`.
+    pub(crate) reason: &'static str,
+    pub(crate) tool_name: Option<String>,
+}
+
+pub(crate) fn wrapped_tool_call_near_misses(
+    raw: &str,
+    allowed_tool_names: &std::collections::BTreeSet<String>,
+) -> Vec<WrappedToolCallNearMiss> {
+    wrapped_tool_call_bodies(raw)
+        .into_iter()
+        .filter_map(|body| {
+            let Some(v) = parse_jsonish(body) else {
+                return Some(WrappedToolCallNearMiss {
+                    reason: "unparseable_body",
+                    tool_name: None,
+                });
+            };
+            match v.get("name").and_then(|x| x.as_str()) {
+                None => Some(WrappedToolCallNearMiss {
+                    reason: "missing_tool_name",
+                    tool_name: None,
+                }),
+                Some(name) if !allowed_tool_names.contains(name) => Some(WrappedToolCallNearMiss {
+                    reason: "tool_not_allowed",
+                    tool_name: Some(name.to_string()),
+                }),
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// Copy of `raw` with fenced code blocks (``` and ~~~, info strings included)
+/// and inline code spans blanked to spaces. Newlines and byte offsets are
+/// preserved, so positions found in the mask index into `raw`.
+fn mask_code_regions(raw: &str) -> String {
+    let mut out = raw.as_bytes().to_vec();
+    let mut fence: Option<(u8, usize)> = None;
+    let mut line_start = 0usize;
+    for line in raw.split_inclusive('\n') {
+        let range = line_start..line_start + line.len();
+        line_start = range.end;
+        let trimmed = line.trim_start();
+        let run_of = |c: u8| trimmed.bytes().take_while(|b| *b == c).count();
+        if let Some((ch, len)) = fence {
+            let run = run_of(ch);
+            if run >= len && trimmed[run..].trim().is_empty() {
+                fence = None;
+            }
+            blank_code_bytes(&mut out[range]);
+            continue;
+        }
+        let opener = [b'`', b'~']
+            .into_iter()
+            .map(|c| (c, run_of(c)))
+            .find(|(_, run)| *run >= 3)
+            .filter(|(c, run)| *c != b'`' || !trimmed[*run..].contains('`'));
+        if let Some(opener) = opener {
+            fence = Some(opener);
+            blank_code_bytes(&mut out[range]);
+            continue;
+        }
+        mask_inline_code_spans(line.as_bytes(), &mut out[range]);
+    }
+    String::from_utf8(out).unwrap_or_else(|_| raw.to_string())
+}
+
+/// Blanks backtick-delimited spans of `line` (a run of N backticks closed by
+/// the next run of exactly N) into `out`.
+fn mask_inline_code_spans(line: &[u8], out: &mut [u8]) {
+    let run_at = |i: usize| line[i..].iter().take_while(|b| **b == b'`').count();
+    let mut i = 0usize;
+    while i < line.len() {
+        if line[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let open = run_at(i);
+        let mut j = i + open;
+        let mut close = None;
+        while j < line.len() {
+            if line[j] == b'`' {
+                let run = run_at(j);
+                if run == open {
+                    close = Some(j + run);
+                    break;
+                }
+                j += run;
+            } else {
+                j += 1;
+            }
+        }
+        match close {
+            Some(end) => {
+                blank_code_bytes(&mut out[i..end]);
+                i = end;
+            }
+            None => i += open,
+        }
+    }
+}
+
+fn blank_code_bytes(bytes: &mut [u8]) {
+    for b in bytes {
+        if *b != b'\n' && *b != b'\r' {
+            *b = b' ';
+        }
+    }
+}
+
 fn extract_recoverable_single_wrapped_tool_call(
     raw: &str,
     step: u32,
@@ -451,7 +624,7 @@ fn extract_recoverable_single_wrapped_tool_call(
 
 fn recoverable_wrapped_tool_call_body(raw: &str) -> Option<&str> {
     let trimmed = raw.trim();
-    let upper = trimmed.to_ascii_uppercase();
+    let upper = mask_code_regions(trimmed).to_ascii_uppercase();
     let start_tag = "[TOOL_CALL]";
     let start_count = upper.matches(start_tag).count();
     if start_count != 1 {
@@ -460,11 +633,10 @@ fn recoverable_wrapped_tool_call_body(raw: &str) -> Option<&str> {
     let start = upper.find(start_tag)? + start_tag.len();
     let body = &trimmed[start..];
 
+    let masked_body = &upper[start..];
+
     for malformed_end in ["[/TOOL_CALL]", "[END_TOOL_CALL]", "<|END_OF_BOX|>"] {
-        if let Some(end) = body
-            .to_ascii_uppercase()
-            .find(&malformed_end.to_ascii_uppercase())
-        {
+        if let Some(end) = masked_body.find(malformed_end) {
             let candidate = body[..end].trim();
             if !candidate.is_empty() {
                 return Some(candidate);
@@ -6,6 +6,7 @@ pub(crate) fn provider_name(provider: crate::gate::ProviderKind) -> &'static str
         crate::gate::ProviderKind::Llamacpp => "llamacpp",
         crate::gate::ProviderKind::Ollama => "ollama",
         crate::gate::ProviderKind::Mock => "mock",
+        crate::gate::ProviderKind::OpenAi => "openai",
     }
 }
 
@@ -8,7 +8,8 @@ pub(crate) fn parse_worker_step_status(
     let value = parse_jsonish(raw)?;
     let obj = value.as_object()?;
     let schema = obj.get("schema_version").and_then(|v| v.as_str())?;
-    if schema != crate::planner::STEP_RESULT_SCHEMA_VERSION {
+    let v2 = schema == crate::planner::STEP_RESULT_SCHEMA_VERSION_V2;
+    if schema != crate::planner::STEP_RESULT_SCHEMA_VERSION && !v2 {
         return None;
     }
     let step_id = obj.get("step_id").and_then(|v| v.as_str())?.to_string();
@@ -23,6 +24,27 @@ pub(crate) fn parse_worker_step_status(
         .get("next_step_id")
         .and_then(|v| v.as_str())
         .map(str::to_string);
+    // Unknown ids and bad counters are kept so the transition check can
+    // reject them with a specific error.
+    let next_step_ids = if v2 {
+        obj.get("next_step_ids")
+            .and_then(|v| v.as_array())
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let retry_of = if v2 {
+        obj.get("retry_of")
+            .and_then(|v| v.as_u64())
+            .map(|n| u32::try_from(n).unwrap_or(u32::MAX))
+    } else {
+        None
+    };
     let user_output = obj
         .get("user_output")
         .and_then(|v| v.as_str())
@@ -31,6 +53,9 @@ pub(crate) fn parse_worker_step_status(
         step_id,
         status,
         next_step_id,
+        next_step_ids,
+        retry_of,
+        v2,
         user_output,
     })
 }
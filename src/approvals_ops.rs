@@ -1,15 +1,18 @@
+use crate::gate::{list_persisted_approvals, revoke_persisted_approval};
 use crate::trust::approvals::ApprovalsStore;
 use crate::ApprovalsSubcommand;
 
 pub(crate) fn handle_approvals_command(
     path: &std::path::Path,
+    workspace_path: &std::path::Path,
     command: &ApprovalsSubcommand,
 ) -> anyhow::Result<()> {
     let store = ApprovalsStore::new(path.to_path_buf());
     match command {
         ApprovalsSubcommand::List => {
             let data = store.list()?;
-            if data.requests.is_empty() {
+            let persisted = list_persisted_approvals(workspace_path)?;
+            if data.requests.is_empty() && persisted.is_empty() {
                 println!("no approval requests");
                 return Ok(());
             }
@@ -40,6 +43,31 @@ pub(crate) fn handle_approvals_command(
                     key_prefix
                 );
             }
+            let now = time::OffsetDateTime::now_utc();
+            for entry in persisted {
+                let status = if entry.is_expired(now) {
+                    "Expired"
+                } else {
+                    "Persisted"
+                };
+                println!(
+                    "{}\t{status}\t{}\t{}\t{}\t-\t{}\t{}\t{}{}",
+                    entry.id,
+                    entry.tool,
+                    entry.created_at,
+                    entry.expires_at,
+                    entry.approval_key_version,
+                    entry.approval_key.chars().take(8).collect::<String>(),
+                    entry.exec_target,
+                    if entry.unsafe_mode { "\tunsafe" } else { "" }
+                );
+            }
+        }
+        ApprovalsSubcommand::Revoke { id } => {
+            if !revoke_persisted_approval(workspace_path, id)? {
+                return Err(anyhow::anyhow!("persisted approval not found: {id}"));
+            }
+            println!("revoked {id}");
         }
         ApprovalsSubcommand::Prune => {
             let removed = store.prune()?;
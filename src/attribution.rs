@@ -0,0 +1,481 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use globset::{Glob, GlobMatcher};
+use serde::{Deserialize, Serialize};
+
+use crate::store::sha256_hex;
+use crate::trust::policy::Policy;
+
+pub const DEFAULT_ATTRIBUTION_TEMPLATE: &str =
+    "Generated by LocalAgent (run {run_id}, model {model}, {date})";
+
+const DEFAULT_COMMENT_SYNTAX: &[(&str, &str)] = &[
+    ("bash", "# {}"),
+    ("c", "// {}"),
+    ("cc", "// {}"),
+    ("cpp", "// {}"),
+    ("cs", "// {}"),
+    ("css", "/* {} */"),
+    ("go", "// {}"),
+    ("h", "// {}"),
+    ("hpp", "// {}"),
+    ("hs", "-- {}"),
+    ("htm", "<!-- {} -->"),
+    ("html", "<!-- {} -->"),
+    ("java", "// {}"),
+    ("js", "// {}"),
+    ("jsx", "// {}"),
+    ("kt", "// {}"),
+    ("less", "/* {} */"),
+    ("lua", "-- {}"),
+    ("md", "<!-- {} -->"),
+    ("php", "// {}"),
+    ("pl", "# {}"),
+    ("ps1", "# {}"),
+    ("py", "# {}"),
+    ("rb", "# {}"),
+    ("rs", "// {}"),
+    ("scala", "// {}"),
+    ("scss", "/* {} */"),
+    ("sh", "# {}"),
+    ("sql", "-- {}"),
+    ("swift", "// {}"),
+    ("toml", "# {}"),
+    ("ts", "// {}"),
+    ("tsx", "// {}"),
+    ("xml", "<!-- {} -->"),
+    ("yaml", "# {}"),
+    ("yml", "# {}"),
+    ("zsh", "# {}"),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributionPlacement {
+    Top,
+    #[default]
+    Bottom,
+}
+
+/// Which kind of write produced the content being attributed. `write_file`
+/// creates content wholesale; patch-style tools modify existing files and are
+/// only attributed when the policy opts in via `include_patches`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributionWriteKind {
+    NewContent,
+    Patch,
+}
+
+impl AttributionWriteKind {
+    pub fn for_tool(name: &str) -> Option<Self> {
+        match name {
+            "write_file" => Some(Self::NewContent),
+            "apply_patch" | "edit" | "str_replace" => Some(Self::Patch),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributionConfig {
+    pub enabled: bool,
+    pub template: String,
+    pub placement: AttributionPlacement,
+    pub include_patches: bool,
+    pub overridable: bool,
+    /// Extension (lowercase, no dot) to comment pattern; `{}` marks where the
+    /// attribution text goes, otherwise the text follows the pattern.
+    pub comment_syntax: BTreeMap<String, String>,
+    applies_to_matchers: Vec<GlobMatcher>,
+}
+
+impl AttributionConfig {
+    pub fn new(template: Option<String>, applies_to_globs: Vec<String>) -> anyhow::Result<Self> {
+        let template = template.unwrap_or_else(|| DEFAULT_ATTRIBUTION_TEMPLATE.to_string());
+        if template.trim().is_empty() {
+            return Err(anyhow!("attribution.template must be non-empty"));
+        }
+        let mut applies_to_matchers = Vec::with_capacity(applies_to_globs.len());
+        for pat in &applies_to_globs {
+            applies_to_matchers.push(Glob::new(pat)?.compile_matcher());
+        }
+        Ok(Self {
+            enabled: true,
+            template,
+            placement: AttributionPlacement::default(),
+            include_patches: false,
+            overridable: false,
+            comment_syntax: DEFAULT_COMMENT_SYNTAX
+                .iter()
+                .map(|(ext, pattern)| (ext.to_string(), pattern.to_string()))
+                .collect(),
+            applies_to_matchers,
+        })
+    }
+
+    /// Indicates whether a write of `kind` to `path` is in scope. An empty glob
+    /// list scopes attribution to every path.
+    pub fn applies_to(&self, path: &str, kind: AttributionWriteKind) -> bool {
+        if !self.enabled || (kind == AttributionWriteKind::Patch && !self.include_patches) {
+            return false;
+        }
+        if self.applies_to_matchers.is_empty() {
+            return true;
+        }
+        let normalized = normalize_attribution_path(path);
+        self.applies_to_matchers
+            .iter()
+            .any(|m| m.is_match(&normalized))
+    }
+
+    pub fn comment_pattern_for(&self, path: &str) -> Option<&str> {
+        let ext = path_extension(path)?;
+        self.comment_syntax.get(&ext).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AttributionVars<'a> {
+    pub run_id: &'a str,
+    pub model: &'a str,
+    pub date: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributionRecord {
+    pub path: String,
+    pub line: String,
+    pub placement: AttributionPlacement,
+    pub pre_injection_sha256: String,
+    pub post_injection_sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributionOutcome {
+    NotApplicable,
+    UnknownExtension {
+        path: String,
+    },
+    AlreadyPresent {
+        path: String,
+    },
+    Injected {
+        content: String,
+        record: AttributionRecord,
+    },
+}
+
+/// Resolves the attribution config that applies to this run. `--no-attribution`
+/// only disables attribution when the policy marks it `overridable`.
+pub fn resolve_run_attribution(
+    policy: Option<&Policy>,
+    no_attribution: bool,
+) -> anyhow::Result<Option<AttributionConfig>> {
+    let Some(config) = policy.and_then(Policy::attribution).filter(|c| c.enabled) else {
+        return Ok(None);
+    };
+    if no_attribution {
+        if !config.overridable {
+            return Err(anyhow!(
+                "--no-attribution is not permitted: policy attribution section does not declare overridable: true"
+            ));
+        }
+        return Ok(None);
+    }
+    Ok(Some(config.clone()))
+}
+
+pub fn render_attribution_line(pattern: &str, template: &str, vars: AttributionVars<'_>) -> String {
+    let text = template
+        .replace("{run_id}", vars.run_id)
+        .replace("{model}", vars.model)
+        .replace("{date}", vars.date)
+        .replace(['\r', '\n'], " ");
+    if pattern.contains("{}") {
+        pattern.replacen("{}", &text, 1)
+    } else {
+        format!("{pattern} {text}")
+    }
+}
+
+pub fn apply_attribution(
+    config: &AttributionConfig,
+    path: &str,
+    content: &str,
+    kind: AttributionWriteKind,
+    vars: AttributionVars<'_>,
+) -> AttributionOutcome {
+    if !config.applies_to(path, kind) {
+        return AttributionOutcome::NotApplicable;
+    }
+    let Some(pattern) = config.comment_pattern_for(path) else {
+        return AttributionOutcome::UnknownExtension {
+            path: path.to_string(),
+        };
+    };
+    let line = render_attribution_line(pattern, &config.template, vars);
+    if content.lines().any(|l| l.trim_end() == line) {
+        return AttributionOutcome::AlreadyPresent {
+            path: path.to_string(),
+        };
+    }
+    let injected = match config.placement {
+        AttributionPlacement::Top => prepend_line(content, &line),
+        AttributionPlacement::Bottom => append_line(content, &line),
+    };
+    AttributionOutcome::Injected {
+        record: AttributionRecord {
+            path: path.to_string(),
+            line,
+            placement: config.placement,
+            pre_injection_sha256: sha256_hex(content.as_bytes()),
+            post_injection_sha256: sha256_hex(injected.as_bytes()),
+        },
+        content: injected,
+    }
+}
+
+fn prepend_line(content: &str, line: &str) -> String {
+    // Keep interpreter lines first so scripts stay executable.
+    if content.starts_with("#!") {
+        if let Some(idx) = content.find('\n') {
+            let (shebang, rest) = content.split_at(idx + 1);
+            return format!("{shebang}{line}\n{rest}");
+        }
+        return format!("{content}\n{line}\n");
+    }
+    format!("{line}\n{content}")
+}
+
+fn append_line(content: &str, line: &str) -> String {
+    if content.is_empty() {
+        format!("{line}\n")
+    } else if content.ends_with('\n') {
+        format!("{content}{line}\n")
+    } else {
+        format!("{content}\n{line}")
+    }
+}
+
+fn normalize_attribution_path(path: &str) -> String {
+    let normalized = path.replace('\\', "/");
+    normalized
+        .strip_prefix("./")
+        .unwrap_or(&normalized)
+        .to_string()
+}
+
+fn path_extension(path: &str) -> Option<String> {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: AttributionVars<'static> = AttributionVars {
+        run_id: "run-1",
+        model: "m1",
+        date: "2026-01-02",
+    };
+
+    fn config(globs: &[&str]) -> AttributionConfig {
+        AttributionConfig::new(
+            Some("AI generated: {run_id} {model} {date}".to_string()),
+            globs.iter().map(|g| g.to_string()).collect(),
+        )
+        .expect("config")
+    }
+
+    fn injected(outcome: AttributionOutcome) -> (String, AttributionRecord) {
+        match outcome {
+            AttributionOutcome::Injected { content, record } => (content, record),
+            other => panic!("expected injection, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn comment_syntax_follows_file_extension() {
+        let cfg = config(&[]);
+        let (rs, _) = injected(apply_attribution(
+            &cfg,
+            "src/lib.rs",
+            "fn a() {}\n",
+            AttributionWriteKind::NewContent,
+            VARS,
+        ));
+        assert_eq!(rs, "fn a() {}\n// AI generated: run-1 m1 2026-01-02\n");
+        let (py, _) = injected(apply_attribution(
+            &cfg,
+            "tool.PY",
+            "print(1)",
+            AttributionWriteKind::NewContent,
+            VARS,
+        ));
+        assert_eq!(py, "print(1)\n# AI generated: run-1 m1 2026-01-02");
+        let (html, _) = injected(apply_attribution(
+            &cfg,
+            "index.html",
+            "",
+            AttributionWriteKind::NewContent,
+            VARS,
+        ));
+        assert_eq!(html, "<!-- AI generated: run-1 m1 2026-01-02 -->\n");
+
+        let mut top = config(&[]);
+        top.placement = AttributionPlacement::Top;
+        top.comment_syntax
+            .insert("sh".to_string(), "##".to_string());
+        let (sh, _) = injected(apply_attribution(
+            &top,
+            "run.sh",
+            "#!/bin/sh\necho hi\n",
+            AttributionWriteKind::NewContent,
+            VARS,
+        ));
+        assert_eq!(
+            sh,
+            "#!/bin/sh\n## AI generated: run-1 m1 2026-01-02\necho hi\n"
+        );
+    }
+
+    #[test]
+    fn globs_scope_which_paths_are_attributed() {
+        let cfg = config(&["src/**/*.rs", "scripts/*.py"]);
+        assert!(cfg.applies_to("src/a/b.rs", AttributionWriteKind::NewContent));
+        assert!(cfg.applies_to("./scripts/x.py", AttributionWriteKind::NewContent));
+        assert!(!cfg.applies_to("tests/a.rs", AttributionWriteKind::NewContent));
+        assert_eq!(
+            apply_attribution(
+                &cfg,
+                "README.md",
+                "hi\n",
+                AttributionWriteKind::NewContent,
+                VARS
+            ),
+            AttributionOutcome::NotApplicable
+        );
+        let mut disabled = config(&[]);
+        disabled.enabled = false;
+        assert!(!disabled.applies_to("src/a.rs", AttributionWriteKind::NewContent));
+    }
+
+    #[test]
+    fn unknown_extension_is_skipped_without_touching_content() {
+        let cfg = config(&[]);
+        assert_eq!(
+            apply_attribution(
+                &cfg,
+                "data/config.json",
+                "{}",
+                AttributionWriteKind::NewContent,
+                VARS
+            ),
+            AttributionOutcome::UnknownExtension {
+                path: "data/config.json".to_string()
+            }
+        );
+        assert_eq!(
+            apply_attribution(
+                &cfg,
+                "Makefile",
+                "all:\n",
+                AttributionWriteKind::NewContent,
+                VARS
+            ),
+            AttributionOutcome::UnknownExtension {
+                path: "Makefile".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn record_keeps_pre_injection_content_hash() {
+        let cfg = config(&[]);
+        let original = "pub fn a() {}\n";
+        let (content, record) = injected(apply_attribution(
+            &cfg,
+            "src/a.rs",
+            original,
+            AttributionWriteKind::NewContent,
+            VARS,
+        ));
+        assert_eq!(record.pre_injection_sha256, sha256_hex(original.as_bytes()));
+        assert_eq!(record.post_injection_sha256, sha256_hex(content.as_bytes()));
+        assert_ne!(record.pre_injection_sha256, record.post_injection_sha256);
+        assert_eq!(record.line, "// AI generated: run-1 m1 2026-01-02");
+        assert_eq!(
+            apply_attribution(
+                &cfg,
+                "src/a.rs",
+                &content,
+                AttributionWriteKind::NewContent,
+                VARS
+            ),
+            AttributionOutcome::AlreadyPresent {
+                path: "src/a.rs".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn patches_are_exempt_unless_opted_in() {
+        let mut cfg = config(&[]);
+        assert_eq!(
+            AttributionWriteKind::for_tool("apply_patch"),
+            Some(AttributionWriteKind::Patch)
+        );
+        assert_eq!(
+            apply_attribution(&cfg, "src/a.rs", "x\n", AttributionWriteKind::Patch, VARS),
+            AttributionOutcome::NotApplicable
+        );
+        cfg.include_patches = true;
+        let (content, _) = injected(apply_attribution(
+            &cfg,
+            "src/a.rs",
+            "x\n",
+            AttributionWriteKind::Patch,
+            VARS,
+        ));
+        assert!(content.ends_with("// AI generated: run-1 m1 2026-01-02\n"));
+    }
+
+    #[test]
+    fn no_attribution_override_requires_overridable_policy() {
+        let strict = Policy::from_yaml(
+            r#"
+version: 2
+default: allow
+attribution:
+  enabled: true
+"#,
+        )
+        .expect("policy");
+        assert!(resolve_run_attribution(Some(&strict), false)
+            .expect("resolve")
+            .is_some());
+        let err = resolve_run_attribution(Some(&strict), true).expect_err("gate");
+        assert!(err.to_string().contains("overridable"));
+
+        let relaxed = Policy::from_yaml(
+            r#"
+version: 2
+default: allow
+attribution:
+  enabled: true
+  overridable: true
+"#,
+        )
+        .expect("policy");
+        assert!(resolve_run_attribution(Some(&relaxed), true)
+            .expect("resolve")
+            .is_none());
+        assert!(resolve_run_attribution(None, true)
+            .expect("resolve")
+            .is_none());
+    }
+}
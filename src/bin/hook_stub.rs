@@ -20,6 +20,15 @@ fn main() {
         return;
     }
 
+    if let Some(ms) = parsed
+        .as_ref()
+        .and_then(|v| v.get("payload"))
+        .and_then(|p| p.get("sleep_ms"))
+        .and_then(|x| x.as_u64())
+    {
+        std::thread::sleep(std::time::Duration::from_millis(ms));
+    }
+
     let stage = parsed
         .as_ref()
         .and_then(|v| {
@@ -66,6 +75,16 @@ fn main() {
             "action":"modify",
             "payload":{"append_messages":[{"role":"system","content":"stub appended"}]}
         }),
+        ("pre_tool", "rewrite") => json!({
+            "schema_version":"openagent.hook_output.v1",
+            "action":"modify",
+            "payload":{"rewrite_arguments": parsed
+                .as_ref()
+                .and_then(|v| v.get("payload"))
+                .and_then(|p| p.get("rewrite_to"))
+                .cloned()
+                .unwrap_or_default()}
+        }),
         ("tool_result", "modify") => json!({
             "schema_version":"openagent.hook_output.v1",
             "action":"modify",
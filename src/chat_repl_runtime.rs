@@ -6,6 +6,7 @@ use anyhow::anyhow;
 
 use crate::chat_runtime;
 use crate::chat_tui_runtime;
+use crate::hooks::session::SessionHooks;
 use crate::mcp::registry::McpRegistry;
 use crate::project_guidance;
 use crate::provider_runtime;
@@ -16,7 +17,9 @@ use crate::runtime_config;
 use crate::runtime_paths;
 use crate::session::SessionStore;
 use crate::store;
-use crate::{run_agent, AgentExitReason, ChatArgs, ProviderKind, RunArgs};
+use crate::{
+    run_agent_with_ui, AgentExitReason, AgentUiRunRequest, ChatArgs, ProviderKind, RunArgs,
+};
 
 fn prepare_chat_turn_args(active_run: &RunArgs, input: &str, chat_tui: bool) -> RunArgs {
     let mut turn_args = active_run.clone();
@@ -49,6 +52,7 @@ pub(crate) async fn run_chat_repl(
     let mut pending_params_input = false;
     let mut timeout_notice_active = false;
     let mut shared_chat_mcp_registry: Option<Arc<McpRegistry>> = None;
+    let session_hooks = SessionHooks::default();
 
     println!(
         "LocalAgent chat started (provider={} model={} tui={}).",
@@ -146,7 +150,7 @@ pub(crate) async fn run_chat_repl(
                     pending_params_input = true;
                     println!("{}", runtime_config::params_settings_summary(&active_run));
                     println!(
-                        "editable keys: max_steps, max_context_chars, compaction_mode(off|summary), compaction_keep_last, tool_result_persist(all|digest|none), max_tool_output_bytes, max_read_bytes, stream(on|off), allow_shell(on|off), allow_write(on|off), enable_write_tools(on|off), allow_shell_in_workdir(on|off)"
+                        "editable keys: max_steps, max_context_chars, compaction_mode(off|summary|summarize), compaction_keep_last, tool_result_persist(all|digest|none|artifact_file), max_tool_output_bytes, max_read_bytes, stream(on|off), allow_shell(on|off), allow_write(on|off), enable_write_tools(on|off), allow_shell_in_workdir(on|off)"
                     );
                     println!("enter '<key> <value>' or 'cancel'");
                 }
@@ -256,21 +260,24 @@ pub(crate) async fn run_chat_repl(
         let turn_args = prepare_chat_turn_args(&active_run, input, chat.tui);
 
         match provider_kind {
-            ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+            ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
                 let provider = OpenAiCompatProvider::new(
                     provider_kind,
                     base_url.clone(),
                     turn_args.api_key.clone(),
                     provider_runtime::http_config_from_run_args(&turn_args),
                 )?;
-                let res = run_agent(
+                let res = run_agent_with_ui(
                     provider,
-                    provider_kind,
-                    &base_url,
-                    &model,
-                    input,
-                    &turn_args,
-                    paths,
+                    AgentUiRunRequest::new(
+                        provider_kind,
+                        &base_url,
+                        &model,
+                        input,
+                        &turn_args,
+                        paths,
+                    )
+                    .with_session_hooks(session_hooks.clone()),
                 )
                 .await?;
                 if matches!(res.outcome.exit_reason, AgentExitReason::ProviderError) {
@@ -297,14 +304,17 @@ pub(crate) async fn run_chat_repl(
                     base_url.clone(),
                     provider_runtime::http_config_from_run_args(&turn_args),
                 )?;
-                let res = run_agent(
+                let res = run_agent_with_ui(
                     provider,
-                    provider_kind,
-                    &base_url,
-                    &model,
-                    input,
-                    &turn_args,
-                    paths,
+                    AgentUiRunRequest::new(
+                        provider_kind,
+                        &base_url,
+                        &model,
+                        input,
+                        &turn_args,
+                        paths,
+                    )
+                    .with_session_hooks(session_hooks.clone()),
                 )
                 .await?;
                 if matches!(res.outcome.exit_reason, AgentExitReason::ProviderError) {
@@ -328,14 +338,17 @@ pub(crate) async fn run_chat_repl(
             }
             ProviderKind::Mock => {
                 let provider = MockProvider::new();
-                let _ = run_agent(
+                let _ = run_agent_with_ui(
                     provider,
-                    provider_kind,
-                    &base_url,
-                    &model,
-                    input,
-                    &turn_args,
-                    paths,
+                    AgentUiRunRequest::new(
+                        provider_kind,
+                        &base_url,
+                        &model,
+                        input,
+                        &turn_args,
+                        paths,
+                    )
+                    .with_session_hooks(session_hooks.clone()),
                 )
                 .await?;
             }
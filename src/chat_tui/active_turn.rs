@@ -199,7 +199,20 @@ pub(crate) async fn drive_tui_active_turn_loop(
                     }
                 }
                 EventKind::QueueDelivered => {
-                    if let Some(queue_id) = ev.data.get("queue_id").and_then(|v| v.as_str()) {
+                    let coalesced = ev
+                        .data
+                        .get("coalesced_queue_ids")
+                        .and_then(|v| v.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|v| v.as_str());
+                    for queue_id in ev
+                        .data
+                        .get("queue_id")
+                        .and_then(|v| v.as_str())
+                        .into_iter()
+                        .chain(coalesced)
+                    {
                         if let Some(row) = active_queue_rows.get_mut(queue_id) {
                             row.status = "delivered".to_string();
                             row.delivery_phrase =
@@ -212,6 +225,20 @@ pub(crate) async fn drive_tui_active_turn_loop(
                         }
                     }
                 }
+                EventKind::QueueOverflow => {
+                    for queue_id in ev
+                        .data
+                        .get("dropped_queue_ids")
+                        .and_then(|v| v.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|v| v.as_str())
+                    {
+                        if let Some(row) = active_queue_rows.get_mut(queue_id) {
+                            row.status = "dropped".to_string();
+                        }
+                    }
+                }
                 EventKind::QueueInterrupt => {
                     if let Some(queue_id) = ev.data.get("queue_id").and_then(|v| v.as_str()) {
                         if let Some(row) = active_queue_rows.get_mut(queue_id) {
@@ -415,10 +442,8 @@ pub(crate) async fn drive_tui_active_turn_loop(
                         KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                             *show_logs = !*show_logs;
                         }
-                        KeyCode::Tab => {
-                            if *show_tools && *show_approvals {
-                                *tools_focus = !*tools_focus;
-                            }
+                        KeyCode::Tab if *show_tools && *show_approvals => {
+                            *tools_focus = !*tools_focus;
                         }
                         KeyCode::Char('1') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                             *show_tools = !*show_tools;
@@ -279,10 +279,8 @@ pub(crate) fn handle_tui_outer_key_dispatch(
             KeyCode::Up => {
                 *input.palette_selected = input.palette_selected.saturating_sub(1);
             }
-            KeyCode::Down => {
-                if *input.palette_selected + 1 < input.palette_items.len() {
-                    *input.palette_selected += 1;
-                }
+            KeyCode::Down if *input.palette_selected + 1 < input.palette_items.len() => {
+                *input.palette_selected += 1;
             }
             KeyCode::Enter => {
                 match *input.palette_selected {
@@ -109,7 +109,7 @@ pub(crate) async fn handle_tui_slash_command(
                 .logs
                 .push(runtime_config::params_settings_summary(input.active_run));
             input.logs.push(
-                "editable keys: max_steps, max_context_chars, compaction_mode(off|summary), compaction_keep_last, tool_result_persist(all|digest|none), max_tool_output_bytes, max_read_bytes, stream(on|off), allow_shell(on|off), allow_write(on|off), enable_write_tools(on|off), allow_shell_in_workdir(on|off)"
+                "editable keys: max_steps, max_context_chars, compaction_mode(off|summary|summarize), compaction_keep_last, tool_result_persist(all|digest|none|artifact_file), max_tool_output_bytes, max_read_bytes, stream(on|off), allow_shell(on|off), allow_write(on|off), enable_write_tools(on|off), allow_shell_in_workdir(on|off)"
                     .to_string(),
             );
             input
@@ -13,16 +13,17 @@ use crate::chat_tui::text::render_with_optional_caret;
 use crate::chat_ui;
 use crate::events::Event;
 use crate::gate::ProviderKind;
+use crate::hooks::session::SessionHooks;
 use crate::mcp::registry::McpRegistry;
 use crate::provider_runtime;
 use crate::providers::mock::MockProvider;
 use crate::providers::ollama::OllamaProvider;
 use crate::providers::openai_compat::OpenAiCompatProvider;
-use crate::run_agent_with_ui;
 use crate::runtime_paths;
 use crate::store;
 use crate::tui::state::UiState;
 use crate::RunArgs;
+use crate::{run_agent_with_ui, AgentUiRunRequest};
 
 pub(crate) enum TuiNormalSubmitPrepOutcome {
     ContinueToRun,
@@ -93,6 +94,7 @@ pub(crate) struct TuiNormalSubmitLaunchInput<'a> {
     pub(crate) follow_output: &'a bool,
     pub(crate) transcript_scroll: &'a mut usize,
     pub(crate) shared_chat_mcp_registry: &'a mut Option<std::sync::Arc<McpRegistry>>,
+    pub(crate) session_hooks: &'a SessionHooks,
 }
 
 fn prepare_tui_turn_args(active_run: &RunArgs, line: &str) -> RunArgs {
@@ -117,15 +119,18 @@ pub(crate) async fn build_tui_normal_submit_launch(
     if !turn_args.mcp.is_empty() && input.shared_chat_mcp_registry.is_none() {
         let mcp_config_path =
             runtime_paths::resolved_mcp_config_path(&turn_args, &input.paths.state_dir);
-        match McpRegistry::from_config_path(
+        match McpRegistry::from_config_path_with_metadata_policy(
             &mcp_config_path,
             &turn_args.mcp,
             Duration::from_secs(30),
+            runtime_paths::mcp_metadata_policy(&turn_args),
         )
         .await
         {
             Ok(reg) => {
-                *input.shared_chat_mcp_registry = Some(std::sync::Arc::new(reg));
+                *input.shared_chat_mcp_registry = Some(std::sync::Arc::new(
+                    reg.with_reconnect_policy(runtime_paths::mcp_reconnect_policy(&turn_args)),
+                ));
             }
             Err(e) => {
                 let msg = format!("failed to initialize MCP session: {e}");
@@ -148,73 +153,36 @@ pub(crate) async fn build_tui_normal_submit_launch(
     let line = input.line.to_string();
     let paths = input.paths.clone();
     let shared_chat_mcp_registry = input.shared_chat_mcp_registry.clone();
+    let session_hooks = input.session_hooks.clone();
     let queue_rx = queue_rx_opt.take().expect("queue rx once");
     let fut: TuiRunFuture = Box::pin(async move {
+        let request =
+            AgentUiRunRequest::new(provider_kind, &base_url, &model, &line, &turn_args, &paths)
+                .with_ui_tx(tx)
+                .with_operator_queue(queue_rx)
+                .with_shared_mcp_registry(shared_chat_mcp_registry)
+                .with_session_hooks(session_hooks)
+                .suppress_stdout_stream(true);
         match provider_kind {
-            ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+            ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
                 let provider = OpenAiCompatProvider::new(
                     provider_kind,
                     base_url.clone(),
                     turn_args.api_key.clone(),
                     provider_runtime::http_config_from_run_args(&turn_args),
                 )?;
-                run_agent_with_ui(
-                    provider,
-                    provider_kind,
-                    &base_url,
-                    &model,
-                    &line,
-                    &turn_args,
-                    &paths,
-                    Some(tx),
-                    Some(queue_rx),
-                    None,
-                    shared_chat_mcp_registry,
-                    None,
-                    true,
-                )
-                .await
+                run_agent_with_ui(provider, request).await
             }
             ProviderKind::Ollama => {
                 let provider = OllamaProvider::new(
                     base_url.clone(),
                     provider_runtime::http_config_from_run_args(&turn_args),
                 )?;
-                run_agent_with_ui(
-                    provider,
-                    provider_kind,
-                    &base_url,
-                    &model,
-                    &line,
-                    &turn_args,
-                    &paths,
-                    Some(tx),
-                    Some(queue_rx),
-                    None,
-                    shared_chat_mcp_registry,
-                    None,
-                    true,
-                )
-                .await
+                run_agent_with_ui(provider, request).await
             }
             ProviderKind::Mock => {
                 let provider = MockProvider::new();
-                run_agent_with_ui(
-                    provider,
-                    provider_kind,
-                    &base_url,
-                    &model,
-                    &line,
-                    &turn_args,
-                    &paths,
-                    Some(tx),
-                    Some(queue_rx),
-                    None,
-                    shared_chat_mcp_registry,
-                    None,
-                    true,
-                )
-                .await
+                run_agent_with_ui(provider, request).await
             }
         }
     });
@@ -267,6 +235,7 @@ pub(crate) struct TuiEnterSubmitInput<'a> {
     pub(crate) search_mode: bool,
     pub(crate) search_query: &'a str,
     pub(crate) shared_chat_mcp_registry: &'a mut Option<std::sync::Arc<McpRegistry>>,
+    pub(crate) session_hooks: &'a SessionHooks,
     pub(crate) learn_overlay: &'a mut Option<LearnOverlayState>,
     pub(crate) input_cursor: &'a mut usize,
     pub(crate) search_input_cursor: &'a mut usize,
@@ -321,6 +290,7 @@ pub(crate) async fn handle_tui_enter_submit(
         search_mode,
         search_query,
         shared_chat_mcp_registry,
+        session_hooks,
         learn_overlay,
         input_cursor,
         search_input_cursor,
@@ -499,6 +469,7 @@ pub(crate) async fn handle_tui_enter_submit(
         follow_output,
         transcript_scroll,
         shared_chat_mcp_registry,
+        session_hooks,
     })
     .await?
     {
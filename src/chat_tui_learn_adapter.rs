@@ -4,6 +4,7 @@ use clap::Parser;
 use crate::cli_args::{
     LearnArgs, LearnCategoryArg, LearnPromoteTargetArg, LearnStatusArg, LearnSubcommand, RunArgs,
 };
+use crate::cli_dispatch_learn::{capture_input_from_args, ReplayVerifyChainRequest};
 use crate::learning;
 use crate::providers::ModelProvider;
 use crate::store::StatePaths;
@@ -31,37 +32,16 @@ async fn dispatch_learn_args(
 ) -> anyhow::Result<String> {
     match args.command {
         LearnSubcommand::Capture {
-            run,
             assist,
             write,
-            category,
-            summary,
-            task_summary,
-            profile,
-            guidance_text,
-            check_text,
-            tags,
-            evidence,
-            evidence_notes,
+            ref from_run,
+            ..
         } => {
-            validate_capture_assist_flags(assist, write)?;
-            let category = match category {
-                LearnCategoryArg::WorkflowHint => learning::LearningCategoryV1::WorkflowHint,
-                LearnCategoryArg::PromptGuidance => learning::LearningCategoryV1::PromptGuidance,
-                LearnCategoryArg::CheckCandidate => learning::LearningCategoryV1::CheckCandidate,
-            };
-            let input = learning::build_capture_input(
-                run,
-                category,
-                summary,
-                task_summary,
-                profile,
-                guidance_text,
-                check_text,
-                tags,
-                evidence,
-                evidence_notes,
-            );
+            validate_capture_assist_flags(assist, write, from_run.is_some())?;
+            let input = capture_input_from_args(&args.command, &paths.state_dir)?;
+            if from_run.is_some() && !assist && !write {
+                return Ok(learning::render_capture_draft_preview(&input));
+            }
             if assist {
                 let assisted = generate_assisted_capture_preview(active_run, &input).await?;
                 let mut out = vec![learning::render_assist_capture_preview(&assisted.preview)];
@@ -97,6 +77,7 @@ async fn dispatch_learn_args(
             categories,
             limit,
             show_archived,
+            stale,
             format,
         } => {
             let mut entries = learning::list_learning_entries(&paths.state_dir)?;
@@ -130,6 +111,9 @@ async fn dispatch_learn_args(
             } else if !show_archived {
                 entries.retain(|e| e.status != learning::LearningStatusV1::Archived);
             }
+            if stale {
+                entries.retain(|e| e.promotion_stale);
+            }
             if entries.len() > limit {
                 entries.truncate(limit);
             }
@@ -141,6 +125,35 @@ async fn dispatch_learn_args(
                 )),
             }
         }
+        LearnSubcommand::Search {
+            query,
+            tags,
+            since,
+            limit,
+            show_archived,
+            format,
+        } => {
+            let entries = learning::search_learning_entries(
+                &paths.state_dir,
+                &query,
+                &learning::LearningSearchFilters {
+                    tags,
+                    since,
+                    include_archived: show_archived,
+                    limit,
+                },
+            )?
+            .into_iter()
+            .map(|hit| hit.entry)
+            .collect::<Vec<_>>();
+            match format.as_str() {
+                "table" => Ok(learning::render_learning_list_table(&entries)),
+                "json" => learning::render_learning_list_json_preview(&entries),
+                other => Err(anyhow!(
+                    "unsupported learn search format '{other}' (expected table|json)"
+                )),
+            }
+        }
         LearnSubcommand::Show {
             id,
             format,
@@ -153,6 +166,7 @@ async fn dispatch_learn_args(
                     &entry,
                     show_evidence,
                     show_proposed,
+                    learning::promotion_target_status(&paths.state_dir, &entry),
                 )),
                 "json" => learning::render_learning_show_json_preview(
                     &entry,
@@ -168,6 +182,10 @@ async fn dispatch_learn_args(
             let out = learning::archive_learning_entry(&paths.state_dir, &id)?;
             Ok(learning::render_archive_confirmation(&out))
         }
+        LearnSubcommand::Sync => {
+            let report = learning::sync_learning_promotions(&paths.state_dir)?;
+            Ok(learning::render_learning_sync_report(&report))
+        }
         LearnSubcommand::Promote {
             id,
             to,
@@ -191,11 +209,15 @@ async fn dispatch_learn_args(
                 let mut logs = vec![learning::render_promote_to_check_confirmation(&out)];
                 if check_run {
                     let check_out = crate::cli_dispatch_checks::run_check_command(
-                        Some(out.target_path.clone()),
-                        Some(1),
-                        active_run,
-                        &active_run.workdir,
-                        paths,
+                        crate::cli_dispatch_checks::CheckRunRequest {
+                            path: Some(&out.target_path),
+                            max_checks: Some(1),
+                            ..crate::cli_dispatch_checks::CheckRunRequest::new(
+                                active_run,
+                                &active_run.workdir,
+                                paths,
+                            )
+                        },
                     )
                     .await
                     .context("chained check run failed")?;
@@ -212,9 +234,9 @@ async fn dispatch_learn_args(
                 if replay_verify {
                     let (verify_text, failed) = run_chained_replay_verify_report(
                         paths,
-                        &id,
-                        replay_verify_run_id.as_deref(),
-                        replay_verify_strict,
+                        &ReplayVerifyChainRequest::new(id.clone())
+                            .run_id_override(replay_verify_run_id.clone())
+                            .strict(replay_verify_strict),
                     )?;
                     logs.push(verify_text);
                     if failed {
@@ -234,9 +256,9 @@ async fn dispatch_learn_args(
                 if replay_verify {
                     let (verify_text, failed) = run_chained_replay_verify_report(
                         paths,
-                        &id,
-                        replay_verify_run_id.as_deref(),
-                        replay_verify_strict,
+                        &ReplayVerifyChainRequest::new(id.clone())
+                            .run_id_override(replay_verify_run_id.clone())
+                            .strict(replay_verify_strict),
                     )?;
                     logs.push(verify_text);
                     if failed {
@@ -253,9 +275,9 @@ async fn dispatch_learn_args(
                 if replay_verify {
                     let (verify_text, failed) = run_chained_replay_verify_report(
                         paths,
-                        &id,
-                        replay_verify_run_id.as_deref(),
-                        replay_verify_strict,
+                        &ReplayVerifyChainRequest::new(id.clone())
+                            .run_id_override(replay_verify_run_id.clone())
+                            .strict(replay_verify_strict),
                     )?;
                     logs.push(verify_text);
                     if failed {
@@ -271,10 +293,13 @@ async fn dispatch_learn_args(
 fn render_tui_learn_help() -> String {
     [
         "/learn help",
-        "/learn list [--status <captured|promoted|archived>] [--category <workflow-hint|prompt-guidance|check-candidate>] [--limit N] [--show-archived] [--format table|json]",
+        "/learn list [--status <captured|promoted|archived>] [--category <workflow-hint|prompt-guidance|check-candidate>] [--limit N] [--show-archived] [--stale] [--format table|json]",
+        "/learn search <terms> [--tag <tag>] [--since <rfc3339>] [--limit N] [--show-archived] [--format table|json]",
         "/learn show <id> [--format text|json] [--show-evidence true|false] [--show-proposed true|false]",
         "/learn archive <id>",
+        "/learn sync",
         "/learn capture --category <...> --summary <...> [--assist] [--write] ...",
+        "/learn capture --from-run <run_id> [--write] [--category <...>] [--summary <...>] ...",
         "/learn promote <id> --to <check|pack|agents> [target flags] [--force] [--check-run] [--replay-verify ...]",
         "note: overlay Promote tab is simplified (target + force + arm/run). Use typed /learn promote for advanced flags.",
     ]
@@ -362,10 +387,10 @@ fn validate_promote_chain_flags(
     Ok(())
 }
 
-fn validate_capture_assist_flags(assist: bool, write: bool) -> anyhow::Result<()> {
-    if write && !assist {
+fn validate_capture_assist_flags(assist: bool, write: bool, from_run: bool) -> anyhow::Result<()> {
+    if write && !assist && !from_run {
         return Err(anyhow!(
-            "{}: --write requires --assist",
+            "{}: --write requires --assist or --from-run",
             learning::LEARN_ASSIST_WRITE_REQUIRES_ASSIST
         ));
     }
@@ -374,10 +399,11 @@ fn validate_capture_assist_flags(assist: bool, write: bool) -> anyhow::Result<()
 
 fn run_chained_replay_verify_report(
     paths: &StatePaths,
-    learning_id: &str,
-    run_id_override: Option<&str>,
-    strict: bool,
+    request: &ReplayVerifyChainRequest,
 ) -> anyhow::Result<(String, bool)> {
+    let learning_id = request.learning_id.as_str();
+    let run_id_override = request.run_id_override.as_deref();
+    let strict = request.strict;
     let source_run_id = if run_id_override.is_none() {
         let entry =
             learning::load_learning_entry(&paths.state_dir, learning_id).with_context(|| {
@@ -493,7 +519,9 @@ async fn call_assist_model(
     };
 
     let resp = match provider_kind {
-        crate::ProviderKind::Lmstudio | crate::ProviderKind::Llamacpp => {
+        crate::ProviderKind::Lmstudio
+        | crate::ProviderKind::Llamacpp
+        | crate::ProviderKind::OpenAi => {
             let provider = crate::OpenAiCompatProvider::new(
                 provider_kind,
                 base_url.to_string(),
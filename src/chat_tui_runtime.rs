@@ -115,10 +115,12 @@ pub(crate) async fn run_chat_tui(
     let mut learn_overlay: Option<LearnOverlayState> = None;
     let mut learn_overlay_cursor = 0usize;
     let mut shared_chat_mcp_registry: Option<std::sync::Arc<McpRegistry>> = None;
+    let session_hooks = crate::hooks::session::SessionHooks::default();
     let mut pending_timeout_input = false;
     let mut pending_params_input = false;
     let mut timeout_notice_active = false;
     let mut ui_state = UiState::new(max_logs);
+    ui_state.max_line_chars = base_run.tui_max_line_chars;
     ui_state.provider = provider_runtime::provider_cli_name(provider_kind).to_string();
     ui_state.model = model.clone();
     ui_state.caps_source = format!("{:?}", base_run.caps).to_lowercase();
@@ -440,6 +442,7 @@ pub(crate) async fn run_chat_tui(
                             search_mode,
                             search_query: &search_query,
                             shared_chat_mcp_registry: &mut shared_chat_mcp_registry,
+                            session_hooks: &session_hooks,
                             learn_overlay: &mut learn_overlay,
                             input_cursor: &mut input_cursor,
                             search_input_cursor: &mut search_input_cursor,
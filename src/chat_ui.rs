@@ -725,13 +725,15 @@ fn draw_approvals_pane(
 
     if show_detail {
         if let Some(a) = ui_state.pending_approvals.get(approvals_selected) {
+            let (arguments, _) =
+                crate::target::elide_long_lines(&a.arguments, ui_state.max_line_chars);
             let detail = format!(
                 "status: approval required\npolicy reason: not stored\ndecision: {}  risk: {}\ntool: {}  id: {}\nargs: {}",
                 approval_status_label(&a.status, 16),
                 a.risk,
                 a.tool,
                 a.id,
-                a.arguments
+                arguments
             );
             f.render_widget(
                 Paragraph::new(detail)
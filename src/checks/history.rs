@@ -0,0 +1,323 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::checks::report::CheckRunResult;
+
+pub const CHECK_HISTORY_SCHEMA_V1: &str = "localagent.checks.history.v1";
+pub const CHECK_FLAKY_REPORT_SCHEMA_V1: &str = "localagent.checks.flaky.v1";
+pub const DEFAULT_CHECK_HISTORY_RETENTION: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckHistoryRecordV1 {
+    pub schema: String,
+    pub name: String,
+    pub check_hash_hex: String,
+    pub status: String,
+    pub recorded_at: String,
+    pub runner_config_hash_hex: String,
+}
+
+pub fn check_history_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("checks").join("history.jsonl")
+}
+
+/// Appends one record per executed check, then prunes the oldest lines once the
+/// file exceeds `retention` records. Loader/runner errors without a check hash
+/// are not recorded.
+pub fn append_check_history(
+    path: &Path,
+    results: &[CheckRunResult],
+    runner_config_hash_hex: &str,
+    recorded_at: &str,
+    retention: usize,
+) -> anyhow::Result<()> {
+    let records = results
+        .iter()
+        .filter(|r| !r.check_hash_hex.is_empty())
+        .map(|r| CheckHistoryRecordV1 {
+            schema: CHECK_HISTORY_SCHEMA_V1.to_string(),
+            name: r.name.clone(),
+            check_hash_hex: r.check_hash_hex.clone(),
+            status: r.status.clone(),
+            recorded_at: recorded_at.to_string(),
+            runner_config_hash_hex: runner_config_hash_hex.to_string(),
+        })
+        .collect::<Vec<_>>();
+    if records.is_empty() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open check history {}", path.display()))?;
+    for record in &records {
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+    }
+    drop(file);
+    prune_check_history(path, retention)
+}
+
+fn prune_check_history(path: &Path, retention: usize) -> anyhow::Result<()> {
+    let text = fs::read_to_string(path)?;
+    let lines = text.lines().collect::<Vec<_>>();
+    if lines.len() <= retention {
+        return Ok(());
+    }
+    let mut kept = lines[lines.len() - retention..].join("\n");
+    if !kept.is_empty() {
+        kept.push('\n');
+    }
+    let tmp = path.with_extension(format!("jsonl.tmp.{}", uuid::Uuid::new_v4()));
+    fs::write(&tmp, kept)?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("failed to prune check history {}", path.display()))
+}
+
+/// Reads history in append order, skipping lines that do not parse.
+pub fn load_check_history(path: &Path) -> anyhow::Result<Vec<CheckHistoryRecordV1>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read check history {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter_map(|l| serde_json::from_str::<CheckHistoryRecordV1>(l).ok())
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FlakyBounds {
+    pub min_pass_rate: f64,
+    pub max_pass_rate: f64,
+}
+
+impl Default for FlakyBounds {
+    fn default() -> Self {
+        Self {
+            min_pass_rate: 0.0,
+            max_pass_rate: 1.0,
+        }
+    }
+}
+
+impl FlakyBounds {
+    /// Bounds are exclusive: with the defaults, a check that always passes or
+    /// always fails is consistent, anything in between is flaky.
+    pub fn is_flaky(&self, pass_rate: f64) -> bool {
+        pass_rate > self.min_pass_rate && pass_rate < self.max_pass_rate
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckFlakyEntry {
+    pub name: String,
+    pub check_hash_hex: String,
+    pub runs: usize,
+    pub passed: usize,
+    pub failed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pass_rate: Option<f64>,
+    pub flaky: bool,
+    pub quarantined: bool,
+    pub recent: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckFlakyReport {
+    pub schema_version: String,
+    pub window: usize,
+    pub min_pass_rate: f64,
+    pub max_pass_rate: f64,
+    pub checks: Vec<CheckFlakyEntry>,
+    pub flaky: usize,
+}
+
+/// Computes per-check pass rates over the last `window` runs of each check's
+/// most recent hash, so editing a check starts its history over. Only passed and
+/// failed runs count toward the rate; other statuses still show in `recent`.
+pub fn compute_flaky_report(
+    history: &[CheckHistoryRecordV1],
+    window: usize,
+    bounds: FlakyBounds,
+    quarantined: &[String],
+) -> CheckFlakyReport {
+    let mut by_name: BTreeMap<&str, Vec<&CheckHistoryRecordV1>> = BTreeMap::new();
+    for record in history {
+        by_name
+            .entry(record.name.as_str())
+            .or_default()
+            .push(record);
+    }
+    let mut checks = Vec::new();
+    for (name, records) in by_name {
+        let Some(latest) = records.last() else {
+            continue;
+        };
+        let current = records
+            .iter()
+            .filter(|r| r.check_hash_hex == latest.check_hash_hex)
+            .collect::<Vec<_>>();
+        let recent = &current[current.len().saturating_sub(window)..];
+        let passed = recent.iter().filter(|r| r.status == "passed").count();
+        let failed = recent.iter().filter(|r| r.status == "failed").count();
+        let pass_rate = (passed + failed > 0).then(|| passed as f64 / (passed + failed) as f64);
+        checks.push(CheckFlakyEntry {
+            name: name.to_string(),
+            check_hash_hex: latest.check_hash_hex.clone(),
+            runs: recent.len(),
+            passed,
+            failed,
+            pass_rate,
+            flaky: pass_rate.is_some_and(|rate| bounds.is_flaky(rate)),
+            quarantined: quarantined.iter().any(|q| q == name),
+            recent: recent.iter().map(|r| r.status.clone()).collect(),
+        });
+    }
+    let flaky = checks.iter().filter(|c| c.flaky).count();
+    CheckFlakyReport {
+        schema_version: CHECK_FLAKY_REPORT_SCHEMA_V1.to_string(),
+        window,
+        min_pass_rate: bounds.min_pass_rate,
+        max_pass_rate: bounds.max_pass_rate,
+        checks,
+        flaky,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, hash: &str, status: &str) -> CheckRunResult {
+        CheckRunResult {
+            name: name.to_string(),
+            path: format!("{name}.md"),
+            description: None,
+            status: status.to_string(),
+            reason_code: None,
+            summary: String::new(),
+            required: false,
+            file_bytes_hash_hex: String::new(),
+            frontmatter_hash_hex: String::new(),
+            check_hash_hex: hash.to_string(),
+            matrix_cell: None,
+        }
+    }
+
+    fn record(name: &str, hash: &str, status: &str) -> CheckHistoryRecordV1 {
+        CheckHistoryRecordV1 {
+            schema: CHECK_HISTORY_SCHEMA_V1.to_string(),
+            name: name.to_string(),
+            check_hash_hex: hash.to_string(),
+            status: status.to_string(),
+            recorded_at: "2026-01-01T00:00:00Z".to_string(),
+            runner_config_hash_hex: "cfg".to_string(),
+        }
+    }
+
+    #[test]
+    fn history_accumulates_across_runs_and_skips_unhashed_results() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = check_history_path(tmp.path());
+        for status in ["passed", "failed", "passed"] {
+            append_check_history(
+                &path,
+                &[result("a", "h1", status), result("loader", "", "error")],
+                "cfg",
+                "2026-01-01T00:00:00Z",
+                DEFAULT_CHECK_HISTORY_RETENTION,
+            )
+            .expect("append");
+        }
+        let history = load_check_history(&path).expect("load");
+        assert_eq!(
+            history
+                .iter()
+                .map(|r| r.status.as_str())
+                .collect::<Vec<_>>(),
+            vec!["passed", "failed", "passed"]
+        );
+        assert!(history.iter().all(|r| r.name == "a"));
+    }
+
+    #[test]
+    fn retention_prunes_oldest_records() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = check_history_path(tmp.path());
+        for i in 0..5 {
+            let status = if i < 3 { "failed" } else { "passed" };
+            append_check_history(&path, &[result("a", "h1", status)], "cfg", "t", 2)
+                .expect("append");
+        }
+        let history = load_check_history(&path).expect("load");
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|r| r.status == "passed"));
+    }
+
+    #[test]
+    fn flakiness_bounds_are_exclusive() {
+        let bounds = FlakyBounds::default();
+        assert!(!bounds.is_flaky(1.0));
+        assert!(!bounds.is_flaky(0.0));
+        assert!(bounds.is_flaky(0.8));
+
+        let history = ["passed", "failed", "passed", "passed"]
+            .iter()
+            .map(|s| record("a", "h1", s))
+            .collect::<Vec<_>>();
+        let tight = FlakyBounds {
+            min_pass_rate: 0.5,
+            max_pass_rate: 0.75,
+        };
+        let report = compute_flaky_report(&history, 20, tight, &[]);
+        assert_eq!(report.checks[0].pass_rate, Some(0.75));
+        assert!(!report.checks[0].flaky);
+
+        let report = compute_flaky_report(&history, 20, FlakyBounds::default(), &[]);
+        assert!(report.checks[0].flaky);
+        assert_eq!(report.flaky, 1);
+        assert_eq!(
+            report.checks[0].recent,
+            vec!["passed", "failed", "passed", "passed"]
+        );
+    }
+
+    #[test]
+    fn window_limits_runs_and_ignores_other_statuses_in_rate() {
+        let history = ["failed", "passed", "skipped", "passed"]
+            .iter()
+            .map(|s| record("a", "h1", s))
+            .collect::<Vec<_>>();
+        let report = compute_flaky_report(&history, 3, FlakyBounds::default(), &[]);
+        let entry = &report.checks[0];
+        assert_eq!(entry.runs, 3);
+        assert_eq!(entry.recent, vec!["passed", "skipped", "passed"]);
+        assert_eq!(entry.pass_rate, Some(1.0));
+        assert!(!entry.flaky);
+    }
+
+    #[test]
+    fn editing_a_check_resets_its_history() {
+        let history = vec![
+            record("a", "old", "failed"),
+            record("a", "old", "passed"),
+            record("a", "new", "passed"),
+        ];
+        let report = compute_flaky_report(&history, 20, FlakyBounds::default(), &["a".into()]);
+        let entry = &report.checks[0];
+        assert_eq!(entry.check_hash_hex, "new");
+        assert_eq!(entry.runs, 1);
+        assert!(!entry.flaky);
+        assert!(entry.quarantined);
+    }
+}
@@ -5,7 +5,9 @@ use std::path::{Path, PathBuf};
 use anyhow::Context;
 use serde::Serialize;
 
-use crate::checks::schema::{validate_frontmatter, CheckFrontmatter};
+use crate::checks::schema::{
+    validate_check_files, validate_frontmatter, validate_pass_criteria, CheckFrontmatter,
+};
 use crate::store::sha256_hex;
 
 pub const CODE_DISCOVERY_IO_ERROR: &str = "CHECK_DISCOVERY_IO_ERROR";
@@ -15,6 +17,7 @@ pub const CODE_YAML_PARSE_ERROR: &str = "CHECK_YAML_PARSE_ERROR";
 pub const CODE_SCHEMA_UNKNOWN_KEY: &str = "CHECK_SCHEMA_UNKNOWN_KEY";
 pub const CODE_SCHEMA_MISSING_FIELD: &str = "CHECK_SCHEMA_MISSING_FIELD";
 pub const CODE_DUPLICATE_NAME: &str = "CHECK_DUPLICATE_NAME";
+pub const CODE_CHECK_INVALID: &str = "CHECK_INVALID";
 
 #[derive(Debug, Clone)]
 pub struct LoadedCheck {
@@ -147,6 +150,20 @@ fn load_one_check(root: &Path, path: &Path) -> Result<LoadedCheck, CheckLoadErro
     })?;
     let frontmatter: CheckFrontmatter =
         serde_yaml::from_str(fm_text).map_err(|e| classify_yaml_error(&rel, &e.to_string()))?;
+    if let Err(e) = validate_pass_criteria(&frontmatter.pass_criteria, frontmatter.schema_version) {
+        return Err(CheckLoadError {
+            path: Some(rel.clone()),
+            code: CODE_CHECK_INVALID.to_string(),
+            message: e.to_string(),
+        });
+    }
+    if let Err(e) = validate_check_files(&frontmatter) {
+        return Err(CheckLoadError {
+            path: Some(rel.clone()),
+            code: CODE_CHECK_INVALID.to_string(),
+            message: e.to_string(),
+        });
+    }
     if let Err(e) = validate_frontmatter(&frontmatter) {
         let msg = e.to_string();
         let code = if msg.contains("missing field") {
@@ -221,6 +238,10 @@ fn canonical_frontmatter_json(fm: &CheckFrontmatter) -> String {
         required_flags: &'a Vec<String>,
         pass_criteria: &'a crate::checks::schema::PassCriteria,
         budget: &'a Option<crate::checks::schema::CheckBudget>,
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        fixtures: &'a [crate::checks::schema::CheckFixture],
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        expect_files: &'a [crate::checks::schema::CheckFileExpectation],
     }
     serde_json::to_string(&Canonical {
         schema_version: fm.schema_version,
@@ -233,6 +254,8 @@ fn canonical_frontmatter_json(fm: &CheckFrontmatter) -> String {
         required_flags: &fm.required_flags,
         pass_criteria: &fm.pass_criteria,
         budget: &fm.budget,
+        fixtures: &fm.fixtures,
+        expect_files: &fm.expect_files,
     })
     .expect("canonical frontmatter json")
 }
@@ -246,7 +269,10 @@ fn render_rel_path(path: &Path, root: &Path) -> String {
 mod tests {
     use std::fs;
 
-    use super::{load_checks, CODE_DUPLICATE_NAME, CODE_FRONTMATTER_MISSING};
+    use super::{
+        load_checks, LoadedCheck, CODE_CHECK_INVALID, CODE_DUPLICATE_NAME, CODE_FRONTMATTER_MISSING,
+    };
+    use crate::checks::runner::evaluate_final_output;
 
     #[test]
     fn loader_discovers_and_hashes_deterministically() {
@@ -284,6 +310,111 @@ mod tests {
         assert_eq!(out1.checks[0].body, "hello\n");
     }
 
+    #[test]
+    fn json_schema_criteria_validates_final_output() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let checks = tmp.path().join(".localagent").join("checks");
+        fs::create_dir_all(&checks).expect("checks");
+        fs::write(
+            checks.join("s.md"),
+            "---\nschema_version: 1\nname: s\npass_criteria:\n  type: json_schema\n  value: '{\"type\":\"object\",\"required\":[\"ok\"],\"properties\":{\"ok\":{\"type\":\"boolean\"}}}'\n---\nreport\n",
+        )
+        .expect("s");
+        fs::write(
+            checks.join("t.md"),
+            "---\nschema_version: 1\nname: t\npass_criteria:\n  type: json_schema\n  value: not json\n---\nreport\n",
+        )
+        .expect("t");
+        let out = load_checks(tmp.path(), None);
+        assert_eq!(out.checks.len(), 1);
+        assert_eq!(out.errors.len(), 1);
+        let check = &out.checks[0];
+        assert!(evaluate_final_output(check, r#"{"ok":true}"#).is_ok());
+        let err = evaluate_final_output(check, r#"{"ok":"yes"}"#).expect_err("type");
+        assert!(err.contains("$.ok has invalid type"), "{err}");
+    }
+
+    fn load_single(schema_version: u32, criteria: &str) -> Result<LoadedCheck, (String, String)> {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let checks = tmp.path().join(".localagent").join("checks");
+        fs::create_dir_all(&checks).expect("checks");
+        fs::write(
+            checks.join("c.md"),
+            format!("---\nschema_version: {schema_version}\nname: c\npass_criteria:\n{criteria}---\nbody\n"),
+        )
+        .expect("c");
+        let mut out = load_checks(tmp.path(), None);
+        match out.errors.pop() {
+            Some(e) => Err((e.code, e.message)),
+            None => Ok(out.checks.pop().expect("check")),
+        }
+    }
+
+    #[test]
+    fn regex_criteria_searches_or_full_matches() {
+        let search = load_single(2, "  type: regex\n  value: 'v\\d+\\.\\d+'\n").expect("search");
+        assert!(evaluate_final_output(&search, "released v1.2 today").is_ok());
+        let err = evaluate_final_output(&search, "released today").expect_err("no match");
+        assert!(err.contains("did not match regex"), "{err}");
+
+        let full = load_single(2, "  type: regex\n  value: 'v\\d+\\.\\d+'\n  match: full\n")
+            .expect("full");
+        assert!(evaluate_final_output(&full, "v1.2").is_ok());
+        assert!(evaluate_final_output(&full, "released v1.2").is_err());
+        assert_ne!(search.check_hash_hex, full.check_hash_hex);
+    }
+
+    #[test]
+    fn invalid_regex_and_old_schema_version_fail_load_as_check_invalid() {
+        let (code, message) =
+            load_single(2, "  type: regex\n  value: '(unclosed'\n").expect_err("invalid");
+        assert_eq!(code, CODE_CHECK_INVALID);
+        assert!(message.contains("not a valid regex"), "{message}");
+
+        let (code, message) = load_single(1, "  type: regex\n  value: ok\n").expect_err("v1");
+        assert_eq!(code, CODE_CHECK_INVALID);
+        assert!(message.contains("requires schema_version 2"), "{message}");
+
+        let (code, _) =
+            load_single(2, "  type: json_path\n  value: 'status==ok'\n").expect_err("path");
+        assert_eq!(code, CODE_CHECK_INVALID);
+        assert!(load_single(1, "  type: output_contains\n  value: ok\n").is_ok());
+    }
+
+    #[test]
+    fn json_path_criteria_compares_value_in_parsed_output() {
+        let check = load_single(
+            2,
+            "  type: json_path\n  value: '$.results[1].status==\"ok\"'\n",
+        )
+        .expect("check");
+        let output = r#"{"results":[{"status":"fail"},{"status":"ok"}]}"#;
+        assert!(evaluate_final_output(&check, output).is_ok());
+
+        let err = evaluate_final_output(&check, r#"{"results":[{},{"status":"fail"}]}"#)
+            .expect_err("mismatch");
+        assert_eq!(
+            err,
+            r#"json_path $.results[1].status is "fail", expected "ok""#
+        );
+        let err = evaluate_final_output(&check, r#"{"results":[]}"#).expect_err("missing");
+        assert!(err.contains("not found"), "{err}");
+        let err = evaluate_final_output(&check, "all good").expect_err("not json");
+        assert!(err.contains("final_output is not JSON"), "{err}");
+
+        let count = load_single(2, "  type: json_path\n  value: '$.count==3'\n").expect("count");
+        assert!(evaluate_final_output(&count, r#"{"count":3}"#).is_ok());
+        assert!(evaluate_final_output(&count, r#"{"count":"3"}"#).is_err());
+    }
+
+    #[test]
+    fn not_contains_criteria_rejects_forbidden_substring() {
+        let check = load_single(1, "  type: output_not_contains\n  value: TODO\n").expect("check");
+        assert!(evaluate_final_output(&check, "done").is_ok());
+        let err = evaluate_final_output(&check, "TODO: finish").expect_err("forbidden");
+        assert!(err.contains("forbidden substring"), "{err}");
+    }
+
     #[test]
     fn loader_reports_missing_frontmatter() {
         let tmp = tempfile::tempdir().expect("tempdir");
@@ -307,4 +438,37 @@ mod tests {
         let out = load_checks(tmp.path(), None);
         assert!(out.errors.iter().any(|e| e.code == CODE_DUPLICATE_NAME));
     }
+
+    #[test]
+    fn fixture_paths_must_stay_in_workspace_and_are_hashed() {
+        let load = |fixtures: &str| {
+            let tmp = tempfile::tempdir().expect("tempdir");
+            let checks = tmp.path().join(".localagent").join("checks");
+            fs::create_dir_all(&checks).expect("checks");
+            fs::write(
+                checks.join("f.md"),
+                format!("---\nschema_version: 1\nname: f\npass_criteria:\n  type: output_contains\n  value: ok\n{fixtures}---\nbody\n"),
+            )
+            .expect("f");
+            load_checks(tmp.path(), None)
+        };
+        for bad in ["../escape.txt", "/etc/passwd", "a/../../b"] {
+            let out = load(&format!("fixtures:\n  - path: '{bad}'\n    content: x\n"));
+            assert_eq!(out.errors.len(), 1, "{bad}");
+            assert_eq!(out.errors[0].code, CODE_CHECK_INVALID, "{bad}");
+        }
+        let out = load("expect_files:\n  - path: out.txt\n");
+        assert_eq!(out.errors[0].code, CODE_CHECK_INVALID);
+
+        let plain = load("").checks.remove(0);
+        let a = load("fixtures:\n  - path: cfg/config.json\n    content: '{\"port\": 1}'\n")
+            .checks
+            .remove(0);
+        let b = load("fixtures:\n  - path: cfg/config.json\n    content: '{\"port\": 2}'\n")
+            .checks
+            .remove(0);
+        assert_eq!(a.frontmatter.fixtures.len(), 1);
+        assert_ne!(plain.check_hash_hex, a.check_hash_hex);
+        assert_ne!(a.check_hash_hex, b.check_hash_hex);
+    }
 }
@@ -0,0 +1,233 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::gate::ProviderKind;
+
+/// One provider/model combination a `check run --matrix` runs every check
+/// against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckMatrixCell {
+    pub provider: ProviderKind,
+    pub model: String,
+    pub base_url: Option<String>,
+}
+
+impl CheckMatrixCell {
+    /// `provider:model`, the value of each result's `matrix_cell`.
+    pub fn label(&self) -> String {
+        let provider = self
+            .provider
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_else(|| format!("{:?}", self.provider).to_lowercase());
+        format!("{provider}:{}", self.model)
+    }
+}
+
+/// Cells to run and how many run at once. No cells means a single run with
+/// the top-level `--provider`/`--model`.
+#[derive(Debug, Clone, Default)]
+pub struct CheckMatrix {
+    pub cells: Vec<CheckMatrixCell>,
+    pub parallel: usize,
+}
+
+impl CheckMatrix {
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheckMatrixFile {
+    cells: Vec<CheckMatrixFileCell>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheckMatrixFileCell {
+    provider: String,
+    model: String,
+    #[serde(default)]
+    base_url: Option<String>,
+}
+
+/// Per-cell counts in a matrix report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckMatrixCellSummary {
+    pub cell: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub errors: usize,
+    pub quarantined: usize,
+}
+
+/// Parses a `--matrix` value: comma-separated `provider=`, `model=` and
+/// optional `base_url=`.
+pub fn parse_matrix_cell(spec: &str) -> anyhow::Result<CheckMatrixCell> {
+    let mut provider = None;
+    let mut model = None;
+    let mut base_url = None;
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = part
+            .split_once('=')
+            .ok_or_else(|| anyhow!("matrix cell '{spec}': expected key=value, got '{part}'"))?;
+        let value = value.trim().to_string();
+        match key.trim() {
+            "provider" => provider = Some(value),
+            "model" => model = Some(value),
+            "base_url" => base_url = Some(value),
+            other => return Err(anyhow!("matrix cell '{spec}': unknown key '{other}'")),
+        }
+    }
+    build_cell(
+        spec,
+        provider.ok_or_else(|| anyhow!("matrix cell '{spec}' is missing provider="))?,
+        model.ok_or_else(|| anyhow!("matrix cell '{spec}' is missing model="))?,
+        base_url,
+    )
+}
+
+/// Reads `cells: [{provider, model, base_url?}]` from a YAML file.
+pub fn load_matrix_file(path: &Path) -> anyhow::Result<Vec<CheckMatrixCell>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read matrix file {}", path.display()))?;
+    let file: CheckMatrixFile = serde_yaml::from_str(&raw)
+        .with_context(|| format!("failed to parse matrix file {}", path.display()))?;
+    file.cells
+        .into_iter()
+        .map(|c| {
+            let spec = format!("{}:{}", c.provider, c.model);
+            build_cell(&spec, c.provider, c.model, c.base_url)
+        })
+        .collect()
+}
+
+/// Cells from `--matrix` flags followed by those of `--matrix-file`.
+/// Duplicate labels are rejected since results are keyed by label.
+pub fn resolve_check_matrix(
+    specs: &[String],
+    file: Option<&Path>,
+    parallel: usize,
+) -> anyhow::Result<CheckMatrix> {
+    let mut cells = specs
+        .iter()
+        .map(|s| parse_matrix_cell(s))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if let Some(file) = file {
+        cells.extend(load_matrix_file(file)?);
+    }
+    let mut labels = std::collections::BTreeSet::new();
+    for cell in &cells {
+        if !labels.insert(cell.label()) {
+            return Err(anyhow!("duplicate matrix cell '{}'", cell.label()));
+        }
+    }
+    Ok(CheckMatrix {
+        cells,
+        parallel: parallel.max(1),
+    })
+}
+
+/// Counts per cell, in order of each cell's first result.
+pub fn summarize_matrix(
+    results: &[crate::checks::report::CheckRunResult],
+) -> Vec<CheckMatrixCellSummary> {
+    let mut out: Vec<CheckMatrixCellSummary> = Vec::new();
+    for r in results {
+        let Some(cell) = r.matrix_cell.as_deref() else {
+            continue;
+        };
+        let idx = match out.iter().position(|s| s.cell == cell) {
+            Some(idx) => idx,
+            None => {
+                out.push(CheckMatrixCellSummary {
+                    cell: cell.to_string(),
+                    passed: 0,
+                    failed: 0,
+                    skipped: 0,
+                    errors: 0,
+                    quarantined: 0,
+                });
+                out.len() - 1
+            }
+        };
+        let summary = &mut out[idx];
+        match r.status.as_str() {
+            "passed" => summary.passed += 1,
+            "failed" => summary.failed += 1,
+            "skipped" => summary.skipped += 1,
+            "quarantined" => summary.quarantined += 1,
+            _ => summary.errors += 1,
+        }
+    }
+    out
+}
+
+fn build_cell(
+    spec: &str,
+    provider: String,
+    model: String,
+    base_url: Option<String>,
+) -> anyhow::Result<CheckMatrixCell> {
+    let provider = ProviderKind::from_str(&provider, true)
+        .map_err(|_| anyhow!("matrix cell '{spec}': unknown provider '{provider}'"))?;
+    if model.trim().is_empty() {
+        return Err(anyhow!("matrix cell '{spec}': model must not be empty"));
+    }
+    Ok(CheckMatrixCell {
+        provider,
+        model,
+        base_url: base_url.filter(|u| !u.trim().is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flag_and_file_cells_and_rejects_duplicates() {
+        let cell = parse_matrix_cell("provider=ollama, model=llama3.1").expect("cell");
+        assert_eq!(cell.provider, ProviderKind::Ollama);
+        assert_eq!(cell.label(), "ollama:llama3.1");
+        assert!(parse_matrix_cell("provider=nope,model=x").is_err());
+        assert!(parse_matrix_cell("model=x").is_err());
+        assert!(parse_matrix_cell("provider=mock,model=x,temp=1").is_err());
+
+        let tmp = tempfile::tempdir().expect("tmp");
+        let file = tmp.path().join("matrix.yaml");
+        std::fs::write(
+            &file,
+            "cells:\n  - provider: lmstudio\n    model: qwen\n    base_url: http://127.0.0.1:1234/v1\n",
+        )
+        .expect("write");
+        let matrix = resolve_check_matrix(
+            &["provider=ollama,model=llama3.1".to_string()],
+            Some(&file),
+            0,
+        )
+        .expect("matrix");
+        assert_eq!(matrix.parallel, 1);
+        assert_eq!(
+            matrix
+                .cells
+                .iter()
+                .map(CheckMatrixCell::label)
+                .collect::<Vec<_>>(),
+            vec!["ollama:llama3.1", "lmstudio:qwen"]
+        );
+        assert_eq!(
+            matrix.cells[1].base_url.as_deref(),
+            Some("http://127.0.0.1:1234/v1")
+        );
+
+        let dup = "provider=mock,model=m".to_string();
+        assert!(resolve_check_matrix(&[dup.clone(), dup], None, 1).is_err());
+    }
+}
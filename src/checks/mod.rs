@@ -1,4 +1,7 @@
+pub mod history;
 pub mod loader;
+pub mod matrix;
+pub mod quarantine;
 pub mod report;
 pub mod runner;
 pub mod schema;
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::checks::report::CheckRunResult;
+
+pub const CHECK_STATUS_QUARANTINED: &str = "quarantined";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheckQuarantineFile {
+    #[serde(default)]
+    checks: Vec<String>,
+}
+
+pub fn check_quarantine_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("checks").join("quarantine.yaml")
+}
+
+/// Check names listed under `checks:` in the quarantine file. A missing file
+/// means nothing is quarantined.
+pub fn load_check_quarantine(path: &Path) -> anyhow::Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read check quarantine {}", path.display()))?;
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let file: CheckQuarantineFile = serde_yaml::from_str(&text)
+        .with_context(|| format!("failed to parse check quarantine {}", path.display()))?;
+    Ok(file.checks)
+}
+
+/// Quarantined checks still run, but report `quarantined` instead of their real
+/// status so they never fail the overall exit code. The real status is kept in
+/// the summary.
+pub fn apply_check_quarantine(results: &mut [CheckRunResult], quarantined: &[String]) {
+    for result in results {
+        if !quarantined.iter().any(|q| q == &result.name) {
+            continue;
+        }
+        result.summary = format!("underlying_status={}; {}", result.status, result.summary);
+        result.status = CHECK_STATUS_QUARANTINED.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::report::CheckRunReport;
+    use crate::checks::runner::{exit_for_report, CheckRunExit};
+
+    fn result(name: &str, status: &str) -> CheckRunResult {
+        CheckRunResult {
+            name: name.to_string(),
+            path: format!("{name}.md"),
+            description: None,
+            status: status.to_string(),
+            reason_code: None,
+            summary: "s".to_string(),
+            required: true,
+            file_bytes_hash_hex: String::new(),
+            frontmatter_hash_hex: String::new(),
+            check_hash_hex: "h".to_string(),
+            matrix_cell: None,
+        }
+    }
+
+    #[test]
+    fn quarantined_failures_do_not_affect_exit_code() {
+        let mut results = vec![result("ok", "passed"), result("flaky", "failed")];
+        apply_check_quarantine(&mut results, &["flaky".to_string()]);
+        assert_eq!(results[1].status, CHECK_STATUS_QUARANTINED);
+        assert!(results[1].summary.starts_with("underlying_status=failed; "));
+
+        let report = CheckRunReport::from_results(results);
+        assert_eq!(report.quarantined, 1);
+        assert_eq!(report.failed, 0);
+        assert_eq!(report.errors, 0);
+        assert_eq!(exit_for_report(&report), CheckRunExit::Ok);
+    }
+
+    #[test]
+    fn unquarantined_failures_still_fail() {
+        let mut results = vec![result("a", "failed"), result("b", "error")];
+        apply_check_quarantine(&mut results, &["b".to_string()]);
+        let report = CheckRunReport::from_results(results);
+        assert_eq!(exit_for_report(&report), CheckRunExit::FailedChecks);
+    }
+
+    #[test]
+    fn quarantine_file_loads_names_and_tolerates_absence() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let path = check_quarantine_path(tmp.path());
+        assert!(load_check_quarantine(&path).expect("missing").is_empty());
+        fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        fs::write(&path, "checks:\n  - flaky_one\n  - flaky_two\n").expect("write");
+        assert_eq!(
+            load_check_quarantine(&path).expect("load"),
+            vec!["flaky_one".to_string(), "flaky_two".to_string()]
+        );
+        fs::write(&path, "check: [typo]\n").expect("write");
+        assert!(load_check_quarantine(&path).is_err());
+    }
+}
@@ -13,6 +13,8 @@ pub struct CheckRunResult {
     pub file_bytes_hash_hex: String,
     pub frontmatter_hash_hex: String,
     pub check_hash_hex: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix_cell: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -25,6 +27,9 @@ pub struct CheckRunReport {
     pub failed: usize,
     pub skipped: usize,
     pub errors: usize,
+    pub quarantined: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matrix: Vec<crate::checks::matrix::CheckMatrixCellSummary>,
 }
 
 impl CheckRunReport {
@@ -45,14 +50,17 @@ impl CheckRunReport {
         let mut failed = 0;
         let mut skipped = 0;
         let mut errors = 0;
+        let mut quarantined = 0;
         for c in &checks {
             match c.status.as_str() {
                 "passed" => passed += 1,
                 "failed" => failed += 1,
                 "skipped" => skipped += 1,
+                "quarantined" => quarantined += 1,
                 _ => errors += 1,
             }
         }
+        let matrix = crate::checks::matrix::summarize_matrix(&checks);
         Self {
             schema_version: "localagent.checks.report.v1".to_string(),
             runner_profile,
@@ -62,20 +70,59 @@ impl CheckRunReport {
             failed,
             skipped,
             errors,
+            quarantined,
+            matrix,
         }
     }
 }
 
+/// Writes one `<testsuite>` per matrix cell when results carry a
+/// `matrix_cell`, otherwise a single `localagent-checks` suite.
 pub fn write_junit(path: &Path, report: &CheckRunReport) -> anyhow::Result<()> {
     let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+    if report.matrix.is_empty() {
+        push_junit_testsuite(&mut xml, "localagent-checks", report.checks.iter());
+    } else {
+        for cell in &report.matrix {
+            push_junit_testsuite(
+                &mut xml,
+                &format!("localagent-checks[{}]", cell.cell),
+                report
+                    .checks
+                    .iter()
+                    .filter(|c| c.matrix_cell.as_deref() == Some(cell.cell.as_str())),
+            );
+        }
+    }
+    xml.push_str("</testsuites>\n");
+    std::fs::write(path, xml)?;
+    Ok(())
+}
+
+fn push_junit_testsuite<'a>(
+    xml: &mut String,
+    name: &str,
+    checks: impl Iterator<Item = &'a CheckRunResult> + Clone,
+) {
+    let (mut tests, mut failures, mut skipped, mut errors) = (0, 0, 0, 0);
+    for c in checks.clone() {
+        tests += 1;
+        match c.status.as_str() {
+            "passed" => {}
+            "failed" => failures += 1,
+            "skipped" | "quarantined" => skipped += 1,
+            _ => errors += 1,
+        }
+    }
     xml.push_str(&format!(
-        "<testsuite name=\"localagent-checks\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" errors=\"{}\">\n",
-        report.checks.len(),
-        report.failed,
-        report.skipped,
-        report.errors
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" errors=\"{}\">\n",
+        xml_escape(name),
+        tests,
+        failures,
+        skipped,
+        errors
     ));
-    for c in &report.checks {
+    for c in checks {
         xml.push_str(&format!(
             "<testcase classname=\"{}\" name=\"{}\">",
             xml_escape(&c.path),
@@ -91,6 +138,10 @@ pub fn write_junit(path: &Path, report: &CheckRunReport) -> anyhow::Result<()> {
                 "<skipped message=\"{}\"/>",
                 xml_escape(c.reason_code.as_deref().unwrap_or("CHECK_SKIPPED"))
             )),
+            "quarantined" => xml.push_str(&format!(
+                "<skipped message=\"CHECK_QUARANTINED\">{}</skipped>",
+                xml_escape(&c.summary)
+            )),
             "error" => xml.push_str(&format!(
                 "<error message=\"{}\">{}</error>",
                 xml_escape(c.reason_code.as_deref().unwrap_or("CHECK_ERROR")),
@@ -100,9 +151,7 @@ pub fn write_junit(path: &Path, report: &CheckRunReport) -> anyhow::Result<()> {
         }
         xml.push_str("</testcase>\n");
     }
-    xml.push_str("</testsuite>\n</testsuites>\n");
-    std::fs::write(path, xml)?;
-    Ok(())
+    xml.push_str("</testsuite>\n");
 }
 
 fn xml_escape(s: &str) -> String {
@@ -2,7 +2,10 @@ use std::path::{Path, PathBuf};
 
 use crate::checks::loader::{load_checks, CheckLoadError, LoadedCheck};
 use crate::checks::report::{CheckRunReport, CheckRunResult};
-use crate::checks::schema::PassCriteriaType;
+use crate::checks::schema::{
+    compile_criteria_regex, validate_check_file_path, CheckFileExpectation, CheckFixture,
+    JsonPathCriterion, PassCriteriaType,
+};
 
 #[derive(Debug, Clone)]
 pub struct CheckRunArgs {
@@ -18,6 +21,16 @@ pub enum CheckRunExit {
     RunnerError = 4,
 }
 
+pub fn exit_for_report(report: &CheckRunReport) -> CheckRunExit {
+    if report.errors > 0 {
+        CheckRunExit::RunnerError
+    } else if report.failed > 0 {
+        CheckRunExit::FailedChecks
+    } else {
+        CheckRunExit::Ok
+    }
+}
+
 pub fn load_checks_for_run(
     root: &Path,
     args: &CheckRunArgs,
@@ -50,6 +63,7 @@ pub fn report_from_loader_errors(errors: Vec<CheckLoadError>) -> CheckRunReport
             file_bytes_hash_hex: String::new(),
             frontmatter_hash_hex: String::new(),
             check_hash_hex: String::new(),
+            matrix_cell: None,
         })
         .collect::<Vec<_>>();
     CheckRunReport::from_results(results)
@@ -67,9 +81,67 @@ pub fn report_single_error(code: &str, message: impl Into<String>) -> CheckRunRe
         file_bytes_hash_hex: String::new(),
         frontmatter_hash_hex: String::new(),
         check_hash_hex: String::new(),
+        matrix_cell: None,
     }])
 }
 
+/// Writes `fixtures` under `root`, the check's scratch workspace.
+pub fn materialize_check_fixtures(root: &Path, fixtures: &[CheckFixture]) -> anyhow::Result<()> {
+    for fixture in fixtures {
+        validate_check_file_path("fixtures", &fixture.path)?;
+        let target = root.join(&fixture.path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, &fixture.content)
+            .map_err(|e| anyhow::anyhow!("failed to write fixture {}: {e}", fixture.path))?;
+    }
+    Ok(())
+}
+
+/// Evaluates `expect_files` against `root`; the error names the first
+/// failing path.
+pub fn evaluate_expect_files(root: &Path, expects: &[CheckFileExpectation]) -> Result<(), String> {
+    for expect in expects {
+        validate_check_file_path("expect_files", &expect.path).map_err(|e| e.to_string())?;
+        let target = root.join(&expect.path);
+        let bytes = std::fs::read(&target).ok();
+        if let Some(want) = expect.exists {
+            if want != bytes.is_some() {
+                return Err(if want {
+                    format!("expected file {} to exist", expect.path)
+                } else {
+                    format!("expected file {} not to exist", expect.path)
+                });
+            }
+        }
+        if expect.contains.is_none() && expect.sha256.is_none() {
+            continue;
+        }
+        let Some(bytes) = bytes else {
+            return Err(format!("expected file {} is missing", expect.path));
+        };
+        if let Some(needle) = &expect.contains {
+            if !String::from_utf8_lossy(&bytes).contains(needle.as_str()) {
+                return Err(format!(
+                    "file {} missing expected substring: {needle}",
+                    expect.path
+                ));
+            }
+        }
+        if let Some(want) = &expect.sha256 {
+            let got = crate::store::sha256_hex(&bytes);
+            if &got != want {
+                return Err(format!(
+                    "file {} sha256 is {got}, expected {want}",
+                    expect.path
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn evaluate_final_output(check: &LoadedCheck, final_output: &str) -> Result<(), String> {
     let value = &check.frontmatter.pass_criteria.value;
     match check.frontmatter.pass_criteria.kind {
@@ -100,5 +172,37 @@ pub fn evaluate_final_output(check: &LoadedCheck, final_output: &str) -> Result<
                 Err("final_output did not equal expected value".to_string())
             }
         }
+        PassCriteriaType::JsonSchema => {
+            let schema = serde_json::from_str::<serde_json::Value>(value)
+                .map_err(|e| format!("pass_criteria schema is not valid JSON: {e}"))?;
+            crate::tools::validate_json_output(final_output, &schema)
+                .map(|_| ())
+                .map_err(|errors| format!("final_output failed json_schema: {}", errors.join("; ")))
+        }
+        PassCriteriaType::Regex => {
+            let re = compile_criteria_regex(&check.frontmatter.pass_criteria)
+                .map_err(|e| e.to_string())?;
+            if re.is_match(final_output) {
+                Ok(())
+            } else {
+                Err(format!("final_output did not match regex: {}", value))
+            }
+        }
+        PassCriteriaType::JsonPath => {
+            let criterion = JsonPathCriterion::parse(value).map_err(|e| e.to_string())?;
+            let parsed = serde_json::from_str::<serde_json::Value>(final_output.trim())
+                .map_err(|e| format!("final_output is not JSON, cannot evaluate json_path: {e}"))?;
+            match criterion.lookup(&parsed) {
+                Some(actual) if *actual == criterion.expected => Ok(()),
+                Some(actual) => Err(format!(
+                    "json_path {} is {}, expected {}",
+                    criterion.path, actual, criterion.expected
+                )),
+                None => Err(format!(
+                    "json_path {} not found in final_output",
+                    criterion.path
+                )),
+            }
+        }
     }
 }
@@ -20,6 +20,15 @@ pub struct CheckFrontmatter {
     pub pass_criteria: PassCriteria,
     #[serde(default)]
     pub budget: Option<CheckBudget>,
+    /// Files written into the isolated scratch workspace before the run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixtures: Vec<CheckFixture>,
+    /// File assertions evaluated in the scratch workspace after the run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect_files: Vec<CheckFileExpectation>,
+    /// Learning entry this check was promoted from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub learning_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -28,6 +37,18 @@ pub struct PassCriteria {
     #[serde(rename = "type")]
     pub kind: PassCriteriaType,
     pub value: String,
+    /// How a `regex` criterion matches; `search` when unset.
+    #[serde(default, rename = "match", skip_serializing_if = "Option::is_none")]
+    pub regex_match: Option<RegexMatchMode>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RegexMatchMode {
+    /// The pattern must match somewhere in the final output.
+    Search,
+    /// The pattern must match the whole final output.
+    Full,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -38,6 +59,222 @@ pub enum PassCriteriaType {
     NotContains,
     #[serde(rename = "output_equals")]
     Equals,
+    /// `value` is a JSON schema the final output must parse and validate
+    /// against.
+    #[serde(rename = "json_schema")]
+    JsonSchema,
+    /// `value` is a regex; see [`PassCriteria::regex_match`]. Needs
+    /// schema_version 2.
+    #[serde(rename = "regex")]
+    Regex,
+    /// `value` is `<path>==<expected>`, checked against the final output
+    /// parsed as JSON. Needs schema_version 2.
+    #[serde(rename = "json_path")]
+    JsonPath,
+}
+
+impl PassCriteriaType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Contains => "output_contains",
+            Self::NotContains => "output_not_contains",
+            Self::Equals => "output_equals",
+            Self::JsonSchema => "json_schema",
+            Self::Regex => "regex",
+            Self::JsonPath => "json_path",
+        }
+    }
+
+    fn min_schema_version(&self) -> u32 {
+        match self {
+            Self::Regex | Self::JsonPath => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Highest check file schema_version this build reads.
+pub const CHECK_SCHEMA_VERSION_LATEST: u32 = 2;
+
+/// One step of a `json_path` criterion's path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// A parsed `json_path` criterion. `expected` is the right-hand side parsed
+/// as JSON, or as a plain string when it is not valid JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPathCriterion {
+    pub path: String,
+    pub segments: Vec<JsonPathSegment>,
+    pub expected: serde_json::Value,
+}
+
+impl JsonPathCriterion {
+    /// Parses `$.a.b[0]==expected`. The path starts with `$` and continues
+    /// with `.key` and `[index]` steps.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let (path, expected) = value
+            .split_once("==")
+            .ok_or_else(|| anyhow::anyhow!("json_path value must be '<path>==<expected>'"))?;
+        let path = path.trim();
+        let expected = expected.trim();
+        let mut rest = path
+            .strip_prefix('$')
+            .ok_or_else(|| anyhow::anyhow!("json_path '{path}' must start with '$'"))?;
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                if end == 0 {
+                    anyhow::bail!("json_path '{path}' has an empty key");
+                }
+                segments.push(JsonPathSegment::Key(after[..end].to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let (index, tail) = after
+                    .split_once(']')
+                    .ok_or_else(|| anyhow::anyhow!("json_path '{path}' has an unclosed '['"))?;
+                let index = index.trim().parse::<usize>().map_err(|_| {
+                    anyhow::anyhow!("json_path '{path}' has a non-numeric index '{index}'")
+                })?;
+                segments.push(JsonPathSegment::Index(index));
+                rest = tail;
+            } else {
+                anyhow::bail!("json_path '{path}' must continue with '.key' or '[index]'");
+            }
+        }
+        Ok(Self {
+            path: path.to_string(),
+            segments,
+            expected: serde_json::from_str(expected)
+                .unwrap_or_else(|_| serde_json::Value::String(expected.to_string())),
+        })
+    }
+
+    pub fn lookup<'a>(&self, root: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+        self.segments
+            .iter()
+            .try_fold(root, |value, segment| match segment {
+                JsonPathSegment::Key(key) => value.get(key),
+                JsonPathSegment::Index(index) => value.get(index),
+            })
+    }
+}
+
+/// The compiled regex of a `regex` criterion, anchored for `match: full`.
+pub fn compile_criteria_regex(criteria: &PassCriteria) -> anyhow::Result<regex::Regex> {
+    let pattern = match criteria.regex_match.unwrap_or(RegexMatchMode::Search) {
+        RegexMatchMode::Search => criteria.value.clone(),
+        RegexMatchMode::Full => format!("^(?:{})$", criteria.value),
+    };
+    regex::Regex::new(&pattern)
+        .map_err(|e| anyhow::anyhow!("pass_criteria.value is not a valid regex: {e}"))
+}
+
+/// Checks that the criteria's type is allowed at `schema_version` and that
+/// its value parses. The loader reports failures as `CHECK_INVALID`.
+pub fn validate_pass_criteria(criteria: &PassCriteria, schema_version: u32) -> anyhow::Result<()> {
+    let min = criteria.kind.min_schema_version();
+    if schema_version < min {
+        anyhow::bail!(
+            "pass_criteria type '{}' requires schema_version {min}",
+            criteria.kind.as_str()
+        );
+    }
+    if criteria.regex_match.is_some() && criteria.kind != PassCriteriaType::Regex {
+        anyhow::bail!("pass_criteria.match only applies to type 'regex'");
+    }
+    match criteria.kind {
+        PassCriteriaType::JsonSchema => {
+            if let Err(e) = serde_json::from_str::<serde_json::Value>(&criteria.value) {
+                anyhow::bail!("pass_criteria.value must be a JSON schema: {e}");
+            }
+        }
+        PassCriteriaType::Regex => {
+            compile_criteria_regex(criteria)?;
+        }
+        PassCriteriaType::JsonPath => {
+            JsonPathCriterion::parse(&criteria.value)?;
+        }
+        PassCriteriaType::Contains | PassCriteriaType::NotContains | PassCriteriaType::Equals => {}
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CheckFixture {
+    /// Workspace-relative path; no absolute paths or `..`.
+    pub path: String,
+    pub content: String,
+}
+
+/// A post-run assertion on one workspace file. At least one of `exists`,
+/// `contains` or `sha256` must be set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CheckFileExpectation {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+    /// Lowercase hex sha256 of the file bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// Rejects paths that could leave the scratch workspace.
+pub fn validate_check_file_path(field: &str, path: &str) -> anyhow::Result<()> {
+    let p = std::path::Path::new(path);
+    if path.trim().is_empty() {
+        anyhow::bail!("{field} path must not be empty");
+    }
+    if p.is_absolute() || path.starts_with('/') || path.starts_with('\\') {
+        anyhow::bail!("{field} path '{path}' must be relative to the workspace");
+    }
+    if p.components().any(|c| {
+        !matches!(
+            c,
+            std::path::Component::Normal(_) | std::path::Component::CurDir
+        )
+    }) {
+        anyhow::bail!("{field} path '{path}' must stay inside the workspace");
+    }
+    Ok(())
+}
+
+/// Checks fixture and expect_files paths and expectations. The loader
+/// reports failures as `CHECK_INVALID`.
+pub fn validate_check_files(fm: &CheckFrontmatter) -> anyhow::Result<()> {
+    let mut seen = std::collections::BTreeSet::new();
+    for fixture in &fm.fixtures {
+        validate_check_file_path("fixtures", &fixture.path)?;
+        if !seen.insert(fixture.path.as_str()) {
+            anyhow::bail!("fixtures path '{}' is listed twice", fixture.path);
+        }
+    }
+    for expect in &fm.expect_files {
+        validate_check_file_path("expect_files", &expect.path)?;
+        if expect.exists.is_none() && expect.contains.is_none() && expect.sha256.is_none() {
+            anyhow::bail!(
+                "expect_files '{}' must set exists, contains or sha256",
+                expect.path
+            );
+        }
+        if let Some(hash) = &expect.sha256 {
+            if hash.len() != 64 || !hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+                anyhow::bail!(
+                    "expect_files '{}' sha256 must be 64 lowercase hex characters",
+                    expect.path
+                );
+            }
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -52,9 +289,9 @@ pub struct CheckBudget {
 }
 
 pub fn validate_frontmatter(fm: &CheckFrontmatter) -> anyhow::Result<()> {
-    if fm.schema_version != 1 {
+    if fm.schema_version == 0 || fm.schema_version > CHECK_SCHEMA_VERSION_LATEST {
         anyhow::bail!(
-            "unsupported schema_version {} (expected 1)",
+            "unsupported schema_version {} (expected 1 or {CHECK_SCHEMA_VERSION_LATEST})",
             fm.schema_version
         );
     }
@@ -78,6 +315,8 @@ pub fn validate_frontmatter(fm: &CheckFrontmatter) -> anyhow::Result<()> {
             anyhow::bail!("exact_final_answer must not be empty when set");
         }
     }
+    validate_pass_criteria(&fm.pass_criteria, fm.schema_version)?;
+    validate_check_files(fm)?;
     if let Some(b) = &fm.budget {
         if b.max_steps == Some(0) {
             anyhow::bail!("budget.max_steps must be > 0 when set");
@@ -14,6 +14,9 @@ use crate::hooks::config::HooksMode;
 
 use crate::planner;
 
+use crate::providers::recording::ReplayMatching;
+use crate::providers::retry::RetryOn;
+
 use crate::repro::{ReproEnvMode, ReproMode};
 
 use crate::session::CapsMode;
@@ -71,6 +74,12 @@ pub(crate) enum Commands {
 
     Replay(ReplayArgs),
 
+    Runs(RunsArgs),
+
+    Rollback(RollbackArgs),
+
+    Fixtures(FixturesArgs),
+
     Session(SessionArgs),
 
     Eval(Box<EvalCmd>),
@@ -418,6 +427,43 @@ pub(crate) enum CheckSubcommand {
 
         #[arg(long)]
         max_checks: Option<usize>,
+
+        /// Warn instead of failing the check when the server serves a different model.
+        #[arg(long, default_value_t = false)]
+        allow_model_mismatch: bool,
+
+        /// Maximum records kept in `.localagent/checks/history.jsonl`.
+        #[arg(long, default_value_t = crate::checks::history::DEFAULT_CHECK_HISTORY_RETENTION)]
+        history_retention: usize,
+
+        /// Run every check once per cell, e.g. `provider=ollama,model=llama3.1`. Repeatable.
+        #[arg(long = "matrix")]
+        matrix: Vec<String>,
+
+        /// YAML file with `cells: [{provider, model, base_url?}]`, appended to `--matrix`.
+        #[arg(long)]
+        matrix_file: Option<PathBuf>,
+
+        /// Number of matrix cells run concurrently.
+        #[arg(long, default_value_t = 1)]
+        matrix_parallel: usize,
+
+        /// Number of checks run concurrently, each in its own scratch workspace when > 1.
+        #[arg(long, default_value_t = 1)]
+        jobs: usize,
+    },
+    /// Report per-check pass rates over recent history and flag flaky checks.
+    Flaky {
+        #[arg(long, default_value_t = 20)]
+        window: usize,
+
+        /// Checks with a pass rate strictly above this are candidates for flaky.
+        #[arg(long, default_value_t = 0.0)]
+        min_pass_rate: f64,
+
+        /// Checks with a pass rate strictly below this are candidates for flaky.
+        #[arg(long, default_value_t = 1.0)]
+        max_pass_rate: f64,
     },
 }
 
@@ -436,6 +482,14 @@ pub(crate) enum RepoSubcommand {
         #[arg(long, default_value_t = false)]
         no_write: bool,
 
+        /// Ignore `.localagent/cache/repomap.index.json` and re-read every file.
+        #[arg(long, default_value_t = false)]
+        refresh: bool,
+
+        /// Order entries by relevance to this text before applying `--max-out-bytes`.
+        #[arg(long)]
+        prompt: Option<String>,
+
         #[arg(long, default_value_t = 2000)]
         max_files: usize,
 
@@ -520,11 +574,15 @@ pub(crate) enum LearnSubcommand {
         #[arg(long, default_value_t = false)]
         write: bool,
 
-        #[arg(long, value_enum)]
-        category: LearnCategoryArg,
+        /// Draft the capture from a failed run record; explicit flags override the draft.
+        #[arg(long = "from-run", conflicts_with = "run")]
+        from_run: Option<String>,
 
-        #[arg(long)]
-        summary: String,
+        #[arg(long, value_enum, required_unless_present = "from_run")]
+        category: Option<LearnCategoryArg>,
+
+        #[arg(long, required_unless_present = "from_run")]
+        summary: Option<String>,
 
         #[arg(long = "task-summary")]
         task_summary: Option<String>,
@@ -560,6 +618,33 @@ pub(crate) enum LearnSubcommand {
         #[arg(long, default_value_t = false)]
         show_archived: bool,
 
+        /// Only show entries whose promotion target was deleted or modified.
+        #[arg(long, default_value_t = false)]
+        stale: bool,
+
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Ranked case-insensitive search over summaries, proposed text, tags and evidence.
+    Search {
+        /// Whitespace-separated terms; every term must match.
+        #[arg(default_value = "")]
+        query: String,
+
+        /// Only entries carrying every given tag.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Only entries created at or after this RFC3339 timestamp.
+        #[arg(long)]
+        since: Option<String>,
+
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        #[arg(long, default_value_t = false)]
+        show_archived: bool,
+
         #[arg(long, default_value = "table")]
         format: String,
     },
@@ -578,6 +663,8 @@ pub(crate) enum LearnSubcommand {
     Archive {
         id: String,
     },
+    /// Verify promoted checks/packs still match what was promoted and mark stale entries.
+    Sync,
     Promote {
         id: String,
 
@@ -619,6 +706,21 @@ pub(crate) enum HooksSubcommand {
     List,
 
     Doctor,
+
+    /// Checks a hooks config without starting a run. Exit 0 when clean,
+    /// 1 with warnings only, 2 when invalid, 3 when a dry-fire fails.
+    Validate {
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Run the hooks of this stage once against a synthetic payload.
+        #[arg(long, value_enum)]
+        dry_fire: Option<crate::hooks::config::HookStage>,
+
+        /// Limit --dry-fire to the hook with this name.
+        #[arg(long, requires = "dry_fire")]
+        hook: Option<String>,
+    },
 }
 
 #[derive(Debug, Parser)]
@@ -669,6 +771,11 @@ pub(crate) enum ApprovalsSubcommand {
     List,
 
     Prune,
+
+    /// Remove a persisted workspace approval.
+    Revoke {
+        id: String,
+    },
 }
 
 #[derive(Debug, Parser)]
@@ -722,6 +829,124 @@ pub(crate) struct ReplayArgs {
     pub(crate) command: Option<ReplaySubcommand>,
 }
 
+#[derive(Debug, Subcommand)]
+
+pub(crate) enum RunsSubcommand {
+    List {
+        /// Only list runs carrying every given tag.
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Case-insensitive search over run notes and tags.
+    Search {
+        text: String,
+
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    Tag {
+        run_id: String,
+
+        #[arg(long = "add", num_args = 1.., required_unless_present = "note")]
+        add: Vec<String>,
+
+        /// Replaces the run's note. Secrets are redacted before storage.
+        #[arg(long)]
+        note: Option<String>,
+    },
+    Untag {
+        run_id: String,
+
+        #[arg(long = "remove", num_args = 1.., required_unless_present = "clear_note")]
+        remove: Vec<String>,
+
+        #[arg(long, default_value_t = false)]
+        clear_note: bool,
+    },
+    /// External processes, endpoints and images a run interacted with.
+    Interactions {
+        run_id: String,
+
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Pack a run's record, events, artifacts and compaction report into a
+    /// `.tar.gz` bundle. Secrets are redacted unless `--no-redact` is set.
+    Export {
+        run_id: String,
+
+        #[arg(long)]
+        out: PathBuf,
+
+        #[arg(long, default_value_t = false)]
+        no_redact: bool,
+    },
+    /// Unpack a bundle written by `runs export` into this state dir.
+    Import {
+        bundle: PathBuf,
+
+        /// Replace an existing run with the same id.
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Parser)]
+
+pub(crate) struct RunsArgs {
+    #[command(subcommand)]
+    pub(crate) command: RunsSubcommand,
+}
+
+/// Restore the files a `--checkpoint-writes` run wrote to their pre-run
+/// content.
+#[derive(Debug, Parser)]
+pub(crate) struct RollbackArgs {
+    pub(crate) run_id: String,
+
+    /// Also overwrite files changed after the run.
+    #[arg(long, default_value_t = false)]
+    pub(crate) force: bool,
+}
+
+#[derive(Debug, Subcommand)]
+
+pub(crate) enum FixturesSubcommand {
+    /// List the failure-mode fixtures and their expected outcomes.
+    List,
+    /// Replay fixtures against the scripted provider and print a summary table.
+    Run {
+        #[arg(required_unless_present = "all")]
+        name: Option<String>,
+
+        #[arg(long, default_value_t = false, conflicts_with = "name")]
+        all: bool,
+
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Parser)]
+
+pub(crate) struct FixturesArgs {
+    /// Fixture directory; defaults to the bundled tests/fixtures/failure_modes.
+    #[arg(long)]
+    pub(crate) dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub(crate) command: FixturesSubcommand,
+}
+
 #[derive(Debug, Clone, Parser)]
 
 pub(crate) struct EvalArgs {
@@ -776,6 +1001,11 @@ pub(crate) struct EvalArgs {
     #[arg(long, default_value_t = false)]
     pub(crate) fail_on_any: bool,
 
+    /// Record a ModelMismatch event instead of failing the task when the
+    /// server serves a different model than requested.
+    #[arg(long, default_value_t = false)]
+    pub(crate) allow_model_mismatch: bool,
+
     #[arg(long)]
     pub(crate) max_avg_steps: Option<f64>,
 
@@ -932,6 +1162,10 @@ pub(crate) struct EvalArgs {
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
     pub(crate) bundle_on_fail: bool,
 
+    /// Include operator run annotations (`runs tag`) in the bundle.
+    #[arg(long, default_value_t = false)]
+    pub(crate) bundle_annotations: bool,
+
     #[arg(long)]
     pub(crate) state_dir: Option<PathBuf>,
 
@@ -1007,9 +1241,31 @@ pub(crate) struct RunArgs {
     #[arg(long)]
     pub(crate) api_key: Option<String>,
 
-    #[arg(long)]
+    #[arg(long, conflicts_with = "template")]
     pub(crate) prompt: Option<String>,
 
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Build the prompt from a template: {{name}} takes a --var, {{file:PATH}} includes a workdir file through the read allowlist, {{env:NAME}} needs --allow-env-in-template"
+    )]
+    pub(crate) template: Option<PathBuf>,
+
+    #[arg(
+        long = "var",
+        value_name = "KEY=VALUE",
+        requires = "template",
+        help = "Template variable (repeatable)"
+    )]
+    pub(crate) template_vars: Vec<String>,
+
+    #[arg(
+        long,
+        requires = "template",
+        help = "Allow {{env:NAME}} placeholders in --template"
+    )]
+    pub(crate) allow_env_in_template: bool,
+
     #[arg(long)]
     pub(crate) temperature: Option<f32>,
 
@@ -1025,12 +1281,42 @@ pub(crate) struct RunArgs {
     #[arg(long, default_value_t = 20)]
     pub(crate) max_steps: usize,
 
+    #[arg(
+        long,
+        default_value_t = 2,
+        help = "Consecutive empty model responses (no content, no tool calls) before the run fails with MODEL_EMPTY_RESPONSE"
+    )]
+    pub(crate) max_empty_responses: u32,
+
+    /// JSON schema file the final answer must satisfy; invalid answers get
+    /// repair turns, then the run exits with output_schema_violation.
+    #[arg(long)]
+    pub(crate) final_output_schema: Option<PathBuf>,
+
+    /// Fail the run with MODEL_MISMATCH when the server reports serving a
+    /// different model than --model (always on for `check run` and `eval`
+    /// unless they pass --allow-model-mismatch).
+    #[arg(long, default_value_t = false)]
+    pub(crate) require_exact_model: bool,
+
     #[arg(long, default_value_t = 0)]
     pub(crate) max_wall_time_ms: u64,
 
+    /// Soft run deadline: once passed, remaining tool calls are skipped and
+    /// the model writes one final summary (exit reason deadline_exceeded).
+    #[arg(long, default_value_t = 0)]
+    pub(crate) deadline_ms: u64,
+
     #[arg(long, default_value_t = 0)]
     pub(crate) max_total_tool_calls: usize,
 
+    #[arg(
+        long = "max-tool-calls",
+        value_name = "TOOL=N",
+        help = "Cap calls to one tool by name, e.g. read_file=20 (repeatable)"
+    )]
+    pub(crate) max_tool_calls: Vec<String>,
+
     #[arg(long, default_value_t = 0)]
     pub(crate) max_mcp_calls: usize,
 
@@ -1049,9 +1335,44 @@ pub(crate) struct RunArgs {
     #[arg(long, default_value_t = 0)]
     pub(crate) max_browser_calls: usize,
 
+    /// Model calls `--compaction-mode summarize` may spend summarizing
+    /// evicted messages (0 = unlimited).
+    #[arg(long, default_value_t = 4)]
+    pub(crate) max_compaction_summaries: usize,
+
     #[arg(long, default_value_t = 30_000)]
     pub(crate) tool_exec_timeout_ms: u64,
 
+    /// Emit ToolExecProgress events with byte counts and a tail preview
+    /// while shell commands run.
+    #[arg(long, default_value_t = false)]
+    pub(crate) stream_tool_output: bool,
+
+    /// Run a step's tool calls concurrently when every call only reads
+    /// files; results stay in call order.
+    #[arg(long, default_value_t = false)]
+    pub(crate) parallel_readonly_tools: bool,
+
+    /// Expose the spawn_subtask tool, which runs a prompt as a bounded
+    /// child agent with a subset of this run's tools and budget.
+    #[arg(long, default_value_t = false)]
+    pub(crate) enable_subtasks: bool,
+
+    /// Diff lines shown in approval prompts for write_file/apply_patch
+    /// (0 disables the preview).
+    #[arg(long, default_value_t = crate::gate::DEFAULT_APPROVAL_DIFF_MAX_LINES)]
+    pub(crate) approval_diff_max_lines: usize,
+
+    /// Gate and run write tools but skip the filesystem write; results
+    /// carry the diff that would have been applied.
+    #[arg(long, default_value_t = false)]
+    pub(crate) dry_run_writes: bool,
+
+    /// Snapshot files before the run's write tools change them, so
+    /// `localagent rollback <run_id>` can restore them.
+    #[arg(long, default_value_t = false)]
+    pub(crate) checkpoint_writes: bool,
+
     #[arg(long, default_value_t = 5_000)]
     pub(crate) post_write_verify_timeout_ms: u64,
 
@@ -1061,15 +1382,87 @@ pub(crate) struct RunArgs {
     #[arg(long)]
     pub(crate) state_dir: Option<PathBuf>,
 
+    #[arg(
+        long = "wait-lock",
+        value_name = "SECS",
+        default_value_t = crate::store::DEFAULT_STATE_LOCK_WAIT_SECS,
+        help = "Seconds to wait for another LocalAgent process holding the state dir lock before failing"
+    )]
+    pub(crate) wait_lock: u64,
+
     #[arg(long = "mcp")]
     pub(crate) mcp: Vec<String>,
 
     #[arg(long = "pack")]
     pub(crate) packs: Vec<String>,
 
+    #[arg(
+        long = "context-pack",
+        value_name = "NAME",
+        help = "Preload the files listed in .localagent/packs/NAME.yaml as developer context at run start"
+    )]
+    pub(crate) context_pack: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Inject guidance from promoted learning entries as a non-authoritative developer message"
+    )]
+    pub(crate) use_learnings: bool,
+
+    #[arg(
+        long = "learning-tag",
+        value_name = "TAG",
+        requires = "use_learnings",
+        help = "Also inject captured (unpromoted) learning entries carrying TAG (repeatable)"
+    )]
+    pub(crate) learning_tags: Vec<String>,
+
     #[arg(long)]
     pub(crate) mcp_config: Option<PathBuf>,
 
+    #[arg(
+        long = "mcp-root",
+        value_name = "SERVER:SERVER_ROOT=HOST_ROOT",
+        help = "Declare that SERVER_ROOT on an MCP server is HOST_ROOT under the workdir, so read allowlist, policy, and taint globs apply to its path arguments (repeatable)"
+    )]
+    pub(crate) mcp_root: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Exclude MCP tools whose descriptions look like prompt injection instead of neutralizing the description"
+    )]
+    pub(crate) mcp_strict_metadata: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Fail startup when an MCP tool name shadows a builtin tool or another server's tool instead of skipping the tool"
+    )]
+    pub(crate) mcp_strict_names: bool,
+
+    #[arg(
+        long = "mcp-injection-phrase",
+        value_name = "PHRASE",
+        help = "Extra phrase (case-insensitive) that marks an MCP tool description as prompt injection; repeatable"
+    )]
+    pub(crate) mcp_injection_phrase: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = crate::mcp::health::DEFAULT_MCP_RECONNECT_ATTEMPTS,
+        help = "Reconnect attempts, with doubling backoff, for an MCP server whose connection broke; calls fail fast with E_NETWORK_TRANSIENT while it is degraded (0 = never reconnect)"
+    )]
+    pub(crate) mcp_reconnect_attempts: u32,
+
+    #[arg(
+        long,
+        default_value_t = crate::store::DEFAULT_MAX_RUN_ARTIFACT_BYTES,
+        help = "Cap on total bytes a run may spill to artifacts from large or binary MCP results (0 = unlimited)"
+    )]
+    pub(crate) max_run_artifact_bytes: u64,
+
     #[arg(long, default_value_t = false)]
     pub(crate) allow_shell: bool,
 
@@ -1080,9 +1473,43 @@ pub(crate) struct RunArgs {
     )]
     pub(crate) allow_shell_in_workdir: bool,
 
+    #[arg(
+        long = "allow-shell-cmd",
+        value_name = "PROG",
+        help = "Restrict the shell tool (enabled by --allow-shell or --allow-shell-in-workdir) to programs named PROG, matched on basename (repeatable)"
+    )]
+    pub(crate) allow_shell_cmd: Vec<String>,
+
     #[arg(long, default_value_t = false)]
     pub(crate) allow_write: bool,
 
+    #[arg(
+        long = "allow-read-path",
+        value_name = "GLOB",
+        help = "Restrict read tools and the repo map to workdir-relative paths matching GLOB (repeatable)"
+    )]
+    pub(crate) allow_read_path: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Run operator-declared environment probes (policy environment.probes) once at run start and inject the results as ENVIRONMENT FACTS"
+    )]
+    pub(crate) probe_environment: bool,
+
+    #[arg(
+        long = "replay-queue-from",
+        help = "Re-enqueue the operator queue messages recorded in this run id at the equivalent delivery boundaries; mismatches are reported as divergences"
+    )]
+    pub(crate) replay_queue_from: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Skip policy-required attribution comments on written files (only permitted when policy attribution.overridable is true)"
+    )]
+    pub(crate) no_attribution: bool,
+
     #[arg(long, default_value_t = false)]
     pub(crate) enable_write_tools: bool,
 
@@ -1104,6 +1531,46 @@ pub(crate) struct RunArgs {
     #[arg(long)]
     pub(crate) docker_user: Option<String>,
 
+    /// Start one container for the run and `docker exec` each tool call in it,
+    /// instead of a fresh `docker run` per call.
+    #[arg(long, default_value_t = false)]
+    pub(crate) docker_persistent: bool,
+
+    /// Memory cap for docker tool containers (`docker run --memory`, e.g. `512m`).
+    #[arg(long)]
+    pub(crate) docker_memory: Option<String>,
+
+    /// CPU cap for docker tool containers (`docker run --cpus`, e.g. `1.5`).
+    #[arg(long)]
+    pub(crate) docker_cpus: Option<String>,
+
+    /// Process cap for docker tool containers (`docker run --pids-limit`).
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) docker_pids_limit: Option<u32>,
+
+    /// Mount the workdir read-only; `write_file` and `apply_patch` are refused.
+    #[arg(long, default_value_t = false)]
+    pub(crate) docker_readonly_workdir: bool,
+
+    /// Remote host for `--exec-target ssh`.
+    #[arg(long)]
+    pub(crate) ssh_host: Option<String>,
+
+    /// Login user for `--exec-target ssh`; defaults to the ssh config's.
+    #[arg(long)]
+    pub(crate) ssh_user: Option<String>,
+
+    #[arg(long)]
+    pub(crate) ssh_port: Option<u16>,
+
+    /// Private key passed to `ssh -i`. Never written to events or run records.
+    #[arg(long)]
+    pub(crate) ssh_identity_file: Option<std::path::PathBuf>,
+
+    /// Absolute directory on the remote host that tool paths resolve against.
+    #[arg(long)]
+    pub(crate) ssh_workdir: Option<String>,
+
     #[arg(long, default_value_t = 200_000)]
     pub(crate) max_tool_output_bytes: usize,
 
@@ -1119,9 +1586,21 @@ pub(crate) struct RunArgs {
     #[arg(long, value_enum, default_value_t = AutoApproveScope::Run)]
     pub(crate) auto_approve_scope: AutoApproveScope,
 
+    /// Hours a grant persisted under `--auto-approve-scope workspace` stays
+    /// valid.
+    #[arg(long, default_value_t = crate::gate::DEFAULT_WORKSPACE_APPROVAL_TTL_HOURS)]
+    pub(crate) workspace_approval_ttl_hours: u32,
+
     #[arg(long, value_enum, default_value_t = ApprovalKeyVersion::V1)]
     pub(crate) approval_key: ApprovalKeyVersion,
 
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Omit the gate context snapshot from allow decisions that no gate or policy rule evaluated"
+    )]
+    pub(crate) skip_unevaluated_gate_snapshots: bool,
+
     #[arg(long = "unsafe", default_value_t = false)]
     pub(crate) unsafe_mode: bool,
 
@@ -1158,12 +1637,26 @@ pub(crate) struct RunArgs {
     #[arg(long, default_value_t = 0)]
     pub(crate) max_context_chars: usize,
 
+    /// Compact against an estimated token budget instead of
+    /// `--max-context-chars`.
+    #[arg(long)]
+    pub(crate) max_context_tokens: Option<usize>,
+
     #[arg(long, default_value_t = false)]
     pub(crate) use_repomap: bool,
 
     #[arg(long, default_value_t = 32 * 1024)]
     pub(crate) repomap_max_bytes: usize,
 
+    /// Rebuild the repo map index instead of reusing unchanged files.
+    #[arg(long, default_value_t = false, requires = "use_repomap")]
+    pub(crate) repomap_refresh: bool,
+
+    /// Order repo map entries by relevance to the prompt before applying
+    /// `--repomap-max-bytes`.
+    #[arg(long, default_value_t = false, requires = "use_repomap")]
+    pub(crate) repomap_rank: bool,
+
     #[arg(long, value_enum)]
     pub(crate) lsp_provider: Option<LspProviderKind>,
 
@@ -1197,6 +1690,18 @@ pub(crate) struct RunArgs {
     #[arg(long, default_value_t = 200_000)]
     pub(crate) hooks_max_stdout_bytes: usize,
 
+    /// Per-hook invocation cap for one run; 0 means unlimited
+    #[arg(long, default_value_t = 0)]
+    pub(crate) hooks_max_invocations: u32,
+
+    /// Per-hook cap on summed invocation time for one run; 0 means unlimited
+    #[arg(long, default_value_t = 0)]
+    pub(crate) hooks_max_cumulative_ms: u64,
+
+    /// Fail the run at the first exhausted hook budget instead of skipping
+    #[arg(long, default_value_t = false)]
+    pub(crate) hooks_budget_strict: bool,
+
     #[arg(long, value_enum, default_value_t = ToolArgsStrict::On)]
     pub(crate) tool_args_strict: ToolArgsStrict,
 
@@ -1252,9 +1757,41 @@ pub(crate) struct RunArgs {
     #[arg(long, value_enum, default_value_t = RunOutputMode::Human)]
     pub(crate) output: RunOutputMode,
 
+    #[arg(long, default_value_t = false)]
+    pub(crate) no_progress: bool,
+
     #[arg(long)]
     pub(crate) events: Option<PathBuf>,
 
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Export run, step, provider-call and tool spans to an OTLP/HTTP collector (requires the `otlp` build feature)"
+    )]
+    pub(crate) otlp_endpoint: Option<String>,
+
+    #[arg(
+        long = "otlp-header",
+        value_name = "KEY=VALUE",
+        value_parser = crate::otlp::parse_header_arg,
+        help = "Extra request header for --otlp-endpoint; repeatable"
+    )]
+    pub(crate) otlp_headers: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append run, step, provider-call and tool spans to a local JSONL file (openagent.span.v1)"
+    )]
+    pub(crate) spans_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        help = "Export finished spans at most this often during a run instead of only at run end"
+    )]
+    pub(crate) otlp_flush_interval_ms: Option<u64>,
+
     #[arg(long, default_value_t = 2)]
     pub(crate) http_max_retries: u32,
 
@@ -1273,6 +1810,59 @@ pub(crate) struct RunArgs {
     #[arg(long, default_value_t = 200_000)]
     pub(crate) http_max_line_bytes: usize,
 
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Minimum gap between the starts of consecutive provider requests in a run (0 = off)"
+    )]
+    pub(crate) provider_min_interval_ms: u64,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Maximum simultaneous provider requests per base URL in this process (0 = unlimited)"
+    )]
+    pub(crate) provider_concurrency: usize,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Attempts per model call under the provider retry policy, the first included (1 = off; replaces --http-max-retries when above 1)"
+    )]
+    pub(crate) provider_retry_max_attempts: u32,
+
+    #[arg(long, default_value_t = 500)]
+    pub(crate) provider_retry_base_delay_ms: u64,
+
+    #[arg(long, default_value_t = 30_000)]
+    pub(crate) provider_retry_max_delay_ms: u64,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = RetryOn::ALL,
+        help = "Failure causes the provider retry policy retries"
+    )]
+    pub(crate) provider_retry_on: Vec<RetryOn>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Record every provider request/response of the run to runs/<run_id>/provider_trace.jsonl"
+    )]
+    pub(crate) record_provider: bool,
+
+    #[arg(
+        long,
+        value_name = "RUN_ID",
+        help = "Serve provider responses from a recorded run instead of a model (same as --provider replay:<RUN_ID>)"
+    )]
+    pub(crate) replay_provider: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = ReplayMatching::Strict)]
+    pub(crate) replay_match: ReplayMatching,
+
     #[arg(long, default_value_t = false)]
     pub(crate) tui: bool,
 
@@ -1282,6 +1872,13 @@ pub(crate) struct RunArgs {
     #[arg(long, default_value_t = 200)]
     pub(crate) tui_max_log_lines: usize,
 
+    #[arg(
+        long,
+        default_value_t = crate::tui::state::DEFAULT_MAX_LINE_CHARS,
+        help = "Elide TUI output lines and approval previews past this many characters (0 = no limit)"
+    )]
+    pub(crate) tui_max_line_chars: usize,
+
     #[arg(long, value_enum, default_value_t = planner::RunMode::Single)]
     pub(crate) mode: planner::RunMode,
 
@@ -74,10 +74,59 @@ pub(crate) fn is_bare_startup_bootstrap_invocation(cli: &Cli) -> bool {
         && cli.run.provider.is_none()
         && cli.run.model.is_none()
         && cli.run.prompt.is_none()
+        && cli.run.template.is_none()
+}
+
+/// Subcommand recorded in state-dir lock files. Arguments are left out because
+/// they can carry secrets such as `--api-key`.
+fn state_lock_command_label(command: Option<&Commands>) -> String {
+    let Some(command) = command else {
+        return "localagent run".to_string();
+    };
+    let variant = format!("{command:?}");
+    let mut name = String::new();
+    for ch in variant.chars().take_while(|ch| ch.is_ascii_alphanumeric()) {
+        if ch.is_ascii_uppercase() && !name.is_empty() {
+            name.push('-');
+        }
+        name.push(ch.to_ascii_lowercase());
+    }
+    format!("localagent {name}")
+}
+
+/// Rewrites `--provider replay:<RUN_ID>` into `--replay-provider <RUN_ID>`;
+/// `ProviderKind` is a plain value enum and cannot carry the run id.
+pub(crate) fn expand_replay_provider_argv(
+    argv: Vec<std::ffi::OsString>,
+) -> Vec<std::ffi::OsString> {
+    let mut out = Vec::with_capacity(argv.len());
+    let mut iter = argv.into_iter().peekable();
+    while let Some(arg) = iter.next() {
+        let text = arg.to_string_lossy();
+        if text == "--provider" {
+            let replay_run = iter
+                .peek()
+                .and_then(|next| next.to_str())
+                .and_then(|next| next.strip_prefix("replay:"))
+                .map(str::to_string);
+            if let Some(run_id) = replay_run {
+                iter.next();
+                out.push("--replay-provider".into());
+                out.push(run_id.into());
+                continue;
+            }
+        } else if let Some(run_id) = text.strip_prefix("--provider=replay:") {
+            out.push("--replay-provider".into());
+            out.push(run_id.to_string().into());
+            continue;
+        }
+        out.push(arg);
+    }
+    out
 }
 
 pub(crate) async fn run_cli() -> anyhow::Result<()> {
-    let argv = std::env::args_os().collect::<Vec<_>>();
+    let argv = expand_replay_provider_argv(std::env::args_os().collect());
     let mut cli = Cli::parse_from(argv.clone());
     let run_presence = crate::reliability_profile::detect_run_args_presence_from_argv(&argv);
 
@@ -102,6 +151,11 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
     let ephemeral_state_dir = apply_run_command_defaults(&mut cli, &argv, &workdir);
     let _ephemeral_state_guard = ephemeral_state_dir.map(EphemeralStateDirGuard::new);
 
+    crate::store::configure_state_lock(
+        std::time::Duration::from_secs(cli.run.wait_lock),
+        &state_lock_command_label(cli.command.as_ref()),
+    );
+
     let paths = resolve_state_paths(
         &workdir,
         cli.run.state_dir.clone(),
@@ -124,6 +178,15 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
 
     startup_init::maybe_auto_init_state(&cli.command, cli.run.state_dir.clone(), &workdir, &paths)?;
 
+    if cli.run.template.is_some()
+        && !matches!(
+            cli.command,
+            None | Some(Commands::Run) | Some(Commands::Exec)
+        )
+    {
+        return Err(anyhow!("--template is only supported in run mode"));
+    }
+
     match &cli.command {
         Some(Commands::Run) | Some(Commands::Exec) => {}
 
@@ -285,6 +348,28 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
 
                     return Ok(());
                 }
+
+                HooksSubcommand::Validate {
+                    config,
+                    dry_fire,
+                    hook,
+                } => {
+                    let path = config.clone().unwrap_or(hooks_path);
+                    let exit = ops_helpers::handle_hooks_validate(
+                        &path,
+                        &workdir,
+                        *dry_fire,
+                        hook.as_deref(),
+                        &cli.run,
+                    )
+                    .await?;
+
+                    if exit != hooks::validate::HooksValidateExit::Ok {
+                        std::process::exit(exit as i32);
+                    }
+
+                    return Ok(());
+                }
             }
         }
 
@@ -356,7 +441,11 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
         },
 
         Some(Commands::Approvals(args)) => {
-            approvals_ops::handle_approvals_command(&paths.approvals_path, &args.command)?;
+            approvals_ops::handle_approvals_command(
+                &paths.approvals_path,
+                &crate::gate::workspace_approvals_path(&paths.state_dir),
+                &args.command,
+            )?;
 
             return Ok(());
         }
@@ -386,6 +475,21 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
             return Ok(());
         }
 
+        Some(Commands::Runs(args)) => {
+            crate::cli_dispatch_runs::handle_runs_command(args, &paths)?;
+            return Ok(());
+        }
+
+        Some(Commands::Rollback(args)) => {
+            crate::cli_dispatch_rollback::handle_rollback_command(args, &paths)?;
+            return Ok(());
+        }
+
+        Some(Commands::Fixtures(args)) => {
+            crate::cli_dispatch_misc_ops::handle_fixtures_command(args).await?;
+            return Ok(());
+        }
+
         Some(Commands::Session(args)) => {
             if cli.run.no_session {
                 return Err(anyhow!(
@@ -475,6 +579,10 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
 
     validate_run_output_mode(&cli.run)?;
 
+    if let Some(replay_run_id) = cli.run.replay_provider.clone() {
+        return run_replayed_provider(&cli.run, &replay_run_id, &paths).await;
+    }
+
     let provider_kind = cli
         .run
         .provider
@@ -486,11 +594,13 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
         .clone()
         .ok_or_else(|| anyhow!("--model is required in run mode"))?;
 
-    let prompt = cli
-        .run
-        .prompt
-        .clone()
-        .ok_or_else(|| anyhow!("--prompt is required in run mode"))?;
+    // With --template the prompt is rendered at launch, once the read
+    // allowlist and policy are known.
+    let prompt = match (cli.run.prompt.clone(), cli.run.template.is_some()) {
+        (Some(prompt), _) => prompt,
+        (None, true) => String::new(),
+        (None, false) => return Err(anyhow!("--prompt is required in run mode")),
+    };
 
     let base_url = cli
         .run
@@ -499,7 +609,7 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
         .unwrap_or_else(|| provider_runtime::default_base_url(provider_kind).to_string());
 
     match provider_kind {
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
             let provider = match OpenAiCompatProvider::new(
                 provider_kind,
                 base_url.clone(),
@@ -635,6 +745,52 @@ pub(crate) async fn run_cli() -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn run_replayed_provider(
+    run: &RunArgs,
+    replay_run_id: &str,
+    paths: &store::StatePaths,
+) -> anyhow::Result<()> {
+    let trace_path =
+        crate::providers::recording::provider_trace_path(&paths.state_dir, replay_run_id);
+    let provider =
+        match crate::providers::recording::ReplayProvider::load(&trace_path, run.replay_match) {
+            Ok(p) => p,
+            Err(e) => {
+                maybe_emit_pre_run_json_failure(run, &e.to_string());
+                return Err(e);
+            }
+        };
+    let provider_kind = provider.provider_kind();
+    let base_url = run
+        .base_url
+        .clone()
+        .unwrap_or_else(|| provider.base_url().to_string());
+    let model = run
+        .model
+        .clone()
+        .unwrap_or_else(|| provider.model().to_string());
+    let prompt = match (run.prompt.clone(), run.template.is_some()) {
+        (Some(prompt), _) => prompt,
+        (None, true) => String::new(),
+        (None, false) => return Err(anyhow!("--prompt is required in run mode")),
+    };
+    if let Err(e) = run_agent(
+        provider,
+        provider_kind,
+        &base_url,
+        &model,
+        &prompt,
+        run,
+        paths,
+    )
+    .await
+    {
+        maybe_emit_pre_run_json_failure(run, &e.to_string());
+        return Err(e);
+    }
+    Ok(())
+}
+
 pub(crate) fn validate_sampling_args(run: &RunArgs) -> anyhow::Result<()> {
     if let Some(top_p) = run.top_p {
         if !(top_p > 0.0 && top_p <= 1.0) {
@@ -4,6 +4,46 @@ use crate::provider_runtime;
 use crate::store;
 use crate::*;
 
+/// Inputs of one `check run`, shared by every cell and check it runs.
+#[derive(Clone, Copy)]
+pub(crate) struct CheckRunRequest<'a> {
+    pub(crate) path: Option<&'a std::path::Path>,
+    pub(crate) max_checks: Option<usize>,
+    pub(crate) history_retention: usize,
+    pub(crate) allow_model_mismatch: bool,
+    pub(crate) jobs: usize,
+    pub(crate) matrix: &'a checks::matrix::CheckMatrix,
+    pub(crate) cli_run: &'a RunArgs,
+    pub(crate) workdir: &'a std::path::Path,
+    pub(crate) paths: &'a store::StatePaths,
+}
+
+static NO_MATRIX: checks::matrix::CheckMatrix = checks::matrix::CheckMatrix {
+    cells: Vec::new(),
+    parallel: 0,
+};
+
+impl<'a> CheckRunRequest<'a> {
+    /// Every discovered check, run once with one job and no matrix.
+    pub(crate) fn new(
+        cli_run: &'a RunArgs,
+        workdir: &'a std::path::Path,
+        paths: &'a store::StatePaths,
+    ) -> Self {
+        Self {
+            path: None,
+            max_checks: None,
+            history_retention: checks::history::DEFAULT_CHECK_HISTORY_RETENTION,
+            allow_model_mismatch: false,
+            jobs: 1,
+            matrix: &NO_MATRIX,
+            cli_run,
+            workdir,
+            paths,
+        }
+    }
+}
+
 pub(crate) struct CheckRunCommandOutput {
     pub(crate) report: checks::report::CheckRunReport,
     pub(crate) exit: checks::runner::CheckRunExit,
@@ -21,24 +61,74 @@ pub(crate) async fn handle_check_command(
             json_out,
             junit_out,
             max_checks,
+            allow_model_mismatch,
+            history_retention,
+            matrix,
+            matrix_file,
+            matrix_parallel,
+            jobs,
         } => {
-            let out = run_check_command(path.clone(), *max_checks, cli_run, workdir, paths).await?;
+            let matrix = checks::matrix::resolve_check_matrix(
+                matrix,
+                matrix_file.as_deref(),
+                *matrix_parallel,
+            )?;
+            let out = run_check_command(CheckRunRequest {
+                path: path.as_deref(),
+                max_checks: *max_checks,
+                history_retention: *history_retention,
+                allow_model_mismatch: *allow_model_mismatch,
+                jobs: *jobs,
+                matrix: &matrix,
+                cli_run,
+                workdir,
+                paths,
+            })
+            .await?;
             write_check_run_outputs(&out, json_out.as_ref(), junit_out.as_ref())?;
             match out.exit {
                 checks::runner::CheckRunExit::Ok => Ok(()),
                 _ => std::process::exit(out.exit as i32),
             }
         }
+        CheckSubcommand::Flaky {
+            window,
+            min_pass_rate,
+            max_pass_rate,
+        } => {
+            let history = checks::history::load_check_history(
+                &checks::history::check_history_path(&paths.state_dir),
+            )?;
+            let quarantined = checks::quarantine::load_check_quarantine(
+                &checks::quarantine::check_quarantine_path(&paths.state_dir),
+            )?;
+            let report = checks::history::compute_flaky_report(
+                &history,
+                *window,
+                checks::history::FlakyBounds {
+                    min_pass_rate: *min_pass_rate,
+                    max_pass_rate: *max_pass_rate,
+                },
+                &quarantined,
+            );
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
     }
 }
 
 pub(crate) async fn run_check_command(
-    path: Option<PathBuf>,
-    max_checks: Option<usize>,
-    cli_run: &RunArgs,
-    workdir: &std::path::Path,
-    paths: &store::StatePaths,
+    req: CheckRunRequest<'_>,
 ) -> anyhow::Result<CheckRunCommandOutput> {
+    if !req.matrix.is_empty() {
+        return run_check_matrix(req).await;
+    }
+    let CheckRunRequest {
+        cli_run,
+        workdir,
+        paths,
+        ..
+    } = req;
     let provider_kind = match cli_run.provider {
         Some(p) => p,
         None => {
@@ -73,7 +163,10 @@ pub(crate) async fn run_check_command(
         .unwrap_or_else(|| provider_runtime::default_base_url(provider_kind).to_string());
     let checks = match checks::runner::load_checks_for_run(
         workdir,
-        &checks::runner::CheckRunArgs { path, max_checks },
+        &checks::runner::CheckRunArgs {
+            path: req.path.map(PathBuf::from),
+            max_checks: req.max_checks,
+        },
     ) {
         Ok(c) => c,
         Err(boxed) => {
@@ -82,162 +175,393 @@ pub(crate) async fn run_check_command(
             return Ok(CheckRunCommandOutput { report, exit });
         }
     };
+    let quarantined = match checks::quarantine::load_check_quarantine(
+        &checks::quarantine::check_quarantine_path(&paths.state_dir),
+    ) {
+        Ok(q) => q,
+        Err(e) => {
+            let mut report =
+                checks::runner::report_single_error("CHECK_QUARANTINE_INVALID", format!("{e:#}"));
+            apply_check_runner_report_meta(&mut report, cli_run, Some(provider_kind), Some(&model));
+            return Ok(CheckRunCommandOutput {
+                report,
+                exit: checks::runner::CheckRunExit::InvalidChecks,
+            });
+        }
+    };
 
-    let mut results = Vec::new();
-    for check in checks {
-        if let Some((status, summary)) = check_capability_denial(&check, cli_run) {
-            results.push(checks::report::CheckRunResult {
-                name: check.name,
-                path: check.path,
-                description: check.description,
-                status: status.to_string(),
-                reason_code: Some("CHECK_CAPABILITY_DENIED".to_string()),
-                summary,
-                required: check.required,
-                file_bytes_hash_hex: check.file_bytes_hash_hex,
-                frontmatter_hash_hex: check.frontmatter_hash_hex,
-                check_hash_hex: check.check_hash_hex,
+    let results = run_checks_for_cell(checks, provider_kind, &base_url, &model, req).await;
+    let mut report = checks::report::CheckRunReport::from_results(results);
+    apply_check_runner_report_meta(&mut report, cli_run, Some(provider_kind), Some(&model));
+    // History keeps the real outcome so quarantined checks keep being measured.
+    record_check_history(
+        paths,
+        &report.checks,
+        &report.runner_config_hash_hex,
+        req.history_retention,
+    );
+    Ok(finish_check_report(report, &quarantined))
+}
+
+/// Runs every check once per matrix cell. Cells run in order, or up to
+/// `matrix.parallel` at a time; results keep cell order either way.
+async fn run_check_matrix(req: CheckRunRequest<'_>) -> anyhow::Result<CheckRunCommandOutput> {
+    use futures_util::StreamExt;
+
+    let CheckRunRequest {
+        matrix,
+        cli_run,
+        workdir,
+        paths,
+        ..
+    } = req;
+
+    let checks = match checks::runner::load_checks_for_run(
+        workdir,
+        &checks::runner::CheckRunArgs {
+            path: req.path.map(PathBuf::from),
+            max_checks: req.max_checks,
+        },
+    ) {
+        Ok(c) => c,
+        Err(boxed) => {
+            let (mut report, exit) = *boxed;
+            apply_check_runner_report_meta(&mut report, cli_run, None, None);
+            return Ok(CheckRunCommandOutput { report, exit });
+        }
+    };
+    let quarantined = match checks::quarantine::load_check_quarantine(
+        &checks::quarantine::check_quarantine_path(&paths.state_dir),
+    ) {
+        Ok(q) => q,
+        Err(e) => {
+            let mut report =
+                checks::runner::report_single_error("CHECK_QUARANTINE_INVALID", format!("{e:#}"));
+            apply_check_runner_report_meta(&mut report, cli_run, None, None);
+            return Ok(CheckRunCommandOutput {
+                report,
+                exit: checks::runner::CheckRunExit::InvalidChecks,
             });
-            continue;
         }
+    };
 
-        let mut run_args = cli_run.clone();
-        run_args.no_session = true;
-        run_args.reset_session = false;
-        run_args.approval_mode = crate::gate::ApprovalMode::Fail;
-        run_args.validation_command_override = check.frontmatter.validation_command.clone();
-        run_args.exact_final_answer_override = check.frontmatter.exact_final_answer.clone();
-        if let Some(b) = &check.frontmatter.budget {
-            if let Some(ms) = b.max_steps {
-                run_args.max_steps = ms as usize;
-            }
-            if let Some(mt) = b.max_tool_calls {
-                run_args.max_total_tool_calls = mt as usize;
-            }
-            if let Some(t) = b.max_time_ms {
-                run_args.max_wall_time_ms = t;
+    let cell_runs = matrix
+        .cells
+        .iter()
+        .map(|cell| {
+            let mut cell_run = cli_run.clone();
+            cell_run.provider = Some(cell.provider);
+            cell_run.model = Some(cell.model.clone());
+            cell_run.base_url = cell.base_url.clone();
+            (cell, cell_run)
+        })
+        .collect::<Vec<_>>();
+    let cell_outputs = futures_util::stream::iter(cell_runs.iter().map(|(cell, cell_run)| {
+        let checks = checks.clone();
+        async move {
+            let base_url = cell
+                .base_url
+                .clone()
+                .unwrap_or_else(|| provider_runtime::default_base_url(cell.provider).to_string());
+            let cell_req = CheckRunRequest {
+                cli_run: cell_run,
+                ..req
+            };
+            let mut results =
+                run_checks_for_cell(checks, cell.provider, &base_url, &cell.model, cell_req).await;
+            let label = cell.label();
+            for result in &mut results {
+                result.matrix_cell = Some(label.clone());
             }
+            let mut cell_report = checks::report::CheckRunReport::from_results(Vec::new());
+            apply_check_runner_report_meta(
+                &mut cell_report,
+                cell_run,
+                Some(cell.provider),
+                Some(&cell.model),
+            );
+            (results, cell_report.runner_config_hash_hex)
         }
+    }))
+    .buffered(matrix.parallel.max(1))
+    .collect::<Vec<_>>()
+    .await;
 
-        let mut isolated_paths = None;
-        let mut _scratch_guard = None;
-        if check_requires_scratch_isolation(&check) {
-            match prepare_check_scratch_workspace(workdir) {
-                Ok((scratch_guard, scratch_workdir)) => {
-                    run_args.workdir = scratch_workdir.clone();
-                    isolated_paths = Some(resolve_state_paths(
-                        &scratch_workdir,
-                        None,
-                        None,
-                        None,
-                        None,
-                    ));
-                    _scratch_guard = Some(scratch_guard);
-                }
-                Err(e) => {
-                    results.push(checks::report::CheckRunResult {
-                        name: check.name,
-                        path: check.path,
-                        description: check.description,
-                        status: "error".to_string(),
-                        reason_code: Some("CHECK_RUNNER_INTERNAL_ERROR".to_string()),
-                        summary: format!("failed to prepare isolated scratch workspace: {e}"),
-                        required: check.required,
-                        file_bytes_hash_hex: check.file_bytes_hash_hex,
-                        frontmatter_hash_hex: check.frontmatter_hash_hex,
-                        check_hash_hex: check.check_hash_hex,
-                    });
-                    continue;
-                }
-            }
+    let mut results = Vec::new();
+    let mut cell_hashes = Vec::new();
+    for (cell_results, config_hash) in cell_outputs {
+        record_check_history(paths, &cell_results, &config_hash, req.history_retention);
+        results.extend(cell_results);
+        cell_hashes.push(config_hash);
+    }
+    let cfg = serde_json::json!({
+        "schema": "localagent.check_runner.matrix.v1",
+        "cells": cell_hashes,
+    });
+    let canonical = serde_json::to_string(&cfg).unwrap_or_else(|_| "{}".to_string());
+    let report = checks::report::CheckRunReport::from_results_with_runner_meta(
+        results,
+        "localagent_check_v1".to_string(),
+        crate::store::sha256_hex(canonical.as_bytes()),
+    );
+    Ok(finish_check_report(report, &quarantined))
+}
+
+/// Runs `checks` up to `jobs` at a time. With more than one job every check
+/// gets its own scratch workspace; results keep the input order either way.
+async fn run_checks_for_cell(
+    checks: Vec<checks::loader::LoadedCheck>,
+    provider_kind: ProviderKind,
+    base_url: &str,
+    model: &str,
+    req: CheckRunRequest<'_>,
+) -> Vec<checks::report::CheckRunResult> {
+    use futures_util::StreamExt;
+
+    let isolate = req.jobs > 1;
+    futures_util::stream::iter(
+        checks
+            .into_iter()
+            .map(|check| run_single_check(check, provider_kind, base_url, model, isolate, req)),
+    )
+    .buffered(req.jobs.max(1))
+    .collect()
+    .await
+}
+
+/// Runs one check against `req.cli_run`, which for a matrix cell already
+/// carries that cell's provider and model.
+async fn run_single_check(
+    check: checks::loader::LoadedCheck,
+    provider_kind: ProviderKind,
+    base_url: &str,
+    model: &str,
+    isolate: bool,
+    req: CheckRunRequest<'_>,
+) -> checks::report::CheckRunResult {
+    let CheckRunRequest {
+        cli_run,
+        workdir,
+        paths,
+        allow_model_mismatch,
+        ..
+    } = req;
+    if let Some((status, summary)) = check_capability_denial(&check, cli_run) {
+        return checks::report::CheckRunResult {
+            name: check.name,
+            path: check.path,
+            description: check.description,
+            status: status.to_string(),
+            reason_code: Some("CHECK_CAPABILITY_DENIED".to_string()),
+            summary,
+            required: check.required,
+            file_bytes_hash_hex: check.file_bytes_hash_hex,
+            frontmatter_hash_hex: check.frontmatter_hash_hex,
+            check_hash_hex: check.check_hash_hex,
+            matrix_cell: None,
+        };
+    }
+
+    let mut run_args = cli_run.clone();
+    run_args.no_session = true;
+    run_args.reset_session = false;
+    run_args.approval_mode = crate::gate::ApprovalMode::Fail;
+    run_args.require_exact_model = !allow_model_mismatch;
+    run_args.validation_command_override = check.frontmatter.validation_command.clone();
+    run_args.exact_final_answer_override = check.frontmatter.exact_final_answer.clone();
+    if let Some(b) = &check.frontmatter.budget {
+        if let Some(ms) = b.max_steps {
+            run_args.max_steps = ms as usize;
+        }
+        if let Some(mt) = b.max_tool_calls {
+            run_args.max_total_tool_calls = mt as usize;
         }
+        if let Some(t) = b.max_time_ms {
+            run_args.max_wall_time_ms = t;
+        }
+    }
 
-        let run_res = execute_check_agent_run(
-            provider_kind,
-            &base_url,
-            &model,
-            &check.body,
-            &run_args,
-            isolated_paths.as_ref().unwrap_or(paths),
-        )
-        .await;
-
-        match run_res {
-            Ok(res) => {
-                let outcome = res.outcome;
-                if let Some(msg) = check_allowed_tools_violation(&check, &outcome) {
-                    results.push(checks::report::CheckRunResult {
-                        name: check.name,
-                        path: check.path,
-                        description: check.description,
-                        status: "failed".to_string(),
-                        reason_code: Some("CHECK_ALLOWED_TOOLS_VIOLATION".to_string()),
-                        summary: msg,
-                        required: check.required,
-                        file_bytes_hash_hex: check.file_bytes_hash_hex,
-                        frontmatter_hash_hex: check.frontmatter_hash_hex,
-                        check_hash_hex: check.check_hash_hex,
-                    });
-                    continue;
-                }
-                match checks::runner::evaluate_final_output(&check, &outcome.final_output) {
-                    Ok(()) => results.push(checks::report::CheckRunResult {
-                        name: check.name,
-                        path: check.path,
-                        description: check.description,
-                        status: "passed".to_string(),
-                        reason_code: None,
-                        summary: format!(
-                            "exit_reason={} final_output_len={}",
-                            outcome.exit_reason.as_str(),
-                            outcome.final_output.len()
-                        ),
-                        required: check.required,
-                        file_bytes_hash_hex: check.file_bytes_hash_hex,
-                        frontmatter_hash_hex: check.frontmatter_hash_hex,
-                        check_hash_hex: check.check_hash_hex,
-                    }),
-                    Err(msg) => results.push(checks::report::CheckRunResult {
-                        name: check.name,
-                        path: check.path,
-                        description: check.description,
-                        status: "failed".to_string(),
-                        reason_code: Some("CHECK_PASS_CRITERIA_FAILED".to_string()),
-                        summary: msg,
-                        required: check.required,
-                        file_bytes_hash_hex: check.file_bytes_hash_hex,
-                        frontmatter_hash_hex: check.frontmatter_hash_hex,
-                        check_hash_hex: check.check_hash_hex,
-                    }),
-                }
+    let mut isolated_paths = None;
+    let mut _scratch_guard = None;
+    if isolate || check_requires_scratch_isolation(&check) {
+        let prepared = prepare_check_scratch_workspace(workdir).and_then(|(guard, dir)| {
+            checks::runner::materialize_check_fixtures(&dir, &check.frontmatter.fixtures)?;
+            Ok((guard, dir))
+        });
+        match prepared {
+            Ok((scratch_guard, scratch_workdir)) => {
+                run_args.workdir = scratch_workdir.clone();
+                isolated_paths = Some(resolve_state_paths(
+                    &scratch_workdir,
+                    None,
+                    None,
+                    None,
+                    None,
+                ));
+                _scratch_guard = Some(scratch_guard);
             }
             Err(e) => {
-                results.push(checks::report::CheckRunResult {
+                return checks::report::CheckRunResult {
                     name: check.name,
                     path: check.path,
                     description: check.description,
                     status: "error".to_string(),
                     reason_code: Some("CHECK_RUNNER_INTERNAL_ERROR".to_string()),
-                    summary: e.to_string(),
+                    summary: format!("failed to prepare isolated scratch workspace: {e}"),
                     required: check.required,
                     file_bytes_hash_hex: check.file_bytes_hash_hex,
                     frontmatter_hash_hex: check.frontmatter_hash_hex,
                     check_hash_hex: check.check_hash_hex,
-                });
+                    matrix_cell: None,
+                };
             }
         }
     }
 
-    let mut report = checks::report::CheckRunReport::from_results(results);
-    apply_check_runner_report_meta(&mut report, cli_run, Some(provider_kind), Some(&model));
-    let exit = if report.errors > 0 {
-        checks::runner::CheckRunExit::RunnerError
-    } else if report.failed > 0 {
-        checks::runner::CheckRunExit::FailedChecks
-    } else {
-        checks::runner::CheckRunExit::Ok
-    };
-    Ok(CheckRunCommandOutput { report, exit })
+    let run_res = execute_check_agent_run(
+        provider_kind,
+        base_url,
+        model,
+        &check.body,
+        &run_args,
+        isolated_paths.as_ref().unwrap_or(paths),
+    )
+    .await;
+
+    match run_res {
+        Ok(res) => {
+            let outcome = res.outcome;
+            if let Some(msg) = model_mismatch_failure(&outcome) {
+                return checks::report::CheckRunResult {
+                    name: check.name,
+                    path: check.path,
+                    description: check.description,
+                    status: "failed".to_string(),
+                    reason_code: Some("MODEL_MISMATCH".to_string()),
+                    summary: msg,
+                    required: check.required,
+                    file_bytes_hash_hex: check.file_bytes_hash_hex,
+                    frontmatter_hash_hex: check.frontmatter_hash_hex,
+                    check_hash_hex: check.check_hash_hex,
+                    matrix_cell: None,
+                };
+            }
+            if let Some(msg) = check_allowed_tools_violation(&check, &outcome) {
+                return checks::report::CheckRunResult {
+                    name: check.name,
+                    path: check.path,
+                    description: check.description,
+                    status: "failed".to_string(),
+                    reason_code: Some("CHECK_ALLOWED_TOOLS_VIOLATION".to_string()),
+                    summary: msg,
+                    required: check.required,
+                    file_bytes_hash_hex: check.file_bytes_hash_hex,
+                    frontmatter_hash_hex: check.frontmatter_hash_hex,
+                    check_hash_hex: check.check_hash_hex,
+                    matrix_cell: None,
+                };
+            }
+            if let Err(msg) = checks::runner::evaluate_expect_files(
+                &run_args.workdir,
+                &check.frontmatter.expect_files,
+            ) {
+                return checks::report::CheckRunResult {
+                    name: check.name,
+                    path: check.path,
+                    description: check.description,
+                    status: "failed".to_string(),
+                    reason_code: Some("CHECK_FILE_EXPECTATION_FAILED".to_string()),
+                    summary: msg,
+                    required: check.required,
+                    file_bytes_hash_hex: check.file_bytes_hash_hex,
+                    frontmatter_hash_hex: check.frontmatter_hash_hex,
+                    check_hash_hex: check.check_hash_hex,
+                    matrix_cell: None,
+                };
+            }
+            match checks::runner::evaluate_final_output(&check, &outcome.final_output) {
+                Ok(()) => checks::report::CheckRunResult {
+                    name: check.name,
+                    path: check.path,
+                    description: check.description,
+                    status: "passed".to_string(),
+                    reason_code: None,
+                    summary: format!(
+                        "exit_reason={} final_output_len={}",
+                        outcome.exit_reason.as_str(),
+                        outcome.final_output.len()
+                    ),
+                    required: check.required,
+                    file_bytes_hash_hex: check.file_bytes_hash_hex,
+                    frontmatter_hash_hex: check.frontmatter_hash_hex,
+                    check_hash_hex: check.check_hash_hex,
+                    matrix_cell: None,
+                },
+                Err(msg) => checks::report::CheckRunResult {
+                    name: check.name,
+                    path: check.path,
+                    description: check.description,
+                    status: "failed".to_string(),
+                    reason_code: Some("CHECK_PASS_CRITERIA_FAILED".to_string()),
+                    summary: msg,
+                    required: check.required,
+                    file_bytes_hash_hex: check.file_bytes_hash_hex,
+                    frontmatter_hash_hex: check.frontmatter_hash_hex,
+                    check_hash_hex: check.check_hash_hex,
+                    matrix_cell: None,
+                },
+            }
+        }
+        Err(e) => checks::report::CheckRunResult {
+            name: check.name,
+            path: check.path,
+            description: check.description,
+            status: "error".to_string(),
+            reason_code: Some("CHECK_RUNNER_INTERNAL_ERROR".to_string()),
+            summary: e.to_string(),
+            required: check.required,
+            file_bytes_hash_hex: check.file_bytes_hash_hex,
+            frontmatter_hash_hex: check.frontmatter_hash_hex,
+            check_hash_hex: check.check_hash_hex,
+            matrix_cell: None,
+        },
+    }
+}
+
+fn record_check_history(
+    paths: &store::StatePaths,
+    results: &[checks::report::CheckRunResult],
+    runner_config_hash_hex: &str,
+    history_retention: usize,
+) {
+    let recorded = crate::store::acquire_state_lock(&paths.state_dir).and_then(|_lock| {
+        checks::history::append_check_history(
+            &checks::history::check_history_path(&paths.state_dir),
+            results,
+            runner_config_hash_hex,
+            &crate::trust::now_rfc3339(),
+            history_retention,
+        )
+    });
+    if let Err(e) = recorded {
+        eprintln!("WARN: failed to record check history: {e}");
+    }
+}
+
+fn finish_check_report(
+    mut report: checks::report::CheckRunReport,
+    quarantined: &[String],
+) -> CheckRunCommandOutput {
+    if !quarantined.is_empty() {
+        checks::quarantine::apply_check_quarantine(&mut report.checks, quarantined);
+        report = checks::report::CheckRunReport::from_results_with_runner_meta(
+            report.checks,
+            report.runner_profile,
+            report.runner_config_hash_hex,
+        );
+    }
+    let exit = checks::runner::exit_for_report(&report);
+    CheckRunCommandOutput { report, exit }
 }
 
 pub(crate) fn write_check_run_outputs(
@@ -261,6 +585,14 @@ pub(crate) fn render_check_run_output(out: &CheckRunCommandOutput) -> anyhow::Re
     Ok(serde_json::to_string_pretty(&out.report)?)
 }
 
+fn model_mismatch_failure(outcome: &crate::agent::AgentOutcome) -> Option<String> {
+    outcome
+        .error
+        .as_deref()
+        .filter(|e| e.starts_with("MODEL_MISMATCH"))
+        .map(str::to_string)
+}
+
 fn check_capability_denial(
     check: &checks::loader::LoadedCheck,
     run: &RunArgs,
@@ -381,11 +713,13 @@ fn check_allowed_tools_violation(
 }
 
 fn check_requires_scratch_isolation(check: &checks::loader::LoadedCheck) -> bool {
-    check
-        .frontmatter
-        .required_flags
-        .iter()
-        .any(|f| f == "shell" || f == "write")
+    !check.frontmatter.fixtures.is_empty()
+        || !check.frontmatter.expect_files.is_empty()
+        || check
+            .frontmatter
+            .required_flags
+            .iter()
+            .any(|f| f == "shell" || f == "write")
 }
 
 struct CheckScratchWorkspace {
@@ -455,78 +789,35 @@ async fn execute_check_agent_run(
     run_args: &RunArgs,
     paths: &store::StatePaths,
 ) -> anyhow::Result<RunExecutionResult> {
+    let request = AgentUiRunRequest::new(provider_kind, base_url, model, prompt, run_args, paths)
+        .suppress_stdout_stream(true);
     match provider_kind {
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
             let provider = OpenAiCompatProvider::new(
                 provider_kind,
                 base_url.to_string(),
                 run_args.api_key.clone(),
                 provider_runtime::http_config_from_run_args(run_args),
             )?;
-            run_agent_with_ui(
-                provider,
-                provider_kind,
-                base_url,
-                model,
-                prompt,
-                run_args,
-                paths,
-                None,
-                None,
-                None,
-                None,
-                None,
-                true,
-            )
-            .await
+            run_agent_with_ui(provider, request).await
         }
         ProviderKind::Ollama => {
             let provider = OllamaProvider::new(
                 base_url.to_string(),
                 provider_runtime::http_config_from_run_args(run_args),
             )?;
-            run_agent_with_ui(
-                provider,
-                provider_kind,
-                base_url,
-                model,
-                prompt,
-                run_args,
-                paths,
-                None,
-                None,
-                None,
-                None,
-                None,
-                true,
-            )
-            .await
+            run_agent_with_ui(provider, request).await
         }
         ProviderKind::Mock => {
             let provider = MockProvider::new();
-            run_agent_with_ui(
-                provider,
-                provider_kind,
-                base_url,
-                model,
-                prompt,
-                run_args,
-                paths,
-                None,
-                None,
-                None,
-                None,
-                None,
-                true,
-            )
-            .await
+            run_agent_with_ui(provider, request).await
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{check_allowed_tools_violation, copy_check_workspace_tree};
+    use super::{check_allowed_tools_violation, copy_check_workspace_tree, model_mismatch_failure};
     use crate::agent::{AgentExitReason, AgentOutcome, ToolDecisionRecord};
     use crate::checks::loader::LoadedCheck;
     use crate::checks::schema::{CheckFrontmatter, PassCriteria, PassCriteriaType};
@@ -556,8 +847,12 @@ mod tests {
                 pass_criteria: PassCriteria {
                     kind: PassCriteriaType::Equals,
                     value: "ok".to_string(),
+                    regex_match: None,
                 },
                 budget: None,
+                fixtures: Vec::new(),
+                expect_files: Vec::new(),
+                learning_id: None,
             },
         }
     }
@@ -575,6 +870,7 @@ mod tests {
             tool_decisions: Vec::new(),
             compaction_settings: CompactionSettings {
                 max_context_chars: 0,
+                max_context_tokens: None,
                 mode: CompactionMode::Off,
                 keep_last: 0,
                 tool_result_persist: ToolResultPersist::Digest,
@@ -584,8 +880,16 @@ mod tests {
             hook_invocations: Vec::new(),
             provider_retry_count: 0,
             provider_error_count: 0,
+            provider_retry_wait_ms: 0,
             token_usage: None,
             taint: None,
+            digest_refetch: None,
+            tool_result_artifacts: Vec::new(),
+            model_served: None,
+            subtask_run_ids: Vec::new(),
+            post_run_hook_error: None,
+            timeline: Vec::new(),
+            steps: Vec::new(),
         }
     }
 
@@ -605,6 +909,9 @@ mod tests {
             taint_enforced: false,
             escalated: false,
             escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: None,
+            gate_context: None,
         });
 
         let got = check_allowed_tools_violation(&check, &outcome).expect("violation");
@@ -626,6 +933,22 @@ mod tests {
         assert!(got.contains("tool 'write_file'"));
     }
 
+    #[test]
+    fn model_mismatch_error_becomes_check_failure() {
+        let mut outcome = sample_outcome();
+        assert!(model_mismatch_failure(&outcome).is_none());
+        outcome.exit_reason = AgentExitReason::ProviderError;
+        outcome.error = Some(
+            "MODEL_MISMATCH: requested model 'a' but the server reported serving 'b'".to_string(),
+        );
+        assert_eq!(
+            model_mismatch_failure(&outcome).as_deref(),
+            outcome.error.as_deref()
+        );
+        outcome.error = Some("connection refused".to_string());
+        assert!(model_mismatch_failure(&outcome).is_none());
+    }
+
     #[test]
     fn allowed_tools_none_disables_post_run_filtering() {
         let check = sample_loaded_check(None);
@@ -642,6 +965,9 @@ mod tests {
             taint_enforced: false,
             escalated: false,
             escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: None,
+            gate_context: None,
         });
 
         assert!(check_allowed_tools_violation(&check, &outcome).is_none());
@@ -671,4 +997,180 @@ mod tests {
         assert!(!dst.join("target").exists());
         assert!(!dst.join("node_modules").exists());
     }
+
+    #[tokio::test]
+    async fn matrix_runs_each_check_per_cell_and_writes_nested_junit() {
+        use clap::Parser;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let workdir = tmp.path();
+        let checks_dir = workdir.join(".localagent").join("checks");
+        std::fs::create_dir_all(&checks_dir).expect("checks dir");
+        std::fs::write(
+            checks_dir.join("ok.md"),
+            "---\nschema_version: 1\nname: ok\npass_criteria:\n  type: output_contains\n  value: mock\n---\nsay ok\n",
+        )
+        .expect("check");
+        let paths = crate::store::resolve_state_paths(workdir, None, None, None, None);
+        let mut run_args = crate::RunArgs::parse_from(["localagent"]);
+        run_args.workdir = workdir.to_path_buf();
+        let matrix = crate::checks::matrix::resolve_check_matrix(
+            &[
+                "provider=mock,model=a".to_string(),
+                "provider=mock,model=b".to_string(),
+            ],
+            None,
+            2,
+        )
+        .expect("matrix");
+
+        let out = super::run_check_command(super::CheckRunRequest {
+            matrix: &matrix,
+            ..super::CheckRunRequest::new(&run_args, workdir, &paths)
+        })
+        .await
+        .expect("check run");
+
+        assert_eq!(out.report.checks.len(), 2);
+        assert_eq!(out.report.passed, 2, "{:?}", out.report.checks);
+        assert_eq!(
+            out.report
+                .checks
+                .iter()
+                .map(|c| c.matrix_cell.as_deref().unwrap_or(""))
+                .collect::<Vec<_>>(),
+            vec!["mock:a", "mock:b"]
+        );
+        assert_eq!(
+            out.report
+                .matrix
+                .iter()
+                .map(|m| (m.cell.as_str(), m.passed, m.failed))
+                .collect::<Vec<_>>(),
+            vec![("mock:a", 1, 0), ("mock:b", 1, 0)]
+        );
+        assert_eq!(out.exit, crate::checks::runner::CheckRunExit::Ok);
+
+        let junit = workdir.join("junit.xml");
+        crate::checks::report::write_junit(&junit, &out.report).expect("junit");
+        let xml = std::fs::read_to_string(&junit).expect("read junit");
+        assert_eq!(xml.matches("<testsuite ").count(), 2);
+        assert!(xml.contains("<testsuite name=\"localagent-checks[mock:a]\" tests=\"1\""));
+        assert!(xml.contains("<testsuite name=\"localagent-checks[mock:b]\" tests=\"1\""));
+    }
+
+    #[tokio::test]
+    async fn fixtures_are_materialized_and_expect_files_gate_the_result() {
+        use clap::Parser;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let workdir = tmp.path();
+        let checks_dir = workdir.join(".localagent").join("checks");
+        std::fs::create_dir_all(&checks_dir).expect("checks dir");
+        let fixture = "fixtures:\n  - path: cfg/config.json\n    content: '{\"port\": 8080}'\n";
+        std::fs::write(
+            checks_dir.join("pass.md"),
+            format!("---\nschema_version: 1\nname: pass\npass_criteria:\n  type: output_contains\n  value: mock\n{fixture}expect_files:\n  - path: cfg/config.json\n    contains: '8080'\n---\nreport the port\n"),
+        )
+        .expect("pass check");
+        std::fs::write(
+            checks_dir.join("fail.md"),
+            format!("---\nschema_version: 1\nname: fail\npass_criteria:\n  type: output_contains\n  value: mock\n{fixture}expect_files:\n  - path: out/report.txt\n    exists: true\n---\nwrite a report\n"),
+        )
+        .expect("fail check");
+        let paths = crate::store::resolve_state_paths(workdir, None, None, None, None);
+        let mut run_args =
+            crate::RunArgs::parse_from(["localagent", "--provider", "mock", "--model", "m"]);
+        run_args.workdir = workdir.to_path_buf();
+
+        let out = super::run_check_command(super::CheckRunRequest::new(&run_args, workdir, &paths))
+            .await
+            .expect("check run");
+
+        let by_name = |name: &str| {
+            out.report
+                .checks
+                .iter()
+                .find(|c| c.name == name)
+                .expect("result")
+        };
+        assert_eq!(by_name("pass").status, "passed", "{:?}", out.report.checks);
+        let failed = by_name("fail");
+        assert_eq!(failed.status, "failed");
+        assert_eq!(
+            failed.reason_code.as_deref(),
+            Some("CHECK_FILE_EXPECTATION_FAILED")
+        );
+        assert!(
+            failed.summary.contains("out/report.txt"),
+            "{}",
+            failed.summary
+        );
+        assert!(
+            !workdir.join("cfg").exists(),
+            "fixtures stay in the scratch workspace"
+        );
+    }
+
+    #[tokio::test]
+    async fn jobs_keep_report_order_and_match_serial_output() {
+        use clap::Parser;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let workdir = tmp.path();
+        let checks_dir = workdir.join(".localagent").join("checks");
+        std::fs::create_dir_all(&checks_dir).expect("checks dir");
+        // Earlier checks are slower so completion order is the reverse of
+        // report order under --jobs.
+        for i in 0..6u64 {
+            std::fs::write(
+                checks_dir.join(format!("c{i}.md")),
+                format!(
+                    "---\nschema_version: 1\nname: c{i}\npass_criteria:\n  type: output_contains\n  value: mock\n---\n__mock_delay_ms__:{}\nanswer\n",
+                    (6 - i) * 60
+                ),
+            )
+            .expect("check");
+        }
+        std::fs::write(
+            checks_dir.join("denied.md"),
+            "---\nschema_version: 1\nname: denied\nrequired_flags: [shell]\npass_criteria:\n  type: output_contains\n  value: mock\n---\nrun a shell\n",
+        )
+        .expect("denied check");
+        let paths = crate::store::resolve_state_paths(workdir, None, None, None, None);
+        let mut run_args =
+            crate::RunArgs::parse_from(["localagent", "--provider", "mock", "--model", "m"]);
+        run_args.workdir = workdir.to_path_buf();
+
+        let mut reports = Vec::new();
+        let mut elapsed = Vec::new();
+        for jobs in [1, 4] {
+            let started = std::time::Instant::now();
+            let out = super::run_check_command(super::CheckRunRequest {
+                jobs,
+                ..super::CheckRunRequest::new(&run_args, workdir, &paths)
+            })
+            .await
+            .expect("check run");
+            elapsed.push(started.elapsed());
+            assert_eq!(
+                out.report
+                    .checks
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["c0", "c1", "c2", "c3", "c4", "c5", "denied"]
+            );
+            assert_eq!(out.report.passed, 6, "{:?}", out.report.checks);
+            assert_eq!(out.report.skipped, 1);
+            reports.push(super::render_check_run_output(&out).expect("render"));
+        }
+        assert_eq!(reports[0], reports[1]);
+        assert!(
+            elapsed[1] < elapsed[0],
+            "jobs=4 took {:?}, jobs=1 took {:?}",
+            elapsed[1],
+            elapsed[0]
+        );
+    }
 }
@@ -111,7 +111,7 @@ async fn resume_from_runtime_checkpoint(
         .unwrap_or_else(|| provider_runtime::default_base_url(provider_kind).to_string());
 
     match provider_kind {
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
             let provider = OpenAiCompatProvider::new(
                 provider_kind,
                 base_url.clone(),
@@ -261,6 +261,7 @@ fn seed_resume_session(
             messages,
             settings: crate::session::SessionSettings::default(),
             task_memory: Vec::new(),
+            environment_facts: None,
         },
         std::cmp::max(
             run_args.max_session_messages,
@@ -602,6 +603,7 @@ pub(crate) async fn handle_eval_command(
         worker_model: args.worker_model.clone(),
         min_pass_rate: args.min_pass_rate,
         fail_on_any: args.fail_on_any,
+        require_exact_model: !args.allow_model_mismatch,
         max_avg_steps: args.max_avg_steps,
         resolved_profile_name: args.profile.clone(),
         resolved_profile_path: loaded_profile
@@ -705,6 +707,7 @@ pub(crate) async fn handle_eval_command(
                 baseline_name: args.compare_baseline.clone(),
                 profile_name: args.profile.clone(),
                 profile_hash_hex: results.config.resolved_profile_hash_hex.clone(),
+                include_annotations: args.bundle_annotations,
             })?;
 
             println!("bundle written: {}", out.display());
@@ -768,6 +771,7 @@ mod tests {
                     },
                     tool_calls: Vec::new(),
                     usage: None,
+                    served_model: None,
                 });
             }
             Ok(GenerateResponse {
@@ -784,6 +788,7 @@ mod tests {
                     arguments: json!({"command":"Write-Output resumed"}),
                 }],
                 usage: None,
+                served_model: None,
             })
         }
     }
@@ -1067,6 +1072,7 @@ mod tests {
                         match ExecTargetKind::Host {
                             ExecTargetKind::Host => "host",
                             ExecTargetKind::Docker => "docker",
+                            ExecTargetKind::Ssh => "ssh",
                         }
                         .to_string(),
                     ),
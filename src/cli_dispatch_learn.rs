@@ -16,37 +16,17 @@ pub(crate) async fn handle_learn_command(
 ) -> anyhow::Result<()> {
     match &args.command {
         LearnSubcommand::Capture {
-            run,
             assist,
             write,
-            category,
-            summary,
-            task_summary,
-            profile,
-            guidance_text,
-            check_text,
-            tags,
-            evidence,
-            evidence_notes,
+            from_run,
+            ..
         } => {
-            validate_capture_assist_flags(*assist, *write)?;
-            let category = match category {
-                LearnCategoryArg::WorkflowHint => learning::LearningCategoryV1::WorkflowHint,
-                LearnCategoryArg::PromptGuidance => learning::LearningCategoryV1::PromptGuidance,
-                LearnCategoryArg::CheckCandidate => learning::LearningCategoryV1::CheckCandidate,
-            };
-            let input = learning::build_capture_input(
-                run.clone(),
-                category,
-                summary.clone(),
-                task_summary.clone(),
-                profile.clone(),
-                guidance_text.clone(),
-                check_text.clone(),
-                tags.clone(),
-                evidence.clone(),
-                evidence_notes.clone(),
-            );
+            validate_capture_assist_flags(*assist, *write, from_run.is_some())?;
+            let input = capture_input_from_args(&args.command, &paths.state_dir)?;
+            if from_run.is_some() && !*assist && !*write {
+                println!("{}", learning::render_capture_draft_preview(&input));
+                return Ok(());
+            }
             if *assist {
                 let assisted = generate_assisted_capture_preview(cli_run, &input).await?;
                 println!(
@@ -87,6 +67,7 @@ pub(crate) async fn handle_learn_command(
             categories,
             limit,
             show_archived,
+            stale,
             format,
         } => {
             let mut entries = learning::list_learning_entries(&paths.state_dir)
@@ -121,6 +102,9 @@ pub(crate) async fn handle_learn_command(
             } else if !show_archived {
                 entries.retain(|e| e.status != learning::LearningStatusV1::Archived);
             }
+            if *stale {
+                entries.retain(|e| e.promotion_stale);
+            }
             let limit = *limit;
             if entries.len() > limit {
                 entries.truncate(limit);
@@ -143,6 +127,46 @@ pub(crate) async fn handle_learn_command(
                 )),
             }
         }
+        LearnSubcommand::Search {
+            query,
+            tags,
+            since,
+            limit,
+            show_archived,
+            format,
+        } => {
+            let entries = learning::search_learning_entries(
+                &paths.state_dir,
+                query,
+                &learning::LearningSearchFilters {
+                    tags: tags.clone(),
+                    since: since.clone(),
+                    include_archived: *show_archived,
+                    limit: *limit,
+                },
+            )
+            .context("failed to search learning entries")?
+            .into_iter()
+            .map(|hit| hit.entry)
+            .collect::<Vec<_>>();
+            match format.as_str() {
+                "table" => {
+                    println!("{}", learning::render_learning_list_table(&entries));
+                    Ok(())
+                }
+                "json" => {
+                    println!(
+                        "{}",
+                        learning::render_learning_list_json_preview(&entries)
+                            .context("failed to render learn search JSON preview")?
+                    );
+                    Ok(())
+                }
+                other => Err(anyhow!(
+                    "unsupported learn search format '{other}' (expected table|json)"
+                )),
+            }
+        }
         LearnSubcommand::Show {
             id,
             format,
@@ -155,7 +179,12 @@ pub(crate) async fn handle_learn_command(
                 "text" => {
                     println!(
                         "{}",
-                        learning::render_learning_show_text(&entry, *show_evidence, *show_proposed)
+                        learning::render_learning_show_text(
+                            &entry,
+                            *show_evidence,
+                            *show_proposed,
+                            learning::promotion_target_status(&paths.state_dir, &entry)
+                        )
                     );
                     Ok(())
                 }
@@ -182,6 +211,12 @@ pub(crate) async fn handle_learn_command(
             println!("{}", learning::render_archive_confirmation(&out));
             Ok(())
         }
+        LearnSubcommand::Sync => {
+            let report = learning::sync_learning_promotions(&paths.state_dir)
+                .context("failed to sync learning promotions")?;
+            println!("{}", learning::render_learning_sync_report(&report));
+            Ok(())
+        }
         LearnSubcommand::Promote {
             id,
             to,
@@ -210,11 +245,13 @@ pub(crate) async fn handle_learn_command(
                 println!("{}", learning::render_promote_to_check_confirmation(&out));
                 if *check_run {
                     let check_out = crate::cli_dispatch_checks::run_check_command(
-                        Some(out.target_path.clone()),
-                        Some(1),
-                        cli_run,
-                        workdir,
-                        paths,
+                        crate::cli_dispatch_checks::CheckRunRequest {
+                            path: Some(&out.target_path),
+                            max_checks: Some(1),
+                            ..crate::cli_dispatch_checks::CheckRunRequest::new(
+                                cli_run, workdir, paths,
+                            )
+                        },
                     )
                     .await
                     .context("chained check run failed")?;
@@ -226,9 +263,9 @@ pub(crate) async fn handle_learn_command(
                 if *replay_verify {
                     run_chained_replay_verify(
                         paths,
-                        id,
-                        replay_verify_run_id.as_deref(),
-                        *replay_verify_strict,
+                        &ReplayVerifyChainRequest::new(id.clone())
+                            .run_id_override(replay_verify_run_id.clone())
+                            .strict(*replay_verify_strict),
                     )?;
                 }
                 Ok(())
@@ -249,9 +286,9 @@ pub(crate) async fn handle_learn_command(
                 if *replay_verify {
                     run_chained_replay_verify(
                         paths,
-                        id,
-                        replay_verify_run_id.as_deref(),
-                        *replay_verify_strict,
+                        &ReplayVerifyChainRequest::new(id.clone())
+                            .run_id_override(replay_verify_run_id.clone())
+                            .strict(*replay_verify_strict),
                     )?;
                 }
                 Ok(())
@@ -269,9 +306,9 @@ pub(crate) async fn handle_learn_command(
                 if *replay_verify {
                     run_chained_replay_verify(
                         paths,
-                        id,
-                        replay_verify_run_id.as_deref(),
-                        *replay_verify_strict,
+                        &ReplayVerifyChainRequest::new(id.clone())
+                            .run_id_override(replay_verify_run_id.clone())
+                            .strict(*replay_verify_strict),
                     )?;
                 }
                 Ok(())
@@ -280,16 +317,71 @@ pub(crate) async fn handle_learn_command(
     }
 }
 
-fn validate_capture_assist_flags(assist: bool, write: bool) -> anyhow::Result<()> {
-    if write && !assist {
+fn validate_capture_assist_flags(assist: bool, write: bool, from_run: bool) -> anyhow::Result<()> {
+    if write && !assist && !from_run {
         return Err(anyhow!(
-            "{}: --write requires --assist",
+            "{}: --write requires --assist or --from-run",
             learning::LEARN_ASSIST_WRITE_REQUIRES_ASSIST
         ));
     }
     Ok(())
 }
 
+/// Builds the capture input for `learn capture`. With `--from-run` the run
+/// record supplies a draft and explicit flags override its category, summary
+/// and source fields; explicit tags and evidence come before the drafted
+/// ones so `--evidence-note` still pairs with `--evidence`.
+pub(crate) fn capture_input_from_args(
+    command: &LearnSubcommand,
+    state_dir: &std::path::Path,
+) -> anyhow::Result<learning::CaptureLearningInput> {
+    let LearnSubcommand::Capture {
+        run,
+        from_run,
+        category,
+        summary,
+        task_summary,
+        profile,
+        guidance_text,
+        check_text,
+        tags,
+        evidence,
+        evidence_notes,
+        ..
+    } = command
+    else {
+        return Err(anyhow!("not a learn capture command"));
+    };
+    let mut parts = match from_run {
+        Some(run_id) => learning::draft_capture_from_run(state_dir, run_id)?,
+        None => learning::CaptureInputParts::default(),
+    };
+    if let Some(category) = category {
+        parts.category = match category {
+            LearnCategoryArg::WorkflowHint => learning::LearningCategoryV1::WorkflowHint,
+            LearnCategoryArg::PromptGuidance => learning::LearningCategoryV1::PromptGuidance,
+            LearnCategoryArg::CheckCandidate => learning::LearningCategoryV1::CheckCandidate,
+        };
+    }
+    if let Some(summary) = summary {
+        parts.summary = summary.clone();
+    }
+    if run.is_some() {
+        parts.run = run.clone();
+    }
+    let drafted_evidence = std::mem::take(&mut parts.evidence);
+    Ok(learning::build_capture_input(
+        parts
+            .task_summary(task_summary.clone())
+            .profile(profile.clone())
+            .guidance_text(guidance_text.clone())
+            .check_text(check_text.clone())
+            .tags(tags.clone())
+            .evidence(evidence.iter().cloned().chain(drafted_evidence).collect())
+            .evidence_notes(evidence_notes.clone()),
+    ))
+}
+
 struct AssistedPreviewBuild {
     preview: learning::AssistedCapturePreview,
     output_truncated: bool,
@@ -373,7 +465,9 @@ async fn call_assist_model(
     };
 
     let resp = match provider_kind {
-        crate::ProviderKind::Lmstudio | crate::ProviderKind::Llamacpp => {
+        crate::ProviderKind::Lmstudio
+        | crate::ProviderKind::Llamacpp
+        | crate::ProviderKind::OpenAi => {
             let provider = crate::OpenAiCompatProvider::new(
                 provider_kind,
                 base_url.to_string(),
@@ -397,12 +491,41 @@ async fn call_assist_model(
     Ok(resp.assistant.content.unwrap_or_default())
 }
 
+/// Inputs for the replay verify that `learn promote --replay-verify` chains
+/// after a successful promotion.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ReplayVerifyChainRequest {
+    pub(crate) learning_id: String,
+    pub(crate) run_id_override: Option<String>,
+    pub(crate) strict: bool,
+}
+
+impl ReplayVerifyChainRequest {
+    pub(crate) fn new(learning_id: impl Into<String>) -> Self {
+        Self {
+            learning_id: learning_id.into(),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn run_id_override(mut self, run_id_override: Option<String>) -> Self {
+        self.run_id_override = run_id_override;
+        self
+    }
+
+    pub(crate) fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
 fn run_chained_replay_verify(
     paths: &StatePaths,
-    learning_id: &str,
-    run_id_override: Option<&str>,
-    strict: bool,
+    request: &ReplayVerifyChainRequest,
 ) -> anyhow::Result<()> {
+    let learning_id = request.learning_id.as_str();
+    let run_id_override = request.run_id_override.as_deref();
+    let strict = request.strict;
     let source_run_id = if run_id_override.is_none() {
         let entry =
             learning::load_learning_entry(&paths.state_dir, learning_id).with_context(|| {
@@ -520,15 +643,38 @@ mod tests {
             .contains("no source run_id on learning entry L1; pass --replay-verify-run-id"));
     }
 
+    #[test]
+    fn replay_verify_chain_request_builder_sets_named_fields() {
+        let request = ReplayVerifyChainRequest::new("L1")
+            .run_id_override(Some("run_9".to_string()))
+            .strict(true);
+        assert_eq!(
+            request,
+            ReplayVerifyChainRequest {
+                learning_id: "L1".to_string(),
+                run_id_override: Some("run_9".to_string()),
+                strict: true,
+            }
+        );
+        assert_eq!(
+            ReplayVerifyChainRequest::new("L2"),
+            ReplayVerifyChainRequest {
+                learning_id: "L2".to_string(),
+                ..ReplayVerifyChainRequest::default()
+            }
+        );
+    }
+
     #[test]
     fn validate_capture_assist_flags_requires_assist_for_write() {
-        let err = validate_capture_assist_flags(false, true).expect_err("invalid");
+        let err = validate_capture_assist_flags(false, true, false).expect_err("invalid");
         assert!(err
             .to_string()
             .contains("LEARN_ASSIST_WRITE_REQUIRES_ASSIST"));
-        validate_capture_assist_flags(false, false).expect("plain capture");
-        validate_capture_assist_flags(true, false).expect("assist preview");
-        validate_capture_assist_flags(true, true).expect("assist write");
+        validate_capture_assist_flags(false, false, false).expect("plain capture");
+        validate_capture_assist_flags(true, false, false).expect("assist preview");
+        validate_capture_assist_flags(true, true, false).expect("assist write");
+        validate_capture_assist_flags(false, true, true).expect("from-run write");
     }
 
     fn parse_learn_cli(args: &[&str]) -> (LearnArgs, RunArgs) {
@@ -567,6 +713,58 @@ mod tests {
         out
     }
 
+    #[test]
+    fn capture_from_run_draft_is_overridden_by_explicit_flags() {
+        let tmp = tempdir().expect("tempdir");
+        let state_dir = tmp.path().join(".localagent");
+        let fixture = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/run_records/v2_envelope.json"
+        ))
+        .expect("fixture");
+        let mut record: serde_json::Value = serde_json::from_str(&fixture).expect("parse");
+        record["metadata"]["run_id"] = serde_json::json!("run_denied");
+        record["metadata"]["exit_reason"] = serde_json::json!("denied");
+        fs::create_dir_all(state_dir.join("runs")).expect("mkdir");
+        fs::write(
+            state_dir.join("runs/run_denied.json"),
+            serde_json::to_string(&record).expect("ser"),
+        )
+        .expect("write");
+
+        let (learn_args, _) = parse_learn_cli(&["learn", "capture", "--from-run", "run_denied"]);
+        let input = capture_input_from_args(&learn_args.command, &state_dir).expect("draft");
+        assert_eq!(input.summary, "Run exited with denied");
+        assert_eq!(input.category, learning::LearningCategoryV1::WorkflowHint);
+
+        let (learn_args, _) = parse_learn_cli(&[
+            "learn",
+            "capture",
+            "--from-run",
+            "run_denied",
+            "--category",
+            "check-candidate",
+            "--summary",
+            "Ask before shell",
+            "--evidence",
+            "artifact_path:notes.md",
+            "--evidence-note",
+            "operator notes",
+        ]);
+        let input = capture_input_from_args(&learn_args.command, &state_dir).expect("draft");
+        assert_eq!(input.summary, "Ask before shell");
+        assert_eq!(input.category, learning::LearningCategoryV1::CheckCandidate);
+        assert_eq!(input.run_id.as_deref(), Some("run_denied"));
+        assert_eq!(
+            input.evidence_specs,
+            vec![
+                "artifact_path:notes.md",
+                "run_id:run_denied",
+                "exit_reason:denied"
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn assist_preview_without_write_performs_zero_filesystem_writes() {
         let tmp = tempdir().expect("tempdir");
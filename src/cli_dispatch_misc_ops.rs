@@ -47,6 +47,8 @@ pub(crate) fn handle_repo_command(
         RepoSubcommand::Map {
             print_content,
             no_write,
+            refresh,
+            prompt,
             max_files,
             max_scan_bytes,
             max_out_bytes,
@@ -57,8 +59,14 @@ pub(crate) fn handle_repo_command(
                     max_files: *max_files,
                     max_scan_bytes: *max_scan_bytes,
                     max_out_bytes: *max_out_bytes,
+                    relevance_prompt: prompt.clone(),
                     ..repo_map::RepoMapLimits::default()
                 },
+                Some(&repo_map::RepoMapCacheOptions {
+                    index_path: repo_map::repo_map_index_path(&paths.state_dir),
+                    refresh: *refresh,
+                    write: !*no_write,
+                }),
             )?;
 
             let cache_path = if *no_write {
@@ -116,3 +124,55 @@ pub(crate) fn handle_pack_command(
     }
     Ok(())
 }
+
+pub(crate) async fn handle_fixtures_command(args: &FixturesArgs) -> anyhow::Result<()> {
+    use crate::eval::failure_modes;
+
+    let dir = args
+        .dir
+        .clone()
+        .unwrap_or_else(failure_modes::default_fixture_dir);
+    let fixtures = failure_modes::load_fixture_dir(&dir)?;
+    match &args.command {
+        FixturesSubcommand::List => {
+            for f in &fixtures {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    f.name,
+                    f.expect.exit_reason,
+                    f.expect.error_code.as_deref().unwrap_or("-"),
+                    f.description
+                );
+            }
+        }
+        FixturesSubcommand::Run { name, all, json } => {
+            let selected = fixtures
+                .iter()
+                .filter(|f| *all || name.as_deref() == Some(f.name.as_str()))
+                .collect::<Vec<_>>();
+            if selected.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "no fixture named '{}' in {}",
+                    name.as_deref().unwrap_or_default(),
+                    dir.display()
+                ));
+            }
+            let mut reports = Vec::with_capacity(selected.len());
+            for fixture in selected {
+                reports.push(failure_modes::run_fixture(fixture).await?);
+            }
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&reports)?);
+            } else {
+                print!("{}", failure_modes::render_summary_table(&reports));
+            }
+            let failed = reports.iter().filter(|r| !r.passed()).count();
+            if failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{failed} fixture(s) did not match their expectations"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
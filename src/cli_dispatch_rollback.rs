@@ -0,0 +1,77 @@
+use anyhow::anyhow;
+
+use crate::cli_args::RollbackArgs;
+use crate::store::{self, RollbackReport, StatePaths};
+
+pub(crate) fn handle_rollback_command(
+    args: &RollbackArgs,
+    paths: &StatePaths,
+) -> anyhow::Result<()> {
+    let report = store::rollback_write_checkpoint(&paths.state_dir, &args.run_id, args.force)?;
+    print!(
+        "{}",
+        render_rollback_report(&args.run_id, &report, args.force)
+    );
+    if !report.conflicts.is_empty() && !args.force {
+        return Err(anyhow!(
+            "rollback refused: {} file(s) changed after run {}; rerun with --force to overwrite them",
+            report.conflicts.len(),
+            args.run_id
+        ));
+    }
+    Ok(())
+}
+
+fn render_rollback_report(run_id: &str, report: &RollbackReport, force: bool) -> String {
+    let mut out = String::new();
+    let conflict_label = if force { "overwritten" } else { "changed" };
+    for path in &report.conflicts {
+        out.push_str(&format!("{conflict_label}\t{path}\n"));
+    }
+    if !report.conflicts.is_empty() && !force {
+        return out;
+    }
+    for path in &report.restored {
+        out.push_str(&format!("restored\t{path}\n"));
+    }
+    for path in &report.removed {
+        out.push_str(&format!("removed\t{path}\n"));
+    }
+    for path in &report.unchanged {
+        out.push_str(&format!("unchanged\t{path}\n"));
+    }
+    out.push_str(&format!(
+        "rolled back run {run_id}: {} restored, {} removed, {} unchanged\n",
+        report.restored.len(),
+        report.removed.len(),
+        report.unchanged.len()
+    ));
+    if let Some(head) = &report.git_head {
+        out.push_str(&format!(
+            "workdir was at git commit {head} when the run started writing\n"
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_rollback_report;
+    use crate::store::RollbackReport;
+
+    #[test]
+    fn refused_rollback_lists_only_the_conflicts() {
+        let report = RollbackReport {
+            restored: vec!["a.txt".to_string()],
+            conflicts: vec!["a.txt".to_string()],
+            ..RollbackReport::default()
+        };
+        assert_eq!(
+            render_rollback_report("run_a", &report, false),
+            "changed\ta.txt\n"
+        );
+        let forced = render_rollback_report("run_a", &report, true);
+        assert!(forced.starts_with("overwritten\ta.txt\nrestored\ta.txt\n"));
+        assert!(forced.contains("1 restored, 0 removed, 0 unchanged"));
+    }
+}
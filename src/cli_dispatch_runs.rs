@@ -0,0 +1,299 @@
+use anyhow::Context;
+
+use crate::cli_args::{RunsArgs, RunsSubcommand};
+use crate::store::{
+    self, ExternalInteractionsV1, InteractionUseV1, RunAnnotationUpdate, RunListQuery, RunSummary,
+    StatePaths,
+};
+
+const NOTE_PREVIEW_CHARS: usize = 60;
+
+pub(crate) fn handle_runs_command(args: &RunsArgs, paths: &StatePaths) -> anyhow::Result<()> {
+    match &args.command {
+        RunsSubcommand::List { tags, limit, json } => {
+            let runs = store::list_runs(
+                &paths.state_dir,
+                &RunListQuery {
+                    tags: tags.clone(),
+                    text: None,
+                    limit: Some(*limit),
+                },
+            )
+            .context("failed to list runs")?;
+            print_runs(&runs, *json)
+        }
+        RunsSubcommand::Search { text, limit, json } => {
+            let runs = store::list_runs(
+                &paths.state_dir,
+                &RunListQuery {
+                    tags: Vec::new(),
+                    text: Some(text.clone()),
+                    limit: Some(*limit),
+                },
+            )
+            .context("failed to search runs")?;
+            print_runs(&runs, *json)
+        }
+        RunsSubcommand::Tag { run_id, add, note } => {
+            let annotations = store::update_run_annotations(
+                &paths.state_dir,
+                run_id,
+                &RunAnnotationUpdate {
+                    add_tags: add.clone(),
+                    note: note.clone(),
+                    ..RunAnnotationUpdate::default()
+                },
+            )?;
+            println!("run {} tags: {}", run_id, tags_label(&annotations.tags));
+            Ok(())
+        }
+        RunsSubcommand::Untag {
+            run_id,
+            remove,
+            clear_note,
+        } => {
+            let annotations = store::update_run_annotations(
+                &paths.state_dir,
+                run_id,
+                &RunAnnotationUpdate {
+                    remove_tags: remove.clone(),
+                    clear_note: *clear_note,
+                    ..RunAnnotationUpdate::default()
+                },
+            )?;
+            println!("run {} tags: {}", run_id, tags_label(&annotations.tags));
+            Ok(())
+        }
+        RunsSubcommand::Interactions { run_id, json } => {
+            let record = store::load_run_record(&paths.state_dir, run_id)
+                .with_context(|| format!("failed to load run record for {run_id}"))?;
+            let interactions = record
+                .external_interactions
+                .clone()
+                .unwrap_or_else(|| store::derive_external_interactions(&record));
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&interactions)?);
+            } else {
+                print!("{}", render_interactions_table(&interactions));
+            }
+            Ok(())
+        }
+        RunsSubcommand::Export {
+            run_id,
+            out,
+            no_redact,
+        } => {
+            let manifest = store::export_run_bundle(
+                &paths.state_dir,
+                run_id,
+                out,
+                store::RunBundleExportOptions {
+                    redact: !*no_redact,
+                },
+            )
+            .with_context(|| format!("failed to export run {run_id}"))?;
+            println!(
+                "exported run {} to {} ({} files{})",
+                run_id,
+                out.display(),
+                manifest.files.len(),
+                if manifest.redacted { ", redacted" } else { "" }
+            );
+            if !manifest.withheld_artifacts.is_empty() {
+                println!(
+                    "withheld {} artifacts containing secrets: {}",
+                    manifest.withheld_artifacts.len(),
+                    manifest.withheld_artifacts.join(", ")
+                );
+            }
+            Ok(())
+        }
+        RunsSubcommand::Import { bundle, force } => {
+            let imported = store::import_run_bundle(&paths.state_dir, bundle, *force)
+                .with_context(|| format!("failed to import {}", bundle.display()))?;
+            println!(
+                "{} run {} from {}",
+                if imported.replaced {
+                    "replaced"
+                } else {
+                    "imported"
+                },
+                imported.manifest.run_id,
+                bundle.display()
+            );
+            Ok(())
+        }
+    }
+}
+
+fn tags_label(tags: &[String]) -> String {
+    if tags.is_empty() {
+        "-".to_string()
+    } else {
+        tags.join(",")
+    }
+}
+
+fn note_preview(note: &str) -> String {
+    let flat = note.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= NOTE_PREVIEW_CHARS {
+        return flat;
+    }
+    let mut out = flat.chars().take(NOTE_PREVIEW_CHARS).collect::<String>();
+    out.push_str("...");
+    out
+}
+
+fn print_runs(runs: &[RunSummary], json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(runs)?);
+    } else {
+        print!("{}", render_runs_table(runs));
+    }
+    Ok(())
+}
+
+fn render_runs_table(runs: &[RunSummary]) -> String {
+    if runs.is_empty() {
+        return "no runs found\n".to_string();
+    }
+    let mut out = String::from("RUN_ID\tSTARTED\tEXIT\tMODEL\tTAGS\tNOTE\n");
+    for run in runs {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            run.run_id,
+            run.started_at,
+            run.exit_reason,
+            run.model,
+            tags_label(&run.tags),
+            run.note
+                .as_deref()
+                .map(note_preview)
+                .unwrap_or_else(|| "-".to_string())
+        ));
+    }
+    out
+}
+
+fn exec_seq_label(seq: Option<u64>) -> String {
+    seq.map(|s| s.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn usage_columns(usage: &InteractionUseV1) -> String {
+    format!(
+        "{}\t{}\t{}",
+        usage.count,
+        exec_seq_label(usage.first_exec_seq),
+        exec_seq_label(usage.last_exec_seq)
+    )
+}
+
+fn render_interactions_table(interactions: &ExternalInteractionsV1) -> String {
+    let mut out = String::from("PROCESSES\nKIND\tPROGRAM\tARGV_HASH\tCOUNT\tFIRST\tLAST\n");
+    if interactions.processes.is_empty() {
+        out.push_str("(none)\n");
+    }
+    for p in &interactions.processes {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            p.kind,
+            p.program,
+            p.argv_hash.chars().take(12).collect::<String>(),
+            usage_columns(&p.usage)
+        ));
+    }
+    out.push_str("\nENDPOINTS\nKIND\tTARGET\tCOUNT\tFIRST\tLAST\n");
+    if interactions.endpoints.is_empty() {
+        out.push_str("(none)\n");
+    }
+    for e in &interactions.endpoints {
+        out.push_str(&format!(
+            "{}\t{}\t{}\n",
+            e.kind,
+            e.target,
+            usage_columns(&e.usage)
+        ));
+    }
+    out.push_str("\nIMAGES\nIMAGE\tCOUNT\tFIRST\tLAST\n");
+    if interactions.images.is_empty() {
+        out.push_str("(none)\n");
+    }
+    for i in &interactions.images {
+        out.push_str(&format!("{}\t{}\n", i.image, usage_columns(&i.usage)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_table_shows_tags_and_flattened_note_preview() {
+        let runs = vec![
+            RunSummary {
+                run_id: "r2".to_string(),
+                started_at: "2026-01-02T00:00:00Z".to_string(),
+                exit_reason: "ok".to_string(),
+                model: "m".to_string(),
+                tags: vec!["patch-bug".to_string(), "repro".to_string()],
+                note: Some(format!("good\nrepro {}", "x".repeat(80))),
+            },
+            RunSummary {
+                run_id: "r1".to_string(),
+                started_at: "2026-01-01T00:00:00Z".to_string(),
+                exit_reason: "provider_error".to_string(),
+                model: "m".to_string(),
+                tags: Vec::new(),
+                note: None,
+            },
+        ];
+        let table = render_runs_table(&runs);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "RUN_ID\tSTARTED\tEXIT\tMODEL\tTAGS\tNOTE");
+        assert!(
+            lines[1].starts_with("r2\t2026-01-02T00:00:00Z\tok\tm\tpatch-bug,repro\tgood repro x")
+        );
+        assert!(lines[1].ends_with("..."));
+        assert_eq!(
+            lines[2],
+            "r1\t2026-01-01T00:00:00Z\tprovider_error\tm\t-\t-"
+        );
+        assert_eq!(render_runs_table(&[]), "no runs found\n");
+    }
+
+    #[test]
+    fn interactions_table_lists_sections_with_exec_seq_ranges() {
+        let interactions = ExternalInteractionsV1 {
+            processes: vec![store::ExternalProcessV1 {
+                kind: "shell".to_string(),
+                program: "cargo".to_string(),
+                argv_hash: "0123456789abcdef".to_string(),
+                source: None,
+                usage: InteractionUseV1 {
+                    count: 2,
+                    first_exec_seq: Some(1),
+                    last_exec_seq: Some(3),
+                },
+            }],
+            endpoints: vec![store::ExternalEndpointV1 {
+                kind: "provider".to_string(),
+                target: "ollama http://localhost:11434".to_string(),
+                usage: InteractionUseV1 {
+                    count: 4,
+                    first_exec_seq: None,
+                    last_exec_seq: None,
+                },
+            }],
+            images: Vec::new(),
+        };
+        let table = render_interactions_table(&interactions);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "PROCESSES");
+        assert_eq!(lines[2], "shell\tcargo\t0123456789ab\t2\t1\t3");
+        assert_eq!(lines[6], "provider\tollama http://localhost:11434\t4\t-\t-");
+        assert_eq!(lines[8], "IMAGES");
+        assert_eq!(lines[10], "(none)");
+    }
+}
@@ -1,13 +1,29 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::store::{sha256_hex, RunArtifactStore, ToolResultArtifactV1};
+use crate::types::{Message, Role, ToolCall};
 
-use crate::store::sha256_hex;
-use crate::types::{Message, Role};
+const DIGEST_HEADER: &str = "TOOL_OUTPUT_DIGEST v2";
+const DIGEST_HEAD_CHARS: usize = 200;
+const DIGEST_MAX_ARGS_CHARS: usize = 240;
+const DIGEST_MAX_ARTIFACT_REFS: usize = 4;
+const TOOL_RESULT_SCHEMA: &str = "openagent.tool_result.v1";
+const ARTIFACT_FILE_TRUNCATE_REASON: &str = "artifact_file";
+/// Smaller results stay inline in `ArtifactFile` mode; a stub would not be
+/// much shorter.
+const ARTIFACT_FILE_MIN_BYTES: usize = 1024;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum CompactionMode {
     Off,
     Summary,
+    /// Like `Summary`, but the agent asks the model to summarize the evicted
+    /// messages and falls back to the `Summary` text when that call fails.
+    Summarize,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
@@ -16,16 +32,100 @@ pub enum ToolResultPersist {
     All,
     Digest,
     None,
+    /// Large results move to `runs/<run_id>/artifacts/<tool_call_id>.json`
+    /// and the transcript keeps an envelope stub pointing at the file.
+    /// Without a run artifact store this behaves like `Digest`.
+    ArtifactFile,
+}
+
+impl ToolResultPersist {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Digest => "digest",
+            Self::None => "none",
+            Self::ArtifactFile => "artifact_file",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactionSettings {
     pub max_context_chars: usize,
+    /// Token budget for the transcript. When set it replaces
+    /// `max_context_chars` as the compaction trigger; tokens are estimated
+    /// with a [`TokenCounter`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<usize>,
     pub mode: CompactionMode,
     pub keep_last: usize,
     pub tool_result_persist: ToolResultPersist,
 }
 
+impl CompactionSettings {
+    pub fn has_budget(&self) -> bool {
+        self.max_context_chars > 0 || self.max_context_tokens.is_some_and(|t| t > 0)
+    }
+
+    pub fn enabled(&self) -> bool {
+        !matches!(self.mode, CompactionMode::Off) && self.has_budget()
+    }
+
+    /// Whether `messages` exceed the budget: the token budget when one is
+    /// configured, the char budget otherwise.
+    pub fn exceeds_budget(&self, messages: &[Message], counter: &dyn TokenCounter) -> bool {
+        match self.max_context_tokens.filter(|t| *t > 0) {
+            Some(max_tokens) => context_size_tokens(messages, counter) > max_tokens,
+            None => {
+                self.max_context_chars > 0 && context_size_chars(messages) > self.max_context_chars
+            }
+        }
+    }
+}
+
+/// Estimates how many tokens a piece of transcript text costs.
+pub trait TokenCounter: Send + Sync {
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Feeds back the prompt tokens a provider reported for a transcript of
+    /// `prompt_chars` characters, so estimates can follow the real tokenizer.
+    fn calibrate(&mut self, _prompt_chars: usize, _prompt_tokens: usize) {}
+}
+
+const DEFAULT_CHARS_PER_TOKEN: f64 = 4.0;
+
+/// About four characters per token until calibrated; afterwards the ratio
+/// of all reported prompt tokens to their transcript characters.
+#[derive(Debug, Clone, Default)]
+pub struct HeuristicTokenCounter {
+    observed_chars: usize,
+    observed_tokens: usize,
+}
+
+impl HeuristicTokenCounter {
+    pub fn chars_per_token(&self) -> f64 {
+        if self.observed_tokens == 0 || self.observed_chars == 0 {
+            DEFAULT_CHARS_PER_TOKEN
+        } else {
+            self.observed_chars as f64 / self.observed_tokens as f64
+        }
+    }
+}
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        (text.chars().count() as f64 / self.chars_per_token()).ceil() as usize
+    }
+
+    fn calibrate(&mut self, prompt_chars: usize, prompt_tokens: usize) {
+        if prompt_chars == 0 || prompt_tokens == 0 {
+            return;
+        }
+        self.observed_chars = self.observed_chars.saturating_add(prompt_chars);
+        self.observed_tokens = self.observed_tokens.saturating_add(prompt_tokens);
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactionReport {
     pub before_chars: usize,
@@ -33,8 +133,131 @@ pub struct CompactionReport {
     pub before_messages: usize,
     pub after_messages: usize,
     pub compacted_messages: usize,
+    /// Estimated token counts, from the run's [`TokenCounter`].
+    #[serde(default)]
+    pub before_tokens: usize,
+    #[serde(default)]
+    pub after_tokens: usize,
     pub summary_digest_sha256: String,
     pub summary_text: String,
+    /// Whether `summary_text` was written by the model (`Summarize` mode).
+    #[serde(default)]
+    pub summary_generated: bool,
+    /// Tool results replaced by a digest in this compaction. Results that were
+    /// already digested by an earlier compaction are not repeated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub digested_tool_results: Vec<DigestedToolResult>,
+    /// Tool results moved to artifact files in this compaction.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifact_files: Vec<ToolResultArtifactV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestedToolResult {
+    pub tool_call_id: String,
+    pub tool: String,
+    pub args_sha256: String,
+    pub content_sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<u32>,
+}
+
+/// The call that produced a tool result, keyed by tool call id. Digests use it
+/// to tell the model how to fetch evicted content again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolResultOrigin {
+    pub tool: String,
+    pub arguments: Value,
+    pub step: u32,
+}
+
+/// Refetch telemetry stored on the run record: how many digested results the
+/// model later asked for again with the same tool and arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestRefetchStatsV1 {
+    pub digested_results: u64,
+    pub refetched_results: u64,
+    pub refetch_calls: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestRefetch {
+    pub tool: String,
+    pub args_sha256: String,
+    pub digested_tool_call_id: String,
+    pub digested_step: Option<u32>,
+}
+
+/// Records the origin of every tool call in a run and correlates later calls
+/// with results compaction has digested, matching on tool name plus the hash
+/// of the canonicalized arguments.
+#[derive(Debug, Clone, Default)]
+pub struct DigestRefetchTracker {
+    origins: BTreeMap<String, ToolResultOrigin>,
+    digested: BTreeMap<(String, String), DigestedToolResult>,
+    refetched: BTreeSet<(String, String)>,
+    refetch_calls: u64,
+}
+
+impl DigestRefetchTracker {
+    /// Records `tc` as issued in `step`; returns the digested result it fetches
+    /// again, if any.
+    pub fn record_call(&mut self, tc: &ToolCall, step: u32) -> Option<DigestRefetch> {
+        self.origins.insert(
+            tc.id.clone(),
+            ToolResultOrigin {
+                tool: tc.name.clone(),
+                arguments: tc.arguments.clone(),
+                step,
+            },
+        );
+        let key = (tc.name.clone(), tool_args_sha256(&tc.name, &tc.arguments));
+        let digested = self.digested.get(&key)?;
+        self.refetch_calls = self.refetch_calls.saturating_add(1);
+        self.refetched.insert(key.clone());
+        Some(DigestRefetch {
+            tool: key.0,
+            args_sha256: key.1,
+            digested_tool_call_id: digested.tool_call_id.clone(),
+            digested_step: digested.step,
+        })
+    }
+
+    pub fn origins(&self) -> &BTreeMap<String, ToolResultOrigin> {
+        &self.origins
+    }
+
+    pub fn record_digests(&mut self, digested: &[DigestedToolResult]) {
+        for d in digested {
+            self.digested
+                .entry((d.tool.clone(), d.args_sha256.clone()))
+                .or_insert_with(|| d.clone());
+        }
+    }
+
+    /// `None` until compaction has digested at least one tool result.
+    pub fn stats(&self) -> Option<DigestRefetchStatsV1> {
+        if self.digested.is_empty() {
+            return None;
+        }
+        Some(DigestRefetchStatsV1 {
+            digested_results: self.digested.len() as u64,
+            refetched_results: self.refetched.len() as u64,
+            refetch_calls: self.refetch_calls,
+        })
+    }
+}
+
+/// Hash of a tool call's canonicalized arguments. Legacy builtin argument
+/// shapes are normalized first so `shell {command}` and `shell {cmd, args}`
+/// match.
+pub fn tool_args_sha256(tool: &str, arguments: &Value) -> String {
+    sha256_hex(canonical_tool_args(tool, arguments).as_bytes())
+}
+
+fn canonical_tool_args(tool: &str, arguments: &Value) -> String {
+    let normalized = crate::tools::normalize_builtin_tool_args(tool, arguments);
+    crate::trust::approvals::canonical_json(&normalized).unwrap_or_else(|_| normalized.to_string())
 }
 
 #[derive(Debug, Clone)]
@@ -47,10 +270,43 @@ pub fn context_size_chars(messages: &[Message]) -> usize {
     messages.iter().map(message_size_chars).sum()
 }
 
-pub fn maybe_compact(
+pub fn context_size_tokens(messages: &[Message], counter: &dyn TokenCounter) -> usize {
+    messages
+        .iter()
+        .flat_map(message_text_parts)
+        .map(|part| counter.count_tokens(part))
+        .sum()
+}
+
+/// Compacts `messages` in place when they exceed the budget. The agent calls
+/// this every step, so the common under-budget case costs a size scan and
+/// leaves the transcript untouched. `origins` holds the calls behind each tool
+/// result, so digests can name the step a result was produced in; results
+/// without an origin fall back to the tool calls still present in `messages`.
+/// `counter` is the run's token counter and `artifacts` the run's artifact
+/// store for `ArtifactFile` persistence.
+pub fn maybe_compact_in_place_with_origins(
+    messages: &mut Vec<Message>,
+    settings: &CompactionSettings,
+    origins: &BTreeMap<String, ToolResultOrigin>,
+    counter: &dyn TokenCounter,
+    artifacts: Option<&RunArtifactStore>,
+) -> anyhow::Result<Option<CompactionReport>> {
+    Ok(
+        compact(messages, settings, origins, counter, artifacts)?.and_then(|outcome| {
+            *messages = outcome.messages;
+            outcome.report
+        }),
+    )
+}
+
+fn compact(
     messages: &[Message],
     settings: &CompactionSettings,
-) -> anyhow::Result<CompactionOutcome> {
+    origins: &BTreeMap<String, ToolResultOrigin>,
+    counter: &dyn TokenCounter,
+    artifacts: Option<&RunArtifactStore>,
+) -> anyhow::Result<Option<CompactionOutcome>> {
     #[cfg(test)]
     if messages.iter().any(|m| {
         m.content
@@ -60,25 +316,22 @@ pub fn maybe_compact(
         return Err(anyhow::anyhow!("forced compaction error"));
     }
 
-    if settings.max_context_chars == 0 || matches!(settings.mode, CompactionMode::Off) {
-        return Ok(CompactionOutcome {
-            messages: messages.to_vec(),
-            report: None,
-        });
+    if !settings.enabled() || !settings.exceeds_budget(messages, counter) {
+        return Ok(None);
     }
 
     let before_chars = context_size_chars(messages);
-    if before_chars <= settings.max_context_chars {
-        return Ok(CompactionOutcome {
-            messages: messages.to_vec(),
-            report: None,
-        });
-    }
+    let before_tokens = context_size_tokens(messages, counter);
 
     let split_at = messages.len().saturating_sub(settings.keep_last);
     let compacted = &messages[..split_at];
     let mut tail = messages[split_at..].to_vec();
-    apply_tool_persistence(&mut tail, settings.tool_result_persist);
+    let persisted = apply_tool_persistence(
+        &mut tail,
+        settings.tool_result_persist,
+        &resolve_origins(messages, origins),
+        artifacts,
+    );
 
     let summary_text = build_summary(compacted);
     let summary_digest_sha256 = sha256_hex(summary_text.as_bytes());
@@ -94,9 +347,10 @@ pub fn maybe_compact(
     out_messages.push(summary_message);
     out_messages.extend(tail);
     let after_chars = context_size_chars(&out_messages);
+    let after_tokens = context_size_tokens(&out_messages, counter);
     let after_messages = out_messages.len();
 
-    Ok(CompactionOutcome {
+    Ok(Some(CompactionOutcome {
         messages: out_messages,
         report: Some(CompactionReport {
             before_chars,
@@ -104,25 +358,59 @@ pub fn maybe_compact(
             before_messages: messages.len(),
             after_messages,
             compacted_messages: compacted.len(),
+            before_tokens,
+            after_tokens,
             summary_digest_sha256,
             summary_text,
+            summary_generated: false,
+            digested_tool_results: persisted.digested,
+            artifact_files: persisted.artifact_files,
         }),
-    })
+    }))
 }
 
-fn message_size_chars(message: &Message) -> usize {
-    let mut size = 0usize;
-    size += role_name(message.role.clone()).chars().count();
-    if let Some(content) = &message.content {
-        size += content.chars().count();
-    }
-    if let Some(id) = &message.tool_call_id {
-        size += id.chars().count();
+/// Origins for every tool call id in the transcript: the agent-provided entry
+/// when there is one, otherwise the assistant tool call (no step known).
+fn resolve_origins(
+    messages: &[Message],
+    origins: &BTreeMap<String, ToolResultOrigin>,
+) -> BTreeMap<String, (String, Value, Option<u32>)> {
+    let mut out = BTreeMap::new();
+    for tc in messages
+        .iter()
+        .filter_map(|m| m.tool_calls.as_ref())
+        .flatten()
+    {
+        out.insert(tc.id.clone(), (tc.name.clone(), tc.arguments.clone(), None));
     }
-    if let Some(name) = &message.tool_name {
-        size += name.chars().count();
+    for (id, origin) in origins {
+        out.insert(
+            id.clone(),
+            (
+                origin.tool.clone(),
+                origin.arguments.clone(),
+                Some(origin.step),
+            ),
+        );
     }
-    size
+    out
+}
+
+fn message_size_chars(message: &Message) -> usize {
+    message_text_parts(message)
+        .map(|part| part.chars().count())
+        .sum()
+}
+
+fn message_text_parts(message: &Message) -> impl Iterator<Item = &str> {
+    [
+        Some(role_name(message.role.clone())),
+        message.content.as_deref(),
+        message.tool_call_id.as_deref(),
+        message.tool_name.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
 }
 
 fn role_name(role: Role) -> &'static str {
@@ -135,35 +423,230 @@ fn role_name(role: Role) -> &'static str {
     }
 }
 
-fn apply_tool_persistence(messages: &mut [Message], mode: ToolResultPersist) {
+#[derive(Default)]
+struct PersistedToolResults {
+    digested: Vec<DigestedToolResult>,
+    artifact_files: Vec<ToolResultArtifactV1>,
+}
+
+fn apply_tool_persistence(
+    messages: &mut [Message],
+    mode: ToolResultPersist,
+    origins: &BTreeMap<String, (String, Value, Option<u32>)>,
+    artifacts: Option<&RunArtifactStore>,
+) -> PersistedToolResults {
+    let mut persisted = PersistedToolResults::default();
     if matches!(mode, ToolResultPersist::All) {
-        return;
+        return persisted;
     }
     for message in messages {
         if !matches!(message.role, Role::Tool) {
             continue;
         }
         let original = message.content.clone().unwrap_or_default();
-        message.content = Some(match mode {
-            ToolResultPersist::All => original,
-            ToolResultPersist::Digest => digest_tool_output(&original),
-            ToolResultPersist::None => {
+        let id = message.tool_call_id.clone().unwrap_or_default();
+        let (tool, arguments, step) = origins.get(&id).cloned().unwrap_or_else(|| {
+            (
+                message
+                    .tool_name
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                Value::Object(Default::default()),
+                None,
+            )
+        });
+        let spilled = match (mode, artifacts) {
+            (ToolResultPersist::ArtifactFile, Some(store)) => {
+                if original.len() <= ARTIFACT_FILE_MIN_BYTES
+                    || id.is_empty()
+                    || original.starts_with(DIGEST_HEADER)
+                    || is_artifact_file_stub(&original)
+                {
+                    continue;
+                }
+                store
+                    .store_tool_result_file(&id, &tool, tool_result_ok(&original), &original)
+                    .ok()
+            }
+            _ => None,
+        };
+        message.content = Some(match (mode, spilled) {
+            (ToolResultPersist::All, _) => original,
+            (ToolResultPersist::ArtifactFile, Some(artifact)) => {
+                let stub = artifact_file_stub(&original, &artifact);
+                persisted.artifact_files.push(artifact);
+                stub
+            }
+            (ToolResultPersist::Digest | ToolResultPersist::ArtifactFile, _) => {
+                if original.starts_with(DIGEST_HEADER) || is_artifact_file_stub(&original) {
+                    continue;
+                }
+                let (text, record) = digest_tool_output(&original, &id, &tool, &arguments, step);
+                persisted.digested.push(record);
+                text
+            }
+            (ToolResultPersist::None, _) => {
                 summarize_tool_output_minimal(&original, message.tool_name.as_deref())
             }
         });
     }
+    persisted
 }
 
-fn digest_tool_output(content: &str) -> String {
-    let head: String = content.chars().take(200).collect();
-    format!(
-        "TOOL_OUTPUT_DIGEST v1\nsha256={}\nlen={}\nhead={}\ntruncated=true",
-        sha256_hex(content.as_bytes()),
+fn tool_result_ok(content: &str) -> bool {
+    match serde_json::from_str::<Value>(content) {
+        Ok(v) => v
+            .get("ok")
+            .and_then(Value::as_bool)
+            .unwrap_or_else(|| v.get("error").is_none()),
+        Err(_) => true,
+    }
+}
+
+fn is_artifact_file_stub(content: &str) -> bool {
+    serde_json::from_str::<Value>(content).is_ok_and(|v| {
+        v.get("truncate_reason").and_then(Value::as_str) == Some(ARTIFACT_FILE_TRUNCATE_REASON)
+    })
+}
+
+/// Replacement for a result moved to an artifact file. It stays a
+/// `openagent.tool_result.v1` envelope: everything but `content` is kept
+/// from the original, and `full_output_ref` points at the file.
+fn artifact_file_stub(original: &str, artifact: &ToolResultArtifactV1) -> String {
+    let mut envelope = match serde_json::from_str::<Value>(original) {
+        Ok(Value::Object(map))
+            if map.get("schema_version").and_then(Value::as_str) == Some(TOOL_RESULT_SCHEMA) =>
+        {
+            map
+        }
+        _ => {
+            let mut map = serde_json::Map::new();
+            map.insert("schema_version".into(), TOOL_RESULT_SCHEMA.into());
+            map.insert("tool_name".into(), artifact.tool.clone().into());
+            map.insert("tool_call_id".into(), artifact.tool_call_id.clone().into());
+            map
+        }
+    };
+    envelope.insert("ok".into(), artifact.ok.into());
+    envelope.insert(
+        "content".into(),
+        format!(
+            "tool output moved to {} ({} bytes, sha256={})",
+            artifact.path, artifact.bytes, artifact.sha256
+        )
+        .into(),
+    );
+    envelope.insert("truncated".into(), true.into());
+    envelope.insert(
+        "truncate_reason".into(),
+        ARTIFACT_FILE_TRUNCATE_REASON.into(),
+    );
+    envelope.insert(
+        "full_output_ref".into(),
+        serde_json::json!({
+            "kind": ARTIFACT_FILE_TRUNCATE_REASON,
+            "path": artifact.path,
+            "sha256": artifact.sha256,
+            "bytes": artifact.bytes,
+        }),
+    );
+    Value::Object(envelope).to_string()
+}
+
+/// Replacement text for a digested tool result. Besides the content hash and
+/// a head preview it names the producing call and says how to get the content
+/// back: the artifact reference for spilled output, otherwise the call to
+/// repeat.
+fn digest_tool_output(
+    content: &str,
+    tool_call_id: &str,
+    tool: &str,
+    arguments: &Value,
+    step: Option<u32>,
+) -> (String, DigestedToolResult) {
+    let head: String = content.chars().take(DIGEST_HEAD_CHARS).collect();
+    let content_sha256 = sha256_hex(content.as_bytes());
+    let args_sha256 = tool_args_sha256(tool, arguments);
+    let canonical_args = canonical_tool_args(tool, arguments);
+    let (args, args_truncated) = bounded_args(&canonical_args);
+    let artifact_refs = artifact_refs(content);
+    let retrieve = if artifact_refs.is_empty() {
+        let as_of = step
+            .map(|s| format!("; content unchanged as of step {s}"))
+            .unwrap_or_default();
+        if args_truncated {
+            format!("re-run {tool} with the original arguments (args_sha256={args_sha256}) to retrieve{as_of}")
+        } else {
+            format!("re-run {tool} with {args} to retrieve{as_of}")
+        }
+    } else {
+        format!(
+            "pass {} as the path to read_file to retrieve; do not re-run {tool}",
+            artifact_refs.join(" or ")
+        )
+    };
+
+    let mut out = format!("{DIGEST_HEADER}\ntool={tool}\nargs={args}\n");
+    if args_truncated {
+        out.push_str("args_truncated=true\n");
+    }
+    out.push_str(&format!("args_sha256={args_sha256}\n"));
+    if let Some(step) = step {
+        out.push_str(&format!("step={step}\n"));
+    }
+    if !artifact_refs.is_empty() {
+        out.push_str(&format!("artifacts={}\n", artifact_refs.join(",")));
+    }
+    out.push_str(&format!(
+        "sha256={content_sha256}\nbytes={}\nlen={}\nretrieve={retrieve}\ntruncated=true\nhead={head}",
+        content.len(),
         content.chars().count(),
-        head
+    ));
+    (
+        out,
+        DigestedToolResult {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            args_sha256,
+            content_sha256,
+            step,
+        },
     )
 }
 
+fn bounded_args(canonical: &str) -> (String, bool) {
+    if canonical.chars().count() <= DIGEST_MAX_ARGS_CHARS {
+        return (canonical.to_string(), false);
+    }
+    let mut out = canonical
+        .chars()
+        .take(DIGEST_MAX_ARGS_CHARS)
+        .collect::<String>();
+    out.push_str("...");
+    (out, true)
+}
+
+/// Distinct `artifact:<sha256>` references in a tool result, in order.
+fn artifact_refs(content: &str) -> Vec<String> {
+    let mut refs = Vec::new();
+    let prefix = crate::store::ARTIFACT_REF_PREFIX;
+    for (idx, _) in content.match_indices(prefix) {
+        let candidate = content
+            .get(idx..idx + prefix.len() + 64)
+            .and_then(crate::store::parse_artifact_ref);
+        if let Some(hash) = candidate {
+            let reference = format!("{prefix}{hash}");
+            if !refs.contains(&reference) {
+                refs.push(reference);
+            }
+        }
+        if refs.len() >= DIGEST_MAX_ARTIFACT_REFS {
+            break;
+        }
+    }
+    refs
+}
+
 fn summarize_tool_output_minimal(content: &str, tool_name: Option<&str>) -> String {
     let status = tool_status(content);
     format!(
@@ -173,6 +656,70 @@ fn summarize_tool_output_minimal(content: &str, tool_name: Option<&str>) -> Stri
     )
 }
 
+const GENERATED_SUMMARY_HEADER: &str = "SUMMARY OF EARLIER CONTEXT (generated)";
+const GENERATED_SUMMARY_FOOTER: &str = "END SUMMARY OF EARLIER CONTEXT";
+const SUMMARY_REQUEST_MAX_CHARS: usize = 24_000;
+
+/// The head message that replaces the deterministic summary once the model
+/// has summarized the evicted messages.
+pub fn generated_summary_message(summary: &str) -> Message {
+    Message {
+        role: Role::Developer,
+        content: Some(format!(
+            "{GENERATED_SUMMARY_HEADER}\n{}\n{GENERATED_SUMMARY_FOOTER}",
+            summary.trim()
+        )),
+        tool_call_id: None,
+        tool_name: None,
+        tool_calls: None,
+    }
+}
+
+/// Prompt for the bounded summary call: the evicted messages rendered as
+/// text, keeping the most recent ones when they exceed the request limit.
+pub fn summary_request_messages(evicted: &[Message]) -> Vec<Message> {
+    let mut transcript = String::new();
+    for m in evicted.iter().rev() {
+        let entry = match &m.tool_name {
+            Some(tool) => format!(
+                "[{}:{}]\n{}\n\n",
+                role_name(m.role.clone()),
+                tool,
+                m.content.as_deref().unwrap_or_default()
+            ),
+            None => format!(
+                "[{}]\n{}\n\n",
+                role_name(m.role.clone()),
+                m.content.as_deref().unwrap_or_default()
+            ),
+        };
+        if transcript.len() + entry.len() > SUMMARY_REQUEST_MAX_CHARS {
+            transcript.insert_str(0, "[earlier messages omitted]\n\n");
+            break;
+        }
+        transcript.insert_str(0, &entry);
+    }
+    vec![
+        Message {
+            role: Role::System,
+            content: Some(
+                "Summarize the conversation below for an agent that can no longer see it. Keep the task, decisions made, files and commands involved, results, and open problems. Be concise; do not call tools."
+                    .to_string(),
+            ),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        },
+        Message {
+            role: Role::User,
+            content: Some(transcript),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        },
+    ]
+}
+
 fn build_summary(messages: &[Message]) -> String {
     let mut lines = Vec::new();
     let mut digest_src = String::new();
@@ -228,11 +775,34 @@ fn tool_status(content: &str) -> &'static str {
 mod tests {
     use crate::types::ToolCall;
 
+    use std::collections::BTreeMap;
+
     use super::{
-        context_size_chars, maybe_compact, CompactionMode, CompactionSettings, ToolResultPersist,
+        context_size_chars, maybe_compact_in_place_with_origins, tool_args_sha256, CompactionMode,
+        CompactionOutcome, CompactionSettings, DigestRefetchStatsV1, DigestRefetchTracker,
+        DigestedToolResult, HeuristicTokenCounter, TokenCounter, ToolResultPersist,
     };
+    use crate::store::sha256_hex;
     use crate::types::{Message, Role};
 
+    fn maybe_compact(
+        messages: &[Message],
+        settings: &CompactionSettings,
+    ) -> anyhow::Result<CompactionOutcome> {
+        let mut out = messages.to_vec();
+        let report = maybe_compact_in_place_with_origins(
+            &mut out,
+            settings,
+            &BTreeMap::new(),
+            &HeuristicTokenCounter::default(),
+            None,
+        )?;
+        Ok(CompactionOutcome {
+            messages: out,
+            report,
+        })
+    }
+
     fn msg(role: Role, content: &str) -> Message {
         Message {
             role,
@@ -252,6 +822,7 @@ mod tests {
         ];
         let settings = CompactionSettings {
             max_context_chars: 4,
+            max_context_tokens: None,
             mode: CompactionMode::Summary,
             keep_last: 1,
             tool_result_persist: ToolResultPersist::Digest,
@@ -278,6 +849,7 @@ mod tests {
         ];
         let settings = CompactionSettings {
             max_context_chars: 3,
+            max_context_tokens: None,
             mode: CompactionMode::Summary,
             keep_last: 2,
             tool_result_persist: ToolResultPersist::All,
@@ -309,14 +881,275 @@ mod tests {
         let messages = vec![msg(Role::User, "hello"), tool, msg(Role::Assistant, "done")];
         let settings = CompactionSettings {
             max_context_chars: 5,
+            max_context_tokens: None,
             mode: CompactionMode::Summary,
             keep_last: 2,
             tool_result_persist: ToolResultPersist::Digest,
         };
         let out = maybe_compact(&messages, &settings).expect("compact");
         let digest_msg = out.messages[1].content.as_deref().unwrap_or("");
-        assert!(digest_msg.contains("TOOL_OUTPUT_DIGEST v1"));
+        assert!(digest_msg.contains("TOOL_OUTPUT_DIGEST v2"));
         assert!(digest_msg.contains("sha256="));
+        assert!(digest_msg.contains("tool=read_file\nargs={}\n"));
+        let report = out.report.expect("report");
+        assert_eq!(report.digested_tool_results.len(), 1);
+        assert_eq!(report.digested_tool_results[0].step, None);
+    }
+
+    fn tool_result(id: &str, name: &str, content: &str) -> Message {
+        Message {
+            role: Role::Tool,
+            content: Some(content.to_string()),
+            tool_call_id: Some(id.to_string()),
+            tool_name: Some(name.to_string()),
+            tool_calls: None,
+        }
+    }
+
+    fn call(id: &str, name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    fn digest_settings() -> CompactionSettings {
+        CompactionSettings {
+            max_context_chars: 5,
+            max_context_tokens: None,
+            mode: CompactionMode::Summary,
+            keep_last: 3,
+            tool_result_persist: ToolResultPersist::Digest,
+        }
+    }
+
+    fn compact_with_tracker(
+        messages: &mut Vec<Message>,
+        tracker: &DigestRefetchTracker,
+    ) -> super::CompactionReport {
+        maybe_compact_in_place_with_origins(
+            messages,
+            &digest_settings(),
+            tracker.origins(),
+            &HeuristicTokenCounter::default(),
+            None,
+        )
+        .expect("compact")
+        .expect("report")
+    }
+
+    #[test]
+    fn digest_of_read_file_names_call_and_step_to_rerun() {
+        let mut tracker = DigestRefetchTracker::default();
+        tracker.record_call(
+            &call(
+                "tc1",
+                "read_file",
+                serde_json::json!({"path": "src/lib.rs"}),
+            ),
+            3,
+        );
+        let content = "{\"ok\":true,\"content\":\"fn main() {}\"}";
+        let mut messages = vec![
+            msg(Role::User, "read it"),
+            tool_result("tc1", "read_file", content),
+            msg(Role::Assistant, "read"),
+            msg(Role::User, "next"),
+        ];
+        let report = compact_with_tracker(&mut messages, &tracker);
+        let digest = messages[1].content.clone().unwrap_or_default();
+        assert!(digest.starts_with("TOOL_OUTPUT_DIGEST v2\ntool=read_file\n"));
+        assert!(digest.contains("args={\"path\":\"src/lib.rs\"}\n"));
+        assert!(digest.contains("step=3\n"));
+        assert!(digest.contains(&format!("sha256={}\n", sha256_hex(content.as_bytes()))));
+        assert!(digest.contains(&format!("bytes={}\n", content.len())));
+        assert!(digest.contains(
+            "retrieve=re-run read_file with {\"path\":\"src/lib.rs\"} to retrieve; content unchanged as of step 3\n"
+        ));
+        assert_eq!(
+            report.digested_tool_results,
+            vec![DigestedToolResult {
+                tool_call_id: "tc1".to_string(),
+                tool: "read_file".to_string(),
+                args_sha256: tool_args_sha256(
+                    "read_file",
+                    &serde_json::json!({"path": "src/lib.rs"})
+                ),
+                content_sha256: sha256_hex(content.as_bytes()),
+                step: Some(3),
+            }]
+        );
+
+        // A second compaction keeps the existing digest instead of digesting it.
+        messages.push(msg(Role::User, "more"));
+        let keep_four = CompactionSettings {
+            keep_last: 4,
+            ..digest_settings()
+        };
+        let again = maybe_compact_in_place_with_origins(
+            &mut messages,
+            &keep_four,
+            tracker.origins(),
+            &HeuristicTokenCounter::default(),
+            None,
+        )
+        .expect("compact")
+        .expect("report");
+        assert!(again.digested_tool_results.is_empty());
+        assert_eq!(messages[1].content.as_deref(), Some(digest.as_str()));
+    }
+
+    #[test]
+    fn digest_of_shell_result_uses_normalized_arguments() {
+        let mut tracker = DigestRefetchTracker::default();
+        tracker.record_call(
+            &call(
+                "tc1",
+                "shell",
+                serde_json::json!({"command": "cargo test --quiet"}),
+            ),
+            2,
+        );
+        let mut messages = vec![
+            msg(Role::User, "test"),
+            tool_result("tc1", "shell", "{\"ok\":true,\"stdout\":\"passed\"}"),
+            msg(Role::Assistant, "ran"),
+            msg(Role::User, "next"),
+        ];
+        compact_with_tracker(&mut messages, &tracker);
+        let digest = messages[1].content.as_deref().unwrap_or("");
+        assert!(digest.contains(
+            "retrieve=re-run shell with {\"args\":[\"test\",\"--quiet\"],\"cmd\":\"cargo\"} to retrieve; content unchanged as of step 2\n"
+        ));
+        assert_eq!(
+            tool_args_sha256(
+                "shell",
+                &serde_json::json!({"command": "cargo test --quiet"})
+            ),
+            tool_args_sha256(
+                "shell",
+                &serde_json::json!({"cmd": "cargo", "args": ["test", "--quiet"]})
+            )
+        );
+    }
+
+    #[test]
+    fn digest_of_spilled_result_points_at_artifact() {
+        let mut tracker = DigestRefetchTracker::default();
+        tracker.record_call(
+            &call("tc1", "mcp.stub.screenshot", serde_json::json!({})),
+            1,
+        );
+        let hash = "ab".repeat(32);
+        let content = format!(
+            "{{\"ok\":true,\"content\":{{\"artifact\":{{\"hash\":\"{hash}\",\"path_hint\":\"artifact:{hash}\"}}}}}}"
+        );
+        let mut messages = vec![
+            msg(Role::User, "shot"),
+            tool_result("tc1", "mcp.stub.screenshot", &content),
+            msg(Role::Assistant, "took"),
+            msg(Role::User, "next"),
+        ];
+        compact_with_tracker(&mut messages, &tracker);
+        let digest = messages[1].content.as_deref().unwrap_or("");
+        assert!(digest.contains(&format!("artifacts=artifact:{hash}\n")));
+        assert!(digest.contains(&format!(
+            "retrieve=pass artifact:{hash} as the path to read_file to retrieve; do not re-run mcp.stub.screenshot\n"
+        )));
+        assert!(!digest.contains("retrieve=re-run"));
+    }
+
+    #[test]
+    fn digest_bounds_huge_arguments() {
+        let mut tracker = DigestRefetchTracker::default();
+        let huge = serde_json::json!({"path": "a.txt", "content": "x".repeat(10_000)});
+        tracker.record_call(&call("tc1", "write_file", huge.clone()), 4);
+        let mut messages = vec![
+            msg(Role::User, "write"),
+            tool_result("tc1", "write_file", "{\"ok\":true}"),
+            msg(Role::Assistant, "wrote"),
+            msg(Role::User, "next"),
+        ];
+        compact_with_tracker(&mut messages, &tracker);
+        let digest = messages[1].content.as_deref().unwrap_or("");
+        let args_line = digest
+            .lines()
+            .find(|l| l.starts_with("args="))
+            .expect("args line");
+        assert_eq!(args_line.chars().count(), "args=".len() + 240 + "...".len());
+        assert!(digest.contains("args_truncated=true\n"));
+        let args_sha256 = tool_args_sha256("write_file", &huge);
+        assert!(digest.contains(&format!(
+            "retrieve=re-run write_file with the original arguments (args_sha256={args_sha256}) to retrieve; content unchanged as of step 4\n"
+        )));
+        assert!(digest.len() < 1_000);
+    }
+
+    #[test]
+    fn tracker_counts_refetches_of_digested_results() {
+        let mut tracker = DigestRefetchTracker::default();
+        let args = serde_json::json!({"path": "notes.md"});
+        assert!(tracker
+            .record_call(&call("tc1", "read_file", args.clone()), 1)
+            .is_none());
+        assert_eq!(tracker.stats(), None);
+        let mut messages = vec![
+            msg(Role::User, "read"),
+            tool_result("tc1", "read_file", "{\"ok\":true,\"content\":\"hi\"}"),
+            msg(Role::Assistant, "read"),
+            msg(Role::User, "next"),
+        ];
+        let report = compact_with_tracker(&mut messages, &tracker);
+        tracker.record_digests(&report.digested_tool_results);
+
+        assert!(tracker
+            .record_call(
+                &call("tc2", "read_file", serde_json::json!({"path": "other.md"})),
+                5
+            )
+            .is_none());
+        let hit = tracker
+            .record_call(&call("tc3", "read_file", args.clone()), 6)
+            .expect("refetch");
+        assert_eq!(hit.digested_tool_call_id, "tc1");
+        assert_eq!(hit.digested_step, Some(1));
+        assert_eq!(hit.args_sha256, tool_args_sha256("read_file", &args));
+        tracker.record_call(&call("tc4", "read_file", args), 7);
+        assert_eq!(
+            tracker.stats(),
+            Some(DigestRefetchStatsV1 {
+                digested_results: 1,
+                refetched_results: 1,
+                refetch_calls: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn under_budget_check_leaves_transcript_untouched() {
+        let messages = vec![msg(Role::User, "u1"), msg(Role::Assistant, "a1")];
+        let roomy = CompactionSettings {
+            max_context_chars: 10_000,
+            max_context_tokens: None,
+            mode: CompactionMode::Summary,
+            keep_last: 1,
+            tool_result_persist: ToolResultPersist::Digest,
+        };
+        let mut untouched = messages.clone();
+        assert!(maybe_compact_in_place_with_origins(
+            &mut untouched,
+            &roomy,
+            &BTreeMap::new(),
+            &HeuristicTokenCounter::default(),
+            None,
+        )
+        .expect("under budget")
+        .is_none());
+        assert_eq!(
+            serde_json::to_string(&untouched).expect("serialize untouched"),
+            serde_json::to_string(&messages).expect("serialize original")
+        );
     }
 
     #[test]
@@ -324,6 +1157,7 @@ mod tests {
         let messages = vec![msg(Role::User, "a very long message")];
         let off = CompactionSettings {
             max_context_chars: 0,
+            max_context_tokens: None,
             mode: CompactionMode::Summary,
             keep_last: 1,
             tool_result_persist: ToolResultPersist::Digest,
@@ -335,4 +1169,169 @@ mod tests {
             context_size_chars(&messages)
         );
     }
+
+    #[test]
+    fn token_budget_compacts_a_transcript_within_the_char_budget() {
+        let tool_json = r#"{"path":"a.toml","lines":[1,2,3]}"#.repeat(40);
+        let mut messages = vec![
+            msg(Role::System, "banner"),
+            msg(Role::User, "read the config"),
+            msg(Role::Assistant, &tool_json),
+            msg(Role::User, "now summarize it"),
+        ];
+        let chars = context_size_chars(&messages);
+        let settings = CompactionSettings {
+            max_context_chars: chars + 100,
+            max_context_tokens: Some(chars / 3),
+            mode: CompactionMode::Summary,
+            keep_last: 1,
+            tool_result_persist: ToolResultPersist::Digest,
+        };
+        let char_only = CompactionSettings {
+            max_context_tokens: None,
+            ..settings.clone()
+        };
+        assert!(maybe_compact(&messages, &char_only)
+            .expect("chars")
+            .report
+            .is_none());
+
+        // Reported usage says this model's tokenizer packs two chars a token.
+        let mut counter = HeuristicTokenCounter::default();
+        counter.calibrate(1_000, 500);
+        let report = maybe_compact_in_place_with_origins(
+            &mut messages,
+            &settings,
+            &Default::default(),
+            &counter,
+            None,
+        )
+        .expect("compact")
+        .expect("report");
+        assert_eq!(report.before_chars, chars);
+        assert!(report.before_tokens > chars / 3, "{}", report.before_tokens);
+        assert!(report.after_tokens < report.before_tokens);
+        assert_eq!(
+            messages.last().and_then(|m| m.content.as_deref()),
+            Some("now summarize it")
+        );
+    }
+
+    #[test]
+    fn heuristic_counter_calibrates_from_reported_usage() {
+        let mut counter = HeuristicTokenCounter::default();
+        assert_eq!(counter.count_tokens("abcdefgh"), 2);
+        counter.calibrate(0, 10);
+        assert_eq!(counter.count_tokens("abcdefgh"), 2);
+        counter.calibrate(300, 100);
+        counter.calibrate(100, 100);
+        assert_eq!(counter.count_tokens("abcdefgh"), 4);
+    }
+
+    fn artifact_file_settings() -> CompactionSettings {
+        CompactionSettings {
+            tool_result_persist: ToolResultPersist::ArtifactFile,
+            ..digest_settings()
+        }
+    }
+
+    fn large_envelope(id: &str, ok: bool) -> String {
+        serde_json::json!({
+            "schema_version": "openagent.tool_result.v1",
+            "tool_name": "shell",
+            "tool_call_id": id,
+            "ok": ok,
+            "content": "line\n".repeat(400),
+            "truncated": false,
+            "meta": {"side_effects": "shell_exec", "source": "builtin", "execution_target": "host"}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn artifact_file_mode_spills_large_results_behind_an_envelope_stub() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let store = crate::store::RunArtifactStore::new(tmp.path(), "run_a", 0);
+        let big = large_envelope("tc_big", false);
+        let mut messages = vec![
+            msg(Role::System, "sys"),
+            msg(Role::User, "run it"),
+            tool_result("tc_big", "shell", &big),
+            tool_result("tc_small", "read_file", r#"{"ok":true,"content":"x"}"#),
+        ];
+        let report = maybe_compact_in_place_with_origins(
+            &mut messages,
+            &artifact_file_settings(),
+            &Default::default(),
+            &HeuristicTokenCounter::default(),
+            Some(&store),
+        )
+        .expect("compact")
+        .expect("report");
+
+        assert_eq!(report.artifact_files.len(), 1);
+        assert!(report.digested_tool_results.is_empty());
+        let artifact = &report.artifact_files[0];
+        assert_eq!(artifact.path, "runs/run_a/artifacts/tc_big.json");
+        assert_eq!(artifact.bytes, big.len() as u64);
+        assert_eq!(artifact.sha256, sha256_hex(big.as_bytes()));
+        assert!(!artifact.ok);
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join(&artifact.path)).expect("read"),
+            big
+        );
+
+        let stub: serde_json::Value =
+            serde_json::from_str(messages[2].content.as_deref().expect("stub")).expect("json");
+        assert_eq!(stub["schema_version"], "openagent.tool_result.v1");
+        assert_eq!(stub["ok"], false);
+        assert_eq!(stub["truncated"], true);
+        assert_eq!(stub["truncate_reason"], "artifact_file");
+        assert_eq!(stub["meta"]["source"], "builtin");
+        assert_eq!(stub["full_output_ref"]["path"], artifact.path);
+        assert_eq!(stub["full_output_ref"]["sha256"], artifact.sha256);
+        assert!(stub["content"]
+            .as_str()
+            .is_some_and(|c| c.contains(&artifact.path) && c.contains(&artifact.sha256)));
+        assert_eq!(
+            messages[3].content.as_deref(),
+            Some(r#"{"ok":true,"content":"x"}"#)
+        );
+
+        messages.push(msg(Role::User, "more"));
+        let again = maybe_compact_in_place_with_origins(
+            &mut messages,
+            &CompactionSettings {
+                keep_last: 4,
+                ..artifact_file_settings()
+            },
+            &Default::default(),
+            &HeuristicTokenCounter::default(),
+            Some(&store),
+        )
+        .expect("compact")
+        .expect("report");
+        assert!(again.artifact_files.is_empty());
+        assert!(again.digested_tool_results.is_empty());
+        assert_eq!(store.tool_result_files().len(), 1);
+    }
+
+    #[test]
+    fn artifact_file_mode_without_a_store_digests() {
+        let mut messages = vec![
+            msg(Role::User, "run it"),
+            tool_result("tc_big", "shell", &large_envelope("tc_big", true)),
+        ];
+        let report = maybe_compact_in_place_with_origins(
+            &mut messages,
+            &artifact_file_settings(),
+            &Default::default(),
+            &HeuristicTokenCounter::default(),
+            None,
+        )
+        .expect("compact")
+        .expect("report");
+        assert!(report.artifact_files.is_empty());
+        assert_eq!(report.digested_tool_results.len(), 1);
+    }
 }
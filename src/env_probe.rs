@@ -0,0 +1,538 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::store::sha256_hex;
+use crate::target::{ExecTarget, ExecTargetKind, ShellReq};
+use crate::types::{Message, Role};
+
+pub const ENVIRONMENT_FACTS_SCHEMA_VERSION: &str = "openagent.environment_facts.v1";
+pub const MAX_ENVIRONMENT_PROBES: usize = 12;
+pub const ENVIRONMENT_PROBE_TIMEOUT_MS: u64 = 3_000;
+pub const ENVIRONMENT_PROBE_MAX_OUTPUT_BYTES: usize = 256;
+pub const DEFAULT_ENVIRONMENT_PROBE_TTL_SECS: u64 = 6 * 60 * 60;
+
+const DEFAULT_ENVIRONMENT_PROBES: &[&str] = &[
+    "uname -sr",
+    "python3 --version",
+    "python --version",
+    "node --version",
+    "npm --version",
+    "cargo --version",
+    "rustc --version",
+    "go version",
+    "git --version",
+];
+
+pub fn default_environment_probes() -> Vec<String> {
+    DEFAULT_ENVIRONMENT_PROBES
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+}
+
+/// Operator-declared probe settings. Probes are split on whitespace and run
+/// without a shell, so pipes, redirects, and globbing are not interpreted.
+#[derive(Debug, Clone)]
+pub struct EnvironmentProbeConfig {
+    pub probes: Vec<String>,
+    pub ttl_secs: u64,
+}
+
+impl Default for EnvironmentProbeConfig {
+    fn default() -> Self {
+        Self {
+            probes: default_environment_probes(),
+            ttl_secs: DEFAULT_ENVIRONMENT_PROBE_TTL_SECS,
+        }
+    }
+}
+
+impl EnvironmentProbeConfig {
+    pub fn from_policy(policy: Option<&crate::trust::policy::Policy>) -> Self {
+        let mut config = Self::default();
+        if let Some(policy) = policy {
+            if let Some(probes) = policy.environment_probes() {
+                config.probes = probes.to_vec();
+            }
+            if let Some(ttl_secs) = policy.environment_probe_ttl_secs() {
+                config.ttl_secs = ttl_secs;
+            }
+        }
+        config
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvironmentProbeResult {
+    pub command: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub output: String,
+    pub output_sha256: String,
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub timed_out: bool,
+}
+
+/// Probe results as cached in session state and copied into the run record.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvironmentFactsCache {
+    pub schema_version: String,
+    pub probed_at: String,
+    pub exec_target: String,
+    pub probes_hash_hex: String,
+    pub results: Vec<EnvironmentProbeResult>,
+    #[serde(default)]
+    pub skipped_over_cap: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentProbeRecord {
+    pub cache_hit: bool,
+    pub ttl_secs: u64,
+    pub facts: EnvironmentFactsCache,
+}
+
+pub fn exec_target_label(kind: ExecTargetKind) -> &'static str {
+    match kind {
+        ExecTargetKind::Host => "host",
+        ExecTargetKind::Docker => "docker",
+        ExecTargetKind::Ssh => "ssh",
+    }
+}
+
+pub fn probes_hash_hex(probes: &[String], exec_target: &str) -> String {
+    let mut material = format!("{exec_target}\n");
+    for probe in probes {
+        material.push_str(probe.trim());
+        material.push('\n');
+    }
+    sha256_hex(material.as_bytes())
+}
+
+/// A cache entry is reusable when it was produced for the same probe list and
+/// exec target and has not outlived the TTL. `ttl_secs == 0` disables reuse.
+pub fn cache_is_fresh(
+    cache: &EnvironmentFactsCache,
+    probes_hash_hex: &str,
+    ttl_secs: u64,
+    now: OffsetDateTime,
+) -> bool {
+    if ttl_secs == 0
+        || cache.schema_version != ENVIRONMENT_FACTS_SCHEMA_VERSION
+        || cache.probes_hash_hex != probes_hash_hex
+    {
+        return false;
+    }
+    let Ok(probed_at) = OffsetDateTime::parse(&cache.probed_at, &Rfc3339) else {
+        return false;
+    };
+    let age = now - probed_at;
+    !age.is_negative() && age.whole_seconds() < ttl_secs as i64
+}
+
+pub async fn run_environment_probes(
+    target: &dyn ExecTarget,
+    workdir: &Path,
+    probes: &[String],
+    now: OffsetDateTime,
+) -> EnvironmentFactsCache {
+    let exec_target = exec_target_label(target.kind());
+    let mut results = Vec::new();
+    let mut skipped_over_cap = Vec::new();
+    for (idx, probe) in probes.iter().enumerate() {
+        if idx >= MAX_ENVIRONMENT_PROBES {
+            skipped_over_cap.push(probe.clone());
+            continue;
+        }
+        results.push(run_single_probe(target, workdir, probe).await);
+    }
+    EnvironmentFactsCache {
+        schema_version: ENVIRONMENT_FACTS_SCHEMA_VERSION.to_string(),
+        probed_at: now
+            .format(&Rfc3339)
+            .unwrap_or_else(|_| crate::trust::now_rfc3339()),
+        exec_target: exec_target.to_string(),
+        probes_hash_hex: probes_hash_hex(probes, exec_target),
+        results,
+        skipped_over_cap,
+    }
+}
+
+async fn run_single_probe(
+    target: &dyn ExecTarget,
+    workdir: &Path,
+    probe: &str,
+) -> EnvironmentProbeResult {
+    let mut parts = probe.split_whitespace().map(ToString::to_string);
+    let Some(cmd) = parts.next() else {
+        return probe_result(probe, false, None, String::new(), false);
+    };
+    let req = ShellReq {
+        workdir: workdir.to_path_buf(),
+        cmd,
+        args: parts.collect(),
+        cwd: None,
+        max_tool_output_bytes: ENVIRONMENT_PROBE_MAX_OUTPUT_BYTES,
+        // The docker target rejects non-zero timeouts, so the outer tokio
+        // timeout below is the enforcement point for both targets.
+        timeout_ms: match target.kind() {
+            ExecTargetKind::Host | ExecTargetKind::Ssh => ENVIRONMENT_PROBE_TIMEOUT_MS,
+            ExecTargetKind::Docker => 0,
+        },
+        stream: None,
+        create_cwd: false,
+        redaction: None,
+    };
+    let timeout = std::time::Duration::from_millis(ENVIRONMENT_PROBE_TIMEOUT_MS);
+    let Ok(out) = tokio::time::timeout(timeout, target.exec_shell(req)).await else {
+        return probe_result(probe, false, None, String::new(), true);
+    };
+    let parsed = serde_json::from_str::<serde_json::Value>(&out.content).ok();
+    let timed_out = parsed
+        .as_ref()
+        .and_then(|v| v.get("timed_out"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let output = parsed
+        .as_ref()
+        .map(|v| {
+            let stdout = v.get("stdout").and_then(|s| s.as_str()).unwrap_or_default();
+            let stderr = v.get("stderr").and_then(|s| s.as_str()).unwrap_or_default();
+            // Some tools (python2, java) print their version on stderr.
+            if stdout.trim().is_empty() {
+                stderr.trim().to_string()
+            } else {
+                stdout.trim().to_string()
+            }
+        })
+        .unwrap_or_default();
+    probe_result(
+        probe,
+        out.ok,
+        out.exit_code,
+        if out.ok { output } else { String::new() },
+        timed_out,
+    )
+}
+
+fn probe_result(
+    command: &str,
+    ok: bool,
+    exit_code: Option<i32>,
+    output: String,
+    timed_out: bool,
+) -> EnvironmentProbeResult {
+    let first_line = output.lines().next().unwrap_or_default().trim().to_string();
+    let (kept, truncated) = truncate_to_bytes(&first_line, ENVIRONMENT_PROBE_MAX_OUTPUT_BYTES);
+    EnvironmentProbeResult {
+        command: command.to_string(),
+        ok,
+        exit_code,
+        output_sha256: sha256_hex(output.as_bytes()),
+        output: kept,
+        truncated: truncated || output.lines().count() > 1,
+        timed_out,
+    }
+}
+
+fn truncate_to_bytes(input: &str, max_bytes: usize) -> (String, bool) {
+    if input.len() <= max_bytes {
+        return (input.to_string(), false);
+    }
+    let mut end = max_bytes;
+    while end > 0 && !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    (input[..end].to_string(), true)
+}
+
+/// Reuse the cached facts when fresh, otherwise probe again. The returned
+/// record's `facts` is what callers should persist back into session state.
+pub async fn resolve_environment_facts(
+    target: &dyn ExecTarget,
+    workdir: &Path,
+    config: &EnvironmentProbeConfig,
+    cached: Option<&EnvironmentFactsCache>,
+    now: OffsetDateTime,
+) -> EnvironmentProbeRecord {
+    let hash = probes_hash_hex(&config.probes, exec_target_label(target.kind()));
+    if let Some(cache) = cached.filter(|c| cache_is_fresh(c, &hash, config.ttl_secs, now)) {
+        return EnvironmentProbeRecord {
+            cache_hit: true,
+            ttl_secs: config.ttl_secs,
+            facts: cache.clone(),
+        };
+    }
+    EnvironmentProbeRecord {
+        cache_hit: false,
+        ttl_secs: config.ttl_secs,
+        facts: run_environment_probes(target, workdir, &config.probes, now).await,
+    }
+}
+
+pub fn environment_facts_message(facts: &EnvironmentFactsCache) -> Option<Message> {
+    if facts.results.is_empty() {
+        return None;
+    }
+    let mut body = String::from(
+        "ENVIRONMENT FACTS (probed by the runtime; do not re-run these commands to discover versions)\n",
+    );
+    body.push_str(&format!("exec_target: {}\n", facts.exec_target));
+    if facts.exec_target == "host" {
+        body.push_str(&format!(
+            "os: {} ({})\n",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ));
+    }
+    for result in &facts.results {
+        let value = if result.timed_out {
+            "timed out".to_string()
+        } else if result.ok && !result.output.is_empty() {
+            result.output.clone()
+        } else {
+            "unavailable".to_string()
+        };
+        body.push_str(&format!("- {}: {}\n", result.command, value));
+    }
+    body.push_str("END_ENVIRONMENT_FACTS");
+    Some(Message {
+        role: Role::Developer,
+        content: Some(body),
+        tool_call_id: None,
+        tool_name: None,
+        tool_calls: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use serde_json::json;
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::target::{
+        ListReq, PatchReq, ReadReq, SearchReq, TargetDescribe, TargetResult, WriteReq,
+    };
+
+    #[derive(Default)]
+    struct CountingTarget {
+        shell_calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ExecTarget for CountingTarget {
+        fn kind(&self) -> ExecTargetKind {
+            ExecTargetKind::Host
+        }
+
+        fn describe(&self) -> TargetDescribe {
+            TargetDescribe {
+                exec_target: "host".to_string(),
+                docker: None,
+                ssh: None,
+            }
+        }
+
+        async fn exec_shell(&self, req: ShellReq) -> TargetResult {
+            self.shell_calls.fetch_add(1, Ordering::SeqCst);
+            if req.cmd == "missing" {
+                return TargetResult::failed(
+                    ExecTargetKind::Host,
+                    "shell execution failed: program not found".to_string(),
+                    None,
+                );
+            }
+            TargetResult {
+                ok: true,
+                content: json!({
+                    "status": 0,
+                    "stdout": format!("{} {}\nsecond line\n", req.cmd, req.args.join(" ")),
+                    "stderr": ""
+                })
+                .to_string(),
+                truncated: false,
+                bytes: None,
+                exit_code: Some(0),
+                stderr_truncated: Some(false),
+                stdout_truncated: Some(false),
+                execution_target: ExecTargetKind::Host,
+                docker: None,
+                write_protection: None,
+                cwd: None,
+            }
+        }
+
+        async fn read_file(&self, _req: ReadReq) -> TargetResult {
+            unreachable!("probes only use exec_shell")
+        }
+
+        async fn list_dir(&self, _req: ListReq) -> TargetResult {
+            unreachable!("probes only use exec_shell")
+        }
+
+        async fn write_file(&self, _req: WriteReq) -> TargetResult {
+            unreachable!("probes only use exec_shell")
+        }
+
+        async fn apply_patch(&self, _req: PatchReq) -> TargetResult {
+            unreachable!("probes only use exec_shell")
+        }
+
+        async fn search(&self, _req: SearchReq) -> TargetResult {
+            unreachable!("probes only use exec_shell")
+        }
+    }
+
+    fn config(probes: &[&str], ttl_secs: u64) -> EnvironmentProbeConfig {
+        EnvironmentProbeConfig {
+            probes: probes.iter().map(|p| p.to_string()).collect(),
+            ttl_secs,
+        }
+    }
+
+    #[tokio::test]
+    async fn probes_execute_and_render_compact_facts_message() {
+        let target = CountingTarget::default();
+        let facts = run_environment_probes(
+            &target,
+            Path::new("."),
+            &[
+                "tool --version".to_string(),
+                "missing --version".to_string(),
+            ],
+            OffsetDateTime::now_utc(),
+        )
+        .await;
+        assert_eq!(facts.results.len(), 2);
+        assert_eq!(facts.results[0].output, "tool --version");
+        assert!(facts.results[0].truncated);
+        assert!(!facts.results[1].ok);
+
+        let msg = environment_facts_message(&facts).expect("message");
+        assert!(matches!(msg.role, Role::Developer));
+        let body = msg.content.expect("content");
+        assert!(body.starts_with("ENVIRONMENT FACTS"));
+        assert!(body.contains("- tool --version: tool --version\n"));
+        assert!(body.contains("- missing --version: unavailable\n"));
+        assert!(body.ends_with("END_ENVIRONMENT_FACTS"));
+        assert!(!body.contains("second line"));
+    }
+
+    #[tokio::test]
+    async fn fresh_cache_is_reused_across_runs() {
+        let target = CountingTarget::default();
+        let cfg = config(&["a --version", "b --version"], 3600);
+        let now = OffsetDateTime::now_utc();
+        let first = resolve_environment_facts(&target, Path::new("."), &cfg, None, now).await;
+        assert!(!first.cache_hit);
+        assert_eq!(target.shell_calls.load(Ordering::SeqCst), 2);
+
+        let second =
+            resolve_environment_facts(&target, Path::new("."), &cfg, Some(&first.facts), now).await;
+        assert!(second.cache_hit);
+        assert_eq!(second.facts, first.facts);
+        assert_eq!(target.shell_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn expired_or_mismatched_cache_triggers_reprobe() {
+        let target = CountingTarget::default();
+        let cfg = config(&["a --version"], 60);
+        let now = OffsetDateTime::now_utc();
+        let first = resolve_environment_facts(&target, Path::new("."), &cfg, None, now).await;
+
+        let later = now + time::Duration::seconds(61);
+        let expired =
+            resolve_environment_facts(&target, Path::new("."), &cfg, Some(&first.facts), later)
+                .await;
+        assert!(!expired.cache_hit);
+        assert_eq!(target.shell_calls.load(Ordering::SeqCst), 2);
+
+        let changed = config(&["a --version", "b --version"], 60);
+        let reprobed =
+            resolve_environment_facts(&target, Path::new("."), &changed, Some(&first.facts), now)
+                .await;
+        assert!(!reprobed.cache_hit);
+        assert_eq!(target.shell_calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn probe_count_and_output_size_are_capped() {
+        let target = CountingTarget::default();
+        let mut probes = (0..MAX_ENVIRONMENT_PROBES + 3)
+            .map(|i| format!("p{i} --version"))
+            .collect::<Vec<_>>();
+        probes[0] = format!(
+            "long {}",
+            "x".repeat(ENVIRONMENT_PROBE_MAX_OUTPUT_BYTES * 2)
+        );
+        let facts =
+            run_environment_probes(&target, Path::new("."), &probes, OffsetDateTime::now_utc())
+                .await;
+        assert_eq!(facts.results.len(), MAX_ENVIRONMENT_PROBES);
+        assert_eq!(facts.skipped_over_cap.len(), 3);
+        assert_eq!(
+            target.shell_calls.load(Ordering::SeqCst),
+            MAX_ENVIRONMENT_PROBES
+        );
+        assert!(facts.results[0].output.len() <= ENVIRONMENT_PROBE_MAX_OUTPUT_BYTES);
+        assert!(facts.results[0].truncated);
+        assert_eq!(facts.results[0].output_sha256.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn probing_does_not_enable_model_initiated_shell() {
+        let tmp = tempfile::tempdir().expect("tmp");
+        let rt = crate::tools::ToolRuntime {
+            workdir: tmp.path().to_path_buf(),
+            allow_shell: false,
+            allow_shell_in_workdir_only: false,
+            shell_allowlist: None,
+            allow_write: false,
+            max_tool_output_bytes: 1024,
+            max_read_bytes: 1024,
+            unsafe_bypass_allow_flags: false,
+            restrict_to_workdir: true,
+            tool_timeout_ms: None,
+            stream_tool_output: false,
+            dry_run_writes: false,
+            write_checkpoint: None,
+            tool_args_strict: crate::tools::ToolArgsStrict::On,
+            exec_target_kind: ExecTargetKind::Host,
+            exec_target: Arc::new(CountingTarget::default()),
+            read_allowlist: None,
+            run_artifacts: None,
+            secret_redactor: None,
+        };
+        let facts = run_environment_probes(
+            rt.exec_target.as_ref(),
+            &rt.workdir,
+            &default_environment_probes(),
+            OffsetDateTime::now_utc(),
+        )
+        .await;
+        assert!(!facts.results.is_empty());
+        let msg = crate::tools::execute_tool(
+            &rt,
+            &crate::types::ToolCall {
+                id: "tc1".to_string(),
+                name: "shell".to_string(),
+                arguments: json!({"cmd":"python3","args":["--version"]}),
+            },
+        )
+        .await;
+        let content = msg.content.unwrap_or_default();
+        assert!(content.contains("shell tool is disabled"), "{content}");
+    }
+}
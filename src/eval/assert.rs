@@ -166,6 +166,7 @@ mod tests {
             tool_decisions: Vec::new(),
             compaction_settings: CompactionSettings {
                 max_context_chars: 0,
+                max_context_tokens: None,
                 mode: CompactionMode::Off,
                 keep_last: 20,
                 tool_result_persist: ToolResultPersist::Digest,
@@ -175,8 +176,16 @@ mod tests {
             hook_invocations: Vec::new(),
             provider_retry_count: 0,
             provider_error_count: 0,
+            provider_retry_wait_ms: 0,
             token_usage: None,
             taint: None,
+            digest_refetch: None,
+            tool_result_artifacts: Vec::new(),
+            model_served: None,
+            subtask_run_ids: Vec::new(),
+            post_run_hook_error: None,
+            timeline: Vec::new(),
+            steps: Vec::new(),
         };
         let failures = evaluate_assertions(
             &[
@@ -212,6 +221,7 @@ mod tests {
             tool_decisions: Vec::new(),
             compaction_settings: CompactionSettings {
                 max_context_chars: 0,
+                max_context_tokens: None,
                 mode: CompactionMode::Off,
                 keep_last: 20,
                 tool_result_persist: ToolResultPersist::Digest,
@@ -221,8 +231,16 @@ mod tests {
             hook_invocations: Vec::new(),
             provider_retry_count: 0,
             provider_error_count: 0,
+            provider_retry_wait_ms: 0,
             token_usage: None,
             taint: None,
+            digest_refetch: None,
+            tool_result_artifacts: Vec::new(),
+            model_served: None,
+            subtask_run_ids: Vec::new(),
+            post_run_hook_error: None,
+            timeline: Vec::new(),
+            steps: Vec::new(),
         };
         let ok = evaluate_assertions(
             &[Assertion::ToolNotUsedGlob {
@@ -6,7 +6,7 @@ use anyhow::{anyhow, Context};
 use serde::{Deserialize, Serialize};
 
 use crate::eval::runner::EvalResults;
-use crate::store::load_run_record;
+use crate::store::{load_run_record, run_annotations_path, RUN_ANNOTATIONS_FILE_NAME};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BundleManifest {
@@ -33,6 +33,8 @@ pub struct BundleSpec {
     pub baseline_name: Option<String>,
     pub profile_name: Option<String>,
     pub profile_hash_hex: Option<String>,
+    /// Also copy each run's operator annotations sidecar when present.
+    pub include_annotations: bool,
 }
 
 pub fn create_bundle(spec: &BundleSpec) -> anyhow::Result<PathBuf> {
@@ -75,6 +77,16 @@ pub fn create_bundle(spec: &BundleSpec) -> anyhow::Result<PathBuf> {
                 &PathBuf::from("runs").join(format!("{}.json", run.run_id)),
                 &run_path,
             )?;
+            let annotations_path = run_annotations_path(&spec.state_dir, &run.run_id);
+            if spec.include_annotations && annotations_path.is_file() {
+                copy_to_staging(
+                    &staging,
+                    &PathBuf::from("runs")
+                        .join(&run.run_id)
+                        .join(RUN_ANNOTATIONS_FILE_NAME),
+                    &annotations_path,
+                )?;
+            }
             if let Ok(record) = load_run_record(&spec.state_dir, &run.run_id) {
                 copy_snapshot_if_exists(
                     &staging,
@@ -277,6 +289,15 @@ with zipfile.ZipFile(sys.argv[1], "r") as z:
             "{\"metadata\":{\"run_id\":\"r1\",\"started_at\":\"x\",\"finished_at\":\"x\",\"exit_reason\":\"provider_error\"},\"cli\":{\"provider\":\"ollama\",\"base_url\":\"x\",\"model\":\"m\",\"trust_mode\":\"off\",\"allow_shell\":false,\"allow_write\":false,\"enable_write_tools\":false,\"max_tool_output_bytes\":0,\"max_read_bytes\":0,\"approval_mode\":\"interrupt\",\"auto_approve_scope\":\"run\",\"unsafe_mode\":false,\"no_limits\":false,\"unsafe_bypass_allow_flags\":false,\"stream\":false,\"max_context_chars\":0,\"compaction_mode\":\"off\",\"compaction_keep_last\":0,\"tool_result_persist\":\"digest\",\"hooks_mode\":\"off\",\"caps_mode\":\"off\",\"hooks_config_path\":\"\",\"hooks_strict\":false,\"hooks_timeout_ms\":0,\"hooks_max_stdout_bytes\":0,\"tool_args_strict\":\"on\",\"use_session_settings\":false,\"resolved_settings_source\":{},\"tui_enabled\":false,\"tui_refresh_ms\":0,\"tui_max_log_lines\":0,\"http_max_retries\":0,\"http_timeout_ms\":0,\"http_connect_timeout_ms\":0,\"http_stream_idle_timeout_ms\":0,\"http_max_response_bytes\":0,\"http_max_line_bytes\":0,\"tool_catalog\":[],\"policy_version\":null,\"includes_resolved\":[]},\"resolved_paths\":{\"state_dir\":\"x\",\"policy_path\":\"x\",\"approvals_path\":\"x\",\"audit_path\":\"x\"},\"policy_source\":\"none\",\"policy_hash_hex\":null,\"policy_version\":null,\"includes_resolved\":[],\"config_hash_hex\":\"x\",\"transcript\":[],\"tool_calls\":[],\"tool_decisions\":[],\"hook_report\":[],\"tool_catalog\":[],\"final_output\":\"\",\"error\":null}",
         )
         .expect("run write");
+        crate::store::update_run_annotations(
+            &state_dir,
+            "r1",
+            &crate::store::RunAnnotationUpdate {
+                add_tags: vec!["repro".to_string()],
+                ..Default::default()
+            },
+        )
+        .expect("annotate");
         let results = EvalResults {
             schema_version: "openagent.eval.v1".to_string(),
             created_at: "x".to_string(),
@@ -335,11 +356,15 @@ with zipfile.ZipFile(sys.argv[1], "r") as z:
             baseline_name: None,
             profile_name: None,
             profile_hash_hex: None,
+            include_annotations: true,
         })
         .expect("bundle");
         let names = list_zip_entries(&bundle_path).expect("entries");
         assert!(names.contains(&"manifest.json".to_string()));
         assert!(names.contains(&"results.json".to_string()));
         assert!(names.iter().any(|n| n.ends_with("runs/r1.json")));
+        assert!(names
+            .iter()
+            .any(|n| n.ends_with("runs/r1/annotations.json")));
     }
 }
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{Agent, INTERNAL_ENFORCE_IMPLEMENTATION_GUARD_FLAG};
+use crate::providers::scripted::{ScriptedProvider, ScriptedTurn};
+use crate::tools::builtin_tools_enabled;
+use crate::types::{Message, Role};
+
+/// One adversarial scenario: a scripted model, the workspace it runs in and
+/// the outcome the runtime must produce. Loaded from
+/// `tests/fixtures/failure_modes/<name>.yaml`; the file stem is the name.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FailureFixture {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub prompt: String,
+    /// Files written into the scratch workdir before the run.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    #[serde(default)]
+    pub config: FixtureConfig,
+    pub turns: Vec<ScriptedTurn>,
+    pub expect: FixtureExpectation,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixtureConfig {
+    #[serde(default = "default_max_steps")]
+    pub max_steps: usize,
+    /// Expose and allow write tools (`write_file`, `apply_patch`).
+    #[serde(default)]
+    pub allow_write: bool,
+    /// Treat the prompt as a file-edit task, as `eval` does.
+    #[serde(default)]
+    pub implementation_guard: bool,
+}
+
+impl Default for FixtureConfig {
+    fn default() -> Self {
+        Self {
+            max_steps: default_max_steps(),
+            allow_write: false,
+            implementation_guard: false,
+        }
+    }
+}
+
+fn default_max_steps() -> usize {
+    8
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixtureExpectation {
+    /// `AgentExitReason::as_str()` value, e.g. `planner_error`.
+    pub exit_reason: String,
+    /// Leading `CODE:` of the run error; omit for runs that must not error.
+    #[serde(default)]
+    pub error_code: Option<String>,
+    #[serde(default)]
+    pub error_contains: Option<String>,
+    #[serde(default)]
+    pub max_provider_calls: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureReport {
+    pub name: String,
+    pub expected_exit_reason: String,
+    pub exit_reason: String,
+    pub expected_error_code: Option<String>,
+    pub error_code: Option<String>,
+    pub error: Option<String>,
+    pub provider_calls: usize,
+    pub mismatches: Vec<String>,
+}
+
+impl FixtureReport {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+pub fn default_fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/failure_modes")
+}
+
+pub fn load_fixture(path: &Path) -> anyhow::Result<FailureFixture> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read fixture {}", path.display()))?;
+    let mut fixture: FailureFixture = serde_yaml::from_str(&raw)
+        .with_context(|| format!("failed to parse fixture {}", path.display()))?;
+    fixture.name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if fixture.turns.is_empty() {
+        return Err(anyhow!("fixture {} has no turns", path.display()));
+    }
+    Ok(fixture)
+}
+
+/// Every `*.yaml` fixture in `dir`, sorted by name.
+pub fn load_fixture_dir(dir: &Path) -> anyhow::Result<Vec<FailureFixture>> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read fixture dir {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "yaml"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.iter().map(|p| load_fixture(p)).collect()
+}
+
+/// The `CODE` in an error of the form `CODE: detail`.
+pub fn error_code(error: &str) -> Option<&str> {
+    let (code, _) = error.split_once(':')?;
+    (code.len() > 1
+        && code
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_'))
+    .then_some(code)
+}
+
+/// Runs the fixture against the standard safe configuration: host target in
+/// a scratch workdir, no shell, no gate prompts, hooks and compaction off.
+pub async fn run_fixture(fixture: &FailureFixture) -> anyhow::Result<FixtureReport> {
+    let scratch = std::env::temp_dir().join(format!(
+        "localagent-fixture-{}-{}",
+        fixture.name,
+        uuid::Uuid::new_v4()
+    ));
+    let result = run_fixture_in(fixture, &scratch).await;
+    let _ = std::fs::remove_dir_all(&scratch);
+    result
+}
+
+async fn run_fixture_in(fixture: &FailureFixture, workdir: &Path) -> anyhow::Result<FixtureReport> {
+    std::fs::create_dir_all(workdir).context("failed to create fixture workdir")?;
+    for (rel, content) in &fixture.files {
+        let path = workdir.join(rel);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+    }
+    let provider = ScriptedProvider::new(fixture.turns.clone());
+    let mut agent = safe_agent(provider, workdir, &fixture.config)?;
+    let mut injected = Vec::new();
+    if fixture.config.implementation_guard {
+        injected.push(Message {
+            role: Role::System,
+            content: Some(INTERNAL_ENFORCE_IMPLEMENTATION_GUARD_FLAG.to_string()),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        });
+    }
+    let outcome = agent.run(&fixture.prompt, Vec::new(), injected).await;
+    let provider_calls = agent.provider.calls();
+
+    let exit_reason = outcome.exit_reason.as_str().to_string();
+    let code = outcome
+        .error
+        .as_deref()
+        .and_then(error_code)
+        .map(str::to_string);
+    let expect = &fixture.expect;
+    let mut mismatches = Vec::new();
+    if exit_reason != expect.exit_reason {
+        mismatches.push(format!(
+            "exit_reason: expected {}, got {}",
+            expect.exit_reason, exit_reason
+        ));
+    }
+    if code != expect.error_code {
+        mismatches.push(format!(
+            "error_code: expected {}, got {}",
+            expect.error_code.as_deref().unwrap_or("-"),
+            code.as_deref().unwrap_or("-")
+        ));
+    }
+    if let Some(needle) = &expect.error_contains {
+        if !outcome
+            .error
+            .as_deref()
+            .unwrap_or("")
+            .contains(needle.as_str())
+        {
+            mismatches.push(format!("error does not contain '{needle}'"));
+        }
+    }
+    if let Some(max) = expect.max_provider_calls {
+        if provider_calls > max {
+            mismatches.push(format!(
+                "provider_calls: expected at most {max}, got {provider_calls}"
+            ));
+        }
+    }
+    Ok(FixtureReport {
+        name: fixture.name.clone(),
+        expected_exit_reason: expect.exit_reason.clone(),
+        exit_reason,
+        expected_error_code: expect.error_code.clone(),
+        error_code: code,
+        error: outcome.error,
+        provider_calls,
+        mismatches,
+    })
+}
+
+fn safe_agent(
+    provider: ScriptedProvider,
+    workdir: &Path,
+    config: &FixtureConfig,
+) -> anyhow::Result<Agent<ScriptedProvider>> {
+    Agent::builder(provider)
+        .model("scripted")
+        .workdir(workdir)
+        .allow_write(config.allow_write)
+        .enable_write_tools(config.allow_write)
+        .tools(builtin_tools_enabled(config.allow_write, false))
+        .max_steps(config.max_steps)
+        .build()
+}
+
+pub fn render_summary_table(reports: &[FixtureReport]) -> String {
+    let rows = reports
+        .iter()
+        .map(|r| {
+            [
+                r.name.clone(),
+                if r.passed() { "PASS" } else { "FAIL" }.to_string(),
+                r.exit_reason.clone(),
+                r.error_code.clone().unwrap_or_else(|| "-".to_string()),
+                r.provider_calls.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = ["FIXTURE", "RESULT", "EXIT_REASON", "ERROR_CODE", "CALLS"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(c, w)| format!("{c:<w$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut out = line(&header.map(str::to_string));
+    out.push('\n');
+    for (row, report) in rows.iter().zip(reports) {
+        out.push_str(&line(row));
+        out.push('\n');
+        for mismatch in &report.mismatches {
+            out.push_str(&format!("  ! {mismatch}\n"));
+        }
+    }
+    let passed = reports.iter().filter(|r| r.passed()).count();
+    out.push_str(&format!("{passed}/{} fixtures passed\n", reports.len()));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::error_code;
+
+    #[test]
+    fn error_code_takes_only_uppercase_prefixes() {
+        assert_eq!(
+            error_code("MODEL_EMPTY_RESPONSE: model returned 2 empty responses"),
+            Some("MODEL_EMPTY_RESPONSE")
+        );
+        assert_eq!(error_code("provider error: timed out"), None);
+        assert_eq!(error_code("no colon"), None);
+    }
+}
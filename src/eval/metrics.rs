@@ -39,10 +39,10 @@ pub fn derive_tool_retry_metrics(events: &[Event]) -> (u32, BTreeMap<String, u32
     let mut failures_by_class: BTreeMap<String, u32> = BTreeMap::new();
     for ev in events {
         match ev.kind {
-            EventKind::ToolRetry => {
-                if ev.data.get("action").and_then(|v| v.as_str()) == Some("retry") {
-                    retries = retries.saturating_add(1);
-                }
+            EventKind::ToolRetry
+                if ev.data.get("action").and_then(|v| v.as_str()) == Some("retry") =>
+            {
+                retries = retries.saturating_add(1);
             }
             EventKind::ToolExecEnd => {
                 let ok = ev.data.get("ok").and_then(|v| v.as_bool()).unwrap_or(true);
@@ -221,6 +221,7 @@ mod tests {
                 provider: crate::eval::types::EvalProviderMetrics {
                     http_retries: 1,
                     provider_errors: 0,
+                    retry_wait_ms: 0,
                 },
                 tool_retries: 2,
                 step_invariant_violations: 1,
@@ -255,6 +256,7 @@ mod tests {
                 provider: crate::eval::types::EvalProviderMetrics {
                     http_retries: 3,
                     provider_errors: 1,
+                    retry_wait_ms: 0,
                 },
                 tool_retries: 0,
                 step_invariant_violations: 0,
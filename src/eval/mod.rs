@@ -2,6 +2,7 @@ pub mod assert;
 pub mod baseline;
 pub mod bundle;
 pub mod cost;
+pub mod failure_modes;
 pub mod fixtures;
 pub mod fixtures_repo;
 pub mod metrics;
@@ -521,6 +521,7 @@ mod tests {
                     tool_retries: 0,
                     tool_failures_by_class: BTreeMap::new(),
                     step_invariant_violations: 0,
+                    hook_time_ms: 0,
                 }),
                 tokens: None,
                 estimated_cost_usd: None,
@@ -623,6 +624,7 @@ mod tests {
             worker_model: None,
             min_pass_rate: 0.0,
             fail_on_any: false,
+            require_exact_model: true,
             max_avg_steps: None,
             resolved_profile_name: None,
             resolved_profile_path: None,
@@ -715,6 +717,7 @@ mod tests {
             worker_model: None,
             min_pass_rate: 0.0,
             fail_on_any: false,
+            require_exact_model: true,
             max_avg_steps: None,
             resolved_profile_name: None,
             resolved_profile_path: None,
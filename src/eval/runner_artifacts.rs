@@ -39,6 +39,7 @@ pub(crate) fn write_synthetic_error_artifact(
         tool_decisions: Vec::new(),
         compaction_settings: CompactionSettings {
             max_context_chars: config.max_context_chars,
+            max_context_tokens: None,
             mode: config.compaction_mode,
             keep_last: config.compaction_keep_last,
             tool_result_persist: config.tool_result_persist,
@@ -48,8 +49,16 @@ pub(crate) fn write_synthetic_error_artifact(
         hook_invocations: Vec::new(),
         provider_retry_count: 0,
         provider_error_count: 0,
+        provider_retry_wait_ms: 0,
         token_usage: None,
         taint: None,
+        digest_refetch: None,
+        tool_result_artifacts: Vec::new(),
+        model_served: None,
+        subtask_run_ids: Vec::new(),
+        post_run_hook_error: None,
+        timeline: Vec::new(),
+        steps: Vec::new(),
     };
     let _ = write_run_artifact_for_eval(
         config,
@@ -105,6 +114,9 @@ pub(crate) fn write_run_artifact_for_eval(
         allow_write: config.allow_write,
         enable_write_tools: config.enable_write_tools,
         exec_target: "host".to_string(),
+        read_allowlist: Vec::new(),
+        shell_allowlist: Vec::new(),
+        max_tool_calls: Default::default(),
         docker_image: None,
         docker_workdir: None,
         docker_network: None,
@@ -173,6 +185,7 @@ pub(crate) fn write_run_artifact_for_eval(
         mcp_tool_catalog_hash_hex: None,
         mcp_servers: Vec::new(),
         mcp_config_path: None,
+        mcp_roots: Vec::new(),
         policy_version: policy.version,
         includes_resolved: policy.includes_resolved.clone(),
         mcp_allowlist: policy.mcp_allowlist.clone(),
@@ -215,6 +228,10 @@ pub(crate) fn write_run_artifact_for_eval(
         profile_source: None,
         profile_hash_hex: None,
         activated_packs: Vec::new(),
+        context_pack: None,
+        prompt_template: None,
+        learned_guidance: Vec::new(),
+        mcp_server_launches: Vec::new(),
     };
     let fingerprint = ConfigFingerprintV1 {
         schema_version: "openagent.confighash.v1".to_string(),
@@ -372,6 +389,10 @@ pub(crate) fn write_run_artifact_for_eval(
         None,
         Vec::new(),
         None,
+        None,
+        None,
+        None,
+        None,
     )?;
     Ok(())
 }
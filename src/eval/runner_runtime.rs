@@ -20,7 +20,8 @@ use crate::eval::types::{
 };
 use crate::events::{Event, EventSink};
 use crate::gate::{
-    compute_policy_hash_hex, GateContext, NoGate, ProviderKind, ToolGate, TrustGate, TrustMode,
+    compute_policy_hash_hex, GateContext, NoGate, ProviderKind, RunConfig, ToolGate, TrustGate,
+    TrustMode,
 };
 use crate::hooks::config::HooksMode;
 use crate::hooks::runner::{HookManager, HookRuntimeConfig};
@@ -276,9 +277,14 @@ fn make_provider(
     http: HttpConfig,
 ) -> anyhow::Result<EvalProvider> {
     match provider {
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => Ok(EvalProvider::OpenAiCompat(
-            OpenAiCompatProvider::new(provider, base_url.to_string(), api_key, http)?,
-        )),
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
+            Ok(EvalProvider::OpenAiCompat(OpenAiCompatProvider::new(
+                provider,
+                base_url.to_string(),
+                api_key,
+                http,
+            )?))
+        }
         ProviderKind::Ollama => Ok(EvalProvider::Ollama(OllamaProvider::new(
             base_url.to_string(),
             http,
@@ -338,30 +344,25 @@ pub(crate) async fn run_single(
     } else {
         task.prompt.clone()
     };
-    let gate_ctx = GateContext {
+    let gate_ctx = GateContext::from_run_config(&RunConfig {
         workdir: workdir.to_path_buf(),
         allow_shell: config.allow_shell,
+        shell_allowlist: None,
         allow_write: config.allow_write,
-        approval_mode: config.approval_mode,
-        auto_approve_scope: config.auto_approve_scope,
-        approval_key_version: config.approval_key,
-        tool_schema_hashes: std::collections::BTreeMap::new(),
-        hooks_config_hash_hex: None,
-        planner_hash_hex: None,
+        enable_write_tools: config.enable_write_tools,
         unsafe_mode: config.unsafe_mode,
         unsafe_bypass_allow_flags: config.unsafe_bypass_allow_flags,
-        run_id: None,
-        enable_write_tools: config.enable_write_tools,
         max_tool_output_bytes: if config.no_limits { 0 } else { 200_000 },
         max_read_bytes: if config.no_limits { 0 } else { 200_000 },
         provider: config.provider,
         model: model.to_string(),
         exec_target: ExecTargetKind::Host,
+        approval_mode: config.approval_mode,
+        auto_approve_scope: config.auto_approve_scope,
+        approval_key_version: config.approval_key,
         taint_enabled: false,
         taint_mode: crate::taint::TaintMode::Propagate,
-        taint_overall: crate::taint::TaintLevel::Clean,
-        taint_sources: Vec::new(),
-    };
+    })?;
     let gate_build = build_gate(config.trust, state_paths)?;
     let policy_hash_hex = gate_build.policy_hash_hex.clone();
     let policy_source = gate_build.policy_source.to_string();
@@ -378,6 +379,7 @@ pub(crate) async fn run_single(
         includes_count: includes_resolved.len(),
         includes_resolved: includes_resolved.clone(),
         mcp_allowlist: mcp_allowlist.clone(),
+        policy_hash_hex: policy_hash_hex.clone(),
     });
 
     let mcp_config_path = config
@@ -462,13 +464,23 @@ pub(crate) async fn run_single(
             workdir: workdir.to_path_buf(),
             allow_shell: config.allow_shell,
             allow_shell_in_workdir_only: false,
+            shell_allowlist: None,
             allow_write: config.allow_write,
             max_tool_output_bytes: if config.no_limits { 0 } else { 200_000 },
             max_read_bytes: if config.no_limits { 0 } else { 200_000 },
             unsafe_bypass_allow_flags: config.unsafe_bypass_allow_flags,
+            restrict_to_workdir: !config.unsafe_bypass_allow_flags,
+            tool_timeout_ms: (config.tool_exec_timeout_ms > 0)
+                .then_some(config.tool_exec_timeout_ms),
+            stream_tool_output: false,
+            dry_run_writes: false,
+            write_checkpoint: None,
             tool_args_strict: config.tool_args_strict,
             exec_target_kind: ExecTargetKind::Host,
             exec_target: std::sync::Arc::new(HostTarget),
+            read_allowlist: None,
+            run_artifacts: None,
+            secret_redactor: None,
         },
         gate: gate_build.gate,
         gate_ctx: GateContext {
@@ -493,6 +505,7 @@ pub(crate) async fn run_single(
         })),
         compaction_settings: CompactionSettings {
             max_context_chars: config.max_context_chars,
+            max_context_tokens: None,
             mode: config.compaction_mode,
             keep_last: config.compaction_keep_last,
             tool_result_persist: config.tool_result_persist,
@@ -503,6 +516,9 @@ pub(crate) async fn run_single(
             strict: config.hooks_strict,
             timeout_ms: config.hooks_timeout_ms,
             max_stdout_bytes: config.hooks_max_stdout_bytes,
+            max_invocations_per_run: 0,
+            max_cumulative_ms: 0,
+            budget_strict: false,
         })?,
         policy_loaded: policy_loaded_info,
         policy_for_taint: gate_build.policy_for_exposure.clone(),
@@ -517,6 +533,7 @@ pub(crate) async fn run_single(
         current_plan: Vec::new(),
         tool_call_budget: ToolCallBudget {
             max_wall_time_ms: task_max_wall_time_ms,
+            deadline_ms: 0,
             max_total_tool_calls: 0,
             max_mcp_calls: config.max_mcp_calls,
             max_filesystem_read_calls: 0,
@@ -524,6 +541,8 @@ pub(crate) async fn run_single(
             max_shell_calls: 0,
             max_network_calls: 0,
             max_browser_calls: 0,
+            per_tool: Default::default(),
+            max_compaction_summaries: 0,
             tool_exec_timeout_ms: config.tool_exec_timeout_ms,
             post_write_verify_timeout_ms: config.post_write_verify_timeout_ms,
         },
@@ -531,6 +550,28 @@ pub(crate) async fn run_single(
         operator_queue: crate::operator_queue::PendingMessageQueue::default(),
         operator_queue_limits: crate::operator_queue::QueueLimits::default(),
         operator_queue_rx: None,
+        attribution: None,
+        max_consecutive_empty_responses: 2,
+        digest_refetch_tracker: crate::compaction::DigestRefetchTracker::default(),
+        require_exact_model: config.require_exact_model,
+        served_model: None,
+        provider_retry_wait_ms: 0,
+        mcp_root_map: Default::default(),
+        timeline_recorder: Default::default(),
+        gate_context_snapshot: None,
+        skip_unevaluated_gate_snapshots: false,
+        run_deadline: None,
+        parallel_readonly_tools: false,
+        approval_diff_max_lines: crate::gate::DEFAULT_APPROVAL_DIFF_MAX_LINES,
+        parallel_tool_batch: None,
+        token_counter: Box::new(crate::compaction::HeuristicTokenCounter::default()),
+        final_output_schema: None,
+        subtask_depth: 0,
+        subtask_run_ids: Vec::new(),
+        run_started: None,
+        operator_cancel: None,
+        plan_step_queue: Vec::new(),
+        completed_plan_step_ids: std::collections::BTreeSet::new(),
     };
     let session_messages = Vec::new();
     let mut injected_messages = instruction_resolution.messages.clone();
@@ -546,6 +587,13 @@ pub(crate) async fn run_single(
         .await;
     let wall_time_ms = run_started.elapsed().as_millis() as u64;
     let mut failures = evaluate_assertions(&task.assertions, workdir, &outcome);
+    if let Some(err) = outcome
+        .error
+        .as_deref()
+        .filter(|e| e.starts_with("MODEL_MISMATCH"))
+    {
+        failures.insert(0, err.to_string());
+    }
     let verifier_started = std::time::Instant::now();
     let verifier = run_task_verifier(task.verifier.as_ref(), workdir, 200_000)?;
     let verifier_time_ms = verifier_started.elapsed().as_millis() as u64;
@@ -628,10 +676,12 @@ pub(crate) async fn run_single(
         provider: EvalProviderMetrics {
             http_retries: outcome.provider_retry_count,
             provider_errors: outcome.provider_error_count,
+            retry_wait_ms: outcome.provider_retry_wait_ms,
         },
         tool_retries,
         tool_failures_by_class,
         step_invariant_violations,
+        hook_time_ms: outcome.hook_stats().total_duration_ms as u64,
     };
 
     write_run_artifact_for_eval(
@@ -72,6 +72,7 @@ pub struct EvalConfig {
     pub worker_model: Option<String>,
     pub min_pass_rate: f64,
     pub fail_on_any: bool,
+    pub require_exact_model: bool,
     pub max_avg_steps: Option<f64>,
     pub resolved_profile_name: Option<String>,
     pub resolved_profile_path: Option<String>,
@@ -378,6 +379,8 @@ pub struct EvalRunMetrics {
     pub tool_failures_by_class: BTreeMap<String, u32>,
     #[serde(default)]
     pub step_invariant_violations: u32,
+    #[serde(default)]
+    pub hook_time_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -551,6 +554,8 @@ pub fn flatten_ux_metric_rows(ux: &EvalUxRunMetrics) -> Vec<EvalMetricRow> {
 pub struct EvalProviderMetrics {
     pub http_retries: u32,
     pub provider_errors: u32,
+    #[serde(default)]
+    pub retry_wait_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -5,6 +5,12 @@ use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+mod payloads;
+mod reader;
+pub use payloads::*;
+#[allow(unused_imports)]
+pub use reader::{read_events, EventStreamError, EventStreamReader};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EventKind {
@@ -13,18 +19,28 @@ pub enum EventKind {
     ModelRequestStart,
     ModelDelta,
     ModelResponseEnd,
+    ModelMismatch,
     ToolCallDetected,
+    ToolCallStreamed,
+    ToolCallNearMiss,
     ToolDecision,
     ToolExecTarget,
     ToolExecStart,
     ToolExecEnd,
     ShellOutputChunk,
+    ToolExecProgress,
     PlanUpdated,
     PostWriteVerifyStart,
     PostWriteVerifyEnd,
+    WriteSkippedDryRun,
+    SecretRedacted,
+    AttributionInjected,
+    AttributionSkipped,
     ToolRetry,
     TaintUpdated,
     CompactionPerformed,
+    ContextOverflowRecovered,
+    ToolResultRefetched,
     PolicyLoaded,
     PlannerStart,
     PlannerEnd,
@@ -40,6 +56,10 @@ pub enum EventKind {
     HookStart,
     HookEnd,
     HookError,
+    HookBudgetExhausted,
+    HookRewroteArgs,
+    HooksConfigReloaded,
+    HooksConfigReloadFailed,
     ProviderRetry,
     ProviderError,
     ReproSnapshot,
@@ -47,12 +67,18 @@ pub enum EventKind {
     McpServerStop,
     McpProgress,
     McpCancelled,
+    McpServerDegraded,
+    McpServerReconnected,
+    ToolOutputTruncated,
     McpPinned,
     McpDrift,
+    McpMetadataSanitized,
+    McpToolRejected,
     PackActivated,
     QueueSubmitted,
     QueueDelivered,
     QueueInterrupt,
+    QueueOverflow,
     PhaseEntered,
     PhaseExited,
     CheckpointSaved,
@@ -65,6 +91,10 @@ pub enum EventKind {
     ExecutionTierSelected,
     LearningCaptured,
     LearningPromoted,
+    SessionRecovered,
+    StateLockReclaimed,
+    DeadlineReached,
+    RunResumed,
     Error,
 }
 
@@ -75,18 +105,43 @@ pub struct Event {
     pub step: u32,
     pub kind: EventKind,
     pub data: Value,
+    /// Set by [`JsonlFileSink`] when the event is written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Position in the events file, starting at 1; see [`read_events`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
 }
 
 impl Event {
-    pub fn new(run_id: String, step: u32, kind: EventKind, data: Value) -> Self {
+    pub const SCHEMA_VERSION: &'static str = "openagent.event.v1";
+
+    pub fn new(run_id: String, step: u32, payload: impl Into<EventPayload>) -> Self {
+        let payload = payload.into();
+        let kind = payload.kind();
+        let data = serde_json::to_value(&payload).unwrap_or_default();
+        Self::new_raw(run_id, step, kind, data)
+    }
+
+    /// Builds an event from untyped `data`, for kinds without a typed payload
+    /// and for replaying recorded events.
+    pub fn new_raw(run_id: String, step: u32, kind: EventKind, data: Value) -> Self {
         Self {
             ts: crate::trust::now_rfc3339(),
             run_id,
             step,
             kind,
             data,
+            schema: None,
+            seq: None,
         }
     }
+
+    /// Parses `data` as the typed payload for this event's kind. Returns
+    /// `None` for kinds without a payload or data that does not match it.
+    pub fn payload(&self) -> Option<EventPayload> {
+        EventPayload::from_data(&self.kind, &self.data)?.ok()
+    }
 }
 
 pub trait EventSink: Send {
@@ -121,6 +176,7 @@ impl EventSink for StdoutSink {
 
 pub struct JsonlFileSink {
     file: std::fs::File,
+    seq: u64,
 }
 
 impl JsonlFileSink {
@@ -133,7 +189,10 @@ impl JsonlFileSink {
             .append(true)
             .open(path)
             .with_context(|| format!("failed to open events file {}", path.display()))?;
-        Ok(Self { file })
+        Ok(Self {
+            file,
+            seq: reader::last_seq_in_file(path),
+        })
     }
 }
 
@@ -326,20 +385,61 @@ impl EventSink for JsonStdoutProjectedSink {
 }
 
 impl EventSink for JsonlFileSink {
-    fn emit(&mut self, event: Event) -> anyhow::Result<()> {
+    fn emit(&mut self, mut event: Event) -> anyhow::Result<()> {
+        self.seq += 1;
+        event.schema = Some(Event::SCHEMA_VERSION.to_string());
+        event.seq = Some(self.seq);
         let line = serde_json::to_string(&event)?;
         writeln!(self.file, "{line}")?;
         Ok(())
     }
 }
 
+/// What [`MultiSink`] does when one of its children fails to take an event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultiSinkFailurePolicy {
+    /// Stop at the first failing child and return its error; later children
+    /// do not see the event.
+    #[default]
+    FailFast,
+    /// Deliver to every child regardless, recording failures instead of
+    /// returning them.
+    Continue,
+}
+
+/// A child failure recorded under [`MultiSinkFailurePolicy::Continue`].
+#[derive(Debug, Clone)]
+pub struct SinkFailure {
+    /// Position of the child in attach order.
+    pub sink_index: usize,
+    pub event_kind: EventKind,
+    pub message: String,
+}
+
+/// Fans each event out to its children in attach order.
 pub struct MultiSink {
     sinks: Vec<Box<dyn EventSink>>,
+    policy: MultiSinkFailurePolicy,
+    failures: Vec<SinkFailure>,
 }
 
 impl MultiSink {
     pub fn new() -> Self {
-        Self { sinks: Vec::new() }
+        Self {
+            sinks: Vec::new(),
+            policy: MultiSinkFailurePolicy::default(),
+            failures: Vec::new(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: MultiSinkFailurePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_sink(mut self, sink: Box<dyn EventSink>) -> Self {
+        self.sinks.push(sink);
+        self
     }
 
     pub fn push(&mut self, sink: Box<dyn EventSink>) {
@@ -349,6 +449,15 @@ impl MultiSink {
     pub fn is_empty(&self) -> bool {
         self.sinks.is_empty()
     }
+
+    pub fn len(&self) -> usize {
+        self.sinks.len()
+    }
+
+    /// Failures swallowed so far under [`MultiSinkFailurePolicy::Continue`].
+    pub fn failures(&self) -> &[SinkFailure] {
+        &self.failures
+    }
 }
 
 impl Default for MultiSink {
@@ -359,13 +468,43 @@ impl Default for MultiSink {
 
 impl EventSink for MultiSink {
     fn emit(&mut self, event: Event) -> anyhow::Result<()> {
-        for sink in &mut self.sinks {
-            sink.emit(event.clone())?;
+        for (sink_index, sink) in self.sinks.iter_mut().enumerate() {
+            let Err(e) = sink.emit(event.clone()) else {
+                continue;
+            };
+            match self.policy {
+                MultiSinkFailurePolicy::FailFast => return Err(e),
+                MultiSinkFailurePolicy::Continue => self.failures.push(SinkFailure {
+                    sink_index,
+                    event_kind: event.kind.clone(),
+                    message: format!("{e:#}"),
+                }),
+            }
         }
         Ok(())
     }
 }
 
+/// Forwards events to an in-process consumer. Fails once the receiver is
+/// dropped so a [`MultiSink`] can record or surface the disconnect.
+pub struct ChannelSink {
+    tx: tokio::sync::mpsc::UnboundedSender<Event>,
+}
+
+impl ChannelSink {
+    pub fn new(tx: tokio::sync::mpsc::UnboundedSender<Event>) -> Self {
+        Self { tx }
+    }
+}
+
+impl EventSink for ChannelSink {
+    fn emit(&mut self, event: Event) -> anyhow::Result<()> {
+        self.tx
+            .send(event)
+            .map_err(|_| anyhow::anyhow!("event channel receiver dropped"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::tempdir;
@@ -373,12 +512,26 @@ mod tests {
     use serde_json::Value;
 
     use super::{
-        project_event_v1, Event, EventKind, EventSink, JsonlFileSink, ProjectedRunEventV1,
+        project_event_v1, ChannelSink, Event, EventKind, EventPayload, EventSink, JsonlFileSink,
+        LearningPromotedPayload, McpDriftPayload, MultiSink, MultiSinkFailurePolicy,
+        PlannerEndPayload, ProjectedRunEventV1, StepBlockedPayload, ToolDecisionGateContext,
+        ToolDecisionPayload, ToolExecEndPayload,
     };
+    use crate::types::SideEffects;
+
+    fn assert_same_wire_json(typed: Event, kind: EventKind, legacy: Value) {
+        let mut legacy = Event::new_raw(typed.run_id.clone(), typed.step, kind, legacy);
+        legacy.ts = typed.ts.clone();
+        assert_eq!(
+            serde_json::to_string(&typed).expect("typed"),
+            serde_json::to_string(&legacy).expect("legacy")
+        );
+        assert!(typed.payload().is_some(), "payload should parse back");
+    }
 
     #[test]
     fn event_serializes() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "run1".to_string(),
             0,
             EventKind::RunStart,
@@ -394,14 +547,14 @@ mod tests {
         let tmp = tempdir().expect("tempdir");
         let path = tmp.path().join("events.jsonl");
         let mut sink = JsonlFileSink::new(&path).expect("sink");
-        sink.emit(Event::new(
+        sink.emit(Event::new_raw(
             "r".to_string(),
             0,
             EventKind::RunStart,
             serde_json::json!({}),
         ))
         .expect("emit1");
-        sink.emit(Event::new(
+        sink.emit(Event::new_raw(
             "r".to_string(),
             1,
             EventKind::RunEnd,
@@ -414,7 +567,7 @@ mod tests {
 
     #[test]
     fn taint_updated_kind_serializes() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "r".to_string(),
             1,
             EventKind::TaintUpdated,
@@ -426,7 +579,7 @@ mod tests {
 
     #[test]
     fn repro_snapshot_kind_serializes() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "r".to_string(),
             1,
             EventKind::ReproSnapshot,
@@ -438,7 +591,7 @@ mod tests {
 
     #[test]
     fn pack_activated_kind_serializes() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "r".to_string(),
             1,
             EventKind::PackActivated,
@@ -454,8 +607,9 @@ mod tests {
             EventKind::QueueSubmitted,
             EventKind::QueueDelivered,
             EventKind::QueueInterrupt,
+            EventKind::QueueOverflow,
         ] {
-            let ev = Event::new(
+            let ev = Event::new_raw(
                 "r".to_string(),
                 1,
                 kind,
@@ -468,7 +622,7 @@ mod tests {
 
     #[test]
     fn learning_captured_kind_serializes() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "learn".to_string(),
             0,
             EventKind::LearningCaptured,
@@ -480,7 +634,7 @@ mod tests {
 
     #[test]
     fn learning_promoted_kind_serializes() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "learn".to_string(),
             0,
             EventKind::LearningPromoted,
@@ -492,7 +646,7 @@ mod tests {
 
     #[test]
     fn projection_ignores_unmapped_kind() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "r".to_string(),
             1,
             EventKind::McpProgress,
@@ -503,7 +657,7 @@ mod tests {
 
     #[test]
     fn projection_includes_run_finished_required_fields() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "r".to_string(),
             2,
             EventKind::RunEnd,
@@ -528,7 +682,7 @@ mod tests {
     #[test]
     fn projection_truncates_large_content_preview() {
         let huge = "a".repeat(5000);
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "r".to_string(),
             3,
             EventKind::ToolExecEnd,
@@ -554,7 +708,7 @@ mod tests {
 
     #[test]
     fn projection_serializes_parseable_json_line() {
-        let ev = Event::new(
+        let ev = Event::new_raw(
             "r".to_string(),
             0,
             EventKind::RunStart,
@@ -565,4 +719,298 @@ mod tests {
         let parsed: ProjectedRunEventV1 = serde_json::from_str(&line).expect("parse");
         assert_eq!(parsed.event_type, "run_started");
     }
+
+    #[test]
+    fn typed_payloads_serialize_like_legacy_json_data() {
+        assert_same_wire_json(
+            Event::new(
+                "r".to_string(),
+                2,
+                ToolDecisionPayload {
+                    tool_call_id: "tc1".to_string(),
+                    name: "shell".to_string(),
+                    decision: "allow".to_string(),
+                    reason: None,
+                    approval_id: Some(None),
+                    approval_key: Some(None),
+                    source: Some(Some("policy".to_string())),
+                    planner_hash_hex: Some(None),
+                    side_effects: Some(SideEffects::ShellExec),
+                    gate: Some(Box::new(ToolDecisionGateContext {
+                        approval_key_version: None,
+                        tool_schema_hash_hex: Some("abc".to_string()),
+                        hooks_config_hash_hex: None,
+                        exec_target: Some("host".to_string()),
+                        taint_overall: "clean".to_string(),
+                        taint_enforced: false,
+                        escalated: false,
+                        escalation_reason: None,
+                        tool_args_strict: "on".to_string(),
+                    })),
+                    ..ToolDecisionPayload::default()
+                },
+            ),
+            EventKind::ToolDecision,
+            serde_json::json!({
+                "tool_call_id": "tc1",
+                "name": "shell",
+                "decision": "allow",
+                "approval_id": null,
+                "approval_key": null,
+                "reason": null,
+                "source": "policy",
+                "approval_key_version": null,
+                "tool_schema_hash_hex": "abc",
+                "hooks_config_hash_hex": null,
+                "planner_hash_hex": null,
+                "exec_target": "host",
+                "taint_overall": "clean",
+                "taint_enforced": false,
+                "escalated": false,
+                "escalation_reason": null,
+                "side_effects": SideEffects::ShellExec,
+                "tool_args_strict": "on"
+            }),
+        );
+        assert_same_wire_json(
+            Event::new(
+                "r".to_string(),
+                3,
+                ToolExecEndPayload {
+                    tool_call_id: "tc1".to_string(),
+                    name: "shell".to_string(),
+                    ok: true,
+                    truncated: false,
+                    retry_count: Some(0),
+                    failure_class: Some(None),
+                    error_code: Some(None),
+                    ..ToolExecEndPayload::default()
+                },
+            ),
+            EventKind::ToolExecEnd,
+            serde_json::json!({
+                "tool_call_id": "tc1",
+                "name": "shell",
+                "ok": true,
+                "truncated": false,
+                "retry_count": 0,
+                "failure_class": null,
+                "error_code": null
+            }),
+        );
+        assert_same_wire_json(
+            Event::new(
+                "r".to_string(),
+                4,
+                StepBlockedPayload {
+                    step_id: Some("S1".to_string()),
+                    reason: Some("retry_limit_exceeded".to_string()),
+                    retry_count: Some(3),
+                    ..StepBlockedPayload::default()
+                },
+            ),
+            EventKind::StepBlocked,
+            serde_json::json!({
+                "step_id": "S1",
+                "reason": "retry_limit_exceeded",
+                "retry_count": 3
+            }),
+        );
+        assert_same_wire_json(
+            Event::new(
+                "r".to_string(),
+                0,
+                PlannerEndPayload {
+                    phase: None,
+                    ok: false,
+                    planner_hash_hex: "h".to_string(),
+                    error_short: Some(String::new()),
+                    lineage_parent_plan_hash_hex: None,
+                },
+            ),
+            EventKind::PlannerEnd,
+            serde_json::json!({"ok": false, "planner_hash_hex": "h", "error_short": ""}),
+        );
+        assert_same_wire_json(
+            Event::new(
+                "r".to_string(),
+                5,
+                McpDriftPayload {
+                    tool_call_id: "tc2".to_string(),
+                    name: "mcp.stub.echo".to_string(),
+                    expected_hash_hex: "e".to_string(),
+                    actual_hash_hex: Some("a".to_string()),
+                    catalog_hash_expected: "e".to_string(),
+                    catalog_hash_live: Some("a".to_string()),
+                    catalog_drift: Some(true),
+                    docs_hash_expected: Some(None),
+                    docs_drift: Some(false),
+                    enforcement: "hard".to_string(),
+                    codes: vec!["MCP_CATALOG_DRIFT".to_string()],
+                    primary_code: "MCP_CATALOG_DRIFT".to_string(),
+                    ..McpDriftPayload::default()
+                },
+            ),
+            EventKind::McpDrift,
+            serde_json::json!({
+                "tool_call_id": "tc2",
+                "name": "mcp.stub.echo",
+                "expected_hash_hex": "e",
+                "actual_hash_hex": "a",
+                "catalog_hash_expected": "e",
+                "catalog_hash_live": "a",
+                "catalog_drift": true,
+                "docs_hash_expected": null,
+                "docs_drift": false,
+                "enforcement": "hard",
+                "codes": ["MCP_CATALOG_DRIFT"],
+                "primary_code": "MCP_CATALOG_DRIFT"
+            }),
+        );
+        assert_same_wire_json(
+            Event::new(
+                "learn:l1".to_string(),
+                0,
+                LearningPromotedPayload {
+                    schema: "openagent.learning_promoted.v1".to_string(),
+                    learning_id: "l1".to_string(),
+                    entry_hash_hex: "x".to_string(),
+                    target: "check".to_string(),
+                    target_path: ".localagent/checks/a.md".to_string(),
+                    forced: false,
+                    target_file_sha256_hex: "y".to_string(),
+                    slug: Some("a".to_string()),
+                    pack_id: None,
+                    noop: None,
+                },
+            ),
+            EventKind::LearningPromoted,
+            serde_json::json!({
+                "schema": "openagent.learning_promoted.v1",
+                "learning_id": "l1",
+                "entry_hash_hex": "x",
+                "target": "check",
+                "target_path": ".localagent/checks/a.md",
+                "forced": false,
+                "target_file_sha256_hex": "y",
+                "slug": "a"
+            }),
+        );
+    }
+
+    #[test]
+    fn payload_parses_recorded_data_and_skips_untyped_kinds() {
+        let ev = Event::new_raw(
+            "r".to_string(),
+            1,
+            EventKind::StepBlocked,
+            serde_json::json!({"reason": "invalid_done_transition", "step_id": "S2"}),
+        );
+        match ev.payload() {
+            Some(EventPayload::StepBlocked(p)) => {
+                assert_eq!(p.reason.as_deref(), Some("invalid_done_transition"));
+                assert_eq!(p.step_id.as_deref(), Some("S2"));
+            }
+            other => panic!("unexpected payload: {other:?}"),
+        }
+        let untyped = Event::new_raw(
+            "r".to_string(),
+            1,
+            EventKind::CheckpointSaved,
+            serde_json::json!({}),
+        );
+        assert!(untyped.payload().is_none());
+        let mismatched = Event::new_raw(
+            "r".to_string(),
+            1,
+            EventKind::RunEnd,
+            serde_json::json!({"exit_reason": 7}),
+        );
+        assert!(mismatched.payload().is_none());
+    }
+
+    struct RecordingSink {
+        seen: std::sync::Arc<std::sync::Mutex<Vec<u32>>>,
+        fail_on_step: Option<u32>,
+    }
+
+    impl EventSink for RecordingSink {
+        fn emit(&mut self, event: Event) -> anyhow::Result<()> {
+            if self.fail_on_step == Some(event.step) {
+                anyhow::bail!("sink down at step {}", event.step);
+            }
+            self.seen.lock().expect("lock").push(event.step);
+            Ok(())
+        }
+    }
+
+    fn recording(
+        fail_on_step: Option<u32>,
+    ) -> (RecordingSink, std::sync::Arc<std::sync::Mutex<Vec<u32>>>) {
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        (
+            RecordingSink {
+                seen: seen.clone(),
+                fail_on_step,
+            },
+            seen,
+        )
+    }
+
+    fn step_event(step: u32) -> Event {
+        Event::new_raw(
+            "r".to_string(),
+            step,
+            EventKind::StepStarted,
+            serde_json::json!({}),
+        )
+    }
+
+    #[test]
+    fn multi_sink_continue_keeps_delivering_around_a_failing_child() {
+        let (first, first_seen) = recording(Some(1));
+        let (second, second_seen) = recording(None);
+        let mut multi = MultiSink::new()
+            .with_policy(MultiSinkFailurePolicy::Continue)
+            .with_sink(Box::new(first))
+            .with_sink(Box::new(second));
+        for step in 0..4 {
+            multi
+                .emit(step_event(step))
+                .expect("continue mode swallows");
+        }
+        assert_eq!(*first_seen.lock().expect("lock"), vec![0, 2, 3]);
+        assert_eq!(*second_seen.lock().expect("lock"), vec![0, 1, 2, 3]);
+        assert_eq!(multi.failures().len(), 1);
+        assert_eq!(multi.failures()[0].sink_index, 0);
+        assert!(multi.failures()[0].message.contains("sink down at step 1"));
+    }
+
+    #[test]
+    fn multi_sink_fail_fast_stops_at_first_failing_child() {
+        let (first, _) = recording(Some(1));
+        let (second, second_seen) = recording(None);
+        let mut multi = MultiSink::new()
+            .with_sink(Box::new(first))
+            .with_sink(Box::new(second));
+        multi.emit(step_event(0)).expect("ok");
+        assert!(multi.emit(step_event(1)).is_err());
+        assert_eq!(*second_seen.lock().expect("lock"), vec![0]);
+        assert!(multi.failures().is_empty());
+    }
+
+    #[test]
+    fn channel_sink_forwards_in_order_and_fails_once_receiver_drops() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut sink = ChannelSink::new(tx);
+        for step in 0..3 {
+            sink.emit(step_event(step)).expect("send");
+        }
+        let steps = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|e| e.step)
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec![0, 1, 2]);
+        drop(rx);
+        assert!(sink.emit(step_event(3)).is_err());
+    }
 }
@@ -0,0 +1,1068 @@
+//! Typed `data` payloads for [`EventKind`]s the runtime emits.
+//!
+//! Each payload serializes to the flat object previously written as `data`,
+//! so JSONL logs and projections keep their shape. Fields that some emit
+//! sites omit are skipped when `None`; fields that are written as `null` stay
+//! plain `Option`s. A few fields are omitted by some sites and written as a
+//! possibly-null value by others; those use [`MaybeNull`].
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::EventKind;
+use crate::agent::task_contract::{
+    AllowedToolsSemantics, ContractValueSource, ValidationRequirement,
+};
+use crate::attribution::AttributionPlacement;
+use crate::mcp::sanitize::{McpMetadataAction, McpToolRejectReason};
+use crate::operator_queue::{DeliveryBoundary, QueueMessageKind, QueueOverflowPolicy};
+use crate::providers::http::ProviderErrorKind;
+use crate::store::ExecutionTier;
+use crate::trust::policy::McpAllowSummary;
+use crate::types::{SideEffects, TokenUsage};
+
+/// `None` omits the field; `Some(None)` writes `null`.
+pub type MaybeNull<T> = Option<Option<T>>;
+
+macro_rules! event_payloads {
+    ($($kind:ident => $payload:ident),+ $(,)?) => {
+        /// Typed event data, one variant per emitted [`EventKind`].
+        #[derive(Debug, Clone, Serialize)]
+        #[serde(untagged)]
+        pub enum EventPayload {
+            $($kind($payload),)+
+        }
+
+        impl EventPayload {
+            pub fn kind(&self) -> EventKind {
+                match self {
+                    $(Self::$kind(_) => EventKind::$kind,)+
+                }
+            }
+
+            /// Parses `data` as the payload for `kind`. Returns `None` for kinds
+            /// that have no typed payload.
+            pub fn from_data(kind: &EventKind, data: &Value) -> Option<serde_json::Result<Self>> {
+                match kind {
+                    $(EventKind::$kind => Some($payload::deserialize(data).map(Self::$kind)),)+
+                    _ => None,
+                }
+            }
+        }
+
+        $(
+            impl From<$payload> for EventPayload {
+                fn from(payload: $payload) -> Self {
+                    Self::$kind(payload)
+                }
+            }
+        )+
+    };
+}
+
+event_payloads! {
+    RunStart => RunStartPayload,
+    RunEnd => RunEndPayload,
+    ModelRequestStart => ModelRequestStartPayload,
+    ModelDelta => ModelDeltaPayload,
+    ModelResponseEnd => ModelResponseEndPayload,
+    ModelMismatch => ModelMismatchPayload,
+    ToolCallDetected => ToolCallDetectedPayload,
+    ToolCallStreamed => ToolCallStreamedPayload,
+    ToolCallNearMiss => ToolCallNearMissPayload,
+    ToolDecision => ToolDecisionPayload,
+    ToolExecTarget => ToolExecTargetPayload,
+    ToolExecStart => ToolExecStartPayload,
+    ToolExecEnd => ToolExecEndPayload,
+    ShellOutputChunk => ShellOutputChunkPayload,
+    ToolExecProgress => ToolExecProgressPayload,
+    PlanUpdated => PlanUpdatedPayload,
+    PostWriteVerifyStart => PostWriteVerifyStartPayload,
+    PostWriteVerifyEnd => PostWriteVerifyEndPayload,
+    WriteSkippedDryRun => WriteSkippedDryRunPayload,
+    SecretRedacted => SecretRedactedPayload,
+    AttributionInjected => AttributionInjectedPayload,
+    AttributionSkipped => AttributionSkippedPayload,
+    ToolRetry => ToolRetryPayload,
+    TaintUpdated => TaintUpdatedPayload,
+    CompactionPerformed => CompactionPerformedPayload,
+    ContextOverflowRecovered => ContextOverflowRecoveredPayload,
+    ToolResultRefetched => ToolResultRefetchedPayload,
+    PolicyLoaded => PolicyLoadedPayload,
+    PlannerStart => PlannerStartPayload,
+    PlannerEnd => PlannerEndPayload,
+    WorkerStart => WorkerStartPayload,
+    StepStarted => StepStartedPayload,
+    StepVerified => StepVerifiedPayload,
+    StepBlocked => StepBlockedPayload,
+    StepReplanned => StepReplannedPayload,
+    TaskgraphStart => TaskgraphStartPayload,
+    TaskgraphNodeStart => TaskgraphNodeStartPayload,
+    TaskgraphNodeEnd => TaskgraphNodeEndPayload,
+    TaskgraphEnd => TaskgraphEndPayload,
+    HookStart => HookStartPayload,
+    HookEnd => HookEndPayload,
+    HookError => HookErrorPayload,
+    HookBudgetExhausted => HookBudgetExhaustedPayload,
+    HookRewroteArgs => HookRewroteArgsPayload,
+    HooksConfigReloaded => HooksConfigReloadedPayload,
+    HooksConfigReloadFailed => HooksConfigReloadFailedPayload,
+    ProviderRetry => ProviderRetryPayload,
+    ProviderError => ProviderErrorPayload,
+    ReproSnapshot => ReproSnapshotPayload,
+    McpProgress => McpProgressPayload,
+    McpCancelled => McpCancelledPayload,
+    McpServerDegraded => McpServerDegradedPayload,
+    McpServerReconnected => McpServerReconnectedPayload,
+    ToolOutputTruncated => ToolOutputTruncatedPayload,
+    McpPinned => McpPinnedPayload,
+    McpDrift => McpDriftPayload,
+    McpMetadataSanitized => McpMetadataSanitizedPayload,
+    McpToolRejected => McpToolRejectedPayload,
+    PackActivated => PackActivatedPayload,
+    QueueSubmitted => QueueSubmittedPayload,
+    QueueDelivered => QueueDeliveredPayload,
+    QueueInterrupt => QueueInterruptPayload,
+    QueueOverflow => QueueOverflowPayload,
+    PhaseEntered => PhaseEnteredPayload,
+    PhaseExited => PhaseExitedPayload,
+    InterruptRaised => InterruptRaisedPayload,
+    CompletionBlocked => CompletionBlockedPayload,
+    TaskContractResolved => TaskContractResolvedPayload,
+    ExecutionTierSelected => ExecutionTierSelectedPayload,
+    LearningCaptured => LearningCapturedPayload,
+    LearningPromoted => LearningPromotedPayload,
+    SessionRecovered => SessionRecoveredPayload,
+    StateLockReclaimed => StateLockReclaimedPayload,
+    DeadlineReached => DeadlineReachedPayload,
+    RunResumed => RunResumedPayload,
+    Error => ErrorPayload,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunStartPayload {
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunEndPayload {
+    pub exit_reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelRequestStartPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_count: Option<usize>,
+    pub tool_count: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelDeltaPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_fragment: Option<ToolCallFragmentPayload>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallFragmentPayload {
+    pub index: usize,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub arguments_fragment: Option<String>,
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelResponseEndPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    pub tool_calls: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+}
+
+/// The server reported a different model name than the one requested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelMismatchPayload {
+    pub requested_model: String,
+    pub served_model: String,
+    /// True when `--require-exact-model` turned the mismatch into a run failure.
+    pub enforced: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallDetectedPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub arguments: Value,
+    pub side_effects: SideEffects,
+    pub tool_args_strict: String,
+}
+
+/// A tool call fully assembled from streamed fragments, before the response
+/// is handed to the agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallStreamedPayload {
+    pub index: usize,
+    pub tool_call_id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// A complete `[TOOL_CALL]` block in assistant content that was not turned
+/// into a tool call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallNearMissPayload {
+    /// `unparseable_body`, `missing_tool_name`, or `tool_not_allowed`.
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolDecisionPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub decision: String,
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_id: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_key: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planner_hash_hex: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side_effects: Option<SideEffects>,
+    #[serde(flatten)]
+    pub gate: Option<Box<ToolDecisionGateContext>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<ToolBudgetPayload>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_step_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_step_index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_allowed_tools: Option<Vec<String>>,
+    /// Id of the plan step argument constraint the call broke.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_constraint_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enforcement_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denial_class: Option<crate::agent::DenialClass>,
+    /// Set on `require_approval` for file writes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_preview: Option<Box<crate::gate::ApprovalDiffPreview>>,
+}
+
+/// Approval-gate provenance carried by decisions that went through the gate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDecisionGateContext {
+    pub approval_key_version: Option<String>,
+    pub tool_schema_hash_hex: Option<String>,
+    pub hooks_config_hash_hex: Option<String>,
+    pub exec_target: Option<String>,
+    pub taint_overall: String,
+    pub taint_enforced: bool,
+    pub escalated: bool,
+    pub escalation_reason: Option<String>,
+    pub tool_args_strict: String,
+}
+
+/// Budget limits echoed on budget denials; each site reports the limits it
+/// checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolBudgetPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_tool_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_mcp_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_filesystem_read_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_filesystem_write_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_shell_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_network_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_browser_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_calls_per_tool: Option<BTreeMap<String, usize>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolExecTargetPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub exec_target: String,
+    /// Image, network, user and resource limits of the sandbox when the call
+    /// runs in docker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docker: Option<crate::target::DockerMeta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolExecStartPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub side_effects: SideEffects,
+    /// Set when the call ran in a parallel read-only batch. All calls of a
+    /// batch have finished before its first start event; start/end events
+    /// then follow in call order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolExecEndPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub ok: bool,
+    pub truncated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_class: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repair_attempted: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repair_succeeded: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<u32>,
+    /// See [`ToolExecStartPayload::batch_id`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Byte length of the tool message content after result hooks ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellOutputChunkPayload {
+    pub tool_call_id: String,
+    pub stream: String,
+    pub chunk: String,
+}
+
+/// Running byte count and tail of one output stream of a tool still in
+/// flight. Emitted with `--stream-tool-output`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolExecProgressPayload {
+    pub tool_call_id: String,
+    /// `stdout` or `stderr`.
+    pub stream: String,
+    pub bytes_so_far: u64,
+    /// Last bytes written to the stream, lossily decoded.
+    pub preview: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanUpdatedPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub explanation: Option<String>,
+    pub items: Vec<PlanItemPayload>,
+    pub item_count: usize,
+    pub pending: usize,
+    pub completed: usize,
+    pub in_progress: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanItemPayload {
+    pub step: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostWriteVerifyStartPayload {
+    pub name: String,
+    pub path: String,
+    pub source: String,
+    pub timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostWriteVerifyEndPayload {
+    pub name: String,
+    pub path: String,
+    pub ok: bool,
+    pub status: String,
+    pub source: String,
+    pub failure_class: Option<String>,
+    pub elapsed_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+/// A write tool ran under `--dry-run-writes`; the envelope carries the diff
+/// and nothing was written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteSkippedDryRunPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+}
+
+/// A secret was redacted from a tool result under `redact_tool_results`.
+/// Carries only the digest, so a secret reappearing can be matched without
+/// it being recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretRedactedPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub digest: String,
+    pub occurrences: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributionInjectedPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub path: String,
+    pub placement: AttributionPlacement,
+    pub pre_injection_sha256: String,
+    pub post_injection_sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributionSkippedPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolRetryPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub attempt: u32,
+    pub max_retries: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    pub failure_class: String,
+    pub action: String,
+    pub error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaintUpdatedPayload {
+    pub overall: String,
+    pub new_spans: usize,
+    pub sources: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactionPerformedPayload {
+    pub before_chars: usize,
+    pub after_chars: usize,
+    pub before_messages: usize,
+    pub after_messages: usize,
+    pub compacted_messages: usize,
+    pub summary_digest_sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(default)]
+    pub digested_tool_results: usize,
+    #[serde(default)]
+    pub summary_generated: bool,
+}
+
+/// The provider rejected a prompt as too long and an emergency compaction let
+/// the same call go through. Sizes span every compaction pass it took.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextOverflowRecoveredPayload {
+    pub attempts: u32,
+    pub before_chars: usize,
+    pub after_chars: usize,
+    pub before_messages: usize,
+    pub after_messages: usize,
+    pub keep_last: usize,
+}
+
+/// A tool call repeating the tool and arguments of a result that compaction
+/// had replaced by a digest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResultRefetchedPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub args_sha256: String,
+    pub digested_tool_call_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digested_step: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyLoadedPayload {
+    pub version: u32,
+    pub rules_count: usize,
+    pub includes_count: usize,
+    pub mcp_allowlist: Option<McpAllowSummary>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlannerStartPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planner_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enforce_plan_tools_effective: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannerEndPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    pub ok: bool,
+    pub planner_hash_hex: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_short: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage_parent_plan_hash_hex: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerStartPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    pub worker_model: String,
+    pub planner_hash_hex: String,
+    pub enforce_plan_tools_effective: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepStartedPayload {
+    pub step_id: String,
+    pub step_index: usize,
+    pub allowed_tools: Vec<String>,
+    pub enforcement_mode: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepVerifiedPayload {
+    pub step_id: String,
+    pub next_step_id: Option<String>,
+    pub status: String,
+    /// Fan-out siblings still waiting after this transition.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub queued_step_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StepBlockedPayload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_schema_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_step_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_step_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_constraint_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_limit: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_key_sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReplannedPayload {
+    pub step_id: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskgraphStartPayload {
+    pub graph_run_id: String,
+    pub taskfile_hash_hex: String,
+    pub nodes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskgraphNodeStartPayload {
+    pub node_id: String,
+    pub index: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskgraphNodeEndPayload {
+    pub node_id: String,
+    pub status: String,
+    pub run_id: String,
+    pub exit_reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskgraphEndPayload {
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookStartPayload {
+    pub hook_name: String,
+    pub stage: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookEndPayload {
+    pub hook_name: String,
+    pub stage: String,
+    pub action: String,
+    pub modified: bool,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_digest: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_digest: MaybeNull<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookErrorPayload {
+    pub stage: String,
+    pub error: String,
+}
+
+/// Emitted once per hook, when its budget first stops it from running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookBudgetExhaustedPayload {
+    pub hook_name: String,
+    pub stage: String,
+    pub limit: String,
+    pub limit_value: u64,
+    pub invocations: u32,
+    pub cumulative_ms: u64,
+}
+
+/// pre_tool hooks replaced a tool call's arguments before the gate saw it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookRewroteArgsPayload {
+    pub tool_call_id: String,
+    pub tool: String,
+    pub hooks: Vec<String>,
+    pub original_args_sha256: String,
+    pub rewritten_args_sha256: String,
+    pub arguments: Value,
+}
+
+/// hooks.yaml changed since the session's previous run and was reloaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HooksConfigReloadedPayload {
+    pub old_hash_hex: Option<String>,
+    pub new_hash_hex: Option<String>,
+    /// Approvals keyed to the old hooks hash that were removed.
+    pub approvals_invalidated: usize,
+}
+
+/// hooks.yaml changed but failed to load; the previous config stays active.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HooksConfigReloadFailedPayload {
+    pub active_hash_hex: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderRetryPayload {
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub kind: ProviderErrorKind,
+    pub status: Option<u16>,
+    pub backoff_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderErrorPayload {
+    pub kind: ProviderErrorKind,
+    pub status: Option<u16>,
+    pub retryable: bool,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub message_short: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReproSnapshotPayload {
+    pub enabled: bool,
+    pub env_mode: String,
+    pub repro_hash_hex: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpProgressPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub progress_ticks: u32,
+    pub elapsed_ms: u64,
+    pub phase: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpCancelledPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub reason: String,
+    pub elapsed_ms: u64,
+}
+
+/// An MCP server's connection broke, or a reconnect attempt failed. Calls
+/// to it fail fast until `retry_after_ms` has passed; `None` means no
+/// attempts are left.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerDegradedPayload {
+    pub server: String,
+    pub tool_call_id: String,
+    pub name: String,
+    pub reason: String,
+    pub reconnect_attempts: u32,
+    pub max_reconnect_attempts: u32,
+    pub retry_after_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerReconnectedPayload {
+    pub server: String,
+    pub tool_call_id: String,
+    pub name: String,
+    pub degraded_ms: u64,
+    pub reconnect_attempts: u32,
+}
+
+/// An MCP result was over `max_tool_output_bytes`. `rejected` results were
+/// over the hard cap and dropped, so `kept_bytes` is 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolOutputTruncatedPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub server: String,
+    pub tool: String,
+    pub original_bytes: u64,
+    pub kept_bytes: u64,
+    pub limit_bytes: u64,
+    pub rejected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPinnedPayload {
+    pub enforcement: String,
+    pub configured_hash_hex: Option<String>,
+    pub startup_live_hash_hex: Option<String>,
+    pub configured_docs_hash_hex: Option<String>,
+    pub startup_live_docs_hash_hex: Option<String>,
+    pub mcp_config_hash_hex: Option<String>,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpDriftPayload {
+    pub tool_call_id: String,
+    pub name: String,
+    pub expected_hash_hex: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_hash_hex: Option<String>,
+    pub catalog_hash_expected: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog_hash_live: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog_drift: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub catalog_probe_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_hash_expected: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_hash_live: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_probe_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_drift: Option<bool>,
+    /// Pinned and live input schema hashes of the called tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash_pinned: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_hash_live: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_drift: Option<bool>,
+    pub enforcement: String,
+    pub codes: Vec<String>,
+    pub primary_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpMetadataSanitizedPayload {
+    pub schema: String,
+    pub server: String,
+    pub tool: String,
+    pub action: McpMetadataAction,
+    pub matched_patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpToolRejectedPayload {
+    pub schema: String,
+    pub server: String,
+    pub tool: String,
+    pub reason: McpToolRejectReason,
+    pub conflicts_with: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackActivatedPayload {
+    pub schema: String,
+    pub pack_id: String,
+    pub pack_hash_hex: String,
+    pub truncated: bool,
+    pub bytes_kept: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueSubmittedPayload {
+    pub queue_id: String,
+    pub sequence_no: u64,
+    pub kind: QueueMessageKind,
+    pub truncated: bool,
+    pub bytes_kept: u64,
+    pub bytes_loaded: u64,
+    pub next_delivery: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueDeliveredPayload {
+    pub queue_id: String,
+    pub sequence_no: u64,
+    pub kind: QueueMessageKind,
+    pub truncated: bool,
+    pub bytes_kept: u64,
+    pub bytes_loaded: u64,
+    pub delivery_boundary: DeliveryBoundary,
+    /// Messages merged into this delivery by steer coalescing, when more
+    /// than one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coalesced_count: Option<usize>,
+    /// Queue ids folded into `queue_id`'s delivery.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub coalesced_queue_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueOverflowPayload {
+    /// The submission that went over the limit.
+    pub queue_id: String,
+    pub policy: QueueOverflowPolicy,
+    pub max_pending: usize,
+    pub dropped_queue_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueInterruptPayload {
+    pub queue_id: String,
+    pub sequence_no: u64,
+    pub kind: QueueMessageKind,
+    pub delivery_boundary: DeliveryBoundary,
+    pub cancelled_remaining_work: bool,
+    pub cancelled_reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseEnteredPayload {
+    pub phase: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseExitedPayload {
+    pub phase: String,
+    pub next_phase: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterruptRaisedPayload {
+    pub kind: String,
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_id: MaybeNull<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: MaybeNull<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionBlockedPayload {
+    pub reason: String,
+    pub next_phase: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskContractResolvedPayload {
+    pub task_kind: String,
+    pub validation_requirement: ValidationRequirement,
+    pub allowed_tools_semantics: AllowedToolsSemantics,
+    pub task_kind_source: ContractValueSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionTierSelectedPayload {
+    pub execution_tier: ExecutionTier,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningCapturedPayload {
+    pub schema: String,
+    pub learning_id: String,
+    pub entry_hash_hex: String,
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearningPromotedPayload {
+    pub schema: String,
+    pub learning_id: String,
+    pub entry_hash_hex: String,
+    pub target: String,
+    pub target_path: String,
+    pub forced: bool,
+    pub target_file_sha256_hex: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noop: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecoveredPayload {
+    pub schema: String,
+    pub session_path: String,
+    pub backup_path: String,
+    pub reason: String,
+    pub recovered_messages: usize,
+}
+
+/// A stale state-dir lock was removed so this process could take it. The
+/// holder fields are absent when the lock file was unreadable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateLockReclaimedPayload {
+    pub schema: String,
+    pub lock_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_command: Option<String>,
+}
+
+impl From<crate::store::StateLockReclaim> for StateLockReclaimedPayload {
+    fn from(reclaim: crate::store::StateLockReclaim) -> Self {
+        Self {
+            schema: "openagent.state_lock_reclaimed.v1".to_string(),
+            lock_path: reclaim.lock_path.display().to_string(),
+            stale_pid: reclaim.stale_holder.as_ref().map(|h| h.pid),
+            stale_command: reclaim.stale_holder.map(|h| h.command),
+        }
+    }
+}
+
+/// The run hit `--deadline-ms`; remaining tool calls were skipped and the
+/// model gets one tool-less turn to summarize.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadlineReachedPayload {
+    pub elapsed_ms: u64,
+    pub deadline_ms: u64,
+    pub skipped_tool_calls: usize,
+}
+
+/// A run continued from a prior run's transcript under the same run id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResumedPayload {
+    pub prior_exit_reason: String,
+    pub prior_messages: usize,
+    /// SHA-256 of the prior run's final transcript (JSON messages).
+    pub prior_transcript_sha256: String,
+    /// Tool calls of the prior transcript that never got a result and were
+    /// closed with a failed one.
+    pub closed_tool_calls: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorPayload {
+    pub error: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wall_time_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<ToolBudgetPayload>,
+}
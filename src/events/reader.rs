@@ -0,0 +1,261 @@
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::Event;
+
+/// A problem with an event stream as a whole, as opposed to one event's
+/// payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventStreamError {
+    /// Sequence numbers jumped: events were lost or the file was cut.
+    Gap { expected: u64, got: u64 },
+    /// A line that is not an event. `line` is 1-based.
+    Malformed { line: usize, message: String },
+    /// The event was written with a schema this build does not read.
+    UnsupportedSchema { line: usize, schema: String },
+}
+
+impl std::fmt::Display for EventStreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventStreamError::Gap { expected, got } => {
+                write!(f, "event seq gap: expected {expected}, got {got}")
+            }
+            EventStreamError::Malformed { line, message } => {
+                write!(f, "malformed event on line {line}: {message}")
+            }
+            EventStreamError::UnsupportedSchema { line, schema } => write!(
+                f,
+                "unsupported event schema {schema} on line {line} (expected {})",
+                Event::SCHEMA_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EventStreamError {}
+
+/// Reads every event of a JSONL events file. A single malformed last line,
+/// left by a process killed mid-write, is dropped; any other malformed
+/// line, unknown schema or seq gap fails with an [`EventStreamError`].
+pub fn read_events(path: &Path) -> anyhow::Result<Vec<Event>> {
+    let mut events = Vec::new();
+    for item in EventStreamReader::open(path, false)? {
+        events.push(item.with_context(|| format!("reading events file {}", path.display()))?);
+    }
+    Ok(events)
+}
+
+/// Streams events from JSONL, validating the schema stamp and that `seq`
+/// increases by one. Events written before seq stamping carry neither and
+/// pass through unchecked. After a gap the reader resyncs on the new seq.
+pub struct EventStreamReader<R: BufRead> {
+    lines: std::iter::Peekable<std::io::Lines<R>>,
+    strict: bool,
+    line: usize,
+    last_seq: u64,
+    truncated_tail: bool,
+}
+
+impl EventStreamReader<BufReader<std::fs::File>> {
+    pub fn open(path: &Path, strict: bool) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("failed to open events file {}", path.display()))?;
+        Ok(Self::new(BufReader::new(file), strict))
+    }
+}
+
+impl<R: BufRead> EventStreamReader<R> {
+    /// In strict mode a malformed last line is an error like any other.
+    pub fn new(reader: R, strict: bool) -> Self {
+        Self {
+            lines: reader.lines().peekable(),
+            strict,
+            line: 0,
+            last_seq: 0,
+            truncated_tail: false,
+        }
+    }
+
+    /// A malformed last line was dropped.
+    pub fn truncated_tail(&self) -> bool {
+        self.truncated_tail
+    }
+
+    fn at_end(&mut self) -> bool {
+        loop {
+            match self.lines.peek() {
+                Some(Ok(next)) if next.trim().is_empty() => {
+                    self.lines.next();
+                    self.line += 1;
+                }
+                None => return true,
+                Some(_) => return false,
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for EventStreamReader<R> {
+    type Item = anyhow::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let raw = loop {
+            let raw = match self.lines.next()? {
+                Ok(raw) => raw,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+            if !raw.trim().is_empty() {
+                break raw;
+            }
+        };
+        let event = match serde_json::from_str::<Event>(&raw) {
+            Ok(event) => event,
+            Err(e) => {
+                if !self.strict && self.at_end() {
+                    self.truncated_tail = true;
+                    return None;
+                }
+                return Some(Err(EventStreamError::Malformed {
+                    line: self.line,
+                    message: e.to_string(),
+                }
+                .into()));
+            }
+        };
+        if let Some(schema) = event.schema.as_deref() {
+            if schema != Event::SCHEMA_VERSION {
+                return Some(Err(EventStreamError::UnsupportedSchema {
+                    line: self.line,
+                    schema: schema.to_string(),
+                }
+                .into()));
+            }
+        }
+        if let Some(seq) = event.seq {
+            let expected = self.last_seq + 1;
+            self.last_seq = seq;
+            if seq != expected {
+                return Some(Err(EventStreamError::Gap { expected, got: seq }.into()));
+            }
+        }
+        Some(Ok(event))
+    }
+}
+
+/// Highest `seq` in an existing events file, so an appending sink continues
+/// the sequence instead of restarting it.
+pub(super) fn last_seq_in_file(path: &Path) -> u64 {
+    let Ok(raw) = std::fs::read_to_string(path) else {
+        return 0;
+    };
+    raw.lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find_map(|v| v.get("seq").and_then(serde_json::Value::as_u64))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tempfile::tempdir;
+
+    use super::{read_events, EventStreamError, EventStreamReader};
+    use crate::events::{Event, EventKind, EventSink, JsonlFileSink};
+
+    fn write_run(path: &std::path::Path, steps: u32) {
+        let mut sink = JsonlFileSink::new(path).expect("sink");
+        for step in 0..steps {
+            sink.emit(Event::new_raw(
+                "r".to_string(),
+                step,
+                EventKind::StepStarted,
+                serde_json::json!({}),
+            ))
+            .expect("emit");
+        }
+    }
+
+    #[test]
+    fn sink_stamps_schema_and_seq_across_reopen() {
+        let tmp = tempdir().expect("tmp");
+        let path = tmp.path().join("events.jsonl");
+        write_run(&path, 2);
+        write_run(&path, 2);
+        let events = read_events(&path).expect("read");
+        assert_eq!(
+            events.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(3), Some(4)]
+        );
+        assert!(events
+            .iter()
+            .all(|e| e.schema.as_deref() == Some(Event::SCHEMA_VERSION)));
+    }
+
+    #[test]
+    fn trailing_partial_line_is_tolerated_only_when_lenient() {
+        let tmp = tempdir().expect("tmp");
+        let path = tmp.path().join("events.jsonl");
+        write_run(&path, 3);
+        let mut raw = std::fs::read_to_string(&path).expect("read");
+        raw.push_str("{\"ts\":\"2026-01-01T00:00:00Z\",\"run_id\":\"r\",\"st");
+        std::fs::write(&path, &raw).expect("write");
+
+        assert_eq!(read_events(&path).expect("lenient").len(), 3);
+        let mut strict = EventStreamReader::open(&path, true).expect("open");
+        assert!(strict.by_ref().take(3).all(|e| e.is_ok()));
+        let err = strict.next().expect("item").expect_err("strict");
+        assert!(matches!(
+            err.downcast_ref::<EventStreamError>(),
+            Some(EventStreamError::Malformed { line: 4, .. })
+        ));
+
+        let corrupt_middle = raw.replacen("\n", "\nnot json\n", 1);
+        let err = read_events_from(&corrupt_middle, false).expect_err("corrupt");
+        assert!(err.contains("malformed event on line 2"), "{err}");
+    }
+
+    #[test]
+    fn gaps_and_foreign_schemas_are_typed_errors() {
+        let tmp = tempdir().expect("tmp");
+        let path = tmp.path().join("events.jsonl");
+        write_run(&path, 4);
+        let raw = std::fs::read_to_string(&path).expect("read");
+        let gapped = raw
+            .lines()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, l)| format!("{l}\n"))
+            .collect::<String>();
+        let items = EventStreamReader::new(Cursor::new(gapped), false).collect::<Vec<_>>();
+        assert_eq!(items.len(), 3);
+        let gap = items[1].as_ref().expect_err("gap");
+        assert_eq!(
+            gap.downcast_ref::<EventStreamError>(),
+            Some(&EventStreamError::Gap {
+                expected: 2,
+                got: 3
+            })
+        );
+        assert!(items[2].is_ok(), "reader resyncs after a gap");
+
+        let foreign = raw.replacen(Event::SCHEMA_VERSION, "openagent.event.v9", 1);
+        let err = read_events_from(&foreign, false).expect_err("schema");
+        assert!(err.contains("openagent.event.v9"), "{err}");
+
+        let legacy =
+            "{\"ts\":\"t\",\"run_id\":\"r\",\"step\":0,\"kind\":\"run_start\",\"data\":{}}\n";
+        assert!(read_events_from(legacy, true).is_ok());
+    }
+
+    fn read_events_from(raw: &str, strict: bool) -> Result<Vec<Event>, String> {
+        EventStreamReader::new(Cursor::new(raw.to_string()), strict)
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())
+    }
+}
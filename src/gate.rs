@@ -4,15 +4,24 @@ use std::path::PathBuf;
 use clap::ValueEnum;
 use serde_json::Value;
 
+mod diff_preview;
 mod helpers;
+mod persisted;
 #[cfg(test)]
 mod tests;
 
-use helpers::with_exec_target_arg;
+pub use diff_preview::{
+    approval_diff_preview, ApprovalDiffPreview, DEFAULT_APPROVAL_DIFF_MAX_LINES,
+};
 #[allow(unused_imports)]
 pub use helpers::{
     compute_approval_key, compute_approval_key_with_version, compute_policy_hash_hex,
 };
+use helpers::{shell_allowlist_note, taint_sink_reason, with_exec_target_arg, write_rule_paths};
+pub use persisted::{
+    list_persisted_approvals, revoke_persisted_approval, workspace_approvals_path,
+    WorkspaceApprovals, DEFAULT_WORKSPACE_APPROVAL_TTL_HOURS, PERSISTED_APPROVAL_SOURCE,
+};
 
 use crate::taint::{TaintLevel, TaintMode};
 use crate::target::ExecTargetKind;
@@ -29,6 +38,8 @@ pub enum ProviderKind {
     Llamacpp,
     Ollama,
     Mock,
+    #[value(name = "openai")]
+    OpenAi,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -49,6 +60,8 @@ pub enum ApprovalMode {
 pub enum AutoApproveScope {
     Run,
     Session,
+    /// Grants persist in the workspace approvals store across runs.
+    Workspace,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -101,6 +114,9 @@ pub enum GateDecision {
 pub struct GateContext {
     pub workdir: PathBuf,
     pub allow_shell: bool,
+    /// `--allow-shell-cmd` programs; approval prompts say whether a shell
+    /// call's program is on it.
+    pub shell_allowlist: Option<Vec<String>>,
     pub allow_write: bool,
     pub approval_mode: ApprovalMode,
     pub auto_approve_scope: AutoApproveScope,
@@ -120,7 +136,84 @@ pub struct GateContext {
     pub taint_enabled: bool,
     pub taint_mode: TaintMode,
     pub taint_overall: TaintLevel,
+    /// Distinct taint sources seen so far in the run.
     pub taint_sources: Vec<String>,
+    /// Digest of the latest tool result flagged as a suspected injection.
+    pub taint_injection_digest: Option<String>,
+}
+
+/// The run settings a [`GateContext`] is derived from. Per-run state
+/// (run id, schema and hook hashes, accumulated taint) starts empty and is
+/// filled in as the run proceeds.
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    pub workdir: PathBuf,
+    pub allow_shell: bool,
+    pub shell_allowlist: Option<Vec<String>>,
+    pub allow_write: bool,
+    pub enable_write_tools: bool,
+    pub unsafe_mode: bool,
+    pub unsafe_bypass_allow_flags: bool,
+    pub max_tool_output_bytes: usize,
+    pub max_read_bytes: usize,
+    pub provider: ProviderKind,
+    pub model: String,
+    pub exec_target: ExecTargetKind,
+    pub approval_mode: ApprovalMode,
+    pub auto_approve_scope: AutoApproveScope,
+    pub approval_key_version: ApprovalKeyVersion,
+    pub taint_enabled: bool,
+    pub taint_mode: TaintMode,
+}
+
+impl RunConfig {
+    /// Rejects combinations the runtime would only fail on later, one tool
+    /// call at a time.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.enable_write_tools && !self.allow_write && !self.unsafe_bypass_allow_flags {
+            anyhow::bail!(
+                "write tools are enabled but writes are not allowed; add --allow-write or drop --enable-write-tools"
+            );
+        }
+        if self.unsafe_bypass_allow_flags && self.exec_target == ExecTargetKind::Docker {
+            anyhow::bail!(
+                "--unsafe-bypass-allow-flags only applies to the host target, not --exec-target docker"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl GateContext {
+    pub fn from_run_config(config: &RunConfig) -> anyhow::Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            workdir: config.workdir.clone(),
+            allow_shell: config.allow_shell,
+            shell_allowlist: config.shell_allowlist.clone(),
+            allow_write: config.allow_write,
+            approval_mode: config.approval_mode,
+            auto_approve_scope: config.auto_approve_scope,
+            unsafe_mode: config.unsafe_mode,
+            unsafe_bypass_allow_flags: config.unsafe_bypass_allow_flags,
+            run_id: None,
+            enable_write_tools: config.enable_write_tools,
+            max_tool_output_bytes: config.max_tool_output_bytes,
+            max_read_bytes: config.max_read_bytes,
+            provider: config.provider,
+            model: config.model.clone(),
+            exec_target: config.exec_target,
+            approval_key_version: config.approval_key_version,
+            tool_schema_hashes: BTreeMap::new(),
+            hooks_config_hash_hex: None,
+            planner_hash_hex: None,
+            taint_enabled: config.taint_enabled,
+            taint_mode: config.taint_mode,
+            taint_overall: TaintLevel::Clean,
+            taint_sources: Vec::new(),
+            taint_injection_digest: None,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -224,6 +317,8 @@ pub struct TrustGate {
     #[allow(dead_code)]
     pub trust_mode: TrustMode,
     pub policy_hash_hex: String,
+    /// Consulted and filled under `AutoApproveScope::Workspace`.
+    pub workspace_approvals: Option<WorkspaceApprovals>,
 }
 
 impl TrustGate {
@@ -240,7 +335,45 @@ impl TrustGate {
             audit,
             trust_mode,
             policy_hash_hex,
+            workspace_approvals: None,
+        }
+    }
+
+    pub fn with_workspace_approvals(mut self, store: WorkspaceApprovals) -> Self {
+        self.workspace_approvals = Some(store);
+        self
+    }
+
+    fn persisted_approval_id(&self, ctx: &GateContext, approval_key: &str) -> Option<String> {
+        if !matches!(ctx.auto_approve_scope, AutoApproveScope::Workspace) {
+            return None;
         }
+        self.workspace_approvals
+            .as_ref()?
+            .find_active(ctx, approval_key)
+            .ok()
+            .flatten()
+            .map(|entry| entry.id)
+    }
+
+    /// Persists a grant under workspace scope; returns `fallback_id` when
+    /// the scope is different or the store cannot be written.
+    fn persist_grant(
+        &self,
+        ctx: &GateContext,
+        call: &ToolCall,
+        approval_key: &str,
+        fallback_id: String,
+    ) -> String {
+        if !matches!(ctx.auto_approve_scope, AutoApproveScope::Workspace) {
+            return fallback_id;
+        }
+        let Some(store) = &self.workspace_approvals else {
+            return fallback_id;
+        };
+        store
+            .grant(ctx, call, approval_key, Some(fallback_id.clone()))
+            .unwrap_or(fallback_id)
     }
 }
 
@@ -266,6 +399,7 @@ impl ToolGate for TrustGate {
                 match ctx.exec_target {
                     ExecTargetKind::Host => "host",
                     ExecTargetKind::Docker => "docker",
+                    ExecTargetKind::Ssh => "ssh",
                 }
                 .to_string(),
             ),
@@ -311,24 +445,71 @@ impl ToolGate for TrustGate {
             };
         }
 
-        let eval = self.policy.evaluate(&call.name, &args_with_target);
+        let mut eval = self.policy.evaluate(&call.name, &args_with_target);
+        if let Some(targets) = write_rule_paths(call, &ctx.workdir) {
+            eval = self
+                .policy
+                .evaluate_write(&targets.paths, &targets.outside_workdir, eval);
+        }
         let side_effects = crate::tools::tool_side_effects(&call.name);
-        let taint_enforced = ctx.taint_enabled
-            && matches!(ctx.taint_mode, TaintMode::PropagateAndEnforce)
-            && matches!(ctx.taint_overall, TaintLevel::Tainted);
-        let should_escalate = taint_enforced
-            && matches!(
-                side_effects,
-                crate::types::SideEffects::FilesystemWrite
-                    | crate::types::SideEffects::ShellExec
-                    | crate::types::SideEffects::Network
-            );
-        let escalation_reason = if should_escalate {
-            Some("taint_escalation".to_string())
+        let tainted = ctx.taint_enabled && matches!(ctx.taint_overall, TaintLevel::Tainted);
+        // Sink rules ignore what the arguments point at: tainted content can
+        // be embedded in any argument, e.g. a patch to an unrelated file.
+        let sink = tainted
+            .then(|| self.policy.taint_sink_match(&call.name))
+            .flatten();
+        if let Some(pattern) = &sink {
+            if matches!(ctx.taint_mode, TaintMode::PropagateAndEnforce) {
+                return GateDecision::Deny {
+                    reason: taint_sink_reason(ctx, call, pattern),
+                    approval_key: Some(approval_key),
+                    source: Some("taint_sink".to_string()),
+                    taint_enforced: true,
+                    escalated: false,
+                    escalation_reason: None,
+                };
+            }
+        }
+        let taint_enforced =
+            (tainted && matches!(ctx.taint_mode, TaintMode::PropagateAndEnforce)) || sink.is_some();
+        let should_escalate = sink.is_some()
+            || (taint_enforced
+                && matches!(
+                    side_effects,
+                    crate::types::SideEffects::FilesystemWrite
+                        | crate::types::SideEffects::ShellExec
+                        | crate::types::SideEffects::Network
+                ));
+        let escalation_reason = if let Some(pattern) = &sink {
+            Some(format!("taint_sink:{pattern}"))
+        } else if should_escalate {
+            Some(match &ctx.taint_injection_digest {
+                Some(digest) => format!(
+                    "taint_escalation:{}:{digest}",
+                    crate::taint::INJECTION_SUSPECTED_SOURCE
+                ),
+                None => "taint_escalation".to_string(),
+            })
+        } else {
+            None
+        };
+        let persisted_approval_id = if matches!(eval.decision, PolicyDecision::RequireApproval) {
+            self.persisted_approval_id(ctx, &approval_key)
         } else {
             None
         };
         let mut decision = match eval.decision {
+            PolicyDecision::RequireApproval if persisted_approval_id.is_some() => {
+                GateDecision::Allow {
+                    approval_id: persisted_approval_id,
+                    approval_key: Some(approval_key),
+                    reason: eval.reason,
+                    source: Some(PERSISTED_APPROVAL_SOURCE.to_string()),
+                    taint_enforced,
+                    escalated: false,
+                    escalation_reason: None,
+                }
+            }
             PolicyDecision::Allow => GateDecision::Allow {
                 approval_id: None,
                 approval_key: Some(approval_key),
@@ -364,6 +545,27 @@ impl ToolGate for TrustGate {
                             escalated: false,
                             escalation_reason: None,
                         },
+                        AutoApproveScope::Workspace => {
+                            let auto_id = format!(
+                                "auto:{}:{}",
+                                ctx.run_id.clone().unwrap_or_else(|| "run".to_string()),
+                                call.id
+                            );
+                            GateDecision::Allow {
+                                approval_id: Some(self.persist_grant(
+                                    ctx,
+                                    call,
+                                    &approval_key,
+                                    auto_id,
+                                )),
+                                approval_key: Some(approval_key),
+                                reason: eval.reason.clone(),
+                                source: eval.source.clone(),
+                                taint_enforced,
+                                escalated: false,
+                                escalation_reason: None,
+                            }
+                        }
                         AutoApproveScope::Session => {
                             match self.approvals.ensure_approved_for_key(
                                 &call.name,
@@ -398,7 +600,12 @@ impl ToolGate for TrustGate {
                     .consume_matching_approved(&approval_key, ctx.approval_key_version.as_str())
                 {
                     Ok(Some(usage)) => GateDecision::Allow {
-                        approval_id: Some(usage.id),
+                        approval_id: Some(self.persist_grant(
+                            ctx,
+                            call,
+                            &usage.approval_key,
+                            usage.id,
+                        )),
                         approval_key: Some(usage.approval_key),
                         reason: eval.reason.clone(),
                         source: eval.source.clone(),
@@ -522,7 +729,8 @@ impl ToolGate for TrustGate {
                 } => {
                     if matches!(ctx.approval_mode, ApprovalMode::Auto) {
                         let auto_id = match ctx.auto_approve_scope {
-                            AutoApproveScope::Run => format!(
+                            // Taint escalations are never persisted.
+                            AutoApproveScope::Run | AutoApproveScope::Workspace => format!(
                                 "auto:{}:{}",
                                 ctx.run_id.clone().unwrap_or_else(|| "run".to_string()),
                                 call.id
@@ -565,7 +773,10 @@ impl ToolGate for TrustGate {
                             )
                             .unwrap_or_else(|_| format!("pending:{}:{}", call.name, call.id));
                         GateDecision::RequireApproval {
-                            reason: "approval required due to tainted content".to_string(),
+                            reason: match &sink {
+                                Some(pattern) => taint_sink_reason(ctx, call, pattern),
+                                None => "approval required due to tainted content".to_string(),
+                            },
                             approval_id: id,
                             approval_key,
                             source,
@@ -578,6 +789,11 @@ impl ToolGate for TrustGate {
             };
         }
 
+        if let GateDecision::RequireApproval { reason, .. } = &mut decision {
+            if let Some(note) = shell_allowlist_note(ctx, call) {
+                reason.push_str(&format!(" ({note})"));
+            }
+        }
         decision
     }
 
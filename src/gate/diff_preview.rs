@@ -0,0 +1,310 @@
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::ToolCall;
+
+pub const DEFAULT_APPROVAL_DIFF_MAX_LINES: usize = 40;
+/// Existing file plus proposed content above this size is not diffed.
+const DIFF_INPUT_MAX_BYTES: usize = 1024 * 1024;
+/// Preview lines are cut here so one minified line cannot flood a terminal.
+const PREVIEW_LINE_MAX_CHARS: usize = 240;
+
+/// What a pending `write_file`/`apply_patch` would change, for approval
+/// prompts. Built read-only from the workdir and the call arguments.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalDiffPreview {
+    pub tool: String,
+    pub paths: Vec<String>,
+    /// The target of a `write_file` does not exist yet.
+    #[serde(default)]
+    pub new_file: bool,
+    pub added_lines: usize,
+    pub removed_lines: usize,
+    /// Unified diff lines, at most the configured maximum.
+    pub lines: Vec<String>,
+    /// Lines were left out of `lines`.
+    #[serde(default)]
+    pub truncated: bool,
+    /// Why no diff could be computed, e.g. the file is too large.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub omitted: Option<String>,
+}
+
+impl ApprovalDiffPreview {
+    /// Plain-text block for terminal approval prompts.
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{} {} (+{} -{}{})",
+            self.tool,
+            if self.paths.is_empty() {
+                "<unknown path>".to_string()
+            } else {
+                self.paths.join(", ")
+            },
+            self.added_lines,
+            self.removed_lines,
+            if self.new_file { ", new file" } else { "" }
+        );
+        if let Some(omitted) = &self.omitted {
+            out.push_str(&format!("\n  diff omitted: {omitted}"));
+        }
+        for line in &self.lines {
+            out.push_str("\n  ");
+            out.push_str(line);
+        }
+        if self.truncated {
+            let shown = self.lines.len();
+            out.push_str(&format!("\n  ... preview limited to {shown} lines"));
+        }
+        out
+    }
+}
+
+/// Diff preview for a `write_file` or `apply_patch` call; `None` for other
+/// tools, malformed arguments, or `max_lines == 0`. Paths that are absolute
+/// or climb out of `workdir` are not read.
+pub fn approval_diff_preview(
+    workdir: &Path,
+    call: &ToolCall,
+    max_lines: usize,
+) -> Option<ApprovalDiffPreview> {
+    if max_lines == 0 {
+        return None;
+    }
+    match call.name.as_str() {
+        "write_file" => {
+            let path = call.arguments.get("path")?.as_str()?;
+            let content = call.arguments.get("content")?.as_str()?;
+            Some(write_file_preview(workdir, path, content, max_lines))
+        }
+        "apply_patch" => {
+            let patch = call.arguments.get("patch")?.as_str()?;
+            let path = call
+                .arguments
+                .get("path")
+                .and_then(|v| v.as_str())
+                .filter(|p| !p.is_empty());
+            Some(apply_patch_preview(path, patch, max_lines))
+        }
+        _ => None,
+    }
+}
+
+fn write_file_preview(
+    workdir: &Path,
+    path: &str,
+    content: &str,
+    max_lines: usize,
+) -> ApprovalDiffPreview {
+    let mut preview = ApprovalDiffPreview {
+        tool: "write_file".to_string(),
+        paths: vec![path.to_string()],
+        ..ApprovalDiffPreview::default()
+    };
+    let current = if stays_in_workdir(path) {
+        read_existing(&workdir.join(path))
+    } else {
+        Err("path is outside the workdir".to_string())
+    };
+    match current {
+        Ok(None) => {
+            preview.new_file = true;
+            preview.added_lines = content.lines().count();
+            let lines = content.lines().map(|l| format!("+{l}"));
+            fill_lines(&mut preview, lines, max_lines);
+        }
+        Ok(Some(old)) if old.len().saturating_add(content.len()) > DIFF_INPUT_MAX_BYTES => {
+            preview.omitted = Some(format!(
+                "existing and proposed content exceed {DIFF_INPUT_MAX_BYTES} bytes"
+            ));
+        }
+        Ok(Some(old)) => {
+            let diff = diffy::create_patch(&old, content).to_string();
+            count_and_fill(&mut preview, &diff, max_lines);
+        }
+        Err(reason) => preview.omitted = Some(reason),
+    }
+    preview
+}
+
+fn apply_patch_preview(path: Option<&str>, patch: &str, max_lines: usize) -> ApprovalDiffPreview {
+    let mut preview = ApprovalDiffPreview {
+        tool: "apply_patch".to_string(),
+        ..ApprovalDiffPreview::default()
+    };
+    preview.paths = match path {
+        Some(path) => vec![path.to_string()],
+        None => patch
+            .lines()
+            .filter_map(|l| l.strip_prefix("+++ "))
+            .map(|p| {
+                let p = p.split('\t').next().unwrap_or(p).trim();
+                p.strip_prefix("b/").unwrap_or(p).to_string()
+            })
+            .filter(|p| p != "/dev/null")
+            .collect(),
+    };
+    count_and_fill(&mut preview, patch, max_lines);
+    preview
+}
+
+/// Counts added/removed lines of a unified diff and keeps everything but
+/// the `---`/`+++` file headers as preview lines.
+fn count_and_fill(preview: &mut ApprovalDiffPreview, diff: &str, max_lines: usize) {
+    let body = diff
+        .lines()
+        .filter(|l| !l.starts_with("--- ") && !l.starts_with("+++ "));
+    for line in body.clone() {
+        if line.starts_with('+') {
+            preview.added_lines += 1;
+        } else if line.starts_with('-') {
+            preview.removed_lines += 1;
+        }
+    }
+    fill_lines(preview, body.map(str::to_string), max_lines);
+}
+
+fn fill_lines(
+    preview: &mut ApprovalDiffPreview,
+    mut lines: impl Iterator<Item = String>,
+    max_lines: usize,
+) {
+    preview.lines = lines
+        .by_ref()
+        .take(max_lines)
+        .map(|l| clip_line(&l))
+        .collect();
+    preview.truncated = lines.next().is_some();
+}
+
+fn clip_line(line: &str) -> String {
+    match line.char_indices().nth(PREVIEW_LINE_MAX_CHARS) {
+        Some((idx, _)) => format!("{}...", &line[..idx]),
+        None => line.to_string(),
+    }
+}
+
+fn stays_in_workdir(path: &str) -> bool {
+    let path = Path::new(path);
+    !path.is_absolute()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// `Ok(None)` when the file does not exist yet.
+fn read_existing(path: &Path) -> Result<Option<String>, String> {
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("cannot read current file: {e}")),
+    };
+    if meta.len() as usize > DIFF_INPUT_MAX_BYTES {
+        return Err(format!(
+            "current file is larger than {DIFF_INPUT_MAX_BYTES} bytes"
+        ));
+    }
+    std::fs::read_to_string(path)
+        .map(Some)
+        .map_err(|e| format!("cannot read current file: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::approval_diff_preview;
+    use crate::types::ToolCall;
+
+    fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+        ToolCall {
+            id: "tc1".to_string(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    #[test]
+    fn write_file_preview_diffs_existing_content_without_writing() {
+        let tmp = tempdir().expect("tmp");
+        std::fs::write(tmp.path().join("notes.txt"), "a\nb\nc\n").expect("write");
+        let preview = approval_diff_preview(
+            tmp.path(),
+            &call(
+                "write_file",
+                json!({"path":"notes.txt","content":"a\nB\nc\nd\n"}),
+            ),
+            40,
+        )
+        .expect("preview");
+        assert!(!preview.new_file);
+        assert_eq!((preview.added_lines, preview.removed_lines), (2, 1));
+        assert!(preview.lines.iter().any(|l| l == "-b"));
+        assert!(preview.lines.iter().any(|l| l == "+B"));
+        assert!(!preview.truncated);
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("notes.txt")).expect("read"),
+            "a\nb\nc\n"
+        );
+    }
+
+    #[test]
+    fn write_file_preview_handles_new_files_and_caps_lines() {
+        let tmp = tempdir().expect("tmp");
+        let content = (0..50_000)
+            .map(|i| format!("line {i}\n"))
+            .collect::<String>();
+        let preview = approval_diff_preview(
+            tmp.path(),
+            &call("write_file", json!({"path":"gen/out.rs","content":content})),
+            5,
+        )
+        .expect("preview");
+        assert!(preview.new_file);
+        assert_eq!(preview.added_lines, 50_000);
+        assert_eq!(
+            preview.lines,
+            vec!["+line 0", "+line 1", "+line 2", "+line 3", "+line 4"]
+        );
+        assert!(preview.truncated);
+        assert!(preview.render().ends_with("... preview limited to 5 lines"));
+        assert!(!tmp.path().join("gen").exists());
+
+        let outside = approval_diff_preview(
+            tmp.path(),
+            &call("write_file", json!({"path":"../x.txt","content":"x"})),
+            5,
+        )
+        .expect("preview");
+        assert!(outside.lines.is_empty());
+        assert!(outside.omitted.is_some());
+    }
+
+    #[test]
+    fn apply_patch_preview_counts_hunks_per_file() {
+        let patch = "--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n ctx\n--- a/src/b.rs\n+++ b/src/b.rs\n@@ -1 +1,2 @@\n keep\n+added\n";
+        let preview = approval_diff_preview(
+            std::path::Path::new("."),
+            &call("apply_patch", json!({"patch":patch})),
+            40,
+        )
+        .expect("preview");
+        assert_eq!(preview.paths, vec!["src/a.rs", "src/b.rs"]);
+        assert_eq!((preview.added_lines, preview.removed_lines), (2, 1));
+        assert_eq!(preview.lines[0], "@@ -1,2 +1,2 @@");
+        assert!(approval_diff_preview(
+            std::path::Path::new("."),
+            &call("shell", json!({"cmd":"ls"})),
+            40
+        )
+        .is_none());
+        assert!(approval_diff_preview(
+            std::path::Path::new("."),
+            &call("apply_patch", json!({"patch":patch})),
+            0
+        )
+        .is_none());
+    }
+}
@@ -1,10 +1,14 @@
+use std::path::{Component, Path, PathBuf};
+
 use hex::encode as hex_encode;
 use serde_json::Value;
 use sha2::{Digest, Sha256};
 
-use super::ApprovalKeyVersion;
+use super::{ApprovalKeyVersion, GateContext};
 use crate::target::ExecTargetKind;
+use crate::tools::shell_program_allowlisted;
 use crate::trust::approvals::canonical_json;
+use crate::types::ToolCall;
 
 pub(super) fn with_exec_target_arg(args: &Value, exec_target: ExecTargetKind) -> Value {
     let mut out = match args {
@@ -18,6 +22,7 @@ pub(super) fn with_exec_target_arg(args: &Value, exec_target: ExecTargetKind) ->
                 match exec_target {
                     ExecTargetKind::Host => "host",
                     ExecTargetKind::Docker => "docker",
+                    ExecTargetKind::Ssh => "ssh",
                 }
                 .to_string(),
             ),
@@ -26,6 +31,118 @@ pub(super) fn with_exec_target_arg(args: &Value, exec_target: ExecTargetKind) ->
     out
 }
 
+/// Tells an approval prompt whether a shell call's program is on the
+/// `--allow-shell-cmd` list. `None` for other tools or without a list.
+pub(super) fn shell_allowlist_note(ctx: &GateContext, call: &ToolCall) -> Option<String> {
+    let allowlist = ctx.shell_allowlist.as_ref()?;
+    if call.name != "shell" {
+        return None;
+    }
+    let cmd = call.arguments.get("cmd").and_then(|v| v.as_str())?;
+    Some(if shell_program_allowlisted(allowlist, cmd) {
+        format!("shell command '{cmd}' is pre-approved by the shell allowlist")
+    } else {
+        format!("shell command '{cmd}' is not in the shell allowlist")
+    })
+}
+
+/// Paths a file write would touch, for policy `writes` rules.
+#[derive(Debug, Default)]
+pub(super) struct WriteRuleTargets {
+    /// Workdir-relative, `/`-separated, with symlinks followed.
+    pub(super) paths: Vec<String>,
+    /// Targets that resolve outside the workdir, as the call named them.
+    pub(super) outside_workdir: Vec<String>,
+}
+
+/// `None` for tools that do not write files; empty when an `apply_patch`
+/// diff names no parsable targets.
+pub(super) fn write_rule_paths(call: &ToolCall, workdir: &Path) -> Option<WriteRuleTargets> {
+    if !matches!(
+        call.name.as_str(),
+        "write_file" | "apply_patch" | "edit" | "str_replace"
+    ) {
+        return None;
+    }
+    let path = call
+        .arguments
+        .get("path")
+        .and_then(|v| v.as_str())
+        .filter(|p| !p.is_empty());
+    let paths = match path {
+        Some(path) => vec![path.to_string()],
+        None if call.name == "apply_patch" => call
+            .arguments
+            .get("patch")
+            .and_then(|v| v.as_str())
+            .and_then(|patch| crate::target::split_multi_file_patch(patch).ok())
+            .map(|files| files.into_iter().map(|f| f.path).collect())
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let mut targets = WriteRuleTargets::default();
+    for path in paths {
+        match workdir_relative_write_path(workdir, &path) {
+            Some(relative) => targets.paths.push(relative),
+            None => targets.outside_workdir.push(path),
+        }
+    }
+    Some(targets)
+}
+
+/// Resolves `path` the way the write tools' workdir check does, following
+/// symlinks, and keeps case since policy globs are case-sensitive. `None`
+/// when it lands outside the workdir.
+fn workdir_relative_write_path(workdir: &Path, path: &str) -> Option<String> {
+    let root = std::fs::canonicalize(workdir).unwrap_or_else(|_| workdir.to_path_buf());
+    let resolved = crate::tools::resolve_following_symlinks(&root, path)
+        .unwrap_or_else(|| crate::target::resolve_path(&root, path));
+    let relative = normalize_lexically(&resolved)?
+        .strip_prefix(normalize_lexically(&root)?)
+        .ok()?
+        .to_path_buf();
+    Some(
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+/// `None` when `..` climbs above the root.
+fn normalize_lexically(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return None;
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    Some(out)
+}
+
+/// Why a `taint.deny_sinks` rule stopped `call`. The arguments digest lets
+/// reviewers tie the decision to the exact payload.
+pub(super) fn taint_sink_reason(ctx: &GateContext, call: &ToolCall, pattern: &str) -> String {
+    let canonical_args = canonical_json(&call.arguments).unwrap_or_else(|_| "null".to_string());
+    let sources = if ctx.taint_sources.is_empty() {
+        "unknown".to_string()
+    } else {
+        ctx.taint_sources.join(", ")
+    };
+    format!(
+        "taint sink '{pattern}' matched '{}': tainted context (sources: {sources}) may flow into its arguments (args digest {})",
+        call.name,
+        compute_policy_hash_hex(canonical_args.as_bytes())
+    )
+}
+
 pub fn compute_policy_hash_hex(bytes: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(bytes);
@@ -77,6 +194,7 @@ pub fn compute_approval_key_with_version(
                 match exec_target {
                     ExecTargetKind::Host => "host",
                     ExecTargetKind::Docker => "docker",
+                    ExecTargetKind::Ssh => "ssh",
                 },
                 planner_hash_hex.unwrap_or("none"),
             );
@@ -85,7 +203,7 @@ pub fn compute_approval_key_with_version(
     }
 }
 
-fn normalize_workdir(path: &std::path::Path) -> String {
+pub(super) fn normalize_workdir(path: &std::path::Path) -> String {
     match std::fs::canonicalize(path) {
         Ok(p) => p.display().to_string(),
         Err(_) => path.display().to_string(),
@@ -0,0 +1,171 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use super::helpers::normalize_workdir;
+use super::GateContext;
+use crate::target::ExecTargetKind;
+use crate::trust::approvals::canonical_json;
+use crate::types::ToolCall;
+
+pub const PERSISTED_APPROVAL_SOURCE: &str = "persisted_approval";
+pub const DEFAULT_WORKSPACE_APPROVAL_TTL_HOURS: u32 = 168;
+const PERSISTED_APPROVAL_SCHEMA: &str = "openagent.persisted_approval.v1";
+
+/// `<state_dir>/approvals.jsonl`, next to the per-request `approvals.json`.
+pub fn workspace_approvals_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("approvals.jsonl")
+}
+
+/// One approval granted under `--auto-approve-scope workspace`. It applies
+/// to later runs only while the approval key, key version, workdir, unsafe
+/// mode and exec target all still match and it has not expired.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedApproval {
+    pub schema_version: String,
+    pub id: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub tool: String,
+    /// sha256 of the canonical arguments, for listing without the payload.
+    pub args_digest: String,
+    pub approval_key: String,
+    pub approval_key_version: String,
+    pub workdir: String,
+    pub unsafe_mode: bool,
+    pub exec_target: String,
+    /// The request or auto-approval the grant came from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub granted_from: Option<String>,
+}
+
+impl PersistedApproval {
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        OffsetDateTime::parse(&self.expires_at, &Rfc3339).map_or(true, |exp| now > exp)
+    }
+
+    fn applies_to(&self, ctx: &GateContext, approval_key: &str, now: OffsetDateTime) -> bool {
+        self.approval_key == approval_key
+            && self.approval_key_version == ctx.approval_key_version.as_str()
+            && self.workdir == normalize_workdir(&ctx.workdir)
+            && self.unsafe_mode == ctx.unsafe_mode
+            && self.exec_target == exec_target_name(ctx.exec_target)
+            && !self.is_expired(now)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceApprovals {
+    path: PathBuf,
+    ttl_hours: u32,
+}
+
+impl WorkspaceApprovals {
+    pub fn new(path: PathBuf, ttl_hours: u32) -> Self {
+        Self { path, ttl_hours }
+    }
+
+    /// The live entry for this call, if one was granted earlier.
+    pub fn find_active(
+        &self,
+        ctx: &GateContext,
+        approval_key: &str,
+    ) -> anyhow::Result<Option<PersistedApproval>> {
+        let now = OffsetDateTime::now_utc();
+        Ok(list_persisted_approvals(&self.path)?
+            .into_iter()
+            .find(|entry| entry.applies_to(ctx, approval_key, now)))
+    }
+
+    /// Persists a grant and returns its id; an existing live entry for the
+    /// same call is reused.
+    pub fn grant(
+        &self,
+        ctx: &GateContext,
+        call: &ToolCall,
+        approval_key: &str,
+        granted_from: Option<String>,
+    ) -> anyhow::Result<String> {
+        let _lock = self.lock_state()?;
+        if let Some(existing) = self.find_active(ctx, approval_key)? {
+            return Ok(existing.id);
+        }
+        let now = OffsetDateTime::now_utc();
+        let expires = now + Duration::hours(i64::from(self.ttl_hours));
+        let canonical_args = canonical_json(&call.arguments).unwrap_or_else(|_| "null".to_string());
+        let entry = PersistedApproval {
+            schema_version: PERSISTED_APPROVAL_SCHEMA.to_string(),
+            id: format!("ws-{}", Uuid::new_v4()),
+            created_at: crate::trust::now_rfc3339(),
+            expires_at: expires
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| crate::trust::now_rfc3339()),
+            tool: call.name.clone(),
+            args_digest: crate::store::sha256_hex(canonical_args.as_bytes()),
+            approval_key: approval_key.to_string(),
+            approval_key_version: ctx.approval_key_version.as_str().to_string(),
+            workdir: normalize_workdir(&ctx.workdir),
+            unsafe_mode: ctx.unsafe_mode,
+            exec_target: exec_target_name(ctx.exec_target).to_string(),
+            granted_from,
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(entry.id)
+    }
+
+    fn lock_state(&self) -> anyhow::Result<crate::store::StateLockGuard> {
+        crate::store::acquire_state_lock(self.path.parent().unwrap_or_else(|| Path::new(".")))
+    }
+}
+
+/// Every entry in the store, expired ones included. Unparseable lines are
+/// skipped so one bad line cannot disable the rest.
+pub fn list_persisted_approvals(path: &Path) -> anyhow::Result<Vec<PersistedApproval>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = std::fs::read_to_string(path)?;
+    Ok(raw
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str::<PersistedApproval>(line).ok())
+        .collect())
+}
+
+/// Removes the entry with `id`; false when there is none.
+pub fn revoke_persisted_approval(path: &Path, id: &str) -> anyhow::Result<bool> {
+    let _lock = crate::store::acquire_state_lock(path.parent().unwrap_or_else(|| Path::new(".")))?;
+    let entries = list_persisted_approvals(path)?;
+    let kept = entries.iter().filter(|e| e.id != id).collect::<Vec<_>>();
+    if kept.len() == entries.len() {
+        return Ok(false);
+    }
+    let mut content = String::new();
+    for entry in kept {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    let tmp_path = path.with_extension(format!("tmp.{}", Uuid::new_v4().as_hyphenated()));
+    std::fs::write(&tmp_path, content)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(true)
+}
+
+fn exec_target_name(kind: ExecTargetKind) -> &'static str {
+    match kind {
+        ExecTargetKind::Host => "host",
+        ExecTargetKind::Docker => "docker",
+        ExecTargetKind::Ssh => "ssh",
+    }
+}
@@ -20,6 +20,7 @@ fn nogate_always_allows() {
     let ctx = GateContext {
         workdir: PathBuf::from("."),
         allow_shell: false,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -40,6 +41,7 @@ fn nogate_always_allows() {
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_0".to_string(),
@@ -61,6 +63,7 @@ fn approval_key_matching_allows_when_approved() {
     let ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -81,6 +84,7 @@ fn approval_key_matching_allows_when_approved() {
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_1".to_string(),
@@ -114,6 +118,7 @@ fn approval_ttl_expired_requires_new() {
     let ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -134,6 +139,7 @@ fn approval_ttl_expired_requires_new() {
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_1".to_string(),
@@ -167,6 +173,7 @@ fn approval_max_uses_exhaustion() {
     let ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -187,6 +194,7 @@ fn approval_max_uses_exhaustion() {
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_1".to_string(),
@@ -226,6 +234,7 @@ fn approval_mode_fail_requires_approval() {
     let ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Fail,
         auto_approve_scope: AutoApproveScope::Run,
@@ -246,6 +255,7 @@ fn approval_mode_fail_requires_approval() {
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_1".to_string(),
@@ -265,6 +275,60 @@ fn approval_mode_fail_requires_approval() {
     ));
 }
 
+#[test]
+fn approval_reason_says_whether_shell_program_is_allowlisted() {
+    let tmp = tempdir().expect("tempdir");
+    let mut gate = TrustGate::new(
+        Policy::safe_default(),
+        ApprovalsStore::new(tmp.path().join("approvals.json")),
+        AuditLog::new(tmp.path().join("audit.jsonl")),
+        TrustMode::On,
+        compute_policy_hash_hex(b"default"),
+    );
+    let ctx = GateContext {
+        workdir: tmp.path().to_path_buf(),
+        allow_shell: true,
+        shell_allowlist: Some(vec!["cargo".to_string()]),
+        allow_write: false,
+        approval_mode: ApprovalMode::Interrupt,
+        auto_approve_scope: AutoApproveScope::Run,
+        unsafe_mode: false,
+        unsafe_bypass_allow_flags: false,
+        run_id: Some("r1".to_string()),
+        enable_write_tools: false,
+        max_tool_output_bytes: 200_000,
+        max_read_bytes: 200_000,
+        provider: ProviderKind::Lmstudio,
+        model: "m".to_string(),
+        exec_target: ExecTargetKind::Host,
+        approval_key_version: ApprovalKeyVersion::V1,
+        tool_schema_hashes: BTreeMap::new(),
+        hooks_config_hash_hex: None,
+        planner_hash_hex: None,
+        taint_enabled: false,
+        taint_mode: crate::taint::TaintMode::Propagate,
+        taint_overall: crate::taint::TaintLevel::Clean,
+        taint_sources: Vec::new(),
+        taint_injection_digest: None,
+    };
+    let reason_for = |gate: &mut TrustGate, cmd: &str| {
+        let call = ToolCall {
+            id: format!("tc_{cmd}"),
+            name: "shell".to_string(),
+            arguments: json!({"cmd": cmd}),
+        };
+        match gate.decide(&ctx, &call) {
+            GateDecision::RequireApproval { reason, .. } => reason,
+            other => panic!("expected require_approval, got {other:?}"),
+        }
+    };
+    assert!(reason_for(&mut gate, "/usr/bin/cargo")
+        .ends_with("(shell command '/usr/bin/cargo' is pre-approved by the shell allowlist)"));
+    assert!(
+        reason_for(&mut gate, "rm").ends_with("(shell command 'rm' is not in the shell allowlist)")
+    );
+}
+
 #[test]
 fn approval_mode_auto_run_allows() {
     let tmp = tempdir().expect("tempdir");
@@ -276,6 +340,7 @@ fn approval_mode_auto_run_allows() {
     let ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Auto,
         auto_approve_scope: AutoApproveScope::Run,
@@ -296,6 +361,7 @@ fn approval_mode_auto_run_allows() {
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_abc".to_string(),
@@ -355,6 +421,7 @@ rules:
     let mut ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: false,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -375,6 +442,7 @@ rules:
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     assert!(matches!(
         gate.decide(&ctx, &call),
@@ -456,6 +524,7 @@ fn gate_key_version_matching_v1_vs_v2() {
     let mut ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -476,6 +545,7 @@ fn gate_key_version_matching_v1_vs_v2() {
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Clean,
         taint_sources: Vec::new(),
+        taint_injection_digest: None,
     };
     assert!(matches!(
         gate.decide(&ctx, &call),
@@ -515,6 +585,7 @@ rules:
     let ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -535,6 +606,7 @@ rules:
         taint_mode: crate::taint::TaintMode::PropagateAndEnforce,
         taint_overall: crate::taint::TaintLevel::Tainted,
         taint_sources: vec!["browser".to_string()],
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_taint".to_string(),
@@ -581,6 +653,7 @@ rules:
     let ctx = GateContext {
         workdir: tmp.path().to_path_buf(),
         allow_shell: true,
+        shell_allowlist: None,
         allow_write: false,
         approval_mode: ApprovalMode::Interrupt,
         auto_approve_scope: AutoApproveScope::Run,
@@ -601,6 +674,7 @@ rules:
         taint_mode: crate::taint::TaintMode::Propagate,
         taint_overall: crate::taint::TaintLevel::Tainted,
         taint_sources: vec!["browser".to_string()],
+        taint_injection_digest: None,
     };
     let call = ToolCall {
         id: "tc_taint".to_string(),
@@ -612,3 +686,682 @@ rules:
         _ => panic!("expected allow"),
     }
 }
+
+#[test]
+fn injected_browser_snapshots_gate_write_and_shell_with_span_digest() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/injection");
+    for (fixture, pattern) in [
+        (
+            "snapshot_ignore_instructions.txt",
+            "phrase:ignore previous instructions",
+        ),
+        ("snapshot_tool_call_wrapper.txt", "tool_call_wrapper"),
+        ("snapshot_step_result_envelope.txt", "step_result_envelope"),
+    ] {
+        let snapshot = std::fs::read_to_string(fixtures.join(fixture)).expect("fixture");
+        let snapshot_call = ToolCall {
+            id: "tc_snapshot".to_string(),
+            name: "mcp.playwright.browser_snapshot".to_string(),
+            arguments: json!({}),
+        };
+        let envelope = json!({
+            "schema_version": "openagent.tool_result.v1",
+            "content": snapshot
+        })
+        .to_string();
+        let spans = crate::agent_taint_helpers::compute_taint_spans_for_tool(
+            &snapshot_call,
+            &envelope,
+            None,
+            4096,
+            &[],
+        );
+        let injection = spans
+            .iter()
+            .find(|s| s.source == "injection_suspected")
+            .unwrap_or_else(|| panic!("{fixture}: no injection span"));
+        assert!(injection.detail.contains(pattern), "{fixture}");
+        let mut state = crate::taint::TaintState::new();
+        state.add_tool_spans(&snapshot_call.id, 1, spans.clone());
+
+        let tmp = tempdir().expect("tmp");
+        let policy = Policy::from_yaml(
+            r#"
+version: 2
+default: deny
+rules:
+  - tool: "shell"
+    decision: allow
+  - tool: "write_file"
+    decision: allow
+"#,
+        )
+        .expect("policy");
+        let mut gate = TrustGate::new(
+            policy,
+            ApprovalsStore::new(tmp.path().join("approvals.json")),
+            AuditLog::new(tmp.path().join("audit.jsonl")),
+            TrustMode::On,
+            compute_policy_hash_hex(b"custom"),
+        );
+        let ctx = GateContext {
+            workdir: tmp.path().to_path_buf(),
+            allow_shell: true,
+            shell_allowlist: None,
+            allow_write: true,
+            approval_mode: ApprovalMode::Interrupt,
+            auto_approve_scope: AutoApproveScope::Run,
+            unsafe_mode: false,
+            unsafe_bypass_allow_flags: false,
+            run_id: Some("r".to_string()),
+            enable_write_tools: true,
+            max_tool_output_bytes: 200_000,
+            max_read_bytes: 200_000,
+            provider: ProviderKind::Lmstudio,
+            model: "m".to_string(),
+            exec_target: ExecTargetKind::Host,
+            approval_key_version: ApprovalKeyVersion::V1,
+            tool_schema_hashes: BTreeMap::new(),
+            hooks_config_hash_hex: None,
+            planner_hash_hex: None,
+            taint_enabled: true,
+            taint_mode: crate::taint::TaintMode::PropagateAndEnforce,
+            taint_overall: state.overall,
+            taint_sources: state.last_sources.clone(),
+            taint_injection_digest: state.injection_digest.clone(),
+        };
+        let expected_reason = format!("taint_escalation:injection_suspected:{}", injection.digest);
+        for call in [
+            ToolCall {
+                id: "tc_write".to_string(),
+                name: "write_file".to_string(),
+                arguments: json!({"path":"out.txt","content":"x"}),
+            },
+            ToolCall {
+                id: "tc_shell".to_string(),
+                name: "shell".to_string(),
+                arguments: json!({"cmd":"echo","args":["hi"]}),
+            },
+        ] {
+            match gate.decide(&ctx, &call) {
+                GateDecision::RequireApproval {
+                    escalated,
+                    escalation_reason,
+                    ..
+                } => {
+                    assert!(escalated, "{fixture}: {}", call.name);
+                    assert_eq!(
+                        escalation_reason.as_deref(),
+                        Some(expected_reason.as_str()),
+                        "{fixture}: {}",
+                        call.name
+                    );
+                }
+                other => panic!(
+                    "{fixture}: expected require_approval for {}, got {other:?}",
+                    call.name
+                ),
+            }
+        }
+    }
+}
+
+fn taint_sink_gate(dir: &std::path::Path) -> TrustGate {
+    let policy = Policy::from_yaml(
+        r#"
+version: 2
+default: deny
+rules:
+  - tool: "apply_patch"
+    decision: allow
+  - tool: "shell"
+    decision: allow
+  - tool: "read_file"
+    decision: allow
+  - tool: "mcp.*"
+    decision: allow
+taint:
+  deny_sinks: ["write_file", "apply_patch", "shell", "mcp.*"]
+"#,
+    )
+    .expect("policy");
+    TrustGate::new(
+        policy,
+        ApprovalsStore::new(dir.join("approvals.json")),
+        AuditLog::new(dir.join("audit.jsonl")),
+        TrustMode::On,
+        compute_policy_hash_hex(b"custom"),
+    )
+}
+
+fn taint_sink_ctx(
+    dir: &std::path::Path,
+    mode: crate::taint::TaintMode,
+    overall: crate::taint::TaintLevel,
+) -> GateContext {
+    GateContext {
+        workdir: dir.to_path_buf(),
+        allow_shell: true,
+        shell_allowlist: None,
+        allow_write: true,
+        approval_mode: ApprovalMode::Interrupt,
+        auto_approve_scope: AutoApproveScope::Run,
+        unsafe_mode: false,
+        unsafe_bypass_allow_flags: false,
+        run_id: Some("r".to_string()),
+        enable_write_tools: true,
+        max_tool_output_bytes: 200_000,
+        max_read_bytes: 200_000,
+        provider: ProviderKind::Lmstudio,
+        model: "m".to_string(),
+        exec_target: ExecTargetKind::Host,
+        approval_key_version: ApprovalKeyVersion::V1,
+        tool_schema_hashes: BTreeMap::new(),
+        hooks_config_hash_hex: None,
+        planner_hash_hex: None,
+        taint_enabled: true,
+        taint_mode: mode,
+        taint_overall: overall,
+        taint_sources: vec!["file".to_string()],
+        taint_injection_digest: None,
+    }
+}
+
+fn unrelated_patch_call() -> ToolCall {
+    // A tainted `.env` was read earlier; the patch targets another file.
+    ToolCall {
+        id: "tc_patch".to_string(),
+        name: "apply_patch".to_string(),
+        arguments: json!({"path":"src/lib.rs","patch":"@@ -1 +1 @@\n-a\n+b\n"}),
+    }
+}
+
+#[test]
+fn taint_sink_denies_unrelated_patch_in_enforce_mode() {
+    let tmp = tempdir().expect("tmp");
+    let mut gate = taint_sink_gate(tmp.path());
+    let ctx = taint_sink_ctx(
+        tmp.path(),
+        crate::taint::TaintMode::PropagateAndEnforce,
+        crate::taint::TaintLevel::Tainted,
+    );
+    let call = unrelated_patch_call();
+    let args_digest = compute_policy_hash_hex(
+        crate::trust::approvals::canonical_json(&call.arguments)
+            .expect("canonical")
+            .as_bytes(),
+    );
+    match gate.decide(&ctx, &call) {
+        GateDecision::Deny {
+            reason,
+            source,
+            taint_enforced,
+            ..
+        } => {
+            assert_eq!(source.as_deref(), Some("taint_sink"));
+            assert!(taint_enforced);
+            assert!(reason.contains("taint sink 'apply_patch'"), "{reason}");
+            assert!(reason.contains("sources: file"), "{reason}");
+            assert!(reason.contains(&args_digest), "{reason}");
+        }
+        other => panic!("expected deny, got {other:?}"),
+    }
+    let read = ToolCall {
+        id: "tc_read".to_string(),
+        name: "read_file".to_string(),
+        arguments: json!({"path":"src/lib.rs"}),
+    };
+    assert!(matches!(
+        gate.decide(&ctx, &read),
+        GateDecision::Allow { .. }
+    ));
+}
+
+#[test]
+fn taint_sink_requires_approval_in_propagate_mode() {
+    let tmp = tempdir().expect("tmp");
+    let mut gate = taint_sink_gate(tmp.path());
+    let ctx = taint_sink_ctx(
+        tmp.path(),
+        crate::taint::TaintMode::Propagate,
+        crate::taint::TaintLevel::Tainted,
+    );
+    for call in [
+        unrelated_patch_call(),
+        ToolCall {
+            id: "tc_mcp".to_string(),
+            name: "mcp.fs.write".to_string(),
+            arguments: json!({"path":"out.txt"}),
+        },
+    ] {
+        match gate.decide(&ctx, &call) {
+            GateDecision::RequireApproval {
+                taint_enforced,
+                escalated,
+                escalation_reason,
+                ..
+            } => {
+                assert!(taint_enforced, "{}", call.name);
+                assert!(escalated, "{}", call.name);
+                assert!(
+                    escalation_reason
+                        .as_deref()
+                        .is_some_and(|r| r.starts_with("taint_sink:")),
+                    "{}",
+                    call.name
+                );
+            }
+            other => panic!("expected require_approval for {}, got {other:?}", call.name),
+        }
+    }
+}
+
+#[test]
+fn taint_sink_passes_clean_context() {
+    let tmp = tempdir().expect("tmp");
+    let mut gate = taint_sink_gate(tmp.path());
+    let ctx = taint_sink_ctx(
+        tmp.path(),
+        crate::taint::TaintMode::PropagateAndEnforce,
+        crate::taint::TaintLevel::Clean,
+    );
+    match gate.decide(&ctx, &unrelated_patch_call()) {
+        GateDecision::Allow {
+            taint_enforced,
+            escalated,
+            ..
+        } => {
+            assert!(!taint_enforced);
+            assert!(!escalated);
+        }
+        other => panic!("expected allow, got {other:?}"),
+    }
+}
+
+fn workspace_scope_ctx(dir: &std::path::Path) -> GateContext {
+    GateContext {
+        workdir: dir.to_path_buf(),
+        allow_shell: true,
+        shell_allowlist: None,
+        allow_write: false,
+        approval_mode: ApprovalMode::Interrupt,
+        auto_approve_scope: AutoApproveScope::Workspace,
+        unsafe_mode: false,
+        unsafe_bypass_allow_flags: false,
+        run_id: Some("r1".to_string()),
+        enable_write_tools: false,
+        max_tool_output_bytes: 200_000,
+        max_read_bytes: 200_000,
+        provider: ProviderKind::Lmstudio,
+        model: "m".to_string(),
+        exec_target: ExecTargetKind::Host,
+        approval_key_version: ApprovalKeyVersion::V1,
+        tool_schema_hashes: BTreeMap::new(),
+        hooks_config_hash_hex: None,
+        planner_hash_hex: None,
+        taint_enabled: false,
+        taint_mode: crate::taint::TaintMode::Propagate,
+        taint_overall: crate::taint::TaintLevel::Clean,
+        taint_sources: Vec::new(),
+        taint_injection_digest: None,
+    }
+}
+
+#[test]
+fn workspace_scope_persists_granted_approvals_across_runs() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    let workspace_path = crate::gate::workspace_approvals_path(&state_dir);
+    let new_gate = || {
+        TrustGate::new(
+            Policy::safe_default(),
+            ApprovalsStore::new(state_dir.join("approvals.json")),
+            AuditLog::new(state_dir.join("audit.jsonl")),
+            TrustMode::On,
+            compute_policy_hash_hex(b"default"),
+        )
+        .with_workspace_approvals(crate::gate::WorkspaceApprovals::new(
+            workspace_path.clone(),
+            24,
+        ))
+    };
+    let ctx = workspace_scope_ctx(tmp.path());
+    let call = ToolCall {
+        id: "tc_1".to_string(),
+        name: "shell".to_string(),
+        arguments: json!({"cmd":"cargo","args":["test"]}),
+    };
+
+    let mut gate = new_gate();
+    let GateDecision::RequireApproval { approval_id, .. } = gate.decide(&ctx, &call) else {
+        panic!("expected require_approval");
+    };
+    ApprovalsStore::new(state_dir.join("approvals.json"))
+        .approve(&approval_id, None, None)
+        .expect("approve");
+    let GateDecision::Allow {
+        approval_id: Some(granted),
+        ..
+    } = gate.decide(&ctx, &call)
+    else {
+        panic!("expected allow");
+    };
+    let persisted = crate::gate::list_persisted_approvals(&workspace_path).expect("list");
+    assert_eq!(persisted.len(), 1);
+    assert_eq!(persisted[0].id, granted);
+    assert_eq!(
+        persisted[0].granted_from.as_deref(),
+        Some(approval_id.as_str())
+    );
+
+    // A later run starts without the request store.
+    std::fs::remove_file(state_dir.join("approvals.json")).expect("rm");
+    let mut gate = new_gate();
+    match gate.decide(&ctx, &call) {
+        GateDecision::Allow {
+            approval_id,
+            source,
+            ..
+        } => {
+            assert_eq!(approval_id.as_deref(), Some(granted.as_str()));
+            assert_eq!(source.as_deref(), Some("persisted_approval"));
+        }
+        other => panic!("expected persisted allow, got {other:?}"),
+    }
+
+    let mut unsafe_ctx = workspace_scope_ctx(tmp.path());
+    unsafe_ctx.unsafe_mode = true;
+    let mut docker_ctx = workspace_scope_ctx(tmp.path());
+    docker_ctx.exec_target = ExecTargetKind::Docker;
+    let mut v2_ctx = workspace_scope_ctx(tmp.path());
+    v2_ctx.approval_key_version = ApprovalKeyVersion::V2;
+    let mut run_scope_ctx = workspace_scope_ctx(tmp.path());
+    run_scope_ctx.auto_approve_scope = AutoApproveScope::Run;
+    for other in [&unsafe_ctx, &docker_ctx, &v2_ctx, &run_scope_ctx] {
+        assert!(
+            matches!(
+                gate.decide(other, &call),
+                GateDecision::RequireApproval { .. }
+            ),
+            "{other:?}"
+        );
+    }
+
+    assert!(crate::gate::revoke_persisted_approval(&workspace_path, &granted).expect("revoke"));
+    assert!(!crate::gate::revoke_persisted_approval(&workspace_path, &granted).expect("revoke"));
+    assert!(matches!(
+        gate.decide(&ctx, &call),
+        GateDecision::RequireApproval { .. }
+    ));
+}
+
+#[test]
+fn workspace_scope_auto_mode_persists_but_taint_escalations_do_not() {
+    let tmp = tempdir().expect("tempdir");
+    let workspace_path = crate::gate::workspace_approvals_path(tmp.path());
+    let policy = Policy::from_yaml(
+        r#"
+version: 2
+default: deny
+rules:
+  - tool: "shell"
+    decision: require_approval
+  - tool: "write_file"
+    decision: allow
+"#,
+    )
+    .expect("policy");
+    let mut gate = TrustGate::new(
+        policy,
+        ApprovalsStore::new(tmp.path().join("approvals.json")),
+        AuditLog::new(tmp.path().join("audit.jsonl")),
+        TrustMode::On,
+        compute_policy_hash_hex(b"default"),
+    )
+    .with_workspace_approvals(crate::gate::WorkspaceApprovals::new(
+        workspace_path.clone(),
+        24,
+    ));
+    let mut ctx = workspace_scope_ctx(tmp.path());
+    ctx.approval_mode = ApprovalMode::Auto;
+    let call = ToolCall {
+        id: "tc_1".to_string(),
+        name: "shell".to_string(),
+        arguments: json!({"cmd":"cargo","args":["test"]}),
+    };
+    assert!(matches!(
+        gate.decide(&ctx, &call),
+        GateDecision::Allow { .. }
+    ));
+    assert_eq!(
+        crate::gate::list_persisted_approvals(&workspace_path)
+            .expect("list")
+            .len(),
+        1
+    );
+
+    ctx.taint_enabled = true;
+    ctx.taint_mode = crate::taint::TaintMode::PropagateAndEnforce;
+    ctx.taint_overall = crate::taint::TaintLevel::Tainted;
+    ctx.allow_write = true;
+    ctx.enable_write_tools = true;
+    let write = ToolCall {
+        id: "tc_2".to_string(),
+        name: "write_file".to_string(),
+        arguments: json!({"path":"a.txt","content":"x"}),
+    };
+    match gate.decide(&ctx, &write) {
+        GateDecision::Allow { escalated, .. } => assert!(escalated),
+        other => panic!("expected escalated allow, got {other:?}"),
+    }
+    assert_eq!(
+        crate::gate::list_persisted_approvals(&workspace_path)
+            .expect("list")
+            .len(),
+        1
+    );
+}
+
+fn run_config(dir: &std::path::Path) -> crate::gate::RunConfig {
+    crate::gate::RunConfig {
+        workdir: dir.to_path_buf(),
+        allow_shell: false,
+        shell_allowlist: None,
+        allow_write: true,
+        enable_write_tools: true,
+        unsafe_mode: false,
+        unsafe_bypass_allow_flags: false,
+        max_tool_output_bytes: 200_000,
+        max_read_bytes: 100_000,
+        provider: ProviderKind::Ollama,
+        model: "m".to_string(),
+        exec_target: ExecTargetKind::Docker,
+        approval_mode: ApprovalMode::Fail,
+        auto_approve_scope: AutoApproveScope::Session,
+        approval_key_version: ApprovalKeyVersion::V2,
+        taint_enabled: true,
+        taint_mode: crate::taint::TaintMode::PropagateAndEnforce,
+    }
+}
+
+#[test]
+fn from_run_config_copies_settings_and_starts_per_run_state_empty() {
+    let tmp = tempdir().expect("tmp");
+    let ctx = GateContext::from_run_config(&run_config(tmp.path())).expect("ctx");
+    assert_eq!(ctx.workdir, tmp.path());
+    assert!(ctx.allow_write && ctx.enable_write_tools && !ctx.allow_shell);
+    assert_eq!(ctx.max_read_bytes, 100_000);
+    assert_eq!(ctx.provider, ProviderKind::Ollama);
+    assert_eq!(ctx.exec_target, ExecTargetKind::Docker);
+    assert_eq!(ctx.approval_mode, ApprovalMode::Fail);
+    assert_eq!(ctx.auto_approve_scope, AutoApproveScope::Session);
+    assert_eq!(ctx.approval_key_version, ApprovalKeyVersion::V2);
+    assert!(ctx.taint_enabled);
+    assert_eq!(ctx.taint_mode, crate::taint::TaintMode::PropagateAndEnforce);
+    assert_eq!(ctx.taint_overall, crate::taint::TaintLevel::Clean);
+    assert!(ctx.run_id.is_none() && ctx.tool_schema_hashes.is_empty());
+    assert!(ctx.taint_sources.is_empty() && ctx.planner_hash_hex.is_none());
+}
+
+#[test]
+fn from_run_config_rejects_inconsistent_capabilities() {
+    let tmp = tempdir().expect("tmp");
+    let mut config = run_config(tmp.path());
+    config.allow_write = false;
+    let err = GateContext::from_run_config(&config).expect_err("write tools without writes");
+    assert!(err.to_string().contains("--allow-write"), "{err}");
+    config.enable_write_tools = false;
+    assert!(GateContext::from_run_config(&config).is_ok());
+
+    config.unsafe_bypass_allow_flags = true;
+    let err = GateContext::from_run_config(&config).expect_err("bypass on docker");
+    assert!(err.to_string().contains("host target"), "{err}");
+    config.exec_target = ExecTargetKind::Host;
+    config.enable_write_tools = true;
+    assert!(
+        GateContext::from_run_config(&config).is_ok(),
+        "the bypass lifts the allow-write requirement on the host"
+    );
+}
+
+fn write_rules_gate(dir: &std::path::Path) -> (TrustGate, GateContext) {
+    let policy = Policy::from_yaml(
+        r#"
+version: 3
+default: deny
+rules:
+  - tool: "write_file"
+    decision: allow
+  - tool: "apply_patch"
+    decision: allow
+writes:
+  default: deny
+  rules:
+    - glob: "src/**"
+      action: allow
+    - glob: "Cargo.toml"
+      action: approve
+    - glob: ".github/**"
+      action: approve
+"#,
+    )
+    .expect("policy");
+    let gate = TrustGate::new(
+        policy,
+        ApprovalsStore::new(dir.join("approvals.json")),
+        AuditLog::new(dir.join("audit.jsonl")),
+        TrustMode::On,
+        compute_policy_hash_hex(b"writes"),
+    );
+    let mut config = run_config(dir);
+    config.exec_target = ExecTargetKind::Host;
+    config.approval_mode = ApprovalMode::Interrupt;
+    config.auto_approve_scope = AutoApproveScope::Run;
+    config.taint_enabled = false;
+    let ctx = GateContext::from_run_config(&config).expect("ctx");
+    (gate, ctx)
+}
+
+fn write_call(path: &str) -> ToolCall {
+    ToolCall {
+        id: "tc_w".to_string(),
+        name: "write_file".to_string(),
+        arguments: json!({"path": path, "content": "x"}),
+    }
+}
+
+#[test]
+fn write_rule_allows_matching_path() {
+    let tmp = tempdir().expect("tmp");
+    let (mut gate, ctx) = write_rules_gate(tmp.path());
+    match gate.decide(&ctx, &write_call("./src/lib.rs")) {
+        GateDecision::Allow { reason, source, .. } => {
+            assert_eq!(source.as_deref(), Some("policy_write_rule"));
+            assert_eq!(
+                reason.as_deref(),
+                Some("write rule 'src/**' allows 'src/lib.rs'")
+            );
+        }
+        other => panic!("expected allow, got {other:?}"),
+    }
+}
+
+#[test]
+fn write_rule_default_denies_unmatched_path() {
+    let tmp = tempdir().expect("tmp");
+    let (mut gate, ctx) = write_rules_gate(tmp.path());
+    let absolute = tmp.path().join("README.md").display().to_string();
+    match gate.decide(&ctx, &write_call(&absolute)) {
+        GateDecision::Deny { reason, source, .. } => {
+            assert_eq!(source.as_deref(), Some("policy_write_rule"));
+            assert!(reason.contains("'README.md'"), "{reason}");
+            assert!(reason.contains("writes default denies"), "{reason}");
+        }
+        other => panic!("expected deny, got {other:?}"),
+    }
+}
+
+#[test]
+fn write_rule_requires_approval_and_strictest_patch_path_decides() {
+    let tmp = tempdir().expect("tmp");
+    let (mut gate, ctx) = write_rules_gate(tmp.path());
+    match gate.decide(&ctx, &write_call("Cargo.toml")) {
+        GateDecision::RequireApproval { reason, source, .. } => {
+            assert_eq!(source.as_deref(), Some("policy_write_rule"));
+            assert!(reason.contains("write rule 'Cargo.toml'"), "{reason}");
+        }
+        other => panic!("expected approval, got {other:?}"),
+    }
+    let patch = ToolCall {
+        id: "tc_p".to_string(),
+        name: "apply_patch".to_string(),
+        arguments: json!({
+            "patch": "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n--- a/.github/ci.yml\n+++ b/.github/ci.yml\n@@ -1 +1 @@\n-a\n+b\n"
+        }),
+    };
+    match gate.decide(&ctx, &patch) {
+        GateDecision::RequireApproval { reason, .. } => {
+            assert!(reason.contains("'.github/**'"), "{reason}");
+        }
+        other => panic!("expected approval, got {other:?}"),
+    }
+}
+
+#[test]
+fn write_rule_targets_outside_the_workdir_are_denied() {
+    let tmp = tempdir().expect("tmp");
+    let (mut gate, ctx) = write_rules_gate(tmp.path());
+    let outside = tmp.path().parent().expect("parent").join("src/lib.rs");
+    for path in ["../src/lib.rs", outside.to_str().expect("utf8")] {
+        match gate.decide(&ctx, &write_call(path)) {
+            GateDecision::Deny { reason, source, .. } => {
+                assert_eq!(source.as_deref(), Some("policy_write_rule"));
+                assert!(reason.contains("resolves outside the workdir"), "{reason}");
+            }
+            other => panic!("expected deny for {path}, got {other:?}"),
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn write_rule_paths_follow_symlinks() {
+    let tmp = tempdir().expect("tmp");
+    let outside = tempdir().expect("outside");
+    std::fs::create_dir(tmp.path().join("src")).expect("src");
+    std::fs::create_dir(tmp.path().join(".github")).expect(".github");
+    std::os::unix::fs::symlink(outside.path(), tmp.path().join("src/escape")).expect("symlink");
+    std::os::unix::fs::symlink(tmp.path().join(".github"), tmp.path().join("src/ci"))
+        .expect("symlink");
+    let (mut gate, ctx) = write_rules_gate(tmp.path());
+    assert!(matches!(
+        gate.decide(&ctx, &write_call("src/escape/a.rs")),
+        GateDecision::Deny { .. }
+    ));
+    match gate.decide(&ctx, &write_call("src/ci/ci.yml")) {
+        GateDecision::RequireApproval { reason, .. } => {
+            assert!(reason.contains("'.github/ci.yml'"), "{reason}");
+        }
+        other => panic!("expected approval, got {other:?}"),
+    }
+}
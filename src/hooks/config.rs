@@ -12,11 +12,14 @@ pub enum HooksMode {
     On,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
 #[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
 pub enum HookStage {
     PreModel,
+    PreTool,
     ToolResult,
+    PostRun,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +44,16 @@ pub struct HookConfig {
     pub args: Vec<String>,
     pub timeout_ms: Option<u64>,
     pub r#match: Option<HookMatch>,
+    /// Parent environment variables passed through in addition to the default
+    /// allowlist, e.g. a token the hook needs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_passthrough: Vec<String>,
+    /// Overrides `--hooks-max-invocations` for this hook; 0 means unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_invocations_per_run: Option<u32>,
+    /// Overrides `--hooks-max-cumulative-ms` for this hook; 0 means unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cumulative_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -126,6 +139,12 @@ impl LoadedHook {
     }
 }
 
+/// Runs the per-hook checks of [`LoadedHooks::load`] on one entry.
+pub fn check_hook(hook: &HookConfig) -> anyhow::Result<()> {
+    validate_hook(hook)?;
+    LoadedHook::from_config(hook.clone()).map(|_| ())
+}
+
 fn validate_hook(hook: &HookConfig) -> anyhow::Result<()> {
     if hook.name.trim().is_empty() {
         return Err(anyhow!("hook name must not be empty"));
@@ -139,6 +158,15 @@ fn validate_hook(hook: &HookConfig) -> anyhow::Result<()> {
             hook.name
         ));
     }
+    for var in &hook.env_passthrough {
+        if var.is_empty() || var.contains('=') || var.contains('\0') {
+            return Err(anyhow!(
+                "hook '{}' env_passthrough has invalid variable name '{}'",
+                hook.name,
+                var
+            ));
+        }
+    }
     Ok(())
 }
 
@@ -162,6 +190,19 @@ hooks:
   #   timeout_ms: 2000
   #   match:
   #     tools: ["shell", "read_file", "mcp.playwright.*"]
+  #   env_passthrough: ["REDACT_RULES_TOKEN"]
+  #   max_invocations_per_run: 200
+  #   max_cumulative_ms: 60000
+  # - name: "normalize-paths"
+  #   stages: ["pre_tool"]
+  #   command: "python3"
+  #   args: ["scripts/normalize_paths.py"]
+  #   match:
+  #     tools: ["read_file", "list_dir"]
+  # - name: "notify"
+  #   stages: ["post_run"]
+  #   command: "sh"
+  #   args: ["scripts/notify.sh"]
 "#;
     std::fs::write(path, template)?;
     Ok(())
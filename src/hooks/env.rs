@@ -0,0 +1,222 @@
+use crate::hooks::config::HookConfig;
+use crate::hooks::protocol::{HookInput, HookStageWire};
+
+/// Parent variables a hook inherits by default: enough to locate interpreters
+/// and behave like a login shell, nothing that typically carries credentials.
+/// Anything else (provider API keys included) needs `env_passthrough`.
+pub const INHERITED_ENV_ALLOWLIST: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TERM",
+    "TZ",
+    "TMPDIR",
+    "TMP",
+    "TEMP",
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "COMSPEC",
+    "PATHEXT",
+    "WINDIR",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+];
+
+/// Upper bound for any single variable handed to a hook. Context values are
+/// truncated to it; passthrough values above it are withheld.
+pub const MAX_HOOK_ENV_VALUE_BYTES: usize = 4096;
+
+pub const HOOK_ENV_PREFIX: &str = "LOCALAGENT_";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookEnv {
+    pub vars: Vec<(String, String)>,
+    /// Passthrough variables withheld for exceeding `MAX_HOOK_ENV_VALUE_BYTES`.
+    pub refused: Vec<String>,
+}
+
+/// Builds the complete environment for one hook invocation from an explicit
+/// parent environment, so the result does not depend on the process env.
+pub fn build_hook_env<I>(hook: &HookConfig, input: &HookInput, parent: I) -> HookEnv
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut env = HookEnv::default();
+    for (key, value) in parent {
+        if key.starts_with(HOOK_ENV_PREFIX) {
+            continue;
+        }
+        if is_allowlisted(&key) {
+            env.vars.push((key, value));
+        } else if hook.env_passthrough.iter().any(|p| env_name_eq(p, &key)) {
+            if value.len() > MAX_HOOK_ENV_VALUE_BYTES {
+                env.refused.push(key);
+            } else {
+                env.vars.push((key, value));
+            }
+        }
+    }
+    for (name, value) in context_vars(input) {
+        let (value, _) = truncate_utf8_to_bytes(&value, MAX_HOOK_ENV_VALUE_BYTES);
+        env.vars.push((format!("{HOOK_ENV_PREFIX}{name}"), value));
+    }
+    env
+}
+
+fn context_vars(input: &HookInput) -> Vec<(&'static str, String)> {
+    let event = match input.stage {
+        HookStageWire::PreModel => "pre_model",
+        HookStageWire::PreTool => "pre_tool",
+        HookStageWire::ToolResult => "tool_result",
+        HookStageWire::PostRun => "post_run",
+    };
+    let mut vars = vec![
+        ("RUN_ID", input.run_id.clone()),
+        ("EVENT", event.to_string()),
+        ("STEP", input.step.to_string()),
+        ("WORKDIR", input.workdir.clone()),
+    ];
+    for (name, field) in [
+        ("TOOL_NAME", "tool_name"),
+        ("TOOL_CALL_ID", "tool_call_id"),
+        ("EXIT_REASON", "exit_reason"),
+    ] {
+        if let Some(v) = input.payload.get(field).and_then(|v| v.as_str()) {
+            vars.push((name, v.to_string()));
+        }
+    }
+    if let Some(seq) = input.exec_seq {
+        vars.push(("EXEC_SEQ", seq.to_string()));
+    }
+    if let Some(decision) = &input.decision {
+        vars.push(("DECISION", decision.clone()));
+    }
+    vars
+}
+
+fn is_allowlisted(key: &str) -> bool {
+    INHERITED_ENV_ALLOWLIST.iter().any(|a| env_name_eq(a, key))
+}
+
+/// Windows treats variable names case-insensitively.
+fn env_name_eq(a: &str, b: &str) -> bool {
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+fn truncate_utf8_to_bytes(input: &str, max_bytes: usize) -> (String, bool) {
+    if input.len() <= max_bytes {
+        return (input.to_string(), false);
+    }
+    let mut end = max_bytes;
+    while end > 0 && !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    (input[..end].to_string(), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::config::HookStage;
+
+    fn hook(passthrough: &[&str]) -> HookConfig {
+        HookConfig {
+            name: "h".to_string(),
+            stages: vec![HookStage::ToolResult],
+            command: "true".to_string(),
+            args: Vec::new(),
+            timeout_ms: None,
+            r#match: None,
+            env_passthrough: passthrough.iter().map(|s| s.to_string()).collect(),
+            max_invocations_per_run: None,
+            max_cumulative_ms: None,
+        }
+    }
+
+    fn input() -> HookInput {
+        let mut input = crate::hooks::runner::make_tool_result_input(
+            "run_1",
+            3,
+            "mock",
+            "m",
+            std::path::Path::new("/nonexistent/work"),
+            serde_json::json!({"tool_call_id": "tc_9", "tool_name": "shell"}),
+        );
+        input.exec_seq = Some(4);
+        input.decision = Some("allow".to_string());
+        input
+    }
+
+    fn parent(extra: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut vars = vec![
+            ("PATH".to_string(), "/bin".to_string()),
+            ("OPENAI_API_KEY".to_string(), "sk-parent".to_string()),
+            ("LOCALAGENT_RUN_ID".to_string(), "spoofed".to_string()),
+        ];
+        vars.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        vars
+    }
+
+    fn get<'a>(env: &'a HookEnv, key: &str) -> Option<&'a str> {
+        env.vars
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn context_fields_are_exported_and_secrets_scrubbed() {
+        let env = build_hook_env(&hook(&[]), &input(), parent(&[]));
+        assert_eq!(get(&env, "LOCALAGENT_RUN_ID"), Some("run_1"));
+        assert_eq!(get(&env, "LOCALAGENT_EVENT"), Some("tool_result"));
+        assert_eq!(get(&env, "LOCALAGENT_TOOL_NAME"), Some("shell"));
+        assert_eq!(get(&env, "LOCALAGENT_TOOL_CALL_ID"), Some("tc_9"));
+        assert_eq!(get(&env, "LOCALAGENT_EXEC_SEQ"), Some("4"));
+        assert_eq!(get(&env, "LOCALAGENT_DECISION"), Some("allow"));
+        assert_eq!(get(&env, "LOCALAGENT_WORKDIR"), Some("/nonexistent/work"));
+        assert_eq!(get(&env, "PATH"), Some("/bin"));
+        assert_eq!(get(&env, "OPENAI_API_KEY"), None);
+        assert_eq!(
+            env.vars
+                .iter()
+                .filter(|(k, _)| k == "LOCALAGENT_RUN_ID")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn passthrough_opts_in_named_variables() {
+        let env = build_hook_env(&hook(&["OPENAI_API_KEY"]), &input(), parent(&[]));
+        assert_eq!(get(&env, "OPENAI_API_KEY"), Some("sk-parent"));
+    }
+
+    #[test]
+    fn oversized_values_are_truncated_or_refused() {
+        let big = "x".repeat(MAX_HOOK_ENV_VALUE_BYTES + 10);
+        let mut long_input = input();
+        long_input.run_id = big.clone();
+        let env = build_hook_env(
+            &hook(&["BIG_VAR"]),
+            &long_input,
+            parent(&[("BIG_VAR", big.as_str())]),
+        );
+        assert_eq!(
+            get(&env, "LOCALAGENT_RUN_ID").map(str::len),
+            Some(MAX_HOOK_ENV_VALUE_BYTES)
+        );
+        assert_eq!(get(&env, "BIG_VAR"), None);
+        assert_eq!(env.refused, vec!["BIG_VAR".to_string()]);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod config;
+pub mod env;
 pub mod protocol;
 pub mod runner;
+pub mod session;
+pub mod validate;
@@ -2,13 +2,15 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::compaction::CompactionSettings;
-use crate::types::{Message, ToolDef};
+use crate::types::{Message, TokenUsage, ToolDef};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum HookStageWire {
     PreModel,
+    PreTool,
     ToolResult,
+    PostRun,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +24,12 @@ pub struct HookInput {
     pub workdir: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub caps: Option<Value>,
+    /// 1-based tool execution number in the run (tool_result stage).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_seq: Option<u64>,
+    /// Gate decision for the tool call this stage observes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision: Option<String>,
     pub payload: Value,
 }
 
@@ -55,6 +63,14 @@ impl From<&CompactionSettings> for PreModelCompactionPayload {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreToolPayload {
+    pub tool_call_id: String,
+    pub tool_name: String,
+    /// Arguments as left by earlier pre_tool hooks, if any rewrote them.
+    pub arguments: Value,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResultPayload {
     pub tool_call_id: String,
@@ -64,6 +80,43 @@ pub struct ToolResultPayload {
     pub truncated: bool,
 }
 
+/// What a post_run hook sees once the run has finished, whatever its exit
+/// reason.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostRunPayload {
+    pub run_id: String,
+    pub exit_reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Cut to `--hooks-max-stdout-bytes`.
+    pub final_output: String,
+    pub final_output_truncated: bool,
+    pub tool_call_count: usize,
+    pub budget: PostRunBudgetPayload,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taint: Option<PostRunTaintPayload>,
+}
+
+/// Usage against the run's limits; a limit of `0` is unlimited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostRunBudgetPayload {
+    pub steps: usize,
+    pub max_steps: usize,
+    pub tool_calls: usize,
+    pub max_total_tool_calls: usize,
+    pub wall_time_ms: u64,
+    pub max_wall_time_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostRunTaintPayload {
+    pub overall: String,
+    /// Tool calls whose output contributed taint.
+    pub source_count: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookOutput {
     pub schema_version: String,
@@ -101,6 +154,25 @@ pub struct ToolResultModifyPayload {
     pub truncated: Option<bool>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreToolModifyPayload {
+    /// Replaces the call's arguments before gating and execution. Must be an
+    /// object that still passes the tool schema and does not broaden the call.
+    #[serde(default)]
+    pub rewrite_arguments: Option<Value>,
+}
+
+/// Arguments a pre_tool hook rewrote, as recorded on the tool decision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookArgsRewrite {
+    /// Hooks whose rewrite was applied, in run order.
+    pub hooks: Vec<String>,
+    /// sha256 of the arguments the model sent.
+    pub original_args_sha256: String,
+    /// Arguments the gate and the tool saw.
+    pub arguments: Value,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HookInvocationReport {
     pub ts: String,
@@ -120,4 +192,34 @@ pub struct HookInvocationReport {
     pub appended_message_count: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub appended_digests: Option<Vec<String>>,
+    /// Set when the hook was not run because its budget was exhausted.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub skipped_due_to_budget: bool,
+}
+
+/// Hook totals for one run, aggregated from its invocation reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookRunStats {
+    pub invocations: usize,
+    pub skipped_due_to_budget: usize,
+    pub total_duration_ms: u128,
+}
+
+impl HookRunStats {
+    pub fn from_reports(reports: &[HookInvocationReport]) -> Self {
+        let mut stats = Self::default();
+        for report in reports {
+            if report.skipped_due_to_budget {
+                stats.skipped_due_to_budget += 1;
+            } else {
+                stats.invocations += 1;
+                stats.total_duration_ms += report.duration_ms;
+            }
+        }
+        stats
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
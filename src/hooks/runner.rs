@@ -1,13 +1,16 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 
 use crate::hooks::config::{HookStage, HooksMode, LoadedHook, LoadedHooks};
+use crate::hooks::env::{build_hook_env, MAX_HOOK_ENV_VALUE_BYTES};
 use crate::hooks::protocol::{
-    HookAction, HookInput, HookInvocationReport, HookOutput, HookStageWire, PreModelModifyPayload,
-    ToolResultModifyPayload,
+    HookAction, HookArgsRewrite, HookInput, HookInvocationReport, HookOutput, HookStageWire,
+    PreModelModifyPayload, PreToolModifyPayload, ToolResultModifyPayload,
 };
 use crate::store::sha256_hex;
 use crate::types::{Message, Role};
@@ -19,6 +22,12 @@ pub struct HookRuntimeConfig {
     pub strict: bool,
     pub timeout_ms: u64,
     pub max_stdout_bytes: usize,
+    /// Per-hook invocation cap for one run; 0 means unlimited.
+    pub max_invocations_per_run: u32,
+    /// Per-hook cap on summed invocation time for one run; 0 means unlimited.
+    pub max_cumulative_ms: u64,
+    /// Fail the run at the first exhausted budget instead of skipping.
+    pub budget_strict: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -27,8 +36,78 @@ pub struct HookManager {
     pub strict: bool,
     pub timeout_ms: u64,
     pub max_stdout_bytes: usize,
+    pub max_invocations_per_run: u32,
+    pub max_cumulative_ms: u64,
+    pub budget_strict: bool,
     pub config_path: PathBuf,
     pub hooks: Vec<LoadedHook>,
+    /// Hash of the config file `hooks` was loaded from; `None` when hooks are
+    /// off or the file is missing.
+    pub config_hash_hex: Option<String>,
+    pub(crate) budget_usage: Arc<Mutex<HookBudgetUsage>>,
+    /// Working directory for hook processes; `None` inherits the agent's.
+    pub(crate) current_dir: Option<PathBuf>,
+}
+
+/// Budget consumption per hook name, reset whenever a new run id shows up.
+#[derive(Debug, Default)]
+pub(crate) struct HookBudgetUsage {
+    run_id: String,
+    per_hook: BTreeMap<String, HookUsage>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct HookUsage {
+    invocations: u32,
+    cumulative_ms: u64,
+    exhausted: bool,
+}
+
+impl HookBudgetUsage {
+    fn for_hook(&mut self, run_id: &str, hook_name: &str) -> &mut HookUsage {
+        if self.run_id != run_id {
+            self.run_id = run_id.to_string();
+            self.per_hook.clear();
+        }
+        self.per_hook.entry(hook_name.to_string()).or_default()
+    }
+}
+
+/// First exhaustion of a hook's budget in a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookBudgetExhaustion {
+    pub hook_name: String,
+    pub stage: String,
+    /// `max_invocations_per_run` or `max_cumulative_ms`.
+    pub limit: String,
+    pub limit_value: u64,
+    pub invocations: u32,
+    pub cumulative_ms: u64,
+}
+
+enum BudgetCheck {
+    Run {
+        timeout_ms: u64,
+    },
+    Skip {
+        message: String,
+        exhaustion: Option<HookBudgetExhaustion>,
+    },
+}
+
+/// Result of [`HookManager::reload`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HooksReload {
+    Unchanged,
+    Reloaded {
+        old_hash_hex: Option<String>,
+        new_hash_hex: Option<String>,
+    },
+    /// The config changed but did not load; the previous one stays active.
+    Failed {
+        active_hash_hex: Option<String>,
+        error: String,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +120,27 @@ pub struct PreModelHookResult {
     pub append_messages: Vec<Message>,
     pub abort_reason: Option<String>,
     pub invocations: Vec<HookInvocationReport>,
+    pub budget_exhausted: Vec<HookBudgetExhaustion>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PreToolHookResult {
+    /// Arguments after every accepted rewrite; the model's when none applied.
+    pub arguments: serde_json::Value,
+    /// Set when at least one hook's rewrite was accepted.
+    pub rewrite: Option<HookArgsRewrite>,
+    pub abort_reason: Option<String>,
+    pub invocations: Vec<HookInvocationReport>,
+    pub budget_exhausted: Vec<HookBudgetExhaustion>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PostRunHookResult {
+    /// Budget skips are included, marked `skipped_due_to_budget`.
+    pub invocations: Vec<HookInvocationReport>,
+    /// First failure under `strict` or `budget_strict`; post_run hooks after
+    /// it did not run. The run's exit reason never depends on it.
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +153,7 @@ pub struct ToolResultHookResult {
     pub output_digest: String,
     pub input_len: usize,
     pub output_len: usize,
+    pub budget_exhausted: Vec<HookBudgetExhaustion>,
 }
 
 impl HookManager {
@@ -75,16 +176,55 @@ impl HookManager {
                 }
             }
         };
+        // After the template write, so a session does not see it as an edit.
+        let config_hash_hex =
+            crate::ops_helpers::compute_hooks_config_hash_hex(cfg.mode, &cfg.config_path);
         Ok(Self {
             mode: cfg.mode,
             strict: cfg.strict,
             timeout_ms: cfg.timeout_ms,
             max_stdout_bytes: cfg.max_stdout_bytes,
+            max_invocations_per_run: cfg.max_invocations_per_run,
+            max_cumulative_ms: cfg.max_cumulative_ms,
+            budget_strict: cfg.budget_strict,
             config_path: cfg.config_path,
             hooks,
+            config_hash_hex,
+            budget_usage: Arc::default(),
+            current_dir: None,
         })
     }
 
+    /// Re-reads the config file if its hash changed. Only call this between
+    /// runs: hooks must not change under a run in progress. When the new
+    /// config fails to load, the current hooks and hash stay active.
+    pub fn reload(&mut self) -> HooksReload {
+        let new_hash_hex =
+            crate::ops_helpers::compute_hooks_config_hash_hex(self.mode, &self.config_path);
+        if new_hash_hex == self.config_hash_hex {
+            return HooksReload::Unchanged;
+        }
+        let hooks = if new_hash_hex.is_some() {
+            match LoadedHooks::load(&self.config_path) {
+                Ok(loaded) => loaded.hooks,
+                Err(e) => {
+                    return HooksReload::Failed {
+                        active_hash_hex: self.config_hash_hex.clone(),
+                        error: format!("{e:#}"),
+                    }
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        self.hooks = hooks;
+        let old_hash_hex = std::mem::replace(&mut self.config_hash_hex, new_hash_hex.clone());
+        HooksReload::Reloaded {
+            old_hash_hex,
+            new_hash_hex,
+        }
+    }
+
     pub fn enabled(&self) -> bool {
         !matches!(self.mode, HooksMode::Off)
     }
@@ -93,20 +233,49 @@ impl HookManager {
         &self.hooks
     }
 
+    /// Whether any hook would run at `stage` for `tool_name`.
+    pub fn has_hooks_for(&self, stage: HookStage, tool_name: &str) -> bool {
+        self.hooks
+            .iter()
+            .any(|h| h.has_stage(stage) && h.matches_tool(tool_name))
+    }
+
     pub async fn run_pre_model_hooks(
         &self,
         base_input: HookInput,
     ) -> Result<PreModelHookResult, HookExecError> {
         let mut appended = Vec::<Message>::new();
         let mut invocations = Vec::new();
+        let mut budget_exhausted = Vec::new();
 
         for hook in self
             .hooks
             .iter()
             .filter(|h| h.has_stage(HookStage::PreModel))
         {
+            let timeout_ms = match self.check_budget(hook, &base_input.run_id, "pre_model") {
+                BudgetCheck::Run { timeout_ms } => timeout_ms,
+                BudgetCheck::Skip {
+                    message,
+                    exhaustion,
+                } => {
+                    if self.budget_strict {
+                        return Err(HookExecError { message });
+                    }
+                    budget_exhausted.extend(exhaustion);
+                    invocations.push(budget_skip_report(
+                        base_input.step,
+                        "pre_model",
+                        hook,
+                        message,
+                        None,
+                    ));
+                    continue;
+                }
+            };
             let started = Instant::now();
-            let out = self.invoke_hook(hook, &base_input).await;
+            let out = self.invoke_hook(hook, &base_input, timeout_ms).await;
+            self.record_hook_time(hook, &base_input.run_id, started.elapsed());
             match out {
                 Ok(output) => {
                     let mut report = HookInvocationReport {
@@ -122,6 +291,7 @@ impl HookManager {
                         output_digest: None,
                         appended_message_count: None,
                         appended_digests: None,
+                        skipped_due_to_budget: false,
                     };
                     match output.action {
                         HookAction::Pass => {}
@@ -133,6 +303,7 @@ impl HookManager {
                                     format!("hook '{}' aborted run", hook.cfg.name)
                                 })),
                                 invocations,
+                                budget_exhausted,
                             });
                         }
                         HookAction::Modify => {
@@ -220,6 +391,7 @@ impl HookManager {
                         output_digest: None,
                         appended_message_count: None,
                         appended_digests: None,
+                        skipped_due_to_budget: false,
                     });
                 }
             }
@@ -229,6 +401,174 @@ impl HookManager {
             append_messages: appended,
             abort_reason: None,
             invocations,
+            budget_exhausted,
+        })
+    }
+
+    /// Runs pre_tool hooks in order. Each hook sees the arguments left by the
+    /// previous one. `validate_rewrite` decides whether a proposed rewrite may
+    /// replace the current arguments; a rejected rewrite is a hook failure.
+    pub async fn run_pre_tool_hooks(
+        &self,
+        base_input: HookInput,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+        validate_rewrite: impl Fn(&serde_json::Value) -> Result<(), String>,
+    ) -> Result<PreToolHookResult, HookExecError> {
+        let original_args_sha256 = args_digest(arguments);
+        let mut current = arguments.clone();
+        let mut rewritten_by = Vec::new();
+        let mut invocations = Vec::new();
+        let mut budget_exhausted = Vec::new();
+        let finish = |current: serde_json::Value, rewritten_by: Vec<String>| {
+            let rewrite = (!rewritten_by.is_empty()).then(|| HookArgsRewrite {
+                hooks: rewritten_by,
+                original_args_sha256: original_args_sha256.clone(),
+                arguments: current.clone(),
+            });
+            (current, rewrite)
+        };
+
+        for hook in self
+            .hooks
+            .iter()
+            .filter(|h| h.has_stage(HookStage::PreTool) && h.matches_tool(tool_name))
+        {
+            let timeout_ms = match self.check_budget(hook, &base_input.run_id, "pre_tool") {
+                BudgetCheck::Run { timeout_ms } => timeout_ms,
+                BudgetCheck::Skip {
+                    message,
+                    exhaustion,
+                } => {
+                    if self.budget_strict {
+                        return Err(HookExecError { message });
+                    }
+                    budget_exhausted.extend(exhaustion);
+                    invocations.push(budget_skip_report(
+                        base_input.step,
+                        "pre_tool",
+                        hook,
+                        message,
+                        Some(args_digest(&current)),
+                    ));
+                    continue;
+                }
+            };
+            let mut input = base_input.clone();
+            if let Some(payload) = input.payload.as_object_mut() {
+                payload.insert("arguments".to_string(), current.clone());
+            }
+            let started = Instant::now();
+            let output = self.invoke_hook(hook, &input, timeout_ms).await;
+            self.record_hook_time(hook, &base_input.run_id, started.elapsed());
+            match output {
+                Ok(out) => {
+                    let mut report = HookInvocationReport {
+                        ts: crate::trust::now_rfc3339(),
+                        step: base_input.step,
+                        stage: "pre_tool".to_string(),
+                        hook_name: hook.cfg.name.clone(),
+                        action: format!("{:?}", out.action).to_lowercase(),
+                        message: out.message.clone(),
+                        modified: false,
+                        duration_ms: started.elapsed().as_millis(),
+                        input_digest: Some(args_digest(&current)),
+                        output_digest: None,
+                        appended_message_count: None,
+                        appended_digests: None,
+                        skipped_due_to_budget: false,
+                    };
+                    match out.action {
+                        HookAction::Pass => {}
+                        HookAction::Abort => {
+                            invocations.push(report);
+                            let (arguments, rewrite) = finish(current, rewritten_by);
+                            return Ok(PreToolHookResult {
+                                arguments,
+                                rewrite,
+                                abort_reason: Some(out.message.unwrap_or_else(|| {
+                                    format!("hook '{}' aborted run", hook.cfg.name)
+                                })),
+                                invocations,
+                                budget_exhausted,
+                            });
+                        }
+                        HookAction::Modify => {
+                            let payload_val = out.payload.ok_or_else(|| HookExecError {
+                                message: format!(
+                                    "hook '{}' returned modify without payload",
+                                    hook.cfg.name
+                                ),
+                            })?;
+                            let payload: PreToolModifyPayload = serde_json::from_value(payload_val)
+                                .map_err(|e| HookExecError {
+                                    message: format!(
+                                        "hook '{}' invalid pre_tool payload: {}",
+                                        hook.cfg.name, e
+                                    ),
+                                })?;
+                            if let Some(rewrite) = payload.rewrite_arguments {
+                                let checked = if rewrite.is_object() {
+                                    validate_rewrite(&rewrite)
+                                } else {
+                                    Err("rewrite_arguments must be a JSON object".to_string())
+                                };
+                                if let Err(reason) = checked {
+                                    let msg = format!(
+                                        "hook '{}' rewrite_arguments rejected: {}",
+                                        hook.cfg.name, reason
+                                    );
+                                    if self.strict {
+                                        return Err(HookExecError { message: msg });
+                                    }
+                                    eprintln!("WARN: hook failed: {}", msg);
+                                    report.message = Some(msg);
+                                    report.output_digest = report.input_digest.clone();
+                                    invocations.push(report);
+                                    continue;
+                                }
+                                report.modified = rewrite != current;
+                                if report.modified {
+                                    current = rewrite;
+                                    rewritten_by.push(hook.cfg.name.clone());
+                                }
+                            }
+                        }
+                    }
+                    report.output_digest = Some(args_digest(&current));
+                    invocations.push(report);
+                }
+                Err(e) => {
+                    if self.strict {
+                        return Err(e);
+                    }
+                    eprintln!("WARN: hook failed: {}", e.message);
+                    invocations.push(HookInvocationReport {
+                        ts: crate::trust::now_rfc3339(),
+                        step: base_input.step,
+                        stage: "pre_tool".to_string(),
+                        hook_name: hook.cfg.name.clone(),
+                        action: "pass".to_string(),
+                        message: Some(e.message),
+                        modified: false,
+                        duration_ms: started.elapsed().as_millis(),
+                        input_digest: Some(args_digest(&current)),
+                        output_digest: Some(args_digest(&current)),
+                        appended_message_count: None,
+                        appended_digests: None,
+                        skipped_due_to_budget: false,
+                    });
+                }
+            }
+        }
+
+        let (arguments, rewrite) = finish(current, rewritten_by);
+        Ok(PreToolHookResult {
+            arguments,
+            rewrite,
+            abort_reason: None,
+            invocations,
+            budget_exhausted,
         })
     }
 
@@ -244,14 +584,36 @@ impl HookManager {
         let mut current = content.to_string();
         let mut current_truncated = truncated;
         let mut invocations = Vec::new();
+        let mut budget_exhausted = Vec::new();
 
         for hook in self
             .hooks
             .iter()
             .filter(|h| h.has_stage(HookStage::ToolResult) && h.matches_tool(tool_name))
         {
+            let timeout_ms = match self.check_budget(hook, &base_input.run_id, "tool_result") {
+                BudgetCheck::Run { timeout_ms } => timeout_ms,
+                BudgetCheck::Skip {
+                    message,
+                    exhaustion,
+                } => {
+                    if self.budget_strict {
+                        return Err(HookExecError { message });
+                    }
+                    budget_exhausted.extend(exhaustion);
+                    invocations.push(budget_skip_report(
+                        base_input.step,
+                        "tool_result",
+                        hook,
+                        message,
+                        Some(sha256_hex(current.as_bytes())),
+                    ));
+                    continue;
+                }
+            };
             let started = Instant::now();
-            let output = self.invoke_hook(hook, &base_input).await;
+            let output = self.invoke_hook(hook, &base_input, timeout_ms).await;
+            self.record_hook_time(hook, &base_input.run_id, started.elapsed());
             match output {
                 Ok(out) => {
                     let mut report = HookInvocationReport {
@@ -267,6 +629,7 @@ impl HookManager {
                         output_digest: None,
                         appended_message_count: None,
                         appended_digests: None,
+                        skipped_due_to_budget: false,
                     };
                     match out.action {
                         HookAction::Pass => {}
@@ -283,6 +646,7 @@ impl HookManager {
                                 output_digest: sha256_hex(content.as_bytes()),
                                 input_len,
                                 output_len: content.chars().count(),
+                                budget_exhausted,
                             });
                         }
                         HookAction::Modify => {
@@ -341,6 +705,7 @@ impl HookManager {
                         output_digest: Some(sha256_hex(current.as_bytes())),
                         appended_message_count: None,
                         appended_digests: None,
+                        skipped_due_to_budget: false,
                     });
                 }
             }
@@ -357,16 +722,172 @@ impl HookManager {
             output_digest,
             input_len,
             output_len,
+            budget_exhausted,
         })
     }
+
+    /// Runs post_run hooks once the run is over. Their answer cannot change
+    /// the outcome, so `modify` and `abort` are recorded but have no effect,
+    /// and failures are reported in the result rather than as an error.
+    pub async fn run_post_run_hooks(&self, base_input: HookInput) -> PostRunHookResult {
+        let mut invocations = Vec::new();
+
+        for hook in self
+            .hooks
+            .iter()
+            .filter(|h| h.has_stage(HookStage::PostRun))
+        {
+            let timeout_ms = match self.check_budget(hook, &base_input.run_id, "post_run") {
+                BudgetCheck::Run { timeout_ms } => timeout_ms,
+                BudgetCheck::Skip { message, .. } => {
+                    invocations.push(budget_skip_report(
+                        base_input.step,
+                        "post_run",
+                        hook,
+                        message.clone(),
+                        None,
+                    ));
+                    if self.budget_strict {
+                        return PostRunHookResult {
+                            invocations,
+                            error: Some(message),
+                        };
+                    }
+                    continue;
+                }
+            };
+            let started = Instant::now();
+            let out = self.invoke_hook(hook, &base_input, timeout_ms).await;
+            self.record_hook_time(hook, &base_input.run_id, started.elapsed());
+            let (action, message, failure) = match out {
+                Ok(output) => (
+                    format!("{:?}", output.action).to_lowercase(),
+                    output.message,
+                    None,
+                ),
+                Err(e) => {
+                    if !self.strict {
+                        eprintln!("WARN: hook failed: {}", e.message);
+                    }
+                    let action = if self.strict { "error" } else { "pass" };
+                    (action.to_string(), Some(e.message.clone()), Some(e.message))
+                }
+            };
+            invocations.push(HookInvocationReport {
+                ts: crate::trust::now_rfc3339(),
+                step: base_input.step,
+                stage: "post_run".to_string(),
+                hook_name: hook.cfg.name.clone(),
+                action,
+                message,
+                modified: false,
+                duration_ms: started.elapsed().as_millis(),
+                input_digest: None,
+                output_digest: None,
+                appended_message_count: None,
+                appended_digests: None,
+                skipped_due_to_budget: false,
+            });
+            if self.strict && failure.is_some() {
+                return PostRunHookResult {
+                    invocations,
+                    error: failure,
+                };
+            }
+        }
+
+        PostRunHookResult {
+            invocations,
+            error: None,
+        }
+    }
+
+    /// Decides whether `hook` may run again in this run. With a cumulative
+    /// budget the per-invocation timeout is clamped to the time left, so a
+    /// slow hook cannot overrun its budget by more than one timeout.
+    fn check_budget(&self, hook: &LoadedHook, run_id: &str, stage: &str) -> BudgetCheck {
+        let timeout_ms = hook.cfg.timeout_ms.unwrap_or(self.timeout_ms);
+        let max_invocations = hook
+            .cfg
+            .max_invocations_per_run
+            .unwrap_or(self.max_invocations_per_run);
+        let max_cumulative_ms = hook.cfg.max_cumulative_ms.unwrap_or(self.max_cumulative_ms);
+        if max_invocations == 0 && max_cumulative_ms == 0 {
+            return BudgetCheck::Run { timeout_ms };
+        }
+        let mut guard = self
+            .budget_usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let usage = guard.for_hook(run_id, &hook.cfg.name);
+        let exhausted_limit = if max_invocations > 0 && usage.invocations >= max_invocations {
+            Some(("max_invocations_per_run", u64::from(max_invocations)))
+        } else if max_cumulative_ms > 0 && usage.cumulative_ms >= max_cumulative_ms {
+            Some(("max_cumulative_ms", max_cumulative_ms))
+        } else {
+            None
+        };
+        let Some((limit, limit_value)) = exhausted_limit else {
+            let timeout_ms = if max_cumulative_ms > 0 {
+                timeout_ms.min(max_cumulative_ms - usage.cumulative_ms)
+            } else {
+                timeout_ms
+            };
+            return BudgetCheck::Run { timeout_ms };
+        };
+        let message = format!(
+            "hook '{}' budget exhausted: {limit} {limit_value} reached after {} invocations ({}ms)",
+            hook.cfg.name, usage.invocations, usage.cumulative_ms
+        );
+        let exhaustion = (!usage.exhausted).then(|| HookBudgetExhaustion {
+            hook_name: hook.cfg.name.clone(),
+            stage: stage.to_string(),
+            limit: limit.to_string(),
+            limit_value,
+            invocations: usage.invocations,
+            cumulative_ms: usage.cumulative_ms,
+        });
+        usage.exhausted = true;
+        BudgetCheck::Skip {
+            message,
+            exhaustion,
+        }
+    }
+
+    fn record_hook_time(&self, hook: &LoadedHook, run_id: &str, elapsed: Duration) {
+        let mut guard = self
+            .budget_usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let usage = guard.for_hook(run_id, &hook.cfg.name);
+        usage.invocations = usage.invocations.saturating_add(1);
+        usage.cumulative_ms = usage
+            .cumulative_ms
+            .saturating_add(elapsed.as_millis() as u64);
+    }
+
     async fn invoke_hook(
         &self,
         hook: &LoadedHook,
         input: &HookInput,
+        timeout_ms: u64,
     ) -> Result<HookOutput, HookExecError> {
-        let timeout_ms = hook.cfg.timeout_ms.unwrap_or(self.timeout_ms);
         let mut command = Command::new(&hook.cfg.command);
         command.args(&hook.cfg.args);
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        let parent = std::env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)));
+        let env = build_hook_env(&hook.cfg, input, parent);
+        for var in &env.refused {
+            eprintln!(
+                "WARN: hook '{}' env_passthrough {} exceeds {} bytes; not passed",
+                hook.cfg.name, var, MAX_HOOK_ENV_VALUE_BYTES
+            );
+        }
+        command.env_clear();
+        command.envs(env.vars);
         command.stdin(std::process::Stdio::piped());
         command.stdout(std::process::Stdio::piped());
         command.stderr(std::process::Stdio::piped());
@@ -436,6 +957,37 @@ impl HookManager {
     }
 }
 
+fn budget_skip_report(
+    step: u32,
+    stage: &str,
+    hook: &LoadedHook,
+    message: String,
+    digest: Option<String>,
+) -> HookInvocationReport {
+    HookInvocationReport {
+        ts: crate::trust::now_rfc3339(),
+        step,
+        stage: stage.to_string(),
+        hook_name: hook.cfg.name.clone(),
+        action: "skip".to_string(),
+        message: Some(message),
+        modified: false,
+        duration_ms: 0,
+        input_digest: digest.clone(),
+        output_digest: digest,
+        appended_message_count: None,
+        appended_digests: None,
+        skipped_due_to_budget: true,
+    }
+}
+
+/// sha256 of the canonical JSON form, so key order does not matter.
+pub fn args_digest(arguments: &serde_json::Value) -> String {
+    let canonical = crate::trust::approvals::canonical_json(arguments)
+        .unwrap_or_else(|_| arguments.to_string());
+    sha256_hex(canonical.as_bytes())
+}
+
 fn parse_append_role(role: &str) -> Option<Role> {
     match role {
         "system" => Some(Role::System),
@@ -461,6 +1013,31 @@ pub fn make_pre_model_input(
         model: model.to_string(),
         workdir: stable_workdir(workdir),
         caps: None,
+        exec_seq: None,
+        decision: None,
+        payload,
+    }
+}
+
+pub fn make_pre_tool_input(
+    run_id: &str,
+    step: u32,
+    provider: &str,
+    model: &str,
+    workdir: &Path,
+    payload: serde_json::Value,
+) -> HookInput {
+    HookInput {
+        schema_version: "openagent.hook_input.v1".to_string(),
+        stage: HookStageWire::PreTool,
+        run_id: run_id.to_string(),
+        step,
+        provider: provider.to_string(),
+        model: model.to_string(),
+        workdir: stable_workdir(workdir),
+        caps: None,
+        exec_seq: None,
+        decision: None,
         payload,
     }
 }
@@ -482,6 +1059,31 @@ pub fn make_tool_result_input(
         model: model.to_string(),
         workdir: stable_workdir(workdir),
         caps: None,
+        exec_seq: None,
+        decision: None,
+        payload,
+    }
+}
+
+pub fn make_post_run_input(
+    run_id: &str,
+    step: u32,
+    provider: &str,
+    model: &str,
+    workdir: &Path,
+    payload: serde_json::Value,
+) -> HookInput {
+    HookInput {
+        schema_version: "openagent.hook_input.v1".to_string(),
+        stage: HookStageWire::PostRun,
+        run_id: run_id.to_string(),
+        step,
+        provider: provider.to_string(),
+        model: model.to_string(),
+        workdir: stable_workdir(workdir),
+        caps: None,
+        exec_seq: None,
+        decision: None,
         payload,
     }
 }
@@ -497,9 +1099,79 @@ fn stable_workdir(path: &Path) -> String {
 mod tests {
     use tempfile::tempdir;
 
-    use super::{HookManager, HookRuntimeConfig};
+    use super::{make_tool_result_input, HookManager, HookRuntimeConfig};
     use crate::hooks::config::HooksMode;
 
+    #[cfg(unix)]
+    const ENV_ECHO_HOOK: &str = r#"
+version: 1
+hooks:
+  - name: env_echo
+    stages: ["tool_result"]
+    command: "sh"
+    args:
+      - "-c"
+      - 'cat >/dev/null; printf "{\"schema_version\":\"openagent.hook_output.v1\",\"action\":\"pass\",\"message\":\"%s|%s|%s|%s|%s\"}" "$LOCALAGENT_TOOL_NAME" "$LOCALAGENT_EXEC_SEQ" "$LOCALAGENT_DECISION" "${HOOK_ENV_TEST_PARENT_SECRET:-unset}" "${HOOK_ENV_TEST_PASSTHROUGH:-unset}"'
+    env_passthrough: ["HOOK_ENV_TEST_PASSTHROUGH"]
+"#;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn hook_sees_context_env_but_not_parent_secrets() {
+        std::env::set_var("HOOK_ENV_TEST_PARENT_SECRET", "sk-should-not-leak");
+        std::env::set_var("HOOK_ENV_TEST_PASSTHROUGH", "opted-in");
+        let tmp = tempdir().expect("tmp");
+        let cfg = tmp.path().join("hooks.yaml");
+        std::fs::write(&cfg, ENV_ECHO_HOOK).expect("write");
+        let manager = HookManager::build(HookRuntimeConfig {
+            mode: HooksMode::On,
+            config_path: cfg,
+            strict: true,
+            timeout_ms: 5000,
+            max_stdout_bytes: 10_000,
+            max_invocations_per_run: 0,
+            max_cumulative_ms: 0,
+            budget_strict: false,
+        })
+        .expect("build");
+        let mut input = make_tool_result_input(
+            "run_env",
+            1,
+            "mock",
+            "m",
+            tmp.path(),
+            serde_json::json!({"tool_call_id": "tc1", "tool_name": "read_file"}),
+        );
+        input.exec_seq = Some(2);
+        input.decision = Some("allow".to_string());
+        let out = manager
+            .run_tool_result_hooks(input, "read_file", "content", false)
+            .await
+            .map_err(|e| e.message)
+            .expect("hook ran");
+        assert_eq!(
+            out.invocations[0].message.as_deref(),
+            Some("read_file|2|allow|unset|opted-in")
+        );
+    }
+
+    #[test]
+    fn hooks_config_hash_covers_env_passthrough() {
+        let tmp = tempdir().expect("tmp");
+        let cfg = tmp.path().join("hooks.yaml");
+        let base =
+            "version: 1\nhooks:\n  - name: a\n    stages: [\"pre_model\"]\n    command: \"echo\"\n";
+        std::fs::write(&cfg, base).expect("write");
+        let before = crate::ops_helpers::compute_hooks_config_hash_hex(HooksMode::On, &cfg);
+        std::fs::write(&cfg, format!("{base}    env_passthrough: [\"GH_TOKEN\"]\n"))
+            .expect("write");
+        let loaded = crate::hooks::config::LoadedHooks::load(&cfg).expect("load");
+        assert_eq!(loaded.hooks[0].cfg.env_passthrough, vec!["GH_TOKEN"]);
+        let after = crate::ops_helpers::compute_hooks_config_hash_hex(HooksMode::On, &cfg);
+        assert!(before.is_some());
+        assert_ne!(before, after);
+    }
+
     #[test]
     fn invalid_config_is_error() {
         let tmp = tempdir().expect("tmp");
@@ -511,6 +1183,9 @@ mod tests {
             strict: false,
             timeout_ms: 1000,
             max_stdout_bytes: 1000,
+            max_invocations_per_run: 0,
+            max_cumulative_ms: 0,
+            budget_strict: false,
         });
         assert!(res.is_err());
     }
@@ -0,0 +1,79 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+
+use crate::hooks::runner::{HookManager, HookRuntimeConfig, HooksReload};
+use crate::trust::approvals::ApprovalsStore;
+
+/// Hooks config kept across the runs of one interactive session. Every run
+/// starts from the pinned manager, re-reading hooks.yaml only at that run
+/// boundary so edits apply to the next run and never to one in progress.
+#[derive(Debug, Clone, Default)]
+pub struct SessionHooks {
+    pinned: Arc<Mutex<Option<HookManager>>>,
+}
+
+/// What changed in the hooks config since the session's previous run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionHooksReload {
+    pub reload: HooksReload,
+    pub approvals_invalidated: usize,
+}
+
+impl SessionHooks {
+    /// Builds the manager on the first run and reloads it on later ones.
+    /// Runtime settings (`strict`, timeouts, budgets) always follow `cfg`.
+    ///
+    /// A reload drops approvals keyed to the previous hooks hash. A config
+    /// that fails to load keeps the previous one active, unless `cfg.strict`,
+    /// in which case this run is refused until the file is fixed.
+    pub fn manager_for_run(
+        &self,
+        cfg: HookRuntimeConfig,
+        approvals: &ApprovalsStore,
+    ) -> anyhow::Result<(HookManager, SessionHooksReload)> {
+        let mut pinned = self
+            .pinned
+            .lock()
+            .map_err(|_| anyhow!("session hooks lock poisoned"))?;
+        let Some(manager) = pinned.as_mut() else {
+            let manager = HookManager::build(cfg)?;
+            *pinned = Some(manager.clone());
+            let unchanged = SessionHooksReload {
+                reload: HooksReload::Unchanged,
+                approvals_invalidated: 0,
+            };
+            return Ok((manager, unchanged));
+        };
+        manager.mode = cfg.mode;
+        manager.config_path = cfg.config_path;
+        manager.strict = cfg.strict;
+        manager.timeout_ms = cfg.timeout_ms;
+        manager.max_stdout_bytes = cfg.max_stdout_bytes;
+        manager.max_invocations_per_run = cfg.max_invocations_per_run;
+        manager.max_cumulative_ms = cfg.max_cumulative_ms;
+        manager.budget_strict = cfg.budget_strict;
+        let reload = manager.reload();
+        let approvals_invalidated = match &reload {
+            HooksReload::Reloaded {
+                old_hash_hex: Some(old),
+                ..
+            } => approvals.invalidate_hooks_config_hash(old)?,
+            HooksReload::Failed { error, .. } if cfg.strict => {
+                return Err(anyhow!("hooks config reload failed: {error}"));
+            }
+            HooksReload::Failed { error, .. } => {
+                eprintln!("WARN: hooks config reload failed, keeping previous hooks: {error}");
+                0
+            }
+            _ => 0,
+        };
+        Ok((
+            manager.clone(),
+            SessionHooksReload {
+                reload,
+                approvals_invalidated,
+            },
+        ))
+    }
+}
@@ -0,0 +1,741 @@
+//! Dry validation of hooks configs for `localagent hooks validate`.
+//!
+//! Validation never spawns a hook. Dry-fire runs one stage of the configured
+//! hooks once against a synthetic payload, in a scratch directory, with the
+//! usual timeout and stdout caps.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::compaction::{CompactionMode, CompactionSettings, ToolResultPersist};
+use crate::hooks::config::{check_hook, HookConfig, HookStage, HooksConfigFile, LoadedHooks};
+use crate::hooks::protocol::{
+    PostRunBudgetPayload, PostRunPayload, PreModelCompactionPayload, PreModelPayload,
+    PreToolPayload, ToolResultPayload,
+};
+use crate::hooks::runner::{
+    make_post_run_input, make_pre_model_input, make_pre_tool_input, make_tool_result_input,
+    HookExecError, HookManager,
+};
+use crate::store::sha256_hex;
+use crate::types::{Message, Role};
+
+const TOP_LEVEL_KEYS: &[&str] = &["version", "hooks"];
+const HOOK_KEYS: &[&str] = &[
+    "name",
+    "stages",
+    "command",
+    "args",
+    "timeout_ms",
+    "match",
+    "env_passthrough",
+    "max_invocations_per_run",
+    "max_cumulative_ms",
+];
+const MATCH_KEYS: &[&str] = &["tools"];
+const SCRIPT_EXTENSIONS: &[&str] = &[
+    "py", "sh", "bash", "js", "mjs", "cjs", "ts", "rb", "pl", "ps1",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HooksValidateExit {
+    Ok = 0,
+    Warnings = 1,
+    Invalid = 2,
+    DryFireFailed = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookDiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookDiagnostic {
+    pub severity: HookDiagnosticSeverity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hook: Option<String>,
+    /// 1-based line in the config file, when it could be located.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HooksValidationReport {
+    pub config_path: String,
+    /// Same value a run records as `hooks_config_hash_hex`.
+    pub config_hash_hex: Option<String>,
+    pub hooks: Vec<String>,
+    pub diagnostics: Vec<HookDiagnostic>,
+}
+
+impl HooksValidationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &HookDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == HookDiagnosticSeverity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &HookDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == HookDiagnosticSeverity::Warning)
+    }
+
+    pub fn exit(&self) -> HooksValidateExit {
+        if self.errors().next().is_some() {
+            HooksValidateExit::Invalid
+        } else if self.warnings().next().is_some() {
+            HooksValidateExit::Warnings
+        } else {
+            HooksValidateExit::Ok
+        }
+    }
+
+    fn push(
+        &mut self,
+        severity: HookDiagnosticSeverity,
+        hook: Option<&str>,
+        line: Option<usize>,
+        message: String,
+    ) {
+        self.diagnostics.push(HookDiagnostic {
+            severity,
+            hook: hook.map(ToOwned::to_owned),
+            line,
+            message,
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookDryFireResult {
+    pub hook: String,
+    pub stage: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub duration_ms: u128,
+}
+
+/// Loads `path` the way a run would and collects every problem instead of
+/// stopping at the first. Relative script paths are checked against
+/// `workdir`; missing or non-executable scripts are warnings only.
+pub fn validate_hooks_config(path: &Path, workdir: &Path) -> HooksValidationReport {
+    let mut report = HooksValidationReport {
+        config_path: path.display().to_string(),
+        config_hash_hex: None,
+        hooks: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            report.push(
+                HookDiagnosticSeverity::Error,
+                None,
+                None,
+                format!("failed to read hooks config {}: {e}", path.display()),
+            );
+            return report;
+        }
+    };
+    report.config_hash_hex = Some(sha256_hex(&bytes));
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+    let lines = YamlLines::new(&text);
+
+    let raw: serde_yaml::Value = match serde_yaml::from_str(&text) {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(
+                HookDiagnosticSeverity::Error,
+                None,
+                e.location().map(|l| l.line()),
+                format!("failed to parse hooks config: {e}"),
+            );
+            return report;
+        }
+    };
+    check_unknown_keys(&raw, &lines, &mut report);
+
+    let parsed: HooksConfigFile = match serde_yaml::from_str(&text) {
+        Ok(v) => v,
+        Err(e) => {
+            report.push(
+                HookDiagnosticSeverity::Error,
+                None,
+                e.location().map(|l| l.line()),
+                format!("invalid hooks config: {e}"),
+            );
+            return report;
+        }
+    };
+    if parsed.version != 1 {
+        report.push(
+            HookDiagnosticSeverity::Error,
+            None,
+            lines.key_line("version", 0),
+            format!("unsupported hooks config version: {}", parsed.version),
+        );
+    }
+    for (idx, hook) in parsed.hooks.iter().enumerate() {
+        let line = lines.hook_start(idx);
+        let label = (!hook.name.trim().is_empty()).then_some(hook.name.as_str());
+        if let Err(e) = check_hook(hook) {
+            report.push(HookDiagnosticSeverity::Error, label, line, format!("{e:#}"));
+            continue;
+        }
+        check_scripts(hook, workdir, line, &mut report);
+    }
+
+    if report.errors().next().is_none() {
+        match LoadedHooks::load(path) {
+            Ok(loaded) => {
+                report.hooks = loaded.hooks.into_iter().map(|h| h.cfg.name).collect();
+            }
+            Err(e) => report.push(HookDiagnosticSeverity::Error, None, None, format!("{e:#}")),
+        }
+    }
+    report
+}
+
+/// Runs every hook with `stage` (or only `hook_name`) once against a synthetic
+/// payload. Hooks run in a fresh scratch directory that is removed afterwards,
+/// so nothing under the real workdir or state dir is touched.
+pub async fn dry_fire_hooks(
+    path: &Path,
+    stage: HookStage,
+    hook_name: Option<&str>,
+    timeout_ms: u64,
+    max_stdout_bytes: usize,
+) -> anyhow::Result<Vec<HookDryFireResult>> {
+    let loaded = LoadedHooks::load(path)?;
+    let selected = loaded
+        .hooks
+        .into_iter()
+        .filter(|h| h.has_stage(stage) && hook_name.is_none_or(|n| h.cfg.name == n))
+        .collect::<Vec<_>>();
+    let stage_name = stage_name(stage);
+    if selected.is_empty() {
+        return Err(match hook_name {
+            Some(name) => anyhow!("no hook named '{name}' with stage {stage_name}"),
+            None => anyhow!("no hooks with stage {stage_name}"),
+        });
+    }
+
+    let scratch = std::env::temp_dir().join(format!(
+        "localagent-hooks-dry-fire-{}",
+        uuid::Uuid::new_v4()
+    ));
+    std::fs::create_dir_all(&scratch)?;
+    let run_id = format!("dry_fire_{}", uuid::Uuid::new_v4());
+    let mut results = Vec::with_capacity(selected.len());
+    for hook in selected {
+        let manager = HookManager {
+            mode: crate::hooks::config::HooksMode::On,
+            strict: true,
+            timeout_ms,
+            max_stdout_bytes,
+            max_invocations_per_run: 0,
+            max_cumulative_ms: 0,
+            budget_strict: false,
+            config_path: path.to_path_buf(),
+            hooks: vec![hook.clone()],
+            config_hash_hex: None,
+            budget_usage: Arc::default(),
+            current_dir: Some(scratch.clone()),
+        };
+        let started = std::time::Instant::now();
+        let outcome = match stage {
+            HookStage::PreModel => {
+                let input = make_pre_model_input(
+                    &run_id,
+                    0,
+                    "dry_fire",
+                    "dry-fire",
+                    &scratch,
+                    serde_json::to_value(synthetic_pre_model_payload())?,
+                );
+                manager
+                    .run_pre_model_hooks(input)
+                    .await
+                    .map(|r| r.invocations)
+            }
+            HookStage::PreTool => {
+                let payload = synthetic_pre_tool_payload();
+                let input = make_pre_tool_input(
+                    &run_id,
+                    0,
+                    "dry_fire",
+                    "dry-fire",
+                    &scratch,
+                    serde_json::to_value(&payload)?,
+                );
+                manager
+                    .run_pre_tool_hooks(input, &payload.tool_name, &payload.arguments, |_| Ok(()))
+                    .await
+                    .map(|r| r.invocations)
+            }
+            HookStage::ToolResult => {
+                let payload = synthetic_tool_result_payload();
+                let input = make_tool_result_input(
+                    &run_id,
+                    0,
+                    "dry_fire",
+                    "dry-fire",
+                    &scratch,
+                    serde_json::to_value(&payload)?,
+                );
+                manager
+                    .run_tool_result_hooks(input, &payload.tool_name, &payload.content, false)
+                    .await
+                    .map(|r| r.invocations)
+            }
+            HookStage::PostRun => {
+                let input = make_post_run_input(
+                    &run_id,
+                    0,
+                    "dry_fire",
+                    "dry-fire",
+                    &scratch,
+                    serde_json::to_value(synthetic_post_run_payload(&run_id))?,
+                );
+                let result = manager.run_post_run_hooks(input).await;
+                match result.error {
+                    Some(message) => Err(HookExecError { message }),
+                    None => Ok(result.invocations),
+                }
+            }
+        };
+        results.push(match outcome {
+            Ok(invocations) => {
+                let report = invocations.into_iter().next();
+                HookDryFireResult {
+                    hook: hook.cfg.name.clone(),
+                    stage: stage_name.to_string(),
+                    ok: true,
+                    action: report.as_ref().map(|r| r.action.clone()),
+                    message: report.as_ref().and_then(|r| r.message.clone()),
+                    duration_ms: report
+                        .map(|r| r.duration_ms)
+                        .unwrap_or_else(|| started.elapsed().as_millis()),
+                }
+            }
+            Err(HookExecError { message }) => HookDryFireResult {
+                hook: hook.cfg.name.clone(),
+                stage: stage_name.to_string(),
+                ok: false,
+                action: None,
+                message: Some(message),
+                duration_ms: started.elapsed().as_millis(),
+            },
+        });
+    }
+    let _ = std::fs::remove_dir_all(&scratch);
+    Ok(results)
+}
+
+/// Text output of `hooks validate`: hash, one line per diagnostic, summary.
+pub fn render_validation_report(report: &HooksValidationReport) -> String {
+    let mut out = format!("hooks config: {}\n", report.config_path);
+    if let Some(hash) = &report.config_hash_hex {
+        out.push_str(&format!("hooks_config_hash_hex: {hash}\n"));
+    }
+    for d in &report.diagnostics {
+        let level = match d.severity {
+            HookDiagnosticSeverity::Error => "ERROR",
+            HookDiagnosticSeverity::Warning => "WARN",
+        };
+        let location = match d.line {
+            Some(line) => format!("{}:{line}: ", report.config_path),
+            None => String::new(),
+        };
+        let hook = d
+            .hook
+            .as_deref()
+            .map(|h| format!("hook '{h}': "))
+            .unwrap_or_default();
+        out.push_str(&format!("{level}: {location}{hook}{}\n", d.message));
+    }
+    let errors = report.errors().count();
+    let warnings = report.warnings().count();
+    if errors > 0 {
+        out.push_str(&format!(
+            "FAIL: hooks config invalid errors={errors} warnings={warnings}\n"
+        ));
+    } else {
+        out.push_str(&format!(
+            "OK: hooks config valid hooks={} warnings={warnings}\n",
+            report.hooks.len()
+        ));
+    }
+    out
+}
+
+pub fn render_dry_fire_results(results: &[HookDryFireResult]) -> String {
+    let mut out = String::new();
+    for r in results {
+        if r.ok {
+            out.push_str(&format!(
+                "DRY-FIRE OK: hook {} stage={} action={} duration_ms={}{}\n",
+                r.hook,
+                r.stage,
+                r.action.as_deref().unwrap_or("-"),
+                r.duration_ms,
+                r.message
+                    .as_deref()
+                    .map(|m| format!(" message={m}"))
+                    .unwrap_or_default()
+            ));
+        } else {
+            out.push_str(&format!(
+                "DRY-FIRE FAIL: hook {} stage={}: {}\n",
+                r.hook,
+                r.stage,
+                r.message.as_deref().unwrap_or("failed")
+            ));
+        }
+    }
+    out
+}
+
+fn stage_name(stage: HookStage) -> &'static str {
+    match stage {
+        HookStage::PreModel => "pre_model",
+        HookStage::PreTool => "pre_tool",
+        HookStage::ToolResult => "tool_result",
+        HookStage::PostRun => "post_run",
+    }
+}
+
+fn synthetic_pre_model_payload() -> PreModelPayload {
+    PreModelPayload {
+        messages: vec![Message {
+            role: Role::User,
+            content: Some("hooks dry-fire: synthetic prompt".to_string()),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        }],
+        tools: Vec::new(),
+        stream: false,
+        compaction: PreModelCompactionPayload::from(&CompactionSettings {
+            max_context_chars: 0,
+            max_context_tokens: None,
+            mode: CompactionMode::Off,
+            keep_last: 20,
+            tool_result_persist: ToolResultPersist::Digest,
+        }),
+    }
+}
+
+fn synthetic_pre_tool_payload() -> PreToolPayload {
+    PreToolPayload {
+        tool_call_id: "dry_fire_tc".to_string(),
+        tool_name: "read_file".to_string(),
+        arguments: serde_json::json!({"path": "README.md"}),
+    }
+}
+
+pub(crate) fn synthetic_post_run_payload(run_id: &str) -> PostRunPayload {
+    PostRunPayload {
+        run_id: run_id.to_string(),
+        exit_reason: "ok".to_string(),
+        error: None,
+        final_output: "hooks dry-fire: synthetic final output".to_string(),
+        final_output_truncated: false,
+        tool_call_count: 0,
+        budget: PostRunBudgetPayload {
+            steps: 1,
+            max_steps: 0,
+            tool_calls: 0,
+            max_total_tool_calls: 0,
+            wall_time_ms: 0,
+            max_wall_time_ms: 0,
+            token_usage: None,
+        },
+        taint: None,
+    }
+}
+
+fn synthetic_tool_result_payload() -> ToolResultPayload {
+    ToolResultPayload {
+        tool_call_id: "dry_fire_tc".to_string(),
+        tool_name: "read_file".to_string(),
+        ok: true,
+        content: "hooks dry-fire: synthetic tool output".to_string(),
+        truncated: false,
+    }
+}
+
+fn check_unknown_keys(
+    raw: &serde_yaml::Value,
+    lines: &YamlLines,
+    report: &mut HooksValidationReport,
+) {
+    let Some(top) = raw.as_mapping() else {
+        return;
+    };
+    for key in top.keys().filter_map(|k| k.as_str()) {
+        if !TOP_LEVEL_KEYS.contains(&key) {
+            report.push(
+                HookDiagnosticSeverity::Warning,
+                None,
+                lines.key_line(key, 0),
+                unknown_key_message(key, TOP_LEVEL_KEYS),
+            );
+        }
+    }
+    let Some(hooks) = top.get("hooks").and_then(|h| h.as_sequence()) else {
+        return;
+    };
+    for (idx, hook) in hooks.iter().enumerate() {
+        let Some(map) = hook.as_mapping() else {
+            continue;
+        };
+        let name = map.get("name").and_then(|n| n.as_str());
+        let from = lines.hook_start(idx).unwrap_or(1);
+        for key in map.keys().filter_map(|k| k.as_str()) {
+            if !HOOK_KEYS.contains(&key) {
+                report.push(
+                    HookDiagnosticSeverity::Warning,
+                    name,
+                    lines.key_line(key, from),
+                    unknown_key_message(key, HOOK_KEYS),
+                );
+            }
+        }
+        let Some(matcher) = map.get("match").and_then(|m| m.as_mapping()) else {
+            continue;
+        };
+        for key in matcher.keys().filter_map(|k| k.as_str()) {
+            if !MATCH_KEYS.contains(&key) {
+                report.push(
+                    HookDiagnosticSeverity::Warning,
+                    name,
+                    lines.key_line(key, from),
+                    unknown_key_message(&format!("match.{key}"), MATCH_KEYS),
+                );
+            }
+        }
+    }
+}
+
+fn unknown_key_message(key: &str, known: &[&str]) -> String {
+    let bare = key.rsplit('.').next().unwrap_or(key);
+    match nearest_key(bare, known) {
+        Some(suggestion) => format!("unknown key '{key}' is ignored; did you mean '{suggestion}'?"),
+        None => format!("unknown key '{key}' is ignored"),
+    }
+}
+
+fn nearest_key<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (key.chars().count() / 3).max(2);
+    known
+        .iter()
+        .map(|k| (edit_distance(key, k), *k))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+fn check_scripts(
+    hook: &HookConfig,
+    workdir: &Path,
+    line: Option<usize>,
+    report: &mut HooksValidationReport,
+) {
+    let name = Some(hook.name.as_str());
+    let command = Path::new(&hook.command);
+    if has_path_separator(&hook.command) {
+        let resolved = resolve_relative(workdir, command);
+        if !resolved.is_file() {
+            report.push(
+                HookDiagnosticSeverity::Warning,
+                name,
+                line,
+                format!("command '{}' does not exist", resolved.display()),
+            );
+        } else if !is_executable(&resolved) {
+            report.push(
+                HookDiagnosticSeverity::Warning,
+                name,
+                line,
+                format!("command '{}' is not executable", resolved.display()),
+            );
+        }
+    } else if find_on_path(&hook.command).is_none() {
+        report.push(
+            HookDiagnosticSeverity::Warning,
+            name,
+            line,
+            format!("command '{}' was not found on PATH", hook.command),
+        );
+    }
+    for arg in hook.args.iter().filter(|a| looks_like_script(a)) {
+        let resolved = resolve_relative(workdir, Path::new(arg));
+        if !resolved.is_file() {
+            report.push(
+                HookDiagnosticSeverity::Warning,
+                name,
+                line,
+                format!("script '{}' does not exist", resolved.display()),
+            );
+        }
+    }
+}
+
+fn has_path_separator(s: &str) -> bool {
+    s.contains('/') || s.contains('\\')
+}
+
+fn looks_like_script(arg: &str) -> bool {
+    !arg.starts_with('-')
+        && !arg.contains(char::is_whitespace)
+        && Path::new(arg)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| SCRIPT_EXTENSIONS.contains(&e))
+}
+
+fn resolve_relative(workdir: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        workdir.join(path)
+    }
+}
+
+fn find_on_path(command: &str) -> Option<PathBuf> {
+    let path_os = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_os).find_map(|dir| {
+        let candidate = dir.join(command);
+        if candidate.is_file() && is_executable(&candidate) {
+            return Some(candidate);
+        }
+        if cfg!(windows) {
+            for ext in ["exe", "cmd", "bat"] {
+                let with_ext = candidate.with_extension(ext);
+                if with_ext.is_file() {
+                    return Some(with_ext);
+                }
+            }
+        }
+        None
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Best-effort line lookup for block-style YAML. Flow-style configs parse
+/// fine but their diagnostics carry no line.
+struct YamlLines<'a> {
+    lines: Vec<&'a str>,
+    hook_starts: Vec<usize>,
+}
+
+impl<'a> YamlLines<'a> {
+    fn new(text: &'a str) -> Self {
+        let lines = text.lines().collect::<Vec<_>>();
+        let mut hook_starts = Vec::new();
+        let mut item_indent = None;
+        let mut in_hooks = false;
+        for (idx, line) in lines.iter().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            if indent == 0 {
+                in_hooks = trimmed.starts_with("hooks:");
+                continue;
+            }
+            if !in_hooks || !trimmed.starts_with("- ") {
+                continue;
+            }
+            match item_indent {
+                None => {
+                    item_indent = Some(indent);
+                    hook_starts.push(idx + 1);
+                }
+                Some(i) if i == indent => hook_starts.push(idx + 1),
+                Some(_) => {}
+            }
+        }
+        Self { lines, hook_starts }
+    }
+
+    fn hook_start(&self, idx: usize) -> Option<usize> {
+        self.hook_starts.get(idx).copied()
+    }
+
+    /// First line at or after the 1-based `from` that starts the key `key`.
+    fn key_line(&self, key: &str, from: usize) -> Option<usize> {
+        let prefix = format!("{key}:");
+        self.lines
+            .iter()
+            .enumerate()
+            .skip(from.saturating_sub(1))
+            .find(|(_, l)| {
+                let t = l.trim_start();
+                t.strip_prefix("- ").unwrap_or(t).starts_with(&prefix)
+            })
+            .map(|(idx, _)| idx + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, nearest_key, YamlLines, HOOK_KEYS};
+
+    #[test]
+    fn nearest_key_suggests_close_matches_only() {
+        assert_eq!(edit_distance("timeout_ms", "timout_ms"), 1);
+        assert_eq!(nearest_key("timout_ms", HOOK_KEYS), Some("timeout_ms"));
+        assert_eq!(nearest_key("stage", HOOK_KEYS), Some("stages"));
+        assert_eq!(nearest_key("retries", HOOK_KEYS), None);
+    }
+
+    #[test]
+    fn yaml_lines_locate_hook_items_and_keys() {
+        let text = "version: 1\nhooks:\n  # comment\n  - name: a\n    stages: [\"pre_model\"]\n    args:\n      - \"x\"\n  - name: b\n    comand: \"echo\"\n";
+        let lines = YamlLines::new(text);
+        assert_eq!(lines.hook_start(0), Some(4));
+        assert_eq!(lines.hook_start(1), Some(8));
+        assert_eq!(lines.hook_start(2), None);
+        assert_eq!(lines.key_line("comand", 8), Some(9));
+        assert_eq!(lines.key_line("name", 5), Some(8));
+        assert_eq!(lines.key_line("version", 0), Some(1));
+    }
+}
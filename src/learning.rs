@@ -4,27 +4,38 @@ use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
 
-use crate::events::{Event, EventKind, EventSink, JsonlFileSink};
+use crate::events::{Event, EventSink, JsonlFileSink, LearningCapturedPayload};
 use crate::store;
 mod assist;
 mod capture;
+mod from_run;
+mod guidance;
 mod promotion;
 mod render;
+mod search;
 mod store_ops;
 mod support;
+mod sync;
 #[allow(unused_imports)]
 pub use assist::{
     apply_assisted_draft_to_capture_input, build_assist_capture_input_canonical,
     build_assist_capture_meta, compute_assist_input_hash_hex, parse_assisted_capture_draft,
     render_assist_capture_preview,
 };
-#[allow(unused_imports)]
-pub use capture::build_capture_input;
 use capture::{
     attach_evidence_notes, build_proposed_memory, infer_sensitivity_flags, parse_evidence_specs,
     truncate_string,
 };
 #[allow(unused_imports)]
+pub use capture::{build_capture_input, CaptureInputParts};
+#[allow(unused_imports)]
+pub use from_run::{draft_capture_from_run, draft_capture_from_run_record};
+#[allow(unused_imports)]
+pub use guidance::{
+    learned_guidance_message, select_learned_guidance, LearnedGuidance, LearnedGuidanceSelector,
+    DEFAULT_LEARNED_GUIDANCE_MAX_CHARS, LEARNED_GUIDANCE_HEADER,
+};
+#[allow(unused_imports)]
 pub use promotion::{
     insert_managed_learning_block, promote_learning_to_agents, promote_learning_to_check,
     promote_learning_to_pack, render_learning_to_check_markdown, render_learning_to_guidance_block,
@@ -34,21 +45,22 @@ pub use promotion::{
 #[allow(unused_imports)]
 pub use render::{
     learning_status_str, render_archive_confirmation, render_capture_confirmation,
-    render_learning_list_json_preview, render_learning_list_table,
+    render_capture_draft_preview, render_learning_list_json_preview, render_learning_list_table,
     render_learning_show_json_preview, render_learning_show_text,
 };
 #[allow(unused_imports)]
+pub use search::{search_learning_entries, LearningSearchFilters, LearningSearchHit};
+#[allow(unused_imports)]
 pub use store_ops::{
     archive_learning_entry, learning_entries_dir, learning_entry_path, learning_events_path,
     list_learning_entries, load_learning_entry,
 };
 use store_ops::{
     compute_file_sha256_hex, emit_learning_promoted_event, emit_learning_promoted_event_for_check,
-    learning_agents_target_path, learning_check_path, learning_pack_target_path,
-    update_learning_status,
+    learning_agents_target_path, learning_check_path, learning_context_pack_path,
+    learning_pack_target_path,
 };
-#[cfg(test)]
-use support::redact_secrets_for_display;
+pub(crate) use support::redact_secrets_for_display;
 #[allow(unused_imports)]
 pub use support::require_force_for_sensitive_promotion;
 #[cfg(test)]
@@ -58,6 +70,12 @@ use support::{
     has_any_sensitivity, preview_text, redact_and_bound_terminal_output,
     stable_learning_target_path,
 };
+use sync::{managed_block_hash_hex, record_learning_promotion};
+#[allow(unused_imports)]
+pub use sync::{
+    promotion_target_status, render_learning_sync_report, sync_learning_promotions,
+    LearningSyncItem, LearningSyncReport, PromotionTargetStatusV1,
+};
 
 pub const LEARNING_ENTRY_SCHEMA_V1: &str = "openagent.learning_entry.v1";
 const MAX_RUN_ID_CHARS: usize = 128;
@@ -110,6 +128,20 @@ pub struct LearningEntryV1 {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub truncations: Vec<FieldTruncationV1>,
     pub entry_hash_hex: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub promotion: Option<LearningPromotionV1>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub promotion_stale: bool,
+}
+
+/// Where an entry was promoted to. `target_path` is stable (workdir-relative,
+/// forward slashes). `target_hash` covers the whole check file, or only this
+/// entry's managed block for pack/agents targets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LearningPromotionV1 {
+    pub target_kind: String,
+    pub target_path: String,
+    pub target_hash: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -345,12 +377,14 @@ pub struct ArchiveLearningResult {
     pub learning_id: String,
     pub previous_status: LearningStatusV1,
     pub archived: bool,
+    pub live_promotion_target: Option<LearningPromotionV1>,
 }
 
 pub fn capture_learning_entry(
     state_dir: &Path,
     input: CaptureLearningInput,
 ) -> anyhow::Result<CaptureLearningOutput> {
+    let _lock = crate::store::acquire_state_lock(state_dir)?;
     let mut truncations = Vec::new();
     let id = Ulid::new().to_string();
     let created_at = crate::trust::now_rfc3339();
@@ -407,6 +441,8 @@ pub fn capture_learning_entry(
         status: LearningStatusV1::Captured,
         truncations,
         entry_hash_hex: String::new(),
+        promotion: None,
+        promotion_stale: false,
     };
 
     entry.entry_hash_hex = compute_entry_hash_hex(&entry)?;
@@ -423,20 +459,16 @@ pub fn emit_learning_captured_event(
     entry: &LearningEntryV1,
 ) -> anyhow::Result<()> {
     let mut sink = JsonlFileSink::new(&learning_events_path(state_dir))?;
-    let mut data = serde_json::json!({
-        "schema": "openagent.learning_captured.v1",
-        "learning_id": entry.id,
-        "entry_hash_hex": entry.entry_hash_hex,
-        "category": learning_category_str(&entry.category),
-    });
-    if let Some(run_id) = &entry.source.run_id {
-        data["run_id"] = serde_json::Value::String(run_id.clone());
-    }
     sink.emit(Event::new(
         format!("learn:{}", entry.id),
         0,
-        EventKind::LearningCaptured,
-        data,
+        LearningCapturedPayload {
+            schema: "openagent.learning_captured.v1".to_string(),
+            learning_id: entry.id.clone(),
+            entry_hash_hex: entry.entry_hash_hex.clone(),
+            category: learning_category_str(&entry.category).to_string(),
+            run_id: entry.source.run_id.clone(),
+        },
     ))?;
     Ok(())
 }
@@ -469,6 +501,10 @@ pub fn compute_entry_hash_hex(entry: &LearningEntryV1) -> anyhow::Result<String>
     Ok(store::sha256_hex(&bytes))
 }
 
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 pub fn learning_category_str(category: &LearningCategoryV1) -> &'static str {
     match category {
         LearningCategoryV1::WorkflowHint => "workflow_hint",
@@ -179,19 +179,85 @@ pub(super) fn parse_learning_category_str(raw: &str) -> Option<LearningCategoryV
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn build_capture_input(
-    run: Option<String>,
-    category: LearningCategoryV1,
-    summary: String,
-    task_summary: Option<String>,
-    profile: Option<String>,
-    guidance_text: Option<String>,
-    check_text: Option<String>,
-    tags: Vec<String>,
-    evidence: Vec<String>,
-    evidence_notes: Vec<String>,
-) -> CaptureLearningInput {
+/// Named inputs for [`build_capture_input`], so adjacent optional strings such
+/// as `task_summary` and `profile` cannot be swapped at a call site.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureInputParts {
+    pub run: Option<String>,
+    pub category: LearningCategoryV1,
+    pub summary: String,
+    pub task_summary: Option<String>,
+    pub profile: Option<String>,
+    pub guidance_text: Option<String>,
+    pub check_text: Option<String>,
+    pub tags: Vec<String>,
+    pub evidence: Vec<String>,
+    pub evidence_notes: Vec<String>,
+}
+
+impl CaptureInputParts {
+    pub fn new(category: LearningCategoryV1, summary: impl Into<String>) -> Self {
+        Self {
+            category,
+            summary: summary.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn run(mut self, run: Option<String>) -> Self {
+        self.run = run;
+        self
+    }
+
+    pub fn task_summary(mut self, task_summary: Option<String>) -> Self {
+        self.task_summary = task_summary;
+        self
+    }
+
+    pub fn profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn guidance_text(mut self, guidance_text: Option<String>) -> Self {
+        self.guidance_text = guidance_text;
+        self
+    }
+
+    pub fn check_text(mut self, check_text: Option<String>) -> Self {
+        self.check_text = check_text;
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn evidence(mut self, evidence: Vec<String>) -> Self {
+        self.evidence = evidence;
+        self
+    }
+
+    pub fn evidence_notes(mut self, evidence_notes: Vec<String>) -> Self {
+        self.evidence_notes = evidence_notes;
+        self
+    }
+}
+
+pub fn build_capture_input(parts: CaptureInputParts) -> CaptureLearningInput {
+    let CaptureInputParts {
+        run,
+        category,
+        summary,
+        task_summary,
+        profile,
+        guidance_text,
+        check_text,
+        tags,
+        evidence,
+        evidence_notes,
+    } = parts;
     CaptureLearningInput {
         run_id: run,
         category,
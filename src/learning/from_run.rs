@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+
+use crate::agent_tool_exec::{classify_tool_failure, tool_result_has_error, ToolFailureClass};
+use crate::store::{self, RunRecord};
+use crate::types::{Role, ToolCall};
+
+use super::{CaptureInputParts, LearningCategoryV1};
+
+/// Loads `run_id` from the state dir and drafts a capture for it; see
+/// [`draft_capture_from_run_record`].
+pub fn draft_capture_from_run(state_dir: &Path, run_id: &str) -> anyhow::Result<CaptureInputParts> {
+    let record = store::load_run_record(state_dir, run_id)
+        .with_context(|| format!("failed to load run record {run_id}"))?;
+    draft_capture_from_run_record(&record)
+}
+
+/// Drafts capture parts for a failed run: the summary comes from the exit
+/// reason and error, evidence names the run, its exit reason and the last
+/// failing tool call with its failure class. Planner errors map to
+/// `workflow_hint`, two or more `E_SCHEMA` tool failures to
+/// `prompt_guidance`, and everything else to `workflow_hint`.
+pub fn draft_capture_from_run_record(record: &RunRecord) -> anyhow::Result<CaptureInputParts> {
+    let run_id = record.metadata.run_id.as_str();
+    let exit_reason = record.metadata.exit_reason.as_str();
+    if exit_reason == "ok" {
+        return Err(anyhow!(
+            "run {run_id} exited ok; --from-run only drafts captures for failed runs"
+        ));
+    }
+
+    let failures = failing_tool_calls(record);
+    let schema_failures = failures
+        .iter()
+        .filter(|(_, class)| *class == ToolFailureClass::Schema)
+        .count();
+    let category = if exit_reason == "planner_error" {
+        LearningCategoryV1::WorkflowHint
+    } else if schema_failures >= 2 {
+        LearningCategoryV1::PromptGuidance
+    } else {
+        LearningCategoryV1::WorkflowHint
+    };
+
+    let summary = match record.error.as_deref().map(str::trim) {
+        Some(error) if !error.is_empty() => format!("Run exited with {exit_reason}: {error}"),
+        _ => format!("Run exited with {exit_reason}"),
+    };
+
+    let mut evidence = vec![
+        format!("run_id:{run_id}"),
+        format!("exit_reason:{exit_reason}"),
+    ];
+    if let Some((tool_call_id, class)) = failures.last() {
+        evidence.push(format!("tool_call_id:{tool_call_id}"));
+        evidence.push(format!("reason_code:{}", class.as_str()));
+    }
+
+    Ok(CaptureInputParts::new(category, summary)
+        .run(Some(run_id.to_string()))
+        .evidence(evidence))
+}
+
+/// Failed tool results in transcript order, with their failure class.
+fn failing_tool_calls(record: &RunRecord) -> Vec<(String, ToolFailureClass)> {
+    record
+        .transcript
+        .iter()
+        .filter(|m| matches!(m.role, Role::Tool))
+        .filter_map(|m| {
+            let content = m.content.as_deref()?;
+            if !tool_result_has_error(content) {
+                return None;
+            }
+            let tool_call_id = m.tool_call_id.clone()?;
+            let call = record
+                .tool_calls
+                .iter()
+                .find(|tc| tc.id == tool_call_id)
+                .cloned()
+                .unwrap_or_else(|| ToolCall {
+                    id: tool_call_id.clone(),
+                    name: m.tool_name.clone().unwrap_or_default(),
+                    arguments: serde_json::Value::Null,
+                });
+            Some((tool_call_id, classify_tool_failure(&call, content, false)))
+        })
+        .collect()
+}
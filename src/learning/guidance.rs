@@ -0,0 +1,127 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::store::LearnedGuidanceRecord;
+use crate::types::{Message, Role};
+
+use super::{list_learning_entries, LearningEntryV1, LearningStatusV1};
+
+pub const LEARNED_GUIDANCE_HEADER: &str = "LEARNED GUIDANCE (non-authoritative)";
+pub const DEFAULT_LEARNED_GUIDANCE_MAX_CHARS: usize = 4096;
+
+/// Which entries `--use-learnings` may inject into a run.
+#[derive(Debug, Clone)]
+pub struct LearnedGuidanceSelector<'a> {
+    /// Active reliability profile; entries captured under another profile are skipped.
+    pub profile: Option<&'a str>,
+    /// Captured (not yet promoted) entries carrying any of these tags are also eligible.
+    pub tags: &'a [String],
+    /// Cap on the summed guidance text of all injected entries.
+    pub max_chars: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LearnedGuidance {
+    pub message: Option<Message>,
+    pub injected: Vec<LearnedGuidanceRecord>,
+}
+
+/// Selects promoted entries, plus captured entries tagged with one of
+/// `selector.tags`, whose `guidance_text` fits the char budget. Newest entries
+/// are considered first; archived entries, entries with suspected secrets and
+/// duplicate entry hashes are skipped.
+pub fn select_learned_guidance(
+    state_dir: &Path,
+    selector: &LearnedGuidanceSelector<'_>,
+) -> anyhow::Result<LearnedGuidance> {
+    let entries = list_learning_entries(state_dir)?;
+    let tags = selector
+        .tags
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let mut seen_hashes = BTreeSet::new();
+    let mut budget = selector.max_chars;
+    let mut selected = Vec::new();
+    for entry in entries.iter().rev() {
+        if !is_eligible(entry, selector.profile, &tags) {
+            continue;
+        }
+        let Some(text) = entry
+            .proposed_memory
+            .guidance_text
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+        else {
+            continue;
+        };
+        let chars = text.chars().count();
+        if chars > budget || !seen_hashes.insert(entry.entry_hash_hex.as_str()) {
+            continue;
+        }
+        budget -= chars;
+        selected.push((entry, text, chars));
+    }
+    selected.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+    Ok(LearnedGuidance {
+        message: learned_guidance_message(
+            &selected
+                .iter()
+                .map(|(entry, text, _)| (entry.id.as_str(), *text))
+                .collect::<Vec<_>>(),
+        ),
+        injected: selected
+            .iter()
+            .map(|(entry, _, chars)| LearnedGuidanceRecord {
+                learning_id: entry.id.clone(),
+                entry_hash_hex: entry.entry_hash_hex.clone(),
+                chars: *chars as u64,
+            })
+            .collect(),
+    })
+}
+
+fn is_eligible(entry: &LearningEntryV1, profile: Option<&str>, tags: &[String]) -> bool {
+    if entry.sensitivity_flags.contains_secrets_suspected {
+        return false;
+    }
+    if entry
+        .source
+        .profile
+        .as_deref()
+        .is_some_and(|p| Some(p) != profile)
+    {
+        return false;
+    }
+    match entry.status {
+        LearningStatusV1::Promoted => true,
+        LearningStatusV1::Captured => entry
+            .proposed_memory
+            .tags
+            .iter()
+            .any(|t| tags.contains(&t.to_lowercase())),
+        LearningStatusV1::Archived => false,
+    }
+}
+
+/// Renders `(learning_id, guidance_text)` pairs under
+/// [`LEARNED_GUIDANCE_HEADER`], in the task-memory layout.
+pub fn learned_guidance_message(items: &[(&str, &str)]) -> Option<Message> {
+    if items.is_empty() {
+        return None;
+    }
+    let mut content = String::new();
+    content.push_str(LEARNED_GUIDANCE_HEADER);
+    content.push('\n');
+    for (id, text) in items {
+        content.push_str(&format!("- [{id}] {}\n", text.replace('\n', "\n  ")));
+    }
+    Some(Message {
+        role: Role::Developer,
+        content: Some(content.trim_end().to_string()),
+        tool_call_id: None,
+        tool_name: None,
+        tool_calls: None,
+    })
+}
@@ -10,8 +10,9 @@ use super::support::{
 use super::{
     compute_file_sha256_hex, emit_learning_promoted_event, emit_learning_promoted_event_for_check,
     learning_agents_target_path, learning_category_str, learning_check_path,
-    learning_pack_target_path, load_learning_entry, update_learning_status, LearningEntryV1,
-    LearningPromoteError, LearningStatusV1, LEARNED_GUIDANCE_MANAGED_SECTION_MARKER,
+    learning_context_pack_path, learning_pack_target_path, load_learning_entry,
+    managed_block_hash_hex, record_learning_promotion, EvidenceKindV1, LearningEntryV1,
+    LearningPromoteError, LEARNED_GUIDANCE_MANAGED_SECTION_MARKER,
 };
 
 #[derive(Debug, Clone)]
@@ -43,6 +44,9 @@ pub struct PromoteToTargetResult {
     pub changed: bool,
     pub noop: bool,
     pub pack_id: Option<String>,
+    /// `.localagent/packs/<pack_id>.yaml` updated with the entry's artifact
+    /// paths, so the pack can also be used with `--context-pack`.
+    pub context_pack_path: Option<PathBuf>,
 }
 
 pub fn promote_learning_to_check(
@@ -52,6 +56,7 @@ pub fn promote_learning_to_check(
     force: bool,
 ) -> anyhow::Result<PromoteToCheckResult> {
     validate_promote_slug(slug)?;
+    let _lock = crate::store::acquire_state_lock(state_dir)?;
     let mut entry = load_learning_entry(state_dir, id)?;
     require_force_for_sensitive_promotion(&entry, force)?;
 
@@ -69,7 +74,13 @@ pub fn promote_learning_to_check(
         .with_context(|| format!("failed to write check file {}", target_path.display()))?;
     let target_file_sha256_hex = compute_file_sha256_hex(&target_path)?;
 
-    update_learning_status(state_dir, &mut entry, LearningStatusV1::Promoted)?;
+    record_learning_promotion(
+        state_dir,
+        &mut entry,
+        "check",
+        &target_path,
+        target_file_sha256_hex.clone(),
+    )?;
     emit_learning_promoted_event_for_check(
         state_dir,
         &entry,
@@ -102,11 +113,14 @@ pub fn render_promote_to_check_confirmation(out: &PromoteToCheckResult) -> Strin
 }
 
 pub fn render_promote_to_target_confirmation(out: &PromoteToTargetResult) -> String {
-    let pack_suffix = out
+    let mut pack_suffix = out
         .pack_id
         .as_deref()
         .map(|p| format!(", pack_id={p}"))
         .unwrap_or_default();
+    if let Some(path) = &out.context_pack_path {
+        pack_suffix.push_str(&format!(", context_pack={}", path.display()));
+    }
     if out.noop {
         return format!(
             "Already promoted (noop): LEARN-{} already present in managed section (target={}, path={}{} )",
@@ -148,7 +162,67 @@ pub fn promote_learning_to_pack(
 ) -> anyhow::Result<PromoteToTargetResult> {
     validate_promote_pack_id(pack_id)?;
     let target_path = learning_pack_target_path(state_dir, pack_id);
-    promote_learning_to_managed_target(state_dir, id, force, "pack", &target_path, Some(pack_id))
+    let mut out = promote_learning_to_managed_target(
+        state_dir,
+        id,
+        force,
+        "pack",
+        &target_path,
+        Some(pack_id),
+    )?;
+    out.context_pack_path = Some(write_learning_context_pack(state_dir, id, pack_id)?);
+    Ok(out)
+}
+
+/// Merges the entry's workdir-relative artifact paths into the pack's
+/// `--context-pack` file, creating it when missing.
+fn write_learning_context_pack(
+    state_dir: &Path,
+    id: &str,
+    pack_id: &str,
+) -> anyhow::Result<PathBuf> {
+    let _lock = crate::store::acquire_state_lock(state_dir)?;
+    let entry = load_learning_entry(state_dir, id)?;
+    let path = learning_context_pack_path(state_dir, pack_id);
+    let existing = if path.exists() {
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read context pack {}", path.display()))?;
+        Some(
+            crate::context_packs::parse_context_pack_spec(&raw)
+                .with_context(|| format!("invalid context pack {}", path.display()))?,
+        )
+    } else {
+        None
+    };
+    let files = entry
+        .evidence
+        .iter()
+        .filter(|e| e.kind == EvidenceKindV1::ArtifactPath)
+        .map(|e| e.value.trim())
+        .filter(|v| {
+            !v.is_empty() && !Path::new(v).is_absolute() && !v.split(['/', '\\']).any(|s| s == "..")
+        })
+        .map(ToOwned::to_owned)
+        .collect::<Vec<_>>();
+    let summary = entry
+        .summary
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let spec = crate::context_packs::merge_learning_into_context_pack(
+        existing.clone(),
+        &truncate_utf8_chars(&summary, 240),
+        &files,
+        &entry.id,
+    );
+    if existing.as_ref() != Some(&spec) {
+        write_text_atomic(
+            &path,
+            &crate::context_packs::render_context_pack_spec(&spec)?,
+        )
+        .with_context(|| format!("failed to write context pack {}", path.display()))?;
+    }
+    Ok(path)
 }
 
 pub fn render_learning_to_check_markdown(
@@ -161,17 +235,19 @@ pub fn render_learning_to_check_markdown(
     let name = serde_json::to_string(&fm.name)?;
     let description = serde_json::to_string(fm.description.as_deref().unwrap_or(""))?;
     let pass_value = serde_json::to_string(&fm.pass_criteria.value)?;
-    let pass_kind = match fm.pass_criteria.kind {
-        crate::checks::schema::PassCriteriaType::Contains => "output_contains",
-        crate::checks::schema::PassCriteriaType::NotContains => "output_not_contains",
-        crate::checks::schema::PassCriteriaType::Equals => "output_equals",
-    };
+    let pass_kind = fm.pass_criteria.kind.as_str();
 
     let mut out = String::new();
     out.push_str("---\n");
     out.push_str(&format!("schema_version: {}\n", fm.schema_version));
     out.push_str(&format!("name: {name}\n"));
     out.push_str(&format!("description: {description}\n"));
+    if let Some(learning_id) = &fm.learning_id {
+        out.push_str(&format!(
+            "learning_id: {}\n",
+            serde_json::to_string(learning_id)?
+        ));
+    }
     out.push_str(&format!("required: {}\n", fm.required));
     out.push_str("allowed_tools: []\n");
     out.push_str("pass_criteria:\n");
@@ -211,8 +287,12 @@ fn build_generated_check_from_learning(
         pass_criteria: crate::checks::schema::PassCriteria {
             kind: crate::checks::schema::PassCriteriaType::Contains,
             value: "TODO".to_string(),
+            regex_match: None,
         },
         budget: None,
+        fixtures: Vec::new(),
+        expect_files: Vec::new(),
+        learning_id: Some(entry.id.clone()),
     }
 }
 
@@ -245,6 +325,7 @@ fn promote_learning_to_managed_target(
     target_path: &Path,
     pack_id: Option<&str>,
 ) -> anyhow::Result<PromoteToTargetResult> {
+    let _lock = crate::store::acquire_state_lock(state_dir)?;
     let mut entry = load_learning_entry(state_dir, id)?;
     require_force_for_sensitive_promotion(&entry, force)?;
 
@@ -261,7 +342,8 @@ fn promote_learning_to_managed_target(
         write_text_atomic(target_path, &insert.text)
             .with_context(|| format!("failed to write target file {}", target_path.display()))?;
         let target_file_sha256_hex = compute_file_sha256_hex(target_path)?;
-        update_learning_status(state_dir, &mut entry, LearningStatusV1::Promoted)?;
+        let block_hash = managed_block_hash_hex(&insert.text, &entry.id).unwrap_or_default();
+        record_learning_promotion(state_dir, &mut entry, target, target_path, block_hash)?;
         emit_learning_promoted_event(
             state_dir,
             &entry,
@@ -283,6 +365,7 @@ fn promote_learning_to_managed_target(
             changed: true,
             noop: false,
             pack_id: pack_id.map(ToOwned::to_owned),
+            context_pack_path: None,
         });
     }
 
@@ -301,6 +384,7 @@ fn promote_learning_to_managed_target(
         changed: false,
         noop: true,
         pack_id: pack_id.map(ToOwned::to_owned),
+        context_pack_path: None,
     })
 }
 
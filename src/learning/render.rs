@@ -1,16 +1,23 @@
 use super::{
     has_any_sensitivity, learning_category_str, preview_text, redact_and_bound_terminal_output,
-    ArchiveLearningResult, LearningEntryV1, LearningStatusV1, LEARN_SHOW_MAX_BYTES,
-    LIST_SUMMARY_PREVIEW_CHARS,
+    ArchiveLearningResult, CaptureLearningInput, LearningEntryV1, LearningStatusV1,
+    PromotionTargetStatusV1, LEARN_SHOW_MAX_BYTES, LIST_SUMMARY_PREVIEW_CHARS,
 };
 
 pub fn render_archive_confirmation(out: &ArchiveLearningResult) -> String {
     if out.archived {
-        return format!(
+        let mut msg = format!(
             "Archived learning {} (previous_status={})",
             out.learning_id,
             learning_status_str(&out.previous_status)
         );
+        if let Some(target) = &out.live_promotion_target {
+            msg.push_str(&format!(
+                "\nWARN: promoted {} target {} still exists; remove it manually if the guidance no longer applies",
+                target.target_kind, target.target_path
+            ));
+        }
+        return msg;
     }
     format!("Already archived (noop): {}", out.learning_id)
 }
@@ -24,6 +31,28 @@ pub fn render_capture_confirmation(entry: &LearningEntryV1) -> String {
     )
 }
 
+/// Preview of a capture that has not been written yet.
+pub fn render_capture_draft_preview(input: &CaptureLearningInput) -> String {
+    let mut out = String::new();
+    out.push_str("CAPTURE DRAFT PREVIEW (not saved). Use --write to persist.\n");
+    out.push_str(&format!(
+        "run_id: {}\ncategory: {}\n",
+        input.run_id.as_deref().unwrap_or("-"),
+        learning_category_str(&input.category)
+    ));
+    out.push_str("summary:\n");
+    out.push_str(&input.summary);
+    out.push('\n');
+    out.push_str("evidence:\n");
+    if input.evidence_specs.is_empty() {
+        out.push_str("  -\n");
+    }
+    for spec in &input.evidence_specs {
+        out.push_str(&format!("  {spec}\n"));
+    }
+    redact_and_bound_terminal_output(&out, LEARN_SHOW_MAX_BYTES)
+}
+
 pub fn render_learning_list_table(entries: &[LearningEntryV1]) -> String {
     let mut out = String::new();
     out.push_str("ID  STATUS  CATEGORY  RUN_ID  S  SUMMARY\n");
@@ -36,10 +65,15 @@ pub fn render_learning_list_table(entries: &[LearningEntryV1]) -> String {
         };
         let summary = preview_text(&e.summary, LIST_SUMMARY_PREVIEW_CHARS);
         let summary = redact_and_bound_terminal_output(&summary, 512);
+        let status = if e.promotion_stale {
+            format!("{}(stale)", learning_status_str(&e.status))
+        } else {
+            learning_status_str(&e.status).to_string()
+        };
         out.push_str(&format!(
             "{}  {}  {}  {}  {}  {}\n",
             e.id,
-            learning_status_str(&e.status),
+            status,
             learning_category_str(&e.category),
             run_id,
             sensitive,
@@ -64,6 +98,7 @@ pub fn render_learning_show_text(
     entry: &LearningEntryV1,
     show_evidence: bool,
     show_proposed: bool,
+    target_status: Option<PromotionTargetStatusV1>,
 ) -> String {
     let mut out = String::new();
     out.push_str(&format!("id: {}\n", entry.id));
@@ -97,6 +132,17 @@ pub fn render_learning_show_text(
         entry.sensitivity_flags.contains_secrets_suspected,
         entry.sensitivity_flags.contains_user_data
     ));
+    if let Some(promotion) = &entry.promotion {
+        out.push_str("promotion:\n");
+        out.push_str(&format!("  target_kind: {}\n", promotion.target_kind));
+        out.push_str(&format!("  target_path: {}\n", promotion.target_path));
+        out.push_str(&format!("  target_hash: {}\n", promotion.target_hash));
+        out.push_str(&format!(
+            "  target_status: {}\n",
+            target_status.map(|s| s.as_str()).unwrap_or("-")
+        ));
+        out.push_str(&format!("  stale: {}\n", entry.promotion_stale));
+    }
     if show_evidence {
         out.push_str("evidence:\n");
         if entry.evidence.is_empty() {
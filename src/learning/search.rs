@@ -0,0 +1,154 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::{learning_entries_dir, LearningEntryV1, LearningStatusV1};
+
+const SEARCH_WEIGHT_TAG: u32 = 100;
+const SEARCH_WEIGHT_SUMMARY: u32 = 10;
+const SEARCH_WEIGHT_BODY: u32 = 1;
+
+#[derive(Debug, Clone, Default)]
+pub struct LearningSearchFilters {
+    /// Entries must carry every tag (case-insensitive).
+    pub tags: Vec<String>,
+    /// RFC3339 lower bound on `created_at`, inclusive.
+    pub since: Option<String>,
+    pub include_archived: bool,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct LearningSearchHit {
+    pub score: u32,
+    pub entry: LearningEntryV1,
+}
+
+impl PartialEq for LearningSearchHit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for LearningSearchHit {}
+
+impl PartialOrd for LearningSearchHit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LearningSearchHit {
+    /// Higher score ranks first, then the newer (larger) id.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .cmp(&other.score)
+            .then_with(|| self.entry.id.cmp(&other.entry.id))
+    }
+}
+
+/// Ranks entries whose fields contain every whitespace-separated query term
+/// (case-insensitive). Each term scores by its best field: tag > summary >
+/// guidance/check text and evidence values. An empty query matches every
+/// entry that passes the filters. Entry files are read one at a time and at
+/// most `filters.limit` hits are kept.
+pub fn search_learning_entries(
+    state_dir: &Path,
+    query: &str,
+    filters: &LearningSearchFilters,
+) -> anyhow::Result<Vec<LearningSearchHit>> {
+    let since = filters
+        .since
+        .as_deref()
+        .map(|s| {
+            OffsetDateTime::parse(s, &Rfc3339)
+                .map_err(|e| anyhow!("invalid --since '{s}' (expected RFC3339): {e}"))
+        })
+        .transpose()?;
+    let terms = query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    let tags = filters
+        .tags
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let dir = learning_entries_dir(state_dir);
+    if !dir.exists() || filters.limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut top: BinaryHeap<Reverse<LearningSearchHit>> = BinaryHeap::new();
+    for ent in fs::read_dir(&dir)
+        .with_context(|| format!("failed to read learning entries dir {}", dir.display()))?
+    {
+        let path = ent?.path();
+        if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+        let bytes = fs::read(&path)
+            .with_context(|| format!("failed to read learning entry {}", path.display()))?;
+        let entry: LearningEntryV1 = serde_json::from_slice(&bytes)
+            .with_context(|| format!("failed to parse learning entry {}", path.display()))?;
+        if !filters.include_archived && entry.status == LearningStatusV1::Archived {
+            continue;
+        }
+        if let Some(since) = since {
+            match OffsetDateTime::parse(&entry.created_at, &Rfc3339) {
+                Ok(created) if created >= since => {}
+                _ => continue,
+            }
+        }
+        let entry_tags = entry
+            .proposed_memory
+            .tags
+            .iter()
+            .map(|t| t.to_lowercase())
+            .collect::<Vec<_>>();
+        if !tags.iter().all(|t| entry_tags.contains(t)) {
+            continue;
+        }
+        let Some(score) = score_entry(&entry, &entry_tags, &terms) else {
+            continue;
+        };
+        top.push(Reverse(LearningSearchHit { score, entry }));
+        if top.len() > filters.limit {
+            top.pop();
+        }
+    }
+    let mut hits = top.into_iter().map(|Reverse(hit)| hit).collect::<Vec<_>>();
+    hits.sort_by(|a, b| b.cmp(a));
+    Ok(hits)
+}
+
+fn score_entry(entry: &LearningEntryV1, entry_tags: &[String], terms: &[String]) -> Option<u32> {
+    let summary = entry.summary.to_lowercase();
+    let body = [
+        entry.proposed_memory.guidance_text.as_deref(),
+        entry.proposed_memory.check_text.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .chain(entry.evidence.iter().map(|e| e.value.as_str()))
+    .map(str::to_lowercase)
+    .collect::<Vec<_>>();
+    let mut score = 0;
+    for term in terms {
+        score += if entry_tags.iter().any(|t| t.contains(term.as_str())) {
+            SEARCH_WEIGHT_TAG
+        } else if summary.contains(term.as_str()) {
+            SEARCH_WEIGHT_SUMMARY
+        } else if body.iter().any(|b| b.contains(term.as_str())) {
+            SEARCH_WEIGHT_BODY
+        } else {
+            return None;
+        };
+    }
+    Some(score)
+}
@@ -3,25 +3,31 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context};
 
-use crate::events::{Event, EventKind, EventSink, JsonlFileSink};
+use crate::events::{Event, EventSink, JsonlFileSink, LearningPromotedPayload};
 use crate::store;
 
 use super::{
-    stable_learning_target_path, ArchiveLearningResult, LearningEntryV1, LearningStatusV1,
-    LEARNING_PROMOTED_SCHEMA_V1,
+    promotion_target_status, stable_learning_target_path, ArchiveLearningResult, LearningEntryV1,
+    LearningStatusV1, PromotionTargetStatusV1, LEARNING_PROMOTED_SCHEMA_V1,
 };
 
 pub fn archive_learning_entry(state_dir: &Path, id: &str) -> anyhow::Result<ArchiveLearningResult> {
+    let _lock = crate::store::acquire_state_lock(state_dir)?;
     let mut entry = load_learning_entry(state_dir, id)?;
     let previous_status = entry.status.clone();
     let archived = previous_status != LearningStatusV1::Archived;
     if archived {
         update_learning_status(state_dir, &mut entry, LearningStatusV1::Archived)?;
     }
+    let live_promotion_target = match promotion_target_status(state_dir, &entry) {
+        Some(PromotionTargetStatusV1::Missing) | None => None,
+        Some(_) => entry.promotion.clone(),
+    };
     Ok(ArchiveLearningResult {
         learning_id: entry.id,
         previous_status,
         archived,
+        live_promotion_target,
     })
 }
 
@@ -83,29 +89,21 @@ pub(crate) fn emit_learning_promoted_event(
     noop: bool,
 ) -> anyhow::Result<()> {
     let mut sink = JsonlFileSink::new(&learning_events_path(state_dir))?;
-    let mut data = serde_json::json!({
-        "schema": LEARNING_PROMOTED_SCHEMA_V1,
-        "learning_id": entry.id,
-        "entry_hash_hex": entry.entry_hash_hex,
-        "target": target,
-        "target_path": stable_learning_target_path(state_dir, target_path),
-        "forced": forced,
-        "target_file_sha256_hex": target_file_sha256_hex,
-    });
-    if let Some(slug) = slug {
-        data["slug"] = serde_json::Value::String(slug.to_string());
-    }
-    if let Some(pack_id) = pack_id {
-        data["pack_id"] = serde_json::Value::String(pack_id.to_string());
-    }
-    if noop {
-        data["noop"] = serde_json::Value::Bool(true);
-    }
     sink.emit(Event::new(
         format!("learn:{}", entry.id),
         0,
-        EventKind::LearningPromoted,
-        data,
+        LearningPromotedPayload {
+            schema: LEARNING_PROMOTED_SCHEMA_V1.to_string(),
+            learning_id: entry.id.clone(),
+            entry_hash_hex: entry.entry_hash_hex.clone(),
+            target: target.to_string(),
+            target_path: stable_learning_target_path(state_dir, target_path),
+            forced,
+            target_file_sha256_hex: target_file_sha256_hex.to_string(),
+            slug: slug.map(str::to_string),
+            pack_id: pack_id.map(str::to_string),
+            noop: noop.then_some(true),
+        },
     ))?;
     Ok(())
 }
@@ -138,6 +136,20 @@ pub(crate) fn learning_pack_target_path(state_dir: &Path, pack_id: &str) -> Path
     path.join("PACK.md")
 }
 
+/// `--context-pack` file next to the pack's directory: `packs/<pack_id>.yaml`.
+pub(crate) fn learning_context_pack_path(state_dir: &Path, pack_id: &str) -> PathBuf {
+    let mut path = state_dir.join("packs");
+    let mut segments = pack_id.split('/').peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_some() {
+            path = path.join(segment);
+        } else {
+            path = path.join(format!("{segment}.yaml"));
+        }
+    }
+    path
+}
+
 pub fn load_learning_entry(state_dir: &Path, id: &str) -> anyhow::Result<LearningEntryV1> {
     let path = learning_entry_path(state_dir, id);
     let bytes = fs::read(&path)
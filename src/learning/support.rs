@@ -22,7 +22,7 @@ pub(super) fn redact_and_bound_terminal_output(input: &str, max_bytes: usize) ->
     truncate_utf8_bytes(redacted, max_bytes)
 }
 
-pub(super) fn redact_secrets_for_display(input: &str) -> String {
+pub(crate) fn redact_secrets_for_display(input: &str) -> String {
     let mut matches = collect_secret_matches(input);
     matches.sort_by(|a, b| a.start.cmp(&b.start).then(a.end.cmp(&b.end)));
     let mut chosen = Vec::new();
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::store;
+
+use super::store_ops::write_learning_entry;
+use super::support::normalize_newlines;
+use super::{
+    compute_file_sha256_hex, list_learning_entries, stable_learning_target_path, LearningEntryV1,
+    LearningPromotionV1, LearningStatusV1,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionTargetStatusV1 {
+    Ok,
+    Missing,
+    Modified,
+}
+
+impl PromotionTargetStatusV1 {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Missing => "missing",
+            Self::Modified => "modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LearningSyncItem {
+    pub learning_id: String,
+    pub target_kind: String,
+    pub target_path: String,
+    pub status: PromotionTargetStatusV1,
+    pub stale_changed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LearningSyncReport {
+    pub items: Vec<LearningSyncItem>,
+}
+
+impl LearningSyncReport {
+    pub fn stale_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|i| i.status != PromotionTargetStatusV1::Ok)
+            .count()
+    }
+}
+
+/// Records the promotion target on the entry and marks it promoted in a single
+/// atomic rewrite.
+pub(crate) fn record_learning_promotion(
+    state_dir: &Path,
+    entry: &mut LearningEntryV1,
+    target_kind: &str,
+    target_path: &Path,
+    target_hash: String,
+) -> anyhow::Result<()> {
+    entry.status = LearningStatusV1::Promoted;
+    entry.promotion = Some(LearningPromotionV1 {
+        target_kind: target_kind.to_string(),
+        target_path: stable_learning_target_path(state_dir, target_path),
+        target_hash,
+    });
+    entry.promotion_stale = false;
+    write_learning_entry(state_dir, entry)
+}
+
+fn resolve_promotion_target_path(state_dir: &Path, target_path: &str) -> PathBuf {
+    let path = Path::new(target_path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    state_dir.parent().unwrap_or(state_dir).join(path)
+}
+
+/// Hash of this learning's `### LEARN-<id>` block inside a managed section, so
+/// unrelated promotions into the same file do not make the target look modified.
+pub(crate) fn managed_block_hash_hex(text: &str, learning_id: &str) -> Option<String> {
+    let text = normalize_newlines(text);
+    let header = format!("### LEARN-{learning_id}");
+    let start = text
+        .match_indices(&header)
+        .map(|(idx, _)| idx)
+        .find(|idx| *idx == 0 || text[..*idx].ends_with('\n'))?;
+    let body_start = start + header.len();
+    let rest = &text[body_start..];
+    let end = ["\n### ", "\n## "]
+        .iter()
+        .filter_map(|marker| rest.find(marker))
+        .min()
+        .map(|rel| body_start + rel)
+        .unwrap_or(text.len());
+    Some(store::sha256_hex(text[start..end].trim_end().as_bytes()))
+}
+
+pub fn promotion_target_status(
+    state_dir: &Path,
+    entry: &LearningEntryV1,
+) -> Option<PromotionTargetStatusV1> {
+    let promotion = entry.promotion.as_ref()?;
+    let path = resolve_promotion_target_path(state_dir, &promotion.target_path);
+    if !path.is_file() {
+        return Some(PromotionTargetStatusV1::Missing);
+    }
+    let current = if promotion.target_kind == "check" {
+        compute_file_sha256_hex(&path).ok()
+    } else {
+        match fs::read_to_string(&path) {
+            Ok(text) => match managed_block_hash_hex(&text, &entry.id) {
+                Some(hash) => Some(hash),
+                None => return Some(PromotionTargetStatusV1::Missing),
+            },
+            Err(_) => None,
+        }
+    };
+    Some(match current {
+        Some(hash) if hash == promotion.target_hash => PromotionTargetStatusV1::Ok,
+        Some(_) => PromotionTargetStatusV1::Modified,
+        None => PromotionTargetStatusV1::Missing,
+    })
+}
+
+/// Verifies every recorded promotion target and flips `promotion_stale` on
+/// entries whose target was deleted or changed. Archived entries are skipped.
+pub fn sync_learning_promotions(state_dir: &Path) -> anyhow::Result<LearningSyncReport> {
+    let _lock = crate::store::acquire_state_lock(state_dir)?;
+    let mut report = LearningSyncReport::default();
+    for mut entry in list_learning_entries(state_dir)? {
+        if entry.status == LearningStatusV1::Archived {
+            continue;
+        }
+        let Some(status) = promotion_target_status(state_dir, &entry) else {
+            continue;
+        };
+        let stale = status != PromotionTargetStatusV1::Ok;
+        let stale_changed = stale != entry.promotion_stale;
+        if stale_changed {
+            entry.promotion_stale = stale;
+            write_learning_entry(state_dir, &entry)?;
+        }
+        let promotion = entry.promotion.as_ref().expect("promotion present");
+        report.items.push(LearningSyncItem {
+            learning_id: entry.id.clone(),
+            target_kind: promotion.target_kind.clone(),
+            target_path: promotion.target_path.clone(),
+            status,
+            stale_changed,
+        });
+    }
+    Ok(report)
+}
+
+pub fn render_learning_sync_report(report: &LearningSyncReport) -> String {
+    if report.items.is_empty() {
+        return "No promoted learning entries to sync.".to_string();
+    }
+    let mut out = String::new();
+    out.push_str("ID  TARGET  STATUS  PATH\n");
+    for item in &report.items {
+        out.push_str(&format!(
+            "{}  {}  {}{}  {}\n",
+            item.learning_id,
+            item.target_kind,
+            item.status.as_str(),
+            if item.stale_changed { "*" } else { "" },
+            item.target_path
+        ));
+    }
+    out.push_str(&format!(
+        "Synced {} promoted entries ({} stale; * = changed this sync)",
+        report.items.len(),
+        report.stale_count()
+    ));
+    out
+}
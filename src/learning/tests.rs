@@ -50,6 +50,8 @@ fn sample_entry() -> LearningEntryV1 {
         status: LearningStatusV1::Captured,
         truncations: Vec::new(),
         entry_hash_hex: String::new(),
+        promotion: None,
+        promotion_stale: false,
     }
 }
 
@@ -164,6 +166,32 @@ fn parse_evidence_specs_rejects_invalid() {
     assert!(err.to_string().contains("invalid --evidence format"));
 }
 
+#[test]
+fn capture_input_parts_land_in_matching_fields() {
+    let input = build_capture_input(
+        CaptureInputParts::new(LearningCategoryV1::PromptGuidance, "summary")
+            .run(Some("run_1".to_string()))
+            .task_summary(Some("task".to_string()))
+            .profile(Some("profile".to_string()))
+            .guidance_text(Some("guidance".to_string()))
+            .check_text(Some("check".to_string()))
+            .tags(vec!["tag".to_string()])
+            .evidence(vec!["run_id:run_1".to_string()])
+            .evidence_notes(vec!["note".to_string()]),
+    );
+    assert_eq!(input.run_id.as_deref(), Some("run_1"));
+    assert_eq!(input.category, LearningCategoryV1::PromptGuidance);
+    assert_eq!(input.summary, "summary");
+    assert_eq!(input.task_summary.as_deref(), Some("task"));
+    assert_eq!(input.profile.as_deref(), Some("profile"));
+    assert_eq!(input.guidance_text.as_deref(), Some("guidance"));
+    assert_eq!(input.check_text.as_deref(), Some("check"));
+    assert_eq!(input.tags, vec!["tag".to_string()]);
+    assert_eq!(input.evidence_specs, vec!["run_id:run_1".to_string()]);
+    assert_eq!(input.evidence_notes, vec!["note".to_string()]);
+    assert!(input.assist.is_none());
+}
+
 #[test]
 fn capture_writes_under_learning_entries() {
     let tmp = tempdir().expect("tempdir");
@@ -242,7 +270,7 @@ fn load_learning_entry_unknown_id_errors() {
 fn learn_show_redacts_and_bounds_output() {
     let mut e = sample_entry();
     e.summary = format!("token {} and {}", secret_ghp(), "x".repeat(20_000));
-    let out = render_learning_show_text(&e, true, true);
+    let out = render_learning_show_text(&e, true, true, None);
     assert!(out.contains(REDACTED_SECRET_TOKEN));
     assert!(!out.contains("ghp_"));
     assert!(out.len() <= LEARN_SHOW_MAX_BYTES + "\n...[truncated]".len());
@@ -787,6 +815,10 @@ fn promote_to_pack_creates_nested_pack_md_and_emits_event_with_pack_id() {
     let text = fs::read_to_string(&pack_md).expect("read pack");
     assert!(text.contains(LEARNED_GUIDANCE_MANAGED_SECTION_MARKER));
     assert!(text.contains(&format!("### LEARN-{}", e.id)));
+    assert_eq!(
+        out.context_pack_path.as_deref(),
+        Some(state_dir.join("packs/web/playwright.yaml").as_path())
+    );
 
     let lines = read_learning_events_lines(&state_dir);
     let v: serde_json::Value =
@@ -799,6 +831,39 @@ fn promote_to_pack_creates_nested_pack_md_and_emits_event_with_pack_id() {
     );
 }
 
+#[test]
+fn promote_to_pack_writes_context_pack_loadable_by_run() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    let mut e = sample_check_candidate_learning_entry();
+    for value in ["src/api/client.rs", "../outside.rs", "src/api/client.rs"] {
+        e.evidence.push(EvidenceRefV1 {
+            kind: EvidenceKindV1::ArtifactPath,
+            value: value.to_string(),
+            hash_hex: None,
+            note: None,
+        });
+    }
+    e.entry_hash_hex = compute_entry_hash_hex(&e).expect("hash");
+    write_entry(&state_dir, e.clone());
+
+    promote_learning_to_pack(&state_dir, &e.id, "web/playwright", false).expect("promote pack");
+    let first = fs::read_to_string(state_dir.join("packs/web/playwright.yaml")).expect("yaml");
+    promote_learning_to_pack(&state_dir, &e.id, "web/playwright", false).expect("re-promote");
+    let second = fs::read_to_string(state_dir.join("packs/web/playwright.yaml")).expect("yaml");
+    assert_eq!(first, second);
+
+    let pack = crate::context_packs::load_context_pack(tmp.path(), "web/playwright")
+        .expect("load context pack");
+    let paths = pack.spec.files.iter().map(|f| f.path()).collect::<Vec<_>>();
+    assert_eq!(paths, vec!["src/api/client.rs"]);
+    assert_eq!(
+        pack.spec.description.as_deref(),
+        Some("Ensure output includes success marker")
+    );
+    assert_eq!(pack.spec.learning_ids, vec![e.id.clone()]);
+}
+
 #[test]
 fn promote_to_pack_path_safety_only_expected_files_modified() {
     let tmp = tempdir().expect("tempdir");
@@ -814,6 +879,553 @@ fn promote_to_pack_path_safety_only_expected_files_modified() {
         format!("learn/entries/{}.json", e.id),
         "learn/events.jsonl".to_string(),
         "packs/web/playwright/PACK.md".to_string(),
+        "packs/web/playwright.yaml".to_string(),
     ]);
     assert_eq!(after, expected);
 }
+
+#[test]
+fn promote_records_target_on_entry_and_check_links_back() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    let mut e = sample_check_candidate_learning_entry();
+    e.entry_hash_hex = compute_entry_hash_hex(&e).expect("hash");
+    write_entry(&state_dir, e.clone());
+
+    let out = promote_learning_to_check(&state_dir, &e.id, "my_check", false).expect("promote");
+    let updated = load_learning_entry(&state_dir, &e.id).expect("load updated");
+    let promotion = updated.promotion.as_ref().expect("promotion record");
+    assert_eq!(promotion.target_kind, "check");
+    assert_eq!(promotion.target_path, ".localagent/checks/my_check.md");
+    assert_eq!(promotion.target_hash, out.target_file_sha256_hex);
+    assert!(!updated.promotion_stale);
+    assert_eq!(
+        promotion_target_status(&state_dir, &updated),
+        Some(PromotionTargetStatusV1::Ok)
+    );
+
+    let check = fs::read_to_string(&out.target_path).expect("read check");
+    assert!(check.contains(&format!("\nlearning_id: \"{}\"\n", e.id)));
+}
+
+#[test]
+fn sync_distinguishes_deleted_and_modified_targets() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    let mut check_entry = sample_check_candidate_learning_entry();
+    check_entry.entry_hash_hex = compute_entry_hash_hex(&check_entry).expect("hash");
+    write_entry(&state_dir, check_entry.clone());
+    let mut pack_entry = sample_entry();
+    pack_entry.id = "01JPACKENTRY".to_string();
+    pack_entry.entry_hash_hex = compute_entry_hash_hex(&pack_entry).expect("hash");
+    write_entry(&state_dir, pack_entry.clone());
+
+    let check = promote_learning_to_check(&state_dir, &check_entry.id, "my_check", false)
+        .expect("promote check");
+    let pack =
+        promote_learning_to_pack(&state_dir, &pack_entry.id, "web", false).expect("promote pack");
+
+    let clean = sync_learning_promotions(&state_dir).expect("sync clean");
+    assert_eq!(clean.items.len(), 2);
+    assert!(clean
+        .items
+        .iter()
+        .all(|i| i.status == PromotionTargetStatusV1::Ok && !i.stale_changed));
+
+    fs::remove_file(&check.target_path).expect("delete check");
+    let pack_text = fs::read_to_string(&pack.target_path).expect("read pack");
+    fs::write(
+        &pack.target_path,
+        pack_text.replace("Summary:\ns", "Summary:\nedited"),
+    )
+    .expect("edit pack");
+
+    let report = sync_learning_promotions(&state_dir).expect("sync");
+    let status_of = |id: &str| {
+        report
+            .items
+            .iter()
+            .find(|i| i.learning_id == id)
+            .map(|i| (i.status, i.stale_changed))
+    };
+    assert_eq!(
+        status_of(&check_entry.id),
+        Some((PromotionTargetStatusV1::Missing, true))
+    );
+    assert_eq!(
+        status_of(&pack_entry.id),
+        Some((PromotionTargetStatusV1::Modified, true))
+    );
+    assert!(
+        load_learning_entry(&state_dir, &check_entry.id)
+            .expect("load")
+            .promotion_stale
+    );
+
+    let again = sync_learning_promotions(&state_dir).expect("sync again");
+    assert!(again.items.iter().all(|i| !i.stale_changed));
+    assert_eq!(again.stale_count(), 2);
+}
+
+#[test]
+fn sync_ignores_unrelated_blocks_in_shared_managed_target() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    let mut a = sample_entry();
+    a.id = "01JAGENTSA".to_string();
+    a.entry_hash_hex = compute_entry_hash_hex(&a).expect("hash");
+    write_entry(&state_dir, a.clone());
+    let mut b = sample_entry();
+    b.id = "01JAGENTSB".to_string();
+    b.entry_hash_hex = compute_entry_hash_hex(&b).expect("hash");
+    write_entry(&state_dir, b.clone());
+
+    promote_learning_to_agents(&state_dir, &a.id, false).expect("promote a");
+    promote_learning_to_agents(&state_dir, &b.id, false).expect("promote b");
+
+    let report = sync_learning_promotions(&state_dir).expect("sync");
+    assert_eq!(report.stale_count(), 0);
+}
+
+#[test]
+fn list_flags_stale_promotions_and_show_renders_promotion_block() {
+    let mut e = sample_entry();
+    e.status = LearningStatusV1::Promoted;
+    e.promotion = Some(LearningPromotionV1 {
+        target_kind: "check".to_string(),
+        target_path: ".localagent/checks/x.md".to_string(),
+        target_hash: "abc".to_string(),
+    });
+    e.promotion_stale = true;
+
+    let table = render_learning_list_table(std::slice::from_ref(&e));
+    assert!(table.contains(&format!("{}  promoted(stale)  ", e.id)));
+
+    let show = render_learning_show_text(&e, false, false, Some(PromotionTargetStatusV1::Missing));
+    assert!(show.contains("promotion:\n  target_kind: check\n"));
+    assert!(show.contains("  target_status: missing\n"));
+    assert!(show.contains("  stale: true\n"));
+}
+
+#[test]
+fn archive_warns_when_promoted_target_still_exists() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    let mut e = sample_check_candidate_learning_entry();
+    e.entry_hash_hex = compute_entry_hash_hex(&e).expect("hash");
+    write_entry(&state_dir, e.clone());
+    promote_learning_to_check(&state_dir, &e.id, "my_check", false).expect("promote");
+
+    let out = archive_learning_entry(&state_dir, &e.id).expect("archive");
+    let target = out.live_promotion_target.as_ref().expect("live target");
+    assert_eq!(target.target_path, ".localagent/checks/my_check.md");
+    let msg = render_archive_confirmation(&out);
+    assert!(msg.contains("WARN: promoted check target .localagent/checks/my_check.md"));
+}
+
+fn search_entry(id: &str, summary: &str, tags: &[&str], guidance: Option<&str>) -> LearningEntryV1 {
+    let mut e = sample_entry();
+    e.id = id.to_string();
+    e.summary = summary.to_string();
+    e.proposed_memory.tags = tags.iter().map(|t| t.to_string()).collect();
+    e.proposed_memory.guidance_text = guidance.map(str::to_string);
+    e
+}
+
+fn search_ids(hits: &[LearningSearchHit]) -> Vec<&str> {
+    hits.iter().map(|h| h.entry.id.as_str()).collect()
+}
+
+#[test]
+fn search_ranks_tag_over_summary_over_body_and_ands_terms() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    write_entry(
+        &state_dir,
+        search_entry("01A", "unrelated", &[], Some("Retry the flaky build")),
+    );
+    write_entry(
+        &state_dir,
+        search_entry("01B", "Flaky build retries", &[], None),
+    );
+    write_entry(
+        &state_dir,
+        search_entry("01C", "unrelated", &["flaky"], None),
+    );
+    write_entry(&state_dir, search_entry("01D", "nothing here", &[], None));
+    let filters = LearningSearchFilters {
+        limit: 10,
+        ..LearningSearchFilters::default()
+    };
+
+    let hits = search_learning_entries(&state_dir, "FLAKY", &filters).expect("search");
+    assert_eq!(search_ids(&hits), vec!["01C", "01B", "01A"]);
+
+    let hits = search_learning_entries(&state_dir, "flaky retry", &filters).expect("search");
+    assert_eq!(search_ids(&hits), vec!["01A"]);
+
+    let mut evidence = sample_entry();
+    evidence.id = "01E".to_string();
+    evidence.evidence[0].value = "TOOL_TIMEOUT".to_string();
+    write_entry(&state_dir, evidence);
+    let hits = search_learning_entries(&state_dir, "tool_timeout", &filters).expect("search");
+    assert_eq!(search_ids(&hits), vec!["01E"]);
+}
+
+#[test]
+fn search_keeps_only_top_limit_and_applies_filters() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    for i in 0..20 {
+        let mut e = search_entry(&format!("01K{i:02}"), "build note", &[], None);
+        e.created_at = format!("2026-01-{:02}T00:00:00Z", i + 1);
+        if i % 2 == 0 {
+            e.proposed_memory.tags = vec!["CI".to_string()];
+        }
+        if i == 19 {
+            e.status = LearningStatusV1::Archived;
+        }
+        write_entry(&state_dir, e);
+    }
+
+    let hits = search_learning_entries(
+        &state_dir,
+        "build",
+        &LearningSearchFilters {
+            limit: 3,
+            ..LearningSearchFilters::default()
+        },
+    )
+    .expect("search");
+    assert_eq!(search_ids(&hits), vec!["01K18", "01K17", "01K16"]);
+
+    let hits = search_learning_entries(
+        &state_dir,
+        "",
+        &LearningSearchFilters {
+            tags: vec!["ci".to_string()],
+            since: Some("2026-01-15T00:00:00Z".to_string()),
+            include_archived: false,
+            limit: 50,
+        },
+    )
+    .expect("search");
+    assert_eq!(search_ids(&hits), vec!["01K18", "01K16", "01K14"]);
+
+    let err = search_learning_entries(
+        &state_dir,
+        "",
+        &LearningSearchFilters {
+            since: Some("yesterday".to_string()),
+            limit: 1,
+            ..LearningSearchFilters::default()
+        },
+    )
+    .expect_err("bad since");
+    assert!(err.to_string().contains("RFC3339"), "{err}");
+}
+
+#[test]
+fn search_does_not_modify_entry_files() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    write_entry(
+        &state_dir,
+        search_entry("01S", "secret search", &["x"], None),
+    );
+    let snapshot = || {
+        fs::read_dir(learning_entries_dir(&state_dir))
+            .expect("read_dir")
+            .map(|r| {
+                let path = r.expect("dirent").path();
+                let meta = fs::metadata(&path).expect("meta");
+                (
+                    path.file_name()
+                        .expect("name")
+                        .to_string_lossy()
+                        .to_string(),
+                    fs::read(&path).expect("read"),
+                    meta.modified().expect("mtime"),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let before = snapshot();
+    let hits = search_learning_entries(
+        &state_dir,
+        "search",
+        &LearningSearchFilters {
+            limit: 5,
+            ..LearningSearchFilters::default()
+        },
+    )
+    .expect("search");
+    let entries = hits.into_iter().map(|h| h.entry).collect::<Vec<_>>();
+    let _ = render_learning_list_table(&entries);
+    assert_eq!(before, snapshot());
+}
+
+fn write_failed_run(
+    state_dir: &Path,
+    run_id: &str,
+    exit_reason: &str,
+    error: Option<&str>,
+    failed_tool_results: &[(&str, &str, &str)],
+) {
+    let fixture = fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/run_records/v2_envelope.json"
+    ))
+    .expect("fixture");
+    let mut record: serde_json::Value = serde_json::from_str(&fixture).expect("parse");
+    record["metadata"]["run_id"] = serde_json::json!(run_id);
+    record["metadata"]["exit_reason"] = serde_json::json!(exit_reason);
+    record["error"] = serde_json::json!(error);
+    let mut transcript = Vec::new();
+    let mut tool_calls = Vec::new();
+    for (id, tool, content) in failed_tool_results {
+        tool_calls.push(serde_json::json!({"id": id, "name": tool, "arguments": {}}));
+        let envelope = serde_json::json!({
+            "schema_version": "openagent.tool_result.v1",
+            "tool_name": tool,
+            "tool_call_id": id,
+            "ok": false,
+            "content": content,
+        });
+        transcript.push(serde_json::json!({
+            "role": "tool",
+            "content": envelope.to_string(),
+            "tool_call_id": id,
+            "tool_name": tool,
+        }));
+    }
+    record["transcript"] = serde_json::json!(transcript);
+    record["tool_calls"] = serde_json::json!(tool_calls);
+    let path = state_dir.join("runs").join(format!("{run_id}.json"));
+    fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+    fs::write(&path, serde_json::to_string(&record).expect("ser")).expect("write");
+}
+
+#[test]
+fn draft_capture_from_run_maps_exit_reasons_and_failing_tool() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    write_failed_run(
+        &state_dir,
+        "run_planner",
+        "planner_error",
+        Some("E_PLAN_INVALID: bad step"),
+        &[("tc_1", "read_file", "missing required field: path")],
+    );
+    write_failed_run(
+        &state_dir,
+        "run_denied",
+        "denied",
+        Some("tool denied by policy"),
+        &[("tc_9", "shell", "denied by policy rule")],
+    );
+    write_failed_run(&state_dir, "run_budget", "budget_exceeded", None, &[]);
+    write_failed_run(
+        &state_dir,
+        "run_schema",
+        "max_steps",
+        None,
+        &[
+            ("tc_1", "read_file", "invalid tool arguments: path"),
+            ("tc_2", "read_file", "missing required field: path"),
+        ],
+    );
+    write_failed_run(&state_dir, "run_ok", "ok", None, &[]);
+
+    let planner = build_capture_input(
+        draft_capture_from_run(&state_dir, "run_planner").expect("planner draft"),
+    );
+    assert_eq!(planner.category, LearningCategoryV1::WorkflowHint);
+    assert_eq!(
+        planner.summary,
+        "Run exited with planner_error: E_PLAN_INVALID: bad step"
+    );
+    assert_eq!(planner.run_id.as_deref(), Some("run_planner"));
+    assert_eq!(
+        planner.evidence_specs,
+        vec![
+            "run_id:run_planner",
+            "exit_reason:planner_error",
+            "tool_call_id:tc_1",
+            "reason_code:E_SCHEMA",
+        ]
+    );
+
+    let denied = draft_capture_from_run(&state_dir, "run_denied").expect("denied draft");
+    assert_eq!(denied.category, LearningCategoryV1::WorkflowHint);
+    assert_eq!(
+        denied.evidence[2..],
+        ["tool_call_id:tc_9", "reason_code:E_POLICY"]
+    );
+
+    let budget = draft_capture_from_run(&state_dir, "run_budget").expect("budget draft");
+    assert_eq!(budget.category, LearningCategoryV1::WorkflowHint);
+    assert_eq!(budget.summary, "Run exited with budget_exceeded");
+    assert_eq!(
+        budget.evidence,
+        vec!["run_id:run_budget", "exit_reason:budget_exceeded"]
+    );
+
+    let schema = draft_capture_from_run(&state_dir, "run_schema").expect("schema draft");
+    assert_eq!(schema.category, LearningCategoryV1::PromptGuidance);
+    assert_eq!(
+        schema.evidence[2..],
+        ["tool_call_id:tc_2", "reason_code:E_SCHEMA"]
+    );
+
+    let err = draft_capture_from_run(&state_dir, "run_ok").expect_err("ok run");
+    assert!(err.to_string().contains("exited ok"), "{err}");
+    assert!(draft_capture_from_run(&state_dir, "missing").is_err());
+}
+
+#[test]
+fn draft_capture_from_run_writes_nothing_until_captured() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    write_failed_run(
+        &state_dir,
+        "run_denied",
+        "denied",
+        Some("tool denied by policy"),
+        &[("tc_9", "shell", "denied by policy rule")],
+    );
+
+    let input =
+        build_capture_input(draft_capture_from_run(&state_dir, "run_denied").expect("draft"));
+    let preview = render_capture_draft_preview(&input);
+    assert!(preview.contains("not saved"), "{preview}");
+    assert!(preview.contains("exit_reason:denied"), "{preview}");
+    assert!(!learning_entries_dir(&state_dir).exists());
+
+    let out = capture_learning_entry(&state_dir, input).expect("capture");
+    assert_eq!(out.entry.source.run_id.as_deref(), Some("run_denied"));
+    assert_eq!(out.entry.evidence.len(), 4);
+    assert_eq!(out.entry.evidence[3].kind, EvidenceKindV1::ReasonCode);
+    assert_eq!(
+        out.entry.entry_hash_hex,
+        compute_entry_hash_hex(&out.entry).expect("hash")
+    );
+}
+
+fn guidance_entry(
+    id: &str,
+    status: LearningStatusV1,
+    guidance: &str,
+    tags: &[&str],
+) -> LearningEntryV1 {
+    let mut e = search_entry(id, &format!("summary {id}"), tags, Some(guidance));
+    e.status = status;
+    e
+}
+
+fn injected_ids(guidance: &LearnedGuidance) -> Vec<&str> {
+    guidance
+        .injected
+        .iter()
+        .map(|r| r.learning_id.as_str())
+        .collect()
+}
+
+#[test]
+fn learned_guidance_selects_promoted_and_tagged_entries_for_profile() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    write_entry(
+        &state_dir,
+        guidance_entry("01G1", LearningStatusV1::Promoted, "Run cargo fmt", &[]),
+    );
+    write_entry(
+        &state_dir,
+        guidance_entry("01G2", LearningStatusV1::Captured, "Untagged draft", &[]),
+    );
+    write_entry(
+        &state_dir,
+        guidance_entry("01G3", LearningStatusV1::Captured, "Tagged draft", &["CI"]),
+    );
+    write_entry(
+        &state_dir,
+        guidance_entry("01G4", LearningStatusV1::Archived, "Old advice", &["ci"]),
+    );
+    let mut other_profile = guidance_entry("01G5", LearningStatusV1::Promoted, "Other", &[]);
+    other_profile.source.profile = Some("strict".to_string());
+    write_entry(&state_dir, other_profile);
+    let mut secret = guidance_entry("01G6", LearningStatusV1::Promoted, "Use the token", &[]);
+    secret.sensitivity_flags.contains_secrets_suspected = true;
+    write_entry(&state_dir, secret);
+
+    let tags = vec!["ci".to_string()];
+    let guidance = select_learned_guidance(
+        &state_dir,
+        &LearnedGuidanceSelector {
+            profile: Some("p"),
+            tags: &tags,
+            max_chars: DEFAULT_LEARNED_GUIDANCE_MAX_CHARS,
+        },
+    )
+    .expect("select");
+    assert_eq!(injected_ids(&guidance), vec!["01G1", "01G3"]);
+    let content = guidance
+        .message
+        .as_ref()
+        .and_then(|m| m.content.clone())
+        .expect("message");
+    assert_eq!(
+        content,
+        format!("{LEARNED_GUIDANCE_HEADER}\n- [01G1] Run cargo fmt\n- [01G3] Tagged draft")
+    );
+
+    let guidance = select_learned_guidance(
+        &state_dir,
+        &LearnedGuidanceSelector {
+            profile: None,
+            tags: &[],
+            max_chars: DEFAULT_LEARNED_GUIDANCE_MAX_CHARS,
+        },
+    )
+    .expect("select");
+    assert!(guidance.injected.is_empty());
+    assert!(guidance.message.is_none());
+}
+
+#[test]
+fn learned_guidance_dedupes_by_hash_and_caps_total_chars() {
+    let tmp = tempdir().expect("tempdir");
+    let state_dir = tmp.path().join(".localagent");
+    let mut first = guidance_entry("01H1", LearningStatusV1::Promoted, "aaaa", &[]);
+    first.proposed_memory.guidance_text = Some("a".repeat(30));
+    write_entry(&state_dir, first);
+    let mut dup_a = guidance_entry("01H2", LearningStatusV1::Promoted, "same", &[]);
+    dup_a.summary = "same".to_string();
+    let mut dup_b = dup_a.clone();
+    dup_b.id = "01H3".to_string();
+    write_entry(&state_dir, dup_a);
+    write_entry(&state_dir, dup_b);
+    write_entry(
+        &state_dir,
+        guidance_entry("01H4", LearningStatusV1::Promoted, "newest", &[]),
+    );
+
+    let guidance = select_learned_guidance(
+        &state_dir,
+        &LearnedGuidanceSelector {
+            profile: Some("p"),
+            tags: &[],
+            max_chars: 20,
+        },
+    )
+    .expect("select");
+    assert_eq!(injected_ids(&guidance), vec!["01H3", "01H4"]);
+    assert_eq!(
+        guidance.injected.iter().map(|r| r.chars).sum::<u64>(),
+        "same".len() as u64 + "newest".len() as u64
+    );
+    assert_ne!(
+        guidance.injected[0].entry_hash_hex,
+        guidance.injected[1].entry_hash_hex
+    );
+}
@@ -1,3 +1,22 @@
+//! The LocalAgent runtime as a library.
+//!
+//! Construct agents with [`agent::AgentBuilder`] rather than an
+//! [`agent::Agent`] struct literal; the builder supplies defaults for every
+//! field, so new fields do not break callers.
+//!
+//! ```
+//! use localagent::agent::Agent;
+//! use localagent::providers::mock::MockProvider;
+//!
+//! let agent = Agent::builder(MockProvider::new())
+//!     .model("mock-model")
+//!     .workdir(std::env::temp_dir())
+//!     .max_steps(8)
+//!     .build()
+//!     .expect("valid agent");
+//! assert_eq!(agent.max_steps, 8);
+//! ```
+
 #![deny(unreachable_pub)]
 
 pub mod agent;
@@ -12,13 +31,16 @@ pub(crate) mod agent_taint_helpers;
 pub(crate) mod agent_tool_exec;
 pub(crate) mod agent_utils;
 pub(crate) mod agent_worker_protocol;
+pub mod attribution;
 pub mod checks;
 #[allow(dead_code)]
 pub(crate) mod cli_args;
 #[allow(unused_imports)]
 pub(crate) use cli_args::{AgentMode, Cli, DockerNetwork, RunArgs, RunOutputMode};
 pub mod compaction;
+pub mod context_packs;
 pub mod diagnostics;
+pub mod env_probe;
 pub mod eval;
 pub mod events;
 pub mod gate;
@@ -36,11 +58,14 @@ pub mod mcp;
 pub mod operator_queue;
 #[allow(dead_code)]
 pub(crate) mod ops_helpers;
+pub mod otlp;
 pub mod packs;
 pub mod planner;
 #[allow(dead_code)]
 pub(crate) mod planner_runtime;
+pub mod progress_line;
 pub mod project_guidance;
+pub mod prompt_template;
 pub mod providers;
 #[allow(dead_code)]
 pub(crate) mod qualification;
@@ -12,6 +12,8 @@ mod agent_runtime;
 
 mod approvals_ops;
 
+mod attribution;
+
 mod chat_commands;
 
 mod chat_repl_runtime;
@@ -34,14 +36,20 @@ mod cli_dispatch_checks;
 mod cli_dispatch_eval_replay;
 mod cli_dispatch_learn;
 mod cli_dispatch_misc_ops;
+mod cli_dispatch_rollback;
+mod cli_dispatch_runs;
 
 mod compaction;
+mod context_packs;
 
 #[allow(dead_code)]
 mod diagnostics;
 
+mod env_probe;
+
 mod eval;
 
+#[allow(dead_code)]
 mod events;
 
 mod gate;
@@ -68,9 +76,12 @@ mod planner;
 mod planner_runtime;
 
 mod operator_queue;
+mod otlp;
 mod packs;
 
+mod progress_line;
 mod project_guidance;
+mod prompt_template;
 
 mod provider_runtime;
 
@@ -131,7 +142,9 @@ mod types;
 
 pub(crate) use agent::AgentExitReason;
 
-pub(crate) use agent_runtime::{run_agent, run_agent_with_ui, RunExecutionResult};
+pub(crate) use agent_runtime::{
+    run_agent, run_agent_with_ui, AgentUiRunRequest, RunExecutionResult,
+};
 
 pub(crate) use cli_args::*;
 
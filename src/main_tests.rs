@@ -64,6 +64,7 @@ impl ModelProvider for PlannerTestProvider {
             tool_calls: Vec::new(),
 
             usage: None,
+            served_model: None,
         })
     }
 
@@ -134,6 +135,7 @@ impl ModelProvider for QualificationTestProvider {
             tool_calls,
 
             usage: None,
+            served_model: None,
         })
     }
 
@@ -302,6 +304,38 @@ fn bare_startup_bootstrap_detection_matches_no_flags_invocation() {
     ));
 }
 
+#[test]
+fn provider_replay_spec_expands_to_replay_provider_flag() {
+    for spelling in [
+        vec!["--provider", "replay:run-1"],
+        vec!["--provider=replay:run-1"],
+    ] {
+        let mut argv = vec!["localagent"];
+        argv.extend(spelling);
+        argv.extend(["--replay-match", "lenient", "--prompt", "hi"]);
+        let argv = crate::cli_dispatch::expand_replay_provider_argv(
+            argv.into_iter().map(std::ffi::OsString::from).collect(),
+        );
+        let cli = Cli::parse_from(argv);
+        assert_eq!(cli.run.provider, None);
+        assert_eq!(cli.run.replay_provider.as_deref(), Some("run-1"));
+        assert_eq!(
+            cli.run.replay_match,
+            crate::providers::recording::ReplayMatching::Lenient
+        );
+    }
+    let plain = crate::cli_dispatch::expand_replay_provider_argv(
+        ["localagent", "--provider", "mock"]
+            .into_iter()
+            .map(std::ffi::OsString::from)
+            .collect(),
+    );
+    assert_eq!(
+        Cli::parse_from(plain).run.provider,
+        Some(ProviderKind::Mock)
+    );
+}
+
 #[test]
 fn bare_localagent_invocation_defaults_to_sessionless_and_ephemeral_state() {
     let mut cli = Cli::parse_from([
@@ -448,6 +482,7 @@ fn probe_parser_accepts_inline_json_tool_call() {
         tool_calls: Vec::new(),
 
         usage: None,
+        served_model: None,
     };
 
     let tc = super::qualification::probe_response_to_tool_call(&resp).expect("tool call");
@@ -478,6 +513,7 @@ fn probe_parser_accepts_fenced_json_tool_call() {
         tool_calls: Vec::new(),
 
         usage: None,
+        served_model: None,
     };
 
     let tc = super::qualification::probe_response_to_tool_call(&resp).expect("tool call");
@@ -508,6 +544,7 @@ fn probe_parser_accepts_named_arguments_textual_tool_call() {
         tool_calls: Vec::new(),
 
         usage: None,
+        served_model: None,
     };
 
     let tc = super::qualification::probe_response_to_tool_call(&resp).expect("tool call");
@@ -538,6 +575,7 @@ fn probe_parser_rejects_ambiguous_named_arguments_textual_tool_call() {
         tool_calls: Vec::new(),
 
         usage: None,
+        served_model: None,
     };
 
     assert!(super::qualification::probe_response_to_tool_call(&resp).is_none());
@@ -933,6 +971,7 @@ async fn qualification_accepts_named_arguments_textual_probe_fallback() {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             })
         }
 
@@ -988,6 +1027,7 @@ async fn qualification_rejects_malformed_named_arguments_textual_probe() {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             })
         }
 
@@ -1043,6 +1083,7 @@ async fn qualification_keeps_success_when_later_attempts_would_be_ambiguous() {
                     arguments: serde_json::json!({"path":"."}),
                 }],
                 usage: None,
+                served_model: None,
             },
             GenerateResponse {
                 assistant: Message {
@@ -1057,6 +1098,7 @@ async fn qualification_keeps_success_when_later_attempts_would_be_ambiguous() {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             },
         ],
     };
@@ -1094,6 +1136,7 @@ async fn qualification_accepts_later_success_after_prior_soft_fail() {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             },
             GenerateResponse {
                 assistant: Message {
@@ -1109,6 +1152,7 @@ async fn qualification_accepts_later_success_after_prior_soft_fail() {
                     arguments: serde_json::json!({"path":"."}),
                 }],
                 usage: None,
+                served_model: None,
             },
         ],
     };
@@ -1399,6 +1443,46 @@ fn parser_derived_eval_defaults_match_cli_surface() {
     assert_eq!(eval.run.http_stream_idle_timeout_ms, 30_000);
 }
 
+#[test]
+fn exact_model_verification_is_strict_by_default_only_for_check_and_eval() {
+    let cli = Cli::parse_from(["localagent"]);
+    assert!(!cli.run.require_exact_model);
+    let cli = Cli::parse_from(["localagent", "--require-exact-model"]);
+    assert!(cli.run.require_exact_model);
+
+    let cli = Cli::parse_from(["localagent", "eval"]);
+    let Some(Commands::Eval(eval)) = cli.command else {
+        panic!("expected eval command");
+    };
+    assert!(!eval.run.allow_model_mismatch);
+    let cli = Cli::parse_from(["localagent", "eval", "--allow-model-mismatch"]);
+    let Some(Commands::Eval(eval)) = cli.command else {
+        panic!("expected eval command");
+    };
+    assert!(eval.run.allow_model_mismatch);
+
+    for (argv, expected) in [
+        (vec!["localagent", "check", "run"], false),
+        (
+            vec!["localagent", "check", "run", "--allow-model-mismatch"],
+            true,
+        ),
+    ] {
+        let cli = Cli::parse_from(argv);
+        let Some(Commands::Check(check)) = cli.command else {
+            panic!("expected check command");
+        };
+        let crate::CheckSubcommand::Run {
+            allow_model_mismatch,
+            ..
+        } = check.command
+        else {
+            panic!("expected check run");
+        };
+        assert_eq!(allow_model_mismatch, expected);
+    }
+}
+
 #[test]
 fn canonical_cli_reference_run_example_parses_with_global_flags_before_run() {
     let cli = Cli::parse_from([
@@ -1456,6 +1540,36 @@ fn serve_command_parses_bind_and_port() {
     assert_eq!(args.port, 8080);
 }
 
+#[test]
+fn hooks_validate_parses_dry_fire_stage_and_hook() {
+    let cli = Cli::parse_from([
+        "localagent",
+        "hooks",
+        "validate",
+        "--config",
+        "hooks.yaml",
+        "--dry-fire",
+        "tool_result",
+        "--hook",
+        "redact",
+    ]);
+    let Some(Commands::Hooks(args)) = cli.command else {
+        panic!("expected hooks command");
+    };
+    let crate::HooksSubcommand::Validate {
+        config,
+        dry_fire,
+        hook,
+    } = args.command
+    else {
+        panic!("expected hooks validate");
+    };
+    assert_eq!(config, Some(PathBuf::from("hooks.yaml")));
+    assert_eq!(dry_fire, Some(crate::hooks::config::HookStage::ToolResult));
+    assert_eq!(hook.as_deref(), Some("redact"));
+    assert!(Cli::try_parse_from(["localagent", "hooks", "validate", "--hook", "redact"]).is_err());
+}
+
 #[test]
 fn lsp_provider_command_parses_for_typescript() {
     let cli = super::Cli::parse_from([
@@ -1612,11 +1726,64 @@ fn run_cli_config_persists_agent_mode() {
         repo_map: None,
         lsp_context: None,
         activated_packs: &[],
+        context_pack: None,
+        learned_guidance: None,
+        prompt_template: None,
     });
     assert_eq!(cli.agent_mode, "build");
     assert_eq!(cli.output_mode, "human");
 }
 
+#[test]
+fn run_cli_config_records_read_allowlist() {
+    let mut args = default_run_args();
+    assert!(args.allow_read_path.is_empty());
+    args.allow_read_path = vec!["docs/**".to_string(), "src/module_x/**".to_string()];
+    let resolved = crate::session::RunSettingResolution {
+        max_context_chars: 0,
+        compaction_mode: crate::compaction::CompactionMode::Off,
+        compaction_keep_last: 20,
+        tool_result_persist: crate::compaction::ToolResultPersist::Digest,
+        tool_args_strict: crate::tools::ToolArgsStrict::On,
+        caps_mode: crate::session::CapsMode::Off,
+        hooks_mode: crate::hooks::config::HooksMode::Off,
+        sources: std::collections::BTreeMap::new(),
+    };
+    let cli = crate::runtime_paths::build_run_cli_config(crate::runtime_paths::RunCliConfigInput {
+        provider_kind: crate::ProviderKind::Mock,
+        base_url: "http://localhost:1",
+        model: "mock-model",
+        args: &args,
+        resolved_settings: &resolved,
+        hooks_config_path: std::path::Path::new("hooks.yaml"),
+        mcp_config_path: std::path::Path::new("mcp_servers.json"),
+        tool_catalog: Vec::new(),
+        mcp_tool_snapshot: Vec::new(),
+        mcp_tool_catalog_hash_hex: None,
+        policy_version: None,
+        includes_resolved: Vec::new(),
+        mcp_allowlist: None,
+        mode: crate::planner::RunMode::Single,
+        planner_model: None,
+        worker_model: None,
+        planner_max_steps: None,
+        planner_output: None,
+        planner_strict: None,
+        enforce_plan_tools: None,
+        instructions: &crate::instructions::InstructionResolution::empty(),
+        project_guidance: None,
+        repo_map: None,
+        lsp_context: None,
+        activated_packs: &[],
+        context_pack: None,
+        learned_guidance: None,
+        prompt_template: None,
+    });
+    assert_eq!(cli.read_allowlist, vec!["docs/**", "src/module_x/**"]);
+    let value = serde_json::to_value(&cli).expect("serialize");
+    assert_eq!(value["read_allowlist"][1], "src/module_x/**");
+}
+
 #[test]
 fn run_cli_config_includes_lsp_context_metadata_when_present() {
     let args = default_run_args();
@@ -1717,6 +1884,9 @@ fn run_cli_config_includes_lsp_context_metadata_when_present() {
         repo_map: None,
         lsp_context: Some(&lsp_context),
         activated_packs: &[],
+        context_pack: None,
+        learned_guidance: None,
+        prompt_template: None,
     });
     assert_eq!(cli.lsp_context_provider.as_deref(), Some("mock_lsp"));
     assert_eq!(
@@ -1749,13 +1919,23 @@ fn default_run_args() -> super::RunArgs {
         api_key: None,
 
         prompt: None,
+        template: None,
+        template_vars: Vec::new(),
+        allow_env_in_template: false,
 
         max_steps: 20,
+        max_empty_responses: 2,
+        final_output_schema: None,
+        require_exact_model: false,
 
         max_wall_time_ms: 0,
 
+        deadline_ms: 0,
+
         max_total_tool_calls: 0,
 
+        max_tool_calls: Vec::new(),
+
         max_mcp_calls: 0,
 
         max_filesystem_read_calls: 0,
@@ -1768,24 +1948,47 @@ fn default_run_args() -> super::RunArgs {
 
         max_browser_calls: 0,
 
+        max_compaction_summaries: 4,
+
         tool_exec_timeout_ms: 30_000,
 
+        stream_tool_output: false,
+
+        parallel_readonly_tools: false,
+        enable_subtasks: false,
+        approval_diff_max_lines: crate::gate::DEFAULT_APPROVAL_DIFF_MAX_LINES,
+        dry_run_writes: false,
+        checkpoint_writes: false,
+
         post_write_verify_timeout_ms: 5_000,
 
         workdir: std::path::PathBuf::from("."),
 
         state_dir: None,
+        wait_lock: crate::store::DEFAULT_STATE_LOCK_WAIT_SECS,
 
         mcp: Vec::new(),
         packs: Vec::new(),
+        context_pack: None,
+        use_learnings: false,
+        learning_tags: Vec::new(),
 
         mcp_config: None,
+        mcp_root: Vec::new(),
+        mcp_strict_metadata: false,
+        mcp_strict_names: false,
+        mcp_injection_phrase: Vec::new(),
+        mcp_reconnect_attempts: crate::mcp::health::DEFAULT_MCP_RECONNECT_ATTEMPTS,
+        max_run_artifact_bytes: crate::store::DEFAULT_MAX_RUN_ARTIFACT_BYTES,
 
         allow_shell: false,
 
         allow_shell_in_workdir: false,
 
         allow_write: false,
+        probe_environment: false,
+        replay_queue_from: None,
+        no_attribution: false,
 
         enable_write_tools: false,
 
@@ -1800,6 +2003,16 @@ fn default_run_args() -> super::RunArgs {
         docker_network: DockerNetwork::None,
 
         docker_user: None,
+        docker_persistent: false,
+        docker_memory: None,
+        docker_cpus: None,
+        docker_pids_limit: None,
+        docker_readonly_workdir: false,
+        ssh_host: None,
+        ssh_user: None,
+        ssh_port: None,
+        ssh_identity_file: None,
+        ssh_workdir: None,
 
         max_tool_output_bytes: 200_000,
 
@@ -1810,8 +2023,10 @@ fn default_run_args() -> super::RunArgs {
         approval_mode: crate::gate::ApprovalMode::Interrupt,
 
         auto_approve_scope: crate::gate::AutoApproveScope::Run,
+        workspace_approval_ttl_hours: crate::gate::DEFAULT_WORKSPACE_APPROVAL_TTL_HOURS,
 
         approval_key: crate::gate::ApprovalKeyVersion::V1,
+        skip_unevaluated_gate_snapshots: false,
 
         unsafe_mode: false,
 
@@ -1837,9 +2052,13 @@ fn default_run_args() -> super::RunArgs {
 
         max_context_chars: 0,
 
+        max_context_tokens: None,
+
         use_repomap: false,
 
         repomap_max_bytes: 32 * 1024,
+        repomap_refresh: false,
+        repomap_rank: false,
         lsp_provider: None,
         lsp_command: None,
         reliability_profile: None,
@@ -1859,6 +2078,9 @@ fn default_run_args() -> super::RunArgs {
         hooks_timeout_ms: 2000,
 
         hooks_max_stdout_bytes: 200_000,
+        hooks_max_invocations: 0,
+        hooks_max_cumulative_ms: 0,
+        hooks_budget_strict: false,
 
         tool_args_strict: crate::tools::ToolArgsStrict::On,
 
@@ -1891,10 +2113,17 @@ fn default_run_args() -> super::RunArgs {
         caps: crate::session::CapsMode::Off,
 
         stream: false,
+        no_progress: false,
+        allow_read_path: Vec::new(),
+        allow_shell_cmd: Vec::new(),
 
         output: crate::RunOutputMode::Human,
 
         events: None,
+        otlp_endpoint: None,
+        otlp_headers: Vec::new(),
+        spans_file: None,
+        otlp_flush_interval_ms: None,
 
         http_max_retries: 2,
 
@@ -1907,12 +2136,22 @@ fn default_run_args() -> super::RunArgs {
         http_max_response_bytes: 10_000_000,
 
         http_max_line_bytes: 200_000,
+        provider_min_interval_ms: 0,
+        provider_concurrency: 0,
+        provider_retry_max_attempts: 1,
+        provider_retry_base_delay_ms: 500,
+        provider_retry_max_delay_ms: 30_000,
+        provider_retry_on: crate::providers::retry::RetryOn::ALL.to_vec(),
+        record_provider: false,
+        replay_provider: None,
+        replay_match: crate::providers::recording::ReplayMatching::Strict,
 
         tui: false,
 
         tui_refresh_ms: 50,
 
         tui_max_log_lines: 200,
+        tui_max_line_chars: 400,
 
         mode: crate::planner::RunMode::Single,
 
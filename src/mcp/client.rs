@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 use std::process::Stdio;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::{anyhow, Context};
+use anyhow::anyhow;
 use serde_json::{json, Value};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, Command};
@@ -17,6 +17,24 @@ pub struct McpClient {
     stdin: Arc<Mutex<ChildStdin>>,
     pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
     next_id: AtomicU64,
+    closed: Arc<AtomicBool>,
+}
+
+/// The connection to the server broke: its stdout closed or a request could
+/// not be written. Timeouts and JSON-RPC errors are not transport errors.
+#[derive(Debug)]
+pub struct McpTransportError(pub String);
+
+impl std::fmt::Display for McpTransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for McpTransportError {}
+
+pub fn is_transport_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<McpTransportError>().is_some()
 }
 
 impl McpClient {
@@ -38,6 +56,8 @@ impl McpClient {
         let pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>> =
             Arc::new(Mutex::new(HashMap::new()));
         let pending_reader = Arc::clone(&pending);
+        let closed = Arc::new(AtomicBool::new(false));
+        let closed_reader = Arc::clone(&closed);
 
         tokio::spawn(async move {
             let mut reader = BufReader::new(stdout).lines();
@@ -63,6 +83,10 @@ impl McpClient {
                     Err(_) => break,
                 }
             }
+            // Dropping the senders fails waiting calls now instead of at
+            // their timeout.
+            closed_reader.store(true, Ordering::SeqCst);
+            pending_reader.lock().await.clear();
         });
         tokio::spawn(async move {
             let mut reader = BufReader::new(stderr).lines();
@@ -74,6 +98,7 @@ impl McpClient {
             stdin: Arc::new(Mutex::new(stdin)),
             pending,
             next_id: AtomicU64::new(1),
+            closed,
         })
     }
 
@@ -112,6 +137,12 @@ impl McpClient {
     }
 
     async fn call(&self, method: &str, params: Value, timeout: Duration) -> anyhow::Result<Value> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(McpTransportError(format!(
+                "MCP server connection closed before method '{method}'"
+            ))
+            .into());
+        }
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
         let req = json!({
             "jsonrpc":"2.0",
@@ -130,8 +161,11 @@ impl McpClient {
             stdin
                 .write_all(format!("{line}\n").as_bytes())
                 .await
-                .context("failed to write MCP request")?;
-            stdin.flush().await.context("failed to flush MCP request")?;
+                .map_err(|e| McpTransportError(format!("failed to write MCP request: {e}")))?;
+            stdin
+                .flush()
+                .await
+                .map_err(|e| McpTransportError(format!("failed to flush MCP request: {e}")))?;
         }
 
         let msg = match tokio::time::timeout(timeout, rx).await {
@@ -139,7 +173,10 @@ impl McpClient {
             Ok(Err(_)) => {
                 let mut map = self.pending.lock().await;
                 map.remove(&id);
-                return Err(anyhow!("MCP response channel closed for method '{method}'"));
+                return Err(McpTransportError(format!(
+                    "MCP response channel closed for method '{method}'"
+                ))
+                .into());
             }
             Err(_) => {
                 let mut map = self.pending.lock().await;
@@ -0,0 +1,235 @@
+//! Connection health for MCP servers.
+//!
+//! A server whose call failed at the transport level (process gone, pipe
+//! closed) is marked degraded. Calls to it then fail fast until the backoff
+//! for the next reconnect attempt has passed; attempts stop after
+//! `max_attempts`, leaving the server degraded for the rest of the run.
+
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_MCP_RECONNECT_ATTEMPTS: u32 = 3;
+pub const DEFAULT_MCP_RECONNECT_BACKOFF_MS: u64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct McpReconnectPolicy {
+    /// Reconnect attempts per degradation window (`0` = never reconnect).
+    pub max_attempts: u32,
+    /// Wait before the first attempt; doubles after each failed attempt.
+    pub base_backoff_ms: u64,
+}
+
+impl Default for McpReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MCP_RECONNECT_ATTEMPTS,
+            base_backoff_ms: DEFAULT_MCP_RECONNECT_BACKOFF_MS,
+        }
+    }
+}
+
+impl McpReconnectPolicy {
+    /// Wait after `failed_attempts` failed reconnects.
+    pub fn backoff_ms(&self, failed_attempts: u32) -> u64 {
+        self.base_backoff_ms
+            .saturating_mul(1u64 << failed_attempts.min(16))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) enum McpServerHealth {
+    #[default]
+    Healthy,
+    Degraded {
+        since: Instant,
+        reason: String,
+        attempts: u32,
+        next_attempt_at: Instant,
+    },
+}
+
+/// What a call should do given the server's health.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum McpHealthCheck {
+    Proceed,
+    Reconnect,
+    FailFast {
+        reason: String,
+        attempts: u32,
+        /// `None` once every reconnect attempt is spent.
+        retry_after_ms: Option<u64>,
+    },
+}
+
+impl McpServerHealth {
+    pub(crate) fn is_degraded(&self) -> bool {
+        matches!(self, Self::Degraded { .. })
+    }
+
+    pub(crate) fn check(&self, policy: &McpReconnectPolicy, now: Instant) -> McpHealthCheck {
+        match self {
+            Self::Healthy => McpHealthCheck::Proceed,
+            Self::Degraded {
+                reason,
+                attempts,
+                next_attempt_at,
+                ..
+            } => {
+                if *attempts >= policy.max_attempts {
+                    McpHealthCheck::FailFast {
+                        reason: reason.clone(),
+                        attempts: *attempts,
+                        retry_after_ms: None,
+                    }
+                } else if now >= *next_attempt_at {
+                    McpHealthCheck::Reconnect
+                } else {
+                    McpHealthCheck::FailFast {
+                        reason: reason.clone(),
+                        attempts: *attempts,
+                        retry_after_ms: Some(
+                            next_attempt_at.saturating_duration_since(now).as_millis() as u64,
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Marks the server degraded. Returns false when it already was.
+    pub(crate) fn mark_degraded(
+        &mut self,
+        reason: String,
+        policy: &McpReconnectPolicy,
+        now: Instant,
+    ) -> bool {
+        if self.is_degraded() {
+            return false;
+        }
+        *self = Self::Degraded {
+            since: now,
+            reason,
+            attempts: 0,
+            next_attempt_at: now + Duration::from_millis(policy.backoff_ms(0)),
+        };
+        true
+    }
+
+    /// Records a failed reconnect and returns the attempt count so far.
+    pub(crate) fn reconnect_failed(
+        &mut self,
+        failure: String,
+        policy: &McpReconnectPolicy,
+        now: Instant,
+    ) -> u32 {
+        let Self::Degraded {
+            reason,
+            attempts,
+            next_attempt_at,
+            ..
+        } = self
+        else {
+            return 0;
+        };
+        *attempts = attempts.saturating_add(1);
+        *reason = failure;
+        *next_attempt_at = now + Duration::from_millis(policy.backoff_ms(*attempts));
+        *attempts
+    }
+
+    /// Marks the server healthy again and returns how long it was degraded
+    /// and how many attempts the reconnect took.
+    pub(crate) fn reconnected(&mut self, now: Instant) -> Option<(u64, u32)> {
+        let Self::Degraded {
+            since, attempts, ..
+        } = std::mem::take(self)
+        else {
+            return None;
+        };
+        Some((
+            now.saturating_duration_since(since).as_millis() as u64,
+            attempts.saturating_add(1),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{McpHealthCheck, McpReconnectPolicy, McpServerHealth};
+
+    #[test]
+    fn degraded_server_fails_fast_until_backoff_then_reconnects() {
+        let policy = McpReconnectPolicy {
+            max_attempts: 2,
+            base_backoff_ms: 100,
+        };
+        let t0 = Instant::now();
+        let mut health = McpServerHealth::default();
+        assert_eq!(health.check(&policy, t0), McpHealthCheck::Proceed);
+        assert!(health.mark_degraded("pipe closed".to_string(), &policy, t0));
+        assert!(!health.mark_degraded("again".to_string(), &policy, t0));
+        assert_eq!(
+            health.check(&policy, t0 + Duration::from_millis(40)),
+            McpHealthCheck::FailFast {
+                reason: "pipe closed".to_string(),
+                attempts: 0,
+                retry_after_ms: Some(60),
+            }
+        );
+        let t1 = t0 + Duration::from_millis(100);
+        assert_eq!(health.check(&policy, t1), McpHealthCheck::Reconnect);
+
+        // The second wait doubles.
+        assert_eq!(
+            health.reconnect_failed("spawn failed".to_string(), &policy, t1),
+            1
+        );
+        assert!(matches!(
+            health.check(&policy, t1 + Duration::from_millis(150)),
+            McpHealthCheck::FailFast { .. }
+        ));
+        let t2 = t1 + Duration::from_millis(200);
+        assert_eq!(health.check(&policy, t2), McpHealthCheck::Reconnect);
+        assert_eq!(
+            health.reconnected(t2 + Duration::from_millis(5)),
+            Some((305, 2))
+        );
+        assert_eq!(health.check(&policy, t2), McpHealthCheck::Proceed);
+    }
+
+    #[test]
+    fn reconnect_attempts_are_bounded() {
+        let policy = McpReconnectPolicy {
+            max_attempts: 1,
+            base_backoff_ms: 0,
+        };
+        let now = Instant::now();
+        let mut health = McpServerHealth::default();
+        health.mark_degraded("pipe closed".to_string(), &policy, now);
+        assert_eq!(health.check(&policy, now), McpHealthCheck::Reconnect);
+        health.reconnect_failed("spawn failed".to_string(), &policy, now);
+        assert_eq!(
+            health.check(&policy, now + Duration::from_secs(60)),
+            McpHealthCheck::FailFast {
+                reason: "spawn failed".to_string(),
+                attempts: 1,
+                retry_after_ms: None,
+            }
+        );
+
+        let never = McpReconnectPolicy {
+            max_attempts: 0,
+            ..policy
+        };
+        let mut health = McpServerHealth::default();
+        health.mark_degraded("pipe closed".to_string(), &never, now);
+        assert!(matches!(
+            health.check(&never, now),
+            McpHealthCheck::FailFast {
+                retry_after_ms: None,
+                ..
+            }
+        ));
+    }
+}
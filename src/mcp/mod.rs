@@ -1,3 +1,7 @@
 pub mod client;
+pub mod health;
 pub mod registry;
+pub mod roots;
+pub mod sanitize;
+pub mod spill;
 pub mod types;
@@ -1,6 +1,7 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::Duration;
 use std::time::Instant;
 
@@ -8,23 +9,87 @@ use anyhow::{anyhow, Context};
 use serde::Serialize;
 use serde_json::json;
 
-use crate::mcp::client::McpClient;
-use crate::mcp::types::{McpConfigFile, McpServerConfig};
-use crate::store::{ensure_dir, mcp_tool_snapshot_hash_hex, sha256_hex, McpToolSnapshotEntry};
+use crate::mcp::client::{is_transport_error, McpClient};
+use crate::mcp::health::{McpHealthCheck, McpReconnectPolicy, McpServerHealth};
+use crate::mcp::sanitize::{
+    mcp_tool_name_conflict, sanitize_mcp_description, McpMetadataPolicy,
+    McpMetadataSanitizedRecord, McpToolRejectedRecord,
+};
+use crate::mcp::spill::spill_mcp_result;
+use crate::mcp::types::{McpConfigFile, McpServerConfig, McpTool};
+use crate::store::{
+    ensure_dir, hash_tool_schema, mcp_tool_snapshot_hash_hex, sha256_hex, McpToolSnapshotEntry,
+    RunArtifactStore,
+};
 use crate::tools::{
-    envelope_to_message, to_tool_result_envelope, tool_side_effects, validate_schema_args,
-    ToolArgsStrict, ToolResultContentRef, ToolResultMeta,
+    envelope_to_message, to_tool_result_envelope, to_tool_result_envelope_with_error,
+    tool_side_effects, validate_schema_args, ToolArgsStrict, ToolErrorCode, ToolErrorDetail,
+    ToolResultContentRef, ToolResultEnvelope, ToolResultMeta,
 };
 use crate::types::{Message, ToolCall, ToolDef};
 
 pub struct McpRegistry {
-    clients: BTreeMap<String, McpClient>,
+    clients: BTreeMap<String, McpServerConnection>,
     tool_map: BTreeMap<String, (String, String)>,
     tool_schema_map: BTreeMap<String, Option<serde_json::Value>>,
     tool_doc_meta_map: BTreeMap<String, McpToolDocMeta>,
+    /// Tools whose server lists them with `readOnlyHint`.
+    read_only_tools: BTreeSet<String>,
     tool_defs: Vec<ToolDef>,
     timeout: Duration,
     mcp_spool_dir: PathBuf,
+    metadata_policy: McpMetadataPolicy,
+    metadata_sanitized: Vec<McpMetadataSanitizedRecord>,
+    tool_rejected: Vec<McpToolRejectedRecord>,
+    reconnect_policy: McpReconnectPolicy,
+}
+
+/// A running server, how to start it again, and its connection health.
+struct McpServerConnection {
+    config: McpServerConfig,
+    client: tokio::sync::RwLock<Arc<McpClient>>,
+    health: Mutex<McpServerHealth>,
+    /// Input schema hash per tool, as the server last listed it.
+    schema_hashes: Mutex<BTreeMap<String, String>>,
+}
+
+impl McpServerConnection {
+    fn new(config: McpServerConfig, client: McpClient, tools: &[McpTool]) -> Self {
+        let conn = Self {
+            config,
+            client: tokio::sync::RwLock::new(Arc::new(client)),
+            health: Mutex::new(McpServerHealth::default()),
+            schema_hashes: Mutex::new(BTreeMap::new()),
+        };
+        conn.refresh_schema_hashes(tools);
+        conn
+    }
+
+    fn refresh_schema_hashes(&self, tools: &[McpTool]) {
+        *self
+            .schema_hashes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = tools
+            .iter()
+            .map(|t| (t.name.clone(), hash_tool_schema(&listed_parameters(t))))
+            .collect();
+    }
+
+    fn schema_hash(&self, tool: &str) -> Option<String> {
+        self.schema_hashes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(tool)
+            .cloned()
+    }
+
+    async fn client(&self) -> Arc<McpClient> {
+        Arc::clone(&*self.client.read().await)
+    }
+
+    fn health(&self) -> MutexGuard<'_, McpServerHealth> {
+        self.health.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -39,6 +104,43 @@ pub struct McpCallMeta {
     pub progress_ticks: u32,
     pub elapsed_ms: u64,
     pub cancelled: bool,
+    /// Set when the result was over the output limit and was truncated or
+    /// rejected.
+    pub output_limit: Option<McpOutputLimitRecord>,
+    /// The server became degraded during this call, or a reconnect attempt
+    /// made by it failed.
+    pub degraded: Option<McpServerDegradedRecord>,
+    /// A degraded server was reconnected before this call ran.
+    pub reconnected: Option<McpServerReconnectedRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpServerDegradedRecord {
+    pub server: String,
+    pub reason: String,
+    pub reconnect_attempts: u32,
+    pub max_reconnect_attempts: u32,
+    /// `None` once every reconnect attempt is spent.
+    pub retry_after_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpServerReconnectedRecord {
+    pub server: String,
+    pub degraded_ms: u64,
+    pub reconnect_attempts: u32,
+}
+
+/// Evidence for a result cut down or refused at the registry boundary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpOutputLimitRecord {
+    pub server: String,
+    pub tool: String,
+    pub original_bytes: u64,
+    pub kept_bytes: u64,
+    pub limit_bytes: u64,
+    /// The result was over the hard cap and dropped entirely.
+    pub rejected: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -52,13 +154,37 @@ impl McpRegistry {
         path: &Path,
         enabled: &[String],
         timeout: Duration,
+    ) -> anyhow::Result<Self> {
+        Self::from_config_path_with_metadata_policy(
+            path,
+            enabled,
+            timeout,
+            McpMetadataPolicy::default(),
+        )
+        .await
+    }
+
+    /// Like [`Self::from_config_path`], sanitizing server-provided tool
+    /// descriptions under `metadata_policy` before they are stored or hashed.
+    pub async fn from_config_path_with_metadata_policy(
+        path: &Path,
+        enabled: &[String],
+        timeout: Duration,
+        metadata_policy: McpMetadataPolicy,
     ) -> anyhow::Result<Self> {
         let config = load_or_create_config(path)?;
         let mut clients = BTreeMap::new();
         let mut tool_map = BTreeMap::new();
         let mut tool_schema_map = BTreeMap::new();
         let mut tool_doc_meta_map = BTreeMap::new();
+        let mut read_only_tools = BTreeSet::new();
         let mut tool_defs = Vec::new();
+        let mut metadata_sanitized = Vec::new();
+        let mut tool_rejected = Vec::new();
+        let builtin_names = crate::tools::builtin_tools_enabled(true, true)
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>();
         let mcp_spool_dir = path
             .parent()
             .unwrap_or_else(|| Path::new("."))
@@ -74,11 +200,45 @@ impl McpRegistry {
             client.initialize(Duration::from_secs(5)).await?;
             let tools = client.tools_list(timeout).await?;
             for tool in &tools {
+                let sanitized = sanitize_mcp_description(&tool.description, &metadata_policy);
+                if let Some(action) = sanitized.action {
+                    metadata_sanitized.push(McpMetadataSanitizedRecord {
+                        server: name.clone(),
+                        tool: tool.name.clone(),
+                        action,
+                        matched_patterns: sanitized.matched_patterns.clone(),
+                    });
+                }
+                if sanitized.is_excluded() {
+                    continue;
+                }
                 let namespaced = format!("mcp.{}.{}", name, tool.name);
-                let raw_doc = build_mcp_tool_doc_meta(&tool.description);
+                if let Some((reason, conflicts_with)) =
+                    mcp_tool_name_conflict(&tool.name, &namespaced, &builtin_names, |n| {
+                        tool_map.contains_key(n)
+                    })
+                {
+                    if metadata_policy.strict_names {
+                        return Err(anyhow!(
+                            "MCP server '{name}' offers tool '{}' whose name conflicts with '{conflicts_with}'",
+                            tool.name
+                        ));
+                    }
+                    tool_rejected.push(McpToolRejectedRecord {
+                        server: name.clone(),
+                        tool: tool.name.clone(),
+                        reason,
+                        conflicts_with,
+                    });
+                    continue;
+                }
+                let raw_doc = build_mcp_tool_doc_meta(&sanitized.text);
                 tool_map.insert(namespaced.clone(), (name.clone(), tool.name.clone()));
                 tool_schema_map.insert(namespaced.clone(), tool.input_schema.clone());
                 tool_doc_meta_map.insert(namespaced.clone(), raw_doc.clone());
+                if tool.annotations.as_ref().is_some_and(|a| a.read_only_hint) {
+                    read_only_tools.insert(namespaced.clone());
+                }
                 tool_defs.push(ToolDef {
                     name: namespaced.clone(),
                     description: model_facing_mcp_tool_description(name, &namespaced),
@@ -89,7 +249,10 @@ impl McpRegistry {
                     side_effects: tool_side_effects(&format!("mcp.{}.{}", name, tool.name)),
                 });
             }
-            clients.insert(name.clone(), client);
+            clients.insert(
+                name.clone(),
+                McpServerConnection::new(server.clone(), client, &tools),
+            );
         }
 
         tool_defs.sort_by(|a, b| a.name.cmp(&b.name));
@@ -98,16 +261,57 @@ impl McpRegistry {
             tool_map,
             tool_schema_map,
             tool_doc_meta_map,
+            read_only_tools,
             tool_defs,
             timeout,
             mcp_spool_dir,
+            metadata_policy,
+            metadata_sanitized,
+            tool_rejected,
+            reconnect_policy: McpReconnectPolicy::default(),
         })
     }
 
+    /// How calls to a server whose connection broke are retried.
+    pub fn with_reconnect_policy(mut self, policy: McpReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    /// Tools whose description was stripped, neutralized, or excluded.
+    pub fn metadata_sanitized(&self) -> &[McpMetadataSanitizedRecord] {
+        &self.metadata_sanitized
+    }
+
+    /// Tools left out because their name shadows a builtin or another
+    /// registered tool.
+    pub fn tool_rejected(&self) -> &[McpToolRejectedRecord] {
+        &self.tool_rejected
+    }
+
     pub fn tool_defs(&self) -> Vec<ToolDef> {
         self.tool_defs.clone()
     }
 
+    /// Server name and input schema behind a namespaced tool.
+    pub fn tool_server_and_schema(
+        &self,
+        namespaced_tool: &str,
+    ) -> Option<(&str, Option<&serde_json::Value>)> {
+        let (server, _) = self.tool_map.get(namespaced_tool)?;
+        let schema = self
+            .tool_schema_map
+            .get(namespaced_tool)
+            .and_then(|s| s.as_ref());
+        Some((server.as_str(), schema))
+    }
+
+    /// Whether the server marks `namespaced_tool` read-only, making it
+    /// eligible for parallel read batches.
+    pub fn tool_is_read_only(&self, namespaced_tool: &str) -> bool {
+        self.read_only_tools.contains(namespaced_tool)
+    }
+
     pub fn tool_doc_meta(&self, namespaced_tool: &str) -> Option<&McpToolDocMeta> {
         self.tool_doc_meta_map.get(namespaced_tool)
     }
@@ -171,10 +375,7 @@ impl McpRegistry {
         let snapshot = self
             .tool_defs
             .iter()
-            .map(|t| McpToolSnapshotEntry {
-                name: t.name.clone(),
-                parameters: t.parameters.clone(),
-            })
+            .map(catalog_snapshot_entry)
             .collect::<Vec<_>>();
         mcp_tool_snapshot_hash_hex(&snapshot)
     }
@@ -183,50 +384,56 @@ impl McpRegistry {
         let mut snapshot = self
             .tool_defs
             .iter()
-            .map(|t| McpToolDocsSnapshotEntry {
-                name: t.name.clone(),
-                parameters: t.parameters.clone(),
-                description_preview: self
-                    .tool_doc_meta_map
-                    .get(&t.name)
-                    .and_then(|m| m.raw_description.as_deref())
-                    .map(normalized_description_preview)
-                    .unwrap_or_default(),
-            })
+            .map(|t| self.docs_snapshot_entry(t))
             .collect::<Vec<_>>();
         snapshot.sort_by(|a, b| a.name.cmp(&b.name));
         mcp_tool_docs_snapshot_hash_hex(&snapshot)
     }
 
+    /// Degraded servers are not probed: calls to them fail fast, so their
+    /// configured entries stand in for the live ones.
     pub async fn live_tool_catalog_hash_hex(&self) -> anyhow::Result<String> {
         let mut snapshot: Vec<McpToolSnapshotEntry> = Vec::new();
-        for (server, client) in &self.clients {
-            let tools = client.tools_list(self.timeout).await?;
-            for tool in tools {
-                snapshot.push(McpToolSnapshotEntry {
-                    name: format!("mcp.{}.{}", server, tool.name),
-                    parameters: tool
-                        .input_schema
-                        .clone()
-                        .unwrap_or_else(|| json!({"type":"object"})),
-                });
+        for (server, conn) in &self.clients {
+            if conn.health().is_degraded() {
+                snapshot.extend(
+                    self.configured_server_defs(server)
+                        .map(catalog_snapshot_entry),
+                );
+                continue;
             }
+            let tools = conn.client().await.tools_list(self.timeout).await?;
+            snapshot.extend(self.live_catalog_entries(server, &tools));
         }
         mcp_tool_snapshot_hash_hex(&snapshot)
     }
 
     pub async fn live_tool_docs_hash_hex(&self) -> anyhow::Result<String> {
         let mut snapshot: Vec<McpToolDocsSnapshotEntry> = Vec::new();
-        for (server, client) in &self.clients {
-            let tools = client.tools_list(self.timeout).await?;
+        for (server, conn) in &self.clients {
+            if conn.health().is_degraded() {
+                snapshot.extend(
+                    self.configured_server_defs(server)
+                        .map(|t| self.docs_snapshot_entry(t)),
+                );
+                continue;
+            }
+            let tools = conn.client().await.tools_list(self.timeout).await?;
             for tool in tools {
+                if self.is_rejected(server, &tool.name) {
+                    continue;
+                }
+                let sanitized = sanitize_mcp_description(&tool.description, &self.metadata_policy);
+                if sanitized.is_excluded() {
+                    continue;
+                }
                 snapshot.push(McpToolDocsSnapshotEntry {
                     name: format!("mcp.{}.{}", server, tool.name),
                     parameters: tool
                         .input_schema
                         .clone()
                         .unwrap_or_else(|| json!({"type":"object"})),
-                    description_preview: normalized_description_preview(&tool.description),
+                    description_preview: normalized_description_preview(&sanitized.text),
                 });
             }
         }
@@ -234,6 +441,71 @@ impl McpRegistry {
         mcp_tool_docs_snapshot_hash_hex(&snapshot)
     }
 
+    /// Input schema hash of `namespaced_tool` as its server lists it now,
+    /// comparable with `store::tool_schema_hash_hex_map`. A degraded server
+    /// is not probed; the definitions it listed last stand in. `None` when
+    /// the tool is unknown or no longer listed.
+    pub async fn live_tool_schema_hash_hex(
+        &self,
+        namespaced_tool: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let Some((server, tool)) = self.tool_map.get(namespaced_tool) else {
+            return Ok(None);
+        };
+        let Some(conn) = self.clients.get(server) else {
+            return Ok(None);
+        };
+        if !conn.health().is_degraded() {
+            let tools = conn.client().await.tools_list(self.timeout).await?;
+            conn.refresh_schema_hashes(&tools);
+        }
+        Ok(conn.schema_hash(tool))
+    }
+
+    fn configured_server_defs<'a>(&'a self, server: &str) -> impl Iterator<Item = &'a ToolDef> {
+        let prefix = format!("mcp.{server}.");
+        self.tool_defs
+            .iter()
+            .filter(move |t| t.name.starts_with(&prefix))
+    }
+
+    fn is_rejected(&self, server: &str, tool: &str) -> bool {
+        self.tool_rejected
+            .iter()
+            .any(|r| r.server == server && r.tool == tool)
+    }
+
+    fn live_catalog_entries(&self, server: &str, tools: &[McpTool]) -> Vec<McpToolSnapshotEntry> {
+        tools
+            .iter()
+            .filter(|tool| {
+                !self.is_rejected(server, &tool.name)
+                    && !sanitize_mcp_description(&tool.description, &self.metadata_policy)
+                        .is_excluded()
+            })
+            .map(|tool| McpToolSnapshotEntry {
+                name: format!("mcp.{}.{}", server, tool.name),
+                parameters: tool
+                    .input_schema
+                    .clone()
+                    .unwrap_or_else(|| json!({"type":"object"})),
+            })
+            .collect()
+    }
+
+    fn docs_snapshot_entry(&self, def: &ToolDef) -> McpToolDocsSnapshotEntry {
+        McpToolDocsSnapshotEntry {
+            name: def.name.clone(),
+            parameters: def.parameters.clone(),
+            description_preview: self
+                .tool_doc_meta_map
+                .get(&def.name)
+                .and_then(|m| m.raw_description.as_deref())
+                .map(normalized_description_preview)
+                .unwrap_or_default(),
+        }
+    }
+
     pub fn tool_docs_hash_hex(&self, namespaced_tool: &str) -> anyhow::Result<Option<String>> {
         let Some(def) = self.tool_defs.iter().find(|t| t.name == namespaced_tool) else {
             return Ok(None);
@@ -252,10 +524,39 @@ impl McpRegistry {
         Ok(Some(mcp_tool_docs_snapshot_hash_hex(&snapshot)?))
     }
 
+    #[allow(dead_code)]
     pub async fn call_namespaced_tool(
         &self,
         tc: &ToolCall,
         strict: ToolArgsStrict,
+    ) -> anyhow::Result<McpCallOutcome> {
+        self.call_namespaced_tool_with_artifacts(tc, strict, None)
+            .await
+    }
+
+    /// Like [`Self::call_namespaced_tool`], spilling large or binary results
+    /// into `artifacts` and returning compact references in their place.
+    pub async fn call_namespaced_tool_with_artifacts(
+        &self,
+        tc: &ToolCall,
+        strict: ToolArgsStrict,
+        artifacts: Option<&RunArtifactStore>,
+    ) -> anyhow::Result<McpCallOutcome> {
+        self.call_namespaced_tool_with_limits(tc, strict, artifacts, 0)
+            .await
+    }
+
+    /// Like [`Self::call_namespaced_tool_with_artifacts`], holding the result
+    /// to `max_output_bytes` (`0` = no limit beyond the model cap). Longer
+    /// results are truncated at a char boundary; results over
+    /// `MCP_OVERSIZE_REJECT_FACTOR` times the limit are rejected with
+    /// `tool_output_oversize` before anything is spilled or spooled.
+    pub async fn call_namespaced_tool_with_limits(
+        &self,
+        tc: &ToolCall,
+        strict: ToolArgsStrict,
+        artifacts: Option<&RunArtifactStore>,
+        max_output_bytes: usize,
     ) -> anyhow::Result<McpCallOutcome> {
         let (server, tool) = self
             .tool_map
@@ -271,29 +572,24 @@ impl McpRegistry {
                     false,
                     format!("invalid tool arguments: {e}"),
                     false,
-                    ToolResultMeta {
-                        side_effects: tool_side_effects(&tc.name),
-                        bytes: None,
-                        exit_code: None,
-                        stderr_truncated: None,
-                        stdout_truncated: None,
-                        source: "mcp".to_string(),
-                        execution_target: "host".to_string(),
-                        warnings: None,
-                        warnings_max: None,
-                        warnings_truncated: None,
-                        docker: None,
-                    },
+                    mcp_result_meta(tc, None),
                 )),
                 meta: McpCallMeta::default(),
             });
         }
-        let client = self
+        let conn = self
             .clients
             .get(&server)
             .ok_or_else(|| anyhow!("MCP server '{}' not active", server))?;
 
         let mut meta = McpCallMeta::default();
+        if let Some(message) = self
+            .ensure_server_connected(tc, &server, &tool, conn, &mut meta)
+            .await
+        {
+            return Ok(McpCallOutcome { message, meta });
+        }
+        let client = conn.client().await;
         let started = Instant::now();
         let mut interval = tokio::time::interval(Duration::from_millis(750));
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -317,6 +613,9 @@ impl McpRegistry {
                 if err.to_ascii_lowercase().contains("timed out") {
                     meta.cancelled = true;
                 }
+                if is_transport_error(&e) {
+                    self.mark_server_degraded(&server, conn, &err, &mut meta);
+                }
                 return Ok(McpCallOutcome {
                     message: envelope_to_message(to_tool_result_envelope(
                         tc,
@@ -324,52 +623,74 @@ impl McpRegistry {
                         false,
                         format!("mcp call failed: {err}"),
                         false,
-                        ToolResultMeta {
-                            side_effects: tool_side_effects(&tc.name),
-                            bytes: None,
-                            exit_code: None,
-                            stderr_truncated: None,
-                            stdout_truncated: None,
-                            source: "mcp".to_string(),
-                            execution_target: "host".to_string(),
-                            warnings: None,
-                            warnings_max: None,
-                            warnings_truncated: None,
-                            docker: None,
-                        },
+                        mcp_result_meta(tc, None),
                     )),
                     meta,
                 });
             }
         };
+        let model_limit = match max_output_bytes {
+            0 => MCP_MAX_MODEL_RESULT_BYTES,
+            limit => limit.min(MCP_MAX_MODEL_RESULT_BYTES),
+        };
+        let hard_cap = max_output_bytes.saturating_mul(MCP_OVERSIZE_REJECT_FACTOR);
+        let raw_bytes = serialized_len(&result);
+        if max_output_bytes > 0 && raw_bytes > hard_cap {
+            meta.output_limit = Some(McpOutputLimitRecord {
+                server: server.clone(),
+                tool: tool.clone(),
+                original_bytes: raw_bytes as u64,
+                kept_bytes: 0,
+                limit_bytes: max_output_bytes as u64,
+                rejected: true,
+            });
+            return Ok(McpCallOutcome {
+                message: envelope_to_message(oversize_envelope(tc, raw_bytes, hard_cap)),
+                meta,
+            });
+        }
+        let result = match artifacts {
+            Some(store) => match spill_mcp_result(store, tc, result, model_limit) {
+                Ok(spilled) => spilled,
+                Err(e) => {
+                    return Ok(McpCallOutcome {
+                        message: envelope_to_message(to_tool_result_envelope(
+                            tc,
+                            "mcp",
+                            false,
+                            format!("mcp artifact spill failed: {e}"),
+                            false,
+                            mcp_result_meta(tc, None),
+                        )),
+                        meta,
+                    });
+                }
+            },
+            None => result,
+        };
         let result_str = match result {
             serde_json::Value::String(s) => s,
             other => serde_json::to_string(&other)
                 .unwrap_or_else(|e| format!("mcp result serialization failed: {e}")),
         };
-        let (model_content, was_truncated) =
-            truncate_utf8_to_bytes(&result_str, MCP_MAX_MODEL_RESULT_BYTES);
+        let (model_content, was_truncated) = truncate_utf8_to_bytes(&result_str, model_limit);
         let mut env = to_tool_result_envelope(
             tc,
             "mcp",
             true,
             model_content.clone(),
             was_truncated,
-            ToolResultMeta {
-                side_effects: tool_side_effects(&tc.name),
-                bytes: Some(result_str.len() as u64),
-                exit_code: None,
-                stderr_truncated: None,
-                stdout_truncated: None,
-                source: "mcp".to_string(),
-                execution_target: "host".to_string(),
-                warnings: None,
-                warnings_max: None,
-                warnings_truncated: None,
-                docker: None,
-            },
+            mcp_result_meta(tc, Some(result_str.len() as u64)),
         );
         if was_truncated {
+            meta.output_limit = Some(McpOutputLimitRecord {
+                server: server.clone(),
+                tool: tool.clone(),
+                original_bytes: result_str.len() as u64,
+                kept_bytes: model_content.len() as u64,
+                limit_bytes: model_limit as u64,
+                rejected: false,
+            });
             env.truncate_reason = Some("max_bytes".to_string());
             if let Some(output_ref) = spool_full_mcp_output(&self.mcp_spool_dir, tc, &result_str) {
                 env.full_output_ref = Some(output_ref);
@@ -380,12 +701,265 @@ impl McpRegistry {
             meta,
         })
     }
+
+    /// Fails the call fast while `server` is degraded, or reconnects it once
+    /// its backoff has passed. Returns the failure message when the call
+    /// must not go ahead, which includes a reconnect that changed the
+    /// called tool's input schema: the call was planned against the old one.
+    async fn ensure_server_connected(
+        &self,
+        tc: &ToolCall,
+        server: &str,
+        tool: &str,
+        conn: &McpServerConnection,
+        meta: &mut McpCallMeta,
+    ) -> Option<Message> {
+        let policy = self.reconnect_policy;
+        let check = conn.health().check(&policy, Instant::now());
+        match check {
+            McpHealthCheck::Proceed => None,
+            McpHealthCheck::FailFast {
+                reason,
+                attempts,
+                retry_after_ms,
+            } => Some(server_degraded_message(
+                tc,
+                server,
+                &reason,
+                attempts,
+                retry_after_ms,
+            )),
+            McpHealthCheck::Reconnect => match self.reconnect_server(server, &conn.config).await {
+                Ok((client, tools)) => {
+                    let before = conn.schema_hash(tool);
+                    conn.refresh_schema_hashes(&tools);
+                    let after = conn.schema_hash(tool);
+                    *conn.client.write().await = Arc::new(client);
+                    if let Some((degraded_ms, reconnect_attempts)) =
+                        conn.health().reconnected(Instant::now())
+                    {
+                        meta.reconnected = Some(McpServerReconnectedRecord {
+                            server: server.to_string(),
+                            degraded_ms,
+                            reconnect_attempts,
+                        });
+                    }
+                    (before != after).then(|| schema_changed_message(tc, server, before, after))
+                }
+                Err(e) => {
+                    let reason = format!("reconnect failed: {e}");
+                    let attempts =
+                        conn.health()
+                            .reconnect_failed(reason.clone(), &policy, Instant::now());
+                    let retry_after_ms =
+                        (attempts < policy.max_attempts).then(|| policy.backoff_ms(attempts));
+                    meta.degraded = Some(McpServerDegradedRecord {
+                        server: server.to_string(),
+                        reason: reason.clone(),
+                        reconnect_attempts: attempts,
+                        max_reconnect_attempts: policy.max_attempts,
+                        retry_after_ms,
+                    });
+                    Some(server_degraded_message(
+                        tc,
+                        server,
+                        &reason,
+                        attempts,
+                        retry_after_ms,
+                    ))
+                }
+            },
+        }
+    }
+
+    /// Starts `server` again and fetches its tool definitions, which may
+    /// differ from the ones the run started with; pin checks before each
+    /// call compare them against the pinned hashes.
+    async fn reconnect_server(
+        &self,
+        server: &str,
+        config: &McpServerConfig,
+    ) -> anyhow::Result<(McpClient, Vec<McpTool>)> {
+        let client = McpClient::spawn(server, &config.command, &config.args).await?;
+        client.initialize(Duration::from_secs(5)).await?;
+        let tools = client.tools_list(self.timeout).await?;
+        Ok((client, tools))
+    }
+
+    fn mark_server_degraded(
+        &self,
+        server: &str,
+        conn: &McpServerConnection,
+        reason: &str,
+        meta: &mut McpCallMeta,
+    ) {
+        let policy = self.reconnect_policy;
+        if conn
+            .health()
+            .mark_degraded(reason.to_string(), &policy, Instant::now())
+        {
+            meta.degraded = Some(McpServerDegradedRecord {
+                server: server.to_string(),
+                reason: reason.to_string(),
+                reconnect_attempts: 0,
+                max_reconnect_attempts: policy.max_attempts,
+                retry_after_ms: (policy.max_attempts > 0).then(|| policy.backoff_ms(0)),
+            });
+        }
+    }
 }
 
 const MCP_MAX_MODEL_RESULT_BYTES: usize = 64 * 1024;
+/// Results over this multiple of `max_tool_output_bytes` are rejected
+/// instead of truncated.
+const MCP_OVERSIZE_REJECT_FACTOR: usize = 4;
 const MCP_MAX_RAW_DESCRIPTION_BYTES: usize = 8 * 1024;
 const MCP_DOCS_HASH_PREVIEW_BYTES: usize = 1024;
 
+fn catalog_snapshot_entry(def: &ToolDef) -> McpToolSnapshotEntry {
+    McpToolSnapshotEntry {
+        name: def.name.clone(),
+        parameters: def.parameters.clone(),
+    }
+}
+
+/// Deterministic failure for a call to a degraded server, returned without
+/// contacting it.
+fn server_degraded_message(
+    tc: &ToolCall,
+    server: &str,
+    reason: &str,
+    reconnect_attempts: u32,
+    retry_after_ms: Option<u64>,
+) -> Message {
+    let content = json!({
+        "error": "E_NETWORK_TRANSIENT",
+        "server": server,
+        "server_degraded": true,
+        "reconnect_attempts": reconnect_attempts,
+        "retry_after_ms": retry_after_ms,
+        "detail": reason,
+    });
+    envelope_to_message(to_tool_result_envelope_with_error(
+        tc,
+        "mcp",
+        false,
+        content.to_string(),
+        false,
+        Some(ToolErrorDetail {
+            code: ToolErrorCode::McpServerDegraded,
+            message: format!("MCP server '{server}' is degraded; the call was not sent."),
+            expected_schema: None,
+            received_args: None,
+            minimal_example: None,
+            available_tools: None,
+        }),
+        mcp_result_meta(tc, None),
+    ))
+}
+
+fn schema_changed_message(
+    tc: &ToolCall,
+    server: &str,
+    before: Option<String>,
+    after: Option<String>,
+) -> Message {
+    let content = json!({
+        "error": "MCP_SCHEMA_DRIFT",
+        "server": server,
+        "schema_hash_before": before,
+        "schema_hash_after": after,
+    });
+    envelope_to_message(to_tool_result_envelope_with_error(
+        tc,
+        "mcp",
+        false,
+        content.to_string(),
+        false,
+        Some(ToolErrorDetail {
+            code: ToolErrorCode::McpSchemaDrift,
+            message: format!(
+                "definition of '{}' changed when MCP server '{server}' restarted; the call was not sent.",
+                tc.name
+            ),
+            expected_schema: None,
+            received_args: None,
+            minimal_example: None,
+            available_tools: None,
+        }),
+        mcp_result_meta(tc, None),
+    ))
+}
+
+fn listed_parameters(tool: &McpTool) -> serde_json::Value {
+    tool.input_schema
+        .clone()
+        .unwrap_or_else(|| json!({"type":"object"}))
+}
+
+fn mcp_result_meta(tc: &ToolCall, bytes: Option<u64>) -> ToolResultMeta {
+    ToolResultMeta {
+        side_effects: tool_side_effects(&tc.name),
+        bytes,
+        exit_code: None,
+        stderr_truncated: None,
+        stdout_truncated: None,
+        source: "mcp".to_string(),
+        execution_target: "host".to_string(),
+        warnings: None,
+        warnings_max: None,
+        warnings_truncated: None,
+        docker: None,
+        write_protection: None,
+        cwd: None,
+        timed_out: None,
+        dry_run: None,
+    }
+}
+
+fn oversize_envelope(tc: &ToolCall, raw_bytes: usize, hard_cap: usize) -> ToolResultEnvelope {
+    to_tool_result_envelope_with_error(
+        tc,
+        "mcp",
+        false,
+        format!(
+            "mcp result rejected: {raw_bytes} bytes is over the hard cap of {hard_cap} bytes ({MCP_OVERSIZE_REJECT_FACTOR}x max_tool_output_bytes)"
+        ),
+        false,
+        Some(ToolErrorDetail {
+            code: ToolErrorCode::ToolOutputOversize,
+            message: "MCP result exceeded the hard output cap and was dropped.".to_string(),
+            expected_schema: None,
+            received_args: None,
+            minimal_example: None,
+            available_tools: None,
+        }),
+        mcp_result_meta(tc, Some(raw_bytes as u64)),
+    )
+}
+
+/// Serialized size of an MCP result, counted without building the string.
+fn serialized_len(value: &serde_json::Value) -> usize {
+    struct ByteCounter(usize);
+    impl std::io::Write for ByteCounter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    match value {
+        serde_json::Value::String(s) => s.len(),
+        other => {
+            let mut counter = ByteCounter(0);
+            let _ = serde_json::to_writer(&mut counter, other);
+            counter.0
+        }
+    }
+}
+
 fn model_facing_mcp_tool_description(server: &str, namespaced_tool: &str) -> String {
     format!("MCP tool from {server}. Use /tool docs {namespaced_tool} for details.")
 }
@@ -562,7 +1136,7 @@ pub fn list_servers(path: &Path) -> anyhow::Result<Vec<String>> {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
 
     use serde_json::json;
 
@@ -588,6 +1162,7 @@ mod tests {
             name: "echo".to_string(),
             description: "Echo".to_string(),
             input_schema: Some(json!({"type":"object"})),
+            annotations: None,
         };
         let out = tool_def_from_mcp("stub", &t);
         assert_eq!(out.name, "mcp.stub.echo");
@@ -606,18 +1181,28 @@ mod tests {
             tool_map: BTreeMap::new(),
             tool_schema_map: BTreeMap::new(),
             tool_doc_meta_map: BTreeMap::new(),
+            read_only_tools: BTreeSet::new(),
             tool_defs: defs.clone(),
             timeout: std::time::Duration::from_secs(1),
             mcp_spool_dir: std::path::PathBuf::from("."),
+            metadata_policy: crate::mcp::sanitize::McpMetadataPolicy::default(),
+            metadata_sanitized: Vec::new(),
+            tool_rejected: Vec::new(),
+            reconnect_policy: crate::mcp::health::McpReconnectPolicy::default(),
         };
         let reg_b = super::McpRegistry {
             clients: BTreeMap::new(),
             tool_map: BTreeMap::new(),
             tool_schema_map: BTreeMap::new(),
             tool_doc_meta_map: BTreeMap::new(),
+            read_only_tools: BTreeSet::new(),
             tool_defs: defs,
             timeout: std::time::Duration::from_secs(1),
             mcp_spool_dir: std::path::PathBuf::from("."),
+            metadata_policy: crate::mcp::sanitize::McpMetadataPolicy::default(),
+            metadata_sanitized: Vec::new(),
+            tool_rejected: Vec::new(),
+            reconnect_policy: crate::mcp::health::McpReconnectPolicy::default(),
         };
         let a = reg_a.configured_tool_catalog_hash_hex().expect("hash a");
         let b = reg_b.configured_tool_catalog_hash_hex().expect("hash b");
@@ -697,18 +1282,28 @@ mod tests {
             tool_map: BTreeMap::new(),
             tool_schema_map: BTreeMap::new(),
             tool_doc_meta_map: docs_a,
+            read_only_tools: BTreeSet::new(),
             tool_defs: defs.clone(),
             timeout: std::time::Duration::from_secs(1),
             mcp_spool_dir: std::path::PathBuf::from("."),
+            metadata_policy: crate::mcp::sanitize::McpMetadataPolicy::default(),
+            metadata_sanitized: Vec::new(),
+            tool_rejected: Vec::new(),
+            reconnect_policy: crate::mcp::health::McpReconnectPolicy::default(),
         };
         let reg_b = super::McpRegistry {
             clients: BTreeMap::new(),
             tool_map: BTreeMap::new(),
             tool_schema_map: BTreeMap::new(),
             tool_doc_meta_map: docs_b,
+            read_only_tools: BTreeSet::new(),
             tool_defs: defs,
             timeout: std::time::Duration::from_secs(1),
             mcp_spool_dir: std::path::PathBuf::from("."),
+            metadata_policy: crate::mcp::sanitize::McpMetadataPolicy::default(),
+            metadata_sanitized: Vec::new(),
+            tool_rejected: Vec::new(),
+            reconnect_policy: crate::mcp::health::McpReconnectPolicy::default(),
         };
         assert_eq!(
             reg_a.configured_tool_catalog_hash_hex().expect("catalog a"),
@@ -751,9 +1346,14 @@ mod tests {
             tool_map: BTreeMap::new(),
             tool_schema_map: BTreeMap::new(),
             tool_doc_meta_map: docs,
+            read_only_tools: BTreeSet::new(),
             tool_defs: defs,
             timeout: std::time::Duration::from_secs(1),
             mcp_spool_dir: std::path::PathBuf::from("."),
+            metadata_policy: crate::mcp::sanitize::McpMetadataPolicy::default(),
+            metadata_sanitized: Vec::new(),
+            tool_rejected: Vec::new(),
+            reconnect_policy: crate::mcp::health::McpReconnectPolicy::default(),
         };
         let rendered = reg.render_tool_docs_text("mcp.stub.echo");
         assert!(rendered.contains("tool_name: mcp.stub.echo"));
@@ -789,9 +1389,14 @@ mod tests {
             tool_map: BTreeMap::new(),
             tool_schema_map: BTreeMap::new(),
             tool_doc_meta_map: BTreeMap::new(),
+            read_only_tools: BTreeSet::new(),
             tool_defs: defs,
             timeout: std::time::Duration::from_secs(1),
             mcp_spool_dir: std::path::PathBuf::from("."),
+            metadata_policy: crate::mcp::sanitize::McpMetadataPolicy::default(),
+            metadata_sanitized: Vec::new(),
+            tool_rejected: Vec::new(),
+            reconnect_policy: crate::mcp::health::McpReconnectPolicy::default(),
         };
         let rendered = reg.render_tool_docs_text("echo");
         assert!(rendered.starts_with("unknown tool: echo"));
@@ -813,9 +1418,14 @@ mod tests {
             tool_map: BTreeMap::new(),
             tool_schema_map: BTreeMap::new(),
             tool_doc_meta_map: BTreeMap::new(),
+            read_only_tools: BTreeSet::new(),
             tool_defs: defs,
             timeout: std::time::Duration::from_secs(1),
             mcp_spool_dir: std::path::PathBuf::from("."),
+            metadata_policy: crate::mcp::sanitize::McpMetadataPolicy::default(),
+            metadata_sanitized: Vec::new(),
+            tool_rejected: Vec::new(),
+            reconnect_policy: crate::mcp::health::McpReconnectPolicy::default(),
         };
         let rendered = reg.render_tool_docs_text("mcp.stub.empty");
         assert!(rendered.contains("raw_description_hash: -"));
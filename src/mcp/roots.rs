@@ -0,0 +1,575 @@
+//! Declared correspondence between MCP server-side paths and workdir paths.
+//!
+//! `--mcp-root fs:/data=./data` says that `/data/...` as seen by the `fs`
+//! server is `data/...` under the workdir. With a mapping in place the
+//! runtime applies the read allowlist, policy read rules, and taint file
+//! globs to path-typed MCP arguments as if they were `read_file` paths.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::store::{McpRootRecord, McpRootsRecord, McpToolSnapshotEntry};
+use crate::tools::{normalize_allowlist_path, ReadAllowlist, ToolErrorCode};
+use crate::trust::policy::{Policy, PolicyDecision};
+
+/// Property names treated as paths even without a `format` hint.
+const PATH_FIELD_NAMES: &[&str] = &[
+    "path",
+    "paths",
+    "file",
+    "files",
+    "filename",
+    "filepath",
+    "dir",
+    "directory",
+    "source",
+    "destination",
+    "root",
+    "cwd",
+];
+const PATH_FIELD_SUFFIXES: &[&str] = &["_path", "_paths", "_file", "_dir", "_directory"];
+
+/// Upper bound on paths lifted out of a single tool result.
+const MAX_RESULT_PATHS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpRootMapping {
+    pub server: String,
+    /// Absolute server-side root, without a trailing slash.
+    pub server_root: String,
+    /// Workdir-relative host root, normalized (`.` for the workdir itself).
+    pub host_root: String,
+}
+
+impl McpRootMapping {
+    /// Parses `SERVER:SERVER_ROOT=HOST_ROOT`.
+    pub fn parse(spec: &str) -> anyhow::Result<Self> {
+        let (server, rest) = spec
+            .split_once(':')
+            .ok_or_else(|| anyhow!("--mcp-root '{spec}' must look like SERVER:/root=./dir"))?;
+        let (server_root, host_root) = rest
+            .split_once('=')
+            .ok_or_else(|| anyhow!("--mcp-root '{spec}' must look like SERVER:/root=./dir"))?;
+        let server = server.trim();
+        if server.is_empty() {
+            return Err(anyhow!("--mcp-root '{spec}' is missing the server name"));
+        }
+        let server_root = server_root.trim();
+        if !server_root.starts_with('/') {
+            return Err(anyhow!(
+                "--mcp-root '{spec}': server root '{server_root}' must be absolute"
+            ));
+        }
+        let host_root = host_root.trim();
+        if host_root.is_empty()
+            || Path::new(host_root).is_absolute()
+            || host_root.split(['/', '\\']).any(|s| s == "..")
+        {
+            return Err(anyhow!(
+                "--mcp-root '{spec}': host root '{host_root}' must be workdir-relative"
+            ));
+        }
+        let server_root = lexical_normalize(server_root)
+            .ok_or_else(|| anyhow!("--mcp-root '{spec}': server root escapes '/'"))?;
+        Ok(Self {
+            server: server.to_string(),
+            server_root: format!("/{server_root}").trim_end_matches('/').to_string(),
+            host_root: normalize_allowlist_path(host_root),
+        })
+    }
+
+    /// Host path for `server_path` when it lies under this root.
+    fn map(&self, server_path: &str) -> Option<String> {
+        let normalized = format!("/{}", lexical_normalize(server_path)?);
+        let rest = if self.server_root.is_empty() {
+            normalized.as_str()
+        } else if normalized == self.server_root {
+            ""
+        } else {
+            normalized.strip_prefix(&format!("{}/", self.server_root))?
+        };
+        let rest = rest.trim_start_matches('/');
+        Some(match (self.host_root.as_str(), rest) {
+            (root, "") => root.to_string(),
+            (".", rest) => rest.to_string(),
+            (root, rest) => format!("{root}/{rest}"),
+        })
+    }
+}
+
+/// A path-typed MCP argument (or result path) and where it lands on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpMappedPath {
+    /// Argument field name, or `result` for paths found in tool output.
+    pub field: String,
+    pub server_path: String,
+    /// Workdir-relative host path; `None` when outside every declared root.
+    pub host_path: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpPathDenial {
+    pub code: ToolErrorCode,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct McpRootMap {
+    mappings: Vec<McpRootMapping>,
+}
+
+impl McpRootMap {
+    pub fn from_specs(specs: &[String]) -> anyhow::Result<Self> {
+        let mut mappings = specs
+            .iter()
+            .map(|s| McpRootMapping::parse(s))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        // Longest server root first so nested roots win.
+        mappings.sort_by(|a, b| {
+            a.server
+                .cmp(&b.server)
+                .then(b.server_root.len().cmp(&a.server_root.len()))
+        });
+        Ok(Self { mappings })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    pub fn servers(&self) -> BTreeSet<&str> {
+        self.mappings.iter().map(|m| m.server.as_str()).collect()
+    }
+
+    pub fn is_mapped(&self, server: &str) -> bool {
+        self.mappings.iter().any(|m| m.server == server)
+    }
+
+    fn mappings_for<'a>(&'a self, server: &'a str) -> impl Iterator<Item = &'a McpRootMapping> {
+        self.mappings.iter().filter(move |m| m.server == server)
+    }
+
+    /// Maps a server-side path; relative paths resolve against the server's
+    /// first declared root.
+    pub fn map_server_path(&self, server: &str, server_path: &str) -> Option<String> {
+        let absolute = if server_path.starts_with('/') {
+            server_path.to_string()
+        } else {
+            let first = self
+                .mappings
+                .iter()
+                .filter(|m| m.server == server)
+                .min_by_key(|m| m.server_root.len())?;
+            format!("{}/{}", first.server_root, server_path)
+        };
+        self.mappings_for(server).find_map(|m| m.map(&absolute))
+    }
+
+    /// Path-typed argument values of a call to `server`, mapped to the host.
+    /// Empty when the server has no declared roots.
+    pub fn mapped_argument_paths(
+        &self,
+        server: &str,
+        arguments: &Value,
+        schema: Option<&Value>,
+    ) -> Vec<McpMappedPath> {
+        if !self.is_mapped(server) {
+            return Vec::new();
+        }
+        let fields = match schema {
+            Some(schema) => path_typed_fields(schema),
+            None => arguments
+                .as_object()
+                .map(|obj| {
+                    obj.keys()
+                        .filter(|k| is_path_field_name(k))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        let mut out = Vec::new();
+        for field in fields {
+            let values = match arguments.get(&field) {
+                Some(Value::String(s)) => vec![s.as_str()],
+                Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+                _ => continue,
+            };
+            for server_path in values {
+                out.push(McpMappedPath {
+                    field: field.clone(),
+                    server_path: server_path.to_string(),
+                    host_path: self.map_server_path(server, server_path),
+                });
+            }
+        }
+        out
+    }
+
+    /// Best-effort scan of tool output for paths under the server's declared
+    /// roots. Only paths that map are returned.
+    pub fn mapped_result_paths(&self, server: &str, text: &str) -> Vec<McpMappedPath> {
+        let mut out = Vec::new();
+        let mut seen = BTreeSet::new();
+        for mapping in self.mappings_for(server) {
+            let root = if mapping.server_root.is_empty() {
+                "/"
+            } else {
+                mapping.server_root.as_str()
+            };
+            for (idx, _) in text.match_indices(root) {
+                if out.len() >= MAX_RESULT_PATHS {
+                    return out;
+                }
+                let preceded_by_path_char = text[..idx]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-'));
+                if preceded_by_path_char {
+                    continue;
+                }
+                let end = text[idx..]
+                    .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | '`'))
+                    .map_or(text.len(), |e| idx + e);
+                let candidate = text[idx..end].trim_end_matches(['.', ':', ';', ')', ']']);
+                if !seen.insert(candidate.to_string()) {
+                    continue;
+                }
+                if let Some(host_path) = mapping.map(candidate) {
+                    out.push(McpMappedPath {
+                        field: "result".to_string(),
+                        server_path: candidate.to_string(),
+                        host_path: Some(host_path),
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    /// Run-record entries: one per enabled server that has declared roots or
+    /// tools with path-typed parameters.
+    pub fn records(
+        &self,
+        enabled_servers: &[String],
+        tool_snapshot: &[McpToolSnapshotEntry],
+    ) -> Vec<McpRootsRecord> {
+        let mut path_tools: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for entry in tool_snapshot {
+            let Some((server, tool)) = entry
+                .name
+                .strip_prefix("mcp.")
+                .and_then(|rest| rest.split_once('.'))
+            else {
+                continue;
+            };
+            if !path_typed_fields(&entry.parameters).is_empty() {
+                path_tools.entry(server).or_default().push(tool.to_string());
+            }
+        }
+        let servers = enabled_servers
+            .iter()
+            .map(String::as_str)
+            .chain(self.servers())
+            .collect::<BTreeSet<_>>();
+        servers
+            .into_iter()
+            .filter_map(|server| {
+                let roots = self
+                    .mappings_for(server)
+                    .map(|m| McpRootRecord {
+                        server_root: if m.server_root.is_empty() {
+                            "/".to_string()
+                        } else {
+                            m.server_root.clone()
+                        },
+                        host_root: m.host_root.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                let mut path_typed_tools = path_tools.remove(server).unwrap_or_default();
+                path_typed_tools.sort();
+                if roots.is_empty() && path_typed_tools.is_empty() {
+                    return None;
+                }
+                Some(McpRootsRecord {
+                    server: server.to_string(),
+                    unmapped_filesystem_access: roots.is_empty(),
+                    roots,
+                    path_typed_tools,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Top-level schema properties that carry paths: string or string-array
+/// properties with a path-like name or a `format` of `path`/`uri-reference`.
+pub fn path_typed_fields(schema: &Value) -> Vec<String> {
+    let Some(props) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    props
+        .iter()
+        .filter(|(name, prop)| {
+            let is_string = |v: &Value| v.get("type").and_then(Value::as_str) == Some("string");
+            let stringy = is_string(prop)
+                || (prop.get("type").and_then(Value::as_str) == Some("array")
+                    && prop.get("items").is_some_and(is_string));
+            let path_format = matches!(
+                prop.get("format").and_then(Value::as_str),
+                Some("path" | "uri-reference")
+            ) || prop
+                .get("items")
+                .and_then(|i| i.get("format"))
+                .and_then(Value::as_str)
+                == Some("path");
+            stringy && (path_format || is_path_field_name(name))
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+fn is_path_field_name(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    PATH_FIELD_NAMES.contains(&lower.as_str())
+        || PATH_FIELD_SUFFIXES.iter().any(|s| lower.ends_with(s))
+        || (lower.ends_with("path") && lower.len() > "path".len())
+}
+
+/// First denial among `paths`: outside the declared roots, outside the read
+/// allowlist, or denied by a `read_file` policy rule.
+pub fn check_mapped_paths(
+    server: &str,
+    paths: &[McpMappedPath],
+    read_allowlist: Option<&ReadAllowlist>,
+    policy: Option<&Policy>,
+) -> Option<McpPathDenial> {
+    for p in paths {
+        let Some(host_path) = p.host_path.as_deref() else {
+            return Some(McpPathDenial {
+                code: ToolErrorCode::PathOutOfScope,
+                message: format!(
+                    "E_MCP_PATH_OUTSIDE_ROOTS: '{}' ({}) is outside the roots declared for MCP server '{server}'",
+                    p.server_path, p.field
+                ),
+            });
+        };
+        if let Some(allowlist) = read_allowlist {
+            if !allowlist.allows(host_path) {
+                return Some(McpPathDenial {
+                    code: ToolErrorCode::PathNotInReadAllowlist,
+                    message: format!(
+                        "E_PATH_NOT_IN_READ_ALLOWLIST: '{}' maps to '{host_path}', which is outside the read allowlist. Only these paths may be read: {}",
+                        p.server_path,
+                        allowlist.globs().join(", ")
+                    ),
+                });
+            }
+        }
+        if let Some(policy) = policy {
+            let eval = policy.evaluate("read_file", &serde_json::json!({ "path": host_path }));
+            if eval.decision == PolicyDecision::Deny {
+                return Some(McpPathDenial {
+                    code: ToolErrorCode::ToolPathDenied,
+                    message: format!(
+                        "'{}' maps to '{host_path}', which policy denies for reads{}",
+                        p.server_path,
+                        eval.reason.map(|r| format!(": {r}")).unwrap_or_default()
+                    ),
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Collapses `.` and `..` without touching the filesystem; `None` when the
+/// path climbs above its start.
+fn lexical_normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(s) => parts.push(s.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            _ => {}
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn map(specs: &[&str]) -> McpRootMap {
+        McpRootMap::from_specs(&specs.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+            .expect("specs")
+    }
+
+    fn read_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "encoding": {"type": "string"}
+            }
+        })
+    }
+
+    #[test]
+    fn parses_and_maps_server_paths() {
+        let roots = map(&["fs:/data=./data", "fs:/data/nested=vendor"]);
+        assert_eq!(
+            roots.map_server_path("fs", "/data/a/b.txt").as_deref(),
+            Some("data/a/b.txt")
+        );
+        assert_eq!(
+            roots.map_server_path("fs", "/data/nested/x").as_deref(),
+            Some("vendor/x")
+        );
+        assert_eq!(
+            roots.map_server_path("fs", "notes.md").as_deref(),
+            Some("data/notes.md")
+        );
+        assert_eq!(roots.map_server_path("fs", "/database/x"), None);
+        assert_eq!(roots.map_server_path("fs", "/data/../etc/passwd"), None);
+        assert_eq!(roots.map_server_path("other", "/data/x"), None);
+        assert!(McpRootMapping::parse("fs:/data=/abs").is_err());
+        assert!(McpRootMapping::parse("fs:/data=../up").is_err());
+        assert!(McpRootMapping::parse("fs:data=./data").is_err());
+        assert!(McpRootMapping::parse("fs/data=./data").is_err());
+    }
+
+    #[test]
+    fn detects_path_typed_argument_fields() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "paths": {"type": "array", "items": {"type": "string"}},
+                "source_path": {"type": "string"},
+                "outputDir": {"type": "string"},
+                "target": {"type": "string", "format": "path"},
+                "content": {"type": "string"},
+                "recursive": {"type": "boolean"},
+                "filepath_count": {"type": "integer"}
+            }
+        });
+        let mut fields = path_typed_fields(&schema);
+        fields.sort();
+        assert_eq!(fields, vec!["path", "paths", "source_path", "target"]);
+        assert!(!is_path_field_name("outputDir"));
+        assert!(is_path_field_name("inputPath"));
+        assert!(is_path_field_name("log_dir"));
+        assert!(!is_path_field_name("pathological"));
+
+        let roots = map(&["fs:/data=data"]);
+        let mapped = roots.mapped_argument_paths(
+            "fs",
+            &json!({"paths": ["/data/a", "/data/b"], "content": "/data/c"}),
+            Some(&schema),
+        );
+        assert_eq!(
+            mapped
+                .iter()
+                .map(|p| p.host_path.clone().unwrap_or_default())
+                .collect::<Vec<_>>(),
+            vec!["data/a", "data/b"]
+        );
+        let schemaless = roots.mapped_argument_paths("fs", &json!({"file": "/data/x"}), None);
+        assert_eq!(schemaless[0].field, "file");
+    }
+
+    #[test]
+    fn applies_read_allowlist_and_policy_denylist_through_mapping() {
+        let roots = map(&["fs:/srv=data"]);
+        let policy = Policy::from_yaml(
+            r#"
+version: 2
+default: allow
+rules:
+  - tool: "read_file"
+    decision: deny
+    reason: "secrets are off limits"
+    when:
+      - arg: "path"
+        op: glob
+        value: "data/secrets/**"
+"#,
+        )
+        .expect("policy");
+        let denied = roots.mapped_argument_paths("fs", &json!({"path": "/srv/secrets/key"}), None);
+        let denial = check_mapped_paths("fs", &denied, None, Some(&policy)).expect("denied");
+        assert_eq!(denial.code, ToolErrorCode::ToolPathDenied);
+        assert!(denial.message.contains("data/secrets/key"));
+        assert!(denial.message.contains("secrets are off limits"));
+
+        let allowed = roots.mapped_argument_paths("fs", &json!({"path": "/srv/docs/a"}), None);
+        assert!(check_mapped_paths("fs", &allowed, None, Some(&policy)).is_none());
+
+        let allowlist = ReadAllowlist::from_globs(&["data/public/**".to_string()])
+            .expect("globs")
+            .expect("non-empty");
+        let denial = check_mapped_paths("fs", &allowed, Some(&allowlist), None).expect("denied");
+        assert_eq!(denial.code, ToolErrorCode::PathNotInReadAllowlist);
+
+        let outside = roots.mapped_argument_paths("fs", &json!({"path": "/etc/passwd"}), None);
+        let denial = check_mapped_paths("fs", &outside, None, None).expect("denied");
+        assert_eq!(denial.code, ToolErrorCode::PathOutOfScope);
+    }
+
+    #[test]
+    fn finds_mapped_paths_in_results() {
+        let roots = map(&["fs:/srv=data"]);
+        let found = roots.mapped_result_paths(
+            "fs",
+            "[FILE] /srv/a.txt\n[FILE] /srv/sub/.env, /srvx/no \"/srv/a.txt\"",
+        );
+        assert_eq!(
+            found
+                .iter()
+                .filter_map(|p| p.host_path.as_deref())
+                .collect::<Vec<_>>(),
+            vec!["data/a.txt", "data/sub/.env"]
+        );
+    }
+
+    #[test]
+    fn records_flag_mapped_and_unmapped_servers() {
+        let roots = map(&["fs:/srv=data"]);
+        let snapshot = vec![
+            McpToolSnapshotEntry {
+                name: "mcp.fs.read_file".to_string(),
+                parameters: read_schema(),
+            },
+            McpToolSnapshotEntry {
+                name: "mcp.other.read_text".to_string(),
+                parameters: read_schema(),
+            },
+            McpToolSnapshotEntry {
+                name: "mcp.web.fetch".to_string(),
+                parameters: json!({"type":"object","properties":{"url":{"type":"string"}}}),
+            },
+        ];
+        let records = roots.records(
+            &["fs".to_string(), "other".to_string(), "web".to_string()],
+            &snapshot,
+        );
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].server, "fs");
+        assert!(!records[0].unmapped_filesystem_access);
+        assert_eq!(records[0].roots[0].server_root, "/srv");
+        assert_eq!(records[0].roots[0].host_root, "data");
+        assert_eq!(records[0].path_typed_tools, vec!["read_file"]);
+        assert_eq!(records[1].server, "other");
+        assert!(records[1].unmapped_filesystem_access);
+        assert!(records[1].roots.is_empty());
+    }
+}
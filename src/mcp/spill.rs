@@ -0,0 +1,212 @@
+//! Moves large or binary MCP results out of the transcript into run artifacts.
+//!
+//! Base64 `data` on `image`/`audio` content items and `blob` on embedded
+//! resources are decoded and stored, and the item is replaced in place by an
+//! `artifact` reference. A result that is still over the threshold afterwards
+//! is stored whole and replaced by a single reference.
+
+use anyhow::anyhow;
+use serde_json::{json, Map, Value};
+
+use crate::store::RunArtifactStore;
+use crate::types::ToolCall;
+
+pub const MCP_ARTIFACT_HINT: &str = "Output was stored as a run artifact instead of inline. Pass an artifact's path_hint (artifact:<hash>) as the path argument to read_file or to other tools that accept artifact references.";
+
+pub fn spill_mcp_result(
+    store: &RunArtifactStore,
+    tc: &ToolCall,
+    result: Value,
+    threshold_bytes: usize,
+) -> anyhow::Result<Value> {
+    let mut value = result;
+    let mut spilled = 0usize;
+    if let Some(items) = value.get_mut("content").and_then(Value::as_array_mut) {
+        for item in items.iter_mut() {
+            if spill_binary_item(store, tc, item)? {
+                spilled += 1;
+            }
+        }
+    }
+
+    let serialized = match &value {
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string(other)?,
+    };
+    if serialized.len() > threshold_bytes {
+        let content_type = if value.is_string() {
+            "text/plain"
+        } else {
+            "application/json"
+        };
+        let reference = store.store(serialized.as_bytes(), content_type, &tc.name, &tc.id)?;
+        value = json!({ "artifact": reference });
+        spilled += 1;
+    }
+
+    if spilled > 0 {
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("artifact_hint".to_string(), json!(MCP_ARTIFACT_HINT));
+        }
+    }
+    Ok(value)
+}
+
+fn spill_binary_item(
+    store: &RunArtifactStore,
+    tc: &ToolCall,
+    item: &mut Value,
+) -> anyhow::Result<bool> {
+    let Some(obj) = item.as_object() else {
+        return Ok(false);
+    };
+    let kind = obj.get("type").and_then(Value::as_str).unwrap_or_default();
+    let (encoded, mime, uri) = match kind {
+        "image" | "audio" => (
+            obj.get("data").and_then(Value::as_str),
+            obj.get("mimeType").and_then(Value::as_str),
+            None,
+        ),
+        "resource" => {
+            let resource = obj.get("resource");
+            (
+                resource.and_then(|r| r.get("blob")).and_then(Value::as_str),
+                resource
+                    .and_then(|r| r.get("mimeType"))
+                    .and_then(Value::as_str),
+                resource.and_then(|r| r.get("uri")).cloned(),
+            )
+        }
+        _ => return Ok(false),
+    };
+    let Some(encoded) = encoded else {
+        return Ok(false);
+    };
+    // Leave malformed payloads inline; the size check still applies to them.
+    let Ok(bytes) = decode_base64(encoded) else {
+        return Ok(false);
+    };
+    let content_type = mime.unwrap_or("application/octet-stream");
+    let reference = store.store(&bytes, content_type, &tc.name, &tc.id)?;
+    let mut replaced = Map::new();
+    replaced.insert("type".to_string(), json!(kind));
+    if let Some(uri) = uri {
+        replaced.insert("uri".to_string(), uri);
+    }
+    replaced.insert("artifact".to_string(), serde_json::to_value(&reference)?);
+    *item = Value::Object(replaced);
+    Ok(true)
+}
+
+/// Standard-alphabet base64 with optional padding; ASCII whitespace is ignored.
+fn decode_base64(input: &str) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut buf = 0u32;
+    let mut bits = 0u32;
+    let mut padding = 0usize;
+    for b in input.bytes() {
+        let v = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            b if b.is_ascii_whitespace() => continue,
+            other => return Err(anyhow!("invalid base64 byte 0x{other:02x}")),
+        };
+        if padding > 0 {
+            return Err(anyhow!("base64 data after padding"));
+        }
+        buf = (buf << 6) | u32::from(v);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+    if bits >= 6 || padding > 2 {
+        return Err(anyhow!("truncated base64 data"));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn tc() -> ToolCall {
+        ToolCall {
+            id: "tc1".to_string(),
+            name: "mcp.stub.screenshot".to_string(),
+            arguments: json!({}),
+        }
+    }
+
+    #[test]
+    fn decode_base64_handles_padding_and_whitespace() {
+        assert_eq!(decode_base64("aGVsbG8=").expect("decode"), b"hello");
+        assert_eq!(decode_base64("aGVs\nbG8h").expect("decode"), b"hello!");
+        assert_eq!(decode_base64("aGk").expect("decode"), b"hi");
+        assert!(decode_base64("a").is_err());
+        assert!(decode_base64("aGk=x").is_err());
+        assert!(decode_base64("a*b").is_err());
+    }
+
+    #[test]
+    fn binary_items_are_replaced_by_artifact_references() {
+        let tmp = tempdir().expect("tempdir");
+        let store = RunArtifactStore::new(tmp.path(), "run_s", 0);
+        let result = json!({
+            "content": [
+                {"type": "text", "text": "captured"},
+                {"type": "image", "mimeType": "image/png", "data": "aGVsbG8="},
+                {"type": "resource", "resource": {"uri": "file:///r.bin", "blob": "aGk="}}
+            ]
+        });
+        let out = spill_mcp_result(&store, &tc(), result, 64 * 1024).expect("spill");
+        let items = out["content"].as_array().expect("items");
+        assert_eq!(items[0]["text"], "captured");
+        assert_eq!(items[1]["type"], "image");
+        assert_eq!(items[1]["artifact"]["content_type"], "image/png");
+        assert_eq!(items[1]["artifact"]["bytes"], 5);
+        assert!(items[1].get("data").is_none());
+        assert_eq!(items[2]["uri"], "file:///r.bin");
+        assert_eq!(
+            items[2]["artifact"]["content_type"],
+            "application/octet-stream"
+        );
+        assert_eq!(out["artifact_hint"], MCP_ARTIFACT_HINT);
+        let manifest = crate::store::load_run_artifact_manifest(tmp.path(), "run_s")
+            .expect("load")
+            .expect("manifest");
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.total_bytes, 7);
+        assert_eq!(manifest.entries[0].source_tool, "mcp.stub.screenshot");
+    }
+
+    #[test]
+    fn oversized_text_result_is_stored_whole() {
+        let tmp = tempdir().expect("tempdir");
+        let store = RunArtifactStore::new(tmp.path(), "run_t", 0);
+        let result = json!({"content": [{"type": "text", "text": "x".repeat(200)}]});
+        let out = spill_mcp_result(&store, &tc(), result.clone(), 100).expect("spill");
+        assert_eq!(out["artifact"]["content_type"], "application/json");
+        let hash = out["artifact"]["hash"].as_str().expect("hash");
+        let (path, _) = store.resolve(hash).expect("resolve");
+        let stored: Value =
+            serde_json::from_slice(&std::fs::read(path).expect("read")).expect("json");
+        assert_eq!(stored, result);
+
+        let small = json!({"content": [{"type": "text", "text": "ok"}]});
+        let out = spill_mcp_result(&store, &tc(), small.clone(), 100).expect("spill");
+        assert_eq!(out, small);
+    }
+}
@@ -7,6 +7,9 @@ use serde::{Deserialize, Serialize};
 pub enum QueueMessageKind {
     Steer,
     FollowUp,
+    /// Ends the run at the next boundary without further tool calls or
+    /// model turns.
+    Cancel,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -44,15 +47,200 @@ pub struct QueueSubmitRequest {
     pub content: String,
 }
 
+/// What `submit` does when the queue already holds `max_pending` deliveries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOverflowPolicy {
+    /// Drop the oldest pending delivery to make room.
+    #[default]
+    DropOldest,
+    /// Keep the queue as is and drop the new message.
+    RejectNew,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct QueueLimits {
     pub max_message_bytes: usize,
+    /// Pending deliveries allowed at once, counted after steer coalescing.
+    /// Cancel messages are exempt. `0` disables the limit.
+    pub max_pending: usize,
+    pub overflow: QueueOverflowPolicy,
+    /// Deliver consecutive pending steer messages as one, joined with
+    /// newlines.
+    pub coalesce_steer: bool,
 }
 
 impl Default for QueueLimits {
     fn default() -> Self {
         Self {
             max_message_bytes: 1024,
+            max_pending: 32,
+            overflow: QueueOverflowPolicy::DropOldest,
+            coalesce_steer: false,
+        }
+    }
+}
+
+/// One operator message as persisted in the run record. The raw text is never
+/// stored; `text_redacted` has secrets masked and `text_sha256` hashes the text
+/// that was actually delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueLogEntryV1 {
+    pub queue_id: String,
+    pub sequence_no: u64,
+    pub kind: QueueMessageKind,
+    pub text_sha256: String,
+    pub text_redacted: String,
+    pub bytes_loaded: u64,
+    pub bytes_kept: u64,
+    pub truncated: bool,
+    pub enqueued_at: String,
+    pub enqueued_step: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<QueueDeliveryRecordV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replayed_from: Option<String>,
+}
+
+/// Where a message landed: `boundary_seq` counts how many times `boundary` had
+/// been reached in the run (1-based), which is what replay matches on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDeliveryRecordV1 {
+    pub boundary: DeliveryBoundary,
+    pub boundary_seq: u64,
+    pub step: u32,
+    pub delivered_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueReplayDivergenceV1 {
+    pub source_queue_id: String,
+    pub kind: QueueMessageKind,
+    pub expected_boundary: DeliveryBoundary,
+    pub expected_boundary_seq: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_boundary_seq: Option<u64>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueReplayReportV1 {
+    pub source_run_id: String,
+    pub scheduled: usize,
+    pub delivered: usize,
+    #[serde(default)]
+    pub divergences: Vec<QueueReplayDivergenceV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorQueueRunRecordV1 {
+    pub entries: Vec<QueueLogEntryV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay: Option<QueueReplayReportV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InjectedReplayMessage {
+    queue_id: String,
+    scheduled_idx: usize,
+    delivered: bool,
+}
+
+/// Messages from an earlier run's queue log, re-enqueued when the new run
+/// reaches the same boundary occurrence they were originally delivered at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueReplayScript {
+    source_run_id: String,
+    scheduled: Vec<QueueLogEntryV1>,
+    injected: Vec<InjectedReplayMessage>,
+    divergences: Vec<QueueReplayDivergenceV1>,
+}
+
+impl QueueReplayScript {
+    /// Only delivered messages are scheduled; ones that never reached a
+    /// boundary in the source run did not influence it.
+    pub fn from_log(source_run_id: &str, entries: &[QueueLogEntryV1]) -> Self {
+        Self {
+            source_run_id: source_run_id.to_string(),
+            scheduled: entries
+                .iter()
+                .filter(|e| e.delivery.is_some())
+                .cloned()
+                .collect(),
+            injected: Vec::new(),
+            divergences: Vec::new(),
+        }
+    }
+
+    fn expected(&self, idx: usize) -> &QueueDeliveryRecordV1 {
+        self.scheduled[idx]
+            .delivery
+            .as_ref()
+            .expect("only delivered entries are scheduled")
+    }
+
+    fn divergence(
+        &self,
+        idx: usize,
+        actual_boundary_seq: Option<u64>,
+        reason: &str,
+    ) -> QueueReplayDivergenceV1 {
+        let expected = self.expected(idx);
+        QueueReplayDivergenceV1 {
+            source_queue_id: self.scheduled[idx].queue_id.clone(),
+            kind: self.scheduled[idx].kind,
+            expected_boundary: expected.boundary,
+            expected_boundary_seq: expected.boundary_seq,
+            actual_boundary_seq,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn due(&self, boundary: DeliveryBoundary, boundary_seq: u64) -> Vec<usize> {
+        (0..self.scheduled.len())
+            .filter(|idx| {
+                let expected = self.expected(*idx);
+                expected.boundary == boundary
+                    && expected.boundary_seq == boundary_seq
+                    && !self.injected.iter().any(|i| i.scheduled_idx == *idx)
+            })
+            .collect()
+    }
+
+    fn note_delivered(&mut self, queue_id: &str, boundary: DeliveryBoundary, boundary_seq: u64) {
+        let Some(pos) = self.injected.iter().position(|i| i.queue_id == queue_id) else {
+            return;
+        };
+        self.injected[pos].delivered = true;
+        let idx = self.injected[pos].scheduled_idx;
+        let expected = self.expected(idx);
+        if expected.boundary != boundary || expected.boundary_seq != boundary_seq {
+            let divergence =
+                self.divergence(idx, Some(boundary_seq), "delivered_at_different_boundary");
+            self.divergences.push(divergence);
+        }
+    }
+
+    /// Scheduled messages whose boundary the new run never reached (or that
+    /// were re-enqueued but never delivered) are reported, not treated as
+    /// errors: a shorter run is an expected outcome when replaying against
+    /// modified code.
+    pub fn report(&self) -> QueueReplayReportV1 {
+        let mut divergences = self.divergences.clone();
+        for idx in 0..self.scheduled.len() {
+            match self.injected.iter().find(|i| i.scheduled_idx == idx) {
+                None => divergences.push(self.divergence(idx, None, "boundary_not_reached")),
+                Some(i) if !i.delivered => {
+                    divergences.push(self.divergence(idx, None, "not_delivered"))
+                }
+                Some(_) => {}
+            }
+        }
+        QueueReplayReportV1 {
+            source_run_id: self.source_run_id.clone(),
+            scheduled: self.scheduled.len(),
+            delivered: self.injected.iter().filter(|i| i.delivered).count(),
+            divergences,
         }
     }
 }
@@ -62,11 +250,26 @@ pub struct PendingMessageQueue {
     next_sequence_no: u64,
     next_id_counter: u64,
     pending: Vec<QueuedOperatorMessage>,
+    log: Vec<QueueLogEntryV1>,
+    /// Times each boundary has been reached: post_tool, post_step, turn_idle.
+    boundary_counts: [u64; 3],
+    replay: Option<QueueReplayScript>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct QueueSubmitResult {
     pub queued: QueuedOperatorMessage,
+    /// Set when the submission went over `max_pending`.
+    pub overflow: Option<QueueOverflow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueOverflow {
+    pub policy: QueueOverflowPolicy,
+    pub max_pending: usize,
+    /// Messages that will never be delivered: the oldest pending delivery
+    /// (all of its coalesced steers) or, under `RejectNew`, the new message.
+    pub dropped: Vec<QueuedOperatorMessage>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -75,6 +278,8 @@ pub struct QueueDelivery {
     pub delivery_boundary: DeliveryBoundary,
     pub cancelled_remaining_work: bool,
     pub cancelled_reason: Option<&'static str>,
+    /// Later steer messages folded into `message` by `coalesce_steer`.
+    pub coalesced_queue_ids: Vec<String>,
 }
 
 impl PendingMessageQueue {
@@ -83,6 +288,105 @@ impl PendingMessageQueue {
             next_sequence_no: 1,
             next_id_counter: 1,
             pending: Vec::new(),
+            log: Vec::new(),
+            boundary_counts: [0; 3],
+            replay: None,
+        }
+    }
+
+    pub fn with_replay(script: QueueReplayScript) -> Self {
+        Self {
+            replay: Some(script),
+            ..Self::new()
+        }
+    }
+
+    /// Submitted and delivered messages in submission order.
+    pub fn log(&self) -> &[QueueLogEntryV1] {
+        &self.log
+    }
+
+    pub fn replay_report(&self) -> Option<QueueReplayReportV1> {
+        self.replay.as_ref().map(QueueReplayScript::report)
+    }
+
+    /// Queue interactions for the run record; `None` when the run had none.
+    pub fn run_record(&self) -> Option<OperatorQueueRunRecordV1> {
+        if self.log.is_empty() && self.replay.is_none() {
+            return None;
+        }
+        Some(OperatorQueueRunRecordV1 {
+            entries: self.log.clone(),
+            replay: self.replay_report(),
+        })
+    }
+
+    pub fn record_submitted(&mut self, msg: &QueuedOperatorMessage, step: u32, at: &str) {
+        self.log.push(log_entry(msg, step, at));
+    }
+
+    /// Counts one arrival at `boundary` and returns its 1-based occurrence.
+    pub fn reach_boundary(&mut self, boundary: DeliveryBoundary) -> u64 {
+        let slot = &mut self.boundary_counts[boundary_slot(boundary)];
+        *slot = slot.saturating_add(1);
+        *slot
+    }
+
+    /// Re-enqueues replayed messages scheduled for this boundary occurrence and
+    /// returns them so the caller can report the submissions.
+    pub fn submit_replay_due(
+        &mut self,
+        boundary: DeliveryBoundary,
+        boundary_seq: u64,
+        step: u32,
+        at: &str,
+        limits: &QueueLimits,
+    ) -> Vec<QueueSubmitResult> {
+        let Some(script) = self.replay.as_ref() else {
+            return Vec::new();
+        };
+        let due = script
+            .due(boundary, boundary_seq)
+            .into_iter()
+            .map(|idx| (idx, script.scheduled[idx].clone()))
+            .collect::<Vec<_>>();
+        let mut submitted = Vec::new();
+        for (idx, source) in due {
+            let result = self.submit(source.kind, &source.text_redacted, limits);
+            let queued = &result.queued;
+            let mut entry = log_entry(queued, step, at);
+            entry.replayed_from = Some(source.queue_id.clone());
+            self.log.push(entry);
+            if let Some(script) = self.replay.as_mut() {
+                script.injected.push(InjectedReplayMessage {
+                    queue_id: queued.queue_id.clone(),
+                    scheduled_idx: idx,
+                    delivered: false,
+                });
+            }
+            submitted.push(result);
+        }
+        submitted
+    }
+
+    pub fn record_delivered(
+        &mut self,
+        queue_id: &str,
+        boundary: DeliveryBoundary,
+        boundary_seq: u64,
+        step: u32,
+        at: &str,
+    ) {
+        if let Some(entry) = self.log.iter_mut().find(|e| e.queue_id == queue_id) {
+            entry.delivery = Some(QueueDeliveryRecordV1 {
+                boundary,
+                boundary_seq,
+                step,
+                delivered_at: at.to_string(),
+            });
+        }
+        if let Some(script) = self.replay.as_mut() {
+            script.note_delivered(queue_id, boundary, boundary_seq);
         }
     }
 
@@ -118,26 +422,118 @@ impl PendingMessageQueue {
         self.next_id_counter = self.next_id_counter.saturating_add(1);
         self.next_sequence_no = self.next_sequence_no.saturating_add(1);
         self.pending.push(msg.clone());
-        QueueSubmitResult { queued: msg }
+        let overflow = self.enforce_max_pending(&msg, limits);
+        QueueSubmitResult {
+            queued: msg,
+            overflow,
+        }
     }
 
-    pub fn deliver_at_boundary(&mut self, boundary: DeliveryBoundary) -> Option<QueueDelivery> {
+    fn enforce_max_pending(
+        &mut self,
+        submitted: &QueuedOperatorMessage,
+        limits: &QueueLimits,
+    ) -> Option<QueueOverflow> {
+        if limits.max_pending == 0 || submitted.kind == QueueMessageKind::Cancel {
+            return None;
+        }
+        let units = self.delivery_units(limits.coalesce_steer);
+        if units.len() <= limits.max_pending {
+            return None;
+        }
+        let drop_range = match limits.overflow {
+            QueueOverflowPolicy::DropOldest => units[0].clone(),
+            QueueOverflowPolicy::RejectNew => self.pending.len() - 1..self.pending.len(),
+        };
+        Some(QueueOverflow {
+            policy: limits.overflow,
+            max_pending: limits.max_pending,
+            dropped: self.pending.drain(drop_range).collect(),
+        })
+    }
+
+    /// Index ranges of `pending` that each make up one delivery, oldest
+    /// first. Cancels are left out: they never count against the limit.
+    fn delivery_units(&self, coalesce_steer: bool) -> Vec<std::ops::Range<usize>> {
+        let mut units: Vec<std::ops::Range<usize>> = Vec::new();
+        for (idx, msg) in self.pending.iter().enumerate() {
+            match msg.kind {
+                QueueMessageKind::Cancel => {}
+                QueueMessageKind::Steer
+                    if coalesce_steer
+                        && idx > 0
+                        && self.pending[idx - 1].kind == QueueMessageKind::Steer =>
+                {
+                    if let Some(last) = units.last_mut() {
+                        last.end = idx + 1;
+                    }
+                }
+                _ => units.push(idx..idx + 1),
+            }
+        }
+        units
+    }
+
+    pub fn deliver_at_boundary(
+        &mut self,
+        boundary: DeliveryBoundary,
+        limits: &QueueLimits,
+    ) -> Option<QueueDelivery> {
         let idx = self.select_deliverable_index(boundary)?;
-        let msg = self.pending.remove(idx);
-        let is_steer = matches!(msg.kind, QueueMessageKind::Steer);
+        let mut msg = self.pending.remove(idx);
+        let mut coalesced_queue_ids = Vec::new();
+        if limits.coalesce_steer && msg.kind == QueueMessageKind::Steer {
+            while self
+                .pending
+                .get(idx)
+                .is_some_and(|next| next.kind == QueueMessageKind::Steer)
+            {
+                let next = self.pending.remove(idx);
+                msg.content.push('\n');
+                msg.content.push_str(&next.content);
+                msg.bytes_loaded = msg.bytes_loaded.saturating_add(next.bytes_loaded);
+                msg.truncated |= next.truncated;
+                coalesced_queue_ids.push(next.queue_id);
+            }
+            // Each part was capped on submit; the joined message is capped again.
+            let (capped, truncated) =
+                truncate_utf8_to_bytes(&msg.content, limits.max_message_bytes);
+            msg.content = capped;
+            msg.truncated |= truncated;
+            msg.bytes_kept = msg.content.len() as u64;
+        }
+        let cancelled_reason = match msg.kind {
+            QueueMessageKind::Steer => Some("operator_steer"),
+            QueueMessageKind::Cancel => Some("operator_cancel"),
+            QueueMessageKind::FollowUp => None,
+        };
         Some(QueueDelivery {
             message: msg,
             delivery_boundary: boundary,
-            cancelled_remaining_work: is_steer,
-            cancelled_reason: if is_steer {
-                Some("operator_steer")
-            } else {
-                None
-            },
+            cancelled_remaining_work: cancelled_reason.is_some(),
+            cancelled_reason,
+            coalesced_queue_ids,
         })
     }
 
+    pub fn has_pending_cancel(&self) -> bool {
+        self.pending
+            .iter()
+            .any(|m| matches!(m.kind, QueueMessageKind::Cancel))
+    }
+
     fn select_deliverable_index(&self, boundary: DeliveryBoundary) -> Option<usize> {
+        // A cancel beats everything else queued, at any boundary.
+        let earliest_cancel = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| matches!(m.kind, QueueMessageKind::Cancel))
+            .min_by_key(|(_, m)| m.sequence_no)
+            .map(|(idx, _)| idx);
+        if earliest_cancel.is_some() {
+            return earliest_cancel;
+        }
         // Earliest steer always wins when a boundary is eligible.
         let earliest_steer = self
             .pending
@@ -167,6 +563,31 @@ impl Default for PendingMessageQueue {
     }
 }
 
+fn boundary_slot(boundary: DeliveryBoundary) -> usize {
+    match boundary {
+        DeliveryBoundary::PostTool => 0,
+        DeliveryBoundary::PostStep => 1,
+        DeliveryBoundary::TurnIdle => 2,
+    }
+}
+
+fn log_entry(msg: &QueuedOperatorMessage, step: u32, at: &str) -> QueueLogEntryV1 {
+    QueueLogEntryV1 {
+        queue_id: msg.queue_id.clone(),
+        sequence_no: msg.sequence_no,
+        kind: msg.kind,
+        text_sha256: crate::store::sha256_hex(msg.content.as_bytes()),
+        text_redacted: crate::learning::redact_secrets_for_display(&msg.content),
+        bytes_loaded: msg.bytes_loaded,
+        bytes_kept: msg.bytes_kept,
+        truncated: msg.truncated,
+        enqueued_at: at.to_string(),
+        enqueued_step: step,
+        delivery: None,
+        replayed_from: None,
+    }
+}
+
 fn truncate_utf8_to_bytes(input: &str, max_bytes: usize) -> (String, bool) {
     if input.len() <= max_bytes {
         return (input.to_string(), false);
@@ -198,9 +619,11 @@ mod tests {
     fn follow_up_delivers_only_at_turn_idle() {
         let mut q = PendingMessageQueue::new();
         q.submit(QueueMessageKind::FollowUp, "next", &QueueLimits::default());
-        assert!(q.deliver_at_boundary(DeliveryBoundary::PostTool).is_none());
+        assert!(q
+            .deliver_at_boundary(DeliveryBoundary::PostTool, &QueueLimits::default())
+            .is_none());
         let d = q
-            .deliver_at_boundary(DeliveryBoundary::TurnIdle)
+            .deliver_at_boundary(DeliveryBoundary::TurnIdle, &QueueLimits::default())
             .expect("delivery");
         assert_eq!(d.message.kind, QueueMessageKind::FollowUp);
         assert!(!d.cancelled_remaining_work);
@@ -216,28 +639,141 @@ mod tests {
             &QueueLimits::default(),
         );
         let d = q
-            .deliver_at_boundary(DeliveryBoundary::PostTool)
+            .deliver_at_boundary(DeliveryBoundary::PostTool, &QueueLimits::default())
             .expect("delivery");
         assert_eq!(d.message.kind, QueueMessageKind::Steer);
         assert!(d.cancelled_remaining_work);
         assert_eq!(d.cancelled_reason, Some("operator_steer"));
 
         let d2 = q
-            .deliver_at_boundary(DeliveryBoundary::TurnIdle)
+            .deliver_at_boundary(DeliveryBoundary::TurnIdle, &QueueLimits::default())
             .expect("delivery2");
         assert_eq!(d2.message.kind, QueueMessageKind::FollowUp);
     }
 
+    #[test]
+    fn cancel_has_precedence_over_steer_at_any_boundary() {
+        let mut q = PendingMessageQueue::new();
+        q.submit(QueueMessageKind::Steer, "steer", &QueueLimits::default());
+        q.submit(QueueMessageKind::Cancel, "stop", &QueueLimits::default());
+        assert!(q.has_pending_cancel());
+        let d = q
+            .deliver_at_boundary(DeliveryBoundary::TurnIdle, &QueueLimits::default())
+            .expect("delivery");
+        assert_eq!(d.message.kind, QueueMessageKind::Cancel);
+        assert!(d.cancelled_remaining_work);
+        assert_eq!(d.cancelled_reason, Some("operator_cancel"));
+        assert!(!q.has_pending_cancel());
+    }
+
+    fn limits(max_pending: usize, overflow: QueueOverflowPolicy) -> QueueLimits {
+        QueueLimits {
+            max_pending,
+            overflow,
+            coalesce_steer: true,
+            ..QueueLimits::default()
+        }
+    }
+
+    #[test]
+    fn coalesced_steers_deliver_once_joined_with_newlines() {
+        let limits = limits(0, QueueOverflowPolicy::DropOldest);
+        let mut q = PendingMessageQueue::new();
+        q.submit(QueueMessageKind::Steer, "a", &limits);
+        q.submit(QueueMessageKind::Steer, "b", &limits);
+        q.submit(QueueMessageKind::FollowUp, "later", &limits);
+        q.submit(QueueMessageKind::Steer, "c", &limits);
+        let d = q
+            .deliver_at_boundary(DeliveryBoundary::PostTool, &limits)
+            .expect("delivery");
+        assert_eq!(d.message.queue_id, "q1");
+        assert_eq!(d.message.content, "a\nb");
+        assert_eq!(d.message.bytes_kept, 3);
+        assert_eq!(d.coalesced_queue_ids, vec!["q2".to_string()]);
+        // The follow-up separates the runs, so "c" is its own delivery.
+        let d = q
+            .deliver_at_boundary(DeliveryBoundary::PostTool, &limits)
+            .expect("delivery");
+        assert_eq!(d.message.content, "c");
+        assert!(d.coalesced_queue_ids.is_empty());
+    }
+
+    #[test]
+    fn coalesced_steers_are_capped_at_max_message_bytes() {
+        let limits = QueueLimits {
+            max_message_bytes: 8,
+            ..limits(0, QueueOverflowPolicy::DropOldest)
+        };
+        let mut q = PendingMessageQueue::new();
+        q.submit(QueueMessageKind::Steer, "aaaaa", &limits);
+        q.submit(QueueMessageKind::Steer, "bbbbb", &limits);
+        let d = q
+            .deliver_at_boundary(DeliveryBoundary::PostTool, &limits)
+            .expect("delivery");
+        assert_eq!(d.message.content, "aaaaa\nbb");
+        assert_eq!(d.message.bytes_kept, 8);
+        assert_eq!(d.message.bytes_loaded, 10);
+        assert!(d.message.truncated);
+    }
+
+    #[test]
+    fn max_pending_counts_coalesced_steers_once_and_drops_oldest() {
+        let limits = limits(2, QueueOverflowPolicy::DropOldest);
+        let mut q = PendingMessageQueue::new();
+        for text in ["s1", "s2", "s3"] {
+            let r = q.submit(QueueMessageKind::Steer, text, &limits);
+            assert!(r.overflow.is_none());
+        }
+        assert!(q
+            .submit(QueueMessageKind::FollowUp, "f1", &limits)
+            .overflow
+            .is_none());
+        // Cancels never count against the limit and are never dropped.
+        assert!(q
+            .submit(QueueMessageKind::Cancel, "stop", &limits)
+            .overflow
+            .is_none());
+        let overflow = q
+            .submit(QueueMessageKind::FollowUp, "f2", &limits)
+            .overflow
+            .expect("overflow");
+        assert_eq!(overflow.policy, QueueOverflowPolicy::DropOldest);
+        let dropped = overflow
+            .dropped
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(dropped, vec!["s1", "s2", "s3"]);
+        let pending = q
+            .pending()
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(pending, vec!["f1", "stop", "f2"]);
+    }
+
+    #[test]
+    fn reject_new_keeps_the_queue_and_reports_the_new_message() {
+        let limits = limits(1, QueueOverflowPolicy::RejectNew);
+        let mut q = PendingMessageQueue::new();
+        q.submit(QueueMessageKind::FollowUp, "first", &limits);
+        let r = q.submit(QueueMessageKind::FollowUp, "second", &limits);
+        let overflow = r.overflow.expect("overflow");
+        assert_eq!(overflow.dropped, vec![r.queued]);
+        assert_eq!(q.pending().len(), 1);
+        assert_eq!(q.pending()[0].content, "first");
+    }
+
     #[test]
     fn fifo_within_kind_is_preserved() {
         let mut q = PendingMessageQueue::new();
         q.submit(QueueMessageKind::Steer, "a", &QueueLimits::default());
         q.submit(QueueMessageKind::Steer, "b", &QueueLimits::default());
         let d1 = q
-            .deliver_at_boundary(DeliveryBoundary::PostTool)
+            .deliver_at_boundary(DeliveryBoundary::PostTool, &QueueLimits::default())
             .expect("d1");
         let d2 = q
-            .deliver_at_boundary(DeliveryBoundary::PostTool)
+            .deliver_at_boundary(DeliveryBoundary::PostTool, &QueueLimits::default())
             .expect("d2");
         assert_eq!(d1.message.content, "a");
         assert_eq!(d2.message.content, "b");
@@ -253,6 +789,7 @@ mod tests {
             msg,
             &QueueLimits {
                 max_message_bytes: 6,
+                ..QueueLimits::default()
             },
         );
         assert!(r.queued.truncated);
@@ -260,4 +797,156 @@ mod tests {
         assert_eq!(r.queued.bytes_loaded, msg.len() as u64);
         assert!(r.queued.bytes_kept <= 6);
     }
+
+    fn delivered_entry(
+        queue_id: &str,
+        kind: QueueMessageKind,
+        text: &str,
+        boundary: DeliveryBoundary,
+        boundary_seq: u64,
+    ) -> QueueLogEntryV1 {
+        let mut q = PendingMessageQueue::new();
+        let queued = q.submit(kind, text, &QueueLimits::default()).queued;
+        let mut entry = log_entry(&queued, 1, "2026-01-01T00:00:00Z");
+        entry.queue_id = queue_id.to_string();
+        entry.delivery = Some(QueueDeliveryRecordV1 {
+            boundary,
+            boundary_seq,
+            step: 1,
+            delivered_at: "2026-01-01T00:00:01Z".to_string(),
+        });
+        entry
+    }
+
+    #[test]
+    fn log_records_submission_and_delivery_boundary() {
+        let mut q = PendingMessageQueue::new();
+        assert!(q.run_record().is_none());
+        let queued = q
+            .submit(QueueMessageKind::Steer, "stop", &QueueLimits::default())
+            .queued;
+        q.record_submitted(&queued, 2, "t0");
+        q.reach_boundary(DeliveryBoundary::PostTool);
+        let seq = q.reach_boundary(DeliveryBoundary::PostTool);
+        q.record_delivered(&queued.queue_id, DeliveryBoundary::PostTool, seq, 3, "t1");
+
+        let record = q.run_record().expect("record");
+        let entry = &record.entries[0];
+        assert_eq!(entry.kind, QueueMessageKind::Steer);
+        assert_eq!(entry.text_sha256, crate::store::sha256_hex(b"stop"));
+        assert_eq!(entry.text_redacted, "stop");
+        assert_eq!(entry.enqueued_at, "t0");
+        assert_eq!(entry.enqueued_step, 2);
+        assert_eq!(
+            entry.delivery,
+            Some(QueueDeliveryRecordV1 {
+                boundary: DeliveryBoundary::PostTool,
+                boundary_seq: 2,
+                step: 3,
+                delivered_at: "t1".to_string(),
+            })
+        );
+        assert!(record.replay.is_none());
+    }
+
+    #[test]
+    fn log_never_stores_raw_secrets() {
+        let secret = format!("{}{}", "AKIA", "ABCDEFGHIJKLMNOP");
+        let text = format!("use key {secret} please");
+        let mut q = PendingMessageQueue::new();
+        let queued = q
+            .submit(QueueMessageKind::FollowUp, &text, &QueueLimits::default())
+            .queued;
+        q.record_submitted(&queued, 0, "t0");
+        let entry = &q.log()[0];
+        assert_eq!(entry.text_redacted, "use key [REDACTED_SECRET] please");
+        assert_eq!(entry.text_sha256, crate::store::sha256_hex(text.as_bytes()));
+        let json = serde_json::to_string(&q.run_record()).expect("json");
+        assert!(!json.contains(&secret));
+    }
+
+    #[test]
+    fn replay_resubmits_at_matching_boundary_occurrence() {
+        let script = QueueReplayScript::from_log(
+            "run_a",
+            &[delivered_entry(
+                "q7",
+                QueueMessageKind::Steer,
+                "redirect",
+                DeliveryBoundary::PostStep,
+                2,
+            )],
+        );
+        let mut q = PendingMessageQueue::with_replay(script);
+        let limits = QueueLimits::default();
+        let seq = q.reach_boundary(DeliveryBoundary::PostStep);
+        assert!(q
+            .submit_replay_due(DeliveryBoundary::PostStep, seq, 1, "t", &limits)
+            .is_empty());
+        let seq = q.reach_boundary(DeliveryBoundary::PostStep);
+        let due = q.submit_replay_due(DeliveryBoundary::PostStep, seq, 2, "t", &limits);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].queued.content, "redirect");
+        let delivered = q
+            .deliver_at_boundary(DeliveryBoundary::PostStep, &QueueLimits::default())
+            .expect("delivery");
+        q.record_delivered(
+            &delivered.message.queue_id,
+            DeliveryBoundary::PostStep,
+            seq,
+            2,
+            "t",
+        );
+
+        let record = q.run_record().expect("record");
+        assert_eq!(record.entries[0].replayed_from.as_deref(), Some("q7"));
+        let report = record.replay.expect("report");
+        assert_eq!(report.source_run_id, "run_a");
+        assert_eq!((report.scheduled, report.delivered), (1, 1));
+        assert!(report.divergences.is_empty());
+    }
+
+    #[test]
+    fn replay_reports_unreached_boundaries_as_divergences() {
+        let mut undelivered = delivered_entry(
+            "q2",
+            QueueMessageKind::FollowUp,
+            "never delivered",
+            DeliveryBoundary::TurnIdle,
+            1,
+        );
+        undelivered.delivery = None;
+        let script = QueueReplayScript::from_log(
+            "run_a",
+            &[
+                delivered_entry(
+                    "q1",
+                    QueueMessageKind::FollowUp,
+                    "later",
+                    DeliveryBoundary::TurnIdle,
+                    3,
+                ),
+                undelivered,
+            ],
+        );
+        let mut q = PendingMessageQueue::with_replay(script);
+        let seq = q.reach_boundary(DeliveryBoundary::TurnIdle);
+        assert!(q
+            .submit_replay_due(
+                DeliveryBoundary::TurnIdle,
+                seq,
+                1,
+                "t",
+                &QueueLimits::default()
+            )
+            .is_empty());
+
+        let report = q.replay_report().expect("report");
+        assert_eq!((report.scheduled, report.delivered), (1, 0));
+        assert_eq!(report.divergences.len(), 1);
+        let divergence = &report.divergences[0];
+        assert_eq!(divergence.source_queue_id, "q1");
+        assert_eq!(divergence.expected_boundary_seq, 3);
+        assert_eq!(divergence.reason, "boundary_not_reached");
+    }
 }
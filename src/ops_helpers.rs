@@ -3,10 +3,14 @@ use anyhow::anyhow;
 use crate::compaction::CompactionSettings;
 use crate::hooks;
 use crate::hooks::config::HooksMode;
-use crate::hooks::protocol::{PreModelCompactionPayload, PreModelPayload, ToolResultPayload};
+use crate::hooks::protocol::{
+    PreModelCompactionPayload, PreModelPayload, PreToolPayload, ToolResultPayload,
+};
 use crate::hooks::runner::{
-    make_pre_model_input, make_tool_result_input, HookManager, HookRuntimeConfig,
+    make_post_run_input, make_pre_model_input, make_pre_tool_input, make_tool_result_input,
+    HookManager, HookRuntimeConfig,
 };
+use crate::hooks::validate::HooksValidateExit;
 use crate::trust::policy::{McpAllowSummary, Policy};
 use crate::RunArgs;
 
@@ -50,6 +54,38 @@ pub(crate) fn handle_hooks_list(path: &std::path::Path) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub(crate) async fn handle_hooks_validate(
+    path: &std::path::Path,
+    workdir: &std::path::Path,
+    dry_fire: Option<hooks::config::HookStage>,
+    hook: Option<&str>,
+    run: &RunArgs,
+) -> anyhow::Result<HooksValidateExit> {
+    let report = hooks::validate::validate_hooks_config(path, workdir);
+    print!("{}", hooks::validate::render_validation_report(&report));
+    let exit = report.exit();
+    let Some(stage) = dry_fire else {
+        return Ok(exit);
+    };
+    if exit == HooksValidateExit::Invalid {
+        println!("dry-fire skipped: hooks config is invalid");
+        return Ok(exit);
+    }
+    let results = hooks::validate::dry_fire_hooks(
+        path,
+        stage,
+        hook,
+        run.hooks_timeout_ms,
+        run.hooks_max_stdout_bytes,
+    )
+    .await?;
+    print!("{}", hooks::validate::render_dry_fire_results(&results));
+    if results.iter().any(|r| !r.ok) {
+        return Ok(HooksValidateExit::DryFireFailed);
+    }
+    Ok(exit)
+}
+
 pub(crate) fn policy_doctor_output(policy_path: &std::path::Path) -> anyhow::Result<String> {
     let p = Policy::from_path(policy_path)?;
     let mut out = format!(
@@ -96,6 +132,9 @@ pub(crate) async fn handle_hooks_doctor(
         strict: true,
         timeout_ms: run.hooks_timeout_ms,
         max_stdout_bytes: run.hooks_max_stdout_bytes,
+        max_invocations_per_run: 0,
+        max_cumulative_ms: 0,
+        budget_strict: false,
     })?;
     if manager.list().is_empty() {
         println!("no hooks configured");
@@ -110,6 +149,7 @@ pub(crate) async fn handle_hooks_doctor(
                 stream: false,
                 compaction: PreModelCompactionPayload::from(&CompactionSettings {
                     max_context_chars: run.max_context_chars,
+                    max_context_tokens: None,
                     mode: run.compaction_mode,
                     keep_last: run.compaction_keep_last,
                     tool_result_persist: run.tool_result_persist,
@@ -128,13 +168,51 @@ pub(crate) async fn handle_hooks_doctor(
                 strict: true,
                 timeout_ms: manager.timeout_ms,
                 max_stdout_bytes: manager.max_stdout_bytes,
+                max_invocations_per_run: manager.max_invocations_per_run,
+                max_cumulative_ms: manager.max_cumulative_ms,
+                budget_strict: manager.budget_strict,
                 config_path: manager.config_path.clone(),
                 hooks: vec![hook.clone()],
+                config_hash_hex: manager.config_hash_hex.clone(),
+                budget_usage: manager.budget_usage.clone(),
+                current_dir: manager.current_dir.clone(),
             };
             one.run_pre_model_hooks(input)
                 .await
                 .map_err(|e| anyhow!(e.message))?;
         }
+        if hook.has_stage(hooks::config::HookStage::PreTool) {
+            let payload = PreToolPayload {
+                tool_call_id: "doctor_tc".to_string(),
+                tool_name: "read_file".to_string(),
+                arguments: serde_json::json!({"path": "README.md"}),
+            };
+            let input = make_pre_tool_input(
+                &run_id,
+                0,
+                &provider,
+                run.model.as_deref().unwrap_or("doctor"),
+                &run.workdir,
+                serde_json::to_value(&payload)?,
+            );
+            let one = HookManager {
+                mode: manager.mode,
+                strict: true,
+                timeout_ms: manager.timeout_ms,
+                max_stdout_bytes: manager.max_stdout_bytes,
+                max_invocations_per_run: manager.max_invocations_per_run,
+                max_cumulative_ms: manager.max_cumulative_ms,
+                budget_strict: manager.budget_strict,
+                config_path: manager.config_path.clone(),
+                hooks: vec![hook.clone()],
+                config_hash_hex: manager.config_hash_hex.clone(),
+                budget_usage: manager.budget_usage.clone(),
+                current_dir: manager.current_dir.clone(),
+            };
+            one.run_pre_tool_hooks(input, "read_file", &payload.arguments, |_| Ok(()))
+                .await
+                .map_err(|e| anyhow!(e.message))?;
+        }
         if hook.has_stage(hooks::config::HookStage::ToolResult) {
             let payload = ToolResultPayload {
                 tool_call_id: "doctor_tc".to_string(),
@@ -156,13 +234,46 @@ pub(crate) async fn handle_hooks_doctor(
                 strict: true,
                 timeout_ms: manager.timeout_ms,
                 max_stdout_bytes: manager.max_stdout_bytes,
+                max_invocations_per_run: manager.max_invocations_per_run,
+                max_cumulative_ms: manager.max_cumulative_ms,
+                budget_strict: manager.budget_strict,
                 config_path: manager.config_path.clone(),
                 hooks: vec![hook.clone()],
+                config_hash_hex: manager.config_hash_hex.clone(),
+                budget_usage: manager.budget_usage.clone(),
+                current_dir: manager.current_dir.clone(),
             };
             one.run_tool_result_hooks(input, "read_file", "sample", false)
                 .await
                 .map_err(|e| anyhow!(e.message))?;
         }
+        if hook.has_stage(hooks::config::HookStage::PostRun) {
+            let input = make_post_run_input(
+                &run_id,
+                0,
+                &provider,
+                run.model.as_deref().unwrap_or("doctor"),
+                &run.workdir,
+                serde_json::to_value(hooks::validate::synthetic_post_run_payload(&run_id))?,
+            );
+            let one = HookManager {
+                mode: manager.mode,
+                strict: true,
+                timeout_ms: manager.timeout_ms,
+                max_stdout_bytes: manager.max_stdout_bytes,
+                max_invocations_per_run: manager.max_invocations_per_run,
+                max_cumulative_ms: manager.max_cumulative_ms,
+                budget_strict: manager.budget_strict,
+                config_path: manager.config_path.clone(),
+                hooks: vec![hook.clone()],
+                config_hash_hex: manager.config_hash_hex.clone(),
+                budget_usage: manager.budget_usage.clone(),
+                current_dir: manager.current_dir.clone(),
+            };
+            if let Some(e) = one.run_post_run_hooks(input).await.error {
+                return Err(anyhow!(e));
+            }
+        }
         println!("OK: hook {}", hook.cfg.name);
     }
     Ok(())
@@ -0,0 +1,170 @@
+//! OTLP/HTTP JSON transport. Each export batch runs on a short-lived thread
+//! with its own runtime, so it can be called from the synchronous event sink
+//! without blocking on the agent's executor.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+
+use super::{encode_otlp_json, SpanExporter, SpanRecord};
+
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+const OTLP_EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(super) struct OtlpHttpExporter {
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+impl OtlpHttpExporter {
+    pub(super) fn new(endpoint: &str, headers: &[(String, String)]) -> anyhow::Result<Self> {
+        let endpoint = endpoint.trim().trim_end_matches('/');
+        if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) {
+            return Err(anyhow!(
+                "--otlp-endpoint must be an http:// or https:// URL, got '{endpoint}'"
+            ));
+        }
+        let url = if endpoint.ends_with(OTLP_TRACES_PATH) {
+            endpoint.to_string()
+        } else {
+            format!("{endpoint}{OTLP_TRACES_PATH}")
+        };
+        Ok(Self {
+            url,
+            headers: headers.to_vec(),
+        })
+    }
+}
+
+impl SpanExporter for OtlpHttpExporter {
+    fn export(&mut self, spans: &[SpanRecord]) -> anyhow::Result<()> {
+        if spans.is_empty() {
+            return Ok(());
+        }
+        let body = encode_otlp_json(spans);
+        let url = self.url.clone();
+        let headers = self.headers.clone();
+        std::thread::spawn(move || -> anyhow::Result<()> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .context("failed to start otlp export runtime")?;
+            runtime.block_on(async move {
+                let mut request = reqwest::Client::builder()
+                    .timeout(OTLP_EXPORT_TIMEOUT)
+                    .build()?
+                    .post(&url)
+                    .json(&body);
+                for (key, value) in &headers {
+                    request = request.header(key, value);
+                }
+                let response = request.send().await?;
+                if !response.status().is_success() {
+                    return Err(anyhow!("otlp export returned HTTP {}", response.status()));
+                }
+                Ok(())
+            })
+        })
+        .join()
+        .map_err(|_| anyhow!("otlp export thread panicked"))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::otlp::{SpanKind, SpanStatus};
+
+    fn span() -> SpanRecord {
+        SpanRecord {
+            trace_id: "0".repeat(32),
+            span_id: "1".repeat(16),
+            parent_span_id: None,
+            name: "agent.run".to_string(),
+            kind: SpanKind::Internal,
+            start_unix_nano: 1,
+            end_unix_nano: 2,
+            attributes: Default::default(),
+            status: SpanStatus::Ok,
+        }
+    }
+
+    /// Accepts one request, replies with `status`, and returns the raw request.
+    fn one_shot_server(status: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).expect("read");
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(head_end) = text.find("\r\n\r\n") {
+                    let len = text[..head_end]
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= head_end + 4 + len {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            let reply =
+                format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+            stream.write_all(reply.as_bytes()).expect("write");
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (format!("http://{addr}"), handle)
+    }
+
+    #[test]
+    fn export_posts_otlp_json_to_traces_path() {
+        let (endpoint, server) = one_shot_server("200 OK");
+        let headers = [("x-api-key".to_string(), "k1".to_string())];
+        let mut exporter = OtlpHttpExporter::new(&endpoint, &headers).expect("exporter");
+        exporter.export(&[span()]).expect("export");
+        let request = server.join().expect("server");
+        assert!(request.starts_with("POST /v1/traces "), "{request}");
+        assert!(request.contains("x-api-key: k1"), "{request}");
+        assert!(request.contains("\"resourceSpans\""));
+        assert!(request.contains("\"agent.run\""));
+    }
+
+    #[test]
+    fn export_reports_http_errors() {
+        let (endpoint, server) = one_shot_server("503 Service Unavailable");
+        let mut exporter = OtlpHttpExporter::new(&endpoint, &[]).expect("exporter");
+        let err = exporter.export(&[span()]).expect_err("503");
+        assert!(err.to_string().contains("503"), "{err}");
+        server.join().expect("server");
+    }
+
+    #[test]
+    fn endpoint_gets_traces_path_once() {
+        assert_eq!(
+            OtlpHttpExporter::new("http://collector:4318/", &[])
+                .expect("exporter")
+                .url,
+            "http://collector:4318/v1/traces"
+        );
+        assert_eq!(
+            OtlpHttpExporter::new("https://c.example/v1/traces", &[])
+                .expect("exporter")
+                .url,
+            "https://c.example/v1/traces"
+        );
+        assert!(OtlpHttpExporter::new("collector:4318", &[]).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Local span export: one `openagent.span.v1` JSON object per line, for
+//! pipelines that collect files rather than run a collector.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde_json::{json, Map, Value};
+
+use super::{SpanAttributeValue, SpanExporter, SpanKind, SpanRecord, SpanStatus};
+
+pub const SPAN_JSONL_SCHEMA_V1: &str = "openagent.span.v1";
+
+pub(super) struct JsonlSpanExporter {
+    path: PathBuf,
+}
+
+impl JsonlSpanExporter {
+    pub(super) fn new(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl SpanExporter for JsonlSpanExporter {
+    fn export(&mut self, spans: &[SpanRecord]) -> anyhow::Result<()> {
+        let mut out = String::new();
+        for span in spans {
+            out.push_str(&serde_json::to_string(&span_jsonl_value(span))?);
+            out.push('\n');
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open spans file {}", self.path.display()))?;
+        file.write_all(out.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// The documented line format: ids as in OTLP, `kind` and `status` as
+/// lowercase labels, attributes as a plain JSON object.
+pub fn span_jsonl_value(span: &SpanRecord) -> Value {
+    let attributes = span
+        .attributes
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                SpanAttributeValue::Str(s) => json!(s),
+                SpanAttributeValue::Int(v) => json!(v),
+                SpanAttributeValue::Bool(v) => json!(v),
+            };
+            (key.clone(), value)
+        })
+        .collect::<Map<_, _>>();
+    let (status, status_message) = match &span.status {
+        SpanStatus::Unset => ("unset", None),
+        SpanStatus::Ok => ("ok", None),
+        SpanStatus::Error(message) => ("error", Some(message.as_str())),
+    };
+    json!({
+        "schema": SPAN_JSONL_SCHEMA_V1,
+        "trace_id": span.trace_id,
+        "span_id": span.span_id,
+        "parent_span_id": span.parent_span_id,
+        "name": span.name,
+        "kind": match span.kind {
+            SpanKind::Internal => "internal",
+            SpanKind::Client => "client",
+        },
+        "start_unix_nano": span.start_unix_nano,
+        "end_unix_nano": span.end_unix_nano,
+        "status": status,
+        "status_message": status_message,
+        "attributes": attributes,
+    })
+}
@@ -8,11 +8,93 @@ use serde_json::{Map, Value};
 pub const PLAN_SCHEMA_VERSION: &str = "openagent.plan.v1";
 pub const PLANNER_HANDOFF_HEADER: &str = "PLANNER HANDOFF (openagent.plan.v1)";
 pub const STEP_RESULT_SCHEMA_VERSION: &str = "openagent.step_result.v1";
+/// Adds `next_step_ids` fan-out and a `retry_of` counter on retries. The
+/// worker picks the version per result; v1 results are still accepted.
+pub const STEP_RESULT_SCHEMA_VERSION_V2: &str = "openagent.step_result.v2";
+/// Retry transitions allowed per plan step before the run fails.
+pub const MAX_STEP_RETRIES: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanStepTools {
     pub step_id: String,
     pub intended_tools: Vec<String>,
+    pub arg_constraints: Vec<ArgConstraint>,
+}
+
+/// Limit on one argument of the calls a plan step makes to `tool`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArgConstraint {
+    /// `<step id>.A<n>`, assigned during normalization.
+    pub id: String,
+    pub tool: String,
+    /// Dotted path into the call arguments, e.g. `path` or `cmd`.
+    pub field: String,
+    pub matcher: ArgMatcher,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgMatcher {
+    /// Workdir-relative path glob.
+    Glob(String),
+    Equals(Value),
+    OneOf(Vec<Value>),
+}
+
+impl ArgConstraint {
+    /// Why `arguments` break this constraint, or `None` when they satisfy
+    /// it. Callers check `tool` first; constraints only bind their own tool.
+    pub fn violation(&self, arguments: &Value) -> Option<String> {
+        let actual = self
+            .field
+            .split('.')
+            .try_fold(arguments, |value, key| value.get(key));
+        let Some(actual) = actual else {
+            return Some(format!(
+                "argument '{}' is required by plan constraint {}",
+                self.field, self.id
+            ));
+        };
+        let matches = match &self.matcher {
+            ArgMatcher::Glob(pattern) => actual
+                .as_str()
+                .is_some_and(|path| workdir_path_matches_glob(pattern, path)),
+            ArgMatcher::Equals(expected) => actual == expected,
+            ArgMatcher::OneOf(allowed) => allowed.contains(actual),
+        };
+        if matches {
+            return None;
+        }
+        let expected = match &self.matcher {
+            ArgMatcher::Glob(pattern) => format!("glob {pattern}"),
+            ArgMatcher::Equals(expected) => format!("== {expected}"),
+            ArgMatcher::OneOf(allowed) => {
+                format!("one of {}", Value::Array(allowed.clone()))
+            }
+        };
+        Some(format!(
+            "argument '{}' = {} violates plan constraint {} ({} must match {})",
+            self.field, actual, self.id, self.tool, expected
+        ))
+    }
+}
+
+/// Paths that are absolute or climb out with `..` never match, so a glob
+/// like `src/**` cannot be escaped with `src/../secret`.
+fn workdir_path_matches_glob(pattern: &str, path: &str) -> bool {
+    use std::path::{Component, Path};
+    if Path::new(path)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return false;
+    }
+    globset::Glob::new(pattern.trim_start_matches("./"))
+        .map(|glob| {
+            glob.compile_matcher()
+                .is_match(crate::tools::normalize_allowlist_path(path))
+        })
+        .unwrap_or(false)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
@@ -122,7 +204,10 @@ Return final output as JSON only with fields:\n\
   \"next_step_id\": \"optional next step id\",\n\
   \"notes\": \"optional brief note\",\n\
   \"user_output\": \"optional final user-facing response text\"\n\
-}}"
+}}\n\
+With schema_version \"{STEP_RESULT_SCHEMA_VERSION_V2}\" you may instead set \"next_step_ids\": \
+[\"step ids to run next, in this order\"], and status retry must carry \
+\"retry_of\": <attempt number, 1-{MAX_STEP_RETRIES}>."
     ))
 }
 
@@ -151,9 +236,23 @@ pub fn extract_plan_step_tools(plan_json: &Value) -> anyhow::Result<Vec<PlanStep
                     .collect::<Vec<_>>()
             })
             .unwrap_or_default();
+        let arg_constraints = step_obj
+            .get("arg_constraints")
+            .and_then(Value::as_array)
+            .map(|arr| {
+                arr.iter()
+                    .map(|c| {
+                        let id = c.get("id").and_then(Value::as_str).unwrap_or_default();
+                        parse_arg_constraint(c, id)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
         out.push(PlanStepTools {
             step_id,
             intended_tools: tools,
+            arg_constraints,
         });
     }
     Ok(out)
@@ -245,10 +344,20 @@ pub fn normalize_plan_json(raw: &str) -> anyhow::Result<Value> {
             .into_iter()
             .map(Value::String)
             .collect::<Vec<_>>();
+        let step_id = format!("S{}", idx + 1);
+        let arg_constraints = step_obj
+            .get("arg_constraints")
+            .map(|v| value_arg_constraints_array(v, &step_id))
+            .transpose()?
+            .unwrap_or_default();
         let mut out_step = Map::new();
-        out_step.insert("id".to_string(), Value::String(format!("S{}", idx + 1)));
+        out_step.insert("id".to_string(), Value::String(step_id));
         out_step.insert("summary".to_string(), Value::String(summary));
         out_step.insert("intended_tools".to_string(), Value::Array(intended_tools));
+        // Only present when used, so plans without constraints keep their hash.
+        if !arg_constraints.is_empty() {
+            out_step.insert("arg_constraints".to_string(), Value::Array(arg_constraints));
+        }
         out_step.insert("done_criteria".to_string(), Value::Array(done_criteria));
         out_step.insert("verifier_checks".to_string(), Value::Array(verifier_checks));
         steps.push(Value::Object(out_step));
@@ -279,9 +388,10 @@ pub fn normalize_worker_step_result(raw: &str, plan_json: &Value) -> anyhow::Res
         .get("schema_version")
         .and_then(Value::as_str)
         .ok_or_else(|| anyhow!("worker step result missing schema_version"))?;
-    if schema != STEP_RESULT_SCHEMA_VERSION {
+    let v2 = schema == STEP_RESULT_SCHEMA_VERSION_V2;
+    if schema != STEP_RESULT_SCHEMA_VERSION && !v2 {
         return Err(anyhow!(
-            "worker step result schema_version must be {STEP_RESULT_SCHEMA_VERSION}, got {schema}"
+            "worker step result schema_version must be {STEP_RESULT_SCHEMA_VERSION} or {STEP_RESULT_SCHEMA_VERSION_V2}, got {schema}"
         ));
     }
     let step_id = obj
@@ -335,12 +445,51 @@ pub fn normalize_worker_step_result(raw: &str, plan_json: &Value) -> anyhow::Res
             ));
         }
     }
+    let mut next_step_ids = Vec::new();
+    let mut retry_of = None;
+    if v2 {
+        next_step_ids = obj
+            .get("next_step_ids")
+            .map(value_string_array)
+            .transpose()?
+            .unwrap_or_default();
+        if !next_step_ids.is_empty() && next_step_id.is_some() {
+            return Err(anyhow!(
+                "worker step result cannot set both next_step_id and next_step_ids"
+            ));
+        }
+        if let Some(unknown) = next_step_ids
+            .iter()
+            .find(|id| !allowed_steps.iter().any(|s| s == *id))
+        {
+            return Err(anyhow!(
+                "worker next_step_ids entry '{unknown}' not present in plan (allowed: {})",
+                allowed_steps.join(", ")
+            ));
+        }
+        retry_of = obj
+            .get("retry_of")
+            .map(|v| {
+                v.as_u64()
+                    .filter(|n| (1..=u64::from(MAX_STEP_RETRIES)).contains(n))
+                    .ok_or_else(|| {
+                        anyhow!("worker retry_of must be an integer from 1 to {MAX_STEP_RETRIES}")
+                    })
+            })
+            .transpose()?;
+        if status == "retry" && retry_of.is_none() {
+            return Err(anyhow!("worker retry result missing retry_of"));
+        }
+        if status != "retry" && retry_of.is_some() {
+            return Err(anyhow!("worker retry_of is only valid with status retry"));
+        }
+    }
     let notes = obj.get("notes").and_then(Value::as_str).map(str::to_string);
 
     let mut normalized = Map::new();
     normalized.insert(
         "schema_version".to_string(),
-        Value::String(STEP_RESULT_SCHEMA_VERSION.to_string()),
+        Value::String(schema.to_string()),
     );
     normalized.insert("step_id".to_string(), Value::String(step_id.to_string()));
     normalized.insert("status".to_string(), Value::String(status.to_string()));
@@ -348,6 +497,15 @@ pub fn normalize_worker_step_result(raw: &str, plan_json: &Value) -> anyhow::Res
     if let Some(next) = next_step_id {
         normalized.insert("next_step_id".to_string(), Value::String(next));
     }
+    if !next_step_ids.is_empty() {
+        normalized.insert(
+            "next_step_ids".to_string(),
+            Value::Array(next_step_ids.into_iter().map(Value::String).collect()),
+        );
+    }
+    if let Some(n) = retry_of {
+        normalized.insert("retry_of".to_string(), Value::from(n));
+    }
     if let Some(n) = notes {
         normalized.insert("notes".to_string(), Value::String(n));
     }
@@ -486,6 +644,72 @@ fn value_intended_tools_array(value: &Value) -> anyhow::Result<Vec<Value>> {
     Ok(out)
 }
 
+fn value_arg_constraints_array(value: &Value, step_id: &str) -> anyhow::Result<Vec<Value>> {
+    let arr = value
+        .as_array()
+        .ok_or_else(|| anyhow!("planner arg_constraints must be an array"))?;
+    let mut out = Vec::with_capacity(arr.len());
+    for (idx, item) in arr.iter().enumerate() {
+        let constraint = parse_arg_constraint(item, &format!("{step_id}.A{}", idx + 1))?;
+        let (key, expected) = match constraint.matcher {
+            ArgMatcher::Glob(pattern) => ("glob", Value::String(pattern)),
+            ArgMatcher::Equals(expected) => ("equals", expected),
+            ArgMatcher::OneOf(allowed) => ("one_of", Value::Array(allowed)),
+        };
+        let mut obj = Map::new();
+        obj.insert("id".to_string(), Value::String(constraint.id));
+        obj.insert("tool".to_string(), Value::String(constraint.tool));
+        obj.insert("field".to_string(), Value::String(constraint.field));
+        obj.insert(key.to_string(), expected);
+        out.push(Value::Object(obj));
+    }
+    Ok(out)
+}
+
+fn parse_arg_constraint(value: &Value, id: &str) -> anyhow::Result<ArgConstraint> {
+    let obj = value
+        .as_object()
+        .ok_or_else(|| anyhow!("planner arg constraint entries must be objects"))?;
+    let tool = obj
+        .get("tool")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("planner arg constraint {id} missing tool"))?;
+    let field = obj
+        .get("field")
+        .and_then(Value::as_str)
+        .filter(|f| !f.is_empty())
+        .ok_or_else(|| anyhow!("planner arg constraint {id} missing field"))?;
+    let mut matchers = Vec::new();
+    if let Some(pattern) = obj.get("glob") {
+        let pattern = pattern
+            .as_str()
+            .ok_or_else(|| anyhow!("planner arg constraint {id} glob must be a string"))?;
+        globset::Glob::new(pattern.trim_start_matches("./"))
+            .with_context(|| format!("planner arg constraint {id} has an invalid glob"))?;
+        matchers.push(ArgMatcher::Glob(pattern.to_string()));
+    }
+    if let Some(expected) = obj.get("equals") {
+        matchers.push(ArgMatcher::Equals(expected.clone()));
+    }
+    if let Some(allowed) = obj.get("one_of") {
+        let allowed = allowed
+            .as_array()
+            .ok_or_else(|| anyhow!("planner arg constraint {id} one_of must be an array"))?;
+        matchers.push(ArgMatcher::OneOf(allowed.clone()));
+    }
+    if matchers.len() != 1 {
+        return Err(anyhow!(
+            "planner arg constraint {id} needs exactly one of glob, equals, one_of"
+        ));
+    }
+    Ok(ArgConstraint {
+        id: id.to_string(),
+        tool: tool.to_string(),
+        field: field.to_string(),
+        matcher: matchers.remove(0),
+    })
+}
+
 pub fn hash_canonical_json(value: &Value) -> anyhow::Result<String> {
     let canonical = canonical_json_string(value)?;
     Ok(crate::store::sha256_hex(canonical.as_bytes()))
@@ -517,8 +741,9 @@ fn canonicalize(value: &Value) -> Value {
 #[cfg(test)]
 mod tests {
     use super::{
-        hash_canonical_json, normalize_plan_json, normalize_planner_output,
-        normalize_worker_step_result, PlannerOutput, STEP_RESULT_SCHEMA_VERSION,
+        extract_plan_step_tools, hash_canonical_json, normalize_plan_json,
+        normalize_planner_output, normalize_worker_step_result, PlannerOutput,
+        STEP_RESULT_SCHEMA_VERSION, STEP_RESULT_SCHEMA_VERSION_V2,
     };
 
     #[test]
@@ -585,4 +810,147 @@ mod tests {
             Some("done")
         );
     }
+
+    fn three_step_plan() -> serde_json::Value {
+        let step = |id: &str| serde_json::json!({"id":id,"summary":id,"intended_tools":[],"done_criteria":[],"verifier_checks":[]});
+        serde_json::json!({
+            "schema_version":"openagent.plan.v1",
+            "goal":"g",
+            "assumptions":[],
+            "steps":[step("S1"), step("S2"), step("S3")],
+            "risks":[],
+            "success_criteria":[]
+        })
+    }
+
+    #[test]
+    fn v2_step_result_keeps_fan_out_and_retry_counter() {
+        let plan = three_step_plan();
+        let fan_out = format!(
+            r#"{{"schema_version":"{STEP_RESULT_SCHEMA_VERSION_V2}","step_id":"S1","status":"done","next_step_ids":["S3","S2"]}}"#
+        );
+        let normalized = normalize_worker_step_result(&fan_out, &plan).expect("fan-out");
+        assert_eq!(
+            normalized["schema_version"].as_str(),
+            Some(STEP_RESULT_SCHEMA_VERSION_V2)
+        );
+        assert_eq!(normalized["next_step_ids"], serde_json::json!(["S3", "S2"]));
+
+        let retry = format!(
+            r#"{{"schema_version":"{STEP_RESULT_SCHEMA_VERSION_V2}","step_id":"S2","status":"retry","retry_of":1}}"#
+        );
+        let normalized = normalize_worker_step_result(&retry, &plan).expect("retry");
+        assert_eq!(normalized["retry_of"].as_u64(), Some(1));
+    }
+
+    #[test]
+    fn v2_step_result_rejects_bad_fan_out_and_retry_counters() {
+        let plan = three_step_plan();
+        let check = |body: &str, expected: &str| {
+            let raw = format!(r#"{{"schema_version":"{STEP_RESULT_SCHEMA_VERSION_V2}",{body}}}"#);
+            let err = normalize_worker_step_result(&raw, &plan).expect_err(body);
+            assert!(err.to_string().contains(expected), "{err}");
+        };
+        check(
+            r#""step_id":"S1","status":"done","next_step_ids":["S2","S9"]"#,
+            "next_step_ids entry 'S9' not present in plan",
+        );
+        check(
+            r#""step_id":"S1","status":"done","next_step_id":"S2","next_step_ids":["S3"]"#,
+            "cannot set both next_step_id and next_step_ids",
+        );
+        check(r#""step_id":"S1","status":"retry""#, "missing retry_of");
+        check(
+            r#""step_id":"S1","status":"retry","retry_of":3"#,
+            "retry_of must be an integer from 1 to 2",
+        );
+        check(
+            r#""step_id":"S1","status":"done","retry_of":1"#,
+            "only valid with status retry",
+        );
+    }
+
+    #[test]
+    fn v1_step_result_ignores_v2_fields() {
+        let raw = format!(
+            r#"{{"schema_version":"{STEP_RESULT_SCHEMA_VERSION}","step_id":"S1","status":"retry","next_step_ids":["S9"],"retry_of":7}}"#
+        );
+        let normalized = normalize_worker_step_result(&raw, &three_step_plan()).expect("v1");
+        assert!(normalized.get("next_step_ids").is_none());
+        assert!(normalized.get("retry_of").is_none());
+    }
+
+    fn constrained_plan(constraints: &str) -> String {
+        format!(
+            r#"{{
+              "schema_version":"openagent.plan.v1",
+              "goal":"g",
+              "steps":[{{"summary":"s1","intended_tools":[],"arg_constraints":{constraints}}}]
+            }}"#
+        )
+    }
+
+    #[test]
+    fn arg_constraints_get_step_scoped_ids_and_change_the_plan_hash() {
+        let raw = constrained_plan(
+            r#"[{"tool":"read_file","field":"path","glob":"src/**"},
+                {"tool":"shell","field":"cmd","one_of":["cargo"]}]"#,
+        );
+        let plan = normalize_plan_json(&raw).expect("normalize");
+        let steps = extract_plan_step_tools(&plan).expect("steps");
+        let ids = steps[0]
+            .arg_constraints
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["S1.A1", "S1.A2"]);
+
+        let loosened = normalize_plan_json(&constrained_plan(
+            r#"[{"tool":"read_file","field":"path","glob":"**"},
+                {"tool":"shell","field":"cmd","one_of":["cargo"]}]"#,
+        ))
+        .expect("normalize");
+        let unconstrained = normalize_plan_json(&constrained_plan("[]")).expect("normalize");
+        let hashes =
+            [&plan, &loosened, &unconstrained].map(|p| hash_canonical_json(p).expect("hash"));
+        assert_ne!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+        assert!(unconstrained["steps"][0].get("arg_constraints").is_none());
+    }
+
+    #[test]
+    fn arg_constraint_needs_exactly_one_matcher() {
+        let err = normalize_plan_json(&constrained_plan(
+            r#"[{"tool":"shell","field":"cmd","equals":"cargo","one_of":["cargo"]}]"#,
+        ))
+        .expect_err("two matchers");
+        assert!(err.to_string().contains("S1.A1 needs exactly one of"));
+    }
+
+    #[test]
+    fn arg_constraint_glob_rejects_paths_that_escape_it() {
+        let plan = normalize_plan_json(&constrained_plan(
+            r#"[{"tool":"read_file","field":"path","glob":"src/**"},
+                {"tool":"shell","field":"cmd","one_of":["cargo","git"]}]"#,
+        ))
+        .expect("normalize");
+        let steps = extract_plan_step_tools(&plan).expect("steps");
+        let glob = &steps[0].arg_constraints[0];
+        let cmd = &steps[0].arg_constraints[1];
+        for ok in ["src/lib.rs", "./src/a/b.rs"] {
+            assert!(
+                glob.violation(&serde_json::json!({"path": ok})).is_none(),
+                "{ok}"
+            );
+        }
+        for bad in ["README.md", "src/../secret", "/etc/passwd"] {
+            assert!(
+                glob.violation(&serde_json::json!({"path": bad})).is_some(),
+                "{bad}"
+            );
+        }
+        assert!(glob.violation(&serde_json::json!({})).is_some());
+        assert!(cmd.violation(&serde_json::json!({"cmd": "git"})).is_none());
+        assert!(cmd.violation(&serde_json::json!({"cmd": "rm"})).is_some());
+    }
 }
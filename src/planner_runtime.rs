@@ -1,4 +1,7 @@
-use crate::events::{EventKind, EventSink};
+use crate::events::{
+    EventSink, ModelRequestStartPayload, ModelResponseEndPayload, ProviderErrorPayload,
+    ProviderRetryPayload,
+};
 use crate::planner;
 use crate::providers;
 use crate::providers::ModelProvider;
@@ -29,7 +32,7 @@ pub(crate) async fn run_planner_phase<P: ModelProvider>(
         Message {
             role: Role::System,
             content: Some(
-                "You are the planner. Do not call tools. Produce only JSON matching openagent.plan.v1 with fields: schema_version, goal, assumptions[], steps[] where each step includes summary, intended_tools[], done_criteria[], verifier_checks[], plus risks[] and success_criteria[]."
+                "You are the planner. Do not call tools. Produce only JSON matching openagent.plan.v1 with fields: schema_version, goal, assumptions[], steps[] where each step includes summary, intended_tools[], done_criteria[], verifier_checks[], and optional arg_constraints[] (each {tool, field, and one of glob, equals, one_of} limiting that tool's argument during the step), plus risks[] and success_criteria[]."
                     .to_string(),
             ),
             tool_call_id: None,
@@ -52,13 +55,13 @@ pub(crate) async fn run_planner_phase<P: ModelProvider>(
             sink,
             run_id,
             step,
-            EventKind::ModelRequestStart,
-            serde_json::json!({
-                "model": planner_model,
-                "tool_count": 0,
-                "stream": false,
-                "phase": "planner"
-            }),
+            ModelRequestStartPayload {
+                model: Some(planner_model.to_string()),
+                tool_count: 0,
+                stream: Some(false),
+                phase: Some("planner".to_string()),
+                ..ModelRequestStartPayload::default()
+            },
         );
         let req = GenerateRequest {
             model: planner_model.to_string(),
@@ -78,29 +81,27 @@ pub(crate) async fn run_planner_phase<P: ModelProvider>(
                             sink,
                             run_id,
                             step,
-                            EventKind::ProviderRetry,
-                            serde_json::json!({
-                                "attempt": r.attempt,
-                                "max_attempts": r.max_attempts,
-                                "kind": r.kind,
-                                "status": r.status,
-                                "backoff_ms": r.backoff_ms
-                            }),
+                            ProviderRetryPayload {
+                                attempt: r.attempt,
+                                max_attempts: r.max_attempts,
+                                kind: r.kind,
+                                status: r.status,
+                                backoff_ms: r.backoff_ms,
+                            },
                         );
                     }
                     runtime_events::emit_event(
                         sink,
                         run_id,
                         step,
-                        EventKind::ProviderError,
-                        serde_json::json!({
-                            "kind": pe.kind,
-                            "status": pe.http_status,
-                            "retryable": pe.retryable,
-                            "attempt": pe.attempt,
-                            "max_attempts": pe.max_attempts,
-                            "message_short": providers::http::message_short(&pe.message)
-                        }),
+                        ProviderErrorPayload {
+                            kind: pe.kind,
+                            status: pe.http_status,
+                            retryable: pe.retryable,
+                            attempt: pe.attempt,
+                            max_attempts: pe.max_attempts,
+                            message_short: providers::http::message_short(&pe.message),
+                        },
                     );
                 }
                 return Err(e);
@@ -112,12 +113,12 @@ pub(crate) async fn run_planner_phase<P: ModelProvider>(
             sink,
             run_id,
             step,
-            EventKind::ModelResponseEnd,
-            serde_json::json!({
-                "content": output,
-                "tool_calls": resp.tool_calls.len(),
-                "phase": "planner"
-            }),
+            ModelResponseEndPayload {
+                content: Some(output.clone()),
+                tool_calls: resp.tool_calls.len(),
+                phase: Some("planner".to_string()),
+                usage: resp.usage.clone(),
+            },
         );
         if !resp.tool_calls.is_empty() {
             let wrapped =
@@ -0,0 +1,462 @@
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::events::{Event, EventKind, EventSink};
+
+const SPINNER_FRAMES: &[char] = &['-', '\\', '|', '/'];
+const TICK_INTERVAL: Duration = Duration::from_millis(200);
+const FALLBACK_WIDTH: usize = 80;
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressLineConfig {
+    pub max_steps: usize,
+    /// 0 means the run has no tool-call budget.
+    pub max_tool_calls: usize,
+    /// Model deltas are echoed to the same terminal, so the line must stay
+    /// hidden while the echoed text leaves the cursor mid-line.
+    pub echo_stream: bool,
+}
+
+/// Whether the progress line may be drawn at all: stderr must be a terminal
+/// and nothing else may own it (TUI, JSON output, suppressed stdout).
+pub fn progress_line_enabled(
+    no_progress: bool,
+    json_output: bool,
+    tui_enabled: bool,
+    suppress_stdout: bool,
+) -> bool {
+    !no_progress
+        && !json_output
+        && !tui_enabled
+        && !suppress_stdout
+        && std::io::stderr().is_terminal()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Phase {
+    Starting,
+    Thinking,
+    Streaming { chars: usize },
+    Executing { tool: String, since: Instant },
+    AwaitingApproval,
+    Compacting,
+    Finished,
+}
+
+/// Pure progress state derived from the event stream. Time is passed in so
+/// rendering is deterministic under test.
+#[derive(Debug, Clone)]
+pub struct ProgressState {
+    config: ProgressLineConfig,
+    started: Instant,
+    step: usize,
+    phase: Phase,
+    tool_calls: usize,
+    frame: usize,
+}
+
+impl ProgressState {
+    pub fn new(config: ProgressLineConfig, started: Instant) -> Self {
+        Self {
+            config,
+            started,
+            step: 0,
+            phase: Phase::Starting,
+            tool_calls: 0,
+            frame: 0,
+        }
+    }
+
+    pub fn apply(&mut self, event: &Event, now: Instant) {
+        match event.kind {
+            EventKind::RunStart => self.started = now,
+            EventKind::ModelRequestStart => {
+                self.step = event.step as usize + 1;
+                self.phase = Phase::Thinking;
+            }
+            EventKind::ModelDelta => {
+                if let Some(delta) = event.data.get("delta").and_then(|v| v.as_str()) {
+                    let chars = match self.phase {
+                        Phase::Streaming { chars } => chars,
+                        _ => 0,
+                    };
+                    self.phase = Phase::Streaming {
+                        chars: chars + delta.chars().count(),
+                    };
+                }
+            }
+            EventKind::ToolDecision
+                if event.data.get("decision").and_then(|v| v.as_str())
+                    == Some("require_approval") =>
+            {
+                self.phase = Phase::AwaitingApproval;
+            }
+            EventKind::InterruptRaised
+                if event.data.get("kind").and_then(|v| v.as_str()) == Some("approval_required") =>
+            {
+                self.phase = Phase::AwaitingApproval;
+            }
+            EventKind::ToolExecStart => {
+                self.tool_calls += 1;
+                self.phase = Phase::Executing {
+                    tool: event
+                        .data
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("tool")
+                        .to_string(),
+                    since: now,
+                };
+            }
+            EventKind::ToolExecEnd | EventKind::InterruptResolved => self.phase = Phase::Thinking,
+            EventKind::CompactionPerformed => self.phase = Phase::Compacting,
+            EventKind::RunEnd => self.phase = Phase::Finished,
+            _ => {}
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.phase == Phase::Finished
+    }
+
+    pub fn phase_label(&self, now: Instant) -> String {
+        match &self.phase {
+            Phase::Starting => "starting".to_string(),
+            Phase::Thinking => "thinking".to_string(),
+            Phase::Streaming { chars } => format!("streaming {chars} chars"),
+            Phase::Executing { tool, since } => {
+                format!(
+                    "executing {tool} {}s",
+                    now.saturating_duration_since(*since).as_secs()
+                )
+            }
+            Phase::AwaitingApproval => "awaiting approval".to_string(),
+            Phase::Compacting => "compacting".to_string(),
+            Phase::Finished => "done".to_string(),
+        }
+    }
+
+    /// One frame of the line, without control sequences.
+    pub fn render(&self, now: Instant) -> String {
+        let spinner = SPINNER_FRAMES[self.frame % SPINNER_FRAMES.len()];
+        let tools = if self.config.max_tool_calls > 0 {
+            format!("tools {}/{}", self.tool_calls, self.config.max_tool_calls)
+        } else {
+            format!("tools {}", self.tool_calls)
+        };
+        format!(
+            "{spinner} step {}/{} | {} | {}s elapsed | {tools}",
+            self.step,
+            self.config.max_steps,
+            self.phase_label(now),
+            now.saturating_duration_since(self.started).as_secs()
+        )
+    }
+
+    fn advance_frame(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
+
+struct LineRenderer {
+    state: ProgressState,
+    out: Box<dyn Write + Send>,
+    width: Option<usize>,
+    drawn: bool,
+    /// Echoed stream text left the cursor mid-line; drawing now would erase it.
+    cursor_mid_line: bool,
+}
+
+impl LineRenderer {
+    fn clear(&mut self) {
+        if self.drawn {
+            let _ = self.out.write_all(CLEAR_LINE.as_bytes());
+            let _ = self.out.flush();
+            self.drawn = false;
+        }
+    }
+
+    /// Draws the whole frame in one write and parks the cursor at column 0, so
+    /// a stray write to stderr overwrites the line instead of appending to it.
+    fn draw(&mut self, now: Instant) {
+        if self.state.is_finished() || self.cursor_mid_line {
+            return;
+        }
+        let width = self
+            .width
+            .or_else(|| crossterm::terminal::size().ok().map(|(w, _)| w as usize))
+            .unwrap_or(FALLBACK_WIDTH);
+        let line = self
+            .state
+            .render(now)
+            .chars()
+            .take(width.saturating_sub(1))
+            .collect::<String>();
+        let frame = format!("{CLEAR_LINE}{line}\r");
+        let _ = self.out.write_all(frame.as_bytes());
+        let _ = self.out.flush();
+        self.drawn = true;
+    }
+
+    fn on_event(&mut self, event: &Event, now: Instant) {
+        // Other sinks run after this one and may print; clear first, always.
+        self.clear();
+        self.state.apply(event, now);
+        if self.config().echo_stream && matches!(event.kind, EventKind::ModelDelta) {
+            if let Some(delta) = event.data.get("delta").and_then(|v| v.as_str()) {
+                if !delta.is_empty() {
+                    self.cursor_mid_line = !delta.ends_with('\n');
+                }
+            }
+            return;
+        }
+        self.draw(now);
+    }
+
+    fn tick(&mut self, now: Instant) {
+        if self.drawn {
+            self.state.advance_frame();
+            self.draw(now);
+        }
+    }
+
+    fn config(&self) -> ProgressLineConfig {
+        self.state.config
+    }
+}
+
+/// Event sink drawing a single in-place status line on stderr. Push it ahead of
+/// printing sinks in a `MultiSink` so the line is cleared before they write.
+pub struct ProgressSink {
+    inner: Arc<Mutex<LineRenderer>>,
+    stop: Arc<AtomicBool>,
+    ticker: Option<std::thread::JoinHandle<()>>,
+}
+
+impl ProgressSink {
+    /// Renders to `out` with a fixed width and no background redraw.
+    #[cfg(test)]
+    pub fn with_writer(
+        config: ProgressLineConfig,
+        out: Box<dyn Write + Send>,
+        width: usize,
+    ) -> Self {
+        Self::build(config, out, Some(width), false)
+    }
+
+    /// Renders to stderr and redraws the spinner and timers between events.
+    pub fn stderr(config: ProgressLineConfig) -> Self {
+        Self::build(config, Box::new(std::io::stderr()), None, true)
+    }
+
+    fn build(
+        config: ProgressLineConfig,
+        out: Box<dyn Write + Send>,
+        width: Option<usize>,
+        tick: bool,
+    ) -> Self {
+        let inner = Arc::new(Mutex::new(LineRenderer {
+            state: ProgressState::new(config, Instant::now()),
+            out,
+            width,
+            drawn: false,
+            cursor_mid_line: false,
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let ticker = tick.then(|| {
+            let inner = Arc::clone(&inner);
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    std::thread::sleep(TICK_INTERVAL);
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(mut renderer) = inner.lock() {
+                        renderer.tick(Instant::now());
+                    }
+                }
+            })
+        });
+        Self {
+            inner,
+            stop,
+            ticker,
+        }
+    }
+
+    fn stop_ticker(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.ticker.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl EventSink for ProgressSink {
+    fn emit(&mut self, event: Event) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let finished = {
+            let mut renderer = self
+                .inner
+                .lock()
+                .map_err(|_| anyhow::anyhow!("progress line lock poisoned"))?;
+            renderer.on_event(&event, now);
+            renderer.state.is_finished()
+        };
+        if finished {
+            self.stop_ticker();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ProgressSink {
+    fn drop(&mut self) {
+        self.stop_ticker();
+        if let Ok(mut renderer) = self.inner.lock() {
+            renderer.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().expect("buf").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuf {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().expect("buf").clone()).expect("utf8")
+        }
+    }
+
+    fn config() -> ProgressLineConfig {
+        ProgressLineConfig {
+            max_steps: 20,
+            max_tool_calls: 50,
+            echo_stream: false,
+        }
+    }
+
+    fn ev(step: u32, kind: EventKind, data: serde_json::Value) -> Event {
+        Event::new_raw("r".to_string(), step, kind, data)
+    }
+
+    #[test]
+    fn recorded_sequence_renders_expected_phases() {
+        let t0 = Instant::now();
+        let at = |secs: u64| t0 + Duration::from_secs(secs);
+        let mut state = ProgressState::new(config(), t0);
+        let mut labels = Vec::new();
+        let sequence = [
+            (0, ev(0, EventKind::RunStart, json!({}))),
+            (1, ev(0, EventKind::ModelRequestStart, json!({}))),
+            (2, ev(0, EventKind::ModelDelta, json!({"delta": "hello"}))),
+            (2, ev(0, EventKind::ModelDelta, json!({"delta": " world"}))),
+            (
+                3,
+                ev(
+                    0,
+                    EventKind::ToolExecStart,
+                    json!({"tool_call_id": "tc1", "name": "shell"}),
+                ),
+            ),
+            (3, ev(0, EventKind::ToolExecEnd, json!({}))),
+            (5, ev(1, EventKind::CompactionPerformed, json!({}))),
+            (
+                6,
+                ev(
+                    1,
+                    EventKind::ToolDecision,
+                    json!({"decision": "require_approval"}),
+                ),
+            ),
+        ];
+        for (secs, event) in &sequence {
+            state.apply(event, at(*secs));
+            labels.push(state.phase_label(at(*secs + 4)));
+        }
+        assert_eq!(
+            labels,
+            vec![
+                "starting",
+                "thinking",
+                "streaming 5 chars",
+                "streaming 11 chars",
+                "executing shell 4s",
+                "thinking",
+                "compacting",
+                "awaiting approval",
+            ]
+        );
+        assert_eq!(
+            state.render(at(6)),
+            "- step 1/20 | awaiting approval | 6s elapsed | tools 1/50"
+        );
+    }
+
+    #[test]
+    fn line_is_cleared_before_final_output() {
+        let buf = SharedBuf::default();
+        let mut sink = ProgressSink::with_writer(config(), Box::new(buf.clone()), 80);
+        sink.emit(ev(0, EventKind::ModelRequestStart, json!({})))
+            .expect("emit");
+        let drawn = buf.text();
+        assert!(drawn.starts_with(CLEAR_LINE));
+        assert!(drawn.contains("step 1/20 | thinking"));
+        assert!(drawn.ends_with('\r'));
+
+        sink.emit(ev(0, EventKind::RunEnd, json!({"exit_reason": "ok"})))
+            .expect("emit");
+        let out = buf.text();
+        assert!(out.ends_with(CLEAR_LINE), "{out:?}");
+        drop(sink);
+        assert_eq!(buf.text(), out, "nothing drawn after the run ends");
+    }
+
+    #[test]
+    fn echoed_stream_text_hides_line_until_newline() {
+        let buf = SharedBuf::default();
+        let cfg = ProgressLineConfig {
+            echo_stream: true,
+            ..config()
+        };
+        let mut sink = ProgressSink::with_writer(cfg, Box::new(buf.clone()), 30);
+        sink.emit(ev(0, EventKind::ModelRequestStart, json!({})))
+            .expect("emit");
+        sink.emit(ev(0, EventKind::ModelDelta, json!({"delta": "partial"})))
+            .expect("emit");
+        let after_delta = buf.text();
+        assert!(after_delta.ends_with(CLEAR_LINE));
+        sink.emit(ev(0, EventKind::ToolExecStart, json!({"name": "shell"})))
+            .expect("emit");
+        assert_eq!(buf.text(), after_delta);
+
+        sink.emit(ev(0, EventKind::ModelDelta, json!({"delta": "line\n"})))
+            .expect("emit");
+        sink.emit(ev(0, EventKind::ToolExecEnd, json!({})))
+            .expect("emit");
+        let frame = buf.text()[after_delta.len()..].to_string();
+        assert!(frame.starts_with(CLEAR_LINE) && frame.ends_with('\r'));
+        assert!(frame.trim_start_matches(CLEAR_LINE).chars().count() <= 30);
+    }
+}
@@ -13,10 +13,15 @@ pub(crate) async fn doctor_check(args: &DoctorArgs) -> Result<String, String> {
         .base_url
         .clone()
         .unwrap_or_else(|| default_base_url(provider).to_string());
+    let api_key = args.api_key.clone().or_else(|| {
+        (provider == ProviderKind::OpenAi)
+            .then(|| std::env::var(crate::providers::openai_compat::OPENAI_API_KEY_ENV).ok())
+            .flatten()
+    });
     let report = diagnose_provider_readiness(
         provider,
         &base_url,
-        args.api_key.as_deref(),
+        api_key.as_deref(),
         doctor_http_config(),
     )
     .await;
@@ -293,7 +298,7 @@ fn classify_models_response(provider: ProviderKind, status: StatusCode, body: &s
 
     let names = match provider {
         ProviderKind::Ollama => extract_string_field_array(&parsed, "models", "name"),
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
             extract_string_field_array(&parsed, "data", "id")
         }
         ProviderKind::Mock => Some(vec!["mock".to_string()]),
@@ -344,7 +349,7 @@ fn extract_string_field_array(
 fn expected_models_shape(provider: ProviderKind) -> &'static str {
     match provider {
         ProviderKind::Ollama => "expected Ollama JSON shape: {\"models\":[{\"name\":\"...\"}]}",
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
             "expected OpenAI-compatible JSON shape: {\"data\":[{\"id\":\"...\"}]}"
         }
         ProviderKind::Mock => "mock provider does not use a model list endpoint",
@@ -424,9 +429,12 @@ fn next_action(
             ProviderKind::Mock => {
                 "Use `--provider mock --model mock`; no local server is required.".to_string()
             }
+            ProviderKind::OpenAi => format!(
+                "Check network access to {base_url} (and any proxy settings), then rerun doctor."
+            ),
         },
         ProviderReadinessState::WrongBaseUrl => match provider {
-            ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+            ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
                 "Confirm this is the provider's OpenAI-compatible API base URL and that the /models endpoint is enabled.".to_string()
             }
             ProviderKind::Ollama => format!(
@@ -453,9 +461,13 @@ fn next_action(
             ProviderKind::Mock => {
                 "Use `--provider mock --model mock`; no model download is required.".to_string()
             }
+            ProviderKind::OpenAi => {
+                "The API key can list no models; check the key's project permissions, then rerun doctor."
+                    .to_string()
+            }
         },
         ProviderReadinessState::ModelListEndpointUnavailable => match provider {
-            ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+            ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
                 "Confirm the server exposes `GET /v1/models` and retry with the correct `--base-url`.".to_string()
             }
             ProviderKind::Ollama => {
@@ -500,6 +512,7 @@ pub(crate) fn default_base_url(provider: ProviderKind) -> &'static str {
         ProviderKind::Llamacpp => "http://localhost:8080/v1",
         ProviderKind::Ollama => "http://localhost:11434",
         ProviderKind::Mock => "mock://local",
+        ProviderKind::OpenAi => "https://api.openai.com/v1",
     }
 }
 
@@ -509,13 +522,14 @@ pub(crate) fn provider_cli_name(provider: ProviderKind) -> &'static str {
         ProviderKind::Llamacpp => "llamacpp",
         ProviderKind::Ollama => "ollama",
         ProviderKind::Mock => "mock",
+        ProviderKind::OpenAi => "openai",
     }
 }
 
 pub(crate) fn doctor_probe_urls(provider: ProviderKind, base_url: &str) -> Vec<String> {
     let trimmed = base_url.trim_end_matches('/').to_string();
     match provider {
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
             vec![format!("{trimmed}/models"), trimmed]
         }
         ProviderKind::Ollama => vec![format!("{trimmed}/api/tags")],
@@ -530,7 +544,13 @@ pub(crate) fn http_config_from_run_args(args: &RunArgs) -> HttpConfig {
         stream_idle_timeout_ms: args.http_stream_idle_timeout_ms,
         max_response_bytes: args.http_max_response_bytes,
         max_line_bytes: args.http_max_line_bytes,
-        http_max_retries: args.http_max_retries,
+        // The retry policy owns retries when on; transport retries under it
+        // would multiply the attempts.
+        http_max_retries: if args.provider_retry_max_attempts > 1 {
+            0
+        } else {
+            args.http_max_retries
+        },
         ..HttpConfig::default()
     }
 }
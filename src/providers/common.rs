@@ -119,6 +119,7 @@ pub(crate) fn provider_payload_too_large_error(
             actual_bytes, max_bytes
         ),
         retries,
+        retry_after_ms: None,
     }
 }
 
@@ -141,6 +142,7 @@ pub(crate) fn provider_stream_payload_too_large_error(
             actual_bytes, max_bytes
         ),
         retries,
+        retry_after_ms: None,
     }
 }
 
@@ -154,5 +156,6 @@ pub(crate) fn provider_stream_incomplete_error(http: HttpConfig) -> ProviderErro
         max_attempts,
         message: "stream ended before response completed".to_string(),
         retries: Vec::new(),
+        retry_after_ms: None,
     }
 }
@@ -60,6 +60,9 @@ pub enum ProviderErrorKind {
     Parse,
     PayloadTooLarge,
     Unauthorized,
+    /// The request did not fit the model's context window. Never retried:
+    /// the same request fails the same way until the prompt shrinks.
+    ContextLength,
     Other,
 }
 
@@ -81,6 +84,8 @@ pub struct ProviderError {
     pub max_attempts: u32,
     pub message: String,
     pub retries: Vec<RetryRecord>,
+    /// Delay the server asked for in a `Retry-After` header.
+    pub retry_after_ms: Option<u64>,
 }
 
 impl std::fmt::Display for ProviderError {
@@ -162,6 +167,60 @@ pub fn classify_reqwest_error(err: &reqwest::Error) -> ClassifiedError {
     }
 }
 
+/// How each backend words a prompt that does not fit: OpenAI's error code and
+/// message, llama.cpp's `exceed_context_size_error`, Ollama's and LM Studio's
+/// plain-text errors.
+const CONTEXT_OVERFLOW_PATTERNS: &[&str] = &[
+    "context_length_exceeded",
+    "maximum context length",
+    "context length exceeded",
+    "exceeds the context window",
+    "prompt is too long",
+    "exceed_context_size_error",
+    "exceeds the available context size",
+    "input length exceeds the context length",
+    "greater than the context length",
+    "context size has been exceeded",
+];
+
+pub fn mentions_context_overflow(text: &str) -> bool {
+    let lower = text.to_ascii_lowercase();
+    CONTEXT_OVERFLOW_PATTERNS
+        .iter()
+        .any(|needle| lower.contains(needle))
+}
+
+/// Servers report context overflow as a plain 400 (or 413, or a 500 from
+/// llama.cpp and Ollama); the body is the only place the cause shows up.
+pub fn classify_error_body(cls: ClassifiedError, body: &str) -> ClassifiedError {
+    if matches!(cls.status, Some(400 | 413 | 500)) && mentions_context_overflow(body) {
+        ClassifiedError {
+            kind: ProviderErrorKind::ContextLength,
+            retryable: false,
+            status: cls.status,
+        }
+    } else {
+        cls
+    }
+}
+
+/// Whether a model call failed because the prompt was too long: a
+/// [`ProviderError`] classified as such, or any error whose text says so for
+/// providers that do not classify.
+pub fn is_context_overflow(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<ProviderError>() {
+        Some(pe) => matches!(pe.kind, ProviderErrorKind::ContextLength),
+        None => mentions_context_overflow(&format!("{err:#}")),
+    }
+}
+
+/// `Retry-After` in delta-seconds form. HTTP-date values are ignored.
+pub fn retry_after_ms(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let raw = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    let secs = raw.trim().parse::<f64>().ok()?;
+    (secs.is_finite() && secs >= 0.0).then_some((secs * 1000.0) as u64)
+}
+
 pub fn deterministic_backoff_ms(cfg: HttpConfig, retry_index: u32) -> u64 {
     let factor = 1u64 << retry_index.min(16);
     let ms = cfg.initial_backoff_ms.saturating_mul(factor);
@@ -183,7 +242,10 @@ pub fn message_short(s: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{classify_status, deterministic_backoff_ms, HttpConfig, ProviderErrorKind};
+    use super::{
+        classify_error_body, classify_status, deterministic_backoff_ms, is_context_overflow,
+        retry_after_ms, HttpConfig, ProviderErrorKind,
+    };
 
     #[test]
     fn backoff_is_deterministic_and_capped() {
@@ -206,4 +268,37 @@ mod tests {
         let u = classify_status(401);
         assert!(matches!(u.kind, ProviderErrorKind::Unauthorized));
     }
+
+    #[test]
+    fn context_overflow_bodies_are_non_retryable_context_length() {
+        let body = r#"{"error":{"code":"context_length_exceeded","message":"too long"}}"#;
+        let c = classify_error_body(classify_status(400), body);
+        assert!(matches!(c.kind, ProviderErrorKind::ContextLength));
+        assert!(!c.retryable);
+        let plain = classify_error_body(classify_status(400), "bad request");
+        assert!(matches!(plain.kind, ProviderErrorKind::Client));
+        let server = classify_error_body(classify_status(503), body);
+        assert!(matches!(server.kind, ProviderErrorKind::Server));
+        let llamacpp = r#"{"error":{"code":500,"message":"the request exceeds the available context size, try increasing it","type":"exceed_context_size_error"}}"#;
+        let l = classify_error_body(classify_status(500), llamacpp);
+        assert!(matches!(l.kind, ProviderErrorKind::ContextLength));
+
+        assert!(is_context_overflow(&anyhow::anyhow!(
+            "ollama: input length exceeds the context length"
+        )));
+        assert!(!is_context_overflow(&anyhow::anyhow!("connection refused")));
+    }
+
+    #[test]
+    fn retry_after_parses_delta_seconds_only() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after_ms(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(retry_after_ms(&headers), Some(2000));
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after_ms(&headers), None);
+    }
 }
@@ -9,6 +9,9 @@ use crate::types::{GenerateRequest, GenerateResponse, Message, Role, ToolCall};
 
 const MOCK_OK: &str = "mock: ok";
 const MARKER_PREFIX: &str = "__mock_tool_call__:";
+/// A line `__mock_delay_ms__:<N>` in the latest user message delays every
+/// response by N milliseconds, to simulate model latency in tests.
+const DELAY_MARKER_PREFIX: &str = "__mock_delay_ms__:";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MockProviderError {
@@ -59,6 +62,7 @@ impl MockProvider {
                     arguments: invocation.args,
                 }],
                 usage: None,
+                served_model: None,
             }),
             None => Ok(GenerateResponse {
                 assistant: Message {
@@ -70,6 +74,7 @@ impl MockProvider {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             }),
         }
     }
@@ -118,9 +123,24 @@ fn extract_mock_tool_call(messages: &[Message]) -> anyhow::Result<Option<MockToo
     }))
 }
 
+fn mock_response_delay(messages: &[Message]) -> Option<std::time::Duration> {
+    messages
+        .iter()
+        .rev()
+        .find(|m| matches!(m.role, Role::User))
+        .and_then(|m| m.content.as_deref())?
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(DELAY_MARKER_PREFIX))
+        .and_then(|ms| ms.trim().parse::<u64>().ok())
+        .map(std::time::Duration::from_millis)
+}
+
 #[async_trait]
 impl ModelProvider for MockProvider {
     async fn generate(&self, req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+        if let Some(delay) = mock_response_delay(&req.messages) {
+            tokio::time::sleep(delay).await;
+        }
         self.build_response(req)
     }
 
@@ -133,6 +153,9 @@ impl ModelProvider for MockProvider {
         req: GenerateRequest,
         on_delta: &mut (dyn FnMut(StreamDelta) + Send),
     ) -> anyhow::Result<GenerateResponse> {
+        if let Some(delay) = mock_response_delay(&req.messages) {
+            tokio::time::sleep(delay).await;
+        }
         match extract_mock_tool_call(&req.messages)? {
             Some(invocation) => {
                 on_delta(StreamDelta::ToolCallFragment(ToolCallFragment {
@@ -156,6 +179,7 @@ impl ModelProvider for MockProvider {
                         arguments: invocation.args,
                     }],
                     usage: None,
+                    served_model: None,
                 })
             }
             None => {
@@ -170,6 +194,7 @@ impl ModelProvider for MockProvider {
                     },
                     tool_calls: Vec::new(),
                     usage: None,
+                    served_model: None,
                 })
             }
         }
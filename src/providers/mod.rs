@@ -3,10 +3,14 @@ pub mod http;
 pub mod mock;
 pub mod ollama;
 pub mod openai_compat;
+pub mod pacing;
+pub mod recording;
+pub mod retry;
+pub mod scripted;
 
 use async_trait::async_trait;
 
-use crate::types::{GenerateRequest, GenerateResponse};
+use crate::types::{GenerateRequest, GenerateResponse, ToolCall};
 
 #[derive(Debug, Clone)]
 pub enum StreamDelta {
@@ -23,6 +27,54 @@ pub struct ToolCallFragment {
     pub complete: bool,
 }
 
+/// Rebuilds complete tool calls from streamed fragments, keyed by fragment
+/// index: the first id and name seen win, argument fragments concatenate.
+#[derive(Debug, Default)]
+pub struct ToolCallAssembler {
+    partials: Vec<(Option<String>, Option<String>, String)>,
+}
+
+impl ToolCallAssembler {
+    pub fn push(&mut self, fragment: &ToolCallFragment) {
+        if self.partials.len() <= fragment.index {
+            self.partials
+                .resize_with(fragment.index + 1, || (None, None, String::new()));
+        }
+        let (id, name, arguments) = &mut self.partials[fragment.index];
+        if id.is_none() {
+            id.clone_from(&fragment.id);
+        }
+        if name.is_none() {
+            name.clone_from(&fragment.name);
+        }
+        if let Some(part) = &fragment.arguments_fragment {
+            arguments.push_str(part);
+        }
+    }
+
+    /// Calls in index order. Fragments that never named a tool are dropped;
+    /// arguments that are not JSON are kept as a string, as the providers do.
+    pub fn finish(self) -> Vec<ToolCall> {
+        self.partials
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, (id, name, arguments))| {
+                let name = name.filter(|n| !n.is_empty())?;
+                Some(ToolCall {
+                    id: id
+                        .filter(|id| !id.is_empty())
+                        .unwrap_or_else(|| format!("stream_tc_{i}")),
+                    name,
+                    arguments: match serde_json::from_str(&arguments) {
+                        Ok(v) => v,
+                        Err(_) => serde_json::Value::String(arguments),
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
 #[async_trait]
 pub trait ModelProvider: Send + Sync {
     async fn generate(&self, req: GenerateRequest) -> anyhow::Result<GenerateResponse>;
@@ -38,6 +90,13 @@ pub trait ModelProvider: Send + Sync {
     ) -> anyhow::Result<GenerateResponse> {
         self.generate(req).await
     }
+
+    /// Retries a wrapping layer absorbed on calls that went on to succeed,
+    /// drained so the agent can report each once. Failed calls carry theirs
+    /// on the [`http::ProviderError`].
+    fn take_retries(&self) -> Vec<http::RetryRecord> {
+        Vec::new()
+    }
 }
 
 pub(crate) fn to_u32_opt(v: Option<u64>) -> Option<u32> {
@@ -12,8 +12,8 @@ use crate::providers::common::{
     ProviderRetryStepInput, ToolEnvelope as SharedToolEnvelope,
 };
 use crate::providers::http::{
-    classify_reqwest_error, classify_status, HttpConfig, ProviderError, ProviderErrorKind,
-    RetryRecord,
+    classify_error_body, classify_reqwest_error, classify_status, retry_after_ms, HttpConfig,
+    ProviderError, ProviderErrorKind, RetryRecord,
 };
 use crate::providers::{ModelProvider, StreamDelta, ToolCallFragment};
 use crate::types::{GenerateRequest, GenerateResponse, Message, Role, ToolCall};
@@ -72,6 +72,8 @@ struct OllamaOptions {
 
 #[derive(Debug, Deserialize)]
 struct OllamaResponse {
+    #[serde(default)]
+    model: Option<String>,
     message: OllamaMessageIn,
     #[serde(default)]
     done: bool,
@@ -132,16 +134,19 @@ impl ModelProvider for OllamaProvider {
                         max_attempts,
                         message: format!("failed to call Ollama endpoint: {e}"),
                         retries,
+                        retry_after_ms: None,
                     }));
                 }
             };
             let status = response.status();
             if !status.is_success() {
                 let cls = classify_status(status.as_u16());
+                let retry_after_ms = retry_after_ms(response.headers());
                 let body = response
                     .text()
                     .await
                     .unwrap_or_else(|_| "<body unavailable>".to_string());
+                let cls = classify_error_body(cls, &body);
                 if cls.retryable && attempt < max_attempts {
                     record_retry_and_sleep(ProviderRetryStepInput {
                         http: self.http,
@@ -167,6 +172,7 @@ impl ModelProvider for OllamaProvider {
                         format_http_error_body(&body)
                     ),
                     retries,
+                    retry_after_ms,
                 }));
             }
             let bytes = response
@@ -231,16 +237,19 @@ impl ModelProvider for OllamaProvider {
                         max_attempts,
                         message: format!("failed to call Ollama endpoint: {e}"),
                         retries,
+                        retry_after_ms: None,
                     }));
                 }
             };
             let status = response.status();
             if !status.is_success() {
                 let cls = classify_status(status.as_u16());
+                let retry_after_ms = retry_after_ms(response.headers());
                 let body = response
                     .text()
                     .await
                     .unwrap_or_else(|_| "<body unavailable>".to_string());
+                let cls = classify_error_body(cls, &body);
                 if cls.retryable && attempt < max_attempts {
                     record_retry_and_sleep(ProviderRetryStepInput {
                         http: self.http,
@@ -266,6 +275,7 @@ impl ModelProvider for OllamaProvider {
                         format_http_error_body(&body)
                     ),
                     retries,
+                    retry_after_ms,
                 }));
             }
 
@@ -273,6 +283,7 @@ impl ModelProvider for OllamaProvider {
             let mut text_buf = String::new();
             let mut content_accum = String::new();
             let mut tool_calls = Vec::new();
+            let mut served_model = None;
             let mut total_bytes = 0usize;
             let mut emitted_any = false;
 
@@ -303,6 +314,7 @@ impl ModelProvider for OllamaProvider {
                                 max_attempts,
                                 message: "stream idle timeout exceeded".to_string(),
                                 retries,
+                                retry_after_ms: None,
                             }));
                         }
                     }
@@ -337,6 +349,7 @@ impl ModelProvider for OllamaProvider {
                             max_attempts,
                             message: format!("failed reading stream chunk: {e}"),
                             retries,
+                            retry_after_ms: None,
                         }));
                     }
                 };
@@ -370,23 +383,31 @@ impl ModelProvider for OllamaProvider {
                                 self.http.max_line_bytes
                             ),
                             retries,
+                            retry_after_ms: None,
                         }));
                     }
-                    handle_ollama_stream_json(&line, on_delta, &mut content_accum, &mut tool_calls)
-                        .map_err(|e| {
-                            anyhow!(ProviderError {
-                                kind: ProviderErrorKind::Parse,
-                                http_status: Some(status.as_u16()),
-                                retryable: false,
-                                attempt,
-                                max_attempts,
-                                message: format!(
-                                    "malformed Ollama stream line: {}",
-                                    truncate_error_display(&e, 200)
-                                ),
-                                retries: retries.clone(),
-                            })
-                        })?;
+                    handle_ollama_stream_json(
+                        &line,
+                        on_delta,
+                        &mut content_accum,
+                        &mut tool_calls,
+                        &mut served_model,
+                    )
+                    .map_err(|e| {
+                        anyhow!(ProviderError {
+                            kind: ProviderErrorKind::Parse,
+                            http_status: Some(status.as_u16()),
+                            retryable: false,
+                            attempt,
+                            max_attempts,
+                            message: format!(
+                                "malformed Ollama stream line: {}",
+                                truncate_error_display(&e, 200)
+                            ),
+                            retries: retries.clone(),
+                            retry_after_ms: None,
+                        })
+                    })?;
                     emitted_any = true;
                 }
             }
@@ -409,6 +430,7 @@ impl ModelProvider for OllamaProvider {
                 },
                 tool_calls,
                 usage: None,
+                served_model,
             });
         }
 
@@ -502,6 +524,7 @@ fn map_ollama_response(resp: OllamaResponse) -> GenerateResponse {
                 _ => None,
             },
         )),
+        served_model: resp.model.filter(|m| !m.is_empty()),
     }
 }
 
@@ -510,9 +533,13 @@ fn handle_ollama_stream_json(
     on_delta: &mut (dyn FnMut(StreamDelta) + Send),
     content_accum: &mut String,
     tool_calls: &mut Vec<ToolCall>,
+    served_model: &mut Option<String>,
 ) -> anyhow::Result<()> {
     let ev: OllamaResponse =
         serde_json::from_str(line).context("failed parsing Ollama stream event")?;
+    if served_model.is_none() {
+        *served_model = ev.model.filter(|m| !m.is_empty());
+    }
     if let Some(content) = ev.message.content {
         if !content.is_empty() {
             content_accum.push_str(&content);
@@ -546,7 +573,7 @@ mod tests {
         drain_json_lines, handle_ollama_stream_json, map_ollama_response, to_request,
         OllamaResponse,
     };
-    use crate::providers::StreamDelta;
+    use crate::providers::{StreamDelta, ToolCallAssembler};
     use crate::types::GenerateRequest;
 
     #[test]
@@ -559,6 +586,7 @@ mod tests {
             &mut |d| deltas.push(d),
             &mut content,
             &mut tool_calls,
+            &mut None,
         )
         .expect("parse");
         assert_eq!(content, "Hi");
@@ -566,6 +594,36 @@ mod tests {
         assert!(matches!(deltas[0], StreamDelta::Content(_)));
     }
 
+    #[test]
+    fn streamed_fragments_assemble_to_the_non_streaming_tool_calls() {
+        let calls = r#"[{"function":{"name":"read_file","arguments":{"path":"a.txt"}}},{"function":{"name":"list_dir","arguments":{"path":"."}}}]"#;
+        let whole: OllamaResponse = serde_json::from_str(&format!(
+            r#"{{"message":{{"content":"","tool_calls":{calls}}},"done":true}}"#
+        ))
+        .expect("response");
+        let expected = serde_json::to_value(map_ollama_response(whole).tool_calls).expect("json");
+
+        let mut assembler = ToolCallAssembler::default();
+        let mut tool_calls = Vec::new();
+        handle_ollama_stream_json(
+            &format!(r#"{{"message":{{"tool_calls":{calls}}},"done":true}}"#),
+            &mut |d| {
+                if let StreamDelta::ToolCallFragment(f) = d {
+                    assembler.push(&f);
+                }
+            },
+            &mut String::new(),
+            &mut tool_calls,
+            &mut None,
+        )
+        .expect("parse");
+        assert_eq!(
+            serde_json::to_value(assembler.finish()).expect("json"),
+            expected
+        );
+        assert_eq!(serde_json::to_value(tool_calls).expect("json"), expected);
+    }
+
     #[test]
     fn drains_json_lines_with_partial_chunks() {
         let mut buf = "{\"a\":1}".to_string();
@@ -590,6 +648,7 @@ mod tests {
             &mut |d| deltas.push(d),
             &mut content,
             &mut tool_calls,
+            &mut None,
         )
         .expect_err("expected parse error");
         assert!(err
@@ -615,6 +674,44 @@ mod tests {
         assert_eq!(usage.total_tokens, Some(18));
     }
 
+    #[test]
+    fn maps_ollama_reported_model_name() {
+        let resp: OllamaResponse = serde_json::from_str(
+            r#"{"model":"llama3.2:3b","message":{"content":"ok"},"done":true}"#,
+        )
+        .expect("parse");
+        assert_eq!(
+            map_ollama_response(resp).served_model.as_deref(),
+            Some("llama3.2:3b")
+        );
+        let resp: OllamaResponse =
+            serde_json::from_str(r#"{"message":{"content":"ok"},"done":true}"#).expect("parse");
+        assert_eq!(map_ollama_response(resp).served_model, None);
+    }
+
+    #[test]
+    fn stream_keeps_model_from_first_event_that_reports_it() {
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        let mut served = None;
+        for line in [
+            r#"{"message":{"content":"a"},"done":false}"#,
+            r#"{"model":"qwen2.5-coder:7b","message":{"content":"b"},"done":false}"#,
+            r#"{"model":"other","message":{"content":"c"},"done":true}"#,
+        ] {
+            handle_ollama_stream_json(
+                line,
+                &mut |_| {},
+                &mut content,
+                &mut tool_calls,
+                &mut served,
+            )
+            .expect("parse");
+        }
+        assert_eq!(content, "abc");
+        assert_eq!(served.as_deref(), Some("qwen2.5-coder:7b"));
+    }
+
     #[test]
     fn to_request_sets_options_temperature_when_present() {
         let payload = to_request(
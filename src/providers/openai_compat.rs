@@ -14,11 +14,11 @@ use crate::providers::common::{
     truncate_for_error, ProviderRetryStepInput, ToolEnvelope as SharedToolEnvelope,
 };
 use crate::providers::http::{
-    classify_reqwest_error, classify_status, HttpConfig, ProviderError, ProviderErrorKind,
-    RetryRecord,
+    classify_error_body, classify_reqwest_error, classify_status, retry_after_ms, ClassifiedError,
+    HttpConfig, ProviderError, ProviderErrorKind, RetryRecord,
 };
 use crate::providers::{ModelProvider, StreamDelta, ToolCallFragment};
-use crate::types::{GenerateRequest, GenerateResponse, Message, Role, ToolCall};
+use crate::types::{GenerateRequest, GenerateResponse, Message, Role, TokenUsage, ToolCall};
 
 #[derive(Debug, Clone)]
 pub struct OpenAiCompatProvider {
@@ -33,8 +33,19 @@ pub struct OpenAiCompatProvider {
 enum OpenAiCompatMode {
     Standard,
     Lmstudio,
+    /// The hosted OpenAI chat completions API: requires a key, only sends
+    /// `tool_choice` alongside tools, asks for usage on streams, and treats
+    /// every 5xx as transient.
+    OpenAi,
 }
 
+/// Client for the hosted OpenAI chat completions API
+/// (`--provider openai`), sharing the OpenAI-compatible wire handling.
+#[allow(dead_code)]
+pub type OpenAiChatProvider = OpenAiCompatProvider;
+
+pub const OPENAI_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
 #[derive(Debug, Serialize)]
 struct OpenAiCompatTrace {
     schema_version: String,
@@ -83,6 +94,7 @@ impl OpenAiCompatTraceRecorder {
                 provider: match compatibility {
                     OpenAiCompatMode::Standard => "openai_compat".to_string(),
                     OpenAiCompatMode::Lmstudio => "lmstudio".to_string(),
+                    OpenAiCompatMode::OpenAi => "openai".to_string(),
                 },
                 base_url: base_url.to_string(),
                 streaming,
@@ -141,20 +153,41 @@ impl OpenAiCompatProvider {
         api_key: Option<String>,
         http: HttpConfig,
     ) -> anyhow::Result<Self> {
+        let compatibility = match provider_kind {
+            ProviderKind::Lmstudio => OpenAiCompatMode::Lmstudio,
+            ProviderKind::OpenAi => OpenAiCompatMode::OpenAi,
+            ProviderKind::Llamacpp | ProviderKind::Ollama | ProviderKind::Mock => {
+                OpenAiCompatMode::Standard
+            }
+        };
+        let api_key = match compatibility {
+            OpenAiCompatMode::OpenAi => Some(
+                api_key
+                    .or_else(|| std::env::var(OPENAI_API_KEY_ENV).ok())
+                    .filter(|key| !key.trim().is_empty())
+                    .ok_or_else(|| {
+                        anyhow!("--provider openai requires --api-key or {OPENAI_API_KEY_ENV}")
+                    })?,
+            ),
+            OpenAiCompatMode::Standard | OpenAiCompatMode::Lmstudio => api_key,
+        };
         let client = build_http_client(http, "failed to build OpenAI-compatible HTTP client")?;
         Ok(Self {
             client,
             base_url: base_url.trim_end_matches('/').to_string(),
             api_key,
             http,
-            compatibility: match provider_kind {
-                ProviderKind::Lmstudio => OpenAiCompatMode::Lmstudio,
-                ProviderKind::Llamacpp | ProviderKind::Ollama | ProviderKind::Mock => {
-                    OpenAiCompatMode::Standard
-                }
-            },
+            compatibility,
         })
     }
+
+    fn classify_status(&self, status: u16) -> ClassifiedError {
+        let mut cls = classify_status(status);
+        if self.compatibility == OpenAiCompatMode::OpenAi && (500..=599).contains(&status) {
+            cls.retryable = true;
+        }
+        cls
+    }
 }
 
 type OpenAiToolEnvelope = SharedToolEnvelope;
@@ -165,7 +198,8 @@ struct OpenAiRequest {
     messages: Vec<Message>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAiToolEnvelope>>,
-    tool_choice: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<String>,
     temperature: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     top_p: Option<f32>,
@@ -174,10 +208,19 @@ struct OpenAiRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     seed: Option<u64>,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAiStreamOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiStreamOptions {
+    include_usage: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAiResponse {
+    #[serde(default)]
+    model: Option<String>,
     #[serde(default)]
     choices: Vec<OpenAiChoice>,
     #[serde(default)]
@@ -286,16 +329,19 @@ impl ModelProvider for OpenAiCompatProvider {
                         max_attempts,
                         message: format!("failed to call OpenAI-compatible endpoint: {e}"),
                         retries,
+                        retry_after_ms: None,
                     }));
                 }
             };
             let status = response.status();
             if !status.is_success() {
-                let cls = classify_status(status.as_u16());
+                let cls = self.classify_status(status.as_u16());
+                let retry_after_ms = retry_after_ms(response.headers());
                 let body = response
                     .text()
                     .await
                     .unwrap_or_else(|_| "<body unavailable>".to_string());
+                let cls = classify_error_body(cls, &body);
                 if cls.retryable && attempt < max_attempts {
                     record_retry_and_sleep(ProviderRetryStepInput {
                         http: self.http,
@@ -334,6 +380,7 @@ impl ModelProvider for OpenAiCompatProvider {
                         format_http_error_body(&body)
                     ),
                     retries,
+                    retry_after_ms,
                 }));
             }
             let bytes = response
@@ -415,6 +462,7 @@ impl ModelProvider for OpenAiCompatProvider {
                         max_attempts,
                         message: e.to_string(),
                         retries,
+                        retry_after_ms: None,
                     })
                 });
         }
@@ -493,17 +541,20 @@ impl ModelProvider for OpenAiCompatProvider {
                         max_attempts,
                         message: format!("failed to call OpenAI-compatible endpoint: {e}"),
                         retries,
+                        retry_after_ms: None,
                     }));
                 }
             };
 
             let status = response.status();
             if !status.is_success() {
-                let cls = classify_status(status.as_u16());
+                let cls = self.classify_status(status.as_u16());
+                let retry_after_ms = retry_after_ms(response.headers());
                 let body = response
                     .text()
                     .await
                     .unwrap_or_else(|_| "<body unavailable>".to_string());
+                let cls = classify_error_body(cls, &body);
                 if cls.retryable && attempt < max_attempts {
                     record_retry_and_sleep(ProviderRetryStepInput {
                         http: self.http,
@@ -542,6 +593,7 @@ impl ModelProvider for OpenAiCompatProvider {
                         format_http_error_body(&body)
                     ),
                     retries,
+                    retry_after_ms,
                 }));
             }
 
@@ -549,6 +601,8 @@ impl ModelProvider for OpenAiCompatProvider {
             let mut text_buf = String::new();
             let mut content_accum = String::new();
             let mut partials: Vec<PartialToolCall> = Vec::new();
+            let mut served_model = None;
+            let mut usage = None;
             let mut total_bytes: usize = 0;
             let mut emitted_any = false;
             let mut saw_done = false;
@@ -589,6 +643,7 @@ impl ModelProvider for OpenAiCompatProvider {
                                 max_attempts,
                                 message: "stream idle timeout exceeded".to_string(),
                                 retries,
+                                retry_after_ms: None,
                             }));
                         }
                     }
@@ -634,6 +689,7 @@ impl ModelProvider for OpenAiCompatProvider {
                             max_attempts,
                             message: format!("failed reading stream chunk: {e}"),
                             retries,
+                            retry_after_ms: None,
                         }));
                     }
                 };
@@ -675,6 +731,7 @@ impl ModelProvider for OpenAiCompatProvider {
                                 self.http.max_line_bytes
                             ),
                             retries,
+                            retry_after_ms: None,
                         }));
                     }
                     match parse_sse_event_payload(&raw_event) {
@@ -689,8 +746,12 @@ impl ModelProvider for OpenAiCompatProvider {
                                 on_delta,
                                 &mut content_accum,
                                 &mut partials,
+                                &mut served_model,
                             ) {
                                 Ok(summary) => {
+                                    if summary.usage.is_some() {
+                                        usage = summary.usage;
+                                    }
                                     trace.push_event(
                                         "stream_event",
                                         serde_json::json!({
@@ -727,6 +788,7 @@ impl ModelProvider for OpenAiCompatProvider {
                                             truncate_error_display(&e, 200)
                                         ),
                                         retries,
+                                        retry_after_ms: None,
                                     }));
                                 }
                             }
@@ -757,6 +819,7 @@ impl ModelProvider for OpenAiCompatProvider {
                                     truncate_error_display(&e, 200)
                                 ),
                                 retries,
+                                retry_after_ms: None,
                             }));
                         }
                     }
@@ -799,7 +862,8 @@ impl ModelProvider for OpenAiCompatProvider {
                     tool_calls: None,
                 },
                 tool_calls,
-                usage: None,
+                usage,
+                served_model,
             });
         }
 
@@ -823,16 +887,20 @@ fn to_request(
 ) -> OpenAiRequest {
     let tools = build_tool_envelopes(req.tools);
     let messages = normalize_messages(req.messages, tools.is_some(), compatibility);
+    let hosted = compatibility == OpenAiCompatMode::OpenAi;
     OpenAiRequest {
         model: req.model,
         messages,
+        tool_choice: (!hosted || tools.is_some()).then(|| "auto".to_string()),
         tools,
-        tool_choice: "auto".to_string(),
         temperature: req.temperature.unwrap_or(0.2),
         top_p: req.top_p,
         max_tokens: req.max_tokens,
         seed: req.seed,
         stream,
+        stream_options: (hosted && stream).then_some(OpenAiStreamOptions {
+            include_usage: true,
+        }),
     }
 }
 
@@ -841,7 +909,7 @@ fn normalize_messages(
     has_tools: bool,
     compatibility: OpenAiCompatMode,
 ) -> Vec<Message> {
-    if compatibility == OpenAiCompatMode::Standard {
+    if compatibility != OpenAiCompatMode::Lmstudio {
         return messages
             .into_iter()
             .filter(|message| !is_semantically_empty_assistant_message(message))
@@ -930,6 +998,7 @@ fn map_openai_response(resp: OpenAiResponse) -> anyhow::Result<GenerateResponse>
         .usage
         .as_ref()
         .map(|u| map_token_usage_triplet(u.prompt_tokens, u.completion_tokens, u.total_tokens));
+    let served_model = resp.model.filter(|m| !m.is_empty());
     let first = resp
         .choices
         .into_iter()
@@ -962,6 +1031,7 @@ fn map_openai_response(resp: OpenAiResponse) -> anyhow::Result<GenerateResponse>
         },
         tool_calls,
         usage,
+        served_model,
     })
 }
 
@@ -994,6 +1064,8 @@ struct OpenAiStreamEventSummary {
     content_delta_preview: Option<String>,
     tool_fragments: Vec<Value>,
     finish_reason: Option<String>,
+    /// Present on the trailing usage chunk requested via `stream_options`.
+    usage: Option<TokenUsage>,
 }
 
 fn handle_openai_stream_json(
@@ -1001,10 +1073,20 @@ fn handle_openai_stream_json(
     on_delta: &mut (dyn FnMut(StreamDelta) + Send),
     content_accum: &mut String,
     partials: &mut Vec<PartialToolCall>,
+    served_model: &mut Option<String>,
 ) -> anyhow::Result<OpenAiStreamEventSummary> {
     let item: OpenAiResponse =
         serde_json::from_str(payload).context("failed parsing OpenAI-compatible stream event")?;
-    let mut summary = OpenAiStreamEventSummary::default();
+    if served_model.is_none() {
+        *served_model = item.model.filter(|m| !m.is_empty());
+    }
+    let mut summary = OpenAiStreamEventSummary {
+        usage: item
+            .usage
+            .as_ref()
+            .map(|u| map_token_usage_triplet(u.prompt_tokens, u.completion_tokens, u.total_tokens)),
+        ..OpenAiStreamEventSummary::default()
+    };
     if let Some(choice) = item.choices.into_iter().next() {
         summary.finish_reason = choice.finish_reason.clone();
         if let Some(content) = choice.delta.content {
@@ -1025,7 +1107,9 @@ fn handle_openai_stream_json(
                 if !tc.function.name.is_empty() {
                     p.name = tc.function.name.clone();
                 }
-                if let Some(fragment) = value_to_string_fragment(&tc.function.arguments) {
+                let fragment = value_to_string_fragment(&tc.function.arguments);
+                if fragment.is_some() || !tc.id.is_empty() || !tc.function.name.is_empty() {
+                    let fragment = fragment.unwrap_or_default();
                     p.arguments.push_str(&fragment);
                     summary.tool_fragments.push(serde_json::json!({
                         "index": idx,
@@ -1192,6 +1276,7 @@ mod tests {
             &mut |d| deltas.push(d),
             &mut content,
             &mut partials,
+            &mut None,
         )
         .expect("parse1");
         handle_openai_stream_json(
@@ -1199,6 +1284,7 @@ mod tests {
             &mut |d| deltas.push(d),
             &mut content,
             &mut partials,
+            &mut None,
         )
         .expect("parse2");
         let tc = finalize_tool_calls(partials);
@@ -1243,6 +1329,7 @@ mod tests {
             &mut |d| deltas.push(d),
             &mut content,
             &mut partials,
+            &mut None,
         )
         .expect_err("expected parse error");
         assert!(err
@@ -1266,6 +1353,39 @@ mod tests {
         assert_eq!(usage.total_tokens, Some(17));
     }
 
+    #[test]
+    fn maps_reported_model_name() {
+        let resp: OpenAiResponse = serde_json::from_str(
+            r#"{"model":"gpt-4o-mini","choices":[{"message":{"content":"ok"}}]}"#,
+        )
+        .expect("parse");
+        let mapped = map_openai_response(resp).expect("map");
+        assert_eq!(mapped.served_model.as_deref(), Some("gpt-4o-mini"));
+    }
+
+    #[test]
+    fn stream_keeps_model_from_first_event_that_reports_it() {
+        let mut content = String::new();
+        let mut partials = Vec::<PartialToolCall>::new();
+        let mut served = None;
+        for payload in [
+            r#"{"choices":[{"delta":{"role":"assistant"}}]}"#,
+            r#"{"model":"default","choices":[{"delta":{"content":"hi"}}]}"#,
+            r#"{"model":"later","choices":[{"delta":{"content":"!"}}]}"#,
+        ] {
+            handle_openai_stream_json(
+                payload,
+                &mut |_| {},
+                &mut content,
+                &mut partials,
+                &mut served,
+            )
+            .expect("parse");
+        }
+        assert_eq!(content, "hi!");
+        assert_eq!(served.as_deref(), Some("default"));
+    }
+
     #[test]
     fn summarize_openai_response_preserves_finish_reason_and_tool_preview() {
         let resp: OpenAiResponse = serde_json::from_str(
@@ -1302,6 +1422,7 @@ mod tests {
                 arguments: serde_json::json!({"command":"node --test"}),
             }],
             usage: None,
+            served_model: None,
         };
         let summary = summarize_generate_response(&resp);
         assert_eq!(summary["assistant_content_preview"], "verified=yes");
@@ -1541,3 +1662,7 @@ mod tests {
         assert!(matches!(payload.messages[1].role, Role::User));
     }
 }
+
+#[cfg(test)]
+#[path = "openai_compat_server_tests.rs"]
+mod server_tests;
@@ -0,0 +1,322 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+
+use serde_json::{json, Value};
+
+use super::OpenAiChatProvider;
+use crate::gate::ProviderKind;
+use crate::providers::http::{HttpConfig, ProviderError, ProviderErrorKind};
+use crate::providers::{ModelProvider, StreamDelta, ToolCallAssembler};
+use crate::types::{GenerateRequest, Message, Role, SideEffects, ToolDef};
+
+/// Serves one scripted `(status, content_type, body)` reply per connection
+/// and returns the raw requests it saw.
+fn scripted_server(
+    replies: Vec<(&'static str, &'static str, String)>,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let handle = std::thread::spawn(move || {
+        let mut seen = Vec::new();
+        for (status, content_type, body) in replies {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = Vec::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let n = stream.read(&mut buf).expect("read");
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(head_end) = text.find("\r\n\r\n") {
+                    let len = text[..head_end]
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= head_end + 4 + len {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            let reply = format!(
+                "HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(reply.as_bytes()).expect("write");
+            seen.push(String::from_utf8_lossy(&request).to_string());
+        }
+        seen
+    });
+    (format!("http://{addr}/v1"), handle)
+}
+
+fn request_json(raw: &str) -> Value {
+    let body = raw.split_once("\r\n\r\n").map(|(_, b)| b).unwrap_or("");
+    serde_json::from_str(body).expect("request body json")
+}
+
+fn provider(base_url: String) -> OpenAiChatProvider {
+    OpenAiChatProvider::new(
+        ProviderKind::OpenAi,
+        base_url,
+        Some("sk-test".to_string()),
+        HttpConfig {
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            ..HttpConfig::default()
+        },
+    )
+    .expect("provider")
+}
+
+fn request_with_tools() -> GenerateRequest {
+    GenerateRequest {
+        model: "gpt-4o-mini".to_string(),
+        messages: vec![Message {
+            role: Role::User,
+            content: Some("look around".to_string()),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        }],
+        tools: Some(vec![ToolDef {
+            name: "read_file".to_string(),
+            description: "Read a file".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {"path": {"type": "string"}},
+                "required": ["path"]
+            }),
+            side_effects: SideEffects::FilesystemRead,
+        }]),
+        temperature: None,
+        top_p: None,
+        max_tokens: None,
+        seed: None,
+    }
+}
+
+#[tokio::test]
+async fn generate_maps_parallel_tool_calls_and_usage() {
+    let body = json!({
+        "model": "gpt-4o-mini-2024-07-18",
+        "choices": [{
+            "message": {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [
+                    {"id": "call_a", "type": "function",
+                     "function": {"name": "read_file", "arguments": "{\"path\":\"a.txt\"}"}},
+                    {"id": "call_b", "type": "function",
+                     "function": {"name": "read_file", "arguments": "{\"path\":\"b.txt\"}"}}
+                ]
+            },
+            "finish_reason": "tool_calls"
+        }],
+        "usage": {"prompt_tokens": 31, "completion_tokens": 12, "total_tokens": 43}
+    })
+    .to_string();
+    let (base_url, server) = scripted_server(vec![("200 OK", "application/json", body)]);
+
+    let resp = provider(base_url)
+        .generate(request_with_tools())
+        .await
+        .expect("generate");
+
+    let calls = resp
+        .tool_calls
+        .iter()
+        .map(|c| (c.id.as_str(), c.arguments["path"].as_str().unwrap_or("")))
+        .collect::<Vec<_>>();
+    assert_eq!(calls, vec![("call_a", "a.txt"), ("call_b", "b.txt")]);
+    let usage = resp.usage.expect("usage");
+    assert_eq!(usage.prompt_tokens, Some(31));
+    assert_eq!(usage.completion_tokens, Some(12));
+    assert_eq!(resp.served_model.as_deref(), Some("gpt-4o-mini-2024-07-18"));
+
+    let raw = server.join().expect("server").remove(0);
+    assert!(raw.starts_with("POST /v1/chat/completions "), "{raw}");
+    assert!(raw
+        .to_ascii_lowercase()
+        .contains("authorization: bearer sk-test"));
+    let sent = request_json(&raw);
+    assert_eq!(sent["tool_choice"], "auto");
+    assert_eq!(sent["tools"][0]["type"], "function");
+    assert_eq!(sent["tools"][0]["function"]["name"], "read_file");
+    assert_eq!(
+        sent["tools"][0]["function"]["parameters"]["required"],
+        json!(["path"])
+    );
+    assert!(sent.get("stream_options").is_none());
+}
+
+#[tokio::test]
+async fn streaming_assembles_interleaved_tool_calls_and_trailing_usage() {
+    let chunks = [
+        json!({"model": "gpt-4o-mini", "choices": [{"delta": {"role": "assistant", "content": "Reading "}}]}),
+        json!({"choices": [{"delta": {"content": "both."}}]}),
+        json!({"choices": [{"delta": {"tool_calls": [
+            {"index": 0, "id": "call_a", "type": "function", "function": {"name": "read_file", "arguments": ""}},
+            {"index": 1, "id": "call_b", "type": "function", "function": {"name": "read_file", "arguments": ""}}
+        ]}}]}),
+        json!({"choices": [{"delta": {"tool_calls": [{"index": 1, "function": {"arguments": "{\"path\":\"b.txt\"}"}}]}}]}),
+        json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"arguments": "{\"path\":\"a.txt\"}"}}]}, "finish_reason": "tool_calls"}]}),
+        json!({"choices": [], "usage": {"prompt_tokens": 40, "completion_tokens": 9, "total_tokens": 49}}),
+    ];
+    let mut body = chunks
+        .iter()
+        .map(|c| format!("data: {c}\n\n"))
+        .collect::<String>();
+    body.push_str("data: [DONE]\n\n");
+    let (base_url, server) = scripted_server(vec![("200 OK", "text/event-stream", body)]);
+
+    let mut content_deltas = Vec::new();
+    let mut fragment_ids = Vec::new();
+    let resp = provider(base_url)
+        .generate_streaming(request_with_tools(), &mut |delta| match delta {
+            StreamDelta::Content(text) => content_deltas.push(text),
+            StreamDelta::ToolCallFragment(f) => fragment_ids.push(f.id),
+        })
+        .await
+        .expect("stream");
+
+    assert_eq!(content_deltas, vec!["Reading ", "both."]);
+    assert_eq!(resp.assistant.content.as_deref(), Some("Reading both."));
+    assert!(fragment_ids
+        .iter()
+        .all(|id| matches!(id.as_deref(), Some("call_a" | "call_b"))));
+    let calls = resp
+        .tool_calls
+        .iter()
+        .map(|c| (c.id.as_str(), c.arguments["path"].as_str().unwrap_or("")))
+        .collect::<Vec<_>>();
+    assert_eq!(calls, vec![("call_a", "a.txt"), ("call_b", "b.txt")]);
+    let usage = resp.usage.expect("usage from the trailing chunk");
+    assert_eq!(usage.total_tokens, Some(49));
+
+    let sent = request_json(&server.join().expect("server").remove(0));
+    assert_eq!(sent["stream"], true);
+    assert_eq!(sent["stream_options"], json!({"include_usage": true}));
+}
+
+#[tokio::test]
+async fn streamed_fragments_assemble_to_the_non_streaming_tool_calls() {
+    let whole = json!({
+        "choices": [{
+            "message": {"content": null, "tool_calls": [
+                {"id": "call_a", "type": "function",
+                 "function": {"name": "read_file", "arguments": "{\"path\":\"a.txt\"}"}},
+                {"id": "call_b", "type": "function",
+                 "function": {"name": "list_dir", "arguments": "{\"path\":\".\"}"}}
+            ]},
+            "finish_reason": "tool_calls"
+        }]
+    })
+    .to_string();
+    // Same calls split the way hosted APIs stream them: id and name first
+    // without an arguments key, then argument text in pieces.
+    let chunks = [
+        json!({"choices": [{"delta": {"tool_calls": [
+            {"index": 0, "id": "call_a", "type": "function", "function": {"name": "read_file"}}
+        ]}}]}),
+        json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"arguments": "{\"path\":"}}]}}]}),
+        json!({"choices": [{"delta": {"tool_calls": [{"index": 0, "function": {"arguments": "\"a.txt\"}"}}]}}]}),
+        json!({"choices": [{"delta": {"tool_calls": [
+            {"index": 1, "id": "call_b", "type": "function", "function": {"name": "list_dir"}}
+        ]}}]}),
+        json!({"choices": [{"delta": {"tool_calls": [{"index": 1, "function": {"arguments": "{\"path\":\".\"}"}}]}, "finish_reason": "tool_calls"}]}),
+    ];
+    let mut sse = chunks
+        .iter()
+        .map(|c| format!("data: {c}\n\n"))
+        .collect::<String>();
+    sse.push_str("data: [DONE]\n\n");
+    let (base_url, server) = scripted_server(vec![
+        ("200 OK", "application/json", whole),
+        ("200 OK", "text/event-stream", sse),
+    ]);
+    let provider = provider(base_url);
+
+    let plain = provider
+        .generate(request_with_tools())
+        .await
+        .expect("generate");
+    let mut assembler = ToolCallAssembler::default();
+    let streamed = provider
+        .generate_streaming(request_with_tools(), &mut |delta| {
+            if let StreamDelta::ToolCallFragment(f) = delta {
+                assembler.push(&f);
+            }
+        })
+        .await
+        .expect("stream");
+    server.join().expect("server");
+
+    let expected = serde_json::to_value(&plain.tool_calls).expect("json");
+    assert_eq!(expected[1]["arguments"], json!({"path": "."}));
+    assert_eq!(
+        serde_json::to_value(assembler.finish()).expect("json"),
+        expected
+    );
+    assert_eq!(
+        serde_json::to_value(&streamed.tool_calls).expect("json"),
+        expected
+    );
+}
+
+#[tokio::test]
+async fn rate_limits_and_server_errors_are_retried_and_recorded() {
+    let err_body = json!({"error": {"message": "slow down"}}).to_string();
+    let (base_url, server) = scripted_server(vec![
+        (
+            "429 Too Many Requests",
+            "application/json",
+            err_body.clone(),
+        ),
+        (
+            "500 Internal Server Error",
+            "application/json",
+            err_body.clone(),
+        ),
+        ("500 Internal Server Error", "application/json", err_body),
+    ]);
+
+    let err = provider(base_url)
+        .generate(request_with_tools())
+        .await
+        .expect_err("exhausts retries");
+
+    let pe = err.downcast_ref::<ProviderError>().expect("provider error");
+    assert!(matches!(pe.kind, ProviderErrorKind::Server));
+    assert_eq!(pe.http_status, Some(500));
+    assert_eq!(pe.attempt, 3);
+    assert_eq!(
+        pe.retries.iter().map(|r| r.status).collect::<Vec<_>>(),
+        vec![Some(429), Some(500)]
+    );
+    assert_eq!(server.join().expect("server").len(), 3);
+}
+
+#[test]
+fn openai_requires_an_api_key_and_omits_tool_choice_without_tools() {
+    let err = OpenAiChatProvider::new(
+        ProviderKind::OpenAi,
+        "https://api.openai.com/v1".to_string(),
+        Some("  ".to_string()),
+        HttpConfig::default(),
+    )
+    .expect_err("blank key");
+    assert!(err.to_string().contains("--api-key"), "{err}");
+
+    let mut req = request_with_tools();
+    req.tools = None;
+    let payload = super::to_request(req, false, super::OpenAiCompatMode::OpenAi);
+    let sent = serde_json::to_value(&payload).expect("json");
+    assert!(sent.get("tool_choice").is_none());
+    assert!(sent.get("tools").is_none());
+}
@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::providers::{ModelProvider, StreamDelta};
+use crate::types::{GenerateRequest, GenerateResponse};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProviderPacingConfig {
+    /// Minimum gap between the starts of consecutive requests in one run.
+    pub min_interval_ms: u64,
+    /// Maximum simultaneous in-flight requests per base_url in this process.
+    /// Zero means unlimited.
+    pub concurrency: usize,
+}
+
+impl ProviderPacingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.min_interval_ms > 0 || self.concurrency > 0
+    }
+}
+
+/// Pacing telemetry stored on the run record. Waits are real elapsed time and
+/// so still count against the wall-time budget; they are only attributed here.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderPacingRecordV1 {
+    pub min_interval_ms: u64,
+    pub concurrency: usize,
+    pub paced_requests: u64,
+    pub provider_pacing_wait_ms: u64,
+    pub interval_wait_ms: u64,
+    pub concurrency_wait_ms: u64,
+}
+
+#[async_trait]
+pub trait PacingClock: Send + Sync {
+    fn now(&self) -> Instant;
+    async fn sleep(&self, duration: Duration);
+}
+
+struct TokioClock;
+
+#[async_trait]
+impl PacingClock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Concurrency gates shared by every run in the process that targets the same
+/// base_url. The first run to configure a base_url fixes its limit.
+fn shared_semaphore(base_url: &str, concurrency: usize) -> Arc<Semaphore> {
+    static GATES: OnceLock<Mutex<HashMap<String, Arc<Semaphore>>>> = OnceLock::new();
+    let mut gates = GATES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    gates
+        .entry(base_url.to_string())
+        .or_insert_with(|| Arc::new(Semaphore::new(concurrency)))
+        .clone()
+}
+
+pub struct ProviderPacing {
+    config: ProviderPacingConfig,
+    clock: Arc<dyn PacingClock>,
+    semaphore: Option<Arc<Semaphore>>,
+    last_start: tokio::sync::Mutex<Option<Instant>>,
+    paced_requests: AtomicU64,
+    interval_wait_ms: AtomicU64,
+    concurrency_wait_ms: AtomicU64,
+}
+
+impl ProviderPacing {
+    /// Returns `None` when pacing is off so unpaced runs skip the layer.
+    pub fn for_base_url(config: ProviderPacingConfig, base_url: &str) -> Option<Arc<Self>> {
+        if !config.is_enabled() {
+            return None;
+        }
+        let semaphore =
+            (config.concurrency > 0).then(|| shared_semaphore(base_url, config.concurrency));
+        Some(Arc::new(Self::with_parts(
+            config,
+            semaphore,
+            Arc::new(TokioClock),
+        )))
+    }
+
+    fn with_parts(
+        config: ProviderPacingConfig,
+        semaphore: Option<Arc<Semaphore>>,
+        clock: Arc<dyn PacingClock>,
+    ) -> Self {
+        Self {
+            config,
+            clock,
+            semaphore,
+            last_start: tokio::sync::Mutex::new(None),
+            paced_requests: AtomicU64::new(0),
+            interval_wait_ms: AtomicU64::new(0),
+            concurrency_wait_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits until a request may start and returns the permit to hold for the
+    /// duration of the request.
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let interval_started = self.clock.now();
+        let mut last_start = if self.config.min_interval_ms > 0 {
+            Some(self.last_start.lock().await)
+        } else {
+            None
+        };
+        if let Some(prev) = last_start.as_ref().and_then(|guard| **guard) {
+            let ready_at = prev + Duration::from_millis(self.config.min_interval_ms);
+            let now = self.clock.now();
+            if ready_at > now {
+                self.clock.sleep(ready_at - now).await;
+            }
+        }
+        let permit_started = self.clock.now();
+        let permit = match &self.semaphore {
+            Some(semaphore) => semaphore.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        let started = self.clock.now();
+        if let Some(guard) = last_start.as_mut() {
+            **guard = Some(started);
+        }
+        drop(last_start);
+
+        self.paced_requests.fetch_add(1, Ordering::Relaxed);
+        self.interval_wait_ms.fetch_add(
+            millis(permit_started.saturating_duration_since(interval_started)),
+            Ordering::Relaxed,
+        );
+        self.concurrency_wait_ms.fetch_add(
+            millis(started.saturating_duration_since(permit_started)),
+            Ordering::Relaxed,
+        );
+        permit
+    }
+
+    pub fn run_record(&self) -> ProviderPacingRecordV1 {
+        let interval_wait_ms = self.interval_wait_ms.load(Ordering::Relaxed);
+        let concurrency_wait_ms = self.concurrency_wait_ms.load(Ordering::Relaxed);
+        ProviderPacingRecordV1 {
+            min_interval_ms: self.config.min_interval_ms,
+            concurrency: self.config.concurrency,
+            paced_requests: self.paced_requests.load(Ordering::Relaxed),
+            provider_pacing_wait_ms: interval_wait_ms.saturating_add(concurrency_wait_ms),
+            interval_wait_ms,
+            concurrency_wait_ms,
+        }
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Wraps any provider so plain and streaming generate calls go through the
+/// same pacing gate.
+pub struct PacedProvider<P> {
+    inner: P,
+    pacing: Option<Arc<ProviderPacing>>,
+}
+
+impl<P> PacedProvider<P> {
+    pub fn new(inner: P, pacing: Option<Arc<ProviderPacing>>) -> Self {
+        Self { inner, pacing }
+    }
+
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.pacing {
+            Some(pacing) => pacing.acquire().await,
+            None => None,
+        }
+    }
+}
+
+#[async_trait]
+impl<P: ModelProvider> ModelProvider for PacedProvider<P> {
+    async fn generate(&self, req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+        let _permit = self.acquire().await;
+        self.inner.generate(req).await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    async fn generate_streaming(
+        &self,
+        req: GenerateRequest,
+        on_delta: &mut (dyn FnMut(StreamDelta) + Send),
+    ) -> anyhow::Result<GenerateResponse> {
+        let _permit = self.acquire().await;
+        self.inner.generate_streaming(req, on_delta).await
+    }
+
+    fn take_retries(&self) -> Vec<crate::providers::http::RetryRecord> {
+        self.inner.take_retries()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::types::{Message, Role};
+
+    #[derive(Default)]
+    struct FakeClock {
+        origin: OnceLock<Instant>,
+        offset: Mutex<Duration>,
+        sleeps: Mutex<Vec<Duration>>,
+    }
+
+    impl FakeClock {
+        fn advance(&self, by: Duration) {
+            *self.offset.lock().expect("offset") += by;
+        }
+    }
+
+    #[async_trait]
+    impl PacingClock for FakeClock {
+        fn now(&self) -> Instant {
+            *self.origin.get_or_init(Instant::now) + *self.offset.lock().expect("offset")
+        }
+
+        async fn sleep(&self, duration: Duration) {
+            self.sleeps.lock().expect("sleeps").push(duration);
+            self.advance(duration);
+        }
+    }
+
+    /// Records the clock at each call start and the peak number of calls in
+    /// flight at once.
+    struct ProbeProvider {
+        clock: Arc<FakeClock>,
+        starts: Mutex<Vec<Instant>>,
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl ProbeProvider {
+        fn new(clock: Arc<FakeClock>) -> Self {
+            Self {
+                clock,
+                starts: Mutex::new(Vec::new()),
+                in_flight: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ModelProvider for ProbeProvider {
+        async fn generate(&self, _req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+            self.starts.lock().expect("starts").push(self.clock.now());
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            for _ in 0..4 {
+                tokio::task::yield_now().await;
+            }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(GenerateResponse {
+                assistant: Message {
+                    role: Role::Assistant,
+                    content: Some("ok".to_string()),
+                    tool_call_id: None,
+                    tool_name: None,
+                    tool_calls: None,
+                },
+                tool_calls: Vec::new(),
+                usage: None,
+                served_model: None,
+            })
+        }
+    }
+
+    fn request() -> GenerateRequest {
+        GenerateRequest {
+            model: "m".to_string(),
+            messages: Vec::new(),
+            tools: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            seed: None,
+        }
+    }
+
+    fn paced(
+        config: ProviderPacingConfig,
+        semaphore: Option<Arc<Semaphore>>,
+    ) -> (
+        Arc<FakeClock>,
+        Arc<ProviderPacing>,
+        PacedProvider<ProbeProvider>,
+    ) {
+        let clock = Arc::new(FakeClock::default());
+        let pacing = Arc::new(ProviderPacing::with_parts(config, semaphore, clock.clone()));
+        let provider = PacedProvider::new(ProbeProvider::new(clock.clone()), Some(pacing.clone()));
+        (clock, pacing, provider)
+    }
+
+    #[tokio::test]
+    async fn min_interval_spaces_consecutive_starts_and_is_attributed() {
+        let config = ProviderPacingConfig {
+            min_interval_ms: 500,
+            concurrency: 0,
+        };
+        let (clock, pacing, provider) = paced(config, None);
+        provider.generate(request()).await.expect("first");
+        clock.advance(Duration::from_millis(200));
+        provider.generate(request()).await.expect("second");
+        provider.generate(request()).await.expect("third");
+
+        let starts = provider.inner.starts.lock().expect("starts").clone();
+        assert_eq!(starts[1] - starts[0], Duration::from_millis(500));
+        assert_eq!(starts[2] - starts[1], Duration::from_millis(500));
+        assert_eq!(
+            *clock.sleeps.lock().expect("sleeps"),
+            vec![Duration::from_millis(300), Duration::from_millis(500)]
+        );
+        let record = pacing.run_record();
+        assert_eq!(record.paced_requests, 3);
+        assert_eq!(record.interval_wait_ms, 800);
+        assert_eq!(record.concurrency_wait_ms, 0);
+        assert_eq!(record.provider_pacing_wait_ms, 800);
+    }
+
+    #[tokio::test]
+    async fn concurrency_caps_parallel_requests() {
+        let config = ProviderPacingConfig {
+            min_interval_ms: 0,
+            concurrency: 2,
+        };
+        let (_clock, pacing, provider) = paced(config, Some(Arc::new(Semaphore::new(2))));
+        let calls = (0..6).map(|_| provider.generate(request()));
+        for result in futures_util::future::join_all(calls).await {
+            result.expect("generate");
+        }
+        assert_eq!(provider.inner.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(pacing.run_record().paced_requests, 6);
+    }
+
+    #[tokio::test]
+    async fn unset_pacing_adds_no_layer_state() {
+        assert!(
+            ProviderPacing::for_base_url(ProviderPacingConfig::default(), "http://x").is_none()
+        );
+        let clock = Arc::new(FakeClock::default());
+        let provider = PacedProvider::new(ProbeProvider::new(clock.clone()), None);
+        let calls = (0..3).map(|_| provider.generate(request()));
+        for result in futures_util::future::join_all(calls).await {
+            result.expect("generate");
+        }
+        assert_eq!(provider.inner.peak.load(Ordering::SeqCst), 3);
+        assert!(clock.sleeps.lock().expect("sleeps").is_empty());
+    }
+}
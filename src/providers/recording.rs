@@ -0,0 +1,404 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::gate::ProviderKind;
+use crate::providers::{ModelProvider, StreamDelta};
+use crate::store::{provider_to_string, sha256_hex};
+use crate::types::{GenerateRequest, GenerateResponse};
+
+pub const PROVIDER_TRACE_SCHEMA_V1: &str = "openagent.provider_trace.v1";
+pub const PROVIDER_TRACE_FILE_NAME: &str = "provider_trace.jsonl";
+
+/// One provider call of a recorded run. `request_hash` covers only the
+/// request messages, which is what replay matches on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderTraceRecordV1 {
+    pub schema: String,
+    pub ordinal: u64,
+    pub provider: String,
+    pub base_url: String,
+    pub streaming: bool,
+    pub request_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_hash: Option<String>,
+    pub request: GenerateRequest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<GenerateResponse>,
+    /// The provider error, when the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn provider_trace_path(state_dir: &Path, run_id: &str) -> PathBuf {
+    state_dir
+        .join("runs")
+        .join(run_id)
+        .join(PROVIDER_TRACE_FILE_NAME)
+}
+
+pub fn request_messages_hash(req: &GenerateRequest) -> String {
+    sha256_hex(&serde_json::to_vec(&req.messages).unwrap_or_default())
+}
+
+fn response_hash(resp: &GenerateResponse) -> String {
+    sha256_hex(&serde_json::to_vec(resp).unwrap_or_default())
+}
+
+/// Tees every call of the wrapped provider into a provider trace. Without a
+/// path it is a plain pass-through, so unrecorded runs keep one code path.
+pub struct RecordingProvider<P> {
+    inner: P,
+    sink: Option<Mutex<std::fs::File>>,
+    provider: String,
+    base_url: String,
+    next_ordinal: AtomicU64,
+}
+
+impl<P> RecordingProvider<P> {
+    pub fn new(
+        inner: P,
+        path: Option<&Path>,
+        provider_kind: ProviderKind,
+        base_url: &str,
+    ) -> anyhow::Result<Self> {
+        let sink = match path {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("failed to create {}", parent.display()))?;
+                }
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("failed to open provider trace {}", path.display()))?;
+                Some(Mutex::new(file))
+            }
+            None => None,
+        };
+        Ok(Self {
+            inner,
+            sink,
+            provider: provider_to_string(provider_kind),
+            base_url: base_url.to_string(),
+            next_ordinal: AtomicU64::new(1),
+        })
+    }
+
+    fn record(
+        &self,
+        request: GenerateRequest,
+        streaming: bool,
+        result: &anyhow::Result<GenerateResponse>,
+    ) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        let (response, error) = match result {
+            Ok(resp) => (Some(resp.clone()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let record = ProviderTraceRecordV1 {
+            schema: PROVIDER_TRACE_SCHEMA_V1.to_string(),
+            ordinal: self.next_ordinal.fetch_add(1, Ordering::SeqCst),
+            provider: self.provider.clone(),
+            base_url: self.base_url.clone(),
+            streaming,
+            request_hash: request_messages_hash(&request),
+            response_hash: response.as_ref().map(response_hash),
+            request,
+            response,
+            error,
+        };
+        let Ok(mut line) = serde_json::to_string(&record) else {
+            return;
+        };
+        line.push('\n');
+        if let Ok(mut file) = sink.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+#[async_trait]
+impl<P: ModelProvider> ModelProvider for RecordingProvider<P> {
+    async fn generate(&self, req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+        let recorded = self.sink.is_some().then(|| req.clone());
+        let result = self.inner.generate(req).await;
+        if let Some(request) = recorded {
+            self.record(request, false, &result);
+        }
+        result
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    async fn generate_streaming(
+        &self,
+        req: GenerateRequest,
+        on_delta: &mut (dyn FnMut(StreamDelta) + Send),
+    ) -> anyhow::Result<GenerateResponse> {
+        let recorded = self.sink.is_some().then(|| req.clone());
+        let result = self.inner.generate_streaming(req, on_delta).await;
+        if let Some(request) = recorded {
+            self.record(request, true, &result);
+        }
+        result
+    }
+
+    fn take_retries(&self) -> Vec<crate::providers::http::RetryRecord> {
+        self.inner.take_retries()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReplayMatching {
+    /// Every request must hash the same as the recorded one at its ordinal.
+    #[default]
+    Strict,
+    /// Serve the next recorded call with the same request hash, or the next
+    /// call in order when none matches.
+    Lenient,
+}
+
+/// Serves the responses of a provider trace in order instead of calling a
+/// model.
+pub struct ReplayProvider {
+    records: Vec<ProviderTraceRecordV1>,
+    matching: ReplayMatching,
+    served: Mutex<Vec<bool>>,
+}
+
+impl ReplayProvider {
+    pub fn load(path: &Path, matching: ReplayMatching) -> anyhow::Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read provider trace {}", path.display()))?;
+        let mut records = Vec::new();
+        for (i, line) in raw.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: ProviderTraceRecordV1 = serde_json::from_str(line).with_context(|| {
+                format!(
+                    "malformed provider trace record on line {} of {}",
+                    i + 1,
+                    path.display()
+                )
+            })?;
+            if record.schema != PROVIDER_TRACE_SCHEMA_V1 {
+                return Err(anyhow!(
+                    "unsupported provider trace schema {} on line {} (expected {})",
+                    record.schema,
+                    i + 1,
+                    PROVIDER_TRACE_SCHEMA_V1
+                ));
+            }
+            records.push(record);
+        }
+        if records.is_empty() {
+            return Err(anyhow!(
+                "provider trace {} has no recorded calls",
+                path.display()
+            ));
+        }
+        Ok(Self::new(records, matching))
+    }
+
+    pub fn new(records: Vec<ProviderTraceRecordV1>, matching: ReplayMatching) -> Self {
+        let served = Mutex::new(vec![false; records.len()]);
+        Self {
+            records,
+            matching,
+            served,
+        }
+    }
+
+    /// Provider kind of the recorded run, falling back to mock for names
+    /// this build does not know.
+    pub fn provider_kind(&self) -> ProviderKind {
+        self.records
+            .first()
+            .and_then(|r| ProviderKind::from_str(&r.provider, true).ok())
+            .unwrap_or(ProviderKind::Mock)
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.records.first().map_or("", |r| r.base_url.as_str())
+    }
+
+    pub fn model(&self) -> &str {
+        self.records
+            .first()
+            .map_or("", |r| r.request.model.as_str())
+    }
+
+    fn take(&self, req: &GenerateRequest) -> anyhow::Result<&ProviderTraceRecordV1> {
+        let hash = request_messages_hash(req);
+        let mut served = self.served.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(next) = served.iter().position(|done| !done) else {
+            return Err(anyhow!(
+                "provider replay exhausted: all {} recorded calls were already served",
+                self.records.len()
+            ));
+        };
+        let index = match self.matching {
+            ReplayMatching::Strict => {
+                let record = &self.records[next];
+                if record.request_hash != hash {
+                    return Err(anyhow!(mismatch_diagnostic(record, req, &hash)));
+                }
+                next
+            }
+            ReplayMatching::Lenient => (next..self.records.len())
+                .find(|&i| !served[i] && self.records[i].request_hash == hash)
+                .unwrap_or(next),
+        };
+        served[index] = true;
+        Ok(&self.records[index])
+    }
+}
+
+fn mismatch_diagnostic(
+    record: &ProviderTraceRecordV1,
+    req: &GenerateRequest,
+    hash: &str,
+) -> String {
+    let recorded = &record.request.messages;
+    let divergence = recorded
+        .iter()
+        .zip(&req.messages)
+        .position(|(a, b)| serde_json::to_value(a).ok() != serde_json::to_value(b).ok())
+        .unwrap_or_else(|| recorded.len().min(req.messages.len()));
+    format!(
+        "provider replay mismatch at call {}: request messages hash {} but the recording has {}; {} recorded vs {} live messages, first difference at message {}",
+        record.ordinal,
+        &hash[..12.min(hash.len())],
+        &record.request_hash[..12.min(record.request_hash.len())],
+        recorded.len(),
+        req.messages.len(),
+        divergence
+    )
+}
+
+#[async_trait]
+impl ModelProvider for ReplayProvider {
+    async fn generate(&self, req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+        let record = self.take(&req)?;
+        match (&record.response, &record.error) {
+            (Some(resp), _) => Ok(resp.clone()),
+            (None, Some(error)) => Err(anyhow!(error.clone())),
+            (None, None) => Err(anyhow!(
+                "provider trace call {} has neither a response nor an error",
+                record.ordinal
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        provider_trace_path, ProviderTraceRecordV1, RecordingProvider, ReplayMatching,
+        ReplayProvider,
+    };
+    use crate::gate::ProviderKind;
+    use crate::providers::scripted::{ScriptedProvider, ScriptedTurn};
+    use crate::providers::ModelProvider;
+    use crate::types::{GenerateRequest, Message, Role};
+
+    fn request(text: &str) -> GenerateRequest {
+        GenerateRequest {
+            model: "m".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: Some(text.to_string()),
+                tool_call_id: None,
+                tool_name: None,
+                tool_calls: None,
+            }],
+            tools: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            seed: None,
+        }
+    }
+
+    fn turn(text: &str) -> ScriptedTurn {
+        ScriptedTurn {
+            content: Some(text.to_string()),
+            ..ScriptedTurn::default()
+        }
+    }
+
+    async fn record_two_calls(path: &std::path::Path) {
+        let provider = RecordingProvider::new(
+            ScriptedProvider::new(vec![turn("one"), turn("two")]),
+            Some(path),
+            ProviderKind::Lmstudio,
+            "http://localhost:1234/v1",
+        )
+        .expect("recorder");
+        provider.generate(request("a")).await.expect("a");
+        provider.generate(request("b")).await.expect("b");
+    }
+
+    #[tokio::test]
+    async fn recorded_calls_replay_in_order_with_ordinals_and_hashes() {
+        let tmp = tempfile::tempdir().expect("tmp");
+        let path = provider_trace_path(tmp.path(), "run-1");
+        record_two_calls(&path).await;
+
+        let raw = std::fs::read_to_string(&path).expect("trace");
+        let records = raw
+            .lines()
+            .map(|l| serde_json::from_str::<ProviderTraceRecordV1>(l).expect("record"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records.iter().map(|r| r.ordinal).collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert!(records.iter().all(|r| r.response_hash.is_some()));
+
+        let replay = ReplayProvider::load(&path, ReplayMatching::Strict).expect("load");
+        assert_eq!(replay.provider_kind(), ProviderKind::Lmstudio);
+        assert_eq!(replay.model(), "m");
+        let first = replay.generate(request("a")).await.expect("replay a");
+        assert_eq!(first.assistant.content.as_deref(), Some("one"));
+        let second = replay.generate(request("b")).await.expect("replay b");
+        assert_eq!(second.assistant.content.as_deref(), Some("two"));
+        let err = replay.generate(request("c")).await.expect_err("exhausted");
+        assert!(err.to_string().contains("exhausted"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn strict_replay_reports_divergence_and_lenient_replay_skips_ahead() {
+        let tmp = tempfile::tempdir().expect("tmp");
+        let path = provider_trace_path(tmp.path(), "run-1");
+        record_two_calls(&path).await;
+
+        let strict = ReplayProvider::load(&path, ReplayMatching::Strict).expect("load");
+        let err = strict.generate(request("b")).await.expect_err("mismatch");
+        let msg = err.to_string();
+        assert!(msg.contains("provider replay mismatch at call 1"), "{msg}");
+        assert!(msg.contains("first difference at message 0"), "{msg}");
+
+        let lenient = ReplayProvider::load(&path, ReplayMatching::Lenient).expect("load");
+        let resp = lenient.generate(request("b")).await.expect("matched b");
+        assert_eq!(resp.assistant.content.as_deref(), Some("two"));
+        let resp = lenient
+            .generate(request("zzz"))
+            .await
+            .expect("next in order");
+        assert_eq!(resp.assistant.content.as_deref(), Some("one"));
+    }
+}
@@ -0,0 +1,456 @@
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use clap::ValueEnum;
+
+use crate::providers::http::{ProviderError, ProviderErrorKind, RetryRecord};
+use crate::providers::{ModelProvider, StreamDelta};
+use crate::types::{GenerateRequest, GenerateResponse};
+
+/// Failure causes a [`RetryPolicy`] may retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RetryOn {
+    /// Request or stream-idle timeouts.
+    Timeout,
+    /// HTTP 429.
+    RateLimit,
+    /// Any HTTP 5xx.
+    Server,
+    /// Refused or reset connections.
+    Connection,
+}
+
+impl RetryOn {
+    pub const ALL: [RetryOn; 4] = [
+        RetryOn::Timeout,
+        RetryOn::RateLimit,
+        RetryOn::Server,
+        RetryOn::Connection,
+    ];
+
+    fn matches(self, kind: ProviderErrorKind) -> bool {
+        matches!(
+            (self, kind),
+            (RetryOn::Timeout, ProviderErrorKind::Timeout)
+                | (RetryOn::RateLimit, ProviderErrorKind::RateLimit)
+                | (RetryOn::Server, ProviderErrorKind::Server)
+                | (RetryOn::Connection, ProviderErrorKind::Connection)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts per model call, the first one included. `1` turns the layer
+    /// off.
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub retry_on: Vec<RetryOn>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+            retry_on: RetryOn::ALL.to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    pub fn retries(&self, kind: ProviderErrorKind) -> bool {
+        self.retry_on.iter().any(|on| on.matches(kind))
+    }
+
+    /// Full jitter: `jitter` (in `[0, 1]`) scales the capped exponential
+    /// delay. A server's `Retry-After` is a floor, still capped at
+    /// `max_delay_ms` so one header cannot stall a run.
+    pub fn delay_ms(&self, retry_index: u32, retry_after_ms: Option<u64>, jitter: f64) -> u64 {
+        let factor = 1u64 << retry_index.min(16);
+        let ceiling = self
+            .base_delay_ms
+            .saturating_mul(factor)
+            .min(self.max_delay_ms);
+        let jittered = (ceiling as f64 * jitter.clamp(0.0, 1.0)) as u64;
+        jittered
+            .max(retry_after_ms.unwrap_or(0))
+            .min(self.max_delay_ms)
+    }
+}
+
+type JitterFn = dyn Fn() -> f64 + Send + Sync;
+
+fn random_unit() -> f64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Hands the retries taken for a call back on its final error, ahead of any
+/// the inner provider recorded itself.
+fn with_retries(
+    err: anyhow::Error,
+    attempt: u32,
+    max_attempts: u32,
+    retries: &mut Vec<RetryRecord>,
+) -> anyhow::Error {
+    if retries.is_empty() {
+        return err;
+    }
+    match err.downcast::<ProviderError>() {
+        Ok(mut pe) => {
+            let mut all = std::mem::take(retries);
+            all.append(&mut pe.retries);
+            pe.retries = all;
+            pe.attempt = attempt;
+            pe.max_attempts = max_attempts;
+            anyhow::Error::new(pe)
+        }
+        Err(err) => err,
+    }
+}
+
+/// Retries classified provider failures under a [`RetryPolicy`]. Errors that
+/// are not a [`ProviderError`], and provider errors outside `retry_on` (400s,
+/// context overflow, auth), are returned on the first attempt.
+pub struct RetryingProvider<P> {
+    inner: P,
+    policy: RetryPolicy,
+    jitter: Arc<JitterFn>,
+    absorbed: Mutex<Vec<RetryRecord>>,
+}
+
+impl<P: ModelProvider> RetryingProvider<P> {
+    pub fn new(inner: P, policy: RetryPolicy) -> Self {
+        Self::with_jitter(inner, policy, Arc::new(random_unit))
+    }
+
+    fn with_jitter(inner: P, policy: RetryPolicy, jitter: Arc<JitterFn>) -> Self {
+        Self {
+            inner,
+            policy,
+            jitter,
+            absorbed: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Decides whether `err` from attempt `attempt` gets another try. On
+    /// retry the wait is recorded and returned; otherwise the error comes
+    /// back carrying every retry taken for this call.
+    fn next_backoff(
+        &self,
+        err: anyhow::Error,
+        attempt: u32,
+        retries: &mut Vec<RetryRecord>,
+    ) -> Result<u64, anyhow::Error> {
+        let max_attempts = self.policy.max_attempts.max(1);
+        let retry = err
+            .downcast_ref::<ProviderError>()
+            .filter(|pe| attempt < max_attempts && self.policy.retries(pe.kind))
+            .map(|pe| {
+                retries.extend(pe.retries.iter().cloned());
+                let backoff_ms =
+                    self.policy
+                        .delay_ms(attempt - 1, pe.retry_after_ms, (self.jitter)());
+                retries.push(RetryRecord {
+                    attempt,
+                    max_attempts,
+                    kind: pe.kind,
+                    status: pe.http_status,
+                    backoff_ms,
+                });
+                backoff_ms
+            });
+        match retry {
+            Some(backoff_ms) => Ok(backoff_ms),
+            None => Err(with_retries(err, attempt, max_attempts, retries)),
+        }
+    }
+
+    fn absorb(&self, retries: Vec<RetryRecord>) {
+        if retries.is_empty() {
+            return;
+        }
+        if let Ok(mut absorbed) = self.absorbed.lock() {
+            absorbed.extend(retries);
+        }
+    }
+}
+
+#[async_trait]
+impl<P: ModelProvider> ModelProvider for RetryingProvider<P> {
+    async fn generate(&self, req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+        if !self.policy.is_enabled() {
+            return self.inner.generate(req).await;
+        }
+        let mut retries = Vec::new();
+        let mut attempt = 1;
+        loop {
+            match self.inner.generate(req.clone()).await {
+                Ok(resp) => {
+                    self.absorb(retries);
+                    return Ok(resp);
+                }
+                Err(err) => {
+                    let backoff_ms = self.next_backoff(err, attempt, &mut retries)?;
+                    tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    /// A stream that already delivered deltas is not retried: the caller has
+    /// seen part of a response and a second attempt would duplicate it.
+    async fn generate_streaming(
+        &self,
+        req: GenerateRequest,
+        on_delta: &mut (dyn FnMut(StreamDelta) + Send),
+    ) -> anyhow::Result<GenerateResponse> {
+        if !self.policy.is_enabled() {
+            return self.inner.generate_streaming(req, on_delta).await;
+        }
+        let mut retries = Vec::new();
+        let mut attempt = 1;
+        loop {
+            let mut forwarded = false;
+            let result = {
+                let mut forward = |delta| {
+                    forwarded = true;
+                    on_delta(delta);
+                };
+                self.inner
+                    .generate_streaming(req.clone(), &mut forward)
+                    .await
+            };
+            match result {
+                Ok(resp) => {
+                    self.absorb(retries);
+                    return Ok(resp);
+                }
+                Err(err) if forwarded => {
+                    let max_attempts = self.policy.max_attempts;
+                    return Err(with_retries(err, attempt, max_attempts, &mut retries));
+                }
+                Err(err) => {
+                    let backoff_ms = self.next_backoff(err, attempt, &mut retries)?;
+                    tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    fn take_retries(&self) -> Vec<RetryRecord> {
+        let mut out = self
+            .absorbed
+            .lock()
+            .map(|mut absorbed| std::mem::take(&mut *absorbed))
+            .unwrap_or_default();
+        out.extend(self.inner.take_retries());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::gate::ProviderKind;
+    use crate::providers::http::HttpConfig;
+    use crate::providers::openai_compat::OpenAiCompatProvider;
+    use crate::types::{Message, Role};
+
+    type Reply = (&'static str, Option<&'static str>, &'static str);
+
+    /// Serves one `(status, retry_after, body)` reply per connection and
+    /// returns how many requests arrived.
+    fn flaky_server(replies: Vec<Reply>) -> (String, std::thread::JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let handle = std::thread::spawn(move || {
+            let mut served = 0;
+            for (status, retry_after, body) in replies {
+                let (mut stream, _) = listener.accept().expect("accept");
+                let mut request = Vec::new();
+                let mut buf = [0u8; 8192];
+                loop {
+                    let n = stream.read(&mut buf).expect("read");
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(head_end) = text.find("\r\n\r\n") {
+                        let len = text[..head_end]
+                            .lines()
+                            .find_map(|l| {
+                                l.to_ascii_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                            })
+                            .unwrap_or(0);
+                        if request.len() >= head_end + 4 + len {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                let retry_after = retry_after
+                    .map(|v| format!("retry-after: {v}\r\n"))
+                    .unwrap_or_default();
+                let reply = format!(
+                    "HTTP/1.1 {status}\r\ncontent-type: application/json\r\n{retry_after}content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(reply.as_bytes()).expect("write");
+                served += 1;
+            }
+            served
+        });
+        (format!("http://{addr}/v1"), handle)
+    }
+
+    const OK_BODY: &str = r#"{"choices":[{"message":{"role":"assistant","content":"ok"}}]}"#;
+
+    fn retrying(base_url: String, max_attempts: u32) -> RetryingProvider<OpenAiCompatProvider> {
+        let inner = OpenAiCompatProvider::new(
+            ProviderKind::OpenAi,
+            base_url,
+            Some("sk-test".to_string()),
+            HttpConfig {
+                http_max_retries: 0,
+                ..HttpConfig::default()
+            },
+        )
+        .expect("provider");
+        RetryingProvider::with_jitter(
+            inner,
+            RetryPolicy {
+                max_attempts,
+                base_delay_ms: 10,
+                max_delay_ms: 200,
+                retry_on: RetryOn::ALL.to_vec(),
+            },
+            Arc::new(|| 1.0),
+        )
+    }
+
+    fn request() -> GenerateRequest {
+        GenerateRequest {
+            model: "gpt-4o-mini".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: Some("hi".to_string()),
+                tool_call_id: None,
+                tool_name: None,
+                tool_calls: None,
+            }],
+            tools: None,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn delay_is_full_jitter_capped_and_floored_by_retry_after() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay_ms: 100,
+            max_delay_ms: 1000,
+            retry_on: RetryOn::ALL.to_vec(),
+        };
+        assert_eq!(policy.delay_ms(0, None, 1.0), 100);
+        assert_eq!(policy.delay_ms(2, None, 1.0), 400);
+        assert_eq!(policy.delay_ms(2, None, 0.5), 200);
+        assert_eq!(policy.delay_ms(2, None, 0.0), 0);
+        assert_eq!(policy.delay_ms(8, None, 1.0), 1000);
+        assert_eq!(policy.delay_ms(0, Some(700), 0.0), 700);
+        assert_eq!(policy.delay_ms(0, Some(60_000), 1.0), 1000);
+        for _ in 0..100 {
+            let unit = random_unit();
+            assert!((0.0..=1.0).contains(&unit), "{unit}");
+        }
+    }
+
+    #[tokio::test]
+    async fn flaky_server_is_retried_with_bounded_delays_and_retry_after() {
+        let (base_url, server) = flaky_server(vec![
+            ("503 Service Unavailable", None, "{}"),
+            ("429 Too Many Requests", Some("0.05"), "{}"),
+            ("200 OK", None, OK_BODY),
+        ]);
+        let provider = retrying(base_url, 4);
+        let started = Instant::now();
+        let resp = provider.generate(request()).await.expect("retried");
+        let elapsed = started.elapsed();
+
+        assert_eq!(resp.assistant.content.as_deref(), Some("ok"));
+        assert_eq!(server.join().expect("server"), 3);
+        let retries = provider.take_retries();
+        assert_eq!(
+            retries
+                .iter()
+                .map(|r| (r.attempt, r.status, r.backoff_ms))
+                .collect::<Vec<_>>(),
+            vec![(1, Some(503), 10), (2, Some(429), 50)]
+        );
+        assert!(matches!(retries[0].kind, ProviderErrorKind::Server));
+        assert!(matches!(retries[1].kind, ProviderErrorKind::RateLimit));
+        assert!(provider.take_retries().is_empty(), "drained once");
+        assert!(elapsed >= Duration::from_millis(60), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(5), "{elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn exhausted_attempts_return_every_retry_on_the_error() {
+        let (base_url, server) = flaky_server(vec![
+            ("500 Internal Server Error", None, "{}"),
+            ("502 Bad Gateway", None, "{}"),
+            ("500 Internal Server Error", None, "{}"),
+        ]);
+        let provider = retrying(base_url, 3);
+        let err = provider.generate(request()).await.expect_err("exhausted");
+        assert_eq!(server.join().expect("server"), 3);
+        let pe = err.downcast_ref::<ProviderError>().expect("provider error");
+        assert_eq!((pe.attempt, pe.max_attempts), (3, 3));
+        assert_eq!(
+            pe.retries.iter().map(|r| r.backoff_ms).collect::<Vec<_>>(),
+            vec![10, 20]
+        );
+        assert!(provider.take_retries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn context_overflow_fails_on_the_first_attempt() {
+        let (base_url, server) = flaky_server(vec![(
+            "400 Bad Request",
+            None,
+            r#"{"error":{"code":"context_length_exceeded","message":"This model's maximum context length is 8192 tokens"}}"#,
+        )]);
+        let provider = retrying(base_url, 5);
+        let err = provider.generate(request()).await.expect_err("overflow");
+        assert_eq!(server.join().expect("server"), 1);
+        let pe = err.downcast_ref::<ProviderError>().expect("provider error");
+        assert!(matches!(pe.kind, ProviderErrorKind::ContextLength));
+        assert!(pe.retries.is_empty());
+        assert!(err.to_string().contains("ContextLength"), "{err}");
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::providers::ModelProvider;
+use crate::types::{GenerateRequest, GenerateResponse, Message, Role, ToolCall};
+
+/// One canned model reply: assistant text, native tool calls, or both.
+/// An entirely empty turn is a legitimate empty response.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptedTurn {
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<ScriptedToolCall>,
+    /// Fail the provider call with this message instead of replying.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptedToolCall {
+    pub name: String,
+    #[serde(default = "empty_object")]
+    pub arguments: Value,
+}
+
+fn empty_object() -> Value {
+    Value::Object(Default::default())
+}
+
+/// Replays a fixed list of turns in order, ignoring the request. Once the
+/// script runs out the last turn repeats, which is how loops are scripted.
+#[derive(Debug, Default)]
+pub struct ScriptedProvider {
+    turns: Vec<ScriptedTurn>,
+    cursor: AtomicUsize,
+}
+
+impl ScriptedProvider {
+    pub fn new(turns: Vec<ScriptedTurn>) -> Self {
+        Self {
+            turns,
+            cursor: AtomicUsize::new(0),
+        }
+    }
+
+    /// Provider calls served so far.
+    pub fn calls(&self) -> usize {
+        self.cursor.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ModelProvider for ScriptedProvider {
+    async fn generate(&self, _req: GenerateRequest) -> anyhow::Result<GenerateResponse> {
+        let n = self.cursor.fetch_add(1, Ordering::SeqCst);
+        let Some(turn) = self.turns.get(n).or_else(|| self.turns.last()) else {
+            return Err(anyhow!("scripted provider has no turns"));
+        };
+        if let Some(error) = &turn.error {
+            return Err(anyhow!(error.clone()));
+        }
+        Ok(GenerateResponse {
+            assistant: Message {
+                role: Role::Assistant,
+                content: turn.content.clone(),
+                tool_call_id: None,
+                tool_name: None,
+                tool_calls: None,
+            },
+            tool_calls: turn
+                .tool_calls
+                .iter()
+                .enumerate()
+                .map(|(i, tc)| ToolCall {
+                    id: format!("scripted_tc_{n}_{i}"),
+                    name: tc.name.clone(),
+                    arguments: tc.arguments.clone(),
+                })
+                .collect(),
+            usage: None,
+            served_model: None,
+        })
+    }
+}
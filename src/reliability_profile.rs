@@ -375,6 +375,7 @@ fn parse_compaction_mode(v: &str) -> anyhow::Result<CompactionMode> {
     match v {
         "off" => Ok(CompactionMode::Off),
         "summary" => Ok(CompactionMode::Summary),
+        "summarize" => Ok(CompactionMode::Summarize),
         _ => Err(anyhow!(
             "unsupported compaction_mode in builtin profile: {v}"
         )),
@@ -386,6 +387,7 @@ fn parse_tool_result_persist(v: &str) -> anyhow::Result<ToolResultPersist> {
         "all" => Ok(ToolResultPersist::All),
         "digest" => Ok(ToolResultPersist::Digest),
         "none" => Ok(ToolResultPersist::None),
+        "artifact_file" => Ok(ToolResultPersist::ArtifactFile),
         _ => Err(anyhow!(
             "unsupported tool_result_persist in builtin profile: {v}"
         )),
@@ -424,6 +426,7 @@ fn parse_auto_approve_scope(v: &str) -> anyhow::Result<AutoApproveScope> {
     match v {
         "run" => Ok(AutoApproveScope::Run),
         "session" => Ok(AutoApproveScope::Session),
+        "workspace" => Ok(AutoApproveScope::Workspace),
         _ => Err(anyhow!(
             "unsupported auto_approve_scope in builtin profile: {v}"
         )),
@@ -1,9 +1,18 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 use anyhow::Context;
+use serde::{Deserialize, Serialize};
 
-use crate::store::{ensure_dir, sha256_hex};
+use crate::store::{ensure_dir, sha256_hex, write_json_atomic};
+
+mod gitignore;
+
+use gitignore::GitignoreStack;
+
+pub const REPO_MAP_INDEX_SCHEMA_V1: &str = "openagent.repomap_index.v1";
 
 #[derive(Debug, Clone)]
 pub struct RepoMapLimits {
@@ -12,6 +21,16 @@ pub struct RepoMapLimits {
     pub max_out_bytes: usize,
     pub max_symbols_per_file: usize,
     pub max_symbol_line_chars: usize,
+    /// Only paths in the read allowlist are walked. The walk then starts at the
+    /// workdir so entry paths match what the read tools accept.
+    pub read_allowlist: Option<crate::tools::ReadAllowlist>,
+    /// Skip paths matched by `.gitignore` files. Only takes effect when the walk
+    /// starts at a discovered git root; the built-in exclusions (`.git`,
+    /// `.localagent`, secret-looking files) apply regardless.
+    pub respect_gitignore: bool,
+    /// When set, entries are ordered by lexical overlap with this prompt before
+    /// the output budget is applied, so relevant files survive truncation.
+    pub relevance_prompt: Option<String>,
 }
 
 impl Default for RepoMapLimits {
@@ -22,6 +41,9 @@ impl Default for RepoMapLimits {
             max_out_bytes: 64 * 1024,
             max_symbols_per_file: 6,
             max_symbol_line_chars: 160,
+            read_allowlist: None,
+            respect_gitignore: true,
+            relevance_prompt: None,
         }
     }
 }
@@ -39,6 +61,50 @@ pub struct ResolvedRepoMap {
     pub file_count_included: u64,
     pub likely_target_files: Vec<String>,
     pub repomap_hash_hex: String,
+    /// Files whose symbols were reused from the index without being re-read.
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+/// Where the incremental index lives and how to treat it.
+#[derive(Debug, Clone)]
+pub struct RepoMapCacheOptions {
+    pub index_path: PathBuf,
+    /// Ignore the existing index; every file is re-read and the index rebuilt.
+    pub refresh: bool,
+    /// Persist the updated index after the walk.
+    pub write: bool,
+}
+
+/// Per-file scan results keyed by root-relative path. Only valid for the
+/// same root and symbol limits it was built with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RepoMapIndexV1 {
+    schema_version: String,
+    root: String,
+    max_symbols_per_file: usize,
+    max_symbol_line_chars: usize,
+    files: BTreeMap<String, RepoMapIndexEntryV1>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RepoMapIndexEntryV1 {
+    size: u64,
+    mtime_ns: u64,
+    sha256: String,
+    binary: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    symbols: Vec<String>,
+}
+
+/// Index state for one walk: `previous` is read-only, `next` only holds
+/// files seen in this walk so deleted files drop out.
+#[derive(Debug, Default)]
+struct WalkCache {
+    previous: BTreeMap<String, RepoMapIndexEntryV1>,
+    next: BTreeMap<String, RepoMapIndexEntryV1>,
+    hits: u64,
+    misses: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -61,15 +127,39 @@ struct GenerationStop {
     at_path: Option<String>,
 }
 
-pub fn resolve_repo_map(workdir: &Path, limits: RepoMapLimits) -> anyhow::Result<ResolvedRepoMap> {
+pub fn repo_map_index_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("cache").join("repomap.index.json")
+}
+
+/// Builds the repo map for `workdir`. With `cache_opts`, files whose size and
+/// mtime match the index at `index_path` are not re-read; their cached symbols
+/// are reused. The output and `repomap_hash_hex` are identical to a cold run.
+pub fn resolve_repo_map(
+    workdir: &Path,
+    limits: RepoMapLimits,
+    cache_opts: Option<&RepoMapCacheOptions>,
+) -> anyhow::Result<ResolvedRepoMap> {
     let workdir = fs::canonicalize(workdir).unwrap_or_else(|_| workdir.to_path_buf());
-    let git_root = discover_git_root(&workdir);
+    let git_root = if limits.read_allowlist.is_some() {
+        None
+    } else {
+        discover_git_root(&workdir)
+    };
     let root = git_root.clone().unwrap_or_else(|| workdir.clone());
     let root_mode = if git_root.is_some() {
         "git_root"
     } else {
         "workdir"
     };
+    let root_key = root.to_string_lossy().to_string();
+    let mut cache = cache_opts.map(|opts| WalkCache {
+        previous: if opts.refresh {
+            BTreeMap::new()
+        } else {
+            load_repo_map_index(&opts.index_path, &root_key, &limits)
+        },
+        ..WalkCache::default()
+    });
 
     let mut entries = Vec::new();
     let mut stats = GenerationStats {
@@ -77,8 +167,41 @@ pub fn resolve_repo_map(workdir: &Path, limits: RepoMapLimits) -> anyhow::Result
         file_count_scanned: 0,
     };
     let mut stop: Option<GenerationStop> = None;
-    walk_repo(&root, &root, &limits, &mut stats, &mut entries, &mut stop)?;
+    let mut gitignore =
+        (limits.respect_gitignore && git_root.is_some()).then(GitignoreStack::default);
+    walk_repo(
+        &root,
+        &root,
+        &limits,
+        &mut stats,
+        &mut entries,
+        &mut stop,
+        cache.as_mut(),
+        gitignore.as_mut(),
+    )?;
+
+    let (cache_hits, cache_misses) = match (cache_opts, cache) {
+        (Some(opts), Some(cache)) => {
+            if opts.write {
+                let index = RepoMapIndexV1 {
+                    schema_version: REPO_MAP_INDEX_SCHEMA_V1.to_string(),
+                    root: root_key,
+                    max_symbols_per_file: limits.max_symbols_per_file,
+                    max_symbol_line_chars: limits.max_symbol_line_chars,
+                    files: cache.next,
+                };
+                write_json_atomic(&opts.index_path, &index).with_context(|| {
+                    format!("write repo map index {}", opts.index_path.display())
+                })?;
+            }
+            (cache.hits, cache.misses)
+        }
+        _ => (0, 0),
+    };
 
+    if let Some(prompt) = limits.relevance_prompt.as_deref() {
+        rank_entries_by_prompt(&mut entries, prompt);
+    }
     let rendered = render_repo_map_text(&entries, root_mode, &limits, &stats, stop.as_ref());
     let bytes_kept = rendered.content.len() as u64;
     let repomap_hash_hex = sha256_hex(rendered.content.as_bytes());
@@ -94,9 +217,33 @@ pub fn resolve_repo_map(workdir: &Path, limits: RepoMapLimits) -> anyhow::Result
         file_count_included: rendered.file_count_included,
         likely_target_files: Vec::new(),
         repomap_hash_hex,
+        cache_hits,
+        cache_misses,
     })
 }
 
+/// A missing, unreadable or mismatched index is treated as empty.
+fn load_repo_map_index(
+    path: &Path,
+    root: &str,
+    limits: &RepoMapLimits,
+) -> BTreeMap<String, RepoMapIndexEntryV1> {
+    let Some(index) = fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<RepoMapIndexV1>(&bytes).ok())
+    else {
+        return BTreeMap::new();
+    };
+    if index.schema_version != REPO_MAP_INDEX_SCHEMA_V1
+        || index.root != root
+        || index.max_symbols_per_file != limits.max_symbols_per_file
+        || index.max_symbol_line_chars != limits.max_symbol_line_chars
+    {
+        return BTreeMap::new();
+    }
+    index.files
+}
+
 pub fn with_likely_targets(
     map: &ResolvedRepoMap,
     prompt: &str,
@@ -143,6 +290,8 @@ pub fn render_repo_map_summary_text(map: &ResolvedRepoMap, cache_path: Option<&P
         "file_count_included: {}\n",
         map.file_count_included
     ));
+    out.push_str(&format!("cache_hits: {}\n", map.cache_hits));
+    out.push_str(&format!("cache_misses: {}\n", map.cache_misses));
     if let Some(p) = cache_path {
         out.push_str(&format!("cache_path: {}\n", p.display()));
     }
@@ -353,6 +502,31 @@ fn matches_code_extension(path: &str) -> bool {
         || path.ends_with(".hpp")
 }
 
+/// Stable sort by descending lexical overlap with the prompt; ties keep walk
+/// order, so the result is deterministic for a fixed prompt.
+fn rank_entries_by_prompt(entries: &mut [RepoMapEntry], prompt: &str) {
+    let terms = prompt_terms(prompt);
+    if terms.is_empty() {
+        return;
+    }
+    entries.sort_by_cached_key(|entry| std::cmp::Reverse(entry_relevance_score(entry, &terms)));
+}
+
+fn entry_relevance_score(entry: &RepoMapEntry, terms: &[String]) -> i32 {
+    let path = entry.path.to_ascii_lowercase();
+    let path_score = terms
+        .iter()
+        .filter(|term| path.contains(term.as_str()))
+        .count() as i32
+        * 3;
+    path_score
+        + entry
+            .symbols
+            .iter()
+            .map(|line| score_symbol_line(line, terms))
+            .sum::<i32>()
+}
+
 fn score_symbol_line(line: &str, terms: &[String]) -> i32 {
     let lower = line.to_ascii_lowercase();
     terms
@@ -464,6 +638,13 @@ fn build_repo_map_content(
         "max_symbol_line_chars={}\n",
         limits.max_symbol_line_chars
     ));
+    if let Some(prompt) = limits.relevance_prompt.as_deref() {
+        out.push_str("ranking=prompt_lexical.v1\n");
+        out.push_str(&format!(
+            "ranking_prompt_sha256={}\n",
+            sha256_hex(prompt.as_bytes())
+        ));
+    }
     out.push_str(&format!("truncated={truncated}\n"));
     out.push_str(&format!(
         "truncated_reason={}\n",
@@ -506,6 +687,7 @@ fn render_entry_block(entry: &RepoMapEntry) -> String {
     out
 }
 
+#[allow(clippy::too_many_arguments)]
 fn walk_repo(
     root: &Path,
     dir: &Path,
@@ -513,10 +695,15 @@ fn walk_repo(
     stats: &mut GenerationStats,
     entries: &mut Vec<RepoMapEntry>,
     stop: &mut Option<GenerationStop>,
+    mut cache: Option<&mut WalkCache>,
+    mut gitignore: Option<&mut GitignoreStack>,
 ) -> anyhow::Result<()> {
     if stop.is_some() {
         return Ok(());
     }
+    let pushed = gitignore
+        .as_deref_mut()
+        .is_some_and(|g| g.push_dir(dir, &render_rel_path(dir, root)));
     let mut dir_entries = fs::read_dir(dir)
         .with_context(|| format!("read_dir {}", dir.display()))?
         .collect::<Result<Vec<_>, _>>()
@@ -538,7 +725,27 @@ fn walk_repo(
             if should_exclude_dir(&rel) {
                 continue;
             }
-            walk_repo(root, &path, limits, stats, entries, stop)?;
+            if gitignore
+                .as_deref()
+                .is_some_and(|g| g.is_ignored(&rel, true))
+            {
+                continue;
+            }
+            if let Some(allowlist) = &limits.read_allowlist {
+                if !allowlist.may_contain(&rel) {
+                    continue;
+                }
+            }
+            walk_repo(
+                root,
+                &path,
+                limits,
+                stats,
+                entries,
+                stop,
+                cache.as_deref_mut(),
+                gitignore.as_deref_mut(),
+            )?;
             continue;
         }
         if !md.is_file() {
@@ -547,6 +754,17 @@ fn walk_repo(
         if should_exclude_file(&rel) {
             continue;
         }
+        if gitignore
+            .as_deref()
+            .is_some_and(|g| g.is_ignored(&rel, false))
+        {
+            continue;
+        }
+        if let Some(allowlist) = &limits.read_allowlist {
+            if !allowlist.allows(&rel) {
+                continue;
+            }
+        }
         if entries.len() >= limits.max_files {
             *stop = Some(GenerationStop {
                 reason: "max_files".to_string(),
@@ -561,25 +779,69 @@ fn walk_repo(
             });
             break;
         }
-        let data = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
-        stats.file_count_scanned += 1;
-        stats.bytes_scanned = stats.bytes_scanned.saturating_add(data.len() as u64);
-        if is_probably_binary(&data) {
-            continue;
-        }
         let lang = lang_hint(&rel);
-        let symbols = extract_symbols(
-            &String::from_utf8_lossy(&data),
-            lang,
-            limits.max_symbols_per_file,
-            limits.max_symbol_line_chars,
-        );
-        entries.push(RepoMapEntry {
-            path: rel,
-            lang,
-            size_bytes: data.len() as u64,
-            symbols,
+        let mtime_ns = md
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        let cached = cache.as_deref().and_then(|c| {
+            c.previous
+                .get(&rel)
+                .filter(|e| e.size == md.len() && e.mtime_ns == mtime_ns && mtime_ns != 0)
+                .cloned()
         });
+        let scanned = match cached {
+            Some(hit) => {
+                if let Some(cache) = cache.as_deref_mut() {
+                    cache.hits += 1;
+                }
+                hit
+            }
+            None => {
+                let data = fs::read(&path).with_context(|| format!("read {}", path.display()))?;
+                let binary = is_probably_binary(&data);
+                let symbols = if binary {
+                    Vec::new()
+                } else {
+                    extract_symbols(
+                        &String::from_utf8_lossy(&data),
+                        lang,
+                        limits.max_symbols_per_file,
+                        limits.max_symbol_line_chars,
+                    )
+                };
+                if let Some(cache) = cache.as_deref_mut() {
+                    cache.misses += 1;
+                }
+                RepoMapIndexEntryV1 {
+                    size: data.len() as u64,
+                    mtime_ns,
+                    sha256: sha256_hex(&data),
+                    binary,
+                    symbols,
+                }
+            }
+        };
+        stats.file_count_scanned += 1;
+        stats.bytes_scanned = stats.bytes_scanned.saturating_add(scanned.size);
+        if !scanned.binary {
+            entries.push(RepoMapEntry {
+                path: rel.clone(),
+                lang,
+                size_bytes: scanned.size,
+                symbols: scanned.symbols.clone(),
+            });
+        }
+        if let Some(cache) = cache.as_deref_mut() {
+            cache.next.insert(rel, scanned);
+        }
+    }
+    if pushed {
+        if let Some(gitignore) = gitignore {
+            gitignore.pop_dir();
+        }
     }
     Ok(())
 }
@@ -735,7 +997,10 @@ fn sanitize_line(input: &str, max_chars: usize) -> String {
 mod tests {
     use std::fs;
 
-    use super::{resolve_repo_map, with_likely_targets, RepoMapLimits};
+    use super::{
+        repo_map_index_path, resolve_repo_map, with_likely_targets, RepoMapCacheOptions,
+        RepoMapLimits,
+    };
 
     #[test]
     fn deterministic_order_and_path_normalization() {
@@ -754,6 +1019,7 @@ mod tests {
                 max_out_bytes: 100_000,
                 ..RepoMapLimits::default()
             },
+            None,
         )
         .expect("map");
 
@@ -764,6 +1030,159 @@ mod tests {
         assert!(map.content.contains("extractor=v1"));
     }
 
+    #[test]
+    fn read_allowlist_restricts_the_walk() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path().join("repo");
+        fs::create_dir_all(root.join("docs")).expect("docs");
+        fs::create_dir_all(root.join("src")).expect("src");
+        fs::write(root.join(".git"), "gitdir: x").expect("git marker");
+        fs::write(root.join("docs").join("guide.md"), "# Guide\n").expect("guide");
+        fs::write(root.join("src").join("secret.rs"), "pub fn s() {}\n").expect("secret");
+
+        let map = resolve_repo_map(
+            &root,
+            RepoMapLimits {
+                read_allowlist: crate::tools::ReadAllowlist::from_globs(&["docs/**".to_string()])
+                    .expect("allowlist"),
+                ..RepoMapLimits::default()
+            },
+            None,
+        )
+        .expect("map");
+        assert!(map.content.contains("path=docs/guide.md"));
+        assert!(!map.content.contains("secret.rs"));
+        assert_eq!(map.file_count_scanned, 1);
+    }
+
+    #[test]
+    fn gitignore_rules_apply_with_nesting_and_negation() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path().join("repo");
+        fs::create_dir_all(root.join("gen")).expect("gen");
+        fs::create_dir_all(root.join("src").join("vendor")).expect("vendor");
+        fs::write(root.join(".git"), "gitdir: x").expect("git marker");
+        fs::write(root.join(".gitignore"), "gen/\n*.log\n!keep.log\n").expect("root ignore");
+        fs::write(
+            root.join("src").join(".gitignore"),
+            "/vendor/\n!debug.log\n",
+        )
+        .expect("nested ignore");
+        fs::write(root.join("gen").join("out.rs"), "pub fn out() {}\n").expect("gen");
+        fs::write(root.join("src").join("lib.rs"), "pub fn lib() {}\n").expect("lib");
+        fs::write(root.join("src").join("debug.log"), "debug\n").expect("debug log");
+        fs::write(
+            root.join("src").join("vendor").join("dep.rs"),
+            "pub fn dep() {}\n",
+        )
+        .expect("dep");
+        fs::write(root.join("run.log"), "run\n").expect("run log");
+        fs::write(root.join("keep.log"), "keep\n").expect("keep log");
+
+        let map = resolve_repo_map(&root, RepoMapLimits::default(), None).expect("map");
+        assert!(map.content.contains("path=src/lib.rs"));
+        assert!(map.content.contains("path=keep.log"));
+        assert!(map.content.contains("path=src/debug.log"));
+        assert!(!map.content.contains("gen/out.rs"));
+        assert!(!map.content.contains("vendor/dep.rs"));
+        assert!(!map.content.contains("run.log"));
+
+        let unfiltered = resolve_repo_map(
+            &root,
+            RepoMapLimits {
+                respect_gitignore: false,
+                ..RepoMapLimits::default()
+            },
+            None,
+        )
+        .expect("unfiltered");
+        assert!(unfiltered.content.contains("path=gen/out.rs"));
+        assert!(unfiltered.content.contains("path=src/vendor/dep.rs"));
+        assert!(unfiltered.content.contains("path=run.log"));
+    }
+
+    #[test]
+    fn gitignore_negation_cannot_reinclude_hard_exclusions() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path().join("repo");
+        fs::create_dir_all(root.join(".localagent")).expect("state");
+        fs::write(root.join(".git"), "gitdir: x").expect("git marker");
+        fs::write(root.join(".gitignore"), "!.env\n!*.pem\n!.localagent/\n").expect("ignore");
+        fs::write(root.join(".env"), "TOKEN=x\n").expect("env");
+        fs::write(root.join("server.pem"), "-----BEGIN-----\n").expect("pem");
+        fs::write(root.join(".localagent").join("state.json"), "{}\n").expect("state");
+
+        let map = resolve_repo_map(&root, RepoMapLimits::default(), None).expect("map");
+        assert!(!map.content.contains(".env"));
+        assert!(!map.content.contains("server.pem"));
+        assert!(!map.content.contains(".localagent"));
+        assert!(map.content.contains("path=.gitignore"));
+    }
+
+    #[test]
+    fn prompt_ranking_keeps_relevant_file_under_out_budget() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path().join("repo");
+        fs::create_dir_all(root.join("src")).expect("src");
+        fs::write(root.join(".git"), "gitdir: x").expect("git marker");
+        for name in ["alpha", "beta", "gamma", "delta"] {
+            fs::write(
+                root.join("src").join(format!("{name}.rs")),
+                format!("pub fn {name}_handler() {{}}\n"),
+            )
+            .expect("filler");
+        }
+        fs::write(
+            root.join("src").join("zeta_parser.rs"),
+            "pub fn parse_zeta() {}\n",
+        )
+        .expect("target");
+        let limits = RepoMapLimits {
+            max_out_bytes: 700,
+            ..RepoMapLimits::default()
+        };
+
+        let walk_order = resolve_repo_map(&root, limits.clone(), None).expect("walk order");
+        assert!(walk_order.truncated);
+        assert!(!walk_order.content.contains("zeta_parser.rs"));
+        assert!(!walk_order.content.contains("ranking="));
+
+        let prompt = "fix the zeta parser";
+        let ranked = resolve_repo_map(
+            &root,
+            RepoMapLimits {
+                relevance_prompt: Some(prompt.to_string()),
+                ..limits.clone()
+            },
+            None,
+        )
+        .expect("ranked");
+        assert!(ranked.truncated);
+        assert!(ranked.content.contains("ranking=prompt_lexical.v1\n"));
+        assert!(ranked.content.contains(&format!(
+            "ranking_prompt_sha256={}\n",
+            crate::store::sha256_hex(prompt.as_bytes())
+        )));
+        let first = ranked
+            .content
+            .lines()
+            .find(|l| l.starts_with("- path="))
+            .expect("first entry");
+        assert!(first.starts_with("- path=src/zeta_parser.rs "));
+
+        let again = resolve_repo_map(
+            &root,
+            RepoMapLimits {
+                relevance_prompt: Some(prompt.to_string()),
+                ..limits
+            },
+            None,
+        )
+        .expect("again");
+        assert_eq!(again.repomap_hash_hex, ranked.repomap_hash_hex);
+        assert_ne!(ranked.repomap_hash_hex, walk_order.repomap_hash_hex);
+    }
+
     #[test]
     fn out_budget_truncates_at_entry_boundary() {
         let tmp = tempfile::tempdir().expect("tempdir");
@@ -783,6 +1202,7 @@ mod tests {
                 max_out_bytes: 500,
                 ..RepoMapLimits::default()
             },
+            None,
         )
         .expect("map");
         assert!(map.truncated);
@@ -802,7 +1222,7 @@ mod tests {
         fs::write(root.join("ok.rs"), "pub fn ok() {}\n").expect("ok");
         fs::write(root.join(".localagent").join("x.rs"), "pub fn x() {}\n").expect("statefile");
 
-        let map = resolve_repo_map(&root, RepoMapLimits::default()).expect("map");
+        let map = resolve_repo_map(&root, RepoMapLimits::default(), None).expect("map");
         assert!(map.content.contains("path=ok.rs"));
         assert!(!map.content.contains("path=.env"));
         assert!(!map.content.contains("secrets.txt"));
@@ -832,6 +1252,7 @@ mod tests {
                 max_out_bytes: 100_000,
                 ..RepoMapLimits::default()
             },
+            None,
         )
         .expect("map");
         let grounded = with_likely_targets(
@@ -875,7 +1296,7 @@ mod tests {
         )
         .expect("stale parser");
 
-        let map = resolve_repo_map(&root, RepoMapLimits::default()).expect("map");
+        let map = resolve_repo_map(&root, RepoMapLimits::default(), None).expect("map");
         let grounded = with_likely_targets(
             &map,
             "Update the parser so it trims whitespace before parsing.",
@@ -918,6 +1339,7 @@ mod tests {
                 max_out_bytes: 100_000,
                 ..RepoMapLimits::default()
             },
+            None,
         )
         .expect("map");
         let grounded = with_likely_targets(
@@ -962,6 +1384,7 @@ mod tests {
                 max_out_bytes: 100_000,
                 ..RepoMapLimits::default()
             },
+            None,
         )
         .expect("map");
         let grounded = with_likely_targets(
@@ -981,6 +1404,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cached_map_matches_cold_run_and_only_rereads_changed_files() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path().join("repo");
+        fs::create_dir_all(root.join("src")).expect("src");
+        fs::write(root.join(".git"), "gitdir: x").expect("git marker");
+        fs::write(root.join("src").join("a.rs"), "pub fn a() {}\n").expect("a");
+        fs::write(root.join("src").join("b.rs"), "pub fn b() {}\n").expect("b");
+        fs::write(root.join("src").join("c.rs"), "pub fn c() {}\n").expect("c");
+        fs::write(root.join("blob.bin"), [0u8, 1, 2]).expect("blob");
+        let opts = RepoMapCacheOptions {
+            index_path: repo_map_index_path(&tmp.path().join("state")),
+            refresh: false,
+            write: true,
+        };
+
+        let cold = resolve_repo_map(&root, RepoMapLimits::default(), None).expect("cold");
+        let first = resolve_repo_map(&root, RepoMapLimits::default(), Some(&opts)).expect("first");
+        assert_eq!((first.cache_hits, first.cache_misses), (0, 5));
+        assert!(opts.index_path.exists());
+        let warm = resolve_repo_map(&root, RepoMapLimits::default(), Some(&opts)).expect("warm");
+        assert_eq!((warm.cache_hits, warm.cache_misses), (5, 0));
+        for map in [&first, &warm] {
+            assert_eq!(map.content, cold.content);
+            assert_eq!(map.repomap_hash_hex, cold.repomap_hash_hex);
+            assert_eq!(map.bytes_scanned, cold.bytes_scanned);
+            assert_eq!(map.file_count_scanned, cold.file_count_scanned);
+        }
+
+        fs::write(
+            root.join("src").join("b.rs"),
+            "pub fn b() {}\npub struct Renamed;\n",
+        )
+        .expect("edit b");
+        fs::remove_file(root.join("src").join("c.rs")).expect("rm c");
+        let cold =
+            resolve_repo_map(&root, RepoMapLimits::default(), None).expect("cold after edit");
+        let warm =
+            resolve_repo_map(&root, RepoMapLimits::default(), Some(&opts)).expect("warm edit");
+        assert_eq!((warm.cache_hits, warm.cache_misses), (3, 1));
+        assert_eq!(warm.content, cold.content);
+        assert_eq!(warm.repomap_hash_hex, cold.repomap_hash_hex);
+        assert!(warm.content.contains("pub struct Renamed;"));
+        let index = fs::read_to_string(&opts.index_path).expect("index");
+        assert!(!index.contains("src/c.rs"), "{index}");
+
+        let refreshed = resolve_repo_map(
+            &root,
+            RepoMapLimits::default(),
+            Some(&RepoMapCacheOptions {
+                refresh: true,
+                ..opts.clone()
+            }),
+        )
+        .expect("refresh");
+        assert_eq!((refreshed.cache_hits, refreshed.cache_misses), (0, 4));
+        assert_eq!(refreshed.repomap_hash_hex, cold.repomap_hash_hex);
+    }
+
+    #[test]
+    fn cached_map_ignores_index_built_with_other_symbol_limits() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let root = tmp.path().join("repo");
+        fs::create_dir_all(&root).expect("root");
+        fs::write(root.join(".git"), "gitdir: x").expect("git marker");
+        fs::write(root.join("a.rs"), "pub fn a() {}\npub fn b() {}\n").expect("a");
+        let opts = RepoMapCacheOptions {
+            index_path: repo_map_index_path(&tmp.path().join("state")),
+            refresh: false,
+            write: true,
+        };
+        let narrow = RepoMapLimits {
+            max_symbols_per_file: 1,
+            ..RepoMapLimits::default()
+        };
+        resolve_repo_map(&root, narrow, Some(&opts)).expect("narrow");
+
+        let cold = resolve_repo_map(&root, RepoMapLimits::default(), None).expect("cold");
+        let warm = resolve_repo_map(&root, RepoMapLimits::default(), Some(&opts)).expect("warm");
+        assert_eq!((warm.cache_hits, warm.cache_misses), (0, 2));
+        assert_eq!(warm.content, cold.content);
+        assert!(warm.content.contains("pub fn b() {}"));
+    }
+
     #[cfg(unix)]
     #[test]
     fn symlink_is_not_followed() {
@@ -993,7 +1500,7 @@ mod tests {
         fs::write(root.join(".git"), "gitdir: x").expect("git marker");
         fs::write(outside.join("secret.rs"), "pub fn secret() {}\n").expect("secret");
         unixfs::symlink(&outside, root.join("link_out")).expect("symlink");
-        let map = resolve_repo_map(&root, RepoMapLimits::default()).expect("map");
+        let map = resolve_repo_map(&root, RepoMapLimits::default(), None).expect("map");
         assert!(!map.content.contains("secret.rs"));
         assert!(!map.content.contains("link_out"));
     }
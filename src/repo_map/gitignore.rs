@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// `.gitignore` rules in effect for the directory currently being walked,
+/// outermost first. The walk pushes a frame when it enters a directory and
+/// pops it on the way out, so nested files only apply beneath their own dir.
+#[derive(Debug, Default)]
+pub(super) struct GitignoreStack {
+    frames: Vec<GitignoreFile>,
+}
+
+#[derive(Debug)]
+struct GitignoreFile {
+    /// Directory holding the `.gitignore`, relative to the walk root; empty for
+    /// the root itself, otherwise ending in `/`.
+    base: String,
+    rules: Vec<GitignoreRule>,
+}
+
+#[derive(Debug)]
+struct GitignoreRule {
+    matcher: GlobMatcher,
+    negate: bool,
+    dir_only: bool,
+}
+
+impl GitignoreStack {
+    /// Reads `<dir>/.gitignore` (if any) and pushes its rules. Returns whether a
+    /// frame was pushed so the caller knows to pop it.
+    pub(super) fn push_dir(&mut self, dir: &Path, rel_dir: &str) -> bool {
+        let Ok(text) = fs::read_to_string(dir.join(".gitignore")) else {
+            return false;
+        };
+        let rules = parse_gitignore(&text);
+        if rules.is_empty() {
+            return false;
+        }
+        let base = if rel_dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", rel_dir.trim_end_matches('/'))
+        };
+        self.frames.push(GitignoreFile { base, rules });
+        true
+    }
+
+    pub(super) fn pop_dir(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Deeper files win over shallower ones and, within a file, the last
+    /// matching rule wins; a matching `!` rule re-includes the path.
+    pub(super) fn is_ignored(&self, rel: &str, is_dir: bool) -> bool {
+        for file in self.frames.iter().rev() {
+            let Some(sub) = rel.strip_prefix(file.base.as_str()) else {
+                continue;
+            };
+            for rule in file.rules.iter().rev() {
+                if rule.dir_only && !is_dir {
+                    continue;
+                }
+                if rule.matcher.is_match(sub) {
+                    return !rule.negate;
+                }
+            }
+        }
+        false
+    }
+}
+
+fn parse_gitignore(text: &str) -> Vec<GitignoreRule> {
+    text.lines().filter_map(parse_gitignore_line).collect()
+}
+
+fn parse_gitignore_line(line: &str) -> Option<GitignoreRule> {
+    let line = line.trim_end_matches('\r');
+    let line = if line.ends_with("\\ ") {
+        line
+    } else {
+        line.trim_end()
+    };
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (negate, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    if pattern.is_empty() {
+        return None;
+    }
+    // A slash anywhere but the end anchors the pattern to the .gitignore's dir;
+    // otherwise it matches a name at any depth.
+    let glob = if pattern.contains('/') {
+        pattern.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{pattern}")
+    };
+    let matcher = GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .backslash_escape(true)
+        .build()
+        .ok()?
+        .compile_matcher();
+    Some(GitignoreRule {
+        matcher,
+        negate,
+        dir_only,
+    })
+}
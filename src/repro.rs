@@ -141,7 +141,19 @@ pub struct ReproBuildInput {
 pub struct ReplayVerifyReport {
     pub schema_version: String,
     pub run_id: String,
+    /// Run record schema as stored on disk, before any load-time migration.
+    #[serde(default)]
+    pub record_schema_version: String,
+    #[serde(default)]
+    pub record_migrated: bool,
     pub status: String,
+    /// Whether the recomputed transcript chain matched the recorded one;
+    /// only set by `--strict` on records that carry a chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_ok: Option<bool>,
+    /// Index of the first transcript message whose chain hash differs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_divergence_index: Option<usize>,
     pub checks: Vec<ReplayVerifyCheck>,
 }
 
@@ -439,6 +451,14 @@ pub fn verify_run_record(record: &RunRecord, strict: bool) -> anyhow::Result<Rep
     }
 
     checks.push(verify_mcp_runtime_trace_continuity(record));
+    checks.extend(verify_tool_result_artifact_files(record, strict));
+    let mut chain = None;
+    if strict {
+        let (check, divergence) = verify_transcript_chain(record)?;
+        chain = Some((check.ok, divergence));
+        checks.push(check);
+        checks.extend(verify_event_stream(record));
+    }
 
     let has_error_fail = checks.iter().any(|c| !c.ok && c.severity == "error");
     let has_warn_fail = checks.iter().any(|c| !c.ok && c.severity == "warn");
@@ -459,7 +479,14 @@ pub fn verify_run_record(record: &RunRecord, strict: bool) -> anyhow::Result<Rep
     Ok(ReplayVerifyReport {
         schema_version: "openagent.replay_verify.v1".to_string(),
         run_id: record.metadata.run_id.clone(),
+        record_schema_version: record
+            .migrated_from
+            .clone()
+            .unwrap_or_else(|| record.schema_version.clone()),
+        record_migrated: record.migrated_from.is_some(),
         status: status.to_string(),
+        chain_ok: chain.map(|(ok, _)| ok),
+        first_divergence_index: chain.and_then(|(_, divergence)| divergence),
         checks,
     })
 }
@@ -525,6 +552,164 @@ fn has_mcp_runtime_surface(record: &RunRecord) -> bool {
         || !record.mcp_runtime_trace.is_empty()
 }
 
+/// Rehashes the tool results compaction moved to artifact files, and the
+/// artifacts that digested or moved results in the transcript point at.
+/// Paths are relative to the run's state dir. A changed file always fails;
+/// a missing one only warns unless `strict`.
+fn verify_tool_result_artifact_files(
+    record: &RunRecord,
+    strict: bool,
+) -> Option<ReplayVerifyCheck> {
+    let mut expected = BTreeMap::<String, String>::new();
+    for f in record.compaction.iter().flat_map(|c| &c.artifact_files) {
+        expected.insert(f.path.clone(), f.sha256.clone());
+    }
+    for message in &record.transcript {
+        if matches!(message.role, crate::types::Role::Tool) {
+            let content = message.content.as_deref().unwrap_or_default();
+            expected.extend(tool_result_artifact_refs(record, content));
+        }
+    }
+    if expected.is_empty() {
+        return None;
+    }
+    let state_dir = Path::new(&record.resolved_paths.state_dir);
+    let mut missing = Vec::new();
+    let mut changed = Vec::new();
+    for (path, sha256) in &expected {
+        match std::fs::read(state_dir.join(path)) {
+            Ok(bytes) if sha256_hex(&bytes) == *sha256 => {}
+            Ok(_) => changed.push(path.clone()),
+            Err(_) => missing.push(path.clone()),
+        }
+    }
+    let bad = missing.iter().chain(&changed).cloned().collect::<Vec<_>>();
+    Some(ReplayVerifyCheck {
+        name: "tool_result_artifact_files".to_string(),
+        expected: format!("{} files matching recorded sha256", expected.len()),
+        actual: format!("{} matching", expected.len() - bad.len()),
+        ok: bad.is_empty(),
+        severity: if strict || !changed.is_empty() {
+            "error".to_string()
+        } else {
+            "warn".to_string()
+        },
+        note: (!bad.is_empty()).then(|| format!("missing or changed: {}", bad.join(", "))),
+    })
+}
+
+/// `(path, sha256)` of the files a persisted tool result points at: the
+/// `full_output_ref` of an `artifact_file` stub, or the `artifacts=` line of
+/// a digest, whose content-addressed names are their own hashes.
+fn tool_result_artifact_refs(record: &RunRecord, content: &str) -> Vec<(String, String)> {
+    if let Ok(envelope) = serde_json::from_str::<Value>(content) {
+        let full = envelope.get("full_output_ref");
+        let path = full.and_then(|r| r.get("path")).and_then(Value::as_str);
+        let sha256 = full.and_then(|r| r.get("sha256")).and_then(Value::as_str);
+        return match (path, sha256) {
+            (Some(path), Some(sha256)) => vec![(path.to_string(), sha256.to_string())],
+            _ => Vec::new(),
+        };
+    }
+    let Some(refs) = content
+        .lines()
+        .find_map(|line| line.strip_prefix("artifacts="))
+    else {
+        return Vec::new();
+    };
+    refs.split(',')
+        .filter_map(crate::store::parse_artifact_ref)
+        .map(|hash| {
+            (
+                format!(
+                    "runs/{}/{}/{hash}",
+                    record.metadata.run_id,
+                    crate::store::RUN_ARTIFACTS_DIR_NAME
+                ),
+                hash.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Recomputes the transcript chain and returns the index of the first
+/// message whose hash differs from the recorded one. A dropped or added
+/// message diverges at the first index past the shorter chain.
+fn verify_transcript_chain(
+    record: &RunRecord,
+) -> anyhow::Result<(ReplayVerifyCheck, Option<usize>)> {
+    if record.transcript_chain_hex.is_empty() {
+        let mut check = unavailable_check("transcript_chain", "record has no transcript chain");
+        check.ok = record.transcript.is_empty();
+        return Ok((check, None));
+    }
+    let actual = crate::store::transcript_chain_hex(&record.transcript)?;
+    let recorded = &record.transcript_chain_hex;
+    let divergence = recorded
+        .iter()
+        .zip(&actual)
+        .position(|(a, b)| a != b)
+        .or_else(|| (recorded.len() != actual.len()).then(|| recorded.len().min(actual.len())));
+    let check = ReplayVerifyCheck {
+        name: "transcript_chain".to_string(),
+        expected: format!(
+            "{} messages, head {}",
+            recorded.len(),
+            recorded.last().map(String::as_str).unwrap_or("-")
+        ),
+        actual: format!(
+            "{} messages, head {}",
+            actual.len(),
+            actual.last().map(String::as_str).unwrap_or("-")
+        ),
+        ok: divergence.is_none(),
+        severity: "error".to_string(),
+        note: divergence.map(|i| format!("first divergent message index {i}")),
+    };
+    Ok((check, divergence))
+}
+
+/// Reads the run's events file and fails on seq gaps, corrupt lines or a
+/// partially written last line.
+fn verify_event_stream(record: &RunRecord) -> Option<ReplayVerifyCheck> {
+    let path = Path::new(record.cli.events_path.as_deref()?);
+    if !path.exists() {
+        return Some(unavailable_check("event_stream", "events file unavailable"));
+    }
+    let mut check = ReplayVerifyCheck {
+        name: "event_stream".to_string(),
+        expected: "contiguous seq, no corrupt lines".to_string(),
+        actual: String::new(),
+        ok: true,
+        severity: "error".to_string(),
+        note: None,
+    };
+    let mut reader = match crate::events::EventStreamReader::open(path, false) {
+        Ok(reader) => reader,
+        Err(e) => {
+            check.actual = "unreadable".to_string();
+            check.ok = false;
+            check.note = Some(e.to_string());
+            return Some(check);
+        }
+    };
+    let mut events = 0usize;
+    let mut problems = Vec::new();
+    for item in reader.by_ref() {
+        match item {
+            Ok(_) => events += 1,
+            Err(e) => problems.push(e.to_string()),
+        }
+    }
+    if reader.truncated_tail() {
+        problems.push("partially written last line".to_string());
+    }
+    check.actual = format!("{events} events");
+    check.ok = problems.is_empty();
+    check.note = (!problems.is_empty()).then(|| problems.join("; "));
+    Some(check)
+}
+
 fn verify_mcp_runtime_trace_continuity(record: &RunRecord) -> ReplayVerifyCheck {
     if record.mcp_runtime_trace.is_empty() {
         if !has_mcp_runtime_surface(record) {
@@ -556,6 +741,11 @@ fn verify_mcp_runtime_trace_continuity(record: &RunRecord) -> ReplayVerifyCheck
             continue;
         };
         let lifecycle = entry.lifecycle.as_str();
+        // Server health entries mark the degradation window, not a step in
+        // the tool call's own lifecycle.
+        if matches!(lifecycle, "server_degraded" | "server_reconnected") {
+            continue;
+        }
         let prev = last_by_tool_call.get(tool_call_id).map(String::as_str);
         let valid = match prev {
             None => matches!(lifecycle, "running" | "wait_task" | "wait_retry"),
@@ -666,6 +856,25 @@ pub fn render_verify_report(report: &ReplayVerifyReport) -> String {
         "run_id: {}\nstatus: {}\n",
         report.run_id, report.status
     ));
+    out.push_str(&format!(
+        "record_schema: {}{}\n",
+        report.record_schema_version,
+        if report.record_migrated {
+            " (migrated on load)"
+        } else {
+            ""
+        }
+    ));
+    if let Some(chain_ok) = report.chain_ok {
+        out.push_str(&format!(
+            "chain_ok: {}\nfirst_divergence_index: {}\n",
+            chain_ok,
+            report
+                .first_divergence_index
+                .map(|i| i.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        ));
+    }
     let mut checks = report.checks.clone();
     checks.sort_by(|a, b| a.name.cmp(&b.name));
     for c in checks {
@@ -740,6 +949,9 @@ mod tests {
             allow_write: false,
             enable_write_tools: false,
             exec_target: "host".to_string(),
+            read_allowlist: Vec::new(),
+            shell_allowlist: Vec::new(),
+            max_tool_calls: Default::default(),
             docker_image: None,
             docker_workdir: None,
             docker_network: None,
@@ -801,6 +1013,7 @@ mod tests {
             mcp_tool_catalog_hash_hex: None,
             mcp_servers: Vec::new(),
             mcp_config_path: Some(mcp_config_path.display().to_string()),
+            mcp_roots: Vec::new(),
             policy_version: Some(2),
             includes_resolved: Vec::new(),
             mcp_allowlist: None,
@@ -840,6 +1053,10 @@ mod tests {
             profile_source: None,
             profile_hash_hex: None,
             activated_packs: Vec::new(),
+            context_pack: None,
+            prompt_template: None,
+            learned_guidance: Vec::new(),
+            mcp_server_launches: Vec::new(),
         }
     }
 
@@ -975,11 +1192,18 @@ mod tests {
         let config_hash_hex =
             crate::store::config_hash_hex(&config_fingerprint).expect("config hash");
         RunRecord {
+            schema_version: crate::store::RUN_RECORD_SCHEMA_LATEST.to_string(),
+            migrated_from: None,
+            unknown_nested: None,
+            extra: Default::default(),
             metadata: RunMetadata {
                 run_id: "run-1".to_string(),
                 started_at: "2026-01-01T00:00:00Z".to_string(),
                 finished_at: "2026-01-01T00:00:01Z".to_string(),
                 exit_reason: "ok".to_string(),
+                model_served: None,
+                subtask_run_ids: Vec::new(),
+                post_run_hook_error: None,
             },
             mode: "single".to_string(),
             planner: None,
@@ -1009,6 +1233,7 @@ mod tests {
             tool_schema_hash_hex_map: BTreeMap::new(),
             hooks_config_hash_hex: None,
             transcript: Vec::new(),
+            transcript_chain_hex: Vec::new(),
             tool_calls: Vec::new(),
             tool_decisions: Vec::new(),
             tool_facts: Vec::new(),
@@ -1017,12 +1242,20 @@ mod tests {
             hook_report: Vec::new(),
             tool_catalog: Vec::new(),
             mcp_runtime_trace: Vec::new(),
+            timeline: Vec::new(),
+            steps: Vec::new(),
             tool_reliability: Default::default(),
             mcp_pin_snapshot: None,
+            environment_probe: None,
+            operator_queue: None,
+            provider_pacing: None,
+            otlp_export: None,
             taint: None,
             repro: None,
             final_output: "ok".to_string(),
             error: None,
+            external_interactions: None,
+            files_written: Vec::new(),
         }
     }
 
@@ -1171,6 +1404,8 @@ mod tests {
             reason: None,
             progress_ticks: None,
             elapsed_ms: None,
+            server: None,
+            schema_hash_hex: None,
         }];
         bad_trace.tool_calls = vec![ToolCall {
             id: "tc1".to_string(),
@@ -1184,4 +1419,210 @@ mod tests {
         assert_eq!(continuity.actual, "tool_calls=0 violations=1");
         assert_eq!(continuity.note.as_deref(), Some("sample=tc1:none->done"));
     }
+
+    #[test]
+    fn server_degradation_entries_do_not_break_continuity() {
+        let tmp = tempdir().expect("tempdir");
+        let entry = |tool_call_id: &str, lifecycle: &str| McpRuntimeTraceEntry {
+            step: 1,
+            lifecycle: lifecycle.to_string(),
+            tool_call_id: Some(tool_call_id.to_string()),
+            tool_name: Some("mcp.stub.echo".to_string()),
+            reason: None,
+            progress_ticks: None,
+            elapsed_ms: None,
+            server: lifecycle.starts_with("server_").then(|| "stub".to_string()),
+            schema_hash_hex: None,
+        };
+        let mut record = minimal_run_record(tmp.path());
+        record.cli.mcp_servers = vec!["stub".to_string()];
+        record.mcp_runtime_trace = vec![
+            entry("tc1", "running"),
+            entry("tc1", "server_degraded"),
+            entry("tc1", "fail"),
+            entry("tc2", "running"),
+            entry("tc2", "server_reconnected"),
+            entry("tc2", "done"),
+        ];
+
+        let continuity = verify_mcp_runtime_trace_continuity(&record);
+
+        assert!(continuity.ok, "{:?}", continuity.note);
+        assert_eq!(continuity.actual, "tool_calls=2 violations=0");
+    }
+
+    #[test]
+    fn strict_verify_rehashes_tool_result_artifact_files() {
+        let tmp = tempdir().expect("tempdir");
+        let store = crate::store::RunArtifactStore::new(tmp.path(), "run_a", 0);
+        let artifact = store
+            .store_tool_result_file("tc1", "read_file", true, "{\"ok\":true}")
+            .expect("store");
+        let mut record = minimal_run_record(tmp.path());
+        record.compaction = Some(crate::store::RunCompactionRecord {
+            settings: crate::compaction::CompactionSettings {
+                max_context_chars: 10,
+                max_context_tokens: None,
+                mode: crate::compaction::CompactionMode::Summary,
+                keep_last: 1,
+                tool_result_persist: crate::compaction::ToolResultPersist::ArtifactFile,
+            },
+            final_prompt_size_chars: 0,
+            report: None,
+            digest_refetch: None,
+            artifact_files: vec![artifact.clone()],
+        });
+
+        let report = verify_run_record(&record, true).expect("verify");
+        assert!(check(&report, "tool_result_artifact_files").ok);
+        let lenient = verify_run_record(&record, false).expect("verify");
+        assert!(check(&lenient, "tool_result_artifact_files").ok);
+
+        std::fs::write(tmp.path().join(&artifact.path), b"changed").expect("tamper");
+        let report = verify_run_record(&record, true).expect("verify");
+        let files = check(&report, "tool_result_artifact_files");
+        assert!(!files.ok);
+        assert_eq!(report.status, "fail");
+        assert_eq!(
+            files.note.as_deref(),
+            Some("missing or changed: runs/run_a/artifacts/tc1.json")
+        );
+    }
+
+    fn chat_message(role: crate::types::Role, content: &str) -> crate::types::Message {
+        crate::types::Message {
+            role,
+            content: Some(content.to_string()),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        }
+    }
+
+    #[test]
+    fn strict_verify_reports_first_divergent_transcript_message() {
+        use crate::types::Role;
+
+        let tmp = tempdir().expect("tempdir");
+        let mut record = minimal_run_record(tmp.path());
+        record.transcript = vec![
+            chat_message(Role::System, "sys"),
+            chat_message(Role::User, "fix the bug"),
+            chat_message(Role::Assistant, "done"),
+        ];
+        record.transcript_chain_hex =
+            crate::store::transcript_chain_hex(&record.transcript).expect("chain");
+
+        let report = verify_run_record(&record, true).expect("verify");
+        assert!(check(&report, "transcript_chain").ok);
+        assert_eq!(report.chain_ok, Some(true));
+        assert_eq!(report.first_divergence_index, None);
+        let lenient = verify_run_record(&record, false).expect("verify");
+        assert_eq!(lenient.chain_ok, None);
+
+        record.transcript[1].content = Some("fix every bug".to_string());
+        let report = verify_run_record(&record, true).expect("verify");
+        assert_eq!(report.status, "fail");
+        assert_eq!(report.chain_ok, Some(false));
+        assert_eq!(report.first_divergence_index, Some(1));
+        assert_eq!(
+            check(&report, "transcript_chain").note.as_deref(),
+            Some("first divergent message index 1")
+        );
+        let rendered = super::render_verify_report(&report);
+        assert!(rendered.contains("chain_ok: false\nfirst_divergence_index: 1\n"));
+
+        record.transcript[1].content = Some("fix the bug".to_string());
+        record.transcript.pop();
+        let report = verify_run_record(&record, true).expect("verify");
+        assert_eq!(report.first_divergence_index, Some(2));
+    }
+
+    #[test]
+    fn missing_digest_artifact_warns_unless_strict() {
+        use crate::types::Role;
+
+        let tmp = tempdir().expect("tempdir");
+        let mut record = minimal_run_record(tmp.path());
+        let hash = crate::store::sha256_hex(b"full output");
+        let mut digest = chat_message(
+            Role::Tool,
+            &format!("TOOL_OUTPUT_DIGEST v2\ntool=shell\nartifacts=artifact:{hash}\nsha256=x"),
+        );
+        digest.tool_call_id = Some("tc1".to_string());
+        record.transcript = vec![digest];
+
+        let lenient = verify_run_record(&record, false).expect("verify");
+        let files = check(&lenient, "tool_result_artifact_files");
+        assert!(!files.ok);
+        assert_eq!(files.severity, "warn");
+        assert_ne!(lenient.status, "fail");
+        let strict = verify_run_record(&record, true).expect("verify");
+        assert_eq!(
+            check(&strict, "tool_result_artifact_files").severity,
+            "error"
+        );
+        assert_eq!(strict.status, "fail");
+
+        let dir = crate::store::run_artifacts_dir(tmp.path(), &record.metadata.run_id);
+        std::fs::create_dir_all(&dir).expect("mkdir");
+        std::fs::write(dir.join(&hash), b"full output").expect("artifact");
+        let lenient = verify_run_record(&record, false).expect("verify");
+        assert!(check(&lenient, "tool_result_artifact_files").ok);
+
+        std::fs::write(dir.join(&hash), b"edited output").expect("tamper");
+        let lenient = verify_run_record(&record, false).expect("verify");
+        assert_eq!(
+            check(&lenient, "tool_result_artifact_files").severity,
+            "error"
+        );
+        assert_eq!(lenient.status, "fail");
+    }
+
+    #[test]
+    fn strict_verify_flags_event_stream_gaps() {
+        use crate::events::{Event, EventKind, EventSink, JsonlFileSink};
+
+        let tmp = tempdir().expect("tempdir");
+        let events_path = tmp.path().join("events.jsonl");
+        let mut sink = JsonlFileSink::new(&events_path).expect("sink");
+        for step in 0..3 {
+            sink.emit(Event::new_raw(
+                "run_a".to_string(),
+                step,
+                EventKind::StepStarted,
+                serde_json::json!({}),
+            ))
+            .expect("emit");
+        }
+        let mut record = minimal_run_record(tmp.path());
+        record.cli.events_path = Some(events_path.display().to_string());
+
+        let report = verify_run_record(&record, true).expect("verify");
+        let stream = check(&report, "event_stream");
+        assert!(stream.ok, "{:?}", stream.note);
+        assert_eq!(stream.actual, "3 events");
+        assert!(!verify_run_record(&record, false)
+            .expect("verify")
+            .checks
+            .iter()
+            .any(|c| c.name == "event_stream"));
+
+        let raw = std::fs::read_to_string(&events_path).expect("read");
+        let gapped = raw
+            .lines()
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, l)| format!("{l}\n"))
+            .collect::<String>();
+        std::fs::write(&events_path, format!("{gapped}{{\"ts\":")).expect("write");
+        let report = verify_run_record(&record, true).expect("verify");
+        let stream = check(&report, "event_stream");
+        assert!(!stream.ok);
+        assert_eq!(report.status, "fail");
+        assert_eq!(
+            stream.note.as_deref(),
+            Some("event seq gap: expected 2, got 3; partially written last line")
+        );
+    }
 }
@@ -56,6 +56,9 @@ pub(crate) async fn prepare_tools_and_qualification<P: ModelProvider>(
         args.enable_write_tools,
         args.allow_shell || args.allow_shell_in_workdir,
     );
+    if args.enable_subtasks {
+        all_tools.push(crate::tools::spawn_subtask_tool_def());
+    }
     let mut mcp_tool_snapshot: Vec<store::McpToolSnapshotEntry> = Vec::new();
     if let Some(reg) = mcp_registry {
         let mut mcp_defs = reg.tool_defs();
@@ -80,7 +83,8 @@ pub(crate) async fn prepare_tools_and_qualification<P: ModelProvider>(
         base_url,
         worker_model,
         args.stream,
-        args.enable_write_tools || args.allow_write,
+        // A replayed run already reflects the recorded run's qualification.
+        (args.enable_write_tools || args.allow_write) && args.replay_provider.is_none(),
         &mut all_tools,
         &qual_cache_path,
     )
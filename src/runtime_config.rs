@@ -198,9 +198,10 @@ pub(crate) fn apply_params_input(run: &mut RunArgs, input: &str) -> Result<Strin
         "compaction_mode" | "compaction" => match value.to_ascii_lowercase().as_str() {
             "off" => run.compaction_mode = CompactionMode::Off,
             "summary" => run.compaction_mode = CompactionMode::Summary,
+            "summarize" => run.compaction_mode = CompactionMode::Summarize,
             _ => {
                 return Err(format!(
-                    "invalid compaction_mode: {value} (expected off|summary)"
+                    "invalid compaction_mode: {value} (expected off|summary|summarize)"
                 ))
             }
         },
@@ -218,9 +219,10 @@ pub(crate) fn apply_params_input(run: &mut RunArgs, input: &str) -> Result<Strin
                 "all" => ToolResultPersist::All,
                 "digest" => ToolResultPersist::Digest,
                 "none" => ToolResultPersist::None,
+                "artifact_file" | "artifact-file" => ToolResultPersist::ArtifactFile,
                 _ => {
                     return Err(format!(
-                        "invalid tool_result_persist: {value} (expected all|digest|none)"
+                        "invalid tool_result_persist: {value} (expected all|digest|none|artifact_file)"
                     ));
                 }
             };
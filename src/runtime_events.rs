@@ -1,4 +1,4 @@
-use crate::events::{Event, EventSink};
+use crate::events::{Event, EventPayload, EventSink};
 
 pub(crate) fn short_error(s: &str) -> String {
     s.chars().take(200).collect()
@@ -21,14 +21,11 @@ pub(crate) fn emit_event(
     sink: &mut Option<Box<dyn EventSink>>,
     run_id: &str,
     step: u32,
-    kind: EventKind,
-    data: serde_json::Value,
+    payload: impl Into<EventPayload>,
 ) {
     if let Some(s) = sink {
-        if let Err(e) = s.emit(Event::new(run_id.to_string(), step, kind, data)) {
+        if let Err(e) = s.emit(Event::new(run_id.to_string(), step, payload)) {
             eprintln!("WARN: failed to emit event: {e}");
         }
     }
 }
-
-use crate::events::EventKind;
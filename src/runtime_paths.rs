@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use crate::context_packs::ContextPackResolution;
 use crate::gate::ProviderKind;
 use crate::instructions::InstructionResolution;
 use crate::lsp_context::ResolvedLspContext;
@@ -9,7 +10,8 @@ use crate::project_guidance::ResolvedProjectGuidance;
 use crate::repo_map::ResolvedRepoMap;
 use crate::session;
 use crate::store::{
-    self, provider_to_string, stable_path_string, ConfigFingerprintV1, RunCliConfig,
+    self, provider_to_string, stable_path_string, ConfigFingerprintV1, PromptTemplateRecord,
+    RunCliConfig,
 };
 use crate::target::ExecTargetKind;
 use crate::trust::policy::McpAllowSummary;
@@ -72,6 +74,9 @@ pub(crate) struct RunCliConfigInput<'a> {
     pub repo_map: Option<&'a ResolvedRepoMap>,
     pub lsp_context: Option<&'a ResolvedLspContext>,
     pub activated_packs: &'a [ActivatedPack],
+    pub context_pack: Option<&'a ContextPackResolution>,
+    pub learned_guidance: Option<&'a crate::learning::LearnedGuidance>,
+    pub prompt_template: Option<&'a PromptTemplateRecord>,
 }
 
 pub(crate) fn build_run_cli_config(input: RunCliConfigInput<'_>) -> RunCliConfig {
@@ -101,18 +106,39 @@ pub(crate) fn build_run_cli_config(input: RunCliConfigInput<'_>) -> RunCliConfig
         repo_map,
         lsp_context,
         activated_packs,
+        context_pack,
+        learned_guidance,
+        prompt_template,
     } = input;
     let docker_config_summary = if matches!(args.exec_target, ExecTargetKind::Docker) {
-        Some(format!(
-            "docker image={} network={} workdir={} user={} per_call=true",
+        let mut summary = format!(
+            "docker image={} network={} workdir={} user={} per_call={}",
             args.docker_image,
             format!("{:?}", args.docker_network).to_lowercase(),
             args.docker_workdir,
-            args.docker_user.as_deref().unwrap_or("(default)")
-        ))
+            args.docker_user.as_deref().unwrap_or("(default)"),
+            !args.docker_persistent
+        );
+        if let Some(memory) = &args.docker_memory {
+            summary.push_str(&format!(" memory={memory}"));
+        }
+        if let Some(cpus) = &args.docker_cpus {
+            summary.push_str(&format!(" cpus={cpus}"));
+        }
+        if let Some(pids) = args.docker_pids_limit {
+            summary.push_str(&format!(" pids_limit={pids}"));
+        }
+        if args.docker_readonly_workdir {
+            summary.push_str(" readonly_workdir=true");
+        }
+        Some(summary)
     } else {
         None
     };
+    // Specs were validated at launch; a record built from bad specs is empty.
+    let mcp_roots = crate::mcp::roots::McpRootMap::from_specs(&args.mcp_root)
+        .map(|roots| roots.records(&args.mcp, &mcp_tool_snapshot))
+        .unwrap_or_default();
     RunCliConfig {
         mode: format!("{:?}", mode).to_lowercase(),
         agent_mode: format!("{:?}", args.agent_mode).to_lowercase(),
@@ -136,6 +162,10 @@ pub(crate) fn build_run_cli_config(input: RunCliConfigInput<'_>) -> RunCliConfig
         allow_write: args.allow_write,
         enable_write_tools: args.enable_write_tools,
         exec_target: format!("{:?}", args.exec_target).to_lowercase(),
+        read_allowlist: args.allow_read_path.clone(),
+        shell_allowlist: args.allow_shell_cmd.clone(),
+        max_tool_calls: crate::agent_budget::parse_tool_call_limits(&args.max_tool_calls)
+            .unwrap_or_default(),
         docker_image: if matches!(args.exec_target, ExecTargetKind::Docker) {
             Some(args.docker_image.clone())
         } else {
@@ -217,7 +247,9 @@ pub(crate) fn build_run_cli_config(input: RunCliConfigInput<'_>) -> RunCliConfig
             servers.sort();
             servers
         },
+        mcp_server_launches: store::mcp_server_launch_records(mcp_config_path, &args.mcp),
         mcp_config_path: Some(stable_path_string(mcp_config_path)),
+        mcp_roots,
         policy_version,
         includes_resolved,
         mcp_allowlist,
@@ -292,6 +324,11 @@ pub(crate) fn build_run_cli_config(input: RunCliConfigInput<'_>) -> RunCliConfig
                 truncated: p.truncated,
             })
             .collect(),
+        context_pack: context_pack.map(ContextPackResolution::to_record),
+        prompt_template: prompt_template.cloned(),
+        learned_guidance: learned_guidance
+            .map(|g| g.injected.clone())
+            .unwrap_or_default(),
     }
 }
 
@@ -474,6 +511,23 @@ pub(crate) fn resolved_mcp_config_path(args: &RunArgs, state_dir: &std::path::Pa
         .unwrap_or_else(|| state_dir.join("mcp_servers.json"))
 }
 
+pub(crate) fn mcp_metadata_policy(args: &RunArgs) -> crate::mcp::sanitize::McpMetadataPolicy {
+    crate::mcp::sanitize::McpMetadataPolicy {
+        strict_names: args.mcp_strict_names,
+        ..crate::mcp::sanitize::McpMetadataPolicy::new(
+            args.mcp_strict_metadata,
+            &args.mcp_injection_phrase,
+        )
+    }
+}
+
+pub(crate) fn mcp_reconnect_policy(args: &RunArgs) -> crate::mcp::health::McpReconnectPolicy {
+    crate::mcp::health::McpReconnectPolicy {
+        max_attempts: args.mcp_reconnect_attempts,
+        ..Default::default()
+    }
+}
+
 pub(crate) fn resolved_hooks_config_path(args: &RunArgs, state_dir: &std::path::Path) -> PathBuf {
     args.hooks_config
         .clone()
@@ -1,11 +1,17 @@
+use std::collections::BTreeSet;
 use std::sync::mpsc::Sender;
 
 use anyhow::Context;
 
 use crate::events::{
-    Event, EventSink, JsonStdoutProjectedSink, JsonlFileSink, MultiSink, StdoutSink,
+    ErrorPayload, Event, EventSink, JsonStdoutProjectedSink, JsonlFileSink, MultiSink,
+    MultiSinkFailurePolicy, StdoutSink,
 };
-use crate::gate::{compute_policy_hash_hex, NoGate, ToolGate, TrustGate, TrustMode};
+use crate::gate::{
+    compute_policy_hash_hex, workspace_approvals_path, NoGate, ToolGate, TrustGate, TrustMode,
+    WorkspaceApprovals,
+};
+use crate::progress_line::{ProgressLineConfig, ProgressSink};
 use crate::store;
 use crate::trust;
 use crate::trust::approvals::ApprovalsStore;
@@ -13,6 +19,7 @@ use crate::trust::audit::AuditLog;
 use crate::trust::policy::{McpAllowSummary, Policy};
 use crate::{RunArgs, RunOutputMode};
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn build_event_sink(
     stream: bool,
     output_mode: RunOutputMode,
@@ -20,8 +27,15 @@ pub(crate) fn build_event_sink(
     tui_enabled: bool,
     ui_tx: Option<Sender<Event>>,
     suppress_stdout: bool,
+    progress: Option<ProgressLineConfig>,
+    otlp: Option<crate::otlp::OtlpSpanSink>,
+    extra_sinks: Vec<Box<dyn EventSink>>,
 ) -> anyhow::Result<Option<Box<dyn EventSink>>> {
     let mut multi = MultiSink::new();
+    // First in line so it clears its row before any other sink prints.
+    if let Some(config) = progress {
+        multi.push(Box::new(ProgressSink::stderr(config)));
+    }
     if !tui_enabled && !suppress_stdout {
         match output_mode {
             RunOutputMode::Json => multi.push(Box::new(JsonStdoutProjectedSink::new())),
@@ -38,10 +52,69 @@ pub(crate) fn build_event_sink(
     if let Some(path) = events_path {
         multi.push(Box::new(JsonlFileSink::new(path)?));
     }
-    if multi.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(Box::new(multi)))
+    if let Some(sink) = otlp {
+        multi.push(Box::new(sink));
+    }
+    if extra_sinks.is_empty() {
+        return Ok((!multi.is_empty()).then(|| Box::new(multi) as Box<dyn EventSink>));
+    }
+    Ok(Some(Box::new(RunEventSink::new(multi, extra_sinks))))
+}
+
+/// Built-in sinks plus caller-attached ones. Caller sinks go last and are
+/// best-effort: a consumer that hangs up must not cost the built-in sinks an
+/// event or fail the run. The first failure of each caller sink is reported
+/// to the built-in sinks as an `error` event with source
+/// `external_event_sink`.
+pub(crate) struct RunEventSink {
+    builtin: MultiSink,
+    external: MultiSink,
+    reported: BTreeSet<usize>,
+}
+
+impl RunEventSink {
+    pub(crate) fn new(builtin: MultiSink, external: Vec<Box<dyn EventSink>>) -> Self {
+        Self {
+            builtin,
+            external: external.into_iter().fold(
+                MultiSink::new().with_policy(MultiSinkFailurePolicy::Continue),
+                MultiSink::with_sink,
+            ),
+            reported: BTreeSet::new(),
+        }
+    }
+}
+
+impl EventSink for RunEventSink {
+    fn emit(&mut self, event: Event) -> anyhow::Result<()> {
+        let seen = self.external.failures().len();
+        self.builtin.emit(event.clone())?;
+        self.external.emit(event.clone())?;
+        let reported = &mut self.reported;
+        let first_failures = self.external.failures()[seen..]
+            .iter()
+            .filter(|failure| reported.insert(failure.sink_index))
+            .cloned()
+            .collect::<Vec<_>>();
+        for failure in first_failures {
+            let kind = serde_json::to_value(&failure.event_kind)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default();
+            self.builtin.emit(Event::new(
+                event.run_id.clone(),
+                event.step,
+                ErrorPayload {
+                    error: format!(
+                        "attached event sink {} failed on {kind}; it may miss later events: {}",
+                        failure.sink_index, failure.message
+                    ),
+                    source: Some("external_event_sink".to_string()),
+                    ..ErrorPayload::default()
+                },
+            ))?;
+        }
+        Ok(())
     }
 }
 
@@ -55,6 +128,13 @@ pub(crate) struct GateBuild {
     pub(crate) mcp_allowlist: Option<McpAllowSummary>,
 }
 
+fn workspace_approvals(args: &RunArgs, paths: &store::StatePaths) -> WorkspaceApprovals {
+    WorkspaceApprovals::new(
+        workspace_approvals_path(&paths.state_dir),
+        args.workspace_approval_ttl_hours,
+    )
+}
+
 pub(crate) fn build_gate(args: &RunArgs, paths: &store::StatePaths) -> anyhow::Result<GateBuild> {
     match args.trust {
         TrustMode::Off => Ok(GateBuild {
@@ -95,13 +175,16 @@ pub(crate) fn build_gate(args: &RunArgs, paths: &store::StatePaths) -> anyhow::R
             let includes_resolved = policy.includes_resolved().to_vec();
             let mcp_allowlist = policy.mcp_allowlist_summary();
             Ok(GateBuild {
-                gate: Box::new(TrustGate::new(
-                    policy.clone(),
-                    ApprovalsStore::new(paths.approvals_path.clone()),
-                    AuditLog::new(paths.audit_path.clone()),
-                    TrustMode::Auto,
-                    policy_hash_hex.clone(),
-                )),
+                gate: Box::new(
+                    TrustGate::new(
+                        policy.clone(),
+                        ApprovalsStore::new(paths.approvals_path.clone()),
+                        AuditLog::new(paths.audit_path.clone()),
+                        TrustMode::Auto,
+                        policy_hash_hex.clone(),
+                    )
+                    .with_workspace_approvals(workspace_approvals(args, paths)),
+                ),
                 policy_hash_hex: Some(policy_hash_hex),
                 policy_source: "file",
                 policy_for_exposure: Some(policy),
@@ -137,13 +220,16 @@ pub(crate) fn build_gate(args: &RunArgs, paths: &store::StatePaths) -> anyhow::R
             let includes_resolved = policy.includes_resolved().to_vec();
             let mcp_allowlist = policy.mcp_allowlist_summary();
             Ok(GateBuild {
-                gate: Box::new(TrustGate::new(
-                    policy.clone(),
-                    ApprovalsStore::new(paths.approvals_path.clone()),
-                    AuditLog::new(paths.audit_path.clone()),
-                    TrustMode::On,
-                    policy_hash_hex.clone(),
-                )),
+                gate: Box::new(
+                    TrustGate::new(
+                        policy.clone(),
+                        ApprovalsStore::new(paths.approvals_path.clone()),
+                        AuditLog::new(paths.audit_path.clone()),
+                        TrustMode::On,
+                        policy_hash_hex.clone(),
+                    )
+                    .with_workspace_approvals(workspace_approvals(args, paths)),
+                ),
                 policy_hash_hex: Some(policy_hash_hex),
                 policy_source,
                 policy_for_exposure: Some(policy),
@@ -164,7 +250,8 @@ mod tests {
     use serde_json::json;
     use tempfile::tempdir;
 
-    use super::build_gate;
+    use super::{build_gate, RunEventSink};
+    use crate::events::{ChannelSink, Event, EventKind, EventSink, MultiSink};
     use crate::gate::{
         ApprovalKeyVersion, ApprovalMode, AutoApproveScope, GateContext, GateDecision,
         ProviderKind, TrustMode,
@@ -182,6 +269,7 @@ mod tests {
         GateContext {
             workdir: workdir.to_path_buf(),
             allow_shell: true,
+            shell_allowlist: None,
             allow_write: false,
             approval_mode: ApprovalMode::Interrupt,
             auto_approve_scope: AutoApproveScope::Run,
@@ -202,6 +290,7 @@ mod tests {
             taint_mode: TaintMode::Propagate,
             taint_overall: TaintLevel::Clean,
             taint_sources: Vec::new(),
+            taint_injection_digest: None,
         }
     }
 
@@ -223,6 +312,42 @@ rules:
 "#
     }
 
+    #[test]
+    fn failing_attached_sink_is_reported_once_to_builtin_sinks() {
+        let (builtin_tx, mut builtin_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (external_tx, external_rx) = tokio::sync::mpsc::unbounded_channel();
+        drop(external_rx);
+        let mut sink = RunEventSink::new(
+            MultiSink::new().with_sink(Box::new(ChannelSink::new(builtin_tx))),
+            vec![Box::new(ChannelSink::new(external_tx))],
+        );
+        for step in 0..3 {
+            sink.emit(Event::new_raw(
+                "r".to_string(),
+                step,
+                EventKind::StepStarted,
+                json!({}),
+            ))
+            .expect("attached sink failures are not fatal");
+        }
+        let seen = std::iter::from_fn(|| builtin_rx.try_recv().ok()).collect::<Vec<_>>();
+        let kinds = seen.iter().map(|e| e.kind.clone()).collect::<Vec<_>>();
+        assert!(matches!(
+            kinds.as_slice(),
+            [
+                EventKind::StepStarted,
+                EventKind::Error,
+                EventKind::StepStarted,
+                EventKind::StepStarted
+            ]
+        ));
+        assert_eq!(seen[1].data["source"], "external_event_sink");
+        assert!(seen[1].data["error"]
+            .as_str()
+            .expect("error")
+            .contains("receiver dropped"));
+    }
+
     #[test]
     fn build_gate_happy_path_allows_when_trust_off() {
         let tmp = tempdir().expect("tempdir");
@@ -1077,99 +1077,78 @@ async fn execute_backend_run_inner(
     args.stream = false;
     args.tui = false;
     args.disable_implementation_guard = true;
-    let (ui_tx, ui_rx) = std::sync::mpsc::channel::<crate::events::Event>();
-    let event_state = state.clone();
-    let event_run_id = run_id.to_string();
-    let event_thread = std::thread::spawn(move || {
-        let mut sequence = 0u64;
-        while let Ok(event) = ui_rx.recv() {
-            sequence = sequence.saturating_add(1);
-            if let Some(projected) = crate::events::project_event_v1(&event, sequence) {
-                update_run_record(&event_state, &event_run_id, |record| {
-                    record.projected_events.push(projected);
-                    record.event_notify.notify_waiters();
-                });
-            }
-        }
-    });
-
+    let (event_tx, event_task) = spawn_event_projection(state.clone(), run_id.to_string());
+
+    let request = crate::agent_runtime::AgentUiRunRequest::new(
+        provider_kind,
+        &base_url,
+        model,
+        prompt,
+        &args,
+        &state.paths,
+    )
+    .with_event_sink(Box::new(crate::events::ChannelSink::new(event_tx)))
+    .with_operator_queue(operator_queue_rx)
+    .with_cancel_pair(external_cancel_pair)
+    .suppress_stdout_stream(true);
     let result = match provider_kind {
-        ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+        ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
             let provider = OpenAiCompatProvider::new(
                 provider_kind,
                 base_url.clone(),
                 args.api_key.clone(),
                 provider_runtime::http_config_from_run_args(&args),
             )?;
-            crate::run_agent_with_ui(
-                provider,
-                provider_kind,
-                &base_url,
-                model,
-                prompt,
-                &args,
-                &state.paths,
-                Some(ui_tx),
-                Some(operator_queue_rx),
-                Some(external_cancel_pair),
-                None,
-                None,
-                true,
-            )
-            .await
+            crate::run_agent_with_ui(provider, request).await
         }
         ProviderKind::Ollama => {
             let provider = OllamaProvider::new(
                 base_url.clone(),
                 provider_runtime::http_config_from_run_args(&args),
             )?;
-            crate::run_agent_with_ui(
-                provider,
-                provider_kind,
-                &base_url,
-                model,
-                prompt,
-                &args,
-                &state.paths,
-                Some(ui_tx),
-                Some(operator_queue_rx),
-                Some(external_cancel_pair),
-                None,
-                None,
-                true,
-            )
-            .await
+            crate::run_agent_with_ui(provider, request).await
         }
         ProviderKind::Mock => {
             let provider = MockProvider::new();
-            crate::run_agent_with_ui(
-                provider,
-                provider_kind,
-                &base_url,
-                model,
-                prompt,
-                &args,
-                &state.paths,
-                Some(ui_tx),
-                Some(operator_queue_rx),
-                Some(external_cancel_pair),
-                None,
-                None,
-                true,
-            )
-            .await
+            crate::run_agent_with_ui(provider, request).await
         }
     };
-    let _ = event_thread.join();
+    let _ = event_task.await;
     result
 }
 
+/// Projects a run's events into its record as they arrive. The task ends when
+/// the run drops its event sink.
+fn spawn_event_projection(
+    state: Arc<BackendState>,
+    run_id: String,
+) -> (
+    tokio::sync::mpsc::UnboundedSender<crate::events::Event>,
+    tokio::task::JoinHandle<()>,
+) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<crate::events::Event>();
+    let task = tokio::spawn(async move {
+        let mut sequence = 0u64;
+        while let Some(event) = rx.recv().await {
+            sequence = sequence.saturating_add(1);
+            if let Some(projected) = crate::events::project_event_v1(&event, sequence) {
+                update_run_record(&state, &run_id, |record| {
+                    record.projected_events.push(projected);
+                    record.event_notify.notify_waiters();
+                });
+            }
+        }
+    });
+    (tx, task)
+}
+
 fn parse_provider_kind(value: &str) -> anyhow::Result<ProviderKind> {
     match value {
         "lmstudio" => Ok(ProviderKind::Lmstudio),
         "llamacpp" => Ok(ProviderKind::Llamacpp),
         "ollama" => Ok(ProviderKind::Ollama),
         "mock" => Ok(ProviderKind::Mock),
+        "openai" => Ok(ProviderKind::OpenAi),
         _ => Err(anyhow!("unsupported provider '{value}'")),
     }
 }
@@ -1340,6 +1319,7 @@ mod tests {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             })
         }
 
@@ -1510,39 +1490,26 @@ mod tests {
             args.tui = false;
             args.disable_implementation_guard = true;
 
-            let (ui_tx, ui_rx) = std::sync::mpsc::channel::<crate::events::Event>();
-            let event_state = state_for_task.clone();
-            let event_run_id = run_id_for_task.clone();
-            let event_thread = std::thread::spawn(move || {
-                let mut sequence = 0u64;
-                while let Ok(event) = ui_rx.recv() {
-                    sequence = sequence.saturating_add(1);
-                    if let Some(projected) = crate::events::project_event_v1(&event, sequence) {
-                        update_run_record(&event_state, &event_run_id, |record| {
-                            record.projected_events.push(projected);
-                            record.event_notify.notify_waiters();
-                        });
-                    }
-                }
-            });
+            let (event_tx, event_task) =
+                super::spawn_event_projection(state_for_task.clone(), run_id_for_task.clone());
 
             let result = crate::run_agent_with_ui(
                 DelayedTestProvider { delay_ms },
-                ProviderKind::Mock,
-                &base_url,
-                &model,
-                &prompt,
-                &args,
-                &state_for_task.paths,
-                Some(ui_tx),
-                Some(input_rx),
-                Some((cancel_tx, cancel_rx)),
-                None,
-                None,
-                true,
+                crate::agent_runtime::AgentUiRunRequest::new(
+                    ProviderKind::Mock,
+                    &base_url,
+                    &model,
+                    &prompt,
+                    &args,
+                    &state_for_task.paths,
+                )
+                .with_event_sink(Box::new(crate::events::ChannelSink::new(event_tx)))
+                .with_operator_queue(input_rx)
+                .with_cancel_pair((cancel_tx, cancel_rx))
+                .suppress_stdout_stream(true),
             )
             .await;
-            let _ = event_thread.join();
+            let _ = event_task.await;
 
             match result {
                 Ok(exec) => {
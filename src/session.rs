@@ -1,8 +1,9 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::compaction::{CompactionMode, ToolResultPersist};
@@ -72,6 +73,12 @@ pub struct SessionFileV2 {
     pub settings: SessionSettings,
     #[serde(default)]
     pub task_memory: Vec<TaskMemoryBlock>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_facts: Option<crate::env_probe::EnvironmentFactsCache>,
+    /// sha256 of the compact JSON of every other field; absent in files
+    /// written before integrity checks existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +95,7 @@ pub struct SessionData {
     pub messages: Vec<Message>,
     pub settings: SessionSettings,
     pub task_memory: Vec<TaskMemoryBlock>,
+    pub environment_facts: Option<crate::env_probe::EnvironmentFactsCache>,
 }
 
 impl SessionData {
@@ -98,10 +106,33 @@ impl SessionData {
             messages: Vec::new(),
             settings: SessionSettings::default(),
             task_memory: Vec::new(),
+            environment_facts: None,
         }
     }
 }
 
+/// A load that fell back to `<session>.json.bak` because the primary file
+/// failed to parse or verify.
+#[derive(Debug, Clone)]
+pub struct SessionRecovery {
+    pub session_path: PathBuf,
+    pub backup_path: PathBuf,
+    pub reason: String,
+    pub recovered_messages: usize,
+}
+
+impl SessionRecovery {
+    pub fn warning(&self) -> String {
+        format!(
+            "WARN: session file {} is corrupt ({}); recovered {} message(s) from backup {}",
+            self.session_path.display(),
+            self.reason,
+            self.recovered_messages,
+            self.backup_path.display()
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionStore {
     path: PathBuf,
@@ -113,40 +144,117 @@ impl SessionStore {
         Self { path, name }
     }
 
+    /// Sessions normally live in `<state_dir>/sessions/`; a session file
+    /// elsewhere locks its own directory.
+    fn lock_state(&self) -> anyhow::Result<crate::store::StateLockGuard> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+        let state_dir = match dir.file_name() {
+            Some(name) if name == "sessions" => dir.parent().unwrap_or(dir),
+            _ => dir,
+        };
+        crate::store::acquire_state_lock(state_dir)
+    }
+
+    pub fn backup_path(&self) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(".bak");
+        PathBuf::from(name)
+    }
+
+    /// Reads never lock: every writer replaces the file atomically.
     pub fn load(&self) -> anyhow::Result<SessionData> {
+        let (data, recovery) = self.load_with_recovery()?;
+        if let Some(recovery) = recovery {
+            eprintln!("{}", recovery.warning());
+        }
+        Ok(data)
+    }
+
+    /// Like `load`, but falls back to the rolling backup when the primary
+    /// file is corrupt and reports that it did so.
+    pub fn load_with_recovery(&self) -> anyhow::Result<(SessionData, Option<SessionRecovery>)> {
         if !self.path.exists() {
-            return Ok(SessionData::empty(&self.name));
+            return Ok((SessionData::empty(&self.name), None));
         }
-        let raw = std::fs::read_to_string(&self.path)
-            .with_context(|| format!("failed reading session file {}", self.path.display()))?;
+        let primary_err = match self.read_session_file(&self.path) {
+            Ok((data, _)) => return Ok((data, None)),
+            Err(e) => e,
+        };
+        let backup_path = self.backup_path();
+        if !backup_path.exists() {
+            return Err(anyhow!(
+                "session file {} is corrupt: {primary_err:#}\nno backup found at {}; rerun with --reset-session to start a fresh session (this discards the corrupt file)",
+                self.path.display(),
+                backup_path.display()
+            ));
+        }
+        match self.read_session_file(&backup_path) {
+            Ok((data, _)) => {
+                let recovery = SessionRecovery {
+                    session_path: self.path.clone(),
+                    backup_path,
+                    reason: format!("{primary_err:#}"),
+                    recovered_messages: data.messages.len(),
+                };
+                Ok((data, Some(recovery)))
+            }
+            Err(backup_err) => Err(anyhow!(
+                "session file {} and its backup {} are both corrupt\n  session: {primary_err:#}\n  backup: {backup_err:#}\nrerun with --reset-session to start a fresh session (this discards both files)",
+                self.path.display(),
+                backup_path.display()
+            )),
+        }
+    }
+
+    /// Parses, verifies and decodes one session file, returning the verified
+    /// JSON alongside the data so `save` can rotate it into the backup.
+    fn read_session_file(&self, path: &Path) -> anyhow::Result<(SessionData, serde_json::Value)> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed reading session file {}", path.display()))?;
         let val: serde_json::Value =
             serde_json::from_str(&raw).context("failed parsing session JSON")?;
+        if let Some(expected) = val.get("checksum").and_then(|v| v.as_str()) {
+            let actual = payload_checksum(&val);
+            if actual != expected {
+                return Err(anyhow!(
+                    "checksum mismatch (expected {expected}, computed {actual}); the file was truncated or edited"
+                ));
+            }
+        }
         let schema = val
             .get("schema_version")
             .and_then(|v| v.as_str())
             .unwrap_or("openagent.session.v1");
         if schema == "openagent.session.v2" {
             let v2: SessionFileV2 =
-                serde_json::from_value(val).context("failed decoding session v2")?;
-            return Ok(SessionData {
+                serde_json::from_value(val.clone()).context("failed decoding session v2")?;
+            let data = SessionData {
                 name: v2.name,
                 updated_at: v2.updated_at,
                 messages: v2.messages,
                 settings: v2.settings,
                 task_memory: v2.task_memory,
-            });
+                environment_facts: v2.environment_facts,
+            };
+            return Ok((data, val));
         }
         let v1: SessionFileV1 = serde_json::from_str(&raw).context("failed decoding session v1")?;
-        Ok(SessionData {
+        let data = SessionData {
             name: self.name.clone(),
             updated_at: v1.updated_at,
             messages: v1.messages,
             settings: SessionSettings::default(),
             task_memory: Vec::new(),
-        })
+            environment_facts: None,
+        };
+        Ok((data, val))
     }
 
+    /// Writes atomically after rotating the current file into the backup
+    /// slot, but only when it still verifies so a corrupt primary never
+    /// replaces the last good backup.
     pub fn save(&self, data: &SessionData, max_messages: usize) -> anyhow::Result<()> {
+        let _lock = self.lock_state()?;
         let mut msgs = data.messages.clone();
         if msgs.len() > max_messages {
             let keep_from = msgs.len() - max_messages;
@@ -161,19 +269,31 @@ impl SessionStore {
             messages: msgs,
             settings: data.settings.clone(),
             task_memory: mem,
+            environment_facts: data.environment_facts.clone(),
+            checksum: None,
         };
-        crate::store::write_json_atomic(&self.path, &out)
+        let mut val = serde_json::to_value(&out)?;
+        let checksum = payload_checksum(&val);
+        val["checksum"] = serde_json::Value::String(checksum);
+        if let Ok((_, current)) = self.read_session_file(&self.path) {
+            crate::store::write_json_atomic(&self.backup_path(), &current)?;
+        }
+        crate::store::write_json_atomic(&self.path, &val)
     }
 
     pub fn reset(&self) -> anyhow::Result<()> {
-        if self.path.exists() {
-            std::fs::remove_file(&self.path)?;
+        let _lock = self.lock_state()?;
+        for path in [self.path.clone(), self.backup_path()] {
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
         }
         Ok(())
     }
 
     pub fn add_memory(&self, title: &str, content: &str) -> anyhow::Result<String> {
         enforce_memory_size(content)?;
+        let _lock = self.lock_state()?;
         let mut data = self.load()?;
         if data.task_memory.len() >= MAX_MEMORY_BLOCKS {
             return Err(anyhow!(
@@ -202,6 +322,7 @@ impl SessionStore {
         if let Some(c) = content {
             enforce_memory_size(c)?;
         }
+        let _lock = self.lock_state()?;
         let mut data = self.load()?;
         let Some(block) = data.task_memory.iter_mut().find(|b| b.id == id) else {
             return Err(anyhow!("memory id not found: {id}"));
@@ -217,6 +338,7 @@ impl SessionStore {
     }
 
     pub fn delete_memory(&self, id: &str) -> anyhow::Result<()> {
+        let _lock = self.lock_state()?;
         let mut data = self.load()?;
         let before = data.task_memory.len();
         data.task_memory.retain(|b| b.id != id);
@@ -227,6 +349,7 @@ impl SessionStore {
     }
 
     pub fn drop_from(&self, from_index: usize) -> anyhow::Result<()> {
+        let _lock = self.lock_state()?;
         let mut data = self.load()?;
         if from_index >= data.messages.len() {
             return Err(anyhow!(
@@ -240,6 +363,7 @@ impl SessionStore {
     }
 
     pub fn drop_last(&self, count: usize) -> anyhow::Result<()> {
+        let _lock = self.lock_state()?;
         let mut data = self.load()?;
         if count >= data.messages.len() {
             data.messages.clear();
@@ -251,6 +375,14 @@ impl SessionStore {
     }
 }
 
+fn payload_checksum(val: &serde_json::Value) -> String {
+    let mut val = val.clone();
+    if let Some(obj) = val.as_object_mut() {
+        obj.remove("checksum");
+    }
+    hex::encode(Sha256::digest(val.to_string().as_bytes()))
+}
+
 fn enforce_memory_size(content: &str) -> anyhow::Result<()> {
     if content.chars().count() > MAX_MEMORY_CONTENT_CHARS {
         return Err(anyhow!(
@@ -401,7 +533,7 @@ pub fn settings_from_run(resolved: &RunSettingResolution) -> SessionSettings {
             max_context_chars: resolved.max_context_chars,
             mode: format!("{:?}", resolved.compaction_mode).to_lowercase(),
             keep_last: resolved.compaction_keep_last,
-            tool_result_persist: format!("{:?}", resolved.tool_result_persist).to_lowercase(),
+            tool_result_persist: resolved.tool_result_persist.as_str().to_string(),
         },
         tool_args_strict: format!("{:?}", resolved.tool_args_strict).to_lowercase(),
         caps_mode: format!("{:?}", resolved.caps_mode).to_lowercase(),
@@ -434,6 +566,7 @@ fn parse_compaction_mode(s: &str) -> Option<CompactionMode> {
     match s {
         "off" => Some(CompactionMode::Off),
         "summary" => Some(CompactionMode::Summary),
+        "summarize" => Some(CompactionMode::Summarize),
         _ => None,
     }
 }
@@ -443,6 +576,7 @@ fn parse_tool_result_persist(s: &str) -> Option<ToolResultPersist> {
         "all" => Some(ToolResultPersist::All),
         "digest" => Some(ToolResultPersist::Digest),
         "none" => Some(ToolResultPersist::None),
+        "artifact_file" => Some(ToolResultPersist::ArtifactFile),
         _ => None,
     }
 }
@@ -479,7 +613,7 @@ mod tests {
 
     use super::{
         resolve_run_settings, settings_from_run, task_memory_message, CapsMode, ExplicitFlags,
-        RunSettingInputs, SessionStore, TASK_MEMORY_HEADER,
+        RunSettingInputs, SessionData, SessionStore, TASK_MEMORY_HEADER,
     };
     use crate::compaction::{CompactionMode, ToolResultPersist};
     use crate::hooks::config::HooksMode;
@@ -649,4 +783,106 @@ mod tests {
         store.drop_from(1).expect("drop2");
         assert_eq!(store.load().expect("load3").messages.len(), 1);
     }
+
+    fn user_message(content: &str) -> Message {
+        Message {
+            role: Role::User,
+            content: Some(content.to_string()),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        }
+    }
+
+    fn save_messages(store: &SessionStore, contents: &[&str]) {
+        let mut data = SessionData::empty("default");
+        data.messages = contents.iter().map(|c| user_message(c)).collect();
+        store.save(&data, 40).expect("save");
+    }
+
+    #[test]
+    fn torn_write_recovers_from_backup() {
+        let tmp = tempdir().expect("tmp");
+        let p = tmp.path().join("s.json");
+        let store = SessionStore::new(p.clone(), "default".to_string());
+        save_messages(&store, &["one"]);
+        assert!(!store.backup_path().exists());
+        save_messages(&store, &["one", "two"]);
+        assert!(store.backup_path().exists());
+        let raw = std::fs::read_to_string(&p).expect("read");
+        std::fs::write(&p, &raw[..raw.len() / 2]).expect("tear");
+
+        let (data, recovery) = store.load_with_recovery().expect("recover");
+        assert_eq!(data.messages.len(), 1);
+        let recovery = recovery.expect("recovery reported");
+        assert_eq!(recovery.recovered_messages, 1);
+        assert!(recovery.reason.contains("failed parsing session JSON"));
+        assert!(recovery
+            .warning()
+            .contains("recovered 1 message(s) from backup"));
+
+        // The corrupt primary must not be rotated over the good backup.
+        store.save(&data, 40).expect("save after recovery");
+        let (again, recovery) = store.load_with_recovery().expect("load");
+        assert!(recovery.is_none());
+        assert_eq!(again.messages.len(), 1);
+        let (_, backup) = store
+            .read_session_file(&store.backup_path())
+            .expect("backup still verifies");
+        assert_eq!(backup["messages"].as_array().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn double_corruption_points_at_reset_session() {
+        let tmp = tempdir().expect("tmp");
+        let p = tmp.path().join("s.json");
+        let store = SessionStore::new(p.clone(), "default".to_string());
+        save_messages(&store, &["one"]);
+        save_messages(&store, &["one", "two"]);
+        std::fs::write(&p, "{\"schema_version\":").expect("corrupt primary");
+        std::fs::write(store.backup_path(), "").expect("corrupt backup");
+
+        let err = format!("{:#}", store.load().expect_err("both corrupt"));
+        assert!(err.contains("are both corrupt"), "{err}");
+        assert!(err.contains("session:"), "{err}");
+        assert!(err.contains("backup:"), "{err}");
+        assert!(err.contains("--reset-session"), "{err}");
+
+        std::fs::remove_file(store.backup_path()).expect("rm backup");
+        let err = format!("{:#}", store.load().expect_err("no backup"));
+        assert!(err.contains("no backup found"), "{err}");
+        assert!(err.contains("--reset-session"), "{err}");
+
+        store.reset().expect("reset");
+        assert!(store.load().expect("fresh").messages.is_empty());
+    }
+
+    #[test]
+    fn checksum_detects_silent_truncation() {
+        let tmp = tempdir().expect("tmp");
+        let p = tmp.path().join("s.json");
+        let store = SessionStore::new(p.clone(), "default".to_string());
+        save_messages(&store, &["one", "two", "three"]);
+        let mut val: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&p).expect("read")).expect("json");
+        assert!(val["checksum"].as_str().is_some_and(|c| c.len() == 64));
+        val["messages"].as_array_mut().expect("messages").pop();
+        std::fs::write(&p, serde_json::to_string_pretty(&val).expect("ser")).expect("write");
+
+        let err = format!("{:#}", store.load().expect_err("mismatch"));
+        assert!(err.contains("checksum mismatch"), "{err}");
+    }
+
+    #[test]
+    fn legacy_v2_without_checksum_still_loads() {
+        let tmp = tempdir().expect("tmp");
+        let p = tmp.path().join("s.json");
+        let store = SessionStore::new(p.clone(), "default".to_string());
+        save_messages(&store, &["one"]);
+        let mut val: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&p).expect("read")).expect("json");
+        val.as_object_mut().expect("obj").remove("checksum");
+        std::fs::write(&p, val.to_string()).expect("write");
+        assert_eq!(store.load().expect("load").messages.len(), 1);
+    }
 }
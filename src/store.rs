@@ -2,19 +2,52 @@ use std::path::{Path, PathBuf};
 
 use crate::trust::policy::McpAllowSummary;
 
+mod annotations;
+mod artifacts;
+mod bundle;
 mod hash;
+mod interactions;
 mod io;
+mod lock;
+mod migrate;
 mod render;
 mod types;
+mod write_checkpoint;
 #[allow(unused_imports)]
 pub use crate::agent_runtime::state::{
     ApprovalState, CompletionDecisionRecordV1, ExecutionTier, InterruptHistoryEntryV1,
     InterruptKindV1, PhaseSummaryEntryV1, RetryState, RunCheckpointV1 as RuntimeStateCheckpointV1,
     RunPhase, ValidationState,
 };
+#[allow(unused_imports)]
+pub use annotations::{
+    list_runs, load_run_annotations, normalize_run_tag, run_annotations_path, sanitize_run_note,
+    update_run_annotations, RunAnnotationUpdate, RunAnnotationsV1, RunListQuery, RunSummary,
+    RUN_ANNOTATIONS_FILE_NAME, RUN_ANNOTATIONS_SCHEMA_V1, RUN_NOTE_MAX_CHARS, RUN_TAGS_MAX_PER_RUN,
+    RUN_TAG_MAX_CHARS,
+};
+#[allow(unused_imports)]
+pub use artifacts::{
+    load_run_artifact_manifest, parse_artifact_ref, run_artifacts_dir, RunArtifactEntryV1,
+    RunArtifactManifestV1, RunArtifactRef, RunArtifactStore, ToolResultArtifactV1,
+    ARTIFACT_REF_PREFIX, DEFAULT_MAX_RUN_ARTIFACT_BYTES, RUN_ARTIFACTS_DIR_NAME,
+    RUN_ARTIFACTS_SCHEMA_V1, RUN_ARTIFACT_MANIFEST_FILE_NAME,
+};
+#[allow(unused_imports)]
+pub use bundle::{
+    export_run_bundle, import_run_bundle, RunBundleExportOptions, RunBundleFileV1, RunBundleImport,
+    RunBundleManifestV1, RUN_BUNDLE_MANIFEST_FILE_NAME, RUN_BUNDLE_SCHEMA_V1,
+};
 pub use hash::{
     cli_trust_mode, config_hash_hex, hash_tool_schema, mcp_tool_snapshot_hash_hex,
     provider_to_string, sha256_hex, stable_path_string, tool_schema_hash_hex_map,
+    transcript_chain_hex,
+};
+#[allow(unused_imports)]
+pub use interactions::{
+    argv_hash, derive_external_interactions, mcp_server_launch_records, program_name,
+    ExternalEndpointV1, ExternalImageV1, ExternalInteractionsV1, ExternalProcessV1,
+    InteractionUseV1, McpServerLaunchRecord,
 };
 pub(crate) use io::write_json_atomic;
 pub use io::{
@@ -22,13 +55,31 @@ pub use io::{
     write_runtime_checkpoint_record,
 };
 pub use io::{ensure_dir, load_run_record, write_run_record};
+#[allow(unused_imports)]
+pub use lock::{
+    acquire_state_lock, acquire_state_lock_with_wait, configure_state_lock, state_lock_path,
+    take_state_lock_reclaims, StateLockGuard, StateLockHolder, StateLockReclaim,
+    DEFAULT_STATE_LOCK_WAIT_SECS, STATE_LOCK_FILE_NAME,
+};
+#[allow(unused_imports)]
+pub use migrate::{migrate_record, parse_run_record, run_record_schema_version};
 pub use render::{extract_session_messages, render_replay};
+#[allow(unused_imports)]
 pub use types::{
-    ActivatedPackRecord, ConfigFingerprintV1, McpPinSnapshotRecord, McpToolSnapshotEntry,
-    PendingApprovalToolCallV1, PlannerRunRecord, RunCheckpointInterruptKind,
+    ActivatedPackRecord, ConfigFingerprintV1, ContextPackFileRecord, ContextPackRecord,
+    ContextPackSkipRecord, LearnedGuidanceRecord, McpPinSnapshotRecord, McpRootRecord,
+    McpRootsRecord, McpToolSnapshotEntry, PendingApprovalToolCallV1, PlannerRunRecord,
+    PromptTemplateFileRecord, PromptTemplateRecord, RunCheckpointInterruptKind,
     RunCheckpointInterruptV1, RunCheckpointPhase, RunCheckpointV1, RunCliConfig,
     RunCompactionRecord, RunMetadata, RunRecord, RunResolvedPaths, RuntimeRunCheckpointRecordV1,
-    ToolCatalogEntry, ToolReliabilityRecord, WorkerRunRecord,
+    ToolCatalogEntry, ToolReliabilityRecord, WorkerRunRecord, RUN_RECORD_SCHEMA_LATEST,
+    RUN_RECORD_SCHEMA_V1, RUN_RECORD_SCHEMA_V2,
+};
+#[allow(unused_imports)]
+pub use write_checkpoint::{
+    load_write_checkpoint, rollback_write_checkpoint, write_checkpoint_dir, FileWrittenV1,
+    RollbackReport, WriteCheckpointManifestV1, WriteCheckpointStore, WRITE_CHECKPOINT_DIR_NAME,
+    WRITE_CHECKPOINT_MANIFEST_FILE_NAME, WRITE_CHECKPOINT_SCHEMA_V1,
 };
 
 #[derive(Debug, Clone)]
@@ -258,6 +309,7 @@ mod tests {
             tool_decisions: Vec::new(),
             compaction_settings: CompactionSettings {
                 max_context_chars: 0,
+                max_context_tokens: None,
                 mode: CompactionMode::Off,
                 keep_last: 20,
                 tool_result_persist: ToolResultPersist::Digest,
@@ -269,12 +321,18 @@ mod tests {
                 before_messages: 10,
                 after_messages: 4,
                 compacted_messages: 6,
+                before_tokens: 250,
+                after_tokens: 81,
                 summary_digest_sha256: "abc".to_string(),
                 summary_text: "COMPACTED SUMMARY (v1)".to_string(),
+                summary_generated: false,
+                artifact_files: Vec::new(),
+                digested_tool_results: Vec::new(),
             }),
             hook_invocations: Vec::new(),
             provider_retry_count: 0,
             provider_error_count: 0,
+            provider_retry_wait_ms: 0,
             token_usage: None,
             taint: Some(crate::agent::AgentTaintRecord {
                 enabled: true,
@@ -283,6 +341,33 @@ mod tests {
                 overall: "tainted".to_string(),
                 spans_by_tool_call_id: BTreeMap::new(),
             }),
+            digest_refetch: None,
+            tool_result_artifacts: Vec::new(),
+            model_served: None,
+            subtask_run_ids: Vec::new(),
+            post_run_hook_error: None,
+            timeline: vec![crate::agent::TimelineEntry {
+                seq: 0,
+                kind: crate::agent::TimelineEntryKind::ProviderCall,
+                step: 0,
+                start_ms: 2,
+                end_ms: 40,
+                duration_ms: 38,
+                exec_seq: None,
+                tool_call_id: None,
+                name: None,
+                status: "ok".to_string(),
+            }],
+            steps: vec![crate::agent::StepStats {
+                step: 0,
+                start_ms: 0,
+                end_ms: 45,
+                duration_ms: 45,
+                provider_calls: 1,
+                provider_latency_ms: 38,
+                tokens_in: Some(12),
+                ..Default::default()
+            }],
         };
         write_run_record(
             &paths,
@@ -309,6 +394,9 @@ mod tests {
                 allow_write: false,
                 enable_write_tools: false,
                 exec_target: "host".to_string(),
+                read_allowlist: Vec::new(),
+                shell_allowlist: Vec::new(),
+                max_tool_calls: Default::default(),
                 docker_image: None,
                 docker_workdir: None,
                 docker_network: None,
@@ -367,6 +455,7 @@ mod tests {
                 mcp_tool_catalog_hash_hex: None,
                 mcp_servers: Vec::new(),
                 mcp_config_path: None,
+                mcp_roots: Vec::new(),
                 policy_version: None,
                 includes_resolved: Vec::new(),
                 mcp_allowlist: None,
@@ -406,6 +495,10 @@ mod tests {
                 profile_source: None,
                 profile_hash_hex: None,
                 activated_packs: Vec::new(),
+                context_pack: None,
+                prompt_template: None,
+                learned_guidance: Vec::new(),
+                mcp_server_launches: Vec::new(),
             },
             PolicyRecordInfo {
                 source: "none".to_string(),
@@ -469,6 +562,10 @@ mod tests {
             None,
             Vec::new(),
             None,
+            None,
+            None,
+            None,
+            None,
         )
         .expect("write run");
         let loaded = load_run_record(&paths.state_dir, "run_1").expect("load run");
@@ -511,15 +608,22 @@ mod tests {
                 .summary_digest_sha256,
             "abc"
         );
+        assert_eq!(loaded.timeline, outcome.timeline);
+        let timeline_value = serde_json::to_value(&loaded).expect("serialize")["timeline"].clone();
+        assert_eq!(timeline_value[0]["kind"], "provider_call");
+        assert!(timeline_value[0].get("exec_seq").is_none());
+        assert_eq!(loaded.steps, outcome.steps);
 
         let mut legacy_value = serde_json::to_value(&loaded).expect("serialize");
-        legacy_value
-            .as_object_mut()
-            .expect("object")
-            .remove("tool_reliability");
+        let legacy_object = legacy_value.as_object_mut().expect("object");
+        legacy_object.remove("tool_reliability");
+        legacy_object.remove("timeline");
+        legacy_object.remove("steps");
         let legacy_loaded: RunRecord = serde_json::from_value(legacy_value).expect("deserialize");
         assert_eq!(legacy_loaded.tool_reliability.tool_calls_total, 0);
         assert!(legacy_loaded.tool_reliability.by_tool.is_empty());
+        assert!(legacy_loaded.timeline.is_empty());
+        assert!(legacy_loaded.steps.is_empty());
 
         let mut legacy_value_missing_agent_mode =
             serde_json::to_value(&loaded).expect("serialize legacy");
@@ -605,11 +709,18 @@ mod tests {
     #[test]
     fn replay_renders_planner_summary_when_present() {
         let record = RunRecord {
+            schema_version: crate::store::RUN_RECORD_SCHEMA_LATEST.to_string(),
+            migrated_from: None,
+            unknown_nested: None,
+            extra: Default::default(),
             metadata: RunMetadata {
                 run_id: "r".to_string(),
                 started_at: "2026-01-01T00:00:00Z".to_string(),
                 finished_at: "2026-01-01T00:00:01Z".to_string(),
                 exit_reason: "ok".to_string(),
+                model_served: None,
+                subtask_run_ids: Vec::new(),
+                post_run_hook_error: None,
             },
             mode: "planner_worker".to_string(),
             planner: Some(PlannerRunRecord {
@@ -660,6 +771,9 @@ mod tests {
                 allow_write: false,
                 enable_write_tools: false,
                 exec_target: "host".to_string(),
+                read_allowlist: Vec::new(),
+                shell_allowlist: Vec::new(),
+                max_tool_calls: Default::default(),
                 docker_image: None,
                 docker_workdir: None,
                 docker_network: None,
@@ -718,6 +832,7 @@ mod tests {
                 mcp_tool_catalog_hash_hex: None,
                 mcp_servers: Vec::new(),
                 mcp_config_path: None,
+                mcp_roots: Vec::new(),
                 policy_version: None,
                 includes_resolved: Vec::new(),
                 mcp_allowlist: None,
@@ -757,6 +872,10 @@ mod tests {
                 profile_source: None,
                 profile_hash_hex: None,
                 activated_packs: Vec::new(),
+                context_pack: None,
+                prompt_template: None,
+                learned_guidance: Vec::new(),
+                mcp_server_launches: Vec::new(),
             },
             resolved_paths: RunResolvedPaths {
                 state_dir: ".".to_string(),
@@ -788,6 +907,7 @@ mod tests {
                 tool_name: None,
                 tool_calls: None,
             }],
+            transcript_chain_hex: Vec::new(),
             tool_calls: Vec::new(),
             tool_decisions: Vec::new(),
             tool_facts: Vec::new(),
@@ -796,12 +916,20 @@ mod tests {
             hook_report: Vec::new(),
             tool_catalog: Vec::new(),
             mcp_runtime_trace: Vec::new(),
+            timeline: Vec::new(),
+            steps: Vec::new(),
             tool_reliability: ToolReliabilityRecord::default(),
             mcp_pin_snapshot: None,
+            environment_probe: None,
+            operator_queue: None,
+            provider_pacing: None,
+            otlp_export: None,
             taint: None,
             repro: None,
             final_output: String::new(),
             error: None,
+            external_interactions: None,
+            files_written: Vec::new(),
         };
         let rendered = render_replay(&record);
         assert!(rendered.contains("mode: planner_worker"));
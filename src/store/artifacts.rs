@@ -0,0 +1,373 @@
+//! Content-addressed run artifacts.
+//!
+//! Large or binary tool output that the transcript cannot hold is stored under
+//! `runs/<run_id>/artifacts/<sha256>` with a `manifest.json` listing every
+//! entry. Tools refer to a stored payload as `artifact:<sha256>`; references
+//! only resolve to artifacts of the current run and are read-only.
+//!
+//! Compaction in `artifact_file` mode also writes whole tool results to
+//! `runs/<run_id>/artifacts/<tool_call_id>.json`. Those files are named by
+//! call rather than by hash and are listed in the run record, not the
+//! manifest.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use super::hash::sha256_hex;
+use super::io::{ensure_dir, write_json_atomic};
+
+pub const RUN_ARTIFACTS_SCHEMA_V1: &str = "openagent.run_artifacts.v1";
+pub const RUN_ARTIFACTS_DIR_NAME: &str = "artifacts";
+pub const RUN_ARTIFACT_MANIFEST_FILE_NAME: &str = "manifest.json";
+pub const ARTIFACT_REF_PREFIX: &str = "artifact:";
+pub const DEFAULT_MAX_RUN_ARTIFACT_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunArtifactManifestV1 {
+    pub schema_version: String,
+    pub run_id: String,
+    #[serde(default)]
+    pub total_bytes: u64,
+    /// In store order; a payload stored twice keeps its first entry.
+    #[serde(default)]
+    pub entries: Vec<RunArtifactEntryV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunArtifactEntryV1 {
+    pub hash: String,
+    pub bytes: u64,
+    pub content_type: String,
+    pub source_tool: String,
+    pub tool_call_id: String,
+    pub created_at: String,
+}
+
+/// A tool result that compaction moved out of the transcript into its own
+/// file. `path` is relative to the state dir.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolResultArtifactV1 {
+    pub tool_call_id: String,
+    pub tool: String,
+    pub ok: bool,
+    pub path: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Model-visible reference to a stored artifact. `path_hint` is the value to
+/// pass as a path argument to tools that accept artifact references.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunArtifactRef {
+    pub hash: String,
+    pub bytes: u64,
+    pub content_type: String,
+    pub path_hint: String,
+}
+
+impl RunArtifactRef {
+    fn from_entry(entry: &RunArtifactEntryV1) -> Self {
+        Self {
+            hash: entry.hash.clone(),
+            bytes: entry.bytes,
+            content_type: entry.content_type.clone(),
+            path_hint: format!("{ARTIFACT_REF_PREFIX}{}", entry.hash),
+        }
+    }
+}
+
+/// Per-run artifact store shared by the tool runtime. The manifest is loaded
+/// lazily and every write goes through the mutex, so the spill cap holds
+/// across concurrent tool calls of one run.
+#[derive(Debug)]
+pub struct RunArtifactStore {
+    run_id: String,
+    dir: PathBuf,
+    max_total_bytes: u64,
+    manifest: Mutex<Option<RunArtifactManifestV1>>,
+    tool_result_files: Mutex<Vec<ToolResultArtifactV1>>,
+}
+
+impl RunArtifactStore {
+    /// `max_total_bytes` of 0 disables the cap.
+    pub fn new(state_dir: &Path, run_id: &str, max_total_bytes: u64) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            dir: run_artifacts_dir(state_dir, run_id),
+            max_total_bytes,
+            manifest: Mutex::new(None),
+            tool_result_files: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn store(
+        &self,
+        bytes: &[u8],
+        content_type: &str,
+        source_tool: &str,
+        tool_call_id: &str,
+    ) -> anyhow::Result<RunArtifactRef> {
+        let mut guard = self
+            .manifest
+            .lock()
+            .map_err(|_| anyhow!("run artifact manifest lock poisoned"))?;
+        let manifest = self.loaded_manifest(&mut guard)?;
+        let hash = sha256_hex(bytes);
+        if let Some(existing) = manifest.entries.iter().find(|e| e.hash == hash) {
+            return Ok(RunArtifactRef::from_entry(existing));
+        }
+        let len = bytes.len() as u64;
+        let next_total = manifest.total_bytes.saturating_add(len);
+        if self.max_total_bytes > 0 && next_total > self.max_total_bytes {
+            return Err(anyhow!(
+                "run artifact cap exceeded: storing {len} bytes would bring run {} to {next_total} bytes, over the {} byte limit (--max-run-artifact-bytes)",
+                self.run_id,
+                self.max_total_bytes
+            ));
+        }
+        ensure_dir(&self.dir)?;
+        let path = self.dir.join(&hash);
+        std::fs::write(&path, bytes)
+            .with_context(|| format!("failed to write run artifact {}", path.display()))?;
+        let entry = RunArtifactEntryV1 {
+            hash,
+            bytes: len,
+            content_type: content_type.to_string(),
+            source_tool: source_tool.to_string(),
+            tool_call_id: tool_call_id.to_string(),
+            created_at: crate::trust::now_rfc3339(),
+        };
+        let mut updated = manifest.clone();
+        updated.total_bytes = next_total;
+        updated.entries.push(entry.clone());
+        write_json_atomic(&self.dir.join(RUN_ARTIFACT_MANIFEST_FILE_NAME), &updated)?;
+        *manifest = updated;
+        Ok(RunArtifactRef::from_entry(&entry))
+    }
+
+    /// Writes `content`, a whole tool result, to `<tool_call_id>.json`.
+    /// The bytes count against the same cap as stored artifacts.
+    pub fn store_tool_result_file(
+        &self,
+        tool_call_id: &str,
+        tool: &str,
+        ok: bool,
+        content: &str,
+    ) -> anyhow::Result<ToolResultArtifactV1> {
+        let mut files = self
+            .tool_result_files
+            .lock()
+            .map_err(|_| anyhow!("tool result artifact list poisoned"))?;
+        if let Some(existing) = files.iter().find(|f| f.tool_call_id == tool_call_id) {
+            return Ok(existing.clone());
+        }
+        let stored_bytes = {
+            let mut guard = self
+                .manifest
+                .lock()
+                .map_err(|_| anyhow!("run artifact manifest lock poisoned"))?;
+            self.loaded_manifest(&mut guard)?.total_bytes
+        };
+        let len = content.len() as u64;
+        let next_total = files
+            .iter()
+            .fold(stored_bytes, |total, f| total.saturating_add(f.bytes))
+            .saturating_add(len);
+        if self.max_total_bytes > 0 && next_total > self.max_total_bytes {
+            return Err(anyhow!(
+                "run artifact cap exceeded: storing {len} bytes would bring run {} to {next_total} bytes, over the {} byte limit (--max-run-artifact-bytes)",
+                self.run_id,
+                self.max_total_bytes
+            ));
+        }
+        let file_name = tool_result_file_name(tool_call_id);
+        ensure_dir(&self.dir)?;
+        let path = self.dir.join(&file_name);
+        std::fs::write(&path, content.as_bytes())
+            .with_context(|| format!("failed to write tool result artifact {}", path.display()))?;
+        let entry = ToolResultArtifactV1 {
+            tool_call_id: tool_call_id.to_string(),
+            tool: tool.to_string(),
+            ok,
+            path: format!("runs/{}/{RUN_ARTIFACTS_DIR_NAME}/{file_name}", self.run_id),
+            bytes: len,
+            sha256: sha256_hex(content.as_bytes()),
+        };
+        files.push(entry.clone());
+        Ok(entry)
+    }
+
+    /// Tool results written by [`Self::store_tool_result_file`], in write
+    /// order.
+    pub fn tool_result_files(&self) -> Vec<ToolResultArtifactV1> {
+        self.tool_result_files
+            .lock()
+            .map(|files| files.clone())
+            .unwrap_or_default()
+    }
+
+    /// Resolves an artifact hash of this run to its stored file.
+    pub fn resolve(&self, hash: &str) -> anyhow::Result<(PathBuf, RunArtifactEntryV1)> {
+        let mut guard = self
+            .manifest
+            .lock()
+            .map_err(|_| anyhow!("run artifact manifest lock poisoned"))?;
+        let manifest = self.loaded_manifest(&mut guard)?;
+        let entry = manifest
+            .entries
+            .iter()
+            .find(|e| e.hash == hash)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!("unknown artifact '{ARTIFACT_REF_PREFIX}{hash}' for this run")
+            })?;
+        Ok((self.dir.join(hash), entry))
+    }
+
+    fn loaded_manifest<'a>(
+        &self,
+        slot: &'a mut Option<RunArtifactManifestV1>,
+    ) -> anyhow::Result<&'a mut RunArtifactManifestV1> {
+        if slot.is_none() {
+            let manifest = read_manifest(&self.dir)?.unwrap_or_else(|| RunArtifactManifestV1 {
+                schema_version: RUN_ARTIFACTS_SCHEMA_V1.to_string(),
+                run_id: self.run_id.clone(),
+                total_bytes: 0,
+                entries: Vec::new(),
+            });
+            *slot = Some(manifest);
+        }
+        Ok(slot.as_mut().expect("manifest loaded"))
+    }
+}
+
+pub fn run_artifacts_dir(state_dir: &Path, run_id: &str) -> PathBuf {
+    state_dir
+        .join("runs")
+        .join(run_id)
+        .join(RUN_ARTIFACTS_DIR_NAME)
+}
+
+#[allow(dead_code)]
+pub fn load_run_artifact_manifest(
+    state_dir: &Path,
+    run_id: &str,
+) -> anyhow::Result<Option<RunArtifactManifestV1>> {
+    read_manifest(&run_artifacts_dir(state_dir, run_id))
+}
+
+/// Tool call ids come from the model, so anything outside `[A-Za-z0-9_-]`
+/// is replaced to keep the file inside the artifacts dir.
+fn tool_result_file_name(tool_call_id: &str) -> String {
+    let stem = tool_call_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!("{stem}.json")
+}
+
+/// Returns the hash of an `artifact:<sha256>` reference.
+pub fn parse_artifact_ref(value: &str) -> Option<&str> {
+    let hash = value.strip_prefix(ARTIFACT_REF_PREFIX)?;
+    let valid = hash.len() == 64
+        && hash
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    valid.then_some(hash)
+}
+
+fn read_manifest(dir: &Path) -> anyhow::Result<Option<RunArtifactManifestV1>> {
+    let path = dir.join(RUN_ARTIFACT_MANIFEST_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read run artifact manifest {}", path.display()))?;
+    let manifest = serde_json::from_str(&raw)
+        .with_context(|| format!("failed to parse run artifact manifest {}", path.display()))?;
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn store_dedups_by_hash_and_records_manifest_entries() {
+        let tmp = tempdir().expect("tempdir");
+        let store = RunArtifactStore::new(tmp.path(), "run_a", 0);
+        let first = store
+            .store(b"png-bytes", "image/png", "mcp.stub.shot", "tc1")
+            .expect("store");
+        let again = store
+            .store(b"png-bytes", "image/png", "mcp.stub.shot", "tc2")
+            .expect("store again");
+        assert_eq!(first, again);
+        assert_eq!(first.path_hint, format!("artifact:{}", first.hash));
+        assert_eq!(
+            std::fs::read(run_artifacts_dir(tmp.path(), "run_a").join(&first.hash)).expect("read"),
+            b"png-bytes"
+        );
+
+        let manifest = load_run_artifact_manifest(tmp.path(), "run_a")
+            .expect("load")
+            .expect("manifest");
+        assert_eq!(manifest.schema_version, RUN_ARTIFACTS_SCHEMA_V1);
+        assert_eq!(manifest.total_bytes, 9);
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(manifest.entries[0].tool_call_id, "tc1");
+
+        let reopened = RunArtifactStore::new(tmp.path(), "run_a", 0);
+        let (path, entry) = reopened.resolve(&first.hash).expect("resolve");
+        assert_eq!(
+            path,
+            run_artifacts_dir(tmp.path(), "run_a").join(&first.hash)
+        );
+        assert_eq!(entry.content_type, "image/png");
+        assert!(reopened.resolve(&"0".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn store_enforces_total_byte_cap() {
+        let tmp = tempdir().expect("tempdir");
+        let store = RunArtifactStore::new(tmp.path(), "run_b", 10);
+        store
+            .store(b"123456", "text/plain", "mcp.stub.a", "tc1")
+            .expect("first fits");
+        let err = store
+            .store(b"abcdef", "text/plain", "mcp.stub.a", "tc2")
+            .expect_err("cap exceeded");
+        assert!(err.to_string().contains("run artifact cap exceeded"));
+        assert!(err.to_string().contains("10 byte limit"));
+        let manifest = load_run_artifact_manifest(tmp.path(), "run_b")
+            .expect("load")
+            .expect("manifest");
+        assert_eq!(manifest.entries.len(), 1);
+    }
+
+    #[test]
+    fn parse_artifact_ref_requires_full_lowercase_sha256() {
+        let hash = "a".repeat(64);
+        assert_eq!(
+            parse_artifact_ref(&format!("artifact:{hash}")),
+            Some(hash.as_str())
+        );
+        assert_eq!(parse_artifact_ref(&hash), None);
+        assert_eq!(parse_artifact_ref("artifact:abc"), None);
+        assert_eq!(
+            parse_artifact_ref(&format!("artifact:{}", "A".repeat(64))),
+            None
+        );
+    }
+}
@@ -8,6 +8,7 @@ use sha2::{Digest, Sha256};
 use crate::gate::TrustMode;
 
 use super::{ConfigFingerprintV1, McpToolSnapshotEntry};
+use crate::types::Message;
 
 pub fn cli_trust_mode(mode: TrustMode) -> String {
     match mode {
@@ -23,6 +24,7 @@ pub fn provider_to_string(provider: crate::gate::ProviderKind) -> String {
         crate::gate::ProviderKind::Llamacpp => "llamacpp".to_string(),
         crate::gate::ProviderKind::Ollama => "ollama".to_string(),
         crate::gate::ProviderKind::Mock => "mock".to_string(),
+        crate::gate::ProviderKind::OpenAi => "openai".to_string(),
     }
 }
 
@@ -60,6 +62,23 @@ pub fn mcp_tool_snapshot_hash_hex(snapshot: &[McpToolSnapshotEntry]) -> anyhow::
     Ok(sha256_hex(canonical.as_bytes()))
 }
 
+/// One hash per transcript message: sha256 of the previous hash (empty for
+/// the first message) followed by the message's canonical JSON. Editing,
+/// dropping or reordering a message changes every hash from it onward.
+pub fn transcript_chain_hex(messages: &[Message]) -> anyhow::Result<Vec<String>> {
+    let mut chain = Vec::with_capacity(messages.len());
+    let mut prev = String::new();
+    for message in messages {
+        let canonical = crate::trust::approvals::canonical_json(&serde_json::to_value(message)?)?;
+        let mut hasher = Sha256::new();
+        hasher.update(prev.as_bytes());
+        hasher.update(canonical.as_bytes());
+        prev = hex_encode(hasher.finalize());
+        chain.push(prev.clone());
+    }
+    Ok(chain)
+}
+
 pub fn hash_tool_schema(schema: &Value) -> String {
     let canonical = crate::trust::approvals::canonical_json(schema)
         .unwrap_or_else(|_| serde_json::to_string(schema).unwrap_or_else(|_| "null".to_string()));
@@ -8,10 +8,11 @@ use uuid::Uuid;
 use crate::agent::AgentOutcome;
 use crate::planner::RunMode;
 
+use super::migrate::parse_run_record;
 use super::{
     ConfigFingerprintV1, McpPinSnapshotRecord, PlannerRunRecord, PolicyRecordInfo, RunCliConfig,
     RunCompactionRecord, RunMetadata, RunRecord, RunResolvedPaths, RuntimeRunCheckpointRecordV1,
-    StatePaths, ToolReliabilityRecord, WorkerRunRecord,
+    StatePaths, ToolReliabilityRecord, WorkerRunRecord, RUN_RECORD_SCHEMA_LATEST,
 };
 
 fn summarize_tool_reliability(outcome: &AgentOutcome) -> ToolReliabilityRecord {
@@ -109,16 +110,26 @@ pub fn write_run_record(
     repro: Option<crate::repro::RunReproRecord>,
     mcp_runtime_trace: Vec<crate::agent::McpRuntimeTraceEntry>,
     mcp_pin_snapshot: Option<McpPinSnapshotRecord>,
+    environment_probe: Option<crate::env_probe::EnvironmentProbeRecord>,
+    operator_queue: Option<crate::operator_queue::OperatorQueueRunRecordV1>,
+    provider_pacing: Option<crate::providers::pacing::ProviderPacingRecordV1>,
+    otlp_export: Option<crate::otlp::OtlpExportRecordV1>,
 ) -> anyhow::Result<PathBuf> {
     ensure_dir(&paths.runs_dir)?;
     let run_path = paths.runs_dir.join(format!("{}.json", outcome.run_id));
     let tool_catalog = cli.tool_catalog.clone();
-    let record = RunRecord {
+    let mut record = RunRecord {
+        schema_version: RUN_RECORD_SCHEMA_LATEST.to_string(),
+        migrated_from: None,
+        unknown_nested: None,
         metadata: RunMetadata {
             run_id: outcome.run_id.clone(),
             started_at: outcome.started_at.clone(),
             finished_at: outcome.finished_at.clone(),
             exit_reason: outcome.exit_reason.as_str().to_string(),
+            model_served: outcome.model_served.clone(),
+            subtask_run_ids: outcome.subtask_run_ids.clone(),
+            post_run_hook_error: outcome.post_run_hook_error.clone(),
         },
         mode: format!("{:?}", mode).to_lowercase(),
         planner,
@@ -148,6 +159,7 @@ pub fn write_run_record(
         tool_schema_hash_hex_map,
         hooks_config_hash_hex,
         transcript: outcome.messages.clone(),
+        transcript_chain_hex: super::transcript_chain_hex(&outcome.messages)?,
         tool_calls: outcome.tool_calls.clone(),
         tool_decisions: outcome.tool_decisions.clone(),
         tool_facts,
@@ -156,17 +168,33 @@ pub fn write_run_record(
             settings: outcome.compaction_settings.clone(),
             final_prompt_size_chars: outcome.final_prompt_size_chars,
             report: outcome.compaction_report.clone(),
+            digest_refetch: outcome.digest_refetch.clone(),
+            artifact_files: outcome.tool_result_artifacts.clone(),
         }),
         hook_report: outcome.hook_invocations.clone(),
         tool_catalog,
         mcp_runtime_trace,
+        timeline: outcome.timeline.clone(),
+        steps: outcome.steps.clone(),
         tool_reliability: summarize_tool_reliability(outcome),
         mcp_pin_snapshot,
+        environment_probe,
+        operator_queue,
+        provider_pacing,
+        otlp_export,
+        external_interactions: None,
+        files_written: super::load_write_checkpoint(&paths.state_dir, &outcome.run_id)
+            .ok()
+            .flatten()
+            .map(|checkpoint| checkpoint.files)
+            .unwrap_or_default(),
         taint: outcome.taint.clone(),
         repro,
         final_output: outcome.final_output.clone(),
         error: outcome.error.clone(),
+        extra: BTreeMap::new(),
     };
+    record.external_interactions = Some(super::derive_external_interactions(&record));
     write_json_atomic(&run_path, &record)?;
     Ok(run_path)
 }
@@ -174,8 +202,7 @@ pub fn write_run_record(
 pub fn load_run_record(state_dir: &Path, run_id: &str) -> anyhow::Result<RunRecord> {
     let path = state_dir.join("runs").join(format!("{}.json", run_id));
     let content = std::fs::read_to_string(path)?;
-    let record: RunRecord = serde_json::from_str(&content)?;
-    Ok(record)
+    parse_run_record(&content)
 }
 
 pub(crate) fn runtime_checkpoint_path(paths: &StatePaths, runtime_run_id: &str) -> PathBuf {
@@ -0,0 +1,460 @@
+//! Advisory lock for state shared between LocalAgent processes (sessions,
+//! approvals, learning entries, check history).
+//!
+//! The lock is a `.lock` file in the state dir holding the owner's PID, start
+//! time, and command. It is taken only around mutations; reads rely on the
+//! atomic rename every writer already uses and never wait. Per-run files
+//! (records, checkpoints) are keyed by unique run ids and stay lock-free.
+//!
+//! Acquisition is re-entrant per thread, so a locked operation may call other
+//! locked operations on the same state dir. Other threads and processes wait.
+//! Reclaimed stale locks are queued for the run's `state_lock_reclaimed`
+//! event; see [`take_state_lock_reclaims`].
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+pub const STATE_LOCK_FILE_NAME: &str = ".lock";
+pub const DEFAULT_STATE_LOCK_WAIT_SECS: u64 = 5;
+
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(25);
+/// A lock file that never got its holder written (the owner died between
+/// create and write) is treated as stale after this long.
+const UNREADABLE_LOCK_STALE_AFTER: Duration = Duration::from_secs(10);
+
+static DEFAULT_WAIT_MS: AtomicU64 = AtomicU64::new(DEFAULT_STATE_LOCK_WAIT_SECS * 1000);
+static PROCESS_COMMAND: Mutex<Option<String>> = Mutex::new(None);
+static RECLAIMS: Mutex<Vec<StateLockReclaim>> = Mutex::new(Vec::new());
+/// Lets the per-event drain skip the mutex when nothing was reclaimed.
+static RECLAIMS_PENDING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static HELD: RefCell<BTreeMap<PathBuf, usize>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateLockHolder {
+    pub pid: u32,
+    pub started_at: String,
+    pub command: String,
+    pub token: String,
+}
+
+/// A stale lock this process removed. `stale_holder` is `None` when the lock
+/// file was unreadable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateLockReclaim {
+    pub lock_path: PathBuf,
+    pub stale_holder: Option<StateLockHolder>,
+}
+
+/// Drains the reclaims recorded since the last call, oldest first.
+pub fn take_state_lock_reclaims() -> Vec<StateLockReclaim> {
+    if !RECLAIMS_PENDING.swap(false, Ordering::AcqRel) {
+        return Vec::new();
+    }
+    RECLAIMS
+        .lock()
+        .map(|mut reclaims| std::mem::take(&mut *reclaims))
+        .unwrap_or_default()
+}
+
+fn record_reclaim(reclaim: StateLockReclaim) {
+    if let Ok(mut reclaims) = RECLAIMS.lock() {
+        reclaims.push(reclaim);
+        RECLAIMS_PENDING.store(true, Ordering::Release);
+    }
+}
+
+/// Sets how long lock acquisition waits for another holder (`--wait-lock`) and
+/// the command recorded in lock files this process writes.
+pub fn configure_state_lock(wait: Duration, command: &str) {
+    DEFAULT_WAIT_MS.store(wait.as_millis() as u64, Ordering::Relaxed);
+    if let Ok(mut slot) = PROCESS_COMMAND.lock() {
+        *slot = Some(command.to_string());
+    }
+}
+
+pub fn state_lock_path(state_dir: &Path) -> PathBuf {
+    state_dir.join(STATE_LOCK_FILE_NAME)
+}
+
+fn default_wait() -> Duration {
+    Duration::from_millis(DEFAULT_WAIT_MS.load(Ordering::Relaxed))
+}
+
+fn process_command() -> String {
+    PROCESS_COMMAND
+        .lock()
+        .ok()
+        .and_then(|slot| slot.clone())
+        .unwrap_or_else(|| "localagent".to_string())
+}
+
+/// Held lock; releasing the outermost guard on a thread removes the lock file.
+#[derive(Debug)]
+pub struct StateLockGuard {
+    path: PathBuf,
+    token: Option<String>,
+}
+
+impl Drop for StateLockGuard {
+    fn drop(&mut self) {
+        let outermost = HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            match held.get_mut(&self.path) {
+                Some(depth) if *depth > 1 => {
+                    *depth -= 1;
+                    false
+                }
+                _ => {
+                    held.remove(&self.path);
+                    true
+                }
+            }
+        });
+        if !outermost {
+            return;
+        }
+        let still_ours = matches!(
+            (read_holder(&self.path), &self.token),
+            (Some(holder), Some(token)) if &holder.token == token
+        );
+        if still_ours {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Acquires the state-dir lock, waiting up to the configured `--wait-lock`.
+pub fn acquire_state_lock(state_dir: &Path) -> anyhow::Result<StateLockGuard> {
+    acquire_state_lock_with_wait(state_dir, default_wait())
+}
+
+pub fn acquire_state_lock_with_wait(
+    state_dir: &Path,
+    wait: Duration,
+) -> anyhow::Result<StateLockGuard> {
+    let path = state_lock_path(state_dir);
+    let reentered = HELD.with(|held| match held.borrow_mut().get_mut(&path) {
+        Some(depth) => {
+            *depth += 1;
+            true
+        }
+        None => false,
+    });
+    if reentered {
+        return Ok(StateLockGuard { path, token: None });
+    }
+
+    std::fs::create_dir_all(state_dir)
+        .with_context(|| format!("failed to create state dir {}", state_dir.display()))?;
+    let deadline = Instant::now() + wait;
+    loop {
+        let holder = StateLockHolder {
+            pid: std::process::id(),
+            started_at: crate::trust::now_rfc3339(),
+            command: process_command(),
+            token: uuid::Uuid::new_v4().to_string(),
+        };
+        match try_create(&path, &holder) {
+            Ok(()) => {
+                HELD.with(|held| held.borrow_mut().insert(path.clone(), 1));
+                return Ok(StateLockGuard {
+                    path,
+                    token: Some(holder.token),
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to create {}", path.display()))
+            }
+        }
+
+        let current = read_holder(&path);
+        if is_stale(&path, current.as_ref()) {
+            if reclaim(&path, current.as_ref())? {
+                if let Some(stale) = &current {
+                    eprintln!(
+                        "WARN: state_lock_reclaimed: removed stale lock {} held by dead pid {} ({})",
+                        path.display(),
+                        stale.pid,
+                        stale.command
+                    );
+                } else {
+                    eprintln!(
+                        "WARN: state_lock_reclaimed: removed unreadable lock {}",
+                        path.display()
+                    );
+                }
+                record_reclaim(StateLockReclaim {
+                    lock_path: path.clone(),
+                    stale_holder: current,
+                });
+            }
+            continue;
+        }
+        if Instant::now() >= deadline {
+            return Err(lock_busy_error(&path, current.as_ref(), wait));
+        }
+        poll_sleep();
+    }
+}
+
+/// Waits one poll interval. Locked operations are synchronous but often run
+/// on a tokio worker (session saves, approvals); there the wait goes through
+/// `block_in_place` so the worker's other tasks move to another thread.
+fn poll_sleep() {
+    let on_multi_thread_runtime = tokio::runtime::Handle::try_current()
+        .is_ok_and(|h| h.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread);
+    if on_multi_thread_runtime {
+        tokio::task::block_in_place(|| std::thread::sleep(LOCK_POLL_INTERVAL));
+    } else {
+        std::thread::sleep(LOCK_POLL_INTERVAL);
+    }
+}
+
+fn try_create(path: &Path, holder: &StateLockHolder) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    let body = serde_json::to_vec(holder).map_err(std::io::Error::other)?;
+    file.write_all(&body)?;
+    file.sync_all()
+}
+
+fn read_holder(path: &Path) -> Option<StateLockHolder> {
+    let raw = std::fs::read(path).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
+
+fn is_stale(path: &Path, holder: Option<&StateLockHolder>) -> bool {
+    match holder {
+        Some(holder) => !process_alive(holder.pid),
+        None => std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age >= UNREADABLE_LOCK_STALE_AFTER),
+    }
+}
+
+/// Moves the stale lock aside and checks it is the one that was judged stale;
+/// if another process reclaimed and re-locked in between, its lock is put back.
+fn reclaim(path: &Path, stale: Option<&StateLockHolder>) -> anyhow::Result<bool> {
+    let aside = path.with_extension(format!("stale.{}", uuid::Uuid::new_v4()));
+    match std::fs::rename(path, &aside) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to reclaim stale {}", path.display()))
+        }
+    }
+    let moved = read_holder(&aside);
+    let same = moved.as_ref().map(|h| &h.token) == stale.map(|h| &h.token);
+    if !same {
+        let _ = std::fs::hard_link(&aside, path);
+    }
+    let _ = std::fs::remove_file(&aside);
+    Ok(same)
+}
+
+fn lock_busy_error(path: &Path, holder: Option<&StateLockHolder>, wait: Duration) -> anyhow::Error {
+    let waited = format!("{:.1}s", wait.as_secs_f64());
+    match holder {
+        Some(holder) => anyhow!(
+            "state dir is locked by pid {} ({}) since {}; waited {waited}. Another LocalAgent process is writing shared state in {}. Wait for it to finish or retry with --wait-lock <secs>.",
+            holder.pid,
+            holder.command,
+            holder.started_at,
+            path.parent().unwrap_or(path).display()
+        ),
+        None => anyhow!(
+            "state dir lock {} is held by an unknown process; waited {waited}. Retry with --wait-lock <secs>, or remove the file if no LocalAgent process is running.",
+            path.display()
+        ),
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 performs only the existence and permission checks.
+    let rc = unsafe { libc::kill(pid, 0) };
+    rc == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a portable liveness probe, locks are never reclaimed automatically.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+
+    fn write_foreign_lock(state_dir: &Path, pid: u32) -> StateLockHolder {
+        let holder = StateLockHolder {
+            pid,
+            started_at: "2026-01-01T00:00:00Z".to_string(),
+            command: "localagent run".to_string(),
+            token: "foreign".to_string(),
+        };
+        std::fs::create_dir_all(state_dir).expect("state dir");
+        std::fs::write(
+            state_lock_path(state_dir),
+            serde_json::to_vec(&holder).expect("json"),
+        )
+        .expect("write lock");
+        holder
+    }
+
+    #[test]
+    fn concurrent_writers_serialize_through_the_lock() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let state_dir = Arc::new(tmp.path().to_path_buf());
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let start = Arc::new(Barrier::new(2));
+        let handles = (0..2)
+            .map(|i| {
+                let (state_dir, log, start) = (state_dir.clone(), log.clone(), start.clone());
+                std::thread::spawn(move || {
+                    start.wait();
+                    let _lock = acquire_state_lock_with_wait(&state_dir, Duration::from_secs(10))
+                        .expect("lock");
+                    log.lock().expect("log").push(format!("enter{i}"));
+                    std::thread::sleep(Duration::from_millis(50));
+                    log.lock().expect("log").push(format!("exit{i}"));
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+        let log = log.lock().expect("log").clone();
+        assert_eq!(log.len(), 4);
+        for pair in log.chunks(2) {
+            assert_eq!(pair[0].replace("enter", ""), pair[1].replace("exit", ""));
+        }
+        assert!(!state_lock_path(&state_dir).exists());
+    }
+
+    #[test]
+    fn lock_is_reentrant_on_the_same_thread() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let outer = acquire_state_lock_with_wait(tmp.path(), Duration::ZERO).expect("outer");
+        {
+            let _inner = acquire_state_lock_with_wait(tmp.path(), Duration::ZERO).expect("inner");
+        }
+        assert!(state_lock_path(tmp.path()).exists());
+        drop(outer);
+        assert!(!state_lock_path(tmp.path()).exists());
+    }
+
+    #[test]
+    fn busy_lock_error_names_holder_and_wait_lock() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        write_foreign_lock(tmp.path(), std::process::id());
+        let started = Instant::now();
+        let err =
+            acquire_state_lock_with_wait(tmp.path(), Duration::from_millis(200)).expect_err("busy");
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        let msg = err.to_string();
+        assert!(
+            msg.contains(&format!("pid {}", std::process::id())),
+            "{msg}"
+        );
+        assert!(msg.contains("(localagent run)"), "{msg}");
+        assert!(msg.contains("since 2026-01-01T00:00:00Z"), "{msg}");
+        assert!(msg.contains("--wait-lock"), "{msg}");
+        assert!(state_lock_path(tmp.path()).exists());
+    }
+
+    #[test]
+    fn wait_lock_acquires_once_holder_releases() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        write_foreign_lock(tmp.path(), std::process::id());
+        let lock_path = state_lock_path(tmp.path());
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            std::fs::remove_file(lock_path).expect("release");
+        });
+        let _guard =
+            acquire_state_lock_with_wait(tmp.path(), Duration::from_secs(5)).expect("acquired");
+        releaser.join().expect("join");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stale_lock_from_dead_pid_is_reclaimed() {
+        let mut child = std::process::Command::new("true").spawn().expect("spawn");
+        let dead_pid = child.id();
+        child.wait().expect("wait");
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let stale = write_foreign_lock(tmp.path(), dead_pid);
+        let guard = acquire_state_lock_with_wait(tmp.path(), Duration::ZERO).expect("reclaimed");
+        let holder = read_holder(&state_lock_path(tmp.path())).expect("holder");
+        assert_eq!(holder.pid, std::process::id());
+        assert_ne!(holder.token, stale.token);
+        drop(guard);
+        assert!(!state_lock_path(tmp.path()).exists());
+        let reclaims = take_state_lock_reclaims();
+        let reclaim = reclaims
+            .iter()
+            .find(|r| r.lock_path == state_lock_path(tmp.path()))
+            .expect("reclaim recorded");
+        assert_eq!(reclaim.stale_holder.as_ref(), Some(&stale));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn waiting_for_the_lock_does_not_stall_the_runtime_worker() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        write_foreign_lock(tmp.path(), std::process::id());
+        let lock_path = state_lock_path(tmp.path());
+        // Runs on the only worker, so it can release the lock only if the
+        // waiting acquisition below gives that worker up.
+        let releaser = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            std::fs::remove_file(lock_path).expect("release");
+        });
+        let guard =
+            acquire_state_lock_with_wait(tmp.path(), Duration::from_secs(5)).expect("acquired");
+        drop(guard);
+        releaser.await.expect("releaser");
+    }
+
+    #[test]
+    fn reads_do_not_take_the_lock() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let session_path = tmp.path().join("sessions").join("default.json");
+        let store = crate::session::SessionStore::new(session_path, "default".to_string());
+        store
+            .save(&crate::session::SessionData::empty("default"), 40)
+            .expect("save");
+        write_foreign_lock(tmp.path(), std::process::id());
+        configure_state_lock(Duration::ZERO, "localagent");
+        assert!(store.load().is_ok());
+        let err = store
+            .save(&crate::session::SessionData::empty("default"), 40)
+            .expect_err("locked write");
+        assert!(err.to_string().contains("--wait-lock"));
+        configure_state_lock(
+            Duration::from_secs(DEFAULT_STATE_LOCK_WAIT_SECS),
+            "localagent",
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use super::types::{
+    RunRecord, RUN_RECORD_SCHEMA_LATEST, RUN_RECORD_SCHEMA_V1, RUN_RECORD_SCHEMA_V2,
+};
+
+const RUN_RECORD_SCHEMA_PREFIX: &str = "openagent.run_record.v";
+
+/// Schema version of a raw run record. Records written before the envelope was
+/// versioned carry no `schema_version` and are v1.
+pub fn run_record_schema_version(raw: &Value) -> anyhow::Result<String> {
+    match raw.get("schema_version") {
+        None | Some(Value::Null) => Ok(RUN_RECORD_SCHEMA_V1.to_string()),
+        Some(Value::String(v)) => Ok(v.clone()),
+        Some(other) => Err(anyhow!(
+            "run record schema_version must be a string, got {other}"
+        )),
+    }
+}
+
+/// v1 -> v2 only stamps the envelope version: every field added since v1 has a
+/// serde default, so the body loads unchanged.
+fn migrate_v1_to_v2(mut raw: Value) -> anyhow::Result<Value> {
+    let obj = raw
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("run record must be a JSON object"))?;
+    obj.insert(
+        "schema_version".to_string(),
+        Value::String(RUN_RECORD_SCHEMA_V2.to_string()),
+    );
+    Ok(raw)
+}
+
+fn schema_number(version: &str) -> Option<u32> {
+    version.strip_prefix(RUN_RECORD_SCHEMA_PREFIX)?.parse().ok()
+}
+
+/// Upgrades a raw run record to the newest schema this binary writes. A record
+/// one version newer loads as is, its unknown fields kept for the rewrite;
+/// anything newer than that is refused rather than guessed at.
+pub fn migrate_record(raw: Value) -> anyhow::Result<Value> {
+    let version = run_record_schema_version(&raw)?;
+    match version.as_str() {
+        RUN_RECORD_SCHEMA_V1 => migrate_v1_to_v2(raw),
+        RUN_RECORD_SCHEMA_V2 => Ok(raw),
+        other => match (
+            schema_number(other),
+            schema_number(RUN_RECORD_SCHEMA_LATEST),
+        ) {
+            (Some(n), Some(latest)) if n == latest + 1 => Ok(raw),
+            (Some(_), _) => Err(anyhow!(
+                "run record schema {other} is newer than supported {RUN_RECORD_SCHEMA_LATEST}"
+            )),
+            _ => Err(anyhow!("unknown run record schema_version: {other}")),
+        },
+    }
+}
+
+/// The parts of `raw` that `known` (the same record as this binary
+/// re-serializes it) lacks: missing object keys with their values, found by
+/// walking objects and equal-length arrays. Array slots with nothing missing
+/// are `null`. `None` when nothing is missing.
+pub(crate) fn unknown_fields(raw: &Value, known: &Value) -> Option<Value> {
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => {
+            let missing = raw
+                .iter()
+                .filter_map(|(key, value)| match known.get(key) {
+                    None => Some((key.clone(), value.clone())),
+                    Some(known) => unknown_fields(value, known).map(|v| (key.clone(), v)),
+                })
+                .collect::<serde_json::Map<_, _>>();
+            (!missing.is_empty()).then_some(Value::Object(missing))
+        }
+        (Value::Array(raw), Value::Array(known)) if raw.len() == known.len() => {
+            let slots = raw
+                .iter()
+                .zip(known)
+                .map(|(raw, known)| unknown_fields(raw, known))
+                .collect::<Vec<_>>();
+            slots
+                .iter()
+                .any(Option::is_some)
+                .then(|| Value::Array(slots.into_iter().map(Option::unwrap_or_default).collect()))
+        }
+        _ => None,
+    }
+}
+
+/// Writes the output of [`unknown_fields`] back into a serialized record.
+pub(crate) fn merge_unknown_fields(target: &mut Value, unknown: &Value) {
+    match (target, unknown) {
+        (Value::Object(target), Value::Object(unknown)) => {
+            for (key, value) in unknown {
+                match target.get_mut(key) {
+                    Some(existing) => merge_unknown_fields(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(unknown)) if target.len() == unknown.len() => {
+            for (slot, value) in target.iter_mut().zip(unknown) {
+                if !value.is_null() {
+                    merge_unknown_fields(slot, value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parses a run record of any supported version, migrating it to the newest
+/// schema. The stored version is kept in `migrated_from` when it differs.
+pub fn parse_run_record(content: &str) -> anyhow::Result<RunRecord> {
+    let raw: Value = serde_json::from_str(content).context("run record is not valid JSON")?;
+    let stored = run_record_schema_version(&raw)?;
+    let migrated = migrate_record(raw)?;
+    let mut record: RunRecord = serde_json::from_value(migrated.clone())?;
+    record.unknown_nested = unknown_fields(&migrated, &serde_json::to_value(&record)?);
+    if stored != record.schema_version {
+        record.migrated_from = Some(stored);
+    }
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn missing_version_is_v1_and_migrates_to_latest() {
+        let raw = json!({"metadata": {}});
+        assert_eq!(
+            run_record_schema_version(&raw).expect("version"),
+            RUN_RECORD_SCHEMA_V1
+        );
+        let migrated = migrate_record(raw).expect("migrate");
+        assert_eq!(migrated["schema_version"], RUN_RECORD_SCHEMA_LATEST);
+        assert_eq!(migrated["metadata"], json!({}));
+    }
+
+    #[test]
+    fn newer_and_foreign_versions_are_refused() {
+        assert!(migrate_record(json!({"schema_version": "openagent.run_record.v3"})).is_ok());
+        let err = migrate_record(json!({"schema_version": "openagent.run_record.v9"}))
+            .expect_err("newer");
+        assert!(err.to_string().contains("newer than supported"));
+        assert!(migrate_record(json!({"schema_version": "openagent.plan.v1"})).is_err());
+        assert!(migrate_record(json!({"schema_version": 2})).is_err());
+    }
+
+    #[test]
+    fn unknown_fields_merge_back_into_objects_and_arrays() {
+        let raw = json!({"a": {"b": 1, "new": [1]}, "list": [{"x": 1}, {"x": 2, "y": 3}]});
+        let mut known = json!({"a": {"b": 1}, "list": [{"x": 1}, {"x": 2}]});
+        let unknown = unknown_fields(&raw, &known).expect("unknown");
+        assert_eq!(
+            unknown,
+            json!({"a": {"new": [1]}, "list": [null, {"y": 3}]})
+        );
+        merge_unknown_fields(&mut known, &unknown);
+        assert_eq!(known, raw);
+        assert!(unknown_fields(&raw, &raw).is_none());
+    }
+}
@@ -182,6 +182,92 @@ fn push_completion_decisions_section(out: &mut String, record: &RunRecord) {
     }
 }
 
+fn push_artifact_files_section(out: &mut String, record: &RunRecord) {
+    let Some(files) = record
+        .compaction
+        .as_ref()
+        .map(|c| &c.artifact_files)
+        .filter(|files| !files.is_empty())
+    else {
+        return;
+    };
+    out.push_str("tool_result_artifacts:\n");
+    for file in files {
+        out.push_str(&format!(
+            "  - tool_call_id={} tool={} ok={} bytes={} sha256={} path={}\n",
+            file.tool_call_id, file.tool, file.ok, file.bytes, file.sha256, file.path,
+        ));
+    }
+}
+
+fn push_tool_decisions_section(out: &mut String, record: &RunRecord) {
+    if record.tool_decisions.is_empty() {
+        return;
+    }
+    out.push_str("tool_decisions:\n");
+    for decision in &record.tool_decisions {
+        out.push_str(&format!(
+            "  - step={} tool={} decision={} source={}\n",
+            decision.step,
+            decision.tool,
+            decision.decision,
+            decision.source.as_deref().unwrap_or("-"),
+        ));
+        if let Some(gate_context) = &decision.gate_context {
+            out.push_str(&format!(
+                "    gate_context: {}\n",
+                gate_context.render_compact()
+            ));
+        }
+        if let Some(rewrite) = decision
+            .gate_context
+            .as_ref()
+            .and_then(|g| g.hook_rewrite.as_ref())
+        {
+            out.push_str(&format!(
+                "    hook_rewrite: hooks={} original_args_sha256={} args={}\n",
+                rewrite.hooks.join(","),
+                rewrite.original_args_sha256,
+                rewrite.arguments
+            ));
+        }
+    }
+}
+
+fn push_step_stats_section(out: &mut String, record: &RunRecord) {
+    if record.steps.is_empty() {
+        return;
+    }
+    let opt = |v: Option<u32>| v.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+    out.push_str("steps:\n");
+    out.push_str(
+        "  step  start_ms   wall_ms  provider_ms  tool_ms  tok_in  tok_out  compact  tools\n",
+    );
+    for stats in &record.steps {
+        let tools = stats
+            .tools
+            .iter()
+            .map(|t| match t.bytes {
+                Some(bytes) => format!("{}({}ms,{}B)", t.name, t.duration_ms, bytes),
+                None => format!("{}({}ms)", t.name, t.duration_ms),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        out.push_str(&format!(
+            "  {:>4}  {:>8}  {:>8}  {:>11}  {:>7}  {:>6}  {:>7}  {:>7}  {}\n",
+            stats.step,
+            stats.start_ms,
+            stats.duration_ms,
+            stats.provider_latency_ms,
+            stats.tool_ms(),
+            opt(stats.tokens_in),
+            opt(stats.tokens_out),
+            if stats.compaction { "yes" } else { "no" },
+            if tools.is_empty() { "-" } else { &tools },
+        ));
+    }
+}
+
 pub fn render_replay(record: &RunRecord) -> String {
     let mut out = String::new();
     out.push_str(&format!(
@@ -242,6 +328,9 @@ pub fn render_replay(record: &RunRecord) -> String {
     push_interrupt_history_section(&mut out, record);
     push_phase_summary_section(&mut out, record);
     push_completion_decisions_section(&mut out, record);
+    push_tool_decisions_section(&mut out, record);
+    push_artifact_files_section(&mut out, record);
+    push_step_stats_section(&mut out, record);
     for m in &record.transcript {
         let content = m.content.clone().unwrap_or_default();
         match m.role {
@@ -356,11 +445,18 @@ mod tests {
     #[test]
     fn render_replay_includes_task_contract_section() {
         let rendered = render_replay(&crate::store::RunRecord {
+            schema_version: crate::store::RUN_RECORD_SCHEMA_LATEST.to_string(),
+            migrated_from: None,
+            unknown_nested: None,
+            extra: Default::default(),
             metadata: crate::store::RunMetadata {
                 run_id: "r1".to_string(),
                 started_at: "2026-01-01T00:00:00Z".to_string(),
                 finished_at: "2026-01-01T00:00:01Z".to_string(),
                 exit_reason: "ok".to_string(),
+                model_served: None,
+                subtask_run_ids: Vec::new(),
+                post_run_hook_error: None,
             },
             mode: "single".to_string(),
             planner: None,
@@ -388,6 +484,9 @@ mod tests {
                 allow_write: false,
                 enable_write_tools: false,
                 exec_target: "host".to_string(),
+                read_allowlist: Vec::new(),
+                shell_allowlist: Vec::new(),
+                max_tool_calls: Default::default(),
                 docker_image: None,
                 docker_workdir: None,
                 docker_network: None,
@@ -446,6 +545,7 @@ mod tests {
                 mcp_tool_catalog_hash_hex: None,
                 mcp_servers: Vec::new(),
                 mcp_config_path: None,
+                mcp_roots: Vec::new(),
                 policy_version: None,
                 includes_resolved: Vec::new(),
                 mcp_allowlist: None,
@@ -485,6 +585,10 @@ mod tests {
                 profile_source: None,
                 profile_hash_hex: None,
                 activated_packs: Vec::new(),
+                context_pack: None,
+                prompt_template: None,
+                learned_guidance: Vec::new(),
+                mcp_server_launches: Vec::new(),
             },
             resolved_paths: crate::store::RunResolvedPaths {
                 state_dir: ".".to_string(),
@@ -538,6 +642,7 @@ mod tests {
             tool_schema_hash_hex_map: Default::default(),
             hooks_config_hash_hex: None,
             transcript: Vec::new(),
+            transcript_chain_hex: Vec::new(),
             tool_calls: Vec::new(),
             tool_decisions: Vec::new(),
             tool_facts: Vec::new(),
@@ -546,21 +651,94 @@ mod tests {
             hook_report: Vec::new(),
             tool_catalog: Vec::new(),
             mcp_runtime_trace: Vec::new(),
+            timeline: Vec::new(),
+            steps: vec![crate::agent::StepStats {
+                step: 2,
+                start_ms: 10,
+                end_ms: 95,
+                duration_ms: 85,
+                provider_calls: 1,
+                provider_latency_ms: 60,
+                tools: vec![crate::agent::step_stats::StepToolStats {
+                    tool_call_id: "tc1".to_string(),
+                    name: "shell".to_string(),
+                    duration_ms: 20,
+                    bytes: Some(128),
+                    status: "ok".to_string(),
+                }],
+                tokens_in: Some(300),
+                tokens_out: None,
+                compaction: true,
+            }],
             tool_reliability: Default::default(),
             mcp_pin_snapshot: None,
+            environment_probe: None,
+            operator_queue: None,
+            provider_pacing: None,
+            otlp_export: None,
             taint: None,
             repro: None,
             final_output: String::new(),
             error: None,
+            external_interactions: None,
+            files_written: Vec::new(),
         });
         assert!(rendered.contains("task_contract:"));
         assert!(rendered.contains("task_kind: coding"));
         assert!(rendered.contains("allowed_tools_semantics: Defaulted"));
+        assert!(rendered.contains(
+            "steps:\n  step  start_ms   wall_ms  provider_ms  tool_ms  tok_in  tok_out  compact  tools\n     2        10        85           60       20     300        -      yes  shell(20ms,128B)\n"
+        ));
+    }
+
+    #[test]
+    fn render_replay_includes_tool_decision_section() {
+        let path = format!(
+            "{}/tests/fixtures/run_records/v2_envelope.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let mut record =
+            crate::store::parse_run_record(&std::fs::read_to_string(path).expect("fixture"))
+                .expect("record");
+        record.tool_decisions = vec![crate::agent::ToolDecisionRecord {
+            step: 2,
+            tool_call_id: "tc1".to_string(),
+            tool: "shell".to_string(),
+            decision: "deny".to_string(),
+            reason: Some("tainted".to_string()),
+            source: Some("policy".to_string()),
+            approval_id: None,
+            taint_overall: Some("tainted".to_string()),
+            taint_enforced: true,
+            escalated: false,
+            escalation_reason: None,
+            taint_sources: Vec::new(),
+            denial_class: None,
+            gate_context: Some(crate::agent::GateContextSnapshot {
+                taint_overall: "tainted".to_string(),
+                taint_source_count: 1,
+                remaining_total_tool_calls: Some(7),
+                remaining_calls_by_category: [("shell".to_string(), 2)].into(),
+                plan_step_id: Some("S2".to_string()),
+                approval_mode: "interrupt".to_string(),
+                auto_approve_scope: None,
+                policy_hash_hex: Some("abc".to_string()),
+                hook_rewrite: None,
+            }),
+        }];
+        let rendered = render_replay(&record);
+        assert!(rendered.contains(
+            "tool_decisions:\n  - step=2 tool=shell decision=deny source=policy\n    gate_context: taint=tainted taint_sources=1 remaining_total=7 remaining_shell=2 plan_step=S2 approval_mode=interrupt policy=abc\n"
+        ));
     }
 
     #[test]
     fn render_includes_profile_name_and_canonical_task_kind_when_they_differ() {
         let rendered = render_replay(&crate::store::RunRecord {
+            schema_version: crate::store::RUN_RECORD_SCHEMA_LATEST.to_string(),
+            migrated_from: None,
+            unknown_nested: None,
+            extra: Default::default(),
             mode: "single".to_string(),
             planner: None,
             worker: None,
@@ -569,6 +747,9 @@ mod tests {
                 started_at: "2026-01-01T00:00:00Z".to_string(),
                 finished_at: "2026-01-01T00:00:01Z".to_string(),
                 exit_reason: "ok".to_string(),
+                model_served: None,
+                subtask_run_ids: Vec::new(),
+                post_run_hook_error: None,
             },
             cli: crate::store::RunCliConfig {
                 mode: "single".to_string(),
@@ -593,6 +774,9 @@ mod tests {
                 allow_write: false,
                 enable_write_tools: false,
                 exec_target: "host".to_string(),
+                read_allowlist: Vec::new(),
+                shell_allowlist: Vec::new(),
+                max_tool_calls: Default::default(),
                 docker_image: None,
                 docker_workdir: None,
                 docker_network: None,
@@ -651,6 +835,7 @@ mod tests {
                 mcp_tool_catalog_hash_hex: None,
                 mcp_servers: Vec::new(),
                 mcp_config_path: None,
+                mcp_roots: Vec::new(),
                 policy_version: None,
                 includes_resolved: Vec::new(),
                 mcp_allowlist: None,
@@ -690,6 +875,10 @@ mod tests {
                 profile_source: None,
                 profile_hash_hex: None,
                 activated_packs: Vec::new(),
+                context_pack: None,
+                prompt_template: None,
+                learned_guidance: Vec::new(),
+                mcp_server_launches: Vec::new(),
             },
             resolved_paths: crate::store::RunResolvedPaths {
                 state_dir: ".".to_string(),
@@ -715,6 +904,7 @@ mod tests {
             tool_schema_hash_hex_map: Default::default(),
             hooks_config_hash_hex: None,
             transcript: Vec::new(),
+            transcript_chain_hex: Vec::new(),
             tool_calls: Vec::new(),
             tool_decisions: Vec::new(),
             tool_facts: Vec::new(),
@@ -723,12 +913,20 @@ mod tests {
             hook_report: Vec::new(),
             tool_catalog: Vec::new(),
             mcp_runtime_trace: Vec::new(),
+            timeline: Vec::new(),
+            steps: Vec::new(),
             tool_reliability: Default::default(),
             mcp_pin_snapshot: None,
+            environment_probe: None,
+            operator_queue: None,
+            provider_pacing: None,
+            otlp_export: None,
             taint: None,
             repro: None,
             final_output: String::new(),
             error: None,
+            external_interactions: None,
+            files_written: Vec::new(),
         });
         assert!(rendered.contains("instruction_task_profile: coding_orchestrator_v1"));
         assert!(rendered.contains("instruction_task_profile_task_kind: coding"));
@@ -15,8 +15,29 @@ pub(crate) fn default_output_mode() -> String {
     "human".to_string()
 }
 
+pub const RUN_RECORD_SCHEMA_V1: &str = "openagent.run_record.v1";
+pub const RUN_RECORD_SCHEMA_V2: &str = "openagent.run_record.v2";
+pub const RUN_RECORD_SCHEMA_LATEST: &str = RUN_RECORD_SCHEMA_V2;
+
+fn default_run_record_schema_version() -> String {
+    RUN_RECORD_SCHEMA_V1.to_string()
+}
+
+/// Serialized through [`RunRecord::serialize`] below so that unknown nested
+/// fields captured on load are written back; see `unknown_nested`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct RunRecord {
+    #[serde(default = "default_run_record_schema_version")]
+    pub schema_version: String,
+    /// Schema version found on disk when the record was migrated on load.
+    #[serde(skip)]
+    pub migrated_from: Option<String>,
+    /// Fields below the top level that this binary does not know about, in
+    /// the shape of the record itself; see `store::migrate`. `extra` keeps the
+    /// unknown top-level ones.
+    #[serde(skip)]
+    pub unknown_nested: Option<Value>,
     pub metadata: RunMetadata,
     pub mode: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -56,6 +77,10 @@ pub struct RunRecord {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hooks_config_hash_hex: Option<String>,
     pub transcript: Vec<Message>,
+    /// Per-message chain hashes of `transcript`; see
+    /// [`super::transcript_chain_hex`]. Empty for records written before it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcript_chain_hex: Vec<String>,
     pub tool_calls: Vec<ToolCall>,
     #[serde(default)]
     pub tool_decisions: Vec<crate::agent::ToolDecisionRecord>,
@@ -71,16 +96,57 @@ pub struct RunRecord {
     pub tool_catalog: Vec<ToolCatalogEntry>,
     #[serde(default)]
     pub mcp_runtime_trace: Vec<crate::agent::McpRuntimeTraceEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timeline: Vec<crate::agent::TimelineEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<crate::agent::StepStats>,
     #[serde(default)]
     pub tool_reliability: ToolReliabilityRecord,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mcp_pin_snapshot: Option<McpPinSnapshotRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_probe: Option<crate::env_probe::EnvironmentProbeRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator_queue: Option<crate::operator_queue::OperatorQueueRunRecordV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_pacing: Option<crate::providers::pacing::ProviderPacingRecordV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otlp_export: Option<crate::otlp::OtlpExportRecordV1>,
+    /// Derived from the rest of the record when it is written; see
+    /// `derive_external_interactions`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_interactions: Option<super::ExternalInteractionsV1>,
+    /// Files written under `--checkpoint-writes`, for `localagent rollback`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_written: Vec<super::FileWrittenV1>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub taint: Option<crate::agent::AgentTaintRecord>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repro: Option<crate::repro::RunReproRecord>,
     pub final_output: String,
     pub error: Option<String>,
+    /// Fields this binary does not know about, kept so that rewriting a record
+    /// produced by a newer writer of the same schema does not drop them.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+impl Serialize for RunRecord {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(unknown) = &self.unknown_nested else {
+            return RunRecord::serialize(self, serializer);
+        };
+        let mut value = RunRecord::serialize(self, serde_json::value::Serializer)
+            .map_err(serde::ser::Error::custom)?;
+        super::migrate::merge_unknown_fields(&mut value, unknown);
+        value.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RunRecord {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RunRecord::deserialize(deserializer)
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -157,6 +223,27 @@ pub struct McpToolSnapshotEntry {
     pub parameters: Value,
 }
 
+/// How an enabled MCP server's paths relate to the workdir (`--mcp-root`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpRootsRecord {
+    pub server: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roots: Vec<McpRootRecord>,
+    /// Tools with path-typed parameters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_typed_tools: Vec<String>,
+    /// Path-typed tools exist but no roots were declared, so workdir policy
+    /// does not reach this server's file access.
+    #[serde(default)]
+    pub unmapped_filesystem_access: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpRootRecord {
+    pub server_root: String,
+    pub host_root: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivatedPackRecord {
     pub pack_id: String,
@@ -166,6 +253,69 @@ pub struct ActivatedPackRecord {
     pub truncated: bool,
 }
 
+/// A learning entry whose guidance was injected under `--use-learnings`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LearnedGuidanceRecord {
+    pub learning_id: String,
+    pub entry_hash_hex: String,
+    pub chars: u64,
+}
+
+/// `--context-pack` files injected at run start; `skipped` covers missing,
+/// denied, unreadable, and over-budget entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextPackRecord {
+    pub name: String,
+    pub pack_hash_hex: String,
+    pub max_bytes: u64,
+    pub bytes_injected: u64,
+    pub files: Vec<ContextPackFileRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<ContextPackSkipRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextPackFileRecord {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<String>,
+    pub sha256_hex: String,
+    pub bytes: u64,
+    #[serde(default)]
+    pub read_truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextPackSkipRecord {
+    pub path: String,
+    pub reason: String,
+}
+
+/// `--template` rendering. Variable and environment values are stored as
+/// SHA-256 hashes; the rendered prompt is kept whole.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplateRecord {
+    pub path: String,
+    pub template_sha256_hex: String,
+    pub rendered_sha256_hex: String,
+    pub rendered_prompt: String,
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env_vars: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PromptTemplateFileRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplateFileRecord {
+    pub path: String,
+    pub sha256_hex: String,
+    pub bytes: u64,
+    #[serde(default)]
+    pub read_truncated: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpPinSnapshotRecord {
     pub enforcement: String,
@@ -179,6 +329,10 @@ pub struct McpPinSnapshotRecord {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mcp_config_hash_hex: Option<String>,
     pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata_sanitized: Vec<crate::mcp::sanitize::McpMetadataSanitizedRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_rejected: Vec<crate::mcp::sanitize::McpToolRejectedRecord>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -187,6 +341,15 @@ pub struct RunMetadata {
     pub started_at: String,
     pub finished_at: String,
     pub exit_reason: String,
+    /// Model the server reported serving; compare with `cli.model`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_served: Option<String>,
+    /// Child runs started through `spawn_subtask`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtask_run_ids: Vec<String>,
+    /// A strict post_run hook failed after the run ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_run_hook_error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -224,6 +387,13 @@ pub struct RunCliConfig {
     pub allow_write: bool,
     pub enable_write_tools: bool,
     pub exec_target: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_allowlist: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shell_allowlist: Vec<String>,
+    /// Per-tool call limits from `--max-tool-calls`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub max_tool_calls: BTreeMap<String, usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub docker_image: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -303,8 +473,12 @@ pub struct RunCliConfig {
     pub mcp_tool_catalog_hash_hex: Option<String>,
     #[serde(default)]
     pub mcp_servers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mcp_server_launches: Vec<super::McpServerLaunchRecord>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mcp_config_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mcp_roots: Vec<McpRootsRecord>,
     pub policy_version: Option<u32>,
     #[serde(default)]
     pub includes_resolved: Vec<String>,
@@ -382,6 +556,12 @@ pub struct RunCliConfig {
     pub profile_hash_hex: Option<String>,
     #[serde(default)]
     pub activated_packs: Vec<ActivatedPackRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_pack: Option<ContextPackRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_template: Option<PromptTemplateRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub learned_guidance: Vec<LearnedGuidanceRecord>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -533,6 +713,13 @@ pub struct RunCompactionRecord {
     pub final_prompt_size_chars: usize,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub report: Option<CompactionReport>,
+    /// How often the model re-requested results compaction had digested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest_refetch: Option<crate::compaction::DigestRefetchStatsV1>,
+    /// Tool results moved out of the transcript by `artifact_file`
+    /// persistence; `replay verify --strict` rehashes each file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifact_files: Vec<super::ToolResultArtifactV1>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
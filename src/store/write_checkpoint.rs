@@ -0,0 +1,364 @@
+//! Pre-write snapshots for `--checkpoint-writes` and `localagent rollback`.
+//!
+//! Before a write tool first touches a file, its current bytes are copied to
+//! `runs/<run_id>/checkpoint/files/<sha256>`. `manifest.json` lists every
+//! file the run wrote with its hash before the run and after its last write;
+//! the run record carries the same list as `files_written`.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use super::hash::sha256_hex;
+use super::io::{ensure_dir, write_json_atomic};
+
+pub const WRITE_CHECKPOINT_SCHEMA_V1: &str = "openagent.write_checkpoint.v1";
+pub const WRITE_CHECKPOINT_DIR_NAME: &str = "checkpoint";
+pub const WRITE_CHECKPOINT_MANIFEST_FILE_NAME: &str = "manifest.json";
+const SNAPSHOT_FILES_DIR_NAME: &str = "files";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteCheckpointManifestV1 {
+    pub schema_version: String,
+    pub run_id: String,
+    pub created_at: String,
+    pub workdir: String,
+    /// `HEAD` of the workdir's git repository when the checkpoint was taken.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_head: Option<String>,
+    #[serde(default)]
+    pub files: Vec<FileWrittenV1>,
+}
+
+/// One workdir file a run wrote. A `None` hash means the file did not exist.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileWrittenV1 {
+    pub path: String,
+    pub pre_hash: Option<String>,
+    pub post_hash: Option<String>,
+    /// The call that first wrote the file.
+    pub tool_call_id: String,
+}
+
+/// Per-run snapshot store shared by the tool runtime. The manifest is
+/// created on the first write, so runs that never write leave nothing.
+#[derive(Debug)]
+pub struct WriteCheckpointStore {
+    run_id: String,
+    dir: PathBuf,
+    workdir: PathBuf,
+    state: Mutex<CheckpointState>,
+}
+
+#[derive(Debug, Default)]
+struct CheckpointState {
+    /// Read from disk on first use, so a resumed run keeps earlier entries.
+    loaded: bool,
+    manifest: Option<WriteCheckpointManifestV1>,
+    /// Snapshotted before a write whose result is not in yet.
+    pending: BTreeMap<String, (Option<String>, String)>,
+}
+
+impl WriteCheckpointStore {
+    pub fn new(state_dir: &Path, run_id: &str, workdir: &Path) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            dir: write_checkpoint_dir(state_dir, run_id),
+            workdir: workdir.to_path_buf(),
+            state: Mutex::new(CheckpointState::default()),
+        }
+    }
+
+    /// Saves the current bytes of each path the run has not written yet.
+    /// Paths outside the workdir are not snapshotted.
+    pub fn before_write(&self, paths: &[String], tool_call_id: &str) -> anyhow::Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("write checkpoint lock poisoned"))?;
+        self.load(&mut state)?;
+        for path in paths.iter().filter(|p| is_workdir_relative(p)) {
+            let known = state
+                .manifest
+                .as_ref()
+                .is_some_and(|m| m.files.iter().any(|f| &f.path == path));
+            if known || state.pending.contains_key(path) {
+                continue;
+            }
+            let pre_hash = match read_optional(&self.workdir.join(path))? {
+                Some(bytes) => {
+                    let hash = sha256_hex(&bytes);
+                    let blob = self.dir.join(SNAPSHOT_FILES_DIR_NAME).join(&hash);
+                    if !blob.exists() {
+                        ensure_dir(blob.parent().expect("blob parent"))?;
+                        std::fs::write(&blob, &bytes).with_context(|| {
+                            format!("failed to snapshot {path} to {}", blob.display())
+                        })?;
+                    }
+                    Some(hash)
+                }
+                None => None,
+            };
+            state
+                .pending
+                .insert(path.clone(), (pre_hash, tool_call_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Records the post-write hash of each path after a successful write.
+    pub fn after_write(&self, paths: &[String]) -> anyhow::Result<()> {
+        let mut guard = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("write checkpoint lock poisoned"))?;
+        let state = &mut *guard;
+        self.load(state)?;
+        if state.manifest.is_none() {
+            state.manifest = Some(WriteCheckpointManifestV1 {
+                schema_version: WRITE_CHECKPOINT_SCHEMA_V1.to_string(),
+                run_id: self.run_id.clone(),
+                created_at: crate::trust::now_rfc3339(),
+                workdir: self.workdir.display().to_string(),
+                git_head: git_head(&self.workdir),
+                files: Vec::new(),
+            });
+        }
+        let manifest = state.manifest.as_mut().expect("manifest created");
+        for path in paths.iter().filter(|p| is_workdir_relative(p)) {
+            let post_hash = read_optional(&self.workdir.join(path))?.map(|b| sha256_hex(&b));
+            if let Some(entry) = manifest.files.iter_mut().find(|f| &f.path == path) {
+                entry.post_hash = post_hash;
+            } else if let Some((pre_hash, tool_call_id)) = state.pending.remove(path) {
+                manifest.files.push(FileWrittenV1 {
+                    path: path.clone(),
+                    pre_hash,
+                    post_hash,
+                    tool_call_id,
+                });
+            }
+        }
+        write_json_atomic(
+            &self.dir.join(WRITE_CHECKPOINT_MANIFEST_FILE_NAME),
+            manifest,
+        )
+    }
+
+    fn load(&self, state: &mut CheckpointState) -> anyhow::Result<()> {
+        if !state.loaded {
+            state.manifest = read_manifest(&self.dir)?;
+            state.loaded = true;
+        }
+        Ok(())
+    }
+}
+
+pub fn write_checkpoint_dir(state_dir: &Path, run_id: &str) -> PathBuf {
+    state_dir
+        .join("runs")
+        .join(run_id)
+        .join(WRITE_CHECKPOINT_DIR_NAME)
+}
+
+pub fn load_write_checkpoint(
+    state_dir: &Path,
+    run_id: &str,
+) -> anyhow::Result<Option<WriteCheckpointManifestV1>> {
+    read_manifest(&write_checkpoint_dir(state_dir, run_id))
+}
+
+fn read_manifest(dir: &Path) -> anyhow::Result<Option<WriteCheckpointManifestV1>> {
+    let path = dir.join(WRITE_CHECKPOINT_MANIFEST_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read write checkpoint {}", path.display()))?;
+    let manifest = serde_json::from_str(&raw)
+        .with_context(|| format!("failed to parse write checkpoint {}", path.display()))?;
+    Ok(Some(manifest))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RollbackReport {
+    pub restored: Vec<String>,
+    pub removed: Vec<String>,
+    /// Already at their pre-run content.
+    pub unchanged: Vec<String>,
+    /// Changed since the run; reported, and left alone, unless forced.
+    pub conflicts: Vec<String>,
+    pub git_head: Option<String>,
+}
+
+/// Puts every file the run wrote back to its pre-run content, deleting
+/// files the run created. Nothing is touched when any file changed after
+/// the run's last write, unless `force` is set.
+pub fn rollback_write_checkpoint(
+    state_dir: &Path,
+    run_id: &str,
+    force: bool,
+) -> anyhow::Result<RollbackReport> {
+    let manifest = load_write_checkpoint(state_dir, run_id)?.ok_or_else(|| {
+        anyhow!("run {run_id} has no write checkpoint (run with --checkpoint-writes)")
+    })?;
+    let dir = write_checkpoint_dir(state_dir, run_id);
+    let workdir = PathBuf::from(&manifest.workdir);
+    let mut report = RollbackReport {
+        git_head: manifest.git_head.clone(),
+        ..RollbackReport::default()
+    };
+    let mut planned = Vec::new();
+    for file in &manifest.files {
+        if !is_workdir_relative(&file.path) {
+            return Err(anyhow!(
+                "write checkpoint path escapes the workdir: {}",
+                file.path
+            ));
+        }
+        let full = workdir.join(&file.path);
+        let current = read_optional(&full)?.map(|b| sha256_hex(&b));
+        if current == file.pre_hash {
+            report.unchanged.push(file.path.clone());
+            continue;
+        }
+        if current != file.post_hash {
+            report.conflicts.push(file.path.clone());
+        }
+        planned.push((file, full));
+    }
+    if !report.conflicts.is_empty() && !force {
+        return Ok(report);
+    }
+    for (file, full) in planned {
+        match &file.pre_hash {
+            Some(hash) => {
+                let blob = dir.join(SNAPSHOT_FILES_DIR_NAME).join(hash);
+                let bytes = std::fs::read(&blob).with_context(|| {
+                    format!("missing snapshot of {} at {}", file.path, blob.display())
+                })?;
+                if let Some(parent) = full.parent() {
+                    ensure_dir(parent)?;
+                }
+                std::fs::write(&full, bytes)
+                    .with_context(|| format!("failed to restore {}", full.display()))?;
+                report.restored.push(file.path.clone());
+            }
+            None => {
+                if full.exists() {
+                    std::fs::remove_file(&full)
+                        .with_context(|| format!("failed to remove {}", full.display()))?;
+                }
+                report.removed.push(file.path.clone());
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn read_optional(path: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(anyhow!("failed to read {}: {e}", path.display())),
+    }
+}
+
+fn is_workdir_relative(path: &str) -> bool {
+    let path = Path::new(path);
+    !path.is_absolute()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Best effort: `None` outside a git repository or without `git`.
+fn git_head(workdir: &Path) -> Option<String> {
+    let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(workdir)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    let head = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    (out.status.success() && !head.is_empty()).then_some(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::{load_write_checkpoint, rollback_write_checkpoint, WriteCheckpointStore};
+
+    fn write_run(state: &std::path::Path, work: &std::path::Path) {
+        let store = WriteCheckpointStore::new(state, "run_a", work);
+        let paths = vec!["src/a.txt".to_string(), "new.txt".to_string()];
+        store.before_write(&paths, "tc1").expect("before");
+        std::fs::write(work.join("src/a.txt"), "changed\n").expect("write");
+        std::fs::write(work.join("new.txt"), "created\n").expect("write");
+        store.after_write(&paths).expect("after");
+        // A second write keeps the original pre-run snapshot.
+        store
+            .before_write(&["src/a.txt".to_string()], "tc2")
+            .expect("before");
+        std::fs::write(work.join("src/a.txt"), "changed twice\n").expect("write");
+        store
+            .after_write(&["src/a.txt".to_string()])
+            .expect("after");
+        let manifest = load_write_checkpoint(state, "run_a")
+            .expect("load")
+            .expect("manifest");
+        assert_eq!(manifest.files.len(), 2);
+    }
+
+    #[test]
+    fn rollback_restores_pre_run_content_and_removes_created_files() {
+        let state = tempdir().expect("state");
+        let work = tempdir().expect("work");
+        std::fs::create_dir_all(work.path().join("src")).expect("mkdir");
+        std::fs::write(work.path().join("src/a.txt"), "original\n").expect("seed");
+        write_run(state.path(), work.path());
+
+        let manifest = load_write_checkpoint(state.path(), "run_a")
+            .expect("load")
+            .expect("manifest");
+        assert_eq!(manifest.files[0].tool_call_id, "tc1");
+        assert!(manifest.files[1].pre_hash.is_none());
+
+        let report = rollback_write_checkpoint(state.path(), "run_a", false).expect("rollback");
+        assert_eq!(report.restored, vec!["src/a.txt"]);
+        assert_eq!(report.removed, vec!["new.txt"]);
+        assert_eq!(
+            std::fs::read_to_string(work.path().join("src/a.txt")).expect("read"),
+            "original\n"
+        );
+        assert!(!work.path().join("new.txt").exists());
+
+        let again = rollback_write_checkpoint(state.path(), "run_a", false).expect("rollback");
+        assert_eq!(again.unchanged.len(), 2);
+    }
+
+    #[test]
+    fn rollback_refuses_files_modified_after_the_run_unless_forced() {
+        let state = tempdir().expect("state");
+        let work = tempdir().expect("work");
+        std::fs::create_dir_all(work.path().join("src")).expect("mkdir");
+        std::fs::write(work.path().join("src/a.txt"), "original\n").expect("seed");
+        write_run(state.path(), work.path());
+        std::fs::write(work.path().join("src/a.txt"), "edited by hand\n").expect("edit");
+
+        let refused = rollback_write_checkpoint(state.path(), "run_a", false).expect("rollback");
+        assert_eq!(refused.conflicts, vec!["src/a.txt"]);
+        assert!(refused.restored.is_empty() && refused.removed.is_empty());
+        assert!(work.path().join("new.txt").exists());
+
+        let forced = rollback_write_checkpoint(state.path(), "run_a", true).expect("rollback");
+        assert_eq!(forced.restored, vec!["src/a.txt"]);
+        assert_eq!(
+            std::fs::read_to_string(work.path().join("src/a.txt")).expect("read"),
+            "original\n"
+        );
+    }
+}
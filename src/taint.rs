@@ -16,16 +16,24 @@ pub struct TaintSpan {
     pub source: String,
     pub detail: String,
     pub digest: String,
+    /// Workdir path an MCP file access maps to under `--mcp-root`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_path: Option<String>,
 }
 
 pub type MessageId = usize;
 
+/// Span source for tool results whose content looks like a prompt injection.
+pub const INJECTION_SUSPECTED_SOURCE: &str = "injection_suspected";
+
 #[derive(Debug, Clone, Default)]
 pub struct TaintState {
     pub message_taints: HashMap<MessageId, Vec<TaintSpan>>,
     pub spans_by_tool_call_id: BTreeMap<String, Vec<TaintSpan>>,
     pub overall: TaintLevel,
     pub last_sources: Vec<String>,
+    /// Digest of the most recent `injection_suspected` span.
+    pub injection_digest: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
@@ -47,6 +55,7 @@ impl TaintState {
             spans_by_tool_call_id: BTreeMap::new(),
             overall: TaintLevel::Clean,
             last_sources: Vec::new(),
+            injection_digest: None,
         }
     }
 
@@ -61,6 +70,12 @@ impl TaintState {
         }
         self.overall = TaintLevel::Tainted;
         self.last_sources = spans.iter().map(|s| s.source.clone()).collect();
+        if let Some(span) = spans
+            .iter()
+            .find(|s| s.source == INJECTION_SUSPECTED_SOURCE)
+        {
+            self.injection_digest = Some(span.digest.clone());
+        }
         self.message_taints
             .entry(message_id)
             .or_default()
@@ -82,6 +97,7 @@ impl TaintState {
                 source: "other".to_string(),
                 detail: "tainted_context".to_string(),
                 digest: String::new(),
+                host_path: None,
             });
     }
 
@@ -93,6 +109,17 @@ impl TaintState {
         out
     }
 
+    /// Distinct sources of every span recorded so far, sorted.
+    pub fn sources(&self) -> Vec<String> {
+        self.spans_by_tool_call_id
+            .values()
+            .flatten()
+            .map(|s| s.source.clone())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
     pub fn overall_str(&self) -> &'static str {
         match self.overall {
             TaintLevel::Clean => "clean",
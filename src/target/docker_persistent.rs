@@ -0,0 +1,616 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use super::DockerMeta;
+
+/// Upper bound for each lifecycle `docker` call (start, health check,
+/// removal), so a hung daemon cannot stall the run or its teardown.
+const DOCKER_LIFECYCLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Keeps the container alive without depending on anything beyond coreutils.
+const KEEPALIVE_SCRIPT: &str = "tail -f /dev/null";
+
+/// Runs the tool script (`$2`) as the leader of a new session, so its process
+/// group id is its pid, written to `$1`. `setsid` runs as a child of this
+/// shell, never a group leader, so it does not fork and return early.
+const EXEC_IN_OWN_GROUP_SCRIPT: &str = r#"setsid sh -c 'echo $$ > "$1"; exec sh -lc "$2"' localagent-exec "$1" "$2"; status=$?; rm -f "$1"; exit $status"#;
+
+/// Kills the process group recorded in `$1` by [`EXEC_IN_OWN_GROUP_SCRIPT`].
+const KILL_EXEC_GROUP_SCRIPT: &str = r#"kill -s KILL -- "-$(cat "$1")"; rm -f "$1""#;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DockerCliOutput {
+    pub(crate) success: bool,
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+}
+
+/// Runs `docker <args>` for the persistent container lifecycle: start,
+/// health check and removal. Tests replace it with a scripted fake.
+#[async_trait]
+pub(crate) trait DockerCli: Send + Sync {
+    async fn run(&self, args: &[String]) -> std::io::Result<DockerCliOutput>;
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SystemDockerCli;
+
+#[async_trait]
+impl DockerCli for SystemDockerCli {
+    async fn run(&self, args: &[String]) -> std::io::Result<DockerCliOutput> {
+        let mut cmd = tokio::process::Command::new("docker");
+        cmd.args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let output = tokio::time::timeout(DOCKER_LIFECYCLE_TIMEOUT, cmd.output())
+            .await
+            .map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!(
+                        "docker {} timed out after {}s",
+                        args.first().map(String::as_str).unwrap_or_default(),
+                        DOCKER_LIFECYCLE_TIMEOUT.as_secs()
+                    ),
+                )
+            })??;
+        Ok(DockerCliOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// A container started once per run. Tool calls are routed through
+/// `docker exec`; the container is force-removed when the last clone of the
+/// owning target is dropped, including during a panic unwind.
+pub(crate) struct PersistentContainer {
+    id: String,
+    host_workdir: PathBuf,
+    cli: Arc<dyn DockerCli>,
+}
+
+impl std::fmt::Debug for PersistentContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PersistentContainer")
+            .field("id", &self.id)
+            .field("host_workdir", &self.host_workdir)
+            .finish()
+    }
+}
+
+impl PersistentContainer {
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Calls for another workdir cannot use this container's mount.
+    pub(crate) fn serves(&self, host_workdir: &Path) -> bool {
+        self.host_workdir == host_workdir
+    }
+}
+
+impl Drop for PersistentContainer {
+    /// Removal runs on its own thread and runtime, since the last clone is
+    /// usually dropped inside an async task. The caller waits for it so the
+    /// container is gone before the run returns; on a multi-threaded runtime
+    /// that wait moves the worker's other tasks elsewhere first.
+    fn drop(&mut self) {
+        let cli = self.cli.clone();
+        let id = std::mem::take(&mut self.id);
+        let removal = std::thread::spawn(move || {
+            let Ok(rt) = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            else {
+                return;
+            };
+            // Best effort: the daemon may already be gone, in which case there
+            // is nothing left to clean up.
+            let _ = rt.block_on(remove_container(cli.as_ref(), &id));
+        });
+        let on_multi_thread_runtime = tokio::runtime::Handle::try_current()
+            .is_ok_and(|h| h.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread);
+        if on_multi_thread_runtime {
+            let _ = tokio::task::block_in_place(|| removal.join());
+        } else {
+            let _ = removal.join();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum PersistentStart {
+    Ready(PersistentContainer),
+    /// The container could not be started or failed its health check; tool
+    /// calls fall back to one `docker run` each.
+    Fallback(String),
+}
+
+/// `docker run -d`, then a `docker exec <id> setsid true` health check, since
+/// every call runs under `setsid`. Any failure removes whatever was created
+/// and yields a fallback reason.
+pub(crate) async fn start_persistent_container(
+    cli: Arc<dyn DockerCli>,
+    meta: &DockerMeta,
+    mount: &str,
+    host_workdir: &Path,
+    name: &str,
+) -> PersistentStart {
+    let started = match cli.run(&persistent_run_args(meta, mount, name)).await {
+        Ok(out) if out.success => out.stdout.trim().to_string(),
+        Ok(out) => {
+            let _ = remove_container(cli.as_ref(), name).await;
+            return PersistentStart::Fallback(format!(
+                "DOCKER_PERSISTENT_START_FAILED: {}",
+                out.stderr.trim()
+            ));
+        }
+        Err(e) => {
+            let _ = remove_container(cli.as_ref(), name).await;
+            return PersistentStart::Fallback(format!("DOCKER_PERSISTENT_START_FAILED: {e}"));
+        }
+    };
+    let id = if started.is_empty() {
+        name.to_string()
+    } else {
+        started
+    };
+    let health = cli
+        .run(&[
+            "exec".to_string(),
+            id.clone(),
+            "setsid".to_string(),
+            "true".to_string(),
+        ])
+        .await;
+    let failure = match health {
+        Ok(out) if out.success => None,
+        Ok(out) => Some(out.stderr.trim().to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(reason) = failure {
+        let _ = remove_container(cli.as_ref(), &id).await;
+        return PersistentStart::Fallback(format!(
+            "DOCKER_PERSISTENT_HEALTHCHECK_FAILED: {reason}"
+        ));
+    }
+    PersistentStart::Ready(PersistentContainer {
+        id,
+        host_workdir: host_workdir.to_path_buf(),
+        cli,
+    })
+}
+
+async fn remove_container(cli: &dyn DockerCli, id: &str) -> std::io::Result<DockerCliOutput> {
+    cli.run(&["rm".to_string(), "-f".to_string(), id.to_string()])
+        .await
+}
+
+/// Same network, user, limits, mount and workdir as a per-call `docker run`.
+pub(crate) fn persistent_run_args(meta: &DockerMeta, mount: &str, name: &str) -> Vec<String> {
+    let mut args = vec![
+        "run".to_string(),
+        "-d".to_string(),
+        "--name".to_string(),
+        name.to_string(),
+    ];
+    args.extend(meta.sandbox_args());
+    args.extend([
+        "-v".to_string(),
+        mount.to_string(),
+        "-w".to_string(),
+        meta.workdir.clone(),
+        meta.image.clone(),
+        "sh".to_string(),
+        "-c".to_string(),
+        KEEPALIVE_SCRIPT.to_string(),
+    ]);
+    args
+}
+
+/// Container path where one call records its process group id.
+pub(crate) fn exec_group_file() -> String {
+    format!(
+        "/tmp/localagent-exec-{}.pgid",
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// `docker exec` argv (without the leading `docker`) for one tool call, run in
+/// its own process group recorded in `group_file`. `interactive` keeps stdin
+/// open for content piped by writes.
+pub(crate) fn exec_args(
+    container_id: &str,
+    container_workdir: &str,
+    shell_script: &str,
+    interactive: bool,
+    group_file: &str,
+) -> Vec<String> {
+    let mut args = vec!["exec".to_string()];
+    if interactive {
+        args.push("-i".to_string());
+    }
+    args.extend([
+        "-w".to_string(),
+        container_workdir.to_string(),
+        container_id.to_string(),
+        "sh".to_string(),
+        "-c".to_string(),
+        EXEC_IN_OWN_GROUP_SCRIPT.to_string(),
+        "localagent-exec".to_string(),
+        group_file.to_string(),
+        shell_script.to_string(),
+    ]);
+    args
+}
+
+/// Killing the `docker exec` client leaves its process running inside the
+/// container, so a timed-out call kills its own process group, leaving the
+/// keepalive and any concurrent call alone.
+pub(crate) fn kill_exec_processes_args(container_id: &str, group_file: &str) -> Vec<String> {
+    vec![
+        "exec".to_string(),
+        container_id.to_string(),
+        "sh".to_string(),
+        "-c".to_string(),
+        KILL_EXEC_GROUP_SCRIPT.to_string(),
+        "localagent-kill".to_string(),
+        group_file.to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    use super::{
+        exec_args, kill_exec_processes_args, start_persistent_container, DockerCli,
+        DockerCliOutput, PersistentStart,
+    };
+    use crate::target::{DockerLimits, DockerMeta};
+
+    /// Replies with scripted outputs in order and records every call.
+    #[derive(Default)]
+    struct ScriptedCli {
+        replies: Mutex<VecDeque<std::io::Result<DockerCliOutput>>>,
+        calls: Mutex<Vec<Vec<String>>>,
+    }
+
+    impl ScriptedCli {
+        fn new(replies: Vec<std::io::Result<DockerCliOutput>>) -> Arc<Self> {
+            Arc::new(Self {
+                replies: Mutex::new(replies.into()),
+                calls: Mutex::default(),
+            })
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls
+                .lock()
+                .expect("calls")
+                .iter()
+                .map(|c| c.join(" "))
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl DockerCli for ScriptedCli {
+        async fn run(&self, args: &[String]) -> std::io::Result<DockerCliOutput> {
+            self.calls.lock().expect("calls").push(args.to_vec());
+            self.replies
+                .lock()
+                .expect("replies")
+                .pop_front()
+                .unwrap_or_else(|| Err(std::io::Error::other("daemon gone")))
+        }
+    }
+
+    fn ok(stdout: &str) -> std::io::Result<DockerCliOutput> {
+        Ok(DockerCliOutput {
+            success: true,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        })
+    }
+
+    fn fail(stderr: &str) -> std::io::Result<DockerCliOutput> {
+        Ok(DockerCliOutput {
+            success: false,
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+        })
+    }
+
+    fn meta() -> DockerMeta {
+        DockerMeta {
+            image: "ubuntu:24.04".to_string(),
+            workdir: "/work".to_string(),
+            network: "none".to_string(),
+            user: Some("1000:1000".to_string()),
+            container_id: None,
+            persistent_fallback: None,
+            limits: DockerLimits::default(),
+        }
+    }
+
+    async fn start(cli: &Arc<ScriptedCli>) -> PersistentStart {
+        start_persistent_container(
+            cli.clone(),
+            &meta(),
+            "/repo:/work",
+            Path::new("/repo"),
+            "localagent-test",
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn ready_container_is_removed_on_drop() {
+        let cli = ScriptedCli::new(vec![ok("abc123\n"), ok(""), ok("abc123\n")]);
+        let PersistentStart::Ready(container) = start(&cli).await else {
+            panic!("expected ready container");
+        };
+        assert_eq!(container.id(), "abc123");
+        assert!(container.serves(Path::new("/repo")));
+        assert!(!container.serves(Path::new("/other")));
+        drop(container);
+        assert_eq!(
+            cli.calls(),
+            vec![
+                "run -d --name localagent-test --network none --user 1000:1000 -v /repo:/work -w /work ubuntu:24.04 sh -c tail -f /dev/null",
+                "exec abc123 setsid true",
+                "rm -f abc123",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_start_falls_back_and_cleans_up_by_name() {
+        let cli = ScriptedCli::new(vec![fail("no space left on device"), ok("")]);
+        let PersistentStart::Fallback(reason) = start(&cli).await else {
+            panic!("expected fallback");
+        };
+        assert_eq!(
+            reason,
+            "DOCKER_PERSISTENT_START_FAILED: no space left on device"
+        );
+        assert_eq!(
+            cli.calls().last().map(String::as_str),
+            Some("rm -f localagent-test")
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_health_check_removes_container_and_falls_back() {
+        let cli = ScriptedCli::new(vec![ok("abc123\n"), fail("container exited"), ok("")]);
+        let PersistentStart::Fallback(reason) = start(&cli).await else {
+            panic!("expected fallback");
+        };
+        assert_eq!(
+            reason,
+            "DOCKER_PERSISTENT_HEALTHCHECK_FAILED: container exited"
+        );
+        assert_eq!(cli.calls().len(), 3);
+        assert_eq!(cli.calls()[2], "rm -f abc123");
+    }
+
+    #[tokio::test]
+    async fn drop_tolerates_daemon_disappearing() {
+        let cli = ScriptedCli::new(vec![ok("abc123\n"), ok("")]);
+        let PersistentStart::Ready(container) = start(&cli).await else {
+            panic!("expected ready container");
+        };
+        // No scripted reply left: removal errors and drop must not panic.
+        drop(container);
+        assert_eq!(cli.calls().last().map(String::as_str), Some("rm -f abc123"));
+    }
+
+    /// Holds `docker rm` until the test releases it, recording whether it was.
+    struct GatedRemovalCli {
+        inner: Arc<ScriptedCli>,
+        release: Mutex<Option<std::sync::mpsc::Receiver<()>>>,
+        released: Mutex<bool>,
+    }
+
+    #[async_trait::async_trait]
+    impl DockerCli for GatedRemovalCli {
+        async fn run(&self, args: &[String]) -> std::io::Result<DockerCliOutput> {
+            if args.first().map(String::as_str) == Some("rm") {
+                if let Some(release) = self.release.lock().expect("release").take() {
+                    let got = release.recv_timeout(std::time::Duration::from_secs(5));
+                    *self.released.lock().expect("released") = got.is_ok();
+                }
+            }
+            self.inner.run(args).await
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn removal_on_drop_does_not_stall_the_runtime_worker() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let cli = Arc::new(GatedRemovalCli {
+            inner: ScriptedCli::new(vec![ok("abc123\n"), ok(""), ok("")]),
+            release: Mutex::new(Some(rx)),
+            released: Mutex::new(false),
+        });
+        let PersistentStart::Ready(container) = start_persistent_container(
+            cli.clone(),
+            &meta(),
+            "/repo:/work",
+            Path::new("/repo"),
+            "localagent-test",
+        )
+        .await
+        else {
+            panic!("expected ready container");
+        };
+        // Both tasks share the only worker: removal can be released only if
+        // the dropping task gives that worker up while it waits.
+        let dropper = tokio::spawn(async move { drop(container) });
+        let releaser = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            let _ = tx.send(());
+        });
+        dropper.await.expect("dropper");
+        releaser.await.expect("releaser");
+        assert!(*cli.released.lock().expect("released"));
+        assert_eq!(
+            cli.inner.calls().last().map(String::as_str),
+            Some("rm -f abc123")
+        );
+    }
+
+    #[tokio::test]
+    async fn describe_reports_container_id_or_fallback_reason() {
+        use crate::target::{DockerTarget, ExecTarget};
+
+        let target = || {
+            DockerTarget::new(
+                "ubuntu:24.04".to_string(),
+                "/work".to_string(),
+                "none".to_string(),
+                None,
+                crate::target::DockerLimits::default(),
+            )
+        };
+        let cli = ScriptedCli::new(vec![ok("abc123\n"), ok(""), ok("")]);
+        let ready = target()
+            .with_persistent_container_via(cli.clone(), Path::new("/repo"))
+            .await;
+        let meta = serde_json::to_value(ready.describe().docker).expect("meta");
+        assert_eq!(meta["container_id"], "abc123");
+        assert!(meta.get("persistent_fallback").is_none());
+        let clone = ready.clone();
+        drop(ready);
+        assert_eq!(cli.calls().len(), 2, "clones share the container");
+        drop(clone);
+        assert_eq!(cli.calls().last().map(String::as_str), Some("rm -f abc123"));
+
+        let cli = ScriptedCli::new(vec![fail("Cannot connect to the Docker daemon"), ok("")]);
+        let fallback = target()
+            .with_persistent_container_via(cli, Path::new("/repo"))
+            .await;
+        let meta = serde_json::to_value(fallback.describe().docker).expect("meta");
+        assert!(meta.get("container_id").is_none());
+        assert_eq!(
+            meta["persistent_fallback"],
+            "DOCKER_PERSISTENT_START_FAILED: Cannot connect to the Docker daemon"
+        );
+    }
+
+    /// Needs a Docker daemon and `ubuntu:24.04`; runs only with
+    /// `LOCALAGENT_TEST_DOCKER=1`.
+    #[tokio::test]
+    async fn live_persistent_container_keeps_state_between_calls() {
+        use crate::target::{DockerTarget, ExecTarget, ShellReq};
+
+        if std::env::var("LOCALAGENT_TEST_DOCKER").as_deref() != Ok("1") {
+            return;
+        }
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let workdir = std::fs::canonicalize(tmp.path()).expect("canonical");
+        let target = DockerTarget::new(
+            "ubuntu:24.04".to_string(),
+            "/work".to_string(),
+            "none".to_string(),
+            None,
+            crate::target::DockerLimits::default(),
+        )
+        .with_persistent_container(&workdir)
+        .await;
+        let meta = target.describe().docker.expect("docker meta");
+        assert!(
+            meta.container_id.is_some(),
+            "{:?}",
+            meta.persistent_fallback
+        );
+        let shell = |cmd: &str| ShellReq {
+            workdir: workdir.clone(),
+            cmd: "sh".to_string(),
+            args: vec!["-c".to_string(), cmd.to_string()],
+            cwd: None,
+            max_tool_output_bytes: 1000,
+            timeout_ms: 0,
+            stream: None,
+            create_cwd: false,
+            redaction: None,
+        };
+        let first = target.exec_shell(shell("echo kept > /tmp/marker")).await;
+        assert!(first.ok, "{}", first.content);
+        let second = target.exec_shell(shell("cat /tmp/marker")).await;
+        assert!(second.content.contains("kept"), "{}", second.content);
+    }
+
+    #[test]
+    fn exec_args_keep_stdin_open_only_for_piped_content() {
+        let args = exec_args("abc123", "/work", "cat > a.txt", true, "/tmp/g.pgid");
+        assert_eq!(args[..5].join(" "), "exec -i -w /work abc123");
+        assert_eq!(args[args.len() - 2..].join(" "), "/tmp/g.pgid cat > a.txt");
+        assert_eq!(
+            exec_args("abc123", "/work", "ls", false, "/tmp/g.pgid")[..4].join(" "),
+            "exec -w /work abc123"
+        );
+    }
+
+    /// Runs the exec and kill scripts with the host `sh`, as `docker exec`
+    /// would inside the container.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn timed_out_exec_kills_only_its_own_process_group() {
+        use std::time::Duration;
+
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let run = |group_file: &std::path::Path| {
+            let args = exec_args(
+                "ignored",
+                "/work",
+                "sleep 30",
+                false,
+                &group_file.to_string_lossy(),
+            );
+            tokio::process::Command::new("sh")
+                .args(&args[5..])
+                .kill_on_drop(true)
+                .spawn()
+                .expect("spawn")
+        };
+        let timed_out_file = tmp.path().join("timed_out.pgid");
+        let other_file = tmp.path().join("other.pgid");
+        let mut timed_out = run(&timed_out_file);
+        let mut other = run(&other_file);
+        for file in [&timed_out_file, &other_file] {
+            for _ in 0..100 {
+                if std::fs::read_to_string(file).is_ok_and(|s| !s.trim().is_empty()) {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        }
+
+        let kill = |group_file: &std::path::Path| {
+            let args = kill_exec_processes_args("ignored", &group_file.to_string_lossy());
+            tokio::process::Command::new("sh").args(&args[3..]).status()
+        };
+        assert!(kill(&timed_out_file).await.expect("kill").success());
+        let done = tokio::time::timeout(Duration::from_secs(5), timed_out.wait())
+            .await
+            .expect("timed-out call ends")
+            .expect("wait");
+        assert!(!done.success());
+        assert!(!timed_out_file.exists());
+        assert!(other.try_wait().expect("try_wait").is_none());
+        assert!(kill(&other_file).await.expect("kill other").success());
+        other.wait().await.expect("wait other");
+    }
+}
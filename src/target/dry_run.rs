@@ -0,0 +1,216 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use super::{
+    apply_patch_lenient, multi_patch::split_multi_file_patch, normalize_patch_for_diffy,
+    resolve_path_scoped, DockerMeta, ExecTargetKind, TargetResult,
+};
+
+/// `write_file` under `--dry-run-writes`: reports the byte count and a
+/// unified diff against the current file without writing. Reads the host
+/// workdir, which the docker target mounts, so both targets agree.
+pub(super) async fn dry_run_write(
+    kind: ExecTargetKind,
+    docker: Option<DockerMeta>,
+    workdir: &Path,
+    path: &str,
+    content: &str,
+) -> TargetResult {
+    let current = match read_current(workdir, path, "write_file").await {
+        Ok(current) => current,
+        Err(reason) => return TargetResult::failed(kind, reason, docker),
+    };
+    let original = current.as_deref().unwrap_or_default();
+    let file = json!({
+        "path": path,
+        "dry_run": true,
+        "changed": current.is_none() || original != content,
+        "bytes_written": content.len(),
+        "diff": unified_diff(path, original, content),
+    });
+    dry_run_result(kind, docker, file.to_string(), content.len() as u64)
+}
+
+/// `apply_patch` under `--dry-run-writes`: applies the hunks in memory the
+/// way the real write would and reports the result without writing. A
+/// multi-file patch reports every file, and fails if any file would.
+pub(super) async fn dry_run_patch(
+    kind: ExecTargetKind,
+    docker: Option<DockerMeta>,
+    workdir: &Path,
+    path: Option<&str>,
+    patch: &str,
+) -> TargetResult {
+    let files = match path {
+        Some(path) => vec![(path.to_string(), patch.to_string())],
+        None => match split_multi_file_patch(patch) {
+            Ok(files) => files.into_iter().map(|f| (f.path, f.patch)).collect(),
+            Err(e) => return TargetResult::failed(kind, e, docker),
+        },
+    };
+    let mut summaries = Vec::with_capacity(files.len());
+    let mut bytes = 0u64;
+    for (path, patch) in &files {
+        let current = match read_current(workdir, path, "apply_patch").await {
+            Ok(current) => current,
+            Err(reason) => return TargetResult::failed(kind, reason, docker),
+        };
+        let original = current.unwrap_or_default();
+        let normalized = normalize_patch_for_diffy(patch, path);
+        let (patched, warnings) = match apply_patch_lenient(&original, &normalized) {
+            Ok(p) => p,
+            Err(e) => {
+                return TargetResult::failed(
+                    kind,
+                    format!("apply_patch failed for {path}: {e}"),
+                    docker,
+                )
+            }
+        };
+        bytes += patched.len() as u64;
+        summaries.push(json!({
+            "path": path,
+            "changed": patched != original,
+            "bytes_written": patched.len(),
+            "warnings": warnings,
+            "diff": unified_diff(path, &original, &patched),
+        }));
+    }
+    let content = if path.is_some() {
+        let mut file = summaries.remove(0);
+        file["dry_run"] = Value::Bool(true);
+        file
+    } else {
+        let changed = summaries.iter().any(|f| f["changed"] == json!(true));
+        let diff = summaries
+            .iter()
+            .filter_map(|f| f["diff"].as_str())
+            .collect::<String>();
+        json!({"files": summaries, "changed": changed, "dry_run": true, "diff": diff})
+    };
+    dry_run_result(kind, docker, content.to_string(), bytes)
+}
+
+/// Current text of a workdir file; `None` when it does not exist yet.
+async fn read_current(workdir: &Path, path: &str, tool: &str) -> Result<Option<String>, String> {
+    let full = resolve_path_scoped(workdir, path).map_err(|_| {
+        format!("{tool} path must stay within workdir (no absolute paths or '..' traversal)")
+    })?;
+    match tokio::fs::read(&full).await {
+        Ok(bytes) => Ok(Some(String::from_utf8_lossy(&bytes).to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("{tool} dry run failed for {}: {e}", full.display())),
+    }
+}
+
+/// `diffy` output with `a/<path>`/`b/<path>` headers; empty when unchanged.
+fn unified_diff(path: &str, original: &str, modified: &str) -> String {
+    if original == modified {
+        return String::new();
+    }
+    let diff = diffy::create_patch(original, modified).to_string();
+    let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+    for line in diff
+        .lines()
+        .skip_while(|l| l.starts_with("--- ") || l.starts_with("+++ "))
+    {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+fn dry_run_result(
+    kind: ExecTargetKind,
+    docker: Option<DockerMeta>,
+    content: String,
+    bytes: u64,
+) -> TargetResult {
+    TargetResult {
+        ok: true,
+        content,
+        truncated: false,
+        bytes: Some(bytes),
+        exit_code: None,
+        stderr_truncated: None,
+        stdout_truncated: None,
+        execution_target: kind,
+        docker,
+        write_protection: None,
+        cwd: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use tempfile::tempdir;
+
+    use super::{dry_run_patch, dry_run_write};
+    use crate::target::ExecTargetKind;
+
+    #[tokio::test]
+    async fn dry_run_write_reports_diff_and_leaves_disk_untouched() {
+        let tmp = tempdir().expect("tmp");
+        std::fs::write(tmp.path().join("a.txt"), "one\ntwo\n").expect("write");
+        let out = dry_run_write(
+            ExecTargetKind::Host,
+            None,
+            tmp.path(),
+            "a.txt",
+            "one\nTWO\n",
+        )
+        .await;
+        assert!(out.ok, "{}", out.content);
+        let v: Value = serde_json::from_str(&out.content).expect("json");
+        assert_eq!(v["dry_run"], true);
+        assert_eq!(v["bytes_written"], 8);
+        assert_eq!(
+            v["diff"],
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+TWO\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).expect("read"),
+            "one\ntwo\n"
+        );
+
+        let new_file =
+            dry_run_write(ExecTargetKind::Host, None, tmp.path(), "sub/new.txt", "x\n").await;
+        let v: Value = serde_json::from_str(&new_file.content).expect("json");
+        assert_eq!(v["changed"], true);
+        assert!(v["diff"]
+            .as_str()
+            .expect("diff")
+            .ends_with("@@ -0,0 +1 @@\n+x\n"));
+        assert!(!tmp.path().join("sub").exists());
+    }
+
+    #[tokio::test]
+    async fn dry_run_patch_applies_in_memory_only() {
+        let tmp = tempdir().expect("tmp");
+        std::fs::write(tmp.path().join("a.txt"), "one\ntwo\n").expect("write");
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n";
+        let out = dry_run_patch(ExecTargetKind::Host, None, tmp.path(), None, patch).await;
+        assert!(out.ok, "{}", out.content);
+        let v: Value = serde_json::from_str(&out.content).expect("json");
+        assert_eq!(v["dry_run"], true);
+        assert_eq!(v["files"][0]["changed"], true);
+        assert_eq!(v["diff"], patch);
+        assert_eq!(out.bytes, Some(6));
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).expect("read"),
+            "one\ntwo\n"
+        );
+
+        let bad = dry_run_patch(
+            ExecTargetKind::Host,
+            None,
+            tmp.path(),
+            Some("a.txt"),
+            "@@ -1 +1 @@\n-missing\n+x\n",
+        )
+        .await;
+        assert!(!bad.ok);
+    }
+}
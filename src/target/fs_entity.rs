@@ -0,0 +1,249 @@
+use std::path::{Component, Path};
+
+use serde_json::json;
+
+use super::{shell_escape, TargetResult};
+
+/// Marker line the docker probe prints to stderr before exiting with
+/// `DOCKER_PROBE_EXIT_CODE`: `<marker> <code> <entity> [<ancestor>]`.
+const DOCKER_PROBE_MARKER: &str = "__LOCALAGENT_FS_ENTITY__";
+const DOCKER_PROBE_EXIT_CODE: i32 = 66;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FsOp {
+    ReadFile,
+    ListDir,
+}
+
+/// Stable entity-type failures for `read_file` / `list_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsEntityErrorKind {
+    IsDirectory,
+    NotADirectory,
+    NotFound,
+    SpecialFile,
+}
+
+impl FsEntityErrorKind {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::IsDirectory => "E_IS_DIRECTORY",
+            Self::NotADirectory => "E_NOT_A_DIRECTORY",
+            Self::NotFound => "E_NOT_FOUND",
+            Self::SpecialFile => "E_SPECIAL_FILE",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "E_IS_DIRECTORY" => Some(Self::IsDirectory),
+            "E_NOT_A_DIRECTORY" => Some(Self::NotADirectory),
+            "E_NOT_FOUND" => Some(Self::NotFound),
+            "E_SPECIAL_FILE" => Some(Self::SpecialFile),
+            _ => None,
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            Self::IsDirectory => "path is a directory; use list_dir to see its entries",
+            Self::NotADirectory => "path is not a directory; use read_file for regular files",
+            Self::NotFound => {
+                "path does not exist; list nearest_existing_ancestor to find the right name"
+            }
+            Self::SpecialFile => {
+                "path is a socket, device, or fifo; only regular files can be read"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FsEntityError {
+    kind: FsEntityErrorKind,
+    entity: Option<String>,
+    path: String,
+    resolved_path: String,
+    nearest_existing_ancestor: Option<String>,
+    detail: Option<String>,
+}
+
+impl FsEntityError {
+    /// JSON failure content; `error` carries the stable code that
+    /// `FsEntityErrorKind::from_code` parses back.
+    pub(crate) fn to_content(&self) -> String {
+        let mut v = json!({
+            "error": self.kind.code(),
+            "path": self.path,
+            "resolved_path": self.resolved_path,
+            "hint": self.kind.hint(),
+        });
+        if let Some(entity) = &self.entity {
+            v["entity"] = json!(entity);
+        }
+        if let Some(ancestor) = &self.nearest_existing_ancestor {
+            v["nearest_existing_ancestor"] = json!(ancestor);
+        }
+        if let Some(detail) = &self.detail {
+            v["detail"] = json!(detail);
+        }
+        v.to_string()
+    }
+}
+
+/// Stats `full` (following symlinks) and rejects the wrong entity kind for
+/// `op` before the real read or listing runs.
+pub(crate) async fn host_probe(
+    op: FsOp,
+    workdir: &Path,
+    rel: &str,
+    full: &Path,
+) -> Result<(), FsEntityError> {
+    let error = |kind, entity: Option<&str>, ancestor, detail| FsEntityError {
+        kind,
+        entity: entity.map(str::to_string),
+        path: rel.to_string(),
+        resolved_path: full.display().to_string(),
+        nearest_existing_ancestor: ancestor,
+        detail,
+    };
+    let meta = match tokio::fs::metadata(full).await {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(error(
+                FsEntityErrorKind::NotFound,
+                None,
+                Some(host_nearest_existing_ancestor(workdir, rel)),
+                Some(e.to_string()),
+            ));
+        }
+        // Permission and other OS errors are left to the real operation.
+        Err(_) => return Ok(()),
+    };
+    let entity = host_entity_name(&meta);
+    match op {
+        FsOp::ReadFile if meta.is_dir() => Err(error(
+            FsEntityErrorKind::IsDirectory,
+            Some(entity),
+            None,
+            None,
+        )),
+        FsOp::ReadFile if !meta.is_file() => Err(error(
+            FsEntityErrorKind::SpecialFile,
+            Some(entity),
+            None,
+            None,
+        )),
+        FsOp::ListDir if !meta.is_dir() => Err(error(
+            FsEntityErrorKind::NotADirectory,
+            Some(entity),
+            None,
+            None,
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn host_entity_name(meta: &std::fs::Metadata) -> &'static str {
+    let ft = meta.file_type();
+    if ft.is_dir() {
+        return "directory";
+    }
+    if ft.is_file() {
+        return "file";
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if ft.is_socket() {
+            return "socket";
+        }
+        if ft.is_char_device() {
+            return "char_device";
+        }
+        if ft.is_block_device() {
+            return "block_device";
+        }
+        if ft.is_fifo() {
+            return "fifo";
+        }
+    }
+    "special"
+}
+
+/// Deepest existing directory on the way to `rel`, workdir-relative (`.` for
+/// the workdir itself).
+fn host_nearest_existing_ancestor(workdir: &Path, rel: &str) -> String {
+    let parts = Path::new(rel)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for len in (1..parts.len()).rev() {
+        let candidate = parts[..len].join("/");
+        if workdir.join(&candidate).is_dir() {
+            return candidate;
+        }
+    }
+    ".".to_string()
+}
+
+/// Shell prelude for docker reads/listings: prints the probe marker and exits
+/// with `DOCKER_PROBE_EXIT_CODE` when the path is the wrong kind of entity.
+pub(crate) fn docker_probe_script(op: FsOp, rel: &str) -> String {
+    let p = shell_escape(rel);
+    let entity = "if [ -d \"$p\" ]; then e=directory; elif [ -f \"$p\" ]; then e=file; elif [ -S \"$p\" ]; then e=socket; elif [ -c \"$p\" ]; then e=char_device; elif [ -b \"$p\" ]; then e=block_device; elif [ -p \"$p\" ]; then e=fifo; else e=special; fi";
+    let missing = format!(
+        "if [ ! -e \"$p\" ]; then a=$(dirname \"$p\"); while [ \"$a\" != \".\" ] && [ \"$a\" != \"/\" ] && [ ! -d \"$a\" ]; do a=$(dirname \"$a\"); done; echo \"{DOCKER_PROBE_MARKER} E_NOT_FOUND - $a\" >&2; exit {DOCKER_PROBE_EXIT_CODE}; fi"
+    );
+    let wrong_kind = match op {
+        FsOp::ReadFile => format!(
+            "if [ -d \"$p\" ]; then echo \"{DOCKER_PROBE_MARKER} E_IS_DIRECTORY directory\" >&2; exit {DOCKER_PROBE_EXIT_CODE}; fi; if [ ! -f \"$p\" ]; then {entity}; echo \"{DOCKER_PROBE_MARKER} E_SPECIAL_FILE $e\" >&2; exit {DOCKER_PROBE_EXIT_CODE}; fi"
+        ),
+        FsOp::ListDir => format!(
+            "if [ ! -d \"$p\" ]; then {entity}; echo \"{DOCKER_PROBE_MARKER} E_NOT_A_DIRECTORY $e\" >&2; exit {DOCKER_PROBE_EXIT_CODE}; fi"
+        ),
+    };
+    format!("p={p}; {missing}; {wrong_kind}")
+}
+
+/// Parses the probe marker out of a docker run result, if the probe fired.
+pub(crate) fn docker_probe_error(
+    _op: FsOp,
+    rel: &str,
+    container_workdir: &str,
+    out: &TargetResult,
+) -> Option<FsEntityError> {
+    if out.ok || out.exit_code != Some(DOCKER_PROBE_EXIT_CODE) {
+        return None;
+    }
+    let parsed = serde_json::from_str::<serde_json::Value>(&out.content).ok()?;
+    let stderr = parsed.get("stderr")?.as_str()?;
+    let line = stderr
+        .lines()
+        .find_map(|l| l.trim().strip_prefix(DOCKER_PROBE_MARKER))?;
+    let mut fields = line.split_whitespace();
+    let kind = FsEntityErrorKind::from_code(fields.next()?)?;
+    let entity = fields.next().filter(|e| *e != "-").map(str::to_string);
+    let nearest_existing_ancestor = match kind {
+        FsEntityErrorKind::NotFound => Some(fields.next().unwrap_or(".").to_string()),
+        _ => None,
+    };
+    Some(FsEntityError {
+        kind,
+        entity,
+        path: rel.to_string(),
+        resolved_path: format!(
+            "{}/{}",
+            container_workdir.trim_end_matches('/'),
+            rel.trim_start_matches("./")
+        ),
+        nearest_existing_ancestor,
+        detail: match kind {
+            FsEntityErrorKind::NotFound => Some("No such file or directory".to_string()),
+            _ => None,
+        },
+    })
+}
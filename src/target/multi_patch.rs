@@ -0,0 +1,656 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use super::{
+    apply_patch_lenient, normalize_patch_for_diffy, parse_docker_patch_checksum,
+    path_is_workdir_scoped, pinned_host_write, resolve_path_scoped, shell_escape, ExecTargetKind,
+    TargetResult, DOCKER_PATCH_AFTER_MARKER, DOCKER_PATCH_BEFORE_MARKER,
+};
+
+const DOCKER_PATCH_FILE_MARKER: &str = "LOCALAGENT_PATCH_FILE";
+const DOCKER_PATCH_FAILED_MARKER: &str = "LOCALAGENT_PATCH_FAILED";
+/// Files a failed `patch` run may leave next to its target (shell variable
+/// prefix, suffix); rollback removes them unless they existed before.
+const PATCH_LEFTOVERS: [(&str, &str); 2] = [("newrej", ".rej"), ("neworig", ".orig")];
+
+/// One file's section of a multi-file unified diff, headers included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FilePatch {
+    pub(crate) path: String,
+    pub(crate) patch: String,
+    pub(crate) hunks: usize,
+}
+
+/// Path from a `---`/`+++` header: timestamps after a tab are dropped, as is
+/// the `a/`/`b/` prefix git adds.
+fn header_path(rest: &str) -> String {
+    let path = rest.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" {
+        return path.to_string();
+    }
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// Old and new line counts of a `@@ -a,b +c,d @@` header; `None` when the
+/// header is malformed, in which case the hunk body is taken leniently.
+fn hunk_counts(line: &str) -> Option<(usize, usize)> {
+    let mut parts = line.strip_prefix("@@ ")?.split_whitespace();
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, n)) => n.parse().ok(),
+        None => range.parse::<usize>().ok().map(|_| 1),
+    };
+    let old = count(parts.next()?.strip_prefix('-')?)?;
+    let new = count(parts.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// Splits a unified diff with `--- a/...`/`+++ b/...` headers into one patch
+/// per file. Hunk line counts are followed so a removed line starting with
+/// `-- ` is not mistaken for the next file header. New files (`--- /dev/null`)
+/// are accepted; deletions and renames are not.
+pub(crate) fn split_multi_file_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines = patch.lines().collect::<Vec<_>>();
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut old_left = 0usize;
+    let mut new_left = 0usize;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if old_left > 0 || new_left > 0 {
+            if let Some(file) = files.last_mut() {
+                file.patch.push_str(line);
+                file.patch.push('\n');
+            }
+            match line.chars().next() {
+                Some('-') => old_left = old_left.saturating_sub(1),
+                Some('+') => new_left = new_left.saturating_sub(1),
+                Some('\\') => {}
+                _ => {
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                }
+            }
+            continue;
+        }
+        if let (Some(old), Some(new)) = (
+            line.strip_prefix("--- "),
+            lines.get(i).and_then(|next| next.strip_prefix("+++ ")),
+        ) {
+            let (old, new) = (header_path(old), header_path(new));
+            if new == "/dev/null" {
+                return Err(format!(
+                    "invalid patch: deleting {old} is not supported by apply_patch"
+                ));
+            }
+            if old != "/dev/null" && old != new {
+                return Err(format!(
+                    "invalid patch: renaming {old} to {new} is not supported by apply_patch"
+                ));
+            }
+            if files.iter().any(|f| f.path == new) {
+                return Err(format!("invalid patch: {new} appears more than once"));
+            }
+            files.push(FilePatch {
+                path: new,
+                patch: format!("{line}\n{}\n", lines[i]),
+                hunks: 0,
+            });
+            i += 1;
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            // Preamble before the first header (`diff --git`, `index`, prose).
+            continue;
+        };
+        if line.starts_with("@@") {
+            file.hunks += 1;
+            (old_left, new_left) = hunk_counts(line).unwrap_or((0, 0));
+        } else if !matches!(line.chars().next(), Some(' ' | '-' | '+' | '\\')) {
+            // Git metadata between files (`diff --git`, `index`, modes).
+            continue;
+        }
+        file.patch.push_str(line);
+        file.patch.push('\n');
+    }
+    if files.is_empty() {
+        return Err("invalid patch: no '--- '/'+++ ' file headers found".to_string());
+    }
+    if let Some(file) = files.iter().find(|f| f.hunks == 0) {
+        return Err(format!("invalid patch: no hunks for {}", file.path));
+    }
+    Ok(files)
+}
+
+fn summary_content(files: Vec<Value>) -> String {
+    let changed = files.iter().any(|f| f["changed"] == json!(true));
+    json!({"files": files, "changed": changed}).to_string()
+}
+
+fn unscoped_path_error(
+    kind: ExecTargetKind,
+    path: &str,
+    docker: Option<super::DockerMeta>,
+) -> TargetResult {
+    TargetResult::failed(
+        kind,
+        format!(
+            "apply_patch path must stay within workdir (no absolute paths or '..' traversal): {path}"
+        ),
+        docker,
+    )
+}
+
+/// Applies every file's hunks in memory first and writes only when all of
+/// them apply. A failed write restores the files already written.
+pub(super) async fn host_apply_multi_file_patch(workdir: &Path, patch: &str) -> TargetResult {
+    let kind = ExecTargetKind::Host;
+    let files = match split_multi_file_patch(patch) {
+        Ok(files) => files,
+        Err(e) => return TargetResult::failed(kind, e, None),
+    };
+    let mut planned = Vec::with_capacity(files.len());
+    for file in &files {
+        let full = match resolve_path_scoped(workdir, &file.path) {
+            Ok(full) => full,
+            Err(_) => return unscoped_path_error(kind, &file.path, None),
+        };
+        let original = match tokio::fs::read(&full).await {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return TargetResult::failed(
+                    kind,
+                    format!("apply_patch failed for {}: {e}", file.path),
+                    None,
+                )
+            }
+        };
+        let text = String::from_utf8_lossy(original.as_deref().unwrap_or_default()).to_string();
+        let normalized = normalize_patch_for_diffy(&file.patch, &file.path);
+        let (patched, warnings) = match apply_patch_lenient(&text, &normalized) {
+            Ok(p) => p,
+            Err(e) => {
+                return TargetResult::failed(
+                    kind,
+                    format!(
+                        "apply_patch failed for {}: {e}; no files were changed",
+                        file.path
+                    ),
+                    None,
+                )
+            }
+        };
+        planned.push((file, full, original, text, patched, warnings));
+    }
+
+    let mut written: Vec<(&str, &Path, Option<&Vec<u8>>)> = Vec::new();
+    let mut summaries = Vec::with_capacity(planned.len());
+    let mut protection = None;
+    for (file, full, original, text, patched, warnings) in &planned {
+        match pinned_host_write(workdir, &file.path, patched.clone().into_bytes(), true).await {
+            Ok(p) => protection = Some(p),
+            Err(e) => {
+                let restore_errors = host_rollback(workdir, &written).await;
+                return TargetResult::failed(
+                    kind,
+                    format!(
+                        "apply_patch failed for {}: {e}; {}",
+                        file.path,
+                        rollback_note(written.len(), &restore_errors)
+                    ),
+                    None,
+                );
+            }
+        }
+        written.push((&file.path, full, original.as_ref()));
+        summaries.push(json!({
+            "path": file.path,
+            "changed": patched != text || original.is_none(),
+            "hunks_applied": file.hunks,
+            "bytes_written": patched.len(),
+            "warnings": warnings
+        }));
+    }
+    let bytes_written = planned.iter().map(|p| p.4.len() as u64).sum();
+    TargetResult {
+        ok: true,
+        content: summary_content(summaries),
+        truncated: false,
+        bytes: Some(bytes_written),
+        exit_code: None,
+        stderr_truncated: None,
+        stdout_truncated: None,
+        execution_target: kind,
+        docker: None,
+        write_protection: protection,
+        cwd: None,
+    }
+}
+
+/// Puts back the original bytes of each written file, removing files the
+/// patch created. Returns the paths that could not be restored.
+async fn host_rollback(workdir: &Path, written: &[(&str, &Path, Option<&Vec<u8>>)]) -> Vec<String> {
+    let mut failed = Vec::new();
+    for (path, full, original) in written.iter().rev() {
+        let restored = match original {
+            Some(bytes) => pinned_host_write(workdir, path, bytes.to_vec(), true)
+                .await
+                .map(|_| ()),
+            None => tokio::fs::remove_file(full).await.map_err(Into::into),
+        };
+        if restored.is_err() {
+            failed.push(path.to_string());
+        }
+    }
+    failed
+}
+
+fn rollback_note(written: usize, restore_errors: &[String]) -> String {
+    if restore_errors.is_empty() {
+        format!("rolled back {written} already-written file(s); no files were changed")
+    } else {
+        format!("rollback could not restore: {}", restore_errors.join(", "))
+    }
+}
+
+/// Container script for a multi-file patch: backs up every target, patches
+/// them in order, and on the first failure restores the backups (removing
+/// files the patch created) before exiting 1. `cksum` markers before and
+/// after give each file's change status and final size.
+pub(super) fn docker_multi_patch_script(files: &[FilePatch]) -> String {
+    multi_patch_script(files, false)
+}
+
+/// `docker_multi_patch_script` reading the patches from stdin (see
+/// `stdin_multi_patch_input`) instead of heredocs, for targets where the
+/// script travels as a remote command line. Each file's patch is staged in
+/// the temp dir by byte range before anything is patched.
+pub(super) fn stdin_multi_patch_script(files: &[FilePatch]) -> String {
+    multi_patch_script(files, true)
+}
+
+/// Stdin for `stdin_multi_patch_script`: every file's patch, in order.
+pub(super) fn stdin_multi_patch_input(files: &[FilePatch]) -> Vec<u8> {
+    files.iter().flat_map(|file| file.patch.bytes()).collect()
+}
+
+fn multi_patch_script(files: &[FilePatch], from_stdin: bool) -> String {
+    let sum = |p: &str| format!("$({{ cksum < {p}; }} 2>/dev/null || echo missing)");
+    let mut script = String::from("dir=$(mktemp -d) || exit 2\nfailed=\n");
+    if from_stdin {
+        script.push_str("cat > \"$dir/patches\" || exit 2\n");
+        let mut offset = 0;
+        for (i, file) in files.iter().enumerate() {
+            script.push_str(&format!(
+                "tail -c +{} \"$dir/patches\" | head -c {} > \"$dir/patch{i}\" || exit 2\n",
+                offset + 1,
+                file.patch.len()
+            ));
+            offset += file.patch.len();
+        }
+    }
+    for (i, file) in files.iter().enumerate() {
+        let p = shell_escape(&file.path);
+        script.push_str(&format!(
+            "if [ -e {p} ]; then cp -p -- {p} \"$dir/{i}\" || exit 2; fi\n"
+        ));
+        for (var, suffix) in PATCH_LEFTOVERS {
+            let leftover = shell_escape(&format!("{}{suffix}", file.path));
+            script.push_str(&format!("[ -e {leftover} ] || {var}{i}=1\n"));
+        }
+        script.push_str(&format!(
+            "echo \"{DOCKER_PATCH_BEFORE_MARKER} {i} {}\"\n",
+            sum(&p)
+        ));
+    }
+    for (i, file) in files.iter().enumerate() {
+        let p = shell_escape(&file.path);
+        let apply = if from_stdin {
+            format!("patch -u {p} < \"$dir/patch{i}\" || failed={i}")
+        } else {
+            format!(
+                "patch -u {p} <<'OPENAGENT_PATCH_{i}' || failed={i}\n{}\nOPENAGENT_PATCH_{i}",
+                file.patch.trim_end_matches('\n')
+            )
+        };
+        script.push_str(&format!(
+            "if [ -z \"$failed\" ]; then\necho \"{DOCKER_PATCH_FILE_MARKER} {i}\"\n{apply}\nfi\n"
+        ));
+    }
+    script.push_str("if [ -n \"$failed\" ]; then\n");
+    for (i, file) in files.iter().enumerate() {
+        let p = shell_escape(&file.path);
+        let mut cleanup = String::new();
+        for (var, suffix) in PATCH_LEFTOVERS {
+            let leftover = shell_escape(&format!("{}{suffix}", file.path));
+            cleanup.push_str(&format!(
+                " [ -z \"${{{var}{i}:-}}\" ] || rm -f -- {leftover};"
+            ));
+        }
+        script.push_str(&format!(
+            "if [ \"$failed\" -ge {i} ]; then if [ -e \"$dir/{i}\" ]; then cp -p -- \"$dir/{i}\" {p}; else rm -f -- {p}; fi;{cleanup} fi\n"
+        ));
+    }
+    script.push_str(&format!(
+        "rm -rf \"$dir\"\necho \"{DOCKER_PATCH_FAILED_MARKER} $failed\"\nexit 1\nfi\n"
+    ));
+    for (i, file) in files.iter().enumerate() {
+        let p = shell_escape(&file.path);
+        script.push_str(&format!(
+            "echo \"{DOCKER_PATCH_AFTER_MARKER} {i} {}\"\n",
+            sum(&p)
+        ));
+    }
+    script.push_str("rm -rf \"$dir\"\nexit 0");
+    script
+}
+
+/// Turns `docker_multi_patch_script` output into the host envelope.
+pub(super) fn docker_multi_patch_result(
+    files: &[FilePatch],
+    mut out: TargetResult,
+) -> TargetResult {
+    let Ok(parsed) = serde_json::from_str::<Value>(&out.content) else {
+        return out;
+    };
+    let stdout = parsed.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
+    let stderr = parsed.get("stderr").and_then(|v| v.as_str()).unwrap_or("");
+    let mut before = vec![None; files.len()];
+    let mut after = vec![None; files.len()];
+    let mut reports = vec![Vec::new(); files.len()];
+    let mut failed = None;
+    let mut current = None;
+    let indexed = |rest: &str| {
+        let (idx, rest) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+        idx.parse::<usize>()
+            .ok()
+            .filter(|idx| *idx < files.len())
+            .map(|idx| (idx, rest.to_string()))
+    };
+    for line in stdout.lines() {
+        if let Some((idx, sum)) = line
+            .strip_prefix(DOCKER_PATCH_BEFORE_MARKER)
+            .and_then(indexed)
+        {
+            before[idx] = Some(parse_docker_patch_checksum(&sum));
+        } else if let Some((idx, sum)) = line
+            .strip_prefix(DOCKER_PATCH_AFTER_MARKER)
+            .and_then(indexed)
+        {
+            after[idx] = Some(parse_docker_patch_checksum(&sum));
+        } else if let Some((idx, _)) = line
+            .strip_prefix(DOCKER_PATCH_FILE_MARKER)
+            .and_then(indexed)
+        {
+            current = Some(idx);
+        } else if let Some((idx, _)) = line
+            .strip_prefix(DOCKER_PATCH_FAILED_MARKER)
+            .and_then(indexed)
+        {
+            failed = Some(idx);
+        } else if let (Some(idx), false) = (current, line.trim().is_empty()) {
+            reports[idx].push(line.trim_end().to_string());
+        }
+    }
+    out.truncated = false;
+    if !out.ok {
+        let mut lines = vec![match failed {
+            Some(idx) => format!(
+                "apply_patch failed for {}: hunks did not apply; no files were changed",
+                files[idx].path
+            ),
+            None => "apply_patch failed".to_string(),
+        }];
+        lines.extend(failed.map(|idx| reports[idx].clone()).unwrap_or_default());
+        lines.extend(
+            stderr
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.trim_end().to_string()),
+        );
+        out.content = lines.join("\n");
+        out.bytes = None;
+        return out;
+    }
+    let mut summaries = Vec::with_capacity(files.len());
+    let mut bytes_written = 0;
+    for (idx, file) in files.iter().enumerate() {
+        let Some(Some(after_sum)) = &after[idx] else {
+            out.ok = false;
+            out.content = format!(
+                "apply_patch failed for {}: patched file could not be read back",
+                file.path
+            );
+            out.bytes = None;
+            return out;
+        };
+        let warnings = reports[idx]
+            .iter()
+            .filter(|line| line.contains(" with fuzz ") || line.contains(" (offset "))
+            .cloned()
+            .collect::<Vec<_>>();
+        bytes_written += after_sum.size;
+        summaries.push(json!({
+            "path": file.path,
+            "changed": before[idx].as_ref() != Some(&Some(after_sum.clone())),
+            "hunks_applied": file.hunks,
+            "bytes_written": after_sum.size,
+            "warnings": warnings
+        }));
+    }
+    out.content = summary_content(summaries);
+    out.bytes = Some(bytes_written);
+    out
+}
+
+/// Checks every header path before the docker or ssh script runs.
+pub(super) fn first_unscoped_path(
+    kind: ExecTargetKind,
+    files: &[FilePatch],
+    docker: Option<&super::DockerMeta>,
+) -> Option<TargetResult> {
+    files
+        .iter()
+        .find(|f| !path_is_workdir_scoped(&f.path))
+        .map(|f| unscoped_path_error(kind, &f.path, docker.cloned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        docker_multi_patch_result, docker_multi_patch_script, host_apply_multi_file_patch,
+        split_multi_file_patch,
+    };
+    use crate::target::ExecTargetKind;
+
+    const TWO_FILES: &str = concat!(
+        "diff --git a/a.txt b/a.txt\n",
+        "index 1111111..2222222 100644\n",
+        "--- a/a.txt\n",
+        "+++ b/a.txt\n",
+        "@@ -1,2 +1,2 @@\n",
+        "--- old\n",
+        "+new\n",
+        " keep\n",
+        "--- a/b.txt\n",
+        "+++ b/b.txt\n",
+        "@@ -1 +1 @@\n",
+        "-x\n",
+        "+y\n",
+    );
+
+    #[test]
+    fn split_follows_hunk_counts_across_file_headers() {
+        let files = split_multi_file_patch(TWO_FILES).expect("split");
+        let paths = files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+        assert_eq!(
+            files[0].patch,
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n--- old\n+new\n keep\n"
+        );
+        assert_eq!(files[1].hunks, 1);
+    }
+
+    #[test]
+    fn split_rejects_deletes_renames_and_headerless_patches() {
+        let err = split_multi_file_patch("--- a/x\n+++ /dev/null\n@@ -1 +0,0 @@\n-x\n")
+            .expect_err("delete");
+        assert!(err.contains("deleting x"), "{err}");
+        let err =
+            split_multi_file_patch("--- a/x\n+++ b/y\n@@ -1 +1 @@\n-x\n+y\n").expect_err("rename");
+        assert!(err.contains("renaming x to y"), "{err}");
+        let err = split_multi_file_patch("@@ -1 +1 @@\n-x\n+y\n").expect_err("no headers");
+        assert!(err.contains("file headers"), "{err}");
+
+        let files = split_multi_file_patch("--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+hi\n")
+            .expect("new file");
+        assert_eq!(files[0].path, "new.txt");
+    }
+
+    #[tokio::test]
+    async fn host_applies_all_files_and_reports_each() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("a.txt"), "-- old\nkeep\n").expect("a");
+        std::fs::write(tmp.path().join("b.txt"), "x\n").expect("b");
+        let out = host_apply_multi_file_patch(tmp.path(), TWO_FILES).await;
+        assert!(out.ok, "{}", out.content);
+        let v: serde_json::Value = serde_json::from_str(&out.content).expect("json");
+        assert_eq!(v["changed"], true);
+        assert_eq!(v["files"][0]["path"], "a.txt");
+        assert_eq!(v["files"][0]["hunks_applied"], 1);
+        assert_eq!(v["files"][1]["changed"], true);
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).expect("a"),
+            "new\nkeep\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("b.txt")).expect("b"),
+            "y\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn host_writes_nothing_when_any_file_fails() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("a.txt"), "-- old\nkeep\n").expect("a");
+        std::fs::write(tmp.path().join("b.txt"), "unrelated\n").expect("b");
+        let out = host_apply_multi_file_patch(tmp.path(), TWO_FILES).await;
+        assert!(!out.ok);
+        assert!(out.content.contains("b.txt"), "{}", out.content);
+        assert!(out.content.contains("no files were changed"));
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).expect("a"),
+            "-- old\nkeep\n"
+        );
+
+        let out = host_apply_multi_file_patch(
+            tmp.path(),
+            "--- a/../x.txt\n+++ b/../x.txt\n@@ -1 +1 @@\n-x\n+y\n",
+        )
+        .await;
+        assert!(!out.ok);
+        assert!(out.content.contains("must stay within workdir"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn host_rolls_back_written_files_when_a_later_write_fails() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("a.txt"), "-- old\nkeep\n").expect("a");
+        std::fs::write(tmp.path().join("target.txt"), "x\n").expect("target");
+        // Writes never follow symlinks, so patching b.txt fails after a.txt
+        // was written.
+        std::os::unix::fs::symlink(tmp.path().join("target.txt"), tmp.path().join("b.txt"))
+            .expect("symlink");
+        let out = host_apply_multi_file_patch(tmp.path(), TWO_FILES).await;
+        assert!(!out.ok, "{}", out.content);
+        assert!(
+            out.content
+                .contains("rolled back 1 already-written file(s)"),
+            "{}",
+            out.content
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).expect("a"),
+            "-- old\nkeep\n"
+        );
+    }
+
+    fn docker_output(exit: i32, stdout: &str) -> crate::target::TargetResult {
+        crate::target::TargetResult {
+            ok: exit == 0,
+            content: serde_json::json!({"status": exit, "stdout": stdout, "stderr": ""})
+                .to_string(),
+            truncated: false,
+            bytes: Some(stdout.len() as u64),
+            exit_code: Some(exit),
+            stderr_truncated: Some(false),
+            stdout_truncated: Some(false),
+            execution_target: ExecTargetKind::Docker,
+            docker: None,
+            write_protection: None,
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn docker_script_backs_up_patches_in_order_and_restores_on_failure() {
+        let files = split_multi_file_patch(TWO_FILES).expect("split");
+        let script = docker_multi_patch_script(&files);
+        let a = script
+            .find("patch -u 'a.txt' <<'OPENAGENT_PATCH_0'")
+            .expect("a");
+        let b = script
+            .find("patch -u 'b.txt' <<'OPENAGENT_PATCH_1'")
+            .expect("b");
+        assert!(a < b);
+        assert!(script.contains("cp -p -- 'a.txt' \"$dir/0\""));
+        assert!(script.contains(
+            "if [ \"$failed\" -ge 1 ]; then if [ -e \"$dir/1\" ]; then cp -p -- \"$dir/1\" 'b.txt'; else rm -f -- 'b.txt'; fi"
+        ));
+    }
+
+    #[test]
+    fn docker_result_matches_host_summary_shape() {
+        let files = split_multi_file_patch(TWO_FILES).expect("split");
+        let stdout = "LOCALAGENT_PATCH_BEFORE 0 111 12\n\
+LOCALAGENT_PATCH_BEFORE 1 222 2\n\
+LOCALAGENT_PATCH_FILE 0\n\
+patching file a.txt\n\
+LOCALAGENT_PATCH_FILE 1\n\
+patching file b.txt\n\
+Hunk #1 succeeded at 3 (offset 2 lines).\n\
+LOCALAGENT_PATCH_AFTER 0 333 9\n\
+LOCALAGENT_PATCH_AFTER 1 444 2\n";
+        let out = docker_multi_patch_result(&files, docker_output(0, stdout));
+        assert!(out.ok);
+        assert_eq!(out.bytes, Some(11));
+        let v: serde_json::Value = serde_json::from_str(&out.content).expect("json");
+        assert_eq!(
+            v,
+            serde_json::json!({
+                "files": [
+                    {"path": "a.txt", "changed": true, "hunks_applied": 1, "bytes_written": 9, "warnings": []},
+                    {"path": "b.txt", "changed": true, "hunks_applied": 1, "bytes_written": 2,
+                     "warnings": ["Hunk #1 succeeded at 3 (offset 2 lines)."]}
+                ],
+                "changed": true
+            })
+        );
+
+        let stdout = "LOCALAGENT_PATCH_FILE 0\npatching file a.txt\nLOCALAGENT_PATCH_FILE 1\n\
+patching file b.txt\nHunk #1 FAILED at 1.\nLOCALAGENT_PATCH_FAILED 1\n";
+        let out = docker_multi_patch_result(&files, docker_output(1, stdout));
+        assert!(!out.ok);
+        assert_eq!(
+            out.content,
+            "apply_patch failed for b.txt: hunks did not apply; no files were changed\n\
+patching file b.txt\nHunk #1 FAILED at 1."
+        );
+    }
+}
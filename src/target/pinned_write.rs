@@ -0,0 +1,256 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+/// How a host write guarded against a symlink swapped in between validation
+/// and the write itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(unix, allow(dead_code))]
+pub enum WriteProtection {
+    /// Every component re-opened relative to its parent with `O_NOFOLLOW`;
+    /// the write goes through the pinned handle.
+    NofollowDirfd,
+    /// Components checked for reparse points immediately before the open.
+    ReparsePointChecked,
+    /// Components checked with `symlink_metadata` before the open.
+    PathChecked,
+}
+
+/// A validated write target under a canonical workdir. On unix the parent
+/// directory stays open so the final open cannot be redirected.
+pub(crate) struct PinnedWrite {
+    file_name: String,
+    #[cfg(unix)]
+    parent: std::os::fd::OwnedFd,
+    #[cfg(not(unix))]
+    full: PathBuf,
+}
+
+fn split_relative(rel: &str) -> anyhow::Result<(Vec<String>, String)> {
+    let mut parts = Vec::new();
+    for component in Path::new(rel).components() {
+        match component {
+            Component::Normal(s) => parts.push(s.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            _ => {
+                return Err(anyhow!(
+                    "path must stay within workdir (no absolute paths or '..' traversal)"
+                ))
+            }
+        }
+    }
+    let file_name = parts
+        .pop()
+        .ok_or_else(|| anyhow!("write path must name a file"))?;
+    Ok((parts, file_name))
+}
+
+fn canonical_workdir(workdir: &Path) -> anyhow::Result<PathBuf> {
+    std::fs::canonicalize(workdir)
+        .map_err(|e| anyhow!("failed to resolve workdir {}: {e}", workdir.display()))
+}
+
+#[cfg(unix)]
+mod unix_impl {
+    use std::ffi::CString;
+    use std::io::Write;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    use anyhow::anyhow;
+
+    fn cstring(s: &str) -> anyhow::Result<CString> {
+        CString::new(s).map_err(|_| anyhow!("path component contains a NUL byte"))
+    }
+
+    fn last_error() -> std::io::Error {
+        std::io::Error::last_os_error()
+    }
+
+    fn is_symlink_refusal(err: &std::io::Error) -> bool {
+        matches!(err.raw_os_error(), Some(libc::ELOOP) | Some(libc::ENOTDIR))
+    }
+
+    pub(super) fn open_workdir(workdir: &Path) -> anyhow::Result<OwnedFd> {
+        let c = CString::new(workdir.as_os_str().as_bytes())
+            .map_err(|_| anyhow!("workdir contains a NUL byte"))?;
+        // SAFETY: `c` is a valid NUL-terminated path; the fd is owned below.
+        let fd = unsafe {
+            libc::open(
+                c.as_ptr(),
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(anyhow!(
+                "failed to open workdir {}: {}",
+                workdir.display(),
+                last_error()
+            ));
+        }
+        // SAFETY: `fd` was just returned by a successful open.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    /// Opens `name` under `dir` as a directory without following symlinks,
+    /// creating it first when `create` is set and it does not exist.
+    pub(super) fn open_child_dir(
+        dir: &OwnedFd,
+        name: &str,
+        create: bool,
+    ) -> anyhow::Result<OwnedFd> {
+        let c = cstring(name)?;
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+        for attempt in 0..2 {
+            // SAFETY: `dir` is an open directory fd and `c` a valid C string.
+            let fd = unsafe { libc::openat(dir.as_raw_fd(), c.as_ptr(), flags) };
+            if fd >= 0 {
+                // SAFETY: `fd` was just returned by a successful openat.
+                return Ok(unsafe { OwnedFd::from_raw_fd(fd) });
+            }
+            let err = last_error();
+            if is_symlink_refusal(&err) {
+                return Err(anyhow!(
+                    "refusing to write through symlink or non-directory component '{name}'"
+                ));
+            }
+            if err.kind() == std::io::ErrorKind::NotFound && create && attempt == 0 {
+                // SAFETY: same fd/string invariants as above.
+                let rc = unsafe { libc::mkdirat(dir.as_raw_fd(), c.as_ptr(), 0o755) };
+                if rc != 0 {
+                    let err = last_error();
+                    if err.kind() != std::io::ErrorKind::AlreadyExists {
+                        return Err(anyhow!("failed to create directory '{name}': {err}"));
+                    }
+                }
+                continue;
+            }
+            return Err(anyhow!("failed to open directory '{name}': {err}"));
+        }
+        Err(anyhow!("failed to open directory '{name}'"))
+    }
+
+    /// Truncates an existing regular file or creates a new one with `O_EXCL`,
+    /// never following a symlink at the final component.
+    pub(super) fn write_child_file(
+        dir: &OwnedFd,
+        name: &str,
+        content: &[u8],
+    ) -> anyhow::Result<()> {
+        let c = cstring(name)?;
+        let base = libc::O_WRONLY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+        // SAFETY: `dir` is an open directory fd and `c` a valid C string.
+        let mut fd = unsafe { libc::openat(dir.as_raw_fd(), c.as_ptr(), base | libc::O_TRUNC) };
+        if fd < 0 {
+            let err = last_error();
+            if err.raw_os_error() == Some(libc::ELOOP) {
+                return Err(anyhow!("refusing to write through symlink '{name}'"));
+            }
+            if err.kind() != std::io::ErrorKind::NotFound {
+                return Err(anyhow!("failed to open '{name}': {err}"));
+            }
+            // SAFETY: as above; mode is passed for O_CREAT.
+            fd = unsafe {
+                libc::openat(
+                    dir.as_raw_fd(),
+                    c.as_ptr(),
+                    base | libc::O_CREAT | libc::O_EXCL,
+                    0o644 as libc::c_uint,
+                )
+            };
+            if fd < 0 {
+                let err = last_error();
+                if err.kind() == std::io::ErrorKind::AlreadyExists {
+                    return Err(anyhow!(
+                        "refusing to write '{name}': it appeared during the write (possible symlink swap)"
+                    ));
+                }
+                return Err(anyhow!("failed to create '{name}': {err}"));
+            }
+        }
+        // SAFETY: `fd` was just returned by a successful openat.
+        let owned = unsafe { OwnedFd::from_raw_fd(fd) };
+        let mut file = std::fs::File::from(owned);
+        file.write_all(content)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl PinnedWrite {
+    /// Walks `rel` below the canonical workdir, refusing symlinked components
+    /// and creating missing parents when `create_parents` is set.
+    #[cfg(unix)]
+    pub(crate) fn validate(
+        workdir: &Path,
+        rel: &str,
+        create_parents: bool,
+    ) -> anyhow::Result<Self> {
+        let (dirs, file_name) = split_relative(rel)?;
+        let mut parent = unix_impl::open_workdir(&canonical_workdir(workdir)?)?;
+        for dir in &dirs {
+            parent = unix_impl::open_child_dir(&parent, dir, create_parents)?;
+        }
+        Ok(Self { file_name, parent })
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn write(self, content: &[u8]) -> anyhow::Result<WriteProtection> {
+        unix_impl::write_child_file(&self.parent, &self.file_name, content)?;
+        Ok(WriteProtection::NofollowDirfd)
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn validate(
+        workdir: &Path,
+        rel: &str,
+        create_parents: bool,
+    ) -> anyhow::Result<Self> {
+        let (dirs, file_name) = split_relative(rel)?;
+        let mut current = canonical_workdir(workdir)?;
+        for dir in &dirs {
+            current = current.join(dir);
+            match std::fs::symlink_metadata(&current) {
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    return Err(anyhow!(
+                        "refusing to write through symlink component '{dir}'"
+                    ))
+                }
+                Ok(meta) if !meta.is_dir() => {
+                    return Err(anyhow!("path component '{dir}' is not a directory"))
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound && create_parents => {
+                    std::fs::create_dir(&current)
+                        .map_err(|e| anyhow!("failed to create directory '{dir}': {e}"))?;
+                }
+                Err(e) => return Err(anyhow!("failed to inspect '{dir}': {e}")),
+            }
+        }
+        Ok(Self {
+            full: current.join(&file_name),
+            file_name,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn write(self, content: &[u8]) -> anyhow::Result<WriteProtection> {
+        if let Ok(meta) = std::fs::symlink_metadata(&self.full) {
+            if meta.file_type().is_symlink() {
+                return Err(anyhow!(
+                    "refusing to write through symlink '{}'",
+                    self.file_name
+                ));
+            }
+        }
+        std::fs::write(&self.full, content)?;
+        if cfg!(windows) {
+            Ok(WriteProtection::ReparsePointChecked)
+        } else {
+            Ok(WriteProtection::PathChecked)
+        }
+    }
+}
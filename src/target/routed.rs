@@ -0,0 +1,363 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ExecTarget, ExecTargetKind, ListReq, PatchReq, ReadReq, SearchReq, ShellReq, TargetDescribe,
+    TargetResult, WriteReq,
+};
+use crate::types::SideEffects;
+
+/// Policy `execution.route`: which target runs each kind of side effect.
+/// Unset entries use the run's `--exec-target`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecutionRoutes {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filesystem_read: Option<ExecTargetKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filesystem_write: Option<ExecTargetKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell_exec: Option<ExecTargetKind>,
+}
+
+impl ExecutionRoutes {
+    pub fn kind_for(&self, side_effects: SideEffects) -> Option<ExecTargetKind> {
+        match side_effects {
+            SideEffects::FilesystemRead => self.filesystem_read,
+            SideEffects::FilesystemWrite => self.filesystem_write,
+            SideEffects::ShellExec => self.shell_exec,
+            _ => None,
+        }
+    }
+
+    /// True when any route differs from `default`, i.e. a single target
+    /// would not do.
+    pub fn diverges_from(&self, default: ExecTargetKind) -> bool {
+        [self.filesystem_read, self.filesystem_write, self.shell_exec]
+            .into_iter()
+            .flatten()
+            .any(|kind| kind != default)
+    }
+
+    pub fn uses(&self, kind: ExecTargetKind) -> bool {
+        [self.filesystem_read, self.filesystem_write, self.shell_exec].contains(&Some(kind))
+    }
+}
+
+/// Holds a host and a docker target and sends each operation to the one its
+/// side effect is routed to. Both see the same host workdir (docker mounts
+/// it), so a write on one is visible to a read on the other.
+pub struct RoutedTarget {
+    default: ExecTargetKind,
+    host: Arc<dyn ExecTarget>,
+    docker: Arc<dyn ExecTarget>,
+    routes: ExecutionRoutes,
+}
+
+impl RoutedTarget {
+    pub fn new(
+        default: ExecTargetKind,
+        host: Arc<dyn ExecTarget>,
+        docker: Arc<dyn ExecTarget>,
+        routes: ExecutionRoutes,
+    ) -> Self {
+        Self {
+            default,
+            host,
+            docker,
+            routes,
+        }
+    }
+
+    fn target_for(&self, side_effects: SideEffects) -> &Arc<dyn ExecTarget> {
+        match self.routes.kind_for(side_effects).unwrap_or(self.default) {
+            // build_exec_target never routes to or from ssh.
+            ExecTargetKind::Host | ExecTargetKind::Ssh => &self.host,
+            ExecTargetKind::Docker => &self.docker,
+        }
+    }
+}
+
+#[async_trait]
+impl ExecTarget for RoutedTarget {
+    fn kind(&self) -> ExecTargetKind {
+        self.default
+    }
+
+    /// The default target's description; docker metadata is always the
+    /// docker side's, since some calls are routed there either way.
+    fn describe(&self) -> TargetDescribe {
+        let docker = self.docker.describe().docker;
+        match self.default {
+            ExecTargetKind::Host | ExecTargetKind::Ssh => TargetDescribe {
+                docker,
+                ..self.host.describe()
+            },
+            ExecTargetKind::Docker => self.docker.describe(),
+        }
+    }
+
+    fn routed_kind(&self, side_effects: SideEffects) -> ExecTargetKind {
+        self.target_for(side_effects).kind()
+    }
+
+    async fn exec_shell(&self, req: ShellReq) -> TargetResult {
+        self.target_for(SideEffects::ShellExec)
+            .exec_shell(req)
+            .await
+    }
+
+    async fn read_file(&self, req: ReadReq) -> TargetResult {
+        self.target_for(SideEffects::FilesystemRead)
+            .read_file(req)
+            .await
+    }
+
+    async fn list_dir(&self, req: ListReq) -> TargetResult {
+        self.target_for(SideEffects::FilesystemRead)
+            .list_dir(req)
+            .await
+    }
+
+    async fn write_file(&self, req: WriteReq) -> TargetResult {
+        self.target_for(SideEffects::FilesystemWrite)
+            .write_file(req)
+            .await
+    }
+
+    async fn apply_patch(&self, req: PatchReq) -> TargetResult {
+        self.target_for(SideEffects::FilesystemWrite)
+            .apply_patch(req)
+            .await
+    }
+    async fn search(&self, req: SearchReq) -> TargetResult {
+        self.target_for(SideEffects::FilesystemRead)
+            .search(req)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use tempfile::tempdir;
+
+    use super::{ExecutionRoutes, RoutedTarget};
+    use crate::target::{
+        DockerTarget, ExecTarget, ExecTargetKind, HostTarget, ListReq, PatchReq, ReadReq,
+        SearchReq, ShellReq, TargetDescribe, TargetResult, WriteReq,
+    };
+    use crate::types::SideEffects;
+
+    /// Stands in for docker: records each operation and serves reads from
+    /// the host workdir it would have mounted.
+    #[derive(Default)]
+    struct FakeDocker {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl FakeDocker {
+        fn ok(content: String) -> TargetResult {
+            TargetResult {
+                ok: true,
+                ..TargetResult::failed(ExecTargetKind::Docker, content, None)
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ExecTarget for FakeDocker {
+        fn kind(&self) -> ExecTargetKind {
+            ExecTargetKind::Docker
+        }
+
+        fn describe(&self) -> TargetDescribe {
+            TargetDescribe {
+                exec_target: "docker".to_string(),
+                docker: None,
+                ssh: None,
+            }
+        }
+
+        async fn exec_shell(&self, req: ShellReq) -> TargetResult {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("shell {}", req.cmd));
+            Self::ok(String::new())
+        }
+
+        async fn read_file(&self, req: ReadReq) -> TargetResult {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("read {}", req.path));
+            Self::ok(std::fs::read_to_string(req.workdir.join(&req.path)).unwrap_or_default())
+        }
+
+        async fn list_dir(&self, req: ListReq) -> TargetResult {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("list {}", req.path));
+            Self::ok(String::new())
+        }
+
+        async fn write_file(&self, req: WriteReq) -> TargetResult {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("write {}", req.path));
+            Self::ok(String::new())
+        }
+
+        async fn apply_patch(&self, req: PatchReq) -> TargetResult {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("patch {}", req.path.unwrap_or_default()));
+            Self::ok(String::new())
+        }
+
+        async fn search(&self, req: SearchReq) -> TargetResult {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("search {}", req.pattern));
+            Self::ok(String::new())
+        }
+    }
+
+    fn shell_docker_routes() -> ExecutionRoutes {
+        ExecutionRoutes {
+            shell_exec: Some(ExecTargetKind::Docker),
+            filesystem_write: Some(ExecTargetKind::Host),
+            ..ExecutionRoutes::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn shell_goes_to_docker_while_writes_stay_on_host() {
+        let tmp = tempdir().expect("tmp");
+        let docker = Arc::new(FakeDocker::default());
+        let routed = RoutedTarget::new(
+            ExecTargetKind::Host,
+            Arc::new(HostTarget),
+            docker.clone(),
+            shell_docker_routes(),
+        );
+        assert_eq!(routed.kind(), ExecTargetKind::Host);
+        assert_eq!(
+            routed.routed_kind(SideEffects::ShellExec),
+            ExecTargetKind::Docker
+        );
+        assert_eq!(
+            routed.routed_kind(SideEffects::FilesystemWrite),
+            ExecTargetKind::Host
+        );
+        assert_eq!(
+            routed.routed_kind(SideEffects::FilesystemRead),
+            ExecTargetKind::Host
+        );
+
+        let shell = routed
+            .exec_shell(ShellReq {
+                workdir: tmp.path().to_path_buf(),
+                cmd: "make".to_string(),
+                args: Vec::new(),
+                cwd: None,
+                max_tool_output_bytes: 1000,
+                timeout_ms: 0,
+                stream: None,
+                create_cwd: false,
+                redaction: None,
+            })
+            .await;
+        assert_eq!(shell.execution_target, ExecTargetKind::Docker);
+
+        let write = routed
+            .write_file(WriteReq {
+                workdir: tmp.path().to_path_buf(),
+                path: "a.txt".to_string(),
+                content: "hello".to_string(),
+                create_parents: false,
+                dry_run: false,
+            })
+            .await;
+        assert!(write.ok, "{}", write.content);
+        assert_eq!(write.execution_target, ExecTargetKind::Host);
+        assert_eq!(*docker.calls.lock().unwrap(), vec!["shell make"]);
+    }
+
+    #[tokio::test]
+    async fn host_write_is_visible_to_docker_read() {
+        let tmp = tempdir().expect("tmp");
+        let docker = Arc::new(FakeDocker::default());
+        let routed = RoutedTarget::new(
+            ExecTargetKind::Host,
+            Arc::new(HostTarget),
+            docker.clone(),
+            ExecutionRoutes {
+                filesystem_read: Some(ExecTargetKind::Docker),
+                ..ExecutionRoutes::default()
+            },
+        );
+        let write = routed
+            .write_file(WriteReq {
+                workdir: tmp.path().to_path_buf(),
+                path: "src/lib.rs".to_string(),
+                content: "fn main() {}\n".to_string(),
+                create_parents: true,
+                dry_run: false,
+            })
+            .await;
+        assert!(write.ok, "{}", write.content);
+        let read = routed
+            .read_file(ReadReq {
+                workdir: tmp.path().to_path_buf(),
+                path: "src/lib.rs".to_string(),
+                max_read_bytes: 1000,
+                max_line_chars: None,
+                range: None,
+                redaction: None,
+            })
+            .await;
+        assert_eq!(read.execution_target, ExecTargetKind::Docker);
+        assert_eq!(read.content, "fn main() {}\n");
+        assert_eq!(*docker.calls.lock().unwrap(), vec!["read src/lib.rs"]);
+    }
+
+    #[test]
+    fn routed_docker_mounts_the_host_workdir() {
+        let docker = DockerTarget::new(
+            "ubuntu:24.04".to_string(),
+            "/work".to_string(),
+            "none".to_string(),
+            None,
+            crate::target::DockerLimits::default(),
+        );
+        let host_workdir = PathBuf::from("/tmp/project");
+        let argv = docker
+            .build_run_argv_for_test(&host_workdir, "make", None)
+            .expect("argv");
+        let mount = argv.iter().position(|a| a == "-v").expect("mount flag");
+        assert_eq!(argv[mount + 1], "/tmp/project:/work");
+        assert_eq!(argv.last().map(String::as_str), Some("make"));
+    }
+
+    #[test]
+    fn routes_parse_and_report_divergence() {
+        let routes: ExecutionRoutes =
+            serde_yaml::from_str("shell_exec: docker\nfilesystem_write: host\n").expect("parse");
+        assert_eq!(routes, shell_docker_routes());
+        assert!(routes.uses(ExecTargetKind::Docker));
+        assert!(routes.diverges_from(ExecTargetKind::Host));
+        assert!(!ExecutionRoutes::default().diverges_from(ExecTargetKind::Docker));
+        assert!(serde_yaml::from_str::<ExecutionRoutes>("network: docker\n").is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use std::path::{Path, PathBuf};
+
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value};
+
+use super::{elide_long_lines, path_is_workdir_scoped, DockerMeta, ExecTargetKind, TargetResult};
+use crate::context_packs::SKIPPED_WALK_DIRS;
+use crate::tools::ReadAllowlist;
+
+/// Matched lines longer than this are cut so one minified file cannot eat the
+/// whole output budget.
+const SEARCH_MAX_LINE_CHARS: usize = 400;
+
+#[derive(Debug, Clone)]
+pub struct SearchReq {
+    pub workdir: PathBuf,
+    /// Workdir-relative file or directory to search under.
+    pub path: String,
+    pub pattern: String,
+    /// Treat `pattern` as a regex; otherwise it is matched literally.
+    pub regex: bool,
+    pub case_insensitive: bool,
+    pub max_results: usize,
+    pub max_output_bytes: usize,
+    pub read_allowlist: Option<ReadAllowlist>,
+}
+
+pub(crate) fn build_search_regex(
+    pattern: &str,
+    regex: bool,
+    case_insensitive: bool,
+) -> Result<Regex, regex::Error> {
+    let source = if regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(case_insensitive)
+        .build()
+}
+
+/// Walks the workdir without a shell. The docker target mounts the same host
+/// workdir, so both targets search through this and report identical results.
+pub(super) fn search_workdir(
+    kind: ExecTargetKind,
+    docker: Option<DockerMeta>,
+    req: &SearchReq,
+) -> TargetResult {
+    if !path_is_workdir_scoped(&req.path) {
+        return TargetResult::failed(
+            kind,
+            "search path must stay within workdir (no absolute paths or '..' traversal)"
+                .to_string(),
+            docker,
+        );
+    }
+    let re = match build_search_regex(&req.pattern, req.regex, req.case_insensitive) {
+        Ok(re) => re,
+        Err(e) => return TargetResult::failed(kind, format!("invalid pattern: {e}"), docker),
+    };
+    let base = req.workdir.join(&req.path);
+    if std::fs::symlink_metadata(&base).is_err() {
+        return TargetResult::failed(
+            kind,
+            format!("search path does not exist: {}", req.path),
+            docker,
+        );
+    }
+    let mut search = Search {
+        req,
+        re: &re,
+        matches: Vec::new(),
+        output_bytes: 0,
+        truncated: false,
+        skipped_non_text: 0,
+    };
+    search.visit(&base);
+    let content = json!({
+        "path": req.path,
+        "matches": search.matches,
+        "truncated": search.truncated,
+        "max_results": req.max_results,
+        "skipped_binary_or_non_utf8_files": search.skipped_non_text
+    })
+    .to_string();
+    TargetResult {
+        ok: true,
+        bytes: Some(content.len() as u64),
+        content,
+        truncated: search.truncated,
+        exit_code: None,
+        stderr_truncated: None,
+        stdout_truncated: None,
+        execution_target: kind,
+        docker,
+        write_protection: None,
+        cwd: None,
+    }
+}
+
+struct Search<'a> {
+    req: &'a SearchReq,
+    re: &'a Regex,
+    matches: Vec<Value>,
+    output_bytes: usize,
+    truncated: bool,
+    skipped_non_text: usize,
+}
+
+impl Search<'_> {
+    fn rel(&self, path: &Path) -> String {
+        let rel = path.strip_prefix(&self.req.workdir).unwrap_or(path);
+        let parts = rel
+            .components()
+            .filter_map(|c| match c {
+                std::path::Component::Normal(s) => Some(s.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            ".".to_string()
+        } else {
+            parts.join("/")
+        }
+    }
+
+    fn visit(&mut self, path: &Path) {
+        if self.truncated {
+            return;
+        }
+        // Symlinks are never followed, so the walk cannot leave the workdir.
+        let Ok(metadata) = std::fs::symlink_metadata(path) else {
+            return;
+        };
+        let rel = self.rel(path);
+        if metadata.is_dir() {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string());
+            if rel != "." && name.is_some_and(|n| SKIPPED_WALK_DIRS.contains(&n.as_str())) {
+                return;
+            }
+            if self
+                .req
+                .read_allowlist
+                .as_ref()
+                .is_some_and(|a| !a.may_contain(&rel))
+            {
+                return;
+            }
+            let Ok(entries) = std::fs::read_dir(path) else {
+                return;
+            };
+            let mut children = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+            children.sort();
+            for child in children {
+                self.visit(&child);
+            }
+        } else if metadata.is_file() {
+            if self
+                .req
+                .read_allowlist
+                .as_ref()
+                .is_some_and(|a| !a.allows(&rel))
+            {
+                return;
+            }
+            self.search_file(path, &rel);
+        }
+    }
+
+    fn search_file(&mut self, path: &Path, rel: &str) {
+        let Ok(bytes) = std::fs::read(path) else {
+            return;
+        };
+        let text = match std::str::from_utf8(&bytes) {
+            Ok(text) if !bytes.contains(&0) => text,
+            _ => {
+                self.skipped_non_text += 1;
+                return;
+            }
+        };
+        for (idx, line) in text.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if !self.re.is_match(line) {
+                continue;
+            }
+            let (line, _) = elide_long_lines(line, SEARCH_MAX_LINE_CHARS);
+            let entry = json!({"path": rel, "line_number": idx + 1, "line": line});
+            let entry_bytes = entry.to_string().len() + 1;
+            if self.matches.len() >= self.req.max_results
+                || self.output_bytes + entry_bytes > self.req.max_output_bytes
+            {
+                self.truncated = true;
+                return;
+            }
+            self.output_bytes += entry_bytes;
+            self.matches.push(entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{search_workdir, SearchReq};
+    use crate::target::ExecTargetKind;
+    use crate::tools::ReadAllowlist;
+
+    fn req(workdir: &std::path::Path, pattern: &str) -> SearchReq {
+        SearchReq {
+            workdir: workdir.to_path_buf(),
+            path: ".".to_string(),
+            pattern: pattern.to_string(),
+            regex: false,
+            case_insensitive: false,
+            max_results: 100,
+            max_output_bytes: 200_000,
+            read_allowlist: None,
+        }
+    }
+
+    fn search(req: &SearchReq) -> (serde_json::Value, bool) {
+        let out = search_workdir(ExecTargetKind::Host, None, req);
+        assert!(out.ok, "{}", out.content);
+        (
+            serde_json::from_str(&out.content).expect("json"),
+            out.truncated,
+        )
+    }
+
+    fn fixture() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(tmp.path().join("src")).expect("src");
+        std::fs::write(
+            tmp.path().join("src/lib.rs"),
+            "fn run(_x: u8) {}\nfn main() { run(); }\n",
+        )
+        .expect("lib");
+        std::fs::write(tmp.path().join("README.md"), "Call Run() first\n").expect("readme");
+        for skipped in [".git", ".localagent", "target", "node_modules"] {
+            std::fs::create_dir_all(tmp.path().join(skipped)).expect("skipped dir");
+            std::fs::write(tmp.path().join(skipped).join("x.rs"), "run()\n").expect("skipped");
+        }
+        std::fs::write(tmp.path().join("blob.bin"), b"run()\0").expect("bin");
+        tmp
+    }
+
+    #[test]
+    fn literal_search_reports_path_line_number_and_line() {
+        let tmp = fixture();
+        let (v, truncated) = search(&req(tmp.path(), "run()"));
+        assert!(!truncated);
+        assert_eq!(
+            v["matches"],
+            serde_json::json!([
+                {"path": "src/lib.rs", "line_number": 2, "line": "fn main() { run(); }"}
+            ])
+        );
+        assert_eq!(v["skipped_binary_or_non_utf8_files"], 1);
+    }
+
+    #[test]
+    fn regex_case_insensitive_and_path_prefix_narrow_matches() {
+        let tmp = fixture();
+        let mut r = req(tmp.path(), r"run\(\)");
+        r.regex = true;
+        r.case_insensitive = true;
+        let (v, _) = search(&r);
+        let paths = v["matches"]
+            .as_array()
+            .expect("matches")
+            .iter()
+            .map(|m| m["path"].as_str().unwrap_or_default().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["README.md", "src/lib.rs"]);
+
+        r.path = "src".to_string();
+        let (v, _) = search(&r);
+        assert_eq!(v["matches"].as_array().expect("matches").len(), 1);
+    }
+
+    #[test]
+    fn results_are_cut_at_max_results_and_output_bytes() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("a.txt"), "hit\n".repeat(10)).expect("write");
+        let mut r = req(tmp.path(), "hit");
+        r.max_results = 3;
+        let (v, truncated) = search(&r);
+        assert!(truncated);
+        assert_eq!(v["truncated"], true);
+        assert_eq!(v["matches"].as_array().expect("matches").len(), 3);
+
+        r.max_results = 100;
+        r.max_output_bytes = 100;
+        let (v, truncated) = search(&r);
+        assert!(truncated);
+        let kept = v["matches"].as_array().expect("matches").len();
+        assert!((1..10).contains(&kept), "kept {kept}");
+    }
+
+    #[test]
+    fn read_allowlist_limits_searched_files() {
+        let tmp = fixture();
+        let mut r = req(tmp.path(), "run");
+        r.read_allowlist = ReadAllowlist::from_globs(&["README.md".to_string()]).expect("globs");
+        r.case_insensitive = true;
+        let (v, _) = search(&r);
+        assert_eq!(v["matches"].as_array().expect("matches").len(), 1);
+        assert_eq!(v["matches"][0]["path"], "README.md");
+    }
+
+    #[test]
+    fn search_rejects_paths_outside_workdir() {
+        let tmp = fixture();
+        let mut r = req(tmp.path(), "run");
+        r.path = "../elsewhere".to_string();
+        let out = search_workdir(ExecTargetKind::Docker, None, &r);
+        assert!(!out.ok);
+        assert_eq!(out.execution_target, ExecTargetKind::Docker);
+    }
+}
@@ -0,0 +1,601 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::process::Command;
+
+use super::{
+    build_shell_target_result, captured_target_result, docker_cwd_probe_failed,
+    docker_patch_result, docker_shell_script, multi_patch, path_is_workdir_scoped,
+    posix_list_result, posix_list_script, posix_read_output_cap, posix_read_result,
+    posix_read_script, posix_write_script, shell_cwd_not_found_message, shell_escape,
+    spawn_and_wait_managed, split_multi_file_patch, stdin_patch_input, stdin_patch_script,
+    ExecTarget, ExecTargetKind, ListReq, ManagedOutput, PatchReq, ReadReq, SearchReq, ShellReq,
+    TargetDescribe, TargetResult, WriteReq, SSH_CONNECTION_FAILED_PREFIX,
+};
+use crate::tools::RedactionPass;
+
+/// Seconds `ssh` waits for the TCP connection before giving up.
+const SSH_CONNECT_TIMEOUT_SECS: u32 = 10;
+
+/// Keepalive probes on an established connection: after this many
+/// unanswered probes this many seconds apart, `ssh` drops the connection and
+/// exits 255. File operations run without a deadline, so this is what stops
+/// them hanging on a dead peer.
+const SSH_SERVER_ALIVE_INTERVAL_SECS: u32 = 15;
+const SSH_SERVER_ALIVE_COUNT_MAX: u32 = 3;
+
+/// `ssh` exits 255 when it fails itself (unreachable host, auth, host key).
+/// A remote command can exit 255 as well, so the client's own stderr line
+/// decides which it was.
+const SSH_CLIENT_ERROR_EXIT: i32 = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshConfig {
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<PathBuf>,
+    pub remote_workdir: String,
+}
+
+/// What a run records about its ssh target. The identity file is left out on
+/// purpose: key paths do not belong in events or run records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SshMeta {
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    pub remote_workdir: String,
+}
+
+/// Runs every operation on a remote host through the system `ssh` client, as
+/// `sh -c` scripts rooted at the remote workdir. File content goes over stdin.
+/// `search`, and dry-run writes and patches, are refused: they read the local
+/// workdir, which the remote host does not share.
+pub struct SshTarget {
+    meta: SshMeta,
+    identity_file: Option<PathBuf>,
+}
+
+impl SshTarget {
+    pub fn new(config: SshConfig) -> anyhow::Result<Self> {
+        validate_ssh_word("--ssh-host", &config.host)?;
+        if let Some(user) = &config.user {
+            validate_ssh_word("--ssh-user", user)?;
+        }
+        if !config.remote_workdir.starts_with('/') {
+            return Err(anyhow!(
+                "--ssh-workdir must be an absolute remote path, got '{}'",
+                config.remote_workdir
+            ));
+        }
+        Ok(Self {
+            meta: SshMeta {
+                host: config.host,
+                user: config.user,
+                port: config.port,
+                remote_workdir: config.remote_workdir,
+            },
+            identity_file: config.identity_file,
+        })
+    }
+
+    pub fn validate_available() -> anyhow::Result<()> {
+        let out = std::process::Command::new("ssh")
+            .arg("-V")
+            .output()
+            .map_err(|e| anyhow!("failed to execute ssh: {e}"))?;
+        if out.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "ssh -V failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            ))
+        }
+    }
+
+    fn destination(&self) -> String {
+        match &self.meta.user {
+            Some(user) => format!("{user}@{}", self.meta.host),
+            None => self.meta.host.clone(),
+        }
+    }
+
+    /// Arguments after `ssh` for one operation. `BatchMode` makes a missing
+    /// key or unknown host key fail fast instead of prompting.
+    fn ssh_args(&self, script: &str) -> Vec<String> {
+        let mut args = vec![
+            "-T".to_string(),
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            format!("ConnectTimeout={SSH_CONNECT_TIMEOUT_SECS}"),
+            "-o".to_string(),
+            format!("ServerAliveInterval={SSH_SERVER_ALIVE_INTERVAL_SECS}"),
+            "-o".to_string(),
+            format!("ServerAliveCountMax={SSH_SERVER_ALIVE_COUNT_MAX}"),
+        ];
+        if let Some(port) = self.meta.port {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        if let Some(identity) = &self.identity_file {
+            args.extend(["-i".to_string(), identity.display().to_string()]);
+        }
+        args.extend([
+            "--".to_string(),
+            self.destination(),
+            remote_command(&self.meta.remote_workdir, script),
+        ]);
+        args
+    }
+
+    /// Runs `script` on the remote host. `Err` carries the failed result for
+    /// a client that could not spawn or connect.
+    async fn run(
+        &self,
+        script: &str,
+        stdin_bytes: Option<&[u8]>,
+        timeout_ms: u64,
+    ) -> Result<ManagedOutput, TargetResult> {
+        let mut cmd = Command::new("ssh");
+        cmd.args(self.ssh_args(script));
+        let managed = spawn_and_wait_managed(cmd, timeout_ms, stdin_bytes, None)
+            .await
+            .map_err(|e| self.failed(format!("SSH_SPAWN_FAILED: failed to run ssh: {e}")))?;
+        if !managed.timed_out
+            && managed.status.and_then(|s| s.code()) == Some(SSH_CLIENT_ERROR_EXIT)
+        {
+            let stderr = String::from_utf8_lossy(&managed.stderr);
+            if let Some(cause) = ssh_client_error_line(&stderr) {
+                return Err(self.failed(connection_failed_message(&self.meta.host, cause)));
+            }
+        }
+        Ok(managed)
+    }
+
+    /// Runs a file-operation script with no deadline and captures its output.
+    async fn run_captured(
+        &self,
+        script: &str,
+        stdin_bytes: Option<&[u8]>,
+        max_tool_output_bytes: usize,
+        redaction: Option<&RedactionPass>,
+    ) -> TargetResult {
+        let managed = match self.run(script, stdin_bytes, 0).await {
+            Ok(managed) => managed,
+            Err(failed) => return failed,
+        };
+        match managed.status {
+            Some(status) => captured_target_result(
+                ExecTargetKind::Ssh,
+                None,
+                status,
+                &managed.stdout,
+                &managed.stderr,
+                max_tool_output_bytes,
+                redaction,
+            ),
+            None => self.failed("ssh command did not report an exit status".to_string()),
+        }
+    }
+
+    fn failed(&self, reason: String) -> TargetResult {
+        TargetResult::failed(ExecTargetKind::Ssh, reason, None)
+    }
+
+    fn unscoped(&self, tool: &str) -> TargetResult {
+        self.failed(format!(
+            "{tool} path must stay within workdir (no absolute paths or '..' traversal)"
+        ))
+    }
+
+    fn unsupported(&self, what: &str) -> TargetResult {
+        self.failed(format!(
+            "{what} is not supported on the ssh exec target: it reads the local workdir, not {}:{}",
+            self.meta.host, self.meta.remote_workdir
+        ))
+    }
+}
+
+/// Host and user are passed to `ssh` as words of their own; a leading `-`
+/// would be taken as an option.
+fn validate_ssh_word(flag: &str, value: &str) -> anyhow::Result<()> {
+    if value.is_empty() || value.starts_with('-') || value.chars().any(char::is_whitespace) {
+        return Err(anyhow!(
+            "{flag} must be non-empty, must not start with '-' and must not contain whitespace"
+        ));
+    }
+    Ok(())
+}
+
+/// The single argument `ssh` hands to the remote login shell. Wrapping in
+/// `sh -c` keeps the script POSIX whatever that login shell is.
+fn remote_command(remote_workdir: &str, script: &str) -> String {
+    format!(
+        "sh -c {}",
+        shell_escape(&format!("cd {} && {script}", shell_escape(remote_workdir)))
+    )
+}
+
+/// The last stderr line the OpenSSH client prints when it cannot reach or
+/// log into the host. Banners, warnings and the remote command's own output
+/// never match.
+fn ssh_client_error_line(stderr: &str) -> Option<&str> {
+    stderr.lines().rev().map(str::trim).find(|line| {
+        line.starts_with("ssh:")
+            || line.starts_with("kex_exchange_identification:")
+            || line.starts_with("Connection timed out during banner exchange")
+            || line.starts_with("Timeout, server ")
+            || line.contains("Permission denied (")
+            || *line == "Host key verification failed."
+    })
+}
+
+fn connection_failed_message(host: &str, cause: &str) -> String {
+    format!("{SSH_CONNECTION_FAILED_PREFIX} ssh to {host} exited {SSH_CLIENT_ERROR_EXIT}: {cause}")
+}
+
+#[async_trait]
+impl ExecTarget for SshTarget {
+    fn kind(&self) -> ExecTargetKind {
+        ExecTargetKind::Ssh
+    }
+
+    fn describe(&self) -> TargetDescribe {
+        TargetDescribe {
+            exec_target: "ssh".to_string(),
+            docker: None,
+            ssh: Some(self.meta.clone()),
+        }
+    }
+
+    /// On timeout the local `ssh` client is killed; the remote command is
+    /// not, and may run on until it exits by itself.
+    async fn exec_shell(&self, req: ShellReq) -> TargetResult {
+        let args = req
+            .args
+            .iter()
+            .map(|a| shell_escape(a))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let cwd = req.cwd.unwrap_or_else(|| ".".to_string());
+        if !path_is_workdir_scoped(&cwd) {
+            return self.failed(
+                "shell cwd must stay within workdir (no absolute paths or '..' traversal)"
+                    .to_string(),
+            );
+        }
+        let script = docker_shell_script(&cwd, req.create_cwd, &req.cmd, &args);
+        let mut out = match self.run(&script, None, req.timeout_ms).await {
+            Ok(managed) => build_shell_target_result(
+                ExecTargetKind::Ssh,
+                None,
+                managed,
+                req.timeout_ms,
+                req.max_tool_output_bytes,
+                req.redaction.as_ref(),
+            ),
+            Err(failed) => failed,
+        };
+        if docker_cwd_probe_failed(&out) {
+            out = self.failed(shell_cwd_not_found_message(&cwd));
+        }
+        out.cwd = Some(cwd);
+        out
+    }
+
+    async fn read_file(&self, req: ReadReq) -> TargetResult {
+        if !path_is_workdir_scoped(&req.path) {
+            return self.unscoped("read_file");
+        }
+        let out = self
+            .run_captured(
+                &posix_read_script(&req),
+                None,
+                posix_read_output_cap(&req),
+                req.redaction.as_ref(),
+            )
+            .await;
+        posix_read_result(&req, &self.meta.remote_workdir, out)
+    }
+
+    async fn list_dir(&self, req: ListReq) -> TargetResult {
+        if !path_is_workdir_scoped(&req.path) {
+            return self.unscoped("list_dir");
+        }
+        let out = self
+            .run_captured(&posix_list_script(&req.path), None, 200_000, None)
+            .await;
+        posix_list_result(&req, &self.meta.remote_workdir, out)
+    }
+
+    async fn write_file(&self, req: WriteReq) -> TargetResult {
+        if !path_is_workdir_scoped(&req.path) {
+            return self.unscoped("write_file");
+        }
+        if req.dry_run {
+            return self.unsupported("write_file dry_run");
+        }
+        let script = posix_write_script(&req.path, req.create_parents);
+        let mut out = self
+            .run_captured(&script, Some(req.content.as_bytes()), 200_000, None)
+            .await;
+        if out.ok {
+            out.content = json!({"path": req.path, "bytes_written": req.content.len()}).to_string();
+            out.bytes = Some(req.content.len() as u64);
+            out.truncated = false;
+        }
+        out
+    }
+
+    async fn apply_patch(&self, req: PatchReq) -> TargetResult {
+        if req.dry_run {
+            return self.unsupported("apply_patch dry_run");
+        }
+        let Some(path) = req.path else {
+            let files = match split_multi_file_patch(&req.patch) {
+                Ok(files) => files,
+                Err(e) => return self.failed(e),
+            };
+            if let Some(denied) =
+                multi_patch::first_unscoped_path(ExecTargetKind::Ssh, &files, None)
+            {
+                return denied;
+            }
+            let script = multi_patch::stdin_multi_patch_script(&files);
+            let input = multi_patch::stdin_multi_patch_input(&files);
+            let out = self
+                .run_captured(&script, Some(&input), 200_000, None)
+                .await;
+            return multi_patch::docker_multi_patch_result(&files, out);
+        };
+        if !path_is_workdir_scoped(&path) {
+            return self.unscoped("apply_patch");
+        }
+        let script = stdin_patch_script(&path);
+        let input = stdin_patch_input(&req.patch);
+        let out = self
+            .run_captured(&script, Some(&input), 200_000, None)
+            .await;
+        docker_patch_result(&path, out)
+    }
+
+    async fn search(&self, _req: SearchReq) -> TargetResult {
+        self.unsupported("search")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        connection_failed_message, multi_patch, remote_command, split_multi_file_patch,
+        ssh_client_error_line, stdin_patch_input, stdin_patch_script, SshConfig, SshTarget,
+    };
+    use crate::target::{ExecTarget, ExecTargetKind, ShellReq};
+
+    fn config() -> SshConfig {
+        SshConfig {
+            host: "build-box".to_string(),
+            user: Some("ci".to_string()),
+            port: Some(2222),
+            identity_file: Some("/home/me/.ssh/id_ci".into()),
+            remote_workdir: "/srv/work tree".to_string(),
+        }
+    }
+
+    #[test]
+    fn ssh_args_put_options_before_destination_and_one_remote_command() {
+        let target = SshTarget::new(config()).expect("target");
+        assert_eq!(
+            target.ssh_args("echo hi"),
+            vec![
+                "-T",
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ConnectTimeout=10",
+                "-o",
+                "ServerAliveInterval=15",
+                "-o",
+                "ServerAliveCountMax=3",
+                "-p",
+                "2222",
+                "-i",
+                "/home/me/.ssh/id_ci",
+                "--",
+                "ci@build-box",
+                "sh -c 'cd '\"'\"'/srv/work tree'\"'\"' && echo hi'",
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_command_survives_one_round_of_shell_parsing() {
+        let script = "printf '%s\\n' \"$HOME\" 'it'\"'\"'s'";
+        let cmd = remote_command("/w", script);
+        let out = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("set -- {cmd}; printf '%s\\n' \"$@\""))
+            .output()
+            .expect("sh");
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout),
+            format!("sh\n-c\ncd '/w' && {script}\n")
+        );
+    }
+
+    #[test]
+    fn new_rejects_option_like_hosts_and_relative_workdirs() {
+        for (host, user, workdir) in [
+            ("-oProxyCommand=x", None, "/w"),
+            ("build box", None, "/w"),
+            ("", None, "/w"),
+            ("build-box", Some("-l"), "/w"),
+            ("build-box", None, "work"),
+            ("build-box", None, ""),
+        ] {
+            let cfg = SshConfig {
+                host: host.to_string(),
+                user: user.map(str::to_string),
+                port: None,
+                identity_file: None,
+                remote_workdir: workdir.to_string(),
+            };
+            assert!(
+                SshTarget::new(cfg).is_err(),
+                "{host:?} {user:?} {workdir:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn describe_names_host_and_workdir_but_never_the_key() {
+        let target = SshTarget::new(config()).expect("target");
+        let described = serde_json::to_string(&target.describe()).expect("json");
+        assert!(described.contains("\"exec_target\":\"ssh\""));
+        assert!(described.contains("\"host\":\"build-box\""));
+        assert!(described.contains("\"remote_workdir\":\"/srv/work tree\""));
+        assert!(!described.contains("id_ci"));
+    }
+
+    #[test]
+    fn connection_failure_keeps_only_the_client_cause_line() {
+        let cause = ssh_client_error_line(
+            "Warning: banner\nssh: connect to host build-box port 22: Connection refused\n",
+        )
+        .expect("client error");
+        assert_eq!(
+            connection_failed_message("build-box", cause),
+            "SSH_CONNECTION_FAILED: ssh to build-box exited 255: ssh: connect to host build-box port 22: Connection refused"
+        );
+        assert_eq!(
+            ssh_client_error_line("ci@build-box: Permission denied (publickey).\n"),
+            Some("ci@build-box: Permission denied (publickey).")
+        );
+        assert_eq!(
+            ssh_client_error_line("Host key verification failed.\n"),
+            Some("Host key verification failed.")
+        );
+    }
+
+    #[test]
+    fn remote_exit_255_without_a_client_error_is_the_command_status() {
+        assert_eq!(ssh_client_error_line(""), None);
+        assert_eq!(
+            ssh_client_error_line("cat: a.txt: Permission denied\nexit 255 from script\n"),
+            None
+        );
+    }
+
+    /// Needs `LOCALAGENT_TEST_SSH=user@host:/abs/workdir` with key-based,
+    /// non-interactive access to that host.
+    #[tokio::test]
+    async fn live_ssh_shell_runs_in_remote_workdir() {
+        let Ok(spec) = std::env::var("LOCALAGENT_TEST_SSH") else {
+            return;
+        };
+        let (dest, workdir) = spec.split_once(':').expect("user@host:/workdir");
+        let (user, host) = match dest.split_once('@') {
+            Some((u, h)) => (Some(u.to_string()), h.to_string()),
+            None => (None, dest.to_string()),
+        };
+        let target = SshTarget::new(super::SshConfig {
+            host,
+            user,
+            port: None,
+            identity_file: None,
+            remote_workdir: workdir.to_string(),
+        })
+        .expect("target");
+        let out = target
+            .exec_shell(ShellReq {
+                workdir: std::env::temp_dir(),
+                cmd: "pwd".to_string(),
+                args: Vec::new(),
+                cwd: None,
+                max_tool_output_bytes: 4096,
+                timeout_ms: 30_000,
+                stream: None,
+                create_cwd: false,
+                redaction: None,
+            })
+            .await;
+        assert!(out.ok, "{}", out.content);
+        assert_eq!(out.execution_target, ExecTargetKind::Ssh);
+        let parsed: serde_json::Value = serde_json::from_str(&out.content).expect("json");
+        assert_eq!(parsed["stdout"].as_str().map(str::trim), Some(workdir));
+    }
+
+    /// Runs a patch script the way the remote shell would, in `dir`.
+    fn run_patch_script(dir: &std::path::Path, script: &str, input: &[u8]) -> i32 {
+        use std::io::Write;
+        let mut child = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .current_dir(dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .expect("sh");
+        child
+            .stdin
+            .take()
+            .expect("stdin")
+            .write_all(input)
+            .expect("write");
+        child.wait().expect("wait").code().unwrap_or(-1)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn patch_delimiter_in_patch_text_does_not_run_as_shell() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("a.txt"), "one\n").expect("a");
+        let patch =
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-one\n+two\nOPENAGENT_PATCH\ntouch pwned\n";
+        run_patch_script(
+            tmp.path(),
+            &stdin_patch_script("a.txt"),
+            &stdin_patch_input(patch),
+        );
+        assert!(!tmp.path().join("pwned").exists());
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).expect("a"),
+            "two\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn multi_file_patch_delimiter_in_patch_text_does_not_run_as_shell() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("a.txt"), "one\n").expect("a");
+        std::fs::write(tmp.path().join("b.txt"), "uno\n").expect("b");
+        let patch =
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-one\n+two\nOPENAGENT_PATCH_0\ntouch pwned\n\
+--- a/b.txt\n+++ b/b.txt\n@@ -1 +1 @@\n-uno\n+dos\n";
+        let files = split_multi_file_patch(patch).expect("split");
+        let status = run_patch_script(
+            tmp.path(),
+            &multi_patch::stdin_multi_patch_script(&files),
+            &multi_patch::stdin_multi_patch_input(&files),
+        );
+        assert_eq!(status, 0);
+        assert!(!tmp.path().join("pwned").exists());
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("a.txt")).expect("a"),
+            "two\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("b.txt")).expect("b"),
+            "dos\n"
+        );
+    }
+}
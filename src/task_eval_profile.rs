@@ -92,6 +92,7 @@ pub(crate) fn apply_eval_profile_overrides(
         if let Some(v) = &p.auto_approve_scope {
             args.auto_approve_scope = match v.as_str() {
                 "session" => AutoApproveScope::Session,
+                "workspace" => AutoApproveScope::Workspace,
                 _ => AutoApproveScope::Run,
             };
         }
@@ -1,7 +1,9 @@
 use anyhow::anyhow;
 
 use crate::agent::AgentExitReason;
-use crate::events::EventKind;
+use crate::events::{
+    TaskgraphEndPayload, TaskgraphNodeEndPayload, TaskgraphNodeStartPayload, TaskgraphStartPayload,
+};
 use crate::provider_runtime;
 use crate::providers::mock::MockProvider;
 use crate::providers::ollama::OllamaProvider;
@@ -85,17 +87,19 @@ pub(crate) async fn run_tasks_graph(
         false,
         None,
         false,
+        None,
+        None,
+        Vec::new(),
     )?;
     runtime_events::emit_event(
         &mut sink,
         &graph_run_id,
         0,
-        EventKind::TaskgraphStart,
-        serde_json::json!({
-            "graph_run_id": graph_run_id,
-            "taskfile_hash_hex": taskfile_hash_hex,
-            "nodes": order.len()
-        }),
+        TaskgraphStartPayload {
+            graph_run_id: graph_run_id.clone(),
+            taskfile_hash_hex: taskfile_hash_hex.clone(),
+            nodes: order.len(),
+        },
     );
 
     let mut status = "ok".to_string();
@@ -117,13 +121,12 @@ pub(crate) async fn run_tasks_graph(
                 &mut sink,
                 &graph_run_id,
                 idx as u32,
-                EventKind::TaskgraphNodeEnd,
-                serde_json::json!({
-                    "node_id": node_id,
-                    "status":"skipped",
-                    "run_id": cp_node.run_id.unwrap_or_default(),
-                    "exit_reason":"already_done"
-                }),
+                TaskgraphNodeEndPayload {
+                    node_id: node_id.clone(),
+                    status: "skipped".to_string(),
+                    run_id: cp_node.run_id.unwrap_or_default(),
+                    exit_reason: "already_done".to_string(),
+                },
             );
             continue;
         }
@@ -132,12 +135,11 @@ pub(crate) async fn run_tasks_graph(
             &mut sink,
             &graph_run_id,
             idx as u32,
-            EventKind::TaskgraphNodeStart,
-            serde_json::json!({
-                "node_id": node_id,
-                "index": idx + 1,
-                "total": order.len()
-            }),
+            TaskgraphNodeStartPayload {
+                node_id: node_id.clone(),
+                index: idx + 1,
+                total: order.len(),
+            },
         );
         let node = taskgraph::node_by_id(&taskfile, node_id)?;
         let node_args = build_node_run_args(base_run, &taskfile, node, node_id, args, &summaries)?;
@@ -171,7 +173,7 @@ pub(crate) async fn run_tasks_graph(
             .ok_or_else(|| anyhow!("node prompt missing"))?;
 
         let result = match provider_kind {
-            ProviderKind::Lmstudio | ProviderKind::Llamacpp => {
+            ProviderKind::Lmstudio | ProviderKind::Llamacpp | ProviderKind::OpenAi => {
                 let provider = OpenAiCompatProvider::new(
                     provider_kind,
                     base_url.clone(),
@@ -263,13 +265,12 @@ pub(crate) async fn run_tasks_graph(
             &mut sink,
             &graph_run_id,
             idx as u32,
-            EventKind::TaskgraphNodeEnd,
-            serde_json::json!({
-                "node_id": node_id,
-                "status": node_status,
-                "run_id": result.outcome.run_id,
-                "exit_reason": exit_reason
-            }),
+            TaskgraphNodeEndPayload {
+                node_id: node_id.clone(),
+                status: node_status,
+                run_id: result.outcome.run_id.clone(),
+                exit_reason,
+            },
         );
 
         if args.propagate_summaries.enabled() {
@@ -296,8 +297,9 @@ pub(crate) async fn run_tasks_graph(
         &mut sink,
         &graph_run_id,
         order.len() as u32,
-        EventKind::TaskgraphEnd,
-        serde_json::json!({"status": status}),
+        TaskgraphEndPayload {
+            status: status.clone(),
+        },
     );
     let graph = taskgraph::TaskGraphRunArtifact {
         schema_version: "openagent.taskgraph_run.v1".to_string(),
@@ -5,7 +5,7 @@ use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::target::{DockerMeta, ExecTarget, ExecTargetKind};
+use crate::target::{DockerMeta, ExecTarget, ExecTargetKind, WriteProtection};
 use crate::types::{Message, SideEffects, ToolCall};
 
 mod catalog;
@@ -15,21 +15,29 @@ mod exec_plan;
 mod exec_shell;
 mod exec_support;
 mod exec_write;
+mod read_allowlist;
 mod schema;
+mod secret_redaction;
 
 pub(crate) use catalog::normalize_builtin_tool_args;
-pub use catalog::{builtin_tools_enabled, tool_side_effects};
+pub use catalog::{builtin_tools_enabled, spawn_subtask_tool_def, tool_side_effects};
 pub use envelope::{
     envelope_to_message, invalid_args_tool_message, to_tool_result_envelope,
     to_tool_result_envelope_with_error,
 };
 pub(crate) use exec_plan::parse_update_plan_args;
 pub use exec_plan::{PlanItem, PlanStatus};
+pub use exec_shell::shell_program_allowlisted;
+pub(crate) use exec_support::resolve_following_symlinks;
 use exec_support::ToolExecution;
+pub use read_allowlist::{normalize_allowlist_path, ReadAllowlist};
+use schema::parse_read_range;
 pub use schema::{
     compact_builtin_schema, invalid_args_detail, minimal_builtin_example,
-    sorted_builtin_tool_names, validate_builtin_tool_args, validate_schema_args,
+    sorted_builtin_tool_names, validate_builtin_tool_args, validate_json_output,
+    validate_schema_args,
 };
+pub use secret_redaction::{RedactionPass, SecretRedactor};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum ToolArgsStrict {
@@ -48,13 +56,54 @@ pub struct ToolRuntime {
     pub workdir: PathBuf,
     pub allow_shell: bool,
     pub allow_shell_in_workdir_only: bool,
+    /// Program names the shell tool may run (matched on basename). `None`
+    /// leaves shell access as the flags above grant it; an empty list
+    /// denies every command.
+    pub shell_allowlist: Option<Vec<String>>,
     pub allow_write: bool,
     pub max_tool_output_bytes: usize,
     pub max_read_bytes: usize,
     pub unsafe_bypass_allow_flags: bool,
+    /// Resolve filesystem tool paths (following symlinks) and deny any that
+    /// land outside `workdir` with `path_escape`. On unless
+    /// `--unsafe-bypass-allow-flags`.
+    pub restrict_to_workdir: bool,
+    /// Wall-clock limit for one builtin tool call. Shell commands get it as
+    /// their process timeout so the child (or container) is killed; other
+    /// tools are abandoned when it expires. `None` leaves calls unbounded.
+    pub tool_timeout_ms: Option<u64>,
+    /// Emit `ToolExecProgress` events (byte counts and a tail preview) while
+    /// a host shell command runs. The final result is unaffected.
+    pub stream_tool_output: bool,
+    /// `write_file`, `apply_patch`, `edit` and `str_replace` report the diff
+    /// they would apply instead of writing (`--dry-run-writes`).
+    pub dry_run_writes: bool,
+    /// Snapshots files before write tools change them so `localagent
+    /// rollback` can restore them (`--checkpoint-writes`). `None` disables it.
+    pub write_checkpoint: Option<Arc<crate::store::WriteCheckpointStore>>,
     pub tool_args_strict: ToolArgsStrict,
     pub exec_target_kind: ExecTargetKind,
     pub exec_target: Arc<dyn ExecTarget>,
+    pub read_allowlist: Option<ReadAllowlist>,
+    /// Store that MCP results spill into and `artifact:<hash>` paths resolve
+    /// against. `None` disables both.
+    pub run_artifacts: Option<Arc<crate::store::RunArtifactStore>>,
+    /// Secret patterns scrubbed from builtin tool results before they reach
+    /// the transcript (policy `redact_tool_results`). `None` disables it.
+    pub secret_redactor: Option<Arc<SecretRedactor>>,
+}
+
+impl ToolRuntime {
+    /// Target a call with these side effects runs on: `exec_target_kind`
+    /// unless policy routing sends it elsewhere.
+    pub fn exec_target_kind_for(&self, side_effects: SideEffects) -> ExecTargetKind {
+        let routed = self.exec_target.routed_kind(side_effects);
+        if routed != self.exec_target.kind() {
+            routed
+        } else {
+            self.exec_target_kind
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -78,6 +127,16 @@ pub struct ToolResultMeta {
     pub warnings_truncated: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub docker: Option<DockerMeta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_protection: Option<WriteProtection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Set when the call was cut off by a timeout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timed_out: Option<bool>,
+    /// Set when a write tool only reported its change (`--dry-run-writes`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -110,6 +169,12 @@ pub struct ToolResultEnvelope {
     pub full_output_ref: Option<ToolResultContentRef>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<ToolErrorDetail>,
+    /// Secret spans replaced in `content` (policy `redact_tool_results`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redactions: Option<usize>,
+    /// Digest of each replaced span, in order; never the secret itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redaction_digests: Option<Vec<String>>,
     pub meta: ToolResultMeta,
 }
 
@@ -132,6 +197,19 @@ pub enum ToolErrorCode {
     ShellExecNonZeroExit,
     ShellExecTimeout,
     ShellExecTimeoutUnsupported,
+    ShellCwdNotFound,
+    PathNotInReadAllowlist,
+    IsDirectory,
+    NotADirectory,
+    NotFound,
+    SpecialFile,
+    ArtifactNotFound,
+    ArtifactReadOnly,
+    PathEscape,
+    ToolOutputOversize,
+    McpServerDegraded,
+    McpSchemaDrift,
+    SshConnectionFailed,
 }
 
 impl ToolErrorCode {
@@ -152,8 +230,30 @@ impl ToolErrorCode {
             Self::ShellExecNonZeroExit => "shell_exec_non_zero_exit",
             Self::ShellExecTimeout => "shell_exec_timeout",
             Self::ShellExecTimeoutUnsupported => "shell_exec_timeout_unsupported",
+            Self::ShellCwdNotFound => "shell_cwd_not_found",
+            Self::PathNotInReadAllowlist => "path_not_in_read_allowlist",
+            Self::IsDirectory => "is_directory",
+            Self::NotADirectory => "not_a_directory",
+            Self::NotFound => "not_found",
+            Self::SpecialFile => "special_file",
+            Self::ArtifactNotFound => "artifact_not_found",
+            Self::ArtifactReadOnly => "artifact_read_only",
+            Self::PathEscape => "path_escape",
+            Self::ToolOutputOversize => "tool_output_oversize",
+            Self::McpServerDegraded => "mcp_server_degraded",
+            Self::McpSchemaDrift => "mcp_schema_drift",
+            Self::SshConnectionFailed => "ssh_connection_failed",
         }
     }
+
+    /// The path names the wrong kind of entity for the tool. Re-running the
+    /// same call cannot succeed; the model has to pick another tool or path.
+    pub fn is_fs_entity(self) -> bool {
+        matches!(
+            self,
+            Self::IsDirectory | Self::NotADirectory | Self::NotFound | Self::SpecialFile
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -178,6 +278,25 @@ pub async fn execute_tool(rt: &ToolRuntime, tc: &ToolCall) -> Message {
 /// while a `shell` command runs. Only the `shell` tool consults the stream; all
 /// other tools behave identically to [`execute_tool`]. The final result envelope
 /// is unaffected by streaming.
+/// Extra time a shell call gets past `tool_timeout_ms` to stop its process.
+const SHELL_TOOL_TIMEOUT_GRACE_MS: u64 = 15_000;
+
+fn tool_timeout_exec(
+    rt: &ToolRuntime,
+    side_effects: SideEffects,
+    name: &str,
+    timeout_ms: u64,
+) -> ToolExecution {
+    let mut exec = exec_support::failed_exec(
+        rt,
+        side_effects,
+        format!("tool '{name}' timed out after {timeout_ms}ms (tool_timeout_ms)"),
+        None,
+    );
+    exec.meta.timed_out = Some(true);
+    exec
+}
+
 pub async fn execute_tool_streaming(
     rt: &ToolRuntime,
     tc: &ToolCall,
@@ -190,58 +309,127 @@ pub async fn execute_tool_streaming(
             tc,
             "builtin",
             &e,
-            match rt.exec_target_kind {
+            match rt.exec_target_kind_for(side_effects) {
                 ExecTargetKind::Host => "host".to_string(),
                 ExecTargetKind::Docker => "docker".to_string(),
+                ExecTargetKind::Ssh => "ssh".to_string(),
             },
         );
     }
-    let exec = match tc.name.as_str() {
-        "list_dir" => exec_fs::run_list_dir(rt, &normalized_args).await,
-        "read_file" => exec_fs::run_read_file(rt, &normalized_args).await,
-        "glob" => exec_fs::run_glob(rt, &normalized_args).await,
-        "grep" => exec_fs::run_grep(rt, &normalized_args).await,
-        "update_plan" => exec_plan::run_update_plan(rt, &normalized_args).await,
-        "shell" => exec_shell::run_shell(rt, &normalized_args, shell_stream).await,
-        "write_file" => exec_write::run_write_file(rt, &normalized_args).await,
-        "apply_patch" => exec_write::run_apply_patch(rt, &normalized_args).await,
-        "edit" => exec_write::run_edit(rt, &normalized_args).await,
-        "str_replace" => exec_write::run_str_replace(rt, &normalized_args).await,
-        _ => ToolExecution {
-            ok: false,
-            content: format!("unknown tool: {}", tc.name),
-            truncated: false,
-            error: Some(ToolErrorDetail {
-                code: ToolErrorCode::ToolUnknown,
-                message: format!("Unknown tool '{}'.", tc.name),
-                expected_schema: None,
-                received_args: Some(tc.arguments.clone()),
-                minimal_example: None,
-                available_tools: Some({
-                    let mut names = sorted_builtin_tool_names();
-                    names.sort();
-                    names
+    let checkpoint_paths = checkpoint_write_paths(rt, side_effects, &normalized_args);
+    let redaction = rt.secret_redactor.clone().map(RedactionPass::new);
+    let dispatch = async {
+        if let Some((store, paths)) = &checkpoint_paths {
+            if let Err(e) = store.before_write(paths, &tc.id) {
+                return exec_support::failed_exec(
+                    rt,
+                    side_effects,
+                    format!("write checkpoint failed, nothing was written: {e}"),
+                    None,
+                );
+            }
+        }
+        match tc.name.as_str() {
+            "list_dir" => exec_fs::run_list_dir(rt, &normalized_args).await,
+            "read_file" => exec_fs::run_read_file(rt, &normalized_args, redaction.as_ref()).await,
+            "glob" => exec_fs::run_glob(rt, &normalized_args).await,
+            "grep" => exec_fs::run_grep(rt, &normalized_args).await,
+            "search" => exec_fs::run_search(rt, &normalized_args).await,
+            "update_plan" => exec_plan::run_update_plan(rt, &normalized_args).await,
+            "shell" => {
+                exec_shell::run_shell(rt, &normalized_args, shell_stream, redaction.as_ref()).await
+            }
+            "write_file" => exec_write::run_write_file(rt, &normalized_args).await,
+            "apply_patch" => exec_write::run_apply_patch(rt, &normalized_args).await,
+            "edit" => exec_write::run_edit(rt, &normalized_args).await,
+            "str_replace" => exec_write::run_str_replace(rt, &normalized_args).await,
+            _ => ToolExecution {
+                ok: false,
+                content: format!("unknown tool: {}", tc.name),
+                truncated: false,
+                error: Some(ToolErrorDetail {
+                    code: ToolErrorCode::ToolUnknown,
+                    message: format!("Unknown tool '{}'.", tc.name),
+                    expected_schema: None,
+                    received_args: Some(tc.arguments.clone()),
+                    minimal_example: None,
+                    available_tools: Some({
+                        let mut names = sorted_builtin_tool_names();
+                        names.sort();
+                        names
+                    }),
                 }),
-            }),
-            meta: ToolResultMeta {
-                side_effects,
-                bytes: None,
-                exit_code: None,
-                stderr_truncated: None,
-                stdout_truncated: None,
-                source: "builtin".to_string(),
-                execution_target: match rt.exec_target_kind {
-                    ExecTargetKind::Host => "host".to_string(),
-                    ExecTargetKind::Docker => "docker".to_string(),
+                meta: ToolResultMeta {
+                    side_effects,
+                    bytes: None,
+                    exit_code: None,
+                    stderr_truncated: None,
+                    stdout_truncated: None,
+                    source: "builtin".to_string(),
+                    execution_target: match rt.exec_target_kind_for(side_effects) {
+                        ExecTargetKind::Host => "host".to_string(),
+                        ExecTargetKind::Docker => "docker".to_string(),
+                        ExecTargetKind::Ssh => "ssh".to_string(),
+                    },
+                    warnings: None,
+                    warnings_max: None,
+                    warnings_truncated: None,
+                    docker: None,
+                    write_protection: None,
+                    cwd: None,
+                    timed_out: None,
+                    dry_run: None,
                 },
-                warnings: None,
-                warnings_max: None,
-                warnings_truncated: None,
-                docker: None,
             },
-        },
+        }
+    };
+    let mut exec = match rt.tool_timeout_ms.filter(|ms| *ms > 0) {
+        None => dispatch.await,
+        Some(ms) => {
+            // Shell calls already carry `ms` as their process timeout; the
+            // grace lets the target kill the process (or container) and
+            // report it before this backstop abandons the call.
+            let grace = if matches!(side_effects, SideEffects::ShellExec) {
+                SHELL_TOOL_TIMEOUT_GRACE_MS
+            } else {
+                0
+            };
+            match tokio::time::timeout(
+                std::time::Duration::from_millis(ms.saturating_add(grace)),
+                dispatch,
+            )
+            .await
+            {
+                Ok(exec) => exec,
+                Err(_) => tool_timeout_exec(rt, side_effects, &tc.name, ms),
+            }
+        }
     };
-    envelope_to_message(to_tool_result_envelope_with_error(
+    if matches!(side_effects, SideEffects::FilesystemWrite) {
+        warn_write_outside_read_allowlist(rt, &normalized_args, &mut exec);
+    }
+    if let Some((store, paths)) = checkpoint_paths.filter(|_| exec.ok) {
+        if let Err(e) = store.after_write(&paths) {
+            exec.meta
+                .warnings
+                .get_or_insert_with(Vec::new)
+                .push(ToolWarningDetail {
+                    code: "write_checkpoint_failed".to_string(),
+                    path: paths.join(","),
+                    target: "CHECKPOINT".to_string(),
+                    reason: e.to_string(),
+                });
+        }
+    }
+    // Shell and read_file output was already redacted by the target, ahead
+    // of truncation; this pass covers every other tool's result.
+    let redaction_digests = redaction
+        .map(|pass| {
+            exec.content = pass.apply_to_content(std::mem::take(&mut exec.content));
+            pass.digests()
+        })
+        .filter(|digests| !digests.is_empty());
+    let mut envelope = to_tool_result_envelope_with_error(
         tc,
         "builtin",
         exec.ok,
@@ -249,7 +437,61 @@ pub async fn execute_tool_streaming(
         exec.truncated,
         exec.error,
         exec.meta,
-    ))
+    );
+    if let Some(digests) = redaction_digests {
+        envelope.redactions = Some(digests.len());
+        envelope.redaction_digests = Some(digests);
+    }
+    envelope_to_message(envelope)
+}
+
+/// Files a write call will touch, when `--checkpoint-writes` is on: its
+/// `path`, or every file named by a multi-file patch.
+fn checkpoint_write_paths(
+    rt: &ToolRuntime,
+    side_effects: SideEffects,
+    args: &Value,
+) -> Option<(Arc<crate::store::WriteCheckpointStore>, Vec<String>)> {
+    let store = rt.write_checkpoint.clone()?;
+    if !matches!(side_effects, SideEffects::FilesystemWrite) || rt.dry_run_writes {
+        return None;
+    }
+    let paths = match args.get("path").and_then(|v| v.as_str()) {
+        Some(path) if !path.is_empty() => vec![path.to_string()],
+        _ => crate::target::split_multi_file_patch(
+            args.get("patch")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+        )
+        .ok()?
+        .into_iter()
+        .map(|f| f.path)
+        .collect(),
+    };
+    Some((store, paths))
+}
+
+/// Writes are governed by their own flags, but a write the agent could not read
+/// back is worth surfacing when a read allowlist is active.
+fn warn_write_outside_read_allowlist(rt: &ToolRuntime, args: &Value, exec: &mut ToolExecution) {
+    let (Some(allowlist), Some(path)) = (
+        rt.read_allowlist.as_ref(),
+        args.get("path").and_then(|v| v.as_str()),
+    ) else {
+        return;
+    };
+    if !exec.ok || allowlist.allows(path) {
+        return;
+    }
+    exec.meta
+        .warnings
+        .get_or_insert_with(Vec::new)
+        .push(ToolWarningDetail {
+            code: "write_outside_read_allowlist".to_string(),
+            path: normalize_allowlist_path(path),
+            target: "UNREADABLE".to_string(),
+            reason: "path is not in the read allowlist".to_string(),
+        });
 }
 
 #[cfg(test)]
@@ -4,7 +4,7 @@ use crate::types::{SideEffects, ToolDef};
 
 pub fn tool_side_effects(tool_name: &str) -> SideEffects {
     match tool_name {
-        "list_dir" | "read_file" | "glob" | "grep" => SideEffects::FilesystemRead,
+        "list_dir" | "read_file" | "glob" | "grep" | "search" => SideEffects::FilesystemRead,
         "update_plan" => SideEffects::None,
         "shell" => SideEffects::ShellExec,
         "write_file" | "apply_patch" | "edit" | "str_replace" => SideEffects::FilesystemWrite,
@@ -28,10 +28,17 @@ pub fn builtin_tools_enabled(enable_write_tools: bool, enable_shell_tool: bool)
         },
         ToolDef {
             name: "read_file".to_string(),
-            description: "Read a UTF-8 text file (lossy decode allowed).".to_string(),
+            description: "Read a UTF-8 text file (lossy decode allowed). Set max_line_chars to cut long lines (e.g. minified files). To read part of a large file, pass offset_bytes/length_bytes or start_line/end_line (1-based, inclusive), not both.".to_string(),
             parameters: json!({
                 "type":"object",
-                "properties":{"path":{"type":"string"}},
+                "properties":{
+                    "path":{"type":"string"},
+                    "max_line_chars":{"type":"integer","minimum":1},
+                    "offset_bytes":{"type":"integer","minimum":0},
+                    "length_bytes":{"type":"integer","minimum":0},
+                    "start_line":{"type":"integer","minimum":1},
+                    "end_line":{"type":"integer","minimum":1}
+                },
                 "required":["path"]
             }),
             side_effects: SideEffects::FilesystemRead,
@@ -65,6 +72,22 @@ pub fn builtin_tools_enabled(enable_write_tools: bool, enable_shell_tool: bool)
             }),
             side_effects: SideEffects::FilesystemRead,
         },
+        ToolDef {
+            name: "search".to_string(),
+            description: "Find lines matching a pattern in workdir text files, e.g. where a symbol is used before editing. The pattern is literal unless regex is true. Returns path, line_number, and line for each match; skips .git, .localagent, target, and node_modules.".to_string(),
+            parameters: json!({
+                "type":"object",
+                "properties":{
+                    "pattern":{"type":"string"},
+                    "path":{"type":"string"},
+                    "regex":{"type":"boolean"},
+                    "case_insensitive":{"type":"boolean"},
+                    "max_results":{"type":"integer","minimum":1,"maximum":1000}
+                },
+                "required":["pattern"]
+            }),
+            side_effects: SideEffects::FilesystemRead,
+        },
         ToolDef {
             name: "update_plan".to_string(),
             description: "Update the current in-run plan. Provide the full current list of steps with status pending, in_progress, or completed; at most one item may be in_progress.".to_string(),
@@ -94,13 +117,14 @@ pub fn builtin_tools_enabled(enable_write_tools: bool, enable_shell_tool: bool)
     if enable_shell_tool {
         tools.push(ToolDef {
             name: "shell".to_string(),
-            description: "Run a shell command with optional args, cwd, and timeout_ms. timeout_ms bounds the command's wall-clock runtime in milliseconds. If omitted, a safe default timeout (120000 ms) is applied on the host target; pass 0 to opt out and run unbounded.".to_string(),
+            description: "Run a shell command with optional args, cwd, and timeout_ms. timeout_ms bounds the command's wall-clock runtime in milliseconds. If omitted, a safe default timeout (120000 ms) is applied on the host target; pass 0 to opt out and run unbounded. cwd must already exist unless create_cwd is true (requires write access).".to_string(),
             parameters: json!({
                 "type":"object",
                 "properties":{
                     "cmd":{"type":"string"},
                     "args":{"type":"array","items":{"type":"string"}},
                     "cwd":{"type":"string"},
+                    "create_cwd":{"type":"boolean"},
                     "timeout_ms":{"type":"integer","minimum":0}
                 },
                 "required":["cmd"]
@@ -126,11 +150,11 @@ pub fn builtin_tools_enabled(enable_write_tools: bool, enable_shell_tool: bool)
         });
         tools.push(ToolDef {
             name: "apply_patch".to_string(),
-            description: "Apply a unified diff patch to an existing file using a workdir-relative path. Prefer this for larger or multi-hunk edits, or after edit/str_replace exact-match repair fails.".to_string(),
+            description: "Apply a unified diff patch to an existing file using a workdir-relative path. Prefer this for larger or multi-hunk edits, or after edit/str_replace exact-match repair fails. To change several files at once, omit path and pass a full unified diff with --- a/<file> / +++ b/<file> headers; either every file is updated or none is.".to_string(),
             parameters: json!({
                 "type":"object",
                 "properties":{"path":{"type":"string"},"patch":{"type":"string"}},
-                "required":["path","patch"]
+                "required":["patch"]
             }),
             side_effects: SideEffects::FilesystemWrite,
         });
@@ -169,6 +193,27 @@ pub fn builtin_tools_enabled(enable_write_tools: bool, enable_shell_tool: bool)
     tools
 }
 
+/// Opt-in tool that runs a bounded child agent; executed by the agent
+/// itself rather than [`super::execute_tool`].
+pub fn spawn_subtask_tool_def() -> ToolDef {
+    ToolDef {
+        name: "spawn_subtask".to_string(),
+        description: "Delegate a self-contained subtask to a child agent run and get back its final_output, exit_reason, and run_id. allowed_tools must be a subset of your own tools (default: all of them except spawn_subtask). The child gets at most half of your remaining tool-call and wall-time budget; max_steps, max_tool_calls, and max_wall_time_ms can lower its caps further.".to_string(),
+        parameters: json!({
+            "type":"object",
+            "properties":{
+                "prompt":{"type":"string"},
+                "allowed_tools":{"type":"array","items":{"type":"string"}},
+                "max_steps":{"type":"integer","minimum":1},
+                "max_tool_calls":{"type":"integer","minimum":1},
+                "max_wall_time_ms":{"type":"integer","minimum":1}
+            },
+            "required":["prompt"]
+        }),
+        side_effects: SideEffects::None,
+    }
+}
+
 pub(crate) fn normalize_builtin_tool_args(tool_name: &str, args: &Value) -> Value {
     let Some(obj) = args.as_object() else {
         return args.clone();
@@ -35,6 +35,8 @@ pub fn to_tool_result_envelope_with_error(
         truncate_reason: None,
         full_output_ref: None,
         error,
+        redactions: None,
+        redaction_digests: None,
         meta,
     }
 }
@@ -76,6 +78,10 @@ pub fn invalid_args_tool_message(
             warnings_max: None,
             warnings_truncated: None,
             docker: None,
+            write_protection: None,
+            cwd: None,
+            timed_out: None,
+            dry_run: None,
         },
     ))
 }
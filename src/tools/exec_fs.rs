@@ -5,14 +5,20 @@ use globset::Glob;
 use regex::RegexBuilder;
 use serde_json::{json, Value};
 
-use crate::target::{ListReq, ReadReq};
+use crate::store::{parse_artifact_ref, ARTIFACT_REF_PREFIX};
+use crate::target::{
+    build_search_regex, truncate_utf8_to_bytes, ExecTargetKind, FsEntityErrorKind, ListReq,
+    ReadReq, SearchReq,
+};
 use crate::types::SideEffects;
 
 use super::exec_support::{
-    base_meta, failed_exec, has_git_segment, path_is_workdir_scoped, target_to_exec, ToolExecution,
+    base_meta, failed_exec, has_git_segment, path_is_workdir_scoped, target_to_exec,
+    workdir_escape_denied, ToolExecution,
 };
 use super::{
-    invalid_args_detail, ToolErrorCode, ToolErrorDetail, ToolResultMeta, ToolRuntime,
+    invalid_args_detail, minimal_builtin_example, normalize_allowlist_path, parse_read_range,
+    ReadAllowlist, RedactionPass, ToolErrorCode, ToolErrorDetail, ToolResultMeta, ToolRuntime,
     ToolWarningDetail,
 };
 
@@ -38,6 +44,14 @@ pub(super) async fn run_list_dir(rt: &ToolRuntime, args: &Value) -> ToolExecutio
             }),
         );
     }
+    if let Some(denied) = workdir_escape_denied(rt, SideEffects::FilesystemRead, path, args) {
+        return denied;
+    }
+    if let Some(allowlist) = &rt.read_allowlist {
+        if !allowlist.may_contain(path) {
+            return read_allowlist_denied(rt, path, args);
+        }
+    }
     let out = rt
         .exec_target
         .list_dir(ListReq {
@@ -45,11 +59,123 @@ pub(super) async fn run_list_dir(rt: &ToolRuntime, args: &Value) -> ToolExecutio
             path: path.to_string(),
         })
         .await;
-    target_to_exec(SideEffects::FilesystemRead, out)
+    let mut exec = target_to_exec(SideEffects::FilesystemRead, out);
+    if let Some(allowlist) = &rt.read_allowlist {
+        if exec.ok {
+            exec.content = filter_listing(allowlist, path, &exec.content);
+        }
+    }
+    exec
+}
+
+/// Drops listing entries outside the allowlist and reports how many were
+/// hidden, so the model knows the listing is partial.
+fn filter_listing(allowlist: &ReadAllowlist, dir: &str, content: &str) -> String {
+    let Ok(mut listing) = serde_json::from_str::<Value>(content) else {
+        return content.to_string();
+    };
+    let dir = normalize_allowlist_path(dir);
+    let mut filtered = 0usize;
+    if let Some(entries) = listing.get_mut("entries").and_then(|v| v.as_array_mut()) {
+        entries.retain(|entry| {
+            let name = entry.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            let child = if dir == "." {
+                name.to_string()
+            } else {
+                format!("{dir}/{name}")
+            };
+            let is_dir = entry.get("is_dir").and_then(|v| v.as_bool()) == Some(true);
+            let keep = if is_dir {
+                allowlist.may_contain(&child)
+            } else {
+                allowlist.allows(&child)
+            };
+            if !keep {
+                filtered += 1;
+            }
+            keep
+        });
+    }
+    if let Some(obj) = listing.as_object_mut() {
+        obj.insert("filtered".to_string(), json!(filtered));
+    }
+    listing.to_string()
+}
+
+fn read_allowlist_denied(rt: &ToolRuntime, path: &str, args: &Value) -> ToolExecution {
+    failed_exec(
+        rt,
+        SideEffects::FilesystemRead,
+        format!(
+            "E_PATH_NOT_IN_READ_ALLOWLIST: '{path}' is outside the read allowlist. Only these paths may be read: {}",
+            rt.read_allowlist
+                .as_ref()
+                .map(|a| a.globs().join(", "))
+                .unwrap_or_default()
+        ),
+        Some(ToolErrorDetail {
+            code: ToolErrorCode::PathNotInReadAllowlist,
+            message: "Path is not in the read allowlist.".to_string(),
+            expected_schema: None,
+            received_args: Some(args.clone()),
+            minimal_example: None,
+            available_tools: None,
+        }),
+    )
+}
+
+/// Maps the target's entity-type failure content to a structured error whose
+/// example points at the tool that fits the path.
+pub(super) fn classify_fs_entity_error(content: &str) -> Option<ToolErrorDetail> {
+    let v = serde_json::from_str::<Value>(content).ok()?;
+    let kind = FsEntityErrorKind::from_code(v.get("error")?.as_str()?)?;
+    let path = v.get("path").and_then(|p| p.as_str()).unwrap_or(".");
+    let (code, message, minimal_example) = match kind {
+        FsEntityErrorKind::IsDirectory => (
+            ToolErrorCode::IsDirectory,
+            "Path is a directory; use list_dir to see its entries.",
+            Some(json!({"path": path})),
+        ),
+        FsEntityErrorKind::NotADirectory => (
+            ToolErrorCode::NotADirectory,
+            "Path is not a directory; use read_file for regular files.",
+            Some(json!({"path": path})),
+        ),
+        FsEntityErrorKind::NotFound => (
+            ToolErrorCode::NotFound,
+            "Path does not exist; list the nearest existing directory to find it.",
+            Some(json!({
+                "path": v
+                    .get("nearest_existing_ancestor")
+                    .and_then(|a| a.as_str())
+                    .unwrap_or(".")
+            })),
+        ),
+        FsEntityErrorKind::SpecialFile => (
+            ToolErrorCode::SpecialFile,
+            "Path is a socket, device, or fifo; only regular files can be read.",
+            minimal_builtin_example("list_dir"),
+        ),
+    };
+    Some(ToolErrorDetail {
+        code,
+        message: message.to_string(),
+        expected_schema: None,
+        received_args: None,
+        minimal_example,
+        available_tools: None,
+    })
 }
 
-pub(super) async fn run_read_file(rt: &ToolRuntime, args: &Value) -> ToolExecution {
+pub(super) async fn run_read_file(
+    rt: &ToolRuntime,
+    args: &Value,
+    redaction: Option<&RedactionPass>,
+) -> ToolExecution {
     let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+    if path.starts_with(ARTIFACT_REF_PREFIX) {
+        return read_run_artifact(rt, path, args, redaction).await;
+    }
     if !path_is_workdir_scoped(path) && !rt.unsafe_bypass_allow_flags {
         return failed_exec(
             rt,
@@ -65,18 +191,129 @@ pub(super) async fn run_read_file(rt: &ToolRuntime, args: &Value) -> ToolExecuti
             }),
         );
     }
+    if let Some(denied) = workdir_escape_denied(rt, SideEffects::FilesystemRead, path, args) {
+        return denied;
+    }
+    if let Some(allowlist) = &rt.read_allowlist {
+        if !allowlist.allows(path) {
+            return read_allowlist_denied(rt, path, args);
+        }
+    }
+    let range = match parse_read_range(args) {
+        Ok(range) => range,
+        Err(e) => {
+            return failed_exec(
+                rt,
+                SideEffects::FilesystemRead,
+                format!("invalid tool arguments: {e}"),
+                Some(invalid_args_detail("read_file", args, &e)),
+            );
+        }
+    };
     let out = rt
         .exec_target
         .read_file(ReadReq {
             workdir: rt.workdir.clone(),
             path: path.to_string(),
             max_read_bytes: rt.max_read_bytes,
+            max_line_chars: args
+                .get("max_line_chars")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize),
+            range,
+            redaction: redaction.cloned(),
         })
         .await;
     target_to_exec(SideEffects::FilesystemRead, out)
 }
 
+/// Reads a stored run artifact on the host. Artifacts live in the state dir
+/// rather than the workdir, so the read allowlist does not apply to them.
+async fn read_run_artifact(
+    rt: &ToolRuntime,
+    path: &str,
+    args: &Value,
+    redaction: Option<&RedactionPass>,
+) -> ToolExecution {
+    let resolved = match (parse_artifact_ref(path), rt.run_artifacts.as_deref()) {
+        (Some(hash), Some(store)) => store.resolve(hash),
+        (None, _) => Err(anyhow::anyhow!(
+            "invalid artifact reference '{path}'; expected artifact:<sha256>"
+        )),
+        (Some(_), None) => Err(anyhow::anyhow!(
+            "artifact references are not available in this runtime"
+        )),
+    };
+    let (full, entry) = match resolved {
+        Ok(v) => v,
+        Err(e) => {
+            return failed_exec(
+                rt,
+                SideEffects::FilesystemRead,
+                format!("read_file failed: {e}"),
+                Some(ToolErrorDetail {
+                    code: ToolErrorCode::ArtifactNotFound,
+                    message: e.to_string(),
+                    expected_schema: None,
+                    received_args: Some(args.clone()),
+                    minimal_example: None,
+                    available_tools: None,
+                }),
+            )
+        }
+    };
+    match tokio::fs::read(&full).await {
+        Ok(bytes) => {
+            let raw = String::from_utf8_lossy(&bytes);
+            let raw = match redaction {
+                Some(pass) => pass.apply(&raw).into(),
+                None => raw,
+            };
+            let (content, truncated) = truncate_utf8_to_bytes(&raw, rt.max_read_bytes);
+            let mut meta = base_meta(rt, SideEffects::FilesystemRead);
+            meta.bytes = Some(bytes.len() as u64);
+            ToolExecution {
+                ok: true,
+                content: json!({
+                    "path": path,
+                    "content_type": entry.content_type,
+                    "content": content,
+                    "truncated": truncated,
+                    "max_read_bytes": rt.max_read_bytes,
+                    "read_bytes": bytes.len()
+                })
+                .to_string(),
+                truncated,
+                error: None,
+                meta,
+            }
+        }
+        Err(e) => failed_exec(
+            rt,
+            SideEffects::FilesystemRead,
+            format!("read_file failed for {path}: {e}"),
+            None,
+        ),
+    }
+}
+
+/// `glob` and `grep` walk the local workdir, which an ssh target's remote
+/// host does not share.
+fn ssh_local_walk_refused(rt: &ToolRuntime, tool: &str) -> Option<ToolExecution> {
+    (rt.exec_target_kind_for(SideEffects::FilesystemRead) == ExecTargetKind::Ssh).then(|| {
+        failed_exec(
+            rt,
+            SideEffects::FilesystemRead,
+            format!("{tool} is not supported on the ssh exec target; use shell with find or grep on the remote host"),
+            None,
+        )
+    })
+}
+
 pub(super) async fn run_glob(rt: &ToolRuntime, args: &Value) -> ToolExecution {
+    if let Some(refused) = ssh_local_walk_refused(rt, "glob") {
+        return refused;
+    }
     let pattern = match args.get("pattern").and_then(|v| v.as_str()) {
         Some(s) if !s.is_empty() => s,
         _ => {
@@ -161,6 +398,9 @@ pub(super) async fn run_glob(rt: &ToolRuntime, args: &Value) -> ToolExecution {
 }
 
 pub(super) async fn run_grep(rt: &ToolRuntime, args: &Value) -> ToolExecution {
+    if let Some(refused) = ssh_local_walk_refused(rt, "grep") {
+        return refused;
+    }
     let pattern = match args.get("pattern").and_then(|v| v.as_str()) {
         Some(s) if !s.is_empty() => s,
         _ => {
@@ -299,6 +539,89 @@ pub(super) async fn run_grep(rt: &ToolRuntime, args: &Value) -> ToolExecution {
     }
 }
 
+pub(super) async fn run_search(rt: &ToolRuntime, args: &Value) -> ToolExecution {
+    let pattern = args
+        .get("pattern")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    if pattern.is_empty() {
+        return failed_exec(
+            rt,
+            SideEffects::FilesystemRead,
+            "invalid tool arguments: pattern must be a non-empty string".to_string(),
+            Some(invalid_args_detail(
+                "search",
+                args,
+                "pattern must be a non-empty string",
+            )),
+        );
+    }
+    let max_results = match max_results_from_args(args) {
+        Ok(v) => v,
+        Err(e) => {
+            return failed_exec(
+                rt,
+                SideEffects::FilesystemRead,
+                format!("invalid tool arguments: {e}"),
+                Some(invalid_args_detail("search", args, &e)),
+            )
+        }
+    };
+    let path = search_path_from_args(args);
+    if !path_is_workdir_scoped(path) && !rt.unsafe_bypass_allow_flags {
+        return failed_exec(
+            rt,
+            SideEffects::FilesystemRead,
+            "path must stay within workdir (no absolute paths or '..' traversal). Use a workdir-relative path like '.'.".to_string(),
+            Some(ToolErrorDetail {
+                code: ToolErrorCode::PathOutOfScope,
+                message: "Path must stay within workdir. Use a workdir-relative path.".to_string(),
+                expected_schema: None,
+                received_args: Some(args.clone()),
+                minimal_example: super::minimal_builtin_example("search"),
+                available_tools: None,
+            }),
+        );
+    }
+    if let Some(denied) = workdir_escape_denied(rt, SideEffects::FilesystemRead, path, args) {
+        return denied;
+    }
+    let regex = args.get("regex").and_then(|v| v.as_bool()).unwrap_or(false);
+    let case_insensitive = args
+        .get("case_insensitive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if let Err(e) = build_search_regex(pattern, regex, case_insensitive) {
+        return failed_exec(
+            rt,
+            SideEffects::FilesystemRead,
+            format!("invalid pattern: {e}"),
+            Some(ToolErrorDetail {
+                code: ToolErrorCode::InvalidPattern,
+                message: format!("Invalid pattern: {e}"),
+                expected_schema: super::compact_builtin_schema("search"),
+                received_args: Some(args.clone()),
+                minimal_example: super::minimal_builtin_example("search"),
+                available_tools: None,
+            }),
+        );
+    }
+    let out = rt
+        .exec_target
+        .search(SearchReq {
+            workdir: rt.workdir.clone(),
+            path: path.to_string(),
+            pattern: pattern.to_string(),
+            regex,
+            case_insensitive,
+            max_results,
+            max_output_bytes: rt.max_tool_output_bytes,
+            read_allowlist: rt.read_allowlist.clone(),
+        })
+        .await;
+    target_to_exec(SideEffects::FilesystemRead, out)
+}
+
 fn search_path_from_args(args: &Value) -> &str {
     args.get("path").and_then(|v| v.as_str()).unwrap_or(".")
 }
@@ -358,6 +681,14 @@ fn collect_search_files(rt: &ToolRuntime, search_path: &str) -> CollectSearchFil
             }),
         )));
     }
+    if let Some(denied) = workdir_escape_denied(
+        rt,
+        SideEffects::FilesystemRead,
+        search_path,
+        &json!({"path": search_path}),
+    ) {
+        return Err(Box::new(denied));
+    }
 
     let base = rt.workdir.join(search_path);
     if !base.exists() {
@@ -425,6 +756,13 @@ fn collect_search_files(rt: &ToolRuntime, search_path: &str) -> CollectSearchFil
             if rel != "." && has_git_segment(Path::new(&rel)) {
                 continue;
             }
+            if rt
+                .read_allowlist
+                .as_ref()
+                .is_some_and(|a| !a.may_contain(&rel))
+            {
+                continue;
+            }
             let canonical_dir = std::fs::canonicalize(&current).unwrap_or_else(|_| current.clone());
             if !seen_dirs.insert(canonical_dir) {
                 continue;
@@ -465,6 +803,9 @@ fn collect_search_files(rt: &ToolRuntime, search_path: &str) -> CollectSearchFil
             if rel != "." && has_git_segment(Path::new(&rel)) {
                 continue;
             }
+            if rt.read_allowlist.as_ref().is_some_and(|a| !a.allows(&rel)) {
+                continue;
+            }
             files.push((rel, current));
         }
     }
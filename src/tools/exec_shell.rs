@@ -1,10 +1,12 @@
 use serde_json::Value;
 
-use crate::target::{ExecTargetKind, ShellOutputTx, ShellReq, TargetResult};
+use crate::target::{
+    ExecTargetKind, ShellOutputTx, ShellReq, TargetResult, SHELL_CWD_NOT_FOUND_PREFIX,
+};
 use crate::types::SideEffects;
 
 use super::exec_support::{failed_exec, ToolExecution};
-use super::{minimal_builtin_example, ToolErrorCode, ToolErrorDetail, ToolRuntime};
+use super::{minimal_builtin_example, RedactionPass, ToolErrorCode, ToolErrorDetail, ToolRuntime};
 
 /// Default wall-clock timeout (ms) applied to a shell command on the host target
 /// when the caller omits `timeout_ms`. This keeps unattended/autonomous runs
@@ -17,29 +19,41 @@ pub(super) const DEFAULT_SHELL_TIMEOUT_MS: u64 = 120_000;
 /// target.
 ///
 /// - Explicit `timeout_ms` (including `0` = unbounded opt-out): honored as-is.
-/// - Missing `timeout_ms`: the host target applies [`DEFAULT_SHELL_TIMEOUT_MS`].
-///   The docker target instead resolves to `0` (unbounded) because it cannot
-///   enforce timeouts (`DockerTarget::exec_shell` rejects `timeout_ms > 0`), so a
-///   *missing* timeout must not silently turn into a rejection.
+/// - Missing `timeout_ms`: the host and ssh targets apply
+///   [`DEFAULT_SHELL_TIMEOUT_MS`].
+///   The docker target instead resolves to `0` (unbounded) so container runs
+///   keep their historical default; a runtime `tool_timeout_ms` still bounds
+///   them (see [`cap_shell_timeout_ms`]).
 pub(super) fn resolve_shell_timeout_ms(args: &Value, target: ExecTargetKind) -> u64 {
     match args.get("timeout_ms").and_then(|v| v.as_u64()) {
         Some(explicit) => explicit,
         None => match target {
-            ExecTargetKind::Host => DEFAULT_SHELL_TIMEOUT_MS,
+            ExecTargetKind::Host | ExecTargetKind::Ssh => DEFAULT_SHELL_TIMEOUT_MS,
             ExecTargetKind::Docker => 0,
         },
     }
 }
 
+/// Bound a resolved shell timeout by the runtime's per-tool timeout so the
+/// target kills the process itself instead of the call being abandoned.
+pub(super) fn cap_shell_timeout_ms(timeout_ms: u64, tool_timeout_ms: Option<u64>) -> u64 {
+    match tool_timeout_ms.filter(|ms| *ms > 0) {
+        Some(cap) if timeout_ms == 0 || timeout_ms > cap => cap,
+        _ => timeout_ms,
+    }
+}
+
 pub(super) async fn run_shell(
     rt: &ToolRuntime,
     args: &Value,
     stream: Option<ShellOutputTx>,
+    redaction: Option<&RedactionPass>,
 ) -> ToolExecution {
-    // Live streaming is host-only; the docker target ignores ShellReq.stream.
-    let stream = match rt.exec_target_kind {
+    // Live streaming is host-only; the docker and ssh targets ignore
+    // ShellReq.stream.
+    let stream = match rt.exec_target_kind_for(SideEffects::ShellExec) {
         ExecTargetKind::Host => stream,
-        ExecTargetKind::Docker => None,
+        ExecTargetKind::Docker | ExecTargetKind::Ssh => None,
     };
     let shell_allowed =
         rt.allow_shell || (rt.allow_shell_in_workdir_only && shell_cwd_is_workdir_scoped(args));
@@ -60,6 +74,67 @@ pub(super) async fn run_shell(
         );
     }
     let cmd = args.get("cmd").and_then(|v| v.as_str()).unwrap_or_default();
+    if let Some(allowlist) = rt.shell_allowlist.as_ref() {
+        if !shell_program_allowlisted(allowlist, cmd) && !rt.unsafe_bypass_allow_flags {
+            return failed_exec(
+                rt,
+                SideEffects::ShellExec,
+                format!(
+                    "shell command '{}' not in allowlist. Allowed programs: {}",
+                    shell_program_name(cmd),
+                    if allowlist.is_empty() {
+                        "(none)".to_string()
+                    } else {
+                        allowlist.join(", ")
+                    }
+                ),
+                Some(ToolErrorDetail {
+                    code: ToolErrorCode::ShellGateDeny,
+                    message: "Shell command is not in the shell allowlist.".to_string(),
+                    expected_schema: None,
+                    received_args: Some(args.clone()),
+                    minimal_example: None,
+                    available_tools: None,
+                }),
+            );
+        }
+    }
+    if !shell_cwd_is_workdir_scoped(args) && !rt.unsafe_bypass_allow_flags {
+        return failed_exec(
+            rt,
+            SideEffects::ShellExec,
+            "shell cwd must stay within workdir (no absolute paths or '..' traversal). Use a workdir-relative directory like 'src'.".to_string(),
+            Some(ToolErrorDetail {
+                code: ToolErrorCode::ToolPathDenied,
+                message: "Shell cwd must stay within workdir. Use a workdir-relative path."
+                    .to_string(),
+                expected_schema: None,
+                received_args: Some(args.clone()),
+                minimal_example: minimal_builtin_example("shell"),
+                available_tools: None,
+            }),
+        );
+    }
+    let create_cwd = args
+        .get("create_cwd")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if create_cwd && !rt.allow_write && !rt.unsafe_bypass_allow_flags {
+        return failed_exec(
+            rt,
+            SideEffects::ShellExec,
+            "create_cwd requires --allow-write".to_string(),
+            Some(ToolErrorDetail {
+                code: ToolErrorCode::ToolDisabled,
+                message: "Creating the shell cwd needs write tools enabled by runtime flags."
+                    .to_string(),
+                expected_schema: None,
+                received_args: Some(args.clone()),
+                minimal_example: minimal_builtin_example("shell"),
+                available_tools: None,
+            }),
+        );
+    }
     let arg_list = args
         .get("args")
         .and_then(|v| v.as_array())
@@ -76,8 +151,12 @@ pub(super) async fn run_shell(
     // `timeout_ms` is optional. When omitted, the host target applies a safe
     // default so unattended runs cannot hang forever; an explicit `0` opts out
     // (unbounded). The `as_u64` parse makes negative values unrepresentable.
-    // Target-aware so a missing timeout never turns into a docker rejection.
-    let timeout_ms = resolve_shell_timeout_ms(args, rt.exec_target_kind);
+    // Target-aware: docker keeps an unbounded default. Either way the
+    // runtime's per-tool timeout caps it.
+    let timeout_ms = cap_shell_timeout_ms(
+        resolve_shell_timeout_ms(args, rt.exec_target_kind_for(SideEffects::ShellExec)),
+        rt.tool_timeout_ms,
+    );
     let req = ShellReq {
         workdir: rt.workdir.clone(),
         cmd: cmd.to_string(),
@@ -86,6 +165,8 @@ pub(super) async fn run_shell(
         max_tool_output_bytes: rt.max_tool_output_bytes,
         timeout_ms,
         stream: stream.clone(),
+        create_cwd,
+        redaction: redaction.cloned(),
     };
     let mut out = rt.exec_target.exec_shell(req).await;
     if !out.ok && shell_spawn_not_found(&out.content) {
@@ -102,6 +183,8 @@ pub(super) async fn run_shell(
                     max_tool_output_bytes: rt.max_tool_output_bytes,
                     timeout_ms,
                     stream,
+                    create_cwd,
+                    redaction: redaction.cloned(),
                 })
                 .await;
             out = annotate_shell_repair(repaired, repair_strategy);
@@ -114,6 +197,17 @@ pub(super) fn classify_shell_target_error(
     content: &str,
     exit_code: Option<i32>,
 ) -> ToolErrorDetail {
+    if content.starts_with(SHELL_CWD_NOT_FOUND_PREFIX) {
+        return ToolErrorDetail {
+            code: ToolErrorCode::ShellCwdNotFound,
+            message: "Shell cwd does not exist on the execution target; the command was not run."
+                .to_string(),
+            expected_schema: None,
+            received_args: None,
+            minimal_example: minimal_builtin_example("list_dir"),
+            available_tools: None,
+        };
+    }
     if let Some(detail) = timeout_unsupported_error_from_content(content) {
         return detail;
     }
@@ -219,8 +313,8 @@ fn shell_spawn_not_found(content: &str) -> bool {
 }
 
 fn is_windows_exec_target(rt: &ToolRuntime) -> bool {
-    match rt.exec_target_kind {
-        ExecTargetKind::Docker => false,
+    match rt.exec_target_kind_for(SideEffects::ShellExec) {
+        ExecTargetKind::Docker | ExecTargetKind::Ssh => false,
         ExecTargetKind::Host => cfg!(windows),
     }
 }
@@ -340,6 +434,22 @@ fn annotate_shell_repair(mut out: TargetResult, strategy: &str) -> TargetResult
     out
 }
 
+/// Basename of a shell `cmd`, so `/usr/bin/git` and `git` name the same
+/// program.
+fn shell_program_name(cmd: &str) -> &str {
+    cmd.trim().rsplit(['/', '\\']).next().unwrap_or_default()
+}
+
+/// Whether `cmd` names a program in the shell allowlist. Both sides are
+/// compared by basename; an empty allowlist matches nothing.
+pub fn shell_program_allowlisted(allowlist: &[String], cmd: &str) -> bool {
+    let program = shell_program_name(cmd);
+    !program.is_empty()
+        && allowlist
+            .iter()
+            .any(|entry| shell_program_name(entry) == program)
+}
+
 fn shell_cwd_is_workdir_scoped(args: &Value) -> bool {
     let Some(cwd) = args.get("cwd") else {
         return true;
@@ -363,7 +473,7 @@ fn shell_cwd_is_workdir_scoped(args: &Value) -> bool {
 
 #[cfg(test)]
 mod timeout_policy_tests {
-    use super::{resolve_shell_timeout_ms, DEFAULT_SHELL_TIMEOUT_MS};
+    use super::{cap_shell_timeout_ms, resolve_shell_timeout_ms, DEFAULT_SHELL_TIMEOUT_MS};
     use crate::target::ExecTargetKind;
     use serde_json::json;
 
@@ -381,7 +491,7 @@ mod timeout_policy_tests {
     fn explicit_positive_timeout_overrides_default() {
         let args = json!({ "cmd": "echo", "timeout_ms": 500 });
         assert_eq!(resolve_shell_timeout_ms(&args, ExecTargetKind::Host), 500);
-        // Explicit values are honored on docker too (DockerTarget rejects > 0).
+        // Explicit values are honored on docker too.
         assert_eq!(resolve_shell_timeout_ms(&args, ExecTargetKind::Docker), 500);
     }
 
@@ -393,10 +503,18 @@ mod timeout_policy_tests {
     }
 
     #[test]
-    fn missing_timeout_on_docker_stays_unbounded_not_rejected() {
-        // Critical: a missing timeout must not become a docker timeout
-        // rejection. Docker resolves missing -> 0 (unbounded), unlike host.
+    fn missing_timeout_on_docker_stays_unbounded() {
+        // Docker resolves missing -> 0 (unbounded), unlike host.
         let args = json!({ "cmd": "echo" });
         assert_eq!(resolve_shell_timeout_ms(&args, ExecTargetKind::Docker), 0);
     }
+
+    #[test]
+    fn tool_timeout_caps_unbounded_and_longer_shell_timeouts() {
+        assert_eq!(cap_shell_timeout_ms(0, Some(5_000)), 5_000);
+        assert_eq!(cap_shell_timeout_ms(120_000, Some(5_000)), 5_000);
+        assert_eq!(cap_shell_timeout_ms(500, Some(5_000)), 500);
+        assert_eq!(cap_shell_timeout_ms(0, None), 0);
+        assert_eq!(cap_shell_timeout_ms(500, Some(0)), 500);
+    }
 }
@@ -1,9 +1,11 @@
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
-use crate::target::{ExecTargetKind, TargetResult};
+use crate::target::{ExecTargetKind, TargetResult, SSH_CONNECTION_FAILED_PREFIX};
 use crate::types::SideEffects;
 
-use super::{ToolErrorDetail, ToolResultMeta, ToolRuntime};
+use serde_json::{json, Value};
+
+use super::{ToolErrorCode, ToolErrorDetail, ToolResultMeta, ToolRuntime};
 
 #[derive(Debug, Clone)]
 pub(super) struct ToolExecution {
@@ -36,20 +38,141 @@ pub(super) fn path_is_workdir_scoped(path: &str) -> bool {
     })
 }
 
+/// Where `path` lands once symlinks are followed: the deepest existing
+/// ancestor is canonicalized and the rest is applied lexically, so paths to
+/// files that do not exist yet can be checked too.
+pub(crate) fn resolve_following_symlinks(workdir: &Path, path: &str) -> Option<PathBuf> {
+    let joined = crate::target::resolve_path(workdir, path);
+    let components = joined.components().collect::<Vec<_>>();
+    for split in (1..=components.len()).rev() {
+        let prefix = components[..split].iter().collect::<PathBuf>();
+        let Ok(mut resolved) = std::fs::canonicalize(&prefix) else {
+            continue;
+        };
+        for component in &components[split..] {
+            match component {
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                Component::Normal(name) => resolved.push(name),
+                _ => {}
+            }
+        }
+        return Some(resolved);
+    }
+    None
+}
+
+/// Denies a filesystem tool call whose path resolves outside the workdir,
+/// including through a symlink, when `restrict_to_workdir` is on. A path
+/// that cannot be resolved, or a workdir that cannot be canonicalized, is
+/// denied too, since containment cannot be checked.
+pub(super) fn workdir_escape_denied(
+    rt: &ToolRuntime,
+    side_effects: SideEffects,
+    path: &str,
+    args: &Value,
+) -> Option<ToolExecution> {
+    if !rt.restrict_to_workdir {
+        return None;
+    }
+    let (resolved_path, detail, message) = match std::fs::canonicalize(&rt.workdir) {
+        Ok(workdir) => match resolve_following_symlinks(&workdir, path) {
+            Some(resolved) if resolved.starts_with(&workdir) => return None,
+            Some(resolved) => (
+                Some(resolved.display().to_string()),
+                "path resolves outside the workdir".to_string(),
+                "Path resolves outside the workdir (symlinks are followed). Use a workdir-relative path.",
+            ),
+            None => (
+                None,
+                "path could not be resolved".to_string(),
+                "Path could not be resolved, so it cannot be checked against the workdir.",
+            ),
+        },
+        Err(e) => (
+            None,
+            format!("workdir could not be resolved: {e}"),
+            "Workdir could not be resolved, so the path cannot be checked against it.",
+        ),
+    };
+    Some(failed_exec(
+        rt,
+        side_effects,
+        json!({
+            "error": "E_PATH_ESCAPE",
+            "path": path,
+            "resolved_path": resolved_path,
+            "detail": detail
+        })
+        .to_string(),
+        Some(ToolErrorDetail {
+            code: ToolErrorCode::PathEscape,
+            message: message.to_string(),
+            expected_schema: None,
+            received_args: Some(args.clone()),
+            minimal_example: None,
+            available_tools: None,
+        }),
+    ))
+}
+
+/// Artifact references are read-only. Without this check a write to
+/// `artifact:<hash>` would create a workdir file of that name.
+pub(super) fn artifact_write_denied(
+    rt: &ToolRuntime,
+    path: &str,
+    args: &Value,
+) -> Option<ToolExecution> {
+    if !path.starts_with(crate::store::ARTIFACT_REF_PREFIX) {
+        return None;
+    }
+    Some(failed_exec(
+        rt,
+        SideEffects::FilesystemWrite,
+        format!("'{path}' is a read-only run artifact; write to a workdir-relative path instead"),
+        Some(ToolErrorDetail {
+            code: ToolErrorCode::ArtifactReadOnly,
+            message: "Run artifacts are read-only.".to_string(),
+            expected_schema: None,
+            received_args: Some(args.clone()),
+            minimal_example: None,
+            available_tools: None,
+        }),
+    ))
+}
+
 pub(super) fn target_to_exec(side_effects: SideEffects, out: TargetResult) -> ToolExecution {
-    let shell_error = if matches!(side_effects, SideEffects::ShellExec) && !out.ok {
-        Some(super::exec_shell::classify_shell_target_error(
+    let error = match side_effects {
+        _ if out.ok => None,
+        // Checked first: the ssh client's own stderr (e.g. "Permission
+        // denied") would otherwise read as the tool's failure.
+        _ if out.content.starts_with(SSH_CONNECTION_FAILED_PREFIX) => Some(ToolErrorDetail {
+            code: ToolErrorCode::SshConnectionFailed,
+            message:
+                "The ssh client could not reach, log into or stay connected to the remote host."
+                    .to_string(),
+            expected_schema: None,
+            received_args: None,
+            minimal_example: None,
+            available_tools: None,
+        }),
+        SideEffects::ShellExec => Some(super::exec_shell::classify_shell_target_error(
             &out.content,
             out.exit_code,
-        ))
-    } else {
-        None
+        )),
+        SideEffects::FilesystemRead => super::exec_fs::classify_fs_entity_error(&out.content),
+        _ => None,
     };
+    let timed_out = error
+        .as_ref()
+        .is_some_and(|e| e.code == ToolErrorCode::ShellExecTimeout)
+        .then_some(true);
     ToolExecution {
         ok: out.ok,
         content: out.content,
         truncated: out.truncated,
-        error: shell_error,
+        error,
         meta: ToolResultMeta {
             side_effects,
             bytes: out.bytes,
@@ -60,11 +183,16 @@ pub(super) fn target_to_exec(side_effects: SideEffects, out: TargetResult) -> To
             execution_target: match out.execution_target {
                 ExecTargetKind::Host => "host".to_string(),
                 ExecTargetKind::Docker => "docker".to_string(),
+                ExecTargetKind::Ssh => "ssh".to_string(),
             },
             warnings: None,
             warnings_max: None,
             warnings_truncated: None,
             docker: out.docker,
+            write_protection: out.write_protection,
+            cwd: out.cwd,
+            timed_out,
+            dry_run: None,
         },
     }
 }
@@ -77,14 +205,19 @@ pub(super) fn base_meta(rt: &ToolRuntime, side_effects: SideEffects) -> ToolResu
         stderr_truncated: None,
         stdout_truncated: None,
         source: "builtin".to_string(),
-        execution_target: match rt.exec_target_kind {
+        execution_target: match rt.exec_target_kind_for(side_effects) {
             ExecTargetKind::Host => "host".to_string(),
             ExecTargetKind::Docker => "docker".to_string(),
+            ExecTargetKind::Ssh => "ssh".to_string(),
         },
         warnings: None,
         warnings_max: None,
         warnings_truncated: None,
         docker: None,
+        write_protection: None,
+        cwd: None,
+        timed_out: None,
+        dry_run: None,
     }
 }
 
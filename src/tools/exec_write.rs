@@ -1,10 +1,16 @@
 use serde_json::Value;
 
-use crate::target::{PatchReq, ReadReq, WriteReq};
+use crate::target::{split_multi_file_patch, PatchReq, ReadReq, TargetResult, WriteReq};
 use crate::types::SideEffects;
 
-use super::exec_support::{failed_exec, path_is_workdir_scoped, target_to_exec, ToolExecution};
-use super::{minimal_builtin_example, ToolErrorCode, ToolErrorDetail, ToolResultMeta, ToolRuntime};
+use super::exec_support::{
+    artifact_write_denied, failed_exec, path_is_workdir_scoped, target_to_exec,
+    workdir_escape_denied, ToolExecution,
+};
+use super::{
+    invalid_args_detail, minimal_builtin_example, ToolErrorCode, ToolErrorDetail, ToolResultMeta,
+    ToolRuntime,
+};
 
 pub(super) async fn run_write_file(rt: &ToolRuntime, args: &Value) -> ToolExecution {
     if !rt.allow_write && !rt.unsafe_bypass_allow_flags {
@@ -26,6 +32,9 @@ pub(super) async fn run_write_file(rt: &ToolRuntime, args: &Value) -> ToolExecut
         .get("path")
         .and_then(|v| v.as_str())
         .unwrap_or_default();
+    if let Some(denied) = artifact_write_denied(rt, path, args) {
+        return denied;
+    }
     if !path_is_workdir_scoped(path) && !rt.unsafe_bypass_allow_flags {
         return failed_exec(
             rt,
@@ -41,6 +50,9 @@ pub(super) async fn run_write_file(rt: &ToolRuntime, args: &Value) -> ToolExecut
             }),
         );
     }
+    if let Some(denied) = workdir_escape_denied(rt, SideEffects::FilesystemWrite, path, args) {
+        return denied;
+    }
     let content = args
         .get("content")
         .and_then(|v| v.as_str())
@@ -60,6 +72,9 @@ pub(super) async fn run_write_file(rt: &ToolRuntime, args: &Value) -> ToolExecut
                 workdir: rt.workdir.clone(),
                 path: path.to_string(),
                 max_read_bytes: 1,
+                max_line_chars: None,
+                range: None,
+                redaction: None,
             })
             .await;
         if exists_probe.ok {
@@ -78,9 +93,10 @@ pub(super) async fn run_write_file(rt: &ToolRuntime, args: &Value) -> ToolExecut
             path: path.to_string(),
             content: content.to_string(),
             create_parents,
+            dry_run: rt.dry_run_writes,
         })
         .await;
-    target_to_exec(SideEffects::FilesystemWrite, out)
+    write_to_exec(rt, out)
 }
 
 pub(super) async fn run_apply_patch(rt: &ToolRuntime, args: &Value) -> ToolExecution {
@@ -102,35 +118,71 @@ pub(super) async fn run_apply_patch(rt: &ToolRuntime, args: &Value) -> ToolExecu
     let path = args
         .get("path")
         .and_then(|v| v.as_str())
-        .unwrap_or_default();
-    if !path_is_workdir_scoped(path) && !rt.unsafe_bypass_allow_flags {
-        return failed_exec(
-            rt,
-            SideEffects::FilesystemWrite,
-            "path must stay within workdir (no absolute paths or '..' traversal). Use a workdir-relative path like 'src/main.rs'.".to_string(),
-            Some(ToolErrorDetail {
-                code: ToolErrorCode::ToolPathDenied,
-                message: "Path must stay within workdir. Use a workdir-relative path.".to_string(),
-                expected_schema: None,
-                received_args: Some(args.clone()),
-                minimal_example: minimal_builtin_example("apply_patch"),
-                available_tools: None,
-            }),
-        );
-    }
+        .filter(|p| !p.is_empty());
     let patch_text = args
         .get("patch")
         .and_then(|v| v.as_str())
         .unwrap_or_default();
+    // Without a path the patch must be a unified diff whose headers name
+    // every file it touches.
+    let paths = match path {
+        Some(path) => vec![path.to_string()],
+        None => match split_multi_file_patch(patch_text) {
+            Ok(files) => files.into_iter().map(|f| f.path).collect(),
+            Err(e) => {
+                return failed_exec(
+                    rt,
+                    SideEffects::FilesystemWrite,
+                    format!("apply_patch needs a path or a multi-file unified diff: {e}"),
+                    Some(invalid_args_detail("apply_patch", args, &e)),
+                );
+            }
+        },
+    };
+    for path in &paths {
+        if let Some(denied) = artifact_write_denied(rt, path, args) {
+            return denied;
+        }
+        if !path_is_workdir_scoped(path) && !rt.unsafe_bypass_allow_flags {
+            return failed_exec(
+                rt,
+                SideEffects::FilesystemWrite,
+                "path must stay within workdir (no absolute paths or '..' traversal). Use a workdir-relative path like 'src/main.rs'.".to_string(),
+                Some(ToolErrorDetail {
+                    code: ToolErrorCode::ToolPathDenied,
+                    message: "Path must stay within workdir. Use a workdir-relative path."
+                        .to_string(),
+                    expected_schema: None,
+                    received_args: Some(args.clone()),
+                    minimal_example: minimal_builtin_example("apply_patch"),
+                    available_tools: None,
+                }),
+            );
+        }
+        if let Some(denied) = workdir_escape_denied(rt, SideEffects::FilesystemWrite, path, args) {
+            return denied;
+        }
+    }
     let out = rt
         .exec_target
         .apply_patch(PatchReq {
             workdir: rt.workdir.clone(),
-            path: path.to_string(),
+            path: path.map(str::to_string),
             patch: patch_text.to_string(),
+            dry_run: rt.dry_run_writes,
         })
         .await;
-    target_to_exec(SideEffects::FilesystemWrite, out)
+    write_to_exec(rt, out)
+}
+
+/// Marks a successful dry-run write so the agent and run record can tell
+/// it from a real one.
+fn write_to_exec(rt: &ToolRuntime, out: TargetResult) -> ToolExecution {
+    let mut exec = target_to_exec(SideEffects::FilesystemWrite, out);
+    if rt.dry_run_writes && exec.ok {
+        exec.meta.dry_run = Some(true);
+    }
+    exec
 }
 
 pub(super) async fn run_edit(rt: &ToolRuntime, args: &Value) -> ToolExecution {
@@ -161,6 +213,9 @@ async fn run_exact_replace(rt: &ToolRuntime, args: &Value, tool_name: &str) -> T
         .get("path")
         .and_then(|v| v.as_str())
         .unwrap_or_default();
+    if let Some(denied) = artifact_write_denied(rt, path, args) {
+        return denied;
+    }
     if !path_is_workdir_scoped(path) && !rt.unsafe_bypass_allow_flags {
         return failed_exec(
             rt,
@@ -176,6 +231,9 @@ async fn run_exact_replace(rt: &ToolRuntime, args: &Value, tool_name: &str) -> T
             }),
         );
     }
+    if let Some(denied) = workdir_escape_denied(rt, SideEffects::FilesystemWrite, path, args) {
+        return denied;
+    }
     let old_string = args
         .get("old_string")
         .and_then(|v| v.as_str())
@@ -190,6 +248,9 @@ async fn run_exact_replace(rt: &ToolRuntime, args: &Value, tool_name: &str) -> T
             workdir: rt.workdir.clone(),
             path: path.to_string(),
             max_read_bytes: 10 * 1024 * 1024,
+            max_line_chars: None,
+            range: None,
+            redaction: None,
         })
         .await;
     if !read_out.ok {
@@ -245,6 +306,7 @@ async fn run_exact_replace(rt: &ToolRuntime, args: &Value, tool_name: &str) -> T
             path: path.to_string(),
             content: replaced.clone(),
             create_parents: false,
+            dry_run: rt.dry_run_writes,
         })
         .await;
     if !write_out.ok {
@@ -258,6 +320,9 @@ async fn run_exact_replace(rt: &ToolRuntime, args: &Value, tool_name: &str) -> T
             None,
         );
     }
+    if rt.dry_run_writes {
+        return write_to_exec(rt, write_out);
+    }
     let content =
         serde_json::json!({"path": path, "changed": changed, "bytes_written": replaced.len()})
             .to_string();
@@ -273,14 +338,19 @@ async fn run_exact_replace(rt: &ToolRuntime, args: &Value, tool_name: &str) -> T
             stderr_truncated: None,
             stdout_truncated: None,
             source: "builtin".to_string(),
-            execution_target: match rt.exec_target_kind {
+            execution_target: match rt.exec_target_kind_for(SideEffects::FilesystemWrite) {
                 crate::target::ExecTargetKind::Host => "host".to_string(),
                 crate::target::ExecTargetKind::Docker => "docker".to_string(),
+                crate::target::ExecTargetKind::Ssh => "ssh".to_string(),
             },
             warnings: None,
             warnings_max: None,
             warnings_truncated: None,
             docker: None,
+            write_protection: write_out.write_protection,
+            cwd: None,
+            timed_out: None,
+            dry_run: None,
         },
     }
 }
@@ -0,0 +1,120 @@
+use std::path::{Component, Path};
+
+use anyhow::anyhow;
+use globset::{Glob, GlobMatcher};
+
+/// Workdir-relative globs that are the only paths read tools may touch. Built
+/// only when at least one glob is configured; no allowlist means no limit.
+#[derive(Debug, Clone)]
+pub struct ReadAllowlist {
+    globs: Vec<String>,
+    matchers: Vec<GlobMatcher>,
+    /// Literal leading part of each glob, used to decide which directories can
+    /// still contain allowed paths.
+    prefixes: Vec<String>,
+}
+
+impl ReadAllowlist {
+    pub fn from_globs(globs: &[String]) -> anyhow::Result<Option<Self>> {
+        if globs.is_empty() {
+            return Ok(None);
+        }
+        let mut matchers = Vec::with_capacity(globs.len());
+        let mut prefixes = Vec::with_capacity(globs.len());
+        for glob in globs {
+            let normalized = glob.trim().trim_start_matches("./");
+            if normalized.is_empty() {
+                return Err(anyhow!("read allowlist globs must be non-empty"));
+            }
+            if Path::new(normalized).is_absolute() || normalized.split('/').any(|s| s == "..") {
+                return Err(anyhow!(
+                    "read allowlist glob '{glob}' must be workdir-relative"
+                ));
+            }
+            matchers.push(
+                Glob::new(normalized)
+                    .map_err(|e| anyhow!("invalid read allowlist glob '{glob}': {e}"))?
+                    .compile_matcher(),
+            );
+            let literal_end = normalized
+                .find(['*', '?', '[', '{'])
+                .unwrap_or(normalized.len());
+            prefixes.push(normalized[..literal_end].to_string());
+        }
+        Ok(Some(Self {
+            globs: globs.to_vec(),
+            matchers,
+            prefixes,
+        }))
+    }
+
+    pub fn globs(&self) -> &[String] {
+        &self.globs
+    }
+
+    /// Whether a file or directory at `path` may be read.
+    pub fn allows(&self, path: &str) -> bool {
+        let rel = normalize_allowlist_path(path);
+        self.matchers.iter().any(|m| m.is_match(&rel))
+    }
+
+    /// Whether `dir` is allowed or is an ancestor of something allowed, so a
+    /// walk or listing has to descend into it.
+    pub fn may_contain(&self, dir: &str) -> bool {
+        let rel = normalize_allowlist_path(dir);
+        if rel == "." || self.allows(&rel) {
+            return true;
+        }
+        let dir_prefix = format!("{rel}/");
+        self.prefixes
+            .iter()
+            .any(|p| p.starts_with(&dir_prefix) || dir_prefix.starts_with(p.as_str()))
+    }
+}
+
+/// Collapses `./a//b/` style tool paths to `a/b`; the workdir itself is `.`.
+pub fn normalize_allowlist_path(path: &str) -> String {
+    let parts = Path::new(path)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(globs: &[&str]) -> ReadAllowlist {
+        ReadAllowlist::from_globs(&globs.iter().map(|g| g.to_string()).collect::<Vec<_>>())
+            .expect("compile")
+            .expect("non-empty")
+    }
+
+    #[test]
+    fn matches_files_and_ancestor_directories() {
+        let list = allowlist(&["docs/**", "src/module_x/**"]);
+        assert!(list.allows("docs/guide.md"));
+        assert!(list.allows("./src/module_x/lib.rs"));
+        assert!(!list.allows("src/main.rs"));
+        assert!(list.may_contain("."));
+        assert!(list.may_contain("src"));
+        assert!(list.may_contain("src/module_x"));
+        assert!(!list.may_contain("src/other"));
+        assert!(!list.may_contain("srcx"));
+    }
+
+    #[test]
+    fn rejects_paths_escaping_the_workdir() {
+        assert!(ReadAllowlist::from_globs(&[]).expect("empty").is_none());
+        assert!(ReadAllowlist::from_globs(&["../x/**".to_string()]).is_err());
+        assert!(ReadAllowlist::from_globs(&["/etc/**".to_string()]).is_err());
+    }
+}
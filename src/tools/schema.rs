@@ -1,14 +1,27 @@
 use serde_json::{json, Value};
 
 use super::{ToolArgsStrict, ToolErrorCode, ToolErrorDetail};
+use crate::target::{split_multi_file_patch, ReadRange};
 
 pub fn compact_builtin_schema(tool_name: &str) -> Option<Value> {
     match tool_name {
-        "list_dir" | "read_file" => Some(json!({
+        "list_dir" => Some(json!({
             "type":"object",
             "required":["path"],
             "properties":{"path":{"type":"string"}}
         })),
+        "read_file" => Some(json!({
+            "type":"object",
+            "required":["path"],
+            "properties":{
+                "path":{"type":"string"},
+                "max_line_chars":{"type":"integer","minimum":1},
+                "offset_bytes":{"type":"integer","minimum":0},
+                "length_bytes":{"type":"integer","minimum":0},
+                "start_line":{"type":"integer","minimum":1},
+                "end_line":{"type":"integer","minimum":1}
+            }
+        })),
         "glob" => Some(json!({
             "type":"object",
             "required":["pattern"],
@@ -28,6 +41,17 @@ pub fn compact_builtin_schema(tool_name: &str) -> Option<Value> {
                 "ignore_case":{"type":"boolean"}
             }
         })),
+        "search" => Some(json!({
+            "type":"object",
+            "required":["pattern"],
+            "properties":{
+                "pattern":{"type":"string"},
+                "path":{"type":"string"},
+                "regex":{"type":"boolean"},
+                "case_insensitive":{"type":"boolean"},
+                "max_results":{"type":"integer","minimum":1,"maximum":1000}
+            }
+        })),
         "update_plan" => Some(json!({
             "type":"object",
             "required":["items"],
@@ -55,6 +79,7 @@ pub fn compact_builtin_schema(tool_name: &str) -> Option<Value> {
                 "cmd":{"type":"string"},
                 "args":{"type":"array","items":{"type":"string"}},
                 "cwd":{"type":"string"},
+                "create_cwd":{"type":"boolean"},
                 "timeout_ms":{"type":"integer","minimum":0}
             }
         })),
@@ -70,7 +95,7 @@ pub fn compact_builtin_schema(tool_name: &str) -> Option<Value> {
         })),
         "apply_patch" => Some(json!({
             "type":"object",
-            "required":["path","patch"],
+            "required":["patch"],
             "properties":{"path":{"type":"string"},"patch":{"type":"string"}}
         })),
         "edit" => Some(json!({
@@ -94,6 +119,17 @@ pub fn compact_builtin_schema(tool_name: &str) -> Option<Value> {
                 "new_string":{"type":"string"}
             }
         })),
+        "spawn_subtask" => Some(json!({
+            "type":"object",
+            "required":["prompt"],
+            "properties":{
+                "prompt":{"type":"string"},
+                "allowed_tools":{"type":"array","items":{"type":"string"}},
+                "max_steps":{"type":"integer","minimum":1},
+                "max_tool_calls":{"type":"integer","minimum":1},
+                "max_wall_time_ms":{"type":"integer","minimum":1}
+            }
+        })),
         _ => None,
     }
 }
@@ -104,6 +140,7 @@ pub fn minimal_builtin_example(tool_name: &str) -> Option<Value> {
         "read_file" => Some(json!({"path":"src/main.rs"})),
         "glob" => Some(json!({"pattern":"src/**/*.rs","path":".","max_results":200})),
         "grep" => Some(json!({"pattern":"TODO","path":".","max_results":200,"ignore_case":false})),
+        "search" => Some(json!({"pattern":"fn main","path":"src","max_results":50})),
         "update_plan" => Some(
             json!({"items":[{"step":"Inspect the code","status":"in_progress"},{"step":"Run tests","status":"pending"}]}),
         ),
@@ -116,6 +153,9 @@ pub fn minimal_builtin_example(tool_name: &str) -> Option<Value> {
         "str_replace" => Some(
             json!({"path":"src/main.rs","old_string":"println!(\"helo\")","new_string":"println!(\"hello\")"}),
         ),
+        "spawn_subtask" => Some(
+            json!({"prompt":"List the public functions in src/lib.rs","allowed_tools":["read_file","search"]}),
+        ),
         _ => None,
     }
 }
@@ -125,6 +165,7 @@ pub fn sorted_builtin_tool_names() -> Vec<String> {
         "list_dir".to_string(),
         "glob".to_string(),
         "grep".to_string(),
+        "search".to_string(),
         "update_plan".to_string(),
         "read_file".to_string(),
         "edit".to_string(),
@@ -158,7 +199,16 @@ pub fn validate_builtin_tool_args(
         return Ok(());
     }
     match tool_name {
-        "list_dir" | "read_file" => require_non_empty_string(obj, "path")?,
+        "list_dir" => require_non_empty_string(obj, "path")?,
+        "read_file" => {
+            require_non_empty_string(obj, "path")?;
+            if let Some(v) = obj.get("max_line_chars") {
+                if v.as_u64().is_none_or(|n| n < 1) {
+                    return Err("max_line_chars must be a positive integer".to_string());
+                }
+            }
+            parse_read_range(args)?;
+        }
         "glob" => {
             require_non_empty_string(obj, "pattern")?;
             if let Some(v) = obj.get("path") {
@@ -196,6 +246,29 @@ pub fn validate_builtin_tool_args(
                 }
             }
         }
+        "search" => {
+            require_non_empty_string(obj, "pattern")?;
+            if let Some(v) = obj.get("path") {
+                if v.as_str().is_none() {
+                    return Err("path must be a string".to_string());
+                }
+            }
+            if let Some(v) = obj.get("max_results") {
+                let n = v
+                    .as_u64()
+                    .ok_or_else(|| "max_results must be an integer".to_string())?;
+                if !(1..=1000).contains(&n) {
+                    return Err("max_results must be between 1 and 1000".to_string());
+                }
+            }
+            for key in ["regex", "case_insensitive"] {
+                if let Some(v) = obj.get(key) {
+                    if v.as_bool().is_none() {
+                        return Err(format!("{key} must be a boolean"));
+                    }
+                }
+            }
+        }
         "update_plan" => {
             super::exec_plan::parse_update_plan_args(args).map(|_| ())?;
         }
@@ -214,6 +287,11 @@ pub fn validate_builtin_tool_args(
                     return Err("cwd must be a string".to_string());
                 }
             }
+            if let Some(v) = obj.get("create_cwd") {
+                if v.as_bool().is_none() {
+                    return Err("create_cwd must be a boolean".to_string());
+                }
+            }
             if let Some(v) = obj.get("timeout_ms") {
                 if v.as_u64().is_none() {
                     return Err("timeout_ms must be a non-negative integer".to_string());
@@ -235,14 +313,42 @@ pub fn validate_builtin_tool_args(
             }
         }
         "apply_patch" => {
-            require_non_empty_string(obj, "path")?;
             require_non_empty_string(obj, "patch")?;
+            if obj.contains_key("path") {
+                require_non_empty_string(obj, "path")?;
+            } else {
+                let patch = obj
+                    .get("patch")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                split_multi_file_patch(patch).map_err(|e| {
+                    format!("path is required unless patch is a multi-file unified diff ({e})")
+                })?;
+            }
         }
         "edit" | "str_replace" => {
             require_non_empty_string(obj, "path")?;
             require_string(obj, "old_string")?;
             require_string(obj, "new_string")?;
         }
+        "spawn_subtask" => {
+            require_non_empty_string(obj, "prompt")?;
+            if let Some(v) = obj.get("allowed_tools") {
+                let arr = v
+                    .as_array()
+                    .ok_or_else(|| "allowed_tools must be an array of strings".to_string())?;
+                if arr.iter().any(|x| x.as_str().is_none()) {
+                    return Err("allowed_tools must be an array of strings".to_string());
+                }
+            }
+            for key in ["max_steps", "max_tool_calls", "max_wall_time_ms"] {
+                if let Some(v) = obj.get(key) {
+                    if v.as_u64().is_none_or(|n| n < 1) {
+                        return Err(format!("{key} must be a positive integer"));
+                    }
+                }
+            }
+        }
         _ => {}
     }
     Ok(())
@@ -302,25 +408,133 @@ fn validate_value_type(value: &Value, schema: &Value) -> Result<(), String> {
     let Some(kind) = schema.get("type").and_then(|v| v.as_str()) else {
         return Ok(());
     };
+    if !value_has_type(value, kind) {
+        return Err(format!("has invalid type (expected {kind})"));
+    }
+    if let (Some(item_schema), Some(arr)) = (schema.get("items"), value.as_array()) {
+        for item in arr {
+            validate_value_type(item, item_schema)?;
+        }
+    }
+    Ok(())
+}
+
+fn value_has_type(value: &Value, kind: &str) -> bool {
     match kind {
-        "string" if value.is_string() => Ok(()),
-        "number" if value.is_number() => Ok(()),
-        "integer" if value.as_i64().is_some() || value.as_u64().is_some() => Ok(()),
-        "boolean" if value.is_boolean() => Ok(()),
-        "object" if value.is_object() => Ok(()),
-        "array" if value.is_array() => {
-            if let Some(item_schema) = schema.get("items") {
-                if let Some(arr) = value.as_array() {
-                    for item in arr {
-                        validate_value_type(item, item_schema)?;
-                    }
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+/// Parses `text` as JSON, tolerating one surrounding Markdown code fence,
+/// and validates it against `schema`. Errors name the offending path.
+pub fn validate_json_output(text: &str, schema: &Value) -> Result<Value, Vec<String>> {
+    let trimmed = text.trim();
+    let body = trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(|rest| rest.split_once('\n').map_or("", |(_, body)| body))
+        .unwrap_or(trimmed);
+    let value = serde_json::from_str::<Value>(body)
+        .map_err(|e| vec![format!("output is not valid JSON: {e}")])?;
+    let mut errors = Vec::new();
+    validate_json_value(&value, schema, "$", &mut errors);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// The recursive counterpart of [`validate_schema_args`]: `type`, `enum`,
+/// `required`, `properties`, `additionalProperties: false` and `items`.
+fn validate_json_value(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(kind) = schema.get("type").and_then(|v| v.as_str()) {
+        if !value_has_type(value, kind) {
+            errors.push(format!("{path} has invalid type (expected {kind})"));
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(|v| v.as_array()) {
+        if !allowed.contains(value) {
+            errors.push(format!("{path} is not one of the allowed values"));
+        }
+    }
+    if let Some(obj) = value.as_object() {
+        if let Some(req) = schema.get("required").and_then(|v| v.as_array()) {
+            for key in req.iter().filter_map(|v| v.as_str()) {
+                if !obj.contains_key(key) {
+                    errors.push(format!("{path} missing required field: {key}"));
                 }
             }
-            Ok(())
         }
-        "null" if value.is_null() => Ok(()),
-        other => Err(format!("has invalid type (expected {other})")),
+        let props = schema.get("properties").and_then(|v| v.as_object());
+        let closed = schema.get("additionalProperties").and_then(|v| v.as_bool()) == Some(false);
+        for (key, field) in obj {
+            match props.and_then(|p| p.get(key)) {
+                Some(field_schema) => {
+                    validate_json_value(field, field_schema, &format!("{path}.{key}"), errors)
+                }
+                None if closed => errors.push(format!("{path} unknown field not allowed: {key}")),
+                None => {}
+            }
+        }
+    }
+    if let (Some(item_schema), Some(arr)) = (schema.get("items"), value.as_array()) {
+        for (idx, item) in arr.iter().enumerate() {
+            validate_json_value(item, item_schema, &format!("{path}[{idx}]"), errors);
+        }
+    }
+}
+
+/// `read_file` range arguments: `offset_bytes`/`length_bytes` or
+/// `start_line`/`end_line` (1-based, inclusive), never both kinds.
+pub(super) fn parse_read_range(args: &Value) -> Result<Option<ReadRange>, String> {
+    let field = |key: &str, min: u64| -> Result<Option<u64>, String> {
+        match args.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(v) => match v.as_u64() {
+                Some(n) if n >= min => Ok(Some(n)),
+                _ if min == 0 => Err(format!("{key} must be a non-negative integer")),
+                _ => Err(format!("{key} must be a positive integer")),
+            },
+        }
+    };
+    let offset = field("offset_bytes", 0)?;
+    let length = field("length_bytes", 0)?;
+    let start = field("start_line", 1)?;
+    let end = field("end_line", 1)?;
+    let bytes = offset.is_some() || length.is_some();
+    let lines = start.is_some() || end.is_some();
+    if bytes && lines {
+        return Err(
+            "use either offset_bytes/length_bytes or start_line/end_line, not both".to_string(),
+        );
+    }
+    if let (Some(start), Some(end)) = (start, end) {
+        if end < start {
+            return Err("end_line must not be less than start_line".to_string());
+        }
     }
+    Ok(if bytes {
+        Some(ReadRange::Bytes {
+            offset: offset.unwrap_or(0),
+            length,
+        })
+    } else if lines {
+        Some(ReadRange::Lines {
+            start: start.unwrap_or(1),
+            end,
+        })
+    } else {
+        None
+    })
 }
 
 fn require_string(obj: &serde_json::Map<String, Value>, key: &str) -> Result<(), String> {
@@ -1,5 +1,5 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -94,6 +94,7 @@ impl ApprovalsStore {
         ttl_hours: Option<u32>,
         max_uses: Option<u32>,
     ) -> anyhow::Result<()> {
+        let _lock = self.lock_state()?;
         let mut data = self.load_data()?;
         let req = data
             .requests
@@ -119,6 +120,7 @@ impl ApprovalsStore {
     }
 
     pub fn prune(&self) -> anyhow::Result<usize> {
+        let _lock = self.lock_state()?;
         let mut data = self.load_data()?;
         let now = OffsetDateTime::now_utc();
         let before = data.requests.len();
@@ -139,6 +141,27 @@ impl ApprovalsStore {
         Ok(removed)
     }
 
+    /// Removes approvals granted under a hooks config that is no longer
+    /// active. Only v2 keys include the hooks hash; v1 approvals are kept.
+    pub fn invalidate_hooks_config_hash(
+        &self,
+        hooks_config_hash_hex: &str,
+    ) -> anyhow::Result<usize> {
+        let _lock = self.lock_state()?;
+        let mut data = self.load_data()?;
+        let before = data.requests.len();
+        data.requests.retain(|_, req| {
+            !(req.status == StoredStatus::Approved
+                && req.approval_key_version.as_deref() == Some("v2")
+                && req.hooks_config_hash_hex.as_deref() == Some(hooks_config_hash_hex))
+        });
+        let removed = before.saturating_sub(data.requests.len());
+        if removed > 0 {
+            self.save_data(&data)?;
+        }
+        Ok(removed)
+    }
+
     pub fn find_matching_decision(
         &self,
         approval_key: &str,
@@ -179,6 +202,7 @@ impl ApprovalsStore {
         approval_key: &str,
         approval_key_version: &str,
     ) -> anyhow::Result<Option<ApprovedUsage>> {
+        let _lock = self.lock_state()?;
         let mut data = self.load_data()?;
         let now = OffsetDateTime::now_utc();
         let mut selected_id: Option<String> = None;
@@ -221,6 +245,7 @@ impl ApprovalsStore {
         approval_key: Option<String>,
         provenance: Option<ApprovalProvenance>,
     ) -> anyhow::Result<String> {
+        let _lock = self.lock_state()?;
         let mut data = self.load_data()?;
         let id = Uuid::new_v4().to_string();
         let prov = provenance.unwrap_or(ApprovalProvenance {
@@ -259,6 +284,7 @@ impl ApprovalsStore {
         approval_key: &str,
         provenance: Option<ApprovalProvenance>,
     ) -> anyhow::Result<String> {
+        let _lock = self.lock_state()?;
         let mut data = self.load_data()?;
         let target_version = provenance
             .as_ref()
@@ -314,6 +340,7 @@ impl ApprovalsStore {
     }
 
     fn set_status(&self, id: &str, status: StoredStatus) -> anyhow::Result<()> {
+        let _lock = self.lock_state()?;
         let mut data = self.load_data()?;
         let req = data
             .requests
@@ -323,6 +350,12 @@ impl ApprovalsStore {
         self.save_data(&data)
     }
 
+    /// Approvals default to `<state_dir>/approvals.json`, so the containing
+    /// directory is the state dir; an `--approvals` override locks its own.
+    fn lock_state(&self) -> anyhow::Result<crate::store::StateLockGuard> {
+        crate::store::acquire_state_lock(self.path.parent().unwrap_or_else(|| Path::new(".")))
+    }
+
     fn load_data(&self) -> anyhow::Result<ApprovalsData> {
         if !self.path.exists() {
             return Ok(empty_data());
@@ -513,6 +546,62 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    fn invalidate_hooks_config_hash_drops_only_matching_v2_approvals() {
+        let dir = tempdir().expect("tempdir");
+        let store = ApprovalsStore::new(dir.path().join("approvals.json"));
+        let provenance = |hooks: &str| ApprovalProvenance {
+            approval_key_version: "v2".to_string(),
+            tool_schema_hash_hex: None,
+            hooks_config_hash_hex: Some(hooks.to_string()),
+            exec_target: Some("host".to_string()),
+            planner_hash_hex: None,
+        };
+        let old = store
+            .create_pending(
+                "shell",
+                &json!({"cmd":"a"}),
+                Some("ka".to_string()),
+                Some(provenance("old")),
+            )
+            .expect("create");
+        let current = store
+            .create_pending(
+                "shell",
+                &json!({"cmd":"b"}),
+                Some("kb".to_string()),
+                Some(provenance("new")),
+            )
+            .expect("create");
+        let pending = store
+            .create_pending(
+                "shell",
+                &json!({"cmd":"c"}),
+                Some("kc".to_string()),
+                Some(provenance("old")),
+            )
+            .expect("create");
+        store.approve(&old, None, None).expect("approve");
+        store.approve(&current, None, None).expect("approve");
+
+        assert_eq!(
+            store
+                .invalidate_hooks_config_hash("old")
+                .expect("invalidate"),
+            1
+        );
+        let data = store.list().expect("list");
+        assert!(!data.requests.contains_key(&old));
+        assert!(data.requests.contains_key(&current));
+        assert!(data.requests.contains_key(&pending));
+        assert_eq!(
+            store
+                .invalidate_hooks_config_hash("old")
+                .expect("invalidate"),
+            0
+        );
+    }
+
     #[test]
     fn ttl_and_max_use_matrix_is_deterministic() {
         let dir = tempdir().expect("tempdir");
@@ -101,6 +101,7 @@ pub fn run_policy_tests(policy_path: &Path, cases_path: &Path) -> anyhow::Result
         let ctx = GateContext {
             workdir: normalize_path(&case.context.workdir),
             allow_shell: true,
+            shell_allowlist: None,
             allow_write: true,
             approval_mode: ApprovalMode::Interrupt,
             auto_approve_scope: AutoApproveScope::Run,
@@ -125,6 +126,7 @@ pub fn run_policy_tests(policy_path: &Path, cases_path: &Path) -> anyhow::Result
             taint_mode: crate::taint::TaintMode::Propagate,
             taint_overall: crate::taint::TaintLevel::Clean,
             taint_sources: Vec::new(),
+            taint_injection_digest: None,
         };
         let call = ToolCall {
             id: format!("tc_{idx}"),
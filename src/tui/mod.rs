@@ -42,6 +42,7 @@ impl EventSink for UiSink {
 pub struct TuiConfig {
     pub refresh_ms: u64,
     pub max_log_lines: usize,
+    pub max_line_chars: usize,
     pub provider: String,
     pub model: String,
     pub mode_label: String,
@@ -65,6 +66,7 @@ pub fn run_live(
     let mut terminal = Terminal::new(backend)?;
 
     let mut state = UiState::new(cfg.max_log_lines);
+    state.max_line_chars = cfg.max_line_chars;
     state.provider = cfg.provider;
     state.model = cfg.model;
     state.mode_label = cfg.mode_label;
@@ -330,7 +330,7 @@ fn draw_approvals_table(
 
     if show_detail {
         if let Some(row) = state.pending_approvals.get(approvals_selected) {
-            let detail = approval_detail(row);
+            let detail = approval_detail(row, state.max_line_chars);
             frame.render_widget(
                 Paragraph::new(detail)
                     .block(
@@ -408,7 +408,8 @@ fn side_effect_label(raw: &str) -> &'static str {
     }
 }
 
-fn approval_detail(row: &ApprovalRow) -> String {
+fn approval_detail(row: &ApprovalRow, max_line_chars: usize) -> String {
+    let (arguments, _) = crate::target::elide_long_lines(&row.arguments, max_line_chars);
     format!(
         "id: {}\ntool: {}  risk: {}  decision: {}\nkey: {} ({})  target: {}\nargs: {}",
         row.id,
@@ -418,7 +419,7 @@ fn approval_detail(row: &ApprovalRow) -> String {
         row.approval_key_short,
         row.approval_key_version,
         row.exec_target,
-        row.arguments
+        arguments
     )
 }
 
@@ -538,4 +539,24 @@ mod tests {
         assert!(rendered.contains("latest_reason=shell requires approval"));
         assert!(rendered.contains(r#"args: {"args":["/c","echo","hi"],"cmd":"cmd"}"#));
     }
+
+    #[test]
+    fn approval_detail_elides_minified_arguments() {
+        let mut row = approval_state().pending_approvals[0].clone();
+        let content = "x".repeat(2_000);
+        row.arguments = format!(r#"{{"content":"{content}","path":"a.min.js"}}"#);
+        let detail = super::approval_detail(&row, 40);
+        let args_line = detail.lines().last().expect("args line");
+        let omitted = row.arguments.chars().count() - 40;
+        assert_eq!(
+            args_line,
+            format!(
+                "args: {}[... {omitted} chars elided ...]",
+                &row.arguments[..40]
+            )
+        );
+
+        let unlimited = super::approval_detail(&row, 0);
+        assert!(unlimited.ends_with(&format!("args: {}", row.arguments)));
+    }
 }
@@ -37,6 +37,9 @@ pub struct ApprovalRow {
     pub exec_target: String,
 }
 
+/// Default `--tui-max-line-chars`.
+pub const DEFAULT_MAX_LINE_CHARS: usize = 400;
+
 #[derive(Debug, Clone, Default)]
 pub struct PlanRow {
     pub step: String,
@@ -83,6 +86,9 @@ pub struct UiState {
     pub shell_execs: u64,
     pub network_execs: u64,
     pub browser_execs: u64,
+    /// Display-only cap for log lines and approval previews; tool results
+    /// recorded in the transcript are unaffected.
+    pub max_line_chars: usize,
     max_log_lines: usize,
 }
 
@@ -127,6 +133,7 @@ impl UiState {
             shell_execs: 0,
             network_execs: 0,
             browser_execs: 0,
+            max_line_chars: DEFAULT_MAX_LINE_CHARS,
             max_log_lines,
         }
     }
@@ -162,6 +169,7 @@ impl UiState {
             EventKind::QueueSubmitted => self.apply_queue_submitted_event(ev),
             EventKind::QueueDelivered => self.apply_queue_delivered_event(ev),
             EventKind::QueueInterrupt => self.apply_queue_interrupt_event(ev),
+            EventKind::QueueOverflow => self.apply_queue_overflow_event(ev),
             EventKind::Error => self.apply_error_event(ev),
             _ => self.apply_misc_log_event(ev),
         }
@@ -395,6 +395,11 @@ impl UiState {
             .get("primary_code")
             .and_then(|v| v.as_str())
             .unwrap_or("MCP_DRIFT");
+        let schema_drift = ev
+            .data
+            .get("schema_drift")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
         self.mcp_lifecycle = "DRIFT".to_string();
         self.mcp_pin_state = "DRIFT".to_string();
         self.mcp_stalled = false;
@@ -405,6 +410,23 @@ impl UiState {
             .and_then(|v| v.as_str())
             .unwrap_or("mcp.tool");
         let summary = match (catalog_drift, docs_drift) {
+            _ if schema_drift => format!(
+                "mcp_drift[{primary_code}]: schema {}->{} tool={tool}",
+                truncate_chars(
+                    ev.data
+                        .get("schema_hash_pinned")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("-"),
+                    12
+                ),
+                truncate_chars(
+                    ev.data
+                        .get("schema_hash_live")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("-"),
+                    12
+                ),
+            ),
             (true, true) => format!(
                 "mcp_drift[{primary_code}]: catalog {}->{} docs {}->{} tool={tool}",
                 truncate_chars(expected, 12),
@@ -570,9 +592,43 @@ impl UiState {
             .get("delivery_boundary")
             .and_then(|v| v.as_str())
             .unwrap_or("-");
+        let coalesced = ev
+            .data
+            .get("coalesced_count")
+            .and_then(|v| v.as_u64())
+            .map(|n| format!(" coalesced={n}"))
+            .unwrap_or_default();
+        self.push_log(format!(
+            "queue_delivered: id={} kind={} boundary={}{}",
+            queue_id, kind, boundary, coalesced
+        ));
+    }
+
+    pub(super) fn apply_queue_overflow_event(&mut self, ev: &Event) {
+        let queue_id = ev
+            .data
+            .get("queue_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("q?");
+        let policy = ev
+            .data
+            .get("policy")
+            .and_then(|v| v.as_str())
+            .unwrap_or("-");
+        let dropped = ev
+            .data
+            .get("dropped_queue_ids")
+            .and_then(|v| v.as_array())
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .unwrap_or_default();
         self.push_log(format!(
-            "queue_delivered: id={} kind={} boundary={}",
-            queue_id, kind, boundary
+            "queue_overflow: id={} policy={} dropped={}",
+            queue_id, policy, dropped
         ));
     }
 
@@ -768,6 +824,7 @@ impl UiState {
             if line.is_empty() {
                 continue;
             }
+            let (line, _) = crate::target::elide_long_lines(line, self.max_line_chars);
             self.push_log(format!("{prefix} {line}"));
         }
     }
@@ -301,7 +301,7 @@ pub(super) fn class_to_reason_token(class: &str) -> &'static str {
         "E_SCHEMA" => "schema",
         "E_POLICY" => "policy",
         "E_TIMEOUT_TRANSIENT" | "E_NETWORK_TRANSIENT" => "net",
-        "E_SELECTOR_AMBIGUOUS" | "E_NON_IDEMPOTENT" | "E_OTHER" => "tool",
+        "E_SELECTOR_AMBIGUOUS" | "E_NON_IDEMPOTENT" | "E_OVERSIZE" | "E_OTHER" => "tool",
         _ => "other",
     }
 }
@@ -10,7 +10,7 @@ use super::{ApprovalRow, UiState};
 #[test]
 fn apply_event_model_delta_appends() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ModelDelta,
@@ -22,19 +22,19 @@ fn apply_event_model_delta_appends() {
 #[test]
 fn apply_event_tool_lifecycle() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolCallDetected,
         serde_json::json!({"tool_call_id":"tc1","name":"read_file","side_effects":"filesystem_read"}),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolDecision,
         serde_json::json!({"tool_call_id":"tc1","name":"read_file","decision":"allow","reason":"ok"}),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecEnd,
@@ -51,13 +51,13 @@ fn apply_event_tool_lifecycle() {
 #[test]
 fn tool_decision_reflects_deny_and_approval_in_tui_state() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolCallDetected,
         serde_json::json!({"tool_call_id":"tc_mcp","name":"mcp.stub.echo","side_effects":"network"}),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolDecision,
@@ -79,13 +79,13 @@ fn tool_decision_reflects_deny_and_approval_in_tui_state() {
     assert_eq!(s.next_hint, "pending_approval");
     assert_eq!(s.mcp_lifecycle, "WAIT:APPROVAL");
 
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         2,
         EventKind::ToolCallDetected,
         serde_json::json!({"tool_call_id":"tc2","name":"write_file","side_effects":"filesystem_write"}),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         2,
         EventKind::ToolDecision,
@@ -107,7 +107,7 @@ fn tool_decision_reflects_deny_and_approval_in_tui_state() {
 #[test]
 fn schema_repair_flag_turns_on_from_retry_event() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolRetry,
@@ -121,7 +121,7 @@ fn schema_repair_flag_turns_on_from_retry_event() {
 #[test]
 fn failure_class_and_retry_count_are_captured_on_tool_exec_end() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecEnd,
@@ -141,7 +141,7 @@ fn failure_class_and_retry_count_are_captured_on_tool_exec_end() {
 #[test]
 fn tool_exec_end_protocol_violation_sets_protocol_reason_token() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecEnd,
@@ -171,7 +171,7 @@ fn logs_are_capped() {
 #[test]
 fn shell_output_chunk_logs_stream_identity_and_caps() {
     let mut s = UiState::new(3);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ShellOutputChunk,
@@ -181,7 +181,7 @@ fn shell_output_chunk_logs_stream_identity_and_caps() {
             "chunk":"out-a\nout-b"
         }),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ShellOutputChunk,
@@ -191,7 +191,7 @@ fn shell_output_chunk_logs_stream_identity_and_caps() {
             "chunk":"err-a"
         }),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ShellOutputChunk,
@@ -212,10 +212,33 @@ fn shell_output_chunk_logs_stream_identity_and_caps() {
     );
 }
 
+#[test]
+fn shell_output_chunk_elides_long_lines_for_display() {
+    let mut s = UiState::new(10);
+    s.max_line_chars = 8;
+    s.apply_event(&Event::new_raw(
+        "r1".to_string(),
+        1,
+        EventKind::ShellOutputChunk,
+        serde_json::json!({
+            "tool_call_id":"tc1",
+            "stream":"stdout",
+            "chunk":"0123456789abcdef\nshort"
+        }),
+    ));
+    assert_eq!(
+        s.logs,
+        vec![
+            "out> 01234567[... 8 chars elided ...]".to_string(),
+            "out> short".to_string(),
+        ]
+    );
+}
+
 #[test]
 fn plan_updated_event_replaces_current_plan_and_logs_summary() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::PlanUpdated,
@@ -237,7 +260,7 @@ fn plan_updated_event_replaces_current_plan_and_logs_summary() {
     assert!(last.contains("plan: 1/3"));
     assert!(last.contains("active=Implement update_plan"));
 
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::PlanUpdated,
@@ -378,21 +401,21 @@ fn pending_approval_count_counts_pending_only() {
 fn mcp_lifecycle_running_retry_done() {
     let mut s = UiState::new(10);
     s.mcp_catalog_hash = "abcdef123456".to_string();
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecStart,
         serde_json::json!({"tool_call_id":"tc1","name":"mcp.playwright.browser_snapshot","side_effects":"browser"}),
     ));
     assert_eq!(s.mcp_lifecycle, "RUNNING");
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolRetry,
         serde_json::json!({"name":"mcp.playwright.browser_snapshot","attempt":1,"max_retries":1,"failure_class":"E_TIMEOUT_TRANSIENT","action":"retry"}),
     ));
     assert_eq!(s.mcp_lifecycle, "WAIT:RETRY");
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecEnd,
@@ -406,13 +429,13 @@ fn mcp_lifecycle_running_retry_done() {
 fn mcp_running_tool_marked_cancelled_on_run_cancel() {
     let mut s = UiState::new(10);
     s.mark_cancel_requested();
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecStart,
         serde_json::json!({"tool_call_id":"tc1","name":"mcp.playwright.browser_snapshot","side_effects":"browser"}),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::RunEnd,
@@ -427,14 +450,14 @@ fn mcp_running_tool_marked_cancelled_on_run_cancel() {
 #[test]
 fn non_mcp_running_tool_is_closed_on_run_end() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecStart,
         serde_json::json!({"tool_call_id":"tc1","name":"write_file","side_effects":"filesystem_write"}),
     ));
     assert_eq!(s.tool_calls[0].status, "running");
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::RunEnd,
@@ -450,14 +473,14 @@ fn non_mcp_running_tool_is_closed_on_run_end() {
 #[test]
 fn tool_protocol_guard_error_closes_running_tool_row() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecStart,
         serde_json::json!({"tool_call_id":"tc1","name":"apply_patch","side_effects":"filesystem_write"}),
     ));
     assert_eq!(s.tool_calls[0].status, "running");
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::Error,
@@ -474,7 +497,7 @@ fn cancel_request_transitions_to_complete_on_run_end() {
     let mut s = UiState::new(10);
     s.mark_cancel_requested();
     assert!(s.cancel_requested());
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::RunEnd,
@@ -487,7 +510,7 @@ fn cancel_request_transitions_to_complete_on_run_end() {
 fn on_tick_marks_long_running_mcp_as_stalled() {
     let mut s = UiState::new(10);
     s.mcp_catalog_hash = "abcdef123456".to_string();
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::ToolExecStart,
@@ -504,7 +527,7 @@ fn on_tick_marks_long_running_mcp_as_stalled() {
 #[test]
 fn mcp_drift_event_sets_drift_lifecycle() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::McpDrift,
@@ -520,7 +543,7 @@ fn mcp_drift_event_sets_drift_lifecycle() {
 #[test]
 fn mcp_docs_drift_event_logs_docs_specific_summary() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::McpDrift,
@@ -542,10 +565,33 @@ fn mcp_docs_drift_event_logs_docs_specific_summary() {
     assert!(last.contains("mcp.stub.echo"));
 }
 
+#[test]
+fn mcp_schema_drift_event_logs_schema_hashes() {
+    let mut s = UiState::new(10);
+    s.apply_event(&Event::new_raw(
+        "r1".to_string(),
+        2,
+        EventKind::McpDrift,
+        serde_json::json!({
+            "name":"mcp.stub.echo",
+            "catalog_hash_expected":"aaa",
+            "expected_hash_hex":"schema_old",
+            "actual_hash_hex":"schema_new",
+            "schema_hash_pinned":"schema_old",
+            "schema_hash_live":"schema_new",
+            "schema_drift":true,
+            "primary_code":"MCP_SCHEMA_DRIFT"
+        }),
+    ));
+    let last = s.logs.last().cloned().unwrap_or_default();
+    assert!(last.contains("MCP_SCHEMA_DRIFT"));
+    assert!(last.contains("schema schema_old->schema_new"));
+}
+
 #[test]
 fn mcp_pinned_event_sets_pin_state() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r".to_string(),
         1,
         EventKind::McpPinned,
@@ -563,7 +609,7 @@ fn mcp_pinned_event_sets_pin_state() {
 #[test]
 fn mcp_progress_event_updates_lifecycle() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::McpProgress,
@@ -580,7 +626,7 @@ fn mcp_progress_event_updates_lifecycle() {
 #[test]
 fn mcp_cancelled_event_sets_cancelled_lifecycle() {
     let mut s = UiState::new(10);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r1".to_string(),
         1,
         EventKind::McpCancelled,
@@ -596,7 +642,7 @@ fn mcp_cancelled_event_sets_cancelled_lifecycle() {
 #[test]
 fn queue_events_log_stable_summaries() {
     let mut s = UiState::new(50);
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r".to_string(),
         1,
         EventKind::QueueSubmitted,
@@ -608,7 +654,7 @@ fn queue_events_log_stable_summaries() {
             "next_delivery":"after current tool finishes"
         }),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r".to_string(),
         1,
         EventKind::QueueDelivered,
@@ -618,7 +664,7 @@ fn queue_events_log_stable_summaries() {
             "delivery_boundary":"post_tool"
         }),
     ));
-    s.apply_event(&Event::new(
+    s.apply_event(&Event::new_raw(
         "r".to_string(),
         1,
         EventKind::QueueInterrupt,
@@ -634,3 +680,33 @@ fn queue_events_log_stable_summaries() {
     assert!(joined.contains("queue_interrupt: id=q7 cancelled_remaining_work=true"));
     assert_eq!(s.next_hint, "interrupt_applied");
 }
+
+#[test]
+fn queue_overflow_and_coalesced_delivery_are_logged() {
+    let mut s = UiState::new(50);
+    s.apply_event(&Event::new_raw(
+        "r".to_string(),
+        1,
+        EventKind::QueueOverflow,
+        serde_json::json!({
+            "queue_id":"q9",
+            "policy":"drop_oldest",
+            "max_pending": 2,
+            "dropped_queue_ids":["q1","q2"]
+        }),
+    ));
+    s.apply_event(&Event::new_raw(
+        "r".to_string(),
+        1,
+        EventKind::QueueDelivered,
+        serde_json::json!({
+            "queue_id":"q3",
+            "kind":"steer",
+            "delivery_boundary":"post_tool",
+            "coalesced_count": 3
+        }),
+    ));
+    let joined = s.logs.join("\n");
+    assert!(joined.contains("queue_overflow: id=q9 policy=drop_oldest dropped=q1,q2"));
+    assert!(joined.contains("queue_delivered: id=q3 kind=steer boundary=post_tool coalesced=3"));
+}
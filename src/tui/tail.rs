@@ -98,14 +98,14 @@ mod tests {
 
     #[test]
     fn parse_jsonl_updates_state() {
-        let e1 = serde_json::to_string(&Event::new(
+        let e1 = serde_json::to_string(&Event::new_raw(
             "r1".to_string(),
             1,
             EventKind::ModelDelta,
             serde_json::json!({"delta":"hi"}),
         ))
         .expect("e1");
-        let e2 = serde_json::to_string(&Event::new(
+        let e2 = serde_json::to_string(&Event::new_raw(
             "r1".to_string(),
             2,
             EventKind::RunEnd,
@@ -71,6 +71,9 @@ pub struct GenerateResponse {
     pub tool_calls: Vec<ToolCall>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<TokenUsage>,
+    /// Model name the server reported in its response payload, when it reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_model: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -45,6 +45,9 @@ fn build_run_cli_config() -> RunCliConfig {
         allow_write: false,
         enable_write_tools: false,
         exec_target: "host".to_string(),
+        read_allowlist: Vec::new(),
+        shell_allowlist: Vec::new(),
+        max_tool_calls: Default::default(),
         docker_image: None,
         docker_workdir: None,
         docker_network: None,
@@ -112,6 +115,7 @@ fn build_run_cli_config() -> RunCliConfig {
         mcp_tool_catalog_hash_hex: None,
         mcp_servers: Vec::new(),
         mcp_config_path: None,
+        mcp_roots: Vec::new(),
         policy_version: Some(2),
         includes_resolved: vec!["./policy.common.yaml".to_string()],
         mcp_allowlist: None,
@@ -151,6 +155,10 @@ fn build_run_cli_config() -> RunCliConfig {
         profile_source: None,
         profile_hash_hex: None,
         activated_packs: Vec::new(),
+        context_pack: None,
+        prompt_template: None,
+        learned_guidance: Vec::new(),
+        mcp_server_launches: Vec::new(),
     }
 }
 
@@ -177,6 +185,9 @@ fn build_outcome() -> AgentOutcome {
                 taint_enforced: false,
                 escalated: false,
                 escalation_reason: None,
+                taint_sources: Vec::new(),
+                denial_class: None,
+                gate_context: None,
             },
             ToolDecisionRecord {
                 step: 2,
@@ -190,6 +201,9 @@ fn build_outcome() -> AgentOutcome {
                 taint_enforced: false,
                 escalated: false,
                 escalation_reason: None,
+                taint_sources: Vec::new(),
+                denial_class: None,
+                gate_context: None,
             },
             ToolDecisionRecord {
                 step: 3,
@@ -203,10 +217,14 @@ fn build_outcome() -> AgentOutcome {
                 taint_enforced: false,
                 escalated: false,
                 escalation_reason: None,
+                taint_sources: Vec::new(),
+                denial_class: None,
+                gate_context: None,
             },
         ],
         compaction_settings: CompactionSettings {
             max_context_chars: 0,
+            max_context_tokens: None,
             mode: CompactionMode::Off,
             keep_last: 20,
             tool_result_persist: ToolResultPersist::Digest,
@@ -216,8 +234,16 @@ fn build_outcome() -> AgentOutcome {
         hook_invocations: Vec::new(),
         provider_retry_count: 0,
         provider_error_count: 0,
+        provider_retry_wait_ms: 0,
         token_usage: None,
         taint: None,
+        digest_refetch: None,
+        tool_result_artifacts: Vec::new(),
+        model_served: None,
+        subtask_run_ids: Vec::new(),
+        post_run_hook_error: None,
+        timeline: Vec::new(),
+        steps: Vec::new(),
     }
 }
 
@@ -320,6 +346,10 @@ fn run_artifact_schema_and_layout_golden_is_stable() {
         None,
         Vec::new(),
         None,
+        None,
+        None,
+        None,
+        None,
     )
     .expect("write run record");
 
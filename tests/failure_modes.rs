@@ -0,0 +1,35 @@
+use localagent::eval::failure_modes::{
+    default_fixture_dir, load_fixture_dir, render_summary_table, run_fixture,
+};
+
+#[tokio::test]
+async fn failure_mode_fixtures_match_expectations() {
+    let fixtures = load_fixture_dir(&default_fixture_dir()).expect("load fixtures");
+    assert!(
+        fixtures.len() >= 8,
+        "expected at least eight failure-mode fixtures, found {}",
+        fixtures.len()
+    );
+    let mut reports = Vec::new();
+    for fixture in &fixtures {
+        reports.push(run_fixture(fixture).await.expect("run fixture"));
+    }
+    let table = render_summary_table(&reports);
+    println!("{table}");
+    assert!(reports.iter().all(|r| r.passed()), "{table}");
+}
+
+#[tokio::test]
+async fn failure_mode_fixtures_are_deterministic() {
+    let fixtures = load_fixture_dir(&default_fixture_dir()).expect("load fixtures");
+    for fixture in &fixtures {
+        let first = run_fixture(fixture).await.expect("first run");
+        let second = run_fixture(fixture).await.expect("second run");
+        assert_eq!(
+            (first.exit_reason, first.error, first.provider_calls),
+            (second.exit_reason, second.error, second.provider_calls),
+            "fixture {} changed between runs",
+            fixture.name
+        );
+    }
+}
@@ -1,9 +1,13 @@
 use std::path::PathBuf;
 
-use localagent::hooks::config::HooksMode;
+use localagent::hooks::config::{HookStage, HooksMode};
+use localagent::hooks::protocol::{HookInput, HookInvocationReport, HookRunStats};
 use localagent::hooks::runner::{
-    make_pre_model_input, make_tool_result_input, HookManager, HookRuntimeConfig,
+    args_digest, make_post_run_input, make_pre_model_input, make_pre_tool_input,
+    make_tool_result_input, HookManager, HookRuntimeConfig, HooksReload, ToolResultHookResult,
 };
+use localagent::hooks::session::SessionHooks;
+use localagent::trust::approvals::{ApprovalProvenance, ApprovalsStore};
 
 fn hook_stub_path() -> PathBuf {
     if let Ok(p) = std::env::var("CARGO_BIN_EXE_hook_stub") {
@@ -65,6 +69,9 @@ async fn strict_invalid_json_fails_non_strict_passes() {
         strict: true,
         timeout_ms: 2_000,
         max_stdout_bytes: 200_000,
+        max_invocations_per_run: 0,
+        max_cumulative_ms: 0,
+        budget_strict: false,
     })
     .expect("strict manager");
     assert!(strict
@@ -93,6 +100,9 @@ async fn strict_invalid_json_fails_non_strict_passes() {
         strict: false,
         timeout_ms: 2_000,
         max_stdout_bytes: 200_000,
+        max_invocations_per_run: 0,
+        max_cumulative_ms: 0,
+        budget_strict: false,
     })
     .expect("non strict manager");
     let out = non_strict
@@ -113,6 +123,9 @@ async fn pre_model_enforces_append_limits() {
         strict: true,
         timeout_ms: 2_000,
         max_stdout_bytes: 200_000,
+        max_invocations_per_run: 0,
+        max_cumulative_ms: 0,
+        budget_strict: false,
     })
     .expect("manager");
 
@@ -144,6 +157,9 @@ async fn tool_result_modify_updates_digest() {
         strict: true,
         timeout_ms: 2_000,
         max_stdout_bytes: 200_000,
+        max_invocations_per_run: 0,
+        max_cumulative_ms: 0,
+        budget_strict: false,
     })
     .expect("manager");
     let input = make_tool_result_input(
@@ -168,3 +184,463 @@ async fn tool_result_modify_updates_digest() {
     assert_eq!(out.content, "stub redacted");
     assert_ne!(out.input_digest, out.output_digest);
 }
+
+fn budget_manager(
+    cfg: &std::path::Path,
+    max_invocations_per_run: u32,
+    max_cumulative_ms: u64,
+    budget_strict: bool,
+) -> HookManager {
+    HookManager::build(HookRuntimeConfig {
+        mode: HooksMode::On,
+        config_path: cfg.to_path_buf(),
+        strict: false,
+        timeout_ms: 2_000,
+        max_stdout_bytes: 200_000,
+        max_invocations_per_run,
+        max_cumulative_ms,
+        budget_strict,
+    })
+    .expect("manager")
+}
+
+async fn run_tool_result(
+    manager: &HookManager,
+    run_id: &str,
+    workdir: &std::path::Path,
+    extra: serde_json::Value,
+) -> Result<ToolResultHookResult, String> {
+    let mut payload = serde_json::json!({
+        "tool_call_id":"tc",
+        "tool_name":"read_file",
+        "ok": true,
+        "content":"x",
+        "truncated": false
+    });
+    if let (Some(obj), Some(extra)) = (payload.as_object_mut(), extra.as_object()) {
+        obj.extend(extra.clone());
+    }
+    let input = make_tool_result_input(run_id, 0, "ollama", "m", workdir, payload);
+    manager
+        .run_tool_result_hooks(input, "read_file", "x", false)
+        .await
+        .map_err(|e| e.message)
+}
+
+#[tokio::test]
+async fn invocation_budget_skips_remaining_invocations_with_markers() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "tool_result");
+    let manager = budget_manager(&cfg, 2, 0, false);
+
+    for _ in 0..2 {
+        let out = run_tool_result(&manager, "r1", tmp.path(), serde_json::json!({}))
+            .await
+            .expect("within budget");
+        assert!(!out.invocations[0].skipped_due_to_budget);
+        assert_eq!(out.content, "stub redacted");
+        assert!(out.budget_exhausted.is_empty());
+    }
+
+    let third = run_tool_result(&manager, "r1", tmp.path(), serde_json::json!({}))
+        .await
+        .expect("skipped, not failed");
+    assert_eq!(third.content, "x");
+    let report = &third.invocations[0];
+    assert!(report.skipped_due_to_budget);
+    assert_eq!(report.action, "skip");
+    assert_eq!(report.duration_ms, 0);
+    assert!(report
+        .message
+        .as_deref()
+        .is_some_and(|m| m.contains("max_invocations_per_run 2")));
+    assert_eq!(third.budget_exhausted.len(), 1);
+    assert_eq!(third.budget_exhausted[0].limit, "max_invocations_per_run");
+    assert_eq!(third.budget_exhausted[0].invocations, 2);
+
+    let fourth = run_tool_result(&manager, "r1", tmp.path(), serde_json::json!({}))
+        .await
+        .expect("skipped again");
+    assert!(fourth.invocations[0].skipped_due_to_budget);
+    assert!(
+        fourth.budget_exhausted.is_empty(),
+        "exhaustion is reported once per hook and run"
+    );
+
+    let next_run = run_tool_result(&manager, "r2", tmp.path(), serde_json::json!({}))
+        .await
+        .expect("new run");
+    assert!(!next_run.invocations[0].skipped_due_to_budget);
+}
+
+#[tokio::test]
+async fn cumulative_time_budget_clamps_timeout_then_skips() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "tool_result");
+    let manager = budget_manager(&cfg, 0, 150, false);
+    let slow = serde_json::json!({"sleep_ms": 1_000});
+
+    let first = run_tool_result(&manager, "r1", tmp.path(), slow.clone())
+        .await
+        .expect("non-strict timeout passes");
+    let report = &first.invocations[0];
+    assert!(!report.skipped_due_to_budget);
+    assert!(
+        report
+            .message
+            .as_deref()
+            .is_some_and(|m| m.contains("timed out after 150ms")),
+        "{report:?}"
+    );
+    assert!(report.duration_ms < 1_000);
+
+    let second = run_tool_result(&manager, "r1", tmp.path(), slow)
+        .await
+        .expect("skipped");
+    assert!(second.invocations[0].skipped_due_to_budget);
+    assert_eq!(second.budget_exhausted.len(), 1);
+    assert_eq!(second.budget_exhausted[0].limit, "max_cumulative_ms");
+    assert!(second.budget_exhausted[0].cumulative_ms >= 150);
+}
+
+#[tokio::test]
+async fn budget_strict_fails_at_first_exhaustion() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "tool_result");
+    let manager = budget_manager(&cfg, 1, 0, true);
+
+    run_tool_result(&manager, "r1", tmp.path(), serde_json::json!({}))
+        .await
+        .expect("first invocation runs");
+    let err = run_tool_result(&manager, "r1", tmp.path(), serde_json::json!({}))
+        .await
+        .expect_err("budget exhausted");
+    assert!(err.contains("hook 'stub' budget exhausted"), "{err}");
+}
+
+fn write_override_config(path: &std::path::Path) {
+    let escaped = hook_stub_path().display().to_string().replace('\\', "\\\\");
+    std::fs::write(
+        path,
+        format!(
+            r#"
+version: 1
+hooks:
+  - name: capped
+    stages: ["tool_result"]
+    command: "{escaped}"
+    max_invocations_per_run: 1
+  - name: global
+    stages: ["tool_result"]
+    command: "{escaped}"
+  - name: unlimited
+    stages: ["tool_result"]
+    command: "{escaped}"
+    max_invocations_per_run: 0
+"#
+        ),
+    )
+    .expect("write hooks config");
+}
+
+async fn run_override_scenario(workdir: &std::path::Path) -> Vec<HookInvocationReport> {
+    let cfg = workdir.join("hooks.yaml");
+    write_override_config(&cfg);
+    let manager = budget_manager(&cfg, 2, 0, false);
+    let mut reports = Vec::new();
+    for _ in 0..3 {
+        let out = run_tool_result(&manager, "r1", workdir, serde_json::json!({}))
+            .await
+            .expect("hooks ran");
+        reports.extend(out.invocations);
+    }
+    reports
+}
+
+#[tokio::test]
+async fn per_hook_budget_overrides_take_precedence_over_global() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let reports = run_override_scenario(tmp.path()).await;
+    let skipped = |name: &str| {
+        reports
+            .iter()
+            .filter(|r| r.hook_name == name)
+            .map(|r| r.skipped_due_to_budget)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(skipped("capped"), vec![false, true, true]);
+    assert_eq!(skipped("global"), vec![false, false, true]);
+    assert_eq!(skipped("unlimited"), vec![false, false, false]);
+}
+
+#[tokio::test]
+async fn hook_stats_aggregate_time_and_budget_skips() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let reports = run_override_scenario(tmp.path()).await;
+    let stats = HookRunStats::from_reports(&reports);
+    assert_eq!(stats.invocations, 6);
+    assert_eq!(stats.skipped_due_to_budget, 3);
+    assert_eq!(
+        stats.total_duration_ms,
+        reports.iter().map(|r| r.duration_ms).sum::<u128>()
+    );
+    assert_eq!(HookRunStats::from_reports(&[]), HookRunStats::default());
+}
+
+fn pre_tool_manager(cfg: PathBuf, strict: bool) -> HookManager {
+    HookManager::build(HookRuntimeConfig {
+        mode: HooksMode::On,
+        config_path: cfg,
+        strict,
+        timeout_ms: 2_000,
+        max_stdout_bytes: 200_000,
+        max_invocations_per_run: 0,
+        max_cumulative_ms: 0,
+        budget_strict: false,
+    })
+    .expect("manager")
+}
+
+fn pre_tool_input(workdir: &std::path::Path, rewrite_to: serde_json::Value) -> HookInput {
+    make_pre_tool_input(
+        "r1",
+        1,
+        "ollama",
+        "m",
+        workdir,
+        serde_json::json!({
+            "tool_call_id": "tc1",
+            "tool_name": "read_file",
+            "arguments": {"path": "./src/../src/lib.rs"},
+            "force_mode": "rewrite",
+            "rewrite_to": rewrite_to
+        }),
+    )
+}
+
+#[tokio::test]
+async fn pre_tool_rewrite_replaces_arguments_and_records_original_hash() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "pre_tool");
+    let manager = pre_tool_manager(cfg, true);
+    let original = serde_json::json!({"path": "./src/../src/lib.rs"});
+    let out = manager
+        .run_pre_tool_hooks(
+            pre_tool_input(tmp.path(), serde_json::json!({"path": "src/lib.rs"})),
+            "read_file",
+            &original,
+            |_| Ok(()),
+        )
+        .await
+        .map_err(|e| e.message)
+        .expect("hook ran");
+    assert_eq!(out.arguments, serde_json::json!({"path": "src/lib.rs"}));
+    let rewrite = out.rewrite.expect("rewrite recorded");
+    assert_eq!(rewrite.hooks, vec!["stub".to_string()]);
+    assert_eq!(rewrite.original_args_sha256, args_digest(&original));
+    assert!(out.invocations[0].modified);
+    assert_eq!(
+        out.invocations[0].output_digest.as_deref(),
+        Some(args_digest(&out.arguments).as_str())
+    );
+}
+
+#[tokio::test]
+async fn pre_tool_rejected_rewrite_fails_strict_and_keeps_arguments_otherwise() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "pre_tool");
+    let original = serde_json::json!({"path": "./src/../src/lib.rs"});
+    let reject = |_: &serde_json::Value| Err("may not leave the workdir".to_string());
+
+    let err = pre_tool_manager(cfg.clone(), true)
+        .run_pre_tool_hooks(
+            pre_tool_input(tmp.path(), serde_json::json!({"path": "/etc/passwd"})),
+            "read_file",
+            &original,
+            reject,
+        )
+        .await
+        .expect_err("strict rejects");
+    assert!(
+        err.message.contains("rewrite_arguments rejected"),
+        "{}",
+        err.message
+    );
+
+    let out = pre_tool_manager(cfg, false)
+        .run_pre_tool_hooks(
+            pre_tool_input(tmp.path(), serde_json::json!({"path": "/etc/passwd"})),
+            "read_file",
+            &original,
+            reject,
+        )
+        .await
+        .map_err(|e| e.message)
+        .expect("non strict passes");
+    assert_eq!(out.arguments, original);
+    assert!(out.rewrite.is_none());
+    assert!(!out.invocations[0].modified);
+}
+
+fn post_run_input(workdir: &std::path::Path, force_invalid_json: bool) -> HookInput {
+    make_post_run_input(
+        "r1",
+        3,
+        "ollama",
+        "m",
+        workdir,
+        serde_json::json!({
+            "run_id": "r1",
+            "exit_reason": "ok",
+            "final_output": "done",
+            "final_output_truncated": false,
+            "tool_call_count": 0,
+            "budget": {"steps": 3, "max_steps": 8, "tool_calls": 0, "max_total_tool_calls": 0, "wall_time_ms": 5, "max_wall_time_ms": 0},
+            "force_invalid_json": force_invalid_json
+        }),
+    )
+}
+
+#[tokio::test]
+async fn post_run_failure_is_reported_in_the_result_not_raised() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "post_run");
+
+    let ok = pre_tool_manager(cfg.clone(), true)
+        .run_post_run_hooks(post_run_input(tmp.path(), false))
+        .await;
+    assert!(ok.error.is_none());
+    assert_eq!(ok.invocations[0].stage, "post_run");
+    assert_eq!(ok.invocations[0].action, "pass");
+
+    let strict = pre_tool_manager(cfg.clone(), true)
+        .run_post_run_hooks(post_run_input(tmp.path(), true))
+        .await;
+    let error = strict.error.expect("strict failure reported");
+    assert!(error.contains("returned invalid JSON"), "{error}");
+    assert_eq!(strict.invocations[0].action, "error");
+
+    let lenient = pre_tool_manager(cfg, false)
+        .run_post_run_hooks(post_run_input(tmp.path(), true))
+        .await;
+    assert!(lenient.error.is_none());
+    assert_eq!(lenient.invocations[0].action, "pass");
+    assert!(lenient.invocations[0].message.is_some());
+}
+
+#[test]
+fn reload_picks_up_edits_and_keeps_previous_config_on_parse_failure() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "tool_result");
+    let mut manager = pre_tool_manager(cfg.clone(), false);
+    let first_hash = manager.config_hash_hex.clone().expect("hash");
+    assert_eq!(manager.reload(), HooksReload::Unchanged);
+
+    write_hooks_config(&cfg, "pre_model");
+    let second_hash = match manager.reload() {
+        HooksReload::Reloaded {
+            old_hash_hex,
+            new_hash_hex,
+        } => {
+            assert_eq!(old_hash_hex.as_deref(), Some(first_hash.as_str()));
+            new_hash_hex.expect("new hash")
+        }
+        other => panic!("expected reload, got {other:?}"),
+    };
+    assert_ne!(second_hash, first_hash);
+    assert!(manager.hooks[0].has_stage(HookStage::PreModel));
+
+    std::fs::write(&cfg, "version: 1\nhooks: [").expect("write");
+    match manager.reload() {
+        HooksReload::Failed {
+            active_hash_hex,
+            error,
+        } => {
+            assert_eq!(active_hash_hex.as_deref(), Some(second_hash.as_str()));
+            assert!(error.contains("failed to parse hooks config"), "{error}");
+        }
+        other => panic!("expected failure, got {other:?}"),
+    }
+    assert_eq!(
+        manager.config_hash_hex.as_deref(),
+        Some(second_hash.as_str())
+    );
+    assert!(manager.hooks[0].has_stage(HookStage::PreModel));
+}
+
+fn session_run_config(cfg: &std::path::Path, strict: bool) -> HookRuntimeConfig {
+    HookRuntimeConfig {
+        mode: HooksMode::On,
+        config_path: cfg.to_path_buf(),
+        strict,
+        timeout_ms: 2_000,
+        max_stdout_bytes: 200_000,
+        max_invocations_per_run: 0,
+        max_cumulative_ms: 0,
+        budget_strict: false,
+    }
+}
+
+#[test]
+fn session_reload_invalidates_approvals_and_strict_refuses_a_broken_config() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = tmp.path().join("hooks.yaml");
+    write_hooks_config(&cfg, "tool_result");
+    let approvals = ApprovalsStore::new(tmp.path().join("approvals.json"));
+    let session = SessionHooks::default();
+
+    let (first, reload) = session
+        .manager_for_run(session_run_config(&cfg, false), &approvals)
+        .expect("first run");
+    assert_eq!(reload.reload, HooksReload::Unchanged);
+    let first_hash = first.config_hash_hex.expect("hash");
+    let approved = approvals
+        .create_pending(
+            "shell",
+            &serde_json::json!({"cmd": "echo"}),
+            Some("k".to_string()),
+            Some(ApprovalProvenance {
+                approval_key_version: "v2".to_string(),
+                tool_schema_hash_hex: None,
+                hooks_config_hash_hex: Some(first_hash.clone()),
+                exec_target: Some("host".to_string()),
+                planner_hash_hex: None,
+            }),
+        )
+        .expect("create");
+    approvals.approve(&approved, None, None).expect("approve");
+
+    write_hooks_config(&cfg, "pre_model");
+    let (second, reload) = session
+        .manager_for_run(session_run_config(&cfg, false), &approvals)
+        .expect("second run");
+    assert!(matches!(
+        reload.reload,
+        HooksReload::Reloaded { ref old_hash_hex, .. } if old_hash_hex.as_deref() == Some(first_hash.as_str())
+    ));
+    assert_eq!(reload.approvals_invalidated, 1);
+    assert!(approvals.list().expect("list").requests.is_empty());
+    let second_hash = second.config_hash_hex.expect("hash");
+
+    std::fs::write(&cfg, "version: 1\nhooks: [").expect("write");
+    let err = session
+        .manager_for_run(session_run_config(&cfg, true), &approvals)
+        .expect_err("strict refuses the run");
+    assert!(
+        err.to_string().contains("hooks config reload failed"),
+        "{err}"
+    );
+    let (kept, reload) = session
+        .manager_for_run(session_run_config(&cfg, false), &approvals)
+        .expect("non-strict keeps previous hooks");
+    assert!(matches!(reload.reload, HooksReload::Failed { .. }));
+    assert_eq!(kept.config_hash_hex.as_deref(), Some(second_hash.as_str()));
+    assert!(kept.hooks[0].has_stage(HookStage::PreModel));
+}
@@ -0,0 +1,229 @@
+use localagent::hooks::config::HookStage;
+use localagent::hooks::validate::{
+    dry_fire_hooks, render_validation_report, validate_hooks_config, HookDiagnosticSeverity,
+    HooksValidateExit,
+};
+
+fn write(dir: &std::path::Path, name: &str, content: &str) -> std::path::PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, content).expect("write");
+    path
+}
+
+#[test]
+fn unknown_keys_warn_with_nearest_match_and_line() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = write(
+        tmp.path(),
+        "hooks.yaml",
+        r#"version: 1
+hooks:
+  - name: redact
+    stages: ["tool_result"]
+    command: "sh"
+    timout_ms: 2000
+    match:
+      tool: ["shell"]
+"#,
+    );
+    let report = validate_hooks_config(&cfg, tmp.path());
+    let warnings = report.warnings().collect::<Vec<_>>();
+    assert_eq!(warnings.len(), 2, "{:?}", report.diagnostics);
+    assert_eq!(warnings[0].hook.as_deref(), Some("redact"));
+    assert_eq!(warnings[0].line, Some(6));
+    assert_eq!(
+        warnings[0].message,
+        "unknown key 'timout_ms' is ignored; did you mean 'timeout_ms'?"
+    );
+    assert_eq!(warnings[1].line, Some(8));
+    assert_eq!(
+        warnings[1].message,
+        "unknown key 'match.tool' is ignored; did you mean 'tools'?"
+    );
+    assert_eq!(report.hooks, vec!["redact"]);
+    assert_eq!(report.exit(), HooksValidateExit::Warnings);
+}
+
+#[test]
+fn missing_script_and_command_are_warnings_not_errors() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = write(
+        tmp.path(),
+        "hooks.yaml",
+        r#"version: 1
+hooks:
+  - name: lint
+    stages: ["pre_model"]
+    command: "sh"
+    args: ["scripts/missing_hook.sh"]
+  - name: local
+    stages: ["pre_model"]
+    command: "./bin/not-there"
+  - name: ghost
+    stages: ["pre_model"]
+    command: "localagent-definitely-not-on-path"
+"#,
+    );
+    let report = validate_hooks_config(&cfg, tmp.path());
+    assert_eq!(report.errors().count(), 0, "{:?}", report.diagnostics);
+    let messages = report
+        .warnings()
+        .map(|d| (d.hook.clone().unwrap_or_default(), d.message.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(messages.len(), 3, "{messages:?}");
+    assert_eq!(messages[0].0, "lint");
+    assert!(messages[0].1.starts_with("script '"));
+    assert!(messages[0].1.ends_with("missing_hook.sh' does not exist"));
+    assert_eq!(messages[1].0, "local");
+    assert!(messages[1].1.ends_with("not-there' does not exist"));
+    assert_eq!(
+        messages[2].1,
+        "command 'localagent-definitely-not-on-path' was not found on PATH"
+    );
+
+    std::fs::create_dir_all(tmp.path().join("scripts")).expect("mkdir");
+    std::fs::write(tmp.path().join("scripts/missing_hook.sh"), "exit 0\n").expect("write");
+    let report = validate_hooks_config(&cfg, tmp.path());
+    assert!(!report.warnings().any(|d| d.hook.as_deref() == Some("lint")));
+}
+
+#[test]
+fn per_hook_errors_are_reported_together_with_line_context() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = write(
+        tmp.path(),
+        "hooks.yaml",
+        r#"version: 1
+hooks:
+  - name: no_stages
+    stages: []
+    command: "sh"
+  - name: bad_glob
+    stages: ["tool_result"]
+    command: "sh"
+    match:
+      tools: ["mcp.[x"]
+"#,
+    );
+    let report = validate_hooks_config(&cfg, tmp.path());
+    let errors = report.errors().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 2, "{:?}", report.diagnostics);
+    assert_eq!(errors[0].hook.as_deref(), Some("no_stages"));
+    assert_eq!(errors[0].line, Some(3));
+    assert!(errors[0].message.contains("at least one stage"));
+    assert_eq!(errors[1].hook.as_deref(), Some("bad_glob"));
+    assert_eq!(errors[1].line, Some(6));
+    assert!(errors[1].message.contains("invalid tool glob"));
+    assert_eq!(report.exit(), HooksValidateExit::Invalid);
+
+    let text = render_validation_report(&report);
+    assert!(text.contains(&format!("ERROR: {}:3: hook 'no_stages': ", cfg.display())));
+    assert!(text.ends_with("FAIL: hooks config invalid errors=2 warnings=0\n"));
+}
+
+#[test]
+fn parse_errors_carry_yaml_location() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let cfg = write(
+        tmp.path(),
+        "hooks.yaml",
+        "version: 1\nhooks:\n  - name: a\n    stages: [\"post_model\"]\n    command: \"sh\"\n",
+    );
+    let report = validate_hooks_config(&cfg, tmp.path());
+    let errors = report.errors().collect::<Vec<_>>();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].severity, HookDiagnosticSeverity::Error);
+    assert_eq!(errors[0].line, Some(4));
+    assert!(errors[0].message.contains("post_model"));
+
+    let missing = validate_hooks_config(&tmp.path().join("absent.yaml"), tmp.path());
+    assert_eq!(missing.exit(), HooksValidateExit::Invalid);
+    assert_eq!(missing.config_hash_hex, None);
+}
+
+#[test]
+fn report_prints_config_hash_and_maps_clean_config_to_ok() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let content =
+        "version: 1\nhooks:\n  - name: a\n    stages: [\"pre_model\"]\n    command: \"sh\"\n";
+    let cfg = write(tmp.path(), "hooks.yaml", content);
+    let report = validate_hooks_config(&cfg, tmp.path());
+    let hash = localagent::store::sha256_hex(content.as_bytes());
+    assert_eq!(report.config_hash_hex.as_deref(), Some(hash.as_str()));
+    assert_eq!(report.exit(), HooksValidateExit::Ok);
+    let text = render_validation_report(&report);
+    assert!(text.contains(&format!("hooks_config_hash_hex: {hash}\n")));
+    assert!(text.ends_with("OK: hooks config valid hooks=1 warnings=0\n"));
+    assert_eq!(HooksValidateExit::Ok as i32, 0);
+    assert_eq!(HooksValidateExit::Warnings as i32, 1);
+    assert_eq!(HooksValidateExit::Invalid as i32, 2);
+    assert_eq!(HooksValidateExit::DryFireFailed as i32, 3);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn dry_fire_runs_named_hook_with_synthetic_payload_in_scratch_dir() {
+    let tmp = tempfile::tempdir().expect("tmp");
+    let capture = tmp.path().join("captured.json");
+    let cwd_capture = tmp.path().join("cwd.txt");
+    let cfg = write(
+        tmp.path(),
+        "hooks.yaml",
+        &format!(
+            r#"version: 1
+hooks:
+  - name: capture
+    stages: ["tool_result"]
+    command: "sh"
+    args:
+      - "-c"
+      - 'cat > "{}"; pwd > "{}"; printf "{{\"schema_version\":\"openagent.hook_output.v1\",\"action\":\"pass\",\"message\":\"seen\"}}"'
+  - name: other
+    stages: ["tool_result"]
+    command: "sh"
+    args: ["-c", "exit 7"]
+"#,
+            capture.display(),
+            cwd_capture.display()
+        ),
+    );
+
+    let results = dry_fire_hooks(&cfg, HookStage::ToolResult, Some("capture"), 5_000, 10_000)
+        .await
+        .expect("dry fire");
+    assert_eq!(results.len(), 1);
+    assert!(results[0].ok, "{results:?}");
+    assert_eq!(results[0].action.as_deref(), Some("pass"));
+    assert_eq!(results[0].message.as_deref(), Some("seen"));
+
+    let input: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&capture).expect("capture")).expect("json");
+    assert_eq!(input["stage"], "tool_result");
+    assert!(input["run_id"]
+        .as_str()
+        .is_some_and(|r| r.starts_with("dry_fire_")));
+    assert_eq!(input["payload"]["tool_name"], "read_file");
+    assert_eq!(
+        input["payload"]["content"],
+        "hooks dry-fire: synthetic tool output"
+    );
+    let scratch = std::fs::read_to_string(&cwd_capture).expect("cwd");
+    let scratch = scratch.trim();
+    assert!(scratch.contains("localagent-hooks-dry-fire-"), "{scratch}");
+    assert_eq!(input["workdir"].as_str(), Some(scratch));
+    assert!(!std::path::Path::new(scratch).exists());
+
+    let failing = dry_fire_hooks(&cfg, HookStage::ToolResult, Some("other"), 5_000, 10_000)
+        .await
+        .expect("dry fire");
+    assert!(!failing[0].ok);
+    assert!(failing[0]
+        .message
+        .as_deref()
+        .is_some_and(|m| m.contains("exited with")));
+
+    let err = dry_fire_hooks(&cfg, HookStage::PreModel, None, 5_000, 10_000)
+        .await
+        .expect_err("no pre_model hooks");
+    assert_eq!(err.to_string(), "no hooks with stage pre_model");
+}
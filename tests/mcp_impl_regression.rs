@@ -5,27 +5,17 @@ use std::time::Duration;
 use std::{collections::BTreeMap, fs};
 
 use async_trait::async_trait;
-use localagent::agent::{
-    Agent, AgentExitReason, McpPinEnforcementMode, PlanStepConstraint, PlanToolEnforcementMode,
-    ToolCallBudget,
-};
-use localagent::compaction::{CompactionMode, CompactionSettings, ToolResultPersist};
+use localagent::agent::{Agent, AgentExitReason, McpPinEnforcementMode};
 use localagent::events::{Event, EventKind, EventSink};
-use localagent::gate::{
-    compute_policy_hash_hex, ApprovalKeyVersion, ApprovalMode, AutoApproveScope, GateContext,
-    NoGate, ProviderKind, ToolGate, TrustGate, TrustMode,
-};
-use localagent::hooks::config::HooksMode;
-use localagent::hooks::runner::{HookManager, HookRuntimeConfig};
+use localagent::gate::{compute_policy_hash_hex, NoGate, ToolGate, TrustGate, TrustMode};
+use localagent::mcp::health::McpReconnectPolicy;
 use localagent::mcp::registry::McpRegistry;
 use localagent::mcp::types::{McpConfigFile, McpServerConfig};
 use localagent::planner::RunMode;
 use localagent::providers::mock::MockProvider;
 use localagent::providers::ModelProvider;
 use localagent::store::{self, PolicyRecordInfo, RunCliConfig, ToolCatalogEntry, WorkerRunRecord};
-use localagent::taint::{TaintLevel, TaintMode, TaintToggle};
-use localagent::target::{ExecTargetKind, HostTarget};
-use localagent::tools::{builtin_tools_enabled, ToolArgsStrict, ToolRuntime};
+use localagent::tools::builtin_tools_enabled;
 use localagent::trust::approvals::ApprovalsStore;
 use localagent::trust::audit::AuditLog;
 use localagent::trust::policy::Policy;
@@ -97,6 +87,8 @@ enum ScriptStep {
         name: &'static str,
         arguments: Value,
     },
+    /// Several `(id, name, arguments)` calls in one assistant step.
+    Batch(Vec<(&'static str, &'static str, Value)>),
     Final(&'static str),
 }
 
@@ -119,21 +111,8 @@ impl ModelProvider for ScriptedProvider {
                 id,
                 name,
                 arguments,
-            } => GenerateResponse {
-                assistant: Message {
-                    role: Role::Assistant,
-                    content: Some(String::new()),
-                    tool_call_id: None,
-                    tool_name: None,
-                    tool_calls: None,
-                },
-                tool_calls: vec![ToolCall {
-                    id: id.to_string(),
-                    name: name.to_string(),
-                    arguments,
-                }],
-                usage: None,
-            },
+            } => tool_call_response(vec![(id, name, arguments)]),
+            ScriptStep::Batch(calls) => tool_call_response(calls),
             ScriptStep::Final(text) => GenerateResponse {
                 assistant: Message {
                     role: Role::Assistant,
@@ -144,12 +123,35 @@ impl ModelProvider for ScriptedProvider {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             },
         };
         Ok(response)
     }
 }
 
+fn tool_call_response(calls: Vec<(&'static str, &'static str, Value)>) -> GenerateResponse {
+    GenerateResponse {
+        assistant: Message {
+            role: Role::Assistant,
+            content: Some(String::new()),
+            tool_call_id: None,
+            tool_name: None,
+            tool_calls: None,
+        },
+        tool_calls: calls
+            .into_iter()
+            .map(|(id, name, arguments)| ToolCall {
+                id: id.to_string(),
+                name: name.to_string(),
+                arguments,
+            })
+            .collect(),
+        usage: None,
+        served_model: None,
+    }
+}
+
 fn make_agent<P: ModelProvider + 'static>(
     provider: P,
     workdir: &Path,
@@ -182,88 +184,19 @@ fn make_agent_with_mcp<P: ModelProvider + 'static>(
     if let Some(reg) = mcp_registry.as_ref() {
         tools.extend(reg.tool_defs());
     }
-    Agent {
-        provider,
-        model: "mock-model".to_string(),
-        temperature: None,
-        top_p: None,
-        max_tokens: None,
-        seed: None,
-        tools,
-        max_steps: 8,
-        tool_rt: ToolRuntime {
-            workdir: workdir.to_path_buf(),
-            allow_shell,
-            allow_shell_in_workdir_only: false,
-            allow_write,
-            max_tool_output_bytes: 200_000,
-            max_read_bytes: 200_000,
-            unsafe_bypass_allow_flags: false,
-            tool_args_strict: ToolArgsStrict::On,
-            exec_target_kind: ExecTargetKind::Host,
-            exec_target: Arc::new(HostTarget),
-        },
-        gate,
-        gate_ctx: GateContext {
-            workdir: workdir.to_path_buf(),
-            allow_shell,
-            allow_write,
-            approval_mode: ApprovalMode::Interrupt,
-            auto_approve_scope: AutoApproveScope::Run,
-            unsafe_mode: false,
-            unsafe_bypass_allow_flags: false,
-            run_id: None,
-            enable_write_tools,
-            max_tool_output_bytes: 200_000,
-            max_read_bytes: 200_000,
-            provider: ProviderKind::Mock,
-            model: "mock-model".to_string(),
-            exec_target: ExecTargetKind::Host,
-            approval_key_version: ApprovalKeyVersion::V1,
-            tool_schema_hashes: std::collections::BTreeMap::new(),
-            hooks_config_hash_hex: None,
-            planner_hash_hex: None,
-            taint_enabled: false,
-            taint_mode: TaintMode::Propagate,
-            taint_overall: TaintLevel::Clean,
-            taint_sources: Vec::new(),
-        },
-        validation_requirement: None,
-        final_answer_mode: None,
-        mcp_registry,
-        stream: false,
-        event_sink: None,
-        compaction_settings: CompactionSettings {
-            max_context_chars: 0,
-            mode: CompactionMode::Off,
-            keep_last: 20,
-            tool_result_persist: ToolResultPersist::Digest,
-        },
-        hooks: HookManager::build(HookRuntimeConfig {
-            mode: HooksMode::Off,
-            config_path: std::env::temp_dir().join("unused_hooks.yaml"),
-            strict: false,
-            timeout_ms: 1_000,
-            max_stdout_bytes: 200_000,
-        })
-        .expect("hooks"),
-        policy_loaded: None,
-        policy_for_taint: None,
-        taint_toggle: TaintToggle::Off,
-        taint_mode: TaintMode::Propagate,
-        taint_digest_bytes: 4096,
-        run_id_override: None,
-        omit_tools_field_when_empty: false,
-        plan_tool_enforcement: PlanToolEnforcementMode::Off,
-        mcp_pin_enforcement: McpPinEnforcementMode::Hard,
-        plan_step_constraints: Vec::<PlanStepConstraint>::new(),
-        current_plan: Vec::new(),
-        tool_call_budget: ToolCallBudget::default(),
-        mcp_runtime_trace: Vec::new(),
-        operator_queue: localagent::operator_queue::PendingMessageQueue::default(),
-        operator_queue_limits: localagent::operator_queue::QueueLimits::default(),
-        operator_queue_rx: None,
+    let mut builder = Agent::builder(provider)
+        .model("mock-model")
+        .workdir(workdir)
+        .allow_shell(allow_shell)
+        .allow_write(allow_write)
+        .enable_write_tools(enable_write_tools)
+        .tools(tools)
+        .max_steps(8)
+        .gate(gate);
+    if let Some(registry) = mcp_registry {
+        builder = builder.mcp_registry(registry);
     }
+    builder.build().expect("agent")
 }
 
 fn stub_bin() -> Option<String> {
@@ -271,6 +204,15 @@ fn stub_bin() -> Option<String> {
 }
 
 async fn build_stub_registry(tmp: &Path, server_name: &str) -> Option<Arc<McpRegistry>> {
+    build_stub_registry_with_args(tmp, server_name, Vec::new(), McpReconnectPolicy::default()).await
+}
+
+async fn build_stub_registry_with_args(
+    tmp: &Path,
+    server_name: &str,
+    args: Vec<String>,
+    reconnect_policy: McpReconnectPolicy,
+) -> Option<Arc<McpRegistry>> {
     let Some(stub) = stub_bin() else {
         eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
         return None;
@@ -281,7 +223,7 @@ async fn build_stub_registry(tmp: &Path, server_name: &str) -> Option<Arc<McpReg
         server_name.to_string(),
         McpServerConfig {
             command: stub,
-            args: vec![],
+            args,
         },
     );
     let cfg = McpConfigFile {
@@ -300,7 +242,7 @@ async fn build_stub_registry(tmp: &Path, server_name: &str) -> Option<Arc<McpReg
     )
     .await
     .expect("start mcp registry");
-    Some(Arc::new(reg))
+    Some(Arc::new(reg.with_reconnect_policy(reconnect_policy)))
 }
 
 fn minimal_cli_config_for_mcp_test() -> RunCliConfig {
@@ -327,6 +269,9 @@ fn minimal_cli_config_for_mcp_test() -> RunCliConfig {
         allow_write: true,
         enable_write_tools: true,
         exec_target: "host".to_string(),
+        read_allowlist: Vec::new(),
+        shell_allowlist: Vec::new(),
+        max_tool_calls: Default::default(),
         docker_image: None,
         docker_workdir: None,
         docker_network: None,
@@ -394,6 +339,7 @@ fn minimal_cli_config_for_mcp_test() -> RunCliConfig {
         mcp_tool_catalog_hash_hex: None,
         mcp_servers: vec!["stub".to_string()],
         mcp_config_path: None,
+        mcp_roots: Vec::new(),
         policy_version: Some(1),
         includes_resolved: Vec::new(),
         mcp_allowlist: None,
@@ -433,6 +379,10 @@ fn minimal_cli_config_for_mcp_test() -> RunCliConfig {
         profile_source: None,
         profile_hash_hex: None,
         activated_packs: Vec::new(),
+        context_pack: None,
+        prompt_template: None,
+        learned_guidance: Vec::new(),
+        mcp_server_launches: Vec::new(),
     }
 }
 
@@ -990,6 +940,10 @@ rules:
         None,
         Vec::new(),
         None,
+        None,
+        None,
+        None,
+        None,
     )
     .expect("write run artifact");
     assert!(artifact_path.exists());
@@ -1138,3 +1092,378 @@ rules:
     assert!(mcp_tool_msg.contains("\"schema_version\":\"openagent.tool_result.v1\""));
     assert!(mcp_tool_msg.contains("BEGIN_MCP_ADVERSARIAL"));
 }
+
+#[tokio::test]
+async fn mcp_output_over_limit_emits_truncation_events_and_rejects_oversize() {
+    let tmp = tempdir().expect("tempdir");
+    let Some(reg) = build_stub_registry(tmp.path(), "stub").await else {
+        return;
+    };
+    let provider = ScriptedProvider {
+        steps: vec![
+            ScriptStep::Tool {
+                id: "tc_over",
+                name: "mcp.stub.echo",
+                arguments: serde_json::json!({ "msg": "a".repeat(2_000) }),
+            },
+            ScriptStep::Tool {
+                id: "tc_huge",
+                name: "mcp.stub.echo",
+                arguments: serde_json::json!({ "msg": "b".repeat(8_000) }),
+            },
+            ScriptStep::Final("done"),
+        ],
+        next: AtomicUsize::new(0),
+    };
+    let events = Arc::new(Mutex::new(Vec::<Event>::new()));
+    let mut agent = make_agent_with_mcp(
+        provider,
+        tmp.path(),
+        Box::new(NoGate::new()),
+        false,
+        false,
+        false,
+        Some(reg),
+    );
+    agent.tool_rt.max_tool_output_bytes = 1_000;
+    agent.event_sink = Some(Box::new(EventCaptureSink {
+        events: events.clone(),
+    }));
+
+    let out = agent.run("Echo twice.", vec![], Vec::new()).await;
+    assert!(matches!(out.exit_reason, AgentExitReason::Ok));
+
+    let truncations = events
+        .lock()
+        .expect("lock")
+        .iter()
+        .filter(|e| matches!(e.kind, EventKind::ToolOutputTruncated))
+        .map(|e| e.data.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(truncations.len(), 2);
+    assert_eq!(truncations[0]["tool_call_id"], "tc_over");
+    assert_eq!(truncations[0]["server"], "stub");
+    assert_eq!(truncations[0]["tool"], "echo");
+    assert_eq!(truncations[0]["kept_bytes"], 1_000);
+    assert_eq!(truncations[0]["rejected"], false);
+    assert!(truncations[0]["original_bytes"].as_u64().expect("bytes") > 2_000);
+    assert_eq!(truncations[1]["tool_call_id"], "tc_huge");
+    assert_eq!(truncations[1]["kept_bytes"], 0);
+    assert_eq!(truncations[1]["rejected"], true);
+
+    let huge_result = out
+        .messages
+        .iter()
+        .find(|m| m.tool_call_id.as_deref() == Some("tc_huge"))
+        .and_then(|m| m.content.as_deref())
+        .expect("rejected result");
+    assert!(huge_result.contains("\"code\":\"tool_output_oversize\""));
+    assert!(!huge_result.contains("bbbb"));
+}
+
+#[tokio::test]
+async fn mcp_server_crash_degrades_then_fails_fast_and_is_traced() {
+    let tmp = tempdir().expect("tempdir");
+    let Some(reg) = build_stub_registry_with_args(
+        tmp.path(),
+        "stub",
+        vec!["--crash-tool".to_string()],
+        McpReconnectPolicy {
+            max_attempts: 1,
+            base_backoff_ms: 60_000,
+        },
+    )
+    .await
+    else {
+        return;
+    };
+    let provider = ScriptedProvider {
+        steps: vec![
+            ScriptStep::Tool {
+                id: "tc_crash",
+                name: "mcp.stub.crash",
+                arguments: serde_json::json!({}),
+            },
+            ScriptStep::Tool {
+                id: "tc_echo",
+                name: "mcp.stub.echo",
+                arguments: serde_json::json!({"msg":"hi"}),
+            },
+            ScriptStep::Final("done"),
+        ],
+        next: AtomicUsize::new(0),
+    };
+    let events = Arc::new(Mutex::new(Vec::<Event>::new()));
+    let mut agent = make_agent_with_mcp(
+        provider,
+        tmp.path(),
+        Box::new(NoGate::new()),
+        false,
+        false,
+        false,
+        Some(reg),
+    );
+    agent.event_sink = Some(Box::new(EventCaptureSink {
+        events: events.clone(),
+    }));
+
+    let started = std::time::Instant::now();
+    let out = agent.run("Call the server.", vec![], Vec::new()).await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(
+        matches!(out.exit_reason, AgentExitReason::Ok),
+        "{:?} {:?}",
+        out.exit_reason,
+        out.error
+    );
+
+    let degraded = events
+        .lock()
+        .expect("lock")
+        .iter()
+        .filter(|e| matches!(e.kind, EventKind::McpServerDegraded))
+        .map(|e| e.data.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(degraded.len(), 1);
+    assert_eq!(degraded[0]["server"], "stub");
+    assert_eq!(degraded[0]["tool_call_id"], "tc_crash");
+    assert_eq!(degraded[0]["reconnect_attempts"], 0);
+    assert_eq!(degraded[0]["retry_after_ms"], 60_000);
+
+    let echo = out
+        .messages
+        .iter()
+        .find(|m| m.tool_call_id.as_deref() == Some("tc_echo"))
+        .and_then(|m| m.content.as_deref())
+        .expect("echo result");
+    let envelope: serde_json::Value = serde_json::from_str(echo).expect("envelope");
+    assert_eq!(envelope["ok"], false);
+    assert_eq!(envelope["error"]["code"], "mcp_server_degraded");
+    let content: serde_json::Value =
+        serde_json::from_str(envelope["content"].as_str().expect("content")).expect("json");
+    assert_eq!(content["error"], "E_NETWORK_TRANSIENT");
+    assert_eq!(content["server_degraded"], true);
+
+    let window = agent
+        .mcp_runtime_trace
+        .iter()
+        .filter(|e| e.lifecycle == "server_degraded")
+        .collect::<Vec<_>>();
+    assert_eq!(window.len(), 1);
+    assert_eq!(window[0].server.as_deref(), Some("stub"));
+    assert_eq!(window[0].tool_call_id.as_deref(), Some("tc_crash"));
+}
+
+#[tokio::test]
+async fn mcp_schema_drift_between_steps_is_denied_in_hard_and_warned_in_warn_mode() {
+    for enforcement in [McpPinEnforcementMode::Hard, McpPinEnforcementMode::Warn] {
+        let tmp = tempdir().expect("tempdir");
+        let Some(reg) = build_stub_registry_with_args(
+            tmp.path(),
+            "stub",
+            vec!["--mutate-schema-after-call".to_string()],
+            McpReconnectPolicy::default(),
+        )
+        .await
+        else {
+            return;
+        };
+        let provider = ScriptedProvider {
+            steps: vec![
+                ScriptStep::Tool {
+                    id: "tc_1",
+                    name: "mcp.stub.echo",
+                    arguments: serde_json::json!({"msg":"one"}),
+                },
+                ScriptStep::Tool {
+                    id: "tc_2",
+                    name: "mcp.stub.echo",
+                    arguments: serde_json::json!({"msg":"two"}),
+                },
+                ScriptStep::Final("done"),
+            ],
+            next: AtomicUsize::new(0),
+        };
+        let events = Arc::new(Mutex::new(Vec::<Event>::new()));
+        let mut agent = make_agent_with_mcp(
+            provider,
+            tmp.path(),
+            Box::new(NoGate::new()),
+            false,
+            false,
+            false,
+            Some(reg),
+        );
+        agent.mcp_pin_enforcement = enforcement;
+        agent.gate_ctx.tool_schema_hashes = store::tool_schema_hash_hex_map(&agent.tools);
+        let pinned = agent.gate_ctx.tool_schema_hashes["mcp.stub.echo"].clone();
+        agent.event_sink = Some(Box::new(EventCaptureSink {
+            events: events.clone(),
+        }));
+
+        let out = agent.run("Echo twice.", vec![], Vec::new()).await;
+
+        let drift = events
+            .lock()
+            .expect("lock")
+            .iter()
+            .filter(|e| matches!(e.kind, EventKind::McpDrift))
+            .map(|e| e.data.clone())
+            .find(|d| d["primary_code"] == "MCP_SCHEMA_DRIFT")
+            .expect("schema drift event");
+        assert_eq!(drift["tool_call_id"], "tc_2");
+        assert_eq!(drift["schema_hash_pinned"], pinned.as_str());
+        let live = drift["schema_hash_live"].as_str().expect("live hash");
+        assert_ne!(live, pinned);
+        let audit = agent
+            .mcp_runtime_trace
+            .iter()
+            .find(|e| e.lifecycle == "drift" && e.tool_call_id.as_deref() == Some("tc_2"))
+            .expect("drift trace entry");
+        assert_eq!(audit.schema_hash_hex.as_deref(), Some(live));
+
+        let decision = out
+            .tool_decisions
+            .iter()
+            .find(|d| d.tool_call_id == "tc_2")
+            .expect("tc_2 decision");
+        let ran_second_call = out
+            .messages
+            .iter()
+            .any(|m| m.tool_call_id.as_deref() == Some("tc_2"));
+        if enforcement == McpPinEnforcementMode::Hard {
+            assert!(matches!(out.exit_reason, AgentExitReason::Denied));
+            assert_eq!(decision.decision, "deny");
+            assert_eq!(decision.source.as_deref(), Some("mcp_pin"));
+            assert!(decision
+                .reason
+                .as_deref()
+                .unwrap_or_default()
+                .starts_with("MCP_SCHEMA_DRIFT"));
+            assert!(!ran_second_call);
+        } else {
+            assert!(
+                matches!(out.exit_reason, AgentExitReason::Ok),
+                "{:?}",
+                out.error
+            );
+            assert_eq!(decision.decision, "allow");
+            assert_eq!(decision.source.as_deref(), Some("mcp_pin_warn"));
+            assert!(ran_second_call);
+            assert_eq!(
+                agent.gate_ctx.tool_schema_hashes["mcp.stub.echo"].as_str(),
+                live
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn mcp_tools_marked_read_only_run_as_one_parallel_batch() {
+    let tmp = tempdir().expect("tempdir");
+    let Some(reg) = build_stub_registry_with_args(
+        tmp.path(),
+        "stub",
+        vec!["--read-only-tools".to_string()],
+        McpReconnectPolicy::default(),
+    )
+    .await
+    else {
+        return;
+    };
+    assert!(reg.tool_is_read_only("mcp.stub.lookup"));
+    assert!(!reg.tool_is_read_only("mcp.stub.echo"));
+    let provider = ScriptedProvider {
+        steps: vec![
+            ScriptStep::Batch(vec![
+                ("tc_1", "mcp.stub.lookup", serde_json::json!({"key":"one"})),
+                ("tc_2", "mcp.stub.lookup", serde_json::json!({"key":"two"})),
+            ]),
+            ScriptStep::Final("done"),
+        ],
+        next: AtomicUsize::new(0),
+    };
+    let events = Arc::new(Mutex::new(Vec::<Event>::new()));
+    let mut agent = make_agent_with_mcp(
+        provider,
+        tmp.path(),
+        Box::new(NoGate::new()),
+        false,
+        false,
+        false,
+        Some(reg),
+    );
+    agent.parallel_readonly_tools = true;
+    agent.event_sink = Some(Box::new(EventCaptureSink {
+        events: events.clone(),
+    }));
+
+    let out = agent.run("Look up both keys.", vec![], Vec::new()).await;
+
+    assert!(
+        matches!(out.exit_reason, AgentExitReason::Ok),
+        "{:?}",
+        out.error
+    );
+    for (id, key) in [("tc_1", "one"), ("tc_2", "two")] {
+        let content = out
+            .messages
+            .iter()
+            .find(|m| m.tool_call_id.as_deref() == Some(id))
+            .and_then(|m| m.content.clone())
+            .expect("tool result");
+        assert!(content.contains(key), "{content}");
+    }
+    let batch_ids = events
+        .lock()
+        .expect("lock")
+        .iter()
+        .filter(|e| matches!(e.kind, EventKind::ToolExecStart))
+        .map(|e| e.data["batch_id"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        batch_ids,
+        vec![Value::from("step-0"), Value::from("step-0")]
+    );
+}
+
+#[tokio::test]
+async fn mcp_tools_without_read_only_hint_are_not_batched() {
+    let tmp = tempdir().expect("tempdir");
+    let Some(reg) = build_stub_registry_with_args(
+        tmp.path(),
+        "stub",
+        vec!["--read-only-tools".to_string()],
+        McpReconnectPolicy::default(),
+    )
+    .await
+    else {
+        return;
+    };
+    let provider = ScriptedProvider {
+        steps: vec![ScriptStep::Batch(vec![
+            ("tc_1", "mcp.stub.lookup", serde_json::json!({"key":"one"})),
+            ("tc_2", "mcp.stub.echo", serde_json::json!({"msg":"two"})),
+        ])],
+        next: AtomicUsize::new(0),
+    };
+    let mut agent = make_agent_with_mcp(
+        provider,
+        tmp.path(),
+        Box::new(NoGate::new()),
+        false,
+        false,
+        false,
+        Some(reg),
+    );
+    agent.parallel_readonly_tools = true;
+
+    let out = agent.run("Look up and echo.", vec![], Vec::new()).await;
+
+    assert!(matches!(out.exit_reason, AgentExitReason::PlannerError));
+    assert!(out
+        .error
+        .as_deref()
+        .unwrap_or_default()
+        .contains("multiple tool calls in a single assistant step"));
+}
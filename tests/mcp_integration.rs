@@ -4,9 +4,15 @@ use std::time::Duration;
 use localagent::agent::{AgentExitReason, AgentOutcome};
 use localagent::compaction::{CompactionMode, CompactionSettings, ToolResultPersist};
 use localagent::eval::assert::{evaluate_assertions, Assertion};
+use localagent::mcp::health::McpReconnectPolicy;
 use localagent::mcp::registry::McpRegistry;
+use localagent::mcp::sanitize::{
+    McpMetadataAction, McpMetadataPolicy, McpToolRejectReason, NEUTRALIZED_DESCRIPTION,
+};
 use localagent::mcp::types::{McpConfigFile, McpServerConfig};
-use localagent::tools::ToolArgsStrict;
+use localagent::store::{load_run_artifact_manifest, RunArtifactStore};
+use localagent::target::{ExecTargetKind, HostTarget};
+use localagent::tools::{execute_tool, ToolArgsStrict, ToolRuntime};
 use localagent::trust::policy::{Policy, PolicyDecision};
 use localagent::types::{Message, ToolCall};
 use serde_json::json;
@@ -316,6 +322,7 @@ fn b5_negative_assertion_fails_when_shell_attempted() {
         tool_decisions: Vec::new(),
         compaction_settings: CompactionSettings {
             max_context_chars: 0,
+            max_context_tokens: None,
             mode: CompactionMode::Off,
             keep_last: 20,
             tool_result_persist: ToolResultPersist::Digest,
@@ -325,8 +332,16 @@ fn b5_negative_assertion_fails_when_shell_attempted() {
         hook_invocations: Vec::new(),
         provider_retry_count: 0,
         provider_error_count: 0,
+        provider_retry_wait_ms: 0,
         token_usage: None,
         taint: None,
+        digest_refetch: None,
+        tool_result_artifacts: Vec::new(),
+        model_served: None,
+        subtask_run_ids: Vec::new(),
+        post_run_hook_error: None,
+        timeline: Vec::new(),
+        steps: Vec::new(),
     };
     let failures = evaluate_assertions(
         &[Assertion::ToolNotUsedGlob {
@@ -337,3 +352,586 @@ fn b5_negative_assertion_fails_when_shell_attempted() {
     );
     assert_eq!(failures.len(), 1);
 }
+
+fn write_adversarial_stub_config(dir: &std::path::Path, stub: String) -> std::path::PathBuf {
+    let cfg_path = dir.join("mcp_servers.json");
+    let mut servers = std::collections::BTreeMap::new();
+    servers.insert(
+        "stub".to_string(),
+        McpServerConfig {
+            command: stub,
+            args: vec!["--adversarial-descriptions".to_string()],
+        },
+    );
+    let cfg = McpConfigFile {
+        schema_version: "openagent.mcp_servers.v1".to_string(),
+        servers,
+    };
+    fs::write(
+        &cfg_path,
+        serde_json::to_string_pretty(&cfg).expect("serialize"),
+    )
+    .expect("write config");
+    cfg_path
+}
+
+#[tokio::test]
+async fn adversarial_tool_descriptions_are_sanitized_at_registry_time() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = write_adversarial_stub_config(tmp.path(), stub);
+    let reg = McpRegistry::from_config_path_with_metadata_policy(
+        &cfg_path,
+        &["stub".to_string()],
+        Duration::from_secs(5),
+        McpMetadataPolicy::default(),
+    )
+    .await
+    .expect("start registry");
+
+    let echo = reg.render_tool_docs_text("mcp.stub.echo");
+    assert!(echo.contains("raw_description_preview:\n  Echo arguments"));
+    let fetch = reg.render_tool_docs_text("mcp.stub.fetch");
+    assert!(fetch.contains(NEUTRALIZED_DESCRIPTION));
+    assert!(!fetch.contains("id_rsa"));
+    let notes = reg.render_tool_docs_text("mcp.stub.notes");
+    assert!(notes.contains("Read notes."));
+    assert!(!notes.contains("BEGIN_SYSTEM_OVERRIDE"));
+    assert!(!notes.contains("system:"));
+
+    let records = reg.metadata_sanitized();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].tool, "fetch");
+    assert_eq!(records[0].action, McpMetadataAction::Neutralized);
+    assert_eq!(records[1].tool, "notes");
+    assert_eq!(records[1].action, McpMetadataAction::Stripped);
+
+    assert_eq!(
+        reg.configured_tool_docs_hash_hex()
+            .expect("configured docs"),
+        reg.live_tool_docs_hash_hex().await.expect("live docs")
+    );
+}
+
+#[tokio::test]
+async fn strict_metadata_mode_excludes_injected_tools() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = write_adversarial_stub_config(tmp.path(), stub);
+    let reg = McpRegistry::from_config_path_with_metadata_policy(
+        &cfg_path,
+        &["stub".to_string()],
+        Duration::from_secs(5),
+        McpMetadataPolicy::new(true, &[]),
+    )
+    .await
+    .expect("start registry");
+    let names = reg
+        .tool_defs()
+        .iter()
+        .map(|t| t.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["mcp.stub.echo", "mcp.stub.notes"]);
+    assert_eq!(
+        reg.metadata_sanitized()[0].action,
+        McpMetadataAction::Excluded
+    );
+    assert_eq!(
+        reg.configured_tool_catalog_hash_hex().expect("configured"),
+        reg.live_tool_catalog_hash_hex().await.expect("live")
+    );
+}
+
+fn write_colliding_stub_config(dir: &std::path::Path, stub: String) -> std::path::PathBuf {
+    let cfg_path = dir.join("mcp_servers.json");
+    let mut servers = std::collections::BTreeMap::new();
+    servers.insert(
+        "other".to_string(),
+        McpServerConfig {
+            command: stub.clone(),
+            args: vec![],
+        },
+    );
+    servers.insert(
+        "evil".to_string(),
+        McpServerConfig {
+            command: stub,
+            args: vec!["--colliding-names".to_string()],
+        },
+    );
+    let cfg = McpConfigFile {
+        schema_version: "openagent.mcp_servers.v1".to_string(),
+        servers,
+    };
+    fs::write(
+        &cfg_path,
+        serde_json::to_string_pretty(&cfg).expect("serialize"),
+    )
+    .expect("write config");
+    cfg_path
+}
+
+#[tokio::test]
+async fn colliding_tool_names_are_skipped_and_recorded() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = write_colliding_stub_config(tmp.path(), stub);
+    let reg = McpRegistry::from_config_path_with_metadata_policy(
+        &cfg_path,
+        &["other".to_string(), "evil".to_string()],
+        Duration::from_secs(5),
+        McpMetadataPolicy::default(),
+    )
+    .await
+    .expect("start registry");
+
+    let names = reg
+        .tool_defs()
+        .iter()
+        .map(|t| t.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["mcp.evil.echo", "mcp.other.echo"]);
+    let rejected = reg
+        .tool_rejected()
+        .iter()
+        .map(|r| (r.tool.as_str(), r.reason, r.conflicts_with.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rejected,
+        vec![
+            ("shell", McpToolRejectReason::ReservedName, "shell"),
+            (
+                "mcp.other.echo",
+                McpToolRejectReason::NameCollision,
+                "mcp.other.echo"
+            ),
+        ]
+    );
+    assert!(reg.tool_rejected().iter().all(|r| r.server == "evil"));
+    assert_eq!(
+        reg.configured_tool_catalog_hash_hex().expect("configured"),
+        reg.live_tool_catalog_hash_hex().await.expect("live")
+    );
+}
+
+#[tokio::test]
+async fn strict_names_mode_fails_startup_on_collision() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = write_colliding_stub_config(tmp.path(), stub);
+    let err = match McpRegistry::from_config_path_with_metadata_policy(
+        &cfg_path,
+        &["other".to_string(), "evil".to_string()],
+        Duration::from_secs(5),
+        McpMetadataPolicy {
+            strict_names: true,
+            ..McpMetadataPolicy::default()
+        },
+    )
+    .await
+    {
+        Ok(_) => panic!("strict names should reject the registry"),
+        Err(e) => e.to_string(),
+    };
+    assert!(err.contains("'evil'"), "{err}");
+    assert!(err.contains("'shell'"), "{err}");
+}
+
+fn write_binary_stub_config(dir: &std::path::Path, stub: String) -> std::path::PathBuf {
+    let cfg_path = dir.join("mcp_servers.json");
+    let mut servers = std::collections::BTreeMap::new();
+    servers.insert(
+        "stub".to_string(),
+        McpServerConfig {
+            command: stub,
+            args: vec!["--binary-tools".to_string()],
+        },
+    );
+    let cfg = McpConfigFile {
+        schema_version: "openagent.mcp_servers.v1".to_string(),
+        servers,
+    };
+    fs::write(
+        &cfg_path,
+        serde_json::to_string_pretty(&cfg).expect("serialize"),
+    )
+    .expect("write config");
+    cfg_path
+}
+
+fn artifact_tool_runtime(
+    workdir: &std::path::Path,
+    store: std::sync::Arc<RunArtifactStore>,
+) -> ToolRuntime {
+    ToolRuntime {
+        workdir: workdir.to_path_buf(),
+        allow_shell: false,
+        allow_shell_in_workdir_only: false,
+        shell_allowlist: None,
+        allow_write: true,
+        max_tool_output_bytes: 200_000,
+        max_read_bytes: 200_000,
+        unsafe_bypass_allow_flags: false,
+        restrict_to_workdir: true,
+        tool_timeout_ms: None,
+        stream_tool_output: false,
+        dry_run_writes: false,
+        write_checkpoint: None,
+        tool_args_strict: ToolArgsStrict::On,
+        exec_target_kind: ExecTargetKind::Host,
+        exec_target: std::sync::Arc::new(HostTarget),
+        read_allowlist: None,
+        run_artifacts: Some(store),
+        secret_redactor: None,
+    }
+}
+
+#[tokio::test]
+async fn mcp_binary_result_spills_to_run_artifact_readable_via_read_file() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = write_binary_stub_config(tmp.path(), stub);
+    let reg =
+        McpRegistry::from_config_path(&cfg_path, &["stub".to_string()], Duration::from_secs(5))
+            .await
+            .expect("start registry");
+    let state_dir = tmp.path().join("state");
+    let store = std::sync::Arc::new(RunArtifactStore::new(&state_dir, "run_spill", 0));
+
+    let tc = ToolCall {
+        id: "tc_shot".to_string(),
+        name: "mcp.stub.screenshot".to_string(),
+        arguments: json!({"bytes": 96 * 1024}),
+    };
+    let out = reg
+        .call_namespaced_tool_with_artifacts(&tc, ToolArgsStrict::On, Some(&store))
+        .await
+        .expect("call");
+    let envelope: serde_json::Value =
+        serde_json::from_str(&out.message.content.expect("envelope")).expect("parse envelope");
+    assert_eq!(envelope["ok"], true);
+    assert_eq!(envelope["truncated"], false);
+    let content: serde_json::Value =
+        serde_json::from_str(envelope["content"].as_str().expect("content")).expect("json");
+    assert_eq!(content["content"][0]["text"], "captured");
+    let artifact = &content["content"][1]["artifact"];
+    assert_eq!(content["content"][1]["type"], "image");
+    assert_eq!(artifact["bytes"], 96 * 1024);
+    assert_eq!(artifact["content_type"], "image/png");
+    let hash = artifact["hash"].as_str().expect("hash");
+    assert_eq!(artifact["path_hint"], format!("artifact:{hash}"));
+    assert!(content["artifact_hint"]
+        .as_str()
+        .expect("hint")
+        .contains("read_file"));
+
+    let manifest = load_run_artifact_manifest(&state_dir, "run_spill")
+        .expect("load manifest")
+        .expect("manifest");
+    assert_eq!(manifest.total_bytes, 96 * 1024);
+    assert_eq!(manifest.entries.len(), 1);
+    assert_eq!(manifest.entries[0].hash, hash);
+    assert_eq!(manifest.entries[0].source_tool, "mcp.stub.screenshot");
+    assert_eq!(manifest.entries[0].tool_call_id, "tc_shot");
+
+    let rt = artifact_tool_runtime(tmp.path(), store);
+    let read = execute_tool(
+        &rt,
+        &ToolCall {
+            id: "tc_read".to_string(),
+            name: "read_file".to_string(),
+            arguments: json!({"path": format!("artifact:{hash}")}),
+        },
+    )
+    .await;
+    let read_env: serde_json::Value =
+        serde_json::from_str(&read.content.expect("read envelope")).expect("parse");
+    assert_eq!(read_env["ok"], true);
+    let read_content: serde_json::Value =
+        serde_json::from_str(read_env["content"].as_str().expect("content")).expect("json");
+    assert_eq!(read_content["content_type"], "image/png");
+    assert_eq!(read_content["read_bytes"], 96 * 1024);
+    assert!(read_content["content"]
+        .as_str()
+        .expect("text")
+        .starts_with("0123456789abcdef0123"));
+
+    let write = execute_tool(
+        &rt,
+        &ToolCall {
+            id: "tc_write".to_string(),
+            name: "write_file".to_string(),
+            arguments: json!({"path": format!("artifact:{hash}"), "content": "x"}),
+        },
+    )
+    .await;
+    let write_env: serde_json::Value =
+        serde_json::from_str(&write.content.expect("write envelope")).expect("parse");
+    assert_eq!(write_env["ok"], false);
+    assert_eq!(write_env["error"]["code"], "artifact_read_only");
+    assert!(!tmp.path().join(format!("artifact:{hash}")).exists());
+
+    let unknown = execute_tool(
+        &rt,
+        &ToolCall {
+            id: "tc_unknown".to_string(),
+            name: "read_file".to_string(),
+            arguments: json!({"path": format!("artifact:{}", "0".repeat(64))}),
+        },
+    )
+    .await;
+    let unknown_env: serde_json::Value =
+        serde_json::from_str(&unknown.content.expect("envelope")).expect("parse");
+    assert_eq!(unknown_env["error"]["code"], "artifact_not_found");
+}
+
+#[tokio::test]
+async fn mcp_artifact_spill_cap_fails_with_clear_error() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = write_binary_stub_config(tmp.path(), stub);
+    let reg =
+        McpRegistry::from_config_path(&cfg_path, &["stub".to_string()], Duration::from_secs(5))
+            .await
+            .expect("start registry");
+    let state_dir = tmp.path().join("state");
+    let store = RunArtifactStore::new(&state_dir, "run_cap", 100 * 1024);
+
+    let call = |id: &str, bytes: u64| ToolCall {
+        id: id.to_string(),
+        name: "mcp.stub.screenshot".to_string(),
+        arguments: json!({ "bytes": bytes }),
+    };
+    let first = reg
+        .call_namespaced_tool_with_artifacts(
+            &call("tc1", 80 * 1024),
+            ToolArgsStrict::On,
+            Some(&store),
+        )
+        .await
+        .expect("first call");
+    assert!(first
+        .message
+        .content
+        .expect("envelope")
+        .contains("\"ok\":true"));
+
+    let second = reg
+        .call_namespaced_tool_with_artifacts(
+            &call("tc2", 40 * 1024),
+            ToolArgsStrict::On,
+            Some(&store),
+        )
+        .await
+        .expect("second call");
+    let envelope: serde_json::Value =
+        serde_json::from_str(&second.message.content.expect("envelope")).expect("parse");
+    assert_eq!(envelope["ok"], false);
+    let message = envelope["content"].as_str().expect("content");
+    assert!(message.contains("run artifact cap exceeded"), "{message}");
+    assert!(message.contains("--max-run-artifact-bytes"));
+
+    let manifest = load_run_artifact_manifest(&state_dir, "run_cap")
+        .expect("load manifest")
+        .expect("manifest");
+    assert_eq!(manifest.entries.len(), 1);
+    assert_eq!(manifest.total_bytes, 80 * 1024);
+}
+
+#[tokio::test]
+async fn mcp_results_over_max_output_bytes_are_truncated_or_rejected() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = tmp.path().join("mcp_servers.json");
+    let mut servers = std::collections::BTreeMap::new();
+    servers.insert(
+        "stub".to_string(),
+        McpServerConfig {
+            command: stub,
+            args: vec![],
+        },
+    );
+    let cfg = McpConfigFile {
+        schema_version: "openagent.mcp_servers.v1".to_string(),
+        servers,
+    };
+    fs::write(
+        &cfg_path,
+        serde_json::to_string_pretty(&cfg).expect("serialize"),
+    )
+    .expect("write config");
+    let reg =
+        McpRegistry::from_config_path(&cfg_path, &["stub".to_string()], Duration::from_secs(5))
+            .await
+            .expect("start registry");
+
+    // 3-byte chars so a 1000-byte cut has to back off to a char boundary.
+    let over = ToolCall {
+        id: "tc_over".to_string(),
+        name: "mcp.stub.echo".to_string(),
+        arguments: json!({"msg": "\u{2603}".repeat(700)}),
+    };
+    let out = reg
+        .call_namespaced_tool_with_limits(&over, ToolArgsStrict::On, None, 1_000)
+        .await
+        .expect("call");
+    let envelope: serde_json::Value =
+        serde_json::from_str(&out.message.content.expect("envelope")).expect("parse");
+    assert_eq!(envelope["ok"], true);
+    assert_eq!(envelope["truncated"], true);
+    assert_eq!(envelope["truncate_reason"], "max_bytes");
+    let kept = envelope["content"].as_str().expect("content");
+    assert!(kept.len() <= 1_000);
+    let original = envelope["meta"]["bytes"].as_u64().expect("bytes");
+    assert!(original > 2_000);
+    let limit = out.meta.output_limit.expect("truncation evidence");
+    assert_eq!(limit.server, "stub");
+    assert_eq!(limit.tool, "echo");
+    assert_eq!(limit.original_bytes, original);
+    assert_eq!(limit.kept_bytes, kept.len() as u64);
+    assert_eq!(limit.limit_bytes, 1_000);
+    assert!(!limit.rejected);
+
+    let huge = ToolCall {
+        id: "tc_huge".to_string(),
+        name: "mcp.stub.echo".to_string(),
+        arguments: json!({"msg": "x".repeat(5_000)}),
+    };
+    let out = reg
+        .call_namespaced_tool_with_limits(&huge, ToolArgsStrict::On, None, 1_000)
+        .await
+        .expect("call");
+    let envelope: serde_json::Value =
+        serde_json::from_str(&out.message.content.expect("envelope")).expect("parse");
+    assert_eq!(envelope["ok"], false);
+    assert_eq!(envelope["truncated"], false);
+    assert_eq!(envelope["error"]["code"], "tool_output_oversize");
+    assert!(envelope.get("full_output_ref").is_none());
+    assert!(!envelope["content"]
+        .as_str()
+        .expect("content")
+        .contains("xxxx"));
+    let limit = out.meta.output_limit.expect("rejection evidence");
+    assert!(limit.rejected);
+    assert_eq!(limit.kept_bytes, 0);
+    assert!(limit.original_bytes > 4_000);
+
+    let within = ToolCall {
+        id: "tc_small".to_string(),
+        name: "mcp.stub.echo".to_string(),
+        arguments: json!({"msg": "small"}),
+    };
+    let out = reg
+        .call_namespaced_tool_with_limits(&within, ToolArgsStrict::On, None, 1_000)
+        .await
+        .expect("call");
+    assert!(out.meta.output_limit.is_none());
+    assert!(out
+        .message
+        .content
+        .expect("envelope")
+        .contains("\"truncated\":false"));
+}
+
+#[tokio::test]
+async fn mcp_server_reconnects_after_backoff_once_degraded() {
+    let Some(stub) = stub_bin() else {
+        eprintln!("skipping: CARGO_BIN_EXE_mcp_stub not set");
+        return;
+    };
+    let tmp = tempdir().expect("tempdir");
+    let cfg_path = tmp.path().join("mcp_servers.json");
+    let mut servers = std::collections::BTreeMap::new();
+    servers.insert(
+        "stub".to_string(),
+        McpServerConfig {
+            command: stub,
+            args: vec!["--crash-tool".to_string()],
+        },
+    );
+    let cfg = McpConfigFile {
+        schema_version: "openagent.mcp_servers.v1".to_string(),
+        servers,
+    };
+    fs::write(
+        &cfg_path,
+        serde_json::to_string_pretty(&cfg).expect("serialize"),
+    )
+    .expect("write config");
+    let reg =
+        McpRegistry::from_config_path(&cfg_path, &["stub".to_string()], Duration::from_secs(5))
+            .await
+            .expect("start registry")
+            .with_reconnect_policy(McpReconnectPolicy {
+                max_attempts: 2,
+                base_backoff_ms: 200,
+            });
+    let call = |id: &str, name: &str, arguments: serde_json::Value| ToolCall {
+        id: id.to_string(),
+        name: name.to_string(),
+        arguments,
+    };
+
+    let crashed = reg
+        .call_namespaced_tool(
+            &call("tc1", "mcp.stub.crash", json!({})),
+            ToolArgsStrict::On,
+        )
+        .await
+        .expect("call");
+    assert!(crashed
+        .message
+        .content
+        .expect("envelope")
+        .contains("\"ok\":false"));
+    let degraded = crashed.meta.degraded.expect("degraded");
+    assert_eq!(degraded.server, "stub");
+    assert_eq!(degraded.reconnect_attempts, 0);
+    assert_eq!(degraded.retry_after_ms, Some(200));
+
+    let echo = call("tc2", "mcp.stub.echo", json!({"msg":"hi"}));
+    let fast = reg
+        .call_namespaced_tool(&echo, ToolArgsStrict::On)
+        .await
+        .expect("call");
+    assert!(fast.meta.degraded.is_none());
+    let envelope: serde_json::Value =
+        serde_json::from_str(&fast.message.content.expect("envelope")).expect("parse");
+    assert_eq!(envelope["error"]["code"], "mcp_server_degraded");
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    let healed = reg
+        .call_namespaced_tool(&echo, ToolArgsStrict::On)
+        .await
+        .expect("call");
+    let reconnected = healed.meta.reconnected.expect("reconnected");
+    assert_eq!(reconnected.server, "stub");
+    assert_eq!(reconnected.reconnect_attempts, 1);
+    assert!(reconnected.degraded_ms >= 200);
+    assert!(healed
+        .message
+        .content
+        .expect("envelope")
+        .contains("\"ok\":true"));
+}
@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+
+use localagent::repro::{render_verify_report, verify_run_record};
+use localagent::store::{self, RunRecord, RUN_RECORD_SCHEMA_LATEST, RUN_RECORD_SCHEMA_V1};
+use serde_json::{json, Value};
+use tempfile::tempdir;
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/run_records")
+}
+
+fn fixture_paths() -> Vec<PathBuf> {
+    let mut paths = std::fs::read_dir(fixture_dir())
+        .expect("fixture dir")
+        .map(|e| e.expect("fixture entry").path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn read_fixture(name: &str) -> String {
+    std::fs::read_to_string(fixture_dir().join(name)).expect("read fixture")
+}
+
+/// Loads a fixture through `load_run_record` the way a real state dir would.
+fn load_via_state_dir(state_dir: &Path, fixture: &Path) -> RunRecord {
+    let raw: Value =
+        serde_json::from_str(&std::fs::read_to_string(fixture).expect("read")).expect("json");
+    let run_id = raw["metadata"]["run_id"]
+        .as_str()
+        .expect("run_id")
+        .to_string();
+    let runs_dir = state_dir.join("runs");
+    std::fs::create_dir_all(&runs_dir).expect("runs dir");
+    std::fs::copy(fixture, runs_dir.join(format!("{run_id}.json"))).expect("copy fixture");
+    store::load_run_record(state_dir, &run_id)
+        .unwrap_or_else(|e| panic!("fixture {} failed to load: {e}", fixture.display()))
+}
+
+fn canonical_bytes(record: &RunRecord) -> Vec<u8> {
+    let value = serde_json::to_value(record).expect("to value");
+    serde_json::to_vec(&value).expect("canonical bytes")
+}
+
+#[test]
+fn every_fixture_loads_and_migrates_to_latest() {
+    let tmp = tempdir().expect("tempdir");
+    let fixtures = fixture_paths();
+    assert!(!fixtures.is_empty(), "run record fixture corpus is empty");
+    for fixture in fixtures {
+        let record = load_via_state_dir(tmp.path(), &fixture);
+        assert_eq!(
+            record.schema_version,
+            RUN_RECORD_SCHEMA_LATEST,
+            "{}",
+            fixture.display()
+        );
+        assert!(record.extra.is_empty(), "{}", fixture.display());
+        assert_eq!(record.final_output, "done");
+    }
+}
+
+#[test]
+fn v1_record_migrates_without_losing_fields() {
+    let v1: Value = serde_json::from_str(&read_fixture("v1_pre_envelope.json")).expect("json");
+    assert!(v1.get("schema_version").is_none());
+
+    let record = store::parse_run_record(&v1.to_string()).expect("parse v1");
+    assert_eq!(record.migrated_from.as_deref(), Some(RUN_RECORD_SCHEMA_V1));
+
+    let mut expected = store::migrate_record(v1).expect("migrate");
+    let got = serde_json::to_value(&record).expect("to value");
+    expected["schema_version"] = json!(RUN_RECORD_SCHEMA_LATEST);
+    assert_eq!(got, expected);
+}
+
+#[test]
+fn latest_record_round_trips_byte_for_byte() {
+    let record = store::parse_run_record(&read_fixture("v2_envelope.json")).expect("parse v2");
+    assert!(record.migrated_from.is_none());
+    let first = canonical_bytes(&record);
+    let reparsed =
+        store::parse_run_record(std::str::from_utf8(&first).expect("utf8")).expect("reparse");
+    assert_eq!(first, canonical_bytes(&reparsed));
+}
+
+#[test]
+fn unknown_fields_survive_a_rewrite() {
+    let mut raw: Value = serde_json::from_str(&read_fixture("v2_envelope.json")).expect("json");
+    raw["future_summary"] = json!({"score": 3, "notes": ["a", "b"]});
+    let record = store::parse_run_record(&raw.to_string()).expect("parse");
+    assert_eq!(
+        record.extra.get("future_summary"),
+        Some(&json!({"score": 3, "notes": ["a", "b"]}))
+    );
+    let rewritten = serde_json::to_value(&record).expect("to value");
+    assert_eq!(rewritten, raw);
+}
+
+#[test]
+fn next_version_record_round_trips_with_nested_unknown_fields() {
+    let mut raw: Value = serde_json::from_str(&read_fixture("v2_envelope.json")).expect("json");
+    raw["schema_version"] = json!("openagent.run_record.v3");
+    raw["metadata"]["resumed_from"] = json!("run_0");
+    raw["cli"]["sandbox"] = json!({"kind": "microvm", "cpus": 2});
+    raw["transcript"] = json!([{"role": "user", "content": "hi", "annotations": ["pinned"]}]);
+    raw["future_summary"] = json!({"score": 3});
+    let record = store::parse_run_record(&raw.to_string()).expect("parse v3");
+    assert_eq!(record.schema_version, "openagent.run_record.v3");
+    assert!(record.migrated_from.is_none());
+    assert_eq!(
+        record.extra.get("future_summary"),
+        Some(&json!({"score": 3}))
+    );
+    assert_eq!(serde_json::to_value(&record).expect("to value"), raw);
+
+    raw["schema_version"] = json!("openagent.run_record.v4");
+    assert!(store::parse_run_record(&raw.to_string()).is_err());
+}
+
+#[test]
+fn replay_verify_reports_stored_schema_version() {
+    let v1 = store::parse_run_record(&read_fixture("v1_pre_envelope.json")).expect("v1");
+    let report = verify_run_record(&v1, false).expect("verify v1");
+    assert_eq!(report.record_schema_version, RUN_RECORD_SCHEMA_V1);
+    assert!(report.record_migrated);
+    assert!(render_verify_report(&report)
+        .contains("record_schema: openagent.run_record.v1 (migrated on load)"));
+
+    let v2 = store::parse_run_record(&read_fixture("v2_envelope.json")).expect("v2");
+    let report = verify_run_record(&v2, false).expect("verify v2");
+    assert_eq!(report.record_schema_version, RUN_RECORD_SCHEMA_LATEST);
+    assert!(!report.record_migrated);
+}
+
+#[test]
+fn annotating_a_run_leaves_the_record_and_verify_report_unchanged() {
+    let tmp = tempdir().expect("tempdir");
+    let fixture = fixture_dir().join("v2_envelope.json");
+    let record = load_via_state_dir(tmp.path(), &fixture);
+    let run_id = record.metadata.run_id.clone();
+    let record_path = tmp.path().join("runs").join(format!("{run_id}.json"));
+    let bytes_before = std::fs::read(&record_path).expect("read record");
+    let report_before = verify_run_record(&record, true).expect("verify");
+
+    store::update_run_annotations(
+        tmp.path(),
+        &run_id,
+        &store::RunAnnotationUpdate {
+            add_tags: vec!["repro".to_string(), "patch-bug".to_string()],
+            note: Some("good repro of the patch bug".to_string()),
+            ..Default::default()
+        },
+    )
+    .expect("annotate");
+
+    assert_eq!(
+        std::fs::read(&record_path).expect("read record"),
+        bytes_before
+    );
+    let reloaded = store::load_run_record(tmp.path(), &run_id).expect("reload");
+    let report_after = verify_run_record(&reloaded, true).expect("verify");
+    assert_eq!(
+        serde_json::to_value(&report_after).expect("report"),
+        serde_json::to_value(&report_before).expect("report")
+    );
+    let listed = store::list_runs(
+        tmp.path(),
+        &store::RunListQuery {
+            tags: vec!["repro".to_string()],
+            ..Default::default()
+        },
+    )
+    .expect("list");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].tags, vec!["patch-bug", "repro"]);
+}
@@ -1,26 +1,14 @@
-use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
-use localagent::agent::{
-    Agent, AgentExitReason, McpPinEnforcementMode, PlanStepConstraint, PlanToolEnforcementMode,
-    ToolCallBudget,
-};
-use localagent::compaction::{CompactionMode, CompactionSettings, ToolResultPersist};
+use localagent::agent::{Agent, AgentExitReason};
 use localagent::eval::metrics::derive_tool_retry_metrics;
 use localagent::events::{Event, EventKind, EventSink};
-use localagent::gate::{
-    compute_policy_hash_hex, ApprovalKeyVersion, ApprovalMode, AutoApproveScope, GateContext,
-    NoGate, ProviderKind, ToolGate, TrustGate, TrustMode,
-};
-use localagent::hooks::config::HooksMode;
-use localagent::hooks::runner::{HookManager, HookRuntimeConfig};
+use localagent::gate::{compute_policy_hash_hex, NoGate, ToolGate, TrustGate, TrustMode};
 use localagent::providers::ModelProvider;
-use localagent::taint::{TaintLevel, TaintMode, TaintToggle};
-use localagent::target::{ExecTargetKind, HostTarget};
-use localagent::tools::{builtin_tools_enabled, ToolArgsStrict, ToolRuntime};
+use localagent::tools::builtin_tools_enabled;
 use localagent::trust::approvals::ApprovalsStore;
 use localagent::trust::audit::AuditLog;
 use localagent::trust::policy::Policy;
@@ -82,6 +70,7 @@ impl ModelProvider for ScriptedProvider {
                     arguments,
                 }],
                 usage: None,
+                served_model: None,
             },
             ScriptStep::Final(text) => GenerateResponse {
                 assistant: Message {
@@ -93,6 +82,7 @@ impl ModelProvider for ScriptedProvider {
                 },
                 tool_calls: Vec::new(),
                 usage: None,
+                served_model: None,
             },
         })
     }
@@ -120,88 +110,17 @@ fn build_agent<P: ModelProvider + 'static>(
         max_steps,
         events,
     } = input;
-    Agent {
-        provider,
-        model: "mock-model".to_string(),
-        temperature: None,
-        top_p: None,
-        max_tokens: None,
-        seed: None,
-        tools,
-        max_steps,
-        tool_rt: ToolRuntime {
-            workdir: workdir.to_path_buf(),
-            allow_shell,
-            allow_shell_in_workdir_only: false,
-            allow_write,
-            max_tool_output_bytes: 200_000,
-            max_read_bytes: 200_000,
-            unsafe_bypass_allow_flags: false,
-            tool_args_strict: ToolArgsStrict::On,
-            exec_target_kind: ExecTargetKind::Host,
-            exec_target: Arc::new(HostTarget),
-        },
-        gate,
-        gate_ctx: GateContext {
-            workdir: workdir.to_path_buf(),
-            allow_shell,
-            allow_write,
-            approval_mode: ApprovalMode::Interrupt,
-            auto_approve_scope: AutoApproveScope::Run,
-            unsafe_mode: false,
-            unsafe_bypass_allow_flags: false,
-            run_id: None,
-            enable_write_tools: false,
-            max_tool_output_bytes: 200_000,
-            max_read_bytes: 200_000,
-            provider: ProviderKind::Mock,
-            model: "mock-model".to_string(),
-            exec_target: ExecTargetKind::Host,
-            approval_key_version: ApprovalKeyVersion::V1,
-            tool_schema_hashes: BTreeMap::new(),
-            hooks_config_hash_hex: None,
-            planner_hash_hex: None,
-            taint_enabled: false,
-            taint_mode: TaintMode::Propagate,
-            taint_overall: TaintLevel::Clean,
-            taint_sources: Vec::new(),
-        },
-        validation_requirement: None,
-        final_answer_mode: None,
-        mcp_registry: None,
-        stream: false,
-        event_sink: Some(Box::new(EventCaptureSink { events })),
-        compaction_settings: CompactionSettings {
-            max_context_chars: 0,
-            mode: CompactionMode::Off,
-            keep_last: 20,
-            tool_result_persist: ToolResultPersist::Digest,
-        },
-        hooks: HookManager::build(HookRuntimeConfig {
-            mode: HooksMode::Off,
-            config_path: std::env::temp_dir().join("unused_hooks.yaml"),
-            strict: false,
-            timeout_ms: 1_000,
-            max_stdout_bytes: 200_000,
-        })
-        .expect("hooks"),
-        policy_loaded: None,
-        policy_for_taint: None,
-        taint_toggle: TaintToggle::Off,
-        taint_mode: TaintMode::Propagate,
-        taint_digest_bytes: 4096,
-        run_id_override: None,
-        omit_tools_field_when_empty: false,
-        plan_tool_enforcement: PlanToolEnforcementMode::Off,
-        mcp_pin_enforcement: McpPinEnforcementMode::Hard,
-        plan_step_constraints: Vec::<PlanStepConstraint>::new(),
-        current_plan: Vec::new(),
-        tool_call_budget: ToolCallBudget::default(),
-        mcp_runtime_trace: Vec::new(),
-        operator_queue: localagent::operator_queue::PendingMessageQueue::default(),
-        operator_queue_limits: localagent::operator_queue::QueueLimits::default(),
-        operator_queue_rx: None,
-    }
+    Agent::builder(provider)
+        .model("mock-model")
+        .workdir(workdir)
+        .allow_shell(allow_shell)
+        .allow_write(allow_write)
+        .tools(tools)
+        .max_steps(max_steps)
+        .gate(gate)
+        .event_sink(Box::new(EventCaptureSink { events }))
+        .build()
+        .expect("agent")
 }
 
 fn tool_message_error_codes(messages: &[Message]) -> Vec<String> {